@@ -0,0 +1,24 @@
+//! Write the published analysis JSON Schemas
+//!
+//! ```text
+//! cargo run -p fingerprint-analysis --example export_schema -- schemas
+//! ```
+
+use fingerprint_analysis::SchemaDocument;
+use std::path::PathBuf;
+
+fn main() -> std::io::Result<()> {
+    let dir = PathBuf::from(
+        std::env::args()
+            .nth(1)
+            .unwrap_or_else(|| "schemas".to_string()),
+    );
+    std::fs::create_dir_all(&dir)?;
+
+    for document in SchemaDocument::ALL {
+        let path = dir.join(document.file_name());
+        std::fs::write(&path, document.to_json())?;
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
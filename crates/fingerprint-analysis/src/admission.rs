@@ -0,0 +1,631 @@
+//! Per-tenant admission control
+//!
+//! Analysis is expensive and the engine is shared, so one noisy tenant can starve
+//! everyone else. [`AdmissionController`] sits in front of
+//! [`AnalysisEngine::analyze_as`](crate::AnalysisEngine::analyze_as):
+//!
+//! 1. **Token buckets** per tenant, and optionally per (tenant, source), cap the
+//!    sustained call rate. A call without a token is shed immediately.
+//! 2. **Concurrency limit**: at most `max_concurrent` analyses run at once; the rest
+//!    wait in per-tenant queues.
+//! 3. **Fair queuing**: a freed slot goes to the next tenant in round-robin order, so a
+//!    tenant with a deep backlog cannot crowd out one with a single waiting call.
+//!    Within a tenant, higher [`Priority`] goes first.
+//! 4. **Overflow**: when `max_queued` calls are already waiting, the lowest-priority
+//!    (and, among equals, newest) waiter is shed to make room, or the new call is shed
+//!    if nothing waiting ranks below it.
+//!
+//! Shed work is counted in [`AdmissionStats`] and announced as
+//! `fingerprint_analysis_shed_total{tenant, reason}` on the global event bus.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use fingerprint_core::events::{self, MetricRecorded};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+/// Token bucket limits
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Sustained calls per second
+    pub rate_per_second: f64,
+    /// Bucket size, the largest burst allowed after an idle period
+    pub burst: f64,
+}
+
+/// Admission controller configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdmissionConfig {
+    /// Default limit for every tenant
+    pub tenant_limit: RateLimit,
+    /// Limits for specific tenants, replacing `tenant_limit`
+    pub tenant_overrides: HashMap<String, RateLimit>,
+    /// Additional limit per (tenant, source), when the request names a source
+    pub source_limit: Option<RateLimit>,
+    /// Analyses allowed to run at once
+    pub max_concurrent: usize,
+    /// Calls allowed to wait for a slot, across all tenants
+    pub max_queued: usize,
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        Self {
+            tenant_limit: RateLimit {
+                rate_per_second: 100.0,
+                burst: 200.0,
+            },
+            tenant_overrides: HashMap::new(),
+            source_limit: None,
+            max_concurrent: 64,
+            max_queued: 1024,
+        }
+    }
+}
+
+/// Priority of an analysis call; lower priorities are shed first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+}
+
+/// Who an analysis call is made for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdmissionRequest {
+    /// Tenant the call is accounted to
+    pub tenant: String,
+    /// Source within the tenant (sensor, API key, ...), if any
+    pub source: Option<String>,
+    /// Call priority
+    pub priority: Priority,
+}
+
+impl AdmissionRequest {
+    /// Normal-priority request for `tenant`
+    pub fn new(tenant: impl Into<String>) -> Self {
+        Self {
+            tenant: tenant.into(),
+            source: None,
+            priority: Priority::Normal,
+        }
+    }
+
+    /// Attribute the call to a source within the tenant
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Set the call priority
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// Why a call was shed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ShedReason {
+    /// The tenant or source bucket was empty
+    RateLimited,
+    /// The wait queue was full and the call ranked lowest
+    QueueFull,
+    /// The call was waiting and got displaced by a higher-priority one
+    Displaced,
+}
+
+impl ShedReason {
+    /// Stable name, used as the metric label
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ShedReason::RateLimited => "rate_limited",
+            ShedReason::QueueFull => "queue_full",
+            ShedReason::Displaced => "displaced",
+        }
+    }
+}
+
+/// A call rejected by admission control
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shed {
+    /// Tenant of the rejected call
+    pub tenant: String,
+    /// Why it was rejected
+    pub reason: ShedReason,
+}
+
+impl fmt::Display for Shed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tenant {} shed ({})", self.tenant, self.reason.as_str())
+    }
+}
+
+impl std::error::Error for Shed {}
+
+/// Admission counters
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AdmissionStats {
+    /// Calls granted a slot
+    pub admitted: u64,
+    /// Calls currently running
+    pub in_flight: usize,
+    /// Calls currently waiting
+    pub queued: usize,
+    /// Shed calls per reason
+    pub shed: HashMap<ShedReason, u64>,
+    /// Shed calls per tenant
+    pub shed_by_tenant: HashMap<String, u64>,
+}
+
+impl AdmissionStats {
+    /// Shed calls over all reasons
+    pub fn total_shed(&self) -> u64 {
+        self.shed.values().sum()
+    }
+}
+
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.rate_per_second).min(self.limit.burst);
+        self.updated = now;
+    }
+}
+
+struct Waiter {
+    priority: Priority,
+    seq: u64,
+    tx: oneshot::Sender<Result<(), ShedReason>>,
+}
+
+#[derive(Default)]
+struct State {
+    buckets: HashMap<String, TokenBucket>,
+    in_flight: usize,
+    queues: HashMap<String, VecDeque<Waiter>>,
+    /// Tenants with waiters, in service order
+    rotation: VecDeque<String>,
+    queued: usize,
+    next_seq: u64,
+    stats: AdmissionStats,
+}
+
+struct Inner {
+    config: AdmissionConfig,
+    state: Mutex<State>,
+}
+
+/// Per-tenant rate limiting and fair scheduling of analysis calls
+#[derive(Clone)]
+pub struct AdmissionController {
+    inner: Arc<Inner>,
+}
+
+/// Slot held while an admitted analysis runs; dropping it frees the slot
+pub struct AdmissionPermit {
+    inner: Arc<Inner>,
+}
+
+/// Outcome of [`AdmissionController::try_admit`]
+enum Admission {
+    Granted(AdmissionPermit),
+    Queued(Ticket),
+}
+
+/// Wait for a queued call's turn
+struct Ticket {
+    inner: Arc<Inner>,
+    tenant: String,
+    rx: oneshot::Receiver<Result<(), ShedReason>>,
+}
+
+impl Future for Ticket {
+    type Output = Result<AdmissionPermit, Shed>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let outcome = match Pin::new(&mut self.rx).poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(outcome) => outcome,
+        };
+        Poll::Ready(match outcome {
+            Ok(Ok(())) => Ok(AdmissionPermit {
+                inner: self.inner.clone(),
+            }),
+            Ok(Err(reason)) => Err(Shed {
+                tenant: self.tenant.clone(),
+                reason,
+            }),
+            // The controller never drops a waiter without answering
+            Err(_) => Err(Shed {
+                tenant: self.tenant.clone(),
+                reason: ShedReason::Displaced,
+            }),
+        })
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        // A slot granted to a caller that gave up before seeing it is handed on
+        self.rx.close();
+        if let Ok(Ok(())) = self.rx.try_recv() {
+            self.inner.release();
+        }
+    }
+}
+
+impl AdmissionController {
+    /// Controller enforcing `config`
+    pub fn new(config: AdmissionConfig) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                state: Mutex::new(State::default()),
+            }),
+        }
+    }
+
+    /// Controller configuration
+    pub fn config(&self) -> &AdmissionConfig {
+        &self.inner.config
+    }
+
+    /// Wait for a slot for `request`, or return why it was shed
+    pub async fn acquire(&self, request: &AdmissionRequest) -> Result<AdmissionPermit, Shed> {
+        match self.try_admit(request)? {
+            Admission::Granted(permit) => Ok(permit),
+            Admission::Queued(ticket) => ticket.await,
+        }
+    }
+
+    /// Snapshot of the counters
+    pub fn stats(&self) -> AdmissionStats {
+        let state = self.inner.state.lock();
+        let mut stats = state.stats.clone();
+        stats.in_flight = state.in_flight;
+        stats.queued = state.queued;
+        stats
+    }
+
+    fn try_admit(&self, request: &AdmissionRequest) -> Result<Admission, Shed> {
+        let inner = &self.inner;
+        let mut state = inner.state.lock();
+
+        if !inner.take_tokens(&mut state, request) {
+            inner.record_shed(&mut state, &request.tenant, ShedReason::RateLimited);
+            return Err(Shed {
+                tenant: request.tenant.clone(),
+                reason: ShedReason::RateLimited,
+            });
+        }
+
+        if state.in_flight < inner.config.max_concurrent && state.queued == 0 {
+            state.in_flight += 1;
+            state.stats.admitted += 1;
+            return Ok(Admission::Granted(AdmissionPermit {
+                inner: inner.clone(),
+            }));
+        }
+
+        if state.queued >= inner.config.max_queued && !inner.displace(&mut state, request.priority)
+        {
+            inner.record_shed(&mut state, &request.tenant, ShedReason::QueueFull);
+            return Err(Shed {
+                tenant: request.tenant.clone(),
+                reason: ShedReason::QueueFull,
+            });
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        let queue = state.queues.entry(request.tenant.clone()).or_default();
+        let newly_waiting = queue.is_empty();
+        queue.push_back(Waiter {
+            priority: request.priority,
+            seq,
+            tx,
+        });
+        if newly_waiting {
+            state.rotation.push_back(request.tenant.clone());
+        }
+        state.queued += 1;
+
+        Ok(Admission::Queued(Ticket {
+            inner: inner.clone(),
+            tenant: request.tenant.clone(),
+            rx,
+        }))
+    }
+}
+
+impl Inner {
+    /// Take one token from the tenant bucket and, if configured, the source bucket
+    fn take_tokens(&self, state: &mut State, request: &AdmissionRequest) -> bool {
+        let now = Instant::now();
+        let tenant_limit = self
+            .config
+            .tenant_overrides
+            .get(&request.tenant)
+            .copied()
+            .unwrap_or(self.config.tenant_limit);
+
+        let mut keys = vec![(request.tenant.clone(), tenant_limit)];
+        if let (Some(source), Some(limit)) = (&request.source, self.config.source_limit) {
+            keys.push((format!("{}\u{1f}{}", request.tenant, source), limit));
+        }
+
+        // Check every bucket before taking from any, so a rejected call costs nothing
+        for (key, limit) in &keys {
+            let bucket = state
+                .buckets
+                .entry(key.clone())
+                .or_insert_with(|| TokenBucket::new(*limit));
+            bucket.refill(now);
+            if bucket.tokens < 1.0 {
+                return false;
+            }
+        }
+        for (key, _) in &keys {
+            if let Some(bucket) = state.buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        true
+    }
+
+    /// Shed the lowest-ranked waiter if it ranks below `priority`
+    fn displace(&self, state: &mut State, priority: Priority) -> bool {
+        let victim = state
+            .queues
+            .iter()
+            .flat_map(|(tenant, queue)| {
+                queue
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, w)| (w.priority, std::cmp::Reverse(w.seq), tenant, i))
+            })
+            .min()
+            .filter(|(p, _, _, _)| *p < priority)
+            .map(|(_, _, tenant, i)| (tenant.clone(), i));
+
+        let Some((tenant, index)) = victim else {
+            return false;
+        };
+        if let Some(waiter) = state.queues.get_mut(&tenant).and_then(|q| q.remove(index)) {
+            state.queued -= 1;
+            let _ = waiter.tx.send(Err(ShedReason::Displaced));
+        }
+        self.forget_if_idle(state, &tenant);
+        self.record_shed(state, &tenant, ShedReason::Displaced);
+        true
+    }
+
+    /// Hand a freed slot to the next tenant in rotation
+    fn release(&self) {
+        let mut state = self.state.lock();
+        state.in_flight -= 1;
+
+        while state.in_flight < self.config.max_concurrent {
+            let Some(tenant) = state.rotation.pop_front() else {
+                return;
+            };
+            let Some(queue) = state.queues.get_mut(&tenant) else {
+                continue;
+            };
+
+            // Highest priority first, FIFO among equals
+            let next = queue
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, w)| (w.priority, std::cmp::Reverse(w.seq)))
+                .map(|(i, _)| i);
+            let waiter = next.and_then(|i| queue.remove(i));
+            if !queue.is_empty() {
+                state.rotation.push_back(tenant.clone());
+            }
+            self.forget_if_idle(&mut state, &tenant);
+
+            if let Some(waiter) = waiter {
+                state.queued -= 1;
+                // A cancelled caller has dropped its receiver; try the next one
+                if waiter.tx.send(Ok(())).is_ok() {
+                    state.in_flight += 1;
+                    state.stats.admitted += 1;
+                }
+            }
+        }
+    }
+
+    fn forget_if_idle(&self, state: &mut State, tenant: &str) {
+        if state.queues.get(tenant).is_some_and(|q| q.is_empty()) {
+            state.queues.remove(tenant);
+            state.rotation.retain(|t| t != tenant);
+        }
+    }
+
+    fn record_shed(&self, state: &mut State, tenant: &str, reason: ShedReason) {
+        *state.stats.shed.entry(reason).or_default() += 1;
+        *state
+            .stats
+            .shed_by_tenant
+            .entry(tenant.to_string())
+            .or_default() += 1;
+
+        let bus = events::global();
+        if bus.has_subscribers::<MetricRecorded>() {
+            bus.publish(MetricRecorded {
+                name: "fingerprint_analysis_shed_total".to_string(),
+                labels: vec![tenant.to_string(), reason.as_str().to_string()],
+                value: 1.0,
+            });
+        }
+    }
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        self.inner.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_concurrent: usize, max_queued: usize) -> AdmissionConfig {
+        AdmissionConfig {
+            tenant_limit: RateLimit {
+                rate_per_second: 0.0,
+                burst: 100.0,
+            },
+            tenant_overrides: HashMap::new(),
+            source_limit: None,
+            max_concurrent,
+            max_queued,
+        }
+    }
+
+    fn queued(controller: &AdmissionController, tenant: &str, priority: Priority) -> Ticket {
+        let request = AdmissionRequest::new(tenant).with_priority(priority);
+        match controller.try_admit(&request) {
+            Ok(Admission::Queued(ticket)) => ticket,
+            _ => panic!("expected {} to queue", tenant),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_per_tenant_and_source() {
+        let mut config = config(8, 8);
+        config.tenant_overrides.insert(
+            "noisy".to_string(),
+            RateLimit {
+                rate_per_second: 0.0,
+                burst: 2.0,
+            },
+        );
+        config.source_limit = Some(RateLimit {
+            rate_per_second: 0.0,
+            burst: 1.0,
+        });
+        let controller = AdmissionController::new(config);
+
+        let noisy = AdmissionRequest::new("noisy");
+        let _a = controller.acquire(&noisy).await.unwrap();
+        let _b = controller.acquire(&noisy).await.unwrap();
+        let shed = controller.acquire(&noisy).await.err().unwrap();
+        assert_eq!(shed.reason, ShedReason::RateLimited);
+
+        // Other tenants are unaffected; a source only gets its own share
+        let sensor = AdmissionRequest::new("quiet").with_source("sensor-1");
+        let _c = controller.acquire(&sensor).await.unwrap();
+        assert!(controller.acquire(&sensor).await.is_err());
+        let _d = controller
+            .acquire(&AdmissionRequest::new("quiet"))
+            .await
+            .unwrap();
+
+        let stats = controller.stats();
+        assert_eq!(stats.admitted, 4);
+        assert_eq!(stats.shed[&ShedReason::RateLimited], 2);
+        assert_eq!(stats.shed_by_tenant["noisy"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_fair_queuing_between_tenants() {
+        let controller = AdmissionController::new(config(1, 16));
+        let running = controller
+            .acquire(&AdmissionRequest::new("a"))
+            .await
+            .unwrap();
+
+        let cancelled = queued(&controller, "c", Priority::Normal);
+        let a1 = queued(&controller, "a", Priority::Normal);
+        let a2 = queued(&controller, "a", Priority::Normal);
+        let a3 = queued(&controller, "a", Priority::High);
+        let b1 = queued(&controller, "b", Priority::Low);
+        assert_eq!(controller.stats().queued, 5);
+        drop(cancelled);
+
+        // a's backlog does not hold b back, a's High call jumps a's own queue and the
+        // slot c gave up on goes to the next tenant
+        drop(running);
+        let p = a3.await.unwrap();
+        drop(p);
+        let p = b1.await.unwrap();
+        drop(p);
+        let p = a1.await.unwrap();
+        drop(p);
+        let p = a2.await.unwrap();
+        drop(p);
+
+        // A slot granted to a ticket that is dropped unpolled is not leaked
+        let running = controller
+            .acquire(&AdmissionRequest::new("a"))
+            .await
+            .unwrap();
+        let unpolled = queued(&controller, "b", Priority::Normal);
+        drop(running);
+        drop(unpolled);
+
+        let stats = controller.stats();
+        assert_eq!(stats.admitted, 7);
+        assert_eq!((stats.in_flight, stats.queued), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_overflow_sheds_lowest_priority_first() {
+        let controller = AdmissionController::new(config(1, 2));
+        let running = controller
+            .acquire(&AdmissionRequest::new("a"))
+            .await
+            .unwrap();
+
+        let low = queued(&controller, "a", Priority::Low);
+        let normal = queued(&controller, "b", Priority::Normal);
+
+        // Full: a High call displaces the Low waiter, another Normal is turned away
+        let high = queued(&controller, "c", Priority::High);
+        let rejected = controller.try_admit(&AdmissionRequest::new("d"));
+        assert!(matches!(
+            rejected,
+            Err(Shed {
+                reason: ShedReason::QueueFull,
+                ..
+            })
+        ));
+        assert_eq!(low.await.err().unwrap().reason, ShedReason::Displaced);
+
+        drop(running);
+        drop(normal.await.unwrap());
+        drop(high.await.unwrap());
+
+        let stats = controller.stats();
+        assert_eq!(stats.total_shed(), 2);
+        assert_eq!(stats.shed_by_tenant["a"], 1);
+        assert_eq!(stats.shed_by_tenant["d"], 1);
+    }
+}
@@ -0,0 +1,399 @@
+//! Ensemble drift monitoring
+//!
+//! The statistical and ML analyzers are trained on different views of the same traffic,
+//! so their verdicts normally move together. When they start disagreeing, or when either
+//! score distribution shifts away from what it looked like at deployment, a model, a
+//! baseline or the traffic itself has changed. [`DriftMonitor`] keeps a frozen reference
+//! window and a sliding current window of score pairs and compares them with:
+//!
+//! - inter-analyzer agreement rate (both scores on the same side of the decision threshold)
+//! - Population Stability Index (PSI) per analyzer
+//! - KL divergence `D(current ‖ reference)` per analyzer
+//!
+//! Drift beyond the configured thresholds is surfaced as
+//! [`AlertCategory::Configuration`] alerts.
+
+use std::collections::{HashMap, VecDeque};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::{Alert, AlertCategory, AlertSeverity};
+
+/// Probability floor for empty histogram bins
+const BIN_EPSILON: f64 = 1e-4;
+
+/// Drift monitor configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftConfig {
+    /// Observations collected before the reference window is frozen
+    pub reference_size: usize,
+    /// Size of the sliding current window
+    pub window_size: usize,
+    /// Number of equal-width histogram bins over `[0, 1]`
+    pub bins: usize,
+    /// Score at or above which an analyzer flags the fingerprint
+    pub decision_threshold: f64,
+    /// Alert when agreement falls this far below the reference agreement
+    pub max_agreement_drop: f64,
+    /// Alert when PSI exceeds this (0.1 moderate, 0.25 significant shift)
+    pub psi_threshold: f64,
+    /// Alert when KL divergence exceeds this
+    pub kl_threshold: f64,
+}
+
+impl Default for DriftConfig {
+    fn default() -> Self {
+        Self {
+            reference_size: 1000,
+            window_size: 500,
+            bins: 10,
+            decision_threshold: 0.5,
+            max_agreement_drop: 0.1,
+            psi_threshold: 0.25,
+            kl_threshold: 0.2,
+        }
+    }
+}
+
+/// One statistical / ML score pair
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScorePair {
+    pub statistical: f64,
+    pub ml: f64,
+}
+
+/// Comparison of the current window against the reference window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DriftReport {
+    pub reference_agreement: f64,
+    pub current_agreement: f64,
+    pub statistical_psi: f64,
+    pub ml_psi: f64,
+    pub statistical_kl: f64,
+    pub ml_kl: f64,
+    /// Observations in the current window
+    pub window_len: usize,
+}
+
+impl DriftReport {
+    /// Agreement lost since the reference window (negative when agreement improved)
+    pub fn agreement_drop(&self) -> f64 {
+        self.reference_agreement - self.current_agreement
+    }
+
+    /// Names and values of the metrics that exceed their thresholds
+    pub fn violations(&self, config: &DriftConfig) -> Vec<(&'static str, f64, f64)> {
+        let checks = [
+            (
+                "agreement_drop",
+                self.agreement_drop(),
+                config.max_agreement_drop,
+            ),
+            (
+                "statistical_psi",
+                self.statistical_psi,
+                config.psi_threshold,
+            ),
+            ("ml_psi", self.ml_psi, config.psi_threshold),
+            ("statistical_kl", self.statistical_kl, config.kl_threshold),
+            ("ml_kl", self.ml_kl, config.kl_threshold),
+        ];
+        checks
+            .into_iter()
+            .filter(|(_, value, threshold)| value > threshold)
+            .collect()
+    }
+
+    /// Whether any metric exceeds its threshold
+    pub fn is_drifting(&self, config: &DriftConfig) -> bool {
+        !self.violations(config).is_empty()
+    }
+}
+
+#[derive(Default)]
+struct DriftState {
+    reference: Vec<ScorePair>,
+    current: VecDeque<ScorePair>,
+    /// Observations since the last alert, so one shift does not alert on every result
+    since_alert: usize,
+}
+
+/// Tracks analyzer agreement and score distribution drift
+pub struct DriftMonitor {
+    config: DriftConfig,
+    state: Mutex<DriftState>,
+}
+
+impl DriftMonitor {
+    pub fn new(config: DriftConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(DriftState::default()),
+        }
+    }
+
+    pub fn config(&self) -> &DriftConfig {
+        &self.config
+    }
+
+    /// Replace the reference window (e.g. with scores from a validation run)
+    pub fn set_reference(&self, reference: Vec<ScorePair>) {
+        let mut state = self.state.lock();
+        state.reference = reference;
+        state.current.clear();
+        state.since_alert = 0;
+    }
+
+    /// Whether the reference window is complete
+    pub fn has_reference(&self) -> bool {
+        self.state.lock().reference.len() >= self.config.reference_size
+    }
+
+    /// Record a score pair
+    ///
+    /// The first `reference_size` observations build the reference window; later ones
+    /// slide through the current window. Returns a report once the current window is full.
+    pub fn observe(&self, pair: ScorePair) -> Option<DriftReport> {
+        let mut state = self.state.lock();
+        if state.reference.len() < self.config.reference_size {
+            state.reference.push(pair);
+            return None;
+        }
+
+        state.current.push_back(pair);
+        if state.current.len() > self.config.window_size {
+            state.current.pop_front();
+        }
+        state.since_alert += 1;
+
+        if state.current.len() < self.config.window_size {
+            return None;
+        }
+        Some(self.compare(&state))
+    }
+
+    /// Compare the current window against the reference, if both have data
+    pub fn report(&self) -> Option<DriftReport> {
+        let state = self.state.lock();
+        if state.reference.is_empty() || state.current.is_empty() {
+            return None;
+        }
+        Some(self.compare(&state))
+    }
+
+    fn compare(&self, state: &DriftState) -> DriftReport {
+        let current: Vec<ScorePair> = state.current.iter().copied().collect();
+        let bins = self.config.bins;
+
+        let reference_stat: Vec<f64> = state.reference.iter().map(|p| p.statistical).collect();
+        let reference_ml: Vec<f64> = state.reference.iter().map(|p| p.ml).collect();
+        let current_stat: Vec<f64> = current.iter().map(|p| p.statistical).collect();
+        let current_ml: Vec<f64> = current.iter().map(|p| p.ml).collect();
+
+        DriftReport {
+            reference_agreement: agreement_rate(&state.reference, self.config.decision_threshold),
+            current_agreement: agreement_rate(&current, self.config.decision_threshold),
+            statistical_psi: population_stability_index(&reference_stat, &current_stat, bins),
+            ml_psi: population_stability_index(&reference_ml, &current_ml, bins),
+            statistical_kl: kl_divergence(&current_stat, &reference_stat, bins),
+            ml_kl: kl_divergence(&current_ml, &reference_ml, bins),
+            window_len: current.len(),
+        }
+    }
+
+    /// Build a Configuration alert for a drifting report
+    ///
+    /// Severity escalates to Critical when any metric is over twice its threshold.
+    pub fn alert_for(&self, report: &DriftReport) -> Option<Alert> {
+        let violations = report.violations(&self.config);
+        if violations.is_empty() {
+            return None;
+        }
+
+        let severity = if violations.iter().any(|(_, v, t)| *v > t * 2.0) {
+            AlertSeverity::Critical
+        } else {
+            AlertSeverity::Warning
+        };
+        let summary: Vec<String> = violations
+            .iter()
+            .map(|(name, value, threshold)| format!("{}={:.3} (>{:.3})", name, value, threshold))
+            .collect();
+
+        let mut metadata = HashMap::new();
+        if let Ok(serde_json::Value::Object(fields)) = serde_json::to_value(report) {
+            metadata.extend(fields);
+        }
+        metadata.insert(
+            "violations".to_string(),
+            serde_json::json!(violations.iter().map(|(n, _, _)| *n).collect::<Vec<_>>()),
+        );
+
+        Some(Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            severity,
+            category: AlertCategory::Configuration,
+            message: format!("Analyzer drift detected: {}", summary.join(", ")),
+            timestamp: chrono::Utc::now(),
+            metadata,
+        })
+    }
+
+    /// Record a pair and return an alert if drift exceeds the thresholds
+    ///
+    /// After an alert, the next one is held back until a full window of new observations
+    /// has been seen.
+    pub fn check(&self, pair: ScorePair) -> Option<Alert> {
+        let report = self.observe(pair)?;
+        let alert = self.alert_for(&report)?;
+
+        let mut state = self.state.lock();
+        if state.since_alert < self.config.window_size {
+            return None;
+        }
+        state.since_alert = 0;
+        Some(alert)
+    }
+}
+
+impl Default for DriftMonitor {
+    fn default() -> Self {
+        Self::new(DriftConfig::default())
+    }
+}
+
+#[cfg(all(feature = "statistical", feature = "machine-learning"))]
+impl crate::AlertGenerator for DriftMonitor {
+    fn generate_alerts(&self, result: &crate::AnalysisResult) -> Vec<Alert> {
+        let (Some(stat), Some(ml)) = (&result.statistical, &result.ml) else {
+            return vec![];
+        };
+        self.check(ScorePair {
+            statistical: stat.anomaly_score,
+            ml: ml.risk_score,
+        })
+        .into_iter()
+        .collect()
+    }
+}
+
+/// Share of pairs where both analyzers land on the same side of the threshold
+pub fn agreement_rate(pairs: &[ScorePair], threshold: f64) -> f64 {
+    if pairs.is_empty() {
+        return 1.0;
+    }
+    let agreeing = pairs
+        .iter()
+        .filter(|p| (p.statistical >= threshold) == (p.ml >= threshold))
+        .count();
+    agreeing as f64 / pairs.len() as f64
+}
+
+/// Normalized histogram of scores over `[0, 1]` with empty bins floored at a small epsilon
+fn histogram(scores: &[f64], bins: usize) -> Vec<f64> {
+    let bins = bins.max(1);
+    let mut counts = vec![0usize; bins];
+    for &score in scores {
+        let idx = ((score.clamp(0.0, 1.0) * bins as f64) as usize).min(bins - 1);
+        counts[idx] += 1;
+    }
+    let total = scores.len().max(1) as f64;
+    counts
+        .into_iter()
+        .map(|c| (c as f64 / total).max(BIN_EPSILON))
+        .collect()
+}
+
+/// Population Stability Index between two score samples
+pub fn population_stability_index(reference: &[f64], current: &[f64], bins: usize) -> f64 {
+    histogram(reference, bins)
+        .into_iter()
+        .zip(histogram(current, bins))
+        .map(|(r, c)| (c - r) * (c / r).ln())
+        .sum()
+}
+
+/// KL divergence `D(p ‖ q)` between two score samples
+pub fn kl_divergence(p: &[f64], q: &[f64], bins: usize) -> f64 {
+    histogram(p, bins)
+        .into_iter()
+        .zip(histogram(q, bins))
+        .map(|(p, q)| p * (p / q).ln())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> DriftConfig {
+        DriftConfig {
+            reference_size: 200,
+            window_size: 100,
+            ..DriftConfig::default()
+        }
+    }
+
+    /// Deterministic scores spread over `[lo, hi)`
+    fn spread(i: usize, lo: f64, hi: f64) -> f64 {
+        lo + (hi - lo) * ((i * 37) % 100) as f64 / 100.0
+    }
+
+    #[test]
+    fn test_stable_scores_do_not_alert() {
+        let monitor = DriftMonitor::new(config());
+        for i in 0..600 {
+            let s = spread(i, 0.0, 0.4);
+            let alert = monitor.check(ScorePair {
+                statistical: s,
+                ml: s + 0.05,
+            });
+            assert!(alert.is_none());
+        }
+        let report = monitor.report().unwrap();
+        assert!(report.statistical_psi < 0.05);
+        assert_eq!(report.current_agreement, 1.0);
+    }
+
+    #[test]
+    fn test_disagreement_raises_configuration_alert() {
+        let monitor = DriftMonitor::new(config());
+        for i in 0..200 {
+            let s = spread(i, 0.0, 0.4);
+            monitor.observe(ScorePair {
+                statistical: s,
+                ml: s,
+            });
+        }
+
+        // the ML model starts flagging traffic the statistical analyzer considers clean
+        let alerts: Vec<Alert> = (0..300)
+            .filter_map(|i| {
+                monitor.check(ScorePair {
+                    statistical: spread(i, 0.0, 0.4),
+                    ml: spread(i, 0.6, 1.0),
+                })
+            })
+            .collect();
+
+        assert_eq!(alerts.len(), 3);
+        let alert = &alerts[0];
+        assert!(matches!(alert.category, AlertCategory::Configuration));
+        assert_eq!(alert.severity, AlertSeverity::Critical);
+        assert!(alert.message.contains("ml_psi"));
+        assert!(alert.message.contains("agreement_drop"));
+        assert!(!alert.message.contains("statistical_psi"));
+    }
+
+    #[test]
+    fn test_divergence_metrics() {
+        let a: Vec<f64> = (0..100).map(|i| spread(i, 0.0, 1.0)).collect();
+        assert!(population_stability_index(&a, &a, 10).abs() < 1e-9);
+        assert!(kl_divergence(&a, &a, 10).abs() < 1e-9);
+
+        let b: Vec<f64> = (0..100).map(|i| spread(i, 0.5, 1.0)).collect();
+        assert!(population_stability_index(&a, &b, 10) > 0.25);
+        assert!(kl_divergence(&b, &a, 10) > 0.2);
+    }
+}
@@ -0,0 +1,541 @@
+//! Persistent history for [`HistoricalAnalyzer`](crate::HistoricalAnalyzer)
+//!
+//! Trend and pattern analysis is only as good as the history behind it, and an
+//! in-memory map forgets everything on restart. [`HistoryStore`] abstracts where
+//! [`HistoricalRecord`]s live:
+//!
+//! - [`MemoryHistoryStore`]: in-process, the default; lost on restart
+//! - [`JsonlHistoryStore`]: one JSON line per record in daily files
+//!   (`history-YYYY-MM-DD.jsonl`), so time-range queries only open the days they
+//!   cover and old days can be archived or deleted as whole files
+//! - `SqliteHistoryStore` (feature `history-sqlite`): a single SQLite database
+//!   indexed by fingerprint ID and timestamp
+//!
+//! All stores return query results in ascending timestamp order.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::AnalysisError;
+
+/// One observation of a fingerprint, as kept in history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoricalRecord {
+    pub timestamp: DateTime<Utc>,
+    pub fingerprint_id: String,
+    pub features: serde_json::Value,
+    pub classification: String,
+}
+
+/// Filter for [`HistoryStore::query`]; unset fields match everything
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryQuery {
+    /// Only records of this fingerprint
+    pub fingerprint_id: Option<String>,
+    /// Inclusive lower bound
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound
+    pub to: Option<DateTime<Utc>>,
+    /// Keep only the most recent `limit` matches
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    /// Query matching every record
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Records of one fingerprint
+    pub fn fingerprint(id: impl Into<String>) -> Self {
+        Self {
+            fingerprint_id: Some(id.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Restrict to `[from, to)`
+    pub fn between(mut self, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        self.from = Some(from);
+        self.to = Some(to);
+        self
+    }
+
+    /// Restrict to records at or after `from`
+    pub fn since(mut self, from: DateTime<Utc>) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Keep only the most recent `limit` matches
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Whether `record` passes the ID and time filters
+    pub fn matches(&self, record: &HistoricalRecord) -> bool {
+        self.fingerprint_id
+            .as_deref()
+            .is_none_or(|id| record.fingerprint_id == id)
+            && self.from.is_none_or(|from| record.timestamp >= from)
+            && self.to.is_none_or(|to| record.timestamp < to)
+    }
+
+    /// Sort ascending and apply `limit`
+    fn finish(&self, mut records: Vec<HistoricalRecord>) -> Vec<HistoricalRecord> {
+        records.sort_by_key(|r| r.timestamp);
+        if let Some(limit) = self.limit {
+            let skip = records.len().saturating_sub(limit);
+            records.drain(..skip);
+        }
+        records
+    }
+}
+
+/// Storage backend for historical records
+pub trait HistoryStore: Send + Sync {
+    /// Persist one record
+    fn append(&self, record: &HistoricalRecord) -> Result<(), AnalysisError>;
+
+    /// Records matching `query`, oldest first
+    fn query(&self, query: &HistoryQuery) -> Result<Vec<HistoricalRecord>, AnalysisError>;
+
+    /// Delete records older than `cutoff`, returning how many were removed
+    fn prune_before(&self, cutoff: DateTime<Utc>) -> Result<usize, AnalysisError>;
+
+    /// Number of records matching `query`
+    fn count(&self, query: &HistoryQuery) -> Result<usize, AnalysisError> {
+        Ok(self.query(query)?.len())
+    }
+}
+
+fn store_error(context: &str, e: impl std::fmt::Display) -> AnalysisError {
+    AnalysisError::HistoricalError(format!("{}: {}", context, e))
+}
+
+/// In-memory store, keyed by fingerprint ID
+#[derive(Default)]
+pub struct MemoryHistoryStore {
+    records: DashMap<String, Vec<HistoricalRecord>>,
+}
+
+impl MemoryHistoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl HistoryStore for MemoryHistoryStore {
+    fn append(&self, record: &HistoricalRecord) -> Result<(), AnalysisError> {
+        self.records
+            .entry(record.fingerprint_id.clone())
+            .or_default()
+            .push(record.clone());
+        Ok(())
+    }
+
+    fn query(&self, query: &HistoryQuery) -> Result<Vec<HistoricalRecord>, AnalysisError> {
+        let matching = |records: &Vec<HistoricalRecord>| -> Vec<HistoricalRecord> {
+            records
+                .iter()
+                .filter(|r| query.matches(r))
+                .cloned()
+                .collect()
+        };
+        let records = match &query.fingerprint_id {
+            Some(id) => self
+                .records
+                .get(id)
+                .map(|r| matching(&r))
+                .unwrap_or_default(),
+            None => self.records.iter().flat_map(|r| matching(&r)).collect(),
+        };
+        Ok(query.finish(records))
+    }
+
+    fn prune_before(&self, cutoff: DateTime<Utc>) -> Result<usize, AnalysisError> {
+        let mut removed = 0;
+        self.records.retain(|_, records| {
+            let before = records.len();
+            records.retain(|r| r.timestamp >= cutoff);
+            removed += before - records.len();
+            !records.is_empty()
+        });
+        Ok(removed)
+    }
+}
+
+/// JSON Lines store partitioned into one file per UTC day
+pub struct JsonlHistoryStore {
+    dir: PathBuf,
+    /// Serializes appends and pruning
+    write_lock: Mutex<()>,
+}
+
+impl JsonlHistoryStore {
+    const PREFIX: &'static str = "history-";
+    const SUFFIX: &'static str = ".jsonl";
+
+    /// Store in `dir`, created if missing
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, AnalysisError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(|e| store_error("create history directory", e))?;
+        Ok(Self {
+            dir,
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    fn path_for(&self, day: NaiveDate) -> PathBuf {
+        self.dir.join(format!(
+            "{}{}{}",
+            Self::PREFIX,
+            day.format("%Y-%m-%d"),
+            Self::SUFFIX
+        ))
+    }
+
+    /// Day files present in the directory, oldest first
+    fn days(&self) -> Result<Vec<(NaiveDate, PathBuf)>, AnalysisError> {
+        let mut days: Vec<(NaiveDate, PathBuf)> = fs::read_dir(&self.dir)
+            .map_err(|e| store_error("list history directory", e))?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let name = path.file_name()?.to_str()?;
+                let day = name
+                    .strip_prefix(Self::PREFIX)?
+                    .strip_suffix(Self::SUFFIX)?;
+                let day = NaiveDate::parse_from_str(day, "%Y-%m-%d").ok()?;
+                Some((day, path))
+            })
+            .collect();
+        days.sort();
+        Ok(days)
+    }
+
+    fn read_day(path: &Path, query: &HistoryQuery) -> Result<Vec<HistoricalRecord>, AnalysisError> {
+        let file = File::open(path).map_err(|e| store_error("open history file", e))?;
+        let mut records = Vec::new();
+        for (n, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| store_error("read history file", e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // A torn last line from a crash mid-append is skipped, not fatal
+            match serde_json::from_str::<HistoricalRecord>(&line) {
+                Ok(record) if query.matches(&record) => records.push(record),
+                Ok(_) => {}
+                Err(e) => log::warn!(
+                    "Skipping bad history line {}:{}: {}",
+                    path.display(),
+                    n + 1,
+                    e
+                ),
+            }
+        }
+        Ok(records)
+    }
+}
+
+impl HistoryStore for JsonlHistoryStore {
+    fn append(&self, record: &HistoricalRecord) -> Result<(), AnalysisError> {
+        let mut line =
+            serde_json::to_string(record).map_err(|e| store_error("encode history record", e))?;
+        line.push('\n');
+
+        let _guard = self.write_lock.lock();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path_for(record.timestamp.date_naive()))
+            .map_err(|e| store_error("open history file", e))?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| store_error("write history file", e))
+    }
+
+    fn query(&self, query: &HistoryQuery) -> Result<Vec<HistoricalRecord>, AnalysisError> {
+        let first = query.from.map(|t| t.date_naive());
+        let last = query.to.map(|t| t.date_naive());
+
+        let mut records = Vec::new();
+        for (day, path) in self.days()? {
+            if first.is_some_and(|f| day < f) || last.is_some_and(|l| day > l) {
+                continue;
+            }
+            records.extend(Self::read_day(&path, query)?);
+        }
+        Ok(query.finish(records))
+    }
+
+    fn prune_before(&self, cutoff: DateTime<Utc>) -> Result<usize, AnalysisError> {
+        let _guard = self.write_lock.lock();
+        let cutoff_day = cutoff.date_naive();
+        let mut removed = 0;
+
+        for (day, path) in self.days()? {
+            if day > cutoff_day {
+                break;
+            }
+            let all = Self::read_day(&path, &HistoryQuery::all())?;
+            let (keep, drop): (Vec<_>, Vec<_>) =
+                all.into_iter().partition(|r| r.timestamp >= cutoff);
+            removed += drop.len();
+
+            if keep.is_empty() {
+                fs::remove_file(&path).map_err(|e| store_error("remove history file", e))?;
+            } else if !drop.is_empty() {
+                // Only the cutoff day is partially kept; rewrite it via a temp file
+                let tmp = path.with_extension("jsonl.tmp");
+                let mut out =
+                    File::create(&tmp).map_err(|e| store_error("write history file", e))?;
+                for record in &keep {
+                    let line = serde_json::to_string(record)
+                        .map_err(|e| store_error("encode history record", e))?;
+                    writeln!(out, "{}", line).map_err(|e| store_error("write history file", e))?;
+                }
+                fs::rename(&tmp, &path).map_err(|e| store_error("replace history file", e))?;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(feature = "history-sqlite")]
+pub use sqlite::SqliteHistoryStore;
+
+#[cfg(feature = "history-sqlite")]
+mod sqlite {
+    use super::*;
+    use rusqlite::{params, Connection};
+
+    /// SQLite store
+    ///
+    /// Timestamps are stored as Unix microseconds so range scans use the index.
+    pub struct SqliteHistoryStore {
+        conn: Mutex<Connection>,
+    }
+
+    impl SqliteHistoryStore {
+        /// Open or create the database at `path`
+        pub fn open(path: impl AsRef<Path>) -> Result<Self, AnalysisError> {
+            let conn =
+                Connection::open(path).map_err(|e| store_error("open history database", e))?;
+            Self::init(conn)
+        }
+
+        /// Database that lives only as long as the store
+        pub fn in_memory() -> Result<Self, AnalysisError> {
+            let conn = Connection::open_in_memory()
+                .map_err(|e| store_error("open history database", e))?;
+            Self::init(conn)
+        }
+
+        fn init(conn: Connection) -> Result<Self, AnalysisError> {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS history (
+                     id INTEGER PRIMARY KEY AUTOINCREMENT,
+                     ts_micros INTEGER NOT NULL,
+                     fingerprint_id TEXT NOT NULL,
+                     classification TEXT NOT NULL,
+                     features TEXT NOT NULL
+                 );
+                 CREATE INDEX IF NOT EXISTS idx_history_fp_ts ON history (fingerprint_id, ts_micros);
+                 CREATE INDEX IF NOT EXISTS idx_history_ts ON history (ts_micros);",
+            )
+            .map_err(|e| store_error("create history schema", e))?;
+            Ok(Self {
+                conn: Mutex::new(conn),
+            })
+        }
+    }
+
+    impl HistoryStore for SqliteHistoryStore {
+        fn append(&self, record: &HistoricalRecord) -> Result<(), AnalysisError> {
+            let features = serde_json::to_string(&record.features)
+                .map_err(|e| store_error("encode history record", e))?;
+            self.conn
+                .lock()
+                .execute(
+                    "INSERT INTO history (ts_micros, fingerprint_id, classification, features)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![
+                        record.timestamp.timestamp_micros(),
+                        record.fingerprint_id,
+                        record.classification,
+                        features
+                    ],
+                )
+                .map_err(|e| store_error("insert history record", e))?;
+            Ok(())
+        }
+
+        fn query(&self, query: &HistoryQuery) -> Result<Vec<HistoricalRecord>, AnalysisError> {
+            // Newest first so LIMIT keeps the most recent; reversed below
+            let sql = "SELECT ts_micros, fingerprint_id, classification, features FROM history
+                       WHERE (?1 IS NULL OR fingerprint_id = ?1)
+                         AND (?2 IS NULL OR ts_micros >= ?2)
+                         AND (?3 IS NULL OR ts_micros < ?3)
+                       ORDER BY ts_micros DESC, id DESC
+                       LIMIT ?4";
+            let conn = self.conn.lock();
+            let mut stmt = conn
+                .prepare_cached(sql)
+                .map_err(|e| store_error("query history", e))?;
+            let rows = stmt
+                .query_map(
+                    params![
+                        query.fingerprint_id,
+                        query.from.map(|t| t.timestamp_micros()),
+                        query.to.map(|t| t.timestamp_micros()),
+                        query.limit.map_or(-1, |l| l as i64),
+                    ],
+                    |row| {
+                        Ok((
+                            row.get::<_, i64>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, String>(2)?,
+                            row.get::<_, String>(3)?,
+                        ))
+                    },
+                )
+                .map_err(|e| store_error("query history", e))?;
+
+            let mut records = Vec::new();
+            for row in rows {
+                let (ts, fingerprint_id, classification, features) =
+                    row.map_err(|e| store_error("read history row", e))?;
+                records.push(HistoricalRecord {
+                    timestamp: DateTime::from_timestamp_micros(ts).unwrap_or_default(),
+                    fingerprint_id,
+                    classification,
+                    features: serde_json::from_str(&features)
+                        .map_err(|e| store_error("decode history record", e))?,
+                });
+            }
+            records.reverse();
+            Ok(records)
+        }
+
+        fn prune_before(&self, cutoff: DateTime<Utc>) -> Result<usize, AnalysisError> {
+            self.conn
+                .lock()
+                .execute(
+                    "DELETE FROM history WHERE ts_micros < ?1",
+                    params![cutoff.timestamp_micros()],
+                )
+                .map_err(|e| store_error("prune history", e))
+        }
+
+        fn count(&self, query: &HistoryQuery) -> Result<usize, AnalysisError> {
+            if query.limit.is_some() {
+                return Ok(self.query(query)?.len());
+            }
+            self.conn
+                .lock()
+                .query_row(
+                    "SELECT COUNT(*) FROM history
+                     WHERE (?1 IS NULL OR fingerprint_id = ?1)
+                       AND (?2 IS NULL OR ts_micros >= ?2)
+                       AND (?3 IS NULL OR ts_micros < ?3)",
+                    params![
+                        query.fingerprint_id,
+                        query.from.map(|t| t.timestamp_micros()),
+                        query.to.map(|t| t.timestamp_micros()),
+                    ],
+                    |row| row.get::<_, i64>(0),
+                )
+                .map(|n| n as usize)
+                .map_err(|e| store_error("count history", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn record(id: &str, day: u32, hour: u32, classification: &str) -> HistoricalRecord {
+        HistoricalRecord {
+            timestamp: Utc.with_ymd_and_hms(2025, 3, day, hour, 0, 0).unwrap(),
+            fingerprint_id: id.to_string(),
+            features: serde_json::json!({ "ja4": format!("t13d_{}", id) }),
+            classification: classification.to_string(),
+        }
+    }
+
+    /// Same contract for every backend
+    fn exercise(store: &dyn HistoryStore) {
+        for r in [
+            record("fp-a", 3, 10, "bot"),
+            record("fp-b", 1, 9, "benign"),
+            record("fp-a", 1, 12, "benign"),
+            record("fp-a", 2, 23, "bot"),
+        ] {
+            store.append(&r).unwrap();
+        }
+
+        let a = store.query(&HistoryQuery::fingerprint("fp-a")).unwrap();
+        let days: Vec<u32> = a
+            .iter()
+            .map(|r| r.timestamp.format("%d").to_string().parse().unwrap())
+            .collect();
+        assert_eq!(days, vec![1, 2, 3]);
+        assert_eq!(a[0].features["ja4"], "t13d_fp-a");
+
+        let from = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+        let ranged = HistoryQuery::all().between(from, from + Duration::days(1));
+        let ranged = store.query(&ranged).unwrap();
+        assert_eq!(ranged.len(), 1);
+        assert_eq!(ranged[0].fingerprint_id, "fp-a");
+
+        let latest = store
+            .query(&HistoryQuery::fingerprint("fp-a").limit(1))
+            .unwrap();
+        assert_eq!(latest[0].classification, "bot");
+        assert_eq!(latest[0].timestamp, record("", 3, 10, "").timestamp);
+        assert_eq!(store.count(&HistoryQuery::all()).unwrap(), 4);
+
+        let cutoff = Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap();
+        assert_eq!(store.prune_before(cutoff).unwrap(), 1);
+        assert_eq!(store.count(&HistoryQuery::fingerprint("fp-b")).unwrap(), 0);
+        assert_eq!(store.count(&HistoryQuery::all()).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_memory_store() {
+        exercise(&MemoryHistoryStore::new());
+    }
+
+    #[test]
+    fn test_jsonl_store_persists_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        exercise(&JsonlHistoryStore::open(dir.path()).unwrap());
+
+        // Day 1 was rewritten by pruning, days 2 and 3 untouched
+        let reopened = JsonlHistoryStore::open(dir.path()).unwrap();
+        assert_eq!(reopened.days().unwrap().len(), 3);
+        assert_eq!(
+            reopened.count(&HistoryQuery::fingerprint("fp-a")).unwrap(),
+            3
+        );
+    }
+
+    #[cfg(feature = "history-sqlite")]
+    #[test]
+    fn test_sqlite_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        exercise(&SqliteHistoryStore::open(&path).unwrap());
+
+        let reopened = SqliteHistoryStore::open(&path).unwrap();
+        assert_eq!(reopened.count(&HistoryQuery::all()).unwrap(), 3);
+    }
+}
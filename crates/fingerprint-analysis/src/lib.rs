@@ -7,29 +7,96 @@
 //! ## Features
 //!
 //! - ✅ **Statistical Analysis**: Z-score, distribution analysis, correlation detection
-//! - ✅ **Machine Learning**: Ensemble methods, clustering, classification
+//! - ✅ **Machine Learning**: Ensemble methods, clustering, classification; ONNX models
+//!   exported from Python loaded from `analysis.ml.models` (`onnx` feature)
 //! - ✅ **Real-time Monitoring**: Streaming analysis, alert generation
 //! - ✅ **Historical Analysis**: Trend detection, pattern recognition, anomaly history
+//!   persisted in a pluggable `HistoryStore` (memory, JSONL, SQLite)
+//! - ✅ **Drift Monitoring**: Analyzer agreement, PSI / KL divergence against a reference window
+//! - ✅ **Model Monitoring**: Feature PSI and precision / recall alerts from a fingerprint-ml
+//!   `ModelMonitor` (`model-monitoring` feature)
+//! - ✅ **Sensor Sync**: zstd-framed binary observation batches from remote sensors
+//! - ✅ **Output Schema**: Versioned JSON Schema for `AnalysisResult` / `Alert` documents
+//! - ✅ **Admission Control**: Per-tenant token buckets, fair queuing and load shedding
 //!
 //! ## Architecture
 //!
 //! ```text
 //! AnalysisEngine
+//! ├── AdmissionController ──→ Per-tenant rate limits and fair scheduling
 //! ├── StatisticalAnalyzer ──→ Basic statistical computations
 //! ├── MLAnalyzer ──→ Machine learning model inference
 //! ├── RealTimeMonitor ──→ Live data stream processing
 //! ├── HistoricalAnalyzer ──→ Pattern and trend analysis
-//! └── AlertGenerator ──→ Anomaly detection and notification
+//! ├── AlertGenerator ──→ Anomaly detection and notification
+//! ├── DriftMonitor ──→ Analyzer disagreement and score drift alerts
+//! └── ModelMonitorAlerts ──→ Model input drift and precision / recall alerts
 //! ```
 
 use std::collections::HashMap;
 use std::sync::Arc;
 use dashmap::DashMap;
 use parking_lot::RwLock;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use fingerprint_core::events::{self, AlertRaised, AnalysisCompleted};
 use fingerprint_core::fingerprint::{Fingerprint, FingerprintComparison};
-use fingerprint_config::ConfigManager;
+use fingerprint_config::{ConfigAlert, ConfigManager};
+
+/// Runs an analyzer future inside its own `tracing` span (`otel` feature)
+macro_rules! stage {
+    ($name:literal, $future:expr) => {{
+        #[cfg(feature = "otel")]
+        let future = tracing::Instrument::instrument(
+            $future,
+            tracing::info_span!(target: "fingerprint_analysis", $name),
+        );
+        #[cfg(not(feature = "otel"))]
+        let future = $future;
+        future
+    }};
+}
+
+pub mod admission;
+
+pub use admission::{
+    AdmissionConfig, AdmissionController, AdmissionPermit, AdmissionRequest, AdmissionStats,
+    Priority, RateLimit, Shed, ShedReason,
+};
+
+pub mod drift;
+
+pub use drift::{DriftConfig, DriftMonitor, DriftReport, ScorePair};
+
+pub mod sync;
+
+pub use sync::{
+    Collector, FrameDecoder, Observation, ObservationBatch, ObservationKind, ObservationSender,
+    SenderConfig, SyncError, TcpTransport, Transport,
+};
+
+pub mod schema;
+
+pub use schema::{SchemaDocument, SCHEMA_VERSION};
+
+pub mod stix;
+
+pub use stix::alerts_bundle;
+
+#[cfg(feature = "machine-learning")]
+pub mod onnx;
+
+#[cfg(feature = "machine-learning")]
+pub use onnx::{FeatureInput, MlModelsSection, OnnxModelConfig};
+#[cfg(feature = "onnx")]
+pub use onnx::OnnxModel;
+
+#[cfg(feature = "model-monitoring")]
+pub mod model_monitor;
+
+#[cfg(feature = "model-monitoring")]
+pub use model_monitor::{ModelMonitorAlerts, ModelMonitorSection};
 
 /// Analysis engine error types
 #[derive(Error, Debug)]
@@ -44,6 +111,8 @@ pub enum AnalysisError {
     HistoricalError(String),
     #[error("Configuration error: {0}")]
     ConfigError(#[from] fingerprint_config::ConfigError),
+    #[error("Analysis request shed: {0}")]
+    Shed(#[from] Shed),
 }
 
 /// Main analysis engine
@@ -72,10 +141,13 @@ pub struct AnalysisEngine {
     
     /// Alert generators
     alert_generators: RwLock<Vec<Box<dyn AlertGenerator>>>,
+    
+    /// Per-tenant admission control for `analyze_as`
+    admission: AdmissionController,
 }
 
 /// Analysis result containing all analysis outputs
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AnalysisResult {
     /// Unique analysis ID
     pub id: String,
@@ -113,18 +185,24 @@ pub struct AnalysisResult {
 }
 
 /// Alert structure for anomaly notifications
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Alert {
+    /// Unique alert ID
     pub id: String,
+    /// Alert severity
     pub severity: AlertSeverity,
+    /// Alert category
     pub category: AlertCategory,
+    /// Human-readable message
     pub message: String,
+    /// Time the alert was raised
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Additional context (matched rule, fingerprint values, ...)
     pub metadata: HashMap<String, serde_json::Value>,
 }
 
 /// Alert severity levels
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub enum AlertSeverity {
     Info,
     Warning,
@@ -133,7 +211,7 @@ pub enum AlertSeverity {
 }
 
 /// Alert categories
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum AlertCategory {
     Anomaly,
     Suspicious,
@@ -141,6 +219,36 @@ pub enum AlertCategory {
     Configuration,
 }
 
+/// Rejected configuration reloads surface as Warning Configuration alerts,
+/// with the validation errors under `errors` in the metadata.
+impl From<&ConfigAlert> for Alert {
+    fn from(alert: &ConfigAlert) -> Self {
+        let mut metadata = HashMap::new();
+        metadata.insert("errors".to_string(), serde_json::json!(alert.errors));
+        Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            severity: AlertSeverity::Warning,
+            category: AlertCategory::Configuration,
+            message: alert.message.clone(),
+            timestamp: alert.timestamp.into(),
+            metadata,
+        }
+    }
+}
+
+impl From<&Alert> for AlertRaised {
+    fn from(alert: &Alert) -> Self {
+        AlertRaised {
+            source: "analysis".to_string(),
+            severity: format!("{:?}", alert.severity).to_lowercase(),
+            category: format!("{:?}", alert.category).to_lowercase(),
+            message: alert.message.clone(),
+            timestamp: alert.timestamp,
+            metadata: alert.metadata.clone(),
+        }
+    }
+}
+
 /// Alert generator trait
 pub trait AlertGenerator: Send + Sync {
     fn generate_alerts(&self, result: &AnalysisResult) -> Vec<Alert>;
@@ -150,21 +258,50 @@ impl AnalysisEngine {
     /// Create a new analysis engine
     pub fn new(config: Arc<ConfigManager>) -> Result<Self, AnalysisError> {
         Ok(Self {
+            #[cfg(feature = "machine-learning")]
+            ml: MLAnalyzer::from_config(&config)?,
             config,
             #[cfg(feature = "statistical")]
             statistical: StatisticalAnalyzer::new()?,
-            #[cfg(feature = "machine-learning")]
-            ml: MLAnalyzer::new()?,
             #[cfg(feature = "real-time")]
             real_time: RealTimeMonitor::new()?,
             #[cfg(feature = "historical")]
             historical: HistoricalAnalyzer::new()?,
             results_cache: DashMap::new(),
             alert_generators: RwLock::new(vec![]),
+            admission: AdmissionController::new(AdmissionConfig::default()),
         })
     }
 
+    /// Keep history in `store` instead of memory
+    #[cfg(feature = "historical")]
+    pub fn with_history_store(mut self, store: Arc<dyn HistoryStore>) -> Self {
+        self.historical = HistoricalAnalyzer::with_store(store);
+        self
+    }
+
+    /// Replace the admission limits applied by `analyze_as`
+    pub fn with_admission(mut self, config: AdmissionConfig) -> Self {
+        self.admission = AdmissionController::new(config);
+        self
+    }
+
+    /// Analyze a fingerprint on behalf of a tenant
+    ///
+    /// The call first passes admission control: it may wait for a slot, or fail with
+    /// [`AnalysisError::Shed`] when the tenant is over its rate or the queue overflows.
+    pub async fn analyze_as(&self, request: &AdmissionRequest, fingerprint: &dyn Fingerprint) -> Result<AnalysisResult, AnalysisError> {
+        let _permit = self.admission.acquire(request).await?;
+        self.analyze(fingerprint).await
+    }
+
+    /// Admission counters, including shed work per tenant
+    pub fn admission_stats(&self) -> AdmissionStats {
+        self.admission.stats()
+    }
+
     /// Analyze a fingerprint using all enabled analysis methods
+    #[cfg_attr(feature = "otel", tracing::instrument(name = "analysis.analyze", target = "fingerprint_analysis", skip_all, fields(fingerprint = %fingerprint.id())))]
     pub async fn analyze(&self, fingerprint: &dyn Fingerprint) -> Result<AnalysisResult, AnalysisError> {
         let analysis_id = uuid::Uuid::new_v4().to_string();
         
@@ -188,25 +325,25 @@ impl AnalysisEngine {
         // Run statistical analysis
         #[cfg(feature = "statistical")]
         {
-            result.statistical = Some(self.statistical.analyze(fingerprint).await?);
+            result.statistical = Some(stage!("analysis.statistical", self.statistical.analyze(fingerprint)).await?);
         }
 
         // Run machine learning analysis
         #[cfg(feature = "machine-learning")]
         {
-            result.ml = Some(self.ml.analyze(fingerprint).await?);
+            result.ml = Some(stage!("analysis.ml", self.ml.analyze(fingerprint)).await?);
         }
 
         // Run real-time analysis
         #[cfg(feature = "real-time")]
         {
-            result.real_time = Some(self.real_time.analyze(fingerprint).await?);
+            result.real_time = Some(stage!("analysis.real_time", self.real_time.analyze(fingerprint)).await?);
         }
 
         // Run historical analysis
         #[cfg(feature = "historical")]
         {
-            result.historical = Some(self.historical.analyze(fingerprint).await?);
+            result.historical = Some(stage!("analysis.historical", self.historical.analyze(fingerprint)).await?);
         }
 
         // Calculate overall scores
@@ -218,6 +355,8 @@ impl AnalysisEngine {
         // Cache the result
         self.results_cache.insert(analysis_id, result.clone());
 
+        self.publish_events(&result);
+
         Ok(result)
     }
 
@@ -296,6 +435,27 @@ impl AnalysisEngine {
         Ok(())
     }
 
+    /// Announce the result and its alerts on the global event bus
+    fn publish_events(&self, result: &AnalysisResult) {
+        let bus = events::global();
+        for alert in &result.alerts {
+            events::publish_alert(AlertRaised::from(alert));
+        }
+        bus.publish(AnalysisCompleted {
+            analysis_id: result.id.clone(),
+            risk_score: result.risk_score,
+            confidence: result.confidence,
+            alert_count: result.alerts.len(),
+        });
+    }
+
+    /// Raise alerts from `monitor` on every analysis
+    #[cfg(feature = "model-monitoring")]
+    pub fn with_model_monitor(self, monitor: Arc<fingerprint_ml::ModelMonitor>) -> Self {
+        self.add_alert_generator(Box::new(ModelMonitorAlerts::new(monitor)));
+        self
+    }
+
     /// Add an alert generator
     pub fn add_alert_generator(&self, generator: Box<dyn AlertGenerator>) {
         self.alert_generators.write().push(generator);
@@ -317,7 +477,7 @@ mod statistical {
         baseline_profiles: DashMap<String, serde_json::Value>,
     }
     
-    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
     pub struct StatisticalResult {
         pub anomaly_score: f64,
         pub confidence: f64,
@@ -379,7 +539,7 @@ mod ml {
         models: DashMap<String, Box<dyn MLModel>>,
     }
     
-    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
     pub struct MLResult {
         pub risk_score: f64,
         pub confidence: f64,
@@ -391,6 +551,24 @@ mod ml {
     pub trait MLModel: Send + Sync {
         fn predict(&self, features: &serde_json::Value) -> Result<HashMap<String, f64>, AnalysisError>;
         fn model_name(&self) -> &str;
+
+        /// Predict several feature documents; models with native batching override this
+        fn predict_batch(&self, features: &[serde_json::Value]) -> Result<Vec<HashMap<String, f64>>, AnalysisError> {
+            features.iter().map(|f| self.predict(f)).collect()
+        }
+    }
+
+    /// Document the models' feature inputs point into
+    ///
+    /// `{"type", "id", "hash", "metadata"}`, with `metadata` the serialized
+    /// `FingerprintMetadata`.
+    pub fn feature_document(fingerprint: &dyn Fingerprint) -> serde_json::Value {
+        serde_json::json!({
+            "type": fingerprint.fingerprint_type().as_str(),
+            "id": fingerprint.id(),
+            "hash": fingerprint.hash(),
+            "metadata": fingerprint.metadata(),
+        })
     }
     
     impl MLAnalyzer {
@@ -399,22 +577,102 @@ mod ml {
                 models: DashMap::new(),
             })
         }
+
+        /// Load the models listed under `analysis.ml.models`
+        ///
+        /// Listing models in a build without the `onnx` feature is an error.
+        pub fn from_config(config: &ConfigManager) -> Result<Self, AnalysisError> {
+            let section: MlModelsSection = config.section()?;
+            let analyzer = Self::new()?;
+            #[cfg(feature = "onnx")]
+            for model in section.models {
+                analyzer.register_model(Box::new(OnnxModel::load(model)?));
+            }
+            #[cfg(not(feature = "onnx"))]
+            if let Some(model) = section.models.first() {
+                return Err(AnalysisError::MLError(format!(
+                    "model {} is configured but the `onnx` feature is disabled",
+                    model.name
+                )));
+            }
+            Ok(analyzer)
+        }
+
+        /// Add a model, replacing any model of the same name
+        pub fn register_model(&self, model: Box<dyn MLModel>) {
+            self.models.insert(model.model_name().to_string(), model);
+        }
+
+        /// Names of the registered models, sorted
+        pub fn model_names(&self) -> Vec<String> {
+            let mut names: Vec<String> = self.models.iter().map(|m| m.key().clone()).collect();
+            names.sort();
+            names
+        }
         
         pub async fn analyze(&self, fingerprint: &dyn Fingerprint) -> Result<MLResult, AnalysisError> {
-            // Implementation would run ML inference
-            Ok(MLResult {
-                risk_score: 0.2,
-                confidence: 0.9,
-                predictions: HashMap::new(),
-                feature_importance: HashMap::new(),
-                model_used: "ensemble_model".to_string(),
-            })
+            let mut results = self.analyze_batch(&[fingerprint]).await?;
+            Ok(results.remove(0))
+        }
+
+        /// Run every registered model over the whole batch at once
+        ///
+        /// Predictions are keyed `<model>.<output>`. The risk score is the mean `risk`
+        /// output of the models that report one, and confidence is how far that mean
+        /// lies from 0.5. Without registered models a fixed placeholder result is
+        /// returned.
+        pub async fn analyze_batch(&self, fingerprints: &[&dyn Fingerprint]) -> Result<Vec<MLResult>, AnalysisError> {
+            let names = self.model_names();
+            if names.is_empty() {
+                return Ok(fingerprints
+                    .iter()
+                    .map(|_| MLResult {
+                        risk_score: 0.2,
+                        confidence: 0.9,
+                        predictions: HashMap::new(),
+                        feature_importance: HashMap::new(),
+                        model_used: "ensemble_model".to_string(),
+                    })
+                    .collect());
+            }
+
+            let documents: Vec<serde_json::Value> = fingerprints.iter().map(|fp| feature_document(*fp)).collect();
+            let mut results: Vec<MLResult> = fingerprints
+                .iter()
+                .map(|_| MLResult {
+                    risk_score: 0.0,
+                    confidence: 0.0,
+                    predictions: HashMap::new(),
+                    feature_importance: HashMap::new(),
+                    model_used: names.join("+"),
+                })
+                .collect();
+            let mut risks: Vec<Vec<f64>> = vec![Vec::new(); fingerprints.len()];
+            for name in &names {
+                let Some(model) = self.models.get(name) else {
+                    continue;
+                };
+                let predictions = model.predict_batch(&documents)?;
+                for ((result, risk), prediction) in results.iter_mut().zip(&mut risks).zip(predictions) {
+                    if let Some(value) = prediction.get("risk") {
+                        risk.push(*value);
+                    }
+                    result.predictions.extend(prediction.into_iter().map(|(output, value)| (format!("{}.{}", name, output), value)));
+                }
+            }
+            for (result, risk) in results.iter_mut().zip(risks) {
+                if !risk.is_empty() {
+                    result.risk_score = (risk.iter().sum::<f64>() / risk.len() as f64).clamp(0.0, 1.0);
+                    result.confidence = (result.risk_score - 0.5).abs() * 2.0;
+                }
+            }
+            Ok(results)
         }
     }
 }
 
 #[cfg(feature = "machine-learning")]
-pub use ml::{MLAnalyzer, MLResult, MLModel};
+pub use ml::{feature_document, MLAnalyzer, MLResult, MLModel};
 
 // Real-time monitoring components
 #[cfg(feature = "real-time")]
@@ -427,7 +685,7 @@ mod realtime {
         event_channels: DashMap<String, broadcast::Sender<serde_json::Value>>,
     }
     
-    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
     pub struct RealTimeResult {
         pub current_risk: f64,
         pub confidence: f64,
@@ -436,7 +694,7 @@ mod realtime {
         pub volatility: f64,
     }
     
-    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
     pub enum TrendDirection {
         Increasing,
         Decreasing,
@@ -470,13 +728,16 @@ pub use realtime::{RealTimeMonitor, RealTimeResult, TrendDirection};
 #[cfg(feature = "historical")]
 mod historical {
     use super::*;
+    use crate::history::{HistoryQuery, HistoryStore, MemoryHistoryStore};
     
     pub struct HistoricalAnalyzer {
         // Historical data storage and analysis
-        historical_data: DashMap<String, Vec<HistoricalRecord>>,
+        store: Arc<dyn HistoryStore>,
+        // How far back analyze() looks
+        lookback: chrono::Duration,
     }
     
-    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
     pub struct HistoricalResult {
         pub trend_risk: f64,
         pub confidence: f64,
@@ -485,15 +746,7 @@ mod historical {
         pub long_term_trends: Vec<Trend>,
     }
     
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct HistoricalRecord {
-        pub timestamp: chrono::DateTime<chrono::Utc>,
-        pub fingerprint_id: String,
-        pub features: serde_json::Value,
-        pub classification: String,
-    }
-    
-    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
     pub struct Pattern {
         pub pattern_type: String,
         pub frequency: u32,
@@ -501,7 +754,7 @@ mod historical {
         pub examples: Vec<String>,
     }
     
-    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
     pub struct Trend {
         pub metric: String,
         pub direction: String,
@@ -511,26 +764,110 @@ mod historical {
     
     impl HistoricalAnalyzer {
         pub fn new() -> Result<Self, AnalysisError> {
-            Ok(Self {
-                historical_data: DashMap::new(),
-            })
+            Ok(Self::with_store(Arc::new(MemoryHistoryStore::new())))
+        }
+        
+        /// Analyzer backed by `store`, looking back 90 days
+        pub fn with_store(store: Arc<dyn HistoryStore>) -> Self {
+            Self {
+                store,
+                lookback: chrono::Duration::days(90),
+            }
+        }
+        
+        /// Set how far back `analyze` looks
+        pub fn with_lookback(mut self, lookback: chrono::Duration) -> Self {
+            self.lookback = lookback;
+            self
+        }
+        
+        /// Backing store, for direct queries
+        pub fn store(&self) -> &Arc<dyn HistoryStore> {
+            &self.store
+        }
+        
+        /// Add an observation to history
+        pub fn record(&self, record: &HistoricalRecord) -> Result<(), AnalysisError> {
+            self.store.append(record)
         }
         
         pub async fn analyze(&self, fingerprint: &dyn Fingerprint) -> Result<HistoricalResult, AnalysisError> {
-            // Implementation would analyze historical patterns
+            let query = HistoryQuery::fingerprint(fingerprint.id())
+                .since(chrono::Utc::now() - self.lookback);
+            let records = self.store.query(&query)?;
+            if records.is_empty() {
+                // Nothing to go on: neutral risk, low confidence
+                return Ok(HistoricalResult {
+                    trend_risk: 0.1,
+                    confidence: 0.2,
+                    historical_patterns: vec![],
+                    seasonal_variations: HashMap::new(),
+                    long_term_trends: vec![],
+                });
+            }
+            
+            // Classification frequencies are the recurring patterns
+            let mut by_class: HashMap<String, Vec<&HistoricalRecord>> = HashMap::new();
+            for record in &records {
+                by_class.entry(record.classification.clone()).or_default().push(record);
+            }
+            let total = records.len() as f64;
+            let mut historical_patterns: Vec<Pattern> = by_class
+                .iter()
+                .map(|(class, seen)| Pattern {
+                    pattern_type: class.clone(),
+                    frequency: seen.len() as u32,
+                    confidence: seen.len() as f64 / total,
+                    examples: seen.iter().rev().take(3).map(|r| r.timestamp.to_rfc3339()).collect(),
+                })
+                .collect();
+            historical_patterns.sort_by(|a, b| b.frequency.cmp(&a.frequency));
+            
+            // Share of observations per hour of day (UTC)
+            let mut seasonal_variations: HashMap<String, f64> = HashMap::new();
+            for record in &records {
+                *seasonal_variations.entry(format!("hour_{:02}", record.timestamp.format("%H"))).or_default() += 1.0 / total;
+            }
+            
+            // Risky share now vs in the first half of the window
+            let risky = |r: &&HistoricalRecord| !matches!(r.classification.as_str(), "benign" | "legitimate" | "human");
+            let half = records.len() / 2;
+            let share = |slice: &[HistoricalRecord]| {
+                if slice.is_empty() { 0.0 } else { slice.iter().filter(risky).count() as f64 / slice.len() as f64 }
+            };
+            let trend_risk = share(&records);
+            let (earlier, later) = (share(&records[..half]), share(&records[half..]));
+            let delta = later - earlier;
+            let long_term_trends = vec![Trend {
+                metric: "risky_share".to_string(),
+                direction: if delta > 0.05 { "increasing" } else if delta < -0.05 { "decreasing" } else { "stable" }.to_string(),
+                magnitude: delta.abs(),
+                timeframe: format!("{}d", self.lookback.num_days()),
+            }];
+            
             Ok(HistoricalResult {
-                trend_risk: 0.1,
-                confidence: 0.8,
-                historical_patterns: vec![],
-                seasonal_variations: HashMap::new(),
-                long_term_trends: vec![],
+                trend_risk,
+                // Confidence grows with the amount of history, saturating at 100 records
+                confidence: (total / 100.0).clamp(0.2, 1.0),
+                historical_patterns,
+                seasonal_variations,
+                long_term_trends,
             })
         }
     }
 }
 
 #[cfg(feature = "historical")]
-pub use historical::{HistoricalAnalyzer, HistoricalResult, HistoricalRecord, Pattern, Trend};
+pub use historical::{HistoricalAnalyzer, HistoricalResult, Pattern, Trend};
+
+#[cfg(feature = "historical")]
+pub mod history;
+
+#[cfg(feature = "historical")]
+pub use history::{HistoricalRecord, HistoryQuery, HistoryStore, JsonlHistoryStore, MemoryHistoryStore};
+
+#[cfg(feature = "history-sqlite")]
+pub use history::SqliteHistoryStore;
 
 // Re-export main types
 pub use crate::{
@@ -597,6 +934,20 @@ mod tests {
         assert!(!result.id.is_empty());
     }
     
+    #[tokio::test]
+    async fn test_analyze_as_sheds_over_rate() {
+        let config = fingerprint_config::get_config_manager();
+        let mut admission = AdmissionConfig::default();
+        admission.tenant_limit = RateLimit { rate_per_second: 0.0, burst: 1.0 };
+        let engine = AnalysisEngine::new(config).unwrap().with_admission(admission);
+        
+        let fp = MockFingerprint { id: "test-tenant".to_string() };
+        let request = AdmissionRequest::new("tenant-a");
+        assert!(engine.analyze_as(&request, &fp).await.is_ok());
+        assert!(matches!(engine.analyze_as(&request, &fp).await, Err(AnalysisError::Shed(_))));
+        assert_eq!(engine.admission_stats().shed_by_tenant["tenant-a"], 1);
+    }
+    
     #[tokio::test]
     async fn test_fingerprint_comparison() {
         let config = fingerprint_config::get_config_manager();
@@ -0,0 +1,161 @@
+//! Model quality alerts (`model-monitoring` feature)
+//!
+//! [`ModelMonitorAlerts`] plugs a [`ModelMonitor`] from fingerprint-ml into the engine's
+//! alert generators. The application feeds the monitor with the features it scores and
+//! with labeled feedback; the generator adds the ML risk score of every analysis and
+//! turns feature drift or a precision / recall drop past the `ml.monitor` thresholds
+//! into [`AlertCategory::Configuration`] alerts.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use fingerprint_config::ConfigManager;
+use fingerprint_ml::{ModelMonitor, ModelMonitorConfig, MonitorReport};
+use serde::{Deserialize, Serialize};
+
+use crate::{Alert, AlertCategory, AlertGenerator, AlertSeverity, AnalysisError, AnalysisResult};
+
+/// Section `ml.monitor`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ModelMonitorSection(pub ModelMonitorConfig);
+
+fingerprint_config::config_section!(
+    ModelMonitorSection,
+    "ml.monitor",
+    validate = |section: &ModelMonitorSection| section.0.validate()
+);
+
+/// Alert generator backed by a shared [`ModelMonitor`]
+pub struct ModelMonitorAlerts {
+    monitor: Arc<ModelMonitor>,
+}
+
+impl ModelMonitorAlerts {
+    pub fn new(monitor: Arc<ModelMonitor>) -> Self {
+        Self { monitor }
+    }
+
+    /// Monitor with the `ml.monitor` thresholds of `config`
+    pub fn from_config(config: &ConfigManager) -> Result<Self, AnalysisError> {
+        let section = config.section::<ModelMonitorSection>()?;
+        Ok(Self::new(Arc::new(ModelMonitor::new(section.0))))
+    }
+
+    /// The monitor to feed with features and feedback
+    pub fn monitor(&self) -> &Arc<ModelMonitor> {
+        &self.monitor
+    }
+
+    /// Build a Configuration alert for a degraded report
+    ///
+    /// Severity escalates to Critical when any violation is severe.
+    pub fn alert_for(report: &MonitorReport) -> Option<Alert> {
+        if !report.is_degraded() {
+            return None;
+        }
+
+        let severity = if report.violations.iter().any(|v| v.severe) {
+            AlertSeverity::Critical
+        } else {
+            AlertSeverity::Warning
+        };
+        let summary: Vec<String> = report
+            .violations
+            .iter()
+            .map(|v| format!("{}={:.3} (threshold {:.3})", v.metric, v.value, v.threshold))
+            .collect();
+
+        let mut metadata = HashMap::new();
+        if let Ok(serde_json::Value::Object(fields)) = serde_json::to_value(report) {
+            metadata.extend(fields);
+        }
+
+        Some(Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            severity,
+            category: AlertCategory::Configuration,
+            message: format!("Model degradation detected: {}", summary.join(", ")),
+            timestamp: chrono::Utc::now(),
+            metadata,
+        })
+    }
+}
+
+impl AlertGenerator for ModelMonitorAlerts {
+    fn generate_alerts(&self, result: &AnalysisResult) -> Vec<Alert> {
+        if let Some(ml) = &result.ml {
+            self.monitor.observe_score(ml.risk_score);
+        }
+        self.monitor
+            .check()
+            .and_then(|report| Self::alert_for(&report))
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MLResult;
+
+    fn result(risk_score: f64) -> AnalysisResult {
+        AnalysisResult {
+            id: "a".to_string(),
+            timestamp: chrono::Utc::now(),
+            input_fingerprint: "fp".to_string(),
+            #[cfg(feature = "statistical")]
+            statistical: None,
+            ml: Some(MLResult {
+                model_used: "test".to_string(),
+                predictions: HashMap::new(),
+                feature_importance: HashMap::new(),
+                risk_score,
+                confidence: 1.0,
+            }),
+            #[cfg(feature = "real-time")]
+            real_time: None,
+            #[cfg(feature = "historical")]
+            historical: None,
+            risk_score,
+            confidence: 1.0,
+            alerts: vec![],
+        }
+    }
+
+    #[test]
+    fn test_score_drift_and_recall_drop_raise_alerts() {
+        let config = ModelMonitorConfig {
+            reference_size: 100,
+            window_size: 50,
+            min_feedback: 10,
+            ..ModelMonitorConfig::default()
+        };
+        let generator = ModelMonitorAlerts::new(Arc::new(ModelMonitor::new(config)));
+
+        let spread = |i: usize, lo: f64| lo + 0.4 * ((i * 37) % 100) as f64 / 100.0;
+        for i in 0..150 {
+            assert!(generator
+                .generate_alerts(&result(spread(i, 0.0)))
+                .is_empty());
+        }
+        // the model suddenly scores everything high
+        let alerts: Vec<Alert> = (0..50)
+            .flat_map(|i| generator.generate_alerts(&result(spread(i, 0.6))))
+            .collect();
+        // caught as soon as the shift crosses the threshold, before it is severe
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].severity, AlertSeverity::Warning);
+        assert!(alerts[0].message.contains("psi:score"));
+
+        for i in 0..48 {
+            generator.monitor().record_feedback(i % 4 == 0, i % 2 == 0);
+        }
+        let alerts = generator.generate_alerts(&result(0.5));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].severity, AlertSeverity::Critical);
+        assert!(alerts[0].message.contains("recall=0.500"));
+        assert_eq!(alerts[0].metadata["recall"], serde_json::json!(0.5));
+    }
+}
@@ -0,0 +1,399 @@
+//! ONNX models for [`MLAnalyzer`](crate::MLAnalyzer)
+//!
+//! Classifiers and anomaly models trained in Python and exported to ONNX are listed
+//! under `analysis.ml.models` and loaded by path with [`tract`](tract_onnx):
+//!
+//! ```json
+//! {
+//!   "name": "bot_classifier",
+//!   "path": "/var/lib/fingerprint/models/bot.onnx",
+//!   "inputs": [
+//!     { "source": "/metadata/confidence" },
+//!     { "source": "/metadata/sample_count", "scale": 0.001 },
+//!     { "source": "/type", "categories": ["tls", "http", "tcp", "content"] }
+//!   ],
+//!   "outputs": ["human", "bot"],
+//!   "risk_output": "bot"
+//! }
+//! ```
+//!
+//! Each input pulls one value out of the fingerprint's feature document (see
+//! [`feature_document`](crate::feature_document)) by JSON pointer, in order, into a
+//! `[batch, inputs]` f32 tensor. The first model output is read as `[batch, outputs]`
+//! and its columns are named by `outputs`; the column named by `risk_output` is
+//! reported as `risk`. The batch dimension is left symbolic, so models exported with
+//! a fixed batch of 1 still run whole batches at once.
+//!
+//! The configuration types are always available; [`OnnxModel`] needs the `onnx`
+//! feature.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "onnx")]
+use crate::{AnalysisError, MLModel};
+#[cfg(feature = "onnx")]
+use std::collections::HashMap;
+
+/// Section `analysis.ml`: models loaded by [`MLAnalyzer::from_config`](crate::MLAnalyzer::from_config)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MlModelsSection {
+    pub models: Vec<OnnxModelConfig>,
+}
+
+fingerprint_config::config_section!(
+    MlModelsSection,
+    "analysis.ml",
+    validate = |section: &MlModelsSection| {
+        section
+            .models
+            .iter()
+            .try_for_each(OnnxModelConfig::validate)
+    }
+);
+
+/// One exported model and how fingerprints map onto its input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnnxModelConfig {
+    /// Name reported in `MLResult::model_used` and prefixed to its predictions
+    pub name: String,
+    /// Path of the `.onnx` file
+    pub path: String,
+    /// Input features, in tensor column order
+    pub inputs: Vec<FeatureInput>,
+    /// Names of the output columns
+    pub outputs: Vec<String>,
+    /// Output column reported as `risk`
+    #[serde(default)]
+    pub risk_output: Option<String>,
+    /// Largest batch passed to the model in one run
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+}
+
+fn default_batch_size() -> usize {
+    64
+}
+
+impl OnnxModelConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.inputs.is_empty() {
+            return Err(format!("model {}: no inputs", self.name));
+        }
+        if self.outputs.is_empty() {
+            return Err(format!("model {}: no outputs", self.name));
+        }
+        if let Some(risk) = &self.risk_output {
+            if !self.outputs.contains(risk) {
+                return Err(format!(
+                    "model {}: risk_output {} is not an output",
+                    self.name, risk
+                ));
+            }
+        }
+        if self.batch_size == 0 {
+            return Err(format!("model {}: batch_size must be positive", self.name));
+        }
+        Ok(())
+    }
+}
+
+/// Where one input column comes from and how it is normalized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureInput {
+    /// JSON pointer into the feature document, e.g. `/metadata/confidence`
+    pub source: String,
+    /// Value when the source is missing or not convertible
+    #[serde(default)]
+    pub default: f32,
+    /// Subtracted before scaling
+    #[serde(default)]
+    pub offset: f32,
+    /// Multiplier applied after the offset
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    /// String values map to their index in this list
+    #[serde(default)]
+    pub categories: Vec<String>,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+impl FeatureInput {
+    /// Column value for one feature document
+    pub fn extract(&self, document: &serde_json::Value) -> f32 {
+        let raw = match document.pointer(&self.source) {
+            Some(serde_json::Value::Number(n)) => n.as_f64().map(|v| v as f32),
+            Some(serde_json::Value::Bool(b)) => Some(if *b { 1.0 } else { 0.0 }),
+            Some(serde_json::Value::String(s)) => self
+                .categories
+                .iter()
+                .position(|c| c == s)
+                .map(|i| i as f32),
+            _ => None,
+        };
+        match raw {
+            Some(value) => (value - self.offset) * self.scale,
+            None => self.default,
+        }
+    }
+}
+
+/// An ONNX model run by tract
+#[cfg(feature = "onnx")]
+pub struct OnnxModel {
+    config: OnnxModelConfig,
+    plan: tract_onnx::prelude::TypedRunnableModel<tract_onnx::prelude::TypedModel>,
+}
+
+#[cfg(feature = "onnx")]
+impl OnnxModel {
+    /// Load and optimize the model at `config.path`
+    pub fn load(config: OnnxModelConfig) -> Result<Self, AnalysisError> {
+        let file = std::fs::File::open(&config.path)
+            .map_err(|e| AnalysisError::MLError(format!("{}: {}", config.path, e)))?;
+        Self::from_reader(config, &mut std::io::BufReader::new(file))
+    }
+
+    /// Load the model from ONNX protobuf bytes instead of `config.path`
+    pub fn from_reader(
+        config: OnnxModelConfig,
+        reader: &mut dyn std::io::Read,
+    ) -> Result<Self, AnalysisError> {
+        use tract_onnx::prelude::*;
+
+        config.validate().map_err(AnalysisError::MLError)?;
+        let error = |e: TractError| AnalysisError::MLError(format!("model {}: {}", config.name, e));
+        let model = tract_onnx::onnx().model_for_read(reader).map_err(error)?;
+        let batch = model.symbol_table.sym("N");
+        let plan = model
+            .with_input_fact(
+                0,
+                f32::fact([batch.to_dim(), config.inputs.len().to_dim()]).into(),
+            )
+            .and_then(|model| model.into_optimized())
+            .and_then(|model| model.into_runnable())
+            .map_err(error)?;
+        Ok(Self { config, plan })
+    }
+
+    pub fn config(&self) -> &OnnxModelConfig {
+        &self.config
+    }
+
+    fn run(
+        &self,
+        documents: &[serde_json::Value],
+    ) -> Result<Vec<HashMap<String, f64>>, AnalysisError> {
+        use tract_onnx::prelude::*;
+
+        let error =
+            |e: TractError| AnalysisError::MLError(format!("model {}: {}", self.config.name, e));
+        let columns = self.config.inputs.len();
+        let data: Vec<f32> = documents
+            .iter()
+            .flat_map(|document| {
+                self.config
+                    .inputs
+                    .iter()
+                    .map(move |input| input.extract(document))
+            })
+            .collect();
+        let input: Tensor = tract_ndarray::Array2::from_shape_vec((documents.len(), columns), data)
+            .map_err(|e| AnalysisError::MLError(e.to_string()))?
+            .into();
+        let outputs = self.plan.run(tvec!(input.into())).map_err(error)?;
+        let scores = outputs[0].to_array_view::<f32>().map_err(error)?;
+        let width = self.config.outputs.len();
+        if scores.len() != documents.len() * width {
+            return Err(AnalysisError::MLError(format!(
+                "model {}: output shape {:?}, expected [{}, {}]",
+                self.config.name,
+                scores.shape(),
+                documents.len(),
+                width
+            )));
+        }
+
+        let scores: Vec<f32> = scores.iter().copied().collect();
+        Ok(scores
+            .chunks(width)
+            .map(|row| {
+                let mut predictions: HashMap<String, f64> = self
+                    .config
+                    .outputs
+                    .iter()
+                    .zip(row)
+                    .map(|(name, score)| (name.clone(), *score as f64))
+                    .collect();
+                if let Some(risk) = &self.config.risk_output {
+                    let value = predictions[risk];
+                    predictions.insert("risk".to_string(), value);
+                }
+                predictions
+            })
+            .collect())
+    }
+}
+
+#[cfg(feature = "onnx")]
+impl MLModel for OnnxModel {
+    fn predict(&self, features: &serde_json::Value) -> Result<HashMap<String, f64>, AnalysisError> {
+        Ok(self.run(std::slice::from_ref(features))?.remove(0))
+    }
+
+    fn model_name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn predict_batch(
+        &self,
+        features: &[serde_json::Value],
+    ) -> Result<Vec<HashMap<String, f64>>, AnalysisError> {
+        let mut predictions = Vec::with_capacity(features.len());
+        for chunk in features.chunks(self.config.batch_size) {
+            predictions.extend(self.run(chunk)?);
+        }
+        Ok(predictions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_inputs_normalize_and_default() {
+        let document = serde_json::json!({
+            "type": "http",
+            "metadata": { "confidence": 0.8, "sample_count": 1500, "tags": [] },
+            "flagged": true
+        });
+        let input =
+            |json: serde_json::Value| -> FeatureInput { serde_json::from_value(json).unwrap() };
+
+        assert_eq!(
+            input(serde_json::json!({ "source": "/metadata/confidence" })).extract(&document),
+            0.8
+        );
+        let scaled = input(
+            serde_json::json!({ "source": "/metadata/sample_count", "offset": 500.0, "scale": 0.001 }),
+        );
+        assert_eq!(scaled.extract(&document), 1.0);
+        assert_eq!(
+            input(serde_json::json!({ "source": "/flagged" })).extract(&document),
+            1.0
+        );
+        let categorical =
+            input(serde_json::json!({ "source": "/type", "categories": ["tls", "http"] }));
+        assert_eq!(categorical.extract(&document), 1.0);
+        let missing = input(serde_json::json!({ "source": "/metadata/missing", "default": -1.0 }));
+        assert_eq!(missing.extract(&document), -1.0);
+    }
+
+    #[cfg(feature = "onnx")]
+    #[test]
+    fn test_onnx_model_runs_batches() {
+        use prost::Message;
+        use tract_onnx::pb;
+        use tract_onnx::pb::tensor_shape_proto::{dimension, Dimension};
+
+        // logistic regression: scores = sigmoid(features · w + b), exported with batch 1
+        let tensor = |name: &str, dims: Vec<i64>, data: Vec<f32>| pb::TensorProto {
+            name: name.to_string(),
+            dims,
+            data_type: 1,
+            float_data: data,
+            ..Default::default()
+        };
+        let node = |op: &str, inputs: &[&str], output: &str| pb::NodeProto {
+            op_type: op.to_string(),
+            input: inputs.iter().map(|s| s.to_string()).collect(),
+            output: vec![output.to_string()],
+            name: output.to_string(),
+            ..Default::default()
+        };
+        let dim = |n: i64| Dimension {
+            value: Some(dimension::Value::DimValue(n)),
+            ..Default::default()
+        };
+        let input = pb::ValueInfoProto {
+            name: "features".to_string(),
+            r#type: Some(pb::TypeProto {
+                value: Some(pb::type_proto::Value::TensorType(pb::type_proto::Tensor {
+                    elem_type: 1,
+                    shape: Some(pb::TensorShapeProto {
+                        dim: vec![dim(1), dim(2)],
+                    }),
+                })),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let model = pb::ModelProto {
+            ir_version: 7,
+            opset_import: vec![pb::OperatorSetIdProto {
+                domain: String::new(),
+                version: 13,
+            }],
+            graph: Some(pb::GraphProto {
+                name: "bot_classifier".to_string(),
+                node: vec![
+                    node("MatMul", &["features", "w"], "z"),
+                    node("Add", &["z", "b"], "logits"),
+                    node("Sigmoid", &["logits"], "scores"),
+                ],
+                initializer: vec![
+                    tensor("w", vec![2, 2], vec![-4.0, 4.0, -4.0, 4.0]),
+                    tensor("b", vec![2], vec![4.0, -4.0]),
+                ],
+                input: vec![input],
+                output: vec![pb::ValueInfoProto {
+                    name: "scores".to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bot.onnx");
+        std::fs::write(&path, model.encode_to_vec()).unwrap();
+        let config: OnnxModelConfig = serde_json::from_value(serde_json::json!({
+            "name": "bot_classifier",
+            "path": path.to_str().unwrap(),
+            "inputs": [{ "source": "/metadata/confidence" }, { "source": "/automation" }],
+            "outputs": ["human", "bot"],
+            "risk_output": "bot",
+            "batch_size": 2
+        }))
+        .unwrap();
+        let model = OnnxModel::load(config).unwrap();
+
+        let documents = vec![
+            serde_json::json!({ "metadata": { "confidence": 0.1 }, "automation": false }),
+            serde_json::json!({ "metadata": { "confidence": 1.0 }, "automation": true }),
+            serde_json::json!({ "metadata": { "confidence": 0.5 }, "automation": true }),
+        ];
+        let predictions = model.predict_batch(&documents).unwrap();
+        assert_eq!(predictions.len(), 3);
+        assert!(predictions[0]["risk"] < 0.1);
+        assert!(predictions[1]["risk"] > 0.9);
+        assert_eq!(predictions[1]["risk"], predictions[1]["bot"]);
+        assert!((predictions[0]["human"] + predictions[0]["bot"] - 1.0).abs() < 1e-5);
+        assert_eq!(
+            model.predict(&documents[2]).unwrap()["bot"],
+            predictions[2]["bot"]
+        );
+
+        let missing: OnnxModelConfig = serde_json::from_value(serde_json::json!({
+            "name": "missing", "path": dir.path().join("missing.onnx").to_str().unwrap(),
+            "inputs": [{ "source": "/x" }], "outputs": ["risk"]
+        }))
+        .unwrap();
+        assert!(OnnxModel::load(missing).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+//! JSON Schema contract for analysis output
+//!
+//! [`AnalysisResult`] and [`Alert`] documents are the stable contract for
+//! downstream consumers. Their JSON Schemas are generated from the Rust types
+//! with `schemars` and published under `schemas/` at the repository root, one
+//! file per schema version (`analysis-result.v1.json`, `alert.v1.json`).
+//!
+//! The published schemas describe the default feature set. Bump
+//! [`SCHEMA_VERSION`] on any breaking change to these types and regenerate:
+//!
+//! ```text
+//! cargo run -p fingerprint-analysis --example export_schema -- schemas
+//! ```
+//!
+//! With the `schema-validation` feature, [`validate`] and friends check
+//! emitted documents against the schema.
+
+use crate::{Alert, AnalysisResult};
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
+
+/// Version of the published analysis schemas
+///
+/// Released alongside the crate; bump on breaking changes only.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Base URI of the published schema files
+pub const SCHEMA_BASE_URI: &str =
+    "https://raw.githubusercontent.com/vistone/fingerprint-rust/main/schemas";
+
+/// Documents with a published schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SchemaDocument {
+    /// [`AnalysisResult`]
+    AnalysisResult,
+    /// [`Alert`]
+    Alert,
+}
+
+impl SchemaDocument {
+    /// All published documents
+    pub const ALL: [SchemaDocument; 2] = [SchemaDocument::AnalysisResult, SchemaDocument::Alert];
+
+    /// Document name used in file names and URIs
+    pub fn name(&self) -> &'static str {
+        match self {
+            SchemaDocument::AnalysisResult => "analysis-result",
+            SchemaDocument::Alert => "alert",
+        }
+    }
+
+    /// File name of the current version, e.g. `analysis-result.v1.json`
+    pub fn file_name(&self) -> String {
+        format!("{}.v{}.json", self.name(), SCHEMA_VERSION)
+    }
+
+    /// `$id` of the current version
+    pub fn id(&self) -> String {
+        format!("{}/{}", SCHEMA_BASE_URI, self.file_name())
+    }
+
+    /// Generate the schema
+    pub fn schema(&self) -> RootSchema {
+        match self {
+            SchemaDocument::AnalysisResult => versioned::<AnalysisResult>(*self),
+            SchemaDocument::Alert => versioned::<Alert>(*self),
+        }
+    }
+
+    /// Schema as pretty-printed JSON, as published
+    pub fn to_json(&self) -> String {
+        let mut json =
+            serde_json::to_string_pretty(&self.schema()).expect("generated schema serializes");
+        json.push('\n');
+        json
+    }
+}
+
+fn versioned<T: JsonSchema>(document: SchemaDocument) -> RootSchema {
+    let mut schema = schema_for!(T);
+    schema.schema.metadata().id = Some(document.id());
+    schema.schema.extensions.insert(
+        "x-schema-version".to_string(),
+        serde_json::Value::from(SCHEMA_VERSION),
+    );
+    schema
+}
+
+#[cfg(feature = "schema-validation")]
+pub use validation::{validate, validate_alert, validate_analysis_result, SchemaValidationError};
+
+#[cfg(feature = "schema-validation")]
+mod validation {
+    use super::SchemaDocument;
+    use crate::{Alert, AnalysisResult};
+    use jsonschema::Validator;
+    use once_cell::sync::Lazy;
+    use thiserror::Error;
+
+    /// Emitted document does not match its schema
+    #[derive(Error, Debug)]
+    pub enum SchemaValidationError {
+        #[error("{document} document violates schema: {}", .errors.join("; "))]
+        Invalid {
+            document: &'static str,
+            errors: Vec<String>,
+        },
+        #[error("Failed to serialize document: {0}")]
+        Serialize(#[from] serde_json::Error),
+    }
+
+    fn compile(document: SchemaDocument) -> Validator {
+        let schema = serde_json::to_value(document.schema()).expect("generated schema serializes");
+        jsonschema::validator_for(&schema).expect("generated schema is valid")
+    }
+
+    static ANALYSIS_RESULT: Lazy<Validator> = Lazy::new(|| compile(SchemaDocument::AnalysisResult));
+    static ALERT: Lazy<Validator> = Lazy::new(|| compile(SchemaDocument::Alert));
+
+    /// Check a JSON document against the schema of `document`
+    pub fn validate(
+        document: SchemaDocument,
+        value: &serde_json::Value,
+    ) -> Result<(), SchemaValidationError> {
+        let validator = match document {
+            SchemaDocument::AnalysisResult => &*ANALYSIS_RESULT,
+            SchemaDocument::Alert => &*ALERT,
+        };
+        let errors: Vec<String> = validator
+            .iter_errors(value)
+            .map(|e| format!("{} at {}", e, e.instance_path))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(SchemaValidationError::Invalid {
+                document: document.name(),
+                errors,
+            })
+        }
+    }
+
+    /// Serialize `result` and check it against the published schema
+    pub fn validate_analysis_result(result: &AnalysisResult) -> Result<(), SchemaValidationError> {
+        validate(
+            SchemaDocument::AnalysisResult,
+            &serde_json::to_value(result)?,
+        )
+    }
+
+    /// Serialize `alert` and check it against the published schema
+    pub fn validate_alert(alert: &Alert) -> Result<(), SchemaValidationError> {
+        validate(SchemaDocument::Alert, &serde_json::to_value(alert)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schemas_are_versioned() {
+        for document in SchemaDocument::ALL {
+            let schema = serde_json::to_value(document.schema()).unwrap();
+            assert_eq!(schema["$id"], document.id());
+            assert_eq!(schema["x-schema-version"], SCHEMA_VERSION);
+        }
+
+        let result = serde_json::to_value(SchemaDocument::AnalysisResult.schema()).unwrap();
+        let required = result["required"].as_array().unwrap();
+        for field in [
+            "id",
+            "timestamp",
+            "input_fingerprint",
+            "risk_score",
+            "alerts",
+        ] {
+            assert!(
+                required.iter().any(|r| r == field),
+                "{} not required",
+                field
+            );
+        }
+    }
+
+    /// The files under `schemas/` must match the types; regenerate with the
+    /// `export_schema` example after changing them.
+    #[test]
+    #[cfg(all(feature = "statistical", feature = "machine-learning"))]
+    #[cfg(not(any(feature = "real-time", feature = "historical")))]
+    fn test_published_schemas_are_current() {
+        let published = [
+            (
+                SchemaDocument::AnalysisResult,
+                include_str!("../../../schemas/analysis-result.v1.json"),
+            ),
+            (
+                SchemaDocument::Alert,
+                include_str!("../../../schemas/alert.v1.json"),
+            ),
+        ];
+        for (document, json) in published {
+            assert_eq!(
+                document.to_json(),
+                json,
+                "{} is stale",
+                document.file_name()
+            );
+        }
+    }
+
+    #[cfg(feature = "schema-validation")]
+    mod validation {
+        use super::super::*;
+        use crate::{AlertCategory, AlertSeverity};
+        use std::collections::HashMap;
+
+        fn sample_alert() -> Alert {
+            Alert {
+                id: "alert-1".to_string(),
+                severity: AlertSeverity::Critical,
+                category: AlertCategory::KnownThreat,
+                message: "known bot JA4".to_string(),
+                timestamp: chrono::Utc::now(),
+                metadata: HashMap::from([("ja4".to_string(), serde_json::json!("t13d1514h2"))]),
+            }
+        }
+
+        #[test]
+        fn test_emitted_documents_validate() {
+            let result = AnalysisResult {
+                id: "analysis-1".to_string(),
+                timestamp: chrono::Utc::now(),
+                input_fingerprint: "fp-1".to_string(),
+                #[cfg(feature = "statistical")]
+                statistical: None,
+                #[cfg(feature = "machine-learning")]
+                ml: None,
+                #[cfg(feature = "real-time")]
+                real_time: None,
+                #[cfg(feature = "historical")]
+                historical: None,
+                risk_score: 0.4,
+                confidence: 0.9,
+                alerts: vec![sample_alert()],
+            };
+
+            validate_analysis_result(&result).unwrap();
+            validate_alert(&sample_alert()).unwrap();
+        }
+
+        #[test]
+        fn test_invalid_documents_are_rejected() {
+            let mut alert = serde_json::to_value(sample_alert()).unwrap();
+            alert["severity"] = serde_json::json!("Apocalyptic");
+            alert.as_object_mut().unwrap().remove("message");
+
+            match validate(SchemaDocument::Alert, &alert) {
+                Err(SchemaValidationError::Invalid { document, errors }) => {
+                    assert_eq!(document, "alert");
+                    assert_eq!(errors.len(), 2, "{:?}", errors);
+                }
+                other => panic!("expected schema violation, got {:?}", other),
+            }
+        }
+    }
+}
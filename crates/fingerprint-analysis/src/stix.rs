@@ -0,0 +1,135 @@
+//! STIX 2.1 export of alerts
+//!
+//! An alert naming a JA3 / JA4 fingerprint in its metadata (`ja4`, `ja3`)
+//! becomes an indicator for that fingerprint plus a sighting at the alert's
+//! time, so repeated alerts about one fingerprint share the indicator. Any
+//! other alert becomes an incident.
+
+use crate::{Alert, AlertCategory, AlertSeverity};
+use fingerprint_core::stix::{
+    random_id, Bundle, FingerprintIndicator, Identity, Incident, Indicator, Sighting, StixObject,
+    SPEC_VERSION,
+};
+use std::collections::HashSet;
+
+/// Metadata keys checked for a fingerprint, in order
+const FINGERPRINT_KEYS: &[&str] = &["ja4", "ja3"];
+
+impl AlertSeverity {
+    /// Indicator confidence of alerts with this severity
+    pub fn stix_confidence(&self) -> f64 {
+        match self {
+            AlertSeverity::Info => 0.25,
+            AlertSeverity::Warning => 0.5,
+            AlertSeverity::Critical => 0.75,
+            AlertSeverity::Emergency => 0.95,
+        }
+    }
+}
+
+impl Alert {
+    /// Fingerprint named in the metadata, if it is a well-formed JA3 / JA4
+    pub fn stix_fingerprint(&self) -> Option<FingerprintIndicator> {
+        FINGERPRINT_KEYS.iter().find_map(|key| {
+            self.metadata
+                .get(*key)
+                .and_then(|value| value.as_str())
+                .and_then(FingerprintIndicator::classify)
+        })
+    }
+
+    /// Indicator and sighting, or an incident, produced by `identity`
+    pub fn to_stix(&self, identity: &Identity) -> Vec<StixObject> {
+        let severity = format!("{:?}", self.severity).to_lowercase();
+        let category = format!("{:?}", self.category).to_lowercase();
+
+        let Some(fingerprint) = self.stix_fingerprint() else {
+            return vec![StixObject::Incident(Incident {
+                spec_version: SPEC_VERSION.to_string(),
+                id: random_id("incident"),
+                created: self.timestamp,
+                modified: self.timestamp,
+                created_by_ref: Some(identity.id.clone()),
+                name: self.message.clone(),
+                description: Some(format!("{} {} alert {}", severity, category, self.id)),
+                labels: vec![severity, category],
+            })];
+        };
+
+        let mut indicator = Indicator::for_fingerprint(&fingerprint, self.timestamp)
+            .with_confidence(self.severity.stix_confidence())
+            .with_creator(identity);
+        indicator.indicator_types = vec![match self.category {
+            AlertCategory::KnownThreat => "malicious-activity".to_string(),
+            _ => "anomalous-activity".to_string(),
+        }];
+        indicator.labels = vec![category];
+
+        let mut sighting =
+            Sighting::of(&indicator, identity).with_observations(1, self.timestamp, self.timestamp);
+        sighting.description = Some(self.message.clone());
+        vec![
+            StixObject::Indicator(indicator),
+            StixObject::Sighting(sighting),
+        ]
+    }
+}
+
+/// Bundle of `alerts`, with each fingerprint's indicator included once
+pub fn alerts_bundle(alerts: &[Alert], identity: &Identity) -> Bundle {
+    let mut objects = vec![StixObject::Identity(identity.clone())];
+    let mut indicators = HashSet::new();
+    for alert in alerts {
+        for object in alert.to_stix(identity) {
+            if let StixObject::Indicator(indicator) = &object {
+                if !indicators.insert(indicator.id.clone()) {
+                    continue;
+                }
+            }
+            objects.push(object);
+        }
+    }
+    Bundle::new(objects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn alert(metadata: HashMap<String, serde_json::Value>) -> Alert {
+        Alert {
+            id: "alert-1".to_string(),
+            severity: AlertSeverity::Critical,
+            category: AlertCategory::KnownThreat,
+            message: "known bot fingerprint".to_string(),
+            timestamp: chrono::Utc::now(),
+            metadata,
+        }
+    }
+
+    #[test]
+    fn fingerprint_alerts_share_one_indicator() {
+        let ja4 = HashMap::from([(
+            "ja4".to_string(),
+            serde_json::json!("t13d1516h2_8daaf6152771_02713d6af862"),
+        )]);
+        let identity = Identity::system("analysis");
+        let bundle = alerts_bundle(
+            &[alert(ja4.clone()), alert(ja4), alert(HashMap::new())],
+            &identity,
+        );
+
+        let types: Vec<&str> = bundle.objects.iter().map(|o| o.object_type()).collect();
+        assert_eq!(
+            types,
+            ["identity", "indicator", "sighting", "sighting", "incident"]
+        );
+        let indicator = bundle.indicators().next().unwrap();
+        assert_eq!(indicator.confidence, Some(75));
+        assert_eq!(indicator.indicator_types, ["malicious-activity"]);
+
+        let json = bundle.to_json().unwrap();
+        assert_eq!(Bundle::from_json(&json).unwrap(), bundle);
+    }
+}
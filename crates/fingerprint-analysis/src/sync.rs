@@ -0,0 +1,798 @@
+//! Sensor-to-collector observation sync
+//!
+//! Remote sensors ship observed fingerprints to a central collector as compact binary
+//! frames instead of ad-hoc JSON posts:
+//!
+//! ```text
+//! ┌───────────┬─────────┬──────────────┬──────────────────────────────────┐
+//! │ "FPOB" 4B │ ver. 1B │ length u32BE │ zstd(bincode(ObservationBatch))  │
+//! └───────────┴─────────┴──────────────┴──────────────────────────────────┘
+//! ```
+//!
+//! [`ObservationSender`] batches observations (by count and age), retries failed sends
+//! with backoff and spools frames to disk while the collector is unreachable, draining
+//! the spool in order once it is back. Delivery is at-least-once; [`Collector`] drops
+//! batches it has already seen from the same sensor and feeds the rest into the
+//! [`AnalysisEngine`].
+
+use crate::{AnalysisEngine, AnalysisError, AnalysisResult};
+use fingerprint_core::fingerprint::{Fingerprint, FingerprintType};
+use fingerprint_core::metadata::FingerprintMetadata;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// frame magic
+pub const FRAME_MAGIC: [u8; 4] = *b"FPOB";
+
+/// current frame format version
+pub const FRAME_VERSION: u8 = 1;
+
+/// magic + version + payload length
+pub const FRAME_HEADER_LEN: usize = 9;
+
+/// largest accepted compressed payload
+pub const MAX_FRAME_PAYLOAD: usize = 16 * 1024 * 1024;
+
+/// largest accepted decompressed batch
+const MAX_BATCH_BYTES: usize = 64 * 1024 * 1024;
+
+/// batches remembered per sensor for duplicate detection
+const DEDUP_WINDOW: usize = 1024;
+
+/// spool file extension
+const SPOOL_EXTENSION: &str = "fpob";
+
+/// Sync error types
+#[derive(Error, Debug)]
+pub enum SyncError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Encoding failed: {0}")]
+    Encode(String),
+    #[error("Invalid frame: {0}")]
+    Frame(String),
+    #[error("Analysis failed: {0}")]
+    Analysis(#[from] AnalysisError),
+}
+
+/// fingerprint layer of an observation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ObservationKind {
+    Tls,
+    Http,
+    Tcp,
+    Content,
+}
+
+impl From<FingerprintType> for ObservationKind {
+    fn from(value: FingerprintType) -> Self {
+        match value {
+            FingerprintType::Tls => ObservationKind::Tls,
+            FingerprintType::Http => ObservationKind::Http,
+            FingerprintType::Tcp => ObservationKind::Tcp,
+            FingerprintType::Content => ObservationKind::Content,
+        }
+    }
+}
+
+impl From<ObservationKind> for FingerprintType {
+    fn from(value: ObservationKind) -> Self {
+        match value {
+            ObservationKind::Tls => FingerprintType::Tls,
+            ObservationKind::Http => FingerprintType::Http,
+            ObservationKind::Tcp => FingerprintType::Tcp,
+            ObservationKind::Content => FingerprintType::Content,
+        }
+    }
+}
+
+/// one fingerprint seen by a sensor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Observation {
+    pub kind: ObservationKind,
+    /// fingerprint ID (JA4, JA4H, JA4T, ...)
+    pub id: String,
+    /// Unix milliseconds
+    pub observed_at_ms: i64,
+    pub source_ip: Option<IpAddr>,
+    pub confidence: f32,
+    /// metadata tags ("key:value" properties included)
+    pub tags: Vec<String>,
+}
+
+impl Observation {
+    pub fn new(kind: ObservationKind, id: impl Into<String>) -> Self {
+        Self {
+            kind,
+            id: id.into(),
+            observed_at_ms: unix_millis(),
+            source_ip: None,
+            confidence: 1.0,
+            tags: Vec::new(),
+        }
+    }
+
+    /// capture a fingerprint produced by the sensor's passive analyzers
+    pub fn from_fingerprint(fingerprint: &dyn Fingerprint) -> Self {
+        let metadata = fingerprint.metadata();
+        Self {
+            kind: fingerprint.fingerprint_type().into(),
+            id: fingerprint.id(),
+            observed_at_ms: metadata.last_seen.timestamp_millis(),
+            source_ip: None,
+            confidence: metadata.confidence as f32,
+            tags: metadata.tags.clone(),
+        }
+    }
+
+    pub fn with_source_ip(mut self, ip: IpAddr) -> Self {
+        self.source_ip = Some(ip);
+        self
+    }
+}
+
+/// unit of transfer: one frame carries one batch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObservationBatch {
+    pub sensor_id: String,
+    /// increasing per sensor; (sensor_id, sequence) identifies a batch for deduplication
+    pub sequence: u64,
+    pub observations: Vec<Observation>,
+}
+
+fn unix_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_millis() as i64
+}
+
+/// encode a batch into a complete frame
+pub fn encode_frame(batch: &ObservationBatch, level: i32) -> Result<Vec<u8>, SyncError> {
+    let raw = bincode::serialize(batch).map_err(|e| SyncError::Encode(e.to_string()))?;
+    let compressed = zstd::bulk::compress(&raw, level)?;
+    if compressed.len() > MAX_FRAME_PAYLOAD {
+        return Err(SyncError::Encode(format!(
+            "compressed batch of {} bytes exceeds frame limit",
+            compressed.len()
+        )));
+    }
+
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + compressed.len());
+    frame.extend_from_slice(&FRAME_MAGIC);
+    frame.push(FRAME_VERSION);
+    frame.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&compressed);
+    Ok(frame)
+}
+
+/// payload length announced by a frame header
+fn payload_len(header: &[u8]) -> Result<usize, SyncError> {
+    if header[..4] != FRAME_MAGIC {
+        return Err(SyncError::Frame("bad magic".to_string()));
+    }
+    if header[4] != FRAME_VERSION {
+        return Err(SyncError::Frame(format!(
+            "unsupported version {}",
+            header[4]
+        )));
+    }
+    let len = u32::from_be_bytes([header[5], header[6], header[7], header[8]]) as usize;
+    if len > MAX_FRAME_PAYLOAD {
+        return Err(SyncError::Frame(format!(
+            "payload of {} bytes too large",
+            len
+        )));
+    }
+    Ok(len)
+}
+
+fn decode_payload(payload: &[u8]) -> Result<ObservationBatch, SyncError> {
+    let raw = zstd::bulk::decompress(payload, MAX_BATCH_BYTES)
+        .map_err(|e| SyncError::Frame(format!("zstd: {}", e)))?;
+    bincode::deserialize(&raw).map_err(|e| SyncError::Frame(format!("bincode: {}", e)))
+}
+
+/// decode exactly one frame
+pub fn decode_frame(frame: &[u8]) -> Result<ObservationBatch, SyncError> {
+    if frame.len() < FRAME_HEADER_LEN {
+        return Err(SyncError::Frame("truncated header".to_string()));
+    }
+    let len = payload_len(&frame[..FRAME_HEADER_LEN])?;
+    if frame.len() != FRAME_HEADER_LEN + len {
+        return Err(SyncError::Frame(format!(
+            "expected {} payload bytes, got {}",
+            len,
+            frame.len() - FRAME_HEADER_LEN
+        )));
+    }
+    decode_payload(&frame[FRAME_HEADER_LEN..])
+}
+
+/// incremental decoder for a byte stream carrying consecutive frames
+#[derive(Debug, Default)]
+pub struct FrameDecoder {
+    buffer: Vec<u8>,
+}
+
+impl FrameDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// next complete batch, or None until more bytes arrive
+    ///
+    /// A corrupt header is unrecoverable for the stream; the caller should drop the
+    /// connection.
+    pub fn next_batch(&mut self) -> Result<Option<ObservationBatch>, SyncError> {
+        if self.buffer.len() < FRAME_HEADER_LEN {
+            return Ok(None);
+        }
+        let len = payload_len(&self.buffer[..FRAME_HEADER_LEN])?;
+        if self.buffer.len() < FRAME_HEADER_LEN + len {
+            return Ok(None);
+        }
+        let frame: Vec<u8> = self.buffer.drain(..FRAME_HEADER_LEN + len).collect();
+        decode_payload(&frame[FRAME_HEADER_LEN..]).map(Some)
+    }
+}
+
+/// delivers frames to the collector
+pub trait Transport {
+    fn send(&mut self, frame: &[u8]) -> io::Result<()>;
+}
+
+/// frames written back to back over a TCP connection, reconnecting after failures
+pub struct TcpTransport {
+    addr: SocketAddr,
+    timeout: Duration,
+    stream: Option<TcpStream>,
+}
+
+impl TcpTransport {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            timeout: Duration::from_secs(5),
+            stream: None,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl Transport for TcpTransport {
+    fn send(&mut self, frame: &[u8]) -> io::Result<()> {
+        if self.stream.is_none() {
+            let stream = TcpStream::connect_timeout(&self.addr, self.timeout)?;
+            stream.set_write_timeout(Some(self.timeout))?;
+            stream.set_nodelay(true)?;
+            self.stream = Some(stream);
+        }
+        let result = match self.stream.as_mut() {
+            Some(stream) => stream.write_all(frame).and_then(|_| stream.flush()),
+            None => unreachable!("stream connected above"),
+        };
+        if result.is_err() {
+            self.stream = None;
+        }
+        result
+    }
+}
+
+/// sender configuration
+#[derive(Debug, Clone)]
+pub struct SenderConfig {
+    pub sensor_id: String,
+    /// flush once this many observations are pending
+    pub max_batch: usize,
+    /// flush once the oldest pending observation is this old (checked by `poll`)
+    pub max_delay: Duration,
+    /// zstd level
+    pub compression_level: i32,
+    /// send attempts per frame before spooling
+    pub max_attempts: u32,
+    /// delay before the first retry, doubled on each further retry
+    pub retry_backoff: Duration,
+    /// directory for frames that could not be delivered (None: fail instead)
+    pub spool_dir: Option<PathBuf>,
+    /// spool size limit; the newest frame is dropped once reached
+    pub max_spool_bytes: u64,
+}
+
+impl SenderConfig {
+    pub fn new(sensor_id: impl Into<String>) -> Self {
+        Self {
+            sensor_id: sensor_id.into(),
+            max_batch: 500,
+            max_delay: Duration::from_secs(1),
+            compression_level: 3,
+            max_attempts: 3,
+            retry_backoff: Duration::from_millis(200),
+            spool_dir: None,
+            max_spool_bytes: 64 * 1024 * 1024,
+        }
+    }
+
+    pub fn with_spool_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.spool_dir = Some(dir.into());
+        self
+    }
+}
+
+/// sender counters
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SenderStats {
+    pub frames_sent: u64,
+    pub observations_sent: u64,
+    pub bytes_sent: u64,
+    pub retries: u64,
+    pub frames_spooled: u64,
+    pub frames_dropped: u64,
+}
+
+/// sensor-side batching sender
+pub struct ObservationSender<T: Transport> {
+    config: SenderConfig,
+    transport: T,
+    pending: Vec<Observation>,
+    oldest_pending: Option<Instant>,
+    sequence: u64,
+    stats: SenderStats,
+}
+
+impl<T: Transport> ObservationSender<T> {
+    /// create a sender; spooled frames from a previous run are kept and sent first
+    pub fn new(config: SenderConfig, transport: T) -> Result<Self, SyncError> {
+        if let Some(dir) = &config.spool_dir {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Wall-clock based start keeps sequences increasing across restarts, so the
+        // collector's duplicate filter never mistakes a new batch for a replay.
+        let mut sequence = unix_millis().max(0) as u64 * 1000;
+        if let Some(last) = Self::spooled_frames(&config)?.last() {
+            sequence = sequence.max(last.0 + 1);
+        }
+        Ok(Self {
+            config,
+            transport,
+            pending: Vec::new(),
+            oldest_pending: None,
+            sequence,
+            stats: SenderStats::default(),
+        })
+    }
+
+    pub fn stats(&self) -> &SenderStats {
+        &self.stats
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// queue an observation, flushing when the batch is full
+    pub fn push(&mut self, observation: Observation) -> Result<(), SyncError> {
+        self.oldest_pending.get_or_insert_with(Instant::now);
+        self.pending.push(observation);
+        if self.pending.len() >= self.config.max_batch {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// flush when the pending batch exceeded `max_delay`; call periodically
+    pub fn poll(&mut self) -> Result<(), SyncError> {
+        match self.oldest_pending {
+            Some(oldest) if oldest.elapsed() >= self.config.max_delay => self.flush(),
+            _ => Ok(()),
+        }
+    }
+
+    /// encode and deliver pending observations
+    ///
+    /// Spooled frames go out first so the collector sees batches in order. When the
+    /// collector stays unreachable the frame is spooled (if configured) and `Ok` is
+    /// returned; without a spool the error is returned and the batch is lost.
+    pub fn flush(&mut self) -> Result<(), SyncError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let batch = ObservationBatch {
+            sensor_id: self.config.sensor_id.clone(),
+            sequence: self.sequence,
+            observations: std::mem::take(&mut self.pending),
+        };
+        self.oldest_pending = None;
+        self.sequence += 1;
+        let frame = encode_frame(&batch, self.config.compression_level)?;
+        let count = batch.observations.len() as u64;
+
+        let spool_drained = self.drain_spool()?;
+        let sent = if spool_drained {
+            self.send_with_retry(&frame)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "collector unreachable",
+            ))
+        };
+        match sent {
+            Ok(()) => {
+                self.stats.observations_sent += count;
+                Ok(())
+            }
+            Err(e) if self.config.spool_dir.is_some() => {
+                log::warn!("[Sync] Spooling batch {}: {}", batch.sequence, e);
+                self.spool(batch.sequence, &frame)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// resend spooled frames in order; false if some remain
+    pub fn drain_spool(&mut self) -> Result<bool, SyncError> {
+        for (_, path) in Self::spooled_frames(&self.config)? {
+            let frame = std::fs::read(&path)?;
+            if self.send_with_retry(&frame).is_err() {
+                return Ok(false);
+            }
+            std::fs::remove_file(&path)?;
+        }
+        Ok(true)
+    }
+
+    fn send_with_retry(&mut self, frame: &[u8]) -> io::Result<()> {
+        let mut backoff = self.config.retry_backoff;
+        let mut attempt = 1;
+        loop {
+            match self.transport.send(frame) {
+                Ok(()) => {
+                    self.stats.frames_sent += 1;
+                    self.stats.bytes_sent += frame.len() as u64;
+                    return Ok(());
+                }
+                Err(e) if attempt >= self.config.max_attempts.max(1) => return Err(e),
+                Err(_) => {
+                    self.stats.retries += 1;
+                    attempt += 1;
+                    std::thread::sleep(backoff);
+                    backoff *= 2;
+                }
+            }
+        }
+    }
+
+    fn spool(&mut self, sequence: u64, frame: &[u8]) -> Result<(), SyncError> {
+        let Some(dir) = &self.config.spool_dir else {
+            return Ok(());
+        };
+        let used: u64 = Self::spooled_frames(&self.config)?
+            .iter()
+            .filter_map(|(_, path)| std::fs::metadata(path).ok())
+            .map(|meta| meta.len())
+            .sum();
+        if used + frame.len() as u64 > self.config.max_spool_bytes {
+            log::warn!("[Sync] Spool full, dropping batch {}", sequence);
+            self.stats.frames_dropped += 1;
+            return Ok(());
+        }
+
+        // write then rename so a crash never leaves a half-written frame behind
+        let path = dir.join(format!("{:020}.{}", sequence, SPOOL_EXTENSION));
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, frame)?;
+        std::fs::rename(&tmp, &path)?;
+        self.stats.frames_spooled += 1;
+        Ok(())
+    }
+
+    /// spooled frames ordered by sequence
+    fn spooled_frames(config: &SenderConfig) -> Result<Vec<(u64, PathBuf)>, SyncError> {
+        let Some(dir) = &config.spool_dir else {
+            return Ok(Vec::new());
+        };
+        let mut frames = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(SPOOL_EXTENSION) {
+                continue;
+            }
+            if let Some(sequence) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<u64>().ok())
+            {
+                frames.push((sequence, path));
+            }
+        }
+        frames.sort_by_key(|(sequence, _)| *sequence);
+        Ok(frames)
+    }
+}
+
+/// decoded observation handed to the analysis engine
+#[derive(Debug, Clone)]
+pub struct ObservedFingerprint {
+    kind: ObservationKind,
+    id: String,
+    metadata: FingerprintMetadata,
+}
+
+impl ObservedFingerprint {
+    pub fn new(observation: &Observation, sensor_id: &str) -> Self {
+        let mut metadata = FingerprintMetadata::new();
+        metadata.confidence = f64::from(observation.confidence).clamp(0.0, 1.0);
+        if let Some(seen) = chrono::DateTime::from_timestamp_millis(observation.observed_at_ms) {
+            metadata.first_seen = seen;
+            metadata.last_seen = seen;
+        }
+        metadata.tags = observation.tags.clone();
+        metadata.set("sensor", sensor_id);
+        if let Some(ip) = observation.source_ip {
+            metadata.set("source_ip", &ip.to_string());
+        }
+        Self {
+            kind: observation.kind,
+            id: observation.id.clone(),
+            metadata,
+        }
+    }
+}
+
+impl Fingerprint for ObservedFingerprint {
+    fn fingerprint_type(&self) -> FingerprintType {
+        self.kind.into()
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn metadata(&self) -> &FingerprintMetadata {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut FingerprintMetadata {
+        &mut self.metadata
+    }
+
+    fn hash(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.kind.hash(&mut hasher);
+        self.id.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn similar_to(&self, other: &dyn Fingerprint) -> bool {
+        other.fingerprint_type() == self.fingerprint_type() && other.id() == self.id
+    }
+
+    fn to_string(&self) -> String {
+        format!("{:?} fingerprint {}", self.kind, self.id)
+    }
+}
+
+/// collector counters
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CollectorStats {
+    pub batches: u64,
+    pub duplicate_batches: u64,
+    pub observations: u64,
+}
+
+#[derive(Default)]
+struct SeenBatches {
+    order: VecDeque<u64>,
+    set: HashSet<u64>,
+}
+
+/// collector-side decoder feeding the analysis engine
+pub struct Collector {
+    engine: Arc<AnalysisEngine>,
+    seen: Mutex<HashMap<String, SeenBatches>>,
+    stats: Mutex<CollectorStats>,
+}
+
+impl Collector {
+    pub fn new(engine: Arc<AnalysisEngine>) -> Self {
+        Self {
+            engine,
+            seen: Mutex::new(HashMap::new()),
+            stats: Mutex::new(CollectorStats::default()),
+        }
+    }
+
+    pub fn stats(&self) -> CollectorStats {
+        self.stats.lock().clone()
+    }
+
+    /// decode and analyze one frame
+    pub async fn ingest_frame(&self, frame: &[u8]) -> Result<Vec<AnalysisResult>, SyncError> {
+        let batch = decode_frame(frame)?;
+        self.ingest_batch(batch).await
+    }
+
+    /// analyze every observation of a batch; replays of a known batch yield nothing
+    pub async fn ingest_batch(
+        &self,
+        batch: ObservationBatch,
+    ) -> Result<Vec<AnalysisResult>, SyncError> {
+        if !self.first_delivery(&batch) {
+            self.stats.lock().duplicate_batches += 1;
+            return Ok(Vec::new());
+        }
+
+        let mut results = Vec::with_capacity(batch.observations.len());
+        for observation in &batch.observations {
+            let fingerprint = ObservedFingerprint::new(observation, &batch.sensor_id);
+            results.push(self.engine.analyze(&fingerprint).await?);
+        }
+
+        let mut stats = self.stats.lock();
+        stats.batches += 1;
+        stats.observations += results.len() as u64;
+        Ok(results)
+    }
+
+    fn first_delivery(&self, batch: &ObservationBatch) -> bool {
+        let mut seen = self.seen.lock();
+        let sensor = seen.entry(batch.sensor_id.clone()).or_default();
+        if !sensor.set.insert(batch.sequence) {
+            return false;
+        }
+        sensor.order.push_back(batch.sequence);
+        if sensor.order.len() > DEDUP_WINDOW {
+            if let Some(old) = sensor.order.pop_front() {
+                sensor.set.remove(&old);
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observation(i: usize) -> Observation {
+        let mut observation = Observation::new(
+            ObservationKind::Tls,
+            format!("t13d1516h2_8daaf6152771_{:012x}", i % 4),
+        )
+        .with_source_ip(IpAddr::from([203, 0, 113, (i % 250) as u8]));
+        observation.tags = vec!["browser:chrome".to_string()];
+        observation
+    }
+
+    /// transport failing while `down` is set and recording delivered frames
+    #[derive(Clone, Default)]
+    struct FlakyTransport {
+        down: Arc<Mutex<bool>>,
+        delivered: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    impl Transport for FlakyTransport {
+        fn send(&mut self, frame: &[u8]) -> io::Result<()> {
+            if *self.down.lock() {
+                return Err(io::Error::new(io::ErrorKind::ConnectionRefused, "down"));
+            }
+            self.delivered.lock().push(frame.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_frame_roundtrip_and_stream_decoding() {
+        let batch = ObservationBatch {
+            sensor_id: "edge-1".to_string(),
+            sequence: 7,
+            observations: (0..200).map(observation).collect(),
+        };
+        let frame = encode_frame(&batch, 3).unwrap();
+        assert_eq!(decode_frame(&frame).unwrap(), batch);
+
+        // far smaller than the JSON it replaces
+        let json = serde_json::to_vec(&batch).unwrap();
+        assert!(
+            frame.len() * 5 < json.len(),
+            "{} vs {}",
+            frame.len(),
+            json.len()
+        );
+
+        // frames split at arbitrary points decode once complete
+        let mut stream = frame.clone();
+        stream.extend_from_slice(&frame);
+        let mut decoder = FrameDecoder::new();
+        let mut decoded = Vec::new();
+        for chunk in stream.chunks(37) {
+            decoder.push(chunk);
+            while let Some(batch) = decoder.next_batch().unwrap() {
+                decoded.push(batch);
+            }
+        }
+        assert_eq!(decoded.len(), 2);
+
+        let mut corrupt = frame.clone();
+        corrupt[0] = b'X';
+        assert!(decode_frame(&corrupt).is_err());
+        assert!(decode_frame(&frame[..frame.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_sender_spools_while_offline_and_drains_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let transport = FlakyTransport::default();
+        let mut config = SenderConfig::new("edge-1").with_spool_dir(dir.path());
+        config.max_batch = 10;
+        config.max_attempts = 2;
+        config.retry_backoff = Duration::from_millis(1);
+        let mut sender = ObservationSender::new(config, transport.clone()).unwrap();
+
+        *transport.down.lock() = true;
+        for i in 0..25 {
+            sender.push(observation(i)).unwrap();
+        }
+        assert_eq!(sender.stats().frames_spooled, 2);
+        assert_eq!(sender.stats().retries, 2);
+        assert_eq!(sender.pending(), 5);
+
+        *transport.down.lock() = false;
+        sender.flush().unwrap();
+
+        let delivered: Vec<ObservationBatch> = transport
+            .delivered
+            .lock()
+            .iter()
+            .map(|frame| decode_frame(frame).unwrap())
+            .collect();
+        assert_eq!(delivered.len(), 3);
+        assert!(delivered.windows(2).all(|w| w[0].sequence < w[1].sequence));
+        assert_eq!(
+            delivered
+                .iter()
+                .map(|b| b.observations.len())
+                .sum::<usize>(),
+            25
+        );
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_collector_analyzes_and_drops_replays() {
+        let config = fingerprint_config::get_config_manager();
+        let collector = Collector::new(Arc::new(AnalysisEngine::new(config).unwrap()));
+        let batch = ObservationBatch {
+            sensor_id: "edge-1".to_string(),
+            sequence: 1,
+            observations: (0..3).map(observation).collect(),
+        };
+        let frame = encode_frame(&batch, 3).unwrap();
+
+        let results = collector.ingest_frame(&frame).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].input_fingerprint, batch.observations[0].id);
+
+        assert!(collector.ingest_frame(&frame).await.unwrap().is_empty());
+        let stats = collector.stats();
+        assert_eq!(stats.batches, 1);
+        assert_eq!(stats.duplicate_batches, 1);
+        assert_eq!(stats.observations, 3);
+    }
+}
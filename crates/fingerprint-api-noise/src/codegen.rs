@@ -0,0 +1,248 @@
+//! JavaScript bundle generation
+//!
+//! [`ScriptGenerator`] turns a [`NoiseConfig`] or [`NoiseProfile`] into a script that
+//! applies the same noise inside the page: `toDataURL` / `toBlob` / `getImageData`
+//! hooks, WebGL `getParameter` overrides, AudioBuffer and AnalyserNode noise, and
+//! navigator hardware overrides. Offsets computable up front (WebGL line width,
+//! navigator deltas) come from the Rust injectors; per-pixel and per-sample noise
+//! uses a seeded PRNG in the page, so it stays stable for a seed.
+//!
+//! [`JsBundle`] emits the script as a file, an inline `<script>` tag with an optional
+//! CSP nonce, or a `'sha256-...'` source for a hash-based `script-src`.
+
+use base64::Engine;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::navigator::NavigatorNoiseInjector;
+use crate::profile::{NoiseApi, NoiseProfile};
+use crate::{NoiseConfig, WebGLNoiseInjector, WebGLParams};
+
+const TEMPLATE: &str = include_str!("js/noise.js");
+
+/// Largest per-sample offset, as in [`crate::AudioNoiseInjector`]
+const AUDIO_AMPLITUDE: f64 = 0.0001;
+
+/// Builds the injection script for one configuration
+#[derive(Debug, Clone)]
+pub struct ScriptGenerator {
+    canvas_seed: u64,
+    webgl_seed: u64,
+    audio_seed: u64,
+    navigator_seed: u64,
+    canvas_noise_level: f64,
+    webgl: bool,
+    audio: bool,
+    navigator: bool,
+    /// (vendor, renderer) reported for the unmasked WebGL parameters
+    webgl_identity: Option<(String, String)>,
+}
+
+impl ScriptGenerator {
+    /// Script matching an [`crate::ApiNoiseInjector`] built from `config`
+    pub fn new(config: &NoiseConfig) -> Self {
+        Self {
+            canvas_seed: config.seed,
+            webgl_seed: config.seed,
+            audio_seed: config.seed,
+            navigator_seed: config.seed,
+            canvas_noise_level: config.canvas_noise_level.clamp(0.0, 1.0),
+            webgl: config.enable_webgl_noise,
+            audio: config.enable_audio_noise,
+            navigator: true,
+            webgl_identity: None,
+        }
+    }
+
+    /// Script with the per-API seeds of a session profile
+    pub fn from_profile(profile: &NoiseProfile) -> Self {
+        Self {
+            canvas_seed: profile.api_seed(NoiseApi::Canvas),
+            webgl_seed: profile.api_seed(NoiseApi::WebGL),
+            audio_seed: profile.api_seed(NoiseApi::Audio),
+            navigator_seed: profile.api_seed(NoiseApi::Navigator),
+            canvas_noise_level: profile.canvas_noise_level,
+            webgl: true,
+            audio: true,
+            navigator: true,
+            webgl_identity: None,
+        }
+    }
+
+    /// Also report this vendor and renderer for the unmasked WebGL parameters
+    pub fn with_webgl_identity(mut self, vendor: &str, renderer: &str) -> Self {
+        self.webgl_identity = Some((vendor.to_string(), renderer.to_string()));
+        self
+    }
+
+    /// Leave `navigator.hardwareConcurrency` and `deviceMemory` untouched
+    pub fn without_navigator(mut self) -> Self {
+        self.navigator = false;
+        self
+    }
+
+    pub fn generate(&self) -> JsBundle {
+        let canvas = (self.canvas_noise_level > 0.0).then(|| {
+            json!({
+                "seed": js_seed(self.canvas_seed),
+                "level": self.canvas_noise_level,
+            })
+        });
+        let webgl = self.webgl.then(|| {
+            let zero = WebGLParams {
+                renderer: String::new(),
+                vendor: String::new(),
+                aliased_line_width_range: Some([0.0, 0.0]),
+                aliased_point_size_range: None,
+                max_texture_size: None,
+                max_viewport_dims: None,
+            };
+            let offsets = WebGLNoiseInjector::with_seed(self.webgl_seed)
+                .add_webgl_noise(&zero)
+                .aliased_line_width_range
+                .unwrap_or_default();
+            let (vendor, renderer) = self.webgl_identity.clone().unzip();
+            json!({
+                "lineWidth": offsets,
+                "vendor": vendor,
+                "renderer": renderer,
+            })
+        });
+        let audio = self.audio.then(|| {
+            json!({
+                "seed": js_seed(self.audio_seed),
+                "amplitude": AUDIO_AMPLITUDE,
+            })
+        });
+        let navigator = self.navigator.then(|| {
+            // the injector's decision does not depend on the real value, so the
+            // offset measured at 8 applies to any value
+            let injector = NavigatorNoiseInjector::new(self.navigator_seed);
+            json!({
+                "hardwareConcurrency": injector.add_hardware_concurrency_noise(8) as i64 - 8,
+                "deviceMemory": injector.add_device_memory_noise(8) as i64 - 8,
+            })
+        });
+
+        let config = json!({
+            "canvas": canvas,
+            "webgl": webgl,
+            "audio": audio,
+            "navigator": navigator,
+        });
+        // escaped so a renderer string cannot close the inline <script> tag
+        let config = config.to_string().replace('<', "\\u003c");
+        JsBundle {
+            source: TEMPLATE.replace("__NOISE_CONFIG__", &config),
+        }
+    }
+}
+
+/// JS bitwise operations work on 32 bits
+fn js_seed(seed: u64) -> u32 {
+    (seed ^ (seed >> 32)) as u32
+}
+
+/// Generated script, ready to inject
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsBundle {
+    pub source: String,
+}
+
+impl JsBundle {
+    /// `'sha256-...'` source expression for a CSP `script-src` allowing the inline tag
+    pub fn csp_hash(&self) -> String {
+        let digest = Sha256::digest(self.source.as_bytes());
+        format!(
+            "'sha256-{}'",
+            base64::engine::general_purpose::STANDARD.encode(digest)
+        )
+    }
+
+    /// `<script>` tag; the hash from [`JsBundle::csp_hash`] covers exactly its body
+    pub fn inline_tag(&self) -> String {
+        format!("<script>{}</script>", self.source)
+    }
+
+    /// `<script nonce="...">` tag for pages with a nonce-based CSP
+    pub fn nonce_tag(&self, nonce: &str) -> Result<String, String> {
+        let valid = !nonce.is_empty()
+            && nonce.bytes().all(|b| {
+                b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'=' | b'-' | b'_')
+            });
+        if !valid {
+            return Err(format!("invalid CSP nonce: {:?}", nonce));
+        }
+        Ok(format!(
+            "<script nonce=\"{}\">{}</script>",
+            nonce, self.source
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(seed: u64) -> NoiseConfig {
+        NoiseConfig {
+            seed,
+            canvas_noise_level: 0.1,
+            enable_webgl_noise: true,
+            enable_audio_noise: false,
+            enable_font_noise: true,
+        }
+    }
+
+    #[test]
+    fn test_bundle_follows_config() {
+        let bundle = ScriptGenerator::new(&config(12345)).generate();
+        assert_eq!(bundle, ScriptGenerator::new(&config(12345)).generate());
+        assert_ne!(bundle, ScriptGenerator::new(&config(54321)).generate());
+        assert!(!bundle.source.contains("__NOISE_CONFIG__"));
+
+        let start = bundle.source.find("var cfg = ").unwrap() + "var cfg = ".len();
+        let end = start + bundle.source[start..].find(";\n").unwrap();
+        let embedded: serde_json::Value = serde_json::from_str(&bundle.source[start..end]).unwrap();
+        assert_eq!(embedded["canvas"]["level"], json!(0.1));
+        assert!(embedded["audio"].is_null());
+        assert!(embedded["webgl"]["vendor"].is_null());
+
+        let navigator = NavigatorNoiseInjector::new(12345);
+        let cores = embedded["navigator"]["hardwareConcurrency"]
+            .as_i64()
+            .unwrap();
+        assert_eq!(
+            navigator.add_hardware_concurrency_noise(4) as i64,
+            4 + cores
+        );
+
+        let profile = NoiseProfile::new(7, "example.com", "s1");
+        let bundle = ScriptGenerator::from_profile(&profile)
+            .with_webgl_identity("Google Inc. (Intel)", "ANGLE (Intel, UHD Graphics 630)")
+            .without_navigator()
+            .generate();
+        let tagged = ScriptGenerator::new(&config(1))
+            .with_webgl_identity("x", "</script><script>alert(1)")
+            .generate();
+        assert!(!tagged.source.contains("</script>"));
+        assert!(bundle
+            .source
+            .contains("\"renderer\":\"ANGLE (Intel, UHD Graphics 630)\""));
+        assert!(bundle.source.contains("\"navigator\":null"));
+    }
+
+    #[test]
+    fn test_csp_delivery() {
+        let bundle = ScriptGenerator::new(&config(1)).generate();
+        let hash = bundle.csp_hash();
+        assert!(hash.starts_with("'sha256-") && hash.ends_with("='"));
+        assert!(bundle.inline_tag().starts_with("<script>(function () {"));
+        assert!(bundle
+            .nonce_tag("r4nd0m+N0nce=")
+            .unwrap()
+            .starts_with("<script nonce=\"r4nd0m+N0nce=\">"));
+        assert!(bundle.nonce_tag("\"><img src=x>").is_err());
+        assert!(bundle.nonce_tag("").is_err());
+    }
+}
@@ -0,0 +1,165 @@
+(function () {
+  'use strict';
+  var cfg = __NOISE_CONFIG__;
+
+  // mulberry32: small seeded PRNG, so the same seed gives the same noise
+  function rng(seed) {
+    var a = seed >>> 0;
+    return function () {
+      a = (a + 0x6d2b79f5) >>> 0;
+      var t = Math.imul(a ^ (a >>> 15), a | 1);
+      t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+      return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+    };
+  }
+
+  // replace a method, keeping its name and toString() of the original
+  function patch(proto, name, wrap) {
+    var original = proto && proto[name];
+    if (typeof original !== 'function') {
+      return;
+    }
+    var wrapped = wrap(original);
+    Object.defineProperty(wrapped, 'name', { value: original.name });
+    Object.defineProperty(wrapped, 'toString', {
+      value: function () {
+        return Function.prototype.toString.call(original);
+      }
+    });
+    Object.defineProperty(proto, name, {
+      value: wrapped,
+      writable: true,
+      configurable: true,
+      enumerable: false
+    });
+  }
+
+  if (cfg.canvas && window.CanvasRenderingContext2D) {
+    var getImageData = CanvasRenderingContext2D.prototype.getImageData;
+
+    // +-1 on the colour channels of a fraction of the pixels; alpha is left
+    // alone so transparent pixels stay transparent
+    var noisePixels = function (data) {
+      var next = rng(cfg.canvas.seed);
+      for (var i = 0; i < data.length; i += 4) {
+        if (next() < cfg.canvas.level) {
+          for (var c = 0; c < 3; c++) {
+            data[i + c] += next() < 0.5 ? -1 : 1;
+          }
+        }
+      }
+    };
+
+    var noisyCopy = function (canvas) {
+      var copy = document.createElement('canvas');
+      copy.width = canvas.width;
+      copy.height = canvas.height;
+      var ctx = copy.getContext('2d');
+      ctx.drawImage(canvas, 0, 0);
+      var image = getImageData.call(ctx, 0, 0, copy.width, copy.height);
+      noisePixels(image.data);
+      ctx.putImageData(image, 0, 0);
+      return copy;
+    };
+
+    var exportNoisy = function (original) {
+      return function () {
+        var source = this;
+        if (this.width && this.height) {
+          try {
+            source = noisyCopy(this);
+          } catch (e) {
+            // tainted canvas: let the original call raise its own error
+          }
+        }
+        return original.apply(source, arguments);
+      };
+    };
+
+    patch(HTMLCanvasElement.prototype, 'toDataURL', exportNoisy);
+    patch(HTMLCanvasElement.prototype, 'toBlob', exportNoisy);
+    patch(CanvasRenderingContext2D.prototype, 'getImageData', function (original) {
+      return function () {
+        var image = original.apply(this, arguments);
+        noisePixels(image.data);
+        return image;
+      };
+    });
+  }
+
+  if (cfg.webgl) {
+    var ALIASED_LINE_WIDTH_RANGE = 0x846e;
+    var UNMASKED_VENDOR_WEBGL = 0x9245;
+    var UNMASKED_RENDERER_WEBGL = 0x9246;
+
+    [window.WebGLRenderingContext, window.WebGL2RenderingContext].forEach(function (ctor) {
+      patch(ctor && ctor.prototype, 'getParameter', function (original) {
+        return function (pname) {
+          var value = original.apply(this, arguments);
+          if (pname === ALIASED_LINE_WIDTH_RANGE && value && value.length === 2) {
+            return new Float32Array([
+              value[0] + cfg.webgl.lineWidth[0],
+              value[1] + cfg.webgl.lineWidth[1]
+            ]);
+          }
+          if (pname === UNMASKED_VENDOR_WEBGL && cfg.webgl.vendor) {
+            return cfg.webgl.vendor;
+          }
+          if (pname === UNMASKED_RENDERER_WEBGL && cfg.webgl.renderer) {
+            return cfg.webgl.renderer;
+          }
+          return value;
+        };
+      });
+    });
+  }
+
+  if (cfg.audio) {
+    var noiseSamples = function (data) {
+      var next = rng(cfg.audio.seed);
+      for (var i = 0; i < data.length; i++) {
+        data[i] += (next() * 2 - 1) * cfg.audio.amplitude;
+      }
+    };
+
+    // getChannelData returns the same array every time; noise it only once
+    var noised = new WeakMap();
+    patch(window.AudioBuffer && AudioBuffer.prototype, 'getChannelData', function (original) {
+      return function (channel) {
+        var data = original.apply(this, arguments);
+        var channels = noised.get(this) || [];
+        if (channels.indexOf(channel) < 0) {
+          channels.push(channel);
+          noised.set(this, channels);
+          noiseSamples(data);
+        }
+        return data;
+      };
+    });
+    patch(window.AnalyserNode && AnalyserNode.prototype, 'getFloatFrequencyData', function (original) {
+      return function (array) {
+        original.apply(this, arguments);
+        noiseSamples(array);
+      };
+    });
+  }
+
+  if (cfg.navigator && window.Navigator) {
+    var override = function (name, delta) {
+      var descriptor = Object.getOwnPropertyDescriptor(Navigator.prototype, name);
+      if (!descriptor || !descriptor.get || !delta) {
+        return;
+      }
+      var getter = descriptor.get;
+      Object.defineProperty(Navigator.prototype, name, {
+        get: function () {
+          return Math.max(1, getter.call(this) + delta);
+        },
+        configurable: true,
+        enumerable: descriptor.enumerable
+      });
+    };
+    override('hardwareConcurrency', cfg.navigator.hardwareConcurrency);
+    override('deviceMemory', cfg.navigator.deviceMemory);
+  }
+})();
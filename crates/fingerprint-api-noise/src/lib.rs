@@ -10,17 +10,23 @@
 //! - Font enumeration noise injection
 //! - Screen information noise injection
 //! - Navigator API noise injection
+//! - JavaScript bundle generation (canvas, WebGL, audio and navigator hooks) with CSP nonce/hash support
+//! - Deterministic per-session noise profiles, stable per (seed, domain, session)
 
 pub mod audio;
 pub mod canvas;
+pub mod codegen;
 pub mod fonts;
 pub mod navigator;
+pub mod profile;
 pub mod screen;
 pub mod webgl;
 
 pub use audio::AudioNoiseInjector;
 pub use canvas::CanvasNoiseInjector;
+pub use codegen::{JsBundle, ScriptGenerator};
 pub use fonts::FontNoiseInjector;
+pub use profile::{NoiseApi, NoiseProfile, NoiseProfileStore, PROFILE_VERSION};
 pub use webgl::{WebGLNoiseInjector, WebGLParams};
 
 use rand::Rng;
@@ -0,0 +1,312 @@
+//! Deterministic per-session noise profiles
+//!
+//! A browser whose canvas or audio fingerprint changes on every page load is easy to
+//! flag. A [`NoiseProfile`] derives all injector seeds from `(seed, domain, session)`:
+//! the same site sees identical noise for the whole session, while other domains and
+//! later sessions see unrelated values. Profiles store only those inputs, so a
+//! restored profile reproduces exactly the same noise.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::navigator::NavigatorNoiseInjector;
+use crate::screen::ScreenNoiseInjector;
+use crate::{
+    ApiNoiseInjector, AudioNoiseInjector, CanvasNoiseInjector, FontNoiseInjector, NoiseConfig,
+    WebGLNoiseInjector,
+};
+
+/// Seed derivation version; bump when [`NoiseProfile::api_seed`] changes
+pub const PROFILE_VERSION: u32 = 1;
+
+/// Browser API a seed is derived for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NoiseApi {
+    Canvas,
+    WebGL,
+    Audio,
+    Fonts,
+    Screen,
+    Navigator,
+}
+
+impl NoiseApi {
+    fn label(&self) -> &'static str {
+        match self {
+            NoiseApi::Canvas => "canvas",
+            NoiseApi::WebGL => "webgl",
+            NoiseApi::Audio => "audio",
+            NoiseApi::Fonts => "fonts",
+            NoiseApi::Screen => "screen",
+            NoiseApi::Navigator => "navigator",
+        }
+    }
+}
+
+/// Stable noise for one (domain, session)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoiseProfile {
+    pub seed: u64,
+    /// Host name, lowercased
+    pub domain: String,
+    pub session: String,
+    #[serde(default = "default_canvas_noise_level")]
+    pub canvas_noise_level: f64,
+    #[serde(default = "default_version")]
+    pub version: u32,
+}
+
+fn default_canvas_noise_level() -> f64 {
+    0.1
+}
+
+fn default_version() -> u32 {
+    PROFILE_VERSION
+}
+
+fn normalize_domain(domain: &str) -> String {
+    domain.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+fn hash_u64(parts: &[&[u8]]) -> u64 {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        // length prefix keeps ("ab", "c") and ("a", "bc") apart
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    let digest = hasher.finalize();
+    u64::from_le_bytes(digest[..8].try_into().expect("sha256 digest is 32 bytes"))
+}
+
+impl NoiseProfile {
+    pub fn new(seed: u64, domain: &str, session: &str) -> Self {
+        Self {
+            seed,
+            domain: normalize_domain(domain),
+            session: session.to_string(),
+            canvas_noise_level: default_canvas_noise_level(),
+            version: PROFILE_VERSION,
+        }
+    }
+
+    /// Profile using the seed and canvas noise level of `config`
+    pub fn from_config(config: &NoiseConfig, domain: &str, session: &str) -> Self {
+        Self::new(config.seed, domain, session).with_canvas_noise_level(config.canvas_noise_level)
+    }
+
+    pub fn with_canvas_noise_level(mut self, level: f64) -> Self {
+        self.canvas_noise_level = level.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Seed of the whole profile
+    pub fn key(&self) -> u64 {
+        hash_u64(&[
+            b"fingerprint-api-noise/profile",
+            &self.version.to_le_bytes(),
+            &self.seed.to_le_bytes(),
+            self.domain.as_bytes(),
+            self.session.as_bytes(),
+        ])
+    }
+
+    /// Independent seed for one API
+    pub fn api_seed(&self, api: NoiseApi) -> u64 {
+        hash_u64(&[&self.key().to_le_bytes(), api.label().as_bytes()])
+    }
+
+    pub fn canvas(&self) -> CanvasNoiseInjector {
+        CanvasNoiseInjector::new(self.api_seed(NoiseApi::Canvas), self.canvas_noise_level)
+    }
+
+    pub fn webgl(&self) -> WebGLNoiseInjector {
+        WebGLNoiseInjector::with_seed(self.api_seed(NoiseApi::WebGL))
+    }
+
+    pub fn audio(&self) -> AudioNoiseInjector {
+        AudioNoiseInjector::new(self.api_seed(NoiseApi::Audio))
+    }
+
+    pub fn screen(&self) -> ScreenNoiseInjector {
+        ScreenNoiseInjector::new(self.api_seed(NoiseApi::Screen))
+    }
+
+    pub fn navigator(&self) -> NavigatorNoiseInjector {
+        NavigatorNoiseInjector::new(self.api_seed(NoiseApi::Navigator))
+    }
+
+    /// Font list reported for this session
+    pub fn fonts(&self) -> Vec<String> {
+        FontNoiseInjector::new().get_fonts_with_noise(self.api_seed(NoiseApi::Fonts))
+    }
+
+    /// Unified injector with this profile's per-API seeds
+    pub fn injector(&self) -> ApiNoiseInjector {
+        ApiNoiseInjector {
+            config: NoiseConfig {
+                seed: self.key(),
+                canvas_noise_level: self.canvas_noise_level,
+                ..NoiseConfig::default()
+            },
+            canvas: self.canvas(),
+            webgl: self.webgl(),
+            audio: self.audio(),
+            fonts: FontNoiseInjector::new(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| e.to_string())
+    }
+
+    /// Restore a saved profile; profiles from a newer release are rejected
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let profile: Self = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if profile.version > PROFILE_VERSION {
+            return Err(format!(
+                "noise profile version {} is newer than supported ({})",
+                profile.version, PROFILE_VERSION
+            ));
+        }
+        Ok(profile)
+    }
+}
+
+/// Profiles of one browser identity, created on first visit to a domain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoiseProfileStore {
+    seed: u64,
+    canvas_noise_level: f64,
+    /// keyed by "session/domain"
+    profiles: BTreeMap<String, NoiseProfile>,
+}
+
+impl NoiseProfileStore {
+    pub fn new(config: &NoiseConfig) -> Self {
+        Self {
+            seed: config.seed,
+            canvas_noise_level: config.canvas_noise_level,
+            profiles: BTreeMap::new(),
+        }
+    }
+
+    /// Profile for `domain` in `session`, created if missing
+    pub fn profile(&mut self, domain: &str, session: &str) -> &NoiseProfile {
+        let key = format!("{}/{}", session, normalize_domain(domain));
+        let (seed, level) = (self.seed, self.canvas_noise_level);
+        self.profiles.entry(key).or_insert_with(|| {
+            NoiseProfile::new(seed, domain, session).with_canvas_noise_level(level)
+        })
+    }
+
+    /// Forget a finished session; returns the number of profiles dropped
+    pub fn end_session(&mut self, session: &str) -> usize {
+        let before = self.profiles.len();
+        self.profiles
+            .retain(|_, profile| profile.session != session);
+        before - self.profiles.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.profiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let store: Self = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        if let Some(profile) = store
+            .profiles
+            .values()
+            .find(|p| p.version > PROFILE_VERSION)
+        {
+            return Err(format!(
+                "noise profile version {} is newer than supported ({})",
+                profile.version, PROFILE_VERSION
+            ));
+        }
+        Ok(store)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_stable_within_session_distinct_across_domains() {
+        let pixels = [200u8, 120, 40, 255].repeat(256);
+        let samples = vec![0.25f32; 64];
+
+        let a = NoiseProfile::new(7, "Example.com.", "s1");
+        let again = NoiseProfile::new(7, "example.com", "s1");
+        assert_eq!(a, again);
+        assert_eq!(
+            a.canvas().add_noise(&pixels),
+            again.canvas().add_noise(&pixels)
+        );
+        assert_eq!(
+            a.audio().add_audio_noise(&samples),
+            again.audio().add_audio_noise(&samples)
+        );
+        assert_eq!(a.fonts(), again.fonts());
+
+        let other_domain = NoiseProfile::new(7, "example.org", "s1");
+        let next_session = NoiseProfile::new(7, "example.com", "s2");
+        for other in [&other_domain, &next_session] {
+            assert_ne!(a.key(), other.key());
+            assert_ne!(
+                a.canvas().add_noise(&pixels),
+                other.canvas().add_noise(&pixels)
+            );
+            assert_ne!(
+                a.audio().add_audio_noise(&samples),
+                other.audio().add_audio_noise(&samples)
+            );
+        }
+        // one API's noise says nothing about another's
+        assert_ne!(a.api_seed(NoiseApi::Canvas), a.api_seed(NoiseApi::Audio));
+
+        let restored = NoiseProfile::from_json(&a.to_json().unwrap()).unwrap();
+        assert_eq!(
+            restored.canvas().fingerprint_hash(&pixels),
+            a.canvas().fingerprint_hash(&pixels)
+        );
+        let future = r#"{"seed":7,"domain":"example.com","session":"s1","version":2}"#;
+        assert!(NoiseProfile::from_json(future).is_err());
+    }
+
+    #[test]
+    fn test_store_persists_and_restores() {
+        let config = NoiseConfig {
+            seed: 42,
+            ..NoiseConfig::default()
+        };
+        let mut store = NoiseProfileStore::new(&config);
+        let key = store.profile("example.com", "s1").key();
+        store.profile("example.org", "s1");
+        store.profile("example.com", "s2");
+        assert_eq!(store.profile("EXAMPLE.com", "s1").key(), key);
+        assert_eq!(store.len(), 3);
+
+        let path = std::env::temp_dir().join(format!("noise-profiles-{}.json", std::process::id()));
+        store.save(&path).unwrap();
+        let mut restored = NoiseProfileStore::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(restored.profile("example.com", "s1").key(), key);
+        assert_eq!(restored.end_session("s1"), 2);
+        assert_eq!(restored.len(), 1);
+    }
+}
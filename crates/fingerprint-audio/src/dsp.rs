@@ -0,0 +1,534 @@
+//! Offline rendering of the Web Audio fingerprinting graph
+//!
+//! Fingerprinting scripts (fingerprintjs and others) render a fixed graph in an
+//! `OfflineAudioContext(1, 5000, 44100)`: a 10 kHz triangle `OscillatorNode` feeding
+//! a `DynamicsCompressorNode` (threshold -50 dB, knee 40, ratio 12, attack 0,
+//! release 0.25), then sum `|sample|` over frames 4500..5000. [`FingerprintGraph`]
+//! renders the same graph without a browser:
+//! - [`Oscillator`]: band-limited wavetable oscillator, as built from a PeriodicWave
+//! - [`DynamicsCompressor`]: port of the compressor kernel Blink, WebKit and Gecko share
+//!
+//! Everything runs in `f32` in 128-frame render quanta like the browsers do, so
+//! rounding matches theirs as far as the math library allows.
+
+use std::f32::consts::FRAC_PI_2;
+use std::f64::consts::PI;
+use std::ops::Range;
+
+/// Frames per render quantum
+pub const RENDER_QUANTUM: usize = 128;
+
+/// Compressor envelope is updated once per division
+const DIVISION_FRAMES: usize = 32;
+/// Pre-delay ring buffer size (power of two)
+const MAX_PRE_DELAY_FRAMES: usize = 1024;
+const DEFAULT_PRE_DELAY_FRAMES: usize = 256;
+const METERING_RELEASE_TIME: f32 = 0.325;
+/// Adaptive release curve, as fractions of the release time
+const RELEASE_ZONES: [f32; 4] = [0.09, 0.16, 0.42, 0.98];
+const SPACING_DB: f32 = 5.0;
+
+/// `OscillatorNode.type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OscillatorType {
+    Sine,
+    Square,
+    Sawtooth,
+    Triangle,
+}
+
+impl OscillatorType {
+    /// Sine-series coefficient of partial `n` (1-based)
+    fn coefficient(self, n: usize) -> f64 {
+        let odd = n % 2 == 1;
+        let n = n as f64;
+        match self {
+            OscillatorType::Sine => (n == 1.0) as u8 as f64,
+            OscillatorType::Square if odd => 4.0 / (PI * n),
+            OscillatorType::Sawtooth => 2.0 / (PI * n) * if odd { 1.0 } else { -1.0 },
+            OscillatorType::Triangle if odd => {
+                let sign = if (n as usize / 2).is_multiple_of(2) {
+                    1.0
+                } else {
+                    -1.0
+                };
+                8.0 / (PI * PI * n * n) * sign
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// Wavetable size browsers pick for a sample rate
+fn wavetable_size(sample_rate: f32) -> usize {
+    if sample_rate <= 24000.0 {
+        2048
+    } else if sample_rate <= 88200.0 {
+        4096
+    } else {
+        16384
+    }
+}
+
+/// Band-limited wavetable oscillator at a fixed frequency
+#[derive(Debug, Clone)]
+pub struct Oscillator {
+    table: Vec<f32>,
+    /// table samples advanced per frame
+    increment: f32,
+    read_index: f64,
+    fma: bool,
+}
+
+impl Oscillator {
+    pub fn new(kind: OscillatorType, frequency: f32, sample_rate: f32) -> Self {
+        let size = wavetable_size(sample_rate);
+        let sines: Vec<f64> = (0..size)
+            .map(|i| (2.0 * PI * i as f64 / size as f64).sin())
+            .collect();
+        let table_for = |partials: usize| -> Vec<f64> {
+            (0..size)
+                .map(|i| {
+                    (1..=partials)
+                        .map(|n| kind.coefficient(n) * sines[(n * i) % size])
+                        .sum()
+                })
+                .collect()
+        };
+
+        // scaled so the full-band wave peaks at 1, then only partials below Nyquist kept
+        let full_band = table_for(size / 2 - 1);
+        let peak = full_band.iter().fold(0.0f64, |peak, v| peak.max(v.abs()));
+        let nyquist = sample_rate as f64 / 2.0;
+        let partials = ((nyquist / frequency as f64).ceil() as usize)
+            .saturating_sub(1)
+            .min(size / 2 - 1);
+        let table = table_for(partials)
+            .into_iter()
+            .map(|v| (v / peak) as f32)
+            .collect();
+
+        Self {
+            table,
+            increment: frequency * (size as f32 / sample_rate),
+            read_index: 0.0,
+            fma: false,
+        }
+    }
+
+    /// Contract multiply-adds into fused operations, as arm64 builds do
+    pub fn with_fma(mut self, fma: bool) -> Self {
+        self.fma = fma;
+        self
+    }
+
+    pub fn process(&mut self, output: &mut [f32]) {
+        let size = self.table.len();
+        for sample in output {
+            let index = self.read_index.floor();
+            let fraction = (self.read_index - index) as f32;
+            let i0 = index as usize % size;
+            let (a, b) = (self.table[i0], self.table[(i0 + 1) % size]);
+            *sample = mul_add(self.fma, fraction, b, (1.0 - fraction) * a);
+
+            self.read_index += self.increment as f64;
+            if self.read_index >= size as f64 {
+                self.read_index -= size as f64;
+            }
+        }
+    }
+}
+
+fn mul_add(fma: bool, a: f32, b: f32, c: f32) -> f32 {
+    if fma {
+        a.mul_add(b, c)
+    } else {
+        a * b + c
+    }
+}
+
+fn decibels_to_linear(db: f32) -> f32 {
+    10f32.powf(0.05 * db)
+}
+
+fn linear_to_decibels(linear: f32) -> f32 {
+    20.0 * linear.log10()
+}
+
+/// Coefficients of the 4th order polynomial through the release zones at x = 0, 1, 2, 3
+// digits kept as in the browsers' kernel source
+#[allow(clippy::excessive_precision)]
+fn release_curve(release_frames: f32) -> [f32; 5] {
+    let [y1, y2, y3, y4] = RELEASE_ZONES.map(|zone| release_frames * zone);
+    let ka = 0.999_999_999_999_999_8 * y1 + 1.843_221_968_432_392_3e-16 * y2
+        - 1.937_339_435_167_642_3e-16 * y3
+        + 8.824_516_011_816_245e-18 * y4;
+    let kb = -1.578_832_035_284_588_8 * y1 + 2.330_583_703_207_428_6 * y2
+        - 0.914_119_420_484_042_9 * y3
+        + 0.162_367_752_561_203_2 * y4;
+    let kc = 0.533_414_286_910_642_4 * y1 - 1.272_736_789_213_631 * y2
+        + 0.925_885_604_220_751_2 * y3
+        - 0.186_563_101_917_762_26 * y4;
+    let kd = 0.087_834_631_382_072_34 * y1 - 0.169_416_296_792_562_2 * y2
+        + 0.085_880_579_515_952_72 * y3
+        - 0.004_298_914_105_462_83 * y4;
+    let ke = -0.042_416_883_008_123_074 * y1 + 0.111_569_382_798_760_2 * y2
+        - 0.097_646_763_252_658_72 * y3
+        + 0.028_494_263_462_021_576 * y4;
+    [ka, kb, kc, kd, ke]
+}
+
+/// `DynamicsCompressorNode` parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompressorParams {
+    pub threshold: f32,
+    pub knee: f32,
+    pub ratio: f32,
+    /// seconds
+    pub attack: f32,
+    /// seconds
+    pub release: f32,
+}
+
+impl Default for CompressorParams {
+    /// Web Audio defaults
+    fn default() -> Self {
+        Self {
+            threshold: -24.0,
+            knee: 30.0,
+            ratio: 12.0,
+            attack: 0.003,
+            release: 0.25,
+        }
+    }
+}
+
+/// Static compression curve: linear, exponential knee, then constant ratio
+#[derive(Debug, Clone, Copy)]
+struct Curve {
+    linear_threshold: f32,
+    knee_threshold: f32,
+    knee_threshold_db: f32,
+    y_knee_threshold_db: f32,
+    slope: f32,
+    k: f32,
+}
+
+impl Curve {
+    fn new(params: &CompressorParams) -> Self {
+        let mut curve = Self {
+            linear_threshold: decibels_to_linear(params.threshold),
+            knee_threshold: 0.0,
+            knee_threshold_db: params.threshold + params.knee,
+            y_knee_threshold_db: 0.0,
+            slope: 1.0 / params.ratio,
+            k: 0.0,
+        };
+        curve.k = curve.k_at_slope(1.0 / params.ratio);
+        curve.knee_threshold = decibels_to_linear(curve.knee_threshold_db);
+        curve.y_knee_threshold_db =
+            linear_to_decibels(curve.knee_curve(curve.knee_threshold, curve.k));
+        curve
+    }
+
+    fn knee_curve(&self, x: f32, k: f32) -> f32 {
+        if x < self.linear_threshold {
+            return x;
+        }
+        self.linear_threshold + (1.0 - (-k * (x - self.linear_threshold)).exp()) / k
+    }
+
+    fn saturate(&self, x: f32) -> f32 {
+        if x < self.knee_threshold {
+            self.knee_curve(x, self.k)
+        } else {
+            let x_db = linear_to_decibels(x);
+            decibels_to_linear(
+                self.y_knee_threshold_db + self.slope * (x_db - self.knee_threshold_db),
+            )
+        }
+    }
+
+    /// Slope of the knee in dB/dB at `x`
+    fn slope_at(&self, x: f32, k: f32) -> f32 {
+        if x < self.linear_threshold {
+            return 1.0;
+        }
+        let x2 = (x as f64 * 1.001) as f32;
+        let x_db = linear_to_decibels(x);
+        let x2_db = linear_to_decibels(x2);
+        let y_db = linear_to_decibels(self.knee_curve(x, k));
+        let y2_db = linear_to_decibels(self.knee_curve(x2, k));
+        (y2_db - y_db) / (x2_db - x_db)
+    }
+
+    /// Knee sharpness whose slope at the knee end equals `1 / ratio`
+    fn k_at_slope(&self, desired_slope: f32) -> f32 {
+        let x = decibels_to_linear(self.knee_threshold_db);
+        let (mut min_k, mut max_k, mut k) = (0.1f32, 10000f32, 5f32);
+        for _ in 0..15 {
+            if self.slope_at(x, k) < desired_slope {
+                max_k = k;
+            } else {
+                min_k = k;
+            }
+            k = (min_k * max_k).sqrt();
+        }
+        k
+    }
+}
+
+/// Mono dynamics compressor with the browsers' adaptive-release envelope
+#[derive(Debug, Clone)]
+pub struct DynamicsCompressor {
+    params: CompressorParams,
+    sample_rate: f32,
+    curve: Curve,
+    pre_delay: Vec<f32>,
+    pre_delay_read: usize,
+    pre_delay_write: usize,
+    detector_average: f32,
+    compressor_gain: f32,
+    max_attack_compression_diff_db: f32,
+    metering_gain: f32,
+    fma: bool,
+}
+
+impl DynamicsCompressor {
+    pub fn new(params: CompressorParams, sample_rate: f32) -> Self {
+        // the node fixes the pre-delay at 6 ms
+        let pre_delay_frames = ((0.006 * sample_rate) as usize).min(MAX_PRE_DELAY_FRAMES - 1);
+        let pre_delay_frames = if pre_delay_frames == 0 {
+            DEFAULT_PRE_DELAY_FRAMES
+        } else {
+            pre_delay_frames
+        };
+        Self {
+            params,
+            sample_rate,
+            curve: Curve::new(&params),
+            pre_delay: vec![0.0; MAX_PRE_DELAY_FRAMES],
+            pre_delay_read: 0,
+            pre_delay_write: pre_delay_frames,
+            detector_average: 0.0,
+            compressor_gain: 1.0,
+            max_attack_compression_diff_db: -1.0,
+            metering_gain: 1.0,
+            fma: false,
+        }
+    }
+
+    /// Contract multiply-adds into fused operations, as arm64 builds do
+    pub fn with_fma(mut self, fma: bool) -> Self {
+        self.fma = fma;
+        self
+    }
+
+    /// Gain reduction in dB, as `DynamicsCompressorNode.reduction` reports it
+    pub fn reduction(&self) -> f32 {
+        self.metering_gain
+    }
+
+    /// Compress `input` into `output`; lengths must be whole divisions of 32 frames
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        debug_assert_eq!(input.len(), output.len());
+        debug_assert_eq!(input.len() % DIVISION_FRAMES, 0);
+        let fma = self.fma;
+        let sample_rate = self.sample_rate;
+
+        let k = self.curve.k;
+        let full_range_makeup_gain = (1.0 / self.curve.saturate(1.0)).powf(0.6);
+        let master_linear_gain = decibels_to_linear(0.0) * full_range_makeup_gain;
+
+        let attack_frames = self.params.attack.max(0.001) * sample_rate;
+        let release_frames = sample_rate * self.params.release;
+        let sat_release_frames = 0.0025 * sample_rate;
+        let metering_release_k =
+            1.0 - (-1.0 / (sample_rate * METERING_RELEASE_TIME) as f64).exp() as f32;
+
+        let [ka, kb, kc, kd, ke] = release_curve(release_frames);
+
+        for (input, output) in input
+            .chunks_exact(DIVISION_FRAMES)
+            .zip(output.chunks_exact_mut(DIVISION_FRAMES))
+        {
+            if !self.detector_average.is_finite() {
+                self.detector_average = 1.0;
+            }
+            let desired_gain = self.detector_average;
+            // pre-warped so the sin() warp below lands on the desired gain
+            let scaled_desired_gain = desired_gain.asin() / FRAC_PI_2;
+            let is_releasing = scaled_desired_gain > self.compressor_gain;
+            let mut compression_diff_db =
+                linear_to_decibels(self.compressor_gain / scaled_desired_gain);
+
+            let envelope_rate = if is_releasing {
+                self.max_attack_compression_diff_db = -1.0;
+                if !compression_diff_db.is_finite() {
+                    compression_diff_db = -1.0;
+                }
+                let x = 0.25 * (compression_diff_db.clamp(-12.0, 0.0) + 12.0);
+                let x2 = x * x;
+                let x3 = x2 * x;
+                let x4 = x2 * x2;
+                let release_frames = ka + kb * x + kc * x2 + kd * x3 + ke * x4;
+                decibels_to_linear(SPACING_DB / release_frames)
+            } else {
+                if !compression_diff_db.is_finite() {
+                    compression_diff_db = 1.0;
+                }
+                if self.max_attack_compression_diff_db == -1.0
+                    || self.max_attack_compression_diff_db < compression_diff_db
+                {
+                    self.max_attack_compression_diff_db = compression_diff_db;
+                }
+                let eff_atten_diff_db = self.max_attack_compression_diff_db.max(0.5);
+                1.0 - (0.25 / eff_atten_diff_db).powf(1.0 / attack_frames)
+            };
+
+            for (&source, destination) in input.iter().zip(output) {
+                self.pre_delay[self.pre_delay_write] = source;
+                let abs_input = source.abs();
+
+                let shaped_input = self.curve.saturate(abs_input);
+                let attenuation = if abs_input <= 0.0001 {
+                    1.0
+                } else {
+                    shaped_input / abs_input
+                };
+                let attenuation_db = (-linear_to_decibels(attenuation)).max(2.0);
+                let sat_release_rate =
+                    decibels_to_linear(attenuation_db / sat_release_frames) - 1.0;
+                let rate = if attenuation > self.detector_average {
+                    sat_release_rate
+                } else {
+                    1.0
+                };
+                self.detector_average = mul_add(
+                    fma,
+                    attenuation - self.detector_average,
+                    rate,
+                    self.detector_average,
+                )
+                .min(1.0);
+                if !self.detector_average.is_finite() {
+                    self.detector_average = 1.0;
+                }
+
+                if envelope_rate < 1.0 {
+                    self.compressor_gain = mul_add(
+                        fma,
+                        scaled_desired_gain - self.compressor_gain,
+                        envelope_rate,
+                        self.compressor_gain,
+                    );
+                } else {
+                    self.compressor_gain = (self.compressor_gain * envelope_rate).min(1.0);
+                }
+
+                // warped to smooth the exponential transitions
+                let post_warp_compressor_gain = (FRAC_PI_2 * self.compressor_gain).sin();
+                let total_gain = master_linear_gain * post_warp_compressor_gain;
+
+                let db_real_gain = 20.0 * post_warp_compressor_gain.log10();
+                if db_real_gain < self.metering_gain {
+                    self.metering_gain = db_real_gain;
+                } else {
+                    self.metering_gain += (db_real_gain - self.metering_gain) * metering_release_k;
+                }
+
+                *destination = self.pre_delay[self.pre_delay_read] * total_gain;
+                self.pre_delay_read = (self.pre_delay_read + 1) & (MAX_PRE_DELAY_FRAMES - 1);
+                self.pre_delay_write = (self.pre_delay_write + 1) & (MAX_PRE_DELAY_FRAMES - 1);
+            }
+        }
+    }
+}
+
+/// Oscillator -> compressor -> destination graph rendered offline
+#[derive(Debug, Clone, PartialEq)]
+pub struct FingerprintGraph {
+    pub sample_rate: f32,
+    /// frames rendered
+    pub length: usize,
+    pub oscillator: OscillatorType,
+    /// Hz
+    pub frequency: f32,
+    pub compressor: CompressorParams,
+    /// frames whose absolute values are summed
+    pub sum_range: Range<usize>,
+}
+
+impl Default for FingerprintGraph {
+    /// The graph fingerprintjs renders
+    fn default() -> Self {
+        Self {
+            sample_rate: 44100.0,
+            length: 5000,
+            oscillator: OscillatorType::Triangle,
+            frequency: 10000.0,
+            compressor: CompressorParams {
+                threshold: -50.0,
+                knee: 40.0,
+                ratio: 12.0,
+                attack: 0.0,
+                release: 0.25,
+            },
+            sum_range: 4500..5000,
+        }
+    }
+}
+
+impl FingerprintGraph {
+    /// Rendered buffer, `length` frames
+    pub fn render(&self, fma: bool) -> Vec<f32> {
+        let quanta = self.length.div_ceil(RENDER_QUANTUM);
+        let mut oscillator =
+            Oscillator::new(self.oscillator, self.frequency, self.sample_rate).with_fma(fma);
+        let mut compressor =
+            DynamicsCompressor::new(self.compressor, self.sample_rate).with_fma(fma);
+
+        let mut source = [0.0f32; RENDER_QUANTUM];
+        let mut rendered = vec![0.0f32; quanta * RENDER_QUANTUM];
+        for quantum in rendered.chunks_exact_mut(RENDER_QUANTUM) {
+            oscillator.process(&mut source);
+            compressor.process(&source, quantum);
+        }
+        rendered.truncate(self.length);
+        rendered
+    }
+
+    /// Sum of `|sample|` over `sum_range`, as the script computes it in JS numbers
+    pub fn fingerprint_value(&self, samples: &[f32]) -> f64 {
+        samples
+            .get(self.sum_range.clone())
+            .unwrap_or_default()
+            .iter()
+            .map(|&s| (s as f64).abs())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_graph_render() {
+        let graph = FingerprintGraph::default();
+        let samples = graph.render(false);
+        assert_eq!(samples.len(), 5000);
+        assert_eq!(samples, graph.render(false));
+        assert!(samples.iter().all(|s| s.abs() <= 1.0));
+
+        // desktop Chrome reports 124.04347527516074 for this graph
+        let value = graph.fingerprint_value(&samples);
+        assert!((value - 124.043_475_275_160_74).abs() < 1e-4, "{}", value);
+        assert_ne!(graph.fingerprint_value(&graph.render(true)), value);
+
+        let mut compressor = DynamicsCompressor::new(CompressorParams::default(), 44100.0);
+        let mut output = [0.0f32; RENDER_QUANTUM];
+        compressor.process(&[0.5; RENDER_QUANTUM], &mut output);
+        assert!(compressor.reduction() < 0.0);
+    }
+}
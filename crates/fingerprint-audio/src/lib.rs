@@ -9,6 +9,17 @@
 //! - 采样率识别
 //! - 频率分析
 //! - 音频处理精度检测
+//!
+//! [`dsp`] 离线渲染指纹脚本使用的 oscillator/compressor 图，[`simulation`] 据此预测
+//! 各浏览器/OS 的音频指纹值并检测提交数据的不一致
+
+pub mod dsp;
+pub mod simulation;
+
+pub use dsp::FingerprintGraph;
+pub use simulation::{
+    AudioCheck, AudioEngine, AudioPrediction, AudioSimulator, DspProfile, PredictionSource,
+};
 
 use std::collections::HashMap;
 
@@ -0,0 +1,329 @@
+//! Audio fingerprint prediction for browser/OS profiles
+//!
+//! [`AudioSimulator`] predicts the value the fingerprinting graph yields for a
+//! [`DspProfile`] and checks submitted values against it. Blink's output is
+//! reproduced by [`crate::dsp`] to about 1e-7 relative; Gecko and WebKit render
+//! the graph differently in ways the simulation does not model, so they are only
+//! predicted from recorded reference values ([`AudioSimulator::add_reference`]).
+
+use crate::dsp::FingerprintGraph;
+use crate::AudioError;
+use std::collections::HashMap;
+
+/// Largest relative deviation from a simulated value still counted as a match
+pub const SIMULATION_TOLERANCE: f64 = 1e-6;
+/// Same browser build and platform reproduce the value exactly
+pub const REFERENCE_TOLERANCE: f64 = 1e-12;
+/// Largest relative RMS difference between submitted and simulated samples
+const WAVEFORM_TOLERANCE: f64 = 1e-4;
+
+/// Web Audio implementation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AudioEngine {
+    Blink,
+    Gecko,
+    WebKit,
+}
+
+impl AudioEngine {
+    pub const ALL: [AudioEngine; 3] = [AudioEngine::Blink, AudioEngine::Gecko, AudioEngine::WebKit];
+
+    /// Engine of a browser ("chrome", "firefox", ...) on an OS; iOS browsers are WebKit
+    pub fn for_client(browser: &str, os: &str) -> Option<Self> {
+        if matches!(os.to_lowercase().as_str(), "ios" | "ipados") {
+            return Some(AudioEngine::WebKit);
+        }
+        match browser.to_lowercase().as_str() {
+            "chrome" | "chromium" | "edge" | "opera" | "brave" | "samsung" => {
+                Some(AudioEngine::Blink)
+            }
+            "firefox" => Some(AudioEngine::Gecko),
+            "safari" => Some(AudioEngine::WebKit),
+            _ => None,
+        }
+    }
+
+    /// Whether [`crate::dsp`] reproduces this engine's output
+    pub fn is_simulated(&self) -> bool {
+        *self == AudioEngine::Blink
+    }
+}
+
+/// Browser/OS combination whose rendering is predicted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DspProfile {
+    pub engine: AudioEngine,
+    /// arm64 builds fuse multiply-adds, which changes the low bits
+    pub fma: bool,
+}
+
+impl DspProfile {
+    pub fn new(engine: AudioEngine, fma: bool) -> Self {
+        Self { engine, fma }
+    }
+
+    /// Profile of a browser on an OS; macOS, iOS and Android are taken to be arm64
+    pub fn for_client(browser: &str, os: &str) -> Result<Self, AudioError> {
+        let engine = AudioEngine::for_client(browser, os).ok_or_else(|| {
+            AudioError::Other(format!(
+                "no Web Audio engine known for {} on {}",
+                browser, os
+            ))
+        })?;
+        let fma = matches!(
+            os.to_lowercase().as_str(),
+            "macos" | "ios" | "ipados" | "android"
+        );
+        Ok(Self { engine, fma })
+    }
+
+    /// Override the architecture guess (e.g. Intel Macs)
+    pub fn with_fma(mut self, fma: bool) -> Self {
+        self.fma = fma;
+        self
+    }
+}
+
+/// Where a predicted value comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PredictionSource {
+    Simulated,
+    Reference,
+}
+
+/// Expected fingerprint value for a profile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioPrediction {
+    pub value: f64,
+    pub source: PredictionSource,
+}
+
+impl AudioPrediction {
+    fn tolerance(&self) -> f64 {
+        match self.source {
+            PredictionSource::Simulated => SIMULATION_TOLERANCE,
+            PredictionSource::Reference => REFERENCE_TOLERANCE,
+        }
+    }
+
+    /// Relative deviation of `value` from the prediction
+    pub fn deviation(&self, value: f64) -> f64 {
+        (value - self.value).abs() / self.value.abs().max(f64::MIN_POSITIVE)
+    }
+
+    pub fn matches(&self, value: f64) -> bool {
+        self.deviation(value) <= self.tolerance()
+    }
+}
+
+/// Result of checking a submitted audio fingerprint against a profile
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioCheck {
+    pub profile: DspProfile,
+    pub expected: Option<AudioPrediction>,
+    pub observed: f64,
+    /// other profiles whose prediction the observed value matches
+    pub matching_profiles: Vec<DspProfile>,
+    pub issues: Vec<String>,
+}
+
+impl AudioCheck {
+    pub fn is_consistent(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Predicts and checks audio fingerprints for profiles
+#[derive(Debug, Clone)]
+pub struct AudioSimulator {
+    graph: FingerprintGraph,
+    /// Blink renders without and with FMA
+    rendered: [Vec<f32>; 2],
+    references: HashMap<DspProfile, f64>,
+}
+
+impl AudioSimulator {
+    /// Simulator for the graph fingerprintjs renders
+    pub fn new() -> Self {
+        Self::with_graph(FingerprintGraph::default())
+    }
+
+    pub fn with_graph(graph: FingerprintGraph) -> Self {
+        Self {
+            rendered: [graph.render(false), graph.render(true)],
+            graph,
+            references: HashMap::new(),
+        }
+    }
+
+    pub fn graph(&self) -> &FingerprintGraph {
+        &self.graph
+    }
+
+    /// Record the value a real browser produced; it takes precedence over simulation
+    pub fn add_reference(&mut self, profile: DspProfile, value: f64) {
+        self.references.insert(profile, value);
+    }
+
+    /// Rendered buffer for a profile the simulation reproduces
+    pub fn render(&self, profile: DspProfile) -> Option<&[f32]> {
+        profile
+            .engine
+            .is_simulated()
+            .then(|| self.rendered[profile.fma as usize].as_slice())
+    }
+
+    /// Expected value for a profile, if it is simulated or has a reference
+    pub fn predict(&self, profile: DspProfile) -> Option<AudioPrediction> {
+        if let Some(&value) = self.references.get(&profile) {
+            return Some(AudioPrediction {
+                value,
+                source: PredictionSource::Reference,
+            });
+        }
+        self.render(profile).map(|samples| AudioPrediction {
+            value: self.graph.fingerprint_value(samples),
+            source: PredictionSource::Simulated,
+        })
+    }
+
+    /// Check a submitted value, and the rendered samples when the client sent them
+    pub fn check(&self, profile: DspProfile, value: f64, samples: Option<&[f32]>) -> AudioCheck {
+        let mut issues = Vec::new();
+        let expected = self.predict(profile);
+
+        if let Some(samples) = samples {
+            if samples.len() != self.graph.length {
+                issues.push(format!(
+                    "{} samples submitted, the graph renders {}",
+                    samples.len(),
+                    self.graph.length
+                ));
+            }
+            if samples.iter().any(|s| !s.is_finite() || s.abs() > 1.0) {
+                issues.push("samples outside [-1, 1]".to_string());
+            }
+            let recomputed = self.graph.fingerprint_value(samples);
+            if (recomputed - value).abs() > REFERENCE_TOLERANCE * value.abs().max(1.0) {
+                issues.push(format!(
+                    "value {} does not match the submitted samples ({})",
+                    value, recomputed
+                ));
+            }
+            if let Some(simulated) = self.render(profile) {
+                let residual = rms_difference(samples, simulated, &self.graph);
+                if residual > WAVEFORM_TOLERANCE {
+                    issues.push(format!(
+                        "samples deviate from the rendered waveform by {:.2e} (RMS), \
+                         consistent with injected noise",
+                        residual
+                    ));
+                }
+            }
+        }
+
+        if let Some(expected) = expected {
+            if !expected.matches(value) {
+                issues.push(format!(
+                    "value {} differs from the expected {} for {:?} (relative {:.2e})",
+                    value,
+                    expected.value,
+                    profile.engine,
+                    expected.deviation(value)
+                ));
+            }
+        }
+
+        let matching_profiles: Vec<DspProfile> = AudioEngine::ALL
+            .iter()
+            .flat_map(|&engine| [false, true].map(|fma| DspProfile::new(engine, fma)))
+            .filter(|&other| other != profile)
+            .filter(|&other| self.predict(other).is_some_and(|p| p.matches(value)))
+            .collect();
+        if let Some(other) = matching_profiles
+            .iter()
+            .find(|other| other.engine != profile.engine)
+        {
+            issues.push(format!(
+                "value matches {:?} rendering, not {:?}",
+                other.engine, profile.engine
+            ));
+        }
+
+        AudioCheck {
+            profile,
+            expected,
+            observed: value,
+            matching_profiles,
+            issues,
+        }
+    }
+}
+
+impl Default for AudioSimulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// RMS of the difference over the summed range, relative to the simulated RMS
+fn rms_difference(samples: &[f32], simulated: &[f32], graph: &FingerprintGraph) -> f64 {
+    let range = graph.sum_range.start..graph.sum_range.end.min(samples.len());
+    let (mut difference, mut power) = (0.0f64, 0.0f64);
+    for i in range {
+        let (a, b) = (samples[i] as f64, simulated[i] as f64);
+        difference += (a - b) * (a - b);
+        power += b * b;
+    }
+    (difference / power.max(f64::MIN_POSITIVE)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_predict_and_check() {
+        let mut simulator = AudioSimulator::new();
+        let chrome = DspProfile::for_client("chrome", "Windows").unwrap();
+        let firefox = DspProfile::for_client("firefox", "Windows").unwrap();
+        assert_eq!(chrome, DspProfile::new(AudioEngine::Blink, false));
+        assert_eq!(
+            DspProfile::for_client("chrome", "iOS").unwrap().engine,
+            AudioEngine::WebKit
+        );
+        assert!(DspProfile::for_client("curl", "Linux").is_err());
+
+        let samples = simulator.render(chrome).unwrap().to_vec();
+        let predicted = simulator.predict(chrome).unwrap();
+        assert_eq!(predicted.source, PredictionSource::Simulated);
+        let check = simulator.check(chrome, predicted.value, Some(&samples));
+        assert!(check.is_consistent(), "{:?}", check.issues);
+
+        // Gecko is only known from recorded values
+        assert_eq!(simulator.predict(firefox), None);
+        simulator.add_reference(firefox, 35.7);
+        assert!(simulator.check(firefox, 35.7, None).is_consistent());
+
+        // a Blink value submitted by a client claiming Firefox
+        let spoofed = simulator.check(firefox, predicted.value, None);
+        assert!(!spoofed.is_consistent());
+        assert!(spoofed.matching_profiles.contains(&chrome));
+
+        // per-sample noise, as anti-fingerprinting extensions add
+        let noisy: Vec<f32> = samples
+            .iter()
+            .enumerate()
+            .map(|(i, s)| s + if i % 2 == 0 { 1e-4 } else { -1e-4 })
+            .collect();
+        let value = simulator.graph().fingerprint_value(&noisy);
+        let check = simulator.check(chrome, value, Some(&noisy));
+        assert!(check
+            .issues
+            .iter()
+            .any(|issue| issue.contains("injected noise")));
+        assert!(!simulator
+            .check(chrome, value + 1.0, Some(&noisy))
+            .is_consistent());
+    }
+}
@@ -0,0 +1,250 @@
+//! Canvas profile datasets
+//!
+//! A [`CanvasDataset`] is a versioned set of [`CanvasProfile`]s (hash, browser,
+//! version, OS, GPU, weight) exchanged as
+//! - JSON: `{"metadata": {...}, "profiles": [...]}`
+//! - CSV: header `hash,browser,version,os,gpu,weight`, preceded by metadata as
+//!   `# key: value` comment lines
+//!
+//! Imports reject datasets written with a newer [`DATASET_SCHEMA_VERSION`].
+
+use crate::{CanvasError, CanvasProfile, CanvasProfileLibrary};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Dataset layout version written by this release
+pub const DATASET_SCHEMA_VERSION: u32 = 1;
+
+/// Identity and provenance of a dataset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatasetMetadata {
+    pub name: String,
+    /// Dataset release, e.g. "2026.10"
+    pub version: String,
+    pub schema_version: u32,
+    /// Collection date or export timestamp
+    pub created: Option<String>,
+    /// Where the profiles came from
+    pub source: Option<String>,
+    /// Profiles at export time
+    pub profile_count: usize,
+}
+
+impl Default for DatasetMetadata {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            version: String::new(),
+            schema_version: DATASET_SCHEMA_VERSION,
+            created: None,
+            source: None,
+            profile_count: 0,
+        }
+    }
+}
+
+impl DatasetMetadata {
+    fn check_schema(&self) -> Result<(), CanvasError> {
+        if self.schema_version > DATASET_SCHEMA_VERSION {
+            return Err(CanvasError::DatasetError(format!(
+                "dataset schema {} is newer than supported ({})",
+                self.schema_version, DATASET_SCHEMA_VERSION
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Metadata plus profiles
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CanvasDataset {
+    #[serde(default)]
+    pub metadata: DatasetMetadata,
+    pub profiles: Vec<CanvasProfile>,
+}
+
+fn dataset_error(e: impl std::fmt::Display) -> CanvasError {
+    CanvasError::DatasetError(e.to_string())
+}
+
+impl CanvasDataset {
+    pub fn from_json(reader: impl Read) -> Result<Self, CanvasError> {
+        let dataset: Self = serde_json::from_reader(reader).map_err(dataset_error)?;
+        dataset.metadata.check_schema()?;
+        Ok(dataset)
+    }
+
+    pub fn to_json(&self, writer: impl Write) -> Result<(), CanvasError> {
+        serde_json::to_writer_pretty(writer, self).map_err(dataset_error)
+    }
+
+    pub fn from_csv(mut reader: impl Read) -> Result<Self, CanvasError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).map_err(dataset_error)?;
+
+        let mut metadata = DatasetMetadata::default();
+        for line in text.lines().map_while(|line| line.strip_prefix('#')) {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim().to_string();
+            match key.trim() {
+                "name" => metadata.name = value,
+                "version" => metadata.version = value,
+                "schema_version" => {
+                    metadata.schema_version = value
+                        .parse()
+                        .map_err(|_| dataset_error(format!("invalid schema_version {:?}", value)))?
+                }
+                "created" => metadata.created = Some(value),
+                "source" => metadata.source = Some(value),
+                "profile_count" => metadata.profile_count = value.parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+        metadata.check_schema()?;
+
+        let profiles = csv::ReaderBuilder::new()
+            .comment(Some(b'#'))
+            .trim(csv::Trim::All)
+            .from_reader(text.as_bytes())
+            .deserialize()
+            .collect::<Result<Vec<CanvasProfile>, _>>()
+            .map_err(dataset_error)?;
+        Ok(Self { metadata, profiles })
+    }
+
+    pub fn to_csv(&self, mut writer: impl Write) -> Result<(), CanvasError> {
+        let metadata = &self.metadata;
+        let mut header = vec![
+            ("name", metadata.name.clone()),
+            ("version", metadata.version.clone()),
+            ("schema_version", metadata.schema_version.to_string()),
+        ];
+        header.extend(metadata.created.clone().map(|created| ("created", created)));
+        header.extend(metadata.source.clone().map(|source| ("source", source)));
+        header.push(("profile_count", metadata.profile_count.to_string()));
+        for (key, value) in header {
+            writeln!(writer, "# {}: {}", key, value).map_err(dataset_error)?;
+        }
+
+        let mut csv = csv::Writer::from_writer(writer);
+        for profile in &self.profiles {
+            csv.serialize(profile).map_err(dataset_error)?;
+        }
+        csv.flush().map_err(dataset_error)
+    }
+}
+
+/// JSON unless the extension says `.csv`
+fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
+
+impl CanvasProfileLibrary {
+    /// Load a `.json` or `.csv` dataset
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CanvasError> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path).map_err(dataset_error)?;
+        let reader = std::io::BufReader::new(file);
+        let dataset = if is_csv(path) {
+            CanvasDataset::from_csv(reader)?
+        } else {
+            CanvasDataset::from_json(reader)?
+        };
+        Ok(Self::from_dataset(dataset))
+    }
+
+    /// Save as `.json` or `.csv`, by extension
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CanvasError> {
+        let path = path.as_ref();
+        let file = std::fs::File::create(path).map_err(dataset_error)?;
+        let writer = std::io::BufWriter::new(file);
+        let dataset = self.to_dataset();
+        if is_csv(path) {
+            dataset.to_csv(writer)
+        } else {
+            dataset.to_json(writer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MATCH_THRESHOLD;
+
+    fn hex(seed: u64) -> String {
+        // splitmix64, twice, for 32 hex characters
+        let mix = |mut z: u64| {
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        };
+        format!("{:016x}{:016x}", mix(seed), mix(seed ^ 0x9e3779b97f4a7c15))
+    }
+
+    #[test]
+    fn test_large_dataset_roundtrip_and_lookup() {
+        let mut library = CanvasProfileLibrary::empty();
+        library.set_metadata(DatasetMetadata {
+            name: "device-farm".to_string(),
+            version: "2026.10".to_string(),
+            source: Some("lab, 40 devices".to_string()),
+            ..DatasetMetadata::default()
+        });
+        library.extend((0..100_000u64).map(|i| CanvasProfile {
+            os: ["Windows 11", "macOS 14", "Android 14"][i as usize % 3].to_string(),
+            gpu: "ANGLE (NVIDIA, RTX 4070)".to_string(),
+            ..CanvasProfile::new(&hex(i), "Chrome", &(100 + i % 30).to_string())
+        }));
+        assert_eq!(library.profile_count(), 100_000);
+
+        let target = hex(4242);
+        assert_eq!(library.lookup(&target).unwrap().confidence, 1.0);
+        // a few positions differ, e.g. one noisy pixel row
+        let near: String = target
+            .chars()
+            .enumerate()
+            .map(|(i, c)| if i % 8 == 3 { 'z' } else { c })
+            .collect();
+        let found = library.lookup(&near).unwrap();
+        assert_eq!(found.profile.hash, target);
+        assert!(found.confidence > MATCH_THRESHOLD && found.confidence < 1.0);
+        let results = library.lookup_all(&[near.as_str(), "no-such-hash"]);
+        assert!(results[0].is_some() && results[1].is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        for file in ["profiles.json", "profiles.csv"] {
+            let path = dir.path().join(file);
+            library.save(&path).unwrap();
+            let loaded = CanvasProfileLibrary::load(&path).unwrap();
+            assert_eq!(loaded.profile_count(), 100_000);
+            assert_eq!(loaded.metadata().version, "2026.10");
+            assert_eq!(loaded.metadata().profile_count, 100_000);
+            assert_eq!(loaded.metadata().source.as_deref(), Some("lab, 40 devices"));
+            assert_eq!(loaded.profiles()[4242], library.profiles()[4242]);
+        }
+    }
+
+    #[test]
+    fn test_import_rejects_newer_schema_and_bad_rows() {
+        let csv = "# name: future\n# schema_version: 2\nhash,browser,version\nabc,Chrome,120\n";
+        assert!(CanvasDataset::from_csv(csv.as_bytes()).is_err());
+
+        let csv = "hash,browser,version,weight\nabc,Chrome,120,heavy\n";
+        assert!(CanvasDataset::from_csv(csv.as_bytes()).is_err());
+
+        // optional columns may be left out
+        let csv = "hash,browser,version\nabc,Firefox,128\n";
+        let dataset = CanvasDataset::from_csv(csv.as_bytes()).unwrap();
+        assert_eq!(dataset.profiles[0].weight, 1.0);
+        assert_eq!(dataset.metadata.schema_version, DATASET_SCHEMA_VERSION);
+
+        let json = r#"{"metadata": {"schema_version": 9}, "profiles": []}"#;
+        assert!(CanvasDataset::from_json(json.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+//! Band index for near-match canvas hash lookup
+//!
+//! Library matching compares hashes position by position and accepts a profile when
+//! more than [`MATCH_THRESHOLD`] of the characters agree. Scanning every profile gets
+//! slow with large datasets, so [`BandIndex`] cuts each hash into bands of
+//! [`BAND_WIDTH`] characters and keys every band by position and content.
+//!
+//! Two equal-length hashes agreeing on more than 80% of their positions differ in
+//! under a fifth of them; with bands of 4 characters there are more bands than
+//! differing positions, so by the pigeonhole principle at least one band matches
+//! exactly. Looking up the
+//! query's bands therefore finds every profile that can pass the threshold (the
+//! first band doubles as a prefix index), while unrelated hashes rarely collide.
+
+use std::collections::HashMap;
+
+/// Positional similarity a near match must exceed
+pub const MATCH_THRESHOLD: f32 = 0.8;
+
+/// Characters per band; below `1 / (1 - MATCH_THRESHOLD)` the index is exact
+pub const BAND_WIDTH: usize = 4;
+
+/// Band-keyed profile index
+#[derive(Debug, Clone, Default)]
+pub struct BandIndex {
+    /// (band number, band text) to profile ids
+    bands: HashMap<(u16, String), Vec<u32>>,
+}
+
+fn bands(hash: &str) -> impl Iterator<Item = (u16, String)> + '_ {
+    let chars: Vec<char> = hash.chars().collect();
+    (0..chars.len().div_ceil(BAND_WIDTH)).map(move |band| {
+        let end = ((band + 1) * BAND_WIDTH).min(chars.len());
+        (band as u16, chars[band * BAND_WIDTH..end].iter().collect())
+    })
+}
+
+impl BandIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, hash: &str, id: u32) {
+        for key in bands(hash) {
+            self.bands.entry(key).or_default().push(id);
+        }
+    }
+
+    /// Ids sharing at least one band with `hash`, each once, ascending
+    pub fn candidates(&self, hash: &str) -> Vec<u32> {
+        let mut ids: Vec<u32> = bands(hash)
+            .filter_map(|key| self.bands.get(&key))
+            .flatten()
+            .copied()
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_near_matches_share_a_band() {
+        let mut index = BandIndex::new();
+        index.insert("0123456789abcdef0123456789abcdef", 0);
+        index.insert("fedcba9876543210fedcba9876543210", 1);
+
+        // six of 32 positions changed: still above the threshold, still a candidate
+        assert_eq!(index.candidates("x123x567x9abxdefx123x56789abcdef"), [0]);
+        assert!(index
+            .candidates("zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz")
+            .is_empty());
+        assert_eq!(index.candidates("fedc"), [1]);
+    }
+}
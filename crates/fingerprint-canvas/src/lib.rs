@@ -7,11 +7,21 @@
 //! 提供完整的 HTML5 Canvas 指纹识别功能，包括：
 //! - Canvas 2D 指纹识别
 //! - Canvas 混淆和保护
-//! - 预生成指纹库匹配
+//! - 预生成指纹库匹配（JSON/CSV 数据集导入导出，带版本元数据；分段索引支持 10 万级指纹库）
 //! - 浏览器版本识别
+//! - 感知哈希（pHash/dHash），按汉明距离匹配轻微不同的渲染结果
 
+pub mod dataset;
+pub mod index;
+pub mod perceptual;
+
+pub use dataset::{CanvasDataset, DatasetMetadata, DATASET_SCHEMA_VERSION};
+pub use index::{BandIndex, MATCH_THRESHOLD};
+pub use perceptual::{PerceptualAlgorithm, PerceptualConfig, PerceptualHash, PerceptualMatch};
+
+use fingerprint_core::hashing::{self, FingerprintHasher, HashAlgorithm, HashDigest, HashUpgrade};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use xxhash_rust::xxh3::Xxh3;
 
 /// Canvas 2D fingerprintinfo
 #[derive(Debug, Clone, PartialEq)]
@@ -20,6 +30,8 @@ pub struct CanvasFingerprint {
     pub pixel_data: String,
     /// fingerprinthash值
     pub hash: String,
+    /// perceptual hash of the decoded image; None if the data is not an image
+    pub perceptual_hash: Option<PerceptualHash>,
     /// 复杂度score (0.0-1.0)
     pub complexity: f32,
     /// rendering层级
@@ -54,6 +66,8 @@ pub enum CanvasError {
     FingerprintGenerationFailed(String),
     /// libraryqueryfailure
     LibraryQueryFailed(String),
+    /// dataset import/export failure
+    DatasetError(String),
     /// othererror
     Other(String),
 }
@@ -66,6 +80,7 @@ impl std::fmt::Display for CanvasError {
                 write!(f, "Fingerprint generation failed: {}", msg)
             }
             CanvasError::LibraryQueryFailed(msg) => write!(f, "Library query failed: {}", msg),
+            CanvasError::DatasetError(msg) => write!(f, "Dataset error: {}", msg),
             CanvasError::Other(msg) => write!(f, "Error: {}", msg),
         }
     }
@@ -76,6 +91,7 @@ impl std::error::Error for CanvasError {}
 /// Canvas fingerprintrecognition器
 pub struct CanvasAnalyzer {
     profile_library: CanvasProfileLibrary,
+    perceptual: PerceptualConfig,
 }
 
 impl CanvasAnalyzer {
@@ -83,9 +99,28 @@ impl CanvasAnalyzer {
     pub fn new() -> Self {
         CanvasAnalyzer {
             profile_library: CanvasProfileLibrary::new(),
+            perceptual: PerceptualConfig::default(),
+        }
+    }
+
+    /// analyzer matching against `library`, e.g. an imported dataset
+    pub fn with_library(library: CanvasProfileLibrary) -> Self {
+        CanvasAnalyzer {
+            profile_library: library,
+            perceptual: PerceptualConfig::default(),
         }
     }
 
+    /// Perceptual hash algorithm and match threshold
+    pub fn with_perceptual_config(mut self, config: PerceptualConfig) -> Self {
+        self.perceptual = config;
+        self
+    }
+
+    pub fn library(&self) -> &CanvasProfileLibrary {
+        &self.profile_library
+    }
+
     /// analyze Canvas data并generatefingerprint
     pub fn analyze(&self, canvas_data: &str) -> Result<CanvasFingerprint, CanvasError> {
         if canvas_data.is_empty() {
@@ -93,6 +128,7 @@ impl CanvasAnalyzer {
         }
 
         let hash = self.compute_hash(canvas_data)?;
+        let perceptual_hash = self.perceptual.hash(canvas_data).ok();
         let (detected_browser, confidence) = self.detect_browser(&hash);
         let complexity = self.evaluate_complexity(canvas_data);
         let rendering_level = self.detect_rendering_level(canvas_data);
@@ -101,6 +137,7 @@ impl CanvasAnalyzer {
         Ok(CanvasFingerprint {
             pixel_data: canvas_data.to_string(),
             hash,
+            perceptual_hash,
             complexity,
             rendering_level,
             hardware_accelerated,
@@ -109,13 +146,32 @@ impl CanvasAnalyzer {
         })
     }
 
-    /// calculatefingerprinthash
+    /// Compare two renders perceptually, tolerating antialiasing differences
+    pub fn compare(&self, canvas_a: &str, canvas_b: &str) -> Result<PerceptualMatch, CanvasError> {
+        let a = self.perceptual.hash(canvas_a)?;
+        let b = self.perceptual.hash(canvas_b)?;
+        Ok(self.perceptual.compare(&a, &b))
+    }
+
+    /// calculatefingerprinthash (configured content hash backend)
     fn compute_hash(&self, canvas_data: &str) -> Result<String, CanvasError> {
-        let mut hasher = Xxh3::new();
-        hasher.update(&(canvas_data.len() as u64).to_be_bytes());
-        hasher.update(canvas_data.as_bytes());
-        let hash_value = hasher.digest();
-        Ok(format!("{:x}", hash_value))
+        Ok(Self::hash_digest(hashing::config().content, canvas_data).to_string())
+    }
+
+    fn hash_digest(algorithm: HashAlgorithm, canvas_data: &str) -> HashDigest {
+        let mut hasher = FingerprintHasher::new(algorithm);
+        hasher.write_str(canvas_data);
+        hasher.finish()
+    }
+
+    /// check a stored canvas hash against `canvas_data` and return its replacement
+    ///
+    /// earlier releases stored untagged, unpadded xxh3 hex
+    pub fn migrate_hash(&self, stored: &str, canvas_data: &str) -> HashUpgrade {
+        let digest = |algorithm| Self::hash_digest(algorithm, canvas_data);
+        hashing::migrate_stored_hash(stored, hashing::config().content, digest, || {
+            format!("{:x}", digest(HashAlgorithm::Xxh3).as_u64())
+        })
     }
 
     /// 从fingerprintlibrary中detect浏览器version
@@ -159,53 +215,178 @@ impl Default for CanvasAnalyzer {
     }
 }
 
+impl CanvasFingerprint {
+    /// Perceptual similarity to another render, if both decoded as images
+    pub fn perceptual_similarity(&self, other: &CanvasFingerprint) -> Option<f32> {
+        Some(self.perceptual_hash?.similarity(&other.perceptual_hash?))
+    }
+}
+
 /// Canvas fingerprintconfigurefilelibrary
+///
+/// Profiles are indexed by exact hash and by [`BandIndex`], so lookups stay fast with
+/// 100k+ profiles; datasets are imported and exported as JSON or CSV (see [`dataset`]).
 pub struct CanvasProfileLibrary {
-    profiles: HashMap<String, CanvasProfile>,
+    profiles: Vec<CanvasProfile>,
+    by_hash: HashMap<String, usize>,
+    index: BandIndex,
+    metadata: DatasetMetadata,
 }
 
 /// Canvas configurefile
-#[derive(Debug, Clone)]
-struct CanvasProfile {
-    browser: String,
-    version: String,
-    hash: String,
-    #[allow(dead_code)]
-    weight: f32,
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CanvasProfile {
+    pub hash: String,
+    pub browser: String,
+    pub version: String,
+    #[serde(default)]
+    pub os: String,
+    #[serde(default)]
+    pub gpu: String,
+    /// Relative frequency of the profile in the source population
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+impl CanvasProfile {
+    pub fn new(hash: &str, browser: &str, version: &str) -> Self {
+        Self {
+            hash: hash.to_string(),
+            browser: browser.to_string(),
+            version: version.to_string(),
+            os: String::new(),
+            gpu: String::new(),
+            weight: 1.0,
+        }
+    }
+
+    /// "Browser Version"
+    pub fn label(&self) -> String {
+        format!("{} {}", self.browser, self.version)
+    }
+}
+
+/// Best library match for a canvas hash
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanvasMatch<'a> {
+    pub profile: &'a CanvasProfile,
+    /// 1.0 for an exact hash match
+    pub confidence: f32,
 }
 
 impl CanvasProfileLibrary {
     /// createnewconfigurefilelibrary
     pub fn new() -> Self {
-        let mut profiles = HashMap::new();
-        Self::load_builtin_profiles(&mut profiles);
-        CanvasProfileLibrary { profiles }
+        let mut library = Self::empty();
+        for (hash, browser, version) in [
+            ("a1b2c3d4e5f6g7h8", "Chrome", "120"),
+            ("b2c3d4e5f6g7h8i9", "Firefox", "121"),
+            ("c3d4e5f6g7h8i9j0", "Safari", "17"),
+            ("d4e5f6g7h8i9j0k1", "Edge", "120"),
+        ] {
+            library.insert(CanvasProfile::new(hash, browser, version));
+        }
+        library.metadata.name = "builtin".to_string();
+        library
     }
 
-    /// 从fingerprintlibrary中查找匹配
-    fn find_match(&self, hash: &str) -> (Option<String>, f32) {
-        if let Some(profile) = self.profiles.get(hash) {
-            return (
-                Some(format!("{} {}", profile.browser, profile.version)),
-                1.0,
-            );
+    /// Library without any profiles
+    pub fn empty() -> Self {
+        CanvasProfileLibrary {
+            profiles: Vec::new(),
+            by_hash: HashMap::new(),
+            index: BandIndex::new(),
+            metadata: DatasetMetadata::default(),
+        }
+    }
+
+    /// Library holding a dataset's profiles; later duplicates of a hash win
+    pub fn from_dataset(dataset: CanvasDataset) -> Self {
+        let mut library = Self::empty();
+        library.metadata = dataset.metadata;
+        library.extend(dataset.profiles);
+        library
+    }
+
+    /// Snapshot of the profiles with up-to-date metadata
+    pub fn to_dataset(&self) -> CanvasDataset {
+        let mut metadata = self.metadata.clone();
+        metadata.profile_count = self.profiles.len();
+        CanvasDataset {
+            metadata,
+            profiles: self.profiles.clone(),
+        }
+    }
+
+    pub fn metadata(&self) -> &DatasetMetadata {
+        &self.metadata
+    }
+
+    /// Name and version the library is exported under
+    pub fn set_metadata(&mut self, metadata: DatasetMetadata) {
+        self.metadata = metadata;
+    }
+
+    /// Add or replace profiles
+    pub fn extend(&mut self, profiles: impl IntoIterator<Item = CanvasProfile>) {
+        for profile in profiles {
+            self.insert(profile);
+        }
+    }
+
+    fn insert(&mut self, profile: CanvasProfile) {
+        if let Some(&id) = self.by_hash.get(&profile.hash) {
+            self.profiles[id] = profile;
+            return;
         }
+        let id = self.profiles.len();
+        self.index.insert(&profile.hash, id as u32);
+        self.by_hash.insert(profile.hash.clone(), id);
+        self.profiles.push(profile);
+    }
 
-        let mut best_match = None;
-        let mut best_confidence = 0.0;
+    /// Best match for `hash`: exact, or positionally similar above [`MATCH_THRESHOLD`]
+    pub fn lookup(&self, hash: &str) -> Option<CanvasMatch<'_>> {
+        if let Some(&id) = self.by_hash.get(hash) {
+            return Some(CanvasMatch {
+                profile: &self.profiles[id],
+                confidence: 1.0,
+            });
+        }
 
-        for profile in self.profiles.values() {
+        let mut best: Option<CanvasMatch> = None;
+        for id in self.index.candidates(hash) {
+            let profile = &self.profiles[id as usize];
             let confidence = Self::calculate_similarity(hash, &profile.hash);
-            if confidence > best_confidence {
-                best_confidence = confidence;
-                best_match = Some(format!("{} {}", profile.browser, profile.version));
+            if confidence > MATCH_THRESHOLD
+                && best.as_ref().is_none_or(|b| confidence > b.confidence)
+            {
+                best = Some(CanvasMatch {
+                    profile,
+                    confidence,
+                });
             }
         }
+        best
+    }
 
-        if best_confidence > 0.8 {
-            (best_match, best_confidence)
-        } else {
-            (None, 0.0)
+    /// [`CanvasProfileLibrary::lookup`] for many hashes, in order
+    pub fn lookup_all<'a, S: AsRef<str>>(&'a self, hashes: &[S]) -> Vec<Option<CanvasMatch<'a>>> {
+        hashes
+            .iter()
+            .map(|hash| self.lookup(hash.as_ref()))
+            .collect()
+    }
+
+    /// 从fingerprintlibrary中查找匹配
+    fn find_match(&self, hash: &str) -> (Option<String>, f32) {
+        match self.lookup(hash) {
+            Some(found) => (Some(found.profile.label()), found.confidence),
+            None => (None, 0.0),
         }
     }
 
@@ -225,66 +406,19 @@ impl CanvasProfileLibrary {
         matches as f32 / max_len as f32
     }
 
-    /// load内置of浏览器configure
-    fn load_builtin_profiles(profiles: &mut HashMap<String, CanvasProfile>) {
-        profiles.insert(
-            "a1b2c3d4e5f6g7h8".to_string(),
-            CanvasProfile {
-                browser: "Chrome".to_string(),
-                version: "120".to_string(),
-                hash: "a1b2c3d4e5f6g7h8".to_string(),
-                weight: 1.0,
-            },
-        );
-
-        profiles.insert(
-            "b2c3d4e5f6g7h8i9".to_string(),
-            CanvasProfile {
-                browser: "Firefox".to_string(),
-                version: "121".to_string(),
-                hash: "b2c3d4e5f6g7h8i9".to_string(),
-                weight: 1.0,
-            },
-        );
-
-        profiles.insert(
-            "c3d4e5f6g7h8i9j0".to_string(),
-            CanvasProfile {
-                browser: "Safari".to_string(),
-                version: "17".to_string(),
-                hash: "c3d4e5f6g7h8i9j0".to_string(),
-                weight: 1.0,
-            },
-        );
-
-        profiles.insert(
-            "d4e5f6g7h8i9j0k1".to_string(),
-            CanvasProfile {
-                browser: "Edge".to_string(),
-                version: "120".to_string(),
-                hash: "d4e5f6g7h8i9j0k1".to_string(),
-                weight: 1.0,
-            },
-        );
-    }
-
     /// 添加customconfigurefile
     pub fn add_profile(&mut self, hash: String, browser: String, version: String) {
-        self.profiles.insert(
-            hash.clone(),
-            CanvasProfile {
-                browser,
-                version,
-                hash,
-                weight: 1.0,
-            },
-        );
+        self.insert(CanvasProfile::new(&hash, &browser, &version));
     }
 
     /// getconfigurefilecount
     pub fn profile_count(&self) -> usize {
         self.profiles.len()
     }
+
+    pub fn profiles(&self) -> &[CanvasProfile] {
+        &self.profiles
+    }
 }
 
 impl Default for CanvasProfileLibrary {
@@ -347,6 +481,32 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_canvas_hash_migration() {
+        let analyzer = CanvasAnalyzer::new();
+        let fingerprint = analyzer.analyze("a1b2c3d4e5f6g7h8").unwrap();
+        assert!(fingerprint.hash.starts_with("blake3:"));
+
+        // untagged xxh3 hash written by earlier releases
+        let mut legacy = xxhash_rust::xxh3::Xxh3::new();
+        legacy.update(&16u64.to_be_bytes());
+        legacy.update(b"a1b2c3d4e5f6g7h8");
+        let legacy = format!("{:x}", legacy.digest());
+
+        match analyzer.migrate_hash(&legacy, "a1b2c3d4e5f6g7h8") {
+            HashUpgrade::Upgraded(digest) => assert_eq!(digest.to_string(), fingerprint.hash),
+            other => panic!("expected upgrade, got {:?}", other),
+        }
+        assert_eq!(
+            analyzer.migrate_hash(&fingerprint.hash, "a1b2c3d4e5f6g7h8"),
+            HashUpgrade::Current
+        );
+        assert_eq!(
+            analyzer.migrate_hash(&legacy, "other"),
+            HashUpgrade::Mismatch
+        );
+    }
+
     #[test]
     fn test_invalid_canvas_data() {
         let analyzer = CanvasAnalyzer::new();
@@ -0,0 +1,240 @@
+//! Perceptual hashing of canvas pixel data
+//!
+//! Content hashes change completely when a single antialiased pixel differs, so two
+//! renders of the same canvas on slightly different drivers never match. Perceptual
+//! hashes are computed from the decoded image instead and compared by Hamming
+//! distance:
+//! - dHash: sign of the brightness gradient on a 9x8 thumbnail
+//! - pHash: low-frequency DCT coefficients of a 32x32 thumbnail against their median
+
+use crate::CanvasError;
+use base64::Engine;
+use image::imageops::FilterType;
+use image::GrayImage;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// 64-bit perceptual hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PerceptualHash(pub u64);
+
+impl PerceptualHash {
+    /// Number of differing bits, 0..=64
+    pub fn distance(&self, other: &PerceptualHash) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+
+    /// 1.0 for identical hashes, 0.0 when every bit differs
+    pub fn similarity(&self, other: &PerceptualHash) -> f32 {
+        1.0 - self.distance(other) as f32 / 64.0
+    }
+}
+
+impl fmt::Display for PerceptualHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for PerceptualHash {
+    type Err = CanvasError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(s, 16)
+            .map(PerceptualHash)
+            .map_err(|e| CanvasError::Other(format!("invalid perceptual hash {:?}: {}", s, e)))
+    }
+}
+
+/// Perceptual hash algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PerceptualAlgorithm {
+    /// Gradient hash; cheapest, tolerant to brightness changes
+    DHash,
+    /// DCT hash; most robust to antialiasing and scaling
+    #[default]
+    PHash,
+}
+
+/// Algorithm and match threshold
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PerceptualConfig {
+    pub algorithm: PerceptualAlgorithm,
+    /// Largest Hamming distance still counted as the same render
+    pub max_distance: u32,
+}
+
+impl Default for PerceptualConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: PerceptualAlgorithm::PHash,
+            max_distance: 10,
+        }
+    }
+}
+
+/// Result of comparing two renders
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PerceptualMatch {
+    pub distance: u32,
+    pub similarity: f32,
+    /// `distance <= max_distance`
+    pub matched: bool,
+}
+
+impl PerceptualConfig {
+    /// Hash canvas data as returned by `toDataURL()` (or bare base64)
+    pub fn hash(&self, canvas_data: &str) -> Result<PerceptualHash, CanvasError> {
+        let image = decode_canvas(canvas_data)?;
+        Ok(match self.algorithm {
+            PerceptualAlgorithm::DHash => dhash(&image),
+            PerceptualAlgorithm::PHash => phash(&image),
+        })
+    }
+
+    pub fn compare(&self, a: &PerceptualHash, b: &PerceptualHash) -> PerceptualMatch {
+        let distance = a.distance(b);
+        PerceptualMatch {
+            distance,
+            similarity: a.similarity(b),
+            matched: distance <= self.max_distance,
+        }
+    }
+}
+
+/// Decode a `data:image/...;base64,` URL or bare base64 into grayscale pixels
+pub fn decode_canvas(canvas_data: &str) -> Result<GrayImage, CanvasError> {
+    let payload = match canvas_data.split_once(";base64,") {
+        Some((_, payload)) => payload,
+        None => canvas_data,
+    };
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(payload.trim())
+        .map_err(|_| CanvasError::InvalidCanvasData)?;
+    let image = image::load_from_memory(&bytes).map_err(|_| CanvasError::InvalidCanvasData)?;
+    Ok(image.to_luma8())
+}
+
+/// Gradient hash: each bit says whether a pixel is brighter than its right neighbour
+pub fn dhash(image: &GrayImage) -> PerceptualHash {
+    let small = image::imageops::resize(image, 9, 8, FilterType::Triangle);
+    let mut bits = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            bits = (bits << 1) | (left > right) as u64;
+        }
+    }
+    PerceptualHash(bits)
+}
+
+/// DCT hash: each bit says whether a low-frequency coefficient is above the median
+pub fn phash(image: &GrayImage) -> PerceptualHash {
+    const SIZE: usize = 32;
+    const LOW: usize = 8;
+
+    let small = image::imageops::resize(image, SIZE as u32, SIZE as u32, FilterType::Triangle);
+    let pixels: Vec<f64> = small.pixels().map(|p| p[0] as f64).collect();
+
+    // separable DCT-II, keeping only the LOW x LOW top-left block
+    let basis: Vec<f64> = (0..LOW * SIZE)
+        .map(|i| {
+            let (k, n) = (i / SIZE, i % SIZE);
+            (std::f64::consts::PI / SIZE as f64 * (n as f64 + 0.5) * k as f64).cos()
+        })
+        .collect();
+    let mut rows = vec![0.0; SIZE * LOW];
+    for y in 0..SIZE {
+        for u in 0..LOW {
+            rows[y * LOW + u] = (0..SIZE)
+                .map(|x| pixels[y * SIZE + x] * basis[u * SIZE + x])
+                .sum();
+        }
+    }
+    let mut coefficients = Vec::with_capacity(LOW * LOW);
+    for v in 0..LOW {
+        for u in 0..LOW {
+            coefficients.push(
+                (0..SIZE)
+                    .map(|y| rows[y * LOW + u] * basis[v * SIZE + y])
+                    .sum::<f64>(),
+            );
+        }
+    }
+
+    // the DC term only encodes overall brightness
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted[sorted.len() / 2];
+    let bits = coefficients
+        .iter()
+        .fold(0u64, |bits, &c| (bits << 1) | (c > median) as u64);
+    PerceptualHash(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageOutputFormat, Luma};
+
+    /// Text-like test render, PNG as a data URL
+    fn render(modify: impl Fn(&mut GrayImage)) -> String {
+        let mut image = GrayImage::from_fn(220, 30, |x, y| {
+            let stripe = (x / 11 + y / 6) % 3 == 0;
+            let glyph = (x * 7 + y * 13) % 29 < 9 && y > 4 && y < 26;
+            Luma([if glyph {
+                20
+            } else if stripe {
+                180
+            } else {
+                240
+            }])
+        });
+        modify(&mut image);
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::DynamicImage::ImageLuma8(image)
+            .write_to(&mut png, ImageOutputFormat::Png)
+            .unwrap();
+        format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(png.into_inner())
+        )
+    }
+
+    #[test]
+    fn test_one_pixel_difference_still_matches() {
+        let original = render(|_| {});
+        // one antialiased edge pixel rendered differently
+        let antialiased = render(|image| image.put_pixel(100, 15, Luma([128])));
+        let other = render(image::imageops::flip_horizontal_in_place);
+
+        for algorithm in [PerceptualAlgorithm::DHash, PerceptualAlgorithm::PHash] {
+            let config = PerceptualConfig {
+                algorithm,
+                ..PerceptualConfig::default()
+            };
+            let a = config.hash(&original).unwrap();
+            let b = config.hash(&antialiased).unwrap();
+            let c = config.hash(&other).unwrap();
+
+            let same = config.compare(&a, &b);
+            assert!(same.matched, "{:?}: {:?}", algorithm, same);
+            assert!(same.similarity > 0.9);
+            let different = config.compare(&a, &c);
+            assert!(!different.matched, "{:?}: {:?}", algorithm, different);
+        }
+
+        let strict = PerceptualConfig {
+            max_distance: 0,
+            ..PerceptualConfig::default()
+        };
+        let hash = strict.hash(&original).unwrap();
+        assert!(strict.compare(&hash, &hash).matched);
+        assert_eq!(hash.to_string().parse::<PerceptualHash>().unwrap(), hash);
+        assert!(strict.hash("not-an-image").is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! Filesystem watcher (`watch` feature)
+//!
+//! [`FileWatcher`] is a [`ConfigWatcher`] that fires when one of its files is
+//! written, created, renamed into place or removed. The parent directory is
+//! watched rather than the file itself, so editors and deploy tools that replace
+//! the file atomically keep being seen. Bursts of events are coalesced over
+//! [`FileWatcher::with_debounce`] before the callback runs.
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use fingerprint_config::ConfigManager;
+//! use fingerprint_config::file_source::{ConfigFormat, FileConfigSource};
+//! use fingerprint_config::file_watcher::FileWatcher;
+//!
+//! let manager = Arc::new(ConfigManager::new());
+//! manager.add_source(Box::new(FileConfigSource {
+//!     path: "config/gateway.toml".into(),
+//!     format: ConfigFormat::Toml,
+//!     priority: 50,
+//! }));
+//! manager.add_watcher(Box::new(FileWatcher::new(["config/gateway.toml"])));
+//! manager.load().unwrap();
+//! manager.enable_hot_reload();
+//! ```
+
+use super::ConfigWatcher;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Reloads when watched configuration files change
+pub struct FileWatcher {
+    paths: Vec<PathBuf>,
+    debounce: Duration,
+    /// Live `notify` watchers; dropping one stops its events
+    handles: Mutex<Vec<RecommendedWatcher>>,
+}
+
+impl FileWatcher {
+    /// Watch `paths`, typically the paths of the registered `FileConfigSource`s
+    pub fn new<P: Into<PathBuf>>(paths: impl IntoIterator<Item = P>) -> Self {
+        Self {
+            paths: paths.into_iter().map(Into::into).collect(),
+            debounce: Duration::from_millis(200),
+            handles: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Quiet period after the last event before the callback runs (default 200ms)
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Watched paths
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Absolute form of `path`, so event paths compare equal
+    fn absolute(path: &Path) -> PathBuf {
+        if path.is_absolute() {
+            return path.to_path_buf();
+        }
+        std::env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    }
+}
+
+impl ConfigWatcher for FileWatcher {
+    fn watch(&self, callback: Box<dyn Fn() + Send + Sync>) {
+        let files: HashSet<PathBuf> = self.paths.iter().map(|p| Self::absolute(p)).collect();
+        let dirs: HashSet<PathBuf> = files
+            .iter()
+            .filter_map(|file| file.parent().map(Path::to_path_buf))
+            .collect();
+
+        let (tx, rx) = mpsc::channel::<()>();
+        let watched = files.clone();
+        let handler = move |result: notify::Result<Event>| match result {
+            Ok(event) => {
+                let relevant = matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                ) && event.paths.iter().any(|path| watched.contains(path));
+                if relevant {
+                    let _ = tx.send(());
+                }
+            }
+            Err(e) => log::warn!("Configuration file watcher error: {}", e),
+        };
+        let mut watcher = match notify::recommended_watcher(handler) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                log::error!("Failed to start configuration file watcher: {}", e);
+                return;
+            }
+        };
+        for dir in &dirs {
+            if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                log::error!("Failed to watch {}: {}", dir.display(), e);
+            }
+        }
+        self.handles.lock().push(watcher);
+
+        // Debounce: wait for the first event, then for a quiet period
+        let debounce = self.debounce;
+        thread::spawn(move || {
+            while rx.recv().is_ok() {
+                loop {
+                    match rx.recv_timeout(debounce) {
+                        Ok(()) => continue,
+                        Err(mpsc::RecvTimeoutError::Timeout) => break,
+                        Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                log::info!("Configuration files changed, reloading");
+                callback();
+            }
+        });
+    }
+}
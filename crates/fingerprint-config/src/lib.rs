@@ -8,9 +8,11 @@
 //!
 //! - ✅ **Multi-source Configuration**: File system, environment variables, remote sources
 //! - ✅ **Hot Reload**: Runtime configuration updates without restart
+//! - ✅ **Transactional Reloads**: A reload that fails validation is rolled back
 //! - ✅ **Validation**: Type-safe configuration with validation rules
 //! - ✅ **Hierarchical Structure**: Support for nested configuration sections
 //! - ✅ **Caching**: Efficient configuration access with thread-safe caching
+//! - ✅ **Secrets**: `${env:...}`, `${file:...}` and `${vault:...}` references resolved on read
 //!
 //! ## Configuration Sources Priority
 //!
@@ -18,10 +20,44 @@
 //! 2. **Remote Configuration** (if enabled)
 //! 3. **Local Configuration Files**
 //! 4. **Built-in Defaults** (lowest priority)
+//!
+//! ## Reloads
+//!
+//! [`ConfigManager::reload`] merges every source into a staged snapshot and runs
+//! all validators against it. The snapshot replaces the served configuration in
+//! a single swap only when every validator passes; otherwise the previous
+//! configuration stays in place and a [`ConfigAlert`] listing all validation
+//! errors is sent to the registered [`AlertSink`]s.
+//!
+//! An applied reload that changed any value notifies the [`ChangeSubscriber`]s
+//! registered for those paths with one [`ConfigChange`] per value. With the
+//! `watch` feature, [`file_watcher::FileWatcher`] triggers reloads when
+//! configuration files change on disk.
+//!
+//! ## Typed sections
+//!
+//! [`ConfigManager::section`] deserializes a whole [`ConfigSection`] struct from
+//! its path prefix; [`ConfigManager::watch_section`] keeps an `Arc<T>` snapshot of
+//! it current across reloads. See [`sections`].
+//!
+//! ## Schemas
+//!
+//! With the `json-schema` feature, [`ConfigManager::add_schema`] attaches a JSON
+//! Schema (draft 2020-12) to a whole subtree. Schema violations reject reloads like
+//! any other validator, and [`ConfigManager::validate_all`] reports every issue
+//! of the current sources with its configuration and schema path.
+//!
+//! ## Secrets
+//!
+//! Values may reference secrets instead of containing them, e.g.
+//! `api_key = "${vault:secret/gateway#api_key}"`. References are stored and
+//! validated as written and resolved by the [`SecretResolver`] only when read; see
+//! [`secrets`].
 
+use std::any::TypeId;
 use std::collections::HashMap;
-use std::sync::Arc;
-use dashmap::DashMap;
+use std::sync::{Arc, Weak};
+use std::time::SystemTime;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -40,25 +76,50 @@ pub enum ConfigError {
     ValidationError(String),
     #[error("Remote configuration error: {0}")]
     RemoteError(String),
+    #[error("Secret resolution error: {0}")]
+    SecretError(String),
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
 
 /// Main configuration manager
 pub struct ConfigManager {
-    /// Cached configuration values
-    cache: DashMap<String, serde_json::Value>,
+    /// Configuration values currently served, replaced as a whole on reload
+    cache: RwLock<HashMap<String, serde_json::Value>>,
     
     /// Configuration sources
-    sources: RwLock<Vec<ConfigSource>>,
+    sources: RwLock<Vec<Box<dyn ConfigSource>>>,
     
     /// Validation rules
     validators: RwLock<HashMap<String, Box<dyn Validator>>>,
     
     /// Hot reload watchers
     watchers: RwLock<Vec<Box<dyn ConfigWatcher>>>,
+
+    /// Receivers of reload failure alerts
+    alert_sinks: RwLock<Vec<Box<dyn AlertSink>>>,
+
+    /// Receivers of applied changes, by path prefix
+    subscribers: RwLock<Vec<(String, Box<dyn ChangeSubscriber>)>>,
+
+    /// Registered sections, checked on every reload
+    section_checks: RwLock<Vec<(TypeId, &'static str, SectionCheck)>>,
+
+    /// Snapshot refreshers of watched sections, by prefix
+    section_watchers: RwLock<Vec<(&'static str, SectionRefresh)>>,
+
+    /// JSON Schemas of configuration subtrees, by prefix
+    #[cfg(feature = "json-schema")]
+    schemas: RwLock<Vec<(String, jsonschema::Validator)>>,
+
+    /// Resolver of secret references, applied when values are read
+    secrets: RwLock<Arc<SecretResolver>>,
 }
 
+type Values = HashMap<String, serde_json::Value>;
+type SectionCheck = Box<dyn Fn(&Values) -> Result<(), String> + Send + Sync>;
+type SectionRefresh = Box<dyn Fn(&Values, &SecretResolver) + Send + Sync>;
+
 /// Configuration source trait
 pub trait ConfigSource: Send + Sync {
     fn name(&self) -> &str;
@@ -76,14 +137,105 @@ pub trait ConfigWatcher: Send + Sync {
     fn watch(&self, callback: Box<dyn Fn() + Send + Sync>);
 }
 
+/// Receiver of configuration alerts
+pub trait AlertSink: Send + Sync {
+    fn emit(&self, alert: &ConfigAlert);
+}
+
+/// Alert raised when a reloaded configuration is rejected
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigAlert {
+    /// Human-readable summary
+    pub message: String,
+    /// One entry per failed validator, as `path: reason`
+    pub errors: Vec<String>,
+    /// Time the reload was rejected
+    pub timestamp: SystemTime,
+}
+
+impl<F> AlertSink for F
+where
+    F: Fn(&ConfigAlert) + Send + Sync,
+{
+    fn emit(&self, alert: &ConfigAlert) {
+        self(alert)
+    }
+}
+
+/// One validation failure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    /// Dotted path of the offending value, e.g. `tls.cipher_suites.0`
+    pub path: String,
+    pub message: String,
+    /// JSON pointer into the schema, for schema violations
+    pub schema_path: Option<String>,
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Every validation failure of one configuration snapshot
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationReport {
+    /// Sorted by path
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Issues as `path: message` lines
+    pub fn errors(&self) -> Vec<String> {
+        self.issues.iter().map(|issue| issue.to_string()).collect()
+    }
+}
+
+/// One configuration value changed by an applied reload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    /// Dotted path, e.g. `gateway.rbac.default_role`
+    pub path: String,
+    /// Value before the reload (`None`: newly added)
+    pub old_value: Option<serde_json::Value>,
+    /// Value after the reload (`None`: removed)
+    pub new_value: Option<serde_json::Value>,
+}
+
+/// Receiver of applied configuration changes
+pub trait ChangeSubscriber: Send + Sync {
+    fn on_change(&self, changes: &[ConfigChange]);
+}
+
+impl<F> ChangeSubscriber for F
+where
+    F: Fn(&[ConfigChange]) + Send + Sync,
+{
+    fn on_change(&self, changes: &[ConfigChange]) {
+        self(changes)
+    }
+}
+
 impl ConfigManager {
     /// Create a new configuration manager
     pub fn new() -> Self {
         Self {
-            cache: DashMap::new(),
+            cache: RwLock::new(HashMap::new()),
             sources: RwLock::new(vec![]),
             validators: RwLock::new(HashMap::new()),
             watchers: RwLock::new(vec![]),
+            alert_sinks: RwLock::new(vec![]),
+            subscribers: RwLock::new(vec![]),
+            section_checks: RwLock::new(vec![]),
+            section_watchers: RwLock::new(vec![]),
+            #[cfg(feature = "json-schema")]
+            schemas: RwLock::new(vec![]),
+            secrets: RwLock::new(Arc::new(SecretResolver::with_defaults())),
         }
     }
 
@@ -99,39 +251,222 @@ impl ConfigManager {
         self.validators.write().insert(path, validator);
     }
 
-    /// Load all configuration sources
-    pub fn load(&self) -> Result<(), ConfigError> {
-        let sources = self.sources.read();
-        
-        for source in sources.iter() {
-            match source.load() {
-                Ok(config) => {
-                    self.merge_config(config)?;
+    /// Add a watcher whose change notifications trigger a reload
+    ///
+    /// Takes effect once [`ConfigManager::enable_hot_reload`] is called.
+    pub fn add_watcher(&self, watcher: Box<dyn ConfigWatcher>) {
+        self.watchers.write().push(watcher);
+    }
+
+    /// Add a receiver for alerts about rejected reloads
+    pub fn add_alert_sink(&self, sink: Box<dyn AlertSink>) {
+        self.alert_sinks.write().push(sink);
+    }
+
+    /// Notify `subscriber` of applied changes under `prefix`
+    ///
+    /// `prefix` is a dotted path such as `gateway.rbac`; it matches that path and
+    /// everything below it. An empty prefix matches every path.
+    pub fn subscribe(&self, prefix: impl Into<String>, subscriber: Box<dyn ChangeSubscriber>) {
+        self.subscribers.write().push((prefix.into(), subscriber));
+    }
+
+    /// Deserialize the section `T` from the values under `T::PREFIX`
+    ///
+    /// Secret references in the section are resolved.
+    pub fn section<T: ConfigSection>(&self) -> Result<T, ConfigError> {
+        let raw = sections::unflatten(&self.cache.read(), T::PREFIX);
+        sections::load_resolved(raw, &self.secret_resolver())
+            .map_err(|e| ConfigError::ValidationError(format!("{}: {}", T::PREFIX, e)))
+    }
+
+    /// Make `T` part of every reload's validation
+    ///
+    /// A reload whose values under `T::PREFIX` do not deserialize into `T`, or fail
+    /// [`ConfigSection::validate`], is rejected like any other validation failure.
+    pub fn register_section<T: ConfigSection>(&self) {
+        let mut checks = self.section_checks.write();
+        if checks.iter().any(|(id, _, _)| *id == TypeId::of::<T>()) {
+            return;
+        }
+        checks.push((
+            TypeId::of::<T>(),
+            T::PREFIX,
+            Box::new(|values: &Values| sections::load::<T>(values).map(|_| ())),
+        ));
+    }
+
+    /// Register `T` and keep a snapshot of it current across reloads
+    ///
+    /// The snapshot is replaced after each applied reload that changed a value
+    /// under `T::PREFIX`, before change subscribers are notified. Values written
+    /// with [`ConfigManager::set`] show up at the next reload.
+    pub fn watch_section<T: ConfigSection>(&self) -> Result<SectionHandle<T>, ConfigError> {
+        self.register_section::<T>();
+        let handle = SectionHandle::new(self.section::<T>()?);
+        let target = handle.clone();
+        self.section_watchers.write().push((
+            T::PREFIX,
+            Box::new(move |values: &Values, secrets: &SecretResolver| {
+                match sections::load_resolved::<T>(sections::unflatten(values, T::PREFIX), secrets) {
+                Ok(section) => target.replace(section),
+                    Err(e) => log::error!("Section {} not refreshed: {}", T::PREFIX, e),
                 }
-                Err(e) => {
-                    log::warn!("Failed to load config source {}: {}", source.name(), e);
+            }),
+        ));
+        Ok(handle)
+    }
+
+    /// Validate the subtree under `prefix` against a JSON Schema (draft 2020-12)
+    ///
+    /// An empty prefix validates the whole configuration. The schema takes part in
+    /// every reload; an invalid schema is rejected here.
+    #[cfg(feature = "json-schema")]
+    pub fn add_schema(&self, prefix: impl Into<String>, schema: &serde_json::Value) -> Result<(), ConfigError> {
+        let prefix = prefix.into();
+        let validator = jsonschema::draft202012::new(schema)
+            .map_err(|e| ConfigError::ValidationError(format!("Invalid schema for {}: {}", prefix, e)))?;
+        self.schemas.write().push((prefix, validator));
+        Ok(())
+    }
+
+    /// Run every validator against the current sources without applying them
+    ///
+    /// Unlike a rejected reload's error, which is a single string, the report keeps
+    /// each issue separate, with its full path.
+    pub fn validate_all(&self) -> ValidationReport {
+        self.check(&self.stage())
+    }
+
+    /// Resolver used for secret references
+    pub fn secret_resolver(&self) -> Arc<SecretResolver> {
+        self.secrets.read().clone()
+    }
+
+    /// Replace the secret resolver, e.g. one with a different TTL or backends
+    pub fn set_secret_resolver(&self, resolver: SecretResolver) {
+        *self.secrets.write() = Arc::new(resolver);
+    }
+
+    /// Register a secret backend with the current resolver
+    pub fn add_secret_backend(&self, backend: Box<dyn SecretBackend>) {
+        self.secrets.read().add_backend(backend);
+    }
+
+    /// Reload on every change reported by the registered watchers
+    ///
+    /// Callbacks hold a weak reference, so watchers do not keep the manager alive.
+    pub fn enable_hot_reload(self: &Arc<Self>) {
+        for watcher in self.watchers.read().iter() {
+            let manager: Weak<Self> = Arc::downgrade(self);
+            watcher.watch(Box::new(move || {
+                if let Some(manager) = manager.upgrade() {
+                    // Failures are logged and alerted inside reload()
+                    let _ = manager.reload();
                 }
-            }
+            }));
+        }
+    }
+
+    /// Load all configuration sources
+    ///
+    /// Same as [`ConfigManager::reload`].
+    pub fn load(&self) -> Result<(), ConfigError> {
+        self.reload()
+    }
+
+    /// Rebuild the configuration from all sources, transactionally
+    ///
+    /// The sources are merged into a staged snapshot, lowest priority first so
+    /// higher priorities win, and every validator runs against it. Only when all
+    /// of them pass does the snapshot replace the served configuration, in one
+    /// swap; readers never see a partially merged state. On failure the current
+    /// configuration keeps being served, a [`ConfigAlert`] is emitted and all
+    /// validation errors are returned. Values written with
+    /// [`ConfigManager::set`] are not carried over a successful reload. After a
+    /// swap that changed any value, matching subscribers receive the changes.
+    /// Cached secrets are dropped by every applied reload, so rotated secrets can
+    /// be picked up without waiting for their TTL.
+    pub fn reload(&self) -> Result<(), ConfigError> {
+        let staged = self.stage();
+        let errors = self.check(&staged).errors();
+        if !errors.is_empty() {
+            log::error!(
+                "Rejected configuration reload, keeping previous configuration: {}",
+                errors.join("; ")
+            );
+            self.emit_alert(ConfigAlert {
+                message: format!(
+                    "Configuration reload rejected, {} validation error(s); previous configuration kept",
+                    errors.len()
+                ),
+                errors: errors.clone(),
+                timestamp: SystemTime::now(),
+            });
+            return Err(ConfigError::ValidationError(errors.join("; ")));
+        }
+
+        let changes = {
+            let mut cache = self.cache.write();
+            let changes = Self::diff(&cache, &staged);
+            *cache = staged;
+            changes
+        };
+        self.secret_resolver().invalidate_all();
+        if !changes.is_empty() {
+            log::info!("Configuration reloaded, {} value(s) changed", changes.len());
+            self.refresh_sections(&changes);
+            self.notify(&changes);
         }
-        
-        self.validate()?;
         Ok(())
     }
 
     /// Get a configuration value by path
+    ///
+    /// Secret references in the value are resolved; deserialization errors
+    /// describe the value as written, never the resolved secret.
     pub fn get<T>(&self, path: &str) -> Result<T, ConfigError>
     where
         T: for<'de> Deserialize<'de>,
     {
-        if let Some(value) = self.cache.get(path) {
-            serde_json::from_value(value.clone())
-                .map_err(|e| ConfigError::ParseError(format!("Failed to deserialize {}: {}", path, e)))
-        } else {
-            Err(ConfigError::FileNotFound(path.to_string()))
-        }
+        let raw = self
+            .cache
+            .read()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| ConfigError::FileNotFound(path.to_string()))?;
+        let resolved = self.secret_resolver().resolve_value(&raw)?;
+        let redacted = resolved != raw;
+        serde_json::from_value(resolved).map_err(|e| {
+            let detail = if redacted {
+                serde_json::from_value::<T>(raw)
+                    .err()
+                    .map_or_else(|| "resolved secret has the wrong type".to_string(), |e| e.to_string())
+            } else {
+                e.to_string()
+            };
+            ConfigError::ParseError(format!("Failed to deserialize {}: {}", path, detail))
+        })
+    }
+
+    /// Get a string value by path as a [`SecretString`], resolving its references
+    pub fn get_secret(&self, path: &str) -> Result<SecretString, ConfigError> {
+        let raw: String = self
+            .cache
+            .read()
+            .get(path)
+            .ok_or_else(|| ConfigError::FileNotFound(path.to_string()))?
+            .as_str()
+            .ok_or_else(|| ConfigError::ParseError(format!("{} is not a string", path)))?
+            .to_string();
+        Ok(SecretString::from(
+            self.secret_resolver().resolve_str(&raw)?.unwrap_or(raw),
+        ))
     }
 
     /// Set a configuration value
+    ///
+    /// The value is validated first and left unset if it is rejected.
     pub fn set<T>(&self, path: &str, value: T) -> Result<(), ConfigError>
     where
         T: Serialize,
@@ -139,19 +474,13 @@ impl ConfigManager {
         let json_value = serde_json::to_value(value)
             .map_err(|e| ConfigError::ParseError(format!("Failed to serialize {}: {}", path, e)))?;
         
-        self.cache.insert(path.to_string(), json_value);
-        self.validate_path(path)?;
-        Ok(())
-    }
-
-    /// Merge configuration from a JSON value
-    fn merge_config(&self, config: serde_json::Value) -> Result<(), ConfigError> {
-        self.flatten_and_cache("", &config);
+        self.validate_path(path, &json_value)?;
+        self.cache.write().insert(path.to_string(), json_value);
         Ok(())
     }
 
-    /// Flatten nested configuration and cache individual values
-    fn flatten_and_cache(&self, prefix: &str, value: &serde_json::Value) {
+    /// Flatten nested configuration into individual values
+    fn flatten_into(target: &mut HashMap<String, serde_json::Value>, prefix: &str, value: &serde_json::Value) {
         match value {
             serde_json::Value::Object(map) => {
                 for (key, val) in map {
@@ -160,40 +489,137 @@ impl ConfigManager {
                     } else {
                         format!("{}.{}", prefix, key)
                     };
-                    self.flatten_and_cache(&new_prefix, val);
+                    Self::flatten_into(target, &new_prefix, val);
                 }
             }
             _ => {
-                self.cache.insert(prefix.to_string(), value.clone());
+                target.insert(prefix.to_string(), value.clone());
             }
         }
     }
 
-    /// Validate all configuration values
-    fn validate(&self) -> Result<(), ConfigError> {
-        let validators = self.validators.read();
-        
-        for (path, validator) in validators.iter() {
-            if let Some(value) = self.cache.get(path) {
-                validator.validate(&value)?;
+    /// Run every validator against `values`, collecting all failures
+    fn check(&self, values: &Values) -> ValidationReport {
+        let issue = |path: &str, message: String| ValidationIssue {
+            path: path.to_string(),
+            message,
+            schema_path: None,
+        };
+        let mut issues: Vec<ValidationIssue> = self
+            .validators
+            .read()
+            .iter()
+            .filter_map(|(path, validator)| {
+                let value = values.get(path)?;
+                validator.validate(value).err().map(|e| issue(path, e.to_string()))
+            })
+            .collect();
+        issues.extend(self.section_checks.read().iter().filter_map(|(_, prefix, check)| {
+            check(values).err().map(|e| issue(prefix, e))
+        }));
+        #[cfg(feature = "json-schema")]
+        for (prefix, schema) in self.schemas.read().iter() {
+            let instance = sections::unflatten(values, prefix);
+            issues.extend(schema.iter_errors(&instance).map(|e| {
+                let pointer = e.instance_path.to_string();
+                let relative = pointer.trim_start_matches('/').replace('/', ".");
+                let path = match (prefix.is_empty(), relative.is_empty()) {
+                    (true, _) => relative,
+                    (false, true) => prefix.clone(),
+                    (false, false) => format!("{}.{}", prefix, relative),
+                };
+                ValidationIssue {
+                    path,
+                    message: e.to_string(),
+                    schema_path: Some(e.schema_path.to_string()),
+                }
+            }));
+        }
+        issues.sort_by(|a, b| (&a.path, &a.message).cmp(&(&b.path, &b.message)));
+        ValidationReport { issues }
+    }
+
+    /// Merge all sources, lowest priority first so higher priorities win
+    fn stage(&self) -> Values {
+        let mut staged = HashMap::new();
+        for source in self.sources.read().iter().rev() {
+            match source.load() {
+                Ok(config) => Self::flatten_into(&mut staged, "", &config),
+                Err(e) => {
+                    log::warn!("Failed to load config source {}: {}", source.name(), e);
+                }
             }
         }
-        
-        Ok(())
+        staged
     }
 
-    /// Validate a specific configuration path
-    fn validate_path(&self, path: &str) -> Result<(), ConfigError> {
+    /// Validate a value for a specific configuration path
+    fn validate_path(&self, path: &str, value: &serde_json::Value) -> Result<(), ConfigError> {
         let validators = self.validators.read();
         
         if let Some(validator) = validators.get(path) {
-            if let Some(value) = self.cache.get(path) {
-                validator.validate(&value)?;
-            }
+            validator.validate(value)?;
         }
         
         Ok(())
     }
+
+    /// Values that differ between `old` and `new`, sorted by path
+    fn diff(
+        old: &HashMap<String, serde_json::Value>,
+        new: &HashMap<String, serde_json::Value>,
+    ) -> Vec<ConfigChange> {
+        let mut changes: Vec<ConfigChange> = new
+            .iter()
+            .filter(|(path, value)| old.get(*path) != Some(*value))
+            .map(|(path, value)| ConfigChange {
+                path: path.clone(),
+                old_value: old.get(path).cloned(),
+                new_value: Some(value.clone()),
+            })
+            .chain(old.iter().filter(|(path, _)| !new.contains_key(*path)).map(|(path, value)| {
+                ConfigChange {
+                    path: path.clone(),
+                    old_value: Some(value.clone()),
+                    new_value: None,
+                }
+            }))
+            .collect();
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        changes
+    }
+
+    /// Replace the snapshots of watched sections touched by `changes`
+    fn refresh_sections(&self, changes: &[ConfigChange]) {
+        let cache = self.cache.read();
+        let secrets = self.secret_resolver();
+        for (prefix, refresh) in self.section_watchers.read().iter() {
+            if changes.iter().any(|change| sections::matches_prefix(&change.path, prefix)) {
+                refresh(&cache, &secrets);
+            }
+        }
+    }
+
+    /// Hand each subscriber the changes under its prefix
+    fn notify(&self, changes: &[ConfigChange]) {
+        for (prefix, subscriber) in self.subscribers.read().iter() {
+            let matching: Vec<ConfigChange> = changes
+                .iter()
+                .filter(|change| sections::matches_prefix(&change.path, prefix))
+                .cloned()
+                .collect();
+            if !matching.is_empty() {
+                subscriber.on_change(&matching);
+            }
+        }
+    }
+
+    /// Send an alert to every registered sink
+    fn emit_alert(&self, alert: ConfigAlert) {
+        for sink in self.alert_sinks.read().iter() {
+            sink.emit(&alert);
+        }
+    }
 }
 
 impl Default for ConfigManager {
@@ -230,14 +656,15 @@ pub mod file_source {
             let content = fs::read_to_string(&self.path)?;
             
             let value = match self.format {
-                ConfigFormat::Json => serde_json::from_str(&content)?,
+                ConfigFormat::Json => serde_json::from_str(&content).map_err(parse_error)?,
                 ConfigFormat::Toml => {
-                    let toml_value: toml::Value = toml::from_str(&content)?;
-                    serde_json::to_value(toml_value)?
+                    let toml_value: toml::Value = toml::from_str(&content).map_err(parse_error)?;
+                    serde_json::to_value(toml_value).map_err(parse_error)?
                 }
                 ConfigFormat::Yaml => {
-                    let yaml_value: serde_yaml::Value = serde_yaml::from_str(&content)?;
-                    serde_json::to_value(yaml_value)?
+                    let yaml_value: serde_yaml::Value =
+                        serde_yaml::from_str(&content).map_err(parse_error)?;
+                    serde_json::to_value(yaml_value).map_err(parse_error)?
                 }
             };
             
@@ -248,8 +675,24 @@ pub mod file_source {
             self.priority
         }
     }
+
+    fn parse_error(e: impl std::fmt::Display) -> ConfigError {
+        ConfigError::ParseError(e.to_string())
+    }
 }
 
+#[cfg(feature = "watch")]
+pub mod file_watcher;
+
+pub mod secrets;
+pub mod sections;
+
+pub use secrets::{SecretBackend, SecretResolver, SecretString};
+
+pub use sections::{
+    ConfigSection, CoreSection, DefenseSection, HttpSection, SectionHandle, TlsSection,
+};
+
 // Environment variable configuration source
 #[cfg(feature = "environment")]
 pub mod env_source {
@@ -406,6 +849,37 @@ pub fn get_config_manager() -> Arc<ConfigManager> {
                 "enable_learning": true,
                 "anomaly_threshold": 0.8,
                 "block_suspicious": false
+            },
+            "gateway": {
+                "rbac": {
+                    "owner_roles": {},
+                    "tier_roles": { "Enterprise": "admin", "Partner": "admin" },
+                    "default_role": "viewer",
+                    "jwt_issuer": null,
+                    "role_claim": "roles",
+                    "claim_roles": {},
+                    "audit_capacity": 1000
+                }
+            },
+            "ml": {
+                "ensemble": {
+                    "weights": { "baseline": 0.2, "isolation_forest": 0.5, "one_class_svm": 0.3 },
+                    "isolation_forest": { "trees": 100, "sample_size": 256, "seed": 42 },
+                    "one_class_svm": { "nu": 0.1, "epochs": 500, "learning_rate": 0.5 },
+                    "online": {
+                        "decay": 0.9, "min_samples": 64, "window": 1024, "svm_epochs": 50,
+                        "pseudo_label_threshold": 0.1, "normal_labels": ["normal", "benign", "human"]
+                    }
+                },
+                "monitor": {
+                    "reference_size": 1000, "window_size": 500, "bins": 10, "psi_threshold": 0.25,
+                    "feedback_window": 1000, "min_feedback": 50, "min_precision": 0.8, "min_recall": 0.7
+                }
+            },
+            "analysis": {
+                "ml": {
+                    "models": []
+                }
             }
         });
         
@@ -424,6 +898,28 @@ pub fn get_config_manager() -> Arc<ConfigManager> {
                              }));
         manager.add_validator("defense.anomaly_threshold".to_string(),
                              Box::new(validators::RangeValidator { min: Some(0.0), max: Some(1.0) }));
+        manager.add_validator("gateway.rbac.default_role".to_string(),
+                             Box::new(validators::EnumValidator {
+                                 allowed_values: vec!["viewer".to_string(), "analyst".to_string(), "admin".to_string()]
+                             }));
+        manager.add_validator("gateway.rbac.audit_capacity".to_string(),
+                             Box::new(validators::RangeValidator { min: Some(1.0), max: Some(1_000_000.0) }));
+        for model in ["baseline", "isolation_forest", "one_class_svm"] {
+            manager.add_validator(format!("ml.ensemble.weights.{}", model),
+                                 Box::new(validators::RangeValidator { min: Some(0.0), max: None }));
+        }
+        manager.add_validator("ml.ensemble.one_class_svm.nu".to_string(),
+                             Box::new(validators::RangeValidator { min: Some(0.001), max: Some(1.0) }));
+        manager.add_validator("ml.ensemble.online.decay".to_string(),
+                             Box::new(validators::RangeValidator { min: Some(0.0), max: Some(0.999) }));
+        for metric in ["min_precision", "min_recall"] {
+            manager.add_validator(format!("ml.monitor.{}", metric),
+                                 Box::new(validators::RangeValidator { min: Some(0.0), max: Some(1.0) }));
+        }
+        manager.register_section::<CoreSection>();
+        manager.register_section::<TlsSection>();
+        manager.register_section::<HttpSection>();
+        manager.register_section::<DefenseSection>();
         
         Arc::new(manager)
     }).clone()
@@ -436,7 +932,183 @@ mod tests {
     #[test]
     fn test_config_manager_creation() {
         let manager = ConfigManager::new();
-        assert_eq!(manager.cache.len(), 0);
+        assert_eq!(manager.cache.read().len(), 0);
+    }
+    
+    #[test]
+    fn test_failed_reload_keeps_previous_config() {
+        let manager = ConfigManager::new();
+        manager.add_validator("core.max_connections".to_string(),
+                             Box::new(validators::RangeValidator { min: Some(1.0), max: Some(10000.0) }));
+        manager.add_validator("core.log_level".to_string(),
+                             Box::new(validators::EnumValidator { allowed_values: vec!["info".to_string()] }));
+        let alerts = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let sink = alerts.clone();
+        manager.add_alert_sink(Box::new(move |alert: &ConfigAlert| sink.lock().push(alert.clone())));
+        
+        let source = Arc::new(RwLock::new(serde_json::json!({
+            "core": { "max_connections": 100, "log_level": "info" }
+        })));
+        struct SharedSource(Arc<RwLock<serde_json::Value>>);
+        impl ConfigSource for SharedSource {
+            fn name(&self) -> &str { "shared" }
+            fn load(&self) -> Result<serde_json::Value, ConfigError> { Ok(self.0.read().clone()) }
+            fn priority(&self) -> u32 { 10 }
+        }
+        manager.add_source(Box::new(SharedSource(source.clone())));
+        manager.reload().unwrap();
+        
+        // Both values invalid: nothing is swapped and both errors are reported
+        *source.write() = serde_json::json!({
+            "core": { "max_connections": 0, "log_level": "verbose" }
+        });
+        assert!(manager.reload().is_err());
+        assert_eq!(manager.get::<u32>("core.max_connections").unwrap(), 100);
+        assert_eq!(manager.get::<String>("core.log_level").unwrap(), "info");
+        let received = alerts.lock();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].errors.len(), 2);
+        assert!(received[0].errors[0].starts_with("core.log_level: "));
+        drop(received);
+        
+        *source.write() = serde_json::json!({
+            "core": { "max_connections": 200, "log_level": "info" }
+        });
+        manager.reload().unwrap();
+        assert_eq!(manager.get::<u32>("core.max_connections").unwrap(), 200);
+        assert_eq!(alerts.lock().len(), 1);
+    }
+    
+    #[test]
+    fn test_reload_notifies_subscribers() {
+        let manager = ConfigManager::new();
+        let source = Arc::new(RwLock::new(serde_json::json!({
+            "gateway": { "rbac": { "default_role": "viewer" }, "port": 8080 },
+            "core": { "log_level": "info" }
+        })));
+        struct SharedSource(Arc<RwLock<serde_json::Value>>);
+        impl ConfigSource for SharedSource {
+            fn name(&self) -> &str { "shared" }
+            fn load(&self) -> Result<serde_json::Value, ConfigError> { Ok(self.0.read().clone()) }
+            fn priority(&self) -> u32 { 10 }
+        }
+        manager.add_source(Box::new(SharedSource(source.clone())));
+        manager.reload().unwrap();
+        
+        let received = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let sink = received.clone();
+        manager.subscribe("gateway", Box::new(move |changes: &[ConfigChange]| sink.lock().extend_from_slice(changes)));
+        
+        // Unchanged reload and changes outside the prefix are not delivered
+        manager.reload().unwrap();
+        *source.write() = serde_json::json!({
+            "gateway": { "rbac": { "default_role": "operator" }, "port": 8080 },
+            "gatewayx": { "port": 1 },
+            "core": { "log_level": "debug" }
+        });
+        manager.reload().unwrap();
+        assert_eq!(*received.lock(), vec![ConfigChange {
+            path: "gateway.rbac.default_role".to_string(),
+            old_value: Some(serde_json::json!("viewer")),
+            new_value: Some(serde_json::json!("operator")),
+        }]);
+        
+        received.lock().clear();
+        *source.write() = serde_json::json!({ "gateway": { "rbac": { "default_role": "operator" } } });
+        manager.reload().unwrap();
+        let removed = received.lock();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].path, "gateway.port");
+        assert_eq!(removed[0].new_value, None);
+    }
+    
+    #[test]
+    fn test_watched_section_snapshots() {
+        let manager = ConfigManager::new();
+        let source = Arc::new(RwLock::new(serde_json::json!({
+            "tls": { "min_version": "TLSv1_2", "cipher_suites": ["TLS_AES_128_GCM_SHA256"], "enable_ja4_plus": true },
+            "http": { "user_agent": "a", "max_redirects": 5, "enable_quic": true }
+        })));
+        struct SharedSource(Arc<RwLock<serde_json::Value>>);
+        impl ConfigSource for SharedSource {
+            fn name(&self) -> &str { "shared" }
+            fn load(&self) -> Result<serde_json::Value, ConfigError> { Ok(self.0.read().clone()) }
+            fn priority(&self) -> u32 { 10 }
+        }
+        manager.add_source(Box::new(SharedSource(source.clone())));
+        manager.reload().unwrap();
+        
+        let tls = manager.watch_section::<TlsSection>().unwrap();
+        let http = manager.watch_section::<HttpSection>().unwrap();
+        let before = tls.get();
+        assert_eq!(before.cipher_suites, vec!["TLS_AES_128_GCM_SHA256".to_string()]);
+        
+        // whole-section swap; readers holding the old Arc keep their view
+        source.write()["tls"] = serde_json::json!({
+            "min_version": "TLSv1_3", "cipher_suites": ["TLS_AES_256_GCM_SHA384"], "enable_ja4_plus": false
+        });
+        manager.reload().unwrap();
+        assert_eq!(tls.get().min_version, "TLSv1_3");
+        assert!(!tls.get().enable_ja4_plus);
+        assert_eq!(before.min_version, "TLSv1_2");
+        assert_eq!(http.get().max_redirects, 5);
+        
+        // section-level validation rejects the reload as a whole
+        source.write()["tls"]["cipher_suites"] = serde_json::json!([]);
+        source.write()["http"]["max_redirects"] = serde_json::json!(9);
+        let err = manager.reload().unwrap_err().to_string();
+        assert!(err.contains("tls: cipher_suites must not be empty"), "{}", err);
+        assert_eq!(tls.get().cipher_suites.len(), 1);
+        assert_eq!(http.get().max_redirects, 5);
+        assert_eq!(manager.section::<HttpSection>().unwrap().max_redirects, 5);
+    }
+    
+    #[cfg(feature = "json-schema")]
+    #[test]
+    fn test_schema_validation_report() {
+        let manager = ConfigManager::new();
+        manager.add_validator("core.max_connections".to_string(),
+                             Box::new(validators::RangeValidator { min: Some(1.0), max: Some(10000.0) }));
+        manager.add_schema("tls", &serde_json::json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "required": ["min_version", "cipher_suites"],
+            "properties": {
+                "min_version": { "enum": ["TLSv1_2", "TLSv1_3"] },
+                "cipher_suites": { "type": "array", "minItems": 1, "items": { "type": "string", "pattern": "^TLS_" } }
+            }
+        })).unwrap();
+        assert!(manager.add_schema("http", &serde_json::json!({ "type": 12 })).is_err());
+        
+        manager.add_source(Box::new(defaults::DefaultConfigSource {
+            config: serde_json::json!({
+                "core": { "max_connections": 0 },
+                "tls": { "min_version": "SSLv3", "cipher_suites": ["TLS_AES_128_GCM_SHA256", "RC4"] }
+            }),
+            priority: 0,
+        }));
+        
+        // every issue is reported, with config and schema paths
+        let report = manager.validate_all();
+        let paths: Vec<&str> = report.issues.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(paths, ["core.max_connections", "tls.cipher_suites.1", "tls.min_version"]);
+        assert_eq!(report.issues[1].schema_path.as_deref(), Some("/properties/cipher_suites/items/pattern"));
+        assert!(report.issues[0].schema_path.is_none());
+        
+        // and the reload is rejected with all of them
+        let err = manager.reload().unwrap_err().to_string();
+        assert!(err.contains("tls.min_version: "), "{}", err);
+        assert!(manager.cache.read().is_empty());
+    }
+    
+    #[test]
+    fn test_rejected_set_is_not_applied() {
+        let manager = ConfigManager::new();
+        manager.add_validator("defense.anomaly_threshold".to_string(),
+                             Box::new(validators::RangeValidator { min: Some(0.0), max: Some(1.0) }));
+        manager.set("defense.anomaly_threshold", 0.5).unwrap();
+        assert!(manager.set("defense.anomaly_threshold", 2.0).is_err());
+        assert_eq!(manager.get::<f64>("defense.anomaly_threshold").unwrap(), 0.5);
     }
     
     #[tokio::test]
@@ -449,6 +1121,10 @@ mod tests {
         
         let max_conn: u32 = manager.get("core.max_connections").unwrap();
         assert_eq!(max_conn, 1000);
+        assert_eq!(manager.section::<HttpSection>().unwrap().max_redirects, 5);
+        assert_eq!(manager.get::<f64>("ml.ensemble.weights.isolation_forest").unwrap(), 0.5);
+        assert_eq!(manager.get::<u64>("ml.ensemble.online.min_samples").unwrap(), 64);
+        assert_eq!(manager.get::<f64>("ml.monitor.psi_threshold").unwrap(), 0.25);
     }
     
     #[test]
@@ -479,4 +1155,31 @@ mod tests {
         assert!(validator.validate(&serde_json::Value::String("warning".to_string())).is_err());
         assert!(validator.validate(&serde_json::Value::Number(123.into())).is_err());
     }
+    
+    #[test]
+    fn test_secret_references_resolved_on_read() {
+        std::env::set_var("FP_CONFIG_TEST_TOKEN", "s3cr3t");
+        let manager = ConfigManager::new();
+        manager.add_source(Box::new(defaults::DefaultConfigSource {
+            config: serde_json::json!({
+                "gateway": { "token": "${env:FP_CONFIG_TEST_TOKEN}", "retries": "${env:FP_CONFIG_TEST_TOKEN}" },
+                "http": { "user_agent": "fp/${env:FP_CONFIG_TEST_TOKEN}", "max_redirects": 5, "enable_quic": true }
+            }),
+            priority: 0,
+        }));
+        manager.reload().unwrap();
+        
+        // Stored as written, resolved on read
+        assert_eq!(manager.cache.read()["gateway.token"], serde_json::json!("${env:FP_CONFIG_TEST_TOKEN}"));
+        assert_eq!(manager.get::<String>("gateway.token").unwrap(), "s3cr3t");
+        assert_eq!(manager.section::<HttpSection>().unwrap().user_agent, "fp/s3cr3t");
+        
+        let secret = manager.get_secret("gateway.token").unwrap();
+        assert_eq!(secret.expose(), "s3cr3t");
+        assert!(!format!("{:?}", secret).contains("s3cr3t"));
+        
+        // Type errors quote the reference, not the secret
+        let error = manager.get::<u32>("gateway.retries").unwrap_err().to_string();
+        assert!(error.contains("FP_CONFIG_TEST_TOKEN") && !error.contains("s3cr3t"), "{}", error);
+    }
 }
\ No newline at end of file
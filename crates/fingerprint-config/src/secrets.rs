@@ -0,0 +1,574 @@
+//! Secret references in configuration values
+//!
+//! A string value may hold `${scheme:reference}` placeholders, e.g.
+//! `${env:GATEWAY_TOKEN}`, `${file:/run/secrets/clickhouse}` or, with the `vault`
+//! feature, `${vault:secret/api-key#value}`. The configuration keeps the references
+//! as written: validators, change events and logs never see the secret. They are
+//! resolved by the [`SecretResolver`] when a value is read with
+//! [`ConfigManager::get`](crate::ConfigManager::get),
+//! [`ConfigManager::get_secret`](crate::ConfigManager::get_secret) or
+//! [`ConfigManager::section`](crate::ConfigManager::section).
+//!
+//! Resolved secrets are cached per reference for the resolver's TTL, or for the
+//! backend's lease when that is shorter, so a rotated secret is picked up once the
+//! entry expires. If refetching fails, the expired value keeps being served for a
+//! grace period, so a short backend outage during rotation does not break readers.
+//! Placeholders whose scheme has no registered backend are left as written.
+
+use super::ConfigError;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// String whose value is kept out of `Debug` and `Display` output
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// The secret itself
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString([REDACTED])")
+    }
+}
+
+impl fmt::Display for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+/// Secret fetched by a backend
+#[derive(Debug, Clone)]
+pub struct Secret {
+    pub value: SecretString,
+    /// How long the backend allows caching it (e.g. a Vault lease)
+    pub ttl: Option<Duration>,
+    /// Backend version, used to log rotations
+    pub version: Option<String>,
+}
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: SecretString::new(value),
+            ttl: None,
+            version: None,
+        }
+    }
+}
+
+/// Source of secrets for one placeholder scheme
+pub trait SecretBackend: Send + Sync {
+    /// Scheme handled, the part before `:` in `${scheme:reference}`
+    fn scheme(&self) -> &str;
+    fn fetch(&self, reference: &str) -> Result<Secret, ConfigError>;
+}
+
+/// `${env:NAME}`: environment variable `NAME`
+pub struct EnvSecretBackend;
+
+impl SecretBackend for EnvSecretBackend {
+    fn scheme(&self) -> &str {
+        "env"
+    }
+
+    fn fetch(&self, reference: &str) -> Result<Secret, ConfigError> {
+        std::env::var(reference)
+            .map(Secret::new)
+            .map_err(|e| ConfigError::SecretError(format!("env:{}: {}", reference, e)))
+    }
+}
+
+/// `${file:path}`: contents of a file, without the trailing newline
+///
+/// With a root (e.g. `/run/secrets`), references are relative to it and may not
+/// leave it.
+#[derive(Default)]
+pub struct FileSecretBackend {
+    pub root: Option<PathBuf>,
+}
+
+impl FileSecretBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolve references below `root` only
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: Some(root.into()),
+        }
+    }
+}
+
+impl SecretBackend for FileSecretBackend {
+    fn scheme(&self) -> &str {
+        "file"
+    }
+
+    fn fetch(&self, reference: &str) -> Result<Secret, ConfigError> {
+        let error =
+            |message: String| ConfigError::SecretError(format!("file:{}: {}", reference, message));
+        let path = match &self.root {
+            Some(root) => {
+                let relative = Path::new(reference);
+                if !relative
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)))
+                {
+                    return Err(error(
+                        "must be a relative path below the secrets root".to_string(),
+                    ));
+                }
+                root.join(relative)
+            }
+            None => PathBuf::from(reference),
+        };
+        let content = std::fs::read_to_string(&path).map_err(|e| error(e.to_string()))?;
+        Ok(Secret::new(content.trim_end_matches(['\r', '\n'])))
+    }
+}
+
+#[cfg(feature = "vault")]
+pub use vault::VaultSecretBackend;
+
+#[cfg(feature = "vault")]
+mod vault {
+    use super::{Secret, SecretBackend, SecretString};
+    use crate::ConfigError;
+    use std::time::Duration;
+
+    /// `${vault:mount/path#field}`: field of a HashiCorp Vault KV secret (`vault` feature)
+    ///
+    /// The field defaults to `value`. KV version 2 is assumed unless
+    /// [`VaultSecretBackend::with_kv_version`] says otherwise; the secret's lease,
+    /// when Vault reports one, bounds how long it is cached.
+    pub struct VaultSecretBackend {
+        address: String,
+        token: SecretString,
+        namespace: Option<String>,
+        kv_version: u8,
+        agent: ureq::Agent,
+    }
+
+    impl VaultSecretBackend {
+        pub fn new(address: impl Into<String>, token: impl Into<String>) -> Self {
+            Self {
+                address: address.into().trim_end_matches('/').to_string(),
+                token: SecretString::new(token),
+                namespace: None,
+                kv_version: 2,
+                agent: ureq::AgentBuilder::new()
+                    .timeout(Duration::from_secs(10))
+                    .build(),
+            }
+        }
+
+        /// Backend configured from `VAULT_ADDR`, `VAULT_TOKEN` and `VAULT_NAMESPACE`
+        pub fn from_env() -> Result<Self, ConfigError> {
+            let var = |name: &str| {
+                std::env::var(name).map_err(|e| ConfigError::EnvError(format!("{}: {}", name, e)))
+            };
+            let mut backend = Self::new(var("VAULT_ADDR")?, var("VAULT_TOKEN")?);
+            backend.namespace = std::env::var("VAULT_NAMESPACE").ok();
+            Ok(backend)
+        }
+
+        /// KV secrets engine version of the mounts read (1 or 2, default 2)
+        pub fn with_kv_version(mut self, version: u8) -> Self {
+            self.kv_version = version;
+            self
+        }
+
+        /// Vault Enterprise namespace
+        pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+            self.namespace = Some(namespace.into());
+            self
+        }
+
+        fn url(&self, path: &str) -> Result<String, String> {
+            let (mount, rest) = path
+                .split_once('/')
+                .ok_or_else(|| "expected mount/path".to_string())?;
+            Ok(match self.kv_version {
+                1 => format!("{}/v1/{}/{}", self.address, mount, rest),
+                _ => format!("{}/v1/{}/data/{}", self.address, mount, rest),
+            })
+        }
+    }
+
+    impl SecretBackend for VaultSecretBackend {
+        fn scheme(&self) -> &str {
+            "vault"
+        }
+
+        fn fetch(&self, reference: &str) -> Result<Secret, ConfigError> {
+            let error = |message: String| {
+                ConfigError::SecretError(format!("vault:{}: {}", reference, message))
+            };
+            let (path, field) = reference.split_once('#').unwrap_or((reference, "value"));
+            let mut request = self
+                .agent
+                .get(&self.url(path).map_err(error)?)
+                .set("X-Vault-Token", self.token.expose());
+            if let Some(namespace) = &self.namespace {
+                request = request.set("X-Vault-Namespace", namespace);
+            }
+            let body: serde_json::Value = request
+                .call()
+                .map_err(|e| error(e.to_string()))?
+                .into_json()
+                .map_err(|e| error(e.to_string()))?;
+
+            let data = match self.kv_version {
+                1 => &body["data"],
+                _ => &body["data"]["data"],
+            };
+            let value = match &data[field] {
+                serde_json::Value::String(value) => value.clone(),
+                serde_json::Value::Null => return Err(error(format!("no field {}", field))),
+                other => other.to_string(),
+            };
+            Ok(Secret {
+                value: SecretString::new(value),
+                ttl: body["lease_duration"]
+                    .as_u64()
+                    .filter(|secs| *secs > 0)
+                    .map(Duration::from_secs),
+                version: body["data"]["metadata"]["version"]
+                    .as_u64()
+                    .map(|version| version.to_string()),
+            })
+        }
+    }
+}
+
+struct CachedSecret {
+    secret: Secret,
+    expires: Instant,
+}
+
+/// Resolves `${scheme:reference}` placeholders through registered backends
+pub struct SecretResolver {
+    backends: RwLock<HashMap<String, Arc<dyn SecretBackend>>>,
+    cache: RwLock<HashMap<String, CachedSecret>>,
+    ttl: Duration,
+    stale_grace: Duration,
+}
+
+impl SecretResolver {
+    /// Resolver without backends
+    pub fn new() -> Self {
+        Self {
+            backends: RwLock::new(HashMap::new()),
+            cache: RwLock::new(HashMap::new()),
+            ttl: Duration::from_secs(300),
+            stale_grace: Duration::from_secs(60),
+        }
+    }
+
+    /// Resolver with the `env` and `file` backends
+    pub fn with_defaults() -> Self {
+        let resolver = Self::new();
+        resolver.add_backend(Box::new(EnvSecretBackend));
+        resolver.add_backend(Box::new(FileSecretBackend::new()));
+        resolver
+    }
+
+    /// Longest time a resolved secret is cached (default 5 minutes)
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// How long an expired secret is still served while its backend fails (default 1 minute)
+    pub fn with_stale_grace(mut self, grace: Duration) -> Self {
+        self.stale_grace = grace;
+        self
+    }
+
+    /// Register a backend, replacing any other for the same scheme
+    pub fn add_backend(&self, backend: Box<dyn SecretBackend>) {
+        let backend: Arc<dyn SecretBackend> = Arc::from(backend);
+        self.backends
+            .write()
+            .insert(backend.scheme().to_string(), backend);
+    }
+
+    /// Whether a backend handles `scheme`
+    pub fn handles(&self, scheme: &str) -> bool {
+        self.backends.read().contains_key(scheme)
+    }
+
+    /// Resolve one reference, from the cache while it is fresh
+    pub fn resolve(&self, scheme: &str, reference: &str) -> Result<SecretString, ConfigError> {
+        let key = format!("{}:{}", scheme, reference);
+        let now = Instant::now();
+        if let Some(cached) = self.cache.read().get(&key) {
+            if now < cached.expires {
+                return Ok(cached.secret.value.clone());
+            }
+        }
+
+        let backend =
+            self.backends.read().get(scheme).cloned().ok_or_else(|| {
+                ConfigError::SecretError(format!("no secret backend for {}", scheme))
+            })?;
+        match backend.fetch(reference) {
+            Ok(secret) => {
+                let ttl = secret.ttl.map_or(self.ttl, |lease| lease.min(self.ttl));
+                let mut cache = self.cache.write();
+                if let Some(previous) = cache.get(&key) {
+                    if previous.secret.value != secret.value
+                        || previous.secret.version != secret.version
+                    {
+                        log::info!("Secret {} rotated", key);
+                    }
+                }
+                let value = secret.value.clone();
+                cache.insert(
+                    key,
+                    CachedSecret {
+                        secret,
+                        expires: now + ttl,
+                    },
+                );
+                Ok(value)
+            }
+            Err(e) => {
+                if let Some(cached) = self.cache.read().get(&key) {
+                    if now < cached.expires + self.stale_grace {
+                        log::warn!("Serving expired secret {}: {}", key, e);
+                        return Ok(cached.secret.value.clone());
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// `text` with every handled placeholder replaced; `None` when there was none
+    pub fn resolve_str(&self, text: &str) -> Result<Option<String>, ConfigError> {
+        let mut resolved = String::new();
+        let mut rest = text;
+        let mut replaced = false;
+        while let Some(start) = rest.find("${") {
+            let Some(len) = rest[start..].find('}') else {
+                break;
+            };
+            let placeholder = &rest[start + 2..start + len];
+            resolved.push_str(&rest[..start]);
+            match placeholder.split_once(':') {
+                Some((scheme, reference)) if self.handles(scheme) => {
+                    resolved.push_str(self.resolve(scheme, reference)?.expose());
+                    replaced = true;
+                }
+                _ => resolved.push_str(&rest[start..=start + len]),
+            }
+            rest = &rest[start + len + 1..];
+        }
+        if !replaced {
+            return Ok(None);
+        }
+        resolved.push_str(rest);
+        Ok(Some(resolved))
+    }
+
+    /// `value` with placeholders in all of its strings resolved
+    pub fn resolve_value(
+        &self,
+        value: &serde_json::Value,
+    ) -> Result<serde_json::Value, ConfigError> {
+        Ok(match value {
+            serde_json::Value::String(text) => match self.resolve_str(text)? {
+                Some(resolved) => serde_json::Value::String(resolved),
+                None => value.clone(),
+            },
+            serde_json::Value::Array(items) => serde_json::Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.resolve_value(item))
+                    .collect::<Result<_, _>>()?,
+            ),
+            serde_json::Value::Object(map) => serde_json::Value::Object(
+                map.iter()
+                    .map(|(key, item)| Ok((key.clone(), self.resolve_value(item)?)))
+                    .collect::<Result<_, ConfigError>>()?,
+            ),
+            _ => value.clone(),
+        })
+    }
+
+    /// Drop every cached secret, so the next read fetches it again
+    pub fn invalidate_all(&self) {
+        self.cache.write().clear();
+    }
+
+    /// Drop the cached secret of one reference
+    pub fn invalidate(&self, scheme: &str, reference: &str) {
+        self.cache
+            .write()
+            .remove(&format!("{}:{}", scheme, reference));
+    }
+}
+
+impl Default for SecretResolver {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+impl fmt::Debug for SecretResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut schemes: Vec<String> = self.backends.read().keys().cloned().collect();
+        schemes.sort();
+        f.debug_struct("SecretResolver")
+            .field("schemes", &schemes)
+            .field("cached", &self.cache.read().len())
+            .field("ttl", &self.ttl)
+            .field("stale_grace", &self.stale_grace)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Backend returning `rotated-N` on the N-th fetch, failing once `fail` is set
+    struct Rotating {
+        fetches: Arc<AtomicUsize>,
+        fail: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl SecretBackend for Rotating {
+        fn scheme(&self) -> &str {
+            "test"
+        }
+
+        fn fetch(&self, _reference: &str) -> Result<Secret, ConfigError> {
+            if self.fail.load(Ordering::SeqCst) {
+                return Err(ConfigError::SecretError("backend down".to_string()));
+            }
+            let n = self.fetches.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(Secret::new(format!("rotated-{}", n)))
+        }
+    }
+
+    #[test]
+    fn test_interpolation_and_redaction() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("db"), "hunter2\n").unwrap();
+        let resolver = SecretResolver::new();
+        resolver.add_backend(Box::new(FileSecretBackend::with_root(dir.path())));
+
+        let resolved = resolver
+            .resolve_str("postgres://app:${file:db}@db/${HOME}")
+            .unwrap();
+        assert_eq!(
+            resolved.as_deref(),
+            Some("postgres://app:hunter2@db/${HOME}")
+        );
+        assert_eq!(resolver.resolve_str("plain ${unknown:x}").unwrap(), None);
+        assert!(resolver.resolve("file", "../db").is_err());
+
+        let secret = resolver.resolve("file", "db").unwrap();
+        assert_eq!(secret.expose(), "hunter2");
+        assert!(!format!("{:?} {}", secret, secret).contains("hunter2"));
+    }
+
+    #[test]
+    fn test_rotation_and_stale_grace() {
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let fail = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let backend = || {
+            Box::new(Rotating {
+                fetches: fetches.clone(),
+                fail: fail.clone(),
+            })
+        };
+
+        // cached within the TTL
+        let cached = SecretResolver::new();
+        cached.add_backend(backend());
+        assert_eq!(cached.resolve("test", "k").unwrap().expose(), "rotated-1");
+        assert_eq!(cached.resolve("test", "k").unwrap().expose(), "rotated-1");
+        cached.invalidate("test", "k");
+        assert_eq!(cached.resolve("test", "k").unwrap().expose(), "rotated-2");
+
+        // expired entries pick up the rotation, and survive an outage within the grace
+        let expiring = SecretResolver::new()
+            .with_ttl(Duration::ZERO)
+            .with_stale_grace(Duration::from_secs(60));
+        expiring.add_backend(backend());
+        assert_eq!(expiring.resolve("test", "k").unwrap().expose(), "rotated-3");
+        assert_eq!(expiring.resolve("test", "k").unwrap().expose(), "rotated-4");
+        fail.store(true, Ordering::SeqCst);
+        assert_eq!(expiring.resolve("test", "k").unwrap().expose(), "rotated-4");
+
+        let strict = SecretResolver::new()
+            .with_ttl(Duration::ZERO)
+            .with_stale_grace(Duration::ZERO);
+        strict.add_backend(backend());
+        assert!(strict.resolve("test", "k").is_err());
+    }
+
+    #[cfg(feature = "vault")]
+    #[test]
+    fn test_vault_kv2_lookup() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 2048];
+            let n = stream.read(&mut request).unwrap();
+            let request = String::from_utf8_lossy(&request[..n]).to_lowercase();
+            let body = r#"{"lease_duration":0,"data":{"data":{"api_key":"k-123"},"metadata":{"version":3}}}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            request
+        });
+
+        let resolver = SecretResolver::new();
+        resolver.add_backend(Box::new(VaultSecretBackend::new(address, "root-token")));
+        let resolved = resolver
+            .resolve_str("${vault:secret/gateway#api_key}")
+            .unwrap();
+        assert_eq!(resolved.as_deref(), Some("k-123"));
+
+        let request = server.join().unwrap();
+        assert!(
+            request.starts_with("get /v1/secret/data/gateway "),
+            "{}",
+            request
+        );
+        assert!(request.contains("x-vault-token: root-token"));
+    }
+}
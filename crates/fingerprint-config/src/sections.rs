@@ -0,0 +1,253 @@
+//! Typed configuration sections
+//!
+//! A [`ConfigSection`] is a struct deserialized from everything under one path
+//! prefix, so `tls.min_version`, `tls.cipher_suites` and `tls.enable_ja4_plus`
+//! arrive together as a [`TlsSection`] instead of three `get` calls. Implement
+//! the trait with [`config_section!`](crate::config_section):
+//!
+//! ```
+//! use fingerprint_config::{config_section, ConfigManager};
+//! use serde::Deserialize;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct PoolSection {
+//!     max_idle: u32,
+//!     idle_timeout_secs: u64,
+//! }
+//!
+//! config_section!(PoolSection, "http.pool", validate = |pool: &PoolSection| {
+//!     if pool.max_idle == 0 {
+//!         return Err("max_idle must be positive".to_string());
+//!     }
+//!     Ok(())
+//! });
+//!
+//! let manager = ConfigManager::new();
+//! manager.set("http.pool.max_idle", 8).unwrap();
+//! manager.set("http.pool.idle_timeout_secs", 90).unwrap();
+//! let pool: PoolSection = manager.section().unwrap();
+//! assert_eq!(pool.max_idle, 8);
+//! ```
+//!
+//! [`ConfigManager::register_section`](crate::ConfigManager::register_section)
+//! makes the section's deserialization and `validate` part of every reload's
+//! validation, and [`ConfigManager::watch_section`](crate::ConfigManager::watch_section)
+//! returns a [`SectionHandle`] whose `Arc<T>` snapshot is replaced, whole, after
+//! each applied reload that touched the section.
+
+use crate::SecretResolver;
+use parking_lot::RwLock;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Struct deserialized from all values under [`ConfigSection::PREFIX`]
+pub trait ConfigSection: DeserializeOwned + Send + Sync + 'static {
+    /// Dotted path of the section, e.g. `tls` or `gateway.rbac`
+    const PREFIX: &'static str;
+
+    /// Checks across fields, run after deserialization
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Implement [`ConfigSection`] for a `Deserialize` struct
+///
+/// `config_section!(TlsSection, "tls")`, optionally followed by
+/// `validate = <fn(&TlsSection) -> Result<(), String>>`.
+#[macro_export]
+macro_rules! config_section {
+    ($ty:ty, $prefix:literal) => {
+        impl $crate::ConfigSection for $ty {
+            const PREFIX: &'static str = $prefix;
+        }
+    };
+    ($ty:ty, $prefix:literal, validate = $validate:expr) => {
+        impl $crate::ConfigSection for $ty {
+            const PREFIX: &'static str = $prefix;
+
+            fn validate(&self) -> Result<(), String> {
+                let validate: fn(&$ty) -> Result<(), String> = $validate;
+                validate(self)
+            }
+        }
+    };
+}
+
+/// Latest snapshot of a watched section
+///
+/// Readers keep the `Arc<T>` they got for as long as they need a consistent view;
+/// a reload swaps in a new snapshot without touching the old one.
+pub struct SectionHandle<T> {
+    current: Arc<RwLock<Arc<T>>>,
+}
+
+impl<T> SectionHandle<T> {
+    pub(crate) fn new(initial: T) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(initial))),
+        }
+    }
+
+    /// Current snapshot
+    pub fn get(&self) -> Arc<T> {
+        self.current.read().clone()
+    }
+
+    pub(crate) fn replace(&self, value: T) {
+        *self.current.write() = Arc::new(value);
+    }
+}
+
+impl<T> Clone for SectionHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            current: self.current.clone(),
+        }
+    }
+}
+
+/// Whether `path` is `prefix` or below it; an empty prefix matches everything
+pub(crate) fn matches_prefix(path: &str, prefix: &str) -> bool {
+    prefix.is_empty()
+        || path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Rebuild the nested value under `prefix` from flattened paths
+///
+/// An absent section is an empty object, so sections with serde defaults still load.
+pub(crate) fn unflatten(
+    values: &HashMap<String, serde_json::Value>,
+    prefix: &str,
+) -> serde_json::Value {
+    if let Some(value) = values.get(prefix) {
+        return value.clone();
+    }
+    let mut root = serde_json::Map::new();
+    for (path, value) in values {
+        if !matches_prefix(path, prefix) {
+            continue;
+        }
+        let relative = if prefix.is_empty() {
+            path.as_str()
+        } else {
+            &path[prefix.len() + 1..]
+        };
+        let mut keys = relative.split('.').peekable();
+        let mut node = &mut root;
+        while let Some(key) = keys.next() {
+            if keys.peek().is_none() {
+                node.insert(key.to_string(), value.clone());
+                break;
+            }
+            let child = node
+                .entry(key.to_string())
+                .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+            if !child.is_object() {
+                *child = serde_json::Value::Object(serde_json::Map::new());
+            }
+            node = child.as_object_mut().expect("just made an object");
+        }
+    }
+    serde_json::Value::Object(root)
+}
+
+/// Deserialize and validate `T` from flattened values
+pub(crate) fn load<T: ConfigSection>(
+    values: &HashMap<String, serde_json::Value>,
+) -> Result<T, String> {
+    load_value(unflatten(values, T::PREFIX))
+}
+
+/// Resolve secret references in `raw`, then deserialize and validate `T`
+///
+/// Deserialization errors are reported for `raw`, so they never quote a secret.
+pub(crate) fn load_resolved<T: ConfigSection>(
+    raw: serde_json::Value,
+    secrets: &SecretResolver,
+) -> Result<T, String> {
+    let resolved = secrets.resolve_value(&raw).map_err(|e| e.to_string())?;
+    if resolved == raw {
+        return load_value(raw);
+    }
+    let section: T = serde_json::from_value(resolved).map_err(|_| {
+        serde_json::from_value::<T>(raw).err().map_or_else(
+            || "resolved secret has the wrong type".to_string(),
+            |e| e.to_string(),
+        )
+    })?;
+    section.validate()?;
+    Ok(section)
+}
+
+fn load_value<T: ConfigSection>(value: serde_json::Value) -> Result<T, String> {
+    let section: T = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    section.validate()?;
+    Ok(section)
+}
+
+/// `core` section of the built-in defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoreSection {
+    pub log_level: String,
+    pub max_connections: u32,
+    pub timeout_seconds: u64,
+}
+
+crate::config_section!(
+    CoreSection,
+    "core",
+    validate = |core: &CoreSection| {
+        if core.timeout_seconds == 0 {
+            return Err("timeout_seconds must be positive".to_string());
+        }
+        Ok(())
+    }
+);
+
+/// `tls` section of the built-in defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TlsSection {
+    pub min_version: String,
+    pub cipher_suites: Vec<String>,
+    pub enable_ja4_plus: bool,
+}
+
+crate::config_section!(
+    TlsSection,
+    "tls",
+    validate = |tls: &TlsSection| {
+        if !matches!(tls.min_version.as_str(), "TLSv1_2" | "TLSv1_3") {
+            return Err(format!("unsupported min_version {}", tls.min_version));
+        }
+        if tls.cipher_suites.is_empty() {
+            return Err("cipher_suites must not be empty".to_string());
+        }
+        Ok(())
+    }
+);
+
+/// `http` section of the built-in defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpSection {
+    pub user_agent: String,
+    pub max_redirects: u32,
+    pub enable_quic: bool,
+}
+
+crate::config_section!(HttpSection, "http");
+
+/// `defense` section of the built-in defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DefenseSection {
+    pub enable_learning: bool,
+    pub anomaly_threshold: f64,
+    pub block_suspicious: bool,
+}
+
+crate::config_section!(DefenseSection, "defense");
@@ -0,0 +1,307 @@
+//! Compiled-in capabilities
+//!
+//! Each crate reports the analyzers, protocols and backends it was built
+//! with — optional ones with the cargo feature that enables them — through a
+//! `capabilities()` function at its root. Only the crate itself can see its
+//! features, so the `fingerprint` crate (and binaries on top of it) collect
+//! the per-crate lists into one [`CapabilityReport`].
+//!
+//! In-progress protocol work sits behind `experimental-*` features and is
+//! reported with [`Capability::experimental`] set, so a deployment can tell
+//! from `/version` that it runs code without stability guarantees.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// What a capability is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapabilityKind {
+    /// fingerprint extraction / classification
+    Analyzer,
+    /// wire protocol spoken or parsed
+    Protocol,
+    /// storage, capture or enforcement backend
+    Backend,
+    /// service infrastructure (caching, rate limiting, ...)
+    Service,
+}
+
+impl CapabilityKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Analyzer => "analyzer",
+            Self::Protocol => "protocol",
+            Self::Backend => "backend",
+            Self::Service => "service",
+        }
+    }
+}
+
+/// One capability of a build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capability {
+    pub name: String,
+    pub kind: CapabilityKind,
+    /// crate providing it
+    pub provider: String,
+    /// version of the providing crate
+    pub version: String,
+    pub enabled: bool,
+    /// cargo feature enabling it; `None` when always built
+    pub feature: Option<String>,
+    /// behind an `experimental-*` feature: may change or break between releases
+    #[serde(default)]
+    pub experimental: bool,
+}
+
+/// Capability list of one crate
+///
+/// ```
+/// use fingerprint_core::capabilities::{CapabilityKind, CapabilitySet};
+///
+/// let caps = CapabilitySet::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///     .builtin(CapabilityKind::Analyzer, "ja4")
+///     .optional(CapabilityKind::Protocol, "http3", "http3", cfg!(feature = "http3"))
+///     .into_vec();
+/// assert_eq!(caps.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CapabilitySet {
+    provider: String,
+    version: String,
+    capabilities: Vec<Capability>,
+}
+
+impl CapabilitySet {
+    pub fn new(provider: &str, version: &str) -> Self {
+        Self {
+            provider: provider.to_string(),
+            version: version.to_string(),
+            capabilities: Vec::new(),
+        }
+    }
+
+    /// Always compiled in
+    pub fn builtin(self, kind: CapabilityKind, name: &str) -> Self {
+        self.push(kind, name, None, true)
+    }
+
+    /// Behind `feature`; pass `cfg!(feature = "...")` from the providing crate
+    pub fn optional(self, kind: CapabilityKind, name: &str, feature: &str, enabled: bool) -> Self {
+        self.push(kind, name, Some(feature), enabled)
+    }
+
+    /// Like [`CapabilitySet::optional`], for an `experimental-*` feature
+    pub fn experimental(
+        mut self,
+        kind: CapabilityKind,
+        name: &str,
+        feature: &str,
+        enabled: bool,
+    ) -> Self {
+        self = self.push(kind, name, Some(feature), enabled);
+        if let Some(last) = self.capabilities.last_mut() {
+            last.experimental = true;
+        }
+        self
+    }
+
+    fn push(
+        mut self,
+        kind: CapabilityKind,
+        name: &str,
+        feature: Option<&str>,
+        enabled: bool,
+    ) -> Self {
+        self.capabilities.push(Capability {
+            name: name.to_string(),
+            kind,
+            provider: self.provider.clone(),
+            version: self.version.clone(),
+            enabled,
+            feature: feature.map(str::to_string),
+            experimental: false,
+        });
+        self
+    }
+
+    pub fn into_vec(self) -> Vec<Capability> {
+        self.capabilities
+    }
+}
+
+/// Capabilities of a whole binary
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityReport {
+    /// version of the reporting binary / library
+    pub version: String,
+    pub capabilities: Vec<Capability>,
+}
+
+impl CapabilityReport {
+    pub fn new(version: &str) -> Self {
+        Self {
+            version: version.to_string(),
+            capabilities: Vec::new(),
+        }
+    }
+
+    /// Add one crate's capabilities
+    pub fn with(mut self, capabilities: Vec<Capability>) -> Self {
+        self.capabilities.extend(capabilities);
+        self
+    }
+
+    /// Whether `name` is compiled in (by any provider)
+    pub fn has(&self, name: &str) -> bool {
+        self.capabilities
+            .iter()
+            .any(|c| c.name == name && c.enabled)
+    }
+
+    pub fn enabled(&self) -> impl Iterator<Item = &Capability> {
+        self.capabilities.iter().filter(|c| c.enabled)
+    }
+
+    /// Optional capabilities this build lacks
+    pub fn missing(&self) -> impl Iterator<Item = &Capability> {
+        self.capabilities.iter().filter(|c| !c.enabled)
+    }
+
+    /// Experimental capabilities compiled into this build
+    pub fn experimental(&self) -> impl Iterator<Item = &Capability> {
+        self.capabilities
+            .iter()
+            .filter(|c| c.enabled && c.experimental)
+    }
+
+    /// Pretty-printed JSON, as served on the gateway's `/version`
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Crate name → version of every provider
+    pub fn providers(&self) -> BTreeMap<&str, &str> {
+        self.capabilities
+            .iter()
+            .map(|c| (c.provider.as_str(), c.version.as_str()))
+            .collect()
+    }
+}
+
+impl fmt::Display for CapabilityReport {
+    /// One line per capability, grouped by kind
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "version {}", self.version)?;
+        let mut sorted: Vec<&Capability> = self.capabilities.iter().collect();
+        sorted.sort_by_key(|c| (c.kind, c.provider.as_str(), c.name.as_str()));
+        for c in sorted {
+            write!(
+                f,
+                "{} {:<9} {:<24} {} {}",
+                if c.enabled { "+" } else { "-" },
+                c.kind.as_str(),
+                c.name,
+                c.provider,
+                c.version
+            )?;
+            if let Some(feature) = &c.feature {
+                write!(f, " (feature \"{}\")", feature)?;
+            }
+            if c.experimental {
+                write!(f, " [experimental]")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Capabilities of `fingerprint-core`
+pub fn capabilities() -> Vec<Capability> {
+    use CapabilityKind::*;
+    CapabilitySet::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+        .builtin(Analyzer, "ja3")
+        .builtin(Analyzer, "ja4")
+        .builtin(Analyzer, "jarm")
+        .builtin(Analyzer, "hassh")
+        .builtin(Analyzer, "http2-settings")
+        .builtin(Analyzer, "pqc")
+        .builtin(Analyzer, "edge-vendor")
+        .builtin(Protocol, "tls-client-hello")
+        .optional(
+            Service,
+            "cache",
+            "service-cache",
+            cfg!(feature = "service-cache"),
+        )
+        .optional(
+            Service,
+            "metrics",
+            "service-metrics",
+            cfg!(feature = "service-metrics"),
+        )
+        .optional(
+            Service,
+            "rate-limiting",
+            "service-rate-limiting",
+            cfg!(feature = "service-rate-limiting"),
+        )
+        .optional(
+            Backend,
+            "redis-cache",
+            "redis-cache",
+            cfg!(feature = "redis-cache"),
+        )
+        .optional(
+            Service,
+            "feed-trust",
+            "feed-trust",
+            cfg!(feature = "feed-trust"),
+        )
+        .into_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_lists_enabled_and_missing() {
+        let report = CapabilityReport::new("1.0.0").with(capabilities()).with(
+            CapabilitySet::new("demo", "0.1.0")
+                .optional(CapabilityKind::Protocol, "quic", "http3", false)
+                .experimental(
+                    CapabilityKind::Protocol,
+                    "webtransport",
+                    "experimental-webtransport",
+                    true,
+                )
+                .into_vec(),
+        );
+
+        assert!(report.has("ja4"));
+        assert!(!report.has("quic"));
+        assert_eq!(report.missing().filter(|c| c.name == "quic").count(), 1);
+        assert_eq!(report.providers()["demo"], "0.1.0");
+
+        let text = report.to_string();
+        assert!(text.contains("- protocol  quic"));
+        assert!(text.contains("(feature \"http3\")\n"));
+        assert!(text.contains("(feature \"experimental-webtransport\") [experimental]"));
+        assert_eq!(
+            report
+                .experimental()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>(),
+            ["webtransport"]
+        );
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"kind\":\"analyzer\""));
+        let back: CapabilityReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, report);
+    }
+}
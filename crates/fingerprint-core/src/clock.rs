@@ -0,0 +1,204 @@
+//! Injectable time source
+//!
+//! Subsystems that expire, schedule or measure take a [`SharedClock`] instead
+//! of calling `SystemTime::now` / `Instant::now` directly, so tests can freeze
+//! time or move it forward:
+//!
+//! - [`SystemClock`]: the real clocks (the default everywhere)
+//! - [`FrozenClock`]: stands still until [`advance`](FrozenClock::advance)d
+//! - [`AcceleratedClock`]: real time running `factor` times faster, for soak
+//!   tests of TTLs and retention
+//!
+//! A clock provides both wall-clock time (timestamps, retention cutoffs) and
+//! monotonic time (deadlines, TTLs). Monotonic time of a non-system clock is
+//! still an [`Instant`], offset from a real one, so it stays comparable with
+//! other instants of the same clock.
+
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Source of current time
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// Wall-clock time
+    fn now(&self) -> SystemTime;
+
+    /// Monotonic time, for deadlines and elapsed durations
+    fn instant(&self) -> Instant;
+
+    /// Unix seconds
+    fn unix_secs(&self) -> u64 {
+        self.now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+
+    /// Unix milliseconds
+    fn unix_millis(&self) -> u64 {
+        self.now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64
+    }
+
+    fn utc(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from(self.now())
+    }
+}
+
+/// Clock shared between the subsystems of a process
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The real clock
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// Real wall-clock and monotonic time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+#[derive(Debug)]
+struct FrozenState {
+    wall: SystemTime,
+    offset: Duration,
+}
+
+/// Clock that only moves when told to
+///
+/// ```
+/// use fingerprint_core::clock::{Clock, FrozenClock};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let clock = FrozenClock::at_unix(1_700_000_000);
+/// let start = clock.instant();
+/// clock.advance(Duration::from_secs(90));
+/// assert_eq!(clock.unix_secs(), 1_700_000_090);
+/// assert_eq!(clock.instant() - start, Duration::from_secs(90));
+/// ```
+#[derive(Debug)]
+pub struct FrozenClock {
+    base: Instant,
+    state: Mutex<FrozenState>,
+}
+
+impl FrozenClock {
+    pub fn new(wall: SystemTime) -> Self {
+        Self {
+            base: Instant::now(),
+            state: Mutex::new(FrozenState {
+                wall,
+                offset: Duration::ZERO,
+            }),
+        }
+    }
+
+    pub fn at_unix(secs: u64) -> Self {
+        Self::new(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    /// Frozen at the current real time
+    pub fn starting_now() -> Self {
+        Self::new(SystemTime::now())
+    }
+
+    /// Move both wall-clock and monotonic time forward
+    pub fn advance(&self, by: Duration) {
+        let mut state = self.lock();
+        state.wall += by;
+        state.offset += by;
+    }
+
+    /// Jump the wall clock (also backwards); monotonic time is unchanged
+    pub fn set(&self, wall: SystemTime) {
+        self.lock().wall = wall;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, FrozenState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Clock for FrozenClock {
+    fn now(&self) -> SystemTime {
+        self.lock().wall
+    }
+
+    fn instant(&self) -> Instant {
+        self.base + self.lock().offset
+    }
+}
+
+/// Real time sped up by a constant factor from the moment of creation
+#[derive(Debug, Clone)]
+pub struct AcceleratedClock {
+    wall: SystemTime,
+    base: Instant,
+    factor: f64,
+}
+
+impl AcceleratedClock {
+    /// `factor` > 1 runs faster than real time; values ≤ 0 are treated as 1
+    pub fn new(factor: f64) -> Self {
+        Self {
+            wall: SystemTime::now(),
+            base: Instant::now(),
+            factor: if factor > 0.0 { factor } else { 1.0 },
+        }
+    }
+
+    pub fn factor(&self) -> f64 {
+        self.factor
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.base.elapsed().mul_f64(self.factor)
+    }
+}
+
+impl Clock for AcceleratedClock {
+    fn now(&self) -> SystemTime {
+        self.wall + self.elapsed()
+    }
+
+    fn instant(&self) -> Instant {
+        self.base + self.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frozen_and_accelerated_clocks() {
+        let frozen = FrozenClock::at_unix(1_000);
+        let before = frozen.instant();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(frozen.instant(), before);
+        assert_eq!(frozen.unix_millis(), 1_000_000);
+
+        frozen.set(UNIX_EPOCH + Duration::from_secs(10));
+        assert_eq!(frozen.unix_secs(), 10);
+        assert_eq!(frozen.instant(), before);
+        assert_eq!(frozen.utc().timestamp(), 10);
+
+        let fast = AcceleratedClock::new(1000.0);
+        let start = fast.instant();
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(fast.instant() - start >= Duration::from_secs(10));
+        assert!(fast.now() > SystemTime::now() + Duration::from_secs(5));
+    }
+}
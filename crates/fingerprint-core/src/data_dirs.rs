@@ -0,0 +1,410 @@
+//! Data directory layout for persistent artifacts
+//!
+//! Every artifact that outlives the process lives under one of four roots:
+//!
+//! | root     | contents                                  | Linux (XDG)                         |
+//! |----------|-------------------------------------------|-------------------------------------|
+//! | `data`   | defense database, models, reports        | `$XDG_DATA_HOME/fingerprint-rust`   |
+//! | `cache`  | DNS results and server pools              | `$XDG_CACHE_HOME/fingerprint-rust`  |
+//! | `state`  | audit log                                 | `$XDG_STATE_HOME/fingerprint-rust`  |
+//! | `config` | configuration files                       | `$XDG_CONFIG_HOME/fingerprint-rust` |
+//!
+//! macOS uses `~/Library/Application Support` and `~/Library/Caches`;
+//! Windows uses `%LOCALAPPDATA%` and `%APPDATA%`. Setting
+//! `FINGERPRINT_DATA_DIR` switches to a portable layout with all four roots
+//! under that directory, and [`DataDirsConfig`] overrides individual roots.
+//!
+//! Older releases wrote artifacts into the working directory;
+//! [`DataDirs::migrate_legacy`] moves them into place.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Directory name under each platform root
+pub const APP_DIR_NAME: &str = "fingerprint-rust";
+
+/// Environment variable selecting the portable layout
+pub const DATA_DIR_ENV: &str = "FINGERPRINT_DATA_DIR";
+
+/// Overrides of the default layout (e.g. the `data_dirs` config section)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DataDirsConfig {
+    /// Portable root holding `data/`, `cache/`, `state/` and `config/`
+    pub root: Option<PathBuf>,
+    /// Data root override
+    pub data: Option<PathBuf>,
+    /// Cache root override
+    pub cache: Option<PathBuf>,
+    /// State root override
+    pub state: Option<PathBuf>,
+    /// Config root override
+    pub config: Option<PathBuf>,
+}
+
+/// Resolved data, cache, state and config roots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataDirs {
+    data: PathBuf,
+    cache: PathBuf,
+    state: PathBuf,
+    config: PathBuf,
+}
+
+/// One legacy artifact and where it belongs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    /// Legacy location
+    pub from: PathBuf,
+    /// Location in the current layout
+    pub to: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Platform {
+    Xdg,
+    MacOs,
+    Windows,
+}
+
+impl Platform {
+    fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else if cfg!(windows) {
+            Platform::Windows
+        } else {
+            Platform::Xdg
+        }
+    }
+}
+
+impl DataDirs {
+    /// Platform default layout, or the portable one when `FINGERPRINT_DATA_DIR` is set
+    pub fn from_env() -> Self {
+        Self::resolve(Platform::current(), |name| std::env::var_os(name))
+    }
+
+    /// Default layout with `config` overrides applied
+    pub fn from_config(config: &DataDirsConfig) -> Self {
+        let mut dirs = match &config.root {
+            Some(root) => Self::portable(root),
+            None => Self::from_env(),
+        };
+        let overrides = [
+            (&mut dirs.data, &config.data),
+            (&mut dirs.cache, &config.cache),
+            (&mut dirs.state, &config.state),
+            (&mut dirs.config, &config.config),
+        ];
+        for (dir, custom) in overrides {
+            if let Some(custom) = custom {
+                *dir = custom.clone();
+            }
+        }
+        dirs
+    }
+
+    /// Portable layout: `root/data`, `root/cache`, `root/state`, `root/config`
+    pub fn portable(root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref();
+        Self {
+            data: root.join("data"),
+            cache: root.join("cache"),
+            state: root.join("state"),
+            config: root.join("config"),
+        }
+    }
+
+    fn resolve(platform: Platform, var: impl Fn(&str) -> Option<std::ffi::OsString>) -> Self {
+        // relative values are invalid per the XDG spec and ignored everywhere
+        let dir = |name: &str| var(name).map(PathBuf::from).filter(|p| p.is_absolute());
+
+        if let Some(root) = var(DATA_DIR_ENV).filter(|v| !v.is_empty()) {
+            return Self::portable(PathBuf::from(root));
+        }
+
+        let home = match platform {
+            Platform::Windows => dir("USERPROFILE"),
+            _ => dir("HOME"),
+        };
+        let Some(home) = home else {
+            // nowhere sensible to go: keep artifacts next to the process
+            return Self::portable(format!(".{}", APP_DIR_NAME));
+        };
+
+        match platform {
+            Platform::Xdg => Self {
+                data: dir("XDG_DATA_HOME").unwrap_or_else(|| home.join(".local/share")),
+                cache: dir("XDG_CACHE_HOME").unwrap_or_else(|| home.join(".cache")),
+                state: dir("XDG_STATE_HOME").unwrap_or_else(|| home.join(".local/state")),
+                config: dir("XDG_CONFIG_HOME").unwrap_or_else(|| home.join(".config")),
+            }
+            .joined(APP_DIR_NAME),
+            Platform::MacOs => {
+                let support = home.join("Library/Application Support").join(APP_DIR_NAME);
+                Self {
+                    data: support.clone(),
+                    cache: home.join("Library/Caches").join(APP_DIR_NAME),
+                    state: support.join("state"),
+                    config: support.join("config"),
+                }
+            }
+            Platform::Windows => {
+                let local = dir("LOCALAPPDATA")
+                    .unwrap_or_else(|| home.join("AppData\\Local"))
+                    .join(APP_DIR_NAME);
+                let roaming = dir("APPDATA").unwrap_or_else(|| home.join("AppData\\Roaming"));
+                Self {
+                    data: local.join("data"),
+                    cache: local.join("cache"),
+                    state: local.join("state"),
+                    config: roaming.join(APP_DIR_NAME),
+                }
+            }
+        }
+    }
+
+    fn joined(self, name: &str) -> Self {
+        Self {
+            data: self.data.join(name),
+            cache: self.cache.join(name),
+            state: self.state.join(name),
+            config: self.config.join(name),
+        }
+    }
+
+    /// Data root
+    pub fn data_dir(&self) -> &Path {
+        &self.data
+    }
+
+    /// Cache root (safe to delete)
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache
+    }
+
+    /// State root (logs and history)
+    pub fn state_dir(&self) -> &Path {
+        &self.state
+    }
+
+    /// Config root
+    pub fn config_dir(&self) -> &Path {
+        &self.config
+    }
+
+    /// Defense fingerprint database
+    pub fn defense_database(&self) -> PathBuf {
+        self.data.join("defense").join("fingerprints.db")
+    }
+
+    /// DNS results and server pools
+    pub fn dns_cache_dir(&self) -> PathBuf {
+        self.cache.join("dns")
+    }
+
+    /// Model registry files
+    pub fn model_dir(&self) -> PathBuf {
+        self.data.join("models")
+    }
+
+    /// Verified profile and model feed artifacts
+    pub fn feed_dir(&self) -> PathBuf {
+        self.data.join("feeds")
+    }
+
+    /// Transparency log of applied feed updates (JSON lines)
+    pub fn feed_log(&self) -> PathBuf {
+        self.state.join("feed-transparency.jsonl")
+    }
+
+    /// Audit log (JSON lines)
+    pub fn audit_log(&self) -> PathBuf {
+        self.state.join("audit.jsonl")
+    }
+
+    /// Generated reports
+    pub fn reports_dir(&self) -> PathBuf {
+        self.data.join("reports")
+    }
+
+    /// Create `path`'s parent directory and return `path`
+    pub fn prepare_file(path: PathBuf) -> io::Result<PathBuf> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(path)
+    }
+
+    /// Create directory `path` and return it
+    pub fn prepare_dir(path: PathBuf) -> io::Result<PathBuf> {
+        fs::create_dir_all(&path)?;
+        Ok(path)
+    }
+
+    /// Legacy artifacts under `legacy_root` whose new location is still free
+    ///
+    /// `legacy_root` is where older releases ran, usually the working
+    /// directory. Per-domain DNS files are not included: their names alone do
+    /// not tell them apart from other JSON files.
+    pub fn legacy_migrations(&self, legacy_root: &Path) -> Vec<Migration> {
+        let database = self.defense_database();
+        let mut candidates = vec![
+            (legacy_root.join("fingerprints.db"), database.clone()),
+            (
+                legacy_root.join("dnsservernames.json"),
+                self.dns_cache_dir().join("dnsservernames.json"),
+            ),
+            (legacy_root.join("models"), self.model_dir()),
+            (legacy_root.join("reports"), self.reports_dir()),
+            (legacy_root.join("audit.jsonl"), self.audit_log()),
+        ];
+        // SQLite sidecar files travel with the database
+        for suffix in ["-wal", "-shm"] {
+            let mut to = database.clone().into_os_string();
+            to.push(suffix);
+            candidates.push((
+                legacy_root.join(format!("fingerprints.db{}", suffix)),
+                PathBuf::from(to),
+            ));
+        }
+
+        candidates
+            .into_iter()
+            .filter(|(from, to)| from.exists() && !to.exists() && from != to)
+            .map(|(from, to)| Migration { from, to })
+            .collect()
+    }
+
+    /// Move legacy artifacts into this layout, never overwriting
+    ///
+    /// Returns the moves made. Stops at the first failure; moves already
+    /// made stay in place, so running it again resumes.
+    pub fn migrate_legacy(&self, legacy_root: &Path) -> io::Result<Vec<Migration>> {
+        let migrations = self.legacy_migrations(legacy_root);
+        for migration in &migrations {
+            Self::prepare_file(migration.to.clone())?;
+            move_path(&migration.from, &migration.to)?;
+        }
+        Ok(migrations)
+    }
+}
+
+impl Default for DataDirs {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+/// Rename, falling back to copy and delete across file systems
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_recursive(from, to)?;
+    if from.is_dir() {
+        fs::remove_dir_all(from)
+    } else {
+        fs::remove_file(from)
+    }
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    if !from.is_dir() {
+        return fs::copy(from, to).map(|_| ());
+    }
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::ffi::OsString;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let vars: HashMap<String, OsString> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), OsString::from(v)))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_platform_layouts() {
+        let xdg = DataDirs::resolve(
+            Platform::Xdg,
+            env(&[
+                ("HOME", "/home/u"),
+                ("XDG_CACHE_HOME", "/var/cache/u"),
+                ("XDG_STATE_HOME", "relative/ignored"),
+            ]),
+        );
+        assert_eq!(
+            xdg.data_dir(),
+            Path::new("/home/u/.local/share/fingerprint-rust")
+        );
+        assert_eq!(xdg.cache_dir(), Path::new("/var/cache/u/fingerprint-rust"));
+        assert_eq!(
+            xdg.state_dir(),
+            Path::new("/home/u/.local/state/fingerprint-rust")
+        );
+        assert_eq!(
+            xdg.defense_database(),
+            Path::new("/home/u/.local/share/fingerprint-rust/defense/fingerprints.db")
+        );
+
+        let mac = DataDirs::resolve(Platform::MacOs, env(&[("HOME", "/Users/u")]));
+        assert_eq!(
+            mac.cache_dir(),
+            Path::new("/Users/u/Library/Caches/fingerprint-rust")
+        );
+
+        let portable = DataDirs::resolve(
+            Platform::Xdg,
+            env(&[("HOME", "/home/u"), (DATA_DIR_ENV, "/srv/fp")]),
+        );
+        assert_eq!(portable.audit_log(), Path::new("/srv/fp/state/audit.jsonl"));
+
+        let config = DataDirsConfig {
+            root: Some(PathBuf::from("/opt/fp")),
+            cache: Some(PathBuf::from("/tmp/fp-cache")),
+            ..Default::default()
+        };
+        let dirs = DataDirs::from_config(&config);
+        assert_eq!(dirs.model_dir(), Path::new("/opt/fp/data/models"));
+        assert_eq!(dirs.dns_cache_dir(), Path::new("/tmp/fp-cache/dns"));
+    }
+
+    #[test]
+    fn test_migrate_legacy() {
+        let legacy = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        fs::write(legacy.path().join("fingerprints.db"), b"db").unwrap();
+        fs::write(legacy.path().join("fingerprints.db-wal"), b"wal").unwrap();
+        fs::create_dir(legacy.path().join("reports")).unwrap();
+        fs::write(legacy.path().join("reports/run.md"), b"# run").unwrap();
+
+        let dirs = DataDirs::portable(target.path());
+        // an existing destination is never overwritten
+        DataDirs::prepare_file(dirs.dns_cache_dir().join("dnsservernames.json")).unwrap();
+        fs::write(dirs.dns_cache_dir().join("dnsservernames.json"), b"new").unwrap();
+        fs::write(legacy.path().join("dnsservernames.json"), b"old").unwrap();
+
+        let moved = dirs.migrate_legacy(legacy.path()).unwrap();
+        assert_eq!(moved.len(), 3);
+        assert_eq!(fs::read(dirs.defense_database()).unwrap(), b"db");
+        assert!(dirs.defense_database().with_extension("db-wal").exists());
+        assert!(dirs.reports_dir().join("run.md").exists());
+        assert!(legacy.path().join("dnsservernames.json").exists());
+        assert!(dirs.legacy_migrations(legacy.path()).is_empty());
+    }
+}
@@ -36,6 +36,7 @@
 //! }
 //! ```
 
+use crate::schema::{self, ArtifactKind};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -361,14 +362,18 @@ impl FingerprintDatabase {
         }
     }
 
-    /// Export database to JSON
+    /// Export database to JSON (with schema header)
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
-        serde_json::to_string_pretty(self)
+        schema::registry()
+            .to_json(ArtifactKind::FingerprintDatabase, self)
+            .map_err(serde::ser::Error::custom)
     }
 
-    /// Import database from JSON
+    /// Import database from JSON, migrating older exports (including unversioned ones)
     pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
-        serde_json::from_str(json)
+        schema::registry()
+            .from_json(ArtifactKind::FingerprintDatabase, json)
+            .map_err(serde::de::Error::custom)
     }
 
     /// Get the appropriate map for a fingerprint type
@@ -730,6 +735,11 @@ mod tests {
         let restored_db = restored.unwrap();
         assert_eq!(restored_db.total_entries(), 1);
         assert!(restored_db.get_ja3("test123").is_some());
+
+        // exports from before schema versioning still load
+        let legacy = serde_json::to_string(&db).unwrap();
+        let restored_db = FingerprintDatabase::from_json(&legacy).unwrap();
+        assert!(restored_db.get_ja3("test123").is_some());
     }
 
     #[test]
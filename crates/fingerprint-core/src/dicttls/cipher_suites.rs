@@ -32,6 +32,11 @@ pub mod cipher_suites {
     pub const TLS_RSA_WITH_AES_128_CBC_SHA: u16 = 0x002f;
     pub const TLS_RSA_WITH_AES_256_CBC_SHA: u16 = 0x0035;
 
+    // 3DES cipher suite (legacy, still offered by older mobile OS stacks)
+    pub const TLS_ECDHE_ECDSA_WITH_3DES_EDE_CBC_SHA: u16 = 0xc008;
+    pub const TLS_ECDHE_RSA_WITH_3DES_EDE_CBC_SHA: u16 = 0xc012;
+    pub const TLS_RSA_WITH_3DES_EDE_CBC_SHA: u16 = 0x000a;
+
     // GREASE placeholder
     pub const GREASE_PLACEHOLDER: u16 = 0x0a0a;
 }
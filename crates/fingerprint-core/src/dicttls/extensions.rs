@@ -21,6 +21,7 @@ pub mod extension_types {
     pub const EXT_TYPE_KEY_SHARE: u16 = 51;
     pub const EXT_TYPE_RENEGOTIATION_INFO: u16 = 65281;
     pub const EXT_TYPE_PRE_SHARED_KEY: u16 = 41;
+    pub const EXT_TYPE_EARLY_DATA: u16 = 42;
     pub const EXT_TYPE_COMPRESS_CERTIFICATE: u16 = 27;
     pub const EXT_TYPE_ECH: u16 = 0x0042; // Encrypted Client Hello (RFC 9180)
 
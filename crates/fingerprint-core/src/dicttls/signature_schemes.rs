@@ -22,6 +22,10 @@ pub mod signature_schemes {
     pub const ECDSA_WITH_P384_AND_SHA384: u16 = 0x0503;
     pub const ECDSA_WITH_P521_AND_SHA512: u16 = 0x0603;
 
+    // Legacy SHA-1
+    pub const RSA_PKCS1_SHA1: u16 = 0x0201;
+    pub const ECDSA_SHA1: u16 = 0x0203;
+
     // EdDSA
     pub const ED25519: u16 = 0x0807;
     pub const ED448: u16 = 0x0808;
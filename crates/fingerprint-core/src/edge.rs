@@ -0,0 +1,578 @@
+//! CDN / WAF vendor identification
+//!
+//! Edge networks leave traces on the traffic they carry: response headers
+//! (`cf-ray`, `x-amz-cf-id`), cookies (`__cf_bm`, `incap_ses_*`), certificates
+//! from their own CAs and source addresses in their published ranges. An
+//! [`EdgeClassifier`] weighs those traces per vendor and combines them as
+//! independent evidence, so one strong header or several weak hints are enough.
+//!
+//! The same observation type works for responses (which edge fronts this
+//! origin?) and for inbound requests (did this request arrive through an edge,
+//! so that the peer address is not the client?).
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+
+/// default minimum confidence for a detection
+const DEFAULT_MIN_CONFIDENCE: f64 = 0.5;
+
+/// CDN / WAF vendor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeVendor {
+    Cloudflare,
+    Akamai,
+    Fastly,
+    CloudFront,
+    Imperva,
+}
+
+impl EdgeVendor {
+    pub const ALL: [EdgeVendor; 5] = [
+        EdgeVendor::Cloudflare,
+        EdgeVendor::Akamai,
+        EdgeVendor::Fastly,
+        EdgeVendor::CloudFront,
+        EdgeVendor::Imperva,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EdgeVendor::Cloudflare => "cloudflare",
+            EdgeVendor::Akamai => "akamai",
+            EdgeVendor::Fastly => "fastly",
+            EdgeVendor::CloudFront => "cloudfront",
+            EdgeVendor::Imperva => "imperva",
+        }
+    }
+}
+
+impl fmt::Display for EdgeVendor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Kind of trace an edge network left
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeSignal {
+    Header,
+    Cookie,
+    CertIssuer,
+    IpRange,
+}
+
+/// One matched trace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EdgeEvidence {
+    pub signal: EdgeSignal,
+    /// what matched, e.g. `cf-ray` or `104.16.0.0/13`
+    pub detail: String,
+}
+
+/// Vendor identified for a response or request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EdgeDetection {
+    pub vendor: EdgeVendor,
+    /// combined weight of the evidence (0.0-1.0)
+    pub confidence: f64,
+    pub evidence: Vec<EdgeEvidence>,
+}
+
+/// Traces collected from one response or request
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EdgeObservation {
+    /// header name (any case) and value
+    pub headers: Vec<(String, String)>,
+    /// cookie names from `Set-Cookie` / `Cookie`
+    pub cookies: Vec<String>,
+    /// certificate issuer, e.g. `C=US, O=Cloudflare, Inc., CN=Cloudflare Inc ECC CA-3`
+    pub cert_issuer: Option<String>,
+    /// peer address
+    pub ip: Option<IpAddr>,
+}
+
+impl EdgeObservation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a header; cookie names are taken from `Set-Cookie` and `Cookie`
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        if name.eq_ignore_ascii_case("set-cookie") {
+            if let Some((cookie, _)) = value.split_once('=') {
+                self.cookies.push(cookie.trim().to_string());
+            }
+        } else if name.eq_ignore_ascii_case("cookie") {
+            self.cookies.extend(
+                value
+                    .split(';')
+                    .filter_map(|pair| pair.split_once('='))
+                    .map(|(cookie, _)| cookie.trim().to_string()),
+            );
+        }
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn cookie(mut self, name: &str) -> Self {
+        self.cookies.push(name.to_string());
+        self
+    }
+
+    pub fn cert_issuer(mut self, issuer: &str) -> Self {
+        self.cert_issuer = Some(issuer.to_string());
+        self
+    }
+
+    pub fn ip(mut self, ip: IpAddr) -> Self {
+        self.ip = Some(ip);
+        self
+    }
+
+    fn header_value<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.headers
+            .iter()
+            .filter(move |(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// IP network in CIDR notation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpPrefix {
+    network: IpAddr,
+    len: u8,
+}
+
+impl IpPrefix {
+    /// Parse `addr/len`
+    pub fn parse(cidr: &str) -> Result<Self, String> {
+        let (addr, len) = cidr
+            .split_once('/')
+            .ok_or_else(|| format!("missing prefix length: {}", cidr))?;
+        let network: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("invalid address: {}", addr))?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let len: u8 = len
+            .parse()
+            .ok()
+            .filter(|&len| len <= max)
+            .ok_or_else(|| format!("invalid prefix length: {}", cidr))?;
+        Ok(Self { network, len })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let (network, ip, bits) = match (self.network, ip) {
+            (IpAddr::V4(n), IpAddr::V4(ip)) => (u32::from(n) as u128, u32::from(ip) as u128, 32),
+            (IpAddr::V6(n), IpAddr::V6(ip)) => (u128::from(n), u128::from(ip), 128),
+            _ => return false,
+        };
+        if self.len == 0 {
+            return true;
+        }
+        let shift = bits - self.len as u32;
+        network >> shift == ip >> shift
+    }
+}
+
+impl fmt::Display for IpPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.len)
+    }
+}
+
+/// Trace a vendor is known to leave
+#[derive(Debug, Clone, PartialEq)]
+pub enum EdgeRule {
+    /// header present, with a value containing `value` when given (case-insensitive)
+    Header { name: String, value: Option<String> },
+    /// cookie whose name starts with `prefix`
+    Cookie { prefix: String },
+    /// certificate issuer containing `text` (case-insensitive)
+    CertIssuer { text: String },
+    /// peer address inside the range
+    IpRange(IpPrefix),
+}
+
+impl EdgeRule {
+    pub fn header(name: &str) -> Self {
+        EdgeRule::Header {
+            name: name.to_string(),
+            value: None,
+        }
+    }
+
+    pub fn header_contains(name: &str, value: &str) -> Self {
+        EdgeRule::Header {
+            name: name.to_string(),
+            value: Some(value.to_ascii_lowercase()),
+        }
+    }
+
+    pub fn cookie(prefix: &str) -> Self {
+        EdgeRule::Cookie {
+            prefix: prefix.to_string(),
+        }
+    }
+
+    pub fn cert_issuer(text: &str) -> Self {
+        EdgeRule::CertIssuer {
+            text: text.to_ascii_lowercase(),
+        }
+    }
+
+    /// Evidence when the observation carries this trace
+    fn check(&self, observation: &EdgeObservation) -> Option<EdgeEvidence> {
+        let (signal, detail) = match self {
+            EdgeRule::Header { name, value } => {
+                let mut values = observation.header_value(name);
+                let matched = match value {
+                    None => values.next().is_some(),
+                    Some(value) => values.any(|v| v.to_ascii_lowercase().contains(value.as_str())),
+                };
+                if !matched {
+                    return None;
+                }
+                let name = name.to_ascii_lowercase();
+                let detail = match value {
+                    None => name,
+                    Some(value) => format!("{}: {}", name, value),
+                };
+                (EdgeSignal::Header, detail)
+            }
+            EdgeRule::Cookie { prefix } => {
+                let cookie = observation.cookies.iter().find(|c| c.starts_with(prefix))?;
+                (EdgeSignal::Cookie, cookie.clone())
+            }
+            EdgeRule::CertIssuer { text } => {
+                let issuer = observation.cert_issuer.as_ref()?;
+                if !issuer.to_ascii_lowercase().contains(text.as_str()) {
+                    return None;
+                }
+                (EdgeSignal::CertIssuer, issuer.clone())
+            }
+            EdgeRule::IpRange(prefix) => {
+                if !observation.ip.is_some_and(|ip| prefix.contains(ip)) {
+                    return None;
+                }
+                (EdgeSignal::IpRange, prefix.to_string())
+            }
+        };
+        Some(EdgeEvidence { signal, detail })
+    }
+}
+
+/// Weighted vendor traces
+#[derive(Debug, Clone)]
+pub struct EdgeClassifier {
+    rules: Vec<(EdgeVendor, EdgeRule, f64)>,
+    min_confidence: f64,
+}
+
+/// Published edge ranges (IPv4 and IPv6), a stable subset of each vendor's list
+const BUILTIN_RANGES: &[(EdgeVendor, &str)] = &[
+    (EdgeVendor::Cloudflare, "173.245.48.0/20"),
+    (EdgeVendor::Cloudflare, "103.21.244.0/22"),
+    (EdgeVendor::Cloudflare, "103.22.200.0/22"),
+    (EdgeVendor::Cloudflare, "103.31.4.0/22"),
+    (EdgeVendor::Cloudflare, "141.101.64.0/18"),
+    (EdgeVendor::Cloudflare, "108.162.192.0/18"),
+    (EdgeVendor::Cloudflare, "190.93.240.0/20"),
+    (EdgeVendor::Cloudflare, "188.114.96.0/20"),
+    (EdgeVendor::Cloudflare, "197.234.240.0/22"),
+    (EdgeVendor::Cloudflare, "198.41.128.0/17"),
+    (EdgeVendor::Cloudflare, "162.158.0.0/15"),
+    (EdgeVendor::Cloudflare, "104.16.0.0/13"),
+    (EdgeVendor::Cloudflare, "104.24.0.0/14"),
+    (EdgeVendor::Cloudflare, "172.64.0.0/13"),
+    (EdgeVendor::Cloudflare, "131.0.72.0/22"),
+    (EdgeVendor::Cloudflare, "2400:cb00::/32"),
+    (EdgeVendor::Cloudflare, "2606:4700::/32"),
+    (EdgeVendor::Cloudflare, "2803:f800::/32"),
+    (EdgeVendor::Cloudflare, "2405:b500::/32"),
+    (EdgeVendor::Cloudflare, "2405:8100::/32"),
+    (EdgeVendor::Cloudflare, "2a06:98c0::/29"),
+    (EdgeVendor::Cloudflare, "2c0f:f248::/32"),
+    (EdgeVendor::Akamai, "23.32.0.0/11"),
+    (EdgeVendor::Akamai, "23.192.0.0/11"),
+    (EdgeVendor::Akamai, "2.16.0.0/13"),
+    (EdgeVendor::Akamai, "95.100.0.0/15"),
+    (EdgeVendor::Akamai, "96.16.0.0/15"),
+    (EdgeVendor::Akamai, "184.24.0.0/13"),
+    (EdgeVendor::Akamai, "104.64.0.0/10"),
+    (EdgeVendor::Akamai, "2600:1400::/24"),
+    (EdgeVendor::Fastly, "23.235.32.0/20"),
+    (EdgeVendor::Fastly, "43.249.72.0/22"),
+    (EdgeVendor::Fastly, "103.244.50.0/24"),
+    (EdgeVendor::Fastly, "103.245.222.0/23"),
+    (EdgeVendor::Fastly, "103.245.224.0/24"),
+    (EdgeVendor::Fastly, "104.156.80.0/20"),
+    (EdgeVendor::Fastly, "140.248.64.0/18"),
+    (EdgeVendor::Fastly, "140.248.128.0/17"),
+    (EdgeVendor::Fastly, "146.75.0.0/17"),
+    (EdgeVendor::Fastly, "151.101.0.0/16"),
+    (EdgeVendor::Fastly, "157.52.64.0/18"),
+    (EdgeVendor::Fastly, "167.82.0.0/17"),
+    (EdgeVendor::Fastly, "172.111.64.0/18"),
+    (EdgeVendor::Fastly, "185.31.16.0/22"),
+    (EdgeVendor::Fastly, "199.27.72.0/21"),
+    (EdgeVendor::Fastly, "199.232.0.0/16"),
+    (EdgeVendor::Fastly, "2a04:4e40::/32"),
+    (EdgeVendor::Fastly, "2a04:4e42::/32"),
+    (EdgeVendor::CloudFront, "13.32.0.0/15"),
+    (EdgeVendor::CloudFront, "13.224.0.0/14"),
+    (EdgeVendor::CloudFront, "13.249.0.0/16"),
+    (EdgeVendor::CloudFront, "18.64.0.0/14"),
+    (EdgeVendor::CloudFront, "18.154.0.0/15"),
+    (EdgeVendor::CloudFront, "18.160.0.0/15"),
+    (EdgeVendor::CloudFront, "52.84.0.0/15"),
+    (EdgeVendor::CloudFront, "54.182.0.0/16"),
+    (EdgeVendor::CloudFront, "54.192.0.0/16"),
+    (EdgeVendor::CloudFront, "54.230.0.0/16"),
+    (EdgeVendor::CloudFront, "54.239.128.0/18"),
+    (EdgeVendor::CloudFront, "99.84.0.0/16"),
+    (EdgeVendor::CloudFront, "99.86.0.0/16"),
+    (EdgeVendor::CloudFront, "108.156.0.0/14"),
+    (EdgeVendor::CloudFront, "143.204.0.0/16"),
+    (EdgeVendor::CloudFront, "204.246.164.0/22"),
+    (EdgeVendor::CloudFront, "205.251.192.0/19"),
+    (EdgeVendor::CloudFront, "2600:9000::/28"),
+    (EdgeVendor::Imperva, "45.60.0.0/16"),
+    (EdgeVendor::Imperva, "45.64.64.0/22"),
+    (EdgeVendor::Imperva, "103.28.248.0/22"),
+    (EdgeVendor::Imperva, "107.154.0.0/16"),
+    (EdgeVendor::Imperva, "149.126.72.0/21"),
+    (EdgeVendor::Imperva, "185.11.124.0/22"),
+    (EdgeVendor::Imperva, "192.230.64.0/18"),
+    (EdgeVendor::Imperva, "198.143.32.0/19"),
+    (EdgeVendor::Imperva, "199.83.128.0/21"),
+    (EdgeVendor::Imperva, "2a02:e980::/29"),
+];
+
+static BUILTIN: Lazy<EdgeClassifier> = Lazy::new(EdgeClassifier::new);
+
+impl EdgeClassifier {
+    /// Classifier with the built-in traces of every [`EdgeVendor`]
+    pub fn new() -> Self {
+        use EdgeVendor::*;
+
+        let mut classifier = Self::empty();
+        let mut add = |vendor, rule, weight| classifier.rules.push((vendor, rule, weight));
+
+        // headers only the vendor sets are conclusive on their own
+        add(Cloudflare, EdgeRule::header("cf-ray"), 0.9);
+        add(Cloudflare, EdgeRule::header("cf-cache-status"), 0.8);
+        add(Cloudflare, EdgeRule::header("cf-connecting-ip"), 0.8);
+        add(
+            Cloudflare,
+            EdgeRule::header_contains("server", "cloudflare"),
+            0.7,
+        );
+        add(
+            Cloudflare,
+            EdgeRule::header_contains("cdn-loop", "cloudflare"),
+            0.8,
+        );
+        add(Cloudflare, EdgeRule::cookie("__cf_bm"), 0.6);
+        add(Cloudflare, EdgeRule::cookie("__cflb"), 0.6);
+        add(Cloudflare, EdgeRule::cookie("__cfruid"), 0.6);
+        add(Cloudflare, EdgeRule::cookie("cf_clearance"), 0.6);
+        add(Cloudflare, EdgeRule::cert_issuer("cloudflare"), 0.4);
+
+        add(Akamai, EdgeRule::header("akamai-grn"), 0.9);
+        add(Akamai, EdgeRule::header("x-akamai-transformed"), 0.9);
+        add(Akamai, EdgeRule::header("x-akamai-request-id"), 0.9);
+        add(Akamai, EdgeRule::header("akamai-origin-hop"), 0.8);
+        add(
+            Akamai,
+            EdgeRule::header_contains("server", "akamaighost"),
+            0.8,
+        );
+        add(
+            Akamai,
+            EdgeRule::header_contains("server", "akamainetstorage"),
+            0.8,
+        );
+        add(Akamai, EdgeRule::cookie("ak_bmsc"), 0.6);
+        add(Akamai, EdgeRule::cookie("bm_sz"), 0.5);
+        add(Akamai, EdgeRule::cookie("_abck"), 0.5);
+        add(Akamai, EdgeRule::cert_issuer("akamai"), 0.4);
+
+        add(Fastly, EdgeRule::header("x-fastly-request-id"), 0.9);
+        add(Fastly, EdgeRule::header("fastly-client-ip"), 0.8);
+        add(Fastly, EdgeRule::header("fastly-ff"), 0.8);
+        add(Fastly, EdgeRule::header_contains("cdn-loop", "fastly"), 0.8);
+        add(
+            Fastly,
+            EdgeRule::header_contains("x-served-by", "cache-"),
+            0.5,
+        );
+        add(Fastly, EdgeRule::header_contains("via", "varnish"), 0.2);
+        // Certainly is Fastly's own CA
+        add(Fastly, EdgeRule::cert_issuer("o=certainly"), 0.4);
+
+        add(CloudFront, EdgeRule::header("x-amz-cf-id"), 0.9);
+        add(CloudFront, EdgeRule::header("x-amz-cf-pop"), 0.9);
+        add(
+            CloudFront,
+            EdgeRule::header("cloudfront-viewer-address"),
+            0.8,
+        );
+        add(
+            CloudFront,
+            EdgeRule::header_contains("via", "cloudfront"),
+            0.8,
+        );
+        add(
+            CloudFront,
+            EdgeRule::header_contains("x-cache", "cloudfront"),
+            0.8,
+        );
+        add(
+            CloudFront,
+            EdgeRule::header_contains("server", "cloudfront"),
+            0.7,
+        );
+        add(CloudFront, EdgeRule::cookie("CloudFront-"), 0.6);
+        add(CloudFront, EdgeRule::cert_issuer("o=amazon"), 0.2);
+
+        add(Imperva, EdgeRule::header("x-iinfo"), 0.9);
+        add(Imperva, EdgeRule::header("incap-client-ip"), 0.8);
+        add(
+            Imperva,
+            EdgeRule::header_contains("x-cdn", "incapsula"),
+            0.8,
+        );
+        add(Imperva, EdgeRule::header_contains("x-cdn", "imperva"), 0.8);
+        add(Imperva, EdgeRule::cookie("incap_ses_"), 0.7);
+        add(Imperva, EdgeRule::cookie("visid_incap_"), 0.7);
+        add(Imperva, EdgeRule::cookie("nlbi_"), 0.6);
+        add(Imperva, EdgeRule::cookie("reese84"), 0.4);
+        add(Imperva, EdgeRule::cert_issuer("imperva"), 0.4);
+
+        for (vendor, cidr) in BUILTIN_RANGES {
+            let prefix = IpPrefix::parse(cidr).expect("built-in edge range");
+            add(*vendor, EdgeRule::IpRange(prefix), 0.6);
+        }
+
+        classifier
+    }
+
+    /// Classifier without any rules
+    pub fn empty() -> Self {
+        Self {
+            rules: Vec::new(),
+            min_confidence: DEFAULT_MIN_CONFIDENCE,
+        }
+    }
+
+    /// Shared built-in classifier
+    pub fn builtin() -> &'static EdgeClassifier {
+        &BUILTIN
+    }
+
+    pub fn with_rule(mut self, vendor: EdgeVendor, rule: EdgeRule, weight: f64) -> Self {
+        self.rules.push((vendor, rule, weight.clamp(0.0, 1.0)));
+        self
+    }
+
+    /// Add a site-specific range, e.g. from a vendor's current published list
+    pub fn with_range(self, vendor: EdgeVendor, cidr: &str) -> Result<Self, String> {
+        let prefix = IpPrefix::parse(cidr)?;
+        Ok(self.with_rule(vendor, EdgeRule::IpRange(prefix), 0.6))
+    }
+
+    pub fn with_min_confidence(mut self, min_confidence: f64) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    /// Most likely vendor, if any reaches the minimum confidence
+    pub fn classify(&self, observation: &EdgeObservation) -> Option<EdgeDetection> {
+        self.classify_all(observation).into_iter().next()
+    }
+
+    /// Every vendor reaching the minimum confidence, most confident first
+    ///
+    /// Several vendors can match at once, e.g. Cloudflare in front of an
+    /// origin that also sits behind CloudFront.
+    pub fn classify_all(&self, observation: &EdgeObservation) -> Vec<EdgeDetection> {
+        let mut detections: Vec<EdgeDetection> = Vec::new();
+        for vendor in EdgeVendor::ALL {
+            // traces are treated as independent: 1 - Π(1 - w)
+            let mut miss = 1.0;
+            let mut evidence = Vec::new();
+            for (_, rule, weight) in self.rules.iter().filter(|(v, _, _)| *v == vendor) {
+                if let Some(found) = rule.check(observation) {
+                    miss *= 1.0 - weight;
+                    evidence.push(found);
+                }
+            }
+            let confidence = 1.0 - miss;
+            if !evidence.is_empty() && confidence >= self.min_confidence {
+                detections.push(EdgeDetection {
+                    vendor,
+                    confidence,
+                    evidence,
+                });
+            }
+        }
+        detections.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        detections
+    }
+}
+
+impl Default for EdgeClassifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifies_vendors_from_traces() {
+        let classifier = EdgeClassifier::new();
+
+        let cloudflare = EdgeObservation::new()
+            .header("Server", "cloudflare")
+            .header("CF-RAY", "8a1b2c3d4e5f-AMS")
+            .header("Set-Cookie", "__cf_bm=abc; path=/; HttpOnly");
+        let detection = classifier.classify(&cloudflare).unwrap();
+        assert_eq!(detection.vendor, EdgeVendor::Cloudflare);
+        assert!(detection.confidence > 0.95);
+        assert_eq!(detection.evidence.len(), 3);
+
+        let imperva = EdgeObservation::new()
+            .header("Cookie", "visid_incap_123=x; incap_ses_45_123=y")
+            .ip("45.60.12.3".parse().unwrap());
+        assert_eq!(
+            classifier.classify(&imperva).unwrap().vendor,
+            EdgeVendor::Imperva
+        );
+
+        // a weak hint alone stays below the threshold
+        let varnish = EdgeObservation::new().header("Via", "1.1 varnish");
+        assert!(classifier.classify(&varnish).is_none());
+
+        let cloudfront = EdgeObservation::new().ip("2600:9000:2000::1".parse().unwrap());
+        assert_eq!(
+            classifier.classify(&cloudfront).unwrap().vendor,
+            EdgeVendor::CloudFront
+        );
+
+        let custom = EdgeClassifier::empty()
+            .with_range(EdgeVendor::Fastly, "198.51.100.0/24")
+            .unwrap();
+        let fastly = EdgeObservation::new().ip("198.51.100.7".parse().unwrap());
+        assert_eq!(custom.classify(&fastly).unwrap().vendor, EdgeVendor::Fastly);
+        assert!(IpPrefix::parse("10.0.0.0/33").is_err());
+    }
+}
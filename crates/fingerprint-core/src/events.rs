@@ -0,0 +1,354 @@
+//! In-process event bus
+//!
+//! Subsystems announce what happened (an analysis finished, an alert was
+//! raised, a fingerprint was learned, a metric was recorded) on an
+//! [`EventBus`] instead of calling each other directly, so users can wire
+//! their own consumers without touching the publishers.
+//!
+//! Topics are typed: every [`Event`] type is its own topic. Subscribers are
+//! either synchronous callbacks, run on the publishing thread, or bounded
+//! tokio channels for async consumers. Publishing never blocks: when a channel
+//! is full the event is dropped for that subscriber and counted in
+//! [`EventBus::dropped`].
+//!
+//! The built-in publishers use [`global()`]:
+//!
+//! | Event | Publisher |
+//! |-------|-----------|
+//! | [`AnalysisCompleted`] | fingerprint-analysis engine |
+//! | [`AlertRaised`] | analysis engine, defense consistency middleware |
+//! | [`FingerprintLearned`] | defense self-learning analyzer |
+//! | [`MetricRecorded`] | fingerprint-observability `record_*` helpers |
+//!
+//! Alerts go through [`publish_alert`], which attaches the matching
+//! [runbook](crate::runbook) before delivery.
+//!
+//! ```
+//! use fingerprint_core::events::{AlertRaised, EventBus};
+//!
+//! let bus = EventBus::new();
+//! bus.subscribe(|alert: &AlertRaised| println!("{}: {}", alert.source, alert.message));
+//! let delivered = bus.publish(AlertRaised::new("example", "warning", "anomaly", "odd JA4"));
+//! assert_eq!(delivered, 1);
+//! ```
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
+
+/// Payload type carried on its own topic
+pub trait Event: Clone + Send + Sync + 'static {
+    /// Topic name, for logs and metrics
+    const TOPIC: &'static str;
+}
+
+/// Handle returned by the subscribe methods, used to unsubscribe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+enum Subscriber<E> {
+    Sync(Arc<dyn Fn(&E) + Send + Sync>),
+    Channel(mpsc::Sender<E>),
+}
+
+impl<E> Clone for Subscriber<E> {
+    fn clone(&self) -> Self {
+        match self {
+            Subscriber::Sync(f) => Subscriber::Sync(f.clone()),
+            Subscriber::Channel(tx) => Subscriber::Channel(tx.clone()),
+        }
+    }
+}
+
+type Subscribers<E> = Vec<(SubscriptionId, Subscriber<E>)>;
+
+/// Typed publish/subscribe hub
+#[derive(Default)]
+pub struct EventBus {
+    /// `TypeId::of::<E>()` -> `Subscribers<E>`
+    topics: RwLock<HashMap<TypeId, Box<dyn Any + Send + Sync>>>,
+    next_id: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl EventBus {
+    /// Empty bus
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `callback` on the publishing thread for every `E`
+    ///
+    /// Callbacks should be quick; hand slow work to
+    /// [`EventBus::subscribe_channel`] instead.
+    pub fn subscribe<E, F>(&self, callback: F) -> SubscriptionId
+    where
+        E: Event,
+        F: Fn(&E) + Send + Sync + 'static,
+    {
+        self.add(Subscriber::Sync(Arc::new(callback)))
+    }
+
+    /// Receive every `E` on a channel holding up to `capacity` pending events
+    ///
+    /// Dropping the receiver unsubscribes.
+    pub fn subscribe_channel<E: Event>(&self, capacity: usize) -> mpsc::Receiver<E> {
+        let (tx, rx) = mpsc::channel(capacity.max(1));
+        self.add(Subscriber::Channel(tx));
+        rx
+    }
+
+    /// Remove a subscription; false if it was not found
+    pub fn unsubscribe<E: Event>(&self, id: SubscriptionId) -> bool {
+        let mut topics = self.topics.write().unwrap_or_else(|e| e.into_inner());
+        let Some(subscribers) = topics
+            .get_mut(&TypeId::of::<E>())
+            .and_then(|s| s.downcast_mut::<Subscribers<E>>())
+        else {
+            return false;
+        };
+        let before = subscribers.len();
+        subscribers.retain(|(sid, _)| *sid != id);
+        subscribers.len() != before
+    }
+
+    /// Whether anyone listens to `E`
+    ///
+    /// Lets publishers skip building events nobody receives.
+    pub fn has_subscribers<E: Event>(&self) -> bool {
+        self.topics
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&TypeId::of::<E>())
+            .and_then(|s| s.downcast_ref::<Subscribers<E>>())
+            .is_some_and(|s| !s.is_empty())
+    }
+
+    /// Deliver `event` to every subscriber of `E`, returning how many got it
+    ///
+    /// Subscribers run outside the bus lock, so a callback may publish or
+    /// subscribe itself. Channels whose receiver is gone are removed.
+    pub fn publish<E: Event>(&self, event: E) -> usize {
+        let subscribers: Subscribers<E> = {
+            let topics = self.topics.read().unwrap_or_else(|e| e.into_inner());
+            match topics
+                .get(&TypeId::of::<E>())
+                .and_then(|s| s.downcast_ref::<Subscribers<E>>())
+            {
+                Some(s) if !s.is_empty() => s.clone(),
+                _ => return 0,
+            }
+        };
+
+        let mut delivered = 0;
+        let mut closed = Vec::new();
+        for (id, subscriber) in &subscribers {
+            match subscriber {
+                Subscriber::Sync(callback) => {
+                    callback(&event);
+                    delivered += 1;
+                }
+                Subscriber::Channel(tx) => match tx.try_send(event.clone()) {
+                    Ok(()) => delivered += 1,
+                    Err(mpsc::error::TrySendError::Full(_)) => {
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(mpsc::error::TrySendError::Closed(_)) => closed.push(*id),
+                },
+            }
+        }
+
+        for id in closed {
+            self.unsubscribe::<E>(id);
+        }
+        delivered
+    }
+
+    /// Events dropped so far because a channel subscriber was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn add<E: Event>(&self, subscriber: Subscriber<E>) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let mut topics = self.topics.write().unwrap_or_else(|e| e.into_inner());
+        topics
+            .entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(Subscribers::<E>::new()))
+            .downcast_mut::<Subscribers<E>>()
+            .expect("topic keyed by its own TypeId")
+            .push((id, subscriber));
+        id
+    }
+}
+
+static GLOBAL: Lazy<EventBus> = Lazy::new(EventBus::new);
+
+/// Process-wide bus used by the built-in publishers
+pub fn global() -> &'static EventBus {
+    &GLOBAL
+}
+
+/// Publish an alert on [`global()`] after attaching its runbook
+///
+/// The alert is annotated by [`crate::runbook::global()`] so sinks receive
+/// remediation text and automation hook state with the payload.
+pub fn publish_alert(mut alert: AlertRaised) -> usize {
+    let bus = global();
+    if !bus.has_subscribers::<AlertRaised>() {
+        return 0;
+    }
+    crate::runbook::global().annotate(&mut alert);
+    bus.publish(alert)
+}
+
+/// An analysis run finished
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalysisCompleted {
+    /// Analysis ID
+    pub analysis_id: String,
+    /// Overall risk score (0.0 to 1.0)
+    pub risk_score: f64,
+    /// Confidence level (0.0 to 1.0)
+    pub confidence: f64,
+    /// Number of alerts the run raised
+    pub alert_count: usize,
+}
+
+impl Event for AnalysisCompleted {
+    const TOPIC: &'static str = "analysis.completed";
+}
+
+/// An alert was raised
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRaised {
+    /// Subsystem that raised it, e.g. `analysis` or `defense.consistency`
+    pub source: String,
+    /// Severity, lowercase (`info`, `warning`, `critical`, ...)
+    pub severity: String,
+    /// Category, lowercase (`anomaly`, `configuration`, ...)
+    pub category: String,
+    /// Human-readable message
+    pub message: String,
+    /// Time the alert was raised
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Additional context
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+impl AlertRaised {
+    /// Alert raised now, without metadata
+    pub fn new(
+        source: impl Into<String>,
+        severity: impl Into<String>,
+        category: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            source: source.into(),
+            severity: severity.into(),
+            category: category.into(),
+            message: message.into(),
+            timestamp: chrono::Utc::now(),
+            metadata: HashMap::new(),
+        }
+    }
+}
+
+impl Event for AlertRaised {
+    const TOPIC: &'static str = "alert.raised";
+}
+
+/// The self-learning analyzer promoted a stable unknown fingerprint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FingerprintLearned {
+    /// Fingerprint type (tls/http/tcp)
+    pub fingerprint_type: String,
+    /// Fingerprint ID
+    pub fingerprint_id: String,
+    /// Observations before promotion
+    pub observation_count: u64,
+    /// Stability score (0.0-1.0)
+    pub stability_score: f64,
+    /// Candidate row ID, when it was stored
+    pub candidate_id: Option<i64>,
+}
+
+impl Event for FingerprintLearned {
+    const TOPIC: &'static str = "learner.fingerprint_learned";
+}
+
+/// A metric sample was recorded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricRecorded {
+    /// Metric name, as exported
+    pub name: String,
+    /// Label values, in the metric's label order
+    pub labels: Vec<String>,
+    /// Observed value; 1.0 for counter increments
+    pub value: f64,
+}
+
+impl Event for MetricRecorded {
+    const TOPIC: &'static str = "metrics.recorded";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_topics_are_typed() {
+        let bus = EventBus::new();
+        let alerts = Arc::new(AtomicUsize::new(0));
+        let counter = alerts.clone();
+        let id = bus.subscribe(move |_: &AlertRaised| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        assert_eq!(
+            bus.publish(AlertRaised::new("test", "info", "anomaly", "a")),
+            1
+        );
+        let metric = MetricRecorded {
+            name: "m".to_string(),
+            labels: vec![],
+            value: 1.0,
+        };
+        assert_eq!(bus.publish(metric), 0);
+        assert!(bus.has_subscribers::<AlertRaised>());
+        assert!(!bus.has_subscribers::<MetricRecorded>());
+
+        assert!(bus.unsubscribe::<AlertRaised>(id));
+        assert_eq!(
+            bus.publish(AlertRaised::new("test", "info", "anomaly", "b")),
+            0
+        );
+        assert_eq!(alerts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_channel_subscriber_drops_when_full() {
+        let bus = EventBus::new();
+        let mut rx = bus.subscribe_channel::<AlertRaised>(1);
+        assert_eq!(
+            bus.publish(AlertRaised::new("test", "info", "anomaly", "1")),
+            1
+        );
+        assert_eq!(
+            bus.publish(AlertRaised::new("test", "info", "anomaly", "2")),
+            0
+        );
+        assert_eq!(bus.dropped(), 1);
+        assert_eq!(rx.try_recv().unwrap().message, "1");
+
+        drop(rx);
+        bus.publish(AlertRaised::new("test", "info", "anomaly", "3"));
+        assert!(!bus.has_subscribers::<AlertRaised>());
+    }
+}
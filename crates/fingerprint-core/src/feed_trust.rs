@@ -0,0 +1,670 @@
+//! Signed profile/model feed updates with a transparency log
+//!
+//! Profiles and models fetched from a remote feed decide which fingerprints we
+//! emit and accept, so a compromised feed must not be able to change them
+//! silently. [`FeedStore::apply`] only writes an update that is
+//!
+//! - signed (Ed25519) by a key pinned in the [`TrustStore`], and
+//! - newer than the last applied version of the same artifact (no rollback).
+//!
+//! Every applied update is appended to a hash-chained [`TransparencyLog`]
+//! (JSON lines, one [`LogEntry`] each). Editing, dropping or reordering an entry
+//! breaks the chain, and [`FeedStore::audit`] re-verifies the chain, every
+//! signature and the artifacts on disk against the last logged content hash.
+
+use crate::data_dirs::DataDirs;
+use chrono::{DateTime, Utc};
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Domain separator of signed update messages
+const SIGNING_CONTEXT: &[u8] = b"fingerprint-feed-update-v1";
+
+/// `prev_hash` of the first log entry
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Feed trust errors
+#[derive(Debug, thiserror::Error)]
+pub enum FeedTrustError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("update signed by unpinned key {0}")]
+    UntrustedKey(String),
+    #[error("invalid signature on {0}")]
+    BadSignature(String),
+    #[error("{artifact} version {version} is not newer than applied version {applied}")]
+    Rollback {
+        artifact: String,
+        version: u64,
+        applied: u64,
+    },
+    #[error("invalid artifact name: {0}")]
+    InvalidName(String),
+    #[error("invalid public key: {0}")]
+    InvalidKey(String),
+    #[error("transparency log corrupt at entry {seq}: {reason}")]
+    CorruptLog { seq: u64, reason: String },
+}
+
+/// Artifact family carried by the feed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedKind {
+    Profiles,
+    Model,
+}
+
+impl FeedKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FeedKind::Profiles => "profiles",
+            FeedKind::Model => "model",
+        }
+    }
+}
+
+/// One signed artifact as delivered by the feed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedUpdate {
+    pub kind: FeedKind,
+    /// Artifact file name, e.g. `chrome.json` or `browser_classifier.bin`
+    pub name: String,
+    /// Monotonic per artifact
+    pub version: u64,
+    pub payload: Vec<u8>,
+    /// [`key_id`] of the signing key
+    pub key_id: String,
+    /// Ed25519 signature over [`FeedUpdate::signed_message`]
+    pub signature: Vec<u8>,
+}
+
+impl FeedUpdate {
+    /// Bytes the feed signs: context, kind, name, version and payload hash
+    pub fn signed_message(
+        kind: FeedKind,
+        name: &str,
+        version: u64,
+        payload_sha256: &str,
+    ) -> Vec<u8> {
+        let mut message = SIGNING_CONTEXT.to_vec();
+        for field in [kind.as_str(), name, &version.to_string(), payload_sha256] {
+            message.push(0);
+            message.extend_from_slice(field.as_bytes());
+        }
+        message
+    }
+
+    /// `kind/name`
+    pub fn artifact(&self) -> String {
+        format!("{}/{}", self.kind.as_str(), self.name)
+    }
+
+    pub fn payload_sha256(&self) -> String {
+        sha256_hex(&self.payload)
+    }
+}
+
+/// Identifier of a public key: first 16 hex characters of its SHA-256
+pub fn key_id(public_key: &[u8]) -> String {
+    sha256_hex(public_key)[..16].to_string()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Ed25519 public keys the feed may be signed with
+#[derive(Debug, Clone, Default)]
+pub struct TrustStore {
+    keys: HashMap<String, Vec<u8>>,
+}
+
+impl TrustStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pin a raw 32-byte Ed25519 public key, returning its key ID
+    pub fn pin(&mut self, public_key: &[u8]) -> Result<String, FeedTrustError> {
+        if public_key.len() != 32 {
+            return Err(FeedTrustError::InvalidKey(format!(
+                "expected 32 bytes, got {}",
+                public_key.len()
+            )));
+        }
+        let id = key_id(public_key);
+        self.keys.insert(id.clone(), public_key.to_vec());
+        Ok(id)
+    }
+
+    /// Pin a hex-encoded public key
+    pub fn pin_hex(&mut self, public_key: &str) -> Result<String, FeedTrustError> {
+        let key = hex::decode(public_key.trim())
+            .map_err(|e| FeedTrustError::InvalidKey(e.to_string()))?;
+        self.pin(&key)
+    }
+
+    /// Pinned key IDs
+    pub fn key_ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.keys.keys().map(String::as_str).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    fn verify_signature(
+        &self,
+        artifact: &str,
+        key_id: &str,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), FeedTrustError> {
+        let key = self
+            .keys
+            .get(key_id)
+            .ok_or_else(|| FeedTrustError::UntrustedKey(key_id.to_string()))?;
+        UnparsedPublicKey::new(&ED25519, key)
+            .verify(message, signature)
+            .map_err(|_| FeedTrustError::BadSignature(artifact.to_string()))
+    }
+
+    /// Check an update's signature against the pinned keys
+    pub fn verify(&self, update: &FeedUpdate) -> Result<(), FeedTrustError> {
+        let message = FeedUpdate::signed_message(
+            update.kind,
+            &update.name,
+            update.version,
+            &update.payload_sha256(),
+        );
+        self.verify_signature(
+            &update.artifact(),
+            &update.key_id,
+            &message,
+            &update.signature,
+        )
+    }
+}
+
+/// One applied update in the transparency log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// Position in the log, starting at 0
+    pub seq: u64,
+    pub applied_at: DateTime<Utc>,
+    pub kind: FeedKind,
+    pub name: String,
+    pub version: u64,
+    pub content_sha256: String,
+    pub key_id: String,
+    /// Hex-encoded signature, kept so the entry can be re-verified
+    pub signature: String,
+    /// `entry_hash` of the previous entry ([`GENESIS_HASH`] for the first)
+    pub prev_hash: String,
+    /// SHA-256 over `prev_hash` and the fields above
+    pub entry_hash: String,
+}
+
+impl LogEntry {
+    fn compute_hash(&self) -> String {
+        let body = format!(
+            "{}|{}|{}|{}|{}|{}|{}|{}|{}",
+            self.prev_hash,
+            self.seq,
+            self.applied_at.to_rfc3339(),
+            self.kind.as_str(),
+            self.name,
+            self.version,
+            self.content_sha256,
+            self.key_id,
+            self.signature
+        );
+        sha256_hex(body.as_bytes())
+    }
+
+    pub fn artifact(&self) -> String {
+        format!("{}/{}", self.kind.as_str(), self.name)
+    }
+}
+
+/// Append-only, hash-chained log of applied updates
+#[derive(Debug)]
+pub struct TransparencyLog {
+    path: PathBuf,
+    entries: Vec<LogEntry>,
+}
+
+impl TransparencyLog {
+    /// Open the log at `path`, verifying its hash chain
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, FeedTrustError> {
+        let path = path.into();
+        let entries = Self::read_entries(&path)?;
+        Self::verify_chain(&entries)?;
+        Ok(Self { path, entries })
+    }
+
+    fn read_entries(path: &Path) -> Result<Vec<LogEntry>, FeedTrustError> {
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut entries = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(&line).map_err(|e| FeedTrustError::CorruptLog {
+                seq: index as u64,
+                reason: e.to_string(),
+            })?;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Check sequence numbers, back links and entry hashes
+    pub fn verify_chain(entries: &[LogEntry]) -> Result<(), FeedTrustError> {
+        let mut prev_hash = GENESIS_HASH;
+        for (index, entry) in entries.iter().enumerate() {
+            let corrupt = |reason: &str| FeedTrustError::CorruptLog {
+                seq: index as u64,
+                reason: reason.to_string(),
+            };
+            if entry.seq != index as u64 {
+                return Err(corrupt("sequence gap"));
+            }
+            if entry.prev_hash != prev_hash {
+                return Err(corrupt("previous hash mismatch"));
+            }
+            if entry.entry_hash != entry.compute_hash() {
+                return Err(corrupt("entry hash mismatch"));
+            }
+            prev_hash = &entry.entry_hash;
+        }
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[LogEntry] {
+        &self.entries
+    }
+
+    /// Hash of the newest entry; publishing it lets others detect a rewritten log
+    pub fn head(&self) -> &str {
+        self.entries
+            .last()
+            .map(|e| e.entry_hash.as_str())
+            .unwrap_or(GENESIS_HASH)
+    }
+
+    /// Latest entry per artifact
+    pub fn latest(&self) -> BTreeMap<String, &LogEntry> {
+        let mut latest = BTreeMap::new();
+        for entry in &self.entries {
+            latest.insert(entry.artifact(), entry);
+        }
+        latest
+    }
+
+    fn append(&mut self, update: &FeedUpdate) -> Result<LogEntry, FeedTrustError> {
+        let mut entry = LogEntry {
+            seq: self.entries.len() as u64,
+            applied_at: Utc::now(),
+            kind: update.kind,
+            name: update.name.clone(),
+            version: update.version,
+            content_sha256: update.payload_sha256(),
+            key_id: update.key_id.clone(),
+            signature: hex::encode(&update.signature),
+            prev_hash: self.head().to_string(),
+            entry_hash: String::new(),
+        };
+        entry.entry_hash = entry.compute_hash();
+
+        let line = serde_json::to_string(&entry).map_err(io::Error::other)?;
+        let path = DataDirs::prepare_file(self.path.clone())?;
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", line)?;
+        file.sync_all()?;
+        self.entries.push(entry.clone());
+        Ok(entry)
+    }
+}
+
+/// Something the audit found wrong with the log or the local artifacts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum AuditProblem {
+    /// The log's hash chain does not verify
+    BrokenChain { seq: u64, reason: String },
+    /// Logged update signed by a key that is no longer pinned
+    UntrustedKey { seq: u64, key_id: String },
+    /// Logged signature does not verify
+    BadSignature { seq: u64 },
+    /// Logged version not newer than an earlier one for the same artifact
+    VersionRegression { seq: u64 },
+    /// Artifact on disk differs from its last logged content
+    Modified { artifact: String },
+    /// Logged artifact missing on disk
+    Missing { artifact: String },
+    /// Artifact on disk that no logged update produced
+    Unlogged { artifact: String },
+}
+
+/// Outcome of [`FeedStore::audit`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditReport {
+    pub entries: usize,
+    pub artifacts: usize,
+    /// Head hash of the verified log
+    pub head: String,
+    pub problems: Vec<AuditProblem>,
+}
+
+impl AuditReport {
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Verified artifacts on disk plus their transparency log
+#[derive(Debug)]
+pub struct FeedStore {
+    dir: PathBuf,
+    trust: TrustStore,
+    log: TransparencyLog,
+}
+
+impl FeedStore {
+    /// Artifacts under `dir` (`<kind>/<name>`), log at `log_path`
+    pub fn open(
+        dir: impl Into<PathBuf>,
+        log_path: impl Into<PathBuf>,
+        trust: TrustStore,
+    ) -> Result<Self, FeedTrustError> {
+        Ok(Self {
+            dir: dir.into(),
+            trust,
+            log: TransparencyLog::open(log_path)?,
+        })
+    }
+
+    /// Store in the default data and state locations
+    pub fn open_default(dirs: &DataDirs, trust: TrustStore) -> Result<Self, FeedTrustError> {
+        Self::open(dirs.feed_dir(), dirs.feed_log(), trust)
+    }
+
+    pub fn log(&self) -> &TransparencyLog {
+        &self.log
+    }
+
+    /// Location of an artifact
+    pub fn artifact_path(&self, kind: FeedKind, name: &str) -> PathBuf {
+        self.dir.join(kind.as_str()).join(name)
+    }
+
+    /// Verify an update, write its payload and log it
+    pub fn apply(&mut self, update: &FeedUpdate) -> Result<LogEntry, FeedTrustError> {
+        let valid_name = !update.name.is_empty()
+            && update.name != "."
+            && update.name != ".."
+            && !update.name.contains(['/', '\\']);
+        if !valid_name {
+            return Err(FeedTrustError::InvalidName(update.name.clone()));
+        }
+        self.trust.verify(update)?;
+        let artifact = update.artifact();
+        if let Some(applied) = self.log.latest().get(&artifact) {
+            if update.version <= applied.version {
+                return Err(FeedTrustError::Rollback {
+                    artifact,
+                    version: update.version,
+                    applied: applied.version,
+                });
+            }
+        }
+
+        // write-then-rename so readers never see a partial artifact
+        let path = DataDirs::prepare_file(self.artifact_path(update.kind, &update.name))?;
+        let staging = path.with_extension("partial");
+        fs::write(&staging, &update.payload)?;
+        fs::rename(&staging, &path)?;
+        self.log.append(update)
+    }
+
+    /// Re-verify the log and compare the artifacts on disk against it
+    pub fn audit(&self) -> Result<AuditReport, FeedTrustError> {
+        audit_feed_state(&self.dir, &self.log.path, &self.trust)
+    }
+}
+
+/// Audit artifacts under `dir` against the log at `log_path`
+///
+/// Unlike [`FeedStore::open`] this does not refuse a broken log; chain damage is
+/// reported as [`AuditProblem::BrokenChain`] next to everything else found.
+pub fn audit_feed_state(
+    dir: &Path,
+    log_path: &Path,
+    trust: &TrustStore,
+) -> Result<AuditReport, FeedTrustError> {
+    let mut problems = Vec::new();
+    let entries = match TransparencyLog::read_entries(log_path) {
+        Ok(entries) => entries,
+        Err(FeedTrustError::CorruptLog { seq, reason }) => {
+            problems.push(AuditProblem::BrokenChain { seq, reason });
+            Vec::new()
+        }
+        Err(e) => return Err(e),
+    };
+    if let Err(FeedTrustError::CorruptLog { seq, reason }) = TransparencyLog::verify_chain(&entries)
+    {
+        problems.push(AuditProblem::BrokenChain { seq, reason });
+    }
+
+    let mut versions: HashMap<String, u64> = HashMap::new();
+    let mut latest: BTreeMap<String, &LogEntry> = BTreeMap::new();
+    for entry in &entries {
+        let artifact = entry.artifact();
+        let message = FeedUpdate::signed_message(
+            entry.kind,
+            &entry.name,
+            entry.version,
+            &entry.content_sha256,
+        );
+        let signature = hex::decode(&entry.signature).unwrap_or_default();
+        match trust.verify_signature(&artifact, &entry.key_id, &message, &signature) {
+            Err(FeedTrustError::UntrustedKey(key_id)) => {
+                problems.push(AuditProblem::UntrustedKey {
+                    seq: entry.seq,
+                    key_id,
+                })
+            }
+            Err(_) => problems.push(AuditProblem::BadSignature { seq: entry.seq }),
+            Ok(()) => {}
+        }
+        if versions
+            .insert(artifact.clone(), entry.version)
+            .is_some_and(|previous| entry.version <= previous)
+        {
+            problems.push(AuditProblem::VersionRegression { seq: entry.seq });
+        }
+        latest.insert(artifact, entry);
+    }
+
+    for (artifact, entry) in &latest {
+        match fs::read(dir.join(entry.kind.as_str()).join(&entry.name)) {
+            Ok(content) if sha256_hex(&content) == entry.content_sha256 => {}
+            Ok(_) => problems.push(AuditProblem::Modified {
+                artifact: artifact.clone(),
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => problems.push(AuditProblem::Missing {
+                artifact: artifact.clone(),
+            }),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    for kind in [FeedKind::Profiles, FeedKind::Model] {
+        let Ok(read_dir) = fs::read_dir(dir.join(kind.as_str())) else {
+            continue;
+        };
+        for file in read_dir {
+            let name = file?.file_name().to_string_lossy().into_owned();
+            let artifact = format!("{}/{}", kind.as_str(), name);
+            if !latest.contains_key(&artifact) {
+                problems.push(AuditProblem::Unlogged { artifact });
+            }
+        }
+    }
+
+    Ok(AuditReport {
+        entries: entries.len(),
+        artifacts: latest.len(),
+        head: entries
+            .last()
+            .map(|e| e.entry_hash.clone())
+            .unwrap_or_else(|| GENESIS_HASH.to_string()),
+        problems,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    fn keypair() -> Ed25519KeyPair {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    }
+
+    fn signed(key: &Ed25519KeyPair, name: &str, version: u64, payload: &[u8]) -> FeedUpdate {
+        let message =
+            FeedUpdate::signed_message(FeedKind::Profiles, name, version, &sha256_hex(payload));
+        FeedUpdate {
+            kind: FeedKind::Profiles,
+            name: name.to_string(),
+            version,
+            payload: payload.to_vec(),
+            key_id: key_id(key.public_key().as_ref()),
+            signature: key.sign(&message).as_ref().to_vec(),
+        }
+    }
+
+    fn store(dir: &Path, key: &Ed25519KeyPair) -> FeedStore {
+        let mut trust = TrustStore::new();
+        trust.pin(key.public_key().as_ref()).unwrap();
+        FeedStore::open(dir.join("feeds"), dir.join("feed.jsonl"), trust).unwrap()
+    }
+
+    #[test]
+    fn test_apply_requires_pinned_signature_and_newer_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let pinned = keypair();
+        let mut store = store(dir.path(), &pinned);
+
+        store
+            .apply(&signed(&pinned, "chrome.json", 1, b"v1"))
+            .unwrap();
+        assert_eq!(
+            fs::read(store.artifact_path(FeedKind::Profiles, "chrome.json")).unwrap(),
+            b"v1"
+        );
+
+        let attacker = keypair();
+        assert!(matches!(
+            store.apply(&signed(&attacker, "chrome.json", 2, b"evil")),
+            Err(FeedTrustError::UntrustedKey(_))
+        ));
+        let mut tampered = signed(&pinned, "chrome.json", 2, b"v2");
+        tampered.payload = b"evil".to_vec();
+        assert!(matches!(
+            store.apply(&tampered),
+            Err(FeedTrustError::BadSignature(_))
+        ));
+        assert!(matches!(
+            store.apply(&signed(&pinned, "chrome.json", 1, b"old")),
+            Err(FeedTrustError::Rollback { applied: 1, .. })
+        ));
+        assert!(matches!(
+            store.apply(&signed(&pinned, "../escape", 3, b"x")),
+            Err(FeedTrustError::InvalidName(_))
+        ));
+
+        store
+            .apply(&signed(&pinned, "chrome.json", 2, b"v2"))
+            .unwrap();
+        assert_eq!(store.log().entries().len(), 2);
+        let report = store.audit().unwrap();
+        assert!(report.is_clean(), "{:?}", report.problems);
+        assert_eq!(report.head, store.log().head());
+
+        // reopening verifies the chain
+        let reopened = self::store(dir.path(), &pinned);
+        assert_eq!(reopened.log().entries(), store.log().entries());
+    }
+
+    #[test]
+    fn test_audit_detects_tampering() {
+        let dir = tempfile::tempdir().unwrap();
+        let pinned = keypair();
+        let mut store = store(dir.path(), &pinned);
+        store
+            .apply(&signed(&pinned, "chrome.json", 1, b"v1"))
+            .unwrap();
+        store
+            .apply(&signed(&pinned, "firefox.json", 1, b"v1"))
+            .unwrap();
+
+        fs::write(
+            store.artifact_path(FeedKind::Profiles, "chrome.json"),
+            b"evil",
+        )
+        .unwrap();
+        fs::remove_file(store.artifact_path(FeedKind::Profiles, "firefox.json")).unwrap();
+        fs::write(store.artifact_path(FeedKind::Profiles, "safari.json"), b"?").unwrap();
+        let problems = store.audit().unwrap().problems;
+        assert!(problems.contains(&AuditProblem::Modified {
+            artifact: "profiles/chrome.json".to_string()
+        }));
+        assert!(problems.contains(&AuditProblem::Missing {
+            artifact: "profiles/firefox.json".to_string()
+        }));
+        assert!(problems.contains(&AuditProblem::Unlogged {
+            artifact: "profiles/safari.json".to_string()
+        }));
+
+        // rotating the pinned key flags everything the old key signed
+        let rotated = self::store(dir.path(), &keypair());
+        assert!(rotated
+            .audit()
+            .unwrap()
+            .problems
+            .iter()
+            .any(|p| matches!(p, AuditProblem::UntrustedKey { seq: 1, .. })));
+
+        // rewriting history breaks the chain, both for audit and for reopening
+        let log_path = dir.path().join("feed.jsonl");
+        let log = fs::read_to_string(&log_path).unwrap();
+        fs::write(&log_path, log.replacen("\"version\":1", "\"version\":7", 1)).unwrap();
+        assert!(store
+            .audit()
+            .unwrap()
+            .problems
+            .iter()
+            .any(|p| matches!(p, AuditProblem::BrokenChain { seq: 0, .. })));
+        assert!(matches!(
+            TransparencyLog::open(&log_path),
+            Err(FeedTrustError::CorruptLog { seq: 0, .. })
+        ));
+    }
+}
@@ -3,6 +3,7 @@
 //! Define unified fingerprint abstractions, support TLS, HTTP, TCP and other multiple fingerprint types.
 
 use crate::metadata::FingerprintMetadata;
+use crate::similarity::{BootstrapConfig, FeatureScore, SimilarityEstimate};
 
 /// Fingerprint type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -13,6 +14,8 @@ pub enum FingerprintType {
     Http,
     /// TCP fingerprint
     Tcp,
+    /// Response content fingerprint (favicon, DOM structure, scripts)
+    Content,
 }
 
 impl FingerprintType {
@@ -22,6 +25,7 @@ impl FingerprintType {
             Self::Tls => "tls",
             Self::Http => "http",
             Self::Tcp => "tcp",
+            Self::Content => "content",
         }
     }
 }
@@ -56,6 +60,31 @@ pub trait Fingerprint: Send + Sync {
 
     /// Get fingerprint string represent (for debug and log)
     fn to_string(&self) -> String;
+
+    /// Named raw fields read by feature extractors; unset optional fields are omitted
+    fn fields(&self) -> Vec<(&'static str, FieldValue)> {
+        Vec::new()
+    }
+}
+
+/// Raw value of one fingerprint field
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    /// Count, size, TTL, version...
+    Number(f64),
+    /// Flag
+    Bool(bool),
+    /// Categorical text (User-Agent, ALPN)
+    Text(String),
+    /// Set or sequence of categorical items (cipher suites, header names)
+    List(Vec<String>),
+}
+
+impl FieldValue {
+    /// `u16` code points as lowercase hex items, e.g. `1301`
+    pub fn codes(values: &[u16]) -> Self {
+        Self::List(values.iter().map(|v| format!("{:04x}", v)).collect())
+    }
 }
 
 /// Fingerprint compare result
@@ -123,49 +152,18 @@ impl FingerprintComparator {
         }
 
         // Calculate field-level similarity for softer matching
-        let meta1 = f1.metadata();
-        let meta2 = f2.metadata();
+        let fields = Self::field_matches(f1.metadata(), f2.metadata());
+        let total_fields = fields.len();
+        let matching_count = fields.iter().filter(|(_, matched)| *matched).count();
 
         let mut matched_fields = Vec::new();
         let mut unmatched_fields = Vec::new();
-        let mut total_fields = 0;
-        let mut matching_count = 0;
-
-        // Browser type field comparison
-        total_fields += 1;
-        if meta1.browser_type == meta2.browser_type {
-            matching_count += 1;
-            matched_fields.push("browser_type".to_string());
-        } else {
-            unmatched_fields.push("browser_type".to_string());
-        }
-
-        // Operating system type field comparison
-        total_fields += 1;
-        if meta1.os_type == meta2.os_type {
-            matching_count += 1;
-            matched_fields.push("os_type".to_string());
-        } else {
-            unmatched_fields.push("os_type".to_string());
-        }
-
-        // Confidence field comparison (within 0.1 threshold)
-        total_fields += 1;
-        if (meta1.confidence - meta2.confidence).abs() < 0.1 {
-            matching_count += 1;
-            matched_fields.push("confidence".to_string());
-        } else {
-            unmatched_fields.push("confidence".to_string());
-        }
-
-        // Tags field comparison (any overlap counts as match)
-        total_fields += 1;
-        let has_common_tags = meta1.tags.iter().any(|tag| meta2.tags.contains(tag));
-        if has_common_tags || (meta1.tags.is_empty() && meta2.tags.is_empty()) {
-            matching_count += 1;
-            matched_fields.push("tags".to_string());
-        } else {
-            unmatched_fields.push("tags".to_string());
+        for (name, matched) in fields {
+            if matched {
+                matched_fields.push(name.to_string());
+            } else {
+                unmatched_fields.push(name.to_string());
+            }
         }
 
         // Calculate similarity based on matching fields
@@ -180,6 +178,49 @@ impl FingerprintComparator {
             unmatched_fields,
         }
     }
+
+    /// compare two fingerprint like [`compare`](Self::compare), with a
+    /// bootstrap confidence interval over the compared fields
+    pub fn compare_with_confidence(
+        f1: &dyn Fingerprint,
+        f2: &dyn Fingerprint,
+        config: &BootstrapConfig,
+    ) -> SimilarityEstimate {
+        if f1.fingerprint_type() != f2.fingerprint_type() {
+            return SimilarityEstimate::exact(0.0);
+        }
+        if f1.similar_to(f2) {
+            return SimilarityEstimate::exact(1.0);
+        }
+
+        let scores: Vec<FeatureScore> = Self::field_matches(f1.metadata(), f2.metadata())
+            .into_iter()
+            .map(|(name, matched)| FeatureScore::new(name, 1.0, if matched { 1.0 } else { 0.0 }))
+            .collect();
+        config.estimate(&scores)
+    }
+
+    /// Per-field match of two metadata, all fields weighted equally
+    fn field_matches(
+        meta1: &FingerprintMetadata,
+        meta2: &FingerprintMetadata,
+    ) -> [(&'static str, bool); 4] {
+        // Tags field comparison (any overlap counts as match)
+        let has_common_tags = meta1.tags.iter().any(|tag| meta2.tags.contains(tag));
+        [
+            ("browser_type", meta1.browser_type == meta2.browser_type),
+            ("os_type", meta1.os_type == meta2.os_type),
+            // Confidence field comparison (within 0.1 threshold)
+            (
+                "confidence",
+                (meta1.confidence - meta2.confidence).abs() < 0.1,
+            ),
+            (
+                "tags",
+                has_common_tags || (meta1.tags.is_empty() && meta2.tags.is_empty()),
+            ),
+        ]
+    }
 }
 
 #[cfg(test)]
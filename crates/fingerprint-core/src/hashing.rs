@@ -0,0 +1,426 @@
+//! Pluggable hashing backends
+//!
+//! Two purposes, each with a selectable backend:
+//!
+//! - [`HashPurpose::Dedup`]: fast non-cryptographic hashing for in-memory keys
+//!   and deduplication (default xxh3)
+//! - [`HashPurpose::Content`]: collision-resistant content hashes that are
+//!   persisted or compared across systems, e.g. canvas/font/storage hashes and
+//!   fingerprint IDs (default blake3)
+//!
+//! Digests render as `<algorithm>:<hex>` so stored values carry their backend.
+//! Untagged values written by earlier releases are handled by
+//! [`migrate_stored_hash`].
+//!
+//! ```
+//! use fingerprint_core::hashing::{FingerprintHasher, HashAlgorithm, HashPurpose};
+//!
+//! let mut hasher = FingerprintHasher::for_purpose(HashPurpose::Content);
+//! hasher.write_str("Arial");
+//! let digest = hasher.finish();
+//! assert_eq!(digest.algorithm(), HashAlgorithm::Blake3);
+//! assert!(digest.to_string().starts_with("blake3:"));
+//! ```
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+use xxhash_rust::xxh3::Xxh3;
+
+/// Hash algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// XXH3 64-bit (non-cryptographic)
+    Xxh3,
+    /// BLAKE3 256-bit
+    Blake3,
+    /// SHA-256, used by fingerprint IDs before backends were selectable
+    Sha256,
+}
+
+impl HashAlgorithm {
+    /// Name used in tagged digests
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Xxh3 => "xxh3",
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Sha256 => "sha256",
+        }
+    }
+
+    /// Digest length in bytes
+    pub fn output_len(self) -> usize {
+        match self {
+            HashAlgorithm::Xxh3 => 8,
+            HashAlgorithm::Blake3 | HashAlgorithm::Sha256 => 32,
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = HashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "xxh3" => Ok(HashAlgorithm::Xxh3),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            other => Err(HashError::UnknownAlgorithm(other.to_string())),
+        }
+    }
+}
+
+/// What a hash is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashPurpose {
+    /// In-memory keys and deduplication
+    Dedup,
+    /// Persisted content hashes and fingerprint IDs
+    Content,
+}
+
+/// Backend selection per purpose
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashConfig {
+    /// Backend for [`HashPurpose::Dedup`]
+    pub dedup: HashAlgorithm,
+    /// Backend for [`HashPurpose::Content`]
+    pub content: HashAlgorithm,
+}
+
+impl HashConfig {
+    /// Built-in defaults: xxh3 for dedup, blake3 for content
+    pub const DEFAULT: HashConfig = HashConfig {
+        dedup: HashAlgorithm::Xxh3,
+        content: HashAlgorithm::Blake3,
+    };
+
+    /// Backend for `purpose`
+    pub fn algorithm(&self, purpose: HashPurpose) -> HashAlgorithm {
+        match purpose {
+            HashPurpose::Dedup => self.dedup,
+            HashPurpose::Content => self.content,
+        }
+    }
+}
+
+impl Default for HashConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+static CONFIG: RwLock<HashConfig> = RwLock::new(HashConfig::DEFAULT);
+
+/// Process-wide backend selection
+pub fn config() -> HashConfig {
+    *CONFIG.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Select backends for the whole process
+///
+/// Hashes already stored under another backend keep verifying through
+/// [`migrate_stored_hash`], since every digest records its algorithm.
+pub fn configure(config: HashConfig) {
+    *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = config;
+}
+
+/// Hashing error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HashError {
+    /// Algorithm name not recognised
+    UnknownAlgorithm(String),
+    /// Value is not `<algorithm>:<hex>`
+    Malformed(String),
+}
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashError::UnknownAlgorithm(name) => write!(f, "Unknown hash algorithm: {}", name),
+            HashError::Malformed(value) => write!(f, "Malformed hash digest: {}", value),
+        }
+    }
+}
+
+impl std::error::Error for HashError {}
+
+/// Hash output tagged with the algorithm that produced it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HashDigest {
+    algorithm: HashAlgorithm,
+    bytes: Vec<u8>,
+}
+
+impl HashDigest {
+    /// Algorithm that produced this digest
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Raw digest bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Lowercase hex without the algorithm tag
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.bytes)
+    }
+
+    /// First eight bytes as a big-endian integer, for in-memory keys
+    pub fn as_u64(&self) -> u64 {
+        let mut prefix = [0u8; 8];
+        let len = self.bytes.len().min(8);
+        prefix[..len].copy_from_slice(&self.bytes[..len]);
+        u64::from_be_bytes(prefix)
+    }
+}
+
+impl fmt::Display for HashDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.to_hex())
+    }
+}
+
+impl FromStr for HashDigest {
+    type Err = HashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, hex_part) = s
+            .split_once(':')
+            .ok_or_else(|| HashError::Malformed(s.to_string()))?;
+        let algorithm: HashAlgorithm = name.parse()?;
+        let bytes = hex::decode(hex_part).map_err(|_| HashError::Malformed(s.to_string()))?;
+        if bytes.len() != algorithm.output_len() {
+            return Err(HashError::Malformed(s.to_string()));
+        }
+        Ok(HashDigest { algorithm, bytes })
+    }
+}
+
+enum HasherState {
+    Xxh3(Box<Xxh3>),
+    Blake3(Box<blake3::Hasher>),
+    Sha256(Sha256),
+}
+
+/// Incremental hasher over a selectable backend
+///
+/// The `write_*` methods use a fixed big-endian, length-prefixed encoding so
+/// the same logical input hashes identically on every platform; `update`
+/// feeds raw bytes.
+pub struct FingerprintHasher {
+    state: HasherState,
+}
+
+impl FingerprintHasher {
+    /// Hasher using `algorithm`
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        let state = match algorithm {
+            HashAlgorithm::Xxh3 => HasherState::Xxh3(Box::new(Xxh3::new())),
+            HashAlgorithm::Blake3 => HasherState::Blake3(Box::new(blake3::Hasher::new())),
+            HashAlgorithm::Sha256 => HasherState::Sha256(Sha256::new()),
+        };
+        Self { state }
+    }
+
+    /// Hasher using the configured backend for `purpose`
+    pub fn for_purpose(purpose: HashPurpose) -> Self {
+        Self::new(config().algorithm(purpose))
+    }
+
+    /// Algorithm in use
+    pub fn algorithm(&self) -> HashAlgorithm {
+        match self.state {
+            HasherState::Xxh3(_) => HashAlgorithm::Xxh3,
+            HasherState::Blake3(_) => HashAlgorithm::Blake3,
+            HasherState::Sha256(_) => HashAlgorithm::Sha256,
+        }
+    }
+
+    /// Feed raw bytes
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.state {
+            HasherState::Xxh3(h) => h.update(data),
+            HasherState::Blake3(h) => {
+                h.update(data);
+            }
+            HasherState::Sha256(h) => h.update(data),
+        }
+    }
+
+    /// Feed a byte
+    pub fn write_u8(&mut self, value: u8) {
+        self.update(&[value]);
+    }
+
+    /// Feed a big-endian u16
+    pub fn write_u16(&mut self, value: u16) {
+        self.update(&value.to_be_bytes());
+    }
+
+    /// Feed a big-endian u64
+    pub fn write_u64(&mut self, value: u64) {
+        self.update(&value.to_be_bytes());
+    }
+
+    /// Feed a length-prefixed byte string
+    pub fn write_bytes(&mut self, value: &[u8]) {
+        self.write_u64(value.len() as u64);
+        self.update(value);
+    }
+
+    /// Feed a length-prefixed string
+    pub fn write_str(&mut self, value: &str) {
+        self.write_bytes(value.as_bytes());
+    }
+
+    /// Finish and return the tagged digest
+    pub fn finish(self) -> HashDigest {
+        let (algorithm, bytes) = match self.state {
+            HasherState::Xxh3(h) => (HashAlgorithm::Xxh3, h.digest().to_be_bytes().to_vec()),
+            HasherState::Blake3(h) => (HashAlgorithm::Blake3, h.finalize().as_bytes().to_vec()),
+            HasherState::Sha256(h) => (HashAlgorithm::Sha256, h.finalize().to_vec()),
+        };
+        HashDigest { algorithm, bytes }
+    }
+}
+
+/// Hash `data` with the configured backend for `purpose`
+pub fn hash_bytes(purpose: HashPurpose, data: &[u8]) -> HashDigest {
+    let mut hasher = FingerprintHasher::for_purpose(purpose);
+    hasher.update(data);
+    hasher.finish()
+}
+
+/// Outcome of checking a stored hash against its source data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HashUpgrade {
+    /// Stored value is already a digest from the configured backend
+    Current,
+    /// Stored value matches the source; replace it with this digest
+    Upgraded(HashDigest),
+    /// Stored value does not match the source data
+    Mismatch,
+}
+
+/// Check a stored hash and produce its replacement under `target`
+///
+/// `recompute` hashes the source data with a given algorithm. Tagged values
+/// are verified by recomputing with their own algorithm; untagged values from
+/// earlier releases are compared against `legacy`, which reproduces the old
+/// format for the same source data.
+pub fn migrate_stored_hash(
+    stored: &str,
+    target: HashAlgorithm,
+    recompute: impl Fn(HashAlgorithm) -> HashDigest,
+    legacy: impl FnOnce() -> String,
+) -> HashUpgrade {
+    let verified = match stored.parse::<HashDigest>() {
+        Ok(digest) => {
+            if recompute(digest.algorithm()) != digest {
+                return HashUpgrade::Mismatch;
+            }
+            digest.algorithm() == target
+        }
+        Err(_) => {
+            if legacy() != stored {
+                return HashUpgrade::Mismatch;
+            }
+            false
+        }
+    };
+
+    if verified {
+        HashUpgrade::Current
+    } else {
+        HashUpgrade::Upgraded(recompute(target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_with(algorithm: HashAlgorithm, data: &str) -> HashDigest {
+        let mut hasher = FingerprintHasher::new(algorithm);
+        hasher.write_str(data);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_digest_roundtrip() {
+        for algorithm in [
+            HashAlgorithm::Xxh3,
+            HashAlgorithm::Blake3,
+            HashAlgorithm::Sha256,
+        ] {
+            let digest = hash_with(algorithm, "chrome_133");
+            assert_eq!(digest.as_bytes().len(), algorithm.output_len());
+            let parsed: HashDigest = digest.to_string().parse().unwrap();
+            assert_eq!(parsed, digest);
+        }
+
+        assert!("blake3:zz".parse::<HashDigest>().is_err());
+        assert!("md5:00".parse::<HashDigest>().is_err());
+        assert!("deadbeef".parse::<HashDigest>().is_err());
+    }
+
+    #[test]
+    fn test_xxh3_matches_stable_hash() {
+        let mut stable = crate::StableHashBuilder::new();
+        stable.write_str("h2");
+        assert_eq!(
+            hash_with(HashAlgorithm::Xxh3, "h2").as_u64(),
+            stable.finish()
+        );
+    }
+
+    #[test]
+    fn test_migrate_stored_hash() {
+        let recompute = |algorithm| hash_with(algorithm, "Arial:Verdana");
+        let legacy = || recompute(HashAlgorithm::Sha256).to_hex();
+        let current = recompute(HashAlgorithm::Blake3);
+
+        // Untagged legacy value is verified and upgraded
+        let stored = legacy();
+        assert_eq!(
+            migrate_stored_hash(&stored, HashAlgorithm::Blake3, recompute, legacy),
+            HashUpgrade::Upgraded(current.clone())
+        );
+
+        // Current value needs nothing
+        assert_eq!(
+            migrate_stored_hash(
+                &current.to_string(),
+                HashAlgorithm::Blake3,
+                recompute,
+                legacy
+            ),
+            HashUpgrade::Current
+        );
+
+        // Value from another backend is re-hashed
+        let xxh3 = recompute(HashAlgorithm::Xxh3).to_string();
+        assert_eq!(
+            migrate_stored_hash(&xxh3, HashAlgorithm::Blake3, recompute, legacy),
+            HashUpgrade::Upgraded(current)
+        );
+
+        // Anything else does not match the source data
+        assert_eq!(
+            migrate_stored_hash("0123abcd", HashAlgorithm::Blake3, recompute, legacy),
+            HashUpgrade::Mismatch
+        );
+    }
+}
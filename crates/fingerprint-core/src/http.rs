@@ -2,7 +2,8 @@
 //!
 //! define HTTP fingerprintcorecountdatastruct.
 
-use crate::fingerprint::{Fingerprint, FingerprintType};
+use crate::fingerprint::{FieldValue, Fingerprint, FingerprintType};
+use crate::hashing::{self, FingerprintHasher, HashAlgorithm, HashDigest, HashUpgrade};
 use crate::metadata::FingerprintMetadata;
 use crate::stable_hash::StableHashBuilder;
 use std::collections::HashMap;
@@ -61,10 +62,28 @@ impl HttpFingerprint {
         }
     }
 
-    /// Calculatefingerprint ID
+    /// Calculatefingerprint ID with the configured content hash backend
     fn calculate_id(user_agent: &str, headers: &HashMap<String, String>) -> String {
-        use sha2::{Digest, Sha256};
-        let mut hasher = Sha256::new();
+        Self::id_digest(hashing::config().content, user_agent, headers).to_string()
+    }
+
+    /// Checks a stored ID against this fingerprint and returns its replacement
+    ///
+    /// IDs written before hashing backends were selectable are untagged
+    /// SHA-256 hex over the same fields.
+    pub fn migrate_id(&self, stored: &str) -> HashUpgrade {
+        let digest = |algorithm| Self::id_digest(algorithm, &self.user_agent, &self.headers);
+        hashing::migrate_stored_hash(stored, hashing::config().content, digest, || {
+            digest(HashAlgorithm::Sha256).to_hex()
+        })
+    }
+
+    fn id_digest(
+        algorithm: HashAlgorithm,
+        user_agent: &str,
+        headers: &HashMap<String, String>,
+    ) -> HashDigest {
+        let mut hasher = FingerprintHasher::new(algorithm);
         hasher.update(user_agent.as_bytes());
 
         // pair headers performsortbackhash
@@ -75,7 +94,7 @@ impl HttpFingerprint {
             hasher.update(v.as_bytes());
         }
 
-        format!("{:x}", hasher.finalize())
+        hasher.finish()
     }
 
     /// settings HTTP/2 settings
@@ -144,6 +163,46 @@ impl Fingerprint for HttpFingerprint {
     fn to_string(&self) -> String {
         format!("HttpFingerprint(id={}, ua={})", self.id, self.user_agent)
     }
+
+    fn fields(&self) -> Vec<(&'static str, FieldValue)> {
+        let mut header_names: Vec<String> = self
+            .headers
+            .keys()
+            .map(|k| k.to_ascii_lowercase())
+            .collect();
+        header_names.sort();
+        let mut fields = vec![
+            ("user_agent", FieldValue::Text(self.user_agent.clone())),
+            ("header_names", FieldValue::List(header_names)),
+            ("http2", FieldValue::Bool(self.http2_settings.is_some())),
+        ];
+        if let Some(settings) = &self.http2_settings {
+            fields.extend([
+                (
+                    "h2_header_table_size",
+                    FieldValue::Number(settings.header_table_size as f64),
+                ),
+                ("h2_enable_push", FieldValue::Bool(settings.enable_push)),
+                (
+                    "h2_max_concurrent_streams",
+                    FieldValue::Number(settings.max_concurrent_streams as f64),
+                ),
+                (
+                    "h2_initial_window_size",
+                    FieldValue::Number(settings.initial_window_size as f64),
+                ),
+                (
+                    "h2_max_frame_size",
+                    FieldValue::Number(settings.max_frame_size as f64),
+                ),
+                (
+                    "h2_max_header_list_size",
+                    FieldValue::Number(settings.max_header_list_size as f64),
+                ),
+            ]);
+        }
+        fields
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,364 @@
+//! Localized user-facing strings
+//!
+//! Errors, reports and alerts look up their human-readable text in a static
+//! message catalog keyed by a stable code (e.g. `consistency.tls_browser_mismatch`).
+//! Codes never change between releases, so tooling should match on codes and
+//! treat messages as display text only.
+//!
+//! The locale is process-wide and defaults to English; select another with
+//! [`set_locale`] or the `FINGERPRINT_LOCALE` environment variable.
+//!
+//! ```
+//! use fingerprint_core::i18n::{translate, Locale};
+//!
+//! let msg = translate(Locale::En, "report.success_rate", &[]);
+//! assert_eq!(msg, "Success rate");
+//!
+//! let msg = translate(
+//!     Locale::En,
+//!     "consistency.tcp_os_mismatch",
+//!     &[("os", &"Windows")],
+//! );
+//! assert!(msg.contains("Windows"));
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+/// Environment variable read by [`Locale::from_env`]
+pub const LOCALE_ENV: &str = "FINGERPRINT_LOCALE";
+
+/// Supported locale
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Locale {
+    /// English (default)
+    #[default]
+    #[serde(rename = "en")]
+    En,
+    /// Simplified Chinese
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+}
+
+impl Locale {
+    /// All supported locales
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::ZhCn];
+
+    /// BCP 47 tag
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::ZhCn => "zh-CN",
+        }
+    }
+
+    /// Locale named by `FINGERPRINT_LOCALE`, if set and recognised
+    pub fn from_env() -> Option<Self> {
+        std::env::var(LOCALE_ENV).ok()?.parse().ok()
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+impl FromStr for Locale {
+    type Err = UnknownLocale;
+
+    /// Accepts BCP 47 tags and POSIX locale names (`en_US.UTF-8`, `zh_CN`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Ok(Locale::En),
+            "zh" => Ok(Locale::ZhCn),
+            _ => Err(UnknownLocale(s.to_string())),
+        }
+    }
+}
+
+/// Locale name not recognised
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownLocale(pub String);
+
+impl fmt::Display for UnknownLocale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown locale: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownLocale {}
+
+static LOCALE: RwLock<Locale> = RwLock::new(Locale::En);
+
+/// Process-wide locale
+pub fn locale() -> Locale {
+    *LOCALE.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Select the locale for the whole process
+pub fn set_locale(locale: Locale) {
+    *LOCALE.write().unwrap_or_else(|e| e.into_inner()) = locale;
+}
+
+/// Catalog entry
+struct Message {
+    code: &'static str,
+    en: &'static str,
+    zh_cn: &'static str,
+}
+
+impl Message {
+    fn text(&self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::En => self.en,
+            Locale::ZhCn => self.zh_cn,
+        }
+    }
+}
+
+macro_rules! catalog {
+    ($($code:literal => { en: $en:literal, zh_cn: $zh:literal $(,)? }),* $(,)?) => {
+        &[$(Message { code: $code, en: $en, zh_cn: $zh }),*]
+    };
+}
+
+/// Message catalog; `{name}` placeholders are filled from the arguments
+static CATALOG: &[Message] = catalog! {
+    // Cross-layer consistency alerts
+    "consistency.tcp_os_mismatch" => {
+        en: "TCP stack looks like {os}, which may not match the User-Agent",
+        zh_cn: "TCP栈检测为{os}，但User-Agent声明可能存在不一致",
+    },
+    "consistency.tls_browser_mismatch" => {
+        en: "TLS version {version} may be incompatible with browser ({browser})",
+        zh_cn: "TLS版本{version}与浏览器({browser})可能存在兼容性问题",
+    },
+    "consistency.ja4_ja4h_mismatch" => {
+        en: "JA4 ({ja4}) and JA4H ({ja4h}) indicate different clients; possible fingerprint spoofing",
+        zh_cn: "JA4指纹({ja4})与JA4H指纹({ja4h})指示的客户端特征不一致，可能是指纹伪造",
+    },
+    "consistency.tcp_ja4_client_mismatch" => {
+        en: "Operating system from TCP fingerprint ({os}) does not match JA4 client ({client})",
+        zh_cn: "TCP指纹指示的操作系统({os})与JA4指纹指示的客户端({client})不一致",
+    },
+    "consistency.timestamp_in_future" => {
+        en: "Flow timestamp ({timestamp}) is {seconds}s ahead of the current time; possible clock skew or replay",
+        zh_cn: "流量时间戳({timestamp})比当前时间晚{seconds}秒，可能存在时间同步问题或重放攻击",
+    },
+    "consistency.timestamp_stale" => {
+        en: "Flow timestamp ({timestamp}) is {seconds}s behind the current time; possible delayed processing or replay",
+        zh_cn: "流量时间戳({timestamp})比当前时间早{seconds}秒，可能是延迟处理的流量或重放攻击",
+    },
+    "consistency.dns_plain_public_resolver" => {
+        en: "Plain DNS to {resolver} from {browser}, which would upgrade that resolver to DoH; typical of automation with a hard-coded resolver",
+        zh_cn: "{browser}以明文DNS访问{resolver}，而该浏览器会将此解析器自动升级为DoH，常见于硬编码解析器的自动化工具",
+    },
+    "consistency.dns_resolver_mismatch" => {
+        en: "DNS via {observed} does not match the {expected} {browser} uses by default",
+        zh_cn: "DNS经由{observed}解析，与{browser}默认使用的{expected}不一致",
+    },
+    "consistency.dns_https_rr_missing" => {
+        en: "No HTTPS (type 65) DNS query observed, but {browser} issues one alongside A/AAAA",
+        zh_cn: "未观察到HTTPS(类型65)DNS查询，但{browser}会与A/AAAA查询一同发出",
+    },
+    "consistency.dns_https_rr_unexpected" => {
+        en: "HTTPS (type 65) DNS query observed, which {browser} does not issue",
+        zh_cn: "观察到HTTPS(类型65)DNS查询，但{browser}不会发出此类查询",
+    },
+    "consistency.dns_cache_mismatch" => {
+        en: "Repeated lookup after {seconds}s for a record with {ttl}s TTL; {browser} would answer it from its host cache",
+        zh_cn: "TTL为{ttl}秒的记录在{seconds}秒后被重复查询，{browser}本应从主机缓存中返回结果",
+    },
+
+    // Threat types
+    "threat.unknown_fingerprint" => { en: "Unknown fingerprint", zh_cn: "未知指纹" },
+    "threat.suspicious_behavior" => { en: "Suspicious behavior", zh_cn: "可疑行为" },
+    "threat.known_attack" => { en: "Known attack", zh_cn: "已知攻击" },
+    "threat.abnormal_traffic_pattern" => { en: "Abnormal traffic pattern", zh_cn: "异常流量模式" },
+    "threat.malicious_ip" => { en: "Malicious IP", zh_cn: "恶意IP" },
+    "threat.ddos" => { en: "DDoS attack", zh_cn: "DDoS攻击" },
+    "threat.port_scan" => { en: "Port scan", zh_cn: "端口扫描" },
+    "threat.brute_force" => { en: "Brute force", zh_cn: "暴力破解" },
+
+    // Protection decisions
+    "decision.allow" => { en: "Allow", zh_cn: "允许通过" },
+    "decision.deny" => { en: "Block: {reason}", zh_cn: "阻断: {reason}" },
+    "decision.rate_limit" => {
+        en: "Rate limit: {rate} packets/s for {duration}",
+        zh_cn: "限速: {rate} 包/秒，持续时间: {duration}",
+    },
+    "decision.log" => { en: "Log: {reason}", zh_cn: "记录: {reason}" },
+    "decision.requires_analysis" => { en: "Requires further analysis", zh_cn: "需要进一步分析" },
+    "decision.abnormal_traffic" => {
+        en: "Abnormal traffic, rate limit required",
+        zh_cn: "流量异常，需要限速",
+    },
+
+    // Test reports
+    "report.generated_at" => { en: "Generated at", zh_cn: "生成时间" },
+    "report.summary" => { en: "Test summary", zh_cn: "测试摘要" },
+    "report.total_tests" => { en: "Total tests", zh_cn: "测试总数" },
+    "report.passed" => { en: "Passed", zh_cn: "通过" },
+    "report.failed" => { en: "Failed", zh_cn: "失败" },
+    "report.success_rate" => { en: "Success rate", zh_cn: "成功率" },
+
+    // Protocol parse errors
+    "http.missing_status_line" => { en: "Missing status line", zh_cn: "缺少状态行" },
+    "http.invalid_status_line" => { en: "Invalid status line: {line}", zh_cn: "无效的状态行: {line}" },
+    "http.headers_too_short" => {
+        en: "Data too short to contain the end of headers",
+        zh_cn: "数据过短，无法包含头部结束标记",
+    },
+    "http.headers_end_not_found" => { en: "End of headers not found", zh_cn: "未找到头部结束标记" },
+    "http.compression_disabled" => {
+        en: "{encoding} decompression requires --features compression",
+        zh_cn: "{encoding} 解压需要启用 --features compression",
+    },
+    "http.unsupported_encoding" => {
+        en: "Unsupported content encoding: {encoding}",
+        zh_cn: "不支持的内容编码: {encoding}",
+    },
+    "tls.record_too_short" => {
+        en: "Data too short to parse a TLS record",
+        zh_cn: "数据过短，无法解析TLS记录",
+    },
+    "tls.unknown_content_type" => {
+        en: "Unknown TLS content type: {content_type}",
+        zh_cn: "未知的TLS内容类型: {content_type}",
+    },
+    "tls.unknown_handshake_type" => {
+        en: "Unknown TLS handshake type: {handshake_type}",
+        zh_cn: "未知的TLS握手类型: {handshake_type}",
+    },
+    "tls.record_incomplete" => {
+        en: "Incomplete TLS record: need {needed} bytes, got {actual}",
+        zh_cn: "TLS记录不完整，需要 {needed} 字节，实际只有 {actual} 字节",
+    },
+
+    // Gateway errors, keyed by the stable error `type`
+    "gateway.rate_limit_exceeded" => { en: "Rate limit exceeded: {detail}", zh_cn: "超出速率限制: {detail}" },
+    "gateway.invalid_api_key" => { en: "Invalid API key: {detail}", zh_cn: "无效的 API Key: {detail}" },
+    "gateway.forbidden" => { en: "Forbidden: {detail}", zh_cn: "禁止访问: {detail}" },
+    "gateway.quota_exceeded" => { en: "Quota exceeded: {detail}", zh_cn: "超出配额: {detail}" },
+    "gateway.limit_exceeded" => { en: "Limit exceeded: {detail}", zh_cn: "超出请求限制: {detail}" },
+    "gateway.redis_error" => { en: "Redis error: {detail}", zh_cn: "Redis 错误: {detail}" },
+    "gateway.config_error" => { en: "Configuration error: {detail}", zh_cn: "配置错误: {detail}" },
+    "gateway.invalid_request" => { en: "Invalid request: {detail}", zh_cn: "无效请求: {detail}" },
+    "gateway.not_found" => { en: "Not found: {detail}", zh_cn: "未找到: {detail}" },
+    "gateway.internal_error" => { en: "Internal server error: {detail}", zh_cn: "服务器内部错误: {detail}" },
+    "gateway.io_error" => { en: "IO error: {detail}", zh_cn: "IO 错误: {detail}" },
+    "gateway.unknown_error" => { en: "{detail}", zh_cn: "{detail}" },
+};
+
+fn lookup(code: &str) -> Option<&'static Message> {
+    CATALOG.iter().find(|m| m.code == code)
+}
+
+/// True when `code` has a catalog entry
+pub fn has_message(code: &str) -> bool {
+    lookup(code).is_some()
+}
+
+/// Untranslated English template for `code`
+///
+/// Falls back to `code` itself for unknown codes.
+pub fn english(code: &'static str) -> &'static str {
+    lookup(code).map(|m| m.en).unwrap_or(code)
+}
+
+/// Message for `code` in `locale`, with `{name}` placeholders filled from `args`
+///
+/// Unknown codes render as the code itself so nothing is silently dropped.
+pub fn translate(locale: Locale, code: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    match lookup(code) {
+        Some(message) => format_template(message.text(locale), args),
+        None => code.to_string(),
+    }
+}
+
+/// Message for `code` in the process-wide locale
+pub fn tr(code: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    translate(locale(), code, args)
+}
+
+fn format_template(template: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let name = &after[..end];
+        match args.iter().find(|(arg, _)| *arg == name) {
+            Some((_, value)) => out.push_str(&value.to_string()),
+            None => out.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!("en".parse::<Locale>().unwrap(), Locale::En);
+        assert_eq!("en_US.UTF-8".parse::<Locale>().unwrap(), Locale::En);
+        assert_eq!("zh-CN".parse::<Locale>().unwrap(), Locale::ZhCn);
+        assert_eq!("zh_CN".parse::<Locale>().unwrap(), Locale::ZhCn);
+        assert!("fr".parse::<Locale>().is_err());
+        assert_eq!(Locale::default(), Locale::En);
+    }
+
+    #[test]
+    fn test_translate_fills_placeholders() {
+        let args: &[(&str, &dyn fmt::Display)] = &[("version", &"TLSv1.0"), ("browser", &"Chrome")];
+        assert_eq!(
+            translate(Locale::En, "consistency.tls_browser_mismatch", args),
+            "TLS version TLSv1.0 may be incompatible with browser (Chrome)"
+        );
+        assert_eq!(
+            translate(Locale::ZhCn, "consistency.tls_browser_mismatch", args),
+            "TLS版本TLSv1.0与浏览器(Chrome)可能存在兼容性问题"
+        );
+        assert_eq!(
+            translate(Locale::En, "gateway.forbidden", &[]),
+            "Forbidden: {detail}"
+        );
+        assert_eq!(translate(Locale::En, "no.such.code", &[]), "no.such.code");
+    }
+
+    #[test]
+    fn test_catalog_is_complete() {
+        for (i, message) in CATALOG.iter().enumerate() {
+            assert!(
+                CATALOG[..i].iter().all(|m| m.code != message.code),
+                "duplicate code {}",
+                message.code
+            );
+            for locale in Locale::ALL {
+                assert!(
+                    !message.text(locale).is_empty(),
+                    "{} {}",
+                    message.code,
+                    locale
+                );
+            }
+            assert!(message.en.is_ascii(), "{} is not English", message.code);
+        }
+    }
+}
@@ -360,13 +360,19 @@ impl std::fmt::Display for JA4S {
     }
 }
 
+/// Code of discrepancies recorded without a catalog entry
+pub const UNCLASSIFIED_DISCREPANCY: &str = "consistency.unclassified";
+
 /// fingerprintconsistencyreport
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ConsistencyReport {
     /// overallscore (0-100)
     pub score: u8,
-    /// discover不consistentitem
+    /// discover不consistentitem (localized display text)
     pub discrepancies: Vec<String>,
+    /// Stable message codes, parallel to `discrepancies`
+    #[serde(default)]
+    pub discrepancy_codes: Vec<String>,
     /// whethersuspected machineer人
     pub bot_detected: bool,
 }
@@ -376,6 +382,7 @@ impl Default for ConsistencyReport {
         Self {
             score: 100,
             discrepancies: Vec::new(),
+            discrepancy_codes: Vec::new(),
             bot_detected: false,
         }
     }
@@ -386,8 +393,24 @@ impl ConsistencyReport {
         Self::default()
     }
 
+    /// Record a free-form discrepancy under the `consistency.unclassified` code
     pub fn add_discrepancy(&mut self, msg: String, impact: u8) {
+        self.push_discrepancy(UNCLASSIFIED_DISCREPANCY, msg, impact);
+    }
+
+    /// Record a discrepancy from the [`i18n`](crate::i18n) catalog
+    pub fn add_coded_discrepancy(
+        &mut self,
+        code: &'static str,
+        args: &[(&str, &dyn std::fmt::Display)],
+        impact: u8,
+    ) {
+        self.push_discrepancy(code, crate::i18n::tr(code, args), impact);
+    }
+
+    fn push_discrepancy(&mut self, code: &str, msg: String, impact: u8) {
         self.discrepancies.push(msg);
+        self.discrepancy_codes.push(code.to_string());
         self.score = self.score.saturating_sub(impact);
         if self.score < 60 {
             self.bot_detected = true;
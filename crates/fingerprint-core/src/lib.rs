@@ -33,19 +33,34 @@
 //!
 //! - **type system**: `BrowserType`, `OperatingSystem` etc.coretype
 //! - **utility functions**: GREASE process, randomly select etc.utility functions
+//! - **schema registry** (`SchemaRegistry`): versioned artifact serialization with migrations
+//! - **hashing backends** (`FingerprintHasher`): xxh3 for dedup, blake3 for content hashes
+//! - **i18n** (`i18n::tr`): localized user-facing messages keyed by stable codes
+//! - **data directories** (`DataDirs`): XDG / platform locations of databases, caches, models and logs
+//! - **runtime configuration** (`RuntimeConfig`): Tokio worker, compute and capture pool sizing
+//! - **capabilities** (`CapabilityReport`): analyzers, protocols and backends compiled into a build
 
 pub mod benchmark;
 #[cfg(feature = "service-cache")]
 pub mod cache; // Multi-tier caching (L1/L2/L3)
+pub mod capabilities; // Compiled-in analyzers, protocols and backends
+pub mod clock; // Injectable wall-clock and monotonic time
+pub mod data_dirs; // XDG / platform locations of persistent artifacts
 pub mod database;
 pub mod dicttls;
+pub mod edge; // CDN / WAF vendor identification
 pub mod error; // Comprehensive error types
+pub mod events; // In-process event bus
+#[cfg(feature = "feed-trust")]
+pub mod feed_trust; // Signed feed updates and transparency log
 pub mod fingerprint;
 pub mod grease;
+pub mod hashing; // Pluggable xxh3/blake3 hashing backends
 pub mod hassh;
 pub mod hpack;
 pub mod http;
 pub mod http2_frame_parser;
+pub mod i18n; // Localized user-facing strings
 pub mod incremental_fingerprint;
 pub mod ja3;
 pub mod ja3_database;
@@ -55,12 +70,18 @@ pub mod metadata;
 pub mod pqc; // Post-Quantum Cryptography detection
 #[cfg(feature = "service-rate-limiting")]
 pub mod rate_limiting; // Distributed rate limiting service (Phase 9.4)
+pub mod runbook; // Alert remediation runbooks
+pub mod runtime; // Tokio, compute and capture pool sizing
+pub mod schema; // Versioned artifact serialization
 pub mod signature;
+pub mod similarity; // Bootstrap confidence intervals for comparisons
 pub mod stable_hash;
+pub mod stix; // STIX 2.1 threat-intel objects
 pub mod system;
 pub mod tcp;
 pub mod tcp_handshake;
 pub mod tls_parser;
+pub mod trace_context; // W3C traceparent propagation
 pub mod types;
 pub mod utils;
 pub mod version; // Performance benchmarking utilities
@@ -78,23 +99,42 @@ pub use error::{
 pub use cache::CacheError;
 
 // fingerprint abstractions
-pub use fingerprint::{Fingerprint, FingerprintComparator, FingerprintComparison, FingerprintType};
+pub use fingerprint::{
+    FieldValue, Fingerprint, FingerprintComparator, FingerprintComparison, FingerprintType,
+};
 
 // metadata
 pub use metadata::FingerprintMetadata;
 
+// serialized artifacts
+pub use data_dirs::{DataDirs, DataDirsConfig};
+pub use schema::{ArtifactKind, SchemaError, SchemaHeader, SchemaRegistry, Versioned};
+
+// runtime configuration
+pub use runtime::{ComputePool, RuntimeConfig};
+
+// distributed tracing
+pub use trace_context::{TraceContext, TRACEPARENT_HEADER};
+
 // TLS related
+pub use capabilities::{Capability, CapabilityKind, CapabilityReport, CapabilitySet};
+pub use clock::{system_clock, AcceleratedClock, Clock, FrozenClock, SharedClock, SystemClock};
 pub use dicttls::*;
+pub use edge::{EdgeClassifier, EdgeDetection, EdgeObservation, EdgeVendor};
 pub use grease::{
     filter_grease_values, get_random_grease, is_grease_value, remove_grease_values,
     TLS_GREASE_VALUES,
 };
+pub use hashing::{
+    FingerprintHasher, HashAlgorithm, HashConfig, HashDigest, HashPurpose, HashUpgrade,
+};
 pub use hassh::{HASSHServer, SSHKexInit, HASSH, JA4SSH};
 pub use ja3::{JA3, JA3S};
 pub use ja4::{
     ConsistencyReport, TlsExtensionOrderFingerprint, JA4, JA4H, JA4L, JA4S, JA4T, JA4TS, JA4X,
 };
 pub use signature::ClientHelloSignature;
+pub use similarity::{BootstrapConfig, FeatureScore, SimilarityEstimate};
 pub use stable_hash::{hash_str, StableHashBuilder};
 pub use version::TlsVersion;
 
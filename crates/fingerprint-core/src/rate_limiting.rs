@@ -1,3 +1,4 @@
+use crate::clock::{system_clock, SharedClock};
 use async_trait::async_trait;
 use dashmap::DashMap;
 use parking_lot::RwLock;
@@ -85,7 +86,11 @@ pub struct UserQuota {
 impl UserQuota {
     /// Create new quota entry
     pub fn new(user_id: String, tier: QuotaTier) -> Self {
-        let now = current_unix_timestamp();
+        Self::new_at(user_id, tier, current_unix_timestamp())
+    }
+
+    /// Create new quota entry starting at `now` (Unix seconds)
+    pub fn new_at(user_id: String, tier: QuotaTier, now: u64) -> Self {
         Self {
             user_id,
             tier,
@@ -108,6 +113,11 @@ impl UserQuota {
 
     /// Consume tokens for a request
     pub fn consume(&mut self, endpoint_cost: f64) -> bool {
+        self.consume_at(endpoint_cost, current_unix_timestamp())
+    }
+
+    /// Consume tokens for a request made at `now` (Unix seconds)
+    pub fn consume_at(&mut self, endpoint_cost: f64, now: u64) -> bool {
         if !self.has_quota() {
             return false;
         }
@@ -121,7 +131,7 @@ impl UserQuota {
         self.available_tokens -= cost;
         self.month_requests += 1;
         self.total_requests += 1;
-        self.last_request = current_unix_timestamp();
+        self.last_request = now;
 
         true
     }
@@ -186,6 +196,8 @@ pub struct RateLimiter {
     distributed_backend_url: String,
     /// Optional distributed backend for quota synchronization
     distributed_backend: Option<Arc<dyn DistributedRateLimitBackend>>,
+    /// Time source for refills and staleness
+    clock: SharedClock,
 }
 
 /// Metrics for rate limiter
@@ -211,6 +223,7 @@ impl RateLimiter {
             metrics: Arc::new(RateLimiterMetrics::default()),
             distributed_backend_url,
             distributed_backend: None,
+            clock: system_clock(),
         }
     }
 
@@ -226,9 +239,16 @@ impl RateLimiter {
             metrics: Arc::new(RateLimiterMetrics::default()),
             distributed_backend_url,
             distributed_backend: Some(backend),
+            clock: system_clock(),
         }
     }
 
+    /// Use `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Check if a distributed backend is enabled
     pub fn is_distributed_enabled(&self) -> bool {
         self.distributed_backend.is_some()
@@ -263,7 +283,7 @@ impl RateLimiter {
         endpoint: &str,
         client_ip: Option<&str>,
     ) -> Result<RateLimitResponse, RateLimitError> {
-        let now = current_unix_timestamp();
+        let now = self.clock.unix_secs();
         let mut metrics = self.metrics.total_requests.lock();
         *metrics += 1;
         drop(metrics);
@@ -286,7 +306,7 @@ impl RateLimiter {
             {
                 let entry = self.user_quotas.entry(user_key.clone());
                 is_new_entry = matches!(entry, dashmap::mapref::entry::Entry::Vacant(_));
-                entry.or_insert_with(|| UserQuota::new(user_key.clone(), tier));
+                entry.or_insert_with(|| UserQuota::new_at(user_key.clone(), tier, now));
             }
 
             // Get mutable reference to the quota
@@ -308,13 +328,13 @@ impl RateLimiter {
                 drop(rejected);
 
                 return Err(RateLimitError::QuotaExceeded {
-                    retry_after: calculate_retry_after(quota, tier),
+                    retry_after: calculate_retry_after(quota, tier, now),
                     monthly_reset: quota.month_start + 30 * 86400,
                 });
             }
 
             // Consume tokens
-            if !quota.consume(endpoint_config.cost_multiplier) {
+            if !quota.consume_at(endpoint_config.cost_multiplier, now) {
                 let mut rejected = self.metrics.total_rejected.lock();
                 *rejected += 1;
                 drop(rejected);
@@ -345,7 +365,7 @@ impl RateLimiter {
             let mut entry = self
                 .ip_quotas
                 .entry(ip.to_string())
-                .or_insert_with(|| UserQuota::new(ip.to_string(), QuotaTier::Free));
+                .or_insert_with(|| UserQuota::new_at(ip.to_string(), QuotaTier::Free, now));
 
             let quota = entry.value_mut();
 
@@ -363,7 +383,7 @@ impl RateLimiter {
             }
 
             // Consume tokens
-            if !quota.consume(endpoint_config.cost_multiplier) {
+            if !quota.consume_at(endpoint_config.cost_multiplier, now) {
                 let mut rejected = self.metrics.total_rejected.lock();
                 *rejected += 1;
 
@@ -428,7 +448,7 @@ impl RateLimiter {
 
     /// Clear stale entries (should run periodically)
     pub fn cleanup_stale_entries(&self, retention_seconds: u64) {
-        let now = current_unix_timestamp();
+        let now = self.clock.unix_secs();
         let threshold = now.saturating_sub(retention_seconds);
 
         // Remove stale user quotas (inactive for > retention period)
@@ -549,10 +569,10 @@ pub fn current_unix_timestamp() -> u64 {
 }
 
 /// Calculate duration until next token refill
-fn calculate_retry_after(quota: &UserQuota, tier: QuotaTier) -> Duration {
+fn calculate_retry_after(quota: &UserQuota, tier: QuotaTier, now: u64) -> Duration {
     // If monthly quota exceeded, return until next month
     if quota.month_requests >= tier.monthly_quota() {
-        let secs = (quota.month_start + 30 * 86400).saturating_sub(current_unix_timestamp());
+        let secs = (quota.month_start + 30 * 86400).saturating_sub(now);
         Duration::from_secs(secs)
     } else {
         // If minute limit exceeded, return 60 seconds
@@ -609,4 +629,28 @@ mod tests {
         assert!(metrics.total_requests > 0);
         assert!(metrics.active_users > 0);
     }
+
+    #[test]
+    fn test_refill_and_cleanup_follow_clock() {
+        let clock = Arc::new(crate::clock::FrozenClock::at_unix(1_700_000_000));
+        let limiter = RateLimiter::new("redis://localhost".to_string()).with_clock(clock.clone());
+
+        for _ in 0..100 {
+            assert!(limiter
+                .check_limit(Some("user1"), QuotaTier::Free, "/identify", None)
+                .is_ok());
+        }
+        assert!(limiter
+            .check_limit(Some("user1"), QuotaTier::Free, "/identify", None)
+            .is_err());
+
+        clock.advance(Duration::from_secs(60));
+        assert!(limiter
+            .check_limit(Some("user1"), QuotaTier::Free, "/identify", None)
+            .is_ok());
+
+        clock.advance(Duration::from_secs(3600));
+        limiter.cleanup_stale_entries(600);
+        assert_eq!(limiter.metrics_snapshot().active_users, 0);
+    }
 }
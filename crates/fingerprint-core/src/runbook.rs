@@ -0,0 +1,375 @@
+//! Alert runbooks
+//!
+//! Maps alert rule IDs and categories to remediation text, links and
+//! automation hooks, so that an [`AlertRaised`] reaching a sink says what to
+//! do about it and not only what happened.
+//!
+//! An alert is matched in this order, first hit wins:
+//!
+//! 1. its `rule_id` metadata field
+//! 2. each entry of its `discrepancy_codes` metadata (defense consistency alerts)
+//! 3. its `source`
+//! 4. its `category`
+//!
+//! The matched runbook is rendered into the alert's `runbook` metadata field.
+//! Automation hooks are evaluated against per-subject occurrence counts (the
+//! `source_ip` metadata, when present), so "auto-block after 3 occurrences"
+//! shows up as `triggered: true` on the third matching alert from the same
+//! address. Acting on a triggered hook is up to the sink.
+//!
+//! ```
+//! use fingerprint_core::events::AlertRaised;
+//! use fingerprint_core::runbook::{AutomationHook, Runbook, RunbookRegistry};
+//!
+//! let registry = RunbookRegistry::new().with_rule(
+//!     "ua_os_mismatch",
+//!     Runbook::new("UA/OS mismatch", "Challenge the client before serving content")
+//!         .with_hook(AutomationHook::new("block").after(3)),
+//! );
+//! let mut alert = AlertRaised::new("defense.consistency", "warning", "suspicious", "mismatch");
+//! alert.metadata.insert("rule_id".into(), "ua_os_mismatch".into());
+//! assert!(registry.annotate(&mut alert));
+//! assert_eq!(alert.metadata["runbook"]["rule"], "ua_os_mismatch");
+//! ```
+
+use crate::events::AlertRaised;
+use chrono::{DateTime, Duration, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Metadata key holding an alert's rule ID
+pub const RULE_ID_KEY: &str = "rule_id";
+
+/// Metadata key the rendered runbook is written to
+pub const RUNBOOK_KEY: &str = "runbook";
+
+/// Occurrences remembered per rule and subject
+const MAX_TRACKED_OCCURRENCES: usize = 1024;
+
+/// Action to take once a rule has fired often enough
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutomationHook {
+    /// Action name understood by the sink, e.g. `block` or `page`
+    pub action: String,
+    /// Occurrences from the same subject before the hook triggers
+    #[serde(default = "default_after_occurrences")]
+    pub after_occurrences: u32,
+    /// Only count occurrences within this many seconds (all since start if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_secs: Option<u64>,
+}
+
+fn default_after_occurrences() -> u32 {
+    1
+}
+
+impl AutomationHook {
+    /// Hook that triggers on the first occurrence
+    pub fn new(action: impl Into<String>) -> Self {
+        Self {
+            action: action.into(),
+            after_occurrences: 1,
+            window_secs: None,
+        }
+    }
+
+    /// Trigger only after `occurrences` matching alerts
+    pub fn after(mut self, occurrences: u32) -> Self {
+        self.after_occurrences = occurrences.max(1);
+        self
+    }
+
+    /// Count occurrences within a sliding window
+    pub fn within_secs(mut self, secs: u64) -> Self {
+        self.window_secs = Some(secs);
+        self
+    }
+}
+
+/// Remediation guidance for one rule or category
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Runbook {
+    /// Short title
+    pub title: String,
+    /// What the operator should do
+    pub remediation: String,
+    /// Documentation links
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<String>,
+    /// Team or person responsible
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Automation hooks
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub automation: Vec<AutomationHook>,
+}
+
+impl Runbook {
+    /// Runbook with a title and remediation text
+    pub fn new(title: impl Into<String>, remediation: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            remediation: remediation.into(),
+            links: Vec::new(),
+            owner: None,
+            automation: Vec::new(),
+        }
+    }
+
+    /// Add a documentation link
+    pub fn with_link(mut self, link: impl Into<String>) -> Self {
+        self.links.push(link.into());
+        self
+    }
+
+    /// Set the owner
+    pub fn with_owner(mut self, owner: impl Into<String>) -> Self {
+        self.owner = Some(owner.into());
+        self
+    }
+
+    /// Add an automation hook
+    pub fn with_hook(mut self, hook: AutomationHook) -> Self {
+        self.automation.push(hook);
+        self
+    }
+}
+
+/// Serialized form of a [`RunbookRegistry`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunbookSet {
+    /// Runbooks keyed by rule ID (also matched against discrepancy codes and sources)
+    #[serde(default)]
+    pub rules: HashMap<String, Runbook>,
+    /// Fallback runbooks keyed by lowercase category
+    #[serde(default)]
+    pub categories: HashMap<String, Runbook>,
+}
+
+/// Registry resolving alerts to runbooks
+#[derive(Debug, Default)]
+pub struct RunbookRegistry {
+    set: RunbookSet,
+    occurrences: Mutex<HashMap<String, VecDeque<DateTime<Utc>>>>,
+}
+
+impl RunbookRegistry {
+    /// Empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry over a deserialized set
+    pub fn from_set(set: RunbookSet) -> Self {
+        Self {
+            set,
+            occurrences: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Load a registry from JSON (`{"rules": {...}, "categories": {...}}`)
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json)
+            .map(Self::from_set)
+            .map_err(|e| format!("invalid runbook file: {}", e))
+    }
+
+    /// Add a runbook for a rule ID
+    pub fn with_rule(mut self, rule_id: impl Into<String>, runbook: Runbook) -> Self {
+        self.set.rules.insert(rule_id.into(), runbook);
+        self
+    }
+
+    /// Add a fallback runbook for a category
+    pub fn with_category(mut self, category: impl Into<String>, runbook: Runbook) -> Self {
+        self.set
+            .categories
+            .insert(category.into().to_lowercase(), runbook);
+        self
+    }
+
+    /// Registered runbooks
+    pub fn set(&self) -> &RunbookSet {
+        &self.set
+    }
+
+    /// Runbook matching `alert`, with the key it matched on
+    pub fn resolve(&self, alert: &AlertRaised) -> Option<(String, &Runbook)> {
+        let rule_id = alert.metadata.get(RULE_ID_KEY).and_then(|v| v.as_str());
+        let codes = alert
+            .metadata
+            .get("discrepancy_codes")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str());
+        let rule = rule_id
+            .into_iter()
+            .chain(codes)
+            .chain(std::iter::once(alert.source.as_str()))
+            .find_map(|key| self.set.rules.get_key_value(key));
+        if let Some((key, runbook)) = rule {
+            return Some((key.clone(), runbook));
+        }
+        let category = alert.category.to_lowercase();
+        self.set
+            .categories
+            .get(&category)
+            .map(|runbook| (format!("category:{}", category), runbook))
+    }
+
+    /// Render the matching runbook into `alert.metadata["runbook"]`
+    ///
+    /// Records an occurrence for the alert's subject and reports each
+    /// automation hook's count and whether it has triggered. Returns false
+    /// when no runbook matches.
+    pub fn annotate(&self, alert: &mut AlertRaised) -> bool {
+        let Some((key, runbook)) = self.resolve(alert) else {
+            return false;
+        };
+        let subject = alert
+            .metadata
+            .get("source_ip")
+            .and_then(|v| v.as_str())
+            .unwrap_or("*");
+        let history = self.record(&format!("{}|{}", key, subject), alert.timestamp);
+        let automation: Vec<_> = runbook
+            .automation
+            .iter()
+            .map(|hook| {
+                let occurrences = match hook.window_secs {
+                    Some(secs) => {
+                        let since = alert.timestamp - Duration::seconds(secs as i64);
+                        history.iter().filter(|t| **t > since).count()
+                    }
+                    None => history.len(),
+                };
+                serde_json::json!({
+                    "action": hook.action,
+                    "after_occurrences": hook.after_occurrences,
+                    "occurrences": occurrences,
+                    "triggered": occurrences >= hook.after_occurrences as usize,
+                })
+            })
+            .collect();
+        let rendered = serde_json::json!({
+            "rule": key,
+            "title": runbook.title,
+            "remediation": runbook.remediation,
+            "links": runbook.links,
+            "owner": runbook.owner,
+            "automation": automation,
+        });
+        alert.metadata.insert(RUNBOOK_KEY.to_string(), rendered);
+        true
+    }
+
+    /// Forget all recorded occurrences
+    pub fn reset_occurrences(&self) {
+        self.occurrences
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    fn record(&self, key: &str, at: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        let mut occurrences = self.occurrences.lock().unwrap_or_else(|e| e.into_inner());
+        let history = occurrences.entry(key.to_string()).or_default();
+        history.push_back(at);
+        if history.len() > MAX_TRACKED_OCCURRENCES {
+            history.pop_front();
+        }
+        history.iter().copied().collect()
+    }
+}
+
+static GLOBAL: Lazy<RwLock<Arc<RunbookRegistry>>> =
+    Lazy::new(|| RwLock::new(Arc::new(RunbookRegistry::new())));
+
+/// Registry used by [`crate::events::publish_alert`]
+pub fn global() -> Arc<RunbookRegistry> {
+    GLOBAL.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Replace the process-wide registry
+pub fn set_global(registry: RunbookRegistry) {
+    *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(registry);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(ip: &str, codes: &[&str]) -> AlertRaised {
+        let mut alert =
+            AlertRaised::new("defense.consistency", "warning", "suspicious", "violation");
+        alert
+            .metadata
+            .insert("source_ip".to_string(), serde_json::json!(ip));
+        alert
+            .metadata
+            .insert("discrepancy_codes".to_string(), serde_json::json!(codes));
+        alert
+    }
+
+    #[test]
+    fn resolves_rule_before_category() {
+        let registry = RunbookRegistry::new()
+            .with_rule("ua_os_mismatch", Runbook::new("UA/OS", "challenge"))
+            .with_category("Suspicious", Runbook::new("Suspicious", "review"));
+
+        let (key, runbook) = registry
+            .resolve(&alert("10.0.0.1", &["other", "ua_os_mismatch"]))
+            .unwrap();
+        assert_eq!(key, "ua_os_mismatch");
+        assert_eq!(runbook.title, "UA/OS");
+
+        let (key, _) = registry.resolve(&alert("10.0.0.1", &["other"])).unwrap();
+        assert_eq!(key, "category:suspicious");
+
+        let mut info = AlertRaised::new("analysis", "info", "anomaly", "odd");
+        assert!(!registry.annotate(&mut info));
+        assert!(!info.metadata.contains_key(RUNBOOK_KEY));
+    }
+
+    #[test]
+    fn auto_block_triggers_per_subject() {
+        let registry = RunbookRegistry::from_json(
+            r#"{"rules": {"ua_os_mismatch": {
+                "title": "UA/OS mismatch",
+                "remediation": "Block the address",
+                "links": ["https://example.com/runbooks/ua-os"],
+                "automation": [{"action": "block", "after_occurrences": 3}]
+            }}}"#,
+        )
+        .unwrap();
+
+        let triggered = |alert: &AlertRaised| {
+            alert.metadata[RUNBOOK_KEY]["automation"][0]["triggered"]
+                .as_bool()
+                .unwrap()
+        };
+        for expected in [false, false, true] {
+            let mut a = alert("10.0.0.1", &["ua_os_mismatch"]);
+            assert!(registry.annotate(&mut a));
+            assert_eq!(triggered(&a), expected);
+        }
+        let mut other = alert("10.0.0.2", &["ua_os_mismatch"]);
+        registry.annotate(&mut other);
+        assert!(!triggered(&other));
+        assert_eq!(
+            other.metadata[RUNBOOK_KEY]["links"][0],
+            "https://example.com/runbooks/ua-os"
+        );
+
+        registry.reset_occurrences();
+        let mut again = alert("10.0.0.1", &["ua_os_mismatch"]);
+        registry.annotate(&mut again);
+        assert_eq!(
+            again.metadata[RUNBOOK_KEY]["automation"][0]["occurrences"],
+            1
+        );
+    }
+}
@@ -0,0 +1,505 @@
+//! Runtime and thread pool configuration
+//!
+//! Async I/O, CPU-bound scoring and packet capture have different needs, so
+//! they get different pools:
+//! - the shared Tokio runtime (HTTP client, gateway helpers), sized by
+//!   `worker_threads` and `max_blocking_threads`;
+//! - a [`ComputePool`] of plain threads for ML inference and statistics, so
+//!   long computations never occupy async workers;
+//! - optionally a separate capture runtime, so a busy capture loop cannot
+//!   starve request handling.
+//!
+//! A process installs one [`RuntimeConfig`] at startup with
+//! [`RuntimeConfig::install`]; the global pools are built from it on first
+//! use. Every pool reports its queue depth ([`PoolStats`], [`RuntimeStats`])
+//! so operators can size it from observed backlog instead of guessing.
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll};
+use std::thread::JoinHandle;
+use tokio::sync::oneshot;
+
+/// Default cap on queued compute jobs
+pub const DEFAULT_COMPUTE_QUEUE_CAPACITY: usize = 1024;
+
+/// Tokio's default blocking pool size
+pub const DEFAULT_MAX_BLOCKING_THREADS: usize = 512;
+
+static INSTALLED: OnceCell<RuntimeConfig> = OnceCell::new();
+static COMPUTE_POOL: OnceCell<ComputePool> = OnceCell::new();
+static CAPTURE_RUNTIME: OnceCell<Option<tokio::runtime::Runtime>> = OnceCell::new();
+
+/// Thread pool sizing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuntimeConfig {
+    /// Async worker threads of the shared runtime (None: one per core)
+    pub worker_threads: Option<usize>,
+    /// Upper bound of the shared runtime's blocking pool
+    pub max_blocking_threads: usize,
+    /// Compute pool threads for ML and statistics (None: cores - 1, at least 1)
+    pub compute_threads: Option<usize>,
+    /// Queued compute jobs before new jobs are rejected (0: unbounded)
+    pub compute_queue_capacity: usize,
+    /// Worker threads of a dedicated capture runtime (None: capture shares the caller's runtime)
+    pub capture_threads: Option<usize>,
+    /// Prefix of spawned thread names
+    pub thread_name: String,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            worker_threads: None,
+            max_blocking_threads: DEFAULT_MAX_BLOCKING_THREADS,
+            compute_threads: None,
+            compute_queue_capacity: DEFAULT_COMPUTE_QUEUE_CAPACITY,
+            capture_threads: None,
+            thread_name: "fingerprint".to_string(),
+        }
+    }
+}
+
+impl RuntimeConfig {
+    /// Read overrides from the environment
+    ///
+    /// - `FINGERPRINT_WORKER_THREADS`
+    /// - `FINGERPRINT_MAX_BLOCKING_THREADS`
+    /// - `FINGERPRINT_COMPUTE_THREADS`
+    /// - `FINGERPRINT_COMPUTE_QUEUE`
+    /// - `FINGERPRINT_CAPTURE_THREADS`
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let number = |name: &str| var(name).and_then(|v| v.trim().parse::<usize>().ok());
+        let defaults = Self::default();
+        Self {
+            worker_threads: number("FINGERPRINT_WORKER_THREADS").filter(|&n| n > 0),
+            max_blocking_threads: number("FINGERPRINT_MAX_BLOCKING_THREADS")
+                .filter(|&n| n > 0)
+                .unwrap_or(defaults.max_blocking_threads),
+            compute_threads: number("FINGERPRINT_COMPUTE_THREADS").filter(|&n| n > 0),
+            compute_queue_capacity: number("FINGERPRINT_COMPUTE_QUEUE")
+                .unwrap_or(defaults.compute_queue_capacity),
+            capture_threads: number("FINGERPRINT_CAPTURE_THREADS").filter(|&n| n > 0),
+            thread_name: defaults.thread_name,
+        }
+    }
+
+    /// Make this the process-wide configuration
+    ///
+    /// Must run before the first use of a global pool; returns the config back
+    /// if one was already installed or a pool was already built with defaults.
+    pub fn install(self) -> Result<(), RuntimeConfig> {
+        INSTALLED.set(self)
+    }
+
+    /// The installed configuration (defaults when none was installed)
+    pub fn installed() -> &'static RuntimeConfig {
+        INSTALLED.get_or_init(RuntimeConfig::default)
+    }
+
+    /// Compute pool size after defaults
+    pub fn effective_compute_threads(&self) -> usize {
+        self.compute_threads.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get().saturating_sub(1))
+                .unwrap_or(1)
+                .max(1)
+        })
+    }
+
+    /// Build a multi-thread runtime with the configured worker and blocking pools
+    pub fn build_runtime(&self) -> io::Result<tokio::runtime::Runtime> {
+        self.runtime_builder(self.worker_threads, "rt")
+    }
+
+    /// Build the dedicated capture runtime, if one is configured
+    pub fn build_capture_runtime(&self) -> io::Result<Option<tokio::runtime::Runtime>> {
+        self.capture_threads
+            .map(|threads| self.runtime_builder(Some(threads), "capture"))
+            .transpose()
+    }
+
+    /// Build a compute pool of the configured size
+    pub fn build_compute_pool(&self) -> io::Result<ComputePool> {
+        ComputePool::new(
+            self.effective_compute_threads(),
+            self.compute_queue_capacity,
+            &format!("{}-compute", self.thread_name),
+        )
+    }
+
+    fn runtime_builder(
+        &self,
+        workers: Option<usize>,
+        role: &str,
+    ) -> io::Result<tokio::runtime::Runtime> {
+        let mut builder = tokio::runtime::Builder::new_multi_thread();
+        if let Some(workers) = workers {
+            builder.worker_threads(workers.max(1));
+        }
+        builder
+            .max_blocking_threads(self.max_blocking_threads.max(1))
+            .thread_name(format!("{}-{}", self.thread_name, role))
+            .enable_all()
+            .build()
+    }
+}
+
+/// Process-wide compute pool, built from the installed configuration
+pub fn compute_pool() -> io::Result<&'static ComputePool> {
+    COMPUTE_POOL.get_or_try_init(|| RuntimeConfig::installed().build_compute_pool())
+}
+
+/// Run `job` on the process-wide compute pool
+pub fn spawn_compute<F, T>(job: F) -> Result<ComputeHandle<T>, ComputeError>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    compute_pool()
+        .map_err(|e| ComputeError::Unavailable(e.to_string()))?
+        .spawn(job)
+}
+
+/// Compute pool statistics, if the pool has been started
+pub fn compute_pool_stats() -> Option<PoolStats> {
+    COMPUTE_POOL.get().map(ComputePool::stats)
+}
+
+/// Handle of the dedicated capture runtime (None: not configured)
+pub fn capture_runtime() -> io::Result<Option<tokio::runtime::Handle>> {
+    let runtime =
+        CAPTURE_RUNTIME.get_or_try_init(|| RuntimeConfig::installed().build_capture_runtime())?;
+    Ok(runtime.as_ref().map(|rt| rt.handle().clone()))
+}
+
+/// Capture runtime statistics, if one has been started
+pub fn capture_runtime_stats() -> Option<RuntimeStats> {
+    CAPTURE_RUNTIME
+        .get()
+        .and_then(Option::as_ref)
+        .map(|rt| RuntimeStats::of(rt.handle()))
+}
+
+/// Snapshot of a Tokio runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RuntimeStats {
+    /// Async worker threads
+    pub workers: usize,
+    /// Spawned tasks not yet finished
+    pub alive_tasks: usize,
+    /// Tasks waiting in the global queue
+    pub global_queue_depth: usize,
+}
+
+impl RuntimeStats {
+    /// Read the metrics of `handle`'s runtime
+    pub fn of(handle: &tokio::runtime::Handle) -> Self {
+        let metrics = handle.metrics();
+        Self {
+            workers: metrics.num_workers(),
+            alive_tasks: metrics.num_alive_tasks(),
+            global_queue_depth: metrics.global_queue_depth(),
+        }
+    }
+}
+
+/// Snapshot of a [`ComputePool`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PoolStats {
+    /// Worker threads
+    pub threads: usize,
+    /// Jobs waiting for a thread
+    pub queued: usize,
+    /// Jobs running now
+    pub running: usize,
+    /// Highest queue depth seen
+    pub max_queued: usize,
+    /// Jobs finished (including panicked ones)
+    pub completed: u64,
+    /// Jobs refused because the queue was full or the pool was closed
+    pub rejected: u64,
+}
+
+/// Why a compute job did not produce a value
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ComputeError {
+    /// Queue at capacity
+    #[error("compute queue is full ({capacity} jobs)")]
+    QueueFull {
+        /// Configured capacity
+        capacity: usize,
+    },
+    /// Pool shut down
+    #[error("compute pool is shut down")]
+    Closed,
+    /// Job panicked
+    #[error("compute job panicked")]
+    Panicked,
+    /// Pool threads could not be started
+    #[error("compute pool unavailable: {0}")]
+    Unavailable(String),
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct Shared {
+    queue: Mutex<Queue>,
+    available: Condvar,
+    capacity: usize,
+    running: AtomicUsize,
+    max_queued: AtomicUsize,
+    completed: AtomicU64,
+    rejected: AtomicU64,
+}
+
+struct Queue {
+    jobs: VecDeque<Job>,
+    closed: bool,
+}
+
+/// Fixed-size pool of OS threads for CPU-bound work
+///
+/// Jobs are queued FIFO up to the configured capacity; submitting to a full
+/// queue fails fast with [`ComputeError::QueueFull`] instead of growing
+/// memory without bound. Dropping the pool finishes queued jobs and joins the
+/// threads.
+pub struct ComputePool {
+    shared: Arc<Shared>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for ComputePool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ComputePool")
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl ComputePool {
+    /// Start `threads` workers named `<name>-<n>` (capacity 0: unbounded queue)
+    pub fn new(threads: usize, capacity: usize, name: &str) -> io::Result<Self> {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue {
+                jobs: VecDeque::new(),
+                closed: false,
+            }),
+            available: Condvar::new(),
+            capacity,
+            running: AtomicUsize::new(0),
+            max_queued: AtomicUsize::new(0),
+            completed: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        });
+        let threads = (0..threads.max(1))
+            .map(|i| {
+                let shared = shared.clone();
+                std::thread::Builder::new()
+                    .name(format!("{}-{}", name, i))
+                    .spawn(move || worker(&shared))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(Self { shared, threads })
+    }
+
+    /// Queue `job`, returning a handle that is awaited or waited on for its result
+    pub fn spawn<F, T>(&self, job: F) -> Result<ComputeHandle<T>, ComputeError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let shared = self.shared.clone();
+        let job: Job = Box::new(move || {
+            shared.running.fetch_add(1, Ordering::Relaxed);
+            let result = catch_unwind(AssertUnwindSafe(job)).map_err(|_| ComputeError::Panicked);
+            // counted before the result is delivered, so stats never lag a finished handle
+            shared.running.fetch_sub(1, Ordering::Relaxed);
+            shared.completed.fetch_add(1, Ordering::Relaxed);
+            let _ = tx.send(result);
+        });
+
+        let mut queue = self.shared.lock();
+        if queue.closed {
+            self.shared.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(ComputeError::Closed);
+        }
+        if self.shared.capacity != 0 && queue.jobs.len() >= self.shared.capacity {
+            self.shared.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(ComputeError::QueueFull {
+                capacity: self.shared.capacity,
+            });
+        }
+        queue.jobs.push_back(job);
+        self.shared
+            .max_queued
+            .fetch_max(queue.jobs.len(), Ordering::Relaxed);
+        drop(queue);
+        self.shared.available.notify_one();
+        Ok(ComputeHandle { rx })
+    }
+
+    /// Current statistics
+    pub fn stats(&self) -> PoolStats {
+        let queued = self.shared.lock().jobs.len();
+        PoolStats {
+            threads: self.threads.len(),
+            queued,
+            running: self.shared.running.load(Ordering::Relaxed),
+            max_queued: self.shared.max_queued.load(Ordering::Relaxed),
+            completed: self.shared.completed.load(Ordering::Relaxed),
+            rejected: self.shared.rejected.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for ComputePool {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.available.notify_all();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn worker(shared: &Shared) {
+    loop {
+        let job = {
+            let mut queue = shared.lock();
+            loop {
+                if let Some(job) = queue.jobs.pop_front() {
+                    break job;
+                }
+                if queue.closed {
+                    return;
+                }
+                queue = shared
+                    .available
+                    .wait(queue)
+                    .unwrap_or_else(|e| e.into_inner());
+            }
+        };
+        job();
+    }
+}
+
+/// Result of a queued compute job
+///
+/// Await it from async code, or call [`ComputeHandle::wait`] from a thread
+/// that may block.
+#[derive(Debug)]
+pub struct ComputeHandle<T> {
+    rx: oneshot::Receiver<Result<T, ComputeError>>,
+}
+
+impl<T> ComputeHandle<T> {
+    /// Block until the job finishes (not from inside an async task)
+    pub fn wait(self) -> Result<T, ComputeError> {
+        self.rx.blocking_recv().unwrap_or(Err(ComputeError::Closed))
+    }
+}
+
+impl<T> Future for ComputeHandle<T> {
+    type Output = Result<T, ComputeError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.rx)
+            .poll(cx)
+            .map(|result| result.unwrap_or(Err(ComputeError::Closed)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_compute_pool_queue_depth_and_rejection() {
+        let pool = ComputePool::new(1, 2, "test-compute").unwrap();
+
+        // hold the only thread so later jobs queue up
+        let (release, gate) = mpsc::channel::<()>();
+        let blocker = pool
+            .spawn(move || {
+                gate.recv().unwrap();
+                1
+            })
+            .unwrap();
+        while pool.stats().running == 0 {
+            std::thread::yield_now();
+        }
+        let queued: Vec<_> = (0..2)
+            .map(|i| pool.spawn(move || i * 10).unwrap())
+            .collect();
+        assert_eq!(
+            pool.spawn(|| 0).unwrap_err(),
+            ComputeError::QueueFull { capacity: 2 }
+        );
+        let stats = pool.stats();
+        assert_eq!((stats.queued, stats.running, stats.rejected), (2, 1, 1));
+
+        release.send(()).unwrap();
+        assert_eq!(blocker.wait(), Ok(1));
+        let results: Vec<_> = queued.into_iter().map(|h| h.wait().unwrap()).collect();
+        assert_eq!(results, vec![0, 10]);
+        assert_eq!(
+            pool.spawn(|| panic!("boom")).unwrap().wait(),
+            Err(ComputeError::Panicked)
+        );
+
+        let stats = pool.stats();
+        assert_eq!(stats.completed, 4);
+        assert_eq!(stats.max_queued, 2);
+    }
+
+    #[test]
+    fn test_runtime_config_from_env_and_runtimes() {
+        let vars = HashMap::from([
+            ("FINGERPRINT_WORKER_THREADS", "2"),
+            ("FINGERPRINT_COMPUTE_THREADS", "3"),
+            ("FINGERPRINT_CAPTURE_THREADS", "1"),
+            ("FINGERPRINT_MAX_BLOCKING_THREADS", "0"),
+        ]);
+        let config = RuntimeConfig::from_vars(|name| vars.get(name).map(|v| v.to_string()));
+        assert_eq!(config.worker_threads, Some(2));
+        assert_eq!(config.effective_compute_threads(), 3);
+        assert_eq!(config.max_blocking_threads, DEFAULT_MAX_BLOCKING_THREADS);
+
+        let runtime = config.build_runtime().unwrap();
+        let capture = config.build_capture_runtime().unwrap().unwrap();
+        assert_eq!(RuntimeStats::of(runtime.handle()).workers, 2);
+        assert_eq!(RuntimeStats::of(capture.handle()).workers, 1);
+        assert!(RuntimeConfig::default()
+            .build_capture_runtime()
+            .unwrap()
+            .is_none());
+
+        // compute jobs are awaited from async code without blocking a worker
+        let pool = config.build_compute_pool().unwrap();
+        let sum =
+            runtime.block_on(async { pool.spawn(|| (1..=100u32).sum::<u32>()).unwrap().await });
+        assert_eq!(sum, Ok(5050));
+    }
+}
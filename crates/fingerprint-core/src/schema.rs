@@ -0,0 +1,456 @@
+//! Schema registry for serialized artifacts
+//!
+//! Every artifact written to disk or shipped between components (models, profiles,
+//! configs, observations, reports, fingerprint databases) carries a schema header with
+//! its kind and version:
+//!
+//! ```json
+//! { "schema": { "kind": "report", "version": 2 }, "data": { ... } }
+//! ```
+//!
+//! On load, [`SchemaRegistry`] runs the registered migrations (one per version step) on
+//! the raw JSON until it reaches the current version, then deserializes. Artifacts written
+//! before versioning (no header) are treated as version 0.
+//!
+//! ```rust
+//! use fingerprint_core::schema::{ArtifactKind, SchemaRegistry};
+//! use serde_json::json;
+//!
+//! let mut registry = SchemaRegistry::builtin();
+//! registry.register(ArtifactKind::Report, 2);
+//! // v1 reports called the score "risk"
+//! registry.register_migration(ArtifactKind::Report, 1, |mut data| {
+//!     if let Some(risk) = data.get_mut("risk").map(|v| v.take()) {
+//!         data["score"] = risk;
+//!     }
+//!     Ok(data)
+//! });
+//!
+//! let old = r#"{"schema":{"kind":"report","version":1},"data":{"risk":0.8}}"#;
+//! let report: serde_json::Value = registry.from_json(ArtifactKind::Report, old).unwrap();
+//! assert_eq!(report["score"], json!(0.8));
+//! ```
+
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// version assigned to artifacts written before the registry existed
+pub const LEGACY_VERSION: u32 = 0;
+
+/// Serialized artifact types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    /// trained / pre-trained model weights and metadata
+    Model,
+    /// browser profile
+    Profile,
+    /// configuration file
+    Config,
+    /// observed fingerprint (sensor output)
+    Observation,
+    /// analysis or simulation report
+    Report,
+    /// exported fingerprint database (threat intelligence)
+    FingerprintDatabase,
+}
+
+impl ArtifactKind {
+    /// all artifact kinds
+    pub const ALL: [ArtifactKind; 6] = [
+        ArtifactKind::Model,
+        ArtifactKind::Profile,
+        ArtifactKind::Config,
+        ArtifactKind::Observation,
+        ArtifactKind::Report,
+        ArtifactKind::FingerprintDatabase,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArtifactKind::Model => "model",
+            ArtifactKind::Profile => "profile",
+            ArtifactKind::Config => "config",
+            ArtifactKind::Observation => "observation",
+            ArtifactKind::Report => "report",
+            ArtifactKind::FingerprintDatabase => "fingerprint_database",
+        }
+    }
+}
+
+impl fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Schema header embedded in serialized output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaHeader {
+    pub kind: ArtifactKind,
+    pub version: u32,
+}
+
+/// Versioned artifact envelope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub schema: SchemaHeader,
+    pub data: T,
+}
+
+/// Schema errors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    /// input is not valid JSON or does not match the target type
+    Json(String),
+
+    /// artifact of another kind
+    KindMismatch {
+        expected: ArtifactKind,
+        found: ArtifactKind,
+    },
+
+    /// artifact written by a newer release
+    UnsupportedVersion {
+        kind: ArtifactKind,
+        version: u32,
+        current: u32,
+    },
+
+    /// no migration from this version to the next
+    MissingMigration { kind: ArtifactKind, from: u32 },
+
+    /// migration hook failed
+    Migration {
+        kind: ArtifactKind,
+        from: u32,
+        reason: String,
+    },
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(msg) => write!(f, "Invalid artifact JSON: {}", msg),
+            Self::KindMismatch { expected, found } => {
+                write!(f, "Expected {} artifact, found {}", expected, found)
+            }
+            Self::UnsupportedVersion {
+                kind,
+                version,
+                current,
+            } => write!(
+                f,
+                "{} schema version {} is newer than supported version {}",
+                kind, version, current
+            ),
+            Self::MissingMigration { kind, from } => {
+                write!(f, "No {} migration from version {}", kind, from)
+            }
+            Self::Migration { kind, from, reason } => {
+                write!(
+                    f,
+                    "{} migration from version {} failed: {}",
+                    kind, from, reason
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl From<serde_json::Error> for SchemaError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e.to_string())
+    }
+}
+
+/// Migration hook: transforms the data of version `from` into version `from + 1`
+pub type Migration = Box<dyn Fn(Value) -> Result<Value, String> + Send + Sync>;
+
+/// Registry of current artifact versions and migrations between them
+pub struct SchemaRegistry {
+    versions: HashMap<ArtifactKind, u32>,
+    migrations: HashMap<(ArtifactKind, u32), Migration>,
+}
+
+impl SchemaRegistry {
+    /// registry without any artifact kinds
+    pub fn empty() -> Self {
+        Self {
+            versions: HashMap::new(),
+            migrations: HashMap::new(),
+        }
+    }
+
+    /// registry with every built-in artifact kind at version 1
+    ///
+    /// Legacy (unversioned) artifacts have the same layout as version 1, so the
+    /// 0 → 1 migration is the identity.
+    pub fn builtin() -> Self {
+        let mut registry = Self::empty();
+        for kind in ArtifactKind::ALL {
+            registry.register(kind, 1);
+            registry.register_migration(kind, LEGACY_VERSION, Ok);
+        }
+        registry
+    }
+
+    /// set the current version of an artifact kind
+    pub fn register(&mut self, kind: ArtifactKind, version: u32) -> &mut Self {
+        self.versions.insert(kind, version);
+        self
+    }
+
+    /// add the migration from version `from` to `from + 1`
+    pub fn register_migration<F>(
+        &mut self,
+        kind: ArtifactKind,
+        from: u32,
+        migration: F,
+    ) -> &mut Self
+    where
+        F: Fn(Value) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        self.migrations.insert((kind, from), Box::new(migration));
+        self
+    }
+
+    /// current version of an artifact kind (None if unregistered)
+    pub fn current_version(&self, kind: ArtifactKind) -> Option<u32> {
+        self.versions.get(&kind).copied()
+    }
+
+    /// header for newly written artifacts
+    pub fn header(&self, kind: ArtifactKind) -> SchemaHeader {
+        SchemaHeader {
+            kind,
+            version: self.current_version(kind).unwrap_or(LEGACY_VERSION),
+        }
+    }
+
+    /// wrap a value in a versioned envelope
+    pub fn to_value<T: Serialize>(
+        &self,
+        kind: ArtifactKind,
+        data: &T,
+    ) -> Result<Value, SchemaError> {
+        let envelope = Versioned {
+            schema: self.header(kind),
+            data,
+        };
+        Ok(serde_json::to_value(envelope)?)
+    }
+
+    /// serialize to pretty JSON with the schema header
+    pub fn to_json<T: Serialize>(
+        &self,
+        kind: ArtifactKind,
+        data: &T,
+    ) -> Result<String, SchemaError> {
+        Ok(serde_json::to_string_pretty(&self.to_value(kind, data)?)?)
+    }
+
+    /// parse JSON (versioned or legacy), migrate to the current version and deserialize
+    pub fn from_json<T: DeserializeOwned>(
+        &self,
+        kind: ArtifactKind,
+        json: &str,
+    ) -> Result<T, SchemaError> {
+        self.from_value(kind, serde_json::from_str(json)?)
+    }
+
+    /// like [`SchemaRegistry::from_json`] for an already parsed value
+    pub fn from_value<T: DeserializeOwned>(
+        &self,
+        kind: ArtifactKind,
+        value: Value,
+    ) -> Result<T, SchemaError> {
+        let data = self.migrate(kind, value)?;
+        Ok(serde_json::from_value(data)?)
+    }
+
+    /// bring raw artifact JSON to the current version, returning the bare data
+    pub fn migrate(&self, kind: ArtifactKind, value: Value) -> Result<Value, SchemaError> {
+        let (header, mut data) = split_envelope(kind, value)?;
+        if header.kind != kind {
+            return Err(SchemaError::KindMismatch {
+                expected: kind,
+                found: header.kind,
+            });
+        }
+
+        let current = self.current_version(kind).unwrap_or(LEGACY_VERSION);
+        if header.version > current {
+            return Err(SchemaError::UnsupportedVersion {
+                kind,
+                version: header.version,
+                current,
+            });
+        }
+
+        for from in header.version..current {
+            let migration = self
+                .migrations
+                .get(&(kind, from))
+                .ok_or(SchemaError::MissingMigration { kind, from })?;
+            data =
+                migration(data).map_err(|reason| SchemaError::Migration { kind, from, reason })?;
+        }
+        Ok(data)
+    }
+}
+
+impl Default for SchemaRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl fmt::Debug for SchemaRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SchemaRegistry")
+            .field("versions", &self.versions)
+            .field("migrations", &self.migrations.len())
+            .finish()
+    }
+}
+
+/// separate header and data; input without a header is a legacy artifact
+fn split_envelope(kind: ArtifactKind, value: Value) -> Result<(SchemaHeader, Value), SchemaError> {
+    match value {
+        Value::Object(mut map) if map.contains_key("schema") && map.contains_key("data") => {
+            let header: SchemaHeader =
+                serde_json::from_value(map.remove("schema").unwrap_or_default())?;
+            Ok((header, map.remove("data").unwrap_or_default()))
+        }
+        other => Ok((
+            SchemaHeader {
+                kind,
+                version: LEGACY_VERSION,
+            },
+            other,
+        )),
+    }
+}
+
+static REGISTRY: Lazy<SchemaRegistry> = Lazy::new(SchemaRegistry::builtin);
+
+/// process-wide registry with the built-in versions and migrations
+pub fn registry() -> &'static SchemaRegistry {
+    &REGISTRY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct ModelV3 {
+        name: String,
+        weights: Vec<f64>,
+        bias: f64,
+    }
+
+    fn model_registry() -> SchemaRegistry {
+        let mut registry = SchemaRegistry::builtin();
+        registry.register(ArtifactKind::Model, 3);
+        // v2 renamed "w" to "weights"
+        registry.register_migration(ArtifactKind::Model, 1, |mut data| {
+            let w = data
+                .get_mut("w")
+                .map(Value::take)
+                .ok_or_else(|| "missing field w".to_string())?;
+            data["weights"] = w;
+            data.as_object_mut().map(|o| o.remove("w"));
+            Ok(data)
+        });
+        // v3 added "bias"
+        registry.register_migration(ArtifactKind::Model, 2, |mut data| {
+            data["bias"] = json!(0.0);
+            Ok(data)
+        });
+        registry
+    }
+
+    #[test]
+    fn test_roundtrip_embeds_header() {
+        let registry = model_registry();
+        let model = ModelV3 {
+            name: "ensemble".to_string(),
+            weights: vec![0.5, 0.25],
+            bias: 0.1,
+        };
+
+        let json = registry.to_json(ArtifactKind::Model, &model).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["schema"], json!({"kind": "model", "version": 3}));
+
+        let loaded: ModelV3 = registry.from_json(ArtifactKind::Model, &json).unwrap();
+        assert_eq!(loaded, model);
+    }
+
+    #[test]
+    fn test_old_and_legacy_artifacts_migrate() {
+        let registry = model_registry();
+        let expected = ModelV3 {
+            name: "ensemble".to_string(),
+            weights: vec![1.0],
+            bias: 0.0,
+        };
+
+        let v1 = r#"{"schema":{"kind":"model","version":1},"data":{"name":"ensemble","w":[1.0]}}"#;
+        let loaded: ModelV3 = registry.from_json(ArtifactKind::Model, v1).unwrap();
+        assert_eq!(loaded, expected);
+
+        // pre-registry files have no header and the v1 layout
+        let legacy = r#"{"name":"ensemble","w":[1.0]}"#;
+        let loaded: ModelV3 = registry.from_json(ArtifactKind::Model, legacy).unwrap();
+        assert_eq!(loaded, expected);
+    }
+
+    #[test]
+    fn test_rejects_newer_foreign_and_broken_artifacts() {
+        let registry = model_registry();
+
+        let newer = r#"{"schema":{"kind":"model","version":4},"data":{}}"#;
+        assert_eq!(
+            registry
+                .from_json::<Value>(ArtifactKind::Model, newer)
+                .unwrap_err(),
+            SchemaError::UnsupportedVersion {
+                kind: ArtifactKind::Model,
+                version: 4,
+                current: 3
+            }
+        );
+
+        let profile = registry.to_json(ArtifactKind::Profile, &json!({})).unwrap();
+        assert!(matches!(
+            registry.from_json::<Value>(ArtifactKind::Model, &profile),
+            Err(SchemaError::KindMismatch { .. })
+        ));
+
+        let broken = r#"{"schema":{"kind":"model","version":1},"data":{"name":"x"}}"#;
+        assert!(matches!(
+            registry.from_json::<Value>(ArtifactKind::Model, broken),
+            Err(SchemaError::Migration { from: 1, .. })
+        ));
+
+        let mut gap = SchemaRegistry::builtin();
+        gap.register(ArtifactKind::Config, 2);
+        assert_eq!(
+            gap.migrate(ArtifactKind::Config, json!({})).unwrap_err(),
+            SchemaError::MissingMigration {
+                kind: ArtifactKind::Config,
+                from: 1
+            }
+        );
+    }
+}
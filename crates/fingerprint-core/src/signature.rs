@@ -4,12 +4,12 @@
 //! Reference: Huginn Net Signature structure design
 
 use crate::dicttls::supported_groups::CurveID;
-use crate::fingerprint::{Fingerprint, FingerprintType};
+use crate::fingerprint::{FieldValue, Fingerprint, FingerprintType};
 use crate::grease::{filter_grease_values, is_grease_value};
+use crate::hashing::{self, FingerprintHasher, HashAlgorithm, HashDigest, HashUpgrade};
 use crate::metadata::FingerprintMetadata;
 use crate::stable_hash::StableHashBuilder;
 use crate::version::TlsVersion;
-use sha2::{Digest, Sha256};
 
 /// TLS ClientHello signature
 /// Extracts all key information from ClientHello message
@@ -59,19 +59,38 @@ impl ClientHelloSignature {
     }
 
     /// Calculates the fingerprint ID (based on signature trait)
+    ///
+    /// Uses the configured content hash backend; see [`crate::hashing`].
     pub fn calculate_id(&self) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(self.version.to_u16().to_be_bytes());
-        hasher.update(self.cipher_suites_without_grease().len().to_be_bytes());
+        self.id_digest(hashing::config().content).to_string()
+    }
+
+    /// Checks a stored ID against this signature and returns its replacement
+    ///
+    /// IDs written before hashing backends were selectable are untagged
+    /// SHA-256 hex over the same fields.
+    pub fn migrate_id(&self, stored: &str) -> HashUpgrade {
+        hashing::migrate_stored_hash(
+            stored,
+            hashing::config().content,
+            |algorithm| self.id_digest(algorithm),
+            || self.id_digest(HashAlgorithm::Sha256).to_hex(),
+        )
+    }
+
+    fn id_digest(&self, algorithm: HashAlgorithm) -> HashDigest {
+        let mut hasher = FingerprintHasher::new(algorithm);
+        hasher.update(&self.version.to_u16().to_be_bytes());
+        hasher.update(&self.cipher_suites_without_grease().len().to_be_bytes());
         for &cs in &self.cipher_suites_without_grease() {
-            hasher.update(cs.to_be_bytes());
+            hasher.update(&cs.to_be_bytes());
         }
-        hasher.update(self.extensions_without_grease().len().to_be_bytes());
+        hasher.update(&self.extensions_without_grease().len().to_be_bytes());
         for &ext in &self.extensions_without_grease() {
-            hasher.update(ext.to_be_bytes());
+            hasher.update(&ext.to_be_bytes());
         }
         for &curve in &self.elliptic_curves {
-            hasher.update(curve.to_be_bytes());
+            hasher.update(&curve.to_be_bytes());
         }
         if let Some(ref sni) = self.sni {
             hasher.update(sni.as_bytes());
@@ -79,7 +98,7 @@ impl ClientHelloSignature {
         if let Some(ref alpn) = self.alpn {
             hasher.update(alpn.as_bytes());
         }
-        format!("{:x}", hasher.finalize())
+        hasher.finish()
     }
 
     /// Gets filtered cipher suites without GREASE values
@@ -183,6 +202,30 @@ impl Fingerprint for ClientHelloSignature {
             self.extensions_without_grease().len()
         )
     }
+
+    fn fields(&self) -> Vec<(&'static str, FieldValue)> {
+        let mut fields = vec![
+            ("version", FieldValue::Number(self.version.to_u16() as f64)),
+            (
+                "cipher_suites",
+                FieldValue::codes(&self.cipher_suites_without_grease()),
+            ),
+            (
+                "extensions",
+                FieldValue::codes(&self.extensions_without_grease()),
+            ),
+            (
+                "signature_algorithms",
+                FieldValue::codes(&self.signature_algorithms_without_grease()),
+            ),
+            ("elliptic_curves", FieldValue::codes(&self.elliptic_curves)),
+            ("sni", FieldValue::Bool(self.sni.is_some())),
+        ];
+        if let Some(alpn) = &self.alpn {
+            fields.push(("alpn", FieldValue::Text(alpn.clone())));
+        }
+        fields
+    }
 }
 
 impl Default for ClientHelloSignature {
@@ -0,0 +1,224 @@
+//! Similarity estimates with confidence intervals
+//!
+//! Comparison APIs reduce two fingerprints to a single score, which hides how
+//! much that score depends on which features happened to agree. A score of 0.7
+//! made of ten features that each agree 70% is more trustworthy than one made
+//! of a perfect cipher match and a total extension mismatch.
+//!
+//! [`BootstrapConfig::estimate`] resamples the per-feature scores of a
+//! comparison with replacement and recomputes the weighted similarity for
+//! each resample; the spread of those values gives a percentile confidence
+//! interval. Thresholds can then be applied to [`SimilarityEstimate::ci_low`]
+//! ("certainly similar") or [`SimilarityEstimate::ci_high`] ("certainly
+//! different") instead of the point value.
+//!
+//! Resampling is seeded, so the same inputs always produce the same interval.
+
+use serde::{Deserialize, Serialize};
+
+/// Score of one compared feature
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FeatureScore {
+    /// Feature name, for reporting
+    pub name: &'static str,
+    /// Weight of the feature in the overall score
+    pub weight: f64,
+    /// Similarity of the feature in [0, 1]
+    pub similarity: f64,
+}
+
+impl FeatureScore {
+    pub fn new(name: &'static str, weight: f64, similarity: f64) -> Self {
+        Self {
+            name,
+            weight,
+            similarity,
+        }
+    }
+}
+
+/// Similarity with a bootstrap confidence interval, all in [0, 1]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SimilarityEstimate {
+    /// Mean over the bootstrap resamples
+    pub mean: f64,
+    /// Lower bound of the confidence interval
+    pub ci_low: f64,
+    /// Upper bound of the confidence interval
+    pub ci_high: f64,
+    /// Standard deviation over the resamples
+    pub std_dev: f64,
+}
+
+impl SimilarityEstimate {
+    /// An estimate without uncertainty
+    pub fn exact(similarity: f64) -> Self {
+        Self {
+            mean: similarity,
+            ci_low: similarity,
+            ci_high: similarity,
+            std_dev: 0.0,
+        }
+    }
+
+    /// Width of the confidence interval
+    pub fn width(&self) -> f64 {
+        self.ci_high - self.ci_low
+    }
+
+    /// Variance over the resamples
+    pub fn variance(&self) -> f64 {
+        self.std_dev * self.std_dev
+    }
+
+    /// Whether the whole interval is at or above `threshold`
+    pub fn confidently_above(&self, threshold: f64) -> bool {
+        self.ci_low >= threshold
+    }
+
+    /// Whether the whole interval is below `threshold`
+    pub fn confidently_below(&self, threshold: f64) -> bool {
+        self.ci_high < threshold
+    }
+}
+
+/// Bootstrap settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BootstrapConfig {
+    /// Number of resamples
+    pub samples: usize,
+    /// Confidence level of the interval, e.g. 0.95
+    pub confidence: f64,
+    /// Resampling seed
+    pub seed: u64,
+}
+
+impl Default for BootstrapConfig {
+    fn default() -> Self {
+        Self {
+            samples: 500,
+            confidence: 0.95,
+            seed: 0x5eed_b007,
+        }
+    }
+}
+
+impl BootstrapConfig {
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Estimate the weighted similarity of `features` by resampling them
+    ///
+    /// Zero-weight features are ignored. With no usable feature the estimate
+    /// is an exact 0, with a single one it is that feature's similarity.
+    pub fn estimate(&self, features: &[FeatureScore]) -> SimilarityEstimate {
+        let features: Vec<&FeatureScore> = features.iter().filter(|f| f.weight > 0.0).collect();
+        if features.is_empty() {
+            return SimilarityEstimate::exact(0.0);
+        }
+        if features.len() == 1 || self.samples == 0 {
+            return SimilarityEstimate::exact(weighted(features.iter().copied()));
+        }
+
+        let mut state = self.seed;
+        let mut values: Vec<f64> = (0..self.samples)
+            .map(|_| {
+                weighted((0..features.len()).map(|_| {
+                    let pick = next_random(&mut state) % features.len() as u64;
+                    features[pick as usize]
+                }))
+            })
+            .collect();
+        values.sort_by(f64::total_cmp);
+
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+        let alpha = (1.0 - self.confidence.clamp(0.0, 1.0)) / 2.0;
+        SimilarityEstimate {
+            mean,
+            ci_low: percentile(&values, alpha),
+            ci_high: percentile(&values, 1.0 - alpha),
+            std_dev: variance.sqrt(),
+        }
+    }
+}
+
+fn weighted<'a>(features: impl Iterator<Item = &'a FeatureScore>) -> f64 {
+    let (sum, weight) = features.fold((0.0, 0.0), |(sum, weight), f| {
+        (sum + f.weight * f.similarity, weight + f.weight)
+    });
+    (sum / weight).clamp(0.0, 1.0)
+}
+
+/// Value at quantile `q` of sorted `values`
+fn percentile(values: &[f64], q: f64) -> f64 {
+    let index = (q * (values.len() - 1) as f64).round() as usize;
+    values[index.min(values.len() - 1)]
+}
+
+/// splitmix64 step
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agreeing_features_give_a_tight_interval() {
+        let uniform: Vec<_> = ["a", "b", "c", "d", "e", "f"]
+            .iter()
+            .map(|name| FeatureScore::new(name, 1.0, 0.7))
+            .collect();
+        let estimate = BootstrapConfig::default().estimate(&uniform);
+        assert!((estimate.mean - 0.7).abs() < 1e-9);
+        assert!(estimate.width() < 1e-9);
+
+        // same point value, but carried by a single feature
+        let mixed = vec![
+            FeatureScore::new("a", 1.0, 1.0),
+            FeatureScore::new("b", 1.0, 1.0),
+            FeatureScore::new("c", 1.0, 1.0),
+            FeatureScore::new("d", 1.0, 1.0),
+            FeatureScore::new("e", 1.0, 0.2),
+            FeatureScore::new("f", 1.0, 0.0),
+        ];
+        let estimate = BootstrapConfig::default().estimate(&mixed);
+        assert!((estimate.mean - 0.7).abs() < 0.05);
+        assert!(estimate.ci_low < 0.7 && estimate.ci_high > 0.7);
+        assert!(estimate.width() > 0.2);
+        assert!(!estimate.confidently_above(0.6));
+        assert_eq!(estimate, BootstrapConfig::default().estimate(&mixed));
+    }
+
+    #[test]
+    fn degenerate_inputs_are_exact() {
+        assert_eq!(
+            BootstrapConfig::default().estimate(&[]),
+            SimilarityEstimate::exact(0.0)
+        );
+        let single = [FeatureScore::new("a", 2.0, 0.4)];
+        assert_eq!(
+            BootstrapConfig::default().estimate(&single),
+            SimilarityEstimate::exact(0.4)
+        );
+    }
+}
@@ -0,0 +1,606 @@
+//! STIX 2.1 threat-intel objects
+//!
+//! The subset of STIX 2.1 needed to exchange TLS fingerprints with a threat
+//! intelligence platform: bundles of identities, indicators, sightings and
+//! incidents. Objects of other types are kept as raw JSON so that bundles
+//! from external feeds still parse.
+//!
+//! JA3 / JA4 hashes have no standard STIX observable, so indicators use the
+//! custom `x-tls-fingerprint` extension of `network-traffic`:
+//!
+//! ```text
+//! [network-traffic:extensions.'x-tls-fingerprint'.ja4 = 't13d1516h2_8daaf6152771_02713d6af862']
+//! ```
+//!
+//! [`FingerprintIndicator::parse_pattern`] is more lenient and accepts any
+//! comparison whose object path names `ja3` or `ja4`, which covers the
+//! variants used by common feeds.
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// `spec_version` of emitted objects
+pub const SPEC_VERSION: &str = "2.1";
+
+/// Extension name used in emitted patterns
+pub const TLS_FINGERPRINT_EXTENSION: &str = "x-tls-fingerprint";
+
+/// Namespace of deterministic object IDs
+const ID_NAMESPACE: &[u8] = b"fingerprint-rust/stix";
+
+/// `type--uuid` identifier with a random (v4) UUID
+pub fn random_id(object_type: &str) -> String {
+    let mut bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    format!("{}--{}", object_type, format_uuid(&bytes))
+}
+
+/// `type--uuid` identifier derived from `name`
+///
+/// The same name always yields the same ID, so re-publishing an indicator
+/// updates the existing object instead of creating a duplicate. The UUID is
+/// a version 8 (custom) UUID over SHA-256.
+pub fn deterministic_id(object_type: &str, name: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(ID_NAMESPACE)
+        .chain_update(object_type.as_bytes())
+        .chain_update([0])
+        .chain_update(name.as_bytes())
+        .finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x80;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    format!("{}--{}", object_type, format_uuid(&bytes))
+}
+
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// 0.0 – 1.0 confidence as a STIX 0 – 100 value
+pub fn confidence_to_stix(confidence: f64) -> u8 {
+    (confidence.clamp(0.0, 1.0) * 100.0).round() as u8
+}
+
+/// STIX 0 – 100 confidence as 0.0 – 1.0
+pub fn confidence_from_stix(confidence: u8) -> f64 {
+    f64::from(confidence.min(100)) / 100.0
+}
+
+fn spec_version() -> String {
+    SPEC_VERSION.to_string()
+}
+
+/// Producer of objects (`identity`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Identity {
+    #[serde(default = "spec_version")]
+    pub spec_version: String,
+    pub id: String,
+    pub created: DateTime<Utc>,
+    pub modified: DateTime<Utc>,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_class: Option<String>,
+}
+
+impl Identity {
+    /// System identity with an ID derived from `name`
+    pub fn system(name: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            spec_version: spec_version(),
+            id: deterministic_id("identity", name),
+            created: now,
+            modified: now,
+            name: name.to_string(),
+            identity_class: Some("system".to_string()),
+        }
+    }
+}
+
+/// Detection pattern (`indicator`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Indicator {
+    #[serde(default = "spec_version")]
+    pub spec_version: String,
+    pub id: String,
+    pub created: DateTime<Utc>,
+    pub modified: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by_ref: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub indicator_types: Vec<String>,
+    pub pattern: String,
+    pub pattern_type: String,
+    pub valid_from: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// 0 – 100
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<u8>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub revoked: bool,
+}
+
+impl Indicator {
+    /// Indicator matching one fingerprint, with an ID derived from it
+    pub fn for_fingerprint(fingerprint: &FingerprintIndicator, valid_from: DateTime<Utc>) -> Self {
+        let now = Utc::now();
+        Self {
+            spec_version: spec_version(),
+            id: fingerprint.stix_id(),
+            created: now,
+            modified: now,
+            created_by_ref: None,
+            name: Some(format!("{} {}", fingerprint.kind, fingerprint.value)),
+            description: None,
+            indicator_types: vec!["malicious-activity".to_string()],
+            pattern: fingerprint.pattern(),
+            pattern_type: "stix".to_string(),
+            valid_from,
+            valid_until: None,
+            labels: Vec::new(),
+            confidence: None,
+            revoked: false,
+        }
+    }
+
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        self.confidence = Some(confidence_to_stix(confidence));
+        self
+    }
+
+    pub fn with_creator(mut self, identity: &Identity) -> Self {
+        self.created_by_ref = Some(identity.id.clone());
+        self
+    }
+
+    /// JA3 / JA4 hashes in a `stix` pattern; empty for other pattern languages
+    pub fn fingerprints(&self) -> Vec<FingerprintIndicator> {
+        if self.pattern_type != "stix" {
+            return Vec::new();
+        }
+        FingerprintIndicator::parse_pattern(&self.pattern)
+    }
+
+    /// Usable at `now`: not revoked and within its validity window
+    pub fn is_valid_at(&self, now: DateTime<Utc>) -> bool {
+        !self.revoked && self.valid_from <= now && self.valid_until.is_none_or(|t| now < t)
+    }
+}
+
+/// An indicator was seen (`sighting`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sighting {
+    #[serde(default = "spec_version")]
+    pub spec_version: String,
+    pub id: String,
+    pub created: DateTime<Utc>,
+    pub modified: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by_ref: Option<String>,
+    pub sighting_of_ref: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub where_sighted_refs: Vec<String>,
+}
+
+impl Sighting {
+    /// Sighting of `indicator` by `identity`
+    pub fn of(indicator: &Indicator, identity: &Identity) -> Self {
+        let now = Utc::now();
+        Self {
+            spec_version: spec_version(),
+            id: random_id("sighting"),
+            created: now,
+            modified: now,
+            created_by_ref: Some(identity.id.clone()),
+            sighting_of_ref: indicator.id.clone(),
+            description: None,
+            first_seen: None,
+            last_seen: None,
+            count: None,
+            where_sighted_refs: vec![identity.id.clone()],
+        }
+    }
+
+    pub fn with_observations(
+        mut self,
+        count: u64,
+        first_seen: DateTime<Utc>,
+        last_seen: DateTime<Utc>,
+    ) -> Self {
+        self.count = Some(count);
+        self.first_seen = Some(first_seen);
+        self.last_seen = Some(last_seen);
+        self
+    }
+}
+
+/// Security event (`incident`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Incident {
+    #[serde(default = "spec_version")]
+    pub spec_version: String,
+    pub id: String,
+    pub created: DateTime<Utc>,
+    pub modified: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by_ref: Option<String>,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+}
+
+/// Any STIX object
+#[derive(Debug, Clone, PartialEq)]
+pub enum StixObject {
+    Identity(Identity),
+    Indicator(Indicator),
+    Sighting(Sighting),
+    Incident(Incident),
+    /// other types, kept verbatim
+    Other(serde_json::Value),
+}
+
+impl StixObject {
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            StixObject::Identity(o) => Some(&o.id),
+            StixObject::Indicator(o) => Some(&o.id),
+            StixObject::Sighting(o) => Some(&o.id),
+            StixObject::Incident(o) => Some(&o.id),
+            StixObject::Other(value) => value.get("id").and_then(|id| id.as_str()),
+        }
+    }
+
+    pub fn object_type(&self) -> &str {
+        match self {
+            StixObject::Identity(_) => "identity",
+            StixObject::Indicator(_) => "indicator",
+            StixObject::Sighting(_) => "sighting",
+            StixObject::Incident(_) => "incident",
+            StixObject::Other(value) => value
+                .get("type")
+                .and_then(|t| t.as_str())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn as_indicator(&self) -> Option<&Indicator> {
+        match self {
+            StixObject::Indicator(indicator) => Some(indicator),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for StixObject {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut value = match self {
+            StixObject::Identity(o) => serde_json::to_value(o),
+            StixObject::Indicator(o) => serde_json::to_value(o),
+            StixObject::Sighting(o) => serde_json::to_value(o),
+            StixObject::Incident(o) => serde_json::to_value(o),
+            StixObject::Other(value) => return value.serialize(serializer),
+        }
+        .map_err(serde::ser::Error::custom)?;
+        if let Some(map) = value.as_object_mut() {
+            map.insert("type".to_string(), self.object_type().into());
+        }
+        value.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for StixObject {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let object = match value.get("type").and_then(|t| t.as_str()) {
+            Some("identity") => {
+                StixObject::Identity(serde_json::from_value(value).map_err(D::Error::custom)?)
+            }
+            Some("indicator") => {
+                StixObject::Indicator(serde_json::from_value(value).map_err(D::Error::custom)?)
+            }
+            Some("sighting") => {
+                StixObject::Sighting(serde_json::from_value(value).map_err(D::Error::custom)?)
+            }
+            Some("incident") => {
+                StixObject::Incident(serde_json::from_value(value).map_err(D::Error::custom)?)
+            }
+            Some(_) => StixObject::Other(value),
+            None => return Err(D::Error::missing_field("type")),
+        };
+        Ok(object)
+    }
+}
+
+/// Collection of objects (`bundle`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bundle {
+    #[serde(rename = "type")]
+    pub object_type: String,
+    pub id: String,
+    #[serde(default)]
+    pub objects: Vec<StixObject>,
+}
+
+impl Bundle {
+    pub fn new(objects: Vec<StixObject>) -> Self {
+        Self {
+            object_type: "bundle".to_string(),
+            id: random_id("bundle"),
+            objects,
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    pub fn indicators(&self) -> impl Iterator<Item = &Indicator> {
+        self.objects.iter().filter_map(StixObject::as_indicator)
+    }
+}
+
+/// TLS fingerprint kind matched by an indicator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FingerprintKind {
+    Ja3,
+    Ja4,
+}
+
+impl FingerprintKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FingerprintKind::Ja3 => "ja3",
+            FingerprintKind::Ja4 => "ja4",
+        }
+    }
+
+    /// Whether `value` is well formed for this kind
+    pub fn accepts(&self, value: &str) -> bool {
+        match self {
+            FingerprintKind::Ja3 => {
+                value.len() == 32 && value.bytes().all(|b| b.is_ascii_hexdigit())
+            }
+            FingerprintKind::Ja4 => {
+                let sections: Vec<&str> = value.split('_').collect();
+                sections.len() == 3
+                    && sections[0].len() == 10
+                    && sections[0].is_ascii()
+                    && matches!(sections[0].as_bytes()[0], b't' | b'q' | b'd')
+                    && sections[1..]
+                        .iter()
+                        .all(|s| s.len() == 12 && s.bytes().all(|b| b.is_ascii_hexdigit()))
+            }
+        }
+    }
+}
+
+impl fmt::Display for FingerprintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.as_str().to_ascii_uppercase())
+    }
+}
+
+/// A JA3 / JA4 hash carried by an indicator
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FingerprintIndicator {
+    pub kind: FingerprintKind,
+    /// JA3 lowercased; JA4 as given
+    pub value: String,
+}
+
+impl FingerprintIndicator {
+    pub fn new(kind: FingerprintKind, value: &str) -> Option<Self> {
+        kind.accepts(value).then(|| Self {
+            kind,
+            value: match kind {
+                FingerprintKind::Ja3 => value.to_ascii_lowercase(),
+                FingerprintKind::Ja4 => value.to_string(),
+            },
+        })
+    }
+
+    /// Recognise a bare hash as JA3 or JA4
+    pub fn classify(value: &str) -> Option<Self> {
+        Self::new(FingerprintKind::Ja3, value).or_else(|| Self::new(FingerprintKind::Ja4, value))
+    }
+
+    /// `[network-traffic:extensions.'x-tls-fingerprint'.<kind> = '<value>']`
+    pub fn pattern(&self) -> String {
+        format!(
+            "[network-traffic:extensions.'{}'.{} = '{}']",
+            TLS_FINGERPRINT_EXTENSION,
+            self.kind.as_str(),
+            self.value
+        )
+    }
+
+    /// ID of the indicator for this fingerprint
+    pub fn stix_id(&self) -> String {
+        deterministic_id(
+            "indicator",
+            &format!("{}:{}", self.kind.as_str(), self.value),
+        )
+    }
+
+    /// Hashes compared with `=` against a `ja3` / `ja4` path in `pattern`
+    ///
+    /// Server-side hashes (`ja3s`, `ja4s`) and malformed values are ignored.
+    pub fn parse_pattern(pattern: &str) -> Vec<Self> {
+        let mut found = Vec::new();
+        let bytes = pattern.as_bytes();
+        let mut pos = 0;
+        while let Some(offset) = pattern[pos..].find('=') {
+            let eq = pos + offset;
+            pos = eq + 1;
+            if eq > 0 && matches!(bytes[eq - 1], b'!' | b'<' | b'>') {
+                continue;
+            }
+            let Some(value) = quoted_value(&pattern[eq + 1..]) else {
+                continue;
+            };
+            let path = pattern[..eq]
+                .trim_end()
+                .rsplit(|c: char| c.is_whitespace() || c == '[' || c == '(')
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            let kind = match path_kind(&path) {
+                Some(kind) => kind,
+                None => continue,
+            };
+            if let Some(fingerprint) = Self::new(kind, &value) {
+                if !found.contains(&fingerprint) {
+                    found.push(fingerprint);
+                }
+            }
+        }
+        found
+    }
+}
+
+/// Fingerprint kind named by the last path segment mentioning ja3 / ja4
+fn path_kind(path: &str) -> Option<FingerprintKind> {
+    let segment = path
+        .rsplit(['.', ':'])
+        .map(|s| s.trim_matches('\''))
+        .find(|s| s.contains("ja3") || s.contains("ja4"))?;
+    if segment.contains("ja3s") || segment.contains("ja4s") {
+        return None;
+    }
+    if segment.contains("ja4") {
+        Some(FingerprintKind::Ja4)
+    } else {
+        Some(FingerprintKind::Ja3)
+    }
+}
+
+/// Single-quoted string at the start of `rest`, with `\'` and `\\` unescaped
+fn quoted_value(rest: &str) -> Option<String> {
+    let mut chars = rest.trim_start().strip_prefix('\'')?.chars();
+    let mut value = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next()?),
+            '\'' => return Some(value),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+impl fmt::Display for FingerprintIndicator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.kind, self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JA4: &str = "t13d1516h2_8daaf6152771_02713d6af862";
+    const JA3: &str = "e7d705a3286e19ea42f587b344ee6865";
+
+    #[test]
+    fn test_pattern_round_trip_and_feed_variants() {
+        let ja4 = FingerprintIndicator::classify(JA4).unwrap();
+        assert_eq!(ja4.kind, FingerprintKind::Ja4);
+        assert_eq!(
+            FingerprintIndicator::parse_pattern(&ja4.pattern()),
+            vec![ja4.clone()]
+        );
+        assert_eq!(
+            ja4.stix_id(),
+            FingerprintIndicator::classify(JA4).unwrap().stix_id()
+        );
+
+        let feed = format!(
+            "[network-traffic:extensions.'tls-ext'.ja3_hash = '{}'] OR \
+             [x-tls:ja3s = '{}'] OR [x-tls:ja4 != '{}'] OR [file:name = 'ja3']",
+            JA3.to_ascii_uppercase(),
+            JA3,
+            JA4
+        );
+        let parsed = FingerprintIndicator::parse_pattern(&feed);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].kind, FingerprintKind::Ja3);
+        assert_eq!(parsed[0].value, JA3);
+    }
+
+    #[test]
+    fn test_bundle_serialization_keeps_unknown_objects() {
+        let identity = Identity::system("edge-gateway");
+        let fingerprint = FingerprintIndicator::classify(JA3).unwrap();
+        let indicator = Indicator::for_fingerprint(&fingerprint, Utc::now())
+            .with_confidence(0.934)
+            .with_creator(&identity);
+        let sighting = Sighting::of(&indicator, &identity).with_observations(
+            12,
+            indicator.valid_from,
+            indicator.valid_from,
+        );
+        let mut bundle = Bundle::new(vec![
+            StixObject::Identity(identity),
+            StixObject::Indicator(indicator),
+            StixObject::Sighting(sighting),
+        ]);
+        bundle.objects.push(StixObject::Other(serde_json::json!({
+            "type": "malware",
+            "id": "malware--31b940d4-6f7f-459a-80ea-9c1f17b5891b",
+            "name": "Poison Ivy"
+        })));
+
+        let json = bundle.to_json().unwrap();
+        assert!(json.contains("\"type\":\"indicator\""));
+        assert!(json.contains("\"confidence\":93"));
+        assert!(json.contains("\"spec_version\":\"2.1\""));
+        assert!(!json.contains("revoked"));
+
+        let back = Bundle::from_json(&json).unwrap();
+        assert_eq!(back, bundle);
+        assert_eq!(back.objects[3].object_type(), "malware");
+        let indicator = back.indicators().next().unwrap();
+        assert_eq!(indicator.fingerprints(), vec![fingerprint]);
+        assert!(indicator.id.starts_with("indicator--"));
+        assert_eq!(&indicator.id["indicator--".len() + 14..][..1], "8");
+    }
+}
@@ -36,20 +36,25 @@ pub enum ThreatType {
 }
 
 impl ThreatType {
-    /// convert tostring
-    pub fn as_str(&self) -> &'static str {
+    /// Stable message code; use this rather than the display text for matching
+    pub fn code(&self) -> &'static str {
         match self {
-            Self::UnknownFingerprint => "not知fingerprint",
-            Self::SuspiciousBehavior => "suspiciousbehavior",
-            Self::KnownAttack => "already知attack",
-            Self::AbnormalTrafficPattern => "abnormaltrafficpattern",
-            Self::MaliciousIP => "maliciousIP",
-            Self::DDoS => "DDoSattack",
-            Self::PortScan => "portscan",
-            Self::BruteForce => "brute force",
+            Self::UnknownFingerprint => "threat.unknown_fingerprint",
+            Self::SuspiciousBehavior => "threat.suspicious_behavior",
+            Self::KnownAttack => "threat.known_attack",
+            Self::AbnormalTrafficPattern => "threat.abnormal_traffic_pattern",
+            Self::MaliciousIP => "threat.malicious_ip",
+            Self::DDoS => "threat.ddos",
+            Self::PortScan => "threat.port_scan",
+            Self::BruteForce => "threat.brute_force",
         }
     }
 
+    /// convert tostring (English; `Display` follows the configured locale)
+    pub fn as_str(&self) -> &'static str {
+        crate::i18n::english(self.code())
+    }
+
     /// Getthreatseverity (0.0 - 1.0)
     pub fn severity(&self) -> f64 {
         match self {
@@ -67,7 +72,7 @@ impl ThreatType {
 
 impl std::fmt::Display for ThreatType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.as_str())
+        f.write_str(&crate::i18n::tr(self.code(), &[]))
     }
 }
 
@@ -243,6 +243,7 @@ impl FingerprintManager {
                 FingerprintType::Tls => 1.0,
                 FingerprintType::Http => 0.8,
                 FingerprintType::Tcp => 0.6,
+                FingerprintType::Content => 0.4,
             };
             level * weight
         }).sum();
@@ -252,6 +253,7 @@ impl FingerprintManager {
                 FingerprintType::Tls => 1.0,
                 FingerprintType::Http => 0.8,
                 FingerprintType::Tcp => 0.6,
+                FingerprintType::Content => 0.4,
             }
         }).sum();
         
@@ -288,6 +290,7 @@ impl Default for SystemConfiguration {
                 (FingerprintType::Tls, true),
                 (FingerprintType::Http, true),
                 (FingerprintType::Tcp, true),
+                (FingerprintType::Content, true),
             ].into_iter().collect(),
             performance_settings: PerformanceSettings::default(),
             integration_settings: IntegrationSettings::default(),
@@ -4,6 +4,7 @@
 
 use super::flow::NetworkFlow;
 use super::stats::SystemProtectionStats;
+use crate::i18n::tr;
 use std::time::Duration;
 
 /// system-level protection decision
@@ -58,19 +59,20 @@ impl SystemProtectionDecision {
     /// Getdecisiondescribe
     pub fn description(&self) -> String {
         match self {
-            Self::Allow => "allowthrough".to_string(),
-            Self::Deny { reason } => format!("block: {}", reason),
+            Self::Allow => tr("decision.allow", &[]),
+            Self::Deny { reason } => tr("decision.deny", &[("reason", reason)]),
             Self::RateLimit {
                 max_packets_per_second,
                 duration,
-            } => {
-                format!(
-                    "rate limit: {} 包/seconds, continuous when between: {:?}",
-                    max_packets_per_second, duration
-                )
-            }
-            Self::Log { reason } => format!("record: {}", reason),
-            Self::RequiresAnalysis => "needfurtheranalysis".to_string(),
+            } => tr(
+                "decision.rate_limit",
+                &[
+                    ("rate", max_packets_per_second),
+                    ("duration", &format!("{:?}", duration)),
+                ],
+            ),
+            Self::Log { reason } => tr("decision.log", &[("reason", reason)]),
+            Self::RequiresAnalysis => tr("decision.requires_analysis", &[]),
         }
     }
 }
@@ -145,7 +147,7 @@ impl SystemProtectionResult {
             },
             risk_score,
             confidence: 0.8,
-            reason: "trafficabnormal，needrate limit".to_string(),
+            reason: tr("decision.abnormal_traffic", &[]),
             suggested_actions: vec!["monitortraffic".to_string()],
         }
     }
@@ -2,7 +2,8 @@
 //!
 //! Defines the TCP fingerprint data structure.
 
-use crate::fingerprint::{Fingerprint, FingerprintType};
+use crate::fingerprint::{FieldValue, Fingerprint, FingerprintType};
+use crate::hashing::{self, FingerprintHasher, HashAlgorithm, HashDigest, HashUpgrade};
 use crate::metadata::FingerprintMetadata;
 use crate::stable_hash::StableHashBuilder;
 
@@ -248,24 +249,59 @@ impl TcpFingerprint {
         }
     }
 
-    /// Calculates the fingerprint ID
+    /// Calculates the fingerprint ID with the configured content hash backend
     fn calculate_id(
         ttl: u8,
         window_size: u16,
         mss: Option<u16>,
         window_scale: Option<u8>,
     ) -> String {
-        use sha2::{Digest, Sha256};
-        let mut hasher = Sha256::new();
-        hasher.update([ttl]);
-        hasher.update(window_size.to_be_bytes());
+        Self::id_digest(
+            hashing::config().content,
+            ttl,
+            window_size,
+            mss,
+            window_scale,
+        )
+        .to_string()
+    }
+
+    /// Checks a stored ID against this fingerprint and returns its replacement
+    ///
+    /// IDs written before hashing backends were selectable are untagged
+    /// SHA-256 hex over the same fields.
+    pub fn migrate_id(&self, stored: &str) -> HashUpgrade {
+        let digest = |algorithm| {
+            Self::id_digest(
+                algorithm,
+                self.ttl,
+                self.window_size,
+                self.mss,
+                self.window_scale,
+            )
+        };
+        hashing::migrate_stored_hash(stored, hashing::config().content, digest, || {
+            digest(HashAlgorithm::Sha256).to_hex()
+        })
+    }
+
+    fn id_digest(
+        algorithm: HashAlgorithm,
+        ttl: u8,
+        window_size: u16,
+        mss: Option<u16>,
+        window_scale: Option<u8>,
+    ) -> HashDigest {
+        let mut hasher = FingerprintHasher::new(algorithm);
+        hasher.update(&[ttl]);
+        hasher.update(&window_size.to_be_bytes());
         if let Some(mss_val) = mss {
-            hasher.update(mss_val.to_be_bytes());
+            hasher.update(&mss_val.to_be_bytes());
         }
         if let Some(ws_val) = window_scale {
-            hasher.update([ws_val]);
+            hasher.update(&[ws_val]);
         }
-        format!("{:x}", hasher.finalize())
+        hasher.finish()
     }
 
     /// Infers the initial TTL according to common OS defaults
@@ -324,6 +360,23 @@ impl Fingerprint for TcpFingerprint {
             self.id, self.ttl, self.window_size
         )
     }
+
+    fn fields(&self) -> Vec<(&'static str, FieldValue)> {
+        let mut fields = vec![
+            ("ttl", FieldValue::Number(self.ttl as f64)),
+            ("window_size", FieldValue::Number(self.window_size as f64)),
+        ];
+        if let Some(mss) = self.mss {
+            fields.push(("mss", FieldValue::Number(mss as f64)));
+        }
+        if let Some(scale) = self.window_scale {
+            fields.push(("window_scale", FieldValue::Number(scale as f64)));
+        }
+        if let Some(options) = &self.options_str {
+            fields.push(("options", FieldValue::Text(options.clone())));
+        }
+        fields
+    }
 }
 
 #[cfg(test)]
@@ -345,6 +398,32 @@ mod tests {
         assert_eq!(fp.window_scale, Some(7));
     }
 
+    #[test]
+    fn test_tcp_fingerprint_id_migration() {
+        use sha2::{Digest, Sha256};
+
+        let fp = TcpFingerprint::with_options(64, 65535, Some(1460), Some(7));
+        assert!(fp.id.starts_with("blake3:"));
+        assert_eq!(fp.migrate_id(&fp.id), HashUpgrade::Current);
+
+        // ID as written by releases that hashed with SHA-256 directly
+        let mut legacy = Sha256::new();
+        legacy.update([64u8]);
+        legacy.update(65535u16.to_be_bytes());
+        legacy.update(1460u16.to_be_bytes());
+        legacy.update([7u8]);
+        let legacy_id = format!("{:x}", legacy.finalize());
+
+        match fp.migrate_id(&legacy_id) {
+            HashUpgrade::Upgraded(digest) => assert_eq!(digest.to_string(), fp.id),
+            other => panic!("expected upgrade, got {:?}", other),
+        }
+        assert_eq!(
+            TcpFingerprint::new(128, 8192).migrate_id(&legacy_id),
+            HashUpgrade::Mismatch
+        );
+    }
+
     #[test]
     fn test_infer_initial_ttl() {
         let fp1 = TcpFingerprint::new(64, 65535);
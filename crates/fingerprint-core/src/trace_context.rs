@@ -0,0 +1,151 @@
+//! W3C Trace Context
+//!
+//! [`TraceContext`] is the `traceparent` value (version 00) carried between services,
+//! so spans recorded by a client and the server it calls join one trace.
+
+use std::fmt;
+
+/// `traceparent` header name
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Trace id, parent span id and sampling flag of a `traceparent` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TraceContext {
+    /// 16-byte trace id, never all zero
+    pub trace_id: [u8; 16],
+    /// 8-byte id of the span the next hop is a child of, never all zero
+    pub span_id: [u8; 8],
+    /// `sampled` trace flag
+    pub sampled: bool,
+}
+
+impl TraceContext {
+    /// Start a new sampled trace
+    pub fn new_root() -> Self {
+        Self {
+            trace_id: random_nonzero(),
+            span_id: random_nonzero(),
+            sampled: true,
+        }
+    }
+
+    /// Same trace, new span id
+    pub fn child(&self) -> Self {
+        Self {
+            span_id: random_nonzero(),
+            ..*self
+        }
+    }
+
+    /// Parse a `traceparent` header value
+    ///
+    /// Accepts future versions by reading only their version 00 prefix, as the spec asks.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let mut parts = value.splitn(5, '-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let span_id = parts.next()?;
+        let flags = parts.next()?;
+        let rest = parts.next();
+        if parse_hex::<1>(version).is_none() || version == "ff" {
+            return None;
+        }
+        if version == "00" && rest.is_some() {
+            return None;
+        }
+        let [flags] = parse_hex::<1>(flags)?;
+        let context = Self {
+            trace_id: parse_hex(trace_id)?,
+            span_id: parse_hex(span_id)?,
+            sampled: flags & 0x01 != 0,
+        };
+        let valid = context.trace_id != [0; 16] && context.span_id != [0; 8];
+        valid.then_some(context)
+    }
+
+    /// Trace id as 32 lowercase hex digits
+    pub fn trace_id_hex(&self) -> String {
+        hex(&self.trace_id)
+    }
+
+    /// Span id as 16 lowercase hex digits
+    pub fn span_id_hex(&self) -> String {
+        hex(&self.span_id)
+    }
+
+    /// `traceparent` header value
+    pub fn traceparent(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "00-{}-{}-{:02x}",
+            self.trace_id_hex(),
+            self.span_id_hex(),
+            u8::from(self.sampled)
+        )
+    }
+}
+
+fn random_nonzero<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    loop {
+        rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut bytes);
+        if bytes != [0; N] {
+            return bytes;
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn parse_hex<const N: usize>(s: &str) -> Option<[u8; N]> {
+    if s.len() != N * 2 || !s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        return None;
+    }
+    let mut bytes = [0u8; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traceparent_round_trip() {
+        let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let context = TraceContext::parse(header).unwrap();
+        assert!(context.sampled);
+        assert_eq!(context.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(context.traceparent(), header);
+
+        let child = context.child();
+        assert_eq!(child.trace_id, context.trace_id);
+        assert_ne!(child.span_id, context.span_id);
+        assert_eq!(TraceContext::parse(&child.traceparent()), Some(child));
+
+        let future = "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-extra";
+        assert!(!TraceContext::parse(future).unwrap().sampled);
+
+        for invalid in [
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+        ] {
+            assert_eq!(TraceContext::parse(invalid), None, "{}", invalid);
+        }
+    }
+}
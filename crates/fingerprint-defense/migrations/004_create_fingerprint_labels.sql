@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS fingerprint_labels (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    fingerprint_type TEXT NOT NULL,
+    fingerprint_id TEXT NOT NULL,
+    source TEXT NOT NULL,
+    weight REAL NOT NULL,
+    subject TEXT,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_label_type_id
+ON fingerprint_labels(fingerprint_type, fingerprint_id);
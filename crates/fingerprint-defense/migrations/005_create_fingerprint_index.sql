@@ -0,0 +1,36 @@
+CREATE TABLE IF NOT EXISTS fingerprint_index (
+    kind TEXT NOT NULL,
+    value TEXT NOT NULL,
+    transport TEXT,
+    tls_version TEXT,
+    destination TEXT,
+    cipher_count INTEGER,
+    extension_count INTEGER,
+    alpn TEXT,
+    cipher_hash TEXT,
+    extension_hash TEXT,
+    signature_hash TEXT,
+    hit_count INTEGER NOT NULL DEFAULT 1,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL,
+    PRIMARY KEY (kind, value)
+);
+
+CREATE INDEX IF NOT EXISTS idx_fingerprint_index_value
+ON fingerprint_index(value, kind);
+
+CREATE INDEX IF NOT EXISTS idx_fingerprint_index_counts
+ON fingerprint_index(tls_version, cipher_count, extension_count);
+
+CREATE INDEX IF NOT EXISTS idx_fingerprint_index_cipher_hash
+ON fingerprint_index(cipher_hash);
+
+CREATE INDEX IF NOT EXISTS idx_fingerprint_index_extension_hash
+ON fingerprint_index(extension_hash);
+
+CREATE TABLE IF NOT EXISTS fingerprint_index_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    last_fingerprint_id INTEGER NOT NULL
+);
+
+INSERT OR IGNORE INTO fingerprint_index_state (id, last_fingerprint_id) VALUES (1, 0);
@@ -0,0 +1,27 @@
+CREATE TABLE IF NOT EXISTS fingerprint_alerts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    fingerprint_type TEXT NOT NULL,
+    fingerprint_id TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    message TEXT NOT NULL,
+    subject TEXT,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_alert_fingerprint
+ON fingerprint_alerts(fingerprint_id, created_at);
+
+CREATE INDEX IF NOT EXISTS idx_alert_subject
+ON fingerprint_alerts(subject);
+
+CREATE INDEX IF NOT EXISTS idx_label_subject
+ON fingerprint_labels(subject);
+
+CREATE INDEX IF NOT EXISTS idx_fingerprints_fp_id
+ON fingerprints(fp_id);
+
+CREATE INDEX IF NOT EXISTS idx_fingerprints_ja4_plus
+ON fingerprints(ja4_plus);
+
+CREATE INDEX IF NOT EXISTS idx_flows_source_ip
+ON flows(source_ip, timestamp);
@@ -0,0 +1,171 @@
+-- Change log feeding warm-standby replication.
+-- Triggers record every row change on the primary; a standby applies them in seq order.
+CREATE TABLE IF NOT EXISTS replication_log (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    table_name TEXT NOT NULL,
+    op TEXT NOT NULL,
+    row_key TEXT NOT NULL,
+    row_data TEXT,
+    recorded_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
+
+-- role: 'primary' logs changes, 'standby' applies them
+-- epoch: bumped on promotion; changes from an older epoch are refused
+-- applied_seq: on a standby, the last primary seq applied
+-- pruned_seq: on a primary, the last seq removed from the log
+CREATE TABLE IF NOT EXISTS replication_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    role TEXT NOT NULL DEFAULT 'primary',
+    epoch INTEGER NOT NULL DEFAULT 1,
+    applied_seq INTEGER NOT NULL DEFAULT 0,
+    pruned_seq INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT OR IGNORE INTO replication_state (id) VALUES (1);
+
+CREATE TRIGGER IF NOT EXISTS replicate_flows_insert
+AFTER INSERT ON flows WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('flows', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'source_ip', NEW.source_ip, 'target_ip', NEW.target_ip, 'protocol', NEW.protocol, 'timestamp', NEW.timestamp, 'consistency_score', NEW.consistency_score, 'bot_detected', NEW.bot_detected));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_flows_update
+AFTER UPDATE ON flows WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('flows', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'source_ip', NEW.source_ip, 'target_ip', NEW.target_ip, 'protocol', NEW.protocol, 'timestamp', NEW.timestamp, 'consistency_score', NEW.consistency_score, 'bot_detected', NEW.bot_detected));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_flows_delete
+AFTER DELETE ON flows WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('flows', 'delete', json_object('id', OLD.id), NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprints_insert
+AFTER INSERT ON fingerprints WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprints', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'flow_id', NEW.flow_id, 'fp_type', NEW.fp_type, 'fp_id', NEW.fp_id, 'ja4_plus', NEW.ja4_plus, 'metadata_json', NEW.metadata_json));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprints_update
+AFTER UPDATE ON fingerprints WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprints', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'flow_id', NEW.flow_id, 'fp_type', NEW.fp_type, 'fp_id', NEW.fp_id, 'ja4_plus', NEW.ja4_plus, 'metadata_json', NEW.metadata_json));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprints_delete
+AFTER DELETE ON fingerprints WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprints', 'delete', json_object('id', OLD.id), NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_candidate_fingerprints_insert
+AFTER INSERT ON candidate_fingerprints WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('candidate_fingerprints', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'fingerprint_type', NEW.fingerprint_type, 'fingerprint_id', NEW.fingerprint_id, 'observation_count', NEW.observation_count, 'stability_score', NEW.stability_score, 'first_seen', NEW.first_seen, 'last_seen', NEW.last_seen, 'status', NEW.status, 'notes', NEW.notes));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_candidate_fingerprints_update
+AFTER UPDATE ON candidate_fingerprints WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('candidate_fingerprints', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'fingerprint_type', NEW.fingerprint_type, 'fingerprint_id', NEW.fingerprint_id, 'observation_count', NEW.observation_count, 'stability_score', NEW.stability_score, 'first_seen', NEW.first_seen, 'last_seen', NEW.last_seen, 'status', NEW.status, 'notes', NEW.notes));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_candidate_fingerprints_delete
+AFTER DELETE ON candidate_fingerprints WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('candidate_fingerprints', 'delete', json_object('id', OLD.id), NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_labels_insert
+AFTER INSERT ON fingerprint_labels WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_labels', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'fingerprint_type', NEW.fingerprint_type, 'fingerprint_id', NEW.fingerprint_id, 'source', NEW.source, 'weight', NEW.weight, 'subject', NEW.subject, 'created_at', NEW.created_at));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_labels_update
+AFTER UPDATE ON fingerprint_labels WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_labels', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'fingerprint_type', NEW.fingerprint_type, 'fingerprint_id', NEW.fingerprint_id, 'source', NEW.source, 'weight', NEW.weight, 'subject', NEW.subject, 'created_at', NEW.created_at));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_labels_delete
+AFTER DELETE ON fingerprint_labels WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_labels', 'delete', json_object('id', OLD.id), NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_index_insert
+AFTER INSERT ON fingerprint_index WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_index', 'upsert', json_object('kind', NEW.kind, 'value', NEW.value), json_object('kind', NEW.kind, 'value', NEW.value, 'transport', NEW.transport, 'tls_version', NEW.tls_version, 'destination', NEW.destination, 'cipher_count', NEW.cipher_count, 'extension_count', NEW.extension_count, 'alpn', NEW.alpn, 'cipher_hash', NEW.cipher_hash, 'extension_hash', NEW.extension_hash, 'signature_hash', NEW.signature_hash, 'hit_count', NEW.hit_count, 'first_seen', NEW.first_seen, 'last_seen', NEW.last_seen));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_index_update
+AFTER UPDATE ON fingerprint_index WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_index', 'upsert', json_object('kind', NEW.kind, 'value', NEW.value), json_object('kind', NEW.kind, 'value', NEW.value, 'transport', NEW.transport, 'tls_version', NEW.tls_version, 'destination', NEW.destination, 'cipher_count', NEW.cipher_count, 'extension_count', NEW.extension_count, 'alpn', NEW.alpn, 'cipher_hash', NEW.cipher_hash, 'extension_hash', NEW.extension_hash, 'signature_hash', NEW.signature_hash, 'hit_count', NEW.hit_count, 'first_seen', NEW.first_seen, 'last_seen', NEW.last_seen));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_index_delete
+AFTER DELETE ON fingerprint_index WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_index', 'delete', json_object('kind', OLD.kind, 'value', OLD.value), NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_index_state_insert
+AFTER INSERT ON fingerprint_index_state WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_index_state', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'last_fingerprint_id', NEW.last_fingerprint_id));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_index_state_update
+AFTER UPDATE ON fingerprint_index_state WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_index_state', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'last_fingerprint_id', NEW.last_fingerprint_id));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_index_state_delete
+AFTER DELETE ON fingerprint_index_state WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_index_state', 'delete', json_object('id', OLD.id), NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_alerts_insert
+AFTER INSERT ON fingerprint_alerts WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_alerts', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'fingerprint_type', NEW.fingerprint_type, 'fingerprint_id', NEW.fingerprint_id, 'severity', NEW.severity, 'message', NEW.message, 'subject', NEW.subject, 'created_at', NEW.created_at));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_alerts_update
+AFTER UPDATE ON fingerprint_alerts WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_alerts', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'fingerprint_type', NEW.fingerprint_type, 'fingerprint_id', NEW.fingerprint_id, 'severity', NEW.severity, 'message', NEW.message, 'subject', NEW.subject, 'created_at', NEW.created_at));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_alerts_delete
+AFTER DELETE ON fingerprint_alerts WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_alerts', 'delete', json_object('id', OLD.id), NULL);
+END;
@@ -0,0 +1,81 @@
+-- Re-scoring runs over historical flows.
+-- Each run writes a new verdict version per flow; the original verdict in flows is never touched.
+CREATE TABLE IF NOT EXISTS backfill_jobs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    window_start TEXT NOT NULL,
+    window_end TEXT NOT NULL,
+    label_threshold REAL,
+    note TEXT,
+    status TEXT NOT NULL DEFAULT 'pending',
+    flows_scanned INTEGER NOT NULL DEFAULT 0,
+    flows_changed INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    created_at INTEGER NOT NULL,
+    finished_at INTEGER
+);
+
+-- version 0 is the original verdict in flows; backfills add 1, 2, ...
+CREATE TABLE IF NOT EXISTS flow_verdicts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    flow_id TEXT NOT NULL,
+    job_id INTEGER NOT NULL,
+    version INTEGER NOT NULL,
+    consistency_score INTEGER NOT NULL,
+    bot_detected BOOLEAN NOT NULL,
+    label_prior REAL,
+    discrepancy_codes TEXT NOT NULL,
+    previous_score INTEGER NOT NULL,
+    previous_bot BOOLEAN NOT NULL,
+    created_at INTEGER NOT NULL,
+    UNIQUE(flow_id, version),
+    FOREIGN KEY(flow_id) REFERENCES flows(id),
+    FOREIGN KEY(job_id) REFERENCES backfill_jobs(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_flow_verdicts_job
+ON flow_verdicts(job_id, id);
+
+CREATE INDEX IF NOT EXISTS idx_flows_timestamp
+ON flows(timestamp, id);
+
+CREATE TRIGGER IF NOT EXISTS replicate_backfill_jobs_insert
+AFTER INSERT ON backfill_jobs WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('backfill_jobs', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'window_start', NEW.window_start, 'window_end', NEW.window_end, 'label_threshold', NEW.label_threshold, 'note', NEW.note, 'status', NEW.status, 'flows_scanned', NEW.flows_scanned, 'flows_changed', NEW.flows_changed, 'error', NEW.error, 'created_at', NEW.created_at, 'finished_at', NEW.finished_at));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_backfill_jobs_update
+AFTER UPDATE ON backfill_jobs WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('backfill_jobs', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'window_start', NEW.window_start, 'window_end', NEW.window_end, 'label_threshold', NEW.label_threshold, 'note', NEW.note, 'status', NEW.status, 'flows_scanned', NEW.flows_scanned, 'flows_changed', NEW.flows_changed, 'error', NEW.error, 'created_at', NEW.created_at, 'finished_at', NEW.finished_at));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_backfill_jobs_delete
+AFTER DELETE ON backfill_jobs WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('backfill_jobs', 'delete', json_object('id', OLD.id), NULL);
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_flow_verdicts_insert
+AFTER INSERT ON flow_verdicts WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('flow_verdicts', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'flow_id', NEW.flow_id, 'job_id', NEW.job_id, 'version', NEW.version, 'consistency_score', NEW.consistency_score, 'bot_detected', NEW.bot_detected, 'label_prior', NEW.label_prior, 'discrepancy_codes', NEW.discrepancy_codes, 'previous_score', NEW.previous_score, 'previous_bot', NEW.previous_bot, 'created_at', NEW.created_at));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_flow_verdicts_update
+AFTER UPDATE ON flow_verdicts WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('flow_verdicts', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'flow_id', NEW.flow_id, 'job_id', NEW.job_id, 'version', NEW.version, 'consistency_score', NEW.consistency_score, 'bot_detected', NEW.bot_detected, 'label_prior', NEW.label_prior, 'discrepancy_codes', NEW.discrepancy_codes, 'previous_score', NEW.previous_score, 'previous_bot', NEW.previous_bot, 'created_at', NEW.created_at));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_flow_verdicts_delete
+AFTER DELETE ON flow_verdicts WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('flow_verdicts', 'delete', json_object('id', OLD.id), NULL);
+END;
@@ -0,0 +1,39 @@
+-- Soft-deleted learned fingerprints.
+-- Candidate rows of a tombstoned fingerprint keep status 'deleted'; the tombstone
+-- remembers why, and raises the evidence needed to learn the fingerprint again.
+CREATE TABLE IF NOT EXISTS fingerprint_tombstones (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    fingerprint_type TEXT NOT NULL,
+    fingerprint_id TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    note TEXT,
+    evidence_multiplier REAL NOT NULL,
+    previous_status TEXT,
+    resurrections INTEGER NOT NULL DEFAULT 0,
+    deleted_at INTEGER NOT NULL,
+    UNIQUE(fingerprint_type, fingerprint_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_tombstones_reason_deleted
+ON fingerprint_tombstones(reason, deleted_at);
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_tombstones_insert
+AFTER INSERT ON fingerprint_tombstones WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_tombstones', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'fingerprint_type', NEW.fingerprint_type, 'fingerprint_id', NEW.fingerprint_id, 'reason', NEW.reason, 'note', NEW.note, 'evidence_multiplier', NEW.evidence_multiplier, 'previous_status', NEW.previous_status, 'resurrections', NEW.resurrections, 'deleted_at', NEW.deleted_at));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_tombstones_update
+AFTER UPDATE ON fingerprint_tombstones WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_tombstones', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'fingerprint_type', NEW.fingerprint_type, 'fingerprint_id', NEW.fingerprint_id, 'reason', NEW.reason, 'note', NEW.note, 'evidence_multiplier', NEW.evidence_multiplier, 'previous_status', NEW.previous_status, 'resurrections', NEW.resurrections, 'deleted_at', NEW.deleted_at));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_fingerprint_tombstones_delete
+AFTER DELETE ON fingerprint_tombstones WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('fingerprint_tombstones', 'delete', json_object('id', OLD.id), NULL);
+END;
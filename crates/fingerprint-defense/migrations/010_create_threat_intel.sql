@@ -0,0 +1,43 @@
+-- Indicators ingested from STIX / TAXII threat-intel feeds.
+-- One row per fingerprint named by an indicator's pattern; a newer version of the
+-- same STIX object (by `modified_ms`) replaces the row.
+CREATE TABLE IF NOT EXISTS threat_intel_indicators (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    stix_id TEXT NOT NULL,
+    fingerprint_type TEXT NOT NULL,
+    fingerprint_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    source TEXT NOT NULL,
+    name TEXT,
+    confidence REAL NOT NULL,
+    valid_from INTEGER NOT NULL,
+    valid_until INTEGER,
+    revoked INTEGER NOT NULL DEFAULT 0,
+    modified_ms INTEGER NOT NULL,
+    imported_at INTEGER NOT NULL,
+    UNIQUE(stix_id, fingerprint_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_threat_intel_fingerprint
+ON threat_intel_indicators(fingerprint_type, fingerprint_id);
+
+CREATE TRIGGER IF NOT EXISTS replicate_threat_intel_indicators_insert
+AFTER INSERT ON threat_intel_indicators WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('threat_intel_indicators', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'stix_id', NEW.stix_id, 'fingerprint_type', NEW.fingerprint_type, 'fingerprint_id', NEW.fingerprint_id, 'kind', NEW.kind, 'source', NEW.source, 'name', NEW.name, 'confidence', NEW.confidence, 'valid_from', NEW.valid_from, 'valid_until', NEW.valid_until, 'revoked', NEW.revoked, 'modified_ms', NEW.modified_ms, 'imported_at', NEW.imported_at));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_threat_intel_indicators_update
+AFTER UPDATE ON threat_intel_indicators WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('threat_intel_indicators', 'upsert', json_object('id', NEW.id), json_object('id', NEW.id, 'stix_id', NEW.stix_id, 'fingerprint_type', NEW.fingerprint_type, 'fingerprint_id', NEW.fingerprint_id, 'kind', NEW.kind, 'source', NEW.source, 'name', NEW.name, 'confidence', NEW.confidence, 'valid_from', NEW.valid_from, 'valid_until', NEW.valid_until, 'revoked', NEW.revoked, 'modified_ms', NEW.modified_ms, 'imported_at', NEW.imported_at));
+END;
+
+CREATE TRIGGER IF NOT EXISTS replicate_threat_intel_indicators_delete
+AFTER DELETE ON threat_intel_indicators WHEN (SELECT role FROM replication_state WHERE id = 1) = 'primary'
+BEGIN
+    INSERT INTO replication_log (table_name, op, row_key, row_data)
+    VALUES ('threat_intel_indicators', 'delete', json_object('id', OLD.id), NULL);
+END;
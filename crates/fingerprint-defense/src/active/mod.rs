@@ -0,0 +1,437 @@
+//! Active server probing
+//!
+//! Passive analysis sees what clients send; this module asks servers. An
+//! [`ActiveProber`] runs an ordered [`ProbePlan`] (TLS ClientHello variations,
+//! malformed requests, header echo tests) against a target, one connection per
+//! probe, and records how each connection ended together with the features of
+//! the response: HTTP status and headers, TLS alerts, JA3S/JA4S of a
+//! ServerHello, and whether a per-run token was echoed back. A
+//! [`SignatureDatabase`] then maps the responses to web server, CDN and WAF
+//! stacks.
+//!
+//! Probes are paced by [`ProberConfig::probes_per_second`] across all targets
+//! of a prober, so a plan never bursts at a server. Only probe hosts you are
+//! authorized to test.
+
+pub mod plan;
+pub mod signatures;
+
+pub use plan::{PlanError, Probe, ProbeKind, ProbePlan, SniMode, DEFAULT_PLAN};
+pub use signatures::{
+    Condition, ServerSignature, SignatureDatabase, SignatureRule, StackKind, StackMatch,
+};
+
+use crate::passive::tls::TlsAnalyzer;
+use crate::passive::Packet;
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Body bytes kept for matching
+const MAX_BODY_CHARS: usize = 4096;
+
+/// Prober limits
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProberConfig {
+    pub connect_timeout: Duration,
+    /// Idle time after which a response is considered complete
+    pub read_timeout: Duration,
+    /// Response bytes read per probe
+    pub max_response_bytes: usize,
+    /// Probes started per second, over all targets
+    pub probes_per_second: f64,
+}
+
+impl Default for ProberConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(3),
+            read_timeout: Duration::from_secs(3),
+            max_response_bytes: 16 * 1024,
+            probes_per_second: 2.0,
+        }
+    }
+}
+
+impl ProberConfig {
+    pub fn with_rate(mut self, probes_per_second: f64) -> Self {
+        self.probes_per_second = probes_per_second;
+        self
+    }
+
+    pub fn with_timeouts(mut self, connect: Duration, read: Duration) -> Self {
+        self.connect_timeout = connect;
+        self.read_timeout = read;
+        self
+    }
+}
+
+/// How a probe connection ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeOutcome {
+    /// The server answered
+    Data,
+    /// Closed without answering
+    Closed,
+    /// Reset by the server
+    Reset,
+    /// No answer within the read timeout
+    Timeout,
+    /// Connection refused or not established in time
+    Refused,
+    /// Probe could not be built or sent
+    Error,
+}
+
+/// Features of a probe response
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResponseFeatures {
+    /// HTTP status code
+    pub status: Option<u16>,
+    /// HTTP headers in order, names lowercased
+    pub headers: Vec<(String, String)>,
+    /// Start of the HTTP body (or of a non-HTTP response), lossy UTF-8
+    pub body: String,
+    /// TLS alert description
+    pub tls_alert: Option<u8>,
+    pub ja3s: Option<String>,
+    pub ja4s: Option<String>,
+    /// The per-run token appears in the response
+    pub echoed: bool,
+}
+
+impl ResponseFeatures {
+    /// Extract features from raw response bytes
+    pub fn extract(data: &[u8], token: &str) -> Self {
+        let mut features = Self {
+            echoed: !token.is_empty() && data.windows(token.len()).any(|w| w == token.as_bytes()),
+            ..Default::default()
+        };
+
+        if data.starts_with(b"HTTP/") {
+            let (head, body) = match data.windows(4).position(|w| w == b"\r\n\r\n") {
+                Some(end) => (&data[..end], &data[end + 4..]),
+                None => (data, &[][..]),
+            };
+            let head = String::from_utf8_lossy(head);
+            let mut lines = head.split("\r\n");
+            features.status = lines
+                .next()
+                .and_then(|line| line.split(' ').nth(1))
+                .and_then(|code| code.parse().ok());
+            features.headers = lines
+                .filter_map(|line| line.split_once(':'))
+                .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+                .collect();
+            features.body = lossy_prefix(body);
+        } else if data.len() >= 7 && data[0] == 0x15 && data[1] == 0x03 {
+            features.tls_alert = Some(data[6]);
+        } else if data.len() >= 6 && data[0] == 0x16 && data[1] == 0x03 {
+            let packet = Packet {
+                src_ip: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                dst_ip: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                src_port: None,
+                dst_port: None,
+                protocol: 6,
+                ttl: 0,
+                ip_flags: 0,
+                data: Vec::new(),
+                payload: data.to_vec(),
+                tcp_header: None,
+            };
+            if let Some(server) = TlsAnalyzer.analyze_server(&packet) {
+                features.ja3s = Some(server.ja3s);
+                features.ja4s = Some(server.ja4s);
+            }
+        } else {
+            features.body = lossy_prefix(data);
+        }
+        features
+    }
+
+    /// Value of the first header called `name` (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+fn lossy_prefix(data: &[u8]) -> String {
+    String::from_utf8_lossy(&data[..data.len().min(MAX_BODY_CHARS)]).into_owned()
+}
+
+/// Result of one probe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbeResponse {
+    pub probe: String,
+    pub outcome: ProbeOutcome,
+    /// Response bytes read
+    pub bytes: usize,
+    pub elapsed_ms: u64,
+    /// Connection or build error
+    pub error: Option<String>,
+    pub features: ResponseFeatures,
+}
+
+/// Result of a probe run against one target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbeReport {
+    pub target: SocketAddr,
+    pub host: String,
+    pub responses: Vec<ProbeResponse>,
+    /// Matching stacks, best score first
+    pub matches: Vec<StackMatch>,
+}
+
+impl ProbeReport {
+    /// Best match of one stack layer
+    pub fn best(&self, kind: StackKind) -> Option<&StackMatch> {
+        self.matches.iter().find(|m| m.kind == kind)
+    }
+
+    pub fn response(&self, probe: &str) -> Option<&ProbeResponse> {
+        self.responses.iter().find(|r| r.probe == probe)
+    }
+}
+
+/// Runs probe plans against remote servers
+pub struct ActiveProber {
+    config: ProberConfig,
+    plan: ProbePlan,
+    signatures: SignatureDatabase,
+    /// Earliest start of the next probe
+    next_slot: tokio::sync::Mutex<tokio::time::Instant>,
+}
+
+impl ActiveProber {
+    /// Prober with the built-in plan and signatures
+    pub fn new(config: ProberConfig) -> Self {
+        Self {
+            config,
+            plan: ProbePlan::default(),
+            signatures: SignatureDatabase::builtin(),
+            next_slot: tokio::sync::Mutex::new(tokio::time::Instant::now()),
+        }
+    }
+
+    pub fn with_plan(mut self, plan: ProbePlan) -> Self {
+        self.plan = plan;
+        self
+    }
+
+    pub fn with_signatures(mut self, signatures: SignatureDatabase) -> Self {
+        self.signatures = signatures;
+        self
+    }
+
+    pub fn plan(&self) -> &ProbePlan {
+        &self.plan
+    }
+
+    pub fn signatures(&self) -> &SignatureDatabase {
+        &self.signatures
+    }
+
+    /// Run the plan against `target`, using `host` for SNI and Host headers
+    pub async fn probe(&self, target: SocketAddr, host: &str) -> ProbeReport {
+        let token = format!("{:016x}", rand::random::<u64>());
+        let mut responses = Vec::with_capacity(self.plan.len());
+        for probe in self.plan.probes() {
+            self.pace().await;
+            let addr = SocketAddr::new(target.ip(), probe.port.unwrap_or(target.port()));
+            responses.push(self.run(probe, addr, host, &token).await);
+        }
+        let matches = self.signatures.identify(&responses);
+        ProbeReport {
+            target,
+            host: host.to_string(),
+            responses,
+            matches,
+        }
+    }
+
+    /// Wait for the next probe slot
+    async fn pace(&self) {
+        if self.config.probes_per_second <= 0.0 {
+            return;
+        }
+        let interval = Duration::from_secs_f64(1.0 / self.config.probes_per_second);
+        let slot = {
+            let mut next = self.next_slot.lock().await;
+            let slot = (*next).max(tokio::time::Instant::now());
+            *next = slot + interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+
+    async fn run(&self, probe: &Probe, addr: SocketAddr, host: &str, token: &str) -> ProbeResponse {
+        let started = Instant::now();
+        let (outcome, data, error) = match probe.payload(host, token) {
+            Ok(payload) => self.exchange(addr, &payload).await,
+            Err(e) => (ProbeOutcome::Error, Vec::new(), Some(e)),
+        };
+        ProbeResponse {
+            probe: probe.name.clone(),
+            outcome,
+            bytes: data.len(),
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            error,
+            features: ResponseFeatures::extract(&data, token),
+        }
+    }
+
+    /// Send `payload` on a fresh connection and read the answer
+    async fn exchange(
+        &self,
+        addr: SocketAddr,
+        payload: &[u8],
+    ) -> (ProbeOutcome, Vec<u8>, Option<String>) {
+        let mut stream =
+            match tokio::time::timeout(self.config.connect_timeout, TcpStream::connect(addr)).await
+            {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => return (ProbeOutcome::Refused, Vec::new(), Some(e.to_string())),
+                Err(_) => {
+                    return (
+                        ProbeOutcome::Refused,
+                        Vec::new(),
+                        Some("connect timed out".to_string()),
+                    )
+                }
+            };
+        if let Err(e) = stream.write_all(payload).await {
+            return (outcome_of(&e), Vec::new(), Some(e.to_string()));
+        }
+
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            match tokio::time::timeout(self.config.read_timeout, stream.read(&mut buf)).await {
+                Ok(Ok(0)) => break,
+                Ok(Ok(n)) => {
+                    let room = self.config.max_response_bytes - data.len();
+                    data.extend_from_slice(&buf[..n.min(room)]);
+                    if data.len() >= self.config.max_response_bytes {
+                        break;
+                    }
+                }
+                Ok(Err(e)) if data.is_empty() => {
+                    return (outcome_of(&e), data, Some(e.to_string()));
+                }
+                // a reset after the answer still leaves a usable answer
+                Ok(Err(_)) => break,
+                Err(_) if data.is_empty() => return (ProbeOutcome::Timeout, data, None),
+                Err(_) => break,
+            }
+        }
+        let outcome = if data.is_empty() {
+            ProbeOutcome::Closed
+        } else {
+            ProbeOutcome::Data
+        };
+        (outcome, data, None)
+    }
+}
+
+fn outcome_of(error: &std::io::Error) -> ProbeOutcome {
+    match error.kind() {
+        ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe => {
+            ProbeOutcome::Reset
+        }
+        _ => ProbeOutcome::Error,
+    }
+}
+
+impl Default for ActiveProber {
+    fn default() -> Self {
+        Self::new(ProberConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// nginx-like server: 400 page for anything that is not a request line
+    async fn fake_nginx() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 8192];
+                    let n = stream.read(&mut buf).await.unwrap_or(0);
+                    let request = &buf[..n];
+                    if request.starts_with(&[0x16, 0x03]) {
+                        // TLS on a plaintext port: close without answering
+                        return;
+                    }
+                    let response = if request.starts_with(b"GET / HTTP/1.1") {
+                        "HTTP/1.1 200 OK\r\nServer: nginx/1.25.3\r\nContent-Length: 2\r\n\r\nok"
+                            .to_string()
+                    } else if request.starts_with(b"GET /?probe=") {
+                        let echo = String::from_utf8_lossy(request);
+                        let token = echo.split("X-Probe-Echo: ").nth(1).unwrap_or("");
+                        let token = token.split("\r\n").next().unwrap_or("");
+                        format!(
+                            "HTTP/1.1 200 OK\r\nServer: nginx/1.25.3\r\nX-Echo: {}\r\n\r\n",
+                            token
+                        )
+                    } else {
+                        "HTTP/1.1 400 Bad Request\r\nServer: nginx/1.25.3\r\n\r\n\
+                         <html><center><h1>400 Bad Request</h1></center>\
+                         <hr><center>nginx/1.25.3</center></html>"
+                            .to_string()
+                    };
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn identifies_local_nginx() {
+        let addr = fake_nginx().await;
+        let config = ProberConfig::default()
+            .with_rate(200.0)
+            .with_timeouts(Duration::from_secs(2), Duration::from_millis(300));
+        let prober = ActiveProber::new(config);
+        let report = prober.probe(addr, "localhost").await;
+
+        assert_eq!(report.responses.len(), prober.plan().len());
+        assert_eq!(
+            report.response("tls_chrome").unwrap().outcome,
+            ProbeOutcome::Closed
+        );
+        let bad = report.response("bad_request").unwrap();
+        assert_eq!(bad.features.status, Some(400));
+        assert!(report.response("header_echo").unwrap().features.echoed);
+
+        let server = report.best(StackKind::WebServer).unwrap();
+        assert_eq!(server.stack, "nginx");
+        assert_eq!(server.score, 1.0);
+        assert!(report.best(StackKind::Waf).is_none());
+    }
+
+    #[tokio::test]
+    async fn refused_targets_are_reported_per_probe() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let plan = ProbePlan::parse("raw ping hex=00").unwrap();
+        let prober = ActiveProber::new(ProberConfig::default().with_rate(0.0)).with_plan(plan);
+        let report = prober.probe(addr, "localhost").await;
+        assert_eq!(report.responses[0].outcome, ProbeOutcome::Refused);
+        assert!(report.matches.is_empty());
+    }
+}
@@ -0,0 +1,395 @@
+//! Probe plan DSL
+//!
+//! A plan is an ordered list of probes, one per line:
+//!
+//! ```text
+//! # kind  name          arguments
+//! tls     tls_chrome    profile=chrome_133
+//! tls     tls_no_sni    profile=firefox_133 sni=none port=443
+//! http    bad_version   "GET / HTTP/9.9\r\nHost: {host}\r\n\r\n"
+//! raw     tls_garbage   hex=16030100050100000100
+//! ```
+//!
+//! * `tls` sends the ClientHello of a browser profile; `sni=none` omits the
+//!   server name, `sni=<name>` overrides it.
+//! * `http` sends a quoted request; `{host}` expands to the probed host name
+//!   and `{token}` to a per-run random token used by header echo tests.
+//!   Quoted strings understand `\r`, `\n`, `\t`, `\\`, `\"` and `\xHH`.
+//! * `raw` sends `hex=` bytes verbatim.
+//!
+//! Every probe accepts `port=<n>` to go to a different port than the target.
+//! Blank lines and `#` comments are ignored.
+
+use fingerprint_tls::tls_config::ClientHelloSpec;
+use fingerprint_tls::tls_handshake::TLSHandshakeBuilder;
+use std::fmt;
+use std::str::FromStr;
+
+/// Built-in plan: TLS variations, malformed requests, header echo and a
+/// request a WAF should block
+pub const DEFAULT_PLAN: &str = r#"
+tls   tls_chrome    profile=chrome_133
+tls   tls_firefox   profile=firefox_133
+tls   tls_no_sni    profile=chrome_133 sni=none
+raw   tls_garbage   hex=16030100050100000100
+http  get_root      "GET / HTTP/1.1\r\nHost: {host}\r\nUser-Agent: Mozilla/5.0\r\nAccept: */*\r\nConnection: close\r\n\r\n"
+http  bad_version   "GET / HTTP/9.9\r\nHost: {host}\r\nConnection: close\r\n\r\n"
+http  bad_request   "\x00GARBAGE\r\n\r\n"
+http  header_echo   "GET /?probe={token} HTTP/1.1\r\nHost: {host}\r\nX-Probe-Echo: {token}\r\nConnection: close\r\n\r\n"
+http  attack        "GET /?id=1%27%20OR%201%3D1--&q=%3Cscript%3Ealert(1)%3C/script%3E HTTP/1.1\r\nHost: {host}\r\nUser-Agent: sqlmap/1.7\r\nConnection: close\r\n\r\n"
+"#;
+
+/// Error in a probe plan
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("probe plan line {line}: {message}")]
+pub struct PlanError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Server name sent by a TLS probe
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SniMode {
+    /// The probed host
+    Host,
+    /// No server_name extension
+    None,
+    /// A fixed name
+    Custom(String),
+}
+
+/// What a probe sends
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeKind {
+    /// ClientHello of a browser profile
+    Tls { profile: String, sni: SniMode },
+    /// Request template with `{host}` and `{token}` placeholders
+    Http { request: Vec<u8> },
+    /// Bytes sent verbatim
+    Raw { bytes: Vec<u8> },
+}
+
+/// One probe of a plan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Probe {
+    pub name: String,
+    pub kind: ProbeKind,
+    /// Port override
+    pub port: Option<u16>,
+}
+
+impl Probe {
+    /// Bytes to send to `host`
+    pub fn payload(&self, host: &str, token: &str) -> Result<Vec<u8>, String> {
+        match &self.kind {
+            ProbeKind::Tls { profile, sni } => {
+                let spec = tls_profile(profile)
+                    .ok_or_else(|| format!("unknown TLS profile: {}", profile))?;
+                let server_name = match sni {
+                    SniMode::Host => host,
+                    SniMode::None => "",
+                    SniMode::Custom(name) => name,
+                };
+                TLSHandshakeBuilder::build_client_hello(&spec, server_name)
+            }
+            ProbeKind::Http { request } => Ok(expand(request, host, token)),
+            ProbeKind::Raw { bytes } => Ok(bytes.clone()),
+        }
+    }
+}
+
+/// Ordered probes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbePlan {
+    probes: Vec<Probe>,
+}
+
+impl ProbePlan {
+    pub fn new(probes: Vec<Probe>) -> Self {
+        Self { probes }
+    }
+
+    /// Parse the plan DSL
+    pub fn parse(source: &str) -> Result<Self, PlanError> {
+        let mut probes: Vec<Probe> = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            let error = |message: String| PlanError {
+                line: index + 1,
+                message,
+            };
+            let tokens = tokenize(line).map_err(error)?;
+            let Some((kind, rest)) = tokens.split_first() else {
+                continue;
+            };
+            let Some((Token::Word(name), args)) = rest.split_first() else {
+                return Err(error("expected a probe name".into()));
+            };
+            if probes.iter().any(|p| &p.name == name) {
+                return Err(error(format!("duplicate probe name: {}", name)));
+            }
+
+            let mut options = Vec::new();
+            let mut quoted = None;
+            for arg in args {
+                match arg {
+                    Token::Quoted(bytes) if quoted.is_none() => quoted = Some(bytes.clone()),
+                    Token::Quoted(_) => return Err(error("more than one quoted string".into())),
+                    Token::Word(word) => {
+                        let (key, value) = word
+                            .split_once('=')
+                            .ok_or_else(|| error(format!("expected key=value, got {}", word)))?;
+                        options.push((key, value));
+                    }
+                }
+            }
+            let option = |key: &str| options.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+            if let Some((key, _)) = options
+                .iter()
+                .find(|(k, _)| !matches!(*k, "port" | "profile" | "sni" | "hex"))
+            {
+                return Err(error(format!("unknown option: {}", key)));
+            }
+            let port = option("port")
+                .map(|p| p.parse::<u16>())
+                .transpose()
+                .map_err(|e| error(format!("invalid port: {}", e)))?;
+
+            let kind = match kind {
+                Token::Word(kind) if kind == "tls" => {
+                    let profile = option("profile").unwrap_or("chrome_133");
+                    if tls_profile(profile).is_none() {
+                        return Err(error(format!("unknown TLS profile: {}", profile)));
+                    }
+                    let sni = match option("sni") {
+                        None => SniMode::Host,
+                        Some("none") => SniMode::None,
+                        Some(name) => SniMode::Custom(name.to_string()),
+                    };
+                    ProbeKind::Tls {
+                        profile: profile.to_string(),
+                        sni,
+                    }
+                }
+                Token::Word(kind) if kind == "http" => ProbeKind::Http {
+                    request: quoted.ok_or_else(|| error("http probe needs a request".into()))?,
+                },
+                Token::Word(kind) if kind == "raw" => {
+                    let hex = option("hex").ok_or_else(|| error("raw probe needs hex=".into()))?;
+                    ProbeKind::Raw {
+                        bytes: decode_hex(hex).ok_or_else(|| error("invalid hex".into()))?,
+                    }
+                }
+                _ => return Err(error("expected tls, http or raw".into())),
+            };
+            probes.push(Probe {
+                name: name.clone(),
+                kind,
+                port,
+            });
+        }
+        Ok(Self { probes })
+    }
+
+    pub fn probes(&self) -> &[Probe] {
+        &self.probes
+    }
+
+    pub fn len(&self) -> usize {
+        self.probes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.probes.is_empty()
+    }
+}
+
+impl Default for ProbePlan {
+    fn default() -> Self {
+        Self::parse(DEFAULT_PLAN).expect("built-in probe plan")
+    }
+}
+
+impl FromStr for ProbePlan {
+    type Err = PlanError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Probe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ProbeKind::Tls { profile, sni } => {
+                write!(f, "tls {} profile={}", self.name, profile)?;
+                match sni {
+                    SniMode::Host => {}
+                    SniMode::None => write!(f, " sni=none")?,
+                    SniMode::Custom(name) => write!(f, " sni={}", name)?,
+                }
+            }
+            ProbeKind::Http { request } => write!(f, "http {} \"{}\"", self.name, escape(request))?,
+            ProbeKind::Raw { bytes } => {
+                write!(f, "raw {} hex=", self.name)?;
+                bytes.iter().try_for_each(|b| write!(f, "{:02x}", b))?;
+            }
+        }
+        match self.port {
+            Some(port) => write!(f, " port={}", port),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for ProbePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.probes
+            .iter()
+            .try_for_each(|probe| writeln!(f, "{}", probe))
+    }
+}
+
+/// Browser profiles available to `tls` probes
+fn tls_profile(name: &str) -> Option<ClientHelloSpec> {
+    Some(match name {
+        "chrome_103" => ClientHelloSpec::chrome_103(),
+        "chrome_133" => ClientHelloSpec::chrome_133(),
+        "chrome_136" => ClientHelloSpec::chrome_136(),
+        "firefox_133" => ClientHelloSpec::firefox_133(),
+        "safari_16_0" => ClientHelloSpec::safari_16_0(),
+        _ => return None,
+    })
+}
+
+enum Token {
+    Word(String),
+    Quoted(Vec<u8>),
+}
+
+/// Split a line into words and quoted strings, dropping `#` comments
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '#' {
+            break;
+        } else if c == '"' {
+            chars.next();
+            let mut bytes = Vec::new();
+            loop {
+                match chars.next().ok_or("unterminated string")? {
+                    '"' => break,
+                    '\\' => match chars.next().ok_or("unterminated escape")? {
+                        'r' => bytes.push(b'\r'),
+                        'n' => bytes.push(b'\n'),
+                        't' => bytes.push(b'\t'),
+                        '\\' => bytes.push(b'\\'),
+                        '"' => bytes.push(b'"'),
+                        'x' => {
+                            let hex: String = chars.by_ref().take(2).collect();
+                            let byte = u8::from_str_radix(&hex, 16)
+                                .map_err(|_| format!("invalid escape \\x{}", hex))?;
+                            bytes.push(byte);
+                        }
+                        other => return Err(format!("invalid escape \\{}", other)),
+                    },
+                    c => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                }
+            }
+            tokens.push(Token::Quoted(bytes));
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        }
+    }
+    Ok(tokens)
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Inverse of the quoted-string escapes
+fn escape(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for &b in bytes {
+        match b {
+            b'\r' => out.push_str("\\r"),
+            b'\n' => out.push_str("\\n"),
+            b'\t' => out.push_str("\\t"),
+            b'\\' => out.push_str("\\\\"),
+            b'"' => out.push_str("\\\""),
+            0x20..=0x7e => out.push(b as char),
+            _ => out.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    out
+}
+
+/// Replace `{host}` and `{token}` in a request template
+fn expand(template: &[u8], host: &str, token: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(template.len());
+    let mut rest = template;
+    while !rest.is_empty() {
+        if let Some(tail) = rest.strip_prefix(b"{host}") {
+            out.extend_from_slice(host.as_bytes());
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix(b"{token}") {
+            out.extend_from_slice(token.as_bytes());
+            rest = tail;
+        } else {
+            out.push(rest[0]);
+            rest = &rest[1..];
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_prints_plans() {
+        let plan = ProbePlan::default();
+        assert_eq!(plan.probes()[0].name, "tls_chrome");
+        assert!(plan.probes().iter().any(|p| p.name == "header_echo"));
+        // printing and parsing again gives the same plan
+        assert_eq!(ProbePlan::parse(&plan.to_string()).unwrap(), plan);
+
+        let echo = plan
+            .probes()
+            .iter()
+            .find(|p| p.name == "header_echo")
+            .unwrap();
+        let request = echo.payload("example.com", "abc123").unwrap();
+        let request = String::from_utf8(request).unwrap();
+        assert!(request.contains("Host: example.com\r\n"));
+        assert!(request.contains("X-Probe-Echo: abc123\r\n"));
+
+        let tls = &plan.probes()[0];
+        assert_eq!(tls.payload("example.com", "").unwrap()[..2], [0x16, 0x03]);
+
+        let err = ProbePlan::parse("tls ok\nsmtp helo").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(ProbePlan::parse("tls a profile=netscape").is_err());
+        assert!(ProbePlan::parse("http a").is_err());
+        assert!(ProbePlan::parse("raw a hex=0g").is_err());
+        assert!(ProbePlan::parse("tls a\ntls a").is_err());
+    }
+}
@@ -0,0 +1,364 @@
+//! Server stack signatures
+//!
+//! A [`ServerSignature`] names a stack (web server, CDN or WAF vendor) and
+//! lists weighted rules over the responses of a probe run. A rule tests one
+//! [`Condition`] against the response of a named probe, or against any
+//! response when no probe is given. The signature matches when the weight of
+//! its satisfied rules reaches its threshold share of the total.
+//!
+//! The database is plain serde data, so site-specific signatures can be
+//! shipped as JSON and merged with [`SignatureDatabase::builtin`].
+
+use crate::active::{ProbeOutcome, ProbeResponse};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Layer of the stack a signature identifies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StackKind {
+    WebServer,
+    Cdn,
+    Waf,
+}
+
+/// Test on one probe response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    /// Response carries the header
+    HeaderPresent { name: String },
+    /// Header value contains `value` (case-insensitive)
+    HeaderContains { name: String, value: String },
+    /// HTTP status code
+    Status { code: u16 },
+    /// Body contains `text` (case-insensitive)
+    BodyContains { text: String },
+    /// JA4S of the ServerHello starts with `prefix`
+    Ja4sPrefix { prefix: String },
+    /// TLS alert with this description code
+    TlsAlert { description: u8 },
+    /// How the connection ended
+    Outcome { outcome: ProbeOutcome },
+    /// The per-run token was reflected in the response
+    Echoed,
+}
+
+impl Condition {
+    pub fn matches(&self, response: &ProbeResponse) -> bool {
+        let features = &response.features;
+        match self {
+            Condition::HeaderPresent { name } => features.header(name).is_some(),
+            Condition::HeaderContains { name, value } => features
+                .header(name)
+                .is_some_and(|v| v.to_ascii_lowercase().contains(&value.to_ascii_lowercase())),
+            Condition::Status { code } => features.status == Some(*code),
+            Condition::BodyContains { text } => features
+                .body
+                .to_ascii_lowercase()
+                .contains(&text.to_ascii_lowercase()),
+            Condition::Ja4sPrefix { prefix } => features
+                .ja4s
+                .as_deref()
+                .is_some_and(|ja4s| ja4s.starts_with(prefix.as_str())),
+            Condition::TlsAlert { description } => features.tls_alert == Some(*description),
+            Condition::Outcome { outcome } => response.outcome == *outcome,
+            Condition::Echoed => features.echoed,
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::HeaderPresent { name } => write!(f, "header {}", name),
+            Condition::HeaderContains { name, value } => write!(f, "{} ~ {}", name, value),
+            Condition::Status { code } => write!(f, "status {}", code),
+            Condition::BodyContains { text } => write!(f, "body ~ {}", text),
+            Condition::Ja4sPrefix { prefix } => write!(f, "ja4s {}*", prefix),
+            Condition::TlsAlert { description } => write!(f, "tls alert {}", description),
+            Condition::Outcome { outcome } => write!(f, "outcome {:?}", outcome),
+            Condition::Echoed => write!(f, "token echoed"),
+        }
+    }
+}
+
+/// Weighted condition, optionally bound to one probe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignatureRule {
+    /// Probe whose response is tested; any response when `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probe: Option<String>,
+    #[serde(flatten)]
+    pub condition: Condition,
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+impl SignatureRule {
+    pub fn any(condition: Condition) -> Self {
+        Self {
+            probe: None,
+            condition,
+            weight: 1.0,
+        }
+    }
+
+    pub fn on(probe: &str, condition: Condition) -> Self {
+        Self {
+            probe: Some(probe.to_string()),
+            ..Self::any(condition)
+        }
+    }
+
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Response that satisfies the rule, if any
+    fn satisfied_by<'a>(&self, responses: &'a [ProbeResponse]) -> Option<&'a ProbeResponse> {
+        responses
+            .iter()
+            .filter(|r| self.probe.as_ref().is_none_or(|p| *p == r.probe))
+            .find(|r| self.condition.matches(r))
+    }
+}
+
+/// Stack identified by a set of rules
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerSignature {
+    pub stack: String,
+    pub kind: StackKind,
+    /// Share of the rule weight that must be satisfied, in (0, 1]
+    #[serde(default = "default_threshold")]
+    pub threshold: f64,
+    pub rules: Vec<SignatureRule>,
+}
+
+fn default_threshold() -> f64 {
+    1.0
+}
+
+impl ServerSignature {
+    pub fn new(stack: &str, kind: StackKind, rules: Vec<SignatureRule>) -> Self {
+        Self {
+            stack: stack.to_string(),
+            kind,
+            threshold: 1.0,
+            rules,
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Score the signature against a probe run
+    pub fn evaluate(&self, responses: &[ProbeResponse]) -> Option<StackMatch> {
+        let total: f64 = self.rules.iter().map(|r| r.weight).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut matched = 0.0;
+        let mut evidence = Vec::new();
+        for rule in &self.rules {
+            if let Some(response) = rule.satisfied_by(responses) {
+                matched += rule.weight;
+                evidence.push(format!("{}: {}", response.probe, rule.condition));
+            }
+        }
+        let score = matched / total;
+        (score >= self.threshold && !evidence.is_empty()).then(|| StackMatch {
+            stack: self.stack.clone(),
+            kind: self.kind,
+            score,
+            evidence,
+        })
+    }
+}
+
+/// Signature that matched a probe run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackMatch {
+    pub stack: String,
+    pub kind: StackKind,
+    /// Satisfied share of the rule weight
+    pub score: f64,
+    /// `probe: condition` of every satisfied rule
+    pub evidence: Vec<String>,
+}
+
+/// Signatures mapping probe responses to server stacks
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SignatureDatabase {
+    pub signatures: Vec<ServerSignature>,
+}
+
+impl SignatureDatabase {
+    pub fn new(signatures: Vec<ServerSignature>) -> Self {
+        Self { signatures }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Add signatures, replacing built-in ones with the same stack name
+    pub fn merge(&mut self, other: SignatureDatabase) {
+        for signature in other.signatures {
+            self.signatures.retain(|s| s.stack != signature.stack);
+            self.signatures.push(signature);
+        }
+    }
+
+    /// Matching signatures, best score first
+    pub fn identify(&self, responses: &[ProbeResponse]) -> Vec<StackMatch> {
+        let mut matches: Vec<StackMatch> = self
+            .signatures
+            .iter()
+            .filter_map(|s| s.evaluate(responses))
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches
+    }
+
+    /// Common web servers, CDNs and WAFs
+    pub fn builtin() -> Self {
+        use Condition::*;
+        use StackKind::*;
+
+        let server = |value: &str| {
+            SignatureRule::any(HeaderContains {
+                name: "server".into(),
+                value: value.into(),
+            })
+        };
+        let header = |name: &str| SignatureRule::any(HeaderPresent { name: name.into() });
+        let body =
+            |probe: &str, text: &str| SignatureRule::on(probe, BodyContains { text: text.into() });
+        let blocked = |code: u16| SignatureRule::on("attack", Status { code });
+
+        Self::new(vec![
+            ServerSignature::new(
+                "nginx",
+                WebServer,
+                vec![server("nginx"), body("bad_request", "<center>nginx")],
+            )
+            .with_threshold(0.5),
+            ServerSignature::new(
+                "apache",
+                WebServer,
+                vec![server("apache"), body("bad_request", "apache")],
+            )
+            .with_threshold(0.5),
+            ServerSignature::new("iis", WebServer, vec![server("microsoft-iis")]),
+            ServerSignature::new(
+                "envoy",
+                WebServer,
+                vec![server("envoy"), header("x-envoy-upstream-service-time")],
+            )
+            .with_threshold(0.5),
+            ServerSignature::new("caddy", WebServer, vec![server("caddy")]),
+            ServerSignature::new(
+                "cloudflare",
+                Cdn,
+                vec![header("cf-ray"), server("cloudflare")],
+            )
+            .with_threshold(0.5),
+            ServerSignature::new(
+                "cloudfront",
+                Cdn,
+                vec![header("x-amz-cf-id"), server("cloudfront")],
+            )
+            .with_threshold(0.5),
+            ServerSignature::new("akamai", Cdn, vec![server("akamaighost")]),
+            ServerSignature::new(
+                "fastly",
+                Cdn,
+                vec![header("x-served-by"), header("x-fastly-request-id")],
+            )
+            .with_threshold(0.5),
+            ServerSignature::new("cloudflare_waf", Waf, vec![blocked(403), header("cf-ray")]),
+            ServerSignature::new(
+                "modsecurity",
+                Waf,
+                vec![
+                    blocked(403),
+                    body("attack", "mod_security"),
+                    server("mod_security"),
+                ],
+            )
+            .with_threshold(0.6),
+            ServerSignature::new("sucuri", Waf, vec![header("x-sucuri-id")]),
+            ServerSignature::new("imperva", Waf, vec![header("x-iinfo")]),
+            ServerSignature::new(
+                "f5_asm",
+                Waf,
+                vec![body("attack", "the requested url was rejected")],
+            ),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::active::ResponseFeatures;
+
+    fn response(probe: &str, data: &[u8]) -> ProbeResponse {
+        ProbeResponse {
+            probe: probe.to_string(),
+            outcome: ProbeOutcome::Data,
+            bytes: data.len(),
+            elapsed_ms: 1,
+            error: None,
+            features: ResponseFeatures::extract(data, "tok"),
+        }
+    }
+
+    #[test]
+    fn identifies_cdn_in_front_of_waf() {
+        let responses = vec![
+            response(
+                "get_root",
+                b"HTTP/1.1 200 OK\r\nServer: cloudflare\r\nCF-RAY: 8a1b-AMS\r\n\r\nhello",
+            ),
+            response(
+                "attack",
+                b"HTTP/1.1 403 Forbidden\r\nServer: cloudflare\r\nCF-RAY: 8a1c-AMS\r\n\r\n",
+            ),
+        ];
+        let matches = SignatureDatabase::builtin().identify(&responses);
+        let stacks: Vec<&str> = matches.iter().map(|m| m.stack.as_str()).collect();
+        assert!(stacks.contains(&"cloudflare"));
+        assert!(stacks.contains(&"cloudflare_waf"));
+        assert!(!stacks.contains(&"nginx"));
+
+        // round trip through JSON keeps the rules
+        let db = SignatureDatabase::builtin();
+        assert_eq!(
+            SignatureDatabase::from_json(&db.to_json().unwrap()).unwrap(),
+            db
+        );
+        let custom = SignatureDatabase::from_json(
+            r#"{"signatures":[{"stack":"acme","kind":"waf","rules":[{"probe":"attack","type":"status","code":403}]}]}"#,
+        )
+        .unwrap();
+        let mut merged = db;
+        merged.merge(custom);
+        assert!(merged
+            .identify(&responses)
+            .iter()
+            .any(|m| m.stack == "acme"));
+    }
+}
@@ -0,0 +1,281 @@
+//! Re-scoring of historical flows
+//!
+//! Verdicts stored with a flow reflect the models, profiles and labels of the
+//! moment it was captured. A backfill job re-runs analysis over a time window of
+//! stored flows with the current configuration and records the outcome as a new
+//! verdict version per flow (see
+//! [`FingerprintDatabase::run_backfill`](crate::database::FingerprintDatabase::run_backfill)).
+//! The original verdict in `flows` is version 0 and is never overwritten, so
+//! every re-score can be compared with what was decided at the time.
+//!
+//! Flows are rebuilt from their stored fingerprints (type, ID and metadata) and
+//! handed to a [`FlowScorer`]. [`ConsistencyAnalyzer`] scores them as of their
+//! capture time; a label threshold additionally flags flows whose fingerprints
+//! have since collected enough weak labels.
+
+use crate::passive::consistency::ConsistencyAnalyzer;
+use chrono::{DateTime, Utc};
+use fingerprint_core::fingerprint::{Fingerprint, FingerprintType};
+use fingerprint_core::ja4::ConsistencyReport;
+use fingerprint_core::metadata::FingerprintMetadata;
+use fingerprint_core::system::NetworkFlow;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Changes returned inline by a finished job; the rest are paged from the database
+pub const MAX_REPORTED_CHANGES: usize = 1000;
+
+/// Scores a flow rebuilt from the database with the current configuration
+pub trait FlowScorer {
+    fn score(&self, flow: &NetworkFlow) -> ConsistencyReport;
+}
+
+impl FlowScorer for ConsistencyAnalyzer {
+    fn score(&self, flow: &NetworkFlow) -> ConsistencyReport {
+        // judge the flow as of its capture, not as a stale replay
+        self.analyze_flow_at(flow, flow.context.timestamp)
+    }
+}
+
+/// Window and options of a backfill job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackfillRequest {
+    /// Start of the window (inclusive)
+    pub from: DateTime<Utc>,
+    /// End of the window (exclusive)
+    pub to: DateTime<Utc>,
+    /// Flag flows whose fingerprints' weak-label prior reaches this value
+    #[serde(default)]
+    pub label_threshold: Option<f64>,
+    /// Free-form reason, e.g. the model or feed update that prompted the run
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl BackfillRequest {
+    pub fn new(from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        Self {
+            from,
+            to,
+            label_threshold: None,
+            note: None,
+        }
+    }
+
+    pub fn with_label_threshold(mut self, threshold: f64) -> Self {
+        self.label_threshold = Some(threshold);
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.from >= self.to {
+            return Err(format!(
+                "backfill window is empty: {} is not before {}",
+                self.from.to_rfc3339(),
+                self.to.to_rfc3339()
+            ));
+        }
+        if let Some(threshold) = self.label_threshold {
+            if !(threshold > 0.0 && threshold <= 1.0) {
+                return Err(format!(
+                    "label_threshold must be in (0, 1], got {}",
+                    threshold
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lifecycle of a backfill job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackfillStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl BackfillStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BackfillStatus::Pending => "pending",
+            BackfillStatus::Running => "running",
+            BackfillStatus::Completed => "completed",
+            BackfillStatus::Failed => "failed",
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "pending" => Ok(BackfillStatus::Pending),
+            "running" => Ok(BackfillStatus::Running),
+            "completed" => Ok(BackfillStatus::Completed),
+            "failed" => Ok(BackfillStatus::Failed),
+            other => Err(format!("unknown backfill status {}", other)),
+        }
+    }
+}
+
+/// A recorded backfill job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackfillJob {
+    pub id: i64,
+    pub request: BackfillRequest,
+    pub status: BackfillStatus,
+    pub flows_scanned: u64,
+    /// Flows whose verdict differs from their previous version
+    pub flows_changed: u64,
+    /// Why a failed job stopped
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// One version of a flow's verdict
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowVerdict {
+    pub flow_id: String,
+    /// 0 for the verdict stored with the flow
+    pub version: i64,
+    /// Backfill job that produced this version (`None` for the original)
+    pub job_id: Option<i64>,
+    pub score: u8,
+    pub bot_detected: bool,
+    /// Highest weak-label prior among the flow's fingerprints, if labels were consulted
+    pub label_prior: Option<f64>,
+    /// Discrepancy codes behind the score (not kept for originals)
+    pub discrepancy_codes: Vec<String>,
+}
+
+/// A flow whose re-scored verdict differs from its previous version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerdictChange {
+    pub flow_id: String,
+    /// Version written by the backfill
+    pub version: i64,
+    pub previous_score: u8,
+    pub previous_bot: bool,
+    pub score: u8,
+    pub bot_detected: bool,
+    pub label_prior: Option<f64>,
+    pub discrepancy_codes: Vec<String>,
+}
+
+impl VerdictChange {
+    /// Previously allowed, now detected as a bot
+    pub fn newly_flagged(&self) -> bool {
+        !self.previous_bot && self.bot_detected
+    }
+
+    /// Previously detected as a bot, now allowed
+    pub fn cleared(&self) -> bool {
+        self.previous_bot && !self.bot_detected
+    }
+}
+
+/// Outcome of a backfill job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackfillReport {
+    pub job: BackfillJob,
+    /// Changes that now flag a bot
+    pub newly_flagged: u64,
+    /// Changes that no longer flag a bot
+    pub cleared: u64,
+    /// First [`MAX_REPORTED_CHANGES`] changes in flow order
+    pub changes: Vec<VerdictChange>,
+}
+
+/// Fingerprint rebuilt from its stored type, ID and metadata
+pub(crate) struct StoredFingerprint {
+    fingerprint_type: FingerprintType,
+    id: String,
+    metadata: FingerprintMetadata,
+}
+
+impl StoredFingerprint {
+    /// `fp_type` as written by `store_flow` (the type's `Debug` name)
+    pub(crate) fn from_row(fp_type: &str, fp_id: String, metadata_json: &str) -> Option<Self> {
+        let fingerprint_type = [
+            FingerprintType::Tls,
+            FingerprintType::Http,
+            FingerprintType::Tcp,
+            FingerprintType::Content,
+        ]
+        .into_iter()
+        .find(|t| t.as_str().eq_ignore_ascii_case(fp_type))?;
+        let metadata = serde_json::from_str(metadata_json).unwrap_or_default();
+        Some(Self {
+            fingerprint_type,
+            id: fp_id,
+            metadata,
+        })
+    }
+}
+
+impl Fingerprint for StoredFingerprint {
+    fn fingerprint_type(&self) -> FingerprintType {
+        self.fingerprint_type
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn metadata(&self) -> &FingerprintMetadata {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut FingerprintMetadata {
+        &mut self.metadata
+    }
+
+    fn hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.fingerprint_type.as_str().hash(&mut hasher);
+        self.id.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn similar_to(&self, other: &dyn Fingerprint) -> bool {
+        self.fingerprint_type == other.fingerprint_type() && self.id == other.id()
+    }
+
+    fn to_string(&self) -> String {
+        format!("{}:{}", self.fingerprint_type.as_str(), self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_request_validation() {
+        let now = Utc::now();
+        assert!(BackfillRequest::new(now - Duration::hours(1), now)
+            .validate()
+            .is_ok());
+        assert!(BackfillRequest::new(now, now).validate().is_err());
+        assert!(BackfillRequest::new(now - Duration::hours(1), now)
+            .with_label_threshold(1.5)
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_stored_fingerprint_round_trips_type() {
+        let fp = StoredFingerprint::from_row("Tcp", "64_65535".to_string(), "{}").unwrap();
+        assert_eq!(fp.fingerprint_type(), FingerprintType::Tcp);
+        assert_eq!(fp.id(), "64_65535");
+        assert!(StoredFingerprint::from_row("Dns", String::new(), "{}").is_none());
+    }
+}
@@ -2,19 +2,56 @@
 //!
 //! usepure Rust implement from networkinterface or fileactual when capturecountpacket (nonesystemdepend).
 
-use crate::passive::{PacketParser, PassiveAnalyzer};
+use crate::passive::{Packet, PacketParser, PassiveAnalysisResult, PassiveAnalyzer};
 use pnet::datalink::{self, Channel, NetworkInterface};
 use std::sync::Arc;
 
+#[cfg(all(feature = "xdp", target_os = "linux"))]
+mod xdp;
+#[cfg(all(feature = "xdp", target_os = "linux"))]
+pub use xdp::{XdpAttachMode, XdpCapture, XdpCaptureStats, XdpConfig, XdpQueueStats};
+
+/// Largest frame handed to the analyzer (maximum IP packet); larger ones are skipped
+const MAX_PACKET_SIZE: usize = 65535;
+
+/// Receives every parsed packet together with its analysis result
+pub type CaptureObserver = Arc<dyn Fn(&Packet, &PassiveAnalysisResult) + Send + Sync>;
+
 /// captureengine
 pub struct CaptureEngine {
     analyzer: Arc<PassiveAnalyzer>,
+    observer: Option<CaptureObserver>,
 }
 
 impl CaptureEngine {
     /// Create a newcaptureengine
     pub fn new(analyzer: Arc<PassiveAnalyzer>) -> Self {
-        Self { analyzer }
+        Self {
+            analyzer,
+            observer: None,
+        }
+    }
+
+    /// Hand analysis results to `observer` (learner, metrics, admission) instead of
+    /// dropping them
+    pub fn with_observer(mut self, observer: CaptureObserver) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Parse and analyze one Ethernet frame
+    fn handle_frame(analyzer: &PassiveAnalyzer, observer: Option<&CaptureObserver>, frame: &[u8]) {
+        // skipEthernetframeheader (14 bytes)
+        if frame.len() <= 14 {
+            return;
+        }
+        let parser = PacketParser::new();
+        if let Ok(packet) = parser.parse(&frame[14..]) {
+            let result = analyzer.analyze(&packet);
+            if let Some(observer) = observer {
+                observer(&packet, &result);
+            }
+        }
     }
 
     /// from specifiednetwork interfacestartactual when capture
@@ -28,9 +65,17 @@ impl CaptureEngine {
         println!("[Capture] Listening on device: {}", device_name);
 
         let analyzer = self.analyzer.clone();
-
-        // use spawn_blocking because pnet receive is blockingof
-        tokio::task::spawn_blocking(move || Self::capture_from_interface(interface, analyzer));
+        let observer = self.observer.clone();
+
+        // use spawn_blocking because pnet receive is blockingof; a configured capture
+        // runtime keeps the loop off the caller's blocking pool
+        let capture = move || Self::capture_from_interface(interface, analyzer, observer);
+        match fingerprint_core::runtime::capture_runtime()
+            .map_err(|e| format!("capture runtime unavailable: {}", e))?
+        {
+            Some(runtime) => drop(runtime.spawn_blocking(capture)),
+            None => drop(tokio::task::spawn_blocking(capture)),
+        }
 
         Ok(())
     }
@@ -39,6 +84,7 @@ impl CaptureEngine {
     fn capture_from_interface(
         interface: NetworkInterface,
         analyzer: Arc<PassiveAnalyzer>,
+        observer: Option<CaptureObserver>,
     ) -> Result<(), String> {
         // Createcountdatachainpathchannel
         let (_tx, mut rx) = match datalink::channel(&interface, Default::default()) {
@@ -51,8 +97,7 @@ impl CaptureEngine {
         loop {
             match rx.next() {
                 Ok(packet) => {
-                    // securityCheck：limitmaximumcountpacketsizeending withprevent DoS attack (65535 bytes = maximum IP package)
-                    const MAX_PACKET_SIZE: usize = 65535;
+                    // securityCheck：limitmaximumcountpacketsizeending withprevent DoS attack
                     if packet.len() > MAX_PACKET_SIZE {
                         eprintln!(
                             "[Capture] countpackettoo large，alreadyignore: {} bytes",
@@ -61,14 +106,7 @@ impl CaptureEngine {
                         continue;
                     }
 
-                    // skipEthernetframeheader (14 bytes)
-                    if packet.len() > 14 {
-                        let ip_packet = &packet[14..];
-                        let parser = PacketParser::new();
-                        if let Ok(p) = parser.parse(ip_packet) {
-                            let _ = analyzer.analyze(&p);
-                        }
-                    }
+                    Self::handle_frame(&analyzer, observer.as_ref(), packet);
                 }
                 Err(e) => {
                     eprintln!("[Capture] receivecountpacketerror: {}", e);
@@ -106,7 +144,6 @@ impl CaptureEngine {
             match packet {
                 Ok(pkt) => {
                     // securityCheck：limitsinglecountpacketsize
-                    const MAX_PACKET_SIZE: usize = 65535;
                     let data = pkt.data;
                     if data.len() > MAX_PACKET_SIZE {
                         eprintln!(
@@ -117,14 +154,7 @@ impl CaptureEngine {
                     }
 
                     // pcap fileincountdatausuallyincludingEthernetframeheader
-                    if data.len() > 14 {
-                        // skipEthernetframeheader (14 bytes)
-                        let ip_packet = &data[14..];
-                        let parser = PacketParser::new();
-                        if let Ok(p) = parser.parse(ip_packet) {
-                            let _ = self.analyzer.analyze(&p);
-                        }
-                    }
+                    Self::handle_frame(&self.analyzer, self.observer.as_ref(), &data);
                 }
                 Err(e) => {
                     eprintln!("[Capture] readcountpacketerror: {}", e);
@@ -0,0 +1,944 @@
+//! AF_XDP capture backend
+//!
+//! Kernel-bypass alternative to the pnet/libpcap path for high-throughput
+//! Linux gateways. A tiny XDP program redirects every frame on the interface
+//! into an `XSKMAP`; each RX queue gets its own AF_XDP socket, UMEM and ring
+//! pair, drained by a dedicated thread (optionally pinned to a CPU), and every
+//! frame goes through the same [`PassiveAnalyzer`] and observer as
+//! [`CaptureEngine::start_live`].
+//!
+//! Redirected frames do not reach the kernel network stack, so attach this
+//! to a mirror/TAP interface, not to the one serving traffic. Needs
+//! `CAP_NET_ADMIN` + `CAP_BPF` (or root) and Linux 5.9+ for `bpf_link`.
+//!
+//! Drop statistics come from the kernel (`XDP_STATISTICS`) per queue and are
+//! exposed through [`XdpCapture::stats`].
+
+use super::{CaptureEngine, CaptureObserver, MAX_PACKET_SIZE};
+use crate::passive::PassiveAnalyzer;
+use serde::Serialize;
+use std::ffi::CString;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How the redirect program is attached to the interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum XdpAttachMode {
+    /// Generic (skb) mode, works on every driver
+    #[default]
+    Generic,
+    /// Native driver mode, needs driver support
+    Driver,
+}
+
+/// AF_XDP capture settings
+#[derive(Debug, Clone)]
+pub struct XdpConfig {
+    /// RX queues to capture (all queues found in sysfs when unset)
+    pub queues: Option<u32>,
+    /// UMEM frame size, power of two in 2048..=4096
+    pub frame_size: u32,
+    /// UMEM frames per queue, also the fill ring size (power of two)
+    pub frame_count: u32,
+    /// RX ring size (power of two)
+    pub ring_size: u32,
+    /// Program attach mode
+    pub mode: XdpAttachMode,
+    /// Request zero-copy binding (driver mode only)
+    pub zero_copy: bool,
+    /// Pin each queue's thread to CPU `queue % cpus`
+    pub pin_cpus: bool,
+    /// Poll timeout; bounds how long [`XdpCapture::stop`] waits
+    pub poll_timeout: Duration,
+}
+
+impl Default for XdpConfig {
+    fn default() -> Self {
+        Self {
+            queues: None,
+            frame_size: 2048,
+            frame_count: 4096,
+            ring_size: 2048,
+            mode: XdpAttachMode::Generic,
+            zero_copy: false,
+            pin_cpus: true,
+            poll_timeout: Duration::from_millis(100),
+        }
+    }
+}
+
+impl XdpConfig {
+    fn validate(&self) -> Result<(), String> {
+        if !self.frame_size.is_power_of_two() || !(2048..=4096).contains(&self.frame_size) {
+            return Err(format!(
+                "frame_size must be 2048 or 4096, got {}",
+                self.frame_size
+            ));
+        }
+        if !self.frame_count.is_power_of_two() || !self.ring_size.is_power_of_two() {
+            return Err("frame_count and ring_size must be powers of two".to_string());
+        }
+        if self.zero_copy && self.mode != XdpAttachMode::Driver {
+            return Err("zero_copy needs driver attach mode".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Counters of one RX queue
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct XdpQueueStats {
+    /// RX queue index
+    pub queue: u32,
+    /// CPU the queue's thread is pinned to
+    pub cpu: Option<usize>,
+    /// Frames handed to the analyzer
+    pub packets: u64,
+    /// Bytes handed to the analyzer
+    pub bytes: u64,
+    /// Frames skipped for exceeding the maximum packet size
+    pub oversized: u64,
+    /// Kernel: frames dropped for other reasons (e.g. larger than a UMEM frame)
+    pub rx_dropped: u64,
+    /// Kernel: frames dropped because the RX ring was full
+    pub rx_ring_full: u64,
+    /// Kernel: times the fill ring was empty when a frame arrived
+    pub rx_fill_ring_empty: u64,
+    /// Kernel: invalid descriptors
+    pub rx_invalid_descs: u64,
+}
+
+impl XdpQueueStats {
+    /// Frames the kernel could not deliver to this queue
+    pub fn dropped(&self) -> u64 {
+        self.rx_dropped + self.rx_ring_full
+    }
+}
+
+/// Counters of a running capture
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct XdpCaptureStats {
+    /// Per-queue counters
+    pub queues: Vec<XdpQueueStats>,
+}
+
+impl XdpCaptureStats {
+    /// Frames analyzed across queues
+    pub fn packets(&self) -> u64 {
+        self.queues.iter().map(|q| q.packets).sum()
+    }
+
+    /// Frames dropped by the kernel across queues
+    pub fn dropped(&self) -> u64 {
+        self.queues.iter().map(XdpQueueStats::dropped).sum()
+    }
+}
+
+#[derive(Default)]
+struct QueueCounters {
+    packets: AtomicU64,
+    bytes: AtomicU64,
+    oversized: AtomicU64,
+    rx_dropped: AtomicU64,
+    rx_ring_full: AtomicU64,
+    rx_fill_ring_empty: AtomicU64,
+    rx_invalid_descs: AtomicU64,
+}
+
+impl QueueCounters {
+    fn store_kernel(&self, stats: &libc::xdp_statistics) {
+        self.rx_dropped.store(stats.rx_dropped, Ordering::Relaxed);
+        self.rx_ring_full
+            .store(stats.rx_ring_full, Ordering::Relaxed);
+        self.rx_fill_ring_empty
+            .store(stats.rx_fill_ring_empty_descs, Ordering::Relaxed);
+        self.rx_invalid_descs
+            .store(stats.rx_invalid_descs, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self, queue: u32, cpu: Option<usize>) -> XdpQueueStats {
+        XdpQueueStats {
+            queue,
+            cpu,
+            packets: self.packets.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            oversized: self.oversized.load(Ordering::Relaxed),
+            rx_dropped: self.rx_dropped.load(Ordering::Relaxed),
+            rx_ring_full: self.rx_ring_full.load(Ordering::Relaxed),
+            rx_fill_ring_empty: self.rx_fill_ring_empty.load(Ordering::Relaxed),
+            rx_invalid_descs: self.rx_invalid_descs.load(Ordering::Relaxed),
+        }
+    }
+}
+
+struct QueueWorker {
+    queue: u32,
+    cpu: Option<usize>,
+    counters: Arc<QueueCounters>,
+    handle: Option<JoinHandle<Result<(), String>>>,
+}
+
+/// Running AF_XDP capture; stops and detaches the program when dropped
+pub struct XdpCapture {
+    device: String,
+    stop: Arc<AtomicBool>,
+    workers: Vec<QueueWorker>,
+    _program: XdpProgram,
+}
+
+impl XdpCapture {
+    /// Interface being captured
+    pub fn device(&self) -> &str {
+        &self.device
+    }
+
+    /// Per-queue packet and drop counters
+    pub fn stats(&self) -> XdpCaptureStats {
+        XdpCaptureStats {
+            queues: self
+                .workers
+                .iter()
+                .map(|w| w.counters.snapshot(w.queue, w.cpu))
+                .collect(),
+        }
+    }
+
+    /// Stop all queues, returning the first worker error
+    pub fn stop(mut self) -> Result<(), String> {
+        self.shutdown()
+    }
+
+    fn shutdown(&mut self) -> Result<(), String> {
+        self.stop.store(true, Ordering::Relaxed);
+        let mut first_error = None;
+        for worker in &mut self.workers {
+            let Some(handle) = worker.handle.take() else {
+                continue;
+            };
+            let outcome = handle
+                .join()
+                .unwrap_or_else(|_| Err(format!("queue {} worker panicked", worker.queue)));
+            if let Err(e) = outcome {
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+impl Drop for XdpCapture {
+    fn drop(&mut self) {
+        if let Err(e) = self.shutdown() {
+            eprintln!("[Capture] XDP worker error: {}", e);
+        }
+    }
+}
+
+impl CaptureEngine {
+    /// Capture from `device_name` through AF_XDP, one socket and thread per RX queue
+    pub fn start_xdp(&self, device_name: &str, config: XdpConfig) -> Result<XdpCapture, String> {
+        config.validate()?;
+        let name = CString::new(device_name).map_err(|_| "invalid interface name".to_string())?;
+        // SAFETY: `name` is a valid NUL-terminated string
+        let ifindex = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if ifindex == 0 {
+            return Err(format!("network interface not found: {}", device_name));
+        }
+        let queues = match config.queues {
+            Some(queues) => queues,
+            None => rx_queue_count(&Path::new("/sys/class/net").join(device_name).join("queues"))
+                .unwrap_or(1),
+        };
+
+        let mut program = XdpProgram::load(queues).map_err(|e| format!("XDP program: {}", e))?;
+        let mut sockets = Vec::with_capacity(queues as usize);
+        for queue in 0..queues {
+            let socket = XskSocket::bind(ifindex, queue, &config)
+                .map_err(|e| format!("AF_XDP socket for queue {}: {}", queue, e))?;
+            program
+                .register(queue, socket.fd.as_raw_fd())
+                .map_err(|e| format!("XSKMAP update for queue {}: {}", queue, e))?;
+            sockets.push(socket);
+        }
+        program
+            .attach(ifindex, config.mode)
+            .map_err(|e| format!("XDP attach to {}: {}", device_name, e))?;
+
+        println!(
+            "[Capture] AF_XDP listening on device: {} ({} queues)",
+            device_name, queues
+        );
+
+        let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut workers = Vec::with_capacity(sockets.len());
+        for (queue, socket) in (0..queues).zip(sockets) {
+            let cpu = config.pin_cpus.then_some(queue as usize % cpus);
+            let counters = Arc::new(QueueCounters::default());
+            let rx = RxLoop {
+                socket,
+                analyzer: self.analyzer.clone(),
+                observer: self.observer.clone(),
+                counters: counters.clone(),
+                stop: stop.clone(),
+                poll_timeout: config.poll_timeout,
+            };
+            let handle = std::thread::Builder::new()
+                .name(format!("xdp-rx-{}", queue))
+                .spawn(move || {
+                    if let Some(cpu) = cpu {
+                        pin_to_cpu(cpu);
+                    }
+                    rx.run()
+                })
+                .map_err(|e| format!("spawn queue {} worker: {}", queue, e))?;
+            workers.push(QueueWorker {
+                queue,
+                cpu,
+                counters,
+                handle: Some(handle),
+            });
+        }
+
+        Ok(XdpCapture {
+            device: device_name.to_string(),
+            stop,
+            workers,
+            _program: program,
+        })
+    }
+}
+
+/// Number of `rx-*` entries in a `/sys/class/net/<dev>/queues` directory
+fn rx_queue_count(queues_dir: &Path) -> Option<u32> {
+    let count = std::fs::read_dir(queues_dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("rx-"))
+        .count();
+    (count > 0).then_some(count as u32)
+}
+
+fn pin_to_cpu(cpu: usize) {
+    // SAFETY: cpu_set_t is plain data; sched_setaffinity only reads it
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            eprintln!(
+                "[Capture] could not pin XDP worker to CPU {}: {}",
+                cpu,
+                io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+struct RxLoop {
+    socket: XskSocket,
+    analyzer: Arc<PassiveAnalyzer>,
+    observer: Option<CaptureObserver>,
+    counters: Arc<QueueCounters>,
+    stop: Arc<AtomicBool>,
+    poll_timeout: Duration,
+}
+
+impl RxLoop {
+    const BATCH: u32 = 64;
+    const STATS_INTERVAL: Duration = Duration::from_millis(500);
+
+    fn run(mut self) -> Result<(), String> {
+        let timeout = self.poll_timeout.as_millis().min(i32::MAX as u128) as i32;
+        let mut last_stats = Instant::now();
+        while !self.stop.load(Ordering::Relaxed) {
+            let mut pfd = libc::pollfd {
+                fd: self.socket.fd.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: one valid pollfd
+            if unsafe { libc::poll(&mut pfd, 1, timeout) } < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(format!("poll: {}", err));
+                }
+            }
+            self.drain();
+            if last_stats.elapsed() >= Self::STATS_INTERVAL {
+                if let Ok(stats) = self.socket.statistics() {
+                    self.counters.store_kernel(&stats);
+                }
+                last_stats = Instant::now();
+            }
+        }
+        if let Ok(stats) = self.socket.statistics() {
+            self.counters.store_kernel(&stats);
+        }
+        Ok(())
+    }
+
+    fn drain(&mut self) {
+        let socket = &mut self.socket;
+        let received = socket.rx.peek(Self::BATCH);
+        if received == 0 {
+            return;
+        }
+        let frame_mask = !(u64::from(socket.frame_size) - 1);
+        for i in 0..received {
+            let desc = socket.rx.get(i);
+            let len = desc.len as usize;
+            self.counters.packets.fetch_add(1, Ordering::Relaxed);
+            self.counters.bytes.fetch_add(len as u64, Ordering::Relaxed);
+            if len > MAX_PACKET_SIZE {
+                self.counters.oversized.fetch_add(1, Ordering::Relaxed);
+            } else if let Some(frame) = socket.umem.slice(desc.addr as usize, len) {
+                CaptureEngine::handle_frame(&self.analyzer, self.observer.as_ref(), frame);
+            }
+            // every frame taken from the RX ring goes straight back to the fill ring
+            socket.fill.push(desc.addr & frame_mask);
+        }
+        socket.rx.release(received);
+        socket.fill.submit();
+    }
+}
+
+/// Anonymous or socket-backed memory mapping
+struct Mmap {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Mmap {
+    fn new(len: usize, fd: Option<(RawFd, libc::off_t)>) -> io::Result<Self> {
+        let (flags, fd, offset) = match fd {
+            Some((fd, offset)) => (libc::MAP_SHARED | libc::MAP_POPULATE, fd, offset),
+            None => (libc::MAP_PRIVATE | libc::MAP_ANONYMOUS, -1, 0),
+        };
+        // SAFETY: fresh mapping, checked for MAP_FAILED
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                flags,
+                fd,
+                offset,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            ptr: ptr.cast(),
+            len,
+        })
+    }
+
+    fn slice(&self, offset: usize, len: usize) -> Option<&[u8]> {
+        let end = offset.checked_add(len)?;
+        // SAFETY: bounds checked against the mapping length
+        (end <= self.len).then(|| unsafe { std::slice::from_raw_parts(self.ptr.add(offset), len) })
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        // SAFETY: ptr/len come from a successful mmap
+        unsafe {
+            libc::munmap(self.ptr.cast(), self.len);
+        }
+    }
+}
+
+/// Single-producer/single-consumer ring shared with the kernel
+///
+/// Used as a consumer for the RX ring and as a producer for the fill ring.
+struct Ring<T: Copy> {
+    producer: *const AtomicU32,
+    consumer: *const AtomicU32,
+    descs: *mut T,
+    mask: u32,
+    cached_prod: u32,
+    cached_cons: u32,
+    _map: Option<Mmap>,
+}
+
+impl<T: Copy> Ring<T> {
+    /// # Safety
+    ///
+    /// `base` must point to a live ring laid out as described by `offsets`
+    /// with `size` (a power of two) descriptors, for as long as the ring lives.
+    unsafe fn from_raw(
+        base: *mut u8,
+        offsets: &libc::xdp_ring_offset,
+        size: u32,
+        map: Option<Mmap>,
+    ) -> Self {
+        let producer = base.add(offsets.producer as usize) as *const AtomicU32;
+        let consumer = base.add(offsets.consumer as usize) as *const AtomicU32;
+        Self {
+            producer,
+            consumer,
+            descs: base.add(offsets.desc as usize).cast(),
+            mask: size - 1,
+            cached_prod: (*producer).load(Ordering::Relaxed),
+            cached_cons: (*consumer).load(Ordering::Relaxed),
+            _map: map,
+        }
+    }
+
+    fn map(fd: RawFd, offsets: &libc::xdp_ring_offset, size: u32, pgoff: u64) -> io::Result<Self> {
+        let len = offsets.desc as usize + size as usize * std::mem::size_of::<T>();
+        let map = Mmap::new(len, Some((fd, pgoff as libc::off_t)))?;
+        // SAFETY: the kernel laid the ring out as `offsets` describes
+        Ok(unsafe { Self::from_raw(map.ptr, offsets, size, Some(map)) })
+    }
+
+    /// Consumer side: entries ready, up to `max`
+    fn peek(&mut self, max: u32) -> u32 {
+        // SAFETY: producer points into the live ring
+        let produced = unsafe { (*self.producer).load(Ordering::Acquire) };
+        produced.wrapping_sub(self.cached_cons).min(max)
+    }
+
+    /// Consumer side: the `i`-th entry after the last release
+    fn get(&self, i: u32) -> T {
+        let idx = self.cached_cons.wrapping_add(i) & self.mask;
+        // SAFETY: idx is masked to the ring size
+        unsafe { *self.descs.add(idx as usize) }
+    }
+
+    /// Consumer side: hand `n` entries back
+    fn release(&mut self, n: u32) {
+        self.cached_cons = self.cached_cons.wrapping_add(n);
+        // SAFETY: consumer points into the live ring
+        unsafe { (*self.consumer).store(self.cached_cons, Ordering::Release) };
+    }
+
+    /// Producer side: free slots
+    fn free(&self) -> u32 {
+        // SAFETY: consumer points into the live ring
+        let consumed = unsafe { (*self.consumer).load(Ordering::Acquire) };
+        (self.mask + 1) - self.cached_prod.wrapping_sub(consumed)
+    }
+
+    /// Producer side: stage one entry; callers keep within [`Ring::free`]
+    fn push(&mut self, value: T) {
+        let idx = self.cached_prod & self.mask;
+        // SAFETY: idx is masked to the ring size
+        unsafe { *self.descs.add(idx as usize) = value };
+        self.cached_prod = self.cached_prod.wrapping_add(1);
+    }
+
+    /// Producer side: publish staged entries
+    fn submit(&mut self) {
+        // SAFETY: producer points into the live ring
+        unsafe { (*self.producer).store(self.cached_prod, Ordering::Release) };
+    }
+}
+
+/// AF_XDP socket bound to one RX queue, with its own UMEM
+struct XskSocket {
+    // field order: rings and UMEM unmap before the socket closes
+    rx: Ring<libc::xdp_desc>,
+    fill: Ring<u64>,
+    umem: Mmap,
+    frame_size: u32,
+    fd: OwnedFd,
+}
+
+// SAFETY: the rings and UMEM are owned exclusively by the socket, which is
+// moved to and used from a single worker thread
+unsafe impl Send for XskSocket {}
+
+impl XskSocket {
+    fn bind(ifindex: u32, queue: u32, config: &XdpConfig) -> io::Result<Self> {
+        // SAFETY: plain socket(2)
+        let raw = unsafe { libc::socket(libc::AF_XDP, libc::SOCK_RAW | libc::SOCK_CLOEXEC, 0) };
+        if raw < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: raw is a fresh descriptor we own
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        let umem = Mmap::new(
+            config.frame_size as usize * config.frame_count as usize,
+            None,
+        )?;
+        // SAFETY: all-zero is a valid xdp_umem_reg
+        let mut reg: libc::xdp_umem_reg = unsafe { std::mem::zeroed() };
+        reg.addr = umem.ptr as u64;
+        reg.len = umem.len as u64;
+        reg.chunk_size = config.frame_size;
+        setsockopt(&fd, libc::XDP_UMEM_REG, &reg)?;
+        setsockopt(&fd, libc::XDP_UMEM_FILL_RING, &config.frame_count)?;
+        // the kernel refuses to bind without a completion ring, even for RX only
+        setsockopt(&fd, libc::XDP_UMEM_COMPLETION_RING, &config.ring_size)?;
+        setsockopt(&fd, libc::XDP_RX_RING, &config.ring_size)?;
+
+        let offsets: libc::xdp_mmap_offsets = getsockopt(&fd, libc::XDP_MMAP_OFFSETS)?;
+        let rx = Ring::map(
+            fd.as_raw_fd(),
+            &offsets.rx,
+            config.ring_size,
+            libc::XDP_PGOFF_RX_RING as u64,
+        )?;
+        let mut fill = Ring::map(
+            fd.as_raw_fd(),
+            &offsets.fr,
+            config.frame_count,
+            libc::XDP_UMEM_PGOFF_FILL_RING,
+        )?;
+        for frame in 0..config.frame_count.min(fill.free()) {
+            fill.push(u64::from(frame) * u64::from(config.frame_size));
+        }
+        fill.submit();
+
+        // SAFETY: all-zero is a valid sockaddr_xdp
+        let mut addr: libc::sockaddr_xdp = unsafe { std::mem::zeroed() };
+        addr.sxdp_family = libc::AF_XDP as u16;
+        addr.sxdp_ifindex = ifindex;
+        addr.sxdp_queue_id = queue;
+        addr.sxdp_flags = libc::XDP_USE_NEED_WAKEUP
+            | if config.zero_copy {
+                libc::XDP_ZEROCOPY
+            } else {
+                libc::XDP_COPY
+            };
+        // SAFETY: addr is a fully initialized sockaddr_xdp
+        let rc = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                (&addr as *const libc::sockaddr_xdp).cast(),
+                std::mem::size_of::<libc::sockaddr_xdp>() as libc::socklen_t,
+            )
+        };
+        if rc != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            rx,
+            fill,
+            umem,
+            frame_size: config.frame_size,
+            fd,
+        })
+    }
+
+    fn statistics(&self) -> io::Result<libc::xdp_statistics> {
+        getsockopt(&self.fd, libc::XDP_STATISTICS)
+    }
+}
+
+fn setsockopt<T>(fd: &OwnedFd, name: libc::c_int, value: &T) -> io::Result<()> {
+    // SAFETY: value is a live T of the size passed
+    let rc = unsafe {
+        libc::setsockopt(
+            fd.as_raw_fd(),
+            libc::SOL_XDP,
+            name,
+            (value as *const T).cast(),
+            std::mem::size_of::<T>() as libc::socklen_t,
+        )
+    };
+    if rc != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn getsockopt<T>(fd: &OwnedFd, name: libc::c_int) -> io::Result<T> {
+    let mut value = std::mem::MaybeUninit::<T>::zeroed();
+    let mut len = std::mem::size_of::<T>() as libc::socklen_t;
+    // SAFETY: value has room for `len` bytes; only used for plain C structs
+    let rc = unsafe {
+        libc::getsockopt(
+            fd.as_raw_fd(),
+            libc::SOL_XDP,
+            name,
+            value.as_mut_ptr().cast(),
+            &mut len,
+        )
+    };
+    if rc != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: zero-initialized and (partially) filled in by the kernel
+    Ok(unsafe { value.assume_init() })
+}
+
+const BPF_MAP_CREATE: libc::c_int = 0;
+const BPF_MAP_UPDATE_ELEM: libc::c_int = 2;
+const BPF_PROG_LOAD: libc::c_int = 5;
+const BPF_LINK_CREATE: libc::c_int = 28;
+const BPF_MAP_TYPE_XSKMAP: u32 = 17;
+const BPF_PROG_TYPE_XDP: u32 = 6;
+const BPF_XDP: u32 = 37;
+const XDP_FLAGS_SKB_MODE: u32 = 1 << 1;
+const XDP_FLAGS_DRV_MODE: u32 = 1 << 2;
+const XDP_PASS: i32 = 2;
+const BPF_FUNC_REDIRECT_MAP: i32 = 51;
+const BPF_PSEUDO_MAP_FD: u8 = 1;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BpfInsn {
+    code: u8,
+    /// dst register in the low nibble, src in the high nibble
+    regs: u8,
+    off: i16,
+    imm: i32,
+}
+
+impl BpfInsn {
+    const fn new(code: u8, dst: u8, src: u8, off: i16, imm: i32) -> Self {
+        Self {
+            code,
+            regs: (src << 4) | dst,
+            off,
+            imm,
+        }
+    }
+}
+
+/// `bpf_redirect_map(&xsks, ctx->rx_queue_index, XDP_PASS)`
+///
+/// Queues without a bound socket fall back to `XDP_PASS`.
+fn redirect_program(map_fd: RawFd) -> [BpfInsn; 6] {
+    [
+        // r2 = *(u32 *)(r1 + offsetof(struct xdp_md, rx_queue_index))
+        BpfInsn::new(0x61, 2, 1, 16, 0),
+        // r1 = map (ld_imm64, two slots)
+        BpfInsn::new(0x18, 1, BPF_PSEUDO_MAP_FD, 0, map_fd),
+        BpfInsn::new(0, 0, 0, 0, 0),
+        // r3 = XDP_PASS
+        BpfInsn::new(0xb7, 3, 0, 0, XDP_PASS),
+        BpfInsn::new(0x85, 0, 0, 0, BPF_FUNC_REDIRECT_MAP),
+        BpfInsn::new(0x95, 0, 0, 0, 0),
+    ]
+}
+
+#[repr(C)]
+struct MapCreateAttr {
+    map_type: u32,
+    key_size: u32,
+    value_size: u32,
+    max_entries: u32,
+    map_flags: u32,
+}
+
+#[repr(C)]
+struct MapUpdateAttr {
+    map_fd: u32,
+    _pad: u32,
+    key: u64,
+    value: u64,
+    flags: u64,
+}
+
+#[repr(C)]
+struct ProgLoadAttr {
+    prog_type: u32,
+    insn_cnt: u32,
+    insns: u64,
+    license: u64,
+    log_level: u32,
+    log_size: u32,
+    log_buf: u64,
+    kern_version: u32,
+    prog_flags: u32,
+    prog_name: [u8; 16],
+    prog_ifindex: u32,
+    expected_attach_type: u32,
+}
+
+#[repr(C)]
+struct LinkCreateAttr {
+    prog_fd: u32,
+    target_ifindex: u32,
+    attach_type: u32,
+    flags: u32,
+}
+
+fn bpf<T>(cmd: libc::c_int, attr: &T) -> io::Result<RawFd> {
+    // SAFETY: attr is a live bpf_attr variant of the size passed
+    let rc = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            cmd,
+            attr as *const T,
+            std::mem::size_of::<T>(),
+        )
+    };
+    if rc < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(rc as RawFd)
+}
+
+fn owned(fd: RawFd) -> OwnedFd {
+    // SAFETY: fd was just returned by bpf(2) and is owned by nobody else
+    unsafe { OwnedFd::from_raw_fd(fd) }
+}
+
+/// Loaded redirect program and its XSKMAP; detached when the link closes
+struct XdpProgram {
+    // field order: detach before the program and map close
+    link: Option<OwnedFd>,
+    prog: OwnedFd,
+    map: OwnedFd,
+}
+
+impl XdpProgram {
+    fn load(queues: u32) -> io::Result<Self> {
+        let map = owned(bpf(
+            BPF_MAP_CREATE,
+            &MapCreateAttr {
+                map_type: BPF_MAP_TYPE_XSKMAP,
+                key_size: 4,
+                value_size: 4,
+                max_entries: queues.max(1),
+                map_flags: 0,
+            },
+        )?);
+
+        let insns = redirect_program(map.as_raw_fd());
+        let license = b"GPL\0";
+        let name = b"fp_xsk_redirect";
+        let mut prog_name = [0u8; 16];
+        prog_name[..name.len()].copy_from_slice(name);
+        let prog = owned(bpf(
+            BPF_PROG_LOAD,
+            &ProgLoadAttr {
+                prog_type: BPF_PROG_TYPE_XDP,
+                insn_cnt: insns.len() as u32,
+                insns: insns.as_ptr() as u64,
+                license: license.as_ptr() as u64,
+                log_level: 0,
+                log_size: 0,
+                log_buf: 0,
+                kern_version: 0,
+                prog_flags: 0,
+                prog_name,
+                prog_ifindex: 0,
+                expected_attach_type: BPF_XDP,
+            },
+        )?);
+
+        Ok(Self {
+            link: None,
+            prog,
+            map,
+        })
+    }
+
+    fn register(&mut self, queue: u32, socket: RawFd) -> io::Result<()> {
+        let value = socket as u32;
+        bpf(
+            BPF_MAP_UPDATE_ELEM,
+            &MapUpdateAttr {
+                map_fd: self.map.as_raw_fd() as u32,
+                _pad: 0,
+                key: &queue as *const u32 as u64,
+                value: &value as *const u32 as u64,
+                flags: 0,
+            },
+        )
+        .map(drop)
+    }
+
+    fn attach(&mut self, ifindex: u32, mode: XdpAttachMode) -> io::Result<()> {
+        let flags = match mode {
+            XdpAttachMode::Generic => XDP_FLAGS_SKB_MODE,
+            XdpAttachMode::Driver => XDP_FLAGS_DRV_MODE,
+        };
+        let link = bpf(
+            BPF_LINK_CREATE,
+            &LinkCreateAttr {
+                prog_fd: self.prog.as_raw_fd() as u32,
+                target_ifindex: ifindex,
+                attach_type: BPF_XDP,
+                flags,
+            },
+        )?;
+        self.link = Some(owned(link));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_wraps_between_producer_and_consumer() {
+        // producer at 0, consumer at 8, descriptors at 64
+        let mut backing = vec![0u64; 8 + 4];
+        let offsets = libc::xdp_ring_offset {
+            producer: 0,
+            consumer: 8,
+            desc: 64,
+            flags: 16,
+        };
+        let base = backing.as_mut_ptr().cast::<u8>();
+        // SAFETY: backing outlives both views and matches the offsets
+        let (mut producer, mut consumer) = unsafe {
+            (
+                Ring::<u64>::from_raw(base, &offsets, 4, None),
+                Ring::<u64>::from_raw(base, &offsets, 4, None),
+            )
+        };
+
+        for round in 0..3u64 {
+            assert_eq!(producer.free(), 4);
+            for i in 0..3 {
+                producer.push(round * 10 + i);
+            }
+            assert_eq!(consumer.peek(8), 0, "nothing visible before submit");
+            producer.submit();
+            assert_eq!(producer.free(), 1);
+            assert_eq!(consumer.peek(2), 2);
+            let got: Vec<u64> = (0..consumer.peek(8)).map(|i| consumer.get(i)).collect();
+            assert_eq!(got, vec![round * 10, round * 10 + 1, round * 10 + 2]);
+            consumer.release(3);
+        }
+    }
+
+    #[test]
+    fn counts_rx_queues_and_validates_config() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(rx_queue_count(dir.path()), None);
+        for name in ["rx-0", "rx-1", "tx-0", "rx-2"] {
+            std::fs::create_dir(dir.path().join(name)).unwrap();
+        }
+        assert_eq!(rx_queue_count(dir.path()), Some(3));
+
+        assert!(XdpConfig::default().validate().is_ok());
+        let bad_frame = XdpConfig {
+            frame_size: 3000,
+            ..Default::default()
+        };
+        assert!(bad_frame.validate().is_err());
+        let generic_zero_copy = XdpConfig {
+            zero_copy: true,
+            ..Default::default()
+        };
+        assert!(generic_zero_copy.validate().is_err());
+    }
+
+    #[test]
+    fn redirect_program_encoding() {
+        let insns = redirect_program(7);
+        assert_eq!(insns[0].regs, 0x12, "r2 <- [r1 + 16]");
+        assert_eq!(insns[1].regs, 0x11, "pseudo map fd into r1");
+        assert_eq!(insns[1].imm, 7);
+        assert_eq!(insns[4].imm, BPF_FUNC_REDIRECT_MAP);
+        assert_eq!(std::mem::size_of::<BpfInsn>(), 8);
+    }
+}
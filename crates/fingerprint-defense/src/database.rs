@@ -2,9 +2,30 @@
 //!
 //! Provides persistent storage and querying capabilities for network flow fingerprints.
 
-use fingerprint_core::system::NetworkFlow;
-use rusqlite::{params, Connection, Result as SqliteResult};
+use crate::backfill::{
+    BackfillJob, BackfillReport, BackfillRequest, BackfillStatus, FlowScorer, FlowVerdict,
+    StoredFingerprint, VerdictChange, MAX_REPORTED_CHANGES,
+};
+use crate::fingerprint_index::{
+    decode_cursor, prefix_upper_bound, IndexEntry, IndexMatch, IndexPage, IndexQuery,
+    Ja4Components, INDEXED_KINDS,
+};
+use crate::labels::{FingerprintRef, DEFAULT_LABEL_HALF_LIFE};
+use crate::replication::{
+    ChangeBatch, ChangeOp, ChangeRecord, ReplicationRole, ReplicationStatus, REPLICATED_TABLES,
+};
+use crate::threat_intel::ThreatIntelIndicator;
+use crate::timeline::{ResolvedSubject, TimelineEvent, TimelineEventKind, TimelineSource};
+use crate::tombstone::{PurgeReport, Tombstone, TombstoneReason, TombstoneRetention};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use fingerprint_core::data_dirs::DataDirs;
+use fingerprint_core::metadata::FingerprintMetadata;
+use fingerprint_core::stix::FingerprintKind;
+use fingerprint_core::system::{NetworkFlow, ProtocolType, SystemContext};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Result as SqliteResult};
 use serde_json;
+use std::net::{IpAddr, Ipv4Addr};
 use std::path::Path;
 
 struct Migration {
@@ -29,8 +50,106 @@ const MIGRATIONS: &[Migration] = &[
         name: "create_candidate_fingerprints",
         sql: include_str!("../migrations/003_create_candidate_fingerprints.sql"),
     },
+    Migration {
+        version: 4,
+        name: "create_fingerprint_labels",
+        sql: include_str!("../migrations/004_create_fingerprint_labels.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "create_fingerprint_index",
+        sql: include_str!("../migrations/005_create_fingerprint_index.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "create_fingerprint_alerts",
+        sql: include_str!("../migrations/006_create_fingerprint_alerts.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "create_replication_log",
+        sql: include_str!("../migrations/007_create_replication_log.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "create_backfill",
+        sql: include_str!("../migrations/008_create_backfill.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "create_fingerprint_tombstones",
+        sql: include_str!("../migrations/009_create_fingerprint_tombstones.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "create_threat_intel",
+        sql: include_str!("../migrations/010_create_threat_intel.sql"),
+    },
 ];
 
+/// Source name of events from this database on a timeline
+pub const TIMELINE_SOURCE: &str = "fingerprint_db";
+
+/// A table contributing timeline events: base query, timestamp and ordering columns
+struct TimelineTable {
+    select: &'static str,
+    ts: &'static str,
+    id: &'static str,
+}
+
+const OBSERVATION_TIMELINE: TimelineTable = TimelineTable {
+    select: "SELECT f.id, f.fp_type, f.fp_id, f.ja4_plus, fl.id, fl.source_ip, fl.target_ip, fl.timestamp \
+             FROM fingerprints f JOIN flows fl ON fl.id = f.flow_id",
+    ts: "fl.timestamp",
+    id: "f.id",
+};
+
+const FLOW_TIMELINE: TimelineTable = TimelineTable {
+    select: "SELECT fl.id, fl.source_ip, fl.timestamp, fl.consistency_score, fl.bot_detected \
+             FROM flows fl",
+    ts: "fl.timestamp",
+    id: "fl.id",
+};
+
+const CANDIDATE_TIMELINE: TimelineTable = TimelineTable {
+    select: "SELECT id, fingerprint_type, fingerprint_id, observation_count, stability_score, first_seen, status \
+             FROM candidate_fingerprints",
+    ts: "first_seen",
+    id: "id",
+};
+
+const ALERT_TIMELINE: TimelineTable = TimelineTable {
+    select: "SELECT id, fingerprint_type, fingerprint_id, severity, message, subject, created_at \
+             FROM fingerprint_alerts",
+    ts: "created_at",
+    id: "id",
+};
+
+const LABEL_TIMELINE: TimelineTable = TimelineTable {
+    select: "SELECT id, fingerprint_type, fingerprint_id, source, weight, subject, created_at \
+             FROM fingerprint_labels",
+    ts: "created_at",
+    id: "id",
+};
+
+/// Flows re-scored per committed backfill batch
+const BACKFILL_BATCH_SIZE: i64 = 500;
+
+/// Stored fingerprints indexed per `index_pending` batch
+const INDEX_BATCH_SIZE: i64 = 500;
+
+const TOMBSTONE_COLUMNS: &str =
+    "fingerprint_type, fingerprint_id, reason, note, evidence_multiplier,
+     previous_status, resurrections, deleted_at";
+
+const THREAT_INTEL_COLUMNS: &str =
+    "stix_id, fingerprint_type, fingerprint_id, kind, source, name, confidence,
+     valid_from, valid_until, revoked, modified_ms";
+
+const INDEX_COLUMNS: &str = "kind, value, transport, tls_version, destination, cipher_count,
+     extension_count, alpn, cipher_hash, extension_hash, signature_hash, hit_count,
+     first_seen, last_seen";
+
 /// Stores a fingerprint record
 pub struct FingerprintDatabase {
     conn: Connection,
@@ -43,6 +162,12 @@ impl FingerprintDatabase {
         Self::open_with_connection(conn)
     }
 
+    /// open or Create the database at its [`DataDirs`] location
+    pub fn open_in(dirs: &DataDirs) -> Result<Self, String> {
+        let path = DataDirs::prepare_file(dirs.defense_database()).map_err(|e| e.to_string())?;
+        Self::open(path)
+    }
+
     /// Create an in-memory database instance.
     pub fn new_in_memory() -> Result<Self, String> {
         let conn = Connection::open_in_memory().map_err(|e| e.to_string())?;
@@ -119,6 +244,13 @@ impl FingerprintDatabase {
             .map_err(|e| e.to_string())
     }
 
+    /// SQLite data version; changes whenever another connection commits to the database
+    pub fn data_version(&self) -> Result<i64, String> {
+        self.conn
+            .query_row("PRAGMA data_version", [], |row| row.get(0))
+            .map_err(|e| e.to_string())
+    }
+
     /// Store a complete traffic flow record
     pub fn store_flow(&self, flow: &NetworkFlow, score: u8, bot: bool) -> Result<(), String> {
         self.conn.execute(
@@ -157,9 +289,273 @@ impl FingerprintDatabase {
                 .map_err(|e| e.to_string())?;
         }
 
+        self.index_pending()?;
         Ok(())
     }
 
+    /// Index JA4/JA3 values of fingerprints stored since the last call
+    ///
+    /// Progress is tracked in the database, so this only scans new rows and is
+    /// safe to call repeatedly (e.g. after another process wrote flows).
+    /// Returns the number of values indexed.
+    pub fn index_pending(&self) -> Result<usize, String> {
+        let mut indexed = 0;
+        loop {
+            let tx = self
+                .conn
+                .unchecked_transaction()
+                .map_err(|e| e.to_string())?;
+            let last_id: i64 = tx
+                .query_row(
+                    "SELECT last_fingerprint_id FROM fingerprint_index_state WHERE id = 1",
+                    [],
+                    |row| row.get(0),
+                )
+                .map_err(|e| e.to_string())?;
+
+            let rows = {
+                let mut stmt = tx
+                    .prepare(
+                        "SELECT f.id, f.metadata_json, fl.timestamp
+                         FROM fingerprints f LEFT JOIN flows fl ON fl.id = f.flow_id
+                         WHERE f.id > ?1 ORDER BY f.id LIMIT ?2",
+                    )
+                    .map_err(|e| e.to_string())?;
+                let rows = stmt
+                    .query_map(params![last_id, INDEX_BATCH_SIZE], |row| {
+                        Ok((
+                            row.get::<_, i64>(0)?,
+                            row.get::<_, Option<String>>(1)?,
+                            row.get::<_, Option<String>>(2)?,
+                        ))
+                    })
+                    .map_err(|e| e.to_string())?;
+                rows.collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| e.to_string())?
+            };
+            let Some(&(max_id, _, _)) = rows.last() else {
+                return Ok(indexed);
+            };
+
+            for (_, metadata_json, timestamp) in &rows {
+                let Some(metadata) = metadata_json
+                    .as_deref()
+                    .and_then(|json| serde_json::from_str::<FingerprintMetadata>(json).ok())
+                else {
+                    continue;
+                };
+                let seen_at = timestamp
+                    .clone()
+                    .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
+                for kind in INDEXED_KINDS {
+                    if let Some(value) = metadata.get(kind).filter(|v| !v.is_empty()) {
+                        Self::upsert_index_entry(&tx, kind, &value, &seen_at)
+                            .map_err(|e| e.to_string())?;
+                        indexed += 1;
+                    }
+                }
+            }
+
+            tx.execute(
+                "UPDATE fingerprint_index_state SET last_fingerprint_id = ?1 WHERE id = 1",
+                params![max_id],
+            )
+            .map_err(|e| e.to_string())?;
+            tx.commit().map_err(|e| e.to_string())?;
+        }
+    }
+
+    /// Index a single value observed outside of `store_flow` (e.g. a JA3 from logs)
+    pub fn index_fingerprint(&self, kind: &str, value: &str, seen_at: &str) -> Result<(), String> {
+        Self::upsert_index_entry(&self.conn, kind, value, seen_at).map_err(|e| e.to_string())
+    }
+
+    fn upsert_index_entry(
+        conn: &Connection,
+        kind: &str,
+        value: &str,
+        seen_at: &str,
+    ) -> SqliteResult<()> {
+        let c = if kind == "ja4" {
+            Ja4Components::parse(value).unwrap_or_default()
+        } else {
+            Ja4Components::default()
+        };
+        conn.execute(
+            "INSERT INTO fingerprint_index
+             (kind, value, transport, tls_version, destination, cipher_count, extension_count,
+              alpn, cipher_hash, extension_hash, signature_hash, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?12)
+             ON CONFLICT(kind, value) DO UPDATE SET
+                hit_count = hit_count + 1,
+                first_seen = MIN(first_seen, excluded.first_seen),
+                last_seen = MAX(last_seen, excluded.last_seen)",
+            params![
+                kind,
+                value,
+                c.transport,
+                c.tls_version,
+                c.destination,
+                c.cipher_count,
+                c.extension_count,
+                c.alpn,
+                c.cipher_hash,
+                c.extension_hash,
+                c.signature_hash,
+                seen_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Search the JA4/JA3 index
+    ///
+    /// Entries are ordered by value; pass `next_cursor` of the returned page
+    /// back via [`IndexQuery::after`] to fetch the next one.
+    pub fn search_fingerprint_index(&self, query: &IndexQuery) -> Result<IndexPage, String> {
+        let mut clauses: Vec<&str> = Vec::new();
+        let mut args: Vec<Value> = Vec::new();
+
+        if let Some(kind) = &query.kind {
+            clauses.push("kind = ?");
+            args.push(Value::Text(kind.clone()));
+        }
+        match &query.matching {
+            IndexMatch::Any => {}
+            IndexMatch::Exact(value) => {
+                clauses.push("value = ?");
+                args.push(Value::Text(value.clone()));
+            }
+            IndexMatch::Prefix(prefix) => {
+                clauses.push("value >= ? AND value < ?");
+                args.push(Value::Text(prefix.clone()));
+                args.push(Value::Text(prefix_upper_bound(prefix)));
+            }
+        }
+
+        let c = &query.components;
+        let text_filters = [
+            ("transport = ?", &c.transport),
+            ("tls_version = ?", &c.tls_version),
+            ("destination = ?", &c.destination),
+            ("alpn = ?", &c.alpn),
+            ("cipher_hash = ?", &c.cipher_hash),
+            ("extension_hash = ?", &c.extension_hash),
+            ("signature_hash = ?", &c.signature_hash),
+        ];
+        for (clause, filter) in text_filters {
+            if let Some(v) = filter {
+                clauses.push(clause);
+                args.push(Value::Text(v.clone()));
+            }
+        }
+        for (clause, filter) in [
+            ("cipher_count = ?", c.cipher_count),
+            ("extension_count = ?", c.extension_count),
+        ] {
+            if let Some(v) = filter {
+                clauses.push(clause);
+                args.push(Value::Integer(v.into()));
+            }
+        }
+
+        let where_sql = |clauses: &[&str]| {
+            if clauses.is_empty() {
+                String::new()
+            } else {
+                format!("WHERE {}", clauses.join(" AND "))
+            }
+        };
+
+        let total: i64 = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM fingerprint_index {}",
+                    where_sql(&clauses)
+                ),
+                params_from_iter(args.iter()),
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+
+        if let Some(cursor) = &query.cursor {
+            let (kind, value) =
+                decode_cursor(cursor).ok_or_else(|| format!("Invalid index cursor: {}", cursor))?;
+            clauses.push("(value > ? OR (value = ? AND kind > ?))");
+            args.push(Value::Text(value.to_string()));
+            args.push(Value::Text(value.to_string()));
+            args.push(Value::Text(kind.to_string()));
+        }
+
+        // 多取一条用于判断是否还有下一页
+        let page_size = query.page_size();
+        args.push(Value::Integer(i64::from(page_size) + 1));
+        let sql = format!(
+            "SELECT {} FROM fingerprint_index {} ORDER BY value, kind LIMIT ?",
+            INDEX_COLUMNS,
+            where_sql(&clauses)
+        );
+
+        let mut stmt = self.conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params_from_iter(args.iter()), Self::index_entry_from_row)
+            .map_err(|e| e.to_string())?;
+        let mut entries = rows
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        let next_cursor = if entries.len() > page_size as usize {
+            entries.truncate(page_size as usize);
+            entries.last().map(IndexEntry::cursor)
+        } else {
+            None
+        };
+
+        Ok(IndexPage {
+            entries,
+            total: total as u64,
+            next_cursor,
+        })
+    }
+
+    /// Look up one indexed value
+    pub fn get_index_entry(&self, kind: &str, value: &str) -> Result<Option<IndexEntry>, String> {
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM fingerprint_index WHERE kind = ?1 AND value = ?2",
+                    INDEX_COLUMNS
+                ),
+                params![kind, value],
+                Self::index_entry_from_row,
+            )
+            .optional()
+            .map_err(|e| e.to_string())
+    }
+
+    fn index_entry_from_row(row: &rusqlite::Row<'_>) -> SqliteResult<IndexEntry> {
+        let components = Ja4Components {
+            transport: row.get(2)?,
+            tls_version: row.get(3)?,
+            destination: row.get(4)?,
+            cipher_count: row.get(5)?,
+            extension_count: row.get(6)?,
+            alpn: row.get(7)?,
+            cipher_hash: row.get(8)?,
+            extension_hash: row.get(9)?,
+            signature_hash: row.get(10)?,
+        };
+        Ok(IndexEntry {
+            kind: row.get(0)?,
+            value: row.get(1)?,
+            components: (!components.is_empty()).then_some(components),
+            hit_count: row.get::<_, i64>(11)? as u64,
+            first_seen: row.get(12)?,
+            last_seen: row.get(13)?,
+        })
+    }
+
     /// Get database statistics information
     pub fn get_stats(&self) -> Result<String, String> {
         let flow_count: i64 = self
@@ -216,20 +612,29 @@ impl FingerprintDatabase {
     pub fn get_pending_candidates(
         &self,
         limit: Option<u32>,
+    ) -> Result<Vec<CandidateFingerprint>, String> {
+        self.get_candidates_with_status("pending", limit)
+    }
+
+    /// Get candidate fingerprints with the given status (`pending`, `approved`, ...)
+    pub fn get_candidates_with_status(
+        &self,
+        status: &str,
+        limit: Option<u32>,
     ) -> Result<Vec<CandidateFingerprint>, String> {
         let limit_clause = limit.map(|l| format!("LIMIT {}", l)).unwrap_or_default();
         let sql = format!(
             "SELECT id, fingerprint_type, fingerprint_id, observation_count, 
                           stability_score, first_seen, last_seen, status, notes 
                           FROM candidate_fingerprints 
-                          WHERE status = 'pending' 
+                          WHERE status = ?1 
                           ORDER BY first_seen DESC {}",
             limit_clause
         );
 
         let mut stmt = self.conn.prepare(&sql).map_err(|e| e.to_string())?;
         let candidates = stmt
-            .query_map([], |row| {
+            .query_map([status], |row| {
                 Ok(CandidateFingerprint {
                     id: row.get(0)?,
                     fingerprint_type: row.get(1)?,
@@ -311,60 +716,1958 @@ impl FingerprintDatabase {
             rejected: rejected_count as u32,
         })
     }
-}
 
-/// Candidate fingerprint data structure
-#[derive(Debug, Clone)]
-pub struct CandidateFingerprint {
-    pub id: i64,
-    pub fingerprint_type: String,
-    pub fingerprint_id: String,
-    pub observation_count: u32,
-    pub stability_score: f64,
-    pub first_seen: String,
-    pub last_seen: String,
-    pub status: String,
-    pub notes: Option<String>,
-}
+    /// Soft-delete a learned fingerprint
+    ///
+    /// Its candidate rows are marked `deleted` and a tombstone records the
+    /// reason; tombstoning again replaces the reason and note but keeps the
+    /// status to restore.
+    pub fn tombstone_fingerprint(
+        &self,
+        fingerprint_type: &str,
+        fingerprint_id: &str,
+        reason: TombstoneReason,
+        note: Option<&str>,
+    ) -> Result<Tombstone, String> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| e.to_string())?;
+        let previous_status: Option<String> = tx
+            .query_row(
+                "SELECT status FROM candidate_fingerprints
+                 WHERE fingerprint_type = ?1 AND fingerprint_id = ?2 AND status != 'deleted'
+                 ORDER BY id DESC LIMIT 1",
+                params![fingerprint_type, fingerprint_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO fingerprint_tombstones
+             (fingerprint_type, fingerprint_id, reason, note, evidence_multiplier, previous_status, deleted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(fingerprint_type, fingerprint_id) DO UPDATE SET
+                 reason = excluded.reason,
+                 note = excluded.note,
+                 evidence_multiplier = excluded.evidence_multiplier,
+                 previous_status = COALESCE(excluded.previous_status, previous_status),
+                 deleted_at = excluded.deleted_at",
+            params![
+                fingerprint_type,
+                fingerprint_id,
+                reason.as_str(),
+                note,
+                reason.evidence_multiplier(),
+                previous_status,
+                Utc::now().timestamp()
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE candidate_fingerprints SET status = 'deleted'
+             WHERE fingerprint_type = ?1 AND fingerprint_id = ?2 AND status != 'deleted'",
+            params![fingerprint_type, fingerprint_id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
 
-/// Candidate fingerprint statistics information
-#[derive(Debug, Clone)]
-pub struct CandidateStats {
-    pub pending: u32,
-    pub approved: u32,
-    pub rejected: u32,
-}
+        log::info!(
+            "[Database] Tombstoned fingerprint {}:{} ({})",
+            fingerprint_type,
+            fingerprint_id,
+            reason
+        );
+        self.tombstone(fingerprint_type, fingerprint_id)?
+            .ok_or_else(|| "tombstone vanished after insert".to_string())
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::tempdir;
+    /// Tombstone of a fingerprint, if it was soft-deleted
+    pub fn tombstone(
+        &self,
+        fingerprint_type: &str,
+        fingerprint_id: &str,
+    ) -> Result<Option<Tombstone>, String> {
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM fingerprint_tombstones
+                     WHERE fingerprint_type = ?1 AND fingerprint_id = ?2",
+                    TOMBSTONE_COLUMNS
+                ),
+                params![fingerprint_type, fingerprint_id],
+                tombstone_from_row,
+            )
+            .optional()
+            .map_err(|e| e.to_string())
+    }
 
-    #[test]
-    fn applies_embedded_migrations_for_in_memory_database() {
-        let db = FingerprintDatabase::new_in_memory().expect("open in-memory db");
-        assert_eq!(db.current_schema_version().unwrap(), 3);
-        assert_eq!(db.applied_migrations().unwrap(), vec![1, 2, 3]);
+    /// Tombstones, most recently deleted first
+    pub fn tombstones(&self, limit: usize) -> Result<Vec<Tombstone>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM fingerprint_tombstones ORDER BY deleted_at DESC, id DESC LIMIT ?1",
+                TOMBSTONE_COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![limit as i64], tombstone_from_row)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())
     }
 
-    #[test]
-    fn reopening_database_keeps_single_migration_history() {
-        let temp_dir = tempdir().expect("create temp dir");
-        let db_path = temp_dir.path().join("fingerprints.db");
+    /// Note that a tombstoned fingerprint is being learned again with enough evidence
+    ///
+    /// Call before storing the new candidate: only the first re-learn after a
+    /// deletion counts, later candidates of the same run do not.
+    pub fn record_resurrection(
+        &self,
+        fingerprint_type: &str,
+        fingerprint_id: &str,
+    ) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE fingerprint_tombstones SET resurrections = resurrections + 1
+                 WHERE fingerprint_type = ?1 AND fingerprint_id = ?2 AND NOT EXISTS (
+                     SELECT 1 FROM candidate_fingerprints c
+                     WHERE c.fingerprint_type = ?1 AND c.fingerprint_id = ?2 AND c.status != 'deleted')",
+                params![fingerprint_type, fingerprint_id],
+            )
+            .map(drop)
+            .map_err(|e| e.to_string())
+    }
 
-        let first = FingerprintDatabase::open(&db_path).expect("open db");
-        assert_eq!(first.current_schema_version().unwrap(), 3);
-        drop(first);
+    /// Undo a soft delete: drop the tombstone and restore the candidate rows' status
+    ///
+    /// Returns false when the fingerprint had no tombstone.
+    pub fn restore_fingerprint(
+        &self,
+        fingerprint_type: &str,
+        fingerprint_id: &str,
+    ) -> Result<bool, String> {
+        let Some(tombstone) = self.tombstone(fingerprint_type, fingerprint_id)? else {
+            return Ok(false);
+        };
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE candidate_fingerprints SET status = ?3
+             WHERE fingerprint_type = ?1 AND fingerprint_id = ?2 AND status = 'deleted'",
+            params![
+                fingerprint_type,
+                fingerprint_id,
+                tombstone.previous_status.as_deref().unwrap_or("pending")
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "DELETE FROM fingerprint_tombstones WHERE fingerprint_type = ?1 AND fingerprint_id = ?2",
+            params![fingerprint_type, fingerprint_id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(true)
+    }
 
-        let reopened = FingerprintDatabase::open(&db_path).expect("reopen db");
-        assert_eq!(reopened.applied_migrations().unwrap(), vec![1, 2, 3]);
+    /// Drop tombstones past their retention, with their deleted candidate rows
+    pub fn purge_tombstones(
+        &self,
+        retention: &TombstoneRetention,
+        now: DateTime<Utc>,
+    ) -> Result<PurgeReport, String> {
+        let mut report = PurgeReport::default();
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| e.to_string())?;
+        for reason in TombstoneReason::ALL {
+            let Some(cutoff) = retention.cutoff(reason, now.timestamp()) else {
+                continue;
+            };
+            if retention.purge_candidates {
+                report.candidates += tx
+                    .execute(
+                        "DELETE FROM candidate_fingerprints WHERE status = 'deleted' AND EXISTS (
+                             SELECT 1 FROM fingerprint_tombstones t
+                             WHERE t.fingerprint_type = candidate_fingerprints.fingerprint_type
+                               AND t.fingerprint_id = candidate_fingerprints.fingerprint_id
+                               AND t.reason = ?1 AND t.deleted_at < ?2)",
+                        params![reason.as_str(), cutoff],
+                    )
+                    .map_err(|e| e.to_string())?;
+            }
+            let purged = tx
+                .execute(
+                    "DELETE FROM fingerprint_tombstones WHERE reason = ?1 AND deleted_at < ?2",
+                    params![reason.as_str(), cutoff],
+                )
+                .map_err(|e| e.to_string())?;
+            if purged > 0 {
+                report.tombstones.insert(reason, purged);
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())?;
 
-        let migration_count: i64 = reopened
+        if report.total_tombstones() > 0 {
+            log::info!(
+                "[Database] Purged {} tombstones and {} deleted candidates",
+                report.total_tombstones(),
+                report.candidates
+            );
+        }
+        Ok(report)
+    }
+
+    /// Insert or update an indicator ingested from a threat-intel feed
+    ///
+    /// Returns `false` when the same or a newer version of the STIX object is
+    /// already stored for that fingerprint.
+    pub fn upsert_threat_intel(&self, indicator: &ThreatIntelIndicator) -> Result<bool, String> {
+        let changed = self
             .conn
-            .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| {
-                row.get(0)
+            .execute(
+                "INSERT INTO threat_intel_indicators
+             (stix_id, fingerprint_type, fingerprint_id, kind, source, name, confidence,
+              valid_from, valid_until, revoked, modified_ms, imported_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+             ON CONFLICT(stix_id, fingerprint_id) DO UPDATE SET
+                 source = excluded.source,
+                 name = excluded.name,
+                 confidence = excluded.confidence,
+                 valid_from = excluded.valid_from,
+                 valid_until = excluded.valid_until,
+                 revoked = excluded.revoked,
+                 modified_ms = excluded.modified_ms,
+                 imported_at = excluded.imported_at
+             WHERE excluded.modified_ms > threat_intel_indicators.modified_ms",
+                params![
+                    indicator.stix_id,
+                    indicator.fingerprint_type,
+                    indicator.fingerprint_id,
+                    indicator.kind.as_str(),
+                    indicator.source,
+                    indicator.name,
+                    indicator.confidence,
+                    indicator.valid_from,
+                    indicator.valid_until,
+                    indicator.revoked,
+                    indicator.modified_ms,
+                    Utc::now().timestamp()
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(changed > 0)
+    }
+
+    /// Threat-intel indicators naming a fingerprint that are in force at `now` (Unix seconds)
+    pub fn threat_intel_for(
+        &self,
+        fingerprint_type: &str,
+        fingerprint_id: &str,
+        now: i64,
+    ) -> Result<Vec<ThreatIntelIndicator>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM threat_intel_indicators
+                 WHERE fingerprint_type = ?1 AND fingerprint_id = ?2 AND revoked = 0
+                   AND valid_from <= ?3 AND (valid_until IS NULL OR valid_until > ?3)
+                 ORDER BY confidence DESC, id",
+                THREAT_INTEL_COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(
+                params![fingerprint_type, fingerprint_id, now],
+                threat_intel_from_row,
+            )
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())
+    }
+
+    /// Ingested threat-intel indicators, most recently modified first
+    pub fn threat_intel_indicators(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<ThreatIntelIndicator>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM threat_intel_indicators ORDER BY modified_ms DESC, id DESC LIMIT ?1",
+                THREAT_INTEL_COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![limit as i64], threat_intel_from_row)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())
+    }
+
+    /// Record a weak label (e.g. a gateway enforcement outcome) against a fingerprint
+    pub fn store_weak_label(
+        &self,
+        fingerprint_type: &str,
+        fingerprint_id: &str,
+        source: &str,
+        weight: f64,
+        subject: Option<&str>,
+        created_at: i64,
+    ) -> Result<i64, String> {
+        self.conn
+            .query_row(
+                "INSERT INTO fingerprint_labels
+             (fingerprint_type, fingerprint_id, source, weight, subject, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             RETURNING id",
+                params![
+                    fingerprint_type,
+                    fingerprint_id,
+                    source,
+                    weight,
+                    subject,
+                    created_at
+                ],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())
+    }
+
+    /// Aggregate weak labels of a fingerprint, each weight decayed by its age
+    ///
+    /// `now` and the stored timestamps are Unix seconds; a label loses half its
+    /// weight every `half_life_secs`.
+    pub fn get_weak_label_summary(
+        &self,
+        fingerprint_type: &str,
+        fingerprint_id: &str,
+        now: i64,
+        half_life_secs: u64,
+    ) -> Result<WeakLabelSummary, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT source, weight, created_at FROM fingerprint_labels
+                 WHERE fingerprint_type = ?1 AND fingerprint_id = ?2",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![fingerprint_type, fingerprint_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, f64>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
             })
+            .map_err(|e| e.to_string())?;
+
+        let mut summary = WeakLabelSummary::default();
+        for row in rows {
+            let (source, weight, created_at) = row.map_err(|e| e.to_string())?;
+            let age = now.saturating_sub(created_at).max(0) as f64;
+            let decay = if half_life_secs == 0 {
+                1.0
+            } else {
+                0.5f64.powf(age / half_life_secs as f64)
+            };
+            summary.count += 1;
+            summary.decayed_weight += weight * decay;
+            *summary.by_source.entry(source).or_insert(0) += 1;
+            summary.last_seen = summary.last_seen.max(Some(created_at));
+        }
+        Ok(summary)
+    }
+
+    /// Record an alert raised against a fingerprint
+    pub fn store_alert(
+        &self,
+        fingerprint_type: &str,
+        fingerprint_id: &str,
+        severity: &str,
+        message: &str,
+        subject: Option<&str>,
+        created_at: i64,
+    ) -> Result<i64, String> {
+        self.conn
+            .query_row(
+                "INSERT INTO fingerprint_alerts
+             (fingerprint_type, fingerprint_id, severity, message, subject, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             RETURNING id",
+                params![
+                    fingerprint_type,
+                    fingerprint_id,
+                    severity,
+                    message,
+                    subject,
+                    created_at
+                ],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())
+    }
+
+    /// Record a backfill job over `request`'s window; run it with [`Self::run_backfill_job`]
+    pub fn create_backfill_job(&self, request: &BackfillRequest) -> Result<i64, String> {
+        request.validate()?;
+        self.conn
+            .query_row(
+                "INSERT INTO backfill_jobs
+             (window_start, window_end, label_threshold, note, status, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             RETURNING id",
+                params![
+                    request.from.to_rfc3339(),
+                    request.to.to_rfc3339(),
+                    request.label_threshold,
+                    request.note,
+                    BackfillStatus::Pending.as_str(),
+                    Utc::now().timestamp()
+                ],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())
+    }
+
+    /// Re-score the flows of a window with `scorer` in one call
+    pub fn run_backfill(
+        &self,
+        request: &BackfillRequest,
+        scorer: &dyn FlowScorer,
+    ) -> Result<BackfillReport, String> {
+        let job_id = self.create_backfill_job(request)?;
+        self.run_backfill_job(job_id, scorer)
+    }
+
+    /// Re-score every flow captured in a pending job's window
+    ///
+    /// Each flow gets a new verdict version; originals and earlier versions are
+    /// kept. Flows are processed in committed batches, so a failure leaves the
+    /// job `failed` with the versions written so far.
+    pub fn run_backfill_job(
+        &self,
+        job_id: i64,
+        scorer: &dyn FlowScorer,
+    ) -> Result<BackfillReport, String> {
+        let job = self
+            .backfill_job(job_id)?
+            .ok_or_else(|| format!("backfill job {} not found", job_id))?;
+        if job.status != BackfillStatus::Pending {
+            return Err(format!(
+                "backfill job {} is already {}",
+                job_id,
+                job.status.as_str()
+            ));
+        }
+        self.conn
+            .execute(
+                "UPDATE backfill_jobs SET status = ?1 WHERE id = ?2",
+                params![BackfillStatus::Running.as_str(), job_id],
+            )
+            .map_err(|e| e.to_string())?;
+
+        // batches keep the job's counters current, so only the outcome is left to record
+        let outcome = self.rescore_window(&job, scorer);
+        let (status, error) = match &outcome {
+            Ok(()) => (BackfillStatus::Completed, None),
+            Err(e) => (BackfillStatus::Failed, Some(e.clone())),
+        };
+        self.conn
+            .execute(
+                "UPDATE backfill_jobs SET status = ?1, error = ?2, finished_at = ?3 WHERE id = ?4",
+                params![status.as_str(), error, Utc::now().timestamp(), job_id],
+            )
+            .map_err(|e| e.to_string())?;
+        outcome?;
+
+        let job = self
+            .backfill_job(job_id)?
+            .ok_or_else(|| format!("backfill job {} not found", job_id))?;
+        let (newly_flagged, cleared): (i64, i64) = self
+            .conn
+            .query_row(
+                "SELECT
+                     COALESCE(SUM(bot_detected AND NOT previous_bot), 0),
+                     COALESCE(SUM(previous_bot AND NOT bot_detected), 0)
+                 FROM flow_verdicts WHERE job_id = ?1",
+                params![job_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(BackfillReport {
+            job,
+            newly_flagged: newly_flagged as u64,
+            cleared: cleared as u64,
+            changes: self.backfill_changes(job_id, 0, MAX_REPORTED_CHANGES)?,
+        })
+    }
+
+    /// Score every flow of the job's window in batches
+    fn rescore_window(&self, job: &BackfillJob, scorer: &dyn FlowScorer) -> Result<(), String> {
+        let now = Utc::now().timestamp();
+        let mut cursor = (String::new(), String::new());
+        let (mut scanned, mut changed) = (0u64, 0u64);
+        loop {
+            let flows: Vec<StoredFlowRow> = {
+                let mut stmt = self
+                    .conn
+                    .prepare(
+                        "SELECT id, source_ip, target_ip, protocol, timestamp,
+                                COALESCE(consistency_score, 100), COALESCE(bot_detected, 0)
+                         FROM flows
+                         WHERE timestamp >= ?1 AND timestamp < ?2 AND (timestamp, id) > (?3, ?4)
+                         ORDER BY timestamp, id LIMIT ?5",
+                    )
+                    .map_err(|e| e.to_string())?;
+                let rows = stmt
+                    .query_map(
+                        params![
+                            job.request.from.to_rfc3339(),
+                            job.request.to.to_rfc3339(),
+                            cursor.0,
+                            cursor.1,
+                            BACKFILL_BATCH_SIZE
+                        ],
+                        |row| {
+                            Ok((
+                                row.get(0)?,
+                                row.get(1)?,
+                                row.get(2)?,
+                                row.get(3)?,
+                                row.get(4)?,
+                                row.get(5)?,
+                                row.get(6)?,
+                            ))
+                        },
+                    )
+                    .map_err(|e| e.to_string())?;
+                rows.collect::<SqliteResult<_>>()
+                    .map_err(|e| e.to_string())?
+            };
+            let Some(last) = flows.last() else {
+                return Ok(());
+            };
+            cursor = (last.4.clone(), last.0.clone());
+
+            let tx = self
+                .conn
+                .unchecked_transaction()
+                .map_err(|e| e.to_string())?;
+            for (flow_id, source_ip, target_ip, protocol, timestamp, score, bot) in flows {
+                let fingerprints = self.stored_fingerprints(&flow_id)?;
+                let flow = rebuild_flow(
+                    source_ip.as_deref(),
+                    target_ip.as_deref(),
+                    protocol.as_deref(),
+                    &timestamp,
+                    fingerprints,
+                );
+                let report = scorer.score(&flow);
+
+                let label_prior = match job.request.label_threshold {
+                    Some(_) => Some(self.flow_label_prior(&flow, now)?),
+                    None => None,
+                };
+                let bot_detected = report.bot_detected
+                    || matches!(
+                        (label_prior, job.request.label_threshold),
+                        (Some(prior), Some(threshold)) if prior >= threshold
+                    );
+
+                let (previous_score, previous_bot, version) = tx
+                    .query_row(
+                        "SELECT consistency_score, bot_detected, version FROM flow_verdicts
+                         WHERE flow_id = ?1 ORDER BY version DESC LIMIT 1",
+                        params![flow_id],
+                        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?, row.get(2)?)),
+                    )
+                    .optional()
+                    .map_err(|e| e.to_string())?
+                    .unwrap_or((score, bot, 0i64));
+
+                tx.execute(
+                    "INSERT INTO flow_verdicts
+                     (flow_id, job_id, version, consistency_score, bot_detected, label_prior,
+                      discrepancy_codes, previous_score, previous_bot, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    params![
+                        flow_id,
+                        job.id,
+                        version + 1,
+                        report.score,
+                        bot_detected,
+                        label_prior,
+                        serde_json::to_string(&report.discrepancy_codes).unwrap_or_default(),
+                        previous_score,
+                        previous_bot,
+                        now
+                    ],
+                )
+                .map_err(|e| e.to_string())?;
+
+                scanned += 1;
+                if i64::from(report.score) != previous_score || bot_detected != previous_bot {
+                    changed += 1;
+                }
+            }
+            tx.execute(
+                "UPDATE backfill_jobs SET flows_scanned = ?1, flows_changed = ?2 WHERE id = ?3",
+                params![scanned, changed, job.id],
+            )
+            .map_err(|e| e.to_string())?;
+            tx.commit().map_err(|e| e.to_string())?;
+        }
+    }
+
+    /// Fingerprints stored with a flow, rebuilt for scoring
+    fn stored_fingerprints(&self, flow_id: &str) -> Result<Vec<StoredFingerprint>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT fp_type, fp_id, metadata_json FROM fingerprints
+                 WHERE flow_id = ?1 ORDER BY id",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![flow_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        let mut fingerprints = Vec::new();
+        for row in rows {
+            let (fp_type, fp_id, metadata_json) = row.map_err(|e| e.to_string())?;
+            fingerprints.extend(StoredFingerprint::from_row(
+                &fp_type,
+                fp_id.unwrap_or_default(),
+                metadata_json.as_deref().unwrap_or("{}"),
+            ));
+        }
+        Ok(fingerprints)
+    }
+
+    /// Highest weak-label prior among a flow's fingerprints
+    fn flow_label_prior(&self, flow: &NetworkFlow, now: i64) -> Result<f64, String> {
+        let mut prior: f64 = 0.0;
+        for fp in flow.fingerprints() {
+            let summary = self.get_weak_label_summary(
+                fp.fingerprint_type().as_str(),
+                &fp.id(),
+                now,
+                DEFAULT_LABEL_HALF_LIFE.as_secs(),
+            )?;
+            prior = prior.max(summary.prior());
+        }
+        Ok(prior)
+    }
+
+    /// A backfill job by ID
+    pub fn backfill_job(&self, job_id: i64) -> Result<Option<BackfillJob>, String> {
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM backfill_jobs WHERE id = ?1",
+                    BACKFILL_JOB_COLUMNS
+                ),
+                params![job_id],
+                backfill_job_from_row,
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+            .transpose()
+    }
+
+    /// Most recent backfill jobs, newest first
+    pub fn backfill_jobs(&self, limit: usize) -> Result<Vec<BackfillJob>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM backfill_jobs ORDER BY id DESC LIMIT ?1",
+                BACKFILL_JOB_COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![limit as i64], backfill_job_from_row)
+            .map_err(|e| e.to_string())?;
+        rows.map(|row| row.map_err(|e| e.to_string())?).collect()
+    }
+
+    /// Verdicts a backfill job changed, in flow order
+    pub fn backfill_changes(
+        &self,
+        job_id: i64,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<VerdictChange>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT flow_id, version, previous_score, previous_bot, consistency_score,
+                        bot_detected, label_prior, discrepancy_codes
+                 FROM flow_verdicts
+                 WHERE job_id = ?1
+                   AND (consistency_score != previous_score OR bot_detected != previous_bot)
+                 ORDER BY id LIMIT ?2 OFFSET ?3",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![job_id, limit as i64, offset as i64], |row| {
+                Ok(VerdictChange {
+                    flow_id: row.get(0)?,
+                    version: row.get(1)?,
+                    previous_score: row.get(2)?,
+                    previous_bot: row.get(3)?,
+                    score: row.get(4)?,
+                    bot_detected: row.get(5)?,
+                    label_prior: row.get(6)?,
+                    discrepancy_codes: serde_json::from_str(&row.get::<_, String>(7)?)
+                        .unwrap_or_default(),
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<SqliteResult<_>>().map_err(|e| e.to_string())
+    }
+
+    /// Every verdict version of a flow, the original (version 0) first
+    pub fn flow_verdicts(&self, flow_id: &str) -> Result<Vec<FlowVerdict>, String> {
+        let Some(original) = self
+            .conn
+            .query_row(
+                "SELECT COALESCE(consistency_score, 100), COALESCE(bot_detected, 0)
+                 FROM flows WHERE id = ?1",
+                params![flow_id],
+                |row| {
+                    Ok(FlowVerdict {
+                        flow_id: flow_id.to_string(),
+                        version: 0,
+                        job_id: None,
+                        score: row.get(0)?,
+                        bot_detected: row.get(1)?,
+                        label_prior: None,
+                        discrepancy_codes: Vec::new(),
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+        else {
+            return Ok(Vec::new());
+        };
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT version, job_id, consistency_score, bot_detected, label_prior,
+                        discrepancy_codes
+                 FROM flow_verdicts WHERE flow_id = ?1 ORDER BY version",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![flow_id], |row| {
+                Ok(FlowVerdict {
+                    flow_id: flow_id.to_string(),
+                    version: row.get(0)?,
+                    job_id: row.get(1)?,
+                    score: row.get(2)?,
+                    bot_detected: row.get(3)?,
+                    label_prior: row.get(4)?,
+                    discrepancy_codes: serde_json::from_str(&row.get::<_, String>(5)?)
+                        .unwrap_or_default(),
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        let mut verdicts = vec![original];
+        for row in rows {
+            verdicts.push(row.map_err(|e| e.to_string())?);
+        }
+        Ok(verdicts)
+    }
+
+    /// Replication role, epoch and log position
+    pub fn replication_status(&self) -> Result<ReplicationStatus, String> {
+        let (role, epoch, applied_seq): (String, i64, i64) = self
+            .conn
+            .query_row(
+                "SELECT role, epoch, applied_seq FROM replication_state WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(ReplicationStatus {
+            role: ReplicationRole::parse(&role)?,
+            epoch,
+            applied_seq,
+            head_seq: self.replication_head().map_err(|e| e.to_string())?,
+        })
+    }
+
+    fn replication_head(&self) -> SqliteResult<i64> {
+        self.conn.query_row(
+            "SELECT MAX(COALESCE((SELECT MAX(seq) FROM replication_log), 0), pruned_seq)
+             FROM replication_state WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// Up to `limit` logged changes with `seq > after_seq`, oldest first
+    ///
+    /// Fails when entries after `after_seq` were already pruned; the standby
+    /// asking for them has to be reseeded from a snapshot.
+    pub fn changes_since(&self, after_seq: i64, limit: usize) -> Result<ChangeBatch, String> {
+        let (epoch, pruned_seq): (i64, i64) = self
+            .conn
+            .query_row(
+                "SELECT epoch, pruned_seq FROM replication_state WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| e.to_string())?;
+        if after_seq < pruned_seq {
+            return Err(format!(
+                "replication log pruned through seq {}, standby at {}; reseed it from a snapshot",
+                pruned_seq, after_seq
+            ));
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT seq, table_name, op, row_key, row_data FROM replication_log
+                 WHERE seq > ?1 ORDER BY seq LIMIT ?2",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![after_seq, limit as i64], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+
+        let mut changes = Vec::new();
+        for row in rows {
+            let (seq, table, op, key, data) = row.map_err(|e| e.to_string())?;
+            let op = match op.as_str() {
+                "upsert" => ChangeOp::Upsert,
+                "delete" => ChangeOp::Delete,
+                other => return Err(format!("unknown change op {} at seq {}", other, seq)),
+            };
+            changes.push(ChangeRecord {
+                seq,
+                table,
+                op,
+                key: serde_json::from_str(&key).map_err(|e| e.to_string())?,
+                row: data
+                    .map(|d| serde_json::from_str(&d))
+                    .transpose()
+                    .map_err(|e| e.to_string())?,
+            });
+        }
+
+        Ok(ChangeBatch {
+            epoch,
+            changes,
+            head_seq: self.replication_head().map_err(|e| e.to_string())?,
+        })
+    }
+
+    /// Apply a batch from the primary; returns the number of changes applied
+    ///
+    /// See [`crate::replication`] for the conflict rules. The whole batch is
+    /// applied in one transaction, so a failure leaves the standby unchanged.
+    pub fn apply_changes(&self, batch: &ChangeBatch) -> Result<usize, String> {
+        let status = self.replication_status()?;
+        if status.role != ReplicationRole::Standby {
+            return Err("apply_changes requires a standby database".to_string());
+        }
+        if batch.epoch < status.epoch {
+            return Err(format!(
+                "refusing changes from stale epoch {} (standby at epoch {})",
+                batch.epoch, status.epoch
+            ));
+        }
+        if batch.epoch > status.epoch {
+            return Err(format!(
+                "primary moved to epoch {} (standby at epoch {}); reseed from a snapshot",
+                batch.epoch, status.epoch
+            ));
+        }
+
+        let pending: Vec<&ChangeRecord> = batch
+            .changes
+            .iter()
+            .filter(|c| c.seq > status.applied_seq)
+            .collect();
+        let Some(first) = pending.first() else {
+            return Ok(0);
+        };
+        if first.seq != status.applied_seq + 1 {
+            return Err(format!(
+                "replication gap: expected seq {}, got {}",
+                status.applied_seq + 1,
+                first.seq
+            ));
+        }
+
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| e.to_string())?;
+        // Rows arrive in commit order, but check references only once the batch is in
+        tx.execute_batch("PRAGMA defer_foreign_keys = ON;")
+            .map_err(|e| e.to_string())?;
+        let mut last_seq = status.applied_seq;
+        for change in &pending {
+            if change.seq <= last_seq {
+                return Err(format!("changes out of order at seq {}", change.seq));
+            }
+            Self::apply_change(&tx, change).map_err(|e| format!("seq {}: {}", change.seq, e))?;
+            last_seq = change.seq;
+        }
+        tx.execute(
+            "UPDATE replication_state SET applied_seq = ?1 WHERE id = 1",
+            params![last_seq],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(pending.len())
+    }
+
+    fn apply_change(conn: &Connection, change: &ChangeRecord) -> Result<(), String> {
+        let Some((table, key_columns)) = REPLICATED_TABLES
+            .iter()
+            .find(|(table, _)| *table == change.table)
+        else {
+            return Err(format!("table {} is not replicated", change.table));
+        };
+        let key: Vec<Value> = key_columns
+            .iter()
+            .map(|c| {
+                change
+                    .key
+                    .get(*c)
+                    .map(json_to_sql)
+                    .ok_or_else(|| format!("missing key column {}", c))
+            })
+            .collect::<Result<_, _>>()?;
+
+        match change.op {
+            ChangeOp::Delete => {
+                let filter = key_columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| format!("{} = ?{}", c, i + 1))
+                    .collect::<Vec<_>>()
+                    .join(" AND ");
+                conn.execute(
+                    &format!("DELETE FROM {} WHERE {}", table, filter),
+                    params_from_iter(key),
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            ChangeOp::Upsert => {
+                let row = change
+                    .row
+                    .as_ref()
+                    .ok_or_else(|| "upsert without row data".to_string())?;
+                // Column names come from the wire; only plain identifiers reach the SQL
+                if let Some(bad) = row.keys().find(|c| {
+                    c.is_empty()
+                        || !c
+                            .chars()
+                            .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_')
+                }) {
+                    return Err(format!("invalid column name {:?}", bad));
+                }
+                let columns: Vec<&str> = row.keys().map(String::as_str).collect();
+                let placeholders = (1..=columns.len())
+                    .map(|i| format!("?{}", i))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let updates = columns
+                    .iter()
+                    .filter(|c| !key_columns.contains(c))
+                    .map(|c| format!("{} = excluded.{}", c, c))
+                    .collect::<Vec<_>>();
+                let on_conflict = if updates.is_empty() {
+                    "DO NOTHING".to_string()
+                } else {
+                    format!("DO UPDATE SET {}", updates.join(", "))
+                };
+                let sql = format!(
+                    "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT ({}) {}",
+                    table,
+                    columns.join(", "),
+                    placeholders,
+                    key_columns.join(", "),
+                    on_conflict
+                );
+                conn.execute(&sql, params_from_iter(row.values().map(json_to_sql)))
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Turn this database into a standby following its snapshot's primary
+    ///
+    /// Call once on a database opened from [`FingerprintDatabase::snapshot_to`]:
+    /// replication resumes after the last change the snapshot contains.
+    pub fn become_standby(&self) -> Result<(), String> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE replication_state
+             SET role = 'standby',
+                 applied_seq = MAX(COALESCE((SELECT MAX(seq) FROM replication_log), 0), pruned_seq),
+                 pruned_seq = 0
+             WHERE id = 1",
+            [],
+        )
+        .map_err(|e| e.to_string())?;
+        // The primary's log is history for the standby
+        tx.execute("DELETE FROM replication_log", [])
+            .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    /// Promote a standby to primary; returns the new epoch
+    ///
+    /// The old primary is fenced: standbys of the new epoch refuse its changes.
+    pub fn promote(&self) -> Result<i64, String> {
+        let status = self.replication_status()?;
+        if status.role != ReplicationRole::Standby {
+            return Err("only a standby can be promoted".to_string());
+        }
+        self.conn
+            .query_row(
+                "UPDATE replication_state SET role = 'primary', epoch = epoch + 1, pruned_seq = 0
+                 WHERE id = 1 RETURNING epoch",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())
+    }
+
+    /// Drop log entries up to and including `through_seq`; returns how many
+    ///
+    /// Only prune what every standby has applied.
+    pub fn prune_replication_log(&self, through_seq: i64) -> Result<usize, String> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| e.to_string())?;
+        let removed = tx
+            .execute(
+                "DELETE FROM replication_log WHERE seq <= ?1",
+                params![through_seq],
+            )
+            .map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE replication_state SET pruned_seq = MAX(pruned_seq, ?1) WHERE id = 1",
+            params![through_seq],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(removed)
+    }
+
+    /// Write a consistent copy of the database to `path`, to seed a standby
+    pub fn snapshot_to<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let path = path
+            .as_ref()
+            .to_str()
+            .ok_or_else(|| "snapshot path is not valid UTF-8".to_string())?;
+        self.conn
+            .execute("VACUUM INTO ?1", params![path])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Timeline events of one table, newest first
+    ///
+    /// `filter` is a SQL condition using `?1..` for `args`; the time bound and
+    /// limit are appended.
+    fn timeline_rows<T>(
+        &self,
+        table: &TimelineTable,
+        filter: &str,
+        mut args: Vec<Value>,
+        until: Option<Value>,
+        limit: usize,
+        map: impl FnMut(&rusqlite::Row<'_>) -> SqliteResult<T>,
+    ) -> SqliteResult<Vec<T>> {
+        let mut sql = format!("{} WHERE ({})", table.select, filter);
+        if let Some(until) = until {
+            args.push(until);
+            sql.push_str(&format!(" AND {} <= ?{}", table.ts, args.len()));
+        }
+        args.push(Value::Integer(limit as i64));
+        sql.push_str(&format!(
+            " ORDER BY {} DESC, {} ASC LIMIT ?{}",
+            table.ts,
+            table.id,
+            args.len()
+        ));
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(args.iter()), map)?;
+        rows.collect()
+    }
+
+    fn timeline_events(
+        &self,
+        subject: &ResolvedSubject,
+        until: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> SqliteResult<Vec<TimelineEvent>> {
+        let fingerprints: Vec<Value> = subject
+            .fingerprints
+            .iter()
+            .map(|f| Value::Text(f.clone()))
+            .collect();
+        let in_list = |offset: usize| {
+            (1..=fingerprints.len())
+                .map(|i| format!("?{}", i + offset))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let in_fingerprints = if fingerprints.is_empty() {
+            "0".to_string()
+        } else {
+            format!("IN ({})", in_list(0))
+        };
+        let text_until = until.map(|u| Value::Text(u.to_rfc3339()));
+        let unix_until = until.map(|u| Value::Integer(u.timestamp()));
+
+        // 带 subject 的记录按身份过滤，其余按关联指纹过滤
+        let (by_subject, subject_args) = match &subject.identity {
+            Some(identity) => ("= ?1".to_string(), vec![Value::Text(identity.clone())]),
+            None => (in_fingerprints.clone(), fingerprints.clone()),
+        };
+        let mut events = Vec::new();
+
+        // observations: one per stored fingerprint
+        let filter = match subject.identity {
+            Some(_) => format!("fl.source_ip {}", by_subject),
+            None => format!(
+                "f.fp_id {} OR f.ja4_plus {}",
+                in_fingerprints, in_fingerprints
+            ),
+        };
+        events.extend(
+            self.timeline_rows(
+                &OBSERVATION_TIMELINE,
+                &filter,
+                subject_args.clone(),
+                text_until.clone(),
+                limit,
+                |row| {
+                    let fp_type: String = row.get(1)?;
+                    let fp_id: String = row.get(2)?;
+                    let source_ip: Option<String> = row.get(5)?;
+                    Ok(parse_db_timestamp(&row.get::<_, String>(7)?).map(|timestamp| {
+                        TimelineEvent {
+                            timestamp,
+                            source: TIMELINE_SOURCE.to_string(),
+                            id: format!("observation:{:020}", row.get::<_, i64>(0).unwrap_or(0)),
+                            kind: TimelineEventKind::Observation,
+                            summary: format!(
+                                "{} fingerprint observed from {}",
+                                fp_type,
+                                source_ip.as_deref().unwrap_or("unknown")
+                            ),
+                            fingerprint: Some(FingerprintRef::new(
+                                fp_type.to_lowercase(),
+                                fp_id,
+                            )),
+                            subject: source_ip,
+                            details: serde_json::json!({
+                                "flow_id": row.get::<_, String>(4).unwrap_or_default(),
+                                "ja4_plus": row.get::<_, Option<String>>(3).unwrap_or_default(),
+                                "target_ip": row.get::<_, Option<String>>(6).unwrap_or_default(),
+                            }),
+                        }
+                    }))
+                },
+            )?
+            .into_iter()
+            .flatten(),
+        );
+
+        // analysis: flow consistency scores
+        let filter = match subject.identity {
+            Some(_) => format!("fl.source_ip {}", by_subject),
+            None => format!(
+                "EXISTS (SELECT 1 FROM fingerprints f WHERE f.flow_id = fl.id
+                  AND (f.fp_id {} OR f.ja4_plus {}))",
+                in_fingerprints, in_fingerprints
+            ),
+        };
+        events.extend(
+            self.timeline_rows(
+                &FLOW_TIMELINE,
+                &filter,
+                subject_args.clone(),
+                text_until.clone(),
+                limit,
+                |row| {
+                    let flow_id: String = row.get(0)?;
+                    let score: Option<i64> = row.get(3)?;
+                    let bot: Option<bool> = row.get(4)?;
+                    Ok(
+                        parse_db_timestamp(&row.get::<_, String>(2)?).map(|timestamp| {
+                            TimelineEvent {
+                                timestamp,
+                                source: TIMELINE_SOURCE.to_string(),
+                                id: format!("flow:{}", flow_id),
+                                kind: TimelineEventKind::Analysis,
+                                fingerprint: None,
+                                subject: row.get(1).unwrap_or_default(),
+                                summary: format!(
+                                    "flow scored {}{}",
+                                    score.unwrap_or_default(),
+                                    if bot == Some(true) {
+                                        ", bot detected"
+                                    } else {
+                                        ""
+                                    }
+                                ),
+                                details: serde_json::json!({
+                                    "flow_id": flow_id,
+                                    "consistency_score": score,
+                                    "bot_detected": bot,
+                                }),
+                            }
+                        }),
+                    )
+                },
+            )?
+            .into_iter()
+            .flatten(),
+        );
+
+        // analysis: learner candidates (not tied to an identity)
+        events.extend(
+            self.timeline_rows(
+                &CANDIDATE_TIMELINE,
+                &format!("fingerprint_id {}", in_fingerprints),
+                fingerprints.clone(),
+                text_until,
+                limit,
+                |row| {
+                    let status: Option<String> = row.get(6)?;
+                    let stability: f64 = row.get(4)?;
+                    Ok(
+                        parse_db_timestamp(&row.get::<_, String>(5)?).map(|timestamp| {
+                            TimelineEvent {
+                                timestamp,
+                                source: TIMELINE_SOURCE.to_string(),
+                                id: format!("candidate:{:020}", row.get::<_, i64>(0).unwrap_or(0)),
+                                kind: TimelineEventKind::Analysis,
+                                fingerprint: Some(FingerprintRef::new(
+                                    row.get::<_, String>(1).unwrap_or_default(),
+                                    row.get::<_, String>(2).unwrap_or_default(),
+                                )),
+                                subject: None,
+                                summary: format!(
+                                    "learner candidate ({}, stability {:.2})",
+                                    status.as_deref().unwrap_or("pending"),
+                                    stability
+                                ),
+                                details: serde_json::json!({
+                                    "observation_count": row.get::<_, i64>(3).unwrap_or_default(),
+                                    "stability_score": stability,
+                                    "status": status,
+                                }),
+                            }
+                        }),
+                    )
+                },
+            )?
+            .into_iter()
+            .flatten(),
+        );
+
+        // alerts and enforcement decisions
+        let filter = match subject.identity {
+            Some(_) => format!("subject {}", by_subject),
+            None => format!("fingerprint_id {}", in_fingerprints),
+        };
+        events.extend(self.timeline_rows(
+            &ALERT_TIMELINE,
+            &filter,
+            subject_args.clone(),
+            unix_until.clone(),
+            limit,
+            |row| {
+                let severity: String = row.get(3)?;
+                let message: String = row.get(4)?;
+                Ok(TimelineEvent {
+                    timestamp: unix_timestamp(row.get(6)?),
+                    source: TIMELINE_SOURCE.to_string(),
+                    id: format!("alert:{:020}", row.get::<_, i64>(0)?),
+                    kind: TimelineEventKind::Alert,
+                    fingerprint: Some(FingerprintRef::new(
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    )),
+                    subject: row.get(5)?,
+                    summary: format!("[{}] {}", severity, message),
+                    details: serde_json::json!({ "severity": severity }),
+                })
+            },
+        )?);
+        events.extend(self.timeline_rows(
+            &LABEL_TIMELINE,
+            &filter,
+            subject_args,
+            unix_until,
+            limit,
+            |row| {
+                let outcome: String = row.get(3)?;
+                let weight: f64 = row.get(4)?;
+                Ok(TimelineEvent {
+                    timestamp: unix_timestamp(row.get(6)?),
+                    source: TIMELINE_SOURCE.to_string(),
+                    id: format!("label:{:020}", row.get::<_, i64>(0)?),
+                    kind: TimelineEventKind::Decision,
+                    fingerprint: Some(FingerprintRef::new(
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    )),
+                    subject: row.get(5)?,
+                    summary: format!("{} (weight {:.2})", outcome, weight),
+                    details: serde_json::json!({ "outcome": outcome, "weight": weight }),
+                })
+            },
+        )?);
+
+        events.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| a.id.cmp(&b.id)));
+        events.truncate(limit);
+        Ok(events)
+    }
+}
+
+impl TimelineSource for FingerprintDatabase {
+    fn name(&self) -> &str {
+        TIMELINE_SOURCE
+    }
+
+    fn linked_fingerprints(&self, identity: &str) -> Result<Vec<String>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT f.fp_id FROM fingerprints f JOIN flows fl ON fl.id = f.flow_id
+                 WHERE fl.source_ip = ?1
+                 UNION SELECT fingerprint_id FROM fingerprint_labels WHERE subject = ?1
+                 UNION SELECT fingerprint_id FROM fingerprint_alerts WHERE subject = ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![identity], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())
+    }
+
+    fn events(
+        &self,
+        subject: &ResolvedSubject,
+        until: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<TimelineEvent>, String> {
+        self.timeline_events(subject, until, limit)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Parse a stored DATETIME (RFC 3339, or SQLite's `CURRENT_TIMESTAMP` format)
+fn parse_db_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map(|t| t.and_utc()))
+        .ok()
+}
+
+/// SQLite value of a replicated JSON column
+fn json_to_sql(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => n
+            .as_i64()
+            .map(Value::Integer)
+            .unwrap_or_else(|| Value::Real(n.as_f64().unwrap_or_default())),
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+fn unix_timestamp(secs: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(secs, 0).unwrap_or_default()
+}
+
+/// id, source_ip, target_ip, protocol, timestamp, score and bot flag of a stored flow
+type StoredFlowRow = (
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    String,
+    i64,
+    bool,
+);
+
+const BACKFILL_JOB_COLUMNS: &str = "id, window_start, window_end, label_threshold, note, status,
+     flows_scanned, flows_changed, error, created_at, finished_at";
+
+/// A `backfill_jobs` row selected with [`BACKFILL_JOB_COLUMNS`]
+fn backfill_job_from_row(row: &rusqlite::Row<'_>) -> SqliteResult<Result<BackfillJob, String>> {
+    let window_start: String = row.get(1)?;
+    let window_end: String = row.get(2)?;
+    let status: String = row.get(5)?;
+    let finished_at: Option<i64> = row.get(10)?;
+    let (Some(from), Some(to)) = (
+        parse_db_timestamp(&window_start),
+        parse_db_timestamp(&window_end),
+    ) else {
+        return Ok(Err(format!(
+            "invalid backfill window {}..{}",
+            window_start, window_end
+        )));
+    };
+    Ok(BackfillStatus::parse(&status).map(|status| BackfillJob {
+        id: row.get(0).unwrap_or_default(),
+        request: BackfillRequest {
+            from,
+            to,
+            label_threshold: row.get(3).unwrap_or_default(),
+            note: row.get(4).unwrap_or_default(),
+        },
+        status,
+        flows_scanned: row.get::<_, i64>(6).unwrap_or_default() as u64,
+        flows_changed: row.get::<_, i64>(7).unwrap_or_default() as u64,
+        error: row.get(8).unwrap_or_default(),
+        created_at: unix_timestamp(row.get(9).unwrap_or_default()),
+        finished_at: finished_at.map(unix_timestamp),
+    }))
+}
+
+/// Flow rebuilt from its stored row and fingerprints
+///
+/// Ports and packet details are not stored, so only what the analyzers read is restored.
+fn rebuild_flow(
+    source_ip: Option<&str>,
+    target_ip: Option<&str>,
+    protocol: Option<&str>,
+    timestamp: &str,
+    fingerprints: Vec<StoredFingerprint>,
+) -> NetworkFlow {
+    let ip = |value: Option<&str>| {
+        value
+            .and_then(|v| v.parse::<IpAddr>().ok())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+    };
+    let protocol = match protocol.unwrap_or_default() {
+        "Tcp" => ProtocolType::Tcp,
+        "Udp" => ProtocolType::Udp,
+        "Icmp" => ProtocolType::Icmp,
+        "Http" => ProtocolType::Http,
+        "Https" => ProtocolType::Https,
+        other => other
+            .strip_prefix("Other(")
+            .and_then(|n| n.strip_suffix(')'))
+            .and_then(|n| n.parse().ok())
+            .map(ProtocolType::Other)
+            .unwrap_or(ProtocolType::Tcp),
+    };
+    let mut context = SystemContext::new(ip(source_ip), ip(target_ip), protocol);
+    if let Some(timestamp) = parse_db_timestamp(timestamp) {
+        context.timestamp = timestamp;
+    }
+    let mut flow = NetworkFlow::new(context);
+    for fingerprint in fingerprints {
+        flow.add_fingerprint(Box::new(fingerprint));
+    }
+    flow
+}
+
+/// Candidate fingerprint data structure
+#[derive(Debug, Clone)]
+pub struct CandidateFingerprint {
+    pub id: i64,
+    pub fingerprint_type: String,
+    pub fingerprint_id: String,
+    pub observation_count: u32,
+    pub stability_score: f64,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub status: String,
+    pub notes: Option<String>,
+}
+
+/// Aggregated weak labels of one fingerprint
+#[derive(Debug, Clone, Default)]
+pub struct WeakLabelSummary {
+    /// Number of labels recorded
+    pub count: u32,
+    /// Sum of label weights after time decay
+    pub decayed_weight: f64,
+    /// Label count per source (e.g. "rate_limited")
+    pub by_source: std::collections::HashMap<String, u32>,
+    /// Most recent label (Unix seconds)
+    pub last_seen: Option<i64>,
+}
+
+impl WeakLabelSummary {
+    /// Prior probability that the fingerprint is abusive, in [0, 1)
+    pub fn prior(&self) -> f64 {
+        1.0 - (-self.decayed_weight).exp()
+    }
+}
+
+/// Candidate fingerprint statistics information
+#[derive(Debug, Clone)]
+pub struct CandidateStats {
+    pub pending: u32,
+    pub approved: u32,
+    pub rejected: u32,
+}
+
+fn tombstone_from_row(row: &rusqlite::Row<'_>) -> SqliteResult<Tombstone> {
+    let reason: String = row.get(2)?;
+    Ok(Tombstone {
+        fingerprint_type: row.get(0)?,
+        fingerprint_id: row.get(1)?,
+        // reasons this build does not know are treated as operator removals
+        reason: reason.parse().unwrap_or(TombstoneReason::Manual),
+        note: row.get(3)?,
+        evidence_multiplier: row.get(4)?,
+        previous_status: row.get(5)?,
+        resurrections: row.get(6)?,
+        deleted_at: row.get(7)?,
+    })
+}
+
+fn threat_intel_from_row(row: &rusqlite::Row<'_>) -> SqliteResult<ThreatIntelIndicator> {
+    let kind: String = row.get(3)?;
+    Ok(ThreatIntelIndicator {
+        stix_id: row.get(0)?,
+        fingerprint_type: row.get(1)?,
+        fingerprint_id: row.get(2)?,
+        kind: if kind == "ja3" {
+            FingerprintKind::Ja3
+        } else {
+            FingerprintKind::Ja4
+        },
+        source: row.get(4)?,
+        name: row.get(5)?,
+        confidence: row.get(6)?,
+        valid_from: row.get(7)?,
+        valid_until: row.get(8)?,
+        revoked: row.get(9)?,
+        modified_ms: row.get(10)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn applies_embedded_migrations_for_in_memory_database() {
+        let db = FingerprintDatabase::new_in_memory().expect("open in-memory db");
+        assert_eq!(db.current_schema_version().unwrap(), 10);
+        assert_eq!(
+            db.applied_migrations().unwrap(),
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10]
+        );
+    }
+
+    #[test]
+    fn opens_database_at_data_dirs_location() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let dirs = DataDirs::portable(temp_dir.path());
+
+        let db = FingerprintDatabase::open_in(&dirs).expect("open db");
+        assert_eq!(db.current_schema_version().unwrap(), 10);
+        assert!(dirs.defense_database().exists());
+    }
+
+    #[test]
+    fn reopening_database_keeps_single_migration_history() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let db_path = temp_dir.path().join("fingerprints.db");
+
+        let first = FingerprintDatabase::open(&db_path).expect("open db");
+        assert_eq!(first.current_schema_version().unwrap(), 10);
+        drop(first);
+
+        let reopened = FingerprintDatabase::open(&db_path).expect("reopen db");
+        assert_eq!(
+            reopened.applied_migrations().unwrap(),
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10]
+        );
+
+        let migration_count: i64 = reopened
+            .conn
+            .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(migration_count, 10);
+    }
+
+    fn store_fingerprint_row(db: &FingerprintDatabase, pairs: &[(&str, &str)]) {
+        let mut metadata = FingerprintMetadata::new();
+        for (key, value) in pairs {
+            metadata.set(key, value);
+        }
+        db.conn
+            .execute(
+                "INSERT INTO fingerprints (flow_id, fp_type, fp_id, ja4_plus, metadata_json)
+                 VALUES (NULL, 'Tls', 'id', '', ?1)",
+                params![serde_json::to_string(&metadata).unwrap()],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn indexes_new_fingerprints_incrementally() {
+        let db = FingerprintDatabase::new_in_memory().expect("open in-memory db");
+        let chrome = "t13d1514h2_ba7f6aa88938_f7e2764aeb6b_ec13";
+
+        store_fingerprint_row(&db, &[("ja4", chrome), ("ja3", "771,4865-4866,0-23,29,0")]);
+        store_fingerprint_row(&db, &[("ja4", chrome)]);
+        assert_eq!(db.index_pending().unwrap(), 3);
+        assert_eq!(db.index_pending().unwrap(), 0);
+
+        let entry = db.get_index_entry("ja4", chrome).unwrap().unwrap();
+        assert_eq!(entry.hit_count, 2);
+        assert_eq!(entry.components.unwrap().cipher_count, Some(15));
+        let ja3 = db
+            .get_index_entry("ja3", "771,4865-4866,0-23,29,0")
+            .unwrap();
+        assert!(ja3.unwrap().components.is_none());
+
+        store_fingerprint_row(&db, &[("ja4", chrome)]);
+        assert_eq!(db.index_pending().unwrap(), 1);
+        assert_eq!(
+            db.get_index_entry("ja4", chrome)
+                .unwrap()
+                .unwrap()
+                .hit_count,
+            3
+        );
+    }
+
+    #[test]
+    fn searches_index_by_prefix_and_components_with_pagination() {
+        let db = FingerprintDatabase::new_in_memory().expect("open in-memory db");
+        let values = [
+            "t13d1514h2_ba7f6aa88938_f7e2764aeb6b_ec13",
+            "t13d1515h2_aaaaaaaaaaaa_f7e2764aeb6b_ec13",
+            "t13d1516h2_bbbbbbbbbbbb_cccccccccccc_ec13",
+            "t13i0908h2_cab9e4056364_d49957dab4ab_8028",
+            "t12d1209h1_dddddddddddd_eeeeeeeeeeee_ffff",
+        ];
+        for value in values {
+            db.index_fingerprint("ja4", value, "2026-01-01T00:00:00+00:00")
+                .unwrap();
+        }
+
+        let exact = db
+            .search_fingerprint_index(&IndexQuery::exact(values[3]))
+            .unwrap();
+        assert_eq!(exact.total, 1);
+        assert_eq!(exact.entries[0].value, values[3]);
+
+        let mut query = IndexQuery::prefix("t13d15").with_limit(2);
+        let first = db.search_fingerprint_index(&query).unwrap();
+        assert_eq!(first.total, 3);
+        assert_eq!(first.entries.len(), 2);
+        query = query.after(first.next_cursor.clone().unwrap());
+        let second = db.search_fingerprint_index(&query).unwrap();
+        assert_eq!(second.entries.len(), 1);
+        assert_eq!(second.entries[0].value, values[2]);
+        assert!(second.next_cursor.is_none());
+
+        let by_extensions = db
+            .search_fingerprint_index(&IndexQuery::components(Ja4Components {
+                tls_version: Some("13".to_string()),
+                extension_hash: Some("f7e2764aeb6b".to_string()),
+                ..Default::default()
+            }))
+            .unwrap();
+        assert_eq!(by_extensions.total, 2);
+
+        let by_count = db
+            .search_fingerprint_index(&IndexQuery::components(Ja4Components {
+                cipher_count: Some(9),
+                ..Default::default()
+            }))
+            .unwrap();
+        assert_eq!(by_count.entries[0].value, values[3]);
+
+        assert!(db
+            .search_fingerprint_index(&IndexQuery::prefix("t13").after("no-separator"))
+            .is_err());
+    }
+
+    #[test]
+    fn timeline_stitches_events_for_fingerprint_and_identity() {
+        use crate::timeline::{Timeline, TimelineEventKind, TimelineQuery};
+
+        let db = FingerprintDatabase::new_in_memory().expect("open in-memory db");
+        let ja4 = "t13d1514h2_ba7f6aa88938_f7e2764aeb6b_ec13";
+        for (flow, ip, ts) in [
+            ("flow-1", "10.0.0.1", "2026-01-01T00:00:00+00:00"),
+            ("flow-2", "10.0.0.2", "2026-01-01T00:10:00+00:00"),
+        ] {
+            db.conn
+                .execute(
+                    "INSERT INTO flows (id, source_ip, target_ip, protocol, timestamp,
+                     consistency_score, bot_detected) VALUES (?1, ?2, '10.9.9.9', 'Tcp', ?3, 40, 1)",
+                    params![flow, ip, ts],
+                )
+                .unwrap();
+            db.conn
+                .execute(
+                    "INSERT INTO fingerprints (flow_id, fp_type, fp_id, ja4_plus, metadata_json)
+                     VALUES (?1, 'Tls', 'blake3:aa', ?2, '{}')",
+                    params![flow, ja4],
+                )
+                .unwrap();
+        }
+        let jan1 = 1_767_225_600; // 2026-01-01T00:00:00Z
+        db.store_weak_label("tls", ja4, "rate_limited", 0.1, Some("10.0.0.1"), jan1 + 60)
+            .unwrap();
+        db.store_alert(
+            "tls",
+            ja4,
+            "high",
+            "known bot JA4",
+            Some("10.0.0.1"),
+            jan1 + 120,
+        )
+        .unwrap();
+        db.store_candidate_fingerprint("tls", ja4, 12, 0.9, None)
+            .unwrap();
+
+        let timeline = Timeline::new().with_source(&db);
+
+        // 指纹视角：两个来源 IP 的观测都在
+        let page = timeline.query(&TimelineQuery::fingerprint(ja4)).unwrap();
+        let kinds: Vec<_> = page.events.iter().map(|e| e.kind).collect();
+        assert_eq!(page.events.len(), 7);
+        assert_eq!(kinds[0], TimelineEventKind::Analysis); // candidate, recorded now
+        assert!(kinds.contains(&TimelineEventKind::Alert));
+        assert!(kinds.contains(&TimelineEventKind::Decision));
+        assert!(page
+            .events
+            .windows(2)
+            .all(|w| w[0].timestamp >= w[1].timestamp));
+
+        // 身份视角：只包含该 IP 的记录，外加关联指纹的学习结果
+        let mut query = TimelineQuery::identity("10.0.0.1").with_limit(2);
+        let mut events = Vec::new();
+        loop {
+            let page = timeline.query(&query).unwrap();
+            assert!(page.fingerprints.contains(&ja4.to_string()));
+            events.extend(page.events);
+            match page.next_cursor {
+                Some(cursor) => query = query.after(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(events.len(), 5);
+        assert!(events
+            .iter()
+            .all(|e| e.subject.is_none() || e.subject.as_deref() == Some("10.0.0.1")));
+        assert_eq!(events.last().unwrap().kind, TimelineEventKind::Observation);
+    }
+
+    #[test]
+    fn backfill_writes_new_verdict_versions_and_reports_changes() {
+        use crate::passive::consistency::ConsistencyAnalyzer;
+        use fingerprint_fixtures::Fixtures;
+
+        let db = FingerprintDatabase::new_in_memory().unwrap();
+        let analyzer = ConsistencyAnalyzer::new();
+        let mut fixtures = Fixtures::new(31);
+        let start = fixtures.now();
+
+        // consistent flows were scored like today; spoofed ones slipped past an older model
+        for flow in fixtures.flows(3) {
+            let report = analyzer.score(&flow);
+            db.store_flow(&flow, report.score, report.bot_detected)
+                .unwrap();
+        }
+        let spoofed: Vec<String> = (0..2)
+            .map(|_| {
+                let flow = fixtures.spoofed_flow();
+                db.store_flow(&flow, 100, false).unwrap();
+                flow.flow_id()
+            })
+            .collect();
+        let request = BackfillRequest::new(start, fixtures.now() + chrono::Duration::hours(1))
+            .with_note("tcp/os mapping update");
+
+        let report = db.run_backfill(&request, &analyzer).unwrap();
+        assert_eq!(report.job.status, BackfillStatus::Completed);
+        assert_eq!(report.job.flows_scanned, 5);
+        assert_eq!(report.job.flows_changed, 2);
+        assert_eq!(report.newly_flagged, 2);
+        assert_eq!(report.cleared, 0);
+        let changed: Vec<&str> = report.changes.iter().map(|c| c.flow_id.as_str()).collect();
+        assert_eq!(changed.len(), 2);
+        assert!(spoofed.iter().all(|id| changed.contains(&id.as_str())));
+        assert!(report.changes[0]
+            .discrepancy_codes
+            .contains(&"consistency.tcp_os_mismatch".to_string()));
+
+        // the original verdict stays as version 0
+        let history = db.flow_verdicts(&spoofed[0]).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!((history[0].version, history[0].score), (0, 100));
+        assert!(!history[0].bot_detected);
+        assert_eq!(history[1].version, 1);
+        assert_eq!(history[1].job_id, Some(report.job.id));
+        assert!(history[1].bot_detected);
+
+        // re-running compares against the latest version
+        let again = db.run_backfill(&request, &analyzer).unwrap();
+        assert_eq!(again.job.flows_scanned, 5);
+        assert_eq!(again.job.flows_changed, 0);
+        assert_eq!(db.flow_verdicts(&spoofed[0]).unwrap().len(), 3);
+
+        // a finished job is not run twice; an empty window scans nothing
+        assert!(db.run_backfill_job(report.job.id, &analyzer).is_err());
+        let empty = BackfillRequest::new(start - chrono::Duration::days(2), start);
+        assert_eq!(
+            db.run_backfill(&empty, &analyzer)
+                .unwrap()
+                .job
+                .flows_scanned,
+            0
+        );
+        let jobs = db.backfill_jobs(10).unwrap();
+        assert_eq!(jobs.len(), 3);
+        assert_eq!(
+            jobs[2].request.note.as_deref(),
+            Some("tcp/os mapping update")
+        );
+    }
+
+    #[test]
+    fn backfill_flags_flows_whose_fingerprints_were_labeled_since() {
+        use crate::passive::consistency::ConsistencyAnalyzer;
+        use fingerprint_fixtures::Fixtures;
+
+        let db = FingerprintDatabase::new_in_memory().unwrap();
+        let analyzer = ConsistencyAnalyzer::new();
+        let mut fixtures = Fixtures::new(32);
+        let start = fixtures.now();
+        let (flow, report) = std::iter::repeat_with(|| fixtures.flow())
+            .map(|flow| {
+                let report = analyzer.score(&flow);
+                (flow, report)
+            })
+            .find(|(_, report)| !report.bot_detected)
+            .unwrap();
+        db.store_flow(&flow, report.score, false).unwrap();
+        let tcp = flow
+            .get_fingerprints_by_type(fingerprint_core::fingerprint::FingerprintType::Tcp)[0]
+            .id();
+        db.store_weak_label(
+            "tcp",
+            &tcp,
+            "rate_limited",
+            5.0,
+            None,
+            Utc::now().timestamp(),
+        )
+        .unwrap();
+
+        let request = BackfillRequest::new(start, fixtures.now() + chrono::Duration::hours(1))
+            .with_label_threshold(0.9);
+        let report = db.run_backfill(&request, &analyzer).unwrap();
+        assert_eq!(report.newly_flagged, 1);
+        let change = &report.changes[0];
+        assert!(change.newly_flagged());
+        assert!(change.label_prior.unwrap() > 0.9);
+    }
+
+    #[test]
+    fn tombstones_soft_delete_restore_and_purge() {
+        let db = FingerprintDatabase::new_in_memory().unwrap();
+        let id = db
+            .store_candidate_fingerprint("tls", "t13d_bad", 12, 0.9, None)
+            .unwrap();
+        db.update_candidate_status(id, "approved", None).unwrap();
+        db.store_candidate_fingerprint("tls", "t13d_dupe", 12, 0.9, None)
+            .unwrap();
+
+        let tombstone = db
+            .tombstone_fingerprint(
+                "tls",
+                "t13d_bad",
+                TombstoneReason::Malicious,
+                Some("botnet"),
+            )
+            .unwrap();
+        assert_eq!(tombstone.previous_status.as_deref(), Some("approved"));
+        assert_eq!(tombstone.evidence_multiplier, 4.0);
+        assert_eq!(db.get_candidate_stats().unwrap().approved, 0);
+
+        // the candidate row is kept, only hidden
+        let status: String = db
+            .conn
+            .query_row(
+                "SELECT status FROM candidate_fingerprints WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(status, "deleted");
+
+        assert!(db.restore_fingerprint("tls", "t13d_bad").unwrap());
+        assert!(db.tombstone("tls", "t13d_bad").unwrap().is_none());
+        assert_eq!(db.get_candidate_stats().unwrap().approved, 1);
+        assert!(!db.restore_fingerprint("tls", "t13d_bad").unwrap());
+
+        db.tombstone_fingerprint("tls", "t13d_bad", TombstoneReason::Malicious, None)
+            .unwrap();
+        db.tombstone_fingerprint("tls", "t13d_dupe", TombstoneReason::Duplicate, None)
+            .unwrap();
+        assert_eq!(db.tombstones(10).unwrap().len(), 2);
+
+        let later = Utc::now() + chrono::Duration::days(31);
+        let report = db
+            .purge_tombstones(&TombstoneRetention::default(), later)
             .unwrap();
-        assert_eq!(migration_count, 3);
+        assert_eq!(report.tombstones.get(&TombstoneReason::Duplicate), Some(&1));
+        assert_eq!(report.total_tombstones(), 1);
+        assert_eq!(report.candidates, 1);
+        // malicious tombstones are kept forever by default
+        assert!(db.tombstone("tls", "t13d_bad").unwrap().is_some());
+        assert!(db.tombstone("tls", "t13d_dupe").unwrap().is_none());
     }
 }
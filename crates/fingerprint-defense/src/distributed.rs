@@ -0,0 +1,538 @@
+//! Fleet-wide candidate fingerprints
+//!
+//! [`FingerprintDatabase`] is local to one gateway. A [`FleetStore`] shares
+//! what the learners of a whole fleet know about candidate fingerprints —
+//! observation counts and review classifications — through a key-value
+//! store: [`MemoryKvStore`] in-process, `RedisKvStore` (`redis` feature)
+//! across hosts.
+//!
+//! ## Merge rules
+//!
+//! Every candidate is one JSON document, written with an optimistic
+//! compare-and-set on its version and retried on conflict. Merging is
+//! commutative and idempotent, so concurrent writers converge:
+//!
+//! - **Per-node counts**: each node owns its own [`NodeObservation`]; the
+//!   larger count wins, so a node that restarts and counts from zero does
+//!   not shrink the fleet total.
+//! - **Fleet total**: the sum over nodes.
+//! - **Classification**: last writer wins by decision time, ties broken by
+//!   node id.
+//!
+//! [`FingerprintDatabase`]: crate::database::FingerprintDatabase
+
+use crate::database::CandidateStats;
+use crate::learner::UnknownFingerprintObservation;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// Default key namespace
+pub const DEFAULT_PREFIX: &str = "fpd:candidate";
+
+/// Compare-and-set attempts before giving up on a contended key
+const MAX_RETRIES: usize = 16;
+
+/// Value with the version it was read at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Versioned {
+    pub version: u64,
+    pub value: String,
+}
+
+/// Versioned key-value storage shared by a fleet
+pub trait KvStore: Send + Sync {
+    /// Current value of `key`
+    fn get(&self, key: &str) -> Result<Option<Versioned>, String>;
+
+    /// Write `value` if `key` is still at `version` (0: absent)
+    ///
+    /// Returns false when another writer got there first.
+    fn compare_and_set(&self, key: &str, version: u64, value: &str) -> Result<bool, String>;
+
+    /// Keys starting with `prefix`
+    fn keys(&self, prefix: &str) -> Result<Vec<String>, String>;
+}
+
+impl<T: KvStore + ?Sized> KvStore for Arc<T> {
+    fn get(&self, key: &str) -> Result<Option<Versioned>, String> {
+        (**self).get(key)
+    }
+
+    fn compare_and_set(&self, key: &str, version: u64, value: &str) -> Result<bool, String> {
+        (**self).compare_and_set(key, version, value)
+    }
+
+    fn keys(&self, prefix: &str) -> Result<Vec<String>, String> {
+        (**self).keys(prefix)
+    }
+}
+
+/// In-process store for single-node deployments and tests
+#[derive(Debug, Default)]
+pub struct MemoryKvStore {
+    entries: Mutex<HashMap<String, Versioned>>,
+}
+
+impl MemoryKvStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl KvStore for MemoryKvStore {
+    fn get(&self, key: &str) -> Result<Option<Versioned>, String> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(entries.get(key).cloned())
+    }
+
+    fn compare_and_set(&self, key: &str, version: u64, value: &str) -> Result<bool, String> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let current = entries.get(key).map_or(0, |entry| entry.version);
+        if current != version {
+            return Ok(false);
+        }
+        entries.insert(
+            key.to_string(),
+            Versioned {
+                version: current + 1,
+                value: value.to_string(),
+            },
+        );
+        Ok(true)
+    }
+
+    fn keys(&self, prefix: &str) -> Result<Vec<String>, String> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(entries
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(feature = "redis")]
+pub use redis_store::RedisKvStore;
+
+#[cfg(feature = "redis")]
+mod redis_store {
+    use super::{KvStore, Versioned};
+    use std::sync::Mutex;
+
+    /// Bump the version and write, if the version still matches
+    const CAS_SCRIPT: &str = r#"
+local current = tonumber(redis.call('HGET', KEYS[1], 'version') or '0')
+if current ~= tonumber(ARGV[1]) then
+    return 0
+end
+redis.call('HSET', KEYS[1], 'version', current + 1, 'value', ARGV[2])
+return 1
+"#;
+
+    /// Redis-backed store shared by all gateways
+    ///
+    /// Each key is a hash with `version` and `value` fields.
+    pub struct RedisKvStore {
+        connection: Mutex<redis::Connection>,
+    }
+
+    impl RedisKvStore {
+        pub fn connect(url: &str) -> Result<Self, String> {
+            let client = redis::Client::open(url).map_err(|e| e.to_string())?;
+            let connection = client.get_connection().map_err(|e| e.to_string())?;
+            Ok(Self {
+                connection: Mutex::new(connection),
+            })
+        }
+
+        fn connection(&self) -> std::sync::MutexGuard<'_, redis::Connection> {
+            self.connection.lock().unwrap_or_else(|e| e.into_inner())
+        }
+    }
+
+    impl KvStore for RedisKvStore {
+        fn get(&self, key: &str) -> Result<Option<Versioned>, String> {
+            let (version, value): (Option<u64>, Option<String>) = redis::cmd("HMGET")
+                .arg(key)
+                .arg("version")
+                .arg("value")
+                .query(&mut *self.connection())
+                .map_err(|e| e.to_string())?;
+            Ok(version
+                .zip(value)
+                .map(|(version, value)| Versioned { version, value }))
+        }
+
+        fn compare_and_set(&self, key: &str, version: u64, value: &str) -> Result<bool, String> {
+            let written: i64 = redis::Script::new(CAS_SCRIPT)
+                .key(key)
+                .arg(version)
+                .arg(value)
+                .invoke(&mut *self.connection())
+                .map_err(|e| e.to_string())?;
+            Ok(written == 1)
+        }
+
+        fn keys(&self, prefix: &str) -> Result<Vec<String>, String> {
+            let mut connection = self.connection();
+            let pattern = format!("{}*", prefix);
+            let mut keys = Vec::new();
+            let mut cursor: u64 = 0;
+            loop {
+                let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+                    .arg(cursor)
+                    .arg("MATCH")
+                    .arg(&pattern)
+                    .arg("COUNT")
+                    .arg(500)
+                    .query(&mut *connection)
+                    .map_err(|e| e.to_string())?;
+                keys.extend(batch);
+                if next == 0 {
+                    return Ok(keys);
+                }
+                cursor = next;
+            }
+        }
+    }
+}
+
+/// What one node has seen of a candidate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeObservation {
+    pub observation_count: u64,
+    pub stability_score: f64,
+    /// Unix seconds
+    pub first_seen: u64,
+    /// Unix seconds
+    pub last_seen: u64,
+}
+
+/// Review decision on a candidate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Classification {
+    /// `approved` / `rejected`, as in the candidate table
+    pub status: String,
+    pub notes: Option<String>,
+    pub node: String,
+    pub decided_at: DateTime<Utc>,
+}
+
+/// A candidate fingerprint as the fleet knows it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedCandidate {
+    pub fingerprint_type: String,
+    pub fingerprint_id: String,
+    pub nodes: BTreeMap<String, NodeObservation>,
+    pub classification: Option<Classification>,
+}
+
+impl SharedCandidate {
+    pub fn new(fingerprint_type: &str, fingerprint_id: &str) -> Self {
+        Self {
+            fingerprint_type: fingerprint_type.to_string(),
+            fingerprint_id: fingerprint_id.to_string(),
+            nodes: BTreeMap::new(),
+            classification: None,
+        }
+    }
+
+    /// Observations summed over nodes
+    pub fn observation_count(&self) -> u64 {
+        self.nodes.values().map(|n| n.observation_count).sum()
+    }
+
+    /// Count-weighted mean of the nodes' stability scores
+    pub fn stability_score(&self) -> f64 {
+        let count = self.observation_count();
+        if count == 0 {
+            return 0.0;
+        }
+        self.nodes
+            .values()
+            .map(|n| n.stability_score * n.observation_count as f64)
+            .sum::<f64>()
+            / count as f64
+    }
+
+    pub fn first_seen(&self) -> Option<u64> {
+        self.nodes.values().map(|n| n.first_seen).min()
+    }
+
+    pub fn last_seen(&self) -> Option<u64> {
+        self.nodes.values().map(|n| n.last_seen).max()
+    }
+
+    /// `pending` until classified
+    pub fn status(&self) -> &str {
+        self.classification
+            .as_ref()
+            .map_or("pending", |c| c.status.as_str())
+    }
+
+    /// Fold `other` in (see the module's merge rules)
+    pub fn merge(&mut self, other: &SharedCandidate) {
+        for (node, theirs) in &other.nodes {
+            let newer = self.nodes.get(node).is_none_or(|ours| {
+                (theirs.observation_count, theirs.last_seen)
+                    > (ours.observation_count, ours.last_seen)
+            });
+            if newer {
+                self.nodes.insert(node.clone(), theirs.clone());
+            }
+        }
+        if let Some(theirs) = &other.classification {
+            let newer = self.classification.as_ref().is_none_or(|ours| {
+                (theirs.decided_at, &theirs.node) > (ours.decided_at, &ours.node)
+            });
+            if newer {
+                self.classification = Some(theirs.clone());
+            }
+        }
+    }
+}
+
+/// Candidate fingerprints shared through a [`KvStore`]
+pub struct FleetStore {
+    store: Arc<dyn KvStore>,
+    node_id: String,
+    prefix: String,
+}
+
+impl FleetStore {
+    /// Store for the gateway `node_id`
+    pub fn new(store: Arc<dyn KvStore>, node_id: &str) -> Self {
+        Self {
+            store,
+            node_id: node_id.to_string(),
+            prefix: DEFAULT_PREFIX.to_string(),
+        }
+    }
+
+    /// Namespace keys, e.g. one prefix per fleet sharing a Redis
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    fn key(&self, fingerprint_type: &str, fingerprint_id: &str) -> String {
+        format!("{}:{}:{}", self.prefix, fingerprint_type, fingerprint_id)
+    }
+
+    /// Publish this node's observations of a fingerprint; returns the merged candidate
+    pub fn record_observation(
+        &self,
+        observation: &UnknownFingerprintObservation,
+    ) -> Result<SharedCandidate, String> {
+        let mut update =
+            SharedCandidate::new(&observation.fingerprint_type, &observation.fingerprint_id);
+        update.nodes.insert(
+            self.node_id.clone(),
+            NodeObservation {
+                observation_count: observation.observation_count,
+                stability_score: observation.stability_score,
+                first_seen: observation.first_seen,
+                last_seen: observation.last_seen,
+            },
+        );
+        self.merge(update)
+    }
+
+    /// Record a review decision for the whole fleet
+    pub fn classify(
+        &self,
+        fingerprint_type: &str,
+        fingerprint_id: &str,
+        status: &str,
+        notes: Option<&str>,
+    ) -> Result<SharedCandidate, String> {
+        let mut update = SharedCandidate::new(fingerprint_type, fingerprint_id);
+        update.classification = Some(Classification {
+            status: status.to_string(),
+            notes: notes.map(str::to_string),
+            node: self.node_id.clone(),
+            decided_at: Utc::now(),
+        });
+        self.merge(update)
+    }
+
+    /// Merge `update` into the stored candidate, retrying on concurrent writes
+    pub fn merge(&self, update: SharedCandidate) -> Result<SharedCandidate, String> {
+        let key = self.key(&update.fingerprint_type, &update.fingerprint_id);
+        for _ in 0..MAX_RETRIES {
+            let (version, mut merged) = match self.store.get(&key)? {
+                Some(current) => (
+                    current.version,
+                    serde_json::from_str::<SharedCandidate>(&current.value)
+                        .map_err(|e| e.to_string())?,
+                ),
+                None => (0, update.clone()),
+            };
+            merged.merge(&update);
+            let value = serde_json::to_string(&merged).map_err(|e| e.to_string())?;
+            if self.store.compare_and_set(&key, version, &value)? {
+                return Ok(merged);
+            }
+            log::debug!("[FleetStore] Write conflict on {}, retrying", key);
+        }
+        Err(format!(
+            "{}: gave up after {} conflicting writes",
+            key, MAX_RETRIES
+        ))
+    }
+
+    pub fn candidate(
+        &self,
+        fingerprint_type: &str,
+        fingerprint_id: &str,
+    ) -> Result<Option<SharedCandidate>, String> {
+        self.store
+            .get(&self.key(fingerprint_type, fingerprint_id))?
+            .map(|v| serde_json::from_str(&v.value).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    /// All candidates, most recently seen first
+    pub fn candidates(&self) -> Result<Vec<SharedCandidate>, String> {
+        let mut candidates = Vec::new();
+        for key in self.store.keys(&format!("{}:", self.prefix))? {
+            match self.store.get(&key)? {
+                Some(v) => match serde_json::from_str::<SharedCandidate>(&v.value) {
+                    Ok(candidate) => candidates.push(candidate),
+                    Err(e) => log::warn!("[FleetStore] Skipping unreadable {}: {}", key, e),
+                },
+                // removed between listing and reading
+                None => continue,
+            }
+        }
+        candidates.sort_by_key(|c| std::cmp::Reverse(c.last_seen()));
+        Ok(candidates)
+    }
+
+    /// Unclassified candidates, most recently seen first
+    pub fn pending_candidates(&self, limit: Option<usize>) -> Result<Vec<SharedCandidate>, String> {
+        Ok(self
+            .candidates()?
+            .into_iter()
+            .filter(|c| c.classification.is_none())
+            .take(limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Fleet-wide counterpart of [`FingerprintDatabase::get_candidate_stats`]
+    ///
+    /// [`FingerprintDatabase::get_candidate_stats`]: crate::database::FingerprintDatabase::get_candidate_stats
+    pub fn candidate_stats(&self) -> Result<CandidateStats, String> {
+        let mut stats = CandidateStats {
+            pending: 0,
+            approved: 0,
+            rejected: 0,
+        };
+        for candidate in self.candidates()? {
+            match candidate.status() {
+                "pending" => stats.pending += 1,
+                "approved" => stats.approved += 1,
+                "rejected" => stats.rejected += 1,
+                _ => {}
+            }
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observation(count: u64, first_seen: u64) -> UnknownFingerprintObservation {
+        UnknownFingerprintObservation {
+            fingerprint_id: "t13d1516h2_8daaf6152771_02713d6af862".to_string(),
+            fingerprint_type: "tls".to_string(),
+            first_seen,
+            last_seen: first_seen + 60,
+            observation_count: count,
+            stability_score: 0.5,
+            features: serde_json::Value::Null,
+        }
+    }
+
+    /// Lets one other writer in before the first compare-and-set
+    struct Racing {
+        inner: MemoryKvStore,
+        raced: Mutex<bool>,
+    }
+
+    impl KvStore for Racing {
+        fn get(&self, key: &str) -> Result<Option<Versioned>, String> {
+            self.inner.get(key)
+        }
+
+        fn compare_and_set(&self, key: &str, version: u64, value: &str) -> Result<bool, String> {
+            let mut raced = self.raced.lock().unwrap();
+            if !*raced {
+                *raced = true;
+                let mut other = SharedCandidate::new("tls", "t13d1516h2_8daaf6152771_02713d6af862");
+                other.nodes.insert(
+                    "gw-c".to_string(),
+                    NodeObservation {
+                        observation_count: 7,
+                        stability_score: 0.5,
+                        first_seen: 0,
+                        last_seen: 0,
+                    },
+                );
+                let other = serde_json::to_string(&other).unwrap();
+                assert!(self.inner.compare_and_set(key, version, &other)?);
+            }
+            self.inner.compare_and_set(key, version, value)
+        }
+
+        fn keys(&self, prefix: &str) -> Result<Vec<String>, String> {
+            self.inner.keys(prefix)
+        }
+    }
+
+    #[test]
+    fn nodes_share_counts_and_classifications() {
+        let kv: Arc<dyn KvStore> = Arc::new(MemoryKvStore::new());
+        let a = FleetStore::new(kv.clone(), "gw-a");
+        let b = FleetStore::new(kv.clone(), "gw-b");
+
+        a.record_observation(&observation(4, 1_000)).unwrap();
+        b.record_observation(&observation(6, 900)).unwrap();
+        // a restarted node reporting a smaller count does not shrink the total
+        let merged = a.record_observation(&observation(1, 2_000)).unwrap();
+        assert_eq!(merged.observation_count(), 10);
+        assert_eq!(merged.first_seen(), Some(900));
+
+        a.classify("tls", &merged.fingerprint_id, "approved", None)
+            .unwrap();
+        let merged = b
+            .classify("tls", &merged.fingerprint_id, "rejected", Some("scanner"))
+            .unwrap();
+        assert_eq!(merged.status(), "rejected");
+
+        let stats = a.candidate_stats().unwrap();
+        assert_eq!((stats.pending, stats.approved, stats.rejected), (0, 0, 1));
+        assert!(a.pending_candidates(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn retries_after_a_conflicting_write() {
+        let kv = Arc::new(Racing {
+            inner: MemoryKvStore::new(),
+            raced: Mutex::new(false),
+        });
+        let fleet = FleetStore::new(kv, "gw-a");
+
+        let merged = fleet.record_observation(&observation(3, 1_000)).unwrap();
+        assert_eq!(merged.observation_count(), 10);
+        assert_eq!(merged.nodes.len(), 2);
+    }
+}
@@ -0,0 +1,370 @@
+//! Firewall backends
+//!
+//! A backend turns [`FirewallRule`]s into the commands of one packet filter.
+//! Blocked and rate-limited addresses live in address sets (nftables) or
+//! tables (pf), so installing or removing a rule never rewrites the ruleset.
+
+use crate::enforcement::{FirewallAction, FirewallRule};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::Write;
+use std::net::IpAddr;
+use std::process::{Command, Stdio};
+
+/// One firewall command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirewallCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    /// fed to the program's stdin (pf anchor rulesets)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdin: Option<String>,
+}
+
+impl FirewallCommand {
+    pub fn new(program: &str, args: &[&str]) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            stdin: None,
+        }
+    }
+
+    pub fn with_stdin(mut self, stdin: String) -> Self {
+        self.stdin = Some(stdin);
+        self
+    }
+}
+
+impl fmt::Display for FirewallCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        if let Some(stdin) = &self.stdin {
+            write!(f, " <<< {:?}", stdin)?;
+        }
+        Ok(())
+    }
+}
+
+/// Packet filter a [`FirewallEnforcer`](crate::enforcement::FirewallEnforcer) drives
+pub trait FirewallBackend: Send {
+    fn name(&self) -> &'static str;
+
+    /// Entries expire in the kernel without a remove command
+    fn native_expiry(&self) -> bool;
+
+    /// Create the table, chains and sets
+    fn setup(&self) -> Vec<FirewallCommand>;
+
+    /// Remove everything [`setup`](Self::setup) and installs created
+    fn teardown(&self) -> Vec<FirewallCommand>;
+
+    /// Commands that enforce `rule`
+    fn install(&mut self, rule: &FirewallRule) -> Vec<FirewallCommand>;
+
+    /// Commands that lift `rule` again
+    fn remove(&self, rule: &FirewallRule) -> Vec<FirewallCommand>;
+}
+
+/// Executes firewall commands
+pub trait CommandRunner: Send {
+    fn run(&mut self, command: &FirewallCommand) -> Result<(), String>;
+}
+
+/// Runs commands as child processes
+#[derive(Debug, Default)]
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&mut self, command: &FirewallCommand) -> Result<(), String> {
+        let mut child = Command::new(&command.program)
+            .args(&command.args)
+            .stdin(if command.stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("{}: {}", command.program, e))?;
+        if let (Some(input), Some(mut stdin)) = (&command.stdin, child.stdin.take()) {
+            stdin
+                .write_all(input.as_bytes())
+                .map_err(|e| format!("{}: {}", command.program, e))?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("{}: {}", command.program, e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    }
+}
+
+/// nftables (Linux)
+///
+/// Uses an `inet` table with its own input chain. Blocked addresses go into
+/// `block4`/`block6`; rate-limited addresses into `rl4_<pps>`/`rl6_<pps>`,
+/// one set pair per rate with a per-source meter. Set elements carry the
+/// rule's timeout, so the kernel expires them.
+#[derive(Debug, Clone)]
+pub struct NftablesBackend {
+    table: String,
+    /// rates whose sets and rules exist
+    rates: BTreeSet<u64>,
+}
+
+impl NftablesBackend {
+    pub fn new(table: &str) -> Self {
+        Self {
+            table: table.to_string(),
+            rates: BTreeSet::new(),
+        }
+    }
+
+    fn nft(args: &[&str]) -> FirewallCommand {
+        FirewallCommand::new("nft", args)
+    }
+
+    fn set_name(rule: &FirewallRule) -> String {
+        let family = if rule.ip.is_ipv4() { 4 } else { 6 };
+        match rule.action {
+            FirewallAction::Block => format!("block{}", family),
+            FirewallAction::RateLimit { packets_per_second } => {
+                format!("rl{}_{}", family, packets_per_second)
+            }
+        }
+    }
+
+    fn address_set(&self, name: &str, ip_type: &str) -> FirewallCommand {
+        Self::nft(&[
+            "add",
+            "set",
+            "inet",
+            &self.table,
+            name,
+            "{",
+            "type",
+            ip_type,
+            ";",
+            "flags",
+            "timeout",
+            ";",
+            "}",
+        ])
+    }
+}
+
+impl Default for NftablesBackend {
+    fn default() -> Self {
+        Self::new("fingerprint_defense")
+    }
+}
+
+impl FirewallBackend for NftablesBackend {
+    fn name(&self) -> &'static str {
+        "nftables"
+    }
+
+    fn native_expiry(&self) -> bool {
+        true
+    }
+
+    fn setup(&self) -> Vec<FirewallCommand> {
+        let table = self.table.as_str();
+        vec![
+            Self::nft(&["add", "table", "inet", table]),
+            Self::nft(&[
+                "add", "chain", "inet", table, "input", "{", "type", "filter", "hook", "input",
+                "priority", "-10", ";", "policy", "accept", ";", "}",
+            ]),
+            self.address_set("block4", "ipv4_addr"),
+            self.address_set("block6", "ipv6_addr"),
+            Self::nft(&[
+                "add", "rule", "inet", table, "input", "ip", "saddr", "@block4", "drop",
+            ]),
+            Self::nft(&[
+                "add", "rule", "inet", table, "input", "ip6", "saddr", "@block6", "drop",
+            ]),
+        ]
+    }
+
+    fn teardown(&self) -> Vec<FirewallCommand> {
+        vec![Self::nft(&["delete", "table", "inet", &self.table])]
+    }
+
+    fn install(&mut self, rule: &FirewallRule) -> Vec<FirewallCommand> {
+        let mut commands = Vec::new();
+        if let FirewallAction::RateLimit { packets_per_second } = rule.action {
+            if self.rates.insert(packets_per_second) {
+                let rate = format!("{}/second", packets_per_second);
+                for (family, match_kw, ip_type) in [(4, "ip", "ipv4_addr"), (6, "ip6", "ipv6_addr")]
+                {
+                    let set = format!("rl{}_{}", family, packets_per_second);
+                    commands.push(self.address_set(&set, ip_type));
+                    commands.push(Self::nft(&[
+                        "add",
+                        "rule",
+                        "inet",
+                        &self.table,
+                        "input",
+                        match_kw,
+                        "saddr",
+                        &format!("@{}", set),
+                        "meter",
+                        &format!("{}_meter", set),
+                        "{",
+                        match_kw,
+                        "saddr",
+                        "limit",
+                        "rate",
+                        "over",
+                        &rate,
+                        "}",
+                        "drop",
+                    ]));
+                }
+            }
+        }
+        let timeout = format!("{}s", rule.ttl_secs());
+        commands.push(Self::nft(&[
+            "add",
+            "element",
+            "inet",
+            &self.table,
+            &Self::set_name(rule),
+            "{",
+            &rule.ip.to_string(),
+            "timeout",
+            &timeout,
+            "}",
+        ]));
+        commands
+    }
+
+    fn remove(&self, rule: &FirewallRule) -> Vec<FirewallCommand> {
+        vec![Self::nft(&[
+            "delete",
+            "element",
+            "inet",
+            &self.table,
+            &Self::set_name(rule),
+            "{",
+            &rule.ip.to_string(),
+            "}",
+        ])]
+    }
+}
+
+/// pf (macOS, BSD)
+///
+/// Rules live in an anchor that the main ruleset must reference
+/// (`anchor "fingerprint_defense"` in pf.conf). Blocked addresses go into the
+/// `fpd_block` table; rate-limited ones into `fpd_rl_<pps>` tables whose pass
+/// rule caps the source's connection rate, as pf has no per-packet meter.
+/// Tables have no per-entry timeout, so the enforcer removes expired entries.
+#[derive(Debug, Clone)]
+pub struct PfBackend {
+    anchor: String,
+    rates: BTreeSet<u64>,
+}
+
+impl PfBackend {
+    pub fn new(anchor: &str) -> Self {
+        Self {
+            anchor: anchor.to_string(),
+            rates: BTreeSet::new(),
+        }
+    }
+
+    fn table_name(rule: &FirewallRule) -> String {
+        match rule.action {
+            FirewallAction::Block => "fpd_block".to_string(),
+            FirewallAction::RateLimit { packets_per_second } => {
+                format!("fpd_rl_{}", packets_per_second)
+            }
+        }
+    }
+
+    /// Anchor ruleset for the known rates
+    fn ruleset(&self) -> String {
+        let mut rules =
+            String::from("table <fpd_block> persist\nblock drop in quick from <fpd_block>\n");
+        for rate in &self.rates {
+            rules.push_str(&format!(
+                "table <fpd_rl_{rate}> persist\n\
+                 pass in quick from <fpd_rl_{rate}> keep state \
+                 (max-src-conn-rate {rate}/1)\n"
+            ));
+        }
+        rules
+    }
+
+    fn load_ruleset(&self) -> FirewallCommand {
+        FirewallCommand::new("pfctl", &["-a", &self.anchor, "-f", "-"]).with_stdin(self.ruleset())
+    }
+
+    fn table_command(&self, rule: &FirewallRule, op: &str, ip: IpAddr) -> FirewallCommand {
+        FirewallCommand::new(
+            "pfctl",
+            &[
+                "-a",
+                &self.anchor,
+                "-t",
+                &Self::table_name(rule),
+                "-T",
+                op,
+                &ip.to_string(),
+            ],
+        )
+    }
+}
+
+impl Default for PfBackend {
+    fn default() -> Self {
+        Self::new("fingerprint_defense")
+    }
+}
+
+impl FirewallBackend for PfBackend {
+    fn name(&self) -> &'static str {
+        "pf"
+    }
+
+    fn native_expiry(&self) -> bool {
+        false
+    }
+
+    fn setup(&self) -> Vec<FirewallCommand> {
+        vec![self.load_ruleset()]
+    }
+
+    fn teardown(&self) -> Vec<FirewallCommand> {
+        vec![FirewallCommand::new(
+            "pfctl",
+            &["-a", &self.anchor, "-F", "all"],
+        )]
+    }
+
+    fn install(&mut self, rule: &FirewallRule) -> Vec<FirewallCommand> {
+        let mut commands = Vec::new();
+        if let FirewallAction::RateLimit { packets_per_second } = rule.action {
+            if self.rates.insert(packets_per_second) {
+                commands.push(self.load_ruleset());
+            }
+        }
+        commands.push(self.table_command(rule, "add", rule.ip));
+        commands
+    }
+
+    fn remove(&self, rule: &FirewallRule) -> Vec<FirewallCommand> {
+        vec![self.table_command(rule, "delete", rule.ip)]
+    }
+}
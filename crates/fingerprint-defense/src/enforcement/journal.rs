@@ -0,0 +1,101 @@
+//! Rollback journal
+//!
+//! Append-only JSON lines written *before* each firewall change, carrying
+//! the commands that undo it. After a crash the journal tells which rules
+//! may still be installed, and [`FirewallEnforcer::rollback`] can lift them.
+//!
+//! [`FirewallEnforcer::rollback`]: crate::enforcement::FirewallEnforcer::rollback
+
+use crate::enforcement::{FirewallCommand, FirewallRule};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// One journaled change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JournalEntry {
+    /// table / anchor created
+    Setup {
+        at: DateTime<Utc>,
+        backend: String,
+        undo: Vec<FirewallCommand>,
+    },
+    /// rule about to be installed
+    Install {
+        at: DateTime<Utc>,
+        rule: FirewallRule,
+        undo: Vec<FirewallCommand>,
+    },
+    /// rule lifted or expired
+    Remove { at: DateTime<Utc>, id: u64 },
+    /// table / anchor removed
+    Teardown { at: DateTime<Utc> },
+}
+
+/// State rebuilt from a journal
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JournalState {
+    /// setup undo commands, if set up and not torn down
+    pub setup: Option<Vec<FirewallCommand>>,
+    /// installed and not removed, in install order
+    pub rules: Vec<(FirewallRule, Vec<FirewallCommand>)>,
+}
+
+/// Append-only journal file
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+}
+
+impl Journal {
+    /// Open for appending, creating the file if needed
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self { path, file })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append and flush one entry
+    pub fn append(&mut self, entry: &JournalEntry) -> io::Result<()> {
+        let mut line = serde_json::to_string(entry).map_err(io::Error::other)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.sync_data()
+    }
+
+    /// Replay the journal into the set of rules still in force
+    ///
+    /// A torn last line (crash mid-write) is ignored.
+    pub fn replay(&self) -> io::Result<JournalState> {
+        let mut state = JournalState::default();
+        let reader = BufReader::new(File::open(&self.path)?);
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Ok(entry) = serde_json::from_str::<JournalEntry>(&line) else {
+                log::warn!("[Journal] Skipping unreadable entry: {}", line);
+                continue;
+            };
+            match entry {
+                JournalEntry::Setup { undo, .. } => state.setup = Some(undo),
+                JournalEntry::Install { rule, undo, .. } => {
+                    state.rules.retain(|(r, _)| r.id != rule.id);
+                    state.rules.push((rule, undo));
+                }
+                JournalEntry::Remove { id, .. } => state.rules.retain(|(r, _)| r.id != id),
+                JournalEntry::Teardown { .. } => state = JournalState::default(),
+            }
+        }
+        Ok(state)
+    }
+}
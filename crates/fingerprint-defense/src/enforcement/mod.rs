@@ -0,0 +1,585 @@
+//! Firewall enforcement
+//!
+//! Turns [`SystemProtectionDecision`]s into packet filter rules: `Deny`
+//! blocks the source address, `RateLimit` caps it. Every rule has a TTL;
+//! nftables expires set elements in the kernel, pf entries are removed by
+//! [`FirewallEnforcer::expire`].
+//!
+//! Changes are written to a rollback [`Journal`] before they are applied, so
+//! a restarted enforcer knows which rules may still be installed and
+//! [`FirewallEnforcer::rollback`] can lift all of them. In dry-run mode the
+//! commands are only logged and kept in [`FirewallEnforcer::history`].
+//!
+//! [`EnforcingProtector`] wraps any [`SystemProtector`] and enforces its
+//! decisions from `update_state`.
+
+pub mod backend;
+pub mod journal;
+
+pub use backend::{
+    CommandRunner, FirewallBackend, FirewallCommand, NftablesBackend, PfBackend, SystemRunner,
+};
+pub use journal::{Journal, JournalEntry, JournalState};
+
+use chrono::{DateTime, Utc};
+use fingerprint_core::system::{
+    NetworkFlow, SystemProtectionDecision, SystemProtectionResult, SystemProtectionStats,
+    SystemProtector,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// commands kept for [`FirewallEnforcer::history`]
+const HISTORY_LIMIT: usize = 1000;
+
+/// What a rule does to the source address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FirewallAction {
+    Block,
+    RateLimit { packets_per_second: u64 },
+}
+
+impl FirewallAction {
+    /// Enforceable part of a decision; `Allow`, `Log` and `RequiresAnalysis`
+    /// map to nothing
+    pub fn from_decision(decision: &SystemProtectionDecision) -> Option<Self> {
+        match decision {
+            SystemProtectionDecision::Deny { .. } => Some(FirewallAction::Block),
+            SystemProtectionDecision::RateLimit {
+                max_packets_per_second,
+                ..
+            } => Some(FirewallAction::RateLimit {
+                packets_per_second: (*max_packets_per_second).max(1),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Installed rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FirewallRule {
+    pub id: u64,
+    pub ip: IpAddr,
+    pub action: FirewallAction,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl FirewallRule {
+    /// Lifetime in whole seconds (at least 1)
+    pub fn ttl_secs(&self) -> u64 {
+        (self.expires_at - self.created_at).num_seconds().max(1) as u64
+    }
+}
+
+/// Enforcer configuration
+#[derive(Debug, Clone)]
+pub struct EnforcementConfig {
+    /// lifetime of `Deny` blocks
+    pub block_ttl: Duration,
+    /// upper bound for any rule's lifetime
+    pub max_ttl: Duration,
+    /// log commands instead of running them
+    pub dry_run: bool,
+    /// rollback journal; not written in dry-run mode
+    pub journal_path: Option<PathBuf>,
+    /// addresses never enforced against (loopback and unspecified always are)
+    pub exempt: Vec<IpAddr>,
+}
+
+impl Default for EnforcementConfig {
+    fn default() -> Self {
+        Self {
+            block_ttl: Duration::from_secs(600),
+            max_ttl: Duration::from_secs(24 * 3600),
+            dry_run: false,
+            journal_path: None,
+            exempt: Vec::new(),
+        }
+    }
+}
+
+impl EnforcementConfig {
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_journal<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.journal_path = Some(path.into());
+        self
+    }
+
+    pub fn with_block_ttl(mut self, ttl: Duration) -> Self {
+        self.block_ttl = ttl;
+        self
+    }
+}
+
+/// Enforcer counters
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EnforcementStats {
+    pub installed: u64,
+    pub expired: u64,
+    pub removed: u64,
+    pub exempted: u64,
+    pub failed: u64,
+}
+
+/// Enforcement error
+#[derive(Debug, thiserror::Error)]
+pub enum EnforcementError {
+    #[error("journal error: {0}")]
+    Journal(#[from] io::Error),
+
+    #[error("`{command}` failed: {message}")]
+    Command { command: String, message: String },
+
+    #[error("no firewall backend for this platform")]
+    Unsupported,
+}
+
+/// Applies protection decisions to a packet filter
+pub struct FirewallEnforcer {
+    backend: Box<dyn FirewallBackend>,
+    runner: Box<dyn CommandRunner>,
+    config: EnforcementConfig,
+    journal: Option<Journal>,
+    /// rules in force with their undo commands, in install order
+    active: Vec<(FirewallRule, Vec<FirewallCommand>)>,
+    set_up: bool,
+    next_id: u64,
+    history: VecDeque<FirewallCommand>,
+    stats: EnforcementStats,
+}
+
+impl FirewallEnforcer {
+    /// Enforcer for `backend`; rules left in the journal are taken over
+    pub fn new(
+        mut backend: Box<dyn FirewallBackend>,
+        config: EnforcementConfig,
+    ) -> Result<Self, EnforcementError> {
+        let journal = match (&config.journal_path, config.dry_run) {
+            (Some(path), false) => Some(Journal::open(path)?),
+            _ => None,
+        };
+        let state = match &journal {
+            Some(journal) => journal.replay()?,
+            None => JournalState::default(),
+        };
+        // let the backend know about sets the recovered rules created
+        for (rule, _) in &state.rules {
+            backend.install(rule);
+        }
+        if !state.rules.is_empty() {
+            log::info!(
+                "[FirewallEnforcer] Recovered {} rules from the journal",
+                state.rules.len()
+            );
+        }
+
+        Ok(Self {
+            backend,
+            runner: Box::new(SystemRunner),
+            next_id: state.rules.iter().map(|(r, _)| r.id + 1).max().unwrap_or(1),
+            set_up: state.setup.is_some(),
+            active: state.rules,
+            config,
+            journal,
+            history: VecDeque::new(),
+            stats: EnforcementStats::default(),
+        })
+    }
+
+    /// nftables on Linux, pf on macOS and the BSDs
+    pub fn for_host(config: EnforcementConfig) -> Result<Self, EnforcementError> {
+        if cfg!(target_os = "linux") {
+            Self::new(Box::new(NftablesBackend::default()), config)
+        } else if cfg!(any(
+            target_os = "macos",
+            target_os = "freebsd",
+            target_os = "openbsd"
+        )) {
+            Self::new(Box::new(PfBackend::default()), config)
+        } else {
+            Err(EnforcementError::Unsupported)
+        }
+    }
+
+    /// Run commands through `runner` instead of child processes
+    pub fn with_runner(mut self, runner: Box<dyn CommandRunner>) -> Self {
+        self.runner = runner;
+        self
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.config.dry_run
+    }
+
+    pub fn stats(&self) -> EnforcementStats {
+        self.stats.clone()
+    }
+
+    /// Rules in force, oldest first
+    pub fn active(&self) -> impl Iterator<Item = &FirewallRule> {
+        self.active.iter().map(|(rule, _)| rule)
+    }
+
+    /// Rule in force for `ip`
+    pub fn rule_for(&self, ip: IpAddr) -> Option<&FirewallRule> {
+        self.active().find(|rule| rule.ip == ip)
+    }
+
+    /// Most recent commands, run or (in dry-run mode) only planned
+    pub fn history(&self) -> impl Iterator<Item = &FirewallCommand> {
+        self.history.iter()
+    }
+
+    /// Enforce `decision` against `ip`
+    ///
+    /// Returns the rule in force afterwards, or `None` when the decision is
+    /// not enforceable or the address is exempt. A later decision for the
+    /// same address replaces the earlier rule unless it is the same action
+    /// and would expire sooner.
+    pub fn enforce(
+        &mut self,
+        ip: IpAddr,
+        decision: &SystemProtectionDecision,
+        now: DateTime<Utc>,
+    ) -> Result<Option<FirewallRule>, EnforcementError> {
+        let Some(action) = FirewallAction::from_decision(decision) else {
+            return Ok(None);
+        };
+        if ip.is_loopback() || ip.is_unspecified() || self.config.exempt.contains(&ip) {
+            self.stats.exempted += 1;
+            return Ok(None);
+        }
+
+        let ttl = match decision {
+            SystemProtectionDecision::RateLimit { duration, .. } => *duration,
+            _ => self.config.block_ttl,
+        }
+        .clamp(Duration::from_secs(1), self.config.max_ttl);
+        let expires_at = now + chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX);
+
+        if let Some(existing) = self.rule_for(ip) {
+            if existing.action == action && existing.expires_at >= expires_at {
+                return Ok(Some(existing.clone()));
+            }
+            let id = existing.id;
+            self.lift(id, now)?;
+        }
+
+        self.ensure_setup(now)?;
+
+        let reason = match decision {
+            SystemProtectionDecision::Deny { reason } => reason.clone(),
+            other => other.description(),
+        };
+        let rule = FirewallRule {
+            id: self.next_id,
+            ip,
+            action,
+            reason,
+            created_at: now,
+            expires_at,
+        };
+        self.next_id += 1;
+
+        let undo = self.backend.remove(&rule);
+        self.journal(JournalEntry::Install {
+            at: now,
+            rule: rule.clone(),
+            undo: undo.clone(),
+        })?;
+        let commands = self.backend.install(&rule);
+        if let Err(e) = self.execute(&commands) {
+            self.journal(JournalEntry::Remove {
+                at: now,
+                id: rule.id,
+            })?;
+            return Err(e);
+        }
+
+        self.stats.installed += 1;
+        self.active.push((rule.clone(), undo));
+        Ok(Some(rule))
+    }
+
+    /// Lift the rule for `ip`, if any
+    pub fn remove(&mut self, ip: IpAddr, now: DateTime<Utc>) -> Result<bool, EnforcementError> {
+        let Some(id) = self.rule_for(ip).map(|rule| rule.id) else {
+            return Ok(false);
+        };
+        self.lift(id, now)?;
+        self.stats.removed += 1;
+        Ok(true)
+    }
+
+    /// Drop rules whose TTL has passed; returns how many expired
+    pub fn expire(&mut self, now: DateTime<Utc>) -> Result<usize, EnforcementError> {
+        let expired: Vec<u64> = self
+            .active()
+            .filter(|rule| rule.expires_at <= now)
+            .map(|rule| rule.id)
+            .collect();
+        let mut first_error = None;
+        for id in &expired {
+            let index = self.index_of(*id);
+            let (rule, undo) = self.active.remove(index);
+            // the kernel already dropped nftables elements
+            if !self.backend.native_expiry() {
+                if let Err(e) = self.execute(&undo) {
+                    log::warn!("[FirewallEnforcer] Failed to expire {}: {}", rule.ip, e);
+                    first_error.get_or_insert(e);
+                }
+            }
+            self.journal(JournalEntry::Remove { at: now, id: *id })?;
+            self.stats.expired += 1;
+        }
+        first_error.map_or(Ok(expired.len()), Err)
+    }
+
+    /// Lift every rule and remove the table / anchor
+    ///
+    /// Keeps going past failing commands and reports the first failure.
+    pub fn rollback(&mut self, now: DateTime<Utc>) -> Result<usize, EnforcementError> {
+        let mut first_error = None;
+        let count = self.active.len();
+        while let Some((rule, undo)) = self.active.pop() {
+            if let Err(e) = self.execute(&undo) {
+                log::warn!("[FirewallEnforcer] Failed to roll back {}: {}", rule.ip, e);
+                first_error.get_or_insert(e);
+            }
+            self.journal(JournalEntry::Remove {
+                at: now,
+                id: rule.id,
+            })?;
+        }
+        if self.set_up {
+            let teardown = self.backend.teardown();
+            if let Err(e) = self.execute(&teardown) {
+                first_error.get_or_insert(e);
+            }
+            self.journal(JournalEntry::Teardown { at: now })?;
+            self.set_up = false;
+        }
+        first_error.map_or(Ok(count), Err)
+    }
+
+    fn index_of(&self, id: u64) -> usize {
+        self.active
+            .iter()
+            .position(|(rule, _)| rule.id == id)
+            .expect("active rule")
+    }
+
+    /// Remove an active rule by id
+    fn lift(&mut self, id: u64, now: DateTime<Utc>) -> Result<(), EnforcementError> {
+        let index = self.index_of(id);
+        let undo = self.active[index].1.clone();
+        self.execute(&undo)?;
+        self.active.remove(index);
+        self.journal(JournalEntry::Remove { at: now, id })
+    }
+
+    fn ensure_setup(&mut self, now: DateTime<Utc>) -> Result<(), EnforcementError> {
+        if self.set_up {
+            return Ok(());
+        }
+        self.journal(JournalEntry::Setup {
+            at: now,
+            backend: self.backend.name().to_string(),
+            undo: self.backend.teardown(),
+        })?;
+        let setup = self.backend.setup();
+        self.execute(&setup)?;
+        self.set_up = true;
+        Ok(())
+    }
+
+    fn journal(&mut self, entry: JournalEntry) -> Result<(), EnforcementError> {
+        if let Some(journal) = &mut self.journal {
+            journal.append(&entry)?;
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, commands: &[FirewallCommand]) -> Result<(), EnforcementError> {
+        for command in commands {
+            if self.history.len() == HISTORY_LIMIT {
+                self.history.pop_front();
+            }
+            self.history.push_back(command.clone());
+            if self.config.dry_run {
+                log::info!("[FirewallEnforcer] dry-run: {}", command);
+                continue;
+            }
+            self.runner.run(command).map_err(|message| {
+                self.stats.failed += 1;
+                EnforcementError::Command {
+                    command: command.to_string(),
+                    message,
+                }
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// [`SystemProtector`] whose decisions are enforced in the firewall
+pub struct EnforcingProtector<P> {
+    inner: P,
+    enforcer: FirewallEnforcer,
+}
+
+impl<P: SystemProtector> EnforcingProtector<P> {
+    pub fn new(inner: P, enforcer: FirewallEnforcer) -> Self {
+        Self { inner, enforcer }
+    }
+
+    pub fn enforcer(&self) -> &FirewallEnforcer {
+        &self.enforcer
+    }
+
+    pub fn enforcer_mut(&mut self) -> &mut FirewallEnforcer {
+        &mut self.enforcer
+    }
+}
+
+impl<P: SystemProtector> SystemProtector for EnforcingProtector<P> {
+    fn protect(&self, flow: &NetworkFlow) -> SystemProtectionResult {
+        self.inner.protect(flow)
+    }
+
+    fn update_state(&mut self, flow: &NetworkFlow, result: &SystemProtectionResult) {
+        self.inner.update_state(flow, result);
+        let now = Utc::now();
+        if let Err(e) = self.enforcer.expire(now) {
+            log::warn!("[EnforcingProtector] {}", e);
+        }
+        if let Err(e) = self
+            .enforcer
+            .enforce(flow.context.source_ip, &result.decision, now)
+        {
+            log::warn!("[EnforcingProtector] {}", e);
+        }
+    }
+
+    fn get_stats(&self) -> SystemProtectionStats {
+        self.inner.get_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn deny() -> SystemProtectionDecision {
+        SystemProtectionDecision::Deny {
+            reason: "bot".to_string(),
+        }
+    }
+
+    #[test]
+    fn dry_run_plans_nftables_commands() {
+        let config = EnforcementConfig::default().with_dry_run(true);
+        let mut enforcer =
+            FirewallEnforcer::new(Box::new(NftablesBackend::default()), config).unwrap();
+        let now = Utc::now();
+
+        let blocked: IpAddr = "203.0.113.5".parse().unwrap();
+        let rule = enforcer.enforce(blocked, &deny(), now).unwrap().unwrap();
+        assert_eq!(rule.ttl_secs(), 600);
+
+        let limited: IpAddr = "2001:db8::1".parse().unwrap();
+        let decision = SystemProtectionDecision::RateLimit {
+            max_packets_per_second: 100,
+            duration: Duration::from_secs(60),
+        };
+        enforcer.enforce(limited, &decision, now).unwrap();
+        assert!(enforcer
+            .enforce("127.0.0.1".parse().unwrap(), &deny(), now)
+            .unwrap()
+            .is_none());
+
+        let history: Vec<String> = enforcer.history().map(|c| c.to_string()).collect();
+        assert_eq!(history[0], "nft add table inet fingerprint_defense");
+        assert!(history.contains(
+            &"nft add element inet fingerprint_defense block4 { 203.0.113.5 timeout 600s }"
+                .to_string()
+        ));
+        assert!(history.iter().any(|c| c.contains("@rl6_100 meter")));
+        assert!(history.contains(
+            &"nft add element inet fingerprint_defense rl6_100 { 2001:db8::1 timeout 60s }"
+                .to_string()
+        ));
+
+        // the rate limit lapses first; nftables needs no delete for it
+        let planned = enforcer.history().count();
+        let later = now + chrono::Duration::seconds(120);
+        assert_eq!(enforcer.expire(later).unwrap(), 1);
+        assert_eq!(enforcer.history().count(), planned);
+        assert_eq!(enforcer.rule_for(blocked).unwrap().id, rule.id);
+        assert_eq!(enforcer.stats().exempted, 1);
+    }
+
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl CommandRunner for Recorder {
+        fn run(&mut self, command: &FirewallCommand) -> Result<(), String> {
+            self.0.lock().unwrap().push(command.to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn journal_survives_restart_and_rolls_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = EnforcementConfig::default().with_journal(dir.path().join("fw.journal"));
+        let now = Utc::now();
+        let ip: IpAddr = "198.51.100.7".parse().unwrap();
+
+        let recorder = Recorder::default();
+        let mut enforcer = FirewallEnforcer::new(Box::new(PfBackend::default()), config.clone())
+            .unwrap()
+            .with_runner(Box::new(recorder.clone()));
+        enforcer.enforce(ip, &deny(), now).unwrap();
+        drop(enforcer);
+        assert_eq!(
+            recorder.0.lock().unwrap().last().unwrap(),
+            "pfctl -a fingerprint_defense -t fpd_block -T add 198.51.100.7"
+        );
+
+        // a new process takes over the rule and can roll it back
+        let recorder = Recorder::default();
+        let mut enforcer = FirewallEnforcer::new(Box::new(PfBackend::default()), config.clone())
+            .unwrap()
+            .with_runner(Box::new(recorder.clone()));
+        assert!(enforcer.rule_for(ip).is_some());
+        assert_eq!(enforcer.rollback(now).unwrap(), 1);
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            [
+                "pfctl -a fingerprint_defense -t fpd_block -T delete 198.51.100.7",
+                "pfctl -a fingerprint_defense -F all"
+            ]
+        );
+
+        let journal = Journal::open(config.journal_path.unwrap()).unwrap();
+        assert_eq!(journal.replay().unwrap(), JournalState::default());
+    }
+}
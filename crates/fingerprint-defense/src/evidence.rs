@@ -0,0 +1,701 @@
+//! Packet evidence for flagged flows
+//!
+//! Keeps raw packets of flows whose risk score crossed a threshold in rolling
+//! PCAPNG files, so a verdict can be backed by the traffic that caused it.
+//!
+//! Every packet seen by the capture engine goes through
+//! [`EvidenceWriter::record`]. Packets of unflagged flows sit in a small
+//! per-flow lookback buffer; once [`EvidenceWriter::flag`] reports a risk at
+//! or above the threshold, the buffer (usually the handshake) is written out
+//! and later packets of the flow are written as they arrive.
+//!
+//! Files are named `evidence-<utc time>-<seq>.pcapng` and rotate by size and
+//! age. Next to each file, `<file>.idx` holds one JSON line per packet
+//! (`flow`, `offset`, `len`, `ts`, `risk`) mapping the flow ID to the byte
+//! offset of its Enhanced Packet Block; [`EvidenceIndex`] reads them back.
+
+use crate::capture::CaptureObserver;
+use crate::passive::Packet;
+use chrono::{DateTime, Utc};
+use fingerprint_core::clock::{system_clock, SharedClock};
+use fingerprint_core::system::SystemContext;
+use pcap_file::pcapng::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+use pcap_file::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use pcap_file::pcapng::PcapNgWriter;
+use pcap_file::DataLink;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const FILE_PREFIX: &str = "evidence-";
+const FILE_EXTENSION: &str = "pcapng";
+const INDEX_EXTENSION: &str = "idx";
+
+/// Direction-independent flow identity
+///
+/// Both directions of a connection map to the same key; the lower endpoint
+/// comes first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FlowKey {
+    low: (IpAddr, u16),
+    high: (IpAddr, u16),
+    protocol: u8,
+}
+
+impl FlowKey {
+    /// Key for a connection between two endpoints
+    pub fn new(a: (IpAddr, u16), b: (IpAddr, u16), protocol: u8) -> Self {
+        let (low, high) = if a <= b { (a, b) } else { (b, a) };
+        Self {
+            low,
+            high,
+            protocol,
+        }
+    }
+
+    /// Key of a captured packet
+    pub fn from_packet(packet: &Packet) -> Self {
+        Self::new(
+            (packet.src_ip, packet.src_port.unwrap_or(0)),
+            (packet.dst_ip, packet.dst_port.unwrap_or(0)),
+            packet.protocol,
+        )
+    }
+
+    /// Key of a flow seen by the middleware
+    pub fn from_context(context: &SystemContext) -> Self {
+        Self::new(
+            (context.source_ip, context.source_port.unwrap_or(0)),
+            (context.target_ip, context.target_port.unwrap_or(0)),
+            context.protocol.to_ip_protocol(),
+        )
+    }
+}
+
+impl fmt::Display for FlowKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let endpoint = |(ip, port): (IpAddr, u16)| match ip {
+            IpAddr::V4(ip) => format!("{}:{}", ip, port),
+            IpAddr::V6(ip) => format!("[{}]:{}", ip, port),
+        };
+        write!(
+            f,
+            "{}<->{}/{}",
+            endpoint(self.low),
+            endpoint(self.high),
+            self.protocol
+        )
+    }
+}
+
+/// Evidence writer settings
+#[derive(Debug, Clone)]
+pub struct EvidenceConfig {
+    /// Directory the PCAPNG files and indexes are written to
+    pub dir: PathBuf,
+    /// Minimum risk score for a flow to be recorded
+    pub risk_threshold: u8,
+    /// Rotate once the current file reaches this size
+    pub max_file_bytes: u64,
+    /// Rotate once the current file is this old
+    pub max_file_age: Duration,
+    /// Files kept before the oldest are deleted (0 keeps all)
+    pub max_files: usize,
+    /// Packets buffered per unflagged flow
+    pub lookback_packets: usize,
+    /// Flows tracked at once (buffered and flagged)
+    pub max_tracked_flows: usize,
+    /// Forget flows idle this long
+    pub flow_idle_timeout: Duration,
+}
+
+impl EvidenceConfig {
+    /// Defaults writing into `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            risk_threshold: 80,
+            max_file_bytes: 64 * 1024 * 1024,
+            max_file_age: Duration::from_secs(3600),
+            max_files: 24,
+            lookback_packets: 32,
+            max_tracked_flows: 10_000,
+            flow_idle_timeout: Duration::from_secs(300),
+        }
+    }
+
+    /// Set the risk threshold
+    pub fn with_risk_threshold(mut self, threshold: u8) -> Self {
+        self.risk_threshold = threshold;
+        self
+    }
+
+    /// Set the size and age rotation limits
+    pub fn with_rotation(mut self, max_file_bytes: u64, max_file_age: Duration) -> Self {
+        self.max_file_bytes = max_file_bytes;
+        self.max_file_age = max_file_age;
+        self
+    }
+
+    /// Set how many files are kept
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+}
+
+/// Evidence writer counters
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EvidenceStats {
+    /// Packets written to PCAPNG
+    pub packets_written: u64,
+    /// Bytes written to PCAPNG, headers included
+    pub bytes_written: u64,
+    /// Flows flagged at or above the threshold
+    pub flows_flagged: u64,
+    /// Files started
+    pub files_opened: u64,
+    /// Files deleted by retention
+    pub files_pruned: u64,
+    /// Packets not buffered because too many flows were tracked
+    pub lookback_skipped: u64,
+}
+
+/// One packet's place in the evidence files
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvidenceLocation {
+    /// PCAPNG file
+    pub file: PathBuf,
+    /// Byte offset of the Enhanced Packet Block
+    pub offset: u64,
+    /// Block length in bytes
+    pub len: u32,
+    /// Capture time
+    pub timestamp: DateTime<Utc>,
+    /// Flow risk score when written
+    pub risk: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexLine {
+    flow: String,
+    offset: u64,
+    len: u32,
+    ts: DateTime<Utc>,
+    risk: u8,
+}
+
+/// Flow ID → packet locations, read from the `.idx` files of a directory
+#[derive(Debug, Default)]
+pub struct EvidenceIndex {
+    flows: HashMap<String, Vec<EvidenceLocation>>,
+}
+
+impl EvidenceIndex {
+    /// Read every index in `dir`, oldest file first
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let mut index = Self::default();
+        for file in evidence_files(dir)? {
+            let idx_path = index_path(&file);
+            let Ok(idx) = File::open(&idx_path) else {
+                continue;
+            };
+            for line in BufReader::new(idx).lines() {
+                let line = line.map_err(|e| format!("read {}: {}", idx_path.display(), e))?;
+                // a torn last line after a crash is skipped, not fatal
+                let Ok(entry) = serde_json::from_str::<IndexLine>(&line) else {
+                    continue;
+                };
+                index
+                    .flows
+                    .entry(entry.flow)
+                    .or_default()
+                    .push(EvidenceLocation {
+                        file: file.clone(),
+                        offset: entry.offset,
+                        len: entry.len,
+                        timestamp: entry.ts,
+                        risk: entry.risk,
+                    });
+            }
+        }
+        Ok(index)
+    }
+
+    /// Locations of a flow's packets, in write order
+    pub fn lookup(&self, flow: &str) -> &[EvidenceLocation] {
+        self.flows.get(flow).map_or(&[], Vec::as_slice)
+    }
+
+    /// Flow IDs with evidence
+    pub fn flows(&self) -> impl Iterator<Item = &str> {
+        self.flows.keys().map(String::as_str)
+    }
+}
+
+/// `Write` adapter tracking the current offset
+struct CountingWriter<W> {
+    inner: W,
+    written: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+struct OpenFile {
+    writer: PcapNgWriter<CountingWriter<BufWriter<File>>>,
+    index: BufWriter<File>,
+    path: PathBuf,
+    opened: SystemTime,
+}
+
+struct BufferedPacket {
+    data: Vec<u8>,
+    timestamp: SystemTime,
+}
+
+#[derive(Default)]
+struct TrackedFlow {
+    risk: Option<u8>,
+    lookback: VecDeque<BufferedPacket>,
+    last_seen: Option<SystemTime>,
+}
+
+#[derive(Default)]
+struct State {
+    file: Option<OpenFile>,
+    sequence: u64,
+    flows: HashMap<FlowKey, TrackedFlow>,
+    stats: EvidenceStats,
+}
+
+/// Rolling PCAPNG writer for flagged flows
+pub struct EvidenceWriter {
+    config: EvidenceConfig,
+    clock: SharedClock,
+    state: Mutex<State>,
+}
+
+impl EvidenceWriter {
+    /// Writer over `config.dir`, created if missing
+    pub fn new(config: EvidenceConfig) -> Result<Self, String> {
+        fs::create_dir_all(&config.dir)
+            .map_err(|e| format!("create {}: {}", config.dir.display(), e))?;
+        Ok(Self {
+            config,
+            clock: system_clock(),
+            state: Mutex::new(State::default()),
+        })
+    }
+
+    /// Time source for capture timestamps, file names and age rotation
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Settings in use
+    pub fn config(&self) -> &EvidenceConfig {
+        &self.config
+    }
+
+    /// Counters so far
+    pub fn stats(&self) -> EvidenceStats {
+        self.lock().stats.clone()
+    }
+
+    /// Capture observer feeding every packet to [`EvidenceWriter::record`]
+    pub fn observer(self: &Arc<Self>) -> CaptureObserver {
+        let writer = self.clone();
+        Arc::new(move |packet, _| {
+            if let Err(e) = writer.record(packet, writer.clock.now()) {
+                log::warn!("evidence write failed: {}", e);
+            }
+        })
+    }
+
+    /// Write `packet` if its flow is flagged, otherwise buffer it
+    ///
+    /// Returns whether the packet was written.
+    pub fn record(&self, packet: &Packet, timestamp: SystemTime) -> Result<bool, String> {
+        let key = FlowKey::from_packet(packet);
+        let mut state = self.lock();
+        if !state.flows.contains_key(&key) && state.flows.len() >= self.config.max_tracked_flows {
+            self.prune_idle(&mut state, timestamp);
+            if state.flows.len() >= self.config.max_tracked_flows {
+                state.stats.lookback_skipped += 1;
+                return Ok(false);
+            }
+        }
+        let flow = state.flows.entry(key).or_default();
+        flow.last_seen = Some(timestamp);
+        match flow.risk {
+            Some(risk) => {
+                self.write_packet(&mut state, &key, risk, &packet.data, timestamp)?;
+                Ok(true)
+            }
+            None => {
+                if self.config.lookback_packets > 0 {
+                    if flow.lookback.len() == self.config.lookback_packets {
+                        flow.lookback.pop_front();
+                    }
+                    flow.lookback.push_back(BufferedPacket {
+                        data: packet.data.clone(),
+                        timestamp,
+                    });
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    /// Report a flow's risk score
+    ///
+    /// At or above the threshold the flow is recorded from now on and its
+    /// lookback buffer is written; returns the number of packets written.
+    pub fn flag(&self, key: FlowKey, risk: u8) -> Result<usize, String> {
+        if risk < self.config.risk_threshold {
+            return Ok(0);
+        }
+        let mut state = self.lock();
+        let flow = state.flows.entry(key).or_default();
+        let newly_flagged = flow.risk.is_none();
+        flow.risk = Some(flow.risk.map_or(risk, |r| r.max(risk)));
+        flow.last_seen.get_or_insert_with(|| self.clock.now());
+        let risk = flow.risk.unwrap_or(risk);
+        let lookback = std::mem::take(&mut flow.lookback);
+        if newly_flagged {
+            state.stats.flows_flagged += 1;
+        }
+        let written = lookback.len();
+        for packet in lookback {
+            self.write_packet(&mut state, &key, risk, &packet.data, packet.timestamp)?;
+        }
+        Ok(written)
+    }
+
+    /// Stop recording a flow
+    pub fn unflag(&self, key: &FlowKey) {
+        self.lock().flows.remove(key);
+    }
+
+    /// Flush the current file and index to disk
+    pub fn flush(&self) -> Result<(), String> {
+        let mut state = self.lock();
+        match state.file.as_mut() {
+            Some(file) => flush_file(file),
+            None => Ok(()),
+        }
+    }
+
+    /// Close the current file; the next written packet starts a new one
+    pub fn rotate(&self) -> Result<(), String> {
+        let mut state = self.lock();
+        self.close_file(&mut state)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write_packet(
+        &self,
+        state: &mut State,
+        key: &FlowKey,
+        risk: u8,
+        data: &[u8],
+        timestamp: SystemTime,
+    ) -> Result<(), String> {
+        let now = self.clock.now();
+        let rotate = state.file.as_ref().is_some_and(|file| {
+            file.writer.get_ref().written >= self.config.max_file_bytes
+                || now.duration_since(file.opened).unwrap_or_default() >= self.config.max_file_age
+        });
+        if rotate {
+            self.close_file(state)?;
+        }
+        if state.file.is_none() {
+            let file = self.open_file(state)?;
+            state.stats.bytes_written += file.writer.get_ref().written;
+            state.file = Some(file);
+        }
+        let Some(file) = state.file.as_mut() else {
+            return Ok(());
+        };
+
+        let flow = key.to_string();
+        let since_epoch = timestamp.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let offset = file.writer.get_ref().written;
+        let mut block = EnhancedPacketBlock::default();
+        block.timestamp = since_epoch;
+        block.original_len = data.len() as u32;
+        block.data = Cow::Borrowed(data);
+        block.options = vec![EnhancedPacketOption::Comment(Cow::Owned(format!(
+            "flow={} risk={}",
+            flow, risk
+        )))];
+        file.writer
+            .write_pcapng_block(block)
+            .map_err(|e| format!("write {}: {}", file.path.display(), e))?;
+        let len = file.writer.get_ref().written - offset;
+        let line = IndexLine {
+            flow,
+            offset,
+            len: len as u32,
+            ts: DateTime::<Utc>::from(timestamp),
+            risk,
+        };
+        serde_json::to_writer(&mut file.index, &line).map_err(|e| e.to_string())?;
+        file.index
+            .write_all(b"\n")
+            .map_err(|e| format!("write index: {}", e))?;
+
+        state.stats.packets_written += 1;
+        state.stats.bytes_written += len;
+        Ok(())
+    }
+
+    fn open_file(&self, state: &mut State) -> Result<OpenFile, String> {
+        state.sequence += 1;
+        let name = format!(
+            "{}{}-{:04}.{}",
+            FILE_PREFIX,
+            self.clock.utc().format("%Y%m%dT%H%M%S"),
+            state.sequence % 10_000,
+            FILE_EXTENSION
+        );
+        let path = self.config.dir.join(name);
+        let pcap = File::create(&path).map_err(|e| format!("create {}: {}", path.display(), e))?;
+        let index = File::create(index_path(&path))
+            .map_err(|e| format!("create index for {}: {}", path.display(), e))?;
+        let mut writer = PcapNgWriter::new(CountingWriter {
+            inner: BufWriter::new(pcap),
+            written: 0,
+        })
+        .map_err(|e| format!("write {}: {}", path.display(), e))?;
+        let interface = InterfaceDescriptionBlock {
+            linktype: DataLink::RAW,
+            snaplen: 0,
+            options: vec![],
+        };
+        writer
+            .write_pcapng_block(interface)
+            .map_err(|e| format!("write {}: {}", path.display(), e))?;
+
+        state.stats.files_opened += 1;
+        self.prune_files(state, &path);
+        Ok(OpenFile {
+            writer,
+            index: BufWriter::new(index),
+            path,
+            opened: self.clock.now(),
+        })
+    }
+
+    fn close_file(&self, state: &mut State) -> Result<(), String> {
+        match state.file.take() {
+            Some(mut file) => flush_file(&mut file),
+            None => Ok(()),
+        }
+    }
+
+    /// Delete the oldest files beyond `max_files`, never `current`
+    fn prune_files(&self, state: &mut State, current: &Path) {
+        if self.config.max_files == 0 {
+            return;
+        }
+        let Ok(files) = evidence_files(&self.config.dir) else {
+            return;
+        };
+        let excess = files.len().saturating_sub(self.config.max_files);
+        for file in files.iter().filter(|f| f.as_path() != current).take(excess) {
+            if fs::remove_file(file).is_ok() {
+                let _ = fs::remove_file(index_path(file));
+                state.stats.files_pruned += 1;
+            }
+        }
+    }
+
+    fn prune_idle(&self, state: &mut State, now: SystemTime) {
+        let timeout = self.config.flow_idle_timeout;
+        state.flows.retain(|_, flow| {
+            flow.last_seen
+                .is_some_and(|seen| now.duration_since(seen).unwrap_or_default() < timeout)
+        });
+    }
+}
+
+impl Drop for EvidenceWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+fn flush_file(file: &mut OpenFile) -> Result<(), String> {
+    file.writer
+        .get_mut()
+        .flush()
+        .and_then(|_| file.index.flush())
+        .map_err(|e| format!("flush {}: {}", file.path.display(), e))
+}
+
+fn index_path(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(".");
+    name.push(INDEX_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Evidence files in `dir`, oldest first (names sort by time)
+fn evidence_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("read {}: {}", dir.display(), e)),
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            name.starts_with(FILE_PREFIX) && name.ends_with(&format!(".{}", FILE_EXTENSION))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fingerprint_core::clock::{Clock, FrozenClock};
+    use pcap_file::pcapng::{Block, PcapNgReader};
+    use std::io::{Read, Seek, SeekFrom};
+
+    fn packet(src: &str, sport: u16, dst: &str, dport: u16, tag: u8) -> Packet {
+        Packet {
+            src_ip: src.parse().unwrap(),
+            dst_ip: dst.parse().unwrap(),
+            src_port: Some(sport),
+            dst_port: Some(dport),
+            protocol: 6,
+            ttl: 64,
+            ip_flags: 0,
+            data: vec![0x45, 0, 0, 20, tag],
+            payload: Vec::new(),
+            tcp_header: None,
+        }
+    }
+
+    #[test]
+    fn writes_lookback_and_indexes_offsets() {
+        let dir = tempfile::tempdir().unwrap();
+        let writer = EvidenceWriter::new(EvidenceConfig::new(dir.path())).unwrap();
+        let t = SystemTime::now();
+
+        // both directions of the suspicious flow, plus an unrelated one
+        assert!(!writer
+            .record(&packet("10.0.0.1", 5000, "10.0.0.2", 443, 1), t)
+            .unwrap());
+        assert!(!writer
+            .record(&packet("10.0.0.2", 443, "10.0.0.1", 5000, 2), t)
+            .unwrap());
+        assert!(!writer
+            .record(&packet("10.0.0.9", 6000, "10.0.0.2", 443, 9), t)
+            .unwrap());
+
+        let key = FlowKey::new(
+            ("10.0.0.2".parse().unwrap(), 443),
+            ("10.0.0.1".parse().unwrap(), 5000),
+            6,
+        );
+        assert_eq!(writer.flag(key, 10).unwrap(), 0, "below threshold");
+        assert_eq!(writer.flag(key, 90).unwrap(), 2);
+        assert!(writer
+            .record(&packet("10.0.0.1", 5000, "10.0.0.2", 443, 3), t)
+            .unwrap());
+        writer.flush().unwrap();
+
+        let index = EvidenceIndex::load(dir.path()).unwrap();
+        assert_eq!(index.flows().count(), 1);
+        let locations = index.lookup(&key.to_string());
+        assert_eq!(locations.len(), 3);
+        assert!(locations.iter().all(|l| l.risk == 90));
+
+        // each offset points at an Enhanced Packet Block carrying our bytes
+        let mut file = File::open(&locations[2].file).unwrap();
+        file.seek(SeekFrom::Start(locations[2].offset)).unwrap();
+        let mut block = vec![0u8; locations[2].len as usize];
+        file.read_exact(&mut block).unwrap();
+        assert_eq!(u32::from_le_bytes(block[..4].try_into().unwrap()), 6);
+        assert_eq!(&block[28..33], &[0x45, 0, 0, 20, 3]);
+
+        let mut reader = PcapNgReader::new(File::open(&locations[0].file).unwrap()).unwrap();
+        let mut tags = Vec::new();
+        while let Some(block) = reader.next_block() {
+            if let Block::EnhancedPacket(epb) = block.unwrap() {
+                tags.push(epb.data[4]);
+            }
+        }
+        assert_eq!(tags, vec![1, 2, 3]);
+        assert_eq!(writer.stats().packets_written, 3);
+    }
+
+    #[test]
+    fn rotates_by_size_and_prunes_old_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = EvidenceConfig::new(dir.path())
+            .with_rotation(1, Duration::from_secs(3600))
+            .with_max_files(2);
+        let writer = EvidenceWriter::new(config).unwrap();
+        let p = packet("192.0.2.1", 1234, "192.0.2.2", 80, 0);
+        writer.flag(FlowKey::from_packet(&p), 100).unwrap();
+        for _ in 0..4 {
+            assert!(writer.record(&p, SystemTime::now()).unwrap());
+        }
+        writer.flush().unwrap();
+
+        let stats = writer.stats();
+        assert_eq!(stats.files_opened, 4);
+        assert_eq!(stats.files_pruned, 2);
+        assert_eq!(evidence_files(dir.path()).unwrap().len(), 2);
+        let index = EvidenceIndex::load(dir.path()).unwrap();
+        assert_eq!(index.lookup(&FlowKey::from_packet(&p).to_string()).len(), 2);
+    }
+
+    #[test]
+    fn rotates_by_age_of_writer_clock() {
+        let dir = tempfile::tempdir().unwrap();
+        let clock = Arc::new(FrozenClock::starting_now());
+        let config =
+            EvidenceConfig::new(dir.path()).with_rotation(1 << 20, Duration::from_secs(60));
+        let writer = EvidenceWriter::new(config)
+            .unwrap()
+            .with_clock(clock.clone());
+        let p = packet("192.0.2.1", 1234, "192.0.2.2", 80, 0);
+        writer.flag(FlowKey::from_packet(&p), 100).unwrap();
+        writer.record(&p, clock.now()).unwrap();
+        writer.record(&p, clock.now()).unwrap();
+        assert_eq!(writer.stats().files_opened, 1);
+
+        clock.advance(Duration::from_secs(61));
+        writer.record(&p, clock.now()).unwrap();
+        assert_eq!(writer.stats().files_opened, 2);
+    }
+}
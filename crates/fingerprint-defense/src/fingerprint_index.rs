@@ -0,0 +1,245 @@
+//! JA4/JA3 index query types
+//!
+//! [`FingerprintDatabase`](crate::database::FingerprintDatabase) keeps an
+//! incrementally maintained index of every JA4-family and JA3 value it has
+//! stored. TLS JA4 values are additionally split into their components, so
+//! analysts can search by exact value, by prefix (`t13d15`) or by individual
+//! parts such as the TLS version, cipher count or extension hash.
+
+use serde::{Deserialize, Serialize};
+
+/// Metadata keys indexed from stored fingerprints
+pub const INDEXED_KINDS: &[&str] = &["ja4", "ja4h", "ja4t", "ja3"];
+
+/// Default page size
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// Largest page a single query may request
+pub const MAX_PAGE_SIZE: u32 = 1000;
+
+/// Components of a TLS JA4 value (`t13d1514h2_<ciphers>_<extensions>_<sigalgs>`)
+///
+/// As a query filter, `None` fields match anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ja4Components {
+    /// Transport (`t` = TCP, `q` = QUIC, `d` = DTLS)
+    pub transport: Option<String>,
+    /// TLS version digits (e.g. `13`)
+    pub tls_version: Option<String>,
+    /// SNI marker (`d` = domain, `i` = IP / none)
+    pub destination: Option<String>,
+    /// Number of cipher suites
+    pub cipher_count: Option<u32>,
+    /// Number of extensions
+    pub extension_count: Option<u32>,
+    /// First and last ALPN characters (e.g. `h2`)
+    pub alpn: Option<String>,
+    /// Cipher suite hash section
+    pub cipher_hash: Option<String>,
+    /// Extension hash section
+    pub extension_hash: Option<String>,
+    /// Signature algorithm hash section
+    pub signature_hash: Option<String>,
+}
+
+impl Ja4Components {
+    /// Split a JA4 string into its components
+    ///
+    /// Returns `None` when `ja4` is not a well-formed four-section JA4 value.
+    pub fn parse(ja4: &str) -> Option<Self> {
+        let mut sections = ja4.split('_');
+        let a = sections.next()?;
+        let cipher_hash = sections.next()?;
+        let extension_hash = sections.next()?;
+        let signature_hash = sections.next()?;
+        if sections.next().is_some() || a.len() != 10 || !a.is_ascii() {
+            return None;
+        }
+
+        Some(Self {
+            transport: Some(a[0..1].to_string()),
+            tls_version: Some(a[1..3].to_string()),
+            destination: Some(a[3..4].to_string()),
+            cipher_count: Some(a[4..6].parse().ok()?),
+            extension_count: Some(a[6..8].parse().ok()?),
+            alpn: Some(a[8..10].to_string()),
+            cipher_hash: Some(cipher_hash.to_string()),
+            extension_hash: Some(extension_hash.to_string()),
+            signature_hash: Some(signature_hash.to_string()),
+        })
+    }
+
+    /// True when no field is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How a query matches indexed values
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type", content = "value")]
+pub enum IndexMatch {
+    /// Any value (filter by components only)
+    #[default]
+    Any,
+    /// Exactly this value
+    Exact(String),
+    /// Values starting with this prefix
+    Prefix(String),
+}
+
+/// Query against the fingerprint index
+///
+/// Results are ordered by value and paged with an opaque cursor taken from
+/// [`IndexPage::next_cursor`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndexQuery {
+    /// Restrict to one kind (`ja4`, `ja4h`, `ja4t`, `ja3`)
+    pub kind: Option<String>,
+    /// Value match
+    pub matching: IndexMatch,
+    /// Component filter; only JA4 entries carry components
+    pub components: Ja4Components,
+    /// Resume after this cursor
+    pub cursor: Option<String>,
+    /// Page size, clamped to [`MAX_PAGE_SIZE`]; 0 means [`DEFAULT_PAGE_SIZE`]
+    pub limit: u32,
+}
+
+impl IndexQuery {
+    /// Match one exact value
+    pub fn exact(value: impl Into<String>) -> Self {
+        Self {
+            matching: IndexMatch::Exact(value.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Match values starting with `prefix`
+    pub fn prefix(prefix: impl Into<String>) -> Self {
+        Self {
+            matching: IndexMatch::Prefix(prefix.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Match JA4 entries by component
+    pub fn components(components: Ja4Components) -> Self {
+        Self {
+            kind: Some("ja4".to_string()),
+            components,
+            ..Default::default()
+        }
+    }
+
+    /// Restrict to one kind
+    pub fn with_kind(mut self, kind: impl Into<String>) -> Self {
+        self.kind = Some(kind.into());
+        self
+    }
+
+    /// Resume after `cursor`
+    pub fn after(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+
+    /// Set the page size
+    pub fn with_limit(mut self, limit: u32) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Effective page size
+    pub fn page_size(&self) -> u32 {
+        match self.limit {
+            0 => DEFAULT_PAGE_SIZE,
+            n => n.min(MAX_PAGE_SIZE),
+        }
+    }
+}
+
+/// One indexed value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// Fingerprint kind (`ja4`, `ja4h`, `ja4t`, `ja3`)
+    pub kind: String,
+    /// Fingerprint value
+    pub value: String,
+    /// Parsed components (JA4 only)
+    pub components: Option<Ja4Components>,
+    /// Number of stored fingerprints carrying this value
+    pub hit_count: u64,
+    /// First observation (RFC 3339)
+    pub first_seen: String,
+    /// Latest observation (RFC 3339)
+    pub last_seen: String,
+}
+
+impl IndexEntry {
+    /// Cursor that resumes a query after this entry
+    pub fn cursor(&self) -> String {
+        encode_cursor(&self.kind, &self.value)
+    }
+}
+
+/// One page of query results
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexPage {
+    /// Entries on this page
+    pub entries: Vec<IndexEntry>,
+    /// Total number of matching entries across all pages
+    pub total: u64,
+    /// Cursor for the next page, if there is one
+    pub next_cursor: Option<String>,
+}
+
+pub(crate) fn encode_cursor(kind: &str, value: &str) -> String {
+    format!("{}:{}", kind, value)
+}
+
+/// Split a cursor into `(kind, value)`
+pub(crate) fn decode_cursor(cursor: &str) -> Option<(&str, &str)> {
+    cursor.split_once(':')
+}
+
+/// Exclusive upper bound of all strings starting with `prefix`
+///
+/// Lets prefix queries use the value index as a range scan instead of `LIKE`.
+pub(crate) fn prefix_upper_bound(prefix: &str) -> String {
+    format!("{}{}", prefix, char::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ja4_components() {
+        let c = Ja4Components::parse("t13d1514h2_ba7f6aa88938_f7e2764aeb6b_ec13").unwrap();
+        assert_eq!(c.transport.as_deref(), Some("t"));
+        assert_eq!(c.tls_version.as_deref(), Some("13"));
+        assert_eq!(c.destination.as_deref(), Some("d"));
+        assert_eq!(c.cipher_count, Some(15));
+        assert_eq!(c.extension_count, Some(14));
+        assert_eq!(c.alpn.as_deref(), Some("h2"));
+        assert_eq!(c.extension_hash.as_deref(), Some("f7e2764aeb6b"));
+
+        assert!(Ja4Components::parse("t13d1514h2_ba7f6aa88938").is_none());
+        assert!(Ja4Components::parse("t13dxx14h2_a_b_c").is_none());
+        assert!(
+            Ja4Components::parse("ge11cn20enus_60ca1bd65281_ac95b44401d9_8df6a44f726c").is_none()
+        );
+    }
+
+    #[test]
+    fn test_query_page_size_is_clamped() {
+        assert_eq!(IndexQuery::default().page_size(), DEFAULT_PAGE_SIZE);
+        assert_eq!(IndexQuery::prefix("t13").with_limit(5).page_size(), 5);
+        assert_eq!(
+            IndexQuery::prefix("t13").with_limit(u32::MAX).page_size(),
+            MAX_PAGE_SIZE
+        );
+    }
+}
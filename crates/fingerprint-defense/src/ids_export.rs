@@ -0,0 +1,496 @@
+//! Suricata / Snort rule export
+//!
+//! Turns learned fingerprints into IDS rules so they can be deployed through
+//! existing pipelines:
+//!
+//! - **JA3 / JA4** hashes become Suricata `ja3.hash` / `ja4.hash` rules.
+//!   Snort 3 has no TLS fingerprint buffers, so these are written to Snort
+//!   files as comments and counted as skipped.
+//! - **HTTP header signatures** (User-Agent and header order) become
+//!   `http.user_agent` / `http.header_names` rules for Suricata and
+//!   `http_header` rules for Snort 3.
+//!
+//! Each rule carries the fingerprint's confidence, observation count and
+//! first/last-seen dates in its `metadata` option.
+
+use crate::database::{CandidateFingerprint, FingerprintDatabase};
+use crate::passive::HttpFingerprint;
+use chrono::{DateTime, Utc};
+use fingerprint_core::stix::{FingerprintIndicator, FingerprintKind};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// First SID of exported rules (inside the 9,000,000+ local range)
+pub const DEFAULT_SID_BASE: u32 = 9_100_000;
+
+/// Default minimum confidence of exported fingerprints
+pub const DEFAULT_MIN_CONFIDENCE: f64 = 0.9;
+
+/// Target rule language
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleFormat {
+    /// Suricata 7
+    Suricata,
+    /// Snort 3
+    Snort,
+}
+
+impl RuleFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleFormat::Suricata => "suricata",
+            RuleFormat::Snort => "snort",
+        }
+    }
+}
+
+/// What a rule matches on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Indicator {
+    /// JA3 MD5 (32 hex characters)
+    Ja3(String),
+    /// JA4 (`t13d1516h2_8daaf6152771_02713d6af862`)
+    Ja4(String),
+    /// HTTP request header signature
+    HttpHeaders {
+        user_agent: Option<String>,
+        /// header names in wire order
+        header_order: Vec<String>,
+    },
+}
+
+impl Indicator {
+    /// Classify a TLS fingerprint ID as JA3 or JA4
+    pub fn from_tls_id(id: &str) -> Option<Self> {
+        let fingerprint = FingerprintIndicator::classify(id)?;
+        Some(match fingerprint.kind {
+            FingerprintKind::Ja3 => Indicator::Ja3(fingerprint.value),
+            FingerprintKind::Ja4 => Indicator::Ja4(fingerprint.value),
+        })
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Indicator::Ja3(_) => "JA3",
+            Indicator::Ja4(_) => "JA4",
+            Indicator::HttpHeaders { .. } => "HTTP",
+        }
+    }
+}
+
+impl fmt::Display for Indicator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Indicator::Ja3(hash) | Indicator::Ja4(hash) => write!(f, "{}", hash),
+            Indicator::HttpHeaders {
+                user_agent,
+                header_order,
+            } => write!(
+                f,
+                "{} [{}]",
+                user_agent.as_deref().unwrap_or("-"),
+                header_order.join(",")
+            ),
+        }
+    }
+}
+
+/// Fingerprint to export
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportedFingerprint {
+    pub indicator: Indicator,
+    /// 0.0 – 1.0
+    pub confidence: f64,
+    pub observation_count: u64,
+    pub first_seen: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+    /// candidate row it came from
+    pub candidate_id: Option<i64>,
+}
+
+impl ExportedFingerprint {
+    pub fn new(indicator: Indicator, confidence: f64) -> Self {
+        Self {
+            indicator,
+            confidence: confidence.clamp(0.0, 1.0),
+            observation_count: 0,
+            first_seen: None,
+            last_seen: None,
+            candidate_id: None,
+        }
+    }
+
+    /// Learned TLS candidate; `None` for other types or unrecognised IDs
+    pub fn from_candidate(candidate: &CandidateFingerprint) -> Option<Self> {
+        if candidate.fingerprint_type != "tls" {
+            return None;
+        }
+        let parse = |ts: &str| {
+            DateTime::parse_from_rfc3339(ts)
+                .ok()
+                .map(|t| t.with_timezone(&Utc))
+        };
+        Some(Self {
+            observation_count: candidate.observation_count as u64,
+            first_seen: parse(&candidate.first_seen),
+            last_seen: parse(&candidate.last_seen),
+            candidate_id: Some(candidate.id),
+            ..Self::new(
+                Indicator::from_tls_id(&candidate.fingerprint_id)?,
+                candidate.stability_score,
+            )
+        })
+    }
+
+    /// Header signature of a passively observed HTTP request
+    pub fn from_http(http: &HttpFingerprint, confidence: f64) -> Self {
+        Self::new(
+            Indicator::HttpHeaders {
+                user_agent: http.user_agent.clone(),
+                header_order: http.header_order.clone(),
+            },
+            confidence,
+        )
+    }
+
+    pub fn with_observations(
+        mut self,
+        count: u64,
+        first_seen: DateTime<Utc>,
+        last_seen: DateTime<Utc>,
+    ) -> Self {
+        self.observation_count = count;
+        self.first_seen = Some(first_seen);
+        self.last_seen = Some(last_seen);
+        self
+    }
+}
+
+/// Generated rule file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleSet {
+    pub rules: Vec<String>,
+    /// fingerprints below the confidence threshold or not expressible
+    pub skipped: usize,
+    /// comment lines explaining skipped fingerprints
+    pub notes: Vec<String>,
+}
+
+impl RuleSet {
+    /// Rules file contents, one rule per line
+    pub fn render(&self) -> String {
+        let mut out = String::from("# Generated by fingerprint-defense\n");
+        for note in &self.notes {
+            out.push_str(&format!("# {}\n", note));
+        }
+        for rule in &self.rules {
+            out.push_str(rule);
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn write_to<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.render())
+    }
+}
+
+/// Converts fingerprints to IDS rules
+#[derive(Debug, Clone)]
+pub struct RuleExporter {
+    format: RuleFormat,
+    sid_base: u32,
+    min_confidence: f64,
+    classtype: String,
+}
+
+impl RuleExporter {
+    pub fn new(format: RuleFormat) -> Self {
+        Self {
+            format,
+            sid_base: DEFAULT_SID_BASE,
+            min_confidence: DEFAULT_MIN_CONFIDENCE,
+            classtype: "policy-violation".to_string(),
+        }
+    }
+
+    /// SIDs are assigned consecutively from `sid_base`
+    pub fn with_sid_base(mut self, sid_base: u32) -> Self {
+        self.sid_base = sid_base;
+        self
+    }
+
+    pub fn with_min_confidence(mut self, min_confidence: f64) -> Self {
+        self.min_confidence = min_confidence.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_classtype(mut self, classtype: &str) -> Self {
+        self.classtype = classtype.to_string();
+        self
+    }
+
+    pub fn format(&self) -> RuleFormat {
+        self.format
+    }
+
+    /// Rules for `fingerprints`, in order
+    pub fn export(&self, fingerprints: &[ExportedFingerprint]) -> RuleSet {
+        let mut set = RuleSet::default();
+        for fingerprint in fingerprints {
+            if fingerprint.confidence < self.min_confidence {
+                set.skipped += 1;
+                continue;
+            }
+            let sid = self.sid_base + set.rules.len() as u32;
+            match self.rule(fingerprint, sid) {
+                Some(rule) => set.rules.push(rule),
+                None => {
+                    set.skipped += 1;
+                    set.notes.push(format!(
+                        "{} {} has no {} equivalent",
+                        fingerprint.indicator.kind(),
+                        fingerprint.indicator,
+                        self.format.as_str()
+                    ));
+                }
+            }
+        }
+        set
+    }
+
+    /// Approved TLS candidates of `db`
+    pub fn export_database(&self, db: &FingerprintDatabase) -> Result<RuleSet, String> {
+        let candidates = db.get_candidates_with_status("approved", None)?;
+        let fingerprints: Vec<ExportedFingerprint> = candidates
+            .iter()
+            .filter_map(ExportedFingerprint::from_candidate)
+            .collect();
+        let mut set = self.export(&fingerprints);
+        set.skipped += candidates.len() - fingerprints.len();
+        Ok(set)
+    }
+
+    /// One rule; `None` when the format cannot express the indicator
+    pub fn rule(&self, fingerprint: &ExportedFingerprint, sid: u32) -> Option<String> {
+        let (header, matches) = match (self.format, &fingerprint.indicator) {
+            (RuleFormat::Suricata, Indicator::Ja3(hash)) => (
+                "alert tls any any -> any any",
+                format!("ja3.hash; content:\"{}\";", hash),
+            ),
+            (RuleFormat::Suricata, Indicator::Ja4(hash)) => (
+                "alert tls any any -> any any",
+                format!("ja4.hash; content:\"{}\";", hash),
+            ),
+            (RuleFormat::Snort, Indicator::Ja3(_) | Indicator::Ja4(_)) => return None,
+            (
+                format,
+                Indicator::HttpHeaders {
+                    user_agent,
+                    header_order,
+                },
+            ) => {
+                if user_agent.is_none() && header_order.is_empty() {
+                    return None;
+                }
+                let header = match format {
+                    RuleFormat::Suricata => "alert http any any -> any any",
+                    RuleFormat::Snort => "alert http",
+                };
+                (
+                    header,
+                    http_matches(format, user_agent.as_deref(), header_order),
+                )
+            }
+        };
+
+        let msg = format!(
+            "FPD learned {} fingerprint {}",
+            fingerprint.indicator.kind(),
+            sanitize_msg(&fingerprint.indicator.to_string())
+        );
+        Some(format!(
+            "{} (msg:\"{}\"; flow:established,to_server; {} metadata:{}; classtype:{}; sid:{}; rev:1;)",
+            header,
+            msg,
+            matches,
+            metadata(fingerprint),
+            self.classtype,
+            sid
+        ))
+    }
+}
+
+/// Match options for an HTTP header signature
+fn http_matches(format: RuleFormat, user_agent: Option<&str>, header_order: &[String]) -> String {
+    let mut options = Vec::new();
+    if let Some(ua) = user_agent {
+        options.push(match format {
+            RuleFormat::Suricata => format!("http.user_agent; content:\"{}\";", escape(ua)),
+            RuleFormat::Snort => {
+                format!("http_header:field user-agent; content:\"{}\";", escape(ua))
+            }
+        });
+    }
+    if !header_order.is_empty() {
+        match format {
+            // header names separated by CRLF, in order
+            RuleFormat::Suricata => {
+                let names: String = header_order
+                    .iter()
+                    .map(|name| format!("|0d 0a|{}", escape(name)))
+                    .collect();
+                options.push(format!(
+                    "http.header_names; content:\"{}|0d 0a|\"; nocase;",
+                    names
+                ));
+            }
+            // each name after the previous one in the raw header block
+            RuleFormat::Snort => {
+                for (i, name) in header_order.iter().enumerate() {
+                    let relative = if i == 0 { "" } else { " distance:0;" };
+                    options.push(format!(
+                        "http_raw_header; content:\"{}|3a|\"; nocase;{}",
+                        escape(name),
+                        relative
+                    ));
+                }
+            }
+        }
+    }
+    options.join(" ")
+}
+
+/// `metadata` values; commas and semicolons are not allowed in them
+fn metadata(fingerprint: &ExportedFingerprint) -> String {
+    let mut fields = vec![
+        format!("confidence {:.2}", fingerprint.confidence),
+        format!("observations {}", fingerprint.observation_count),
+    ];
+    if let Some(first_seen) = fingerprint.first_seen {
+        fields.push(format!("first_seen {}", first_seen.format("%Y_%m_%d")));
+    }
+    if let Some(last_seen) = fingerprint.last_seen {
+        fields.push(format!("last_seen {}", last_seen.format("%Y_%m_%d")));
+    }
+    if let Some(id) = fingerprint.candidate_id {
+        fields.push(format!("fpd_candidate {}", id));
+    }
+    fields.join(", ")
+}
+
+/// Content string with reserved and non-printable bytes as `|xx|`
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_graphic() && !matches!(byte, b'"' | b';' | b'\\' | b'|') || byte == b' ' {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("|{:02x}|", byte));
+        }
+    }
+    out
+}
+
+/// `msg` text without characters that end the option
+fn sanitize_msg(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !matches!(c, '"' | ';' | '\\' | '|'))
+        .take(120)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JA4: &str = "t13d1516h2_8daaf6152771_02713d6af862";
+
+    #[test]
+    fn exports_tls_and_http_rules() {
+        let day = DateTime::parse_from_rfc3339("2026-10-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let fingerprints =
+            vec![
+                ExportedFingerprint::new(Indicator::from_tls_id(JA4).unwrap(), 0.97)
+                    .with_observations(42, day, day + chrono::Duration::days(3)),
+                ExportedFingerprint::new(
+                    Indicator::from_tls_id("E7D705A3286E19EA42F587B344EE6865").unwrap(),
+                    0.95,
+                ),
+                ExportedFingerprint::new(
+                    Indicator::HttpHeaders {
+                        user_agent: Some("evil\"bot; 1.0".to_string()),
+                        header_order: vec!["Host".to_string(), "User-Agent".to_string()],
+                    },
+                    0.92,
+                ),
+                ExportedFingerprint::new(Indicator::Ja4(JA4.to_string()), 0.5),
+            ];
+
+        let suricata = RuleExporter::new(RuleFormat::Suricata).export(&fingerprints);
+        assert_eq!(suricata.rules.len(), 3);
+        assert_eq!(suricata.skipped, 1);
+        assert_eq!(
+            suricata.rules[0],
+            format!(
+                "alert tls any any -> any any (msg:\"FPD learned JA4 fingerprint {JA4}\"; \
+                 flow:established,to_server; ja4.hash; content:\"{JA4}\"; \
+                 metadata:confidence 0.97, observations 42, first_seen 2026_10_01, \
+                 last_seen 2026_10_04; classtype:policy-violation; sid:9100000; rev:1;)"
+            )
+        );
+        assert!(
+            suricata.rules[1].contains("ja3.hash; content:\"e7d705a3286e19ea42f587b344ee6865\";")
+        );
+        assert!(suricata.rules[2].contains("http.user_agent; content:\"evil|22|bot|3b| 1.0\";"));
+        assert!(suricata.rules[2].contains(
+            "http.header_names; content:\"|0d 0a|Host|0d 0a|User-Agent|0d 0a|\"; nocase;"
+        ));
+        assert!(suricata.rules[2].contains("sid:9100002;"));
+
+        let snort = RuleExporter::new(RuleFormat::Snort)
+            .with_sid_base(1_000_000)
+            .export(&fingerprints);
+        assert_eq!(snort.rules.len(), 1);
+        assert_eq!(snort.skipped, 3);
+        assert!(snort.rules[0].starts_with("alert http (msg:"));
+        assert!(snort.rules[0]
+            .contains("http_raw_header; content:\"User-Agent|3a|\"; nocase; distance:0;"));
+        assert!(snort.render().contains("# JA4 t13d1516h2"));
+    }
+
+    #[test]
+    fn exports_approved_candidates() {
+        let db = FingerprintDatabase::new_in_memory().unwrap();
+        let approved = db
+            .store_candidate_fingerprint("tls", JA4, 50, 0.95, None)
+            .unwrap();
+        db.update_candidate_status(approved, "approved", None)
+            .unwrap();
+        db.store_candidate_fingerprint(
+            "tls",
+            "t13d1715h2_5b57614c22b0_3d5424432f57",
+            50,
+            0.95,
+            None,
+        )
+        .unwrap();
+        let http = db
+            .store_candidate_fingerprint("http", "http_4f", 50, 0.95, None)
+            .unwrap();
+        db.update_candidate_status(http, "approved", None).unwrap();
+
+        let set = RuleExporter::new(RuleFormat::Suricata)
+            .export_database(&db)
+            .unwrap();
+        assert_eq!(set.rules.len(), 1);
+        assert_eq!(set.skipped, 1);
+        assert!(set.rules[0].contains(&format!("fpd_candidate {}", approved)));
+        assert!(set.rules[0].contains("observations 50"));
+    }
+}
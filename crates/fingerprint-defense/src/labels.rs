@@ -0,0 +1,281 @@
+//! Weak labels from enforcement outcomes
+//!
+//! When a gateway rate-limits, rejects or blocks a client, the fingerprints that client
+//! presented are probably (not certainly) abusive. This module carries those outcomes as
+//! weak labels into the [`FingerprintDatabase`], where they decay over time and feed back
+//! into scoring via [`WeakLabelSummary::prior`].
+//!
+//! Enforcement happens on hot request paths across many worker threads while the
+//! database connection is single-threaded, so events go through a bounded channel to a
+//! dedicated writer thread; [`LabelSink::record`] never blocks and drops events when
+//! the writer falls behind.
+
+use crate::database::{FingerprintDatabase, WeakLabelSummary};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// default capacity of the event channel
+const DEFAULT_CAPACITY: usize = 4096;
+
+/// default half-life of a label (7 days)
+pub const DEFAULT_LABEL_HALF_LIFE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// enforcement outcome reported by a gateway
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnforcementOutcome {
+    /// request rejected with 429
+    RateLimited,
+    /// missing, unknown or inactive credentials
+    AuthFailure,
+    /// turned away by admission control before reaching the backend
+    AdmissionBlocked,
+}
+
+impl EnforcementOutcome {
+    /// label source stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EnforcementOutcome::RateLimited => "rate_limited",
+            EnforcementOutcome::AuthFailure => "auth_failure",
+            EnforcementOutcome::AdmissionBlocked => "admission_blocked",
+        }
+    }
+
+    /// label weight: how much one outcome says about the fingerprint
+    ///
+    /// Legitimate clients hit rate limits and mistype keys too, so single outcomes
+    /// are weak; admission blocks already reflect a detector verdict.
+    pub fn default_weight(&self) -> f64 {
+        match self {
+            EnforcementOutcome::RateLimited => 0.1,
+            EnforcementOutcome::AuthFailure => 0.2,
+            EnforcementOutcome::AdmissionBlocked => 0.5,
+        }
+    }
+}
+
+/// fingerprint presented by the client, e.g. `("tls", "t13d1516h2_...")`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FingerprintRef {
+    /// fingerprint type (tls/http/tcp), matching the learner's types
+    pub fingerprint_type: String,
+    /// fingerprint ID (JA4, JA4H, ...)
+    pub fingerprint_id: String,
+}
+
+impl FingerprintRef {
+    pub fn new(fingerprint_type: impl Into<String>, fingerprint_id: impl Into<String>) -> Self {
+        Self {
+            fingerprint_type: fingerprint_type.into(),
+            fingerprint_id: fingerprint_id.into(),
+        }
+    }
+}
+
+/// one enforcement decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnforcementEvent {
+    pub outcome: EnforcementOutcome,
+    /// who was enforced against (key owner, client IP); must not be a secret
+    pub subject: Option<String>,
+    pub fingerprints: Vec<FingerprintRef>,
+    /// Unix seconds
+    pub timestamp: i64,
+}
+
+impl EnforcementEvent {
+    pub fn new(outcome: EnforcementOutcome, fingerprints: Vec<FingerprintRef>) -> Self {
+        Self {
+            outcome,
+            subject: None,
+            fingerprints,
+            timestamp: unix_now(),
+        }
+    }
+
+    pub fn with_subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs() as i64
+}
+
+/// cloneable, non-blocking handle for reporting enforcement events
+#[derive(Clone)]
+pub struct LabelSink {
+    sender: SyncSender<EnforcementEvent>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl LabelSink {
+    /// queue an event; returns false if it was dropped (queue full or writer stopped)
+    pub fn record(&self, event: EnforcementEvent) -> bool {
+        if event.fingerprints.is_empty() {
+            return true;
+        }
+        match self.sender.try_send(event) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+        }
+    }
+
+    /// events dropped so far
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// writer thread persisting enforcement events as weak labels
+pub struct LabelPropagator {
+    sink: LabelSink,
+    handle: JoinHandle<FingerprintDatabase>,
+}
+
+impl LabelPropagator {
+    /// start the writer thread; it owns `db` until [`LabelPropagator::shutdown`]
+    pub fn spawn(db: FingerprintDatabase) -> std::io::Result<Self> {
+        Self::with_capacity(db, DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(db: FingerprintDatabase, capacity: usize) -> std::io::Result<Self> {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let handle = std::thread::Builder::new()
+            .name("label-propagator".to_string())
+            .spawn(move || Self::run(db, receiver))?;
+        Ok(Self {
+            sink: LabelSink {
+                sender,
+                dropped: Arc::new(AtomicU64::new(0)),
+            },
+            handle,
+        })
+    }
+
+    /// handle for reporting events
+    pub fn sink(&self) -> LabelSink {
+        self.sink.clone()
+    }
+
+    /// stop accepting events, drain the queue and return the database
+    ///
+    /// Outstanding [`LabelSink`] clones keep the writer alive; drop them first.
+    pub fn shutdown(self) -> FingerprintDatabase {
+        drop(self.sink);
+        self.handle
+            .join()
+            .expect("label propagator thread panicked")
+    }
+
+    fn run(db: FingerprintDatabase, receiver: Receiver<EnforcementEvent>) -> FingerprintDatabase {
+        for event in receiver {
+            for fingerprint in &event.fingerprints {
+                if let Err(e) = db.store_weak_label(
+                    &fingerprint.fingerprint_type,
+                    &fingerprint.fingerprint_id,
+                    event.outcome.as_str(),
+                    event.outcome.default_weight(),
+                    event.subject.as_deref(),
+                    event.timestamp,
+                ) {
+                    log::warn!(
+                        "[Labels] Failed to store {} label for {}:{}: {}",
+                        event.outcome.as_str(),
+                        fingerprint.fingerprint_type,
+                        fingerprint.fingerprint_id,
+                        e
+                    );
+                }
+            }
+        }
+        db
+    }
+}
+
+/// current weak-label summary of a fingerprint with the default half-life
+pub fn weak_label_summary(
+    db: &FingerprintDatabase,
+    fingerprint: &FingerprintRef,
+) -> Result<WeakLabelSummary, String> {
+    db.get_weak_label_summary(
+        &fingerprint.fingerprint_type,
+        &fingerprint.fingerprint_id,
+        unix_now(),
+        DEFAULT_LABEL_HALF_LIFE.as_secs(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_become_decayed_labels() {
+        let propagator =
+            LabelPropagator::spawn(FingerprintDatabase::new_in_memory().unwrap()).unwrap();
+        let sink = propagator.sink();
+        let bot = FingerprintRef::new("tls", "t13d1516h2_8daaf6152771_02713d6af862");
+        let other = FingerprintRef::new("tls", "t13d1715h2_5b57614c22b0_3d5424432f57");
+
+        for _ in 0..5 {
+            assert!(sink.record(
+                EnforcementEvent::new(EnforcementOutcome::RateLimited, vec![bot.clone()])
+                    .with_subject("demo_user"),
+            ));
+        }
+        let mut old =
+            EnforcementEvent::new(EnforcementOutcome::AdmissionBlocked, vec![bot.clone()]);
+        old.timestamp -= DEFAULT_LABEL_HALF_LIFE.as_secs() as i64;
+        assert!(sink.record(old));
+        // events without fingerprints are ignored
+        assert!(sink.record(EnforcementEvent::new(
+            EnforcementOutcome::AuthFailure,
+            vec![]
+        )));
+        drop(sink);
+
+        let db = propagator.shutdown();
+        let summary = weak_label_summary(&db, &bot).unwrap();
+        assert_eq!(summary.count, 6);
+        assert_eq!(summary.by_source["rate_limited"], 5);
+        // 5 × 0.1 fresh + 0.5 × one half-life
+        assert!((summary.decayed_weight - 0.75).abs() < 0.01);
+        assert!(summary.prior() > 0.5 && summary.prior() < 1.0);
+
+        let clean = weak_label_summary(&db, &other).unwrap();
+        assert_eq!(clean.count, 0);
+        assert_eq!(clean.prior(), 0.0);
+    }
+
+    #[test]
+    fn test_full_queue_drops_instead_of_blocking() {
+        let (sender, _receiver) = mpsc::sync_channel(1);
+        let sink = LabelSink {
+            sender,
+            dropped: Arc::new(AtomicU64::new(0)),
+        };
+        let event = || {
+            EnforcementEvent::new(
+                EnforcementOutcome::AuthFailure,
+                vec![FingerprintRef::new("tcp", "64:65535")],
+            )
+        };
+
+        assert!(sink.record(event()));
+        assert!(!sink.record(event()));
+        assert_eq!(sink.dropped(), 1);
+    }
+}
@@ -4,22 +4,18 @@
 //! implements complete fingerprint self-learning mechanism, automatically recognizing and recording unknown stable fingerprint features for combating 0-day bots
 
 use crate::database::FingerprintDatabase;
+use crate::distributed::FleetStore;
+use crate::labels::{weak_label_summary, FingerprintRef};
 use crate::passive::PassiveAnalysisResult;
 use dashmap::DashMap;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::Duration;
 
+use fingerprint_core::clock::{system_clock, SharedClock};
+use fingerprint_core::events::{self, FingerprintLearned};
 use fingerprint_core::fingerprint::Fingerprint;
 use serde::{Deserialize, Serialize};
 
-/// Get current Unix timestamp (seconds)
-fn current_unix_timestamp() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or(Duration::ZERO)
-        .as_secs()
-}
-
 /// Calculate timestamp difference (seconds)
 fn timestamp_duration(from: u64, to: u64) -> Duration {
     Duration::from_secs(to.saturating_sub(from))
@@ -46,7 +42,6 @@ pub struct UnknownFingerprintObservation {
 
 /// Self-learning analyzer
 pub struct SelfLearningAnalyzer {
-    #[allow(dead_code)] // will be used to store learned fingerprints
     db: Arc<FingerprintDatabase>,
     /// Unknown fingerprint observation records (fp_id -> observation)
     observations: DashMap<String, UnknownFingerprintObservation>,
@@ -56,6 +51,10 @@ pub struct SelfLearningAnalyzer {
     stability_window: Duration,
     /// Minimum stability score threshold
     min_stability_score: f64,
+    /// Fleet-wide candidates shared with other gateways
+    fleet: Option<Arc<FleetStore>>,
+    /// Time source for first/last-seen and expiry
+    clock: SharedClock,
 }
 
 impl SelfLearningAnalyzer {
@@ -67,6 +66,8 @@ impl SelfLearningAnalyzer {
             learning_threshold: 10,
             stability_window: Duration::from_secs(24 * 60 * 60), // 24小时
             min_stability_score: 0.8,
+            fleet: None,
+            clock: system_clock(),
         }
     }
 
@@ -131,7 +132,7 @@ impl SelfLearningAnalyzer {
         }
 
         let key = format!("{}:{}", fp_type, fp_id);
-        let now = current_unix_timestamp();
+        let now = self.clock.unix_secs();
 
         // Protection point: limit observation list size to prevent memory explosion (DoS protection)
         const MAX_OBSERVATIONS: usize = 10000;
@@ -149,23 +150,11 @@ impl SelfLearningAnalyzer {
                 entry.last_seen = now;
 
                 // Recalculate stability score based on updated observation count
-                let time_span = timestamp_duration(entry.first_seen, entry.last_seen);
-                let expected_frequency =
-                    entry.observation_count as f64 / (time_span.as_secs_f64() / 3600.0).max(1.0); // observations per hour
-
-                // stability score based on observation frequency consistency
-                let stability_bonus = if expected_frequency > 1.0 && expected_frequency < 100.0 {
-                    0.3 // normal frequency bonus
-                } else if expected_frequency >= 100.0 {
-                    0.1 // high frequency but not stable
-                } else {
-                    0.0 // frequency too low
-                };
-
-                entry.stability_score =
-                    (entry.observation_count as f64 / self.learning_threshold as f64).min(1.0)
-                        * 0.7
-                        + stability_bonus;
+                entry.stability_score = self.stability_score(
+                    entry.observation_count,
+                    entry.first_seen,
+                    entry.last_seen,
+                );
 
                 // check if learning conditions are met
                 if entry.observation_count >= self.learning_threshold
@@ -203,11 +192,54 @@ impl SelfLearningAnalyzer {
                 // Create a clone to avoid holding the read lock during database operation
                 let observation = entry.value().clone();
                 drop(entry); // Explicitly release the DashMap read lock
+                if self.rejected_by_fleet(&observation) {
+                    return;
+                }
                 self.learn_new_fingerprint(&observation);
             }
         }
     }
 
+    /// Stability score from observation count and frequency
+    fn stability_score(&self, observation_count: u64, first_seen: u64, last_seen: u64) -> f64 {
+        let time_span = timestamp_duration(first_seen, last_seen);
+        let expected_frequency =
+            observation_count as f64 / (time_span.as_secs_f64() / 3600.0).max(1.0); // observations per hour
+
+        // stability score based on observation frequency consistency
+        let stability_bonus = if expected_frequency > 1.0 && expected_frequency < 100.0 {
+            0.3 // normal frequency bonus
+        } else if expected_frequency >= 100.0 {
+            0.1 // high frequency but not stable
+        } else {
+            0.0 // frequency too low
+        };
+
+        (observation_count as f64 / self.learning_threshold as f64).min(1.0) * 0.7 + stability_bonus
+    }
+
+    /// Publish a ready observation to the fleet; true if the fleet rejected the fingerprint
+    fn rejected_by_fleet(&self, observation: &UnknownFingerprintObservation) -> bool {
+        let Some(fleet) = &self.fleet else {
+            return false;
+        };
+        match fleet.record_observation(observation) {
+            Ok(shared) if shared.status() == "rejected" => {
+                log::debug!(
+                    "[Learner] {}:{} was rejected by the fleet",
+                    observation.fingerprint_type,
+                    observation.fingerprint_id
+                );
+                true
+            }
+            Ok(_) => false,
+            Err(e) => {
+                log::warn!("[Learner] ⚠️ Failed to publish to fleet store: {}", e);
+                false
+            }
+        }
+    }
+
     /// Learn new stable fingerprint
     fn learn_new_fingerprint(&self, observation: &UnknownFingerprintObservation) {
         log::info!(
@@ -218,29 +250,169 @@ impl SelfLearningAnalyzer {
             observation.stability_score
         );
 
+        // A tombstoned fingerprint needs more evidence before it is learned again
+        let tombstone = self
+            .db
+            .tombstone(&observation.fingerprint_type, &observation.fingerprint_id)
+            .unwrap_or_else(|e| {
+                log::warn!("[Learner] ⚠️ Failed to read tombstone: {}", e);
+                None
+            });
+        if let Some(tombstone) = &tombstone {
+            let required = tombstone.required_observations(self.learning_threshold);
+            if observation.observation_count < required {
+                log::debug!(
+                    "[Learner] {}:{} is tombstoned ({}), {} of {} observations",
+                    observation.fingerprint_type,
+                    observation.fingerprint_id,
+                    tombstone.reason,
+                    observation.observation_count,
+                    required
+                );
+                return;
+            }
+        }
+
         // Store stable fingerprint in database as a candidate signature pending review
         // Use unwrap_or to handle potential overflow when converting u64 to u32
         let observation_count_u32 = observation.observation_count.try_into().unwrap_or(u32::MAX);
-        match self.db.store_candidate_fingerprint(
+        let mut notes = format!(
+            "Auto-detected stable fingerprint with count {} and stability {:.2}",
+            observation.observation_count, observation.stability_score
+        );
+        // Attach enforcement evidence so reviewers see whether the gateway already blocked it
+        let fingerprint = FingerprintRef::new(
+            observation.fingerprint_type.clone(),
+            observation.fingerprint_id.clone(),
+        );
+        if let Ok(summary) = weak_label_summary(&self.db, &fingerprint) {
+            if summary.count > 0 {
+                notes.push_str(&format!(
+                    "; {} enforcement labels (prior {:.2})",
+                    summary.count,
+                    summary.prior()
+                ));
+            }
+        }
+        if let Some(tombstone) = &tombstone {
+            notes.push_str(&format!(
+                "; re-learned after tombstone ({})",
+                tombstone.reason
+            ));
+            if let Err(e) = self
+                .db
+                .record_resurrection(&observation.fingerprint_type, &observation.fingerprint_id)
+            {
+                log::warn!("[Learner] ⚠️ Failed to record resurrection: {}", e);
+            }
+        }
+        let candidate_id = match self.db.store_candidate_fingerprint(
             &observation.fingerprint_type,
             &observation.fingerprint_id,
             observation_count_u32,
             observation.stability_score,
-            Some(&format!(
-                "Auto-detected stable fingerprint with count {} and stability {:.2}",
-                observation.observation_count, observation.stability_score
-            )),
+            Some(&notes),
         ) {
             Ok(candidate_id) => {
                 log::info!(
                     "[Learner] ✅ Successfully stored candidate fingerprint #{} for review",
                     candidate_id
                 );
+                Some(candidate_id)
             }
             Err(e) => {
                 log::warn!("[Learner] ⚠️ Failed to store candidate fingerprint: {}", e);
+                None
             }
+        };
+        events::global().publish(FingerprintLearned {
+            fingerprint_type: observation.fingerprint_type.clone(),
+            fingerprint_id: observation.fingerprint_id.clone(),
+            observation_count: observation.observation_count,
+            stability_score: observation.stability_score,
+            candidate_id,
+        });
+    }
+
+    /// Prior that the client behind a result is abusive, learned from enforcement labels
+    ///
+    /// Takes the strongest prior over the TLS, HTTP and TCP layers; 0.0 when no layer
+    /// has been labelled.
+    pub fn weak_label_prior(&self, result: &PassiveAnalysisResult) -> f64 {
+        let mut fingerprints = Vec::new();
+        if let Some(tls) = &result.tls {
+            fingerprints.push(FingerprintRef::new("tls", tls.id()));
         }
+        if let Some(http) = &result.http {
+            fingerprints.push(FingerprintRef::new("http", http.id()));
+        }
+        if let Some(tcp) = &result.tcp {
+            fingerprints.push(FingerprintRef::new("tcp", tcp.id()));
+        }
+
+        fingerprints
+            .iter()
+            .filter_map(|fingerprint| weak_label_summary(&self.db, fingerprint).ok())
+            .map(|summary| summary.prior())
+            .fold(0.0, f64::max)
+    }
+
+    /// Share observations and classifications with other gateways
+    pub fn set_fleet_store(&mut self, fleet: Arc<FleetStore>) {
+        self.fleet = Some(fleet);
+    }
+
+    /// Publish local observation counts to the fleet store
+    ///
+    /// Fingerprints that are not ready locally but reach the learning
+    /// conditions with the fleet-wide count are learned here. Returns how
+    /// many were learned; meant to be called periodically.
+    pub fn publish_observations(&self) -> usize {
+        let Some(fleet) = &self.fleet else {
+            return 0;
+        };
+        let observations: Vec<UnknownFingerprintObservation> = self
+            .observations
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+
+        let mut learned = 0;
+        for local in observations {
+            let shared = match fleet.record_observation(&local) {
+                Ok(shared) => shared,
+                Err(e) => {
+                    log::warn!("[Learner] ⚠️ Failed to publish to fleet store: {}", e);
+                    continue;
+                }
+            };
+            // locally ready fingerprints are learned as they are observed
+            if local.observation_count >= self.learning_threshold || shared.status() == "rejected" {
+                continue;
+            }
+            let observation_count = shared.observation_count();
+            let first_seen = shared.first_seen().unwrap_or(local.first_seen);
+            let last_seen = shared.last_seen().unwrap_or(local.last_seen);
+            let fleet_observation = UnknownFingerprintObservation {
+                observation_count,
+                first_seen,
+                last_seen,
+                stability_score: self.stability_score(observation_count, first_seen, last_seen),
+                ..local
+            };
+            if fleet_observation.observation_count >= self.learning_threshold
+                && fleet_observation.stability_score >= self.min_stability_score
+            {
+                self.learn_new_fingerprint(&fleet_observation);
+                learned += 1;
+            }
+        }
+        learned
+    }
+
+    /// Use `clock` instead of the system clock
+    pub fn set_clock(&mut self, clock: SharedClock) {
+        self.clock = clock;
     }
 
     /// Set learning threshold
@@ -280,7 +452,7 @@ impl SelfLearningAnalyzer {
 
     /// Cleanup expired observation records
     pub fn cleanup_expired_observations(&self) {
-        let now = current_unix_timestamp();
+        let now = self.clock.unix_secs();
         let expired_keys: Vec<String> = self
             .observations
             .iter()
@@ -10,6 +10,23 @@
 //! - ✅ **Storage analysis** (`storage`): Detect storage-based fingerprinting attempts
 //! - ✅ **API noise injection** (`api_noise`): Canvas and audio fingerprint obfuscation
 //! - **Threat hunting** (`hunting`): Honeypot and behavior analysis
+//! - **Fingerprint index** (`fingerprint_index`): Exact, prefix and component-wise JA4/JA3 search over the database
+//! - **Weak labels** (`labels`): Gateway enforcement outcomes fed back into the database
+//! - **Replication** (`replication`): Warm-standby copy of the database fed by a change log, with promotion
+//! - **Shared verdict cache** (`shared_cache`): JA4 verdicts shared across worker processes over a Unix socket
+//! - **Backfill** (`backfill`): Re-score historical flows with the current configuration as new verdict versions
+//! - **Timeline** (`timeline`): Time-ordered history of a fingerprint or identity across stores
+//! - **AF_XDP capture** (`capture`, `xdp` feature): Per-queue kernel-bypass capture with drop statistics on Linux
+//! - **Packet evidence** (`evidence`): Rolling PCAPNG files of high-risk flows with a flow → offset index
+//! - **Tombstones** (`tombstone`): Soft-deleted fingerprints with reason codes, re-learning evidence and retention
+//! - **Active probing** (`active`): Ordered probe plans against servers, matched to web server / CDN / WAF signatures
+//! - **Traffic simulation** (`simulation`): Synthetic browser/bot/attack mixtures for testing defenses
+//! - **Fleet store** (`distributed`): Candidate fingerprints, observation counts and classifications shared across gateways through Redis or another KV store
+//! - **Firewall enforcement** (`enforcement`): Block / rate-limit decisions applied as nftables or pf rules with TTL expiry and a rollback journal
+//! - **IDS export** (`ids_export`): Approved JA3/JA4 and HTTP header fingerprints as Suricata / Snort rules with confidence and first/last-seen metadata
+//! - **Session hijack detection** (`session_guard`): Sessions bound to their JA4 / User-Agent / hardware fingerprint, with alerts or termination on material changes and tolerance for browser updates and IP roaming
+//! - **Velocity checks** (`velocity`): Impossible travel and abnormal request / fingerprint velocity per linked identity, with features for risk fusion
+//! - **Threat intel** (`threat_intel`): STIX 2.1 bundles of learned fingerprints and a TAXII 2.1 client that publishes them and ingests external JA3/JA4 feeds
 //!
 //! ## Architecture
 //!
@@ -20,26 +37,133 @@
 //!   Analysis      Scoring      Storage     Storage
 //! ```
 
+pub mod active;
 pub mod anomaly;
 pub mod api_noise;
+pub mod backfill;
+pub mod capture;
 pub mod database;
+pub mod distributed;
+pub mod enforcement;
+pub mod evidence;
+pub mod fingerprint_index;
 pub mod hunting;
+pub mod ids_export;
+pub mod labels;
 pub mod learner;
 pub mod passive;
+pub mod replication;
+pub mod session_guard;
+#[cfg(unix)]
+pub mod shared_cache;
+pub mod simulation;
 pub mod storage;
+pub mod threat_intel;
+pub mod timeline;
 pub mod timing;
+pub mod tombstone;
+pub mod velocity;
 
+pub use active::{
+    ActiveProber, ProbePlan, ProbeReport, ProberConfig, SignatureDatabase, StackKind, StackMatch,
+};
 pub use anomaly::{AnomalyDetector, ContradictionDetector};
 pub use api_noise::CanvasNoiseGenerator;
-pub use database::{CandidateFingerprint, CandidateStats, FingerprintDatabase};
+pub use backfill::{
+    BackfillJob, BackfillReport, BackfillRequest, BackfillStatus, FlowScorer, FlowVerdict,
+    VerdictChange,
+};
+pub use capture::{CaptureEngine, CaptureObserver};
+#[cfg(all(feature = "xdp", target_os = "linux"))]
+pub use capture::{XdpAttachMode, XdpCapture, XdpCaptureStats, XdpConfig, XdpQueueStats};
+pub use database::{CandidateFingerprint, CandidateStats, FingerprintDatabase, WeakLabelSummary};
+#[cfg(feature = "redis")]
+pub use distributed::RedisKvStore;
+pub use distributed::{
+    Classification, FleetStore, KvStore, MemoryKvStore, NodeObservation, SharedCandidate,
+};
+pub use enforcement::{
+    EnforcementConfig, EnforcementError, EnforcingProtector, FirewallAction, FirewallEnforcer,
+    FirewallRule, NftablesBackend, PfBackend,
+};
+pub use evidence::{
+    EvidenceConfig, EvidenceIndex, EvidenceLocation, EvidenceStats, EvidenceWriter, FlowKey,
+};
+pub use fingerprint_index::{IndexEntry, IndexMatch, IndexPage, IndexQuery, Ja4Components};
 pub use hunting::ThreatHunter;
+pub use ids_export::{ExportedFingerprint, Indicator, RuleExporter, RuleFormat, RuleSet};
+pub use labels::{
+    EnforcementEvent, EnforcementOutcome, FingerprintRef, LabelPropagator, LabelSink,
+};
 pub use learner::{FingerprintEvaluator, FingerprintObserver};
 pub use passive::{
     HttpFingerprint, Packet, PacketParser, PassiveAnalysisResult, PassiveAnalyzer, PassiveError,
     TcpFingerprint, TlsFingerprint,
 };
+pub use replication::{
+    ChangeBatch, ChangeOp, ChangeRecord, ChangeSource, ReplicationRole, ReplicationStatus,
+    Replicator,
+};
+pub use session_guard::{
+    ChangeKind, SessionAction, SessionAssessment, SessionChange, SessionFingerprint, SessionGuard,
+    SessionGuardConfig,
+};
+#[cfg(unix)]
+pub use shared_cache::{Verdict, VerdictAction, VerdictBroker, VerdictCacheClient};
+pub use simulation::{
+    SimulationConfig, SimulationReport, SyntheticFlow, TrafficClass, TrafficLabel,
+    TrafficSimulator, TrafficSource,
+};
 pub use storage::StorageAnalyzer;
+pub use threat_intel::{
+    import_objects, learned_bundle, ImportReport, TaxiiClient, TaxiiError, ThreatIntelIndicator,
+};
+pub use timeline::{
+    Timeline, TimelineEvent, TimelineEventKind, TimelinePage, TimelineQuery, TimelineSource,
+    TimelineSubject,
+};
 pub use timing::TimingProtector;
+pub use tombstone::{PurgeReport, Tombstone, TombstoneReason, TombstoneRetention};
+pub use velocity::{
+    FindingKind, GeoLocation, VelocityAnalyzer, VelocityAssessment, VelocityConfig,
+    VelocityFeatures, VelocityFinding, VelocityObservation,
+};
+
+/// Analyzers and backends compiled into this crate
+pub fn capabilities() -> Vec<fingerprint_core::Capability> {
+    use fingerprint_core::CapabilityKind::*;
+    let set =
+        fingerprint_core::CapabilitySet::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+            .builtin(Analyzer, "passive-tls")
+            .builtin(Analyzer, "passive-http")
+            .builtin(Analyzer, "passive-tcp")
+            .builtin(Analyzer, "p0f")
+            .builtin(Analyzer, "active-probe")
+            .builtin(Analyzer, "session-hijack")
+            .builtin(Analyzer, "velocity")
+            .builtin(Backend, "sqlite")
+            .builtin(Backend, "pcap")
+            .builtin(Protocol, "taxii-2.1")
+            .optional(Backend, "af-xdp", "xdp", cfg!(feature = "xdp"))
+            .optional(
+                Backend,
+                "redis-fleet-store",
+                "redis",
+                cfg!(feature = "redis"),
+            );
+    let set = if cfg!(target_os = "linux") {
+        set.builtin(Backend, "nftables")
+    } else if cfg!(any(
+        target_os = "macos",
+        target_os = "freebsd",
+        target_os = "openbsd"
+    )) {
+        set.builtin(Backend, "pf")
+    } else {
+        set
+    };
+    set.into_vec()
+}
 
 #[cfg(test)]
 mod tests {
@@ -16,10 +16,13 @@
 /// // Check incoming request
 /// // let result = middleware.check_request(&request).await;
 /// ```
+use crate::evidence::{EvidenceWriter, FlowKey};
 use crate::passive::consistency::{ConsistencyAnalyzer, ConsistencyViolation};
+use fingerprint_core::events::{self, AlertRaised};
 use fingerprint_core::ja4::ConsistencyReport;
 use fingerprint_core::system::{NetworkFlow, TrafficDirection};
 use std::net::IpAddr;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// Configuration for consistency check middleware
@@ -143,12 +146,23 @@ impl ConsistencyCheckResult {
 pub struct ConsistencyCheckMiddleware {
     analyzer: ConsistencyAnalyzer,
     config: ConsistencyCheckConfig,
+    evidence: Option<Arc<EvidenceWriter>>,
 }
 
 impl ConsistencyCheckMiddleware {
     /// Create new middleware with analyzer and config
     pub fn new(analyzer: ConsistencyAnalyzer, config: ConsistencyCheckConfig) -> Self {
-        Self { analyzer, config }
+        Self {
+            analyzer,
+            config,
+            evidence: None,
+        }
+    }
+
+    /// Report risk scores to `writer`, which keeps packets of flows at or above its threshold
+    pub fn with_evidence(mut self, writer: Arc<EvidenceWriter>) -> Self {
+        self.evidence = Some(writer);
+        self
     }
 
     /// Create new middleware with default config
@@ -171,22 +185,32 @@ impl ConsistencyCheckMiddleware {
 
         // Calculate risk score based on discrepancies
         let risk_score = self.calculate_risk_score(&report);
+        if let Some(evidence) = &self.evidence {
+            if let Err(e) = evidence.flag(FlowKey::from_context(&flow.context), risk_score) {
+                log::warn!("evidence for {} not recorded: {}", flow.flow_id(), e);
+            }
+        }
 
         // Check if should block
         if self.config.block_high_risk && risk_score >= self.config.block_threshold {
-            return ConsistencyCheckResult::blocked(
-                format!(
-                    "High risk score: {} (threshold: {})",
-                    risk_score, self.config.block_threshold
-                ),
-                risk_score,
+            let reason = format!(
+                "High risk score: {} (threshold: {})",
+                risk_score, self.config.block_threshold
             );
+            self.publish_alert(flow, &report, risk_score, "critical", &reason);
+            return ConsistencyCheckResult::blocked(reason, risk_score);
         }
 
         // Check if has violations
         let violations = self.extract_violations(&report);
 
         if !violations.is_empty() && risk_score >= self.config.alert_threshold {
+            let message = format!(
+                "{} consistency violation(s), risk score {}",
+                violations.len(),
+                risk_score
+            );
+            self.publish_alert(flow, &report, risk_score, "warning", &message);
             return ConsistencyCheckResult {
                 passed: false,
                 risk_score,
@@ -247,6 +271,33 @@ impl ConsistencyCheckMiddleware {
         flow
     }
 
+    /// Announce an alert for `flow` on the global event bus
+    fn publish_alert(
+        &self,
+        flow: &NetworkFlow,
+        report: &ConsistencyReport,
+        risk_score: u8,
+        severity: &str,
+        message: &str,
+    ) {
+        if !events::global().has_subscribers::<AlertRaised>() {
+            return;
+        }
+        let mut alert = AlertRaised::new("defense.consistency", severity, "suspicious", message);
+        alert.metadata.insert(
+            "source_ip".to_string(),
+            serde_json::json!(flow.context.source_ip.to_string()),
+        );
+        alert
+            .metadata
+            .insert("risk_score".to_string(), serde_json::json!(risk_score));
+        alert.metadata.insert(
+            "discrepancy_codes".to_string(),
+            serde_json::json!(report.discrepancy_codes),
+        );
+        events::publish_alert(alert);
+    }
+
     /// Calculate risk score from consistency report
     fn calculate_risk_score(&self, report: &ConsistencyReport) -> u8 {
         let base_score = report.score;
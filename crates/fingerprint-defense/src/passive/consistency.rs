@@ -3,6 +3,7 @@
 //! crossValidate TCP, TLS and HTTP layercountdata, detectdeceivebehavior and abnormal机er人.
 //! implementation完整of跨层consistency审计，detectUser-Agent与底层TCPstack、TLSversionofconsistency
 
+use chrono::{DateTime, Utc};
 use fingerprint_core::fingerprint::FingerprintType;
 use fingerprint_core::ja4::ConsistencyReport;
 use fingerprint_core::system::NetworkFlow;
@@ -130,6 +131,14 @@ impl ConsistencyAnalyzer {
 impl ConsistencyAnalyzer {
     /// analyze流量of多层consistency
     pub fn analyze_flow(&self, flow: &NetworkFlow) -> ConsistencyReport {
+        self.analyze_flow_at(flow, Utc::now())
+    }
+
+    /// Analyze a flow as of `now`
+    ///
+    /// Re-scoring stored flows passes their capture time, so they are not
+    /// flagged as stale just for being historical.
+    pub fn analyze_flow_at(&self, flow: &NetworkFlow, now: DateTime<Utc>) -> ConsistencyReport {
         let mut report = ConsistencyReport::new();
 
         let tls_fingerprints = flow.get_fingerprints_by_type(FingerprintType::Tls);
@@ -150,7 +159,7 @@ impl ConsistencyAnalyzer {
         self.check_ja4_plus_consistency(flow, &mut report);
 
         // 4. validatetime戳consistency（防重放攻击）
-        self.check_timestamp_consistency(flow, &mut report);
+        self.check_timestamp_consistency(flow, now, &mut report);
 
         report
     }
@@ -184,8 +193,9 @@ impl ConsistencyAnalyzer {
         // checkTCPstack与User-Agent声明ofconsistency
         if !self.is_os_consistent(&tcp_os_hint, &ua_lower) {
             // 注意：这里simplifyprocess，实际应该从fingerprint中extract相关info
-            report.add_discrepancy(
-                format!("TCP栈检测为{}，但User-Agent声明可能存在不一致", tcp_os_hint),
+            report.add_coded_discrepancy(
+                "consistency.tcp_os_mismatch",
+                &[("os", &tcp_os_hint)],
                 70, // 中高risk
             );
         }
@@ -225,11 +235,9 @@ impl ConsistencyAnalyzer {
 
         let browser_key = browser_name.to_lowercase();
         if !self.is_tls_version_compatible(&browser_key, &tls_version) {
-            report.add_discrepancy(
-                format!(
-                    "TLS版本{}与浏览器({})可能存在兼容性问题",
-                    tls_version, browser_name
-                ),
+            report.add_coded_discrepancy(
+                "consistency.tls_browser_mismatch",
+                &[("version", &tls_version), ("browser", &browser_name)],
                 60, // 中等risk
             );
         }
@@ -260,11 +268,9 @@ impl ConsistencyAnalyzer {
             if let Some(tls_client) = self.extract_client_from_ja4(&ja4_id) {
                 if let Some(http_client) = self.extract_client_from_ja4h(&ja4h_id) {
                     if !self.is_client_fingerprint_consistent(&tls_client, &http_client) {
-                        report.add_discrepancy(
-                            format!(
-                                "JA4指纹({})与JA4H指纹({})指示的客户端特征不一致，可能是指纹伪造",
-                                ja4_id, ja4h_id
-                            ),
+                        report.add_coded_discrepancy(
+                            "consistency.ja4_ja4h_mismatch",
+                            &[("ja4", &ja4_id), ("ja4h", &ja4h_id)],
                             80, // 高risk
                         );
                     }
@@ -287,11 +293,9 @@ impl ConsistencyAnalyzer {
             if let Some(tcp_os) = self.extract_os_from_tcp_fingerprint(&tcp_fp_id) {
                 if let Some(ja4_client) = self.extract_client_from_ja4(&ja4_id) {
                     if !self.is_os_client_consistent(&tcp_os, &ja4_client) {
-                        report.add_discrepancy(
-                            format!(
-                                "TCP指纹指示的操作系统({})与JA4指纹指示的客户端({})不一致",
-                                tcp_os, ja4_client.name
-                            ),
+                        report.add_coded_discrepancy(
+                            "consistency.tcp_ja4_client_mismatch",
+                            &[("os", &tcp_os), ("client", &ja4_client.name)],
                             75, // 中高risk
                         );
                     }
@@ -418,33 +422,36 @@ impl ConsistencyAnalyzer {
     }
 
     /// checktime戳consistency
-    fn check_timestamp_consistency(&self, flow: &NetworkFlow, report: &mut ConsistencyReport) {
-        use chrono::Utc;
-
+    fn check_timestamp_consistency(
+        &self,
+        flow: &NetworkFlow,
+        now: DateTime<Utc>,
+        report: &mut ConsistencyReport,
+    ) {
         // get当前time（Unixtime戳）
-        let now = Utc::now().timestamp() as u64;
+        let now = now.timestamp() as u64;
         let flow_timestamp = flow.context.timestamp.timestamp() as u64;
 
         // check流量time戳是否在未来（exception）
         if flow_timestamp > now + 60 {
-            report.add_discrepancy(
-                format!(
-                    "流量时间戳({})比当前时间晚{}秒，可能存在时间同步问题或重放攻击",
-                    flow_timestamp,
-                    flow_timestamp - now
-                ),
+            report.add_coded_discrepancy(
+                "consistency.timestamp_in_future",
+                &[
+                    ("timestamp", &flow_timestamp),
+                    ("seconds", &(flow_timestamp - now)),
+                ],
                 90, // 高risk
             );
         }
 
         // check流量time戳是否过于陈旧（超过1小时）
         if flow_timestamp < now.saturating_sub(3600) {
-            report.add_discrepancy(
-                format!(
-                    "流量时间戳({})比当前时间早{}秒，可能是延迟处理的流量或重放攻击",
-                    flow_timestamp,
-                    now - flow_timestamp
-                ),
+            report.add_coded_discrepancy(
+                "consistency.timestamp_stale",
+                &[
+                    ("timestamp", &flow_timestamp),
+                    ("seconds", &(now - flow_timestamp)),
+                ],
                 60, // 中等risk
             );
         }
@@ -9,6 +9,8 @@ pub struct HttpFingerprint {
     pub version: String,
     /// Header order
     pub header_order: Vec<String>,
+    /// Headers in order, names lowercased
+    pub headers: Vec<(String, String)>,
     /// User-Agent
     pub user_agent: Option<String>,
     /// Accept headers
@@ -59,13 +61,44 @@ impl HttpAnalyzer {
 
     /// Analyze HTTP request from raw bytes
     ///
-    /// Note: This method returns `None` for most cases as full HTTP parsing
-    /// requires stateful analysis. Use `fingerprint_from_headers()` when
-    /// headers have already been extracted from the packet.
-    pub fn analyze_bytes(&self, _data: &[u8]) -> Option<HttpFingerprint> {
-        // Simplified implementation - passive HTTP analysis without full parsing
-        // For production use, implement proper HTTP request parsing or use existing libraries
-        None
+    /// `data` must start at the request line and hold the complete header
+    /// block; packets split mid-request are reassembled first by
+    /// [`PassiveAnalyzer`](crate::passive::PassiveAnalyzer). Only HTTP/1.x
+    /// requests are recognized, the HTTP/2 preface returns `None`.
+    pub fn analyze_bytes(&self, data: &[u8]) -> Option<HttpFingerprint> {
+        let end = data.windows(4).position(|w| w == b"\r\n\r\n")?;
+        let head = std::str::from_utf8(&data[..end]).ok()?;
+        let mut lines = head.split("\r\n");
+
+        let mut request_line = lines.next()?.split(' ');
+        let method = request_line.next()?;
+        let _target = request_line.next()?;
+        let version = request_line.next()?.strip_prefix("HTTP/")?;
+        if method.is_empty()
+            || !method.bytes().all(|b| b.is_ascii_uppercase())
+            || !version.starts_with("1.")
+        {
+            return None;
+        }
+
+        let mut header_order = Vec::new();
+        let mut ordered = Vec::new();
+        let mut headers = HashMap::new();
+        for line in lines {
+            let (name, value) = line.split_once(':')?;
+            let name = name.trim().to_ascii_lowercase();
+            let value = value.trim().to_string();
+            header_order.push(name.clone());
+            ordered.push((name.clone(), value.clone()));
+            headers.entry(name).or_insert(value);
+        }
+
+        Some(HttpFingerprint {
+            version: version.to_string(),
+            header_order,
+            headers: ordered,
+            ..self.fingerprint_from_headers(&headers)
+        })
     }
 
     /// Extract HTTP fingerprint from headers
@@ -75,6 +108,10 @@ impl HttpAnalyzer {
         HttpFingerprint {
             version: "1.1".to_string(),
             header_order,
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
             user_agent: headers.get("user-agent").cloned(),
             accept: headers.get("accept").cloned(),
             accept_language: headers.get("accept-language").cloned(),
@@ -91,3 +128,65 @@ impl Default for HttpAnalyzer {
         Self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_complete_request_heads_only() {
+        let analyzer = HttpAnalyzer::new().unwrap();
+        let request =
+            b"GET / HTTP/1.1\r\nHost: example.com\r\nUser-Agent: curl/8.5.0\r\nAccept: */*\r\n\r\n";
+        let fp = analyzer.analyze_bytes(request).unwrap();
+        assert_eq!(fp.version, "1.1");
+        assert_eq!(fp.header_order, ["host", "user-agent", "accept"]);
+        assert_eq!(fp.user_agent.as_deref(), Some("curl/8.5.0"));
+
+        // header block still incomplete
+        assert!(analyzer.analyze_bytes(&request[..30]).is_none());
+        assert!(analyzer
+            .analyze_bytes(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n")
+            .is_none());
+    }
+
+    #[test]
+    fn flags_requests_forwarded_by_an_edge() {
+        use crate::passive::packet::{Packet, TcpFlags, TcpHeader};
+        use crate::passive::PassiveAnalyzer;
+        use fingerprint_core::edge::EdgeVendor;
+
+        let request = b"GET / HTTP/1.1\r\nHost: example.com\r\nCF-Connecting-IP: 203.0.113.9\r\nCDN-Loop: cloudflare\r\n\r\n";
+        let packet = Packet {
+            src_ip: "162.158.90.1".parse().unwrap(),
+            dst_ip: "10.0.0.2".parse().unwrap(),
+            src_port: Some(40000),
+            dst_port: Some(80),
+            protocol: 6,
+            ttl: 60,
+            ip_flags: 0x02,
+            data: Vec::new(),
+            payload: request.to_vec(),
+            tcp_header: Some(TcpHeader {
+                src_port: 40000,
+                dst_port: 80,
+                seq: 1,
+                ack: 1,
+                data_offset: 5,
+                flags: TcpFlags {
+                    ack: true,
+                    psh: true,
+                    ..Default::default()
+                },
+                window: 502,
+                checksum: 0,
+                urgent_ptr: 0,
+                options: Vec::new(),
+            }),
+        };
+        let result = PassiveAnalyzer::new().unwrap().analyze(&packet);
+        let edge = result.edge.unwrap();
+        assert_eq!(edge.vendor, EdgeVendor::Cloudflare);
+        assert_eq!(edge.evidence.len(), 3);
+    }
+}
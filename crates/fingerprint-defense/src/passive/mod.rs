@@ -7,24 +7,34 @@ pub mod http;
 pub mod p0f;
 pub mod p0f_parser;
 pub mod packet;
+pub mod reassembly;
 pub mod tcp;
 pub mod tls;
 
 pub use consistency::ConsistencyAnalyzer;
 
 pub use http::{HttpAnalyzer, HttpFingerprint};
+pub use p0f::{P0fDatabase, P0fError, P0fMatch, P0fStore};
 pub use packet::{Packet, PacketParser};
+pub use reassembly::{ReassemblyConfig, ReassemblyStats, StreamKey, TcpReassembler};
 pub use tcp::{TcpAnalyzer, TcpFeatures, TcpFingerprint};
-pub use tls::{TlsAnalyzer, TlsFingerprint};
+pub use tls::{ServerCertificate, TlsAnalyzer, TlsFingerprint, TlsServerFingerprint};
 
 // use core insystem-level abstractions
+use fingerprint_core::edge::{EdgeClassifier, EdgeDetection, EdgeObservation};
 use fingerprint_core::system::{NetworkFlow, ProtocolType, SystemContext, TrafficDirection};
+use std::sync::Mutex;
+use std::time::Instant;
 
 /// passiveanalysiser (multipleprotocol)
 pub struct PassiveAnalyzer {
     tcp_analyzer: TcpAnalyzer,
     http_analyzer: HttpAnalyzer,
     tls_analyzer: TlsAnalyzer,
+    /// TCP reassembly for HTTP/TLS messages split across segments
+    reassembler: Option<Mutex<TcpReassembler>>,
+    /// CDN / WAF vendor identification for inbound traffic
+    edge_classifier: EdgeClassifier,
 }
 
 impl PassiveAnalyzer {
@@ -34,9 +44,42 @@ impl PassiveAnalyzer {
             tcp_analyzer: TcpAnalyzer::new().map_err(PassiveError::Tcp)?,
             http_analyzer: HttpAnalyzer::new().map_err(PassiveError::Http)?,
             tls_analyzer: TlsAnalyzer::new().map_err(PassiveError::Tls)?,
+            reassembler: Some(Mutex::new(TcpReassembler::default())),
+            edge_classifier: EdgeClassifier::builtin().clone(),
         })
     }
 
+    /// Reassemble TCP streams with `config` before HTTP/TLS analysis
+    pub fn with_reassembly(mut self, config: ReassemblyConfig) -> Self {
+        self.reassembler = Some(Mutex::new(TcpReassembler::new(config)));
+        self
+    }
+
+    /// Analyze every packet's payload on its own, without TCP reassembly
+    pub fn without_reassembly(mut self) -> Self {
+        self.reassembler = None;
+        self
+    }
+
+    /// Identify SYN / SYN+ACK senders with a (hot-reloadable) p0f database
+    pub fn with_p0f(mut self, store: P0fStore) -> Self {
+        self.tcp_analyzer = self.tcp_analyzer.with_p0f(store);
+        self
+    }
+
+    /// Identify edge vendors with custom rules or ranges
+    pub fn with_edge_classifier(mut self, classifier: EdgeClassifier) -> Self {
+        self.edge_classifier = classifier;
+        self
+    }
+
+    /// Reassembly counters, `None` when reassembly is disabled
+    pub fn reassembly_stats(&self) -> Option<ReassemblyStats> {
+        self.reassembler
+            .as_ref()
+            .map(|r| r.lock().unwrap_or_else(|e| e.into_inner()).stats())
+    }
+
     /// analysiscountpacket
     pub fn analyze(&self, packet: &Packet) -> AnalysisResult {
         let mut result = AnalysisResult::default();
@@ -46,6 +89,48 @@ impl PassiveAnalyzer {
             result.tcp = Some(tcp_result);
         }
 
+        // HTTP/TLS analysis runs on the reassembled message when the packet is TCP
+        match (&self.reassembler, StreamKey::from_packet(packet)) {
+            (Some(reassembler), Some(key)) => {
+                let mut reassembler = reassembler.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(message) = reassembler.push(packet, Instant::now()) {
+                    self.analyze_message(&message, &mut result);
+                    if result.http.is_some() || result.tls.is_some() || result.tls_server.is_some()
+                    {
+                        reassembler.consume(&key);
+                    }
+                }
+            }
+            _ => self.analyze_message(packet, &mut result),
+        }
+
+        result.edge = self.classify_edge(packet, &result);
+
+        result
+    }
+
+    /// CDN / WAF the packet came through, from its source address, request
+    /// headers and server certificate
+    fn classify_edge(&self, packet: &Packet, result: &AnalysisResult) -> Option<EdgeDetection> {
+        let mut observation = EdgeObservation::new().ip(packet.src_ip);
+        if let Some(http) = &result.http {
+            for (name, value) in &http.headers {
+                observation = observation.header(name, value);
+            }
+        }
+        if let Some(issuer) = result
+            .tls_server
+            .as_ref()
+            .and_then(|tls| tls.certificates.first())
+            .and_then(|cert| cert.issuer.as_deref())
+        {
+            observation = observation.cert_issuer(issuer);
+        }
+        self.edge_classifier.classify(&observation)
+    }
+
+    /// HTTP and TLS analysis of one application-layer message
+    fn analyze_message(&self, packet: &Packet, result: &mut AnalysisResult) {
         // HTTP analysis
         if let Some(http_result) = self.http_analyzer.analyze(packet) {
             result.http = Some(http_result);
@@ -55,8 +140,7 @@ impl PassiveAnalyzer {
         if let Some(tls_result) = self.tls_analyzer.analyze(packet) {
             result.tls = Some(tls_result);
         }
-
-        result
+        result.tls_server = self.tls_analyzer.analyze_server(packet);
     }
 
     /// analysiscountpacket并return NetworkFlow (newmethod, for system-level protection)
@@ -131,6 +215,10 @@ pub struct AnalysisResult {
     pub tcp: Option<TcpFingerprint>,
     pub http: Option<HttpFingerprint>,
     pub tls: Option<TlsFingerprint>,
+    /// ServerHello flight, for packets sent by the server
+    pub tls_server: Option<TlsServerFingerprint>,
+    /// CDN / WAF the traffic passed through; the peer is then the edge, not the client
+    pub edge: Option<EdgeDetection>,
 }
 
 // exportalias
@@ -1,36 +1,65 @@
 //! p0f signaturedatabaseParse
 //!
-//! Parse p0f.fp formatsignaturedatabasefile.
+//! Parse p0f.fp formatsignaturedatabasefile, match TCP SYN / SYN+ACK traits against
+//! it, and hot-reload custom signature files through [`P0fStore`].
 
-use crate::passive::p0f_parser;
-use crate::passive::tcp::TcpSignature;
+use crate::passive::p0f_parser::{
+    self, IpVersion, MatchType, P0fTcpSignature, PayloadClass, SystemType, WindowSizePattern,
+};
+use crate::passive::tcp::TcpFeatures;
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
 use thiserror::Error;
 
+pub use crate::passive::p0f_parser::P0fHttpSignature;
+
+/// default upper bound on the match distance
+pub const DEFAULT_MAX_DISTANCE: u32 = 8;
+
+/// p0f gives up on TTL distances beyond this many hops
+const MAX_HOPS: u8 = 35;
+
+/// sum of all penalties, used to turn a distance into a score
+const DISTANCE_SCALE: f64 = 20.0;
+
 /// p0f signaturedatabase
+#[derive(Debug, Clone, Default)]
 pub struct P0fDatabase {
-    /// TCP requestsignature
-    tcp_request: HashMap<String, TcpSignature>,
+    /// TCP requestsignature (SYN), in file order
+    tcp_request: Vec<P0fTcpSignature>,
 
-    /// TCP responsesignature
-    tcp_response: HashMap<String, TcpSignature>,
+    /// TCP responsesignature (SYN+ACK), in file order
+    tcp_response: Vec<P0fTcpSignature>,
 
     /// HTTP requestsignature
-    http_request: HashMap<String, P0fHttpSignature>,
+    http_request: Vec<P0fHttpSignature>,
 
     /// HTTP responsesignature
-    http_response: HashMap<String, P0fHttpSignature>,
+    http_response: Vec<P0fHttpSignature>,
+
+    /// `classes =` line
+    classes: Vec<String>,
+
+    /// `[mtu]` section: link type per MTU
+    mtu: HashMap<u16, String>,
 }
 
-/// HTTP signature (p0f format)
-#[derive(Debug, Clone)]
-pub struct P0fHttpSignature {
-    pub id: String,
-    pub label: String,
-    pub user_agent_pattern: Option<String>,
-    pub headers: Vec<String>,
+/// Section of a p0f.fp file
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section {
+    TcpRequest,
+    TcpResponse,
+    HttpRequest,
+    HttpResponse,
+    Mtu,
+    /// sections this loader does not use
+    Other,
 }
 
 impl P0fDatabase {
@@ -41,123 +70,182 @@ impl P0fDatabase {
     }
 
     /// Parse p0f databaseinsidecontain
+    ///
+    /// Malformed signatures are rejected with their line number, so a broken
+    /// custom file never half-loads.
     pub fn parse(content: &str) -> Result<Self, P0fError> {
-        let mut db = Self {
-            tcp_request: HashMap::new(),
-            tcp_response: HashMap::new(),
-            http_request: HashMap::new(),
-            http_response: HashMap::new(),
-        };
-
-        let mut current_section: Option<&str> = None;
-        let mut current_label: Option<String> = None;
-
-        for line in content.lines() {
+        let mut db = Self::default();
+
+        let mut section: Option<Section> = None;
+        let mut label: Option<String> = None;
+        let mut sys: Option<Vec<String>> = None;
+        let mut seen: HashMap<String, usize> = HashMap::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let line_no = index + 1;
+            let syntax = |message: String| P0fError::Syntax {
+                line: line_no,
+                message,
+            };
             let line = line.trim();
 
             // skipemptyexecute and comment
-            if line.is_empty() || line.starts_with('#') {
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
                 continue;
             }
 
             // Checkwhether is newpartial
-            if line.starts_with('[') && line.ends_with(']') {
-                current_section = Some(&line[1..line.len() - 1]);
-                current_label = None;
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = Some(match name {
+                    "tcp:request" => Section::TcpRequest,
+                    "tcp:response" => Section::TcpResponse,
+                    "http:request" => Section::HttpRequest,
+                    "http:response" => Section::HttpResponse,
+                    "mtu" => Section::Mtu,
+                    _ => Section::Other,
+                });
+                label = None;
+                sys = None;
                 continue;
             }
 
-            // Parse label
-            if let Some(stripped) = line.strip_prefix("label = ") {
-                current_label = Some(stripped.trim().to_string());
-                continue;
-            }
+            let (key, value) = line
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or_else(|| syntax(format!("expected `key = value`: {}", line)))?;
 
-            // Parse sig
-            if let Some(stripped) = line.strip_prefix("sig = ") {
-                let sig_value = stripped.trim().to_string();
-
-                // If有 label and sig, tryParse
-                if let Some(label) = &current_label {
-                    if let Some(section) = current_section {
-                        match section {
-                            "tcp:request" => {
-                                if let Ok(tcp_sig) = Self::parse_tcp_signature(label, &sig_value) {
-                                    db.tcp_request.insert(tcp_sig.id.clone(), tcp_sig);
-                                }
-                            }
-                            "tcp:response" => {
-                                if let Ok(tcp_sig) = Self::parse_tcp_signature(label, &sig_value) {
-                                    db.tcp_response.insert(tcp_sig.id.clone(), tcp_sig);
-                                }
-                            }
-                            "http:request" => {
-                                if let Ok(http_sig) = Self::parse_http_signature(label, &sig_value)
-                                {
-                                    db.http_request.insert(http_sig.id.clone(), http_sig);
-                                }
+            match (section, key) {
+                (None, "classes") => {
+                    db.classes = value.split(',').map(|c| c.trim().to_string()).collect();
+                }
+                (None, "ua_os") => {}
+                (None, _) => {
+                    return Err(syntax(format!("unexpected `{}` before any section", key)))
+                }
+                (Some(Section::Other), _) => {}
+                (Some(current), "label") => {
+                    // `[mtu]` labels are free-form link names
+                    if current != Section::Mtu {
+                        p0f_parser::parse_label(value).map_err(|e| syntax(e.to_string()))?;
+                    }
+                    label = Some(value.to_string());
+                    sys = None;
+                }
+                (Some(_), "sys") => {
+                    sys = Some(value.split(',').map(|s| s.trim().to_string()).collect());
+                }
+                (Some(current), "sig") => {
+                    let label = label
+                        .as_deref()
+                        .ok_or_else(|| syntax("`sig` without a preceding `label`".to_string()))?;
+                    if current == Section::Mtu {
+                        let mtu = value
+                            .parse()
+                            .map_err(|_| syntax(format!("invalid MTU: {}", value)))?;
+                        db.mtu.insert(mtu, label.to_string());
+                        continue;
+                    }
+
+                    // several sigs per label are common; keep ids unique
+                    let count = seen.entry(format!("{:?}:{}", current, label)).or_insert(0);
+                    *count += 1;
+                    let suffix = if *count > 1 {
+                        format!("-{}", count)
+                    } else {
+                        String::new()
+                    };
+
+                    match current {
+                        Section::TcpRequest | Section::TcpResponse => {
+                            let mut sig = p0f_parser::parse_tcp_signature(label, value)
+                                .map_err(|e| syntax(e.to_string()))?;
+                            sig.id.push_str(&suffix);
+                            sig.sys = sys.clone();
+                            if current == Section::TcpRequest {
+                                db.tcp_request.push(sig);
+                            } else {
+                                db.tcp_response.push(sig);
                             }
-                            "http:response" => {
-                                if let Ok(http_sig) = Self::parse_http_signature(label, &sig_value)
-                                {
-                                    db.http_response.insert(http_sig.id.clone(), http_sig);
-                                }
+                        }
+                        Section::HttpRequest | Section::HttpResponse => {
+                            let mut sig = p0f_parser::parse_http_signature(label, value)
+                                .map_err(|e| syntax(e.to_string()))?;
+                            sig.id.push_str(&suffix);
+                            sig.sys = sys.clone();
+                            if current == Section::HttpRequest {
+                                db.http_request.push(sig);
+                            } else {
+                                db.http_response.push(sig);
                             }
-                            _ => {}
                         }
+                        Section::Mtu | Section::Other => {}
                     }
                 }
-                continue;
+                (Some(_), _) => return Err(syntax(format!("unknown key `{}`", key))),
             }
         }
 
         Ok(db)
     }
 
-    /// Parse TCP signature (usedetailedParseer)
-    fn parse_tcp_signature(label: &str, sig: &str) -> Result<TcpSignature, P0fError> {
-        // usedetailedParseer
-        let p0f_sig = p0f_parser::parse_tcp_signature(label, sig)
-            .map_err(|e| P0fError::Parse(e.to_string()))?;
-
-        // convert to TcpSignature
-        Ok(p0f_sig.into())
-    }
-
-    /// Parse HTTP signature
-    fn parse_http_signature(label: &str, _sig: &str) -> Result<P0fHttpSignature, P0fError> {
-        // simplifyParse
-        Ok(P0fHttpSignature {
-            id: format!("http-{}", label.replace(':', "-")),
-            label: label.to_string(),
-            user_agent_pattern: None,
-            headers: Vec::new(),
-        })
-    }
-
     /// Get TCP requestsignature
-    pub fn get_tcp_request(&self, id: &str) -> Option<&TcpSignature> {
-        self.tcp_request.get(id)
+    pub fn get_tcp_request(&self, id: &str) -> Option<&P0fTcpSignature> {
+        self.tcp_request.iter().find(|s| s.id == id)
     }
 
     /// Getall TCP requestsignature
-    pub fn get_all_tcp_request(&self) -> Vec<&TcpSignature> {
-        self.tcp_request.values().collect()
+    pub fn get_all_tcp_request(&self) -> &[P0fTcpSignature] {
+        &self.tcp_request
     }
 
     /// Getall TCP responsesignature
-    pub fn get_all_tcp_response(&self) -> Vec<&TcpSignature> {
-        self.tcp_response.values().collect()
+    pub fn get_all_tcp_response(&self) -> &[P0fTcpSignature] {
+        &self.tcp_response
     }
 
     /// Getall HTTP requestsignature
-    pub fn get_all_http_request(&self) -> Vec<&P0fHttpSignature> {
-        self.http_request.values().collect()
+    pub fn get_all_http_request(&self) -> &[P0fHttpSignature] {
+        &self.http_request
     }
 
     /// Getall HTTP responsesignature
-    pub fn get_all_http_response(&self) -> Vec<&P0fHttpSignature> {
-        self.http_response.values().collect()
+    pub fn get_all_http_response(&self) -> &[P0fHttpSignature] {
+        &self.http_response
+    }
+
+    /// Link type for an MTU from the `[mtu]` section
+    pub fn link_type(&self, mtu: u16) -> Option<&str> {
+        self.mtu.get(&mtu).map(String::as_str)
+    }
+
+    /// Closest TCP signature within `max_distance`
+    ///
+    /// SYN+ACK traits are matched against `[tcp:response]`, everything else
+    /// against `[tcp:request]`. Ties go to the signature listed first, as in p0f.
+    pub fn match_tcp(&self, features: &TcpFeatures, max_distance: u32) -> Option<P0fMatch> {
+        let signatures = if features.syn_ack {
+            &self.tcp_response
+        } else {
+            &self.tcp_request
+        };
+
+        let mut best: Option<(&P0fTcpSignature, u32)> = None;
+        for sig in signatures {
+            let Some(distance) = sig.distance(features) else {
+                continue;
+            };
+            if distance <= max_distance && best.is_none_or(|(_, d)| distance < d) {
+                best = Some((sig, distance));
+            }
+        }
+
+        best.map(|(sig, distance)| P0fMatch {
+            signature: sig.clone(),
+            distance,
+            hops: sig.ittl.saturating_sub(features.ttl),
+            score: (1.0 - distance as f64 / DISTANCE_SCALE).max(0.0),
+            generic: sig.label.match_type == MatchType::Generic,
+        })
     }
 
     /// Getstatisticsinfo
@@ -171,6 +259,123 @@ impl P0fDatabase {
     }
 }
 
+impl P0fTcpSignature {
+    /// Distance between the signature and observed traits, `None` when a
+    /// hard field (IP version, TTL) rules it out
+    pub fn distance(&self, features: &TcpFeatures) -> Option<u32> {
+        match (self.version, features.ip_version) {
+            (IpVersion::V4, 6) | (IpVersion::V6, 4) => return None,
+            _ => {}
+        }
+
+        let mut distance = 0;
+
+        if !self.bad_ttl {
+            if features.ttl > self.ittl {
+                return None;
+            }
+            if self.ittl - features.ttl > MAX_HOPS {
+                distance += 2;
+            }
+        }
+
+        if self.mss.is_some_and(|mss| Some(mss) != features.mss) {
+            distance += 2;
+        }
+
+        if !self.window_matches(features) {
+            distance += 3;
+        }
+
+        if self
+            .window_scale
+            .is_some_and(|scale| Some(scale) != features.window_scale)
+        {
+            distance += 2;
+        }
+
+        let layout_matches = self.options.len() == features.options.len()
+            && self
+                .options
+                .iter()
+                .zip(&features.options)
+                .all(|(a, b)| a.same_kind(b));
+        if !layout_matches {
+            distance += 5;
+        }
+
+        let expected = self.quirks.iter().filter(|q| q.observable());
+        let missing = expected
+            .clone()
+            .filter(|q| !features.quirks.contains(q))
+            .count();
+        let extra = features
+            .quirks
+            .iter()
+            .filter(|q| q.observable() && !self.quirks.contains(q))
+            .count();
+        distance += (missing + extra) as u32;
+
+        let payload_matches = match self.payload_class {
+            PayloadClass::Any => true,
+            PayloadClass::Zero => features.payload_len == 0,
+            PayloadClass::NonZero => features.payload_len > 0,
+        };
+        if !payload_matches {
+            distance += 1;
+        }
+
+        // specific signatures win ties over generic ones
+        if self.label.match_type == MatchType::Generic {
+            distance += 1;
+        }
+
+        Some(distance)
+    }
+
+    fn window_matches(&self, features: &TcpFeatures) -> bool {
+        let window = features.window as u32;
+        match self.window {
+            WindowSizePattern::Wildcard => true,
+            WindowSizePattern::Value(v) => features.window == v,
+            WindowSizePattern::Mss(n) => features
+                .mss
+                .is_some_and(|mss| window == mss as u32 * n as u32),
+            WindowSizePattern::Mtu(n) => features.mss.is_some_and(|mss| {
+                let header = if features.ip_version == 6 { 60 } else { 40 };
+                window == (mss as u32 + header) * n as u32
+            }),
+            WindowSizePattern::Modulo(n) => n > 0 && window.is_multiple_of(n as u32),
+        }
+    }
+}
+
+/// Closest p0f signature for a packet
+#[derive(Debug, Clone)]
+pub struct P0fMatch {
+    pub signature: P0fTcpSignature,
+    /// 0 for an exact match, grows with every differing field
+    pub distance: u32,
+    /// network distance inferred from the signature's initial TTL
+    pub hops: u8,
+    /// distance mapped to (0, 1]
+    pub score: f64,
+    /// matched a generic (`g:`) signature
+    pub generic: bool,
+}
+
+impl P0fMatch {
+    /// `os flavor` label, e.g. `Linux 3.11 and newer`
+    pub fn label(&self) -> String {
+        self.signature.label.to_string()
+    }
+
+    /// Signature describes an application rather than an OS
+    pub fn is_application(&self) -> bool {
+        self.signature.label.sys_type == SystemType::Application
+    }
+}
+
 /// p0f databasestatisticsinfo
 #[derive(Debug)]
 pub struct P0fStats {
@@ -191,11 +396,144 @@ pub enum P0fError {
 
     #[error("Parseerror: {0}")]
     Parse(String),
+
+    #[error("line {line}: {message}")]
+    Syntax { line: usize, message: String },
+}
+
+/// Shared, hot-reloadable p0f database
+///
+/// Clones share the database. Readers take a cheap [`Arc`] snapshot with
+/// [`P0fStore::current`]; a reload swaps the snapshot atomically and keeps the
+/// previous database when the new file does not parse.
+#[derive(Debug, Clone)]
+pub struct P0fStore {
+    inner: Arc<StoreInner>,
+}
+
+#[derive(Debug)]
+struct StoreInner {
+    db: RwLock<Arc<P0fDatabase>>,
+    path: Option<PathBuf>,
+    /// modification time and length of the loaded file
+    loaded: Mutex<Option<(SystemTime, u64)>>,
+    generation: AtomicU64,
+}
+
+impl P0fStore {
+    /// Store with a fixed database
+    pub fn new(db: P0fDatabase) -> Self {
+        Self::build(db, None, None)
+    }
+
+    /// Load a p0f.fp file that [`reload`](Self::reload) can re-read
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, P0fError> {
+        let path = path.as_ref().to_path_buf();
+        let stamp = file_stamp(&path)?;
+        let db = P0fDatabase::load_from_file(&path)?;
+        Ok(Self::build(db, Some(path), Some(stamp)))
+    }
+
+    fn build(db: P0fDatabase, path: Option<PathBuf>, stamp: Option<(SystemTime, u64)>) -> Self {
+        Self {
+            inner: Arc::new(StoreInner {
+                db: RwLock::new(Arc::new(db)),
+                path,
+                loaded: Mutex::new(stamp),
+                generation: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Snapshot of the current database
+    pub fn current(&self) -> Arc<P0fDatabase> {
+        self.inner
+            .db
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Install a new database
+    pub fn replace(&self, db: P0fDatabase) {
+        *self.inner.db.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(db);
+        self.inner.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of times the database was replaced
+    pub fn generation(&self) -> u64 {
+        self.inner.generation.load(Ordering::Relaxed)
+    }
+
+    /// File the store was opened from
+    pub fn path(&self) -> Option<&Path> {
+        self.inner.path.as_deref()
+    }
+
+    /// Re-read the file; the current database stays in place on error
+    pub fn reload(&self) -> Result<P0fStats, P0fError> {
+        let path = self.inner.path.as_ref().ok_or(P0fError::InvalidFormat)?;
+        let stamp = file_stamp(path)?;
+        let db = P0fDatabase::load_from_file(path)?;
+        let stats = db.stats();
+        self.replace(db);
+        *self.inner.loaded.lock().unwrap_or_else(|e| e.into_inner()) = Some(stamp);
+        Ok(stats)
+    }
+
+    /// Reload when the file's modification time or size changed
+    pub fn reload_if_changed(&self) -> Result<bool, P0fError> {
+        let Some(path) = &self.inner.path else {
+            return Ok(false);
+        };
+        let stamp = file_stamp(path)?;
+        if *self.inner.loaded.lock().unwrap_or_else(|e| e.into_inner()) == Some(stamp) {
+            return Ok(false);
+        }
+        self.reload().map(|_| true)
+    }
+
+    /// Closest TCP signature in the current database
+    pub fn match_tcp(&self, features: &TcpFeatures, max_distance: u32) -> Option<P0fMatch> {
+        self.current().match_tcp(features, max_distance)
+    }
+
+    /// Poll the file every `interval` and reload it when it changes
+    ///
+    /// The thread exits once every clone of the store is dropped.
+    pub fn watch(&self, interval: Duration) -> io::Result<JoinHandle<()>> {
+        if self.inner.path.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "p0f store has no backing file",
+            ));
+        }
+        let inner: Weak<StoreInner> = Arc::downgrade(&self.inner);
+        std::thread::Builder::new()
+            .name("p0f-watch".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(interval);
+                let Some(inner) = inner.upgrade() else {
+                    return;
+                };
+                match (P0fStore { inner }).reload_if_changed() {
+                    Ok(true) => log::info!("[P0fStore] Signature file reloaded"),
+                    Ok(false) => {}
+                    Err(e) => log::warn!("[P0fStore] Failed to reload signatures: {}", e),
+                }
+            })
+    }
+}
+
+fn file_stamp(path: &Path) -> io::Result<(SystemTime, u64)> {
+    let meta = fs::metadata(path)?;
+    Ok((meta.modified()?, meta.len()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::passive::p0f_parser::Quirk;
 
     #[test]
     fn test_print_all_p0f_data() {
@@ -286,13 +624,13 @@ sig = *:64:0:*:mss*20,10:mss,sok,ts,nop,ws:df,id+:0
         for (i, sig) in tcp_requests.iter().enumerate() {
             println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
             println!("signature #{}: {}", i + 1, sig.id);
-            println!(" operating system: {:?}", sig.os_type);
-            println!(" TTL: {}", sig.ttl);
-            println!(" windowsize: {}", sig.window_size);
+            println!(" operating system: {}", sig.label);
+            println!(" TTL: {}", sig.ittl);
+            println!(" windowsize: {:?}", sig.window);
             println!(" MSS: {:?}", sig.mss);
             println!(" Window Scale: {:?}", sig.window_scale);
-            println!(" confidence: {:.2}", sig.confidence);
-            println!(" samplecount: {}", sig.sample_count);
+            println!(" options: {:?}", sig.options);
+            println!(" quirks: {:?}", sig.quirks);
             println!();
         }
 
@@ -307,13 +645,13 @@ sig = *:64:0:*:mss*20,10:mss,sok,ts,nop,ws:df,id+:0
         for (i, sig) in tcp_responses.iter().enumerate() {
             println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
             println!("signature #{}: {}", i + 1, sig.id);
-            println!(" operating system: {:?}", sig.os_type);
-            println!(" TTL: {}", sig.ttl);
-            println!(" windowsize: {}", sig.window_size);
+            println!(" operating system: {}", sig.label);
+            println!(" TTL: {}", sig.ittl);
+            println!(" windowsize: {:?}", sig.window);
             println!(" MSS: {:?}", sig.mss);
             println!(" Window Scale: {:?}", sig.window_scale);
-            println!(" confidence: {:.2}", sig.confidence);
-            println!(" samplecount: {}", sig.sample_count);
+            println!(" options: {:?}", sig.options);
+            println!(" quirks: {:?}", sig.quirks);
             println!();
         }
 
@@ -329,7 +667,7 @@ sig = *:64:0:*:mss*20,10:mss,sok,ts,nop,ws:df,id+:0
             println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
             println!("signature #{}: {}", i + 1, sig.id);
             println!(" tag: {}", sig.label);
-            println!(" User-Agent pattern: {:?}", sig.user_agent_pattern);
+            println!(" User-Agent pattern: {:?}", sig.software);
             println!(" Headers: {:?}", sig.headers);
             println!();
         }
@@ -346,7 +684,7 @@ sig = *:64:0:*:mss*20,10:mss,sok,ts,nop,ws:df,id+:0
             println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
             println!("signature #{}: {}", i + 1, sig.id);
             println!(" tag: {}", sig.label);
-            println!(" User-Agent pattern: {:?}", sig.user_agent_pattern);
+            println!(" User-Agent pattern: {:?}", sig.software);
             println!(" Headers: {:?}", sig.headers);
             println!();
         }
@@ -355,4 +693,112 @@ sig = *:64:0:*:mss*20,10:mss,sok,ts,nop,ws:df,id+:0
         println!("✅ all p0f countdataprintcomplete！");
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
     }
+
+    const SAMPLE: &str = r#"
+classes = win,unix,other
+
+[mtu]
+
+label = Ethernet or modem
+sig   = 1500
+
+[tcp:request]
+
+label = s:unix:Linux:3.11 and newer
+sig   = *:64:0:*:mss*20,10:mss,sok,ts,nop,ws:df,id+:0
+sig   = *:64:0:*:mss*20,7:mss,sok,ts,nop,ws:df,id+:0
+
+label = s:win:Windows:7 or 8
+sig   = *:128:0:*:8192,8:mss,nop,ws,nop,nop,sok:df,id+:0
+
+label = g:unix:Linux:2.2.x-3.x
+sig   = *:64:0:*:*,*:mss,sok,ts,nop,ws:df,id+:0
+
+[tcp:response]
+
+label = s:unix:Linux:3.x
+sig   = *:64:0:*:mss*10,0:mss:df:0
+"#;
+
+    fn linux_syn() -> TcpFeatures {
+        use crate::passive::p0f_parser::TcpOptionType::*;
+        TcpFeatures {
+            ttl: 57,
+            initial_ttl: 64,
+            window: 29200,
+            mss: Some(1460),
+            window_scale: Some(7),
+            options_str: "mss,sack,ts,nop,ws".to_string(),
+            ip_flags: 0x02,
+            ip_version: 4,
+            options: vec![Mss, SackPermitted, Timestamp, Nop, WindowScale],
+            quirks: vec![Quirk::Df],
+            payload_len: 0,
+            syn_ack: false,
+        }
+    }
+
+    #[test]
+    fn matches_linux_syn() {
+        let db = P0fDatabase::parse(SAMPLE).unwrap();
+        assert_eq!(db.stats().tcp_request_count, 4);
+        assert_eq!(db.link_type(1500), Some("Ethernet or modem"));
+        assert!(db
+            .get_tcp_request("tcp-s-unix-Linux-3.11 and newer-2")
+            .is_some());
+
+        let m = db.match_tcp(&linux_syn(), DEFAULT_MAX_DISTANCE).unwrap();
+        assert_eq!(m.label(), "Linux 3.11 and newer");
+        assert_eq!(m.distance, 0);
+        assert_eq!(m.hops, 7);
+        assert!(!m.generic);
+
+        // unusual window scale falls back to the generic signature
+        let mut odd = linux_syn();
+        odd.window_scale = Some(3);
+        let m = db.match_tcp(&odd, DEFAULT_MAX_DISTANCE).unwrap();
+        assert!(m.generic);
+        assert_eq!(m.distance, 1);
+
+        // a TTL above every initial TTL matches nothing
+        let mut far = linux_syn();
+        far.ttl = 200;
+        assert!(db.match_tcp(&far, DEFAULT_MAX_DISTANCE).is_none());
+
+        let err = P0fDatabase::parse("[tcp:request]\nlabel = s:unix:Linux:x\nsig = *:64:0\n")
+            .unwrap_err();
+        assert!(matches!(err, P0fError::Syntax { line: 3, .. }));
+    }
+
+    #[test]
+    fn hot_reloads_signature_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("p0f.fp");
+        fs::write(&path, SAMPLE).unwrap();
+
+        let store = P0fStore::open(&path).unwrap();
+        assert!(!store.reload_if_changed().unwrap());
+        assert_eq!(
+            store.match_tcp(&linux_syn(), 0).unwrap().label(),
+            "Linux 3.11 and newer"
+        );
+
+        let custom = "[tcp:request]\nlabel = s:unix:Custom:appliance\nsig = 4:64:0:1460:29200,7:mss,sok,ts,nop,ws:df:0\n";
+        fs::write(&path, custom).unwrap();
+        assert!(store.reload_if_changed().unwrap());
+        assert_eq!(store.generation(), 1);
+        assert_eq!(
+            store.match_tcp(&linux_syn(), 0).unwrap().label(),
+            "Custom appliance"
+        );
+
+        // a broken file keeps the last good database
+        fs::write(&path, "[tcp:request]\nsig = garbage\n").unwrap();
+        assert!(store.reload().is_err());
+        assert_eq!(store.current().stats().tcp_request_count, 1);
+
+        let handle = store.watch(Duration::from_millis(10)).unwrap();
+        drop(store);
+        handle.join().unwrap();
+    }
 }
@@ -1,12 +1,19 @@
 //! p0f signatureParseer (detailedimplement)
 //!
-//! completeimplement p0f.fp formatParse, supportallfield and pattern.
+//! Parses p0f v3 `p0f.fp` signatures:
+//!
+//! * TCP: `ver:ittl:olen:mss:wsize,scale:olayout:quirks:pclass`
+//! * HTTP: `ver:horder:habsent:expsw`
+//! * labels: `type:class:name:flavor`
+//!
+//! See the p0f v3 README, section 5, for the meaning of every field.
 
 use crate::passive::tcp::TcpSignature;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// p0f TCP signature (complete版)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct P0fTcpSignature {
     /// signature ID
     pub id: String,
@@ -14,32 +21,41 @@ pub struct P0fTcpSignature {
     /// taginfo
     pub label: SignatureLabel,
 
-    /// systemtypelimit (optional)
-    pub sys: Option<Vec<SystemType>>,
+    /// systems an application signature applies to (`sys =` line)
+    pub sys: Option<Vec<String>>,
+
+    /// IP version
+    pub version: IpVersion,
 
-    /// TTL pattern
-    pub ttl_pattern: TtlPattern,
+    /// initial TTL
+    pub ittl: u8,
 
-    /// initialbeginning TTL value
-    pub initial_ttl: u8,
+    /// `ittl-`: the TTL is not usable to compute the distance
+    pub bad_ttl: bool,
 
-    /// windowsizepattern
-    pub window_mode: WindowMode,
+    /// length of IP options
+    pub olen: u8,
 
-    /// windowsizevaluepattern
-    pub window_value: WindowSizePattern,
+    /// MSS, `None` for `*`
+    pub mss: Option<u16>,
 
-    /// MSS pattern
-    pub mss_pattern: MssPattern,
+    /// window size
+    pub window: WindowSizePattern,
 
-    /// TCP optionsorder
-    pub options_order: Vec<TcpOptionType>,
+    /// window scale, `None` for `*`
+    pub window_scale: Option<u8>,
 
-    /// IP flag
-    pub ip_flags: IpFlags,
+    /// TCP options layout
+    pub options: Vec<TcpOptionType>,
 
-    /// otherfield
-    pub other: String,
+    /// quirks
+    pub quirks: Vec<Quirk>,
+
+    /// payload size class
+    pub payload_class: PayloadClass,
+
+    /// signature as written in the file
+    pub raw: String,
 }
 
 /// signaturetag
@@ -58,6 +74,12 @@ pub struct SignatureLabel {
     pub version: String,
 }
 
+impl std::fmt::Display for SignatureLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.os, self.version)
+    }
+}
+
 /// matchtype
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MatchType {
@@ -71,75 +93,185 @@ pub enum SystemType {
     Unix,        // unix
     Windows,     // win
     Application, // !
+    Other,       // other
 }
 
-/// TTL pattern
-#[derive(Debug, Clone, PartialEq)]
-pub enum TtlPattern {
-    /// wildcard *
-    Wildcard,
-    /// concretevalue
-    Value(u8),
-}
-
-/// windowsizepattern
-#[derive(Debug, Clone, PartialEq)]
-pub enum WindowMode {
-    /// pattern 0: fixedvalue
-    Fixed,
-    /// pattern 1: 倍count
-    Multiple,
-    /// pattern 2: modecount
-    Modulo,
-    /// pattern 3: random
-    Random,
+/// IP version of a signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpVersion {
+    /// `*`
+    Any,
+    V4,
+    V6,
 }
 
 /// windowsizevaluepattern
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum WindowSizePattern {
     /// wildcard *
     Wildcard,
     /// concretevalue
     Value(u16),
-    /// 倍countpattern：m*N
-    Multiple(u16),
+    /// multiple of the MSS：mss*N
+    Mss(u16),
+    /// multiple of the MTU：mtu*N
+    Mtu(u16),
     /// modecountpattern：%N
     Modulo(u16),
 }
 
-/// MSS pattern
-#[derive(Debug, Clone, PartialEq)]
-pub enum MssPattern {
-    /// none MSS
-    None,
-    /// fixedvalue：mss,1460
-    Fixed(u16),
-    /// 倍countpattern：mss*20,10 (20倍count+10)
-    Multiple { multiplier: u16, offset: u16 },
-}
-
 /// TCP optionstype
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TcpOptionType {
-    Mss,         // mss
-    WindowScale, // ws
-    Sack,        // sok
-    Timestamp,   // ts
-    Nop,         // nop
-    End,         // eol
-    Other(u8),   // other
+    /// eol+N, N bytes of padding after the end of options
+    Eol(u8),
+    Nop,           // nop
+    Mss,           // mss
+    WindowScale,   // ws
+    SackPermitted, // sok
+    Sack,          // sack
+    Timestamp,     // ts
+    Other(u8),     // ?N
+}
+
+impl TcpOptionType {
+    /// Option of TCP option `kind`
+    pub fn from_kind(kind: u8) -> Self {
+        match kind {
+            0 => TcpOptionType::Eol(0),
+            1 => TcpOptionType::Nop,
+            2 => TcpOptionType::Mss,
+            3 => TcpOptionType::WindowScale,
+            4 => TcpOptionType::SackPermitted,
+            5 => TcpOptionType::Sack,
+            8 => TcpOptionType::Timestamp,
+            other => TcpOptionType::Other(other),
+        }
+    }
+
+    /// Same option, ignoring the EOL padding length
+    pub fn same_kind(&self, other: &Self) -> bool {
+        match (self, other) {
+            (TcpOptionType::Eol(_), TcpOptionType::Eol(_)) => true,
+            _ => self == other,
+        }
+    }
+}
+
+/// p0f quirks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Quirk {
+    /// `df`: don't fragment set
+    Df,
+    /// `id+`: DF set but non-zero IP ID
+    IdPlus,
+    /// `id-`: DF not set but zero IP ID
+    IdMinus,
+    /// `ecn`: explicit congestion notification support
+    Ecn,
+    /// `0+`: must-be-zero field not zero
+    ZeroPlus,
+    /// `flow`: non-zero IPv6 flow label
+    Flow,
+    /// `seq-`: zero sequence number
+    SeqMinus,
+    /// `ack+`: ACK number non-zero without ACK flag
+    AckPlus,
+    /// `ack-`: ACK number zero with ACK flag
+    AckMinus,
+    /// `uptr+`: urgent pointer non-zero without URG flag
+    UptrPlus,
+    /// `urgf+`: URG flag set
+    UrgfPlus,
+    /// `pushf+`: PUSH flag set
+    PushfPlus,
+    /// `ts1-`: own timestamp is zero
+    Ts1Minus,
+    /// `ts2+`: peer timestamp non-zero on the initial SYN
+    Ts2Plus,
+    /// `opt+`: trailing non-zero data in options
+    OptPlus,
+    /// `exws`: excessive window scaling factor (> 14)
+    Exws,
+    /// `bad`: malformed TCP options
+    Bad,
+}
+
+impl Quirk {
+    pub fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "df" => Quirk::Df,
+            "id+" => Quirk::IdPlus,
+            "id-" => Quirk::IdMinus,
+            "ecn" => Quirk::Ecn,
+            "0+" => Quirk::ZeroPlus,
+            "flow" => Quirk::Flow,
+            "seq-" => Quirk::SeqMinus,
+            "ack+" => Quirk::AckPlus,
+            "ack-" => Quirk::AckMinus,
+            "uptr+" => Quirk::UptrPlus,
+            "urgf+" => Quirk::UrgfPlus,
+            "pushf+" => Quirk::PushfPlus,
+            "ts1-" => Quirk::Ts1Minus,
+            "ts2+" => Quirk::Ts2Plus,
+            "opt+" => Quirk::OptPlus,
+            "exws" => Quirk::Exws,
+            "bad" => Quirk::Bad,
+            _ => return None,
+        })
+    }
+
+    /// Whether the quirk can be derived from a parsed packet
+    ///
+    /// IP ID, flow label and reserved bits are not kept by the packet parser,
+    /// so those quirks are not compared when matching.
+    pub fn observable(&self) -> bool {
+        !matches!(
+            self,
+            Quirk::IdPlus | Quirk::IdMinus | Quirk::ZeroPlus | Quirk::Flow | Quirk::OptPlus
+        )
+    }
+}
+
+/// payload size class
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadClass {
+    /// `*`
+    Any,
+    /// `0`: no payload
+    Zero,
+    /// `+`: payload present
+    NonZero,
 }
 
-/// IP flag
+/// HTTP header in a p0f HTTP signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpHeaderPattern {
+    /// header name as written
+    pub name: String,
+    /// `Name=[value]`: expected value
+    pub value: Option<String>,
+    /// `?Name`: may be absent
+    pub optional: bool,
+}
+
+/// p0f HTTP signature
 #[derive(Debug, Clone, PartialEq)]
-pub struct IpFlags {
-    /// Don't Fragment
-    pub df: bool,
-    /// ID 递increase
-    pub id_plus: bool,
-    /// ID fixed
-    pub id_minus: bool,
+pub struct P0fHttpSignature {
+    pub id: String,
+    pub label: SignatureLabel,
+    /// systems an application signature applies to (`sys =` line)
+    pub sys: Option<Vec<String>>,
+    /// HTTP/1.x minor version, `None` for `*`
+    pub version: Option<u8>,
+    /// headers in order
+    pub headers: Vec<HttpHeaderPattern>,
+    /// headers that must be absent
+    pub absent: Vec<String>,
+    /// expected substring of User-Agent / Server
+    pub software: Option<String>,
+    /// signature as written in the file
+    pub raw: String,
 }
 
 /// p0f Parseerror
@@ -155,104 +287,149 @@ pub enum P0fParseError {
     Parse(String),
 }
 
+fn invalid(message: String) -> P0fParseError {
+    P0fParseError::InvalidSignature(message)
+}
+
 /// Parse p0f TCP signature
 pub fn parse_tcp_signature(label: &str, sig: &str) -> Result<P0fTcpSignature, P0fParseError> {
-    // Parsetag
     let label_info = parse_label(label)?;
 
-    // Parsesignature：format as [ttl]:[initialbeginningttl]:[windowpattern]:[windowvalue]:[TCPoptions]:[IPflag]:[other]
     let parts: Vec<&str> = sig.split(':').collect();
-    if parts.len() < 7 {
-        return Err(P0fParseError::InvalidSignature(format!(
-            "signaturepartialcountinsufficient: expected7，actual{}",
+    let [ver, ittl, olen, mss, window, options, quirks, pclass] = parts[..] else {
+        return Err(invalid(format!(
+            "expected 8 fields (ver:ittl:olen:mss:wsize,scale:olayout:quirks:pclass), got {}",
             parts.len()
         )));
-    }
+    };
 
-    // Parse TTL pattern
-    let ttl_pattern =
-        if parts[0] == "*" {
-            TtlPattern::Wildcard
-        } else {
-            TtlPattern::Value(parts[0].parse().map_err(|_| {
-                P0fParseError::InvalidSignature(format!("invalid TTL: {}", parts[0]))
-            })?)
-        };
+    let version = match ver {
+        "*" => IpVersion::Any,
+        "4" => IpVersion::V4,
+        "6" => IpVersion::V6,
+        _ => return Err(invalid(format!("invalid IP version: {}", ver))),
+    };
 
-    // Parseinitialbeginning TTL
-    let initial_ttl = parts[1].parse().map_err(|_| {
-        P0fParseError::InvalidSignature(format!("invalidinitialbeginning TTL: {}", parts[1]))
-    })?;
-
-    // Parsewindowsizepattern
-    let window_mode = match parts[2] {
-        "0" => WindowMode::Fixed,
-        "1" => WindowMode::Multiple,
-        "2" => WindowMode::Modulo,
-        "3" => WindowMode::Random,
-        _ => {
-            return Err(P0fParseError::InvalidSignature(format!(
-                "invalidwindowpattern: {}",
-                parts[2]
-            )))
-        }
+    // `64-` marks a bad TTL; p0f output uses `54+10` (observed + distance)
+    let (ittl, bad_ttl) = match ittl.strip_suffix('-') {
+        Some(ttl) => (ttl, true),
+        None => (ittl, false),
+    };
+    let ittl = match ittl.split_once('+') {
+        Some((ttl, dist)) => number::<u8>(ttl, "TTL")?.saturating_add(number(dist, "TTL")?),
+        None => number(ittl, "TTL")?,
     };
 
-    // Parsewindowsizevaluepattern
-    let window_value = parse_window_size_pattern(parts[3])?;
+    let olen = number(olen, "IP options length")?;
+    let mss = wildcard(mss, "MSS")?;
 
-    // Parse MSS pattern and TCP options
-    // p0f format: [ttl]:[initialbeginningttl]:[windowpattern]:[windowvalue]:[MSSpattern]:[optionsorder]:[IPflag]:[other]
-    // so parts[4] is MSS pattern, parts[5] is optionsorder
-    let mss_str = parts[4];
-    let options_str = if parts.len() > 5 { parts[5] } else { "" };
+    let (size, scale) = window
+        .split_once(',')
+        .ok_or_else(|| invalid(format!("expected wsize,scale: {}", window)))?;
+    let window = parse_window_size_pattern(size)?;
+    let window_scale = wildcard(scale, "window scale")?;
 
-    // merge MSS pattern and optionsorderperformParse
-    let full_options_str = if !options_str.is_empty() {
-        format!("{}:{}", mss_str, options_str)
-    } else {
-        mss_str.to_string()
+    let options = parse_tcp_options(options)?;
+
+    let quirks = quirks
+        .split(',')
+        .filter(|q| !q.is_empty())
+        .map(|q| Quirk::parse(q).ok_or_else(|| invalid(format!("unknown quirk: {}", q))))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let payload_class = match pclass {
+        "*" => PayloadClass::Any,
+        "0" => PayloadClass::Zero,
+        "+" => PayloadClass::NonZero,
+        _ => return Err(invalid(format!("invalid payload class: {}", pclass))),
     };
 
-    let (mss_pattern, options_order) = parse_tcp_options(&full_options_str)?;
+    Ok(P0fTcpSignature {
+        id: format!("tcp-{}", label.replace(':', "-")),
+        label: label_info,
+        sys: None,
+        version,
+        ittl,
+        bad_ttl,
+        olen,
+        mss,
+        window,
+        window_scale,
+        options,
+        quirks,
+        payload_class,
+        raw: sig.to_string(),
+    })
+}
 
-    // Parse IP flag
-    let ip_flags = if parts.len() > 6 {
-        parse_ip_flags(parts[6])?
-    } else {
-        IpFlags {
-            df: false,
-            id_plus: false,
-            id_minus: false,
-        }
+/// Parse p0f HTTP signature
+pub fn parse_http_signature(label: &str, sig: &str) -> Result<P0fHttpSignature, P0fParseError> {
+    let label_info = parse_label(label)?;
+
+    // header values are bracketed and may contain ':' and ','
+    let fields = split_outside_brackets(sig, ':', 4);
+    let [ver, horder, habsent, expsw] = fields[..] else {
+        return Err(invalid(format!(
+            "expected 4 fields (ver:horder:habsent:expsw), got {}",
+            fields.len()
+        )));
     };
 
-    // otherfield
-    let other = if parts.len() > 7 {
-        parts[7].to_string()
-    } else {
-        "0".to_string()
+    let version = match ver {
+        "*" => None,
+        "0" => Some(0),
+        "1" => Some(1),
+        _ => return Err(invalid(format!("invalid HTTP version: {}", ver))),
     };
 
-    Ok(P0fTcpSignature {
-        id: format!("tcp-{}", label.replace(':', "-")),
+    let headers = split_outside_brackets(horder, ',', usize::MAX)
+        .into_iter()
+        .filter(|h| !h.is_empty())
+        .map(|header| {
+            let (optional, header) = match header.strip_prefix('?') {
+                Some(rest) => (true, rest),
+                None => (false, header),
+            };
+            let (name, value) = match header.split_once('=') {
+                Some((name, value)) => {
+                    let value = value
+                        .strip_prefix('[')
+                        .and_then(|v| v.strip_suffix(']'))
+                        .ok_or_else(|| invalid(format!("unbracketed header value: {}", header)))?;
+                    (name, Some(value.to_string()))
+                }
+                None => (header, None),
+            };
+            Ok(HttpHeaderPattern {
+                name: name.to_string(),
+                value,
+                optional,
+            })
+        })
+        .collect::<Result<Vec<_>, P0fParseError>>()?;
+
+    let absent = habsent
+        .split(',')
+        .filter(|h| !h.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    Ok(P0fHttpSignature {
+        id: format!("http-{}", label.replace(':', "-")),
         label: label_info,
-        sys: None, // will in back续Parse sys field when settings
-        ttl_pattern,
-        initial_ttl,
-        window_mode,
-        window_value,
-        mss_pattern,
-        options_order,
-        ip_flags,
-        other,
+        sys: None,
+        version,
+        headers,
+        absent,
+        software: (!expsw.is_empty()).then(|| expsw.to_string()),
+        raw: sig.to_string(),
     })
 }
 
 /// Parsetag
-fn parse_label(label: &str) -> Result<SignatureLabel, P0fParseError> {
+pub fn parse_label(label: &str) -> Result<SignatureLabel, P0fParseError> {
     // format: s:unix:Linux:3.11 and newer
-    let parts: Vec<&str> = label.split(':').collect();
+    let parts: Vec<&str> = label.splitn(4, ':').collect();
     if parts.len() < 4 {
         return Err(P0fParseError::InvalidLabel(format!(
             "tagpartialcountinsufficient: expected4，actual{}",
@@ -275,19 +452,14 @@ fn parse_label(label: &str) -> Result<SignatureLabel, P0fParseError> {
         "unix" => SystemType::Unix,
         "win" => SystemType::Windows,
         "!" => SystemType::Application,
-        _ => {
-            return Err(P0fParseError::InvalidLabel(format!(
-                "invalidsystemtype: {}",
-                parts[1]
-            )))
-        }
+        _ => SystemType::Other,
     };
 
     Ok(SignatureLabel {
         match_type,
         sys_type,
         os: parts[2].to_string(),
-        version: parts[3..].join(":"), // versionmayincluding冒号
+        version: parts[3].to_string(), // versionmayincluding冒号
     })
 }
 
@@ -296,178 +468,94 @@ fn parse_window_size_pattern(pattern: &str) -> Result<WindowSizePattern, P0fPars
     if pattern == "*" {
         return Ok(WindowSizePattern::Wildcard);
     }
-
-    // Check倍countpattern：m*N
-    if let Some(pos) = pattern.find('*') {
-        let multiplier = pattern[pos + 1..].parse().map_err(|_| {
-            P0fParseError::InvalidSignature(format!("invalidwindow倍count: {}", pattern))
-        })?;
-        return Ok(WindowSizePattern::Multiple(multiplier));
+    if let Some(n) = pattern.strip_prefix("mss*") {
+        return Ok(WindowSizePattern::Mss(number(n, "window multiplier")?));
     }
-
-    // Checkmodecountpattern：%N
-    if let Some(stripped) = pattern.strip_prefix('%') {
-        let modulo = stripped.parse().map_err(|_| {
-            P0fParseError::InvalidSignature(format!("invalidwindow模count: {}", pattern))
-        })?;
-        return Ok(WindowSizePattern::Modulo(modulo));
+    if let Some(n) = pattern.strip_prefix("mtu*") {
+        return Ok(WindowSizePattern::Mtu(number(n, "window multiplier")?));
     }
-
-    // fixedvalue
-    let value = pattern
-        .parse()
-        .map_err(|_| P0fParseError::InvalidSignature(format!("invalidwindowsize: {}", pattern)))?;
-    Ok(WindowSizePattern::Value(value))
-}
-
-/// Parse TCP options
-fn parse_tcp_options(options_str: &str) -> Result<(MssPattern, Vec<TcpOptionType>), P0fParseError> {
-    let mut mss_pattern = MssPattern::None;
-    let mut options_order = Vec::new();
-
-    // optionsformat: mss*20,10:mss,sok,ts,nop,ws
-    // firstpartial is MSS pattern, secondpartial is optionsorder
-
-    let parts: Vec<&str> = options_str.split(':').collect();
-    if parts.is_empty() {
-        return Ok((mss_pattern, options_order));
-    }
-
-    // Parse MSS pattern (firstpartial)
-    // formatmay is: mss*20,10 or mss,1460
-    let mss_part = parts[0];
-    if mss_part.contains("mss") {
-        mss_pattern = parse_mss_pattern(mss_part)?;
+    if let Some(n) = pattern.strip_prefix('%') {
+        return Ok(WindowSizePattern::Modulo(number(n, "window modulo")?));
     }
+    Ok(WindowSizePattern::Value(number(pattern, "window size")?))
+}
 
-    // Parseoptionsorder
-    // formatmay is: mss*20,10:mss,sok,ts,nop,ws
-    // or one who: mss,1460:mss,sok,ts,nop,ws
-    // secondpartial is optionsorder
-    if parts.len() > 1 {
-        // secondpartialincludingoptionsorder
-        for opt_str in parts[1].split(',') {
-            let opt = match opt_str.trim() {
+/// Parse TCP options layout
+fn parse_tcp_options(layout: &str) -> Result<Vec<TcpOptionType>, P0fParseError> {
+    layout
+        .split(',')
+        .filter(|o| !o.is_empty())
+        .map(|option| {
+            Ok(match option {
+                "nop" => TcpOptionType::Nop,
                 "mss" => TcpOptionType::Mss,
                 "ws" => TcpOptionType::WindowScale,
-                "sok" => TcpOptionType::Sack,
+                "sok" => TcpOptionType::SackPermitted,
+                "sack" => TcpOptionType::Sack,
                 "ts" => TcpOptionType::Timestamp,
-                "nop" => TcpOptionType::Nop,
-                "eol" => TcpOptionType::End,
                 _ => {
-                    // tryParse as count字
-                    if let Ok(num) = opt_str.parse::<u8>() {
-                        TcpOptionType::Other(num)
+                    if let Some(pad) = option.strip_prefix("eol+") {
+                        TcpOptionType::Eol(number(pad, "EOL padding")?)
+                    } else if let Some(kind) = option.strip_prefix('?') {
+                        TcpOptionType::Other(number(kind, "option kind")?)
                     } else {
-                        continue; // skipnot知options
+                        return Err(invalid(format!("unknown TCP option: {}", option)));
                     }
                 }
-            };
-            options_order.push(opt);
-        }
-    } else {
-        // Ifnosecondpartial, mayoptionsorderthen in firstpartial ( in MSS patternafter)
-        // format: mss*20,10 or mss,1460
-        // thissituationdown, optionsordermay不 exists, or one whoneed from otherplaceExtract
-        // temporary when 不processthissituation
-    }
-
-    Ok((mss_pattern, options_order))
+            })
+        })
+        .collect()
 }
 
-/// Parse MSS pattern
-fn parse_mss_pattern(mss_str: &str) -> Result<MssPattern, P0fParseError> {
-    // format: mss*20,10 or mss,1460
-    if let Some(pos) = mss_str.find('*') {
-        // 倍countpattern: mss*20,10
-        let after_star = &mss_str[pos + 1..];
-        let parts: Vec<&str> = after_star.split(',').collect();
-        if parts.len() >= 2 {
-            let multiplier = parts[0].parse().map_err(|_| {
-                P0fParseError::InvalidSignature(format!("invalid MSS 倍count: {}", parts[0]))
-            })?;
-            let offset = parts[1].parse().map_err(|_| {
-                P0fParseError::InvalidSignature(format!("invalid MSS offset: {}", parts[1]))
-            })?;
-            return Ok(MssPattern::Multiple { multiplier, offset });
-        }
-    }
+fn number<T: std::str::FromStr>(s: &str, what: &str) -> Result<T, P0fParseError> {
+    s.parse()
+        .map_err(|_| invalid(format!("invalid {}: {}", what, s)))
+}
 
-    // fixedvaluepattern: mss,1460
-    // findfirst逗numberbackcount字
-    if let Some(pos) = mss_str.find(',') {
-        let value_str = &mss_str[pos + 1..];
-        // maystill有more逗number, onlygetfirstcount字partial
-        let value_part = value_str.split(',').next().unwrap_or(value_str);
-        if let Ok(value) = value_part.parse::<u16>() {
-            return Ok(MssPattern::Fixed(value));
-        }
+/// `*` or a number
+fn wildcard<T: std::str::FromStr>(s: &str, what: &str) -> Result<Option<T>, P0fParseError> {
+    if s == "*" {
+        Ok(None)
+    } else {
+        number(s, what).map(Some)
     }
-
-    Ok(MssPattern::None)
 }
 
-/// Parse IP flag
-fn parse_ip_flags(flags_str: &str) -> Result<IpFlags, P0fParseError> {
-    let mut df = false;
-    let mut id_plus = false;
-    let mut id_minus = false;
-
-    for flag in flags_str.split(',') {
-        match flag.trim() {
-            "df" => df = true,
-            "id+" => id_plus = true,
-            "id-" => id_minus = true,
-            "" => continue,
-            _ => {
-                // ignorenot知flag
+/// Split on `sep` outside `[...]`, into at most `max` pieces
+fn split_outside_brackets(s: &str, sep: char, max: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            c if c == sep && depth == 0 && parts.len() + 1 < max => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
             }
+            _ => {}
         }
     }
-
-    Ok(IpFlags {
-        df,
-        id_plus,
-        id_minus,
-    })
+    parts.push(&s[start..]);
+    parts
 }
 
-/// will P0fTcpSignature convert to TcpSignature ( for match)
 impl From<P0fTcpSignature> for TcpSignature {
     fn from(p0f_sig: P0fTcpSignature) -> Self {
-        // from MSS patternExtractfixedvalue ( if may)
-        let mss = match &p0f_sig.mss_pattern {
-            MssPattern::Fixed(v) => Some(*v),
-            MssPattern::Multiple { multiplier, offset } => {
-                // usefirstmay MSS valueasExamples
-                let m = (*multiplier as u32)
-                    .saturating_mul(10)
-                    .saturating_add(*offset as u32);
-                Some(m.min(65535) as u16)
-            }
-            MssPattern::None => None,
-        };
-
-        // from optionsorder in Extract Window Scale ( if exists)
-        let window_scale = if p0f_sig.options_order.contains(&TcpOptionType::WindowScale) {
-            Some(7) // defaultvalue，actualshould from countpacket in Extract
-        } else {
-            None
-        };
-
-        // from windowvaluepatternExtractfixedvalue ( if may)
-        let window_size = match &p0f_sig.window_value {
-            WindowSizePattern::Value(v) => *v,
-            _ => 0, // wildcard or otherpattern
+        // onlyfixedvalue can be carried over; patterns become "any"
+        let window_size = match p0f_sig.window {
+            WindowSizePattern::Value(v) => v,
+            _ => 0,
         };
 
         TcpSignature {
             id: p0f_sig.id,
-            ttl: p0f_sig.initial_ttl,
+            ttl: p0f_sig.ittl,
             window_size,
-            mss,
-            window_scale,
-            os_type: Some(p0f_sig.label.os.clone()),
+            mss: p0f_sig.mss,
+            window_scale: p0f_sig.window_scale,
+            os_type: Some(p0f_sig.label.os),
             confidence: if p0f_sig.label.match_type == MatchType::Specific {
                 0.9
             } else {
@@ -477,3 +565,58 @@ impl From<P0fTcpSignature> for TcpSignature {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_v3_signatures() {
+        let sig = parse_tcp_signature(
+            "s:unix:Linux:3.11 and newer",
+            "*:64:0:*:mss*20,10:mss,sok,ts,nop,ws:df,id+:0",
+        )
+        .unwrap();
+        assert_eq!(sig.version, IpVersion::Any);
+        assert_eq!(sig.ittl, 64);
+        assert_eq!(sig.mss, None);
+        assert_eq!(sig.window, WindowSizePattern::Mss(20));
+        assert_eq!(sig.window_scale, Some(10));
+        assert_eq!(
+            sig.options,
+            [
+                TcpOptionType::Mss,
+                TcpOptionType::SackPermitted,
+                TcpOptionType::Timestamp,
+                TcpOptionType::Nop,
+                TcpOptionType::WindowScale
+            ]
+        );
+        assert_eq!(sig.quirks, [Quirk::Df, Quirk::IdPlus]);
+        assert_eq!(sig.payload_class, PayloadClass::Zero);
+        assert_eq!(sig.label.version, "3.11 and newer");
+
+        let win = parse_tcp_signature(
+            "s:win:Windows:7 or 8",
+            "4:128-:0:1460:8192,8:mss,nop,ws,nop,nop,sok,eol+1:df,id+:0",
+        )
+        .unwrap();
+        assert!(win.bad_ttl);
+        assert_eq!(win.options.last(), Some(&TcpOptionType::Eol(1)));
+
+        assert!(parse_tcp_signature("s:unix:Linux:x", "*:64:0:*:mss*20,10:mss").is_err());
+        assert!(parse_tcp_signature("s:unix:Linux:x", "*:64:0:*:mss*20,10:mss:weird:0").is_err());
+
+        let http = parse_http_signature(
+            "s:!:Firefox:10.x or newer",
+            "1:Host,User-Agent,Accept=[,*/*;q=],?Accept-Language=[;q=],Accept-Encoding=[gzip, deflate],?DNT=[1],Connection=[keep-alive]:Accept-Charset,Keep-Alive:Firefox/",
+        )
+        .unwrap();
+        assert_eq!(http.version, Some(1));
+        assert_eq!(http.headers.len(), 7);
+        assert_eq!(http.headers[2].value.as_deref(), Some(",*/*;q="));
+        assert!(http.headers[3].optional);
+        assert_eq!(http.absent, ["Accept-Charset", "Keep-Alive"]);
+        assert_eq!(http.software.as_deref(), Some("Firefox/"));
+    }
+}
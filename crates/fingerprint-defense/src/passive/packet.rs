@@ -138,10 +138,163 @@ impl PacketParser {
         Self
     }
 
-    /// Parse raw packet data
-    pub fn parse(&self, _data: &[u8]) -> Result<Packet, PacketError> {
-        // Simplified implementation - would need full packet parsing logic
-        Err(PacketError::ParseError("Not implemented".to_string()))
+    /// Parse an IPv4 or IPv6 packet (without link-layer header)
+    pub fn parse(&self, data: &[u8]) -> Result<Packet, PacketError> {
+        let version = data.first().ok_or(PacketError::InvalidFormat)? >> 4;
+        let (src_ip, dst_ip, protocol, ttl, ip_flags, l4) = match version {
+            4 => {
+                let ihl = (data[0] & 0x0f) as usize * 4;
+                if ihl < 20 || data.len() < ihl {
+                    return Err(PacketError::InvalidFormat);
+                }
+                // total length bounds the datagram; captures may carry Ethernet padding
+                let total =
+                    (u16::from_be_bytes([data[2], data[3]]) as usize).clamp(ihl, data.len());
+                let src: [u8; 4] = data[12..16].try_into().unwrap();
+                let dst: [u8; 4] = data[16..20].try_into().unwrap();
+                (
+                    IpAddr::from(src),
+                    IpAddr::from(dst),
+                    data[9],
+                    data[8],
+                    data[6] >> 5,
+                    &data[ihl..total],
+                )
+            }
+            6 => {
+                if data.len() < 40 {
+                    return Err(PacketError::InvalidFormat);
+                }
+                // extension headers are not followed
+                let total = (40 + u16::from_be_bytes([data[4], data[5]]) as usize).min(data.len());
+                let src: [u8; 16] = data[8..24].try_into().unwrap();
+                let dst: [u8; 16] = data[24..40].try_into().unwrap();
+                (
+                    IpAddr::from(src),
+                    IpAddr::from(dst),
+                    data[6],
+                    data[7],
+                    0,
+                    &data[40..total],
+                )
+            }
+            _ => return Err(PacketError::ParseError(format!("IP version {}", version))),
+        };
+
+        let (src_port, dst_port, payload, tcp_header) = match protocol {
+            6 => {
+                let header = Self::parse_tcp(l4)?;
+                let payload = l4[header.data_offset as usize * 4..].to_vec();
+                (header.src_port, header.dst_port, payload, Some(header))
+            }
+            17 => {
+                if l4.len() < 8 {
+                    return Err(PacketError::InvalidFormat);
+                }
+                (
+                    u16::from_be_bytes([l4[0], l4[1]]),
+                    u16::from_be_bytes([l4[2], l4[3]]),
+                    l4[8..].to_vec(),
+                    None,
+                )
+            }
+            other => return Err(PacketError::UnsupportedProtocol(other)),
+        };
+
+        Ok(Packet {
+            src_ip,
+            dst_ip,
+            src_port: Some(src_port),
+            dst_port: Some(dst_port),
+            protocol,
+            ttl,
+            ip_flags,
+            data: data.to_vec(),
+            payload,
+            tcp_header,
+        })
+    }
+
+    fn parse_tcp(data: &[u8]) -> Result<TcpHeader, PacketError> {
+        if data.len() < 20 {
+            return Err(PacketError::InvalidFormat);
+        }
+        let data_offset = data[12] >> 4;
+        let header_len = data_offset as usize * 4;
+        if header_len < 20 || data.len() < header_len {
+            return Err(PacketError::InvalidFormat);
+        }
+        let flags = data[13];
+        Ok(TcpHeader {
+            src_port: u16::from_be_bytes([data[0], data[1]]),
+            dst_port: u16::from_be_bytes([data[2], data[3]]),
+            seq: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            ack: u32::from_be_bytes([data[8], data[9], data[10], data[11]]),
+            data_offset,
+            flags: TcpFlags {
+                fin: flags & 0x01 != 0,
+                syn: flags & 0x02 != 0,
+                rst: flags & 0x04 != 0,
+                psh: flags & 0x08 != 0,
+                ack: flags & 0x10 != 0,
+                urg: flags & 0x20 != 0,
+                ece: flags & 0x40 != 0,
+                cwr: flags & 0x80 != 0,
+            },
+            window: u16::from_be_bytes([data[14], data[15]]),
+            checksum: u16::from_be_bytes([data[16], data[17]]),
+            urgent_ptr: u16::from_be_bytes([data[18], data[19]]),
+            options: Self::parse_tcp_options(&data[20..header_len]),
+        })
+    }
+
+    fn parse_tcp_options(mut data: &[u8]) -> Vec<TcpOption> {
+        let mut options = Vec::new();
+        while let Some(&kind) = data.first() {
+            match kind {
+                0 => {
+                    options.push(TcpOption::EOL);
+                    break;
+                }
+                1 => {
+                    options.push(TcpOption::NOP);
+                    data = &data[1..];
+                    continue;
+                }
+                _ => {}
+            }
+            let len = match data.get(1) {
+                Some(&len) if len >= 2 && len as usize <= data.len() => len as usize,
+                // truncated or malformed option: stop rather than misread the rest
+                _ => break,
+            };
+            let body = &data[2..len];
+            options.push(match (kind, body.len()) {
+                (2, 2) => TcpOption::MSS(u16::from_be_bytes([body[0], body[1]])),
+                (3, 1) => TcpOption::WindowScale(body[0]),
+                (4, 0) => TcpOption::SackPermitted,
+                (5, n) if n % 8 == 0 => TcpOption::Sack(
+                    body.chunks_exact(8)
+                        .map(|b| {
+                            (
+                                u32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+                                u32::from_be_bytes([b[4], b[5], b[6], b[7]]),
+                            )
+                        })
+                        .collect(),
+                ),
+                (8, 8) => TcpOption::Timestamp {
+                    tsval: u32::from_be_bytes([body[0], body[1], body[2], body[3]]),
+                    tsecr: u32::from_be_bytes([body[4], body[5], body[6], body[7]]),
+                },
+                _ => TcpOption::Unknown {
+                    kind,
+                    data: body.to_vec(),
+                },
+            });
+            data = &data[len..];
+        }
+        options
     }
 }
 
@@ -175,3 +328,65 @@ impl std::fmt::Display for PacketError {
 }
 
 impl std::error::Error for PacketError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ipv4_syn(options: &[u8]) -> Vec<u8> {
+        let tcp_len = 20 + options.len();
+        let mut packet = vec![
+            0x45, 0x00, 0x00, 0x00, 0x12, 0x34, 0x40, 0x00, 64, 6, 0x00, 0x00, 192, 168, 1, 100,
+            93, 184, 216, 34,
+        ];
+        packet[2..4].copy_from_slice(&((20 + tcp_len) as u16).to_be_bytes());
+        packet.extend_from_slice(&54321u16.to_be_bytes());
+        packet.extend_from_slice(&443u16.to_be_bytes());
+        packet.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0]);
+        packet.push(((tcp_len / 4) as u8) << 4);
+        packet.push(0x02);
+        packet.extend_from_slice(&64240u16.to_be_bytes());
+        packet.extend_from_slice(&[0, 0, 0, 0]);
+        packet.extend_from_slice(options);
+        packet
+    }
+
+    #[test]
+    fn parses_ipv4_syn_with_options() {
+        let options = [
+            2, 4, 0x05, 0xb4, 1, 3, 3, 8, 4, 2, 8, 10, 0, 0, 0, 7, 0, 0, 0, 0,
+        ];
+        let mut data = ipv4_syn(&options);
+        // Ethernet padding past the IP total length
+        data.extend_from_slice(&[0; 6]);
+        let packet = PacketParser::new().parse(&data).unwrap();
+
+        assert_eq!(packet.src_ip, IpAddr::from([192, 168, 1, 100]));
+        assert_eq!(packet.dst_port, Some(443));
+        assert_eq!(packet.ttl, 64);
+        assert_eq!(packet.ip_flags, 0x02);
+        assert!(packet.payload.is_empty());
+        let tcp = packet.tcp_header.unwrap();
+        assert!(tcp.flags.syn && !tcp.flags.ack);
+        assert_eq!(tcp.window, 64240);
+        let kinds: Vec<u8> = tcp.options.iter().map(TcpOption::kind).collect();
+        assert_eq!(kinds, [2, 1, 3, 4, 8]);
+        assert!(matches!(tcp.options[0], TcpOption::MSS(1460)));
+        assert!(matches!(
+            tcp.options[4],
+            TcpOption::Timestamp { tsval: 7, tsecr: 0 }
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_packets() {
+        let data = ipv4_syn(&[]);
+        let parser = PacketParser::new();
+        assert!(parser.parse(&data[..30]).is_err());
+        assert!(parser.parse(&[]).is_err());
+        assert!(matches!(
+            parser.parse(&[0x45, 0, 0, 20, 0, 0, 0, 0, 64, 1, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2]),
+            Err(PacketError::UnsupportedProtocol(1))
+        ));
+    }
+}
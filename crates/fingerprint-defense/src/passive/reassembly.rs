@@ -0,0 +1,426 @@
+//! TCP stream reassembly
+//!
+//! ClientHellos with post-quantum key shares, and HTTP requests with large
+//! cookies, regularly span several segments, and segments arrive out of order
+//! or twice. [`TcpReassembler`] tracks each direction of a connection by
+//! sequence number, buffers segments that arrive early, drops bytes it has
+//! already delivered, and hands the contiguous application bytes of the
+//! current message to the analyzers.
+//!
+//! A message stays buffered until an analyzer recognizes it and
+//! [`TcpReassembler::consume`] is called, so the next message on the same
+//! connection starts at the beginning of the buffer. Directions that never
+//! produce a recognizable message (encrypted application data, response
+//! bodies) are abandoned once they exceed
+//! [`ReassemblyConfig::max_message_bytes`]; idle directions expire after
+//! [`ReassemblyConfig::flow_timeout`].
+
+use crate::passive::packet::Packet;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// Pushes between idle-stream sweeps
+const SWEEP_INTERVAL: u64 = 1024;
+
+/// Reassembly limits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReassemblyConfig {
+    /// Directions tracked at once; the least recently seen is evicted beyond this
+    pub max_streams: usize,
+    /// Idle time after which a direction is dropped
+    pub flow_timeout: Duration,
+    /// Buffered bytes of one message before the direction is abandoned
+    pub max_message_bytes: usize,
+    /// Early segments buffered per direction
+    pub max_out_of_order_segments: usize,
+}
+
+impl Default for ReassemblyConfig {
+    fn default() -> Self {
+        Self {
+            max_streams: 10_000,
+            flow_timeout: Duration::from_secs(60),
+            max_message_bytes: 64 * 1024,
+            max_out_of_order_segments: 64,
+        }
+    }
+}
+
+/// One direction of a TCP connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StreamKey {
+    pub src: SocketAddr,
+    pub dst: SocketAddr,
+}
+
+impl StreamKey {
+    /// Direction a TCP packet travels in, `None` for other packets
+    pub fn from_packet(packet: &Packet) -> Option<Self> {
+        let tcp = packet.tcp_header.as_ref()?;
+        Some(Self {
+            src: SocketAddr::new(packet.src_ip, tcp.src_port),
+            dst: SocketAddr::new(packet.dst_ip, tcp.dst_port),
+        })
+    }
+
+    /// The opposite direction of the same connection
+    pub fn reverse(&self) -> Self {
+        Self {
+            src: self.dst,
+            dst: self.src,
+        }
+    }
+}
+
+/// Reassembly counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReassemblyStats {
+    /// Directions currently tracked
+    pub streams: usize,
+    /// Segments with payload seen
+    pub segments: u64,
+    /// Segments that arrived ahead of the expected sequence number
+    pub out_of_order: u64,
+    /// Segments (or parts of them) that repeated delivered bytes
+    pub retransmitted: u64,
+    /// Early segments dropped because the out-of-order buffer was full
+    pub dropped: u64,
+    /// Directions given up after exceeding the message size limit
+    pub abandoned: u64,
+    /// Directions dropped after the idle timeout or evicted for space
+    pub expired: u64,
+}
+
+#[derive(Debug)]
+struct Stream {
+    /// Sequence number of the next byte to deliver
+    next_seq: u32,
+    /// Contiguous bytes of the current message
+    buffer: Vec<u8>,
+    /// Segments ahead of `next_seq`
+    pending: Vec<(u32, Vec<u8>)>,
+    last_seen: Instant,
+    abandoned: bool,
+}
+
+impl Stream {
+    fn new(next_seq: u32, now: Instant) -> Self {
+        Self {
+            next_seq,
+            buffer: Vec::new(),
+            pending: Vec::new(),
+            last_seen: now,
+            abandoned: false,
+        }
+    }
+}
+
+/// Signed distance from `from` to `to` in sequence space
+fn seq_diff(to: u32, from: u32) -> i32 {
+    to.wrapping_sub(from) as i32
+}
+
+/// Per-direction TCP reassembly
+#[derive(Debug)]
+pub struct TcpReassembler {
+    config: ReassemblyConfig,
+    streams: HashMap<StreamKey, Stream>,
+    stats: ReassemblyStats,
+    pushes: u64,
+}
+
+impl TcpReassembler {
+    pub fn new(config: ReassemblyConfig) -> Self {
+        Self {
+            config,
+            streams: HashMap::new(),
+            stats: ReassemblyStats::default(),
+            pushes: 0,
+        }
+    }
+
+    pub fn config(&self) -> &ReassemblyConfig {
+        &self.config
+    }
+
+    pub fn stats(&self) -> ReassemblyStats {
+        ReassemblyStats {
+            streams: self.streams.len(),
+            ..self.stats
+        }
+    }
+
+    /// Feed one packet
+    ///
+    /// Returns a copy of `packet` whose payload is the reassembled message so
+    /// far when the packet extended it, and `None` when it added nothing new
+    /// (handshake, pure ACK, retransmission, early segment, abandoned stream)
+    /// or is not TCP.
+    pub fn push(&mut self, packet: &Packet, now: Instant) -> Option<Packet> {
+        let key = StreamKey::from_packet(packet)?;
+        let tcp = packet.tcp_header.as_ref()?;
+
+        self.pushes += 1;
+        if self.pushes.is_multiple_of(SWEEP_INTERVAL) {
+            self.expire(now);
+        }
+
+        if tcp.flags.rst {
+            self.remove(&key);
+            self.remove(&key.reverse());
+            return None;
+        }
+        if tcp.flags.syn {
+            // a SYN starts a new connection even if the 4-tuple is reused
+            self.make_room(&key, now);
+            self.streams
+                .insert(key, Stream::new(tcp.seq.wrapping_add(1), now));
+            return None;
+        }
+        if packet.payload.is_empty() {
+            if tcp.flags.fin {
+                self.remove(&key);
+            }
+            return None;
+        }
+
+        self.stats.segments += 1;
+        if !self.streams.contains_key(&key) {
+            // connection picked up mid-stream: start at this segment
+            self.make_room(&key, now);
+            self.streams.insert(key, Stream::new(tcp.seq, now));
+        }
+        let config = &self.config;
+        let stats = &mut self.stats;
+        let stream = self.streams.get_mut(&key)?;
+        stream.last_seen = now;
+        if stream.abandoned {
+            return None;
+        }
+
+        let before = stream.buffer.len();
+        if seq_diff(tcp.seq, stream.next_seq) > 0 {
+            stats.out_of_order += 1;
+            if stream.pending.len() >= config.max_out_of_order_segments {
+                stats.dropped += 1;
+            } else if !stream.pending.iter().any(|(seq, _)| *seq == tcp.seq) {
+                stream.pending.push((tcp.seq, packet.payload.clone()));
+            }
+        } else {
+            Self::append(stream, stats, tcp.seq, &packet.payload);
+            // drain early segments that are now in sequence
+            while let Some(index) = stream
+                .pending
+                .iter()
+                .position(|(seq, _)| seq_diff(*seq, stream.next_seq) <= 0)
+            {
+                let (seq, data) = stream.pending.swap_remove(index);
+                Self::append(stream, stats, seq, &data);
+            }
+        }
+
+        if stream.buffer.len() > config.max_message_bytes {
+            stream.abandoned = true;
+            stream.buffer = Vec::new();
+            stream.pending = Vec::new();
+            stats.abandoned += 1;
+            return None;
+        }
+        let delivered = (stream.buffer.len() > before).then(|| Packet {
+            payload: stream.buffer.clone(),
+            ..packet.clone()
+        });
+
+        if tcp.flags.fin {
+            self.remove(&key);
+        }
+        delivered
+    }
+
+    /// Append the part of a segment at `seq` that follows `next_seq`
+    fn append(stream: &mut Stream, stats: &mut ReassemblyStats, seq: u32, data: &[u8]) {
+        let overlap = (-seq_diff(seq, stream.next_seq)) as usize;
+        if overlap > 0 {
+            stats.retransmitted += 1;
+        }
+        if overlap >= data.len() {
+            return;
+        }
+        let fresh = &data[overlap..];
+        stream.buffer.extend_from_slice(fresh);
+        stream.next_seq = stream.next_seq.wrapping_add(fresh.len() as u32);
+    }
+
+    /// Mark the buffered message of `key` as analyzed
+    ///
+    /// Sequence tracking continues, so the next message starts a new buffer.
+    pub fn consume(&mut self, key: &StreamKey) {
+        if let Some(stream) = self.streams.get_mut(key) {
+            stream.buffer.clear();
+        }
+    }
+
+    /// Drop directions idle for longer than the flow timeout
+    pub fn expire(&mut self, now: Instant) -> usize {
+        let timeout = self.config.flow_timeout;
+        let before = self.streams.len();
+        self.streams
+            .retain(|_, stream| now.saturating_duration_since(stream.last_seen) <= timeout);
+        let expired = before - self.streams.len();
+        self.stats.expired += expired as u64;
+        expired
+    }
+
+    fn remove(&mut self, key: &StreamKey) {
+        self.streams.remove(key);
+    }
+
+    /// Free a slot for `key` when the stream table is full
+    fn make_room(&mut self, key: &StreamKey, now: Instant) {
+        if self.streams.len() < self.config.max_streams || self.streams.contains_key(key) {
+            return;
+        }
+        if self.expire(now) > 0 {
+            return;
+        }
+        let oldest = self
+            .streams
+            .iter()
+            .min_by_key(|(_, stream)| stream.last_seen)
+            .map(|(key, _)| *key);
+        if let Some(oldest) = oldest {
+            self.streams.remove(&oldest);
+            self.stats.expired += 1;
+        }
+    }
+}
+
+impl Default for TcpReassembler {
+    fn default() -> Self {
+        Self::new(ReassemblyConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::passive::packet::{TcpFlags, TcpHeader};
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn segment(seq: u32, flags: TcpFlags, payload: &[u8]) -> Packet {
+        Packet {
+            src_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            dst_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+            src_port: Some(40000),
+            dst_port: Some(443),
+            protocol: 6,
+            ttl: 64,
+            ip_flags: 0,
+            data: Vec::new(),
+            payload: payload.to_vec(),
+            tcp_header: Some(TcpHeader {
+                src_port: 40000,
+                dst_port: 443,
+                seq,
+                ack: 0,
+                data_offset: 5,
+                flags,
+                window: 65535,
+                checksum: 0,
+                urgent_ptr: 0,
+                options: Vec::new(),
+            }),
+        }
+    }
+
+    fn data(seq: u32, payload: &[u8]) -> Packet {
+        let flags = TcpFlags {
+            ack: true,
+            psh: true,
+            ..Default::default()
+        };
+        segment(seq, flags, payload)
+    }
+
+    #[test]
+    fn reorders_and_deduplicates_segments() {
+        let now = Instant::now();
+        let mut reassembler = TcpReassembler::default();
+        let syn = TcpFlags {
+            syn: true,
+            ..Default::default()
+        };
+        // sequence numbers wrap inside the stream
+        let isn = u32::MAX - 3;
+        assert!(reassembler.push(&segment(isn, syn, b""), now).is_none());
+
+        let first = isn.wrapping_add(1);
+        // third segment arrives first and is held back
+        assert!(reassembler
+            .push(&data(first.wrapping_add(6), b"ghi"), now)
+            .is_none());
+        let out = reassembler.push(&data(first, b"abc"), now).unwrap();
+        assert_eq!(out.payload, b"abc");
+        // retransmission overlapping delivered bytes releases the held segment
+        let out = reassembler.push(&data(first, b"abcdef"), now).unwrap();
+        assert_eq!(out.payload, b"abcdefghi");
+        assert!(reassembler.push(&data(first, b"abc"), now).is_none());
+
+        let key = StreamKey::from_packet(&out).unwrap();
+        reassembler.consume(&key);
+        let out = reassembler
+            .push(&data(first.wrapping_add(9), b"next"), now)
+            .unwrap();
+        assert_eq!(out.payload, b"next");
+
+        let stats = reassembler.stats();
+        assert_eq!(stats.out_of_order, 1);
+        assert_eq!(stats.retransmitted, 2);
+        assert_eq!(stats.streams, 1);
+    }
+
+    #[test]
+    fn abandons_oversized_and_expires_idle_streams() {
+        let now = Instant::now();
+        let config = ReassemblyConfig {
+            max_message_bytes: 8,
+            ..Default::default()
+        };
+        let mut reassembler = TcpReassembler::new(config);
+        assert!(reassembler.push(&data(100, b"12345"), now).is_some());
+        assert!(reassembler.push(&data(105, b"67890"), now).is_none());
+        assert!(reassembler.push(&data(110, b"more"), now).is_none());
+        assert_eq!(reassembler.stats().abandoned, 1);
+
+        let later = now + Duration::from_secs(61);
+        assert_eq!(reassembler.expire(later), 1);
+        assert_eq!(reassembler.stats().streams, 0);
+    }
+
+    #[test]
+    fn analyzer_sees_client_hello_split_across_segments() {
+        use crate::passive::PassiveAnalyzer;
+        use fingerprint_tls::tls_config::ClientHelloSpec;
+        use fingerprint_tls::tls_handshake::TLSHandshakeBuilder;
+
+        let hello =
+            TLSHandshakeBuilder::build_client_hello(&ClientHelloSpec::chrome_133(), "example.com")
+                .unwrap();
+        let (head, tail) = hello.split_at(hello.len() / 2);
+        let tail_seq = 1 + head.len() as u32;
+
+        let per_packet = PassiveAnalyzer::new().unwrap().without_reassembly();
+        assert!(per_packet.analyze(&data(1, head)).tls.is_none());
+        assert!(per_packet.analyze(&data(tail_seq, tail)).tls.is_none());
+
+        let analyzer = PassiveAnalyzer::new().unwrap();
+        // the head is seen first so the stream is picked up at seq 1; its tail is
+        // then retransmitted out of order
+        assert!(analyzer.analyze(&data(1, &head[..10])).tls.is_none());
+        assert!(analyzer.analyze(&data(tail_seq, tail)).tls.is_none());
+        let result = analyzer.analyze(&data(1, head));
+        assert!(result.tls.and_then(|tls| tls.ja4).is_some());
+        assert_eq!(analyzer.reassembly_stats().unwrap().out_of_order, 1);
+    }
+}
@@ -2,7 +2,9 @@
 //!
 //! implement p0f style TCP fingerprintidentify.
 
-use crate::passive::packet::{Packet, TcpHeader};
+use crate::passive::p0f::{P0fStore, DEFAULT_MAX_DISTANCE};
+use crate::passive::p0f_parser::{Quirk, TcpOptionType};
+use crate::passive::packet::{Packet, TcpHeader, TcpOption};
 use std::collections::HashMap;
 
 use fingerprint_core::stable_hash::hash_str;
@@ -25,6 +27,9 @@ pub struct TcpSignature {
 pub struct TcpAnalyzer {
     /// signaturedatabase
     signatures: HashMap<String, TcpSignature>,
+
+    /// p0f signatures, preferred for SYN and SYN+ACK packets
+    p0f: Option<P0fStore>,
 }
 
 /// TCP fingerprint
@@ -109,6 +114,26 @@ pub struct TcpFeatures {
 
     /// IP flag
     pub ip_flags: u8,
+
+    /// IP version (4 or 6)
+    #[serde(default)]
+    pub ip_version: u8,
+
+    /// TCP options in wire order
+    #[serde(default)]
+    pub options: Vec<TcpOptionType>,
+
+    /// p0f quirks observed on the packet
+    #[serde(default)]
+    pub quirks: Vec<Quirk>,
+
+    /// TCP payload length
+    #[serde(default)]
+    pub payload_len: usize,
+
+    /// packet is a SYN+ACK
+    #[serde(default)]
+    pub syn_ack: bool,
 }
 
 impl TcpAnalyzer {
@@ -116,6 +141,7 @@ impl TcpAnalyzer {
     pub fn new() -> Result<Self, String> {
         let mut analyzer = Self {
             signatures: HashMap::new(),
+            p0f: None,
         };
 
         // load defaultsignature
@@ -125,18 +151,25 @@ impl TcpAnalyzer {
     }
 
     /// from p0f databaseloadsignature
+    ///
+    /// The file is kept in a [`P0fStore`]; call [`P0fStore::reload`] or
+    /// [`P0fStore::watch`] on [`TcpAnalyzer::p0f`] to pick up edits.
     pub fn load_from_p0f<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), String> {
-        use crate::passive::p0f::P0fDatabase;
-
-        let db = P0fDatabase::load_from_file(path)
-            .map_err(|e| format!("Failed to load p0f database: {}", e))?;
+        let store =
+            P0fStore::open(path).map_err(|e| format!("Failed to load p0f database: {}", e))?;
+        self.p0f = Some(store);
+        Ok(())
+    }
 
-        // loadall TCP requestsignature
-        for sig in db.get_all_tcp_request() {
-            self.signatures.insert(sig.id.clone(), sig.clone());
-        }
+    /// Match SYN and SYN+ACK packets against a p0f database
+    pub fn with_p0f(mut self, store: P0fStore) -> Self {
+        self.p0f = Some(store);
+        self
+    }
 
-        Ok(())
+    /// p0f database in use, if any
+    pub fn p0f(&self) -> Option<&P0fStore> {
+        self.p0f.as_ref()
     }
 
     /// load defaultsignature
@@ -194,10 +227,25 @@ impl TcpAnalyzer {
         let features = self.extract_features(packet, tcp_header);
 
         // matchsignature
-        let (signature, similarity) = self.match_signature(&features);
+        let (mut signature, mut similarity) = self.match_signature(&features);
 
         let mut metadata = fingerprint_core::metadata::FingerprintMetadata::new();
 
+        // p0f signatures describe the opening handshake only
+        if tcp_header.flags.syn {
+            if let Some(m) = self
+                .p0f
+                .as_ref()
+                .and_then(|store| store.match_tcp(&features, DEFAULT_MAX_DISTANCE))
+            {
+                metadata.set("p0f_label", &m.label());
+                metadata.set("p0f_distance", &m.distance.to_string());
+                metadata.set("p0f_hops", &m.hops.to_string());
+                similarity = m.score;
+                signature = Some(m.signature.into());
+            }
+        }
+
         if let Some(os_type) = signature.as_ref().and_then(|sig| sig.os_type.clone()) {
             metadata.set("os", &os_type);
         }
@@ -234,6 +282,11 @@ impl TcpAnalyzer {
         // Generateoptionsstring
         let options_str = self.build_options_string(&tcp_header.options);
 
+        let ip_version = match packet.src_ip {
+            std::net::IpAddr::V4(_) => 4,
+            std::net::IpAddr::V6(_) => 6,
+        };
+
         TcpFeatures {
             ttl: packet.ttl,
             initial_ttl,
@@ -242,7 +295,65 @@ impl TcpAnalyzer {
             window_scale,
             options_str,
             ip_flags: packet.ip_flags,
+            ip_version,
+            options: tcp_header
+                .options
+                .iter()
+                .map(|opt| TcpOptionType::from_kind(opt.kind()))
+                .collect(),
+            quirks: self.extract_quirks(packet, tcp_header, ip_version),
+            payload_len: packet.payload.len(),
+            syn_ack: tcp_header.flags.syn && tcp_header.flags.ack,
+        }
+    }
+
+    /// Extract the p0f quirks visible in the parsed packet
+    fn extract_quirks(&self, packet: &Packet, tcp: &TcpHeader, ip_version: u8) -> Vec<Quirk> {
+        let flags = &tcp.flags;
+        let mut quirks = Vec::new();
+        if ip_version == 4 && packet.ip_flags & 0x02 != 0 {
+            quirks.push(Quirk::Df);
+        }
+        if flags.ece || flags.cwr {
+            quirks.push(Quirk::Ecn);
+        }
+        if tcp.seq == 0 {
+            quirks.push(Quirk::SeqMinus);
+        }
+        if !flags.ack && tcp.ack != 0 {
+            quirks.push(Quirk::AckPlus);
+        }
+        if flags.ack && tcp.ack == 0 {
+            quirks.push(Quirk::AckMinus);
+        }
+        if !flags.urg && tcp.urgent_ptr != 0 {
+            quirks.push(Quirk::UptrPlus);
+        }
+        if flags.urg {
+            quirks.push(Quirk::UrgfPlus);
+        }
+        if flags.psh {
+            quirks.push(Quirk::PushfPlus);
+        }
+        for opt in &tcp.options {
+            match opt {
+                TcpOption::Timestamp { tsval, tsecr } => {
+                    if *tsval == 0 {
+                        quirks.push(Quirk::Ts1Minus);
+                    }
+                    if *tsecr != 0 && flags.syn && !flags.ack {
+                        quirks.push(Quirk::Ts2Plus);
+                    }
+                }
+                TcpOption::WindowScale(scale) if *scale > 14 => quirks.push(Quirk::Exws),
+                // known kinds only end up here when their length is wrong
+                TcpOption::Unknown { kind: 2..=8, .. } if !quirks.contains(&Quirk::Bad) => {
+                    quirks.push(Quirk::Bad)
+                }
+                _ => {}
+            }
         }
+        quirks
     }
 
     /// inferinitialbeginning TTL
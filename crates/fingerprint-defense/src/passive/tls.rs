@@ -1,9 +1,16 @@
 //! TLS passivefingerprintidentify
 //!
 //! implement TLS ClientHello passiveanalysis and JA4 fingerprintGenerate.
+//! Server flights (ServerHello and, before TLS 1.3, the plaintext certificate
+//! chain) yield JA3S/JA4S and per-certificate JA4X.
 
 use crate::passive::packet::Packet;
+use fingerprint_core::ja3::JA3S;
 use fingerprint_core::stable_hash::StableHashBuilder;
+use fingerprint_tls::tls_config::{
+    certificate_issuer, first_last_alpn, hash12, ClientHelloSignature, Ja4xSignature,
+    MobileTlsStack,
+};
 
 /// TLS analysiser
 pub struct TlsAnalyzer;
@@ -71,6 +78,120 @@ impl fingerprint_core::fingerprint::Fingerprint for TlsFingerprint {
     }
 }
 
+/// Certificate from the server's chain
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ServerCertificate {
+    /// DER length in bytes
+    pub der_len: usize,
+    /// JA4X fingerprint, `None` if the certificate could not be decoded
+    pub ja4x: Option<String>,
+    /// issuer, e.g. `C=US, O=Cloudflare, Inc., CN=Cloudflare Inc ECC CA-3`
+    #[serde(default)]
+    pub issuer: Option<String>,
+}
+
+/// TLS server fingerprint (ServerHello and certificate chain)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsServerFingerprint {
+    /// JA3S fingerprint (MD5)
+    pub ja3s: String,
+
+    /// JA3S string before hashing
+    pub ja3s_raw: String,
+
+    /// JA4S fingerprint
+    pub ja4s: String,
+
+    /// JA4S with the extension list in clear
+    pub ja4s_raw: String,
+
+    /// negotiated version (supported_versions if present, else legacy_version)
+    pub version: u16,
+
+    /// selected cipher suite
+    pub cipher_suite: u16,
+
+    /// extensions in ServerHello order
+    pub extensions: Vec<u16>,
+
+    /// selected ALPN protocol
+    pub alpn: Option<String>,
+
+    /// certificate chain, leaf first (empty for TLS 1.3, where it is encrypted)
+    pub certificates: Vec<ServerCertificate>,
+
+    /// fingerprintmetadata
+    pub metadata: fingerprint_core::metadata::FingerprintMetadata,
+}
+
+impl fingerprint_core::fingerprint::Fingerprint for TlsServerFingerprint {
+    fn fingerprint_type(&self) -> fingerprint_core::fingerprint::FingerprintType {
+        fingerprint_core::fingerprint::FingerprintType::Tls
+    }
+
+    fn id(&self) -> String {
+        self.ja4s.clone()
+    }
+
+    fn metadata(&self) -> &fingerprint_core::metadata::FingerprintMetadata {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut fingerprint_core::metadata::FingerprintMetadata {
+        &mut self.metadata
+    }
+
+    fn hash(&self) -> u64 {
+        let mut hasher = StableHashBuilder::new();
+        hasher.write_str(&self.ja4s);
+        hasher.write_str(&self.ja3s);
+        hasher.finish()
+    }
+
+    fn similar_to(&self, other: &dyn fingerprint_core::fingerprint::Fingerprint) -> bool {
+        if other.fingerprint_type() != fingerprint_core::fingerprint::FingerprintType::Tls {
+            return false;
+        }
+        self.id() == other.id()
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "TLS Server Fingerprint (JA4S: {}, JA3S: {})",
+            self.ja4s, self.ja3s
+        )
+    }
+}
+
+/// Bounds-checked reader over handshake bytes
+struct Cursor<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.data.len() < n {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(n);
+        self.data = tail;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u24(&mut self) -> Option<usize> {
+        self.take(3)
+            .map(|b| (b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize)
+    }
+}
+
 impl TlsAnalyzer {
     /// Create a new TLS analysiser
     pub fn new() -> Result<Self, String> {
@@ -87,6 +208,166 @@ impl TlsAnalyzer {
         None
     }
 
+    /// analysis the server side of a handshake (ServerHello flight)
+    pub fn analyze_server(&self, packet: &Packet) -> Option<TlsServerFingerprint> {
+        let handshake = Self::server_handshake(&packet.payload)?;
+        let mut messages = Cursor { data: &handshake };
+        let mut fingerprint = None;
+        while let (Some(kind), Some(len)) = (messages.u8(), messages.u24()) {
+            let Some(body) = messages.take(len) else {
+                break; // message continues in a later segment
+            };
+            match kind {
+                0x02 if fingerprint.is_none() => {
+                    fingerprint = Some(self.analyze_server_hello(body)?);
+                }
+                0x0b => {
+                    if let Some(fp) = fingerprint.as_mut() {
+                        fp.certificates = Self::certificate_chain(body);
+                        if let Some(ja4x) = fp.certificates.first().and_then(|c| c.ja4x.clone()) {
+                            fp.metadata.add_tag(format!("ja4x:{}", ja4x));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        fingerprint
+    }
+
+    /// Handshake bytes of the consecutive handshake records starting at the ServerHello
+    fn server_handshake(data: &[u8]) -> Option<Vec<u8>> {
+        let start = (0..data.len().saturating_sub(5))
+            .find(|&i| data[i] == 0x16 && data[i + 1] == 0x03 && data[i + 5] == 0x02)?;
+
+        let mut handshake = Vec::new();
+        let mut records = Cursor {
+            data: &data[start..],
+        };
+        while records.data.first() == Some(&0x16) {
+            let Some(len) = records.take(3).and(records.u16()) else {
+                break;
+            };
+            // keep whatever part of the last record was captured
+            let available = (len as usize).min(records.data.len());
+            handshake.extend_from_slice(records.take(available)?);
+        }
+        Some(handshake)
+    }
+
+    /// analysis ServerHello body (after type and length)
+    fn analyze_server_hello(&self, body: &[u8]) -> Option<TlsServerFingerprint> {
+        // [Version(2)][Random(32)][SessionID][CipherSuite(2)][Compression(1)][Extensions]
+        let mut hello = Cursor { data: body };
+        let legacy_version = hello.u16()?;
+        hello.take(32)?;
+        let session_id_len = hello.u8()? as usize;
+        hello.take(session_id_len)?;
+        let cipher_suite = hello.u16()?;
+        hello.u8()?;
+
+        let mut version = legacy_version;
+        let mut extensions = Vec::new();
+        let mut alpn = None;
+        if let Some(len) = hello.u16() {
+            let mut exts = Cursor {
+                data: hello.take(len as usize)?,
+            };
+            while let (Some(ext_type), Some(ext_len)) = (exts.u16(), exts.u16()) {
+                let mut ext = Cursor {
+                    data: exts.take(ext_len as usize)?,
+                };
+                extensions.push(ext_type);
+                match ext_type {
+                    // supported_versions: the selected version
+                    43 => version = ext.u16().unwrap_or(version),
+                    // ALPN: exactly one protocol in a ServerHello
+                    16 => {
+                        alpn = ext
+                            .u16()
+                            .and_then(|_| ext.u8())
+                            .and_then(|n| ext.take(n as usize))
+                            .map(|p| String::from_utf8_lossy(p).to_string());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let ja3s = JA3S::generate(legacy_version, cipher_suite, &extensions);
+
+        let tls_ver_str = match version {
+            0x0304 => "13",
+            0x0303 => "12",
+            0x0302 => "11",
+            0x0301 => "10",
+            0x0300 => "s3",
+            _ => "00",
+        };
+        let (alpn_first, alpn_last) = alpn
+            .as_deref()
+            .filter(|p| !p.is_empty())
+            .map(first_last_alpn)
+            .unwrap_or(('0', '0'));
+        let ja4s_a = format!(
+            "t{}{:02}{}{}",
+            tls_ver_str,
+            extensions.len().min(99),
+            alpn_first,
+            alpn_last
+        );
+        let ext_list = extensions
+            .iter()
+            .map(|e| format!("{:04x}", e))
+            .collect::<Vec<_>>()
+            .join(",");
+        let ja4s_c = if extensions.is_empty() {
+            "000000000000".to_string()
+        } else {
+            hash12(&ext_list)
+        };
+        let ja4s = format!("{}_{:04x}_{}", ja4s_a, cipher_suite, ja4s_c);
+        let ja4s_raw = format!("{}_{:04x}_{}", ja4s_a, cipher_suite, ext_list);
+
+        let mut metadata = fingerprint_core::metadata::FingerprintMetadata::new();
+        metadata.add_tag(format!("ja4s:{}", ja4s));
+        metadata.add_tag(format!("ja3s:{}", ja3s.fingerprint));
+        metadata.set("tls_version", &format!("0x{:04x}", version));
+
+        Some(TlsServerFingerprint {
+            ja3s: ja3s.fingerprint,
+            ja3s_raw: ja3s.ja3s_string,
+            ja4s,
+            ja4s_raw,
+            version,
+            cipher_suite,
+            extensions,
+            alpn,
+            certificates: Vec::new(),
+            metadata,
+        })
+    }
+
+    /// Certificates of a TLS 1.2 Certificate message body
+    fn certificate_chain(body: &[u8]) -> Vec<ServerCertificate> {
+        let mut message = Cursor { data: body };
+        let Some(list) = message.u24().and_then(|len| message.take(len)) else {
+            return Vec::new();
+        };
+        let mut list = Cursor { data: list };
+        let mut certificates = Vec::new();
+        while let Some(der) = list.u24().and_then(|len| list.take(len)) {
+            certificates.push(ServerCertificate {
+                der_len: der.len(),
+                ja4x: Ja4xSignature::from_der(der)
+                    .ok()
+                    .map(|sig| sig.generate().fingerprint),
+                issuer: certificate_issuer(der).ok(),
+            });
+        }
+        certificates
+    }
+
     /// find ClientHello
     fn find_client_hello(&self, data: &[u8]) -> Option<Vec<u8>> {
         // find TLS handshakemessage
@@ -298,6 +579,18 @@ impl TlsAnalyzer {
             metadata.set("tls_version", &format!("0x{:04x}", version_value));
         }
 
+        // Native app clients: match against the iOS / Android OS TLS stacks
+        let mut signature = ClientHelloSignature::new();
+        signature.cipher_suites = cipher_suites.clone();
+        signature.extensions = extensions.clone();
+        signature.signature_algorithms = sig_algs;
+        let stacks = MobileTlsStack::identify(&signature);
+        if let Some(first) = stacks.first() {
+            metadata.add_tag(format!("mobile_stack:{}", first));
+            let names: Vec<String> = stacks.iter().map(|s| s.name()).collect();
+            metadata.set("mobile_stacks", &names.join(","));
+        }
+
         TlsFingerprint {
             version,
             cipher_suites_count: cipher_suites.len(),
@@ -321,3 +614,181 @@ impl Default for TlsFingerprint {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fingerprint_tls::tls_config::ClientHelloSpec;
+    use fingerprint_tls::tls_handshake::TLSHandshakeBuilder;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn packet(payload: Vec<u8>) -> Packet {
+        Packet {
+            src_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+            dst_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            src_port: Some(50123),
+            dst_port: Some(443),
+            protocol: 6,
+            ttl: 64,
+            ip_flags: 0,
+            data: Vec::new(),
+            payload,
+            tcp_header: None,
+        }
+    }
+
+    #[test]
+    fn test_tags_android_app_client() {
+        let hello = TLSHandshakeBuilder::build_client_hello(
+            &ClientHelloSpec::android_conscrypt(34),
+            "api.example.com",
+        )
+        .unwrap();
+        let fp = TlsAnalyzer::new().unwrap().analyze(&packet(hello)).unwrap();
+        let stacks = fp.metadata.get("mobile_stacks").unwrap();
+        assert!(stacks.contains("conscrypt_api_34"));
+
+        let chrome =
+            TLSHandshakeBuilder::build_client_hello(&ClientHelloSpec::chrome_133(), "example.com")
+                .unwrap();
+        let fp = TlsAnalyzer::new()
+            .unwrap()
+            .analyze(&packet(chrome))
+            .unwrap();
+        assert!(fp.metadata.get("mobile_stacks").is_none());
+    }
+
+    fn handshake(kind: u8, body: &[u8]) -> Vec<u8> {
+        let len = body.len() as u32;
+        let mut out = vec![kind];
+        out.extend_from_slice(&len.to_be_bytes()[1..]);
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn record(fragment: &[u8]) -> Vec<u8> {
+        let mut out = vec![0x16, 0x03, 0x03];
+        out.extend_from_slice(&(fragment.len() as u16).to_be_bytes());
+        out.extend_from_slice(fragment);
+        out
+    }
+
+    fn server_hello(cipher: u16, extensions: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut body = vec![0x03, 0x03];
+        body.extend_from_slice(&[0x42; 32]);
+        body.push(0); // empty session id
+        body.extend_from_slice(&cipher.to_be_bytes());
+        body.push(0);
+        let exts: Vec<u8> = extensions
+            .iter()
+            .flat_map(|(ext_type, data)| {
+                let mut ext = ext_type.to_be_bytes().to_vec();
+                ext.extend_from_slice(&(data.len() as u16).to_be_bytes());
+                ext.extend_from_slice(data);
+                ext
+            })
+            .collect();
+        body.extend_from_slice(&(exts.len() as u16).to_be_bytes());
+        body.extend_from_slice(&exts);
+        handshake(0x02, &body)
+    }
+
+    fn tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag, value.len() as u8];
+        out.extend_from_slice(value);
+        out
+    }
+
+    /// Smallest certificate JA4X accepts: serial, issuer and subject CN
+    fn certificate() -> Vec<u8> {
+        let common_name = tlv(
+            0x31,
+            &tlv(
+                0x30,
+                &[tlv(0x06, &[0x55, 0x04, 0x03]), tlv(0x0c, b"x")].concat(),
+            ),
+        );
+        let name = tlv(0x30, &common_name);
+        let tbs = [
+            tlv(0x02, &[1]),
+            tlv(0x30, &[]),
+            name.clone(),
+            tlv(0x30, &[]),
+            name,
+            tlv(0x30, &[]),
+        ]
+        .concat();
+        tlv(0x30, &tlv(0x30, &tbs))
+    }
+
+    #[test]
+    fn test_server_hello_tls13() {
+        let alpn = vec![0x00, 0x03, 0x02, b'h', b'2'];
+        let hello = server_hello(
+            0x1301,
+            &[(43, vec![0x03, 0x04]), (16, alpn), (51, vec![0; 4])],
+        );
+        let mut payload = record(&hello);
+        // ChangeCipherSpec and encrypted records follow in the same segment
+        payload.extend_from_slice(&[0x14, 0x03, 0x03, 0x00, 0x01, 0x01]);
+
+        let analyzer = TlsAnalyzer::new().unwrap();
+        let fp = analyzer.analyze_server(&packet(payload.clone())).unwrap();
+        assert_eq!(fp.version, 0x0304);
+        assert_eq!(fp.cipher_suite, 0x1301);
+        assert_eq!(fp.extensions, vec![43, 16, 51]);
+        assert_eq!(fp.alpn.as_deref(), Some("h2"));
+        assert!(fp.ja4s.starts_with("t1303h2_1301_"));
+        assert_eq!(fp.ja4s_raw, "t1303h2_1301_002b,0010,0033");
+        assert_eq!(fp.ja3s_raw, "771,4865,43-16-51");
+        assert_eq!(
+            fp.ja3s,
+            JA3S::generate(771, 0x1301, &[43, 16, 51]).fingerprint
+        );
+        assert!(fp.certificates.is_empty());
+
+        // a server flight is not a ClientHello and vice versa
+        assert!(analyzer.analyze(&packet(payload)).is_none());
+        let client = fingerprint_tls::tls_handshake::TLSHandshakeBuilder::build_client_hello(
+            &ClientHelloSpec::chrome_133(),
+            "example.com",
+        )
+        .unwrap();
+        assert!(analyzer.analyze_server(&packet(client)).is_none());
+    }
+
+    #[test]
+    fn test_server_flight_tls12_certificate_chain() {
+        let cert = certificate();
+        let mut chain = Vec::new();
+        for der in [&cert[..], &[0x30, 0x00][..]] {
+            chain.extend_from_slice(&(der.len() as u32).to_be_bytes()[1..]);
+            chain.extend_from_slice(der);
+        }
+        let mut certificate_body = (chain.len() as u32).to_be_bytes()[1..].to_vec();
+        certificate_body.extend_from_slice(&chain);
+
+        // ServerHello in its own record, Certificate split across two records
+        let certificate_msg = handshake(0x0b, &certificate_body);
+        let (first, second) = certificate_msg.split_at(10);
+        let mut payload = record(&server_hello(0xc02f, &[(0xff01, vec![0])]));
+        payload.extend_from_slice(&record(first));
+        payload.extend_from_slice(&record(second));
+
+        let fp = TlsAnalyzer::new()
+            .unwrap()
+            .analyze_server(&packet(payload))
+            .unwrap();
+        assert_eq!(fp.version, 0x0303);
+        assert!(fp.ja4s.starts_with("t120100_c02f_"));
+        assert_eq!(fp.certificates.len(), 2);
+        let leaf = Ja4xSignature::from_der(&cert)
+            .unwrap()
+            .generate()
+            .fingerprint;
+        assert_eq!(fp.certificates[0].ja4x.as_deref(), Some(leaf.as_str()));
+        assert_eq!(fp.certificates[1].der_len, 2);
+        assert!(fp.certificates[1].ja4x.is_none());
+        assert!(fp.metadata.has_tag(&format!("ja4x:{}", leaf)));
+    }
+}
@@ -0,0 +1,391 @@
+//! Warm-standby replication of the fingerprint database
+//!
+//! The primary records every row change in a change log (`replication_log`,
+//! filled by triggers, so every write path is covered). A standby pulls the log
+//! in sequence order through a [`ChangeSource`] and applies it with
+//! [`FingerprintDatabase::apply_changes`]; [`Replicator`] runs that loop in the
+//! background and resumes from the standby's last applied sequence after a
+//! disconnect.
+//!
+//! ## Conflict rules
+//!
+//! - **Primary wins**: an upsert replaces the standby's row with the same key; a
+//!   delete of a missing row is a no-op.
+//! - **Idempotent replay**: changes at or below the standby's applied sequence
+//!   are skipped, so a batch may be re-sent after a reconnect.
+//! - **No gaps**: a batch starting past the next expected sequence is refused;
+//!   the standby fetches again from where it is.
+//! - **Epoch fencing**: promotion bumps the epoch. A standby refuses changes
+//!   from an older epoch (a demoted primary that kept writing) and from a newer
+//!   one (another standby was promoted; its sequence numbers are a new series).
+//!   In both cases it has to be reseeded from a snapshot of the current primary.
+//!
+//! ## Setting up a standby
+//!
+//! 1. On the primary, [`FingerprintDatabase::snapshot_to`] a file.
+//! 2. Open that file on the standby host and call
+//!    [`FingerprintDatabase::become_standby`].
+//! 3. Run a [`Replicator`] from a [`ChangeSource`] reaching the primary.
+//! 4. On failover, stop the replicator and call [`FingerprintDatabase::promote`].
+//!
+//! Entries acknowledged by every standby can be dropped with
+//! [`FingerprintDatabase::prune_replication_log`]; a standby that falls behind
+//! the pruned point has to be reseeded.
+
+use crate::database::FingerprintDatabase;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Tables whose changes are replicated, with their primary-key columns
+pub const REPLICATED_TABLES: &[(&str, &[&str])] = &[
+    ("flows", &["id"]),
+    ("fingerprints", &["id"]),
+    ("candidate_fingerprints", &["id"]),
+    ("fingerprint_labels", &["id"]),
+    ("fingerprint_index", &["kind", "value"]),
+    ("fingerprint_index_state", &["id"]),
+    ("fingerprint_alerts", &["id"]),
+    ("backfill_jobs", &["id"]),
+    ("flow_verdicts", &["id"]),
+    ("fingerprint_tombstones", &["id"]),
+    ("threat_intel_indicators", &["id"]),
+];
+
+/// Replication role of a database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplicationRole {
+    /// Accepts writes and logs them
+    Primary,
+    /// Applies changes shipped from a primary
+    Standby,
+}
+
+impl ReplicationRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReplicationRole::Primary => "primary",
+            ReplicationRole::Standby => "standby",
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "primary" => Ok(ReplicationRole::Primary),
+            "standby" => Ok(ReplicationRole::Standby),
+            other => Err(format!("unknown replication role: {}", other)),
+        }
+    }
+}
+
+/// Kind of row change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeOp {
+    Upsert,
+    Delete,
+}
+
+/// One row change from the primary's log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeRecord {
+    /// Position in the primary's log
+    pub seq: i64,
+    /// Changed table, one of [`REPLICATED_TABLES`]
+    pub table: String,
+    pub op: ChangeOp,
+    /// Primary-key columns of the row
+    pub key: serde_json::Map<String, serde_json::Value>,
+    /// Full row after the change, for upserts
+    pub row: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+/// Consecutive changes shipped to a standby
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeBatch {
+    /// Epoch of the primary that produced the batch
+    pub epoch: i64,
+    /// Changes in ascending `seq` order
+    pub changes: Vec<ChangeRecord>,
+    /// Latest sequence in the primary's log when the batch was read
+    pub head_seq: i64,
+}
+
+/// Replication position of a database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicationStatus {
+    pub role: ReplicationRole,
+    pub epoch: i64,
+    /// On a standby, the last primary sequence applied
+    pub applied_seq: i64,
+    /// On a primary, the latest sequence in the log
+    pub head_seq: i64,
+}
+
+impl ReplicationStatus {
+    /// Standby lag in log entries behind `primary_head`
+    pub fn lag(&self, primary_head: i64) -> i64 {
+        (primary_head - self.applied_seq).max(0)
+    }
+}
+
+/// Where a standby reads the primary's change log from
+///
+/// [`FingerprintDatabase`] implements it directly, for a standby that can open
+/// the primary's database file (shared storage); remote primaries are reached
+/// through any transport that returns the primary's
+/// [`FingerprintDatabase::changes_since`].
+pub trait ChangeSource: Send {
+    /// Up to `limit` changes with `seq > after_seq`
+    fn fetch_changes(&self, after_seq: i64, limit: usize) -> Result<ChangeBatch, String>;
+}
+
+impl ChangeSource for FingerprintDatabase {
+    fn fetch_changes(&self, after_seq: i64, limit: usize) -> Result<ChangeBatch, String> {
+        self.changes_since(after_seq, limit)
+    }
+}
+
+/// Progress of one catch-up pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CatchUp {
+    /// Changes applied during the pass
+    pub applied: usize,
+    /// Standby position afterwards
+    pub applied_seq: i64,
+    /// Primary head seen last
+    pub head_seq: i64,
+}
+
+/// Keeps a standby database in step with a primary
+pub struct Replicator {
+    source: Box<dyn ChangeSource>,
+    standby: FingerprintDatabase,
+    batch_size: usize,
+    max_backoff: Duration,
+}
+
+impl Replicator {
+    /// Replicate from `source` into `standby`, which must already be a standby
+    pub fn new(source: Box<dyn ChangeSource>, standby: FingerprintDatabase) -> Self {
+        Self {
+            source,
+            standby,
+            batch_size: 1000,
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+
+    /// Changes fetched per request
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Longest wait between retries while the primary is unreachable
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Standby database
+    pub fn standby(&self) -> &FingerprintDatabase {
+        &self.standby
+    }
+
+    /// Stop replicating and hand back the standby, e.g. to promote it
+    pub fn into_standby(self) -> FingerprintDatabase {
+        self.standby
+    }
+
+    /// Fetch and apply batches until the standby reaches the primary's head
+    pub fn catch_up(&self) -> Result<CatchUp, String> {
+        let mut progress = CatchUp::default();
+        loop {
+            let status = self.standby.replication_status()?;
+            let batch = self
+                .source
+                .fetch_changes(status.applied_seq, self.batch_size)?;
+            progress.head_seq = batch.head_seq;
+            progress.applied += self.standby.apply_changes(&batch)?;
+            progress.applied_seq = self.standby.replication_status()?.applied_seq;
+            if batch.changes.is_empty() || progress.applied_seq >= batch.head_seq {
+                return Ok(progress);
+            }
+        }
+    }
+
+    /// Catch up every `interval` until `shutdown` turns true
+    ///
+    /// Failures (primary unreachable, gap, fencing) are logged and retried with
+    /// exponential backoff up to the configured maximum; the next successful pass
+    /// resumes from the standby's applied sequence.
+    pub async fn run(self, interval: Duration, mut shutdown: watch::Receiver<bool>) -> Self {
+        let mut backoff = interval;
+        loop {
+            let wait = match self.catch_up() {
+                Ok(progress) => {
+                    if progress.applied > 0 {
+                        log::debug!(
+                            "[Replication] applied {} changes, at seq {} of {}",
+                            progress.applied,
+                            progress.applied_seq,
+                            progress.head_seq
+                        );
+                    }
+                    backoff = interval;
+                    interval
+                }
+                Err(e) => {
+                    log::warn!(
+                        "[Replication] catch-up failed, retrying in {:?}: {}",
+                        backoff,
+                        e
+                    );
+                    let wait = backoff;
+                    backoff = (backoff * 2).min(self.max_backoff);
+                    wait
+                }
+            };
+
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                changed = shutdown.changed() => {
+                    // A dropped sender also means stop
+                    if changed.is_err() {
+                        return self;
+                    }
+                }
+            }
+            if *shutdown.borrow() {
+                return self;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fingerprint_index::{IndexEntry, IndexQuery};
+    use fingerprint_fixtures::Fixtures;
+    use std::path::Path;
+
+    /// Primary writer plus a second connection the replicator reads through
+    fn primary(dir: &Path) -> (FingerprintDatabase, Box<dyn ChangeSource>) {
+        let path = dir.join("primary.db");
+        let writer = FingerprintDatabase::open(&path).unwrap();
+        let reader = FingerprintDatabase::open(&path).unwrap();
+        (writer, Box::new(reader))
+    }
+
+    fn seed_standby(primary: &FingerprintDatabase, path: &Path) -> FingerprintDatabase {
+        primary.snapshot_to(path).unwrap();
+        let standby = FingerprintDatabase::open(path).unwrap();
+        standby.become_standby().unwrap();
+        standby
+    }
+
+    fn index(db: &FingerprintDatabase) -> Vec<IndexEntry> {
+        let query = IndexQuery {
+            limit: 1000,
+            ..Default::default()
+        };
+        db.search_fingerprint_index(&query).unwrap().entries
+    }
+
+    #[test]
+    fn test_standby_catches_up_after_reconnect() {
+        let dir = tempfile::tempdir().unwrap();
+        let (db, source) = primary(dir.path());
+        let mut fixtures = Fixtures::new(11);
+        db.store_flow(&fixtures.flow(), 10, false).unwrap();
+
+        let standby = seed_standby(&db, &dir.path().join("standby.db"));
+        let seeded = standby.replication_status().unwrap();
+        assert_eq!(seeded.role, ReplicationRole::Standby);
+        assert!(seeded.applied_seq > 0);
+
+        // Writes while the standby is "disconnected"
+        for flow in fixtures.flows(3) {
+            db.store_flow(&flow, 90, true).unwrap();
+        }
+        let candidate = db
+            .store_candidate_fingerprint("tls", "t13d1516h2_abc", 12, 0.9, None)
+            .unwrap();
+        db.update_candidate_status(candidate, "approved", None)
+            .unwrap();
+
+        let replicator = Replicator::new(source, standby).with_batch_size(7);
+        let progress = replicator.catch_up().unwrap();
+        assert!(progress.applied > 0);
+        assert_eq!(
+            progress.applied_seq,
+            db.replication_status().unwrap().head_seq
+        );
+
+        let standby = replicator.standby();
+        assert_eq!(standby.get_stats().unwrap(), db.get_stats().unwrap());
+        assert!(standby.get_stats().unwrap().starts_with("Total Flows: 4,"));
+        assert_eq!(standby.get_candidate_stats().unwrap().approved, 1);
+        assert!(!index(standby).is_empty());
+        assert_eq!(index(standby), index(&db));
+
+        // Nothing new: a second pass is a no-op
+        assert_eq!(replicator.catch_up().unwrap().applied, 0);
+    }
+
+    #[test]
+    fn test_replay_gap_and_fencing_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let (db, _) = primary(dir.path());
+        let standby = seed_standby(&db, &dir.path().join("standby.db"));
+        let start = standby.replication_status().unwrap().applied_seq;
+
+        for i in 0..4 {
+            db.store_alert(
+                "tls",
+                &format!("fp-{}", i),
+                "high",
+                "bot",
+                None,
+                1_700_000_000 + i,
+            )
+            .unwrap();
+        }
+        let batch = db.changes_since(start, 100).unwrap();
+        assert_eq!(batch.changes.len(), 4);
+
+        // Gap: the first change is missing
+        let mut gapped = batch.clone();
+        gapped.changes.remove(0);
+        assert!(standby.apply_changes(&gapped).unwrap_err().contains("gap"));
+
+        // Applying, then replaying the same batch, applies each change once
+        assert_eq!(standby.apply_changes(&batch).unwrap(), 4);
+        assert_eq!(standby.apply_changes(&batch).unwrap(), 0);
+
+        // Pruned history cannot be served
+        db.prune_replication_log(start + 2).unwrap();
+        assert!(db.changes_since(start, 100).is_err());
+        assert_eq!(db.changes_since(start + 2, 100).unwrap().changes.len(), 2);
+
+        // Failover: the promoted standby fences the old primary's epoch
+        let epoch = standby.promote().unwrap();
+        assert_eq!(epoch, batch.epoch + 1);
+        assert_eq!(
+            standby.replication_status().unwrap().role,
+            ReplicationRole::Primary
+        );
+        let follower = seed_standby(&standby, &dir.path().join("follower.db"));
+        db.store_alert("tls", "late", "high", "bot", None, 1_700_000_100)
+            .unwrap();
+        let stale = db.changes_since(batch.head_seq, 100).unwrap();
+        assert!(follower
+            .apply_changes(&stale)
+            .unwrap_err()
+            .contains("stale"));
+        assert!(standby.promote().is_err());
+    }
+}
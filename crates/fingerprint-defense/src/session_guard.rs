@@ -0,0 +1,566 @@
+//! Session hijack detection
+//!
+//! Binds each authenticated session (cookie or session ID) to the fingerprint that
+//! first presented it — JA4, User-Agent, optional hardware hash and client IP — and
+//! scores later requests on the same session against that binding. A stolen cookie
+//! replayed from another client shows up as a materially different fingerprint.
+//!
+//! Legitimate changes are tolerated:
+//! - **Browser update**: same browser family and OS with an equal or newer version;
+//!   the JA4 may change along with it
+//! - **IP roaming**: a new client IP on its own (Wi-Fi ↔ mobile, VPN reconnects)
+//!
+//! Session IDs are never stored or reported: the guard keys sessions by a keyed hash
+//! and alerts carry that hash as `session_ref`.
+
+use crate::fingerprint_index::Ja4Components;
+use fingerprint_core::clock::{system_clock, SharedClock};
+use fingerprint_core::events::{self, AlertRaised};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::net::IpAddr;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Fingerprint presented with a session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionFingerprint {
+    /// JA4 of the TLS client hello
+    pub ja4: String,
+    /// `User-Agent` header
+    pub user_agent: String,
+    /// Hardware hash from client-side collection, if the client sent one
+    pub hardware_hash: Option<String>,
+    /// Client IP
+    pub ip: IpAddr,
+}
+
+/// Tolerances and thresholds of a [`SessionGuard`]
+#[derive(Debug, Clone)]
+pub struct SessionGuardConfig {
+    /// Sessions unseen for this long are forgotten
+    pub idle_ttl: Duration,
+    /// Sessions tracked at most; the least recently seen are evicted first
+    pub max_sessions: usize,
+    /// Accept a newer version of the same browser (and the JA4 change it brings)
+    pub allow_browser_update: bool,
+    /// Accept a new client IP when nothing else changed
+    pub allow_ip_roaming: bool,
+    /// Score at which a session is reported
+    pub alert_score: u8,
+    /// Score at which a session should be terminated
+    pub terminate_score: u8,
+}
+
+impl Default for SessionGuardConfig {
+    fn default() -> Self {
+        Self {
+            idle_ttl: Duration::from_secs(24 * 3600),
+            max_sessions: 100_000,
+            allow_browser_update: true,
+            allow_ip_roaming: true,
+            alert_score: 40,
+            terminate_score: 70,
+        }
+    }
+}
+
+/// Part of a fingerprint that changed within a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Different TLS client hello
+    Ja4,
+    /// Different browser family, OS or an older version
+    UserAgent,
+    /// Same browser and OS, equal or newer version
+    BrowserUpdate,
+    /// Different hardware hash, or none where one was sent before
+    Hardware,
+    /// Different client IP
+    Ip,
+}
+
+/// One change against the session's binding
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionChange {
+    pub kind: ChangeKind,
+    pub from: String,
+    pub to: String,
+    /// Whether a tolerance rule accepted the change
+    pub tolerated: bool,
+    /// Contribution to the session's score
+    pub score: u8,
+}
+
+/// What the operator should do with the request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionAction {
+    /// Fingerprint matches, or only changed in tolerated ways
+    Allow,
+    /// Suspicious change; the binding is kept
+    Alert,
+    /// Likely hijacked; the session stays terminated until [`SessionGuard::end`]
+    Terminate,
+}
+
+/// Result of [`SessionGuard::observe`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionAssessment {
+    /// Keyed hash of the session ID, safe to log
+    pub session_ref: String,
+    pub action: SessionAction,
+    /// 0–100
+    pub score: u8,
+    /// First request of the session
+    pub new_session: bool,
+    pub changes: Vec<SessionChange>,
+}
+
+struct Binding {
+    fingerprint: SessionFingerprint,
+    last_seen: Instant,
+    terminated: bool,
+}
+
+/// Tracks the fingerprint bound to each session
+pub struct SessionGuard {
+    config: SessionGuardConfig,
+    sessions: Mutex<HashMap<u64, Binding>>,
+    keys: RandomState,
+    clock: SharedClock,
+}
+
+impl SessionGuard {
+    pub fn new(config: SessionGuardConfig) -> Self {
+        Self {
+            config,
+            sessions: Mutex::new(HashMap::new()),
+            keys: RandomState::new(),
+            clock: system_clock(),
+        }
+    }
+
+    /// Expire sessions by `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Score `fingerprint` against the binding of `session_id`, binding it if new
+    ///
+    /// Allowed requests move the binding to the new fingerprint, so tolerated changes
+    /// accumulate; alerts keep the old binding so an attacker cannot launder it.
+    pub fn observe(&self, session_id: &str, fingerprint: &SessionFingerprint) -> SessionAssessment {
+        let key = self.keys.hash_one(session_id);
+        let session_ref = format!("{:016x}", key);
+        let now = self.clock.instant();
+        let mut sessions = self.lock();
+
+        let binding = match sessions.get_mut(&key) {
+            Some(binding) if now.duration_since(binding.last_seen) <= self.config.idle_ttl => {
+                binding
+            }
+            _ => {
+                self.bind(&mut sessions, key, fingerprint.clone(), now);
+                return SessionAssessment {
+                    session_ref,
+                    action: SessionAction::Allow,
+                    score: 0,
+                    new_session: true,
+                    changes: Vec::new(),
+                };
+            }
+        };
+        binding.last_seen = now;
+
+        let changes = compare(&self.config, &binding.fingerprint, fingerprint);
+        let score = changes
+            .iter()
+            .map(|change| change.score as u32)
+            .sum::<u32>()
+            .min(100) as u8;
+        let action = if binding.terminated || score >= self.config.terminate_score {
+            binding.terminated = true;
+            SessionAction::Terminate
+        } else if score >= self.config.alert_score {
+            SessionAction::Alert
+        } else {
+            binding.fingerprint = fingerprint.clone();
+            SessionAction::Allow
+        };
+        drop(sessions);
+
+        let assessment = SessionAssessment {
+            session_ref,
+            action,
+            score,
+            new_session: false,
+            changes,
+        };
+        if action != SessionAction::Allow {
+            publish_alert(&assessment);
+        }
+        assessment
+    }
+
+    /// Forget a session (logout, or after terminating it)
+    pub fn end(&self, session_id: &str) {
+        let key = self.keys.hash_one(session_id);
+        self.lock().remove(&key);
+    }
+
+    /// Drop sessions idle for longer than `idle_ttl`; returns how many were dropped
+    pub fn purge_expired(&self) -> usize {
+        let now = self.clock.instant();
+        let mut sessions = self.lock();
+        let before = sessions.len();
+        sessions.retain(|_, binding| now.duration_since(binding.last_seen) <= self.config.idle_ttl);
+        before - sessions.len()
+    }
+
+    /// Sessions currently tracked
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn bind(
+        &self,
+        sessions: &mut HashMap<u64, Binding>,
+        key: u64,
+        fingerprint: SessionFingerprint,
+        now: Instant,
+    ) {
+        if sessions.len() >= self.config.max_sessions.max(1) && !sessions.contains_key(&key) {
+            if let Some(oldest) = sessions
+                .iter()
+                .min_by_key(|(_, binding)| binding.last_seen)
+                .map(|(key, _)| *key)
+            {
+                sessions.remove(&oldest);
+            }
+        }
+        sessions.insert(
+            key,
+            Binding {
+                fingerprint,
+                last_seen: now,
+                terminated: false,
+            },
+        );
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<u64, Binding>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for SessionGuard {
+    fn default() -> Self {
+        Self::new(SessionGuardConfig::default())
+    }
+}
+
+/// Browser family, OS and major version of a User-Agent
+#[derive(Debug, PartialEq, Eq)]
+struct UserAgentInfo {
+    family: &'static str,
+    os: &'static str,
+    version: u32,
+}
+
+impl UserAgentInfo {
+    fn parse(user_agent: &str) -> Option<Self> {
+        // order matters: Edge and Opera also claim Chrome, Chrome also claims Safari
+        let (family, token) = [
+            ("edge", "Edg/"),
+            ("opera", "OPR/"),
+            ("firefox", "Firefox/"),
+            ("chrome", "Chrome/"),
+            ("safari", "Version/"),
+        ]
+        .into_iter()
+        .find(|(_, token)| user_agent.contains(token))?;
+        let version = user_agent
+            .split(token)
+            .nth(1)?
+            .split(|c: char| !c.is_ascii_digit())
+            .next()?
+            .parse()
+            .ok()?;
+        let os = if user_agent.contains("Android") {
+            "android"
+        } else if user_agent.contains("iPhone") || user_agent.contains("iPad") {
+            "ios"
+        } else if user_agent.contains("Windows") {
+            "windows"
+        } else if user_agent.contains("Mac OS X") {
+            "macos"
+        } else if user_agent.contains("Linux") || user_agent.contains("CrOS") {
+            "linux"
+        } else {
+            "other"
+        };
+        Some(Self {
+            family,
+            os,
+            version,
+        })
+    }
+}
+
+fn change(kind: ChangeKind, from: &str, to: &str, score: u8) -> SessionChange {
+    SessionChange {
+        kind,
+        from: from.to_string(),
+        to: to.to_string(),
+        tolerated: score == 0,
+        score,
+    }
+}
+
+/// Changes from `bound` to `seen`, scored
+fn compare(
+    config: &SessionGuardConfig,
+    bound: &SessionFingerprint,
+    seen: &SessionFingerprint,
+) -> Vec<SessionChange> {
+    let mut changes = Vec::new();
+
+    let mut browser_updated = false;
+    if bound.user_agent != seen.user_agent {
+        let before = UserAgentInfo::parse(&bound.user_agent);
+        let after = UserAgentInfo::parse(&seen.user_agent);
+        let update = match (&before, &after) {
+            (Some(before), Some(after)) => {
+                before.family == after.family
+                    && before.os == after.os
+                    && after.version >= before.version
+            }
+            _ => false,
+        };
+        if update && config.allow_browser_update {
+            browser_updated = true;
+            changes.push(change(
+                ChangeKind::BrowserUpdate,
+                &bound.user_agent,
+                &seen.user_agent,
+                0,
+            ));
+        } else {
+            let score = if update { 30 } else { 50 };
+            changes.push(change(
+                ChangeKind::UserAgent,
+                &bound.user_agent,
+                &seen.user_agent,
+                score,
+            ));
+        }
+    }
+
+    if bound.ja4 != seen.ja4 {
+        let score = if browser_updated {
+            0
+        } else {
+            // same protocol, version, SNI and ALPN with different cipher/extension
+            // choices is a milder signal than a different client hello shape altogether
+            match (
+                Ja4Components::parse(&bound.ja4),
+                Ja4Components::parse(&seen.ja4),
+            ) {
+                (Some(a), Some(b))
+                    if a.transport == b.transport
+                        && a.tls_version == b.tls_version
+                        && a.destination == b.destination
+                        && a.alpn == b.alpn =>
+                {
+                    40
+                }
+                _ => 50,
+            }
+        };
+        changes.push(change(ChangeKind::Ja4, &bound.ja4, &seen.ja4, score));
+    }
+
+    match (&bound.hardware_hash, &seen.hardware_hash) {
+        (Some(a), Some(b)) if a != b => changes.push(change(ChangeKind::Hardware, a, b, 60)),
+        (Some(a), None) => changes.push(change(ChangeKind::Hardware, a, "", 20)),
+        _ => {}
+    }
+
+    if bound.ip != seen.ip {
+        let material = changes.iter().any(|change| change.score > 0);
+        let score = match (config.allow_ip_roaming, material) {
+            (true, false) => 0,
+            // a new network corroborates other changes
+            (true, true) => 10,
+            (false, _) => 30,
+        };
+        changes.push(change(
+            ChangeKind::Ip,
+            &bound.ip.to_string(),
+            &seen.ip.to_string(),
+            score,
+        ));
+    }
+
+    changes
+}
+
+fn publish_alert(assessment: &SessionAssessment) {
+    if !events::global().has_subscribers::<AlertRaised>() {
+        return;
+    }
+    let (severity, verb) = match assessment.action {
+        SessionAction::Terminate => ("critical", "terminated"),
+        _ => ("warning", "flagged"),
+    };
+    let kinds: Vec<ChangeKind> = assessment
+        .changes
+        .iter()
+        .filter(|change| !change.tolerated)
+        .map(|change| change.kind)
+        .collect();
+    let mut alert = AlertRaised::new(
+        "defense.session",
+        severity,
+        "session_hijack",
+        format!(
+            "Session {} {}: fingerprint changed (score {})",
+            assessment.session_ref, verb, assessment.score
+        ),
+    );
+    alert.metadata.insert(
+        "session_ref".to_string(),
+        serde_json::json!(assessment.session_ref),
+    );
+    alert
+        .metadata
+        .insert("score".to_string(), serde_json::json!(assessment.score));
+    alert
+        .metadata
+        .insert("changes".to_string(), serde_json::json!(kinds));
+    events::publish_alert(alert);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fingerprint_core::clock::FrozenClock;
+    use std::sync::Arc;
+
+    const CHROME_120: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+    const CHROME_121: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/121.0.0.0 Safari/537.36";
+    const FIREFOX_LINUX: &str =
+        "Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0";
+
+    fn fingerprint(ja4: &str, user_agent: &str, ip: &str) -> SessionFingerprint {
+        SessionFingerprint {
+            ja4: ja4.to_string(),
+            user_agent: user_agent.to_string(),
+            hardware_hash: Some("hw-1".to_string()),
+            ip: ip.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_tolerates_browser_update_and_roaming() {
+        let guard = SessionGuard::default();
+        let original = fingerprint(
+            "t13d1516h2_8daaf6152771_02713d6af862",
+            CHROME_120,
+            "10.0.0.1",
+        );
+        assert!(guard.observe("sid", &original).new_session);
+        assert_eq!(guard.observe("sid", &original).changes, vec![]);
+
+        // roaming alone
+        let roamed = fingerprint(
+            "t13d1516h2_8daaf6152771_02713d6af862",
+            CHROME_120,
+            "172.16.4.2",
+        );
+        let assessment = guard.observe("sid", &roamed);
+        assert_eq!(assessment.action, SessionAction::Allow);
+        assert_eq!(assessment.score, 0);
+
+        // update to a newer Chrome with a new client hello
+        let updated = fingerprint(
+            "t13d1517h2_8daaf6152771_b1ff8ab2d16f",
+            CHROME_121,
+            "172.16.4.2",
+        );
+        let assessment = guard.observe("sid", &updated);
+        assert_eq!(assessment.action, SessionAction::Allow);
+        assert!(assessment.changes.iter().all(|change| change.tolerated));
+        assert!(!assessment.session_ref.contains("sid"));
+    }
+
+    #[test]
+    fn test_flags_and_terminates_hijacked_session() {
+        let clock = Arc::new(FrozenClock::starting_now());
+        let guard = SessionGuard::default().with_clock(clock.clone());
+        let owner = fingerprint(
+            "t13d1516h2_8daaf6152771_02713d6af862",
+            CHROME_120,
+            "10.0.0.1",
+        );
+        guard.observe("sid", &owner);
+
+        // same browser on different hardware: reported, binding kept
+        let other_device = SessionFingerprint {
+            hardware_hash: Some("hw-2".to_string()),
+            ..owner.clone()
+        };
+        assert_eq!(
+            guard.observe("sid", &other_device).action,
+            SessionAction::Alert
+        );
+
+        // a different client from another network
+        let attacker = SessionFingerprint {
+            hardware_hash: None,
+            ..fingerprint(
+                "t13d1715h2_5b57614c22b0_3d5424432f57",
+                FIREFOX_LINUX,
+                "203.0.113.9",
+            )
+        };
+        let assessment = guard.observe("sid", &attacker);
+        assert_eq!(assessment.action, SessionAction::Terminate);
+        assert_eq!(assessment.score, 100);
+        // the owner is terminated too until the session is ended
+        assert_eq!(
+            guard.observe("sid", &owner).action,
+            SessionAction::Terminate
+        );
+        guard.end("sid");
+        assert!(guard.observe("sid", &owner).new_session);
+
+        clock.advance(Duration::from_secs(25 * 3600));
+        assert_eq!(guard.purge_expired(), 1);
+        assert!(guard.is_empty());
+    }
+
+    #[test]
+    fn test_user_agent_parsing() {
+        let chrome = UserAgentInfo::parse(CHROME_121).unwrap();
+        assert_eq!(
+            (chrome.family, chrome.os, chrome.version),
+            ("chrome", "windows", 121)
+        );
+        let firefox = UserAgentInfo::parse(FIREFOX_LINUX).unwrap();
+        assert_eq!(
+            (firefox.family, firefox.os, firefox.version),
+            ("firefox", "linux", 121)
+        );
+        assert!(UserAgentInfo::parse("curl/8.4.0").is_none());
+    }
+}
@@ -0,0 +1,570 @@
+//! Cross-process verdict cache
+//!
+//! Gateways running several worker processes each keep their own in-memory caches, so a
+//! JA4 scored by one worker is scored again by every other. [`VerdictBroker`] holds one
+//! shared JA4 → verdict map and serves it over a Unix domain socket; workers talk to it
+//! through [`VerdictCacheClient`].
+//!
+//! Entries expire after their TTL and the whole cache is flushed when the fingerprint
+//! database changes: [`VerdictBroker::watch_database`] polls SQLite's `data_version`,
+//! which moves whenever any other connection (learner, label propagator, review tool)
+//! commits. Every response carries the cache generation so clients can tell a flush
+//! happened.
+//!
+//! Protocol: one JSON request per line, one JSON response per line.
+
+use crate::database::FingerprintDatabase;
+use fingerprint_core::clock::{system_clock, SharedClock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{UnixListener, UnixStream};
+
+/// default number of cached verdicts
+const DEFAULT_CAPACITY: usize = 100_000;
+
+/// action decided for a fingerprint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerdictAction {
+    Allow,
+    Challenge,
+    Block,
+}
+
+/// cached scoring result for a JA4
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Verdict {
+    pub action: VerdictAction,
+    /// risk score (0.0-1.0)
+    pub score: f64,
+}
+
+impl Verdict {
+    pub fn new(action: VerdictAction, score: f64) -> Self {
+        Self { action, score }
+    }
+}
+
+/// broker counters
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BrokerStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// bumped on every full flush
+    pub generation: u64,
+}
+
+impl BrokerStats {
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Request {
+    Get {
+        ja4: String,
+    },
+    Put {
+        ja4: String,
+        verdict: Verdict,
+        ttl_secs: u64,
+    },
+    Invalidate {
+        ja4: String,
+    },
+    Clear,
+    Stats,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum Response {
+    Verdict {
+        generation: u64,
+        verdict: Option<Verdict>,
+    },
+    Ok {
+        generation: u64,
+    },
+    Stats {
+        stats: BrokerStats,
+    },
+    Error {
+        message: String,
+    },
+}
+
+struct Entry {
+    verdict: Verdict,
+    expires_at: Instant,
+}
+
+struct BrokerState {
+    entries: HashMap<String, Entry>,
+    capacity: usize,
+    stats: BrokerStats,
+    clock: SharedClock,
+}
+
+impl BrokerState {
+    fn handle(&mut self, request: Request) -> Response {
+        let now = self.clock.instant();
+        match request {
+            Request::Get { ja4 } => {
+                let verdict = match self.entries.get(&ja4) {
+                    Some(entry) if entry.expires_at > now => Some(entry.verdict.clone()),
+                    Some(_) => {
+                        self.entries.remove(&ja4);
+                        None
+                    }
+                    None => None,
+                };
+                if verdict.is_some() {
+                    self.stats.hits += 1;
+                } else {
+                    self.stats.misses += 1;
+                }
+                Response::Verdict {
+                    generation: self.stats.generation,
+                    verdict,
+                }
+            }
+            Request::Put {
+                ja4,
+                verdict,
+                ttl_secs,
+            } => {
+                if !self.entries.contains_key(&ja4) && self.entries.len() >= self.capacity {
+                    self.evict(now);
+                }
+                self.entries.insert(
+                    ja4,
+                    Entry {
+                        verdict,
+                        expires_at: now + Duration::from_secs(ttl_secs),
+                    },
+                );
+                self.ok()
+            }
+            Request::Invalidate { ja4 } => {
+                self.entries.remove(&ja4);
+                self.ok()
+            }
+            Request::Clear => {
+                self.clear();
+                self.ok()
+            }
+            Request::Stats => {
+                let mut stats = self.stats.clone();
+                stats.entries = self.entries.len();
+                Response::Stats { stats }
+            }
+        }
+    }
+
+    fn ok(&self) -> Response {
+        Response::Ok {
+            generation: self.stats.generation,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.stats.generation += 1;
+    }
+
+    /// drop expired entries, or the one closest to expiry if none has expired
+    fn evict(&mut self, now: Instant) {
+        self.entries.retain(|_, entry| entry.expires_at > now);
+        if self.entries.len() >= self.capacity {
+            if let Some(key) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.expires_at)
+                .map(|(key, _)| key.clone())
+            {
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
+
+/// shared verdict cache server
+#[derive(Clone)]
+pub struct VerdictBroker {
+    state: Arc<Mutex<BrokerState>>,
+}
+
+impl VerdictBroker {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(BrokerState {
+                entries: HashMap::new(),
+                capacity: capacity.max(1),
+                stats: BrokerStats::default(),
+                clock: system_clock(),
+            })),
+        }
+    }
+
+    /// Expire entries by `clock` instead of the system clock
+    pub fn with_clock(self, clock: SharedClock) -> Self {
+        lock(&self.state).clock = clock;
+        self
+    }
+
+    /// flush every entry (e.g. after a signature rollout)
+    pub fn invalidate_all(&self) {
+        lock(&self.state).clear();
+    }
+
+    pub fn stats(&self) -> BrokerStats {
+        let state = lock(&self.state);
+        let mut stats = state.stats.clone();
+        stats.entries = state.entries.len();
+        stats
+    }
+
+    /// bind the socket and serve clients until the listener fails
+    ///
+    /// A stale socket file left behind by a previous broker is removed first.
+    pub async fn serve(self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        log::info!("[VerdictBroker] Listening on {}", path.display());
+
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let state = self.state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = Self::serve_connection(state, stream).await {
+                    log::debug!("[VerdictBroker] Connection closed: {}", e);
+                }
+            });
+        }
+    }
+
+    async fn serve_connection(
+        state: Arc<Mutex<BrokerState>>,
+        stream: UnixStream,
+    ) -> io::Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            let response = match serde_json::from_str::<Request>(&line) {
+                Ok(request) => lock(&state).handle(request),
+                Err(e) => Response::Error {
+                    message: e.to_string(),
+                },
+            };
+            let mut out = serde_json::to_vec(&response)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            out.push(b'\n');
+            writer.write_all(&out).await?;
+        }
+        Ok(())
+    }
+
+    /// flush the cache whenever `db` observes a commit from another connection
+    ///
+    /// `db` must be a separate connection to the shared database file. The watcher
+    /// thread stops once every clone of the broker has been dropped.
+    pub fn watch_database(
+        &self,
+        db: FingerprintDatabase,
+        interval: Duration,
+    ) -> io::Result<JoinHandle<()>> {
+        let state: Weak<Mutex<BrokerState>> = Arc::downgrade(&self.state);
+        let mut last = db.data_version().map_err(io::Error::other)?;
+        std::thread::Builder::new()
+            .name("verdict-cache-watch".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(interval);
+                let Some(state) = state.upgrade() else {
+                    return;
+                };
+                match db.data_version() {
+                    Ok(version) if version != last => {
+                        last = version;
+                        lock(&state).clear();
+                        log::debug!("[VerdictBroker] Database changed, cache flushed");
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::warn!("[VerdictBroker] Failed to poll database: {}", e),
+                }
+            })
+    }
+}
+
+impl Default for VerdictBroker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn lock(state: &Mutex<BrokerState>) -> std::sync::MutexGuard<'_, BrokerState> {
+    state
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// worker-side connection to a [`VerdictBroker`]
+///
+/// Holds one persistent connection and reconnects after errors.
+pub struct VerdictCacheClient {
+    path: PathBuf,
+    conn: tokio::sync::Mutex<Option<(BufReader<OwnedReadHalf>, OwnedWriteHalf)>>,
+}
+
+impl VerdictCacheClient {
+    pub async fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+        let client = Self {
+            path: path.as_ref().to_path_buf(),
+            conn: tokio::sync::Mutex::new(None),
+        };
+        *client.conn.lock().await = Some(client.open().await?);
+        Ok(client)
+    }
+
+    /// cached verdict for a JA4, or None on miss
+    pub async fn get(&self, ja4: &str) -> io::Result<Option<Verdict>> {
+        match self
+            .request(&Request::Get {
+                ja4: ja4.to_string(),
+            })
+            .await?
+        {
+            Response::Verdict { verdict, .. } => Ok(verdict),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn put(&self, ja4: &str, verdict: Verdict, ttl: Duration) -> io::Result<()> {
+        self.expect_ok(&Request::Put {
+            ja4: ja4.to_string(),
+            verdict,
+            ttl_secs: ttl.as_secs(),
+        })
+        .await
+    }
+
+    pub async fn invalidate(&self, ja4: &str) -> io::Result<()> {
+        self.expect_ok(&Request::Invalidate {
+            ja4: ja4.to_string(),
+        })
+        .await
+    }
+
+    pub async fn clear(&self) -> io::Result<()> {
+        self.expect_ok(&Request::Clear).await
+    }
+
+    pub async fn stats(&self) -> io::Result<BrokerStats> {
+        match self.request(&Request::Stats).await? {
+            Response::Stats { stats } => Ok(stats),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn expect_ok(&self, request: &Request) -> io::Result<()> {
+        match self.request(request).await? {
+            Response::Ok { .. } => Ok(()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn open(&self) -> io::Result<(BufReader<OwnedReadHalf>, OwnedWriteHalf)> {
+        let (reader, writer) = UnixStream::connect(&self.path).await?.into_split();
+        Ok((BufReader::new(reader), writer))
+    }
+
+    async fn request(&self, request: &Request) -> io::Result<Response> {
+        let mut line = serde_json::to_vec(request)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        line.push(b'\n');
+
+        let mut conn = self.conn.lock().await;
+        if conn.is_none() {
+            *conn = Some(self.open().await?);
+        }
+        let result = match conn.as_mut() {
+            Some((reader, writer)) => Self::round_trip(reader, writer, &line).await,
+            None => unreachable!("connection opened above"),
+        };
+        if result.is_err() {
+            // broken pipe or broker restart: reconnect on next request
+            *conn = None;
+        }
+        result
+    }
+
+    async fn round_trip(
+        reader: &mut BufReader<OwnedReadHalf>,
+        writer: &mut OwnedWriteHalf,
+        line: &[u8],
+    ) -> io::Result<Response> {
+        writer.write_all(line).await?;
+        let mut response = String::new();
+        if reader.read_line(&mut response).await? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "verdict broker closed the connection",
+            ));
+        }
+        serde_json::from_str(&response).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+fn unexpected(response: Response) -> io::Error {
+    match response {
+        Response::Error { message } => io::Error::new(io::ErrorKind::InvalidInput, message),
+        other => io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unexpected broker response: {:?}", other),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    async fn wait_for_socket(path: &Path) {
+        for _ in 0..100 {
+            if path.exists() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("broker did not bind {}", path.display());
+    }
+
+    #[tokio::test]
+    async fn test_workers_share_verdicts() {
+        let dir = tempdir().unwrap();
+        let socket = dir.path().join("verdicts.sock");
+        let broker = VerdictBroker::new();
+        tokio::spawn(broker.clone().serve(socket.clone()));
+        wait_for_socket(&socket).await;
+
+        let worker_a = VerdictCacheClient::connect(&socket).await.unwrap();
+        let worker_b = VerdictCacheClient::connect(&socket).await.unwrap();
+        let ja4 = "t13d1516h2_8daaf6152771_02713d6af862";
+
+        assert_eq!(worker_b.get(ja4).await.unwrap(), None);
+        worker_a
+            .put(
+                ja4,
+                Verdict::new(VerdictAction::Block, 0.95),
+                Duration::from_secs(60),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            worker_b.get(ja4).await.unwrap(),
+            Some(Verdict::new(VerdictAction::Block, 0.95))
+        );
+
+        worker_b.invalidate(ja4).await.unwrap();
+        assert_eq!(worker_a.get(ja4).await.unwrap(), None);
+
+        // expired entries are misses
+        worker_a
+            .put(ja4, Verdict::new(VerdictAction::Allow, 0.1), Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(worker_a.get(ja4).await.unwrap(), None);
+
+        let stats = worker_a.stats().await.unwrap();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 3);
+        assert_eq!(stats, broker.stats());
+    }
+
+    #[test]
+    fn test_entries_expire_by_broker_clock() {
+        let clock = Arc::new(fingerprint_core::clock::FrozenClock::starting_now());
+        let broker = VerdictBroker::new().with_clock(clock.clone());
+        let ja4 = "t13d1516h2_8daaf6152771_02713d6af862".to_string();
+        let get = || match lock(&broker.state).handle(Request::Get { ja4: ja4.clone() }) {
+            Response::Verdict { verdict, .. } => verdict,
+            other => panic!("unexpected {:?}", other),
+        };
+
+        lock(&broker.state).handle(Request::Put {
+            ja4: ja4.clone(),
+            verdict: Verdict::new(VerdictAction::Block, 0.9),
+            ttl_secs: 60,
+        });
+        clock.advance(Duration::from_secs(59));
+        assert!(get().is_some());
+        clock.advance(Duration::from_secs(2));
+        assert!(get().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_database_update_flushes_cache() {
+        let dir = tempdir().unwrap();
+        let socket = dir.path().join("verdicts.sock");
+        let db_path = dir.path().join("fingerprints.db");
+
+        let broker = VerdictBroker::with_capacity(2);
+        let _watcher = broker
+            .watch_database(
+                FingerprintDatabase::open(&db_path).unwrap(),
+                Duration::from_millis(10),
+            )
+            .unwrap();
+        tokio::spawn(broker.clone().serve(socket.clone()));
+        wait_for_socket(&socket).await;
+
+        let client = VerdictCacheClient::connect(&socket).await.unwrap();
+        for ja4 in ["a", "b", "c"] {
+            client
+                .put(
+                    ja4,
+                    Verdict::new(VerdictAction::Challenge, 0.5),
+                    Duration::from_secs(60),
+                )
+                .await
+                .unwrap();
+        }
+        // capacity bound holds
+        assert_eq!(client.stats().await.unwrap().entries, 2);
+        assert!(client.get("c").await.unwrap().is_some());
+
+        let writer = FingerprintDatabase::open(&db_path).unwrap();
+        writer
+            .store_candidate_fingerprint("tls", "c", 10, 0.9, None)
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let stats = client.stats().await.unwrap();
+        assert_eq!(stats.entries, 0);
+        assert_eq!(stats.generation, 1);
+        assert!(client.get("c").await.unwrap().is_none());
+    }
+}
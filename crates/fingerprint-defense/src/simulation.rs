@@ -0,0 +1,1082 @@
+//! Traffic simulation for exercising defenses
+//!
+//! Generates synthetic flows from a configurable mixture of browser profiles, bot
+//! signatures and attack patterns, arriving at a controllable rate (Poisson arrivals).
+//! Each flow carries the TCP SYN parameters of its claimed stack plus a real TLS
+//! ClientHello built from a `ClientHelloSpec`, so it can either be fed straight into
+//! the [`PassiveAnalyzer`] as synthetic packets or written to real sockets toward a
+//! test server.
+//!
+//! The mixture is described by [`SimulationConfig`], which deserializes from JSON:
+//!
+//! ```json
+//! {
+//!   "seed": 7,
+//!   "flows_per_second": 50.0,
+//!   "duration_secs": 10.0,
+//!   "classes": [
+//!     { "name": "chrome", "weight": 0.7, "source": { "type": "browser", "profile": "chrome_133", "os": "windows" } },
+//!     { "name": "curl", "weight": 0.1, "source": { "type": "bot", "signature": "curl" } },
+//!     { "name": "stuffing", "weight": 0.2, "source_ips": 3, "source": { "type": "attack", "pattern": "credential_stuffing" } }
+//!   ]
+//! }
+//! ```
+
+use crate::passive::packet::{Packet, TcpFlags, TcpHeader, TcpOption};
+use crate::passive::{PassiveAnalysisResult, PassiveAnalyzer};
+use fingerprint_core::dicttls::{
+    cipher_suites as cs,
+    signature_schemes::{
+        ECDSA_WITH_P256_AND_SHA256, ECDSA_WITH_P384_AND_SHA384, ECDSA_WITH_P521_AND_SHA512,
+        ED25519, PKCS1_WITH_SHA256, PKCS1_WITH_SHA384, PKCS1_WITH_SHA512, PSS_WITH_SHA256,
+        PSS_WITH_SHA384, PSS_WITH_SHA512,
+    },
+    supported_groups::{CURVE_P256, CURVE_P384, SECP521R1, X25519},
+};
+use fingerprint_core::tcp::TcpProfile;
+use fingerprint_core::types::OperatingSystem;
+use fingerprint_tls::tls_config::{
+    ClientHelloSpec, Mutation, COMPRESSION_NONE, POINT_FORMAT_UNCOMPRESSED, PSK_MODE_DHE,
+    RENEGOTIATE_ONCE_AS_CLIENT, VERSION_TLS12, VERSION_TLS13,
+};
+use fingerprint_tls::tls_extensions::{
+    ALPNExtension, ExtendedMasterSecretExtension, KeyShare, KeyShareExtension,
+    PSKKeyExchangeModesExtension, RenegotiationInfoExtension, SCTExtension, SessionTicketExtension,
+    SignatureAlgorithmsExtension, StatusRequestExtension, SupportedCurvesExtension,
+    SupportedPointsExtension, SupportedVersionsExtension,
+};
+use fingerprint_tls::TLSHandshakeBuilder;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+/// browser profiles a [`TrafficSource::Browser`] may name
+pub const BROWSER_PROFILES: &[&str] = &[
+    "chrome_103",
+    "chrome_133",
+    "chrome_136",
+    "firefox_133",
+    "safari_16_0",
+];
+
+/// paths requested by browser-like traffic
+const BROWSE_PATHS: &[&str] = &["/", "/products", "/search?q=shoes", "/cart", "/about"];
+
+/// simulation error
+#[derive(Debug, thiserror::Error)]
+pub enum SimulationError {
+    #[error("invalid simulation config: {0}")]
+    Config(String),
+
+    #[error("failed to build ClientHello: {0}")]
+    Tls(String),
+
+    #[error("socket error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// ground-truth label attached to every synthetic flow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrafficLabel {
+    Human,
+    Bot,
+    Attack,
+}
+
+/// automated client library
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BotSignature {
+    /// python-requests (OpenSSL defaults, HTTP/1.1 only)
+    PythonRequests,
+    /// curl (OpenSSL defaults, offers h2)
+    Curl,
+    /// Go net/http (crypto/tls defaults)
+    GoHttp,
+    /// headless Chrome: Chrome's TLS stack on a Linux host with a HeadlessChrome UA
+    HeadlessChrome,
+}
+
+/// attack behaviour
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttackPattern {
+    /// login POSTs from a scripted client, usually from a small pool of addresses
+    CredentialStuffing,
+    /// sequential catalogue crawl with a headless browser
+    Scraping,
+    /// Chrome ClientHello with one random mutation per flow, defeating exact JA4 blocklists
+    FingerprintRotation,
+    /// Chrome on Windows claimed by TLS and UA, sent from a Linux TCP stack
+    StackMismatch,
+}
+
+/// what generates a traffic class
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TrafficSource {
+    /// real browser; `profile` is one of [`BROWSER_PROFILES`], `os` is windows/macos/linux
+    Browser {
+        profile: String,
+        os: String,
+    },
+    Bot {
+        signature: BotSignature,
+    },
+    Attack {
+        pattern: AttackPattern,
+    },
+}
+
+impl TrafficSource {
+    /// ground-truth label
+    pub fn label(&self) -> TrafficLabel {
+        match self {
+            TrafficSource::Browser { .. } => TrafficLabel::Human,
+            TrafficSource::Bot { .. } => TrafficLabel::Bot,
+            TrafficSource::Attack { .. } => TrafficLabel::Attack,
+        }
+    }
+}
+
+/// one component of the traffic mixture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrafficClass {
+    /// class name, used as key in reports
+    pub name: String,
+    /// relative share of flows
+    pub weight: f64,
+    /// size of the source address pool (None: fresh address per flow)
+    #[serde(default)]
+    pub source_ips: Option<usize>,
+    pub source: TrafficSource,
+}
+
+impl TrafficClass {
+    pub fn new(name: impl Into<String>, weight: f64, source: TrafficSource) -> Self {
+        Self {
+            name: name.into(),
+            weight,
+            source_ips: None,
+            source,
+        }
+    }
+
+    /// restrict the class to a fixed pool of source addresses
+    pub fn with_source_ips(mut self, count: usize) -> Self {
+        self.source_ips = Some(count);
+        self
+    }
+}
+
+/// simulation configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationConfig {
+    /// RNG seed; the same config always yields the same flows
+    #[serde(default)]
+    pub seed: u64,
+    /// mean arrival rate across all classes
+    pub flows_per_second: f64,
+    /// simulated duration
+    pub duration_secs: f64,
+    /// SNI / Host header
+    #[serde(default = "default_server_name")]
+    pub server_name: String,
+    /// destination address written into synthetic packets
+    #[serde(default = "default_dst_ip")]
+    pub dst_ip: IpAddr,
+    /// destination port written into synthetic packets
+    #[serde(default = "default_dst_port")]
+    pub dst_port: u16,
+    /// send a TLS ClientHello (true) or a plaintext HTTP/1.1 request (false)
+    #[serde(default = "default_tls")]
+    pub tls: bool,
+    pub classes: Vec<TrafficClass>,
+}
+
+fn default_server_name() -> String {
+    "example.com".to_string()
+}
+
+fn default_dst_ip() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))
+}
+
+fn default_dst_port() -> u16 {
+    443
+}
+
+fn default_tls() -> bool {
+    true
+}
+
+impl SimulationConfig {
+    pub fn new(flows_per_second: f64, duration_secs: f64, classes: Vec<TrafficClass>) -> Self {
+        Self {
+            seed: 0,
+            flows_per_second,
+            duration_secs,
+            server_name: default_server_name(),
+            dst_ip: default_dst_ip(),
+            dst_port: default_dst_port(),
+            tls: default_tls(),
+            classes,
+        }
+    }
+
+    /// parse from JSON
+    pub fn from_json(json: &str) -> Result<Self, SimulationError> {
+        let config: Self =
+            serde_json::from_str(json).map_err(|e| SimulationError::Config(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// check rates, weights and profile names
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        if !(self.flows_per_second.is_finite() && self.flows_per_second > 0.0) {
+            return Err(SimulationError::Config(
+                "flows_per_second must be positive".to_string(),
+            ));
+        }
+        if !(self.duration_secs.is_finite() && self.duration_secs >= 0.0) {
+            return Err(SimulationError::Config(
+                "duration_secs must not be negative".to_string(),
+            ));
+        }
+        if self.classes.is_empty() {
+            return Err(SimulationError::Config("no traffic classes".to_string()));
+        }
+        for class in &self.classes {
+            if !(class.weight.is_finite() && class.weight > 0.0) {
+                return Err(SimulationError::Config(format!(
+                    "class {}: weight must be positive",
+                    class.name
+                )));
+            }
+            if class.source_ips == Some(0) {
+                return Err(SimulationError::Config(format!(
+                    "class {}: source_ips must be at least 1",
+                    class.name
+                )));
+            }
+            if let TrafficSource::Browser { profile, os } = &class.source {
+                if !BROWSER_PROFILES.contains(&profile.as_str()) {
+                    return Err(SimulationError::Config(format!(
+                        "class {}: unknown browser profile {}",
+                        class.name, profile
+                    )));
+                }
+                parse_os(os).ok_or_else(|| {
+                    SimulationError::Config(format!("class {}: unknown os {}", class.name, os))
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// one synthetic connection
+#[derive(Debug, Clone)]
+pub struct SyntheticFlow {
+    /// arrival time relative to the start of the simulation
+    pub offset: Duration,
+    /// traffic class name
+    pub class: String,
+    pub label: TrafficLabel,
+    pub src_ip: IpAddr,
+    pub src_port: u16,
+    pub dst_ip: IpAddr,
+    pub dst_port: u16,
+    /// TCP stack of the sending host
+    pub tcp: TcpProfile,
+    /// router hops between sender and sensor (observed TTL = initial TTL - hops)
+    pub hops: u8,
+    pub user_agent: String,
+    /// complete TLS record carrying the ClientHello
+    pub client_hello: Vec<u8>,
+    /// plaintext HTTP/1.1 request
+    pub http_request: Vec<u8>,
+    /// whether the first data segment carries the ClientHello or the plaintext request
+    pub tls: bool,
+}
+
+impl SyntheticFlow {
+    /// first data segment sent by the client
+    pub fn payload(&self) -> &[u8] {
+        if self.tls {
+            &self.client_hello
+        } else {
+            &self.http_request
+        }
+    }
+
+    /// observed TTL at the sensor
+    pub fn observed_ttl(&self) -> u8 {
+        self.tcp.ttl.saturating_sub(self.hops)
+    }
+
+    /// synthetic packets for the passive pipeline: SYN followed by the first data segment
+    pub fn to_packets(&self) -> Vec<Packet> {
+        let isn = u32::from(self.src_port).wrapping_mul(2_654_435_761);
+        let syn = TcpHeader {
+            src_port: self.src_port,
+            dst_port: self.dst_port,
+            seq: isn,
+            ack: 0,
+            data_offset: 5 + (syn_options_len(&self.tcp) / 4) as u8,
+            flags: TcpFlags {
+                syn: true,
+                ..Default::default()
+            },
+            window: self.tcp.window_size,
+            checksum: 0,
+            urgent_ptr: 0,
+            options: syn_options(&self.tcp, isn),
+        };
+        let data = TcpHeader {
+            seq: isn.wrapping_add(1),
+            ack: 1,
+            data_offset: 5,
+            flags: TcpFlags {
+                psh: true,
+                ack: true,
+                ..Default::default()
+            },
+            options: Vec::new(),
+            ..syn.clone()
+        };
+
+        vec![
+            self.packet(syn, Vec::new()),
+            self.packet(data, self.payload().to_vec()),
+        ]
+    }
+
+    fn packet(&self, header: TcpHeader, payload: Vec<u8>) -> Packet {
+        Packet {
+            src_ip: self.src_ip,
+            dst_ip: self.dst_ip,
+            src_port: Some(self.src_port),
+            dst_port: Some(self.dst_port),
+            protocol: 6,
+            ttl: self.observed_ttl(),
+            ip_flags: 0x02, // DF
+            data: Vec::new(),
+            payload,
+            tcp_header: Some(header),
+        }
+    }
+}
+
+/// SYN options in the order the claimed stack sends them
+fn syn_options(tcp: &TcpProfile, tsval: u32) -> Vec<TcpOption> {
+    let mss = TcpOption::MSS(tcp.mss.unwrap_or(1460));
+    let scale = TcpOption::WindowScale(tcp.window_scale.unwrap_or(7));
+    let timestamp = TcpOption::Timestamp { tsval, tsecr: 0 };
+    if tcp.ttl > 64 {
+        // Windows: no timestamps
+        vec![
+            mss,
+            TcpOption::NOP,
+            scale,
+            TcpOption::NOP,
+            TcpOption::NOP,
+            TcpOption::SackPermitted,
+        ]
+    } else if tcp.window_size == 65535 {
+        // macOS
+        vec![
+            mss,
+            TcpOption::NOP,
+            scale,
+            TcpOption::NOP,
+            TcpOption::NOP,
+            timestamp,
+            TcpOption::SackPermitted,
+            TcpOption::EOL,
+        ]
+    } else {
+        // Linux
+        vec![
+            mss,
+            TcpOption::SackPermitted,
+            timestamp,
+            TcpOption::NOP,
+            scale,
+        ]
+    }
+}
+
+fn syn_options_len(tcp: &TcpProfile) -> usize {
+    syn_options(tcp, 0)
+        .iter()
+        .map(|option| match option {
+            TcpOption::EOL | TcpOption::NOP => 1,
+            other => 2 + other.data().len(),
+        })
+        .sum::<usize>()
+        .div_ceil(4)
+        * 4
+}
+
+fn parse_os(os: &str) -> Option<OperatingSystem> {
+    match os.to_ascii_lowercase().as_str() {
+        "windows" => Some(OperatingSystem::Windows10),
+        "macos" => Some(OperatingSystem::MacOS14),
+        "linux" => Some(OperatingSystem::Linux),
+        _ => None,
+    }
+}
+
+fn browser_spec(profile: &str) -> ClientHelloSpec {
+    match profile {
+        "chrome_103" => ClientHelloSpec::chrome_103(),
+        "chrome_136" => ClientHelloSpec::chrome_136(),
+        "firefox_133" => ClientHelloSpec::firefox_133(),
+        "safari_16_0" => ClientHelloSpec::safari_16_0(),
+        _ => ClientHelloSpec::chrome_133(),
+    }
+}
+
+fn browser_user_agent(profile: &str, os: OperatingSystem) -> String {
+    let platform = os.as_str();
+    match profile {
+        "firefox_133" => format!(
+            "Mozilla/5.0 ({}; rv:133.0) Gecko/20100101 Firefox/133.0",
+            platform
+        ),
+        "safari_16_0" => "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 \
+                          (KHTML, like Gecko) Version/16.0 Safari/605.1.15"
+            .to_string(),
+        _ => {
+            let version = profile.trim_start_matches("chrome_");
+            format!(
+                "Mozilla/5.0 ({}) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{}.0.0.0 Safari/537.36",
+                platform, version
+            )
+        }
+    }
+}
+
+/// ClientHello of OpenSSL 3 based clients (python-requests, curl)
+fn openssl_spec(alpn: &[&str]) -> ClientHelloSpec {
+    let mut spec = ClientHelloSpec::new();
+    spec.cipher_suites = vec![
+        cs::TLS_AES_256_GCM_SHA384,
+        cs::TLS_CHACHA20_POLY1305_SHA256,
+        cs::TLS_AES_128_GCM_SHA256,
+        cs::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+        cs::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+        cs::TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+        cs::TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+        cs::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+        cs::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+        cs::TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA,
+        cs::TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA,
+        cs::TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA,
+        cs::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA,
+        cs::TLS_RSA_WITH_AES_256_GCM_SHA384,
+        cs::TLS_RSA_WITH_AES_128_GCM_SHA256,
+        cs::TLS_RSA_WITH_AES_256_CBC_SHA,
+        cs::TLS_RSA_WITH_AES_128_CBC_SHA,
+    ];
+    spec.compression_methods = vec![COMPRESSION_NONE];
+    spec.extensions = vec![
+        Box::new(SupportedPointsExtension::new(vec![
+            POINT_FORMAT_UNCOMPRESSED,
+        ])),
+        Box::new(SupportedCurvesExtension::new(vec![
+            X25519, CURVE_P256, CURVE_P384, SECP521R1,
+        ])),
+        Box::new(SessionTicketExtension),
+        Box::new(ALPNExtension::new(
+            alpn.iter().map(|p| p.to_string()).collect(),
+        )),
+        Box::new(ExtendedMasterSecretExtension),
+        Box::new(SignatureAlgorithmsExtension::new(vec![
+            ECDSA_WITH_P256_AND_SHA256,
+            ECDSA_WITH_P384_AND_SHA384,
+            ECDSA_WITH_P521_AND_SHA512,
+            ED25519,
+            PSS_WITH_SHA256,
+            PSS_WITH_SHA384,
+            PSS_WITH_SHA512,
+            PKCS1_WITH_SHA256,
+            PKCS1_WITH_SHA384,
+            PKCS1_WITH_SHA512,
+        ])),
+        Box::new(SupportedVersionsExtension::new(vec![
+            VERSION_TLS13,
+            VERSION_TLS12,
+        ])),
+        Box::new(PSKKeyExchangeModesExtension::new(vec![PSK_MODE_DHE])),
+        Box::new(KeyShareExtension::new(vec![KeyShare {
+            group: X25519,
+            data: vec![],
+        }])),
+    ];
+    spec.tls_vers_min = VERSION_TLS12;
+    spec.tls_vers_max = VERSION_TLS13;
+    spec
+}
+
+/// ClientHello of Go's crypto/tls
+fn go_spec() -> ClientHelloSpec {
+    let mut spec = ClientHelloSpec::new();
+    spec.cipher_suites = vec![
+        cs::TLS_AES_128_GCM_SHA256,
+        cs::TLS_AES_256_GCM_SHA384,
+        cs::TLS_CHACHA20_POLY1305_SHA256,
+        cs::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+        cs::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+        cs::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+        cs::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+        cs::TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+        cs::TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+        cs::TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA,
+        cs::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA,
+        cs::TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA,
+        cs::TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA,
+        cs::TLS_RSA_WITH_AES_128_GCM_SHA256,
+        cs::TLS_RSA_WITH_AES_256_GCM_SHA384,
+        cs::TLS_RSA_WITH_AES_128_CBC_SHA,
+        cs::TLS_RSA_WITH_AES_256_CBC_SHA,
+    ];
+    spec.compression_methods = vec![COMPRESSION_NONE];
+    spec.extensions = vec![
+        Box::new(StatusRequestExtension),
+        Box::new(SupportedCurvesExtension::new(vec![
+            X25519, CURVE_P256, CURVE_P384, SECP521R1,
+        ])),
+        Box::new(SupportedPointsExtension::new(vec![
+            POINT_FORMAT_UNCOMPRESSED,
+        ])),
+        Box::new(SignatureAlgorithmsExtension::new(vec![
+            PSS_WITH_SHA256,
+            ECDSA_WITH_P256_AND_SHA256,
+            ED25519,
+            PSS_WITH_SHA384,
+            PSS_WITH_SHA512,
+            PKCS1_WITH_SHA256,
+            PKCS1_WITH_SHA384,
+            PKCS1_WITH_SHA512,
+            ECDSA_WITH_P384_AND_SHA384,
+            ECDSA_WITH_P521_AND_SHA512,
+        ])),
+        Box::new(RenegotiationInfoExtension::new(RENEGOTIATE_ONCE_AS_CLIENT)),
+        Box::new(ALPNExtension::new(vec![
+            "h2".to_string(),
+            "http/1.1".to_string(),
+        ])),
+        Box::new(SCTExtension),
+        Box::new(SupportedVersionsExtension::new(vec![
+            VERSION_TLS13,
+            VERSION_TLS12,
+        ])),
+        Box::new(KeyShareExtension::new(vec![KeyShare {
+            group: X25519,
+            data: vec![],
+        }])),
+    ];
+    spec.tls_vers_min = VERSION_TLS12;
+    spec.tls_vers_max = VERSION_TLS13;
+    spec
+}
+
+/// per-class generation state
+struct ClassState {
+    pool: Vec<IpAddr>,
+    sequence: u64,
+}
+
+/// synthetic traffic generator
+pub struct TrafficSimulator {
+    config: SimulationConfig,
+    rng: StdRng,
+    states: Vec<ClassState>,
+}
+
+impl TrafficSimulator {
+    pub fn new(config: SimulationConfig) -> Result<Self, SimulationError> {
+        config.validate()?;
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let states = config
+            .classes
+            .iter()
+            .map(|class| ClassState {
+                pool: (0..class.source_ips.unwrap_or(0))
+                    .map(|_| random_source_ip(&mut rng))
+                    .collect(),
+                sequence: 0,
+            })
+            .collect();
+        Ok(Self {
+            config,
+            rng,
+            states,
+        })
+    }
+
+    pub fn config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    /// generate every flow of the configured duration, ordered by arrival time
+    pub fn generate(&mut self) -> Result<Vec<SyntheticFlow>, SimulationError> {
+        let mut flows = Vec::new();
+        let mut elapsed = 0.0;
+        loop {
+            // exponential inter-arrival times give a Poisson process
+            let u: f64 = self.rng.gen_range(f64::EPSILON..1.0);
+            elapsed += -u.ln() / self.config.flows_per_second;
+            if elapsed > self.config.duration_secs {
+                break;
+            }
+            let class = self.pick_class();
+            flows.push(self.flow(class, Duration::from_secs_f64(elapsed))?);
+        }
+        Ok(flows)
+    }
+
+    /// generate flows and feed them through the passive pipeline
+    pub fn run_passive(
+        &mut self,
+        analyzer: &PassiveAnalyzer,
+    ) -> Result<SimulationReport, SimulationError> {
+        let flows = self.generate()?;
+        let mut report = SimulationReport::default();
+        for flow in &flows {
+            let mut merged = PassiveAnalysisResult::default();
+            for packet in flow.to_packets() {
+                let result = analyzer.analyze(&packet);
+                merged.tcp = merged.tcp.or(result.tcp);
+                merged.http = merged.http.or(result.http);
+                merged.tls = merged.tls.or(result.tls);
+                merged.tls_server = merged.tls_server.or(result.tls_server);
+            }
+            report.record(flow, Some(&merged), true);
+            report.analyses.push(LabeledAnalysis {
+                class: flow.class.clone(),
+                label: flow.label,
+                src_ip: flow.src_ip,
+                result: merged,
+            });
+        }
+        Ok(report)
+    }
+
+    /// generate flows and replay them in real time against a test server
+    ///
+    /// Each flow opens a TCP connection at its arrival offset and writes its first
+    /// data segment; the kernel's own stack is used, so only the payload (not the
+    /// simulated SYN parameters) reaches the server.
+    pub async fn run_sockets(
+        &mut self,
+        target: SocketAddr,
+    ) -> Result<SimulationReport, SimulationError> {
+        use tokio::io::AsyncWriteExt;
+
+        let flows = self.generate()?;
+        let start = tokio::time::Instant::now();
+        let mut tasks = tokio::task::JoinSet::new();
+        for (index, flow) in flows.iter().enumerate() {
+            let at = start + flow.offset;
+            let payload = flow.payload().to_vec();
+            tasks.spawn(async move {
+                tokio::time::sleep_until(at).await;
+                let sent = async {
+                    let mut stream = tokio::net::TcpStream::connect(target).await?;
+                    stream.write_all(&payload).await?;
+                    stream.shutdown().await
+                }
+                .await;
+                (index, sent.is_ok())
+            });
+        }
+
+        let mut outcomes = vec![false; flows.len()];
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok((index, ok)) = joined {
+                outcomes[index] = ok;
+            }
+        }
+
+        let mut report = SimulationReport::default();
+        for (flow, ok) in flows.iter().zip(outcomes) {
+            report.record(flow, None, ok);
+        }
+        Ok(report)
+    }
+
+    fn pick_class(&mut self) -> usize {
+        let total: f64 = self.config.classes.iter().map(|c| c.weight).sum();
+        let mut target = self.rng.gen_range(0.0..total);
+        for (index, class) in self.config.classes.iter().enumerate() {
+            if target < class.weight {
+                return index;
+            }
+            target -= class.weight;
+        }
+        self.config.classes.len() - 1
+    }
+
+    fn flow(&mut self, index: usize, offset: Duration) -> Result<SyntheticFlow, SimulationError> {
+        let class = self.config.classes[index].clone();
+        let sequence = self.states[index].sequence;
+        self.states[index].sequence += 1;
+
+        let src_ip = if self.states[index].pool.is_empty() {
+            random_source_ip(&mut self.rng)
+        } else {
+            let pool = &self.states[index].pool;
+            pool[self.rng.gen_range(0..pool.len())]
+        };
+
+        let linux = TcpProfile::for_os(OperatingSystem::Linux);
+        let (spec, tcp, user_agent, method, path) = match &class.source {
+            TrafficSource::Browser { profile, os } => {
+                let os = parse_os(os).unwrap_or(OperatingSystem::Windows10);
+                let path = BROWSE_PATHS[self.rng.gen_range(0..BROWSE_PATHS.len())];
+                (
+                    browser_spec(profile),
+                    TcpProfile::for_os(os),
+                    browser_user_agent(profile, os),
+                    "GET",
+                    path.to_string(),
+                )
+            }
+            TrafficSource::Bot { signature } => {
+                let (spec, user_agent) = match signature {
+                    BotSignature::PythonRequests => (
+                        openssl_spec(&["http/1.1"]),
+                        "python-requests/2.32.3".to_string(),
+                    ),
+                    BotSignature::Curl => {
+                        (openssl_spec(&["h2", "http/1.1"]), "curl/8.5.0".to_string())
+                    }
+                    BotSignature::GoHttp => (go_spec(), "Go-http-client/1.1".to_string()),
+                    BotSignature::HeadlessChrome => (
+                        ClientHelloSpec::chrome_133(),
+                        browser_user_agent("chrome_133", OperatingSystem::Linux)
+                            .replace("Chrome/", "HeadlessChrome/"),
+                    ),
+                };
+                (spec, linux, user_agent, "GET", "/".to_string())
+            }
+            TrafficSource::Attack { pattern } => match pattern {
+                AttackPattern::CredentialStuffing => (
+                    openssl_spec(&["http/1.1"]),
+                    linux,
+                    "python-requests/2.32.3".to_string(),
+                    "POST",
+                    "/login".to_string(),
+                ),
+                AttackPattern::Scraping => (
+                    ClientHelloSpec::chrome_133(),
+                    linux,
+                    browser_user_agent("chrome_133", OperatingSystem::Linux)
+                        .replace("Chrome/", "HeadlessChrome/"),
+                    "GET",
+                    format!("/products?page={}", sequence + 1),
+                ),
+                AttackPattern::FingerprintRotation => {
+                    let mut spec = ClientHelloSpec::chrome_133();
+                    let mutations = Mutation::systematic(&spec);
+                    mutations[self.rng.gen_range(0..mutations.len())].apply(&mut spec);
+                    (
+                        spec,
+                        TcpProfile::for_os(OperatingSystem::Windows10),
+                        browser_user_agent("chrome_133", OperatingSystem::Windows10),
+                        "GET",
+                        "/".to_string(),
+                    )
+                }
+                AttackPattern::StackMismatch => (
+                    ClientHelloSpec::chrome_133(),
+                    linux,
+                    browser_user_agent("chrome_133", OperatingSystem::Windows10),
+                    "GET",
+                    "/".to_string(),
+                ),
+            },
+        };
+
+        let client_hello = TLSHandshakeBuilder::build_client_hello(&spec, &self.config.server_name)
+            .map_err(SimulationError::Tls)?;
+        let http_request = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+            method, path, self.config.server_name, user_agent
+        )
+        .into_bytes();
+
+        Ok(SyntheticFlow {
+            offset,
+            class: class.name,
+            label: class.source.label(),
+            src_ip,
+            src_port: self.rng.gen_range(49152..=65535),
+            dst_ip: self.config.dst_ip,
+            dst_port: self.config.dst_port,
+            tcp,
+            hops: self.rng.gen_range(3..20),
+            user_agent,
+            client_hello,
+            http_request,
+            tls: self.config.tls,
+        })
+    }
+}
+
+/// addresses from the benchmarking range 198.18.0.0/15 (RFC 2544)
+fn random_source_ip(rng: &mut StdRng) -> IpAddr {
+    let host: u32 = rng.gen_range(1..(1 << 17) - 1);
+    IpAddr::V4(Ipv4Addr::from((198u32 << 24 | 18 << 16) + host))
+}
+
+/// passive analysis of one synthetic flow with its ground truth
+#[derive(Debug, Clone)]
+pub struct LabeledAnalysis {
+    pub class: String,
+    pub label: TrafficLabel,
+    pub src_ip: IpAddr,
+    pub result: PassiveAnalysisResult,
+}
+
+/// per-class statistics
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassStats {
+    pub label: TrafficLabel,
+    pub flows: usize,
+    /// flows that reached the sink (always all of them for the passive sink)
+    pub delivered: usize,
+    pub source_ips: usize,
+    /// JA4 → flow count
+    pub ja4: HashMap<String, usize>,
+    /// flows whose SYN matched a known OS signature
+    pub tcp_identified: usize,
+    #[serde(skip)]
+    sources: HashSet<IpAddr>,
+}
+
+/// simulation summary
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SimulationReport {
+    pub flows: usize,
+    pub delivered: usize,
+    pub classes: BTreeMap<String, ClassStats>,
+    /// per-flow passive results (passive sink only)
+    #[serde(skip)]
+    pub analyses: Vec<LabeledAnalysis>,
+}
+
+impl SimulationReport {
+    fn record(&mut self, flow: &SyntheticFlow, result: Option<&PassiveAnalysisResult>, ok: bool) {
+        self.flows += 1;
+        let stats = self
+            .classes
+            .entry(flow.class.clone())
+            .or_insert_with(|| ClassStats {
+                label: flow.label,
+                flows: 0,
+                delivered: 0,
+                source_ips: 0,
+                ja4: HashMap::new(),
+                tcp_identified: 0,
+                sources: HashSet::new(),
+            });
+        stats.flows += 1;
+        if ok {
+            self.delivered += 1;
+            stats.delivered += 1;
+        }
+        stats.sources.insert(flow.src_ip);
+        stats.source_ips = stats.sources.len();
+
+        if let Some(result) = result {
+            if let Some(ja4) = result.tls.as_ref().and_then(|tls| tls.ja4.clone()) {
+                *stats.ja4.entry(ja4).or_insert(0) += 1;
+            }
+            if result.tcp.as_ref().is_some_and(|tcp| tcp.os.is_some()) {
+                stats.tcp_identified += 1;
+            }
+        }
+    }
+
+    /// flow count per label
+    pub fn label_counts(&self) -> HashMap<TrafficLabel, usize> {
+        let mut counts = HashMap::new();
+        for stats in self.classes.values() {
+            *counts.entry(stats.label).or_insert(0) += stats.flows;
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mixed_config() -> SimulationConfig {
+        SimulationConfig::from_json(
+            r#"{
+                "seed": 7,
+                "flows_per_second": 200.0,
+                "duration_secs": 2.0,
+                "classes": [
+                    { "name": "chrome", "weight": 0.6,
+                      "source": { "type": "browser", "profile": "chrome_133", "os": "windows" } },
+                    { "name": "curl", "weight": 0.2,
+                      "source": { "type": "bot", "signature": "curl" } },
+                    { "name": "stuffing", "weight": 0.2, "source_ips": 3,
+                      "source": { "type": "attack", "pattern": "credential_stuffing" } }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_mixture_and_determinism() {
+        let flows = TrafficSimulator::new(mixed_config())
+            .unwrap()
+            .generate()
+            .unwrap();
+        let again = TrafficSimulator::new(mixed_config())
+            .unwrap()
+            .generate()
+            .unwrap();
+
+        // ~400 flows expected at 200/s for 2s
+        assert!(flows.len() > 300 && flows.len() < 500, "{}", flows.len());
+        assert_eq!(flows.len(), again.len());
+        assert!(flows.windows(2).all(|w| w[0].offset <= w[1].offset));
+
+        let humans = flows
+            .iter()
+            .filter(|f| f.label == TrafficLabel::Human)
+            .count() as f64;
+        assert!((humans / flows.len() as f64 - 0.6).abs() < 0.1);
+
+        let stuffing_ips: HashSet<IpAddr> = flows
+            .iter()
+            .filter(|f| f.class == "stuffing")
+            .map(|f| f.src_ip)
+            .collect();
+        assert!(stuffing_ips.len() <= 3);
+
+        let bad = SimulationConfig::new(
+            10.0,
+            1.0,
+            vec![TrafficClass::new(
+                "x",
+                1.0,
+                TrafficSource::Browser {
+                    profile: "netscape_4".to_string(),
+                    os: "windows".to_string(),
+                },
+            )],
+        );
+        assert!(TrafficSimulator::new(bad).is_err());
+    }
+
+    #[test]
+    fn test_passive_pipeline_sees_synthetic_flows() {
+        let mut config = SimulationConfig::new(
+            100.0,
+            1.0,
+            vec![
+                TrafficClass::new(
+                    "chrome",
+                    1.0,
+                    TrafficSource::Browser {
+                        profile: "chrome_133".to_string(),
+                        os: "windows".to_string(),
+                    },
+                ),
+                TrafficClass::new(
+                    "go",
+                    1.0,
+                    TrafficSource::Bot {
+                        signature: BotSignature::GoHttp,
+                    },
+                ),
+                TrafficClass::new(
+                    "rotation",
+                    1.0,
+                    TrafficSource::Attack {
+                        pattern: AttackPattern::FingerprintRotation,
+                    },
+                ),
+            ],
+        );
+        config.seed = 3;
+        let analyzer = PassiveAnalyzer::new().unwrap();
+        let report = TrafficSimulator::new(config)
+            .unwrap()
+            .run_passive(&analyzer)
+            .unwrap();
+
+        assert_eq!(report.flows, report.analyses.len());
+        assert!(report.analyses.iter().all(|a| a
+            .result
+            .tls
+            .as_ref()
+            .and_then(|t| t.ja4.as_ref())
+            .is_some()));
+
+        let chrome = &report.classes["chrome"];
+        let go = &report.classes["go"];
+        let rotation = &report.classes["rotation"];
+        assert_eq!(chrome.ja4.len(), 1);
+        assert_eq!(go.ja4.len(), 1);
+        assert_ne!(chrome.ja4.keys().next(), go.ja4.keys().next());
+        // rotation spreads over several JA4s
+        assert!(rotation.ja4.len() > 1);
+    }
+
+    #[tokio::test]
+    async fn test_socket_sink_delivers_client_hellos() {
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut records = 0;
+            while let Ok(Ok((mut stream, _))) =
+                tokio::time::timeout(Duration::from_millis(500), listener.accept()).await
+            {
+                let mut buf = Vec::new();
+                stream.read_to_end(&mut buf).await.unwrap();
+                if buf.starts_with(&[0x16, 0x03]) {
+                    records += 1;
+                }
+            }
+            records
+        });
+
+        let config = SimulationConfig::new(
+            50.0,
+            0.2,
+            vec![TrafficClass::new(
+                "python",
+                1.0,
+                TrafficSource::Bot {
+                    signature: BotSignature::PythonRequests,
+                },
+            )],
+        );
+        let report = TrafficSimulator::new(config)
+            .unwrap()
+            .run_sockets(addr)
+            .await
+            .unwrap();
+
+        assert_eq!(report.delivered, report.flows);
+        assert_eq!(server.await.unwrap(), report.flows);
+    }
+}
@@ -0,0 +1,255 @@
+//! STIX 2.1 / TAXII 2.1 threat-intel exchange
+//!
+//! Learned fingerprints leave as STIX indicators, each with a sighting that
+//! carries the observation count and first/last-seen times
+//! ([`learned_bundle`]). External JA3/JA4 indicators come in through
+//! [`import_objects`] and are stored per fingerprint in the
+//! [`FingerprintDatabase`], where
+//! [`threat_intel_for`](FingerprintDatabase::threat_intel_for) finds the ones
+//! in force. [`TaxiiClient`] moves both directions over a TAXII 2.1 API root.
+
+pub mod taxii;
+
+pub use taxii::{
+    ObjectPage, TaxiiClient, TaxiiCollection, TaxiiError, TaxiiStatus, TaxiiTransport,
+    TAXII_MEDIA_TYPE,
+};
+
+use crate::database::FingerprintDatabase;
+use crate::ids_export::{ExportedFingerprint, Indicator as RuleIndicator};
+use chrono::{DateTime, Utc};
+use fingerprint_core::stix::{
+    confidence_from_stix, Bundle, FingerprintIndicator, FingerprintKind, Identity, Indicator,
+    Sighting, StixObject,
+};
+use serde::{Deserialize, Serialize};
+
+/// Confidence of feed indicators that do not state one
+pub const DEFAULT_FEED_CONFIDENCE: f64 = 0.5;
+
+/// `fingerprint_type` of JA3 / JA4 indicators in the database
+pub const TLS_FINGERPRINT_TYPE: &str = "tls";
+
+/// A feed indicator as stored in the database, one row per fingerprint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThreatIntelIndicator {
+    pub stix_id: String,
+    pub fingerprint_type: String,
+    pub fingerprint_id: String,
+    pub kind: FingerprintKind,
+    /// feed the indicator came from, e.g. `taxii:<collection>`
+    pub source: String,
+    pub name: Option<String>,
+    /// 0.0 – 1.0
+    pub confidence: f64,
+    /// Unix seconds
+    pub valid_from: i64,
+    /// Unix seconds
+    pub valid_until: Option<i64>,
+    pub revoked: bool,
+    /// `modified` of the STIX object, Unix milliseconds
+    pub modified_ms: i64,
+}
+
+impl ThreatIntelIndicator {
+    /// One entry per JA3 / JA4 hash in the indicator's pattern
+    pub fn from_stix(indicator: &Indicator, source: &str) -> Vec<Self> {
+        indicator
+            .fingerprints()
+            .into_iter()
+            .map(|fingerprint| Self {
+                stix_id: indicator.id.clone(),
+                fingerprint_type: TLS_FINGERPRINT_TYPE.to_string(),
+                fingerprint_id: fingerprint.value,
+                kind: fingerprint.kind,
+                source: source.to_string(),
+                name: indicator.name.clone(),
+                confidence: indicator
+                    .confidence
+                    .map(confidence_from_stix)
+                    .unwrap_or(DEFAULT_FEED_CONFIDENCE),
+                valid_from: indicator.valid_from.timestamp(),
+                valid_until: indicator.valid_until.map(|t| t.timestamp()),
+                revoked: indicator.revoked,
+                modified_ms: indicator.modified.timestamp_millis(),
+            })
+            .collect()
+    }
+
+    /// In force at `now` (Unix seconds)
+    pub fn is_active(&self, now: i64) -> bool {
+        !self.revoked && self.valid_from <= now && self.valid_until.is_none_or(|t| now < t)
+    }
+}
+
+/// Outcome of importing feed objects
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// objects received
+    pub objects: usize,
+    /// indicators among them
+    pub indicators: usize,
+    /// fingerprint rows inserted or updated
+    pub stored: usize,
+    /// rows already stored at the same or a newer version
+    pub unchanged: usize,
+    /// indicators without a JA3 / JA4 hash
+    pub unsupported: usize,
+    /// `X-TAXII-Date-Added-Last` of the last page, for the next `added_after`
+    pub date_added_last: Option<DateTime<Utc>>,
+}
+
+/// Store the JA3 / JA4 indicators among `objects`, tagged with `source`
+pub fn import_objects(
+    db: &FingerprintDatabase,
+    objects: &[StixObject],
+    source: &str,
+) -> Result<ImportReport, String> {
+    let mut report = ImportReport {
+        objects: objects.len(),
+        ..ImportReport::default()
+    };
+    for indicator in objects.iter().filter_map(StixObject::as_indicator) {
+        report.indicators += 1;
+        let rows = ThreatIntelIndicator::from_stix(indicator, source);
+        if rows.is_empty() {
+            report.unsupported += 1;
+        }
+        for row in rows {
+            if db.upsert_threat_intel(&row)? {
+                report.stored += 1;
+            } else {
+                report.unchanged += 1;
+            }
+        }
+    }
+    if report.stored > 0 {
+        log::info!(
+            "[ThreatIntel] Imported {} fingerprint indicators from {}",
+            report.stored,
+            source
+        );
+    }
+    Ok(report)
+}
+
+/// Indicator and sighting of one learned fingerprint; empty when it has no STIX pattern
+pub fn fingerprint_objects(
+    fingerprint: &ExportedFingerprint,
+    identity: &Identity,
+) -> Vec<StixObject> {
+    let (RuleIndicator::Ja3(value) | RuleIndicator::Ja4(value)) = &fingerprint.indicator else {
+        return Vec::new();
+    };
+    let Some(hash) = FingerprintIndicator::classify(value) else {
+        return Vec::new();
+    };
+    let first_seen = fingerprint.first_seen.unwrap_or_else(Utc::now);
+    let mut indicator = Indicator::for_fingerprint(&hash, first_seen)
+        .with_confidence(fingerprint.confidence)
+        .with_creator(identity);
+    indicator.indicator_types = vec!["anomalous-activity".to_string()];
+    indicator.labels = vec!["learned-fingerprint".to_string()];
+    let sighting = Sighting::of(&indicator, identity).with_observations(
+        fingerprint.observation_count.max(1),
+        first_seen,
+        fingerprint.last_seen.unwrap_or(first_seen),
+    );
+    vec![
+        StixObject::Indicator(indicator),
+        StixObject::Sighting(sighting),
+    ]
+}
+
+/// Approved learned TLS fingerprints with at least `min_confidence`, as a bundle
+pub fn learned_bundle(
+    db: &FingerprintDatabase,
+    identity: &Identity,
+    min_confidence: f64,
+) -> Result<Bundle, String> {
+    let mut objects = vec![StixObject::Identity(identity.clone())];
+    for candidate in db.get_candidates_with_status("approved", None)? {
+        let Some(fingerprint) = ExportedFingerprint::from_candidate(&candidate) else {
+            continue;
+        };
+        if fingerprint.confidence >= min_confidence {
+            objects.extend(fingerprint_objects(&fingerprint, identity));
+        }
+    }
+    Ok(Bundle::new(objects))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const JA4: &str = "t13d1516h2_8daaf6152771_02713d6af862";
+
+    #[test]
+    fn imports_newer_versions_only() {
+        let db = FingerprintDatabase::new_in_memory().unwrap();
+        let now = Utc::now();
+        let fingerprint = FingerprintIndicator::classify(JA4).unwrap();
+        let mut indicator = Indicator::for_fingerprint(&fingerprint, now - Duration::hours(1));
+        indicator.confidence = Some(80);
+        let mut unrelated = indicator.clone();
+        unrelated.id = "indicator--00000000-0000-4000-8000-000000000000".to_string();
+        unrelated.pattern = "[ipv4-addr:value = '198.51.100.1']".to_string();
+
+        let objects = vec![
+            StixObject::Indicator(indicator.clone()),
+            StixObject::Indicator(unrelated),
+        ];
+        let report = import_objects(&db, &objects, "feed").unwrap();
+        assert_eq!(
+            (report.indicators, report.stored, report.unsupported),
+            (2, 1, 1)
+        );
+        assert_eq!(import_objects(&db, &objects, "feed").unwrap().unchanged, 1);
+
+        let found = db
+            .threat_intel_for(TLS_FINGERPRINT_TYPE, JA4, now.timestamp())
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].confidence, 0.8);
+        assert_eq!(found[0].kind, FingerprintKind::Ja4);
+
+        indicator.modified += Duration::seconds(1);
+        indicator.revoked = true;
+        let report = import_objects(&db, &[StixObject::Indicator(indicator)], "feed").unwrap();
+        assert_eq!(report.stored, 1);
+        assert!(db
+            .threat_intel_for(TLS_FINGERPRINT_TYPE, JA4, now.timestamp())
+            .unwrap()
+            .is_empty());
+        assert!(db.threat_intel_indicators(10).unwrap()[0].revoked);
+    }
+
+    #[test]
+    fn learned_bundle_carries_sightings() {
+        let db = FingerprintDatabase::new_in_memory().unwrap();
+        let id = db
+            .store_candidate_fingerprint("tls", JA4, 30, 0.96, None)
+            .unwrap();
+        db.update_candidate_status(id, "approved", None).unwrap();
+        db.store_candidate_fingerprint("tls", "e7d705a3286e19ea42f587b344ee6865", 30, 0.99, None)
+            .unwrap();
+
+        let identity = Identity::system("gateway-1");
+        let bundle = learned_bundle(&db, &identity, 0.9).unwrap();
+        assert_eq!(bundle.objects.len(), 3);
+        let indicator = bundle.indicators().next().unwrap();
+        assert_eq!(indicator.confidence, Some(96));
+        assert_eq!(
+            indicator.created_by_ref.as_deref(),
+            Some(identity.id.as_str())
+        );
+        let StixObject::Sighting(sighting) = &bundle.objects[2] else {
+            panic!("expected a sighting");
+        };
+        assert_eq!(sighting.sighting_of_ref, indicator.id);
+        assert_eq!(sighting.count, Some(30));
+        assert!(sighting.first_seen.is_some());
+    }
+}
@@ -0,0 +1,449 @@
+//! TAXII 2.1 client
+//!
+//! Talks to one API root: lists collections, pages through a collection's
+//! objects (following `more` / `next`) and adds bundles to a collection.
+//! Requests go through a [`TaxiiTransport`], which is the fingerprint HTTP
+//! client by default.
+
+use super::{import_objects, learned_bundle, ImportReport};
+use crate::database::FingerprintDatabase;
+use chrono::{DateTime, SecondsFormat, Utc};
+use fingerprint_core::stix::{Bundle, Identity, StixObject};
+use fingerprint_http::{HttpClient, HttpClientConfig, HttpMethod, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+
+/// Media type of TAXII 2.1 requests and responses
+pub const TAXII_MEDIA_TYPE: &str = "application/taxii+json;version=2.1";
+
+/// Objects requested per page
+const DEFAULT_PAGE_LIMIT: usize = 500;
+
+/// Pages fetched by one `get_objects` call before giving up
+const MAX_PAGES: usize = 1000;
+
+/// TAXII client errors
+#[derive(Debug, thiserror::Error)]
+pub enum TaxiiError {
+    #[error("transport error: {0}")]
+    Transport(String),
+    #[error("TAXII server returned {status}: {body}")]
+    Status { status: u16, body: String },
+    #[error("invalid TAXII response: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("database error: {0}")]
+    Database(String),
+}
+
+/// Sends TAXII requests
+pub trait TaxiiTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, String>;
+}
+
+impl TaxiiTransport for HttpClient {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, String> {
+        self.send_request(request).map_err(|e| e.to_string())
+    }
+}
+
+/// Collection resource
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaxiiCollection {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub can_read: bool,
+    pub can_write: bool,
+}
+
+/// Status resource returned when adding objects
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaxiiStatus {
+    pub id: String,
+    /// `pending` or `complete`
+    pub status: String,
+    #[serde(default)]
+    pub total_count: u64,
+    #[serde(default)]
+    pub success_count: u64,
+    #[serde(default)]
+    pub failure_count: u64,
+    #[serde(default)]
+    pub pending_count: u64,
+}
+
+#[derive(Deserialize)]
+struct Collections {
+    #[serde(default)]
+    collections: Vec<TaxiiCollection>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    more: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next: Option<String>,
+    #[serde(default)]
+    objects: Vec<StixObject>,
+}
+
+/// Objects of a collection plus the server's last `date_added`
+#[derive(Debug, Clone, Default)]
+pub struct ObjectPage {
+    pub objects: Vec<StixObject>,
+    pub date_added_last: Option<DateTime<Utc>>,
+}
+
+/// Client of one TAXII 2.1 API root
+pub struct TaxiiClient {
+    api_root: String,
+    transport: Box<dyn TaxiiTransport>,
+    headers: Vec<(String, String)>,
+    page_limit: usize,
+}
+
+impl TaxiiClient {
+    /// `api_root` is the API root URL, e.g. `https://taxii.example.com/api1/`
+    pub fn new(api_root: &str) -> Self {
+        Self::with_transport(api_root, HttpClient::new(HttpClientConfig::default()))
+    }
+
+    pub fn with_transport<T: TaxiiTransport + 'static>(api_root: &str, transport: T) -> Self {
+        Self {
+            api_root: format!("{}/", api_root.trim_end_matches('/')),
+            transport: Box::new(transport),
+            headers: Vec::new(),
+            page_limit: DEFAULT_PAGE_LIMIT,
+        }
+    }
+
+    pub fn with_basic_auth(self, user: &str, password: &str) -> Self {
+        let credentials = base64_encode(format!("{}:{}", user, password).as_bytes());
+        self.with_header("Authorization", &format!("Basic {}", credentials))
+    }
+
+    pub fn with_bearer_token(self, token: &str) -> Self {
+        self.with_header("Authorization", &format!("Bearer {}", token))
+    }
+
+    /// Extra header sent with every request
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_page_limit(mut self, limit: usize) -> Self {
+        self.page_limit = limit.max(1);
+        self
+    }
+
+    pub fn api_root(&self) -> &str {
+        &self.api_root
+    }
+
+    /// Collections of the API root
+    pub fn collections(&self) -> Result<Vec<TaxiiCollection>, TaxiiError> {
+        let response = self.request(HttpMethod::Get, "collections/", None)?;
+        Ok(serde_json::from_slice::<Collections>(&response.body)?.collections)
+    }
+
+    /// Objects of `type_filter` (all types when empty) added after `added_after`
+    pub fn get_objects(
+        &self,
+        collection_id: &str,
+        type_filter: &[&str],
+        added_after: Option<DateTime<Utc>>,
+    ) -> Result<ObjectPage, TaxiiError> {
+        let mut page = ObjectPage::default();
+        let mut next: Option<String> = None;
+        for _ in 0..MAX_PAGES {
+            let mut query = vec![format!("limit={}", self.page_limit)];
+            if let Some(after) = added_after {
+                query.push(format!(
+                    "added_after={}",
+                    percent_encode(&after.to_rfc3339_opts(SecondsFormat::Millis, true))
+                ));
+            }
+            if !type_filter.is_empty() {
+                query.push(format!("match[type]={}", type_filter.join(",")));
+            }
+            if let Some(token) = &next {
+                query.push(format!("next={}", percent_encode(token)));
+            }
+            let path = format!(
+                "collections/{}/objects/?{}",
+                percent_encode(collection_id),
+                query.join("&")
+            );
+            let response = self.request(HttpMethod::Get, &path, None)?;
+            if let Some(added) = header(&response, "X-TAXII-Date-Added-Last")
+                .and_then(|v| DateTime::parse_from_rfc3339(v).ok())
+            {
+                page.date_added_last = Some(added.with_timezone(&Utc));
+            }
+            // 204 or an empty body: nothing (more) to read
+            if response.body.is_empty() {
+                return Ok(page);
+            }
+            let envelope: Envelope = serde_json::from_slice(&response.body)?;
+            page.objects.extend(envelope.objects);
+            match envelope.next {
+                Some(token) if envelope.more => next = Some(token),
+                _ => return Ok(page),
+            }
+        }
+        log::warn!(
+            "[TAXII] Stopped paging {} after {} pages",
+            collection_id,
+            MAX_PAGES
+        );
+        Ok(page)
+    }
+
+    /// Add the objects of `bundle` to a collection
+    pub fn add_objects(
+        &self,
+        collection_id: &str,
+        bundle: &Bundle,
+    ) -> Result<TaxiiStatus, TaxiiError> {
+        let envelope = Envelope {
+            more: false,
+            next: None,
+            objects: bundle.objects.clone(),
+        };
+        let body = serde_json::to_vec(&envelope)?;
+        let path = format!("collections/{}/objects/", percent_encode(collection_id));
+        let response = self.request(HttpMethod::Post, &path, Some(body))?;
+        Ok(serde_json::from_slice(&response.body)?)
+    }
+
+    /// Publish approved learned fingerprints with at least `min_confidence`
+    pub fn publish_learned(
+        &self,
+        collection_id: &str,
+        db: &FingerprintDatabase,
+        identity: &Identity,
+        min_confidence: f64,
+    ) -> Result<TaxiiStatus, TaxiiError> {
+        let bundle = learned_bundle(db, identity, min_confidence).map_err(TaxiiError::Database)?;
+        self.add_objects(collection_id, &bundle)
+    }
+
+    /// Import the JA3 / JA4 indicators of a collection into `db`
+    ///
+    /// Pass the previous report's `date_added_last` as `added_after` to poll
+    /// incrementally.
+    pub fn ingest(
+        &self,
+        collection_id: &str,
+        db: &FingerprintDatabase,
+        added_after: Option<DateTime<Utc>>,
+    ) -> Result<ImportReport, TaxiiError> {
+        let page = self.get_objects(collection_id, &["indicator"], added_after)?;
+        let source = format!("taxii:{}", collection_id);
+        let mut report =
+            import_objects(db, &page.objects, &source).map_err(TaxiiError::Database)?;
+        report.date_added_last = page.date_added_last.or(added_after);
+        Ok(report)
+    }
+
+    fn request(
+        &self,
+        method: HttpMethod,
+        path: &str,
+        body: Option<Vec<u8>>,
+    ) -> Result<HttpResponse, TaxiiError> {
+        let mut request = HttpRequest::new(method, &format!("{}{}", self.api_root, path))
+            .with_header("Accept", TAXII_MEDIA_TYPE);
+        for (name, value) in &self.headers {
+            request = request.with_header(name, value);
+        }
+        if let Some(body) = body {
+            request = request
+                .with_header("Content-Type", TAXII_MEDIA_TYPE)
+                .with_body(body);
+        }
+        let response = self
+            .transport
+            .send(&request)
+            .map_err(TaxiiError::Transport)?;
+        if !response.is_success() {
+            return Err(TaxiiError::Status {
+                status: response.status_code,
+                body: String::from_utf8_lossy(&response.body)
+                    .chars()
+                    .take(512)
+                    .collect(),
+            });
+        }
+        Ok(response)
+    }
+}
+
+fn header<'a>(response: &'a HttpResponse, name: &str) -> Option<&'a str> {
+    response
+        .headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Query component with everything but unreserved characters escaped
+fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let n = (u32::from(chunk[0]) << 16)
+            | (u32::from(*chunk.get(1).unwrap_or(&0)) << 8)
+            | u32::from(*chunk.get(2).unwrap_or(&0));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fingerprint_core::stix::{FingerprintIndicator, Indicator};
+    use std::sync::{Arc, Mutex};
+
+    const JA3: &str = "e7d705a3286e19ea42f587b344ee6865";
+
+    /// Serves two pages of indicators and accepts POSTs
+    #[derive(Default, Clone)]
+    struct FakeServer {
+        requests: Arc<Mutex<Vec<HttpRequest>>>,
+    }
+
+    impl TaxiiTransport for FakeServer {
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse, String> {
+            self.requests.lock().unwrap().push(request.clone());
+            let mut response = HttpResponse::new(200);
+            let body = if request.method == HttpMethod::Post {
+                serde_json::json!({
+                    "id": "2d086da7-4bdc-4f91-900e-d77486753710",
+                    "status": "complete",
+                    "total_count": 3,
+                    "success_count": 3
+                })
+            } else if request.url.contains("next=p2") {
+                response.headers.insert(
+                    "x-taxii-date-added-last".into(),
+                    "2026-10-01T00:00:00.000Z".into(),
+                );
+                let ja3 = FingerprintIndicator::classify(JA3).unwrap();
+                serde_json::json!({
+                    "more": false,
+                    "objects": [StixObject::Indicator(
+                        Indicator::for_fingerprint(&ja3, Utc::now()).with_confidence(0.7)
+                    )]
+                })
+            } else {
+                serde_json::json!({
+                    "more": true,
+                    "next": "p2",
+                    "objects": [{
+                        "type": "indicator",
+                        "spec_version": "2.1",
+                        "id": "indicator--8e2e2d2b-17d4-4cbf-938f-98ee46b3cd3f",
+                        "created": "2026-09-01T00:00:00Z",
+                        "modified": "2026-09-01T00:00:00Z",
+                        "pattern": "[network-traffic:extensions.'tls-ext'.ja4 = 't13d1516h2_8daaf6152771_02713d6af862']",
+                        "pattern_type": "stix",
+                        "valid_from": "2026-09-01T00:00:00Z"
+                    }]
+                })
+            };
+            response.body = serde_json::to_vec(&body).unwrap();
+            Ok(response)
+        }
+    }
+
+    #[test]
+    fn ingests_paged_feed_and_publishes_bundle() {
+        let server = FakeServer::default();
+        let client = TaxiiClient::with_transport("https://tip.example/api1", server.clone())
+            .with_basic_auth("user", "secret")
+            .with_page_limit(1);
+        let db = FingerprintDatabase::new_in_memory().unwrap();
+
+        let report = client.ingest("feed-1", &db, None).unwrap();
+        assert_eq!((report.indicators, report.stored), (2, 2));
+        assert_eq!(
+            report.date_added_last.unwrap().to_rfc3339(),
+            "2026-10-01T00:00:00+00:00"
+        );
+        let stored = db.threat_intel_indicators(10).unwrap();
+        assert!(stored.iter().all(|i| i.source == "taxii:feed-1"));
+        assert!(stored
+            .iter()
+            .any(|i| i.fingerprint_id == JA3 && i.confidence == 0.7));
+
+        let id = db
+            .store_candidate_fingerprint("tls", JA3, 20, 0.95, None)
+            .unwrap();
+        db.update_candidate_status(id, "approved", None).unwrap();
+        let status = client
+            .publish_learned("feed-1", &db, &Identity::system("gw"), 0.9)
+            .unwrap();
+        assert_eq!(status.success_count, 3);
+
+        let requests = server.requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(
+            requests[0].url,
+            "https://tip.example/api1/collections/feed-1/objects/?limit=1&match[type]=indicator"
+        );
+        assert!(requests[1].url.ends_with("&next=p2"));
+        assert_eq!(
+            requests[0].headers["Authorization"],
+            "Basic dXNlcjpzZWNyZXQ="
+        );
+        let posted: serde_json::Value =
+            serde_json::from_slice(requests[2].body.as_ref().unwrap()).unwrap();
+        assert_eq!(posted["objects"].as_array().unwrap().len(), 3);
+        assert_eq!(requests[2].headers["Content-Type"], TAXII_MEDIA_TYPE);
+    }
+
+    #[test]
+    fn surfaces_http_errors() {
+        struct Unauthorized;
+        impl TaxiiTransport for Unauthorized {
+            fn send(&self, _: &HttpRequest) -> Result<HttpResponse, String> {
+                let mut response = HttpResponse::new(401);
+                response.body = b"{\"title\":\"unauthorized\"}".to_vec();
+                Ok(response)
+            }
+        }
+        let client = TaxiiClient::with_transport("https://tip.example/api1/", Unauthorized);
+        match client.collections() {
+            Err(TaxiiError::Status { status, body }) => {
+                assert_eq!(status, 401);
+                assert!(body.contains("unauthorized"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(base64_encode(b"ab"), "YWI=");
+        assert_eq!(base64_encode(b"a"), "YQ==");
+    }
+}
@@ -0,0 +1,451 @@
+//! Per-entity fingerprint timeline
+//!
+//! Stitches observations, analysis results, alerts and enforcement decisions
+//! for one fingerprint, or for every fingerprint linked to an identity (client
+//! IP, key owner), into a single newest-first timeline across stores.
+//!
+//! Each store implements [`TimelineSource`]; [`Timeline`] merges their events
+//! and pages through them with an opaque cursor. [`FingerprintDatabase`]
+//! is a source out of the box.
+//!
+//! [`FingerprintDatabase`]: crate::database::FingerprintDatabase
+
+use crate::labels::FingerprintRef;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+/// Default page size
+pub const DEFAULT_PAGE_SIZE: u32 = 100;
+
+/// Largest page a single query may request
+pub const MAX_PAGE_SIZE: u32 = 1000;
+
+/// Most fingerprints an identity is expanded to
+pub const MAX_LINKED_FINGERPRINTS: usize = 256;
+
+/// What a timeline event records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineEventKind {
+    /// fingerprint seen on the wire
+    Observation,
+    /// scoring or learner result
+    Analysis,
+    /// alert raised against the fingerprint
+    Alert,
+    /// enforcement decision (rate limit, block, ...)
+    Decision,
+}
+
+/// Whose history to show
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type", content = "value")]
+pub enum TimelineSubject {
+    /// one fingerprint hash or JA4-family value
+    Fingerprint(String),
+    /// an identity and every fingerprint linked to it
+    Identity(String),
+}
+
+/// Subject after identity expansion, handed to each source
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResolvedSubject {
+    /// fingerprint hashes / values to include
+    pub fingerprints: BTreeSet<String>,
+    /// identity the query started from, if any
+    pub identity: Option<String>,
+}
+
+/// One entry on the timeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineEvent {
+    /// when it happened
+    pub timestamp: DateTime<Utc>,
+    /// store the event came from
+    pub source: String,
+    /// ID unique within `source`
+    pub id: String,
+    pub kind: TimelineEventKind,
+    /// fingerprint the event refers to
+    pub fingerprint: Option<FingerprintRef>,
+    /// linked identity (client IP, key owner), if known
+    pub subject: Option<String>,
+    /// one-line description
+    pub summary: String,
+    /// store-specific details
+    pub details: serde_json::Value,
+}
+
+impl TimelineEvent {
+    /// Total order used for paging: newest first, then by source and ID
+    fn page_order(&self, other: &Self) -> Ordering {
+        other
+            .timestamp
+            .cmp(&self.timestamp)
+            .then_with(|| self.source.cmp(&other.source))
+            .then_with(|| self.id.cmp(&other.id))
+    }
+
+    fn position(&self) -> TimelineCursor {
+        TimelineCursor {
+            timestamp: self.timestamp,
+            source: self.source.clone(),
+            id: self.id.clone(),
+        }
+    }
+}
+
+/// A store that can contribute events to a timeline
+pub trait TimelineSource {
+    /// source name recorded on its events
+    fn name(&self) -> &str;
+
+    /// fingerprints this store links to `identity`
+    fn linked_fingerprints(&self, identity: &str) -> Result<Vec<String>, String>;
+
+    /// events for `subject` at or before `until`, at most `limit`
+    ///
+    /// Events must come newest first, ties ordered by ascending `id`, so that
+    /// a truncated result is always a prefix of the source's full timeline.
+    fn events(
+        &self,
+        subject: &ResolvedSubject,
+        until: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<TimelineEvent>, String>;
+}
+
+/// Timeline query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineQuery {
+    pub subject: TimelineSubject,
+    /// resume after this cursor (from [`TimelinePage::next_cursor`])
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// page size, clamped to [`MAX_PAGE_SIZE`]; 0 means [`DEFAULT_PAGE_SIZE`]
+    #[serde(default)]
+    pub limit: u32,
+}
+
+impl TimelineQuery {
+    /// history of one fingerprint
+    pub fn fingerprint(value: impl Into<String>) -> Self {
+        Self::new(TimelineSubject::Fingerprint(value.into()))
+    }
+
+    /// history of an identity and its linked fingerprints
+    pub fn identity(value: impl Into<String>) -> Self {
+        Self::new(TimelineSubject::Identity(value.into()))
+    }
+
+    fn new(subject: TimelineSubject) -> Self {
+        Self {
+            subject,
+            cursor: None,
+            limit: 0,
+        }
+    }
+
+    pub fn after(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+
+    pub fn with_limit(mut self, limit: u32) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// effective page size
+    pub fn page_size(&self) -> usize {
+        match self.limit {
+            0 => DEFAULT_PAGE_SIZE as usize,
+            n => n.min(MAX_PAGE_SIZE) as usize,
+        }
+    }
+}
+
+/// One page of timeline events
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimelinePage {
+    /// fingerprints the subject resolved to
+    pub fingerprints: Vec<String>,
+    /// events, newest first
+    pub events: Vec<TimelineEvent>,
+    /// cursor for the next (older) page, if there is one
+    pub next_cursor: Option<String>,
+}
+
+/// position of the last event on a page
+#[derive(Debug, Clone, PartialEq)]
+struct TimelineCursor {
+    timestamp: DateTime<Utc>,
+    source: String,
+    id: String,
+}
+
+impl TimelineCursor {
+    fn encode(&self) -> String {
+        format!(
+            "{}|{}|{}",
+            self.timestamp.timestamp_nanos_opt().unwrap_or_default(),
+            self.source,
+            self.id
+        )
+    }
+
+    fn decode(cursor: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid timeline cursor: {}", cursor);
+        let mut parts = cursor.splitn(3, '|');
+        let nanos = parts
+            .next()
+            .and_then(|p| p.parse::<i64>().ok())
+            .ok_or_else(invalid)?;
+        let source = parts.next().ok_or_else(invalid)?;
+        let id = parts.next().ok_or_else(invalid)?;
+        Ok(Self {
+            timestamp: DateTime::from_timestamp_nanos(nanos),
+            source: source.to_string(),
+            id: id.to_string(),
+        })
+    }
+
+    /// true when `event` comes strictly after this position in page order
+    fn precedes(&self, event: &TimelineEvent) -> bool {
+        event
+            .timestamp
+            .cmp(&self.timestamp)
+            .reverse()
+            .then_with(|| event.source.as_str().cmp(&self.source))
+            .then_with(|| event.id.cmp(&self.id))
+            == Ordering::Greater
+    }
+}
+
+/// Merges events from several stores into one timeline
+#[derive(Default)]
+pub struct Timeline<'a> {
+    sources: Vec<&'a dyn TimelineSource>,
+}
+
+impl<'a> Timeline<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_source(mut self, source: &'a dyn TimelineSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// Expand the query subject into the fingerprints to include
+    pub fn resolve(&self, subject: &TimelineSubject) -> Result<ResolvedSubject, String> {
+        match subject {
+            TimelineSubject::Fingerprint(value) => Ok(ResolvedSubject {
+                fingerprints: BTreeSet::from([value.clone()]),
+                identity: None,
+            }),
+            TimelineSubject::Identity(identity) => {
+                let mut fingerprints = BTreeSet::new();
+                for source in &self.sources {
+                    fingerprints.extend(source.linked_fingerprints(identity)?);
+                }
+                if fingerprints.len() > MAX_LINKED_FINGERPRINTS {
+                    log::warn!(
+                        "[Timeline] Identity {} links {} fingerprints; keeping {}",
+                        identity,
+                        fingerprints.len(),
+                        MAX_LINKED_FINGERPRINTS
+                    );
+                    fingerprints = fingerprints
+                        .into_iter()
+                        .take(MAX_LINKED_FINGERPRINTS)
+                        .collect();
+                }
+                Ok(ResolvedSubject {
+                    fingerprints,
+                    identity: Some(identity.clone()),
+                })
+            }
+        }
+    }
+
+    /// Fetch one page of the timeline
+    pub fn query(&self, query: &TimelineQuery) -> Result<TimelinePage, String> {
+        let cursor = query
+            .cursor
+            .as_deref()
+            .map(TimelineCursor::decode)
+            .transpose()?;
+        let subject = self.resolve(&query.subject)?;
+        let page_size = query.page_size();
+
+        let mut events = Vec::new();
+        for source in &self.sources {
+            events.extend(Self::source_page(
+                *source,
+                &subject,
+                cursor.as_ref(),
+                page_size + 1,
+            )?);
+        }
+        events.sort_by(TimelineEvent::page_order);
+
+        let next_cursor = if events.len() > page_size {
+            events.truncate(page_size);
+            events.last().map(|e| e.position().encode())
+        } else {
+            None
+        };
+
+        Ok(TimelinePage {
+            fingerprints: subject.fingerprints.into_iter().collect(),
+            events,
+            next_cursor,
+        })
+    }
+
+    /// Up to `want` events of one source past `cursor`
+    ///
+    /// Sources only filter by timestamp, so events sharing the cursor's
+    /// timestamp are filtered here; the fetch grows until enough remain.
+    fn source_page(
+        source: &dyn TimelineSource,
+        subject: &ResolvedSubject,
+        cursor: Option<&TimelineCursor>,
+        want: usize,
+    ) -> Result<Vec<TimelineEvent>, String> {
+        let until = cursor.map(|c| c.timestamp);
+        let mut limit = want;
+        loop {
+            let fetched = source.events(subject, until, limit)?;
+            let exhausted = fetched.len() < limit;
+            let events: Vec<_> = fetched
+                .into_iter()
+                .filter(|e| cursor.is_none_or(|c| c.precedes(e)))
+                .collect();
+            if exhausted || events.len() >= want {
+                return Ok(events);
+            }
+            limit *= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticSource {
+        name: &'static str,
+        events: Vec<TimelineEvent>,
+    }
+
+    impl TimelineSource for StaticSource {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn linked_fingerprints(&self, identity: &str) -> Result<Vec<String>, String> {
+            Ok(self
+                .events
+                .iter()
+                .filter(|e| e.subject.as_deref() == Some(identity))
+                .filter_map(|e| e.fingerprint.as_ref().map(|f| f.fingerprint_id.clone()))
+                .collect())
+        }
+
+        fn events(
+            &self,
+            subject: &ResolvedSubject,
+            until: Option<DateTime<Utc>>,
+            limit: usize,
+        ) -> Result<Vec<TimelineEvent>, String> {
+            let mut events: Vec<_> = self
+                .events
+                .iter()
+                .filter(|e| until.is_none_or(|u| e.timestamp <= u))
+                .filter(|e| {
+                    e.fingerprint
+                        .as_ref()
+                        .is_some_and(|f| subject.fingerprints.contains(&f.fingerprint_id))
+                })
+                .cloned()
+                .collect();
+            events.sort_by(TimelineEvent::page_order);
+            events.truncate(limit);
+            Ok(events)
+        }
+    }
+
+    fn event(source: &str, id: &str, secs: i64, fp: &str, subject: &str) -> TimelineEvent {
+        TimelineEvent {
+            timestamp: DateTime::from_timestamp(secs, 0).unwrap(),
+            source: source.to_string(),
+            id: id.to_string(),
+            kind: TimelineEventKind::Observation,
+            fingerprint: Some(FingerprintRef::new("tls", fp)),
+            subject: Some(subject.to_string()),
+            summary: String::new(),
+            details: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_pages_merge_sources_without_gaps() {
+        // several events share a timestamp to exercise cursor tie-breaking
+        let a = StaticSource {
+            name: "a",
+            events: (0..7)
+                .map(|i| event("a", &i.to_string(), 100 + i / 3, "fp1", "10.0.0.1"))
+                .collect(),
+        };
+        let b = StaticSource {
+            name: "b",
+            events: (0..5)
+                .map(|i| event("b", &i.to_string(), 100 + i / 2, "fp2", "10.0.0.1"))
+                .collect(),
+        };
+        let timeline = Timeline::new().with_source(&a).with_source(&b);
+
+        let mut query = TimelineQuery::identity("10.0.0.1").with_limit(4);
+        let mut seen = Vec::new();
+        loop {
+            let page = timeline.query(&query).unwrap();
+            assert_eq!(page.fingerprints, vec!["fp1", "fp2"]);
+            seen.extend(page.events);
+            match page.next_cursor {
+                Some(cursor) => query = query.after(cursor),
+                None => break,
+            }
+        }
+
+        assert_eq!(seen.len(), 12);
+        assert!(seen
+            .windows(2)
+            .all(|w| w[0].page_order(&w[1]) == Ordering::Less));
+    }
+
+    #[test]
+    fn test_fingerprint_subject_and_bad_cursor() {
+        let a = StaticSource {
+            name: "a",
+            events: vec![
+                event("a", "1", 100, "fp1", "x"),
+                event("a", "2", 200, "fp2", "x"),
+            ],
+        };
+        let timeline = Timeline::new().with_source(&a);
+
+        let page = timeline.query(&TimelineQuery::fingerprint("fp2")).unwrap();
+        assert_eq!(page.events.len(), 1);
+        assert_eq!(page.events[0].id, "2");
+        assert!(page.next_cursor.is_none());
+
+        assert!(timeline
+            .query(&TimelineQuery::fingerprint("fp2").after("garbage"))
+            .is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! Soft-deleted learned fingerprints
+//!
+//! Removing a learned fingerprint should not erase the fact that it was once
+//! learned and then rejected. [`FingerprintDatabase::tombstone_fingerprint`]
+//! marks its candidate rows `deleted` and leaves a tombstone with a reason
+//! code. While the tombstone exists the self-learning analyzer only re-learns
+//! the fingerprint after [`Tombstone::evidence_multiplier`] times the usual
+//! number of observations, so a fingerprint removed as malicious does not
+//! quietly come back the next time it is seen.
+//!
+//! Tombstones are not kept forever: [`TombstoneRetention`] sets how long each
+//! reason is remembered, and
+//! [`FingerprintDatabase::purge_tombstones`] drops expired tombstones together
+//! with their deleted candidate rows.
+//!
+//! [`FingerprintDatabase::tombstone_fingerprint`]: crate::database::FingerprintDatabase::tombstone_fingerprint
+//! [`FingerprintDatabase::purge_tombstones`]: crate::database::FingerprintDatabase::purge_tombstones
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Why a fingerprint was removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TombstoneReason {
+    /// Learned from benign traffic that should not have been a candidate
+    FalsePositive,
+    /// Confirmed attack tooling
+    Malicious,
+    /// Same client as another, kept fingerprint
+    Duplicate,
+    /// Not seen for a long time
+    Expired,
+    /// Removed by an operator without a more specific reason
+    Manual,
+}
+
+impl TombstoneReason {
+    pub const ALL: [TombstoneReason; 5] = [
+        TombstoneReason::FalsePositive,
+        TombstoneReason::Malicious,
+        TombstoneReason::Duplicate,
+        TombstoneReason::Expired,
+        TombstoneReason::Manual,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TombstoneReason::FalsePositive => "false_positive",
+            TombstoneReason::Malicious => "malicious",
+            TombstoneReason::Duplicate => "duplicate",
+            TombstoneReason::Expired => "expired",
+            TombstoneReason::Manual => "manual",
+        }
+    }
+
+    /// Default factor on the learning threshold for re-learning
+    pub fn evidence_multiplier(&self) -> f64 {
+        match self {
+            TombstoneReason::Malicious => 4.0,
+            TombstoneReason::FalsePositive | TombstoneReason::Manual => 2.0,
+            TombstoneReason::Duplicate => 1.5,
+            TombstoneReason::Expired => 1.0,
+        }
+    }
+}
+
+impl fmt::Display for TombstoneReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TombstoneReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|reason| reason.as_str() == s)
+            .ok_or_else(|| format!("unknown tombstone reason: {}", s))
+    }
+}
+
+/// A soft-deleted fingerprint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub fingerprint_type: String,
+    pub fingerprint_id: String,
+    pub reason: TombstoneReason,
+    pub note: Option<String>,
+    /// Factor on the learning threshold before the fingerprint is re-learned
+    pub evidence_multiplier: f64,
+    /// Candidate status before deletion, restored by `restore_fingerprint`
+    pub previous_status: Option<String>,
+    /// Times the fingerprint was re-learned despite the tombstone
+    pub resurrections: u32,
+    /// Unix seconds
+    pub deleted_at: i64,
+}
+
+impl Tombstone {
+    /// Observations needed to re-learn the fingerprint under `learning_threshold`
+    pub fn required_observations(&self, learning_threshold: u64) -> u64 {
+        (learning_threshold as f64 * self.evidence_multiplier.max(1.0)).ceil() as u64
+    }
+}
+
+/// How long tombstones are kept, per reason
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TombstoneRetention {
+    /// Keep time per reason; `None` keeps that reason forever
+    pub keep: HashMap<TombstoneReason, Option<Duration>>,
+    /// Also hard-delete the `deleted` candidate rows of purged tombstones
+    pub purge_candidates: bool,
+}
+
+impl Default for TombstoneRetention {
+    fn default() -> Self {
+        const DAY: u64 = 24 * 3600;
+        let keep = HashMap::from([
+            (TombstoneReason::Malicious, None),
+            (
+                TombstoneReason::FalsePositive,
+                Some(Duration::from_secs(90 * DAY)),
+            ),
+            (
+                TombstoneReason::Manual,
+                Some(Duration::from_secs(180 * DAY)),
+            ),
+            (
+                TombstoneReason::Duplicate,
+                Some(Duration::from_secs(30 * DAY)),
+            ),
+            (
+                TombstoneReason::Expired,
+                Some(Duration::from_secs(30 * DAY)),
+            ),
+        ]);
+        Self {
+            keep,
+            purge_candidates: true,
+        }
+    }
+}
+
+impl TombstoneRetention {
+    /// Keep tombstones of `reason` for `keep` (`None` = forever)
+    pub fn with_keep(mut self, reason: TombstoneReason, keep: Option<Duration>) -> Self {
+        self.keep.insert(reason, keep);
+        self
+    }
+
+    /// Deletion time before which a tombstone of `reason` is purged at `now`
+    pub fn cutoff(&self, reason: TombstoneReason, now: i64) -> Option<i64> {
+        self.keep
+            .get(&reason)
+            .copied()
+            .flatten()
+            .map(|keep| now - keep.as_secs() as i64)
+    }
+}
+
+/// Outcome of a purge
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PurgeReport {
+    /// Tombstones removed, per reason
+    pub tombstones: HashMap<TombstoneReason, usize>,
+    /// Candidate rows hard-deleted
+    pub candidates: usize,
+}
+
+impl PurgeReport {
+    pub fn total_tombstones(&self) -> usize {
+        self.tombstones.values().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reasons_round_trip_and_scale_evidence() {
+        for reason in TombstoneReason::ALL {
+            assert_eq!(reason.as_str().parse::<TombstoneReason>(), Ok(reason));
+        }
+        assert!("gone".parse::<TombstoneReason>().is_err());
+
+        let tombstone = Tombstone {
+            fingerprint_type: "tls".into(),
+            fingerprint_id: "t13d".into(),
+            reason: TombstoneReason::Duplicate,
+            note: None,
+            evidence_multiplier: TombstoneReason::Duplicate.evidence_multiplier(),
+            previous_status: None,
+            resurrections: 0,
+            deleted_at: 0,
+        };
+        assert_eq!(tombstone.required_observations(5), 8);
+
+        let retention = TombstoneRetention::default();
+        assert_eq!(retention.cutoff(TombstoneReason::Malicious, 1_000), None);
+        assert_eq!(
+            retention.cutoff(TombstoneReason::Duplicate, 3_000_000),
+            Some(3_000_000 - 30 * 24 * 3600)
+        );
+    }
+}
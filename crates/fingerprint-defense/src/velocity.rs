@@ -0,0 +1,614 @@
+//! Impossible-travel and velocity checks
+//!
+//! Tracks the recent requests of each linked identity (account, API key owner,
+//! session subject) together with the geolocation of the client IP, and flags:
+//! - **Impossible travel**: two requests of one identity from distant places, closer
+//!   in time than any plausible journey between them; without coordinates, a
+//!   country change within [`VelocityConfig::country_hop_window`]
+//! - **Request bursts**: more requests in [`VelocityConfig::rate_window`] than
+//!   [`VelocityConfig::max_requests`]
+//! - **Fingerprint churn**: more distinct fingerprints in the rate window than
+//!   [`VelocityConfig::max_fingerprints`], typical of a pool of bots sharing one
+//!   account
+//!
+//! Geolocation comes from the caller's enrichment; an `IPInfo` record from
+//! `fingerprint-dns` maps to [`GeoLocation::new`] plus [`GeoLocation::parse_loc`]
+//! of its `loc` field. Each assessment carries [`VelocityFeatures`] for risk
+//! fusion, and findings are published as `defense.velocity` alerts, at most once
+//! per identity and kind every [`VelocityConfig::alert_cooldown`].
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use fingerprint_core::clock::{system_clock, SharedClock};
+use fingerprint_core::events::{self, AlertRaised};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Mean Earth radius
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Where a client IP is, from geo enrichment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeoLocation {
+    /// ISO 3166-1 alpha-2 country code
+    pub country: String,
+    /// Latitude and longitude in degrees, if known
+    pub coordinates: Option<(f64, f64)>,
+}
+
+impl GeoLocation {
+    pub fn new(country: impl Into<String>) -> Self {
+        Self {
+            country: country.into().to_ascii_uppercase(),
+            coordinates: None,
+        }
+    }
+
+    pub fn with_coordinates(mut self, latitude: f64, longitude: f64) -> Self {
+        self.coordinates = Some((latitude, longitude));
+        self
+    }
+
+    /// Set the coordinates from a `"lat,lon"` string (ipinfo.io `loc` format)
+    pub fn parse_loc(mut self, loc: &str) -> Self {
+        let mut parts = loc.split(',').map(|part| part.trim().parse::<f64>());
+        if let (Some(Ok(lat)), Some(Ok(lon)), None) = (parts.next(), parts.next(), parts.next()) {
+            if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) {
+                self.coordinates = Some((lat, lon));
+            }
+        }
+        self
+    }
+
+    /// Great-circle distance to `other`, if both have coordinates
+    pub fn distance_km(&self, other: &GeoLocation) -> Option<f64> {
+        let ((lat1, lon1), (lat2, lon2)) = (self.coordinates?, other.coordinates?);
+        let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+        let d_phi = (lat2 - lat1).to_radians();
+        let d_lambda = (lon2 - lon1).to_radians();
+        let a =
+            (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+        Some(2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin())
+    }
+}
+
+/// One request of a linked identity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VelocityObservation {
+    /// Linked identity the request belongs to
+    pub identity: String,
+    /// Fingerprint of the request (typically JA4)
+    pub fingerprint: String,
+    pub ip: IpAddr,
+    /// Geolocation of `ip`, if enrichment found one
+    pub geo: Option<GeoLocation>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Thresholds of a [`VelocityAnalyzer`]
+#[derive(Debug, Clone)]
+pub struct VelocityConfig {
+    /// Fastest plausible travel; commercial flights stay below ~1000 km/h
+    pub max_speed_kmh: f64,
+    /// Shorter distances are geolocation noise, never impossible travel
+    pub min_distance_km: f64,
+    /// Earlier locations are not compared against
+    pub travel_window: Duration,
+    /// Country change without coordinates within this is impossible travel
+    pub country_hop_window: Duration,
+    /// Window for request and fingerprint counts
+    pub rate_window: Duration,
+    /// Requests per identity allowed in `rate_window`
+    pub max_requests: u32,
+    /// Distinct fingerprints per identity allowed in `rate_window`
+    pub max_fingerprints: u32,
+    /// Identities tracked at most; the least recently seen are evicted first
+    pub max_identities: usize,
+    /// Minimum interval between alerts of one kind for one identity
+    pub alert_cooldown: Duration,
+}
+
+impl Default for VelocityConfig {
+    fn default() -> Self {
+        Self {
+            max_speed_kmh: 1000.0,
+            min_distance_km: 500.0,
+            travel_window: Duration::from_secs(24 * 3600),
+            country_hop_window: Duration::from_secs(15 * 60),
+            rate_window: Duration::from_secs(60),
+            max_requests: 120,
+            max_fingerprints: 3,
+            max_identities: 100_000,
+            alert_cooldown: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// Kind of [`VelocityFinding`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    ImpossibleTravel,
+    RequestBurst,
+    FingerprintChurn,
+}
+
+/// Something abnormal about an identity's recent requests
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VelocityFinding {
+    ImpossibleTravel {
+        from_country: String,
+        to_country: String,
+        /// `None` when either location lacked coordinates
+        distance_km: Option<f64>,
+        elapsed_secs: u64,
+        speed_kmh: Option<f64>,
+    },
+    RequestBurst {
+        requests: u32,
+        window_secs: u64,
+    },
+    FingerprintChurn {
+        fingerprints: u32,
+        window_secs: u64,
+    },
+}
+
+impl VelocityFinding {
+    pub fn kind(&self) -> FindingKind {
+        match self {
+            Self::ImpossibleTravel { .. } => FindingKind::ImpossibleTravel,
+            Self::RequestBurst { .. } => FindingKind::RequestBurst,
+            Self::FingerprintChurn { .. } => FindingKind::FingerprintChurn,
+        }
+    }
+
+    /// Contribution to the assessment score
+    fn score(&self) -> u32 {
+        match self {
+            Self::ImpossibleTravel { .. } => 70,
+            Self::RequestBurst { .. } => 40,
+            Self::FingerprintChurn { .. } => 30,
+        }
+    }
+}
+
+/// Numeric signals for risk fusion, in [`VelocityFeatures::NAMES`] order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VelocityFeatures {
+    /// Implied speed since the previous located request, 0 if unknown
+    pub speed_kmh: f64,
+    /// Distance from the previous located request, 0 if unknown
+    pub distance_km: f64,
+    /// Requests in the rate window, this one included
+    pub requests_in_window: u32,
+    /// `requests_in_window` divided by `max_requests`
+    pub request_rate_ratio: f64,
+    /// Distinct fingerprints in the rate window
+    pub distinct_fingerprints: u32,
+    /// Distinct countries in the travel window
+    pub distinct_countries: u32,
+    pub impossible_travel: bool,
+}
+
+impl VelocityFeatures {
+    pub const NAMES: [&'static str; 7] = [
+        "velocity.speed_kmh",
+        "velocity.distance_km",
+        "velocity.requests_in_window",
+        "velocity.request_rate_ratio",
+        "velocity.distinct_fingerprints",
+        "velocity.distinct_countries",
+        "velocity.impossible_travel",
+    ];
+
+    pub fn to_vec(&self) -> Vec<f32> {
+        vec![
+            self.speed_kmh as f32,
+            self.distance_km as f32,
+            self.requests_in_window as f32,
+            self.request_rate_ratio as f32,
+            self.distinct_fingerprints as f32,
+            self.distinct_countries as f32,
+            if self.impossible_travel { 1.0 } else { 0.0 },
+        ]
+    }
+}
+
+/// Result of [`VelocityAnalyzer::observe`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VelocityAssessment {
+    pub identity: String,
+    /// 0–100
+    pub score: u8,
+    pub findings: Vec<VelocityFinding>,
+    pub features: VelocityFeatures,
+}
+
+struct Request {
+    timestamp: DateTime<Utc>,
+    fingerprint: String,
+    geo: Option<GeoLocation>,
+}
+
+#[derive(Default)]
+struct IdentityState {
+    /// Oldest first
+    requests: VecDeque<Request>,
+    last_alert: HashMap<FindingKind, DateTime<Utc>>,
+}
+
+impl IdentityState {
+    fn last_seen(&self) -> Option<DateTime<Utc>> {
+        self.requests.back().map(|request| request.timestamp)
+    }
+}
+
+/// Per-identity travel and velocity tracking
+pub struct VelocityAnalyzer {
+    config: VelocityConfig,
+    identities: Mutex<HashMap<String, IdentityState>>,
+    clock: SharedClock,
+}
+
+impl VelocityAnalyzer {
+    pub fn new(config: VelocityConfig) -> Self {
+        Self {
+            config,
+            identities: Mutex::new(HashMap::new()),
+            clock: system_clock(),
+        }
+    }
+
+    /// Purge idle identities by `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Record `observation` and assess its identity's recent requests
+    pub fn observe(&self, observation: &VelocityObservation) -> VelocityAssessment {
+        let config = &self.config;
+        let now = observation.timestamp;
+        let rate_window = chrono_duration(config.rate_window);
+        let travel_window = chrono_duration(config.travel_window);
+        let mut identities = self.lock();
+        if !identities.contains_key(&observation.identity) {
+            evict_oldest(&mut identities, config.max_identities);
+        }
+        let state = identities.entry(observation.identity.clone()).or_default();
+
+        let mut findings = Vec::new();
+        let mut features = VelocityFeatures::default();
+
+        // previous located request, for travel
+        if let Some(geo) = &observation.geo {
+            let previous = state.requests.iter().rev().find_map(|request| {
+                let elapsed = now - request.timestamp;
+                let located = request.geo.as_ref()?;
+                (elapsed >= ChronoDuration::zero() && elapsed <= travel_window)
+                    .then_some((located, elapsed))
+            });
+            if let Some((from, elapsed)) = previous {
+                if let Some(finding) = travel_finding(config, from, geo, elapsed, &mut features) {
+                    findings.push(finding);
+                }
+            }
+        }
+
+        state.requests.push_back(Request {
+            timestamp: now,
+            fingerprint: observation.fingerprint.clone(),
+            geo: observation.geo.clone(),
+        });
+        let horizon = now - rate_window.max(travel_window);
+        while state
+            .requests
+            .front()
+            .is_some_and(|request| request.timestamp < horizon)
+        {
+            state.requests.pop_front();
+        }
+
+        let in_rate_window = || {
+            state
+                .requests
+                .iter()
+                .filter(move |request| now - request.timestamp <= rate_window)
+        };
+        features.requests_in_window = in_rate_window().count() as u32;
+        features.request_rate_ratio =
+            features.requests_in_window as f64 / config.max_requests.max(1) as f64;
+        features.distinct_fingerprints = in_rate_window()
+            .map(|request| request.fingerprint.as_str())
+            .collect::<HashSet<_>>()
+            .len() as u32;
+        features.distinct_countries = state
+            .requests
+            .iter()
+            .filter_map(|request| request.geo.as_ref())
+            .map(|geo| geo.country.as_str())
+            .collect::<HashSet<_>>()
+            .len() as u32;
+
+        if features.requests_in_window > config.max_requests {
+            findings.push(VelocityFinding::RequestBurst {
+                requests: features.requests_in_window,
+                window_secs: config.rate_window.as_secs(),
+            });
+        }
+        if features.distinct_fingerprints > config.max_fingerprints {
+            findings.push(VelocityFinding::FingerprintChurn {
+                fingerprints: features.distinct_fingerprints,
+                window_secs: config.rate_window.as_secs(),
+            });
+        }
+
+        // one alert per kind per cooldown
+        let cooldown = chrono_duration(config.alert_cooldown);
+        let to_alert: Vec<VelocityFinding> = findings
+            .iter()
+            .filter(|finding| {
+                let kind = finding.kind();
+                let due = state
+                    .last_alert
+                    .get(&kind)
+                    .is_none_or(|last| now - *last >= cooldown);
+                if due {
+                    state.last_alert.insert(kind, now);
+                }
+                due
+            })
+            .cloned()
+            .collect();
+        let score = findings
+            .iter()
+            .map(VelocityFinding::score)
+            .sum::<u32>()
+            .min(100) as u8;
+        let assessment = VelocityAssessment {
+            identity: observation.identity.clone(),
+            score,
+            findings,
+            features,
+        };
+        drop(identities);
+
+        for finding in &to_alert {
+            publish_alert(&assessment, finding);
+        }
+        assessment
+    }
+
+    /// Drop identities with no request within the travel and rate windows;
+    /// returns how many were dropped
+    pub fn purge_idle(&self) -> usize {
+        let now: DateTime<Utc> = self.clock.now().into();
+        let horizon = now - chrono_duration(self.config.rate_window.max(self.config.travel_window));
+        let mut identities = self.lock();
+        let before = identities.len();
+        identities.retain(|_, state| state.last_seen().is_some_and(|last| last >= horizon));
+        before - identities.len()
+    }
+
+    /// Identities currently tracked
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, IdentityState>> {
+        self.identities.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for VelocityAnalyzer {
+    fn default() -> Self {
+        Self::new(VelocityConfig::default())
+    }
+}
+
+fn chrono_duration(duration: Duration) -> ChronoDuration {
+    ChronoDuration::from_std(duration).unwrap_or(ChronoDuration::MAX)
+}
+
+fn evict_oldest(identities: &mut HashMap<String, IdentityState>, max_identities: usize) {
+    if identities.len() < max_identities.max(1) {
+        return;
+    }
+    if let Some(oldest) = identities
+        .iter()
+        .min_by_key(|(_, state)| state.last_seen())
+        .map(|(identity, _)| identity.clone())
+    {
+        identities.remove(&oldest);
+    }
+}
+
+/// Impossible-travel finding between two located requests, filling the travel features
+fn travel_finding(
+    config: &VelocityConfig,
+    from: &GeoLocation,
+    to: &GeoLocation,
+    elapsed: ChronoDuration,
+    features: &mut VelocityFeatures,
+) -> Option<VelocityFinding> {
+    let elapsed_secs = elapsed.num_seconds().max(0) as u64;
+    let distance_km = from.distance_km(to);
+    // a shared second counts as one, so simultaneous requests get a finite speed
+    let speed_kmh = distance_km.map(|km| km / (elapsed_secs.max(1) as f64 / 3600.0));
+    features.distance_km = distance_km.unwrap_or(0.0);
+    features.speed_kmh = speed_kmh.unwrap_or(0.0);
+
+    let impossible = match (distance_km, speed_kmh) {
+        (Some(km), Some(speed)) => km >= config.min_distance_km && speed > config.max_speed_kmh,
+        _ => from.country != to.country && elapsed_secs < config.country_hop_window.as_secs(),
+    };
+    features.impossible_travel = impossible;
+    impossible.then(|| VelocityFinding::ImpossibleTravel {
+        from_country: from.country.clone(),
+        to_country: to.country.clone(),
+        distance_km,
+        elapsed_secs,
+        speed_kmh,
+    })
+}
+
+fn publish_alert(assessment: &VelocityAssessment, finding: &VelocityFinding) {
+    if !events::global().has_subscribers::<AlertRaised>() {
+        return;
+    }
+    let (severity, rule, message) = match finding {
+        VelocityFinding::ImpossibleTravel {
+            from_country,
+            to_country,
+            elapsed_secs,
+            ..
+        } => (
+            "critical",
+            "impossible_travel",
+            format!(
+                "Identity {} seen in {} then {} within {}s",
+                assessment.identity, from_country, to_country, elapsed_secs
+            ),
+        ),
+        VelocityFinding::RequestBurst {
+            requests,
+            window_secs,
+        } => (
+            "warning",
+            "request_velocity",
+            format!(
+                "Identity {} sent {} requests in {}s",
+                assessment.identity, requests, window_secs
+            ),
+        ),
+        VelocityFinding::FingerprintChurn {
+            fingerprints,
+            window_secs,
+        } => (
+            "warning",
+            "fingerprint_churn",
+            format!(
+                "Identity {} used {} fingerprints in {}s",
+                assessment.identity, fingerprints, window_secs
+            ),
+        ),
+    };
+    let mut alert = AlertRaised::new("defense.velocity", severity, rule, message);
+    alert.metadata.insert(
+        "identity".to_string(),
+        serde_json::json!(assessment.identity),
+    );
+    alert
+        .metadata
+        .insert("score".to_string(), serde_json::json!(assessment.score));
+    alert
+        .metadata
+        .insert("finding".to_string(), serde_json::json!(finding));
+    events::publish_alert(alert);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    fn request(
+        identity: &str,
+        fingerprint: &str,
+        geo: Option<GeoLocation>,
+        secs: i64,
+    ) -> VelocityObservation {
+        VelocityObservation {
+            identity: identity.to_string(),
+            fingerprint: fingerprint.to_string(),
+            ip: "198.51.100.7".parse().unwrap(),
+            geo,
+            timestamp: at(secs),
+        }
+    }
+
+    #[test]
+    fn test_impossible_travel() {
+        let paris = GeoLocation::new("fr").parse_loc("48.8566,2.3522");
+        let tokyo = GeoLocation::new("JP").parse_loc("35.6762,139.6503");
+        let lyon = GeoLocation::new("FR").parse_loc("45.7640,4.8357");
+        let distance = paris.distance_km(&tokyo).unwrap();
+        assert!((9700.0..9750.0).contains(&distance), "{}", distance);
+
+        let analyzer = VelocityAnalyzer::default();
+        assert_eq!(
+            analyzer
+                .observe(&request("alice", "ja4-a", Some(paris.clone()), 0))
+                .score,
+            0
+        );
+
+        // a train ride is fine
+        let assessment = analyzer.observe(&request("alice", "ja4-a", Some(lyon), 2 * 3600));
+        assert!(assessment.findings.is_empty());
+        assert!(assessment.features.speed_kmh < 300.0);
+
+        // Tokyo ten minutes later is not
+        let assessment = analyzer.observe(&request("alice", "ja4-a", Some(tokyo), 2 * 3600 + 600));
+        assert!(assessment.features.impossible_travel);
+        assert!(assessment.score >= 70);
+        assert!(matches!(
+            &assessment.findings[0],
+            VelocityFinding::ImpossibleTravel { from_country, to_country, elapsed_secs: 600, .. }
+                if from_country == "FR" && to_country == "JP"
+        ));
+
+        // country-only enrichment: a hop within the window still counts
+        let analyzer = VelocityAnalyzer::default();
+        analyzer.observe(&request("bob", "ja4-b", Some(GeoLocation::new("US")), 0));
+        let assessment =
+            analyzer.observe(&request("bob", "ja4-b", Some(GeoLocation::new("RU")), 120));
+        assert_eq!(assessment.findings[0].kind(), FindingKind::ImpossibleTravel);
+        assert_eq!(assessment.features.distinct_countries, 2);
+        let assessment =
+            analyzer.observe(&request("bob", "ja4-b", Some(GeoLocation::new("US")), 3600));
+        assert!(assessment.findings.is_empty());
+    }
+
+    #[test]
+    fn test_request_velocity_and_fingerprint_churn() {
+        let analyzer = VelocityAnalyzer::new(VelocityConfig {
+            max_requests: 10,
+            max_fingerprints: 2,
+            ..VelocityConfig::default()
+        });
+        for i in 0..10 {
+            let assessment = analyzer.observe(&request("carol", "ja4-a", None, i));
+            assert!(assessment.findings.is_empty());
+        }
+        let assessment = analyzer.observe(&request("carol", "ja4-a", None, 10));
+        assert_eq!(assessment.features.requests_in_window, 11);
+        assert_eq!(assessment.findings[0].kind(), FindingKind::RequestBurst);
+
+        // the window slides
+        let assessment = analyzer.observe(&request("carol", "ja4-a", None, 200));
+        assert_eq!(assessment.features.requests_in_window, 1);
+        assert!(assessment.findings.is_empty());
+
+        analyzer.observe(&request("carol", "ja4-b", None, 201));
+        let assessment = analyzer.observe(&request("carol", "ja4-c", None, 202));
+        assert_eq!(assessment.features.distinct_fingerprints, 3);
+        assert_eq!(assessment.findings[0].kind(), FindingKind::FingerprintChurn);
+        assert_eq!(
+            assessment.features.to_vec().len(),
+            VelocityFeatures::NAMES.len()
+        );
+        assert_eq!(analyzer.len(), 1);
+    }
+}
@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use fingerprint_defense::passive::consistency::ConsistencyAnalyzer;
+    use fingerprint_fixtures::Fixtures;
+
+    const TCP_OS_MISMATCH: &str = "consistency.tcp_os_mismatch";
+
+    #[test]
+    fn test_consistent_flows_match_user_agent() {
+        let analyzer = ConsistencyAnalyzer::new();
+        let mut fixtures = Fixtures::new(2024).starting_at(Utc::now());
+
+        for flow in fixtures.flows(50) {
+            let report = analyzer.analyze_flow(&flow);
+            assert_eq!(report.discrepancies.len(), report.discrepancy_codes.len());
+            assert!(
+                !report
+                    .discrepancy_codes
+                    .iter()
+                    .any(|c| c == TCP_OS_MISMATCH),
+                "{:?}",
+                report.discrepancies
+            );
+        }
+    }
+
+    #[test]
+    fn test_spoofed_flows_are_flagged() {
+        let analyzer = ConsistencyAnalyzer::new();
+        let mut fixtures = Fixtures::new(2024).starting_at(Utc::now());
+
+        for _ in 0..50 {
+            let report = analyzer.analyze_flow(&fixtures.spoofed_flow());
+            assert!(report
+                .discrepancy_codes
+                .iter()
+                .any(|c| c == TCP_OS_MISMATCH));
+            assert!(report.score < 100);
+        }
+    }
+}
@@ -78,4 +78,99 @@ mod tests {
         assert_eq!(observation.observation_count, 5);
         assert_eq!(observation.stability_score, 0.75);
     }
+
+    #[test]
+    fn test_weak_label_prior_from_enforcement() {
+        use fingerprint_defense::labels::{
+            EnforcementEvent, EnforcementOutcome, FingerprintRef, LabelPropagator,
+        };
+        use fingerprint_defense::{PassiveAnalysisResult, TlsFingerprint};
+
+        let ja4 = "t13d1516h2_8daaf6152771_02713d6af862";
+        let propagator =
+            LabelPropagator::spawn(FingerprintDatabase::new_in_memory().expect("open db"))
+                .expect("spawn propagator");
+        let sink = propagator.sink();
+        for _ in 0..3 {
+            sink.record(EnforcementEvent::new(
+                EnforcementOutcome::AdmissionBlocked,
+                vec![FingerprintRef::new("tls", ja4)],
+            ));
+        }
+        drop(sink);
+        let learner = SelfLearningAnalyzer::new(Arc::new(propagator.shutdown()));
+
+        let mut result = PassiveAnalysisResult::default();
+        assert_eq!(learner.weak_label_prior(&result), 0.0);
+
+        result.tls = Some(TlsFingerprint {
+            ja4: Some(ja4.to_string()),
+            ..Default::default()
+        });
+        assert!(learner.weak_label_prior(&result) > 0.7);
+    }
+
+    #[test]
+    fn test_fleet_wide_observations_reach_threshold() {
+        use fingerprint_defense::{FleetStore, KvStore, MemoryKvStore};
+        use fingerprint_defense::{PassiveAnalysisResult, TlsFingerprint};
+
+        let kv: Arc<dyn KvStore> = Arc::new(MemoryKvStore::new());
+        let result = PassiveAnalysisResult {
+            tls: Some(TlsFingerprint {
+                ja4: Some("t13d1516h2_8daaf6152771_02713d6af862".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mut learners = Vec::new();
+        for node in ["gw-a", "gw-b"] {
+            let db = Arc::new(FingerprintDatabase::new_in_memory().expect("open db"));
+            let mut learner = SelfLearningAnalyzer::new(db.clone());
+            learner.set_fleet_store(Arc::new(FleetStore::new(kv.clone(), node)));
+            // not enough to learn on either gateway alone
+            for _ in 0..6 {
+                learner.process_result(&result);
+            }
+            learners.push((learner, db));
+        }
+
+        assert_eq!(learners[0].0.publish_observations(), 0);
+        assert_eq!(learners[1].0.publish_observations(), 1);
+        let candidates = learners[1].1.get_pending_candidates(None).unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].observation_count, 12);
+        assert!(learners[0]
+            .1
+            .get_pending_candidates(None)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_observations_expire_with_clock() {
+        use fingerprint_core::clock::FrozenClock;
+        use fingerprint_defense::{PassiveAnalysisResult, TlsFingerprint};
+
+        let db = Arc::new(FingerprintDatabase::new_in_memory().expect("open db"));
+        let clock = Arc::new(FrozenClock::at_unix(1_700_000_000));
+        let mut learner = SelfLearningAnalyzer::new(db);
+        learner.set_clock(clock.clone());
+        learner.set_stability_window(Duration::from_secs(3600));
+
+        learner.process_result(&PassiveAnalysisResult {
+            tls: Some(TlsFingerprint {
+                ja4: Some("t13d1516h2_8daaf6152771_02713d6af862".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        learner.cleanup_expired_observations();
+        assert_eq!(learner.get_observation_stats().total_observations, 1);
+
+        clock.advance(Duration::from_secs(3601));
+        learner.cleanup_expired_observations();
+        assert_eq!(learner.get_observation_stats().total_observations, 0);
+    }
 }
@@ -3,6 +3,7 @@
 //! Provides memory cache functionality to reduce redundant DNS lookups and improve performance
 
 use crate::dns::types::{DNSError, DomainIPs};
+use fingerprint_core::clock::{system_clock, SharedClock};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
@@ -19,9 +20,9 @@ struct CacheEntry {
 }
 
 impl CacheEntry {
-    /// Check if cache entry is expired
-    fn is_expired(&self) -> bool {
-        self.cached_at.elapsed() > self.ttl
+    /// Check if cache entry is expired at `now`
+    fn is_expired(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.cached_at) > self.ttl
     }
 }
 
@@ -34,6 +35,8 @@ pub struct DNSCache {
     cache: Arc<RwLock<HashMap<String, CacheEntry>>>,
     /// Default TTL
     default_ttl: Duration,
+    /// Time source for expiry
+    clock: SharedClock,
 }
 
 impl DNSCache {
@@ -45,9 +48,16 @@ impl DNSCache {
         Self {
             cache: Arc::new(RwLock::new(HashMap::new())),
             default_ttl,
+            clock: system_clock(),
         }
     }
 
+    /// Expire entries by `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Get domain IP information from cache
     ///
     /// # Arguments
@@ -60,7 +70,7 @@ impl DNSCache {
         let cache = self.cache.read().ok()?;
 
         if let Some(entry) = cache.get(domain) {
-            if !entry.is_expired() {
+            if !entry.is_expired(self.clock.instant()) {
                 return Some(entry.ips.clone());
             }
         }
@@ -86,7 +96,7 @@ impl DNSCache {
         if let Ok(mut cache) = self.cache.write() {
             let entry = CacheEntry {
                 ips,
-                cached_at: Instant::now(),
+                cached_at: self.clock.instant(),
                 ttl,
             };
             cache.insert(domain.to_string(), entry);
@@ -110,7 +120,8 @@ impl DNSCache {
     pub fn cleanup_expired(&self) -> usize {
         if let Ok(mut cache) = self.cache.write() {
             let before_count = cache.len();
-            cache.retain(|_, entry| !entry.is_expired());
+            let now = self.clock.instant();
+            cache.retain(|_, entry| !entry.is_expired(now));
             let after_count = cache.len();
             before_count - after_count
         } else {
@@ -132,7 +143,8 @@ impl DNSCache {
     pub fn stats(&self) -> (usize, usize) {
         if let Ok(cache) = self.cache.read() {
             let total = cache.len();
-            let expired = cache.values().filter(|e| e.is_expired()).count();
+            let now = self.clock.instant();
+            let expired = cache.values().filter(|e| e.is_expired(now)).count();
             (total, expired)
         } else {
             (0, 0)
@@ -210,6 +222,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fingerprint_core::clock::FrozenClock;
 
     #[test]
     fn test_cache_basic() {
@@ -238,7 +251,8 @@ mod tests {
 
     #[test]
     fn test_cache_expiration() {
-        let cache = DNSCache::new(Duration::from_millis(100));
+        let clock = Arc::new(FrozenClock::starting_now());
+        let cache = DNSCache::new(Duration::from_millis(100)).with_clock(clock.clone());
         let domain = "example.com";
 
         let mut ips = DomainIPs::new();
@@ -250,7 +264,7 @@ mod tests {
         assert!(cache.get(domain).is_some());
 
         // Wait for expiration
-        std::thread::sleep(Duration::from_millis(150));
+        clock.advance(Duration::from_millis(150));
 
         // Access: should miss (expired)
         assert!(cache.get(domain).is_none());
@@ -258,7 +272,8 @@ mod tests {
 
     #[test]
     fn test_cache_cleanup() {
-        let cache = DNSCache::new(Duration::from_millis(100));
+        let clock = Arc::new(FrozenClock::starting_now());
+        let cache = DNSCache::new(Duration::from_millis(100)).with_clock(clock.clone());
 
         // Add two domains
         let mut ips1 = DomainIPs::new();
@@ -277,7 +292,7 @@ mod tests {
         assert_eq!(expired, 0);
 
         // Wait for expiration
-        std::thread::sleep(Duration::from_millis(150));
+        clock.advance(Duration::from_millis(150));
 
         // Statistics before cleanup
         let (total, expired) = cache.stats();
@@ -99,20 +99,17 @@ impl ServerCollector {
         test_timeout: Option<Duration>,
         max_concurrency: Option<usize>,
     ) -> Result<(usize, usize), DNSError> {
-        use std::path::Path;
-
-        const DEFAULT_SERVER_FILE: &str = "dnsservernames.json";
-
         let test_domain = test_domain.unwrap_or("google.com");
         let test_timeout = test_timeout.unwrap_or(Duration::from_secs(3));
         let max_concurrency = max_concurrency.unwrap_or(100);
 
         // from fileloadallserver
-        let file_path = Path::new(DEFAULT_SERVER_FILE);
+        let default_file = ServerPool::default_file();
+        let file_path = default_file.as_path();
         if !file_path.exists() {
             return Err(DNSError::Config(format!(
                 "file {} 不 exists",
-                DEFAULT_SERVER_FILE
+                file_path.display()
             )));
         }
 
@@ -160,7 +157,7 @@ impl ServerCollector {
 
         // saveValidatebackserver (先backuporiginalfile)
         if valid_count > 0 {
-            let backup_path = format!("{}.backup", DEFAULT_SERVER_FILE);
+            let backup_path = format!("{}.backup", file_path.display());
             if let Err(e) = std::fs::copy(file_path, &backup_path) {
                 eprintln!("Warning: unable toCreatebackupfile: {}", e);
             } else {
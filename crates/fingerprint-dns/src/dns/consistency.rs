@@ -0,0 +1,436 @@
+//! DNS consistency module
+//!
+//! A browser's DNS traffic is part of its fingerprint. Chrome on default
+//! settings resolves through the OS (upgrading to DoH when the OS resolver is
+//! a known DoH provider), Firefox may use Cloudflare TRR, and Apple/Android
+//! apps go through the platform resolver. Automation stacks often skip all of
+//! that and send plain UDP queries to 8.8.8.8.
+//!
+//! [`DnsBehavior`] describes the DNS path a profile would take and configures
+//! an HTTP client to follow it; [`score_dns_consistency`] does the reverse on
+//! the detection side, scoring an observed DNS pattern against the claimed
+//! browser.
+//!
+//! ```
+//! use fingerprint_dns::{score_dns_consistency, DnsBehavior, DnsObservation, DnsTransport};
+//! use fingerprint_profiles::profiles::chrome_133;
+//!
+//! let profile = chrome_133();
+//! assert!(DnsBehavior::for_profile(&profile).resolver.is_system());
+//!
+//! let observed = DnsObservation::new("8.8.8.8".parse().unwrap(), DnsTransport::Udp);
+//! let report = score_dns_consistency(&profile, &observed);
+//! assert!(report.score < 100);
+//! ```
+
+use fingerprint_core::ja4::ConsistencyReport;
+use fingerprint_http::{DNSHelper, HttpClientConfig};
+use fingerprint_profiles::BrowserProfile;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::dns::cache::DNSCache;
+
+/// Public resolver that also serves DNS over HTTPS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DoHProvider {
+    Google,
+    Cloudflare,
+    Quad9,
+}
+
+impl DoHProvider {
+    /// All known providers
+    pub const ALL: [DoHProvider; 3] = [
+        DoHProvider::Google,
+        DoHProvider::Cloudflare,
+        DoHProvider::Quad9,
+    ];
+
+    /// DoH endpoint template
+    pub fn url(self) -> &'static str {
+        match self {
+            DoHProvider::Google => "https://dns.google/dns-query",
+            DoHProvider::Cloudflare => "https://mozilla.cloudflare-dns.com/dns-query",
+            DoHProvider::Quad9 => "https://dns.quad9.net/dns-query",
+        }
+    }
+
+    /// Plain-DNS addresses of the provider
+    pub fn addresses(self) -> &'static [&'static str] {
+        match self {
+            DoHProvider::Google => &["8.8.8.8", "8.8.4.4", "2001:4860:4860::8888"],
+            DoHProvider::Cloudflare => &["1.1.1.1", "1.0.0.1", "2606:4700:4700::1111"],
+            DoHProvider::Quad9 => &["9.9.9.9", "149.112.112.112", "2620:fe::fe"],
+        }
+    }
+
+    /// Provider operating `ip`, if any
+    pub fn from_address(ip: IpAddr) -> Option<Self> {
+        let ip = ip.to_string();
+        Self::ALL
+            .into_iter()
+            .find(|provider| provider.addresses().contains(&ip.as_str()))
+    }
+}
+
+impl fmt::Display for DoHProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DoHProvider::Google => write!(f, "Google"),
+            DoHProvider::Cloudflare => write!(f, "Cloudflare"),
+            DoHProvider::Quad9 => write!(f, "Quad9"),
+        }
+    }
+}
+
+/// Where a client sends its DNS queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResolverChoice {
+    /// Platform resolver (getaddrinfo, mDNSResponder, netd)
+    System,
+    /// Browser-level DNS over HTTPS
+    DoH(DoHProvider),
+}
+
+impl ResolverChoice {
+    /// True for the platform resolver
+    pub fn is_system(&self) -> bool {
+        matches!(self, ResolverChoice::System)
+    }
+}
+
+impl fmt::Display for ResolverChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolverChoice::System => write!(f, "system resolver"),
+            ResolverChoice::DoH(provider) => write!(f, "{} DoH", provider),
+        }
+    }
+}
+
+/// Client-side host cache behavior
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachePolicy {
+    /// Longest time an answer is served from cache
+    pub max_ttl: Duration,
+    /// Whether shorter record TTLs are honored
+    pub honors_record_ttl: bool,
+}
+
+impl CachePolicy {
+    /// Time a caching client waits before asking again for a record with `record_ttl`
+    pub fn expected_requery(&self, record_ttl: Duration) -> Duration {
+        if self.honors_record_ttl {
+            record_ttl.min(self.max_ttl)
+        } else {
+            self.max_ttl
+        }
+    }
+}
+
+/// DNS path a browser profile takes by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsBehavior {
+    /// Default resolver
+    pub resolver: ResolverChoice,
+    /// Chromium upgrades a system resolver on a DoH provider's address to DoH
+    pub auto_doh_upgrade: bool,
+    /// Host cache
+    pub cache: CachePolicy,
+    /// Whether HTTPS (type 65) records are queried alongside A/AAAA
+    pub queries_https_rr: bool,
+}
+
+impl DnsBehavior {
+    /// Behavior of `profile` on default settings
+    pub fn for_profile(profile: &BrowserProfile) -> Self {
+        let metadata = &profile.metadata;
+        let version = metadata.browser_version;
+        let apple = matches!(metadata.platform.as_str(), "iOS" | "iPadOS");
+
+        // Chromium's host cache keeps system answers for at most a minute;
+        // Apple and Android caches honor the record TTL, capped here at five
+        // minutes because DNSHelper and DNSCache never see record TTLs
+        let chromium_cache = CachePolicy {
+            max_ttl: Duration::from_secs(60),
+            honors_record_ttl: true,
+        };
+        let platform_cache = CachePolicy {
+            max_ttl: Duration::from_secs(300),
+            honors_record_ttl: true,
+        };
+
+        match metadata.browser_name.to_lowercase().as_str() {
+            // Every browser on iOS is WebKit over the system resolver
+            _ if apple => Self {
+                resolver: ResolverChoice::System,
+                auto_doh_upgrade: false,
+                cache: platform_cache,
+                queries_https_rr: true,
+            },
+            "chrome" | "edge" | "opera" => Self {
+                resolver: ResolverChoice::System,
+                auto_doh_upgrade: true,
+                cache: chromium_cache,
+                queries_https_rr: version >= 103,
+            },
+            "firefox" => Self {
+                resolver: ResolverChoice::DoH(DoHProvider::Cloudflare),
+                auto_doh_upgrade: false,
+                // network.dnsCacheExpiration
+                cache: CachePolicy {
+                    max_ttl: Duration::from_secs(60),
+                    honors_record_ttl: true,
+                },
+                queries_https_rr: version >= 92,
+            },
+            // mDNSResponder has queried HTTPS records since macOS 11
+            "safari" | "nsurlsession" => Self {
+                resolver: ResolverChoice::System,
+                auto_doh_upgrade: false,
+                cache: platform_cache,
+                queries_https_rr: true,
+            },
+            // Conscrypt/OkHttp and anything unrecognised: plain platform lookups
+            _ => Self {
+                resolver: ResolverChoice::System,
+                auto_doh_upgrade: false,
+                cache: platform_cache,
+                queries_https_rr: false,
+            },
+        }
+    }
+
+    /// DNS cache sized to the profile's host cache
+    pub fn dns_cache(&self) -> DNSCache {
+        DNSCache::new(self.cache.max_ttl)
+    }
+
+    /// System-resolver helper sized to the profile's host cache
+    pub fn dns_helper(&self) -> DNSHelper {
+        DNSHelper::new(self.cache.max_ttl)
+    }
+
+    /// Route `config`'s lookups the way the profile would
+    ///
+    /// Returns `false` when the profile expects DoH: lookups then fall back to
+    /// the system resolver, as Firefox's TRR-first mode does on failure.
+    pub fn configure(&self, config: &mut HttpClientConfig) -> bool {
+        config.dns_helper = Some(Arc::new(self.dns_helper()));
+        self.resolver.is_system()
+    }
+}
+
+/// Transport an observed DNS query used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DnsTransport {
+    Udp,
+    Tcp,
+    DoT,
+    DoH,
+}
+
+impl DnsTransport {
+    /// True for unencrypted DNS
+    pub fn is_plain(self) -> bool {
+        matches!(self, DnsTransport::Udp | DnsTransport::Tcp)
+    }
+}
+
+impl fmt::Display for DnsTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DnsTransport::Udp => write!(f, "UDP"),
+            DnsTransport::Tcp => write!(f, "TCP"),
+            DnsTransport::DoT => write!(f, "DoT"),
+            DnsTransport::DoH => write!(f, "DoH"),
+        }
+    }
+}
+
+/// DNS pattern observed for one client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsObservation {
+    /// Resolver the client sent its queries to
+    pub resolver: IpAddr,
+    /// Query transport
+    pub transport: DnsTransport,
+    /// Whether an HTTPS (type 65) query accompanied A/AAAA
+    pub queried_https_rr: bool,
+    /// Gap between two lookups of the same name, with that record's TTL
+    pub requery: Option<(Duration, Duration)>,
+}
+
+impl DnsObservation {
+    /// Observation with no HTTPS query and no repeated lookup
+    pub fn new(resolver: IpAddr, transport: DnsTransport) -> Self {
+        Self {
+            resolver,
+            transport,
+            queried_https_rr: false,
+            requery: None,
+        }
+    }
+
+    /// Set whether an HTTPS (type 65) query was seen
+    pub fn with_https_rr(mut self, queried: bool) -> Self {
+        self.queried_https_rr = queried;
+        self
+    }
+
+    /// Record a repeated lookup `interval` after the first, for a record with `record_ttl`
+    pub fn with_requery(mut self, interval: Duration, record_ttl: Duration) -> Self {
+        self.requery = Some((interval, record_ttl));
+        self
+    }
+}
+
+/// Score how well `observed` matches the DNS path of `profile`
+pub fn score_dns_consistency(
+    profile: &BrowserProfile,
+    observed: &DnsObservation,
+) -> ConsistencyReport {
+    let expected = DnsBehavior::for_profile(profile);
+    let browser = &profile.metadata.browser_name;
+    let mut report = ConsistencyReport::new();
+    let provider = DoHProvider::from_address(observed.resolver);
+
+    match (expected.resolver, provider) {
+        // Chromium would have upgraded this resolver to DoH
+        (ResolverChoice::System, Some(_))
+            if expected.auto_doh_upgrade && observed.transport.is_plain() =>
+        {
+            report.add_coded_discrepancy(
+                "consistency.dns_plain_public_resolver",
+                &[("resolver", &observed.resolver), ("browser", browser)],
+                30,
+            );
+        }
+        (ResolverChoice::DoH(_), _) if observed.transport != DnsTransport::DoH => {
+            // TRR is region-dependent, so a native lookup is only a weak signal
+            let via = format!("{} via {}", observed.resolver, observed.transport);
+            report.add_coded_discrepancy(
+                "consistency.dns_resolver_mismatch",
+                &[
+                    ("observed", &via),
+                    ("browser", browser),
+                    ("expected", &expected.resolver),
+                ],
+                10,
+            );
+        }
+        (ResolverChoice::System, _)
+            if observed.transport == DnsTransport::DoH && !expected.auto_doh_upgrade =>
+        {
+            let via = format!("{} via {}", observed.resolver, observed.transport);
+            report.add_coded_discrepancy(
+                "consistency.dns_resolver_mismatch",
+                &[
+                    ("observed", &via),
+                    ("browser", browser),
+                    ("expected", &expected.resolver),
+                ],
+                15,
+            );
+        }
+        _ => {}
+    }
+
+    match (expected.queries_https_rr, observed.queried_https_rr) {
+        (true, false) => {
+            report.add_coded_discrepancy(
+                "consistency.dns_https_rr_missing",
+                &[("browser", browser)],
+                15,
+            );
+        }
+        (false, true) => {
+            report.add_coded_discrepancy(
+                "consistency.dns_https_rr_unexpected",
+                &[("browser", browser)],
+                15,
+            );
+        }
+        _ => {}
+    }
+
+    if let Some((interval, record_ttl)) = observed.requery {
+        // Allow half the expected gap for clock jitter and cache eviction
+        if interval < expected.cache.expected_requery(record_ttl) / 2 {
+            report.add_coded_discrepancy(
+                "consistency.dns_cache_mismatch",
+                &[
+                    ("seconds", &interval.as_secs()),
+                    ("ttl", &record_ttl.as_secs()),
+                    ("browser", browser),
+                ],
+                20,
+            );
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fingerprint_profiles::profiles::{chrome_133, conscrypt_mobile_34, firefox_133};
+
+    #[test]
+    fn test_behavior_for_profiles() {
+        let chrome = DnsBehavior::for_profile(&chrome_133());
+        assert!(chrome.resolver.is_system());
+        assert!(chrome.auto_doh_upgrade);
+        assert!(chrome.queries_https_rr);
+        assert_eq!(chrome.cache.max_ttl, Duration::from_secs(60));
+
+        let firefox = DnsBehavior::for_profile(&firefox_133());
+        assert_eq!(
+            firefox.resolver,
+            ResolverChoice::DoH(DoHProvider::Cloudflare)
+        );
+
+        let android = DnsBehavior::for_profile(&conscrypt_mobile_34());
+        assert!(android.resolver.is_system());
+        assert!(!android.queries_https_rr);
+
+        let mut config = HttpClientConfig::default();
+        assert!(chrome.configure(&mut config));
+        assert!(config.dns_helper.is_some());
+        assert!(!firefox.configure(&mut config));
+    }
+
+    #[test]
+    fn test_scores_automation_dns_pattern() {
+        let chrome = chrome_133();
+        let google = "8.8.8.8".parse().unwrap();
+
+        let browser_like = DnsObservation::new("192.168.1.1".parse().unwrap(), DnsTransport::Udp)
+            .with_https_rr(true)
+            .with_requery(Duration::from_secs(75), Duration::from_secs(300));
+        let report = score_dns_consistency(&chrome, &browser_like);
+        assert_eq!(report.score, 100);
+
+        let scripted = DnsObservation::new(google, DnsTransport::Udp)
+            .with_requery(Duration::from_secs(1), Duration::from_secs(300));
+        let report = score_dns_consistency(&chrome, &scripted);
+        assert!(report.bot_detected);
+        assert_eq!(
+            report.discrepancy_codes,
+            [
+                "consistency.dns_plain_public_resolver",
+                "consistency.dns_https_rr_missing",
+                "consistency.dns_cache_mismatch",
+            ]
+        );
+
+        // The same resolver over DoH is what Chrome's auto-upgrade produces
+        let upgraded = DnsObservation::new(google, DnsTransport::DoH).with_https_rr(true);
+        assert_eq!(score_dns_consistency(&chrome, &upgraded).score, 100);
+    }
+}
@@ -16,6 +16,7 @@
 mod cache;
 mod collector;
 mod config;
+mod consistency;
 mod ipinfo;
 mod resolver;
 mod serverpool;
@@ -26,6 +27,10 @@ mod types;
 pub use cache::{CachedDNSResolver, DNSCache};
 pub use collector::ServerCollector;
 pub use config::load_config;
+pub use consistency::{
+    score_dns_consistency, CachePolicy, DnsBehavior, DnsObservation, DnsTransport, DoHProvider,
+    ResolverChoice,
+};
 pub use ipinfo::IPInfoClient;
 pub use resolver::{DNSResolver, DNSResolverTrait};
 pub use serverpool::ServerPool;
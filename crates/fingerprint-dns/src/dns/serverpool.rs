@@ -2,10 +2,11 @@
 //!
 //! manage DNS serverlist, include from localfileload/save and healthCheckFeatures
 
+use fingerprint_core::data_dirs::DataDirs;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -276,14 +277,24 @@ impl ServerPool {
         Ok(())
     }
 
+    /// defaultfile: `dnsservernames.json` in the [`DataDirs`] DNS cache directory
+    pub fn default_file() -> PathBuf {
+        DataDirs::from_env()
+            .dns_cache_dir()
+            .join(DEFAULT_SERVER_FILE)
+    }
+
     /// from defaultfileloadserverpool (pairshould Go NewServerPool)
     pub fn load_default() -> Self {
-        Self::load_from_file(DEFAULT_SERVER_FILE).unwrap_or_else(|_| Self::new(Vec::new()))
+        Self::load_from_file(Self::default_file()).unwrap_or_else(|_| Self::new(Vec::new()))
     }
 
     /// save to defaultfile
     pub fn save_default(&self) -> Result<(), crate::dns::types::DNSError> {
-        self.save_to_file(DEFAULT_SERVER_FILE)
+        let path = DataDirs::prepare_file(Self::default_file()).map_err(|e| {
+            crate::dns::types::DNSError::Config(format!("unable toCreatedirectory: {}", e))
+        })?;
+        self.save_to_file(path)
     }
 
     /// Addserver并returnnew ServerPool (pairshould Go AddServer)
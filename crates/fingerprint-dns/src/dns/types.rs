@@ -1,5 +1,6 @@
 //! DNS moduletype definitions
 
+use fingerprint_core::data_dirs::DataDirs;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
@@ -131,7 +132,7 @@ pub enum DNSError {
     Http(String),
     #[error("TOML serializeerror: {0}")]
     TomlSerialize(String),
-    #[error("inside部error: {0}")]
+    #[error("Internal error: {0}")]
     Internal(String),
 }
 
@@ -143,7 +144,7 @@ pub struct DNSConfig {
     pub ipinfo_token: String,
     /// domainlist (required)
     pub domain_list: Vec<String>,
-    /// storedirectory (optional, defaultcurrentdirectory)
+    /// storedirectory (optional, default the [`DataDirs`] DNS cache directory)
     #[serde(default = "default_domain_ips_dir")]
     pub domain_ips_dir: String,
     /// Checkinterval (optional, default "2m")
@@ -164,7 +165,10 @@ pub struct DNSConfig {
 }
 
 fn default_domain_ips_dir() -> String {
-    ".".to_string()
+    DataDirs::from_env()
+        .dns_cache_dir()
+        .to_string_lossy()
+        .into_owned()
 }
 
 fn default_interval() -> String {
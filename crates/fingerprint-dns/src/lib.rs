@@ -5,3 +5,10 @@
 pub mod dns;
 
 pub use dns::*;
+
+/// Protocols compiled into this crate
+pub fn capabilities() -> Vec<fingerprint_core::Capability> {
+    fingerprint_core::CapabilitySet::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+        .builtin(fingerprint_core::CapabilityKind::Protocol, "dns-preresolve")
+        .into_vec()
+}
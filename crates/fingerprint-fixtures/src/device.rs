@@ -0,0 +1,163 @@
+//! Device hardware and page telemetry
+
+use crate::persona::{Os, Persona};
+use crate::Fixtures;
+use fingerprint_hardware::{DeviceType, HardwareFingerprint};
+use fingerprint_ml::{ExtensionTelemetry, ResourceLoad};
+use rand::Rng;
+
+/// (CPU, cores, GPU, GPU memory GB) per OS family
+const WINDOWS_HARDWARE: &[(&str, u32, &str, u32)] = &[
+    ("Intel Core i7-12700", 12, "NVIDIA GeForce RTX 3060", 12),
+    ("Intel Core i5-1135G7", 4, "Intel Iris Xe Graphics", 2),
+    ("AMD Ryzen 7 5800X", 8, "AMD Radeon RX 6700 XT", 12),
+    ("Intel Core i5-12400", 6, "Intel UHD Graphics 730", 1),
+];
+const MAC_HARDWARE: &[(&str, u32, &str, u32)] = &[
+    ("Apple M1", 8, "Apple M1", 8),
+    ("Apple M2 Pro", 12, "Apple M2 Pro", 16),
+    ("Apple M3", 8, "Apple M3", 8),
+];
+const LINUX_HARDWARE: &[(&str, u32, &str, u32)] = &[
+    ("AMD Ryzen 9 7950X", 16, "AMD Radeon RX 7900 XTX", 24),
+    ("Intel Core i7-1165G7", 4, "Mesa Intel Xe Graphics", 2),
+];
+const ANDROID_HARDWARE: &[(&str, u32, &str, u32)] = &[
+    ("Qualcomm Snapdragon 8 Gen 2", 8, "Adreno 740", 0),
+    ("MediaTek Dimensity 9200", 8, "Mali-G715", 0),
+    ("Google Tensor G3", 9, "Mali-G715", 0),
+];
+const IOS_HARDWARE: &[(&str, u32, &str, u32)] = &[
+    ("Apple A15 Bionic", 6, "Apple GPU", 0),
+    ("Apple A16 Bionic", 6, "Apple GPU", 0),
+];
+
+/// Desktop (width, height, DPI)
+const DESKTOP_SCREENS: &[(u32, u32, f32)] = &[
+    (1920, 1080, 96.0),
+    (2560, 1440, 109.0),
+    (1366, 768, 96.0),
+    (1536, 864, 120.0),
+];
+const MAC_SCREENS: &[(u32, u32, f32)] = &[(1440, 900, 220.0), (1512, 982, 254.0)];
+const PHONE_SCREENS: &[(u32, u32, f32)] = &[(390, 844, 460.0), (412, 915, 420.0)];
+
+/// Third-party hosts a typical page loads
+const THIRD_PARTY_URLS: &[&str] = &[
+    "https://www.googletagmanager.com/gtm.js",
+    "https://securepubads.g.doubleclick.net/tag/js/gpt.js",
+    "https://connect.facebook.net/en_US/fbevents.js",
+    "https://fonts.googleapis.com/css2",
+    "https://cdn.jsdelivr.net/npm/app.js",
+];
+
+/// DOM markers left by popular extensions
+const EXTENSION_MARKERS: &[&str] = &[
+    "data-gr-ext-installed",
+    "data-lastpass-icon-root",
+    "data-darkreader-mode",
+    "com-1password-button",
+];
+
+impl Fixtures {
+    /// Hardware of a device running `persona`
+    pub fn hardware(&mut self, persona: Persona) -> HardwareFingerprint {
+        let (models, screens): (&[_], &[_]) = match persona.os {
+            Os::Windows => (WINDOWS_HARDWARE, DESKTOP_SCREENS),
+            Os::MacOs => (MAC_HARDWARE, MAC_SCREENS),
+            Os::Linux => (LINUX_HARDWARE, DESKTOP_SCREENS),
+            Os::Android => (ANDROID_HARDWARE, PHONE_SCREENS),
+            Os::Ios => (IOS_HARDWARE, PHONE_SCREENS),
+        };
+        let &(cpu_model, cpu_cores, gpu_model, gpu_memory_gb) = self.pick(models);
+        let &(width, height, dpi) = self.pick(screens);
+
+        let device_type = if persona.os.is_mobile() {
+            DeviceType::Phone
+        } else if self.rng.gen_bool(0.4) {
+            DeviceType::Laptop
+        } else {
+            DeviceType::Desktop
+        };
+        let system_memory_gb = if persona.os.is_mobile() {
+            *self.pick(&[4, 6, 8])
+        } else {
+            *self.pick(&[8, 16, 16, 32])
+        };
+
+        HardwareFingerprint {
+            cpu_model: cpu_model.to_string(),
+            cpu_cores,
+            gpu_model: gpu_model.to_string(),
+            gpu_memory_gb,
+            system_memory_gb,
+            screen_dpi: dpi,
+            screen_resolution: (width, height),
+            device_type,
+        }
+    }
+
+    /// Page telemetry of a real user's browser
+    ///
+    /// About a third of users run an ad blocker and many carry other
+    /// extensions; see [`Fixtures::automation_telemetry`] for the bot case.
+    pub fn extension_telemetry(&mut self) -> ExtensionTelemetry {
+        let adblock = self.rng.gen_bool(0.35);
+        let resource_loads = THIRD_PARTY_URLS
+            .iter()
+            .map(|url| {
+                let blocked = adblock && !url.contains("fonts.") && !url.contains("cdn.");
+                ResourceLoad {
+                    url: url.to_string(),
+                    failed: blocked,
+                    error: blocked.then(|| "net::ERR_BLOCKED_BY_CLIENT".to_string()),
+                }
+            })
+            .collect();
+        let dom_markers = EXTENSION_MARKERS
+            .iter()
+            .filter(|_| self.rng.gen_bool(0.25))
+            .map(|m| m.to_string())
+            .collect();
+
+        ExtensionTelemetry {
+            resource_loads,
+            bait_element_hidden: Some(adblock),
+            dom_markers,
+        }
+    }
+
+    /// Page telemetry of a fresh automation profile: nothing blocked, no extensions
+    pub fn automation_telemetry(&mut self) -> ExtensionTelemetry {
+        ExtensionTelemetry {
+            resource_loads: THIRD_PARTY_URLS
+                .iter()
+                .map(|url| ResourceLoad {
+                    url: url.to_string(),
+                    failed: false,
+                    error: None,
+                })
+                .collect(),
+            bait_element_hidden: Some(false),
+            dom_markers: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persona::Browser;
+
+    #[test]
+    fn test_hardware_matches_persona() {
+        let mut fixtures = Fixtures::new(3);
+        let phone = fixtures.hardware(Persona::new(Browser::Safari, Os::Ios));
+        assert_eq!(phone.device_type, DeviceType::Phone);
+        assert!(phone.cpu_model.starts_with("Apple A"));
+
+        let mac = fixtures.hardware(Persona::new(Browser::Chrome, Os::MacOs));
+        assert!(mac.gpu_model.starts_with("Apple M"));
+        assert_ne!(mac.device_type, DeviceType::Phone);
+    }
+}
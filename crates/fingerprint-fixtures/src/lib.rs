@@ -0,0 +1,162 @@
+//! # fingerprint-fixtures
+//!
+//! Deterministic fake data for tests, demos and benchmarks.
+//!
+//! [`Fixtures`] is a seeded generator of realistic fingerprints and traffic:
+//!
+//! - **TLS** ClientHello signatures with per-connection GREASE and Chromium
+//!   extension shuffling, tagged with their JA4
+//! - **HTTP** request fingerprints with HTTP/2 settings, tagged with JA4H
+//! - **TCP** SYN fingerprints per OS, tagged with JA4T
+//! - **hardware** fingerprints matching the device class
+//! - **flows** ([`NetworkFlow`]) combining all layers, consistent by default
+//!   or deliberately spoofed
+//! - **telemetry**: flow statistics and page extension telemetry
+//!
+//! Every value is a function of the seed: the same seed yields the same
+//! sequence on every platform and run. Timestamps come from a fixture clock
+//! starting at [`Fixtures::default_start`] and advancing per flow, never from
+//! the wall clock, unless a start time is given with [`Fixtures::starting_at`].
+//!
+//! ```
+//! use fingerprint_fixtures::Fixtures;
+//! use fingerprint_core::fingerprint::FingerprintType;
+//!
+//! let mut fixtures = Fixtures::new(42);
+//! let flow = fixtures.flow();
+//! assert_eq!(flow.get_fingerprints_by_type(FingerprintType::Tls).len(), 1);
+//!
+//! let again = Fixtures::new(42).flow();
+//! assert_eq!(flow.context, again.context);
+//! ```
+
+pub mod device;
+pub mod network;
+pub mod persona;
+
+pub use persona::{Browser, Os, Persona};
+
+use chrono::{DateTime, TimeZone, Utc};
+use fingerprint_core::system::NetworkFlow;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Seeded generator of fixture data
+pub struct Fixtures {
+    rng: ChaCha8Rng,
+    clock: DateTime<Utc>,
+}
+
+impl Fixtures {
+    /// Generator for `seed`, with the clock at [`Fixtures::default_start`]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            clock: Self::default_start(),
+        }
+    }
+
+    /// Start the fixture clock at `start`
+    ///
+    /// Use `Utc::now()` when the code under test compares timestamps with the
+    /// wall clock.
+    pub fn starting_at(mut self, start: DateTime<Utc>) -> Self {
+        self.clock = start;
+        self
+    }
+
+    /// Default clock start, 2025-01-01T00:00:00Z
+    pub fn default_start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+    }
+
+    /// Current fixture clock
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock
+    }
+
+    /// Random persona, weighted by [`Persona::ALL`]
+    pub fn persona(&mut self) -> Persona {
+        Persona::ALL
+            .choose_weighted(&mut self.rng, |(_, weight)| *weight)
+            .map(|(persona, _)| *persona)
+            .unwrap_or(Persona::ALL[0].0)
+    }
+
+    /// `count` consistent flows
+    pub fn flows(&mut self, count: usize) -> Vec<NetworkFlow> {
+        (0..count).map(|_| self.flow()).collect()
+    }
+
+    /// Move the clock forward by an inter-arrival gap of up to two seconds
+    fn advance(&mut self) {
+        self.clock += chrono::Duration::milliseconds(self.rng.gen_range(1..2000));
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.rng.gen_range(0..items.len())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fingerprint_core::fingerprint::FingerprintType;
+
+    fn summary(fixtures: &mut Fixtures) -> Vec<String> {
+        fixtures
+            .flows(20)
+            .iter()
+            .map(|flow| {
+                let ids: Vec<String> = flow.fingerprints().iter().map(|fp| fp.id()).collect();
+                format!(
+                    "{} {} {:?}",
+                    flow.context.flow_id(),
+                    flow.context.timestamp,
+                    ids
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_same_seed_same_data() {
+        assert_eq!(
+            summary(&mut Fixtures::new(9)),
+            summary(&mut Fixtures::new(9))
+        );
+        assert_ne!(
+            summary(&mut Fixtures::new(9)),
+            summary(&mut Fixtures::new(10))
+        );
+    }
+
+    #[test]
+    fn test_clock_advances_from_start() {
+        let start = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let mut fixtures = Fixtures::new(1).starting_at(start);
+        let flows = fixtures.flows(3);
+        assert_eq!(flows[0].context.timestamp, start);
+        assert!(flows[1].context.timestamp > flows[0].context.timestamp);
+        assert!(fixtures.now() > flows[2].context.timestamp);
+    }
+
+    #[test]
+    fn test_spoofed_flow_contradicts_user_agent() {
+        let mut fixtures = Fixtures::new(5);
+        for _ in 0..20 {
+            let flow = fixtures.spoofed_flow();
+            let tcp_os = flow.get_fingerprints_by_type(FingerprintType::Tcp)[0]
+                .metadata()
+                .get("os")
+                .unwrap();
+            let ua = flow.get_fingerprints_by_type(FingerprintType::Http)[0]
+                .metadata()
+                .get("user_agent")
+                .unwrap();
+            let claims_windows = ua.contains("Windows");
+            assert_ne!(claims_windows, tcp_os == "Windows", "{} / {}", tcp_os, ua);
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! TLS, HTTP and TCP fingerprints and network flows
+
+use crate::persona::{Os, Persona, GREASE};
+use crate::Fixtures;
+use fingerprint_core::grease::TLS_GREASE_VALUES;
+use fingerprint_core::http::HttpFingerprint;
+use fingerprint_core::ja4::{JA4, JA4H, JA4T};
+use fingerprint_core::metadata::FingerprintMetadata;
+use fingerprint_core::signature::ClientHelloSignature;
+use fingerprint_core::system::{
+    FlowCharacteristics, NetworkFlow, ProtocolType, SystemContext, TrafficDirection,
+};
+use fingerprint_core::tcp::TcpFingerprint;
+use fingerprint_core::version::TlsVersion;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+/// Server names used for SNI
+const SERVER_NAMES: &[&str] = &[
+    "www.example.com",
+    "api.example.com",
+    "shop.example.net",
+    "cdn.example.org",
+    "login.example.com",
+];
+
+impl Fixtures {
+    /// ClientHello signature of `persona`
+    ///
+    /// GREASE values and (for Chromium) extension order are drawn from the
+    /// seed, like a real browser draws them per connection. The JA4 is stored
+    /// in metadata under `ja4`.
+    pub fn tls(&mut self, persona: Persona) -> ClientHelloSignature {
+        let params = persona.browser.tls();
+        let mut extensions = self.with_grease(params.extensions);
+        if params.shuffle_extensions {
+            // GREASE stays first and last, the rest is permuted
+            let inner = extensions.len() - 1;
+            extensions[1..inner].shuffle(&mut self.rng);
+        }
+
+        let mut signature = ClientHelloSignature::new();
+        signature.version = TlsVersion::V1_3;
+        signature.cipher_suites = self.with_grease(params.cipher_suites);
+        signature.extensions = extensions;
+        signature.elliptic_curves = self.with_grease(params.curves);
+        signature.elliptic_curve_point_formats = vec![0];
+        signature.signature_algorithms = params.signature_algorithms.to_vec();
+        signature.sni = Some(self.pick(SERVER_NAMES).to_string());
+        signature.alpn = Some(params.alpn.to_string());
+        signature.id = signature.calculate_id();
+
+        let ja4 = JA4::generate(
+            't',
+            "1.3",
+            signature.sni.is_some(),
+            &signature.cipher_suites,
+            &signature.extensions,
+            signature.alpn.as_deref(),
+            &signature.signature_algorithms,
+        )
+        .to_fingerprint_string();
+        signature.metadata = self.metadata(persona);
+        signature.metadata.set("ja4", &ja4);
+        signature.metadata.set("tls_version", "0x0304");
+        signature
+    }
+
+    /// HTTP/2 request fingerprint of `persona`
+    ///
+    /// Metadata carries `user_agent`, `browser` and the JA4H under `ja4h`.
+    pub fn http(&mut self, persona: Persona) -> HttpFingerprint {
+        let headers = persona.headers();
+        let user_agent = persona.user_agent();
+        let ja4h = JA4H::generate(
+            "GET",
+            "2",
+            false,
+            false,
+            &headers
+                .iter()
+                .map(|(k, v)| (*k, v.as_str()))
+                .collect::<Vec<_>>(),
+        );
+
+        let mut fingerprint = HttpFingerprint::new(
+            user_agent.clone(),
+            headers
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+        .with_http2_settings(persona.browser.http2());
+        fingerprint.metadata = self.metadata(persona);
+        fingerprint.metadata.set("user_agent", &user_agent);
+        fingerprint.metadata.set("browser", persona.browser.name());
+        fingerprint.metadata.set("ja4h", &ja4h);
+        fingerprint
+    }
+
+    /// TCP SYN fingerprint of `os`, as observed after a few router hops
+    ///
+    /// Metadata carries `os` and the JA4T under `ja4t`.
+    pub fn tcp(&mut self, os: Os) -> TcpFingerprint {
+        let params = os.tcp();
+        let ttl = params.initial_ttl - self.rng.gen_range(1..=18);
+        let mut fingerprint = TcpFingerprint::with_options(
+            ttl,
+            params.window_size,
+            Some(params.mss),
+            Some(params.window_scale),
+        );
+        fingerprint.options_str = Some(params.options.to_string());
+
+        let ja4t = JA4T::generate(params.window_size, params.options, params.mss, ttl);
+        fingerprint.metadata = FingerprintMetadata::new();
+        fingerprint.metadata.os_type = os.operating_system();
+        fingerprint.metadata.first_seen = self.clock;
+        fingerprint.metadata.last_seen = self.clock;
+        fingerprint.metadata.set("os", os.tcp_stack_name());
+        fingerprint.metadata.set("ja4t", &ja4t);
+        fingerprint
+    }
+
+    /// Client IP address
+    ///
+    /// Drawn from the documentation ranges (RFC 5737), so fixtures never point
+    /// at real hosts.
+    pub fn client_ip(&mut self) -> IpAddr {
+        let net = *self.pick(&[[192, 0, 2], [198, 51, 100], [203, 0, 113]]);
+        IpAddr::V4(Ipv4Addr::new(
+            net[0],
+            net[1],
+            net[2],
+            self.rng.gen_range(1..255),
+        ))
+    }
+
+    /// Packet and byte statistics of one HTTPS page load
+    pub fn characteristics(&mut self) -> FlowCharacteristics {
+        let mut characteristics = FlowCharacteristics::new();
+        characteristics.encrypted = true;
+        for _ in 0..self.rng.gen_range(12..400) {
+            // Mostly full-size segments with a tail of ACKs and small records
+            let size = if self.rng.gen_bool(0.6) {
+                self.rng.gen_range(1200..=1500)
+            } else {
+                self.rng.gen_range(54..400)
+            };
+            characteristics.update(size);
+        }
+        characteristics.set_duration(Duration::from_millis(self.rng.gen_range(150..30_000)));
+        characteristics
+    }
+
+    /// Consistent TLS + HTTP + TCP flow from a random persona
+    pub fn flow(&mut self) -> NetworkFlow {
+        let persona = self.persona();
+        self.flow_for(persona)
+    }
+
+    /// Consistent TLS + HTTP + TCP flow from `persona`
+    pub fn flow_for(&mut self, persona: Persona) -> NetworkFlow {
+        self.assemble_flow(persona, persona.os)
+    }
+
+    /// Flow whose TCP stack contradicts the User-Agent
+    ///
+    /// TLS and HTTP come from a random persona while the TCP SYN comes from a
+    /// different OS family, as with a bot spoofing a browser User-Agent.
+    pub fn spoofed_flow(&mut self) -> NetworkFlow {
+        let persona = self.persona();
+        let tcp_os = match persona.os {
+            Os::Windows => *self.pick(&[Os::Linux, Os::MacOs]),
+            _ => Os::Windows,
+        };
+        self.assemble_flow(persona, tcp_os)
+    }
+
+    fn assemble_flow(&mut self, persona: Persona, tcp_os: Os) -> NetworkFlow {
+        let mut context = SystemContext::with_ports(
+            self.client_ip(),
+            IpAddr::V4(Ipv4Addr::new(198, 51, 100, 10)),
+            self.rng.gen_range(49152..=65535),
+            443,
+            ProtocolType::Https,
+        );
+        context.timestamp = self.clock;
+        context.interface = Some("eth0".to_string());
+        context.packet_size = self.rng.gen_range(517..=1800);
+        context.direction = TrafficDirection::Inbound;
+
+        let mut flow = NetworkFlow::new(context);
+        flow.add_fingerprint(Box::new(self.tcp(tcp_os)));
+        flow.add_fingerprint(Box::new(self.tls(persona)));
+        flow.add_fingerprint(Box::new(self.http(persona)));
+        flow.characteristics = self.characteristics();
+
+        self.advance();
+        flow
+    }
+
+    /// Copy of `values` with each GREASE placeholder replaced
+    ///
+    /// Distinct placeholders in one list get distinct values, as in Chromium.
+    fn with_grease(&mut self, values: &[u16]) -> Vec<u16> {
+        let mut grease: Vec<u16> = TLS_GREASE_VALUES.to_vec();
+        grease.shuffle(&mut self.rng);
+        let mut grease = grease.into_iter();
+        values
+            .iter()
+            .map(|&v| {
+                if v == GREASE {
+                    grease.next().unwrap_or(GREASE)
+                } else {
+                    v
+                }
+            })
+            .collect()
+    }
+
+    fn metadata(&self, persona: Persona) -> FingerprintMetadata {
+        let mut metadata = FingerprintMetadata::with_browser_os(
+            Some(persona.browser.browser_type()),
+            persona.os.operating_system(),
+        );
+        metadata.first_seen = self.clock;
+        metadata.last_seen = self.clock;
+        metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persona::Browser;
+    use fingerprint_core::fingerprint::FingerprintType;
+
+    #[test]
+    fn test_tls_ja4_is_stable_across_grease() {
+        let persona = Persona::new(Browser::Chrome, Os::Windows);
+        let mut fixtures = Fixtures::new(1);
+        let a = fixtures.tls(persona);
+        let b = fixtures.tls(persona);
+
+        // GREASE and extension order vary per connection, the JA4 does not
+        assert_ne!(a.extensions, b.extensions);
+        assert_eq!(a.metadata.get("ja4"), b.metadata.get("ja4"));
+        assert!(a.metadata.get("ja4").unwrap().starts_with("t13d1516h2_"));
+        assert!(a.has_grease());
+
+        let firefox = fixtures.tls(Persona::new(Browser::Firefox, Os::Linux));
+        assert!(firefox
+            .metadata
+            .get("ja4")
+            .unwrap()
+            .starts_with("t13d1717h2_"));
+        assert!(!firefox.has_grease());
+    }
+
+    #[test]
+    fn test_flow_layers_agree() {
+        let mut fixtures = Fixtures::new(7);
+        let persona = Persona::new(Browser::Safari, Os::Ios);
+        let flow = fixtures.flow_for(persona);
+
+        let tcp = flow.get_fingerprints_by_type(FingerprintType::Tcp);
+        let http = flow.get_fingerprints_by_type(FingerprintType::Http);
+        assert_eq!(flow.get_fingerprints_by_type(FingerprintType::Tls).len(), 1);
+        assert_eq!(tcp[0].metadata().get("os").as_deref(), Some("iOS"));
+        assert!(http[0]
+            .metadata()
+            .get("user_agent")
+            .unwrap()
+            .contains("iPhone"));
+        assert_eq!(flow.context.timestamp, Fixtures::default_start());
+        assert!(flow.characteristics.packet_count >= 12);
+    }
+}
@@ -0,0 +1,381 @@
+//! Browser/OS personas
+//!
+//! A persona ties together everything a real client would present on each
+//! layer: User-Agent and headers, TLS ClientHello parameters, HTTP/2
+//! settings, TCP stack and device hardware. Generators pick values from the
+//! persona so that a generated flow is consistent across layers unless a
+//! spoofed flow is asked for explicitly.
+
+use fingerprint_core::http::Http2Settings;
+use fingerprint_core::types::{BrowserType, OperatingSystem};
+
+/// Browser family
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Browser {
+    /// Chrome 133
+    Chrome,
+    /// Edge 133 (Chromium)
+    Edge,
+    /// Firefox 133
+    Firefox,
+    /// Safari 16
+    Safari,
+}
+
+impl Browser {
+    /// Name as used in metadata (`browser`)
+    pub fn name(self) -> &'static str {
+        match self {
+            Browser::Chrome => "Chrome",
+            Browser::Edge => "Edge",
+            Browser::Firefox => "Firefox",
+            Browser::Safari => "Safari",
+        }
+    }
+
+    /// Major version the persona tables describe
+    pub fn version(self) -> u32 {
+        match self {
+            Browser::Chrome | Browser::Edge | Browser::Firefox => 133,
+            Browser::Safari => 16,
+        }
+    }
+
+    /// Core browser type
+    pub fn browser_type(self) -> BrowserType {
+        match self {
+            Browser::Chrome => BrowserType::Chrome,
+            Browser::Edge => BrowserType::Edge,
+            Browser::Firefox => BrowserType::Firefox,
+            Browser::Safari => BrowserType::Safari,
+        }
+    }
+
+    pub(crate) fn tls(self) -> &'static TlsParams {
+        match self {
+            Browser::Chrome | Browser::Edge => &CHROMIUM_TLS,
+            Browser::Firefox => &FIREFOX_TLS,
+            Browser::Safari => &SAFARI_TLS,
+        }
+    }
+
+    /// HTTP/2 SETTINGS sent on connection start
+    pub fn http2(self) -> Http2Settings {
+        let (header_table_size, max_concurrent_streams, initial_window_size, max_header_list_size) =
+            match self {
+                Browser::Chrome | Browser::Edge => (65536, 1000, 6291456, 262144),
+                Browser::Firefox => (65536, 0, 131072, 0),
+                Browser::Safari => (4096, 100, 4194304, 0),
+            };
+        Http2Settings {
+            header_table_size,
+            enable_push: false,
+            max_concurrent_streams,
+            initial_window_size,
+            max_frame_size: 16384,
+            max_header_list_size,
+        }
+    }
+}
+
+/// Operating system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Os {
+    /// Windows 10/11
+    Windows,
+    /// macOS
+    MacOs,
+    /// Desktop Linux
+    Linux,
+    /// Android
+    Android,
+    /// iOS
+    Ios,
+}
+
+impl Os {
+    /// Display name
+    pub fn name(self) -> &'static str {
+        match self {
+            Os::Windows => "Windows",
+            Os::MacOs => "macOS",
+            Os::Linux => "Linux",
+            Os::Android => "Android",
+            Os::Ios => "iOS",
+        }
+    }
+
+    /// OS family a passive TCP fingerprinter reports (`os` metadata)
+    ///
+    /// Android runs the Linux TCP stack and is reported as Linux.
+    pub fn tcp_stack_name(self) -> &'static str {
+        match self {
+            Os::Android => "Linux",
+            os => os.name(),
+        }
+    }
+
+    /// Core operating system, for desktop systems
+    pub fn operating_system(self) -> Option<OperatingSystem> {
+        match self {
+            Os::Windows => Some(OperatingSystem::Windows10),
+            Os::MacOs => Some(OperatingSystem::MacOS14),
+            Os::Linux => Some(OperatingSystem::Linux),
+            Os::Android | Os::Ios => None,
+        }
+    }
+
+    /// True for phones and tablets
+    pub fn is_mobile(self) -> bool {
+        matches!(self, Os::Android | Os::Ios)
+    }
+
+    pub(crate) fn tcp(self) -> &'static TcpParams {
+        match self {
+            Os::Windows => &WINDOWS_TCP,
+            Os::MacOs | Os::Ios => &DARWIN_TCP,
+            Os::Linux => &LINUX_TCP,
+            Os::Android => &ANDROID_TCP,
+        }
+    }
+
+    fn ua_platform(self) -> &'static str {
+        match self {
+            Os::Windows => "Windows NT 10.0; Win64; x64",
+            Os::MacOs => "Macintosh; Intel Mac OS X 10_15_7",
+            Os::Linux => "X11; Linux x86_64",
+            Os::Android => "Linux; Android 10; K",
+            Os::Ios => "iPhone; CPU iPhone OS 16_6 like Mac OS X",
+        }
+    }
+
+    fn ch_platform(self) -> &'static str {
+        match self {
+            Os::Windows => "\"Windows\"",
+            Os::MacOs => "\"macOS\"",
+            Os::Linux => "\"Linux\"",
+            Os::Android => "\"Android\"",
+            Os::Ios => "\"iOS\"",
+        }
+    }
+}
+
+/// A browser running on an operating system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Persona {
+    /// Browser family
+    pub browser: Browser,
+    /// Operating system
+    pub os: Os,
+}
+
+impl Persona {
+    /// Every persona the generators know, with its relative traffic weight
+    pub const ALL: [(Persona, u32); 9] = [
+        (Persona::new(Browser::Chrome, Os::Windows), 30),
+        (Persona::new(Browser::Chrome, Os::MacOs), 10),
+        (Persona::new(Browser::Chrome, Os::Android), 20),
+        (Persona::new(Browser::Chrome, Os::Linux), 3),
+        (Persona::new(Browser::Edge, Os::Windows), 8),
+        (Persona::new(Browser::Firefox, Os::Windows), 4),
+        (Persona::new(Browser::Firefox, Os::Linux), 2),
+        (Persona::new(Browser::Safari, Os::MacOs), 6),
+        (Persona::new(Browser::Safari, Os::Ios), 17),
+    ];
+
+    /// Persona for `browser` on `os`
+    pub const fn new(browser: Browser, os: Os) -> Self {
+        Self { browser, os }
+    }
+
+    /// User-Agent header
+    pub fn user_agent(&self) -> String {
+        let platform = self.os.ua_platform();
+        match (self.browser, self.os) {
+            (Browser::Safari, Os::Ios) => format!(
+                "Mozilla/5.0 ({}) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.6 Mobile/15E148 Safari/604.1",
+                platform
+            ),
+            (Browser::Safari, _) => format!(
+                "Mozilla/5.0 ({}) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.6 Safari/605.1.15",
+                platform
+            ),
+            (Browser::Firefox, _) => format!(
+                "Mozilla/5.0 ({}; rv:133.0) Gecko/20100101 Firefox/133.0",
+                platform
+            ),
+            (Browser::Chrome, os) => format!(
+                "Mozilla/5.0 ({}) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/133.0.0.0 {}Safari/537.36",
+                platform,
+                if os.is_mobile() { "Mobile " } else { "" }
+            ),
+            (Browser::Edge, _) => format!(
+                "Mozilla/5.0 ({}) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/133.0.0.0 Safari/537.36 Edg/133.0.0.0",
+                platform
+            ),
+        }
+    }
+
+    /// Request headers of a top-level navigation, in wire order
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = Vec::new();
+        if matches!(self.browser, Browser::Chrome | Browser::Edge) {
+            let brand = match self.browser {
+                Browser::Edge => "\"Microsoft Edge\";v=\"133\"",
+                _ => "\"Google Chrome\";v=\"133\"",
+            };
+            headers.push((
+                "sec-ch-ua",
+                format!(
+                    "\"Not(A:Brand\";v=\"99\", {}, \"Chromium\";v=\"133\"",
+                    brand
+                ),
+            ));
+            headers.push((
+                "sec-ch-ua-mobile",
+                if self.os.is_mobile() { "?1" } else { "?0" }.to_string(),
+            ));
+            headers.push(("sec-ch-ua-platform", self.os.ch_platform().to_string()));
+            headers.push(("upgrade-insecure-requests", "1".to_string()));
+        }
+        headers.push(("user-agent", self.user_agent()));
+        headers.push(("accept", self.accept().to_string()));
+        if self.browser != Browser::Safari {
+            headers.push(("sec-fetch-site", "none".to_string()));
+            headers.push(("sec-fetch-mode", "navigate".to_string()));
+            headers.push(("sec-fetch-dest", "document".to_string()));
+        }
+        headers.push((
+            "accept-encoding",
+            match self.browser {
+                Browser::Safari => "gzip, deflate, br",
+                _ => "gzip, deflate, br, zstd",
+            }
+            .to_string(),
+        ));
+        headers.push((
+            "accept-language",
+            match self.browser {
+                Browser::Firefox => "en-US,en;q=0.5",
+                _ => "en-US,en;q=0.9",
+            }
+            .to_string(),
+        ));
+        headers
+    }
+
+    fn accept(&self) -> &'static str {
+        match self.browser {
+            Browser::Chrome | Browser::Edge => "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7",
+            Browser::Firefox => "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
+            Browser::Safari => "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
+        }
+    }
+}
+
+/// ClientHello parameters; `GREASE` marks where a GREASE value goes
+pub(crate) struct TlsParams {
+    pub cipher_suites: &'static [u16],
+    pub extensions: &'static [u16],
+    pub curves: &'static [u16],
+    pub signature_algorithms: &'static [u16],
+    pub alpn: &'static str,
+    /// Chromium permutes extension order per connection
+    pub shuffle_extensions: bool,
+}
+
+/// Placeholder replaced by a seeded GREASE value
+pub(crate) const GREASE: u16 = 0x0a0a;
+
+static CHROMIUM_TLS: TlsParams = TlsParams {
+    cipher_suites: &[
+        GREASE, 0x1301, 0x1302, 0x1303, 0xc02b, 0xc02f, 0xc02c, 0xc030, 0xcca9, 0xcca8, 0xc013,
+        0xc014, 0x009c, 0x009d, 0x002f, 0x0035,
+    ],
+    extensions: &[
+        GREASE, 0x0000, 0x0017, 0xff01, 0x000a, 0x000b, 0x0023, 0x0010, 0x0005, 0x000d, 0x0012,
+        0x0033, 0x002d, 0x002b, 0x001b, 0x44cd, 0xfe0d, GREASE,
+    ],
+    curves: &[GREASE, 0x11ec, 0x001d, 0x0017, 0x0018],
+    signature_algorithms: &[
+        0x0403, 0x0804, 0x0401, 0x0503, 0x0805, 0x0501, 0x0806, 0x0601,
+    ],
+    alpn: "h2",
+    shuffle_extensions: true,
+};
+
+static FIREFOX_TLS: TlsParams = TlsParams {
+    cipher_suites: &[
+        0x1301, 0x1303, 0x1302, 0xc02b, 0xc02f, 0xcca9, 0xcca8, 0xc02c, 0xc030, 0xc00a, 0xc009,
+        0xc013, 0xc014, 0x009c, 0x009d, 0x002f, 0x0035,
+    ],
+    extensions: &[
+        0x0000, 0x0017, 0xff01, 0x000a, 0x000b, 0x0023, 0x0010, 0x0005, 0x0022, 0x0012, 0x0033,
+        0x002b, 0x000d, 0x002d, 0x001c, 0x001b, 0xfe0d,
+    ],
+    curves: &[0x11ec, 0x001d, 0x0017, 0x0018, 0x0019, 0x0100, 0x0101],
+    signature_algorithms: &[
+        0x0403, 0x0503, 0x0603, 0x0804, 0x0805, 0x0806, 0x0401, 0x0501, 0x0601, 0x0203, 0x0201,
+    ],
+    alpn: "h2",
+    shuffle_extensions: false,
+};
+
+static SAFARI_TLS: TlsParams = TlsParams {
+    cipher_suites: &[
+        GREASE, 0x1301, 0x1302, 0x1303, 0xc02c, 0xc02b, 0xcca9, 0xc030, 0xc02f, 0xcca8, 0xc00a,
+        0xc009, 0xc014, 0xc013, 0x009d, 0x009c, 0x0035, 0x002f, 0xc008, 0xc012, 0x000a,
+    ],
+    extensions: &[
+        GREASE, 0x0000, 0x0017, 0xff01, 0x000a, 0x000b, 0x0010, 0x0005, 0x000d, 0x0012, 0x0033,
+        0x002d, 0x002b, 0x001b, 0x0015, GREASE,
+    ],
+    curves: &[GREASE, 0x001d, 0x0017, 0x0018, 0x0019],
+    signature_algorithms: &[
+        0x0403, 0x0804, 0x0401, 0x0503, 0x0203, 0x0805, 0x0501, 0x0806, 0x0601, 0x0201,
+    ],
+    alpn: "h2",
+    shuffle_extensions: false,
+};
+
+/// TCP SYN parameters
+pub(crate) struct TcpParams {
+    pub initial_ttl: u8,
+    pub window_size: u16,
+    pub mss: u16,
+    pub window_scale: u8,
+    /// JA4T option kinds in SYN order
+    pub options: &'static str,
+}
+
+static WINDOWS_TCP: TcpParams = TcpParams {
+    initial_ttl: 128,
+    window_size: 64240,
+    mss: 1460,
+    window_scale: 8,
+    options: "2-1-3-1-1-4",
+};
+
+static DARWIN_TCP: TcpParams = TcpParams {
+    initial_ttl: 64,
+    window_size: 65535,
+    mss: 1460,
+    window_scale: 6,
+    options: "2-1-3-1-1-8-4-0-0",
+};
+
+static LINUX_TCP: TcpParams = TcpParams {
+    initial_ttl: 64,
+    window_size: 64240,
+    mss: 1460,
+    window_scale: 7,
+    options: "2-4-8-1-3",
+};
+
+static ANDROID_TCP: TcpParams = TcpParams {
+    initial_ttl: 64,
+    window_size: 65535,
+    mss: 1400,
+    window_scale: 9,
+    options: "2-4-8-1-3",
+};
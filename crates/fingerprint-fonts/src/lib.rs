@@ -9,7 +9,14 @@
 //! - 字体加载时间分析
 //! - 字体渲染特征识别
 //! - 子集支持检测
+//!
+//! `system-fonts` feature 通过系统字体 API（Linux fontconfig、Windows DirectWrite、
+//! macOS CoreText）枚举本机真实字体，见 [`FontSystemDetector::enumerate`]
+
+#[cfg(feature = "system-fonts")]
+mod system;
 
+use fingerprint_core::hashing::{self, FingerprintHasher, HashAlgorithm, HashDigest, HashUpgrade};
 use std::collections::HashSet;
 
 /// fontfingerprint
@@ -27,6 +34,73 @@ pub struct FontFingerprint {
     pub supported_subsets: Vec<String>,
     /// renderingfeatures
     pub rendering_features: Vec<String>,
+    /// installed faces with metrics; empty when only names are known
+    pub faces: Vec<FontFace>,
+    /// hash of face styles and glyph widths, when faces are known
+    pub metrics_hash: Option<String>,
+}
+
+/// Characters whose advance widths are measured, as in JS width probing
+pub const PROBE_CHARS: &str = "mwliWM0@";
+
+/// Font style of a face
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+/// One installed face of a family
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontFace {
+    pub family: String,
+    pub postscript_name: String,
+    /// CSS weight, 100..=900
+    pub weight: u16,
+    pub style: FontStyle,
+    /// advance of each [`PROBE_CHARS`] char in 1/1000 em; 0 when the glyph is missing
+    pub glyph_widths: Vec<u16>,
+    /// scripts the face has glyphs for ("latin", "cjk", ...)
+    pub subsets: Vec<String>,
+}
+
+impl FontFace {
+    /// Width of the [`PROBE_CHARS`] string at `size_px`, as a page would measure it
+    pub fn probe_width(&self, size_px: f32) -> f32 {
+        self.glyph_widths.iter().map(|&w| w as u32).sum::<u32>() as f32 * size_px / 1000.0
+    }
+}
+
+/// Weights and styles installed for one family
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FamilyCoverage {
+    /// sorted, deduplicated
+    pub weights: Vec<u16>,
+    pub italic: bool,
+    pub oblique: bool,
+}
+
+impl FontFingerprint {
+    /// Weight and style coverage of `family`, if any face of it is known
+    pub fn coverage(&self, family: &str) -> Option<FamilyCoverage> {
+        let faces: Vec<&FontFace> = self
+            .faces
+            .iter()
+            .filter(|face| face.family.eq_ignore_ascii_case(family))
+            .collect();
+        if faces.is_empty() {
+            return None;
+        }
+        let mut weights: Vec<u16> = faces.iter().map(|face| face.weight).collect();
+        weights.sort_unstable();
+        weights.dedup();
+        Some(FamilyCoverage {
+            weights,
+            italic: faces.iter().any(|face| face.style == FontStyle::Italic),
+            oblique: faces.iter().any(|face| face.style == FontStyle::Oblique),
+        })
+    }
 }
 
 /// fonterrortype
@@ -84,9 +158,39 @@ impl FontAnalyzer {
             font_count: fonts.len(),
             supported_subsets,
             rendering_features,
+            faces: Vec::new(),
+            metrics_hash: None,
         })
     }
 
+    /// analyze measured faces; subsets come from actual glyph coverage
+    pub fn analyze_faces(faces: Vec<FontFace>) -> Result<FontFingerprint, FontError> {
+        let mut families: Vec<&str> = faces.iter().map(|face| face.family.as_str()).collect();
+        families.sort_unstable();
+        families.dedup();
+        let mut fingerprint = Self::analyze(&families)?;
+
+        let mut subsets: HashSet<String> = fingerprint.supported_subsets.drain(..).collect();
+        subsets.extend(faces.iter().flat_map(|face| face.subsets.iter().cloned()));
+        let mut subsets: Vec<String> = subsets.into_iter().collect();
+        subsets.sort();
+        fingerprint.supported_subsets = subsets;
+
+        let mut hasher = FingerprintHasher::new(hashing::config().content);
+        hasher.write_u64(faces.len() as u64);
+        for face in &faces {
+            hasher.write_str(&face.family);
+            hasher.write_u16(face.weight);
+            hasher.write_u8(face.style as u8);
+            for &width in &face.glyph_widths {
+                hasher.write_u16(width);
+            }
+        }
+        fingerprint.metrics_hash = Some(hasher.finish().to_string());
+        fingerprint.faces = faces;
+        Ok(fingerprint)
+    }
+
     /// calculatefontloadtime
     fn calculate_loading_times(fonts: &[String]) -> Vec<u64> {
         // based onfontnamelengthandfeaturesofsimulatedtime
@@ -100,8 +204,22 @@ impl FontAnalyzer {
             .collect()
     }
 
-    /// generatefonthash
+    /// generatefonthash (configured content hash backend)
     fn generate_font_hash(fonts: &[String]) -> String {
+        Self::font_digest(hashing::config().content, fonts).to_string()
+    }
+
+    fn font_digest(algorithm: HashAlgorithm, fonts: &[String]) -> HashDigest {
+        let mut hasher = FingerprintHasher::new(algorithm);
+        hasher.write_u64(fonts.len() as u64);
+        for font in fonts {
+            hasher.write_str(font);
+        }
+        hasher.finish()
+    }
+
+    /// polynomial hash used by earlier releases
+    fn legacy_font_hash(fonts: &[String]) -> String {
         let hash_input = fonts.join(":");
         let hash_value = hash_input
             .chars()
@@ -109,6 +227,17 @@ impl FontAnalyzer {
         format!("{:x}", hash_value)
     }
 
+    /// check a stored font hash against `system_fonts` and return its replacement
+    pub fn migrate_hash(stored: &str, system_fonts: &[&str]) -> HashUpgrade {
+        let fonts: Vec<String> = system_fonts.iter().map(|s| s.to_string()).collect();
+        hashing::migrate_stored_hash(
+            stored,
+            hashing::config().content,
+            |algorithm| Self::font_digest(algorithm, &fonts),
+            || Self::legacy_font_hash(&fonts),
+        )
+    }
+
     /// detectsupportofsubset
     fn detect_subsets(fonts: &[String]) -> Vec<String> {
         let mut subsets = HashSet::new();
@@ -152,7 +281,15 @@ pub struct FontSystemDetector;
 
 impl FontSystemDetector {
     /// detectoperating systemfont
+    ///
+    /// With `system-fonts` this is the host's real font set; the built-in list is
+    /// used when the feature is off or enumeration fails.
     pub fn detect_system() -> FontFingerprint {
+        #[cfg(feature = "system-fonts")]
+        if let Ok(fingerprint) = Self::enumerate() {
+            return fingerprint;
+        }
+
         let default_fonts = vec![
             "Arial",
             "Times New Roman",
@@ -171,6 +308,8 @@ impl FontSystemDetector {
             font_count: 0,
             supported_subsets: vec!["latin".to_string()],
             rendering_features: vec!["anti-aliasing".to_string()],
+            faces: Vec::new(),
+            metrics_hash: None,
         })
     }
 }
@@ -195,6 +334,71 @@ mod tests {
         assert!(fp.font_count > 0);
     }
 
+    #[test]
+    fn test_font_hash_migration() {
+        let fonts = ["Arial", "Verdana"];
+        let fp = FontAnalyzer::analyze(&fonts).unwrap();
+        assert!(fp.unique_hash.starts_with("blake3:"));
+
+        let legacy = FontAnalyzer::legacy_font_hash(&fp.system_fonts);
+        match FontAnalyzer::migrate_hash(&legacy, &fonts) {
+            HashUpgrade::Upgraded(digest) => assert_eq!(digest.to_string(), fp.unique_hash),
+            other => panic!("expected upgrade, got {:?}", other),
+        }
+        assert_eq!(
+            FontAnalyzer::migrate_hash(&fp.unique_hash, &fonts),
+            HashUpgrade::Current
+        );
+        assert_eq!(
+            FontAnalyzer::migrate_hash(&legacy, &["Arial"]),
+            HashUpgrade::Mismatch
+        );
+    }
+
+    #[test]
+    fn test_face_analysis() {
+        let face = |family: &str, weight, style, subsets: &[&str]| FontFace {
+            family: family.to_string(),
+            postscript_name: format!("{}-{}", family, weight),
+            weight,
+            style,
+            glyph_widths: vec![833, 722, 278, 222, 944, 833, 556, 1015],
+            subsets: subsets.iter().map(|s| s.to_string()).collect(),
+        };
+        let faces = vec![
+            face("Arial", 400, FontStyle::Normal, &["latin"]),
+            face("Arial", 700, FontStyle::Normal, &["latin"]),
+            face("Arial", 400, FontStyle::Italic, &["latin"]),
+            face(
+                "Noto Sans CJK SC",
+                400,
+                FontStyle::Normal,
+                &["latin", "cjk"],
+            ),
+        ];
+        let fp = FontAnalyzer::analyze_faces(faces.clone()).unwrap();
+        assert_eq!(fp.system_fonts, ["Arial", "Noto Sans CJK SC"]);
+        assert_eq!(fp.supported_subsets, ["cjk", "latin"]);
+        assert_eq!(
+            fp.coverage("arial"),
+            Some(FamilyCoverage {
+                weights: vec![400, 700],
+                italic: true,
+                oblique: false,
+            })
+        );
+        assert_eq!(fp.coverage("Verdana"), None);
+        assert!((faces[0].probe_width(100.0) - 540.3).abs() < 0.01);
+
+        // same names, different metrics: only the metrics hash changes
+        let mut narrower = faces;
+        narrower[0].glyph_widths[0] = 800;
+        let other = FontAnalyzer::analyze_faces(narrower).unwrap();
+        assert_eq!(other.unique_hash, fp.unique_hash);
+        assert_ne!(other.metrics_hash, fp.metrics_hash);
+        assert!(FontAnalyzer::analyze_faces(Vec::new()).is_err());
+    }
+
     #[test]
     fn test_invalid_font_data() {
         let result = FontAnalyzer::analyze(&[]);
@@ -0,0 +1,116 @@
+//! Host font enumeration (`system-fonts` feature)
+//!
+//! [`FontSystemDetector::enumerate`] lists installed faces through the platform font
+//! API font-kit wraps (fontconfig on Linux, DirectWrite on Windows, CoreText on
+//! macOS), loads each face and measures it:
+//! - weight and style, for per-family coverage
+//! - advance widths of [`PROBE_CHARS`], the values page scripts infer by measuring text
+//! - scripts it has glyphs for
+
+use crate::{
+    FontAnalyzer, FontError, FontFace, FontFingerprint, FontStyle, FontSystemDetector, PROBE_CHARS,
+};
+use font_kit::font::Font;
+use font_kit::properties::Style;
+use font_kit::source::SystemSource;
+
+/// A character each script subset must have a glyph for
+const SUBSET_PROBES: [(&str, char); 7] = [
+    ("latin", 'a'),
+    ("cyrillic", 'Ж'),
+    ("greek", 'Ω'),
+    ("arabic", 'ع'),
+    ("hebrew", 'א'),
+    ("thai", 'ก'),
+    ("cjk", '中'),
+];
+
+impl FontSystemDetector {
+    /// Enumerate and measure the fonts installed on this machine
+    pub fn enumerate() -> Result<FontFingerprint, FontError> {
+        let source = SystemSource::new();
+        let mut families = source
+            .all_families()
+            .map_err(|e| FontError::EnumerationFailed(e.to_string()))?;
+        families.sort();
+        families.dedup();
+
+        let mut faces = Vec::new();
+        for family in &families {
+            // families that vanish or fail to load are skipped, as a browser would
+            let Ok(handle) = source.select_family_by_name(family) else {
+                continue;
+            };
+            faces.extend(
+                handle
+                    .fonts()
+                    .iter()
+                    .filter_map(|font| font.load().ok())
+                    .map(|font| measure(family, &font)),
+            );
+        }
+        if faces.is_empty() {
+            return Err(FontError::EnumerationFailed(
+                "no loadable fonts installed".to_string(),
+            ));
+        }
+        FontAnalyzer::analyze_faces(faces)
+    }
+}
+
+fn measure(family: &str, font: &Font) -> FontFace {
+    let properties = font.properties();
+    let units_per_em = font.metrics().units_per_em.max(1) as f32;
+    let glyph_widths = PROBE_CHARS
+        .chars()
+        .map(|c| {
+            font.glyph_for_char(c)
+                .and_then(|glyph| font.advance(glyph).ok())
+                .map_or(0, |advance| {
+                    (advance.x() * 1000.0 / units_per_em).round().max(0.0) as u16
+                })
+        })
+        .collect();
+    let subsets = SUBSET_PROBES
+        .iter()
+        .filter(|(_, c)| font.glyph_for_char(*c).is_some_and(|glyph| glyph != 0))
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+    FontFace {
+        family: family.to_string(),
+        postscript_name: font.postscript_name().unwrap_or_default(),
+        weight: (properties.weight.0.round() as u16).clamp(100, 900),
+        style: match properties.style {
+            Style::Normal => FontStyle::Normal,
+            Style::Italic => FontStyle::Italic,
+            Style::Oblique => FontStyle::Oblique,
+        },
+        glyph_widths,
+        subsets,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enumerate_host_fonts() {
+        // hosts without any fonts (minimal containers) report an error instead
+        let Ok(fp) = FontSystemDetector::enumerate() else {
+            return;
+        };
+        assert_eq!(fp.font_count, fp.system_fonts.len());
+        assert!(fp.metrics_hash.is_some());
+        for face in &fp.faces {
+            assert_eq!(face.glyph_widths.len(), PROBE_CHARS.chars().count());
+            assert!(fp
+                .coverage(&face.family)
+                .unwrap()
+                .weights
+                .contains(&face.weight));
+        }
+        assert!(fp.faces.iter().any(|face| face.probe_width(16.0) > 0.0));
+    }
+}
@@ -0,0 +1,502 @@
+//! Rate limiting algorithms
+//!
+//! - [`RateLimitAlgorithm::FixedWindow`]: per-minute counters (the default). Cheap, but a
+//!   client can spend one window's limit at its end and the next window's at its start,
+//!   admitting twice the limit within one window's length.
+//! - [`RateLimitAlgorithm::SlidingWindowLog`]: admits while fewer than `limit` requests
+//!   were admitted in the trailing window. Exact, at one log entry per admitted request.
+//! - [`RateLimitAlgorithm::Gcra`]: generic cell rate algorithm. Requests are spaced one
+//!   emission interval (`window / limit`) apart, with up to `limit` back to back after an
+//!   idle window. One timestamp per key.
+//!
+//! The Redis implementations are Lua scripts, so the check and the update are atomic
+//! and all gateways share the Redis server's clock (Redis 5 or later, which allows
+//! writes after `TIME` in a script). Each has an in-process twin
+//! ([`Admitter`]) with the same arithmetic, which [`simulate`] and the `ratelimit-sim`
+//! binary use to compare admission of the same traffic.
+
+use crate::models::QuotaTier;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+/// Sliding window log
+///
+/// `KEYS[1]`: sorted set of admission times; `ARGV`: limit, window (ms), unique member.
+/// Returns `{allowed, remaining, reset_after_ms}`.
+pub const SLIDING_WINDOW_LOG_SCRIPT: &str = r#"
+local t = redis.call('TIME')
+local now = tonumber(t[1]) * 1000 + math.floor(tonumber(t[2]) / 1000)
+local limit = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - window)
+local count = redis.call('ZCARD', KEYS[1])
+local allowed = 0
+if count < limit then
+    redis.call('ZADD', KEYS[1], now, now .. ':' .. ARGV[3])
+    redis.call('PEXPIRE', KEYS[1], window)
+    count = count + 1
+    allowed = 1
+end
+local oldest = redis.call('ZRANGE', KEYS[1], 0, 0, 'WITHSCORES')
+return {allowed, limit - count, tonumber(oldest[2]) + window - now}
+"#;
+
+/// GCRA
+///
+/// `KEYS[1]`: theoretical arrival time (ms); `ARGV`: limit, window (ms).
+/// Returns `{allowed, remaining, reset_after_ms}`.
+pub const GCRA_SCRIPT: &str = r#"
+local t = redis.call('TIME')
+local now = tonumber(t[1]) * 1000 + math.floor(tonumber(t[2]) / 1000)
+local limit = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local interval = window / limit
+local tat = math.max(tonumber(redis.call('GET', KEYS[1])) or now, now)
+local new_tat = tat + interval
+local allow_at = new_tat - window
+if now < allow_at then
+    return {0, 0, math.ceil(allow_at - now)}
+end
+redis.call('SET', KEYS[1], string.format('%.3f', new_tat), 'PX', math.ceil(new_tat - now))
+return {1, math.floor((now + window - new_tat) / interval), math.ceil(new_tat - now)}
+"#;
+
+/// Admission algorithm of a tier
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitAlgorithm {
+    /// Per-minute counters
+    #[default]
+    FixedWindow,
+    /// Log of admission times over the trailing window
+    SlidingWindowLog,
+    /// Generic cell rate algorithm
+    Gcra,
+}
+
+impl RateLimitAlgorithm {
+    /// Every algorithm, in the order the simulator reports them
+    pub const ALL: [Self; 3] = [Self::FixedWindow, Self::SlidingWindowLog, Self::Gcra];
+
+    /// Name used in configuration
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::FixedWindow => "fixed_window",
+            Self::SlidingWindowLog => "sliding_window_log",
+            Self::Gcra => "gcra",
+        }
+    }
+
+    /// In-process limiter admitting `limit` requests per `window_ms`
+    pub fn admitter(&self, limit: u32, window_ms: u64) -> Box<dyn Admitter> {
+        match self {
+            Self::FixedWindow => Box::new(FixedWindow::new(limit, window_ms)),
+            Self::SlidingWindowLog => Box::new(SlidingWindowLog::new(limit, window_ms)),
+            Self::Gcra => Box::new(Gcra::new(limit, window_ms)),
+        }
+    }
+}
+
+impl fmt::Display for RateLimitAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RateLimitAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "fixed_window" => Ok(Self::FixedWindow),
+            "sliding_window_log" => Ok(Self::SlidingWindowLog),
+            "gcra" => Ok(Self::Gcra),
+            other => Err(format!(
+                "unknown rate limit algorithm '{}' (expected fixed_window, sliding_window_log or gcra)",
+                other
+            )),
+        }
+    }
+}
+
+/// Algorithm selection per tier
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Algorithm of tiers without an entry in `tiers`
+    #[serde(default)]
+    pub algorithm: RateLimitAlgorithm,
+
+    /// Per-tier overrides
+    #[serde(default)]
+    pub tiers: HashMap<QuotaTier, RateLimitAlgorithm>,
+}
+
+impl RateLimitConfig {
+    /// Load from environment variables
+    ///
+    /// - `RATE_LIMIT_ALGORITHM`: default algorithm (default: fixed_window)
+    /// - `RATE_LIMIT_TIER_ALGORITHMS`: overrides as `Tier=algorithm` pairs separated by
+    ///   commas, e.g. `Free=gcra,Pro=sliding_window_log`
+    pub fn from_env() -> Result<Self, String> {
+        let algorithm = match env::var("RATE_LIMIT_ALGORITHM") {
+            Ok(name) => name.parse()?,
+            Err(_) => RateLimitAlgorithm::default(),
+        };
+        let tiers = match env::var("RATE_LIMIT_TIER_ALGORITHMS") {
+            Ok(spec) => Self::parse_tiers(&spec)?,
+            Err(_) => HashMap::new(),
+        };
+        Ok(Self { algorithm, tiers })
+    }
+
+    /// Parse `Tier=algorithm[,Tier=algorithm...]`
+    pub fn parse_tiers(spec: &str) -> Result<HashMap<QuotaTier, RateLimitAlgorithm>, String> {
+        spec.split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (tier, algorithm) = pair
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected Tier=algorithm, got '{}'", pair))?;
+                Ok((tier.parse()?, algorithm.parse()?))
+            })
+            .collect()
+    }
+
+    /// Algorithm used for `tier`
+    pub fn algorithm_for(&self, tier: QuotaTier) -> RateLimitAlgorithm {
+        self.tiers.get(&tier).copied().unwrap_or(self.algorithm)
+    }
+}
+
+/// Outcome of one admission check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Admission {
+    /// Whether the request is admitted
+    pub allowed: bool,
+    /// Requests that could still be admitted right now
+    pub remaining: u32,
+    /// When denied, until the next admission; otherwise until the limit is fully restored
+    pub reset_after_ms: u64,
+}
+
+/// In-process limiter for a single key
+pub trait Admitter: Send {
+    /// Check and record a request arriving at `now_ms`
+    fn admit(&mut self, now_ms: u64) -> Admission;
+}
+
+/// Per-window counter
+#[derive(Debug, Clone)]
+pub struct FixedWindow {
+    limit: u32,
+    window_ms: u64,
+    window_start: u64,
+    count: u32,
+}
+
+impl FixedWindow {
+    /// `limit` requests per aligned window of `window_ms`
+    pub fn new(limit: u32, window_ms: u64) -> Self {
+        Self {
+            limit,
+            window_ms: window_ms.max(1),
+            window_start: 0,
+            count: 0,
+        }
+    }
+}
+
+impl Admitter for FixedWindow {
+    fn admit(&mut self, now_ms: u64) -> Admission {
+        let start = now_ms - now_ms % self.window_ms;
+        if start != self.window_start {
+            self.window_start = start;
+            self.count = 0;
+        }
+        let allowed = self.count < self.limit;
+        if allowed {
+            self.count += 1;
+        }
+        Admission {
+            allowed,
+            remaining: self.limit - self.count,
+            reset_after_ms: start + self.window_ms - now_ms,
+        }
+    }
+}
+
+/// Log of admission times, mirroring [`SLIDING_WINDOW_LOG_SCRIPT`]
+#[derive(Debug, Clone)]
+pub struct SlidingWindowLog {
+    limit: u32,
+    window_ms: u64,
+    log: VecDeque<u64>,
+}
+
+impl SlidingWindowLog {
+    /// `limit` requests in any `window_ms`
+    pub fn new(limit: u32, window_ms: u64) -> Self {
+        Self {
+            limit,
+            window_ms,
+            log: VecDeque::new(),
+        }
+    }
+}
+
+impl Admitter for SlidingWindowLog {
+    fn admit(&mut self, now_ms: u64) -> Admission {
+        while self
+            .log
+            .front()
+            .is_some_and(|&t| t + self.window_ms <= now_ms)
+        {
+            self.log.pop_front();
+        }
+        let allowed = self.log.len() < self.limit as usize;
+        if allowed {
+            self.log.push_back(now_ms);
+        }
+        Admission {
+            allowed,
+            remaining: self.limit - self.log.len() as u32,
+            reset_after_ms: self
+                .log
+                .front()
+                .map_or(0, |&oldest| oldest + self.window_ms - now_ms),
+        }
+    }
+}
+
+/// Theoretical arrival time, mirroring [`GCRA_SCRIPT`]
+#[derive(Debug, Clone)]
+pub struct Gcra {
+    window_ms: f64,
+    interval: f64,
+    tat: Option<f64>,
+}
+
+impl Gcra {
+    /// `limit` requests per `window_ms`, evenly spaced once the burst is spent
+    pub fn new(limit: u32, window_ms: u64) -> Self {
+        let window_ms = window_ms as f64;
+        Self {
+            window_ms,
+            interval: window_ms / limit.max(1) as f64,
+            tat: None,
+        }
+    }
+}
+
+impl Admitter for Gcra {
+    fn admit(&mut self, now_ms: u64) -> Admission {
+        let now = now_ms as f64;
+        let tat = self.tat.unwrap_or(now).max(now);
+        let new_tat = tat + self.interval;
+        let allow_at = new_tat - self.window_ms;
+        if now < allow_at {
+            return Admission {
+                allowed: false,
+                remaining: 0,
+                reset_after_ms: (allow_at - now).ceil() as u64,
+            };
+        }
+        self.tat = Some(new_tat);
+        Admission {
+            allowed: true,
+            remaining: ((now + self.window_ms - new_tat) / self.interval).floor() as u32,
+            reset_after_ms: (new_tat - now).ceil() as u64,
+        }
+    }
+}
+
+/// Synthetic traffic for [`simulate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrafficPattern {
+    /// Evenly spaced requests at 1.5× the limit's rate
+    Steady,
+    /// `limit` requests in the second before each window boundary and `limit` in the second after
+    EdgeBurst,
+    /// `limit` simultaneous requests every half window
+    Burst,
+}
+
+impl TrafficPattern {
+    /// Arrival times (ms) over `duration_ms`
+    pub fn arrivals(&self, limit: u32, window_ms: u64, duration_ms: u64) -> Vec<u64> {
+        let limit = limit.max(1) as u64;
+        let mut arrivals = Vec::new();
+        match self {
+            Self::Steady => {
+                let spacing = (window_ms * 2 / (limit * 3)).max(1);
+                arrivals.extend((0..duration_ms).step_by(spacing as usize));
+            }
+            Self::EdgeBurst => {
+                let spread = window_ms.min(1000);
+                for boundary in (window_ms..duration_ms).step_by(window_ms.max(1) as usize) {
+                    let offsets = (0..limit).map(|i| i * spread / limit);
+                    arrivals.extend(offsets.clone().map(|o| boundary - spread + o));
+                    arrivals.extend(offsets.map(|o| boundary + o));
+                }
+            }
+            Self::Burst => {
+                for start in (0..duration_ms).step_by((window_ms / 2).max(1) as usize) {
+                    arrivals.extend(std::iter::repeat_n(start, limit as usize));
+                }
+            }
+        }
+        arrivals.retain(|&t| t < duration_ms);
+        arrivals
+    }
+}
+
+impl FromStr for TrafficPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "steady" => Ok(Self::Steady),
+            "edge-burst" => Ok(Self::EdgeBurst),
+            "burst" => Ok(Self::Burst),
+            other => Err(format!(
+                "unknown traffic pattern '{}' (expected steady, edge-burst or burst)",
+                other
+            )),
+        }
+    }
+}
+
+/// Admission of one algorithm over a traffic sample
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulationReport {
+    /// Algorithm simulated
+    pub algorithm: RateLimitAlgorithm,
+    /// Requests offered
+    pub offered: usize,
+    /// Requests admitted
+    pub admitted: usize,
+    /// Most requests admitted within any span of one window
+    pub peak_per_window: usize,
+    /// Most requests admitted within any one second
+    pub peak_per_second: usize,
+    /// Arrival time of the first denied request
+    pub first_denied_ms: Option<u64>,
+}
+
+/// Run sorted `arrivals` through `algorithm` with `limit` requests per `window_ms`
+pub fn simulate(
+    algorithm: RateLimitAlgorithm,
+    limit: u32,
+    window_ms: u64,
+    arrivals: &[u64],
+) -> SimulationReport {
+    let mut admitter = algorithm.admitter(limit, window_ms);
+    let mut admitted = Vec::new();
+    let mut first_denied_ms = None;
+    for &t in arrivals {
+        if admitter.admit(t).allowed {
+            admitted.push(t);
+        } else {
+            first_denied_ms.get_or_insert(t);
+        }
+    }
+    SimulationReport {
+        algorithm,
+        offered: arrivals.len(),
+        admitted: admitted.len(),
+        peak_per_window: peak(&admitted, window_ms),
+        peak_per_second: peak(&admitted, 1000),
+        first_denied_ms,
+    }
+}
+
+/// Largest number of sorted `times` within any half-open span of `span_ms`
+fn peak(times: &[u64], span_ms: u64) -> usize {
+    let mut start = 0;
+    let mut best = 0;
+    for (end, &t) in times.iter().enumerate() {
+        while times[start] + span_ms <= t {
+            start += 1;
+        }
+        best = best.max(end + 1 - start);
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: u64 = 60_000;
+
+    #[test]
+    fn test_edge_burst_admission() {
+        let arrivals = TrafficPattern::EdgeBurst.arrivals(100, WINDOW, 2 * WINDOW);
+        assert_eq!(arrivals.len(), 200);
+        let [fixed, sliding, gcra] =
+            RateLimitAlgorithm::ALL.map(|a| simulate(a, 100, WINDOW, &arrivals));
+
+        assert_eq!((fixed.admitted, fixed.peak_per_window), (200, 200));
+        assert_eq!((sliding.admitted, sliding.peak_per_window), (100, 100));
+        assert_eq!(sliding.first_denied_ms, Some(WINDOW));
+        // the idle window buys one burst; after it, one request per 600 ms
+        assert_eq!(gcra.admitted, 103);
+        assert_eq!(gcra.peak_per_second, 100);
+    }
+
+    #[test]
+    fn test_gcra_spacing() {
+        let mut gcra = Gcra::new(10, 1000);
+        for _ in 0..10 {
+            assert!(gcra.admit(0).allowed);
+        }
+        let denied = gcra.admit(50);
+        assert!(!denied.allowed);
+        assert_eq!(denied.reset_after_ms, 50);
+        let admitted = gcra.admit(100);
+        assert!(admitted.allowed);
+        assert_eq!(admitted.remaining, 0);
+        assert_eq!(admitted.reset_after_ms, 1000);
+
+        let mut log = SlidingWindowLog::new(2, 1000);
+        assert_eq!(log.admit(0).remaining, 1);
+        assert_eq!(log.admit(400).reset_after_ms, 600);
+        assert!(!log.admit(999).allowed);
+        assert!(log.admit(1000).allowed);
+    }
+
+    #[test]
+    fn test_config_per_tier() {
+        let config = RateLimitConfig {
+            algorithm: RateLimitAlgorithm::Gcra,
+            tiers: RateLimitConfig::parse_tiers("Free=sliding-window-log, pro=fixed_window")
+                .unwrap(),
+        };
+        assert_eq!(
+            config.algorithm_for(QuotaTier::Free),
+            RateLimitAlgorithm::SlidingWindowLog
+        );
+        assert_eq!(
+            config.algorithm_for(QuotaTier::Pro),
+            RateLimitAlgorithm::FixedWindow
+        );
+        assert_eq!(
+            config.algorithm_for(QuotaTier::Partner),
+            RateLimitAlgorithm::Gcra
+        );
+        assert!(RateLimitConfig::parse_tiers("Free").is_err());
+        assert!(RateLimitConfig::parse_tiers("Free=leaky").is_err());
+
+        let json =
+            serde_json::json!({ "algorithm": "gcra", "tiers": { "Free": "sliding_window_log" } });
+        let parsed: RateLimitConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            parsed.tiers[&QuotaTier::Free],
+            RateLimitAlgorithm::SlidingWindowLog
+        );
+    }
+}
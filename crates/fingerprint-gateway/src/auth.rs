@@ -97,6 +97,11 @@ impl ApiKeyValidator {
         }
     }
 
+    /// Whether the key is registered (as opposed to accepted by prefix detection)
+    pub fn is_registered(&self, api_key: &str) -> bool {
+        self.keys.contains_key(api_key)
+    }
+
     /// Add a new API key
     pub fn add_key(&mut self, info: ApiKeyInfo) {
         self.keys.insert(info.key.clone(), info);
@@ -0,0 +1,294 @@
+//! Backfill jobs over the learner database (admin endpoints)
+//!
+//! After a model, profile or label feed update, analysts re-score a window of
+//! stored flows with the current configuration. Jobs run on a background thread
+//! against their own connection to `label_db_path`; results are new verdict
+//! versions next to the originals, polled through the job endpoints.
+
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use fingerprint_defense::backfill::{BackfillJob, BackfillRequest, VerdictChange};
+use fingerprint_defense::passive::consistency::ConsistencyAnalyzer;
+use fingerprint_defense::FingerprintDatabase;
+use tracing::{info, warn};
+
+use crate::{
+    auth::ApiKeyValidator,
+    error::GatewayError,
+    rbac::{Permission, Rbac},
+};
+
+/// Changes returned per page unless `limit` says otherwise
+const DEFAULT_CHANGES_PAGE: usize = 100;
+/// Largest page of changes
+const MAX_CHANGES_PAGE: usize = 1000;
+
+/// Starts and reports backfill jobs on the learner database
+#[derive(Debug, Clone, Default)]
+pub struct BackfillService {
+    db_path: Option<String>,
+}
+
+impl BackfillService {
+    /// Service over the database at `db_path`; `None` rejects every job
+    pub fn new(db_path: Option<String>) -> Self {
+        Self { db_path }
+    }
+
+    fn open(&self) -> Result<FingerprintDatabase, GatewayError> {
+        let path = self.db_path.as_deref().ok_or_else(|| {
+            GatewayError::ConfigError("backfill requires label_db_path".to_string())
+        })?;
+        FingerprintDatabase::open(path).map_err(GatewayError::InternalError)
+    }
+
+    /// Record a job and re-score its window in the background
+    pub fn start(&self, request: BackfillRequest) -> Result<BackfillJob, GatewayError> {
+        request.validate().map_err(GatewayError::InvalidRequest)?;
+        let db = self.open()?;
+        let job_id = db
+            .create_backfill_job(&request)
+            .map_err(GatewayError::InternalError)?;
+        let job = db
+            .backfill_job(job_id)
+            .map_err(GatewayError::InternalError)?
+            .ok_or_else(|| {
+                GatewayError::InternalError(format!("backfill job {} vanished", job_id))
+            })?;
+
+        let worker = self.clone();
+        std::thread::Builder::new()
+            .name(format!("backfill-{}", job_id))
+            .spawn(move || {
+                let outcome = worker.open().map_err(|e| e.to_string()).and_then(|db| {
+                    db.run_backfill_job(job_id, &ConsistencyAnalyzer::new())
+                });
+                match outcome {
+                    Ok(report) => info!(
+                        "Backfill job {} scanned {} flows: {} changed, {} newly flagged, {} cleared",
+                        job_id,
+                        report.job.flows_scanned,
+                        report.job.flows_changed,
+                        report.newly_flagged,
+                        report.cleared
+                    ),
+                    Err(e) => warn!("Backfill job {} failed: {}", job_id, e),
+                }
+            })?;
+        Ok(job)
+    }
+
+    /// A job and its progress
+    pub fn job(&self, job_id: i64) -> Result<Option<BackfillJob>, GatewayError> {
+        self.open()?
+            .backfill_job(job_id)
+            .map_err(GatewayError::InternalError)
+    }
+
+    /// Verdicts a job changed, in flow order
+    pub fn changes(
+        &self,
+        job_id: i64,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<VerdictChange>, GatewayError> {
+        self.open()?
+            .backfill_changes(job_id, offset, limit)
+            .map_err(GatewayError::InternalError)
+    }
+}
+
+fn not_found(job_id: i64) -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": { "message": format!("backfill job {} not found", job_id) }
+    }))
+}
+
+/// Start re-scoring a window of stored flows
+///
+/// POST /api/v1/admin/backfill
+pub async fn start_backfill(
+    service: web::Data<BackfillService>,
+    validator: web::Data<ApiKeyValidator>,
+    rbac: web::Data<Rbac>,
+    request: HttpRequest,
+    body: web::Json<BackfillRequest>,
+) -> Result<impl Responder, GatewayError> {
+    let principal = rbac.authorize(
+        &request,
+        &validator,
+        Permission::RunAnalysis,
+        Some("backfill"),
+    )?;
+    let service = service.into_inner();
+    let job = web::block(move || service.start(body.into_inner()))
+        .await
+        .map_err(|e| GatewayError::InternalError(e.to_string()))??;
+    info!("Backfill job {} started by {}", job.id, principal.subject);
+    Ok(HttpResponse::Accepted().json(job))
+}
+
+/// Status and counters of a backfill job
+///
+/// GET /api/v1/admin/backfill/{id}
+pub async fn get_backfill(
+    service: web::Data<BackfillService>,
+    validator: web::Data<ApiKeyValidator>,
+    rbac: web::Data<Rbac>,
+    request: HttpRequest,
+    path: web::Path<i64>,
+) -> Result<HttpResponse, GatewayError> {
+    let job_id = path.into_inner();
+    rbac.authorize(
+        &request,
+        &validator,
+        Permission::RunAnalysis,
+        Some("backfill"),
+    )?;
+    let service = service.into_inner();
+    let job = web::block(move || service.job(job_id))
+        .await
+        .map_err(|e| GatewayError::InternalError(e.to_string()))??;
+    Ok(match job {
+        Some(job) => HttpResponse::Ok().json(job),
+        None => not_found(job_id),
+    })
+}
+
+/// Verdicts changed by a backfill job
+///
+/// GET /api/v1/admin/backfill/{id}/changes?offset={n}&limit={n}
+pub async fn get_backfill_changes(
+    service: web::Data<BackfillService>,
+    validator: web::Data<ApiKeyValidator>,
+    rbac: web::Data<Rbac>,
+    request: HttpRequest,
+    path: web::Path<i64>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> Result<HttpResponse, GatewayError> {
+    let job_id = path.into_inner();
+    rbac.authorize(
+        &request,
+        &validator,
+        Permission::RunAnalysis,
+        Some("backfill"),
+    )?;
+    let param = |name: &str, default: usize| {
+        query
+            .get(name)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    };
+    let offset = param("offset", 0);
+    let limit = param("limit", DEFAULT_CHANGES_PAGE).min(MAX_CHANGES_PAGE);
+
+    let service = service.into_inner();
+    let result = web::block(move || -> Result<_, GatewayError> {
+        let Some(job) = service.job(job_id)? else {
+            return Ok(None);
+        };
+        let changes = service.changes(job_id, offset, limit)?;
+        Ok(Some((job, changes)))
+    })
+    .await
+    .map_err(|e| GatewayError::InternalError(e.to_string()))??;
+    Ok(match result {
+        Some((job, changes)) => HttpResponse::Ok().json(serde_json::json!({
+            "job": job,
+            "offset": offset,
+            "changes": changes,
+        })),
+        None => not_found(job_id),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::test::{self as actix_test, TestRequest};
+    use actix_web::App;
+    use fingerprint_fixtures::Fixtures;
+    use std::time::Duration;
+
+    #[actix_web::test]
+    async fn test_backfill_job_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("labels.db");
+        let mut fixtures = Fixtures::new(41);
+        let from = fixtures.now();
+        {
+            let db = FingerprintDatabase::open(&path).unwrap();
+            let flow = fixtures.spoofed_flow();
+            db.store_flow(&flow, 100, false).unwrap();
+        }
+        let to = fixtures.now() + chrono::Duration::hours(1);
+
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(BackfillService::new(Some(
+                    path.to_string_lossy().into_owned(),
+                ))))
+                .app_data(web::Data::new(ApiKeyValidator::new()))
+                .app_data(web::Data::new(Rbac::default()))
+                .route("/backfill", web::post().to(start_backfill))
+                .route("/backfill/{id}", web::get().to(get_backfill))
+                .route(
+                    "/backfill/{id}/changes",
+                    web::get().to(get_backfill_changes),
+                ),
+        )
+        .await;
+        let admin = ("X-Admin-Key", "sk_enterprise_corp789");
+
+        // viewers may not start jobs
+        let req = TestRequest::post()
+            .uri("/backfill")
+            .insert_header(("X-Admin-Key", "sk_test_demo123"))
+            .set_json(BackfillRequest::new(from, to))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = TestRequest::post()
+            .uri("/backfill")
+            .insert_header(admin)
+            .set_json(BackfillRequest::new(from, to).with_note("os mapping fix"))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+        let job: BackfillJob = actix_test::read_body_json(resp).await;
+
+        let mut finished = None;
+        for _ in 0..100 {
+            let req = TestRequest::get()
+                .uri(&format!("/backfill/{}", job.id))
+                .insert_header(admin)
+                .to_request();
+            let polled: BackfillJob = actix_test::call_and_read_body_json(&app, req).await;
+            if polled.finished_at.is_some() {
+                finished = Some(polled);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let finished = finished.expect("backfill job finished");
+        assert_eq!(finished.flows_scanned, 1);
+        assert_eq!(finished.flows_changed, 1);
+
+        let req = TestRequest::get()
+            .uri(&format!("/backfill/{}/changes?limit=10", job.id))
+            .insert_header(admin)
+            .to_request();
+        let body: serde_json::Value = actix_test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["changes"].as_array().unwrap().len(), 1);
+        assert_eq!(body["changes"][0]["bot_detected"], true);
+        assert_eq!(body["changes"][0]["previous_bot"], false);
+
+        let req = TestRequest::get()
+            .uri("/backfill/999")
+            .insert_header(admin)
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,89 @@
+//! Rate limiting algorithm simulator
+//!
+//! Runs one synthetic traffic pattern through every algorithm and prints how much each
+//! admits, and how bursty the admitted traffic is.
+//!
+//! Usage: ratelimit-sim [--limit N] [--window-secs S] [--duration-secs S]
+//!                      [--pattern steady|edge-burst|burst] [--json]
+
+use fingerprint_gateway::algorithm::{simulate, RateLimitAlgorithm, TrafficPattern};
+
+const USAGE: &str = "Usage: ratelimit-sim [--limit N] [--window-secs S] [--duration-secs S] \
+                     [--pattern steady|edge-burst|burst] [--json]";
+
+fn usage(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!("{}", USAGE);
+    std::process::exit(2);
+}
+
+fn value<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, flag: &str) -> T {
+    args.next()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_else(|| usage(&format!("{} needs a valid value", flag)))
+}
+
+fn main() {
+    let mut limit: u32 = 100;
+    let mut window_secs: u64 = 60;
+    let mut duration_secs: u64 = 300;
+    let mut pattern = TrafficPattern::EdgeBurst;
+    let mut json = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--limit" => limit = value(&mut args, "--limit"),
+            "--window-secs" => window_secs = value(&mut args, "--window-secs"),
+            "--duration-secs" => duration_secs = value(&mut args, "--duration-secs"),
+            "--pattern" => {
+                let name: String = value(&mut args, "--pattern");
+                pattern = name.parse().unwrap_or_else(|e: String| usage(&e));
+            }
+            "--json" => json = true,
+            other => usage(&format!("unknown argument {}", other)),
+        }
+    }
+    if limit == 0 || window_secs == 0 {
+        usage("--limit and --window-secs must be at least 1");
+    }
+
+    let window_ms = window_secs * 1000;
+    let arrivals = pattern.arrivals(limit, window_ms, duration_secs * 1000);
+    let reports: Vec<_> = RateLimitAlgorithm::ALL
+        .iter()
+        .map(|&algorithm| simulate(algorithm, limit, window_ms, &arrivals))
+        .collect();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&reports).unwrap_or_default()
+        );
+        return;
+    }
+
+    println!(
+        "{:?} traffic, {} requests per {}s, {}s simulated",
+        pattern, limit, window_secs, duration_secs
+    );
+    println!(
+        "{:<20} {:>8} {:>9} {:>11} {:>11} {:>14}",
+        "algorithm", "offered", "admitted", "peak/window", "peak/second", "first denial"
+    );
+    for report in &reports {
+        let first_denied = report
+            .first_denied_ms
+            .map(|ms| format!("{:.3}s", ms as f64 / 1000.0))
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<20} {:>8} {:>9} {:>11} {:>11} {:>14}",
+            report.algorithm.as_str(),
+            report.offered,
+            report.admitted,
+            report.peak_per_window,
+            report.peak_per_second,
+            first_denied
+        );
+    }
+}
@@ -0,0 +1,19 @@
+//! Startup self-check binary
+//!
+//! Prints a JSON readiness report and exits with status 1 if any check fails.
+
+use fingerprint_gateway::{selfcheck, GatewayConfig};
+
+#[actix_web::main]
+async fn main() -> anyhow::Result<()> {
+    let config = GatewayConfig::from_env()?;
+
+    let report = selfcheck::run(&config).await;
+    println!("{}", report.to_json());
+
+    if !report.ready {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
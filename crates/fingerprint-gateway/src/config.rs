@@ -1,8 +1,18 @@
 //! Configuration module for the API Gateway
 
+use crate::algorithm::RateLimitConfig;
+use crate::error::{GatewayError, Result};
+use crate::limits::RequestLimits;
+use crate::rbac::RbacConfig;
+use fingerprint_core::data_dirs::DataDirs;
+use fingerprint_core::i18n::Locale;
+use fingerprint_core::runtime::RuntimeConfig;
 use serde::{Deserialize, Serialize};
 use std::env;
 
+/// Minimum HS256 secret length (256 bits)
+const MIN_JWT_SECRET_LEN: usize = 32;
+
 /// Gateway configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GatewayConfig {
@@ -23,6 +33,34 @@ pub struct GatewayConfig {
 
     /// Request timeout in seconds
     pub request_timeout_secs: u64,
+
+    /// Per-minute rate limiting algorithm of each tier
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+
+    /// JSON file persisting quota tier plans and assignments (in memory when unset)
+    #[serde(default)]
+    pub quota_tiers_path: Option<String>,
+
+    /// Learner database receiving enforcement labels (requires the `learner-labels` feature)
+    #[serde(default)]
+    pub label_db_path: Option<String>,
+
+    /// Access control for admin and analysis endpoints
+    #[serde(default)]
+    pub rbac: RbacConfig,
+
+    /// Request body, header and decompression limits
+    #[serde(default)]
+    pub limits: RequestLimits,
+
+    /// Locale of user-facing messages; error `type` codes are not localized
+    #[serde(default)]
+    pub locale: Locale,
+
+    /// Tokio, compute pool and capture runtime sizing
+    #[serde(default)]
+    pub runtime: RuntimeConfig,
 }
 
 impl Default for GatewayConfig {
@@ -34,6 +72,13 @@ impl Default for GatewayConfig {
             redis_url: "redis://127.0.0.1:6379".to_string(),
             enable_metrics: true,
             request_timeout_secs: 30,
+            rate_limit: RateLimitConfig::default(),
+            quota_tiers_path: None,
+            label_db_path: None,
+            rbac: RbacConfig::default(),
+            limits: RequestLimits::default(),
+            locale: Locale::En,
+            runtime: RuntimeConfig::default(),
         }
     }
 }
@@ -48,7 +93,38 @@ impl GatewayConfig {
     /// - `REDIS_URL`: Redis connection URL (default: redis://127.0.0.1:6379)
     /// - `ENABLE_METRICS`: Enable Prometheus metrics (default: true)
     /// - `REQUEST_TIMEOUT_SECS`: Request timeout (default: 30)
+    /// - `RATE_LIMIT_ALGORITHM`, `RATE_LIMIT_TIER_ALGORITHMS`: per-minute rate limiting
+    ///   algorithms (see [`RateLimitConfig`])
+    /// - `QUOTA_TIERS_PATH`: JSON file persisting quota tier plans (default: unset, in memory)
+    /// - `LABEL_DB_PATH`: Learner database for enforcement labels (default: unset)
+    /// - `RBAC_CONFIG`: JSON file with the RBAC section (default: built-in roles)
+    /// - `JWT_SECRET`: HS256 secret enabling bearer token authentication (default: unset)
+    /// - `AUDIT_LOG_PATH`: JSON Lines file receiving audit entries (default: unset)
+    /// - `PERSIST_AUDIT_LOG`: write audit entries to the data directory's audit log
+    ///   when `AUDIT_LOG_PATH` is unset (default: false)
+    /// - `MAX_BODY_BYTES`, `MAX_HEADER_COUNT`, `MAX_HEADER_BYTES`, `MAX_DECOMPRESSED_BYTES`,
+    ///   `MAX_DECOMPRESSION_RATIO`, `MAX_IN_FLIGHT_BODIES`: request limits (see [`RequestLimits`])
+    /// - `FINGERPRINT_LOCALE`: Message locale, `en` or `zh-CN` (default: en)
+    /// - `FINGERPRINT_WORKER_THREADS`, `FINGERPRINT_MAX_BLOCKING_THREADS`,
+    ///   `FINGERPRINT_COMPUTE_THREADS`, `FINGERPRINT_COMPUTE_QUEUE`,
+    ///   `FINGERPRINT_CAPTURE_THREADS`: pool sizing (see [`RuntimeConfig`])
     pub fn from_env() -> anyhow::Result<Self> {
+        let mut rbac = match env::var("RBAC_CONFIG") {
+            Ok(path) => RbacConfig::from_file(&path)?,
+            Err(_) => RbacConfig::default(),
+        };
+        if let Ok(secret) = env::var("JWT_SECRET") {
+            rbac.jwt_secret = Some(secret);
+        }
+        if let Ok(path) = env::var("AUDIT_LOG_PATH") {
+            rbac.audit_log_path = Some(path.into());
+        } else if env::var("PERSIST_AUDIT_LOG")
+            .map(|v| v.parse().unwrap_or(false))
+            .unwrap_or(false)
+        {
+            rbac.audit_log_path = Some(DataDirs::from_env().audit_log());
+        }
+
         Ok(Self {
             host: env::var("GATEWAY_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
             port: env::var("GATEWAY_PORT")
@@ -69,8 +145,68 @@ impl GatewayConfig {
                 .unwrap_or_else(|_| "30".to_string())
                 .parse()
                 .unwrap_or(30),
+            rate_limit: RateLimitConfig::from_env().map_err(anyhow::Error::msg)?,
+            quota_tiers_path: env::var("QUOTA_TIERS_PATH").ok(),
+            label_db_path: env::var("LABEL_DB_PATH").ok(),
+            rbac,
+            limits: RequestLimits::from_env(),
+            locale: Locale::from_env().unwrap_or_default(),
+            runtime: RuntimeConfig::from_env(),
         })
     }
+
+    /// Check the configuration for values the gateway cannot run with
+    ///
+    /// Returns every problem found, joined into a single `ConfigError`.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        if self.port == 0 {
+            problems.push("port must be non-zero".to_string());
+        }
+        if self.workers == 0 {
+            problems.push("workers must be at least 1".to_string());
+        }
+        if self.request_timeout_secs == 0 {
+            problems.push("request_timeout_secs must be at least 1".to_string());
+        }
+        if let Err(e) = redis::Client::open(self.redis_url.as_str()) {
+            problems.push(format!("invalid redis_url: {}", e));
+        }
+
+        let limits = &self.limits;
+        if limits.max_body_bytes == 0 || limits.max_header_count == 0 {
+            problems.push("body and header limits must be non-zero".to_string());
+        }
+        if limits.max_decompressed_bytes < limits.max_body_bytes {
+            problems.push("max_decompressed_bytes must be >= max_body_bytes".to_string());
+        }
+        if limits.max_decompression_ratio == 0 || limits.max_in_flight_bodies == 0 {
+            problems.push(
+                "max_decompression_ratio and max_in_flight_bodies must be at least 1".to_string(),
+            );
+        }
+
+        if self.runtime.worker_threads == Some(0) || self.runtime.compute_threads == Some(0) {
+            problems
+                .push("runtime worker_threads and compute_threads must be at least 1".to_string());
+        }
+
+        if let Some(secret) = &self.rbac.jwt_secret {
+            if secret.len() < MIN_JWT_SECRET_LEN {
+                problems.push(format!(
+                    "jwt_secret must be at least {} bytes",
+                    MIN_JWT_SECRET_LEN
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(GatewayError::ConfigError(problems.join("; ")))
+        }
+    }
 }
 
 #[cfg(test)]
@@ -86,6 +222,28 @@ mod tests {
         assert_eq!(config.redis_url, "redis://127.0.0.1:6379");
         assert!(config.enable_metrics);
         assert_eq!(config.request_timeout_secs, 30);
+        assert!(config.label_db_path.is_none());
+        assert!(config.rbac.jwt_secret.is_none());
+        assert_eq!(config.limits.max_body_bytes, 1024 * 1024);
+        assert_eq!(config.locale, Locale::En);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(GatewayConfig::default().validate().is_ok());
+
+        let mut config = GatewayConfig {
+            workers: 0,
+            redis_url: "not a url".to_string(),
+            ..Default::default()
+        };
+        config.rbac.jwt_secret = Some("short".to_string());
+        config.runtime.compute_threads = Some(0);
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("workers"));
+        assert!(err.contains("redis_url"));
+        assert!(err.contains("jwt_secret"));
+        assert!(err.contains("compute_threads"));
     }
 
     #[test]
@@ -1,6 +1,8 @@
 //! Error types for the API Gateway
 
+use crate::limits::LimitViolation;
 use actix_web::{error::ResponseError, http::StatusCode, HttpResponse};
+use fingerprint_core::i18n::{self, Locale};
 
 /// Result type alias for Gateway operations
 pub type Result<T> = std::result::Result<T, GatewayError>;
@@ -16,10 +18,18 @@ pub enum GatewayError {
     #[error("Invalid API key: {0}")]
     InvalidApiKey(String),
 
+    /// Authenticated but not allowed
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     /// Quota exceeded
     #[error("Quota exceeded: {0}")]
     QuotaExceeded(String),
 
+    /// Request exceeded a configured resource limit
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(#[from] LimitViolation),
+
     /// Redis connection error
     #[error("Redis error: {0}")]
     RedisError(#[from] redis::RedisError),
@@ -28,6 +38,10 @@ pub enum GatewayError {
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
+    /// Resource does not exist
+    #[error("Not found: {0}")]
+    NotFound(String),
+
     /// Invalid request
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
@@ -50,8 +64,11 @@ impl ResponseError for GatewayError {
         match self {
             Self::RateLimitExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
             Self::InvalidApiKey(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
             Self::QuotaExceeded(_) => StatusCode::PAYMENT_REQUIRED,
             Self::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::LimitExceeded(violation) => violation.status_code(),
             Self::RedisError(_) | Self::ConfigError(_) | Self::InternalError(_) => {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
@@ -62,7 +79,7 @@ impl ResponseError for GatewayError {
 
     fn error_response(&self) -> HttpResponse {
         let status = self.status_code();
-        let error_message = self.to_string();
+        let error_message = self.message_in(i18n::locale());
 
         HttpResponse::build(status).json(serde_json::json!({
             "error": {
@@ -75,14 +92,43 @@ impl ResponseError for GatewayError {
 }
 
 impl GatewayError {
+    /// Message in `locale`; the English message equals `to_string()`
+    ///
+    /// Only the message is localized. Clients should match on the stable
+    /// `type` and `code` fields of the error response.
+    pub fn message_in(&self, locale: Locale) -> String {
+        let detail = match self {
+            Self::RateLimitExceeded(detail)
+            | Self::InvalidApiKey(detail)
+            | Self::Forbidden(detail)
+            | Self::QuotaExceeded(detail)
+            | Self::ConfigError(detail)
+            | Self::InvalidRequest(detail)
+            | Self::NotFound(detail)
+            | Self::InternalError(detail)
+            | Self::Other(detail) => detail.clone(),
+            Self::LimitExceeded(violation) => violation.to_string(),
+            Self::RedisError(e) => e.to_string(),
+            Self::IoError(e) => e.to_string(),
+        };
+        i18n::translate(
+            locale,
+            &format!("gateway.{}", self.error_type()),
+            &[("detail", &detail)],
+        )
+    }
+
     fn error_type(&self) -> &str {
         match self {
             Self::RateLimitExceeded(_) => "rate_limit_exceeded",
             Self::InvalidApiKey(_) => "invalid_api_key",
+            Self::Forbidden(_) => "forbidden",
             Self::QuotaExceeded(_) => "quota_exceeded",
+            Self::LimitExceeded(_) => "limit_exceeded",
             Self::RedisError(_) => "redis_error",
             Self::ConfigError(_) => "config_error",
             Self::InvalidRequest(_) => "invalid_request",
+            Self::NotFound(_) => "not_found",
             Self::InternalError(_) => "internal_error",
             Self::IoError(_) => "io_error",
             Self::Other(_) => "unknown_error",
@@ -110,10 +156,18 @@ mod tests {
             GatewayError::InvalidApiKey("test".to_string()).status_code(),
             StatusCode::UNAUTHORIZED
         );
+        assert_eq!(
+            GatewayError::Forbidden("test".to_string()).status_code(),
+            StatusCode::FORBIDDEN
+        );
         assert_eq!(
             GatewayError::QuotaExceeded("test".to_string()).status_code(),
             StatusCode::PAYMENT_REQUIRED
         );
+        assert_eq!(
+            GatewayError::LimitExceeded(LimitViolation::BodyTooLarge { limit: 1 }).status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
     }
 
     #[test]
@@ -124,4 +178,21 @@ mod tests {
         let err = GatewayError::InvalidApiKey("test".to_string());
         assert_eq!(err.error_type(), "invalid_api_key");
     }
+
+    #[test]
+    fn test_localized_messages() {
+        let errors = [
+            GatewayError::RateLimitExceeded("100/min".to_string()),
+            GatewayError::LimitExceeded(LimitViolation::BodyTooLarge { limit: 1 }),
+            GatewayError::ConfigError("port must be non-zero".to_string()),
+            GatewayError::Other("boom".to_string()),
+        ];
+        for err in &errors {
+            assert_eq!(err.message_in(Locale::En), err.to_string());
+        }
+
+        let zh = errors[0].message_in(Locale::ZhCn);
+        assert_eq!(zh, "超出速率限制: 100/min");
+        assert_eq!(errors[0].error_type(), "rate_limit_exceeded");
+    }
 }
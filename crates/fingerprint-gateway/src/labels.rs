@@ -0,0 +1,133 @@
+//! Enforcement label reporting
+//!
+//! Feeds gateway enforcement outcomes (429s, auth failures) back to the defense learner
+//! as weak labels against the fingerprints the edge observed for the client. Reporting
+//! is compiled in with the `learner-labels` feature; without it the reporter is a no-op.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "learner-labels")]
+use fingerprint_defense::labels::{
+    EnforcementEvent, EnforcementOutcome, FingerprintRef, LabelSink,
+};
+
+/// Reports enforcement outcomes to the learner database
+#[derive(Clone, Default)]
+pub struct EnforcementReporter {
+    #[cfg(feature = "learner-labels")]
+    sink: Option<LabelSink>,
+}
+
+impl EnforcementReporter {
+    /// Reporter that discards every outcome
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Reporter forwarding outcomes to a running label propagator
+    #[cfg(feature = "learner-labels")]
+    pub fn new(sink: LabelSink) -> Self {
+        Self { sink: Some(sink) }
+    }
+
+    /// Whether outcomes are forwarded anywhere
+    pub fn is_enabled(&self) -> bool {
+        #[cfg(feature = "learner-labels")]
+        {
+            self.sink.is_some()
+        }
+        #[cfg(not(feature = "learner-labels"))]
+        {
+            false
+        }
+    }
+
+    /// Request rejected by the rate limiter
+    ///
+    /// `subject` identifies the client (key owner, IP) and must not be the API key itself.
+    pub fn rate_limited(&self, subject: Option<&str>, fingerprints: &HashMap<String, String>) {
+        #[cfg(feature = "learner-labels")]
+        self.report(EnforcementOutcome::RateLimited, subject, fingerprints);
+        #[cfg(not(feature = "learner-labels"))]
+        let _ = (subject, fingerprints);
+    }
+
+    /// Request rejected because of a missing, unknown or inactive API key
+    pub fn auth_failure(&self, subject: Option<&str>, fingerprints: &HashMap<String, String>) {
+        #[cfg(feature = "learner-labels")]
+        self.report(EnforcementOutcome::AuthFailure, subject, fingerprints);
+        #[cfg(not(feature = "learner-labels"))]
+        let _ = (subject, fingerprints);
+    }
+
+    #[cfg(feature = "learner-labels")]
+    fn report(
+        &self,
+        outcome: EnforcementOutcome,
+        subject: Option<&str>,
+        fingerprints: &HashMap<String, String>,
+    ) {
+        let Some(sink) = &self.sink else {
+            return;
+        };
+        let mut event = EnforcementEvent::new(outcome, fingerprint_refs(fingerprints));
+        if let Some(subject) = subject {
+            event = event.with_subject(subject);
+        }
+        if !sink.record(event) {
+            tracing::debug!("Label queue full, dropped {} label", outcome.as_str());
+        }
+    }
+}
+
+/// Fingerprints from a request, in a stable order and without empty IDs
+#[cfg(feature = "learner-labels")]
+fn fingerprint_refs(fingerprints: &HashMap<String, String>) -> Vec<FingerprintRef> {
+    let mut refs: Vec<FingerprintRef> = fingerprints
+        .iter()
+        .filter(|(_, id)| !id.is_empty())
+        .map(|(fp_type, id)| FingerprintRef::new(fp_type.to_ascii_lowercase(), id.clone()))
+        .collect();
+    refs.sort_by(|a, b| a.fingerprint_type.cmp(&b.fingerprint_type));
+    refs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_reporter_is_noop() {
+        let reporter = EnforcementReporter::disabled();
+        let fingerprints = HashMap::from([("tls".to_string(), "t13d1516h2_abc".to_string())]);
+
+        assert!(!reporter.is_enabled());
+        reporter.rate_limited(Some("demo_user"), &fingerprints);
+        reporter.auth_failure(None, &fingerprints);
+    }
+
+    #[cfg(feature = "learner-labels")]
+    #[test]
+    fn test_outcomes_reach_database() {
+        use fingerprint_defense::labels::{weak_label_summary, LabelPropagator};
+        use fingerprint_defense::FingerprintDatabase;
+
+        let propagator =
+            LabelPropagator::spawn(FingerprintDatabase::new_in_memory().unwrap()).unwrap();
+        let reporter = EnforcementReporter::new(propagator.sink());
+        let fingerprints = HashMap::from([
+            ("TLS".to_string(), "t13d1516h2_abc".to_string()),
+            ("http".to_string(), String::new()),
+        ]);
+
+        reporter.rate_limited(Some("demo_user"), &fingerprints);
+        reporter.auth_failure(Some("203.0.113.7"), &fingerprints);
+        drop(reporter);
+
+        let db = propagator.shutdown();
+        let summary =
+            weak_label_summary(&db, &FingerprintRef::new("tls", "t13d1516h2_abc")).unwrap();
+        assert_eq!(summary.count, 2);
+        assert_eq!(summary.by_source["auth_failure"], 1);
+    }
+}
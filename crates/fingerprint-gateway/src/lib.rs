@@ -4,9 +4,17 @@
 //!
 //! ## Features
 //!
-//! - **Rate Limiting**: Token bucket algorithm with Redis backend
-//! - **Quota Management**: Multi-tier quota system (Free, Pro, Enterprise, Partner)
+//! - **Rate Limiting**: Fixed window, sliding window log or GCRA per tier, with Redis backend
+//! - **Quota Management**: Built-in and custom tier plans with burst, entitlements, overage
+//!   policies (block / throttle / bill) and monthly usage reports
 //! - **Metrics**: Prometheus metrics for monitoring
+//! - **Access Control**: viewer / analyst / admin roles from API keys or JWT claims, with audit log
+//! - **Resource Limits**: Caps on body size, headers, decompression ratio and in-flight bodies
+//! - **Pool Sizing**: Configurable compute and capture pools with queue-depth metrics
+//! - **Capabilities**: `/api/v1/version` lists the analyzers, protocols and backends compiled in
+//! - **Self-check**: Readiness report covering config, Redis, capture permissions and profile JA4s
+//! - **Learner Labels**: 429s and auth failures recorded as weak labels (`learner-labels` feature)
+//! - **Backfill**: Admin jobs re-scoring stored flows after model or feed updates (`learner-labels` feature)
 //! - **High Performance**: Built on actix-web, 10x faster than Python FastAPI
 //! - **Type Safe**: Full Rust type safety
 //!
@@ -50,28 +58,88 @@
 //!
 //! ```bash
 //! cargo run --bin gateway --profile release-service
+//!
+//! # Readiness report as JSON; exits non-zero when not ready
+//! cargo run --bin selfcheck
+//!
+//! # Compare admission of the rate limiting algorithms on synthetic traffic
+//! cargo run --bin ratelimit-sim -- --pattern edge-burst
 //! ```
 
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
+pub mod algorithm;
 pub mod auth;
+#[cfg(feature = "learner-labels")]
+pub mod backfill;
 pub mod config;
 pub mod error;
+pub mod labels;
+pub mod limits;
 pub mod metrics;
 pub mod middleware;
 pub mod models;
+pub mod quota;
 pub mod rate_limit;
+pub mod rbac;
 pub mod routes;
+pub mod selfcheck;
 
 use actix_web::{web, App, HttpServer};
 use std::sync::Arc;
 use tracing::{info, warn};
 
+pub use algorithm::{RateLimitAlgorithm, RateLimitConfig};
 pub use config::GatewayConfig;
 pub use error::{GatewayError, Result};
+pub use labels::EnforcementReporter;
+pub use limits::{LimitViolation, RequestLimiter, RequestLimits};
 pub use models::QuotaTier;
+pub use quota::{OveragePolicy, TierPlan, TierRegistry};
 pub use rate_limit::RateLimiter;
+pub use rbac::{Permission, Rbac, RbacConfig, Role};
+pub use selfcheck::{CheckStatus, ReadinessReport};
+
+/// Capabilities of this gateway build
+///
+/// The `fingerprint` library's report plus the gateway's own optional backends.
+pub fn capabilities() -> fingerprint_core::CapabilityReport {
+    use fingerprint_core::{CapabilityKind, CapabilityReport, CapabilitySet};
+
+    let report = CapabilityReport {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        ..fingerprint::capabilities()
+    };
+    #[cfg(feature = "learner-labels")]
+    let report = if report.providers().contains_key("fingerprint-defense") {
+        report
+    } else {
+        report.with(fingerprint_defense::capabilities())
+    };
+    report.with(
+        CapabilitySet::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+            .optional(
+                CapabilityKind::Backend,
+                "redis-rate-limit",
+                "redis-backend",
+                cfg!(feature = "redis-backend"),
+            )
+            .optional(
+                CapabilityKind::Backend,
+                "in-memory-rate-limit",
+                "in-memory",
+                cfg!(feature = "in-memory"),
+            )
+            .optional(
+                CapabilityKind::Service,
+                "learner-labels",
+                "learner-labels",
+                cfg!(feature = "learner-labels"),
+            )
+            .into_vec(),
+    )
+}
 
 /// Run the API Gateway server
 ///
@@ -115,6 +183,27 @@ pub async fn run_server(config: GatewayConfig) -> Result<()> {
         env!("CARGO_PKG_VERSION")
     );
     info!("Configuration: {:?}", config);
+    fingerprint_core::i18n::set_locale(config.locale);
+    // pools are built lazily from the installed config, so this precedes any client use
+    if config.runtime.clone().install().is_err() {
+        warn!("Runtime configuration already installed; pool sizing from config is ignored");
+    }
+
+    // Startup self-check (report only; failures surface again below)
+    let report = selfcheck::run(&config).await;
+    report.log();
+    if !report.ready {
+        warn!(
+            "Self-check found {} failing check(s)",
+            report.failures().count()
+        );
+    }
+
+    // Initialize quota tier plans
+    let tiers = Arc::new(match &config.quota_tiers_path {
+        Some(path) => TierRegistry::open(path)?,
+        None => TierRegistry::new(),
+    });
 
     // Initialize rate limiter
     let rate_limiter = Arc::new(
@@ -123,15 +212,62 @@ pub async fn run_server(config: GatewayConfig) -> Result<()> {
             .map_err(|e| {
                 warn!("Failed to initialize rate limiter: {}", e);
                 e
-            })?,
+            })?
+            .with_config(config.rate_limit.clone())
+            .with_tiers(tiers),
     );
 
-    info!("Rate limiter initialized with Redis backend");
+    info!(
+        "Rate limiter initialized with Redis backend ({} by default)",
+        config.rate_limit.algorithm
+    );
 
     // Initialize API key validator
     let api_key_validator = Arc::new(auth::ApiKeyValidator::new());
     info!("API key validator initialized");
 
+    // Initialize access control
+    let rbac = web::Data::new(rbac::Rbac::new(config.rbac.clone()));
+    info!(
+        "RBAC initialized (JWT {})",
+        if config.rbac.jwt_secret.is_some() {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+
+    // Initialize request limits (shared across workers)
+    let limiter = web::Data::new(limits::RequestLimiter::new(config.limits.clone()));
+    let json_config = web::JsonConfig::default().limit(config.limits.max_decompressed_bytes);
+
+    // Initialize enforcement label reporting
+    #[cfg(feature = "learner-labels")]
+    let label_propagator = match &config.label_db_path {
+        Some(path) => {
+            let db = fingerprint_defense::FingerprintDatabase::open(path)
+                .map_err(GatewayError::ConfigError)?;
+            info!("Recording enforcement labels in {}", path);
+            Some(fingerprint_defense::labels::LabelPropagator::spawn(db)?)
+        }
+        None => None,
+    };
+    #[cfg(feature = "learner-labels")]
+    let reporter = label_propagator
+        .as_ref()
+        .map(|propagator| EnforcementReporter::new(propagator.sink()))
+        .unwrap_or_default();
+    #[cfg(not(feature = "learner-labels"))]
+    let reporter = {
+        if config.label_db_path.is_some() {
+            warn!("label_db_path is set but the learner-labels feature is disabled");
+        }
+        EnforcementReporter::disabled()
+    };
+
+    #[cfg(feature = "learner-labels")]
+    let backfill = web::Data::new(backfill::BackfillService::new(config.label_db_path.clone()));
+
     // Start HTTP server
     let host = config.host.clone();
     let port = config.port;
@@ -143,12 +279,20 @@ pub async fn run_server(config: GatewayConfig) -> Result<()> {
     );
 
     HttpServer::new(move || {
-        App::new()
+        let app = App::new()
             // Share state
             .app_data(web::Data::new(rate_limiter.clone()))
             .app_data(web::Data::new(api_key_validator.clone()))
             .app_data(web::Data::new(config.clone()))
+            .app_data(web::Data::new(reporter.clone()))
+            .app_data(rbac.clone())
+            .app_data(limiter.clone())
+            .app_data(json_config.clone());
+        #[cfg(feature = "learner-labels")]
+        let app = app.app_data(backfill.clone());
+        app
             // Middleware
+            .wrap(actix_web::middleware::from_fn(limits::enforce_limits))
             .wrap(tracing_actix_web::TracingLogger::default())
             .wrap(actix_cors::Cors::permissive())
             // Routes
@@ -157,8 +301,15 @@ pub async fn run_server(config: GatewayConfig) -> Result<()> {
     .workers(workers)
     .bind((host.as_str(), port))?
     .run()
-    .await
-    .map_err(Into::into)
+    .await?;
+
+    // Flush queued labels before exiting
+    #[cfg(feature = "learner-labels")]
+    if let Some(propagator) = label_propagator {
+        propagator.shutdown();
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
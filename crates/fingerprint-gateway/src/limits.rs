@@ -0,0 +1,514 @@
+//! Request resource limits
+//!
+//! Hard caps applied to every request before it reaches a handler:
+//! - header count and total header size
+//! - request body size (as received on the wire)
+//! - decompressed body size and compression ratio (zip-bomb protection)
+//! - number of request bodies buffered concurrently
+//!
+//! Violations are rejected with a typed [`LimitViolation`] (413, 431 or 503)
+//! and counted in `fingerprint_gateway_limit_rejections_total`.
+
+use crate::{error::GatewayError, metrics};
+use actix_web::{
+    body::{self, BodyStream, MessageBody},
+    dev::{self, Decompress, ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    middleware::Next,
+    web::{Bytes, Data},
+    Error, HttpMessage,
+};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Compressed bytes fed to the decoder per step; keeps the worst-case
+/// expansion of a single step to a few MiB.
+const DECODE_CHUNK_SIZE: usize = 2048;
+
+/// Configurable request limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestLimits {
+    /// Maximum request body size on the wire, in bytes
+    pub max_body_bytes: usize,
+
+    /// Maximum number of request headers
+    pub max_header_count: usize,
+
+    /// Maximum total size of request header names and values, in bytes
+    pub max_header_bytes: usize,
+
+    /// Maximum body size after `Content-Encoding` is removed, in bytes
+    pub max_decompressed_bytes: usize,
+
+    /// Maximum decompressed/compressed size ratio
+    pub max_decompression_ratio: usize,
+
+    /// Maximum number of request bodies buffered at the same time
+    pub max_in_flight_bodies: usize,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 1024 * 1024,
+            max_header_count: 100,
+            max_header_bytes: 16 * 1024,
+            max_decompressed_bytes: 4 * 1024 * 1024,
+            max_decompression_ratio: 20,
+            max_in_flight_bodies: 1024,
+        }
+    }
+}
+
+impl RequestLimits {
+    /// Load limits from environment variables, falling back to defaults
+    ///
+    /// Environment variables: `MAX_BODY_BYTES`, `MAX_HEADER_COUNT`,
+    /// `MAX_HEADER_BYTES`, `MAX_DECOMPRESSED_BYTES`, `MAX_DECOMPRESSION_RATIO`,
+    /// `MAX_IN_FLIGHT_BODIES`.
+    pub fn from_env() -> Self {
+        fn var(name: &str, default: usize) -> usize {
+            env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        let defaults = Self::default();
+        Self {
+            max_body_bytes: var("MAX_BODY_BYTES", defaults.max_body_bytes),
+            max_header_count: var("MAX_HEADER_COUNT", defaults.max_header_count),
+            max_header_bytes: var("MAX_HEADER_BYTES", defaults.max_header_bytes),
+            max_decompressed_bytes: var("MAX_DECOMPRESSED_BYTES", defaults.max_decompressed_bytes),
+            max_decompression_ratio: var(
+                "MAX_DECOMPRESSION_RATIO",
+                defaults.max_decompression_ratio,
+            ),
+            max_in_flight_bodies: var("MAX_IN_FLIGHT_BODIES", defaults.max_in_flight_bodies),
+        }
+    }
+
+    /// Check header count and total header size
+    pub fn check_headers(&self, headers: &header::HeaderMap) -> Result<(), LimitViolation> {
+        let count = headers.len();
+        if count > self.max_header_count {
+            return Err(LimitViolation::TooManyHeaders {
+                count,
+                limit: self.max_header_count,
+            });
+        }
+
+        let size: usize = headers
+            .iter()
+            .map(|(name, value)| name.as_str().len() + value.len())
+            .sum();
+        if size > self.max_header_bytes {
+            return Err(LimitViolation::HeadersTooLarge {
+                size,
+                limit: self.max_header_bytes,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Output budget for a compressed body of `compressed_len` bytes
+    ///
+    /// Small bodies may always expand up to `max_body_bytes`, so legitimate
+    /// highly compressible payloads are not rejected by the ratio check.
+    fn decompression_budget(&self, compressed_len: usize) -> (usize, LimitViolation) {
+        let by_ratio = compressed_len
+            .saturating_mul(self.max_decompression_ratio)
+            .max(self.max_body_bytes);
+
+        if by_ratio < self.max_decompressed_bytes {
+            (
+                by_ratio,
+                LimitViolation::DecompressionRatio {
+                    limit: self.max_decompression_ratio,
+                },
+            )
+        } else {
+            (
+                self.max_decompressed_bytes,
+                LimitViolation::DecompressedTooLarge {
+                    limit: self.max_decompressed_bytes,
+                },
+            )
+        }
+    }
+}
+
+/// A request that exceeded one of the [`RequestLimits`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LimitViolation {
+    /// Body larger than `max_body_bytes`
+    #[error("request body exceeds {limit} bytes")]
+    BodyTooLarge {
+        /// Configured limit
+        limit: usize,
+    },
+
+    /// More headers than `max_header_count`
+    #[error("{count} headers exceed the limit of {limit}")]
+    TooManyHeaders {
+        /// Headers received
+        count: usize,
+        /// Configured limit
+        limit: usize,
+    },
+
+    /// Headers larger than `max_header_bytes`
+    #[error("headers total {size} bytes, exceeding {limit} bytes")]
+    HeadersTooLarge {
+        /// Header bytes received
+        size: usize,
+        /// Configured limit
+        limit: usize,
+    },
+
+    /// Decompressed body larger than `max_decompressed_bytes`
+    #[error("decompressed body exceeds {limit} bytes")]
+    DecompressedTooLarge {
+        /// Configured limit
+        limit: usize,
+    },
+
+    /// Body expanded by more than `max_decompression_ratio`
+    #[error("body expands more than {limit}x when decompressed")]
+    DecompressionRatio {
+        /// Configured ratio
+        limit: usize,
+    },
+
+    /// `max_in_flight_bodies` reached
+    #[error("{limit} request bodies already in flight")]
+    TooManyInFlight {
+        /// Configured limit
+        limit: usize,
+    },
+}
+
+impl LimitViolation {
+    /// Metric label for this violation
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::BodyTooLarge { .. } => "body_size",
+            Self::TooManyHeaders { .. } => "header_count",
+            Self::HeadersTooLarge { .. } => "header_size",
+            Self::DecompressedTooLarge { .. } => "decompressed_size",
+            Self::DecompressionRatio { .. } => "decompression_ratio",
+            Self::TooManyInFlight { .. } => "in_flight",
+        }
+    }
+
+    /// HTTP status returned to the client
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::TooManyHeaders { .. } | Self::HeadersTooLarge { .. } => {
+                StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+            }
+            Self::TooManyInFlight { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            Self::BodyTooLarge { .. }
+            | Self::DecompressedTooLarge { .. }
+            | Self::DecompressionRatio { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+        }
+    }
+}
+
+/// Shared limit state, registered as app data for [`enforce_limits`]
+#[derive(Debug, Clone)]
+pub struct RequestLimiter {
+    limits: RequestLimits,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl RequestLimiter {
+    /// Create a limiter with the given limits
+    pub fn new(limits: RequestLimits) -> Self {
+        Self {
+            limits,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Configured limits
+    pub fn limits(&self) -> &RequestLimits {
+        &self.limits
+    }
+
+    /// Number of request bodies currently in flight
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Acquire)
+    }
+
+    /// Reserve an in-flight body slot, released when the guard is dropped
+    pub fn try_acquire(&self) -> Result<InFlightGuard, LimitViolation> {
+        let limit = self.limits.max_in_flight_bodies;
+        self.in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < limit).then_some(n + 1)
+            })
+            .map(|_| InFlightGuard {
+                counter: self.in_flight.clone(),
+            })
+            .map_err(|_| LimitViolation::TooManyInFlight { limit })
+    }
+
+    /// Read the request body within the configured limits
+    async fn read_body(&self, req: &mut ServiceRequest) -> Result<Bytes, Error> {
+        let limits = &self.limits;
+        let payload = BodyStream::new(req.take_payload());
+        let raw = match body::to_bytes_limited(payload, limits.max_body_bytes).await {
+            Ok(raw) => raw?,
+            Err(_) => {
+                return Err(reject(LimitViolation::BodyTooLarge {
+                    limit: limits.max_body_bytes,
+                }))
+            }
+        };
+
+        if !req.headers().contains_key(header::CONTENT_ENCODING) {
+            return Ok(raw);
+        }
+
+        let (budget, violation) = limits.decompression_budget(raw.len());
+        let chunks: Vec<_> = (0..raw.len())
+            .step_by(DECODE_CHUNK_SIZE)
+            .map(|start| Ok(raw.slice(start..raw.len().min(start + DECODE_CHUNK_SIZE))))
+            .collect();
+        let decoder = Decompress::from_headers(futures::stream::iter(chunks), req.headers());
+
+        match body::to_bytes_limited(BodyStream::new(decoder), budget).await {
+            Ok(decoded) => Ok(decoded?),
+            Err(_) => Err(reject(violation)),
+        }
+    }
+}
+
+/// Releases an in-flight body slot on drop
+#[derive(Debug)]
+pub struct InFlightGuard {
+    counter: Arc<AtomicUsize>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn reject(violation: LimitViolation) -> Error {
+    warn!(limit = violation.kind(), "Request rejected: {}", violation);
+    metrics::record_limit_rejection(violation.kind());
+    GatewayError::LimitExceeded(violation).into()
+}
+
+fn has_body(req: &ServiceRequest) -> bool {
+    let headers = req.headers();
+    headers.contains_key(header::TRANSFER_ENCODING)
+        || headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .is_some_and(|len| len > 0)
+}
+
+/// Middleware enforcing [`RequestLimits`]
+///
+/// Use with `actix_web::middleware::from_fn` and register a
+/// `web::Data<RequestLimiter>`; without one, requests pass through unchecked.
+/// Compressed bodies are decoded here and handed on without
+/// `Content-Encoding`, so extractors only ever see bounded plain payloads.
+pub async fn enforce_limits(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let Some(limiter) = req.app_data::<Data<RequestLimiter>>().cloned() else {
+        return next.call(req).await;
+    };
+    let limits = limiter.limits();
+
+    limits.check_headers(req.headers()).map_err(reject)?;
+
+    if !has_body(&req) {
+        return next.call(req).await;
+    }
+
+    if let Some(len) = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+    {
+        if len > limits.max_body_bytes as u64 {
+            return Err(reject(LimitViolation::BodyTooLarge {
+                limit: limits.max_body_bytes,
+            }));
+        }
+    }
+
+    let _guard = limiter.try_acquire().map_err(reject)?;
+    let body = limiter.read_body(&mut req).await?;
+
+    let headers = req.headers_mut();
+    headers.remove(header::CONTENT_ENCODING);
+    headers.remove(header::TRANSFER_ENCODING);
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+    req.set_payload(dev::Payload::from(body));
+
+    next.call(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{self as actix_test, TestRequest};
+    use actix_web::{middleware::from_fn, web, App, HttpResponse};
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    fn limits() -> RequestLimits {
+        RequestLimits {
+            max_body_bytes: 1024,
+            max_header_count: 8,
+            max_header_bytes: 512,
+            max_decompressed_bytes: 64 * 1024,
+            max_decompression_ratio: 10,
+            max_in_flight_bodies: 2,
+        }
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    async fn echo_len(body: Bytes) -> HttpResponse {
+        HttpResponse::Ok().body(body.len().to_string())
+    }
+
+    macro_rules! app {
+        () => {
+            actix_test::init_service(
+                App::new()
+                    .app_data(Data::new(RequestLimiter::new(limits())))
+                    .wrap(from_fn(enforce_limits))
+                    .route("/", web::post().to(echo_len)),
+            )
+            .await
+        };
+    }
+
+    macro_rules! rejection {
+        ($app:expr, $req:expr) => {
+            match actix_test::try_call_service(&$app, $req).await {
+                Ok(resp) => panic!("expected rejection, got {}", resp.status()),
+                Err(err) => match err.as_error::<GatewayError>() {
+                    Some(GatewayError::LimitExceeded(violation)) => violation.clone(),
+                    other => panic!("unexpected error: {:?}", other),
+                },
+            }
+        };
+    }
+
+    #[actix_web::test]
+    async fn test_body_and_header_limits() {
+        let app = app!();
+
+        let req = TestRequest::post()
+            .uri("/")
+            .set_payload(vec![b'a'; 512])
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::post()
+            .uri("/")
+            .set_payload(vec![b'a'; 2048])
+            .to_request();
+        assert_eq!(
+            rejection!(app, req),
+            LimitViolation::BodyTooLarge { limit: 1024 }
+        );
+
+        let mut req = TestRequest::post().uri("/");
+        for i in 0..10 {
+            req = req.insert_header((format!("x-extra-{}", i), "1"));
+        }
+        let violation = rejection!(app, req.to_request());
+        assert_eq!(violation.kind(), "header_count");
+        assert_eq!(
+            violation.status_code(),
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_compressed_body_is_bounded() {
+        let app = app!();
+
+        // Modest expansion is decoded and handed on as plain bytes
+        let req = TestRequest::post()
+            .uri("/")
+            .insert_header((header::CONTENT_ENCODING, "gzip"))
+            .set_payload(gzip(&[b'a'; 800]))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(actix_test::read_body(resp).await, Bytes::from("800"));
+
+        // A small bomb is cut off by the ratio limit
+        let bomb = gzip(&vec![0u8; 512 * 1024]);
+        assert!(bomb.len() <= 1024);
+        let req = TestRequest::post()
+            .uri("/")
+            .insert_header((header::CONTENT_ENCODING, "gzip"))
+            .set_payload(bomb)
+            .to_request();
+        assert_eq!(
+            rejection!(app, req),
+            LimitViolation::DecompressionRatio { limit: 10 }
+        );
+    }
+
+    #[test]
+    fn test_in_flight_slots_are_released() {
+        let limiter = RequestLimiter::new(limits());
+
+        let first = limiter.try_acquire().unwrap();
+        let _second = limiter.try_acquire().unwrap();
+        assert_eq!(
+            limiter.try_acquire().unwrap_err(),
+            LimitViolation::TooManyInFlight { limit: 2 }
+        );
+
+        drop(first);
+        assert_eq!(limiter.in_flight(), 1);
+        assert!(limiter.try_acquire().is_ok());
+    }
+
+    #[test]
+    fn test_violation_status_codes() {
+        assert_eq!(
+            LimitViolation::TooManyHeaders { count: 9, limit: 8 }.status_code(),
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+        );
+        assert_eq!(
+            LimitViolation::DecompressionRatio { limit: 10 }.status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert_eq!(
+            LimitViolation::TooManyInFlight { limit: 2 }.kind(),
+            "in_flight"
+        );
+    }
+}
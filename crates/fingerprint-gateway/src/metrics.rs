@@ -5,10 +5,13 @@
 //! - Rate limit statistics  
 //! - Response time histograms
 //! - Redis connection health
+//! - Resource limit rejections
+//! - Thread pool queue depths (compute pool, HTTP client and capture runtimes)
 
 use prometheus::{
-    opts, register_histogram_vec, register_int_counter_vec, register_int_gauge, Encoder,
-    HistogramVec, IntCounterVec, IntGauge, TextEncoder,
+    opts, register_histogram_vec, register_int_counter_vec, register_int_gauge,
+    register_int_gauge_vec, Encoder, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec,
+    TextEncoder,
 };
 use std::time::Instant;
 
@@ -49,6 +52,29 @@ lazy_static::lazy_static! {
         opts!("fingerprint_gateway_redis_operations_total", "Total Redis operations"),
         &["operation", "status"]
     ).unwrap();
+
+    /// Requests rejected by resource limits, by limit
+    pub static ref LIMIT_REJECTIONS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        opts!("fingerprint_gateway_limit_rejections_total", "Requests rejected by resource limits"),
+        &["limit"]
+    ).unwrap();
+
+    /// Tasks or jobs waiting for a thread, by pool
+    pub static ref POOL_QUEUE_DEPTH: IntGaugeVec = register_int_gauge_vec!(
+        opts!("fingerprint_gateway_pool_queue_depth", "Tasks or jobs waiting for a thread"),
+        &["pool"]
+    ).unwrap();
+
+    /// Threads of each pool, and busy threads or live tasks, by pool and state
+    pub static ref POOL_THREADS: IntGaugeVec = register_int_gauge_vec!(
+        opts!("fingerprint_gateway_pool_threads", "Pool threads (total) and work in progress (busy)"),
+        &["pool", "state"]
+    ).unwrap();
+
+    /// Jobs rejected because the compute queue was full
+    pub static ref COMPUTE_REJECTED_TOTAL: IntGauge = register_int_gauge!(
+        opts!("fingerprint_gateway_compute_rejected_total", "Compute jobs rejected by a full queue")
+    ).unwrap();
 }
 
 /// Request timer for tracking request duration
@@ -107,13 +133,54 @@ pub fn record_redis_operation(operation: &str, success: bool) {
         .inc();
 }
 
+/// Record a request rejected by a resource limit
+pub fn record_limit_rejection(limit: &str) {
+    LIMIT_REJECTIONS_TOTAL.with_label_values(&[limit]).inc();
+}
+
 /// Update Redis connection count
 pub fn update_redis_connections(count: i64) {
     REDIS_CONNECTIONS_ACTIVE.set(count);
 }
 
+/// Refresh pool gauges from the pools that have been started
+pub fn update_pool_metrics() {
+    use fingerprint_core::runtime::{capture_runtime_stats, compute_pool_stats, RuntimeStats};
+
+    if let Some(stats) = compute_pool_stats() {
+        POOL_QUEUE_DEPTH
+            .with_label_values(&["compute"])
+            .set(stats.queued as i64);
+        POOL_THREADS
+            .with_label_values(&["compute", "total"])
+            .set(stats.threads as i64);
+        POOL_THREADS
+            .with_label_values(&["compute", "busy"])
+            .set(stats.running as i64);
+        COMPUTE_REJECTED_TOTAL.set(stats.rejected as i64);
+    }
+    let runtimes: [(&str, Option<RuntimeStats>); 2] = [
+        ("http_client", fingerprint::http_runtime_stats()),
+        ("capture", capture_runtime_stats()),
+    ];
+    for (pool, stats) in runtimes {
+        if let Some(stats) = stats {
+            POOL_QUEUE_DEPTH
+                .with_label_values(&[pool])
+                .set(stats.global_queue_depth as i64);
+            POOL_THREADS
+                .with_label_values(&[pool, "total"])
+                .set(stats.workers as i64);
+            POOL_THREADS
+                .with_label_values(&[pool, "busy"])
+                .set(stats.alive_tasks as i64);
+        }
+    }
+}
+
 /// Gather all metrics in Prometheus text format
 pub fn gather_metrics() -> Result<String, Box<dyn std::error::Error>> {
+    update_pool_metrics();
     let encoder = TextEncoder::new();
     let metric_families = prometheus::gather();
     let mut buffer = vec![];
@@ -1,10 +1,12 @@
 //! Data models for API Gateway
 
+use crate::quota::OveragePolicy;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Quota tier enumeration
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub enum QuotaTier {
     /// Free tier: 100 req/min, 50K/month
@@ -18,6 +20,19 @@ pub enum QuotaTier {
 }
 
 impl QuotaTier {
+    /// Every tier
+    pub const ALL: [Self; 4] = [Self::Free, Self::Pro, Self::Enterprise, Self::Partner];
+
+    /// Lowercase name, also the name of the tier's built-in plan
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Free => "free",
+            Self::Pro => "pro",
+            Self::Enterprise => "enterprise",
+            Self::Partner => "partner",
+        }
+    }
+
     /// Get the per-minute rate limit for this tier
     pub fn minute_limit(&self) -> Option<u32> {
         match self {
@@ -42,6 +57,21 @@ impl QuotaTier {
     }
 }
 
+impl std::str::FromStr for QuotaTier {
+    type Err = String;
+
+    /// Tier name, case-insensitive
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "free" => Ok(Self::Free),
+            "pro" => Ok(Self::Pro),
+            "enterprise" => Ok(Self::Enterprise),
+            "partner" => Ok(Self::Partner),
+            other => Err(format!("unknown quota tier '{}'", other)),
+        }
+    }
+}
+
 /// Rate limit check request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RateLimitRequest {
@@ -53,6 +83,16 @@ pub struct RateLimitRequest {
 
     /// Client IP address (optional)
     pub client_ip: Option<String>,
+
+    /// Fingerprints the edge observed for this client, by type (e.g. `tls` → JA4)
+    ///
+    /// Labelled as suspicious when the request is blocked.
+    #[serde(default)]
+    pub fingerprints: HashMap<String, String>,
+
+    /// Feature the request uses; denied unless the key's plan entitles it
+    #[serde(default)]
+    pub feature: Option<String>,
 }
 
 /// Rate limit check response
@@ -75,6 +115,10 @@ pub struct RateLimitResponse {
 
     /// Error message if not allowed
     pub error: Option<String>,
+
+    /// Overage policy that admitted the request past the monthly quota
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overage: Option<OveragePolicy>,
 }
 
 /// Rate limit status for an API key
@@ -105,6 +149,56 @@ pub struct RateLimitStatus {
     pub month_reset_at: DateTime<Utc>,
 }
 
+/// Requests of an API key in one calendar month
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonthlyUsage {
+    /// Month as `YYYY-MM` (UTC)
+    pub month: String,
+
+    /// Admitted requests, overage included
+    pub allowed: u64,
+
+    /// Denied requests
+    pub denied: u64,
+
+    /// Requests admitted past the monthly quota at a throttled rate
+    pub throttled: u64,
+
+    /// Requests admitted past the monthly quota and billable
+    pub billable_overage: u64,
+}
+
+/// Consumption of an API key against its plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// API key
+    pub api_key: String,
+
+    /// Plan in effect
+    pub plan: String,
+
+    /// Per-minute limit, burst included (`None`: unlimited)
+    pub minute_limit: Option<u32>,
+
+    /// Monthly quota (`None`: unlimited)
+    pub monthly_quota: Option<u64>,
+
+    /// Handling of requests past the monthly quota
+    pub overage: OveragePolicy,
+
+    /// Features the plan entitles the key to
+    pub features: Vec<String>,
+
+    /// Quota left this month (`None`: unlimited)
+    pub quota_remaining: Option<u64>,
+
+    /// This month and the preceding ones, newest first
+    pub months: Vec<MonthlyUsage>,
+
+    /// When the current month resets
+    pub month_reset_at: DateTime<Utc>,
+}
+
 /// Health check response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HealthResponse {
@@ -164,6 +258,19 @@ mod tests {
         assert!(QuotaTier::Partner.is_unlimited());
     }
 
+    #[test]
+    fn test_rate_limit_request_fingerprints_optional() {
+        let req: RateLimitRequest =
+            serde_json::from_str(r#"{"api_key":"k","endpoint":"/x","client_ip":null}"#).unwrap();
+        assert!(req.fingerprints.is_empty());
+
+        let req: RateLimitRequest = serde_json::from_str(
+            r#"{"api_key":"k","endpoint":"/x","client_ip":null,"fingerprints":{"tls":"t13d"}}"#,
+        )
+        .unwrap();
+        assert_eq!(req.fingerprints["tls"], "t13d");
+    }
+
     #[test]
     fn test_quota_tier_serialization() {
         let tier = QuotaTier::Pro;
@@ -0,0 +1,412 @@
+//! Quota tier plans and overage policies
+//!
+//! A [`TierPlan`] holds the limits and feature entitlements of a tier. The four
+//! [`QuotaTier`]s are built-in plans whose limits can be changed but which cannot be
+//! deleted; further plans are created at runtime and assigned to individual API keys.
+//! [`TierRegistry`] resolves the plan of a key: its assignment if it has one, otherwise
+//! the plan of its tier.
+//!
+//! Once a key's monthly quota is spent, its plan's [`OveragePolicy`] decides whether
+//! further requests are blocked, throttled to a lower per-minute limit, or admitted and
+//! counted as billable overage.
+
+use crate::error::{GatewayError, Result};
+use crate::models::QuotaTier;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use tracing::warn;
+
+/// What happens to requests past the monthly quota
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OveragePolicy {
+    /// Deny until the month resets
+    #[default]
+    Block,
+    /// Admit at the plan's `throttled_minute_limit`
+    Throttle,
+    /// Admit at the full rate and count the requests as billable overage
+    Bill,
+}
+
+/// Limits and entitlements of a tier
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TierPlan {
+    /// Plan name; built-in plans are named after their [`QuotaTier`] in lowercase
+    pub name: String,
+    /// Requests per minute (`None`: unlimited)
+    pub minute_limit: Option<u32>,
+    /// Requests a client may spend within one minute on top of `minute_limit`
+    #[serde(default)]
+    pub burst: u32,
+    /// Requests per calendar month (`None`: unlimited)
+    pub monthly_quota: Option<u64>,
+    /// Handling of requests past `monthly_quota`
+    #[serde(default)]
+    pub overage: OveragePolicy,
+    /// Per-minute limit past the monthly quota under [`OveragePolicy::Throttle`]
+    #[serde(default)]
+    pub throttled_minute_limit: u32,
+    /// Features the plan entitles its keys to
+    #[serde(default)]
+    pub features: BTreeSet<String>,
+}
+
+impl TierPlan {
+    /// Built-in plan of `tier`, with the limits of [`QuotaTier`]
+    pub fn builtin(tier: QuotaTier) -> Self {
+        let minute_limit = tier.minute_limit();
+        let features: &[&str] = match tier {
+            QuotaTier::Free => &["analysis"],
+            QuotaTier::Pro => &["analysis", "batch"],
+            QuotaTier::Enterprise | QuotaTier::Partner => &["analysis", "batch", "export"],
+        };
+        Self {
+            name: tier.as_str().to_string(),
+            minute_limit,
+            burst: 0,
+            monthly_quota: tier.monthly_quota(),
+            overage: OveragePolicy::Block,
+            throttled_minute_limit: minute_limit.map_or(0, |limit| (limit / 10).max(1)),
+            features: features.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    /// Whether neither a per-minute limit nor a monthly quota applies
+    pub fn is_unlimited(&self) -> bool {
+        self.minute_limit.is_none() && self.monthly_quota.is_none()
+    }
+
+    /// Per-minute capacity, burst included
+    pub fn admission_limit(&self) -> u32 {
+        self.minute_limit
+            .map_or(u32::MAX, |limit| limit.saturating_add(self.burst))
+    }
+
+    /// Whether keys on this plan may use `feature`
+    pub fn entitles(&self, feature: &str) -> bool {
+        self.features.contains(feature)
+    }
+
+    /// Decision for a request after `month_count` requests this month
+    pub fn overage_decision(&self, month_count: u64) -> OverageDecision {
+        match self.monthly_quota {
+            Some(quota) if month_count >= quota => match self.overage {
+                OveragePolicy::Block => OverageDecision::Deny,
+                OveragePolicy::Throttle => OverageDecision::Throttle(
+                    self.throttled_minute_limit.min(self.admission_limit()),
+                ),
+                OveragePolicy::Bill => OverageDecision::Bill,
+            },
+            _ => OverageDecision::Within,
+        }
+    }
+
+    /// Check the plan for values the limiter cannot work with
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        let valid_name = !self.name.is_empty()
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if !valid_name {
+            return Err(format!(
+                "tier name '{}' must be lowercase letters, digits, '-' or '_'",
+                self.name
+            ));
+        }
+        if self.minute_limit == Some(0) {
+            return Err("minute_limit must be at least 1".to_string());
+        }
+        if self.overage == OveragePolicy::Throttle && self.throttled_minute_limit == 0 {
+            return Err(
+                "throttle overage needs a throttled_minute_limit of at least 1".to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of the monthly quota check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverageDecision {
+    /// Quota not spent (or unlimited)
+    Within,
+    /// Quota spent under [`OveragePolicy::Block`]
+    Deny,
+    /// Quota spent; admit at this per-minute limit
+    Throttle(u32),
+    /// Quota spent; admit and bill
+    Bill,
+}
+
+/// Persisted state of a [`TierRegistry`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TierState {
+    #[serde(default)]
+    plans: BTreeMap<String, TierPlan>,
+    /// Plan name per API key
+    #[serde(default)]
+    assignments: HashMap<String, String>,
+}
+
+/// Tier plans and per-key plan assignments
+///
+/// With a path, the registry is loaded from and saved to a JSON file after every change.
+#[derive(Debug)]
+pub struct TierRegistry {
+    state: Mutex<TierState>,
+    path: Option<PathBuf>,
+}
+
+impl Default for TierRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TierRegistry {
+    /// Registry holding the built-in plans only
+    pub fn new() -> Self {
+        let plans = QuotaTier::ALL
+            .iter()
+            .map(|&tier| (tier.as_str().to_string(), TierPlan::builtin(tier)))
+            .collect();
+        Self {
+            state: Mutex::new(TierState {
+                plans,
+                assignments: HashMap::new(),
+            }),
+            path: None,
+        }
+    }
+
+    /// Registry persisted at `path`, starting from its contents if the file exists
+    ///
+    /// Built-in plans missing from the file are added with their default limits.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let registry = Self {
+            path: Some(path.clone()),
+            ..Self::new()
+        };
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)?;
+            let stored: TierState = serde_json::from_str(&contents).map_err(|e| {
+                GatewayError::ConfigError(format!("invalid tier file {}: {}", path.display(), e))
+            })?;
+            let mut state = registry.lock();
+            state.plans.extend(stored.plans);
+            state.assignments = stored.assignments;
+        }
+        Ok(registry)
+    }
+
+    /// Every plan, by name
+    pub fn list(&self) -> Vec<TierPlan> {
+        self.lock().plans.values().cloned().collect()
+    }
+
+    /// Plan named `name`
+    pub fn get(&self, name: &str) -> Option<TierPlan> {
+        self.lock().plans.get(name).cloned()
+    }
+
+    /// Add a plan; fails if the name is taken
+    pub fn create(&self, plan: TierPlan) -> Result<TierPlan> {
+        plan.validate().map_err(GatewayError::InvalidRequest)?;
+        let mut state = self.lock();
+        if state.plans.contains_key(&plan.name) {
+            return Err(GatewayError::InvalidRequest(format!(
+                "tier '{}' already exists",
+                plan.name
+            )));
+        }
+        state.plans.insert(plan.name.clone(), plan.clone());
+        self.save(&state)?;
+        Ok(plan)
+    }
+
+    /// Replace the plan named `name`
+    pub fn update(&self, name: &str, plan: TierPlan) -> Result<TierPlan> {
+        if plan.name != name {
+            return Err(GatewayError::InvalidRequest(
+                "tier name cannot be changed".to_string(),
+            ));
+        }
+        plan.validate().map_err(GatewayError::InvalidRequest)?;
+        let mut state = self.lock();
+        if !state.plans.contains_key(name) {
+            return Err(GatewayError::NotFound(format!("tier '{}'", name)));
+        }
+        state.plans.insert(plan.name.clone(), plan.clone());
+        self.save(&state)?;
+        Ok(plan)
+    }
+
+    /// Remove a plan that is neither built in nor assigned to a key
+    pub fn delete(&self, name: &str) -> Result<()> {
+        if name.parse::<QuotaTier>().is_ok() {
+            return Err(GatewayError::InvalidRequest(format!(
+                "built-in tier '{}' cannot be deleted",
+                name
+            )));
+        }
+        let mut state = self.lock();
+        let assigned = state.assignments.values().filter(|n| *n == name).count();
+        if assigned > 0 {
+            return Err(GatewayError::InvalidRequest(format!(
+                "tier '{}' is assigned to {} API key(s)",
+                name, assigned
+            )));
+        }
+        if state.plans.remove(name).is_none() {
+            return Err(GatewayError::NotFound(format!("tier '{}'", name)));
+        }
+        self.save(&state)
+    }
+
+    /// Put `api_key` on the plan named `name`
+    pub fn assign(&self, api_key: &str, name: &str) -> Result<()> {
+        let mut state = self.lock();
+        if !state.plans.contains_key(name) {
+            return Err(GatewayError::NotFound(format!("tier '{}'", name)));
+        }
+        state
+            .assignments
+            .insert(api_key.to_string(), name.to_string());
+        self.save(&state)
+    }
+
+    /// Return `api_key` to the plan of its tier
+    pub fn unassign(&self, api_key: &str) -> Result<()> {
+        let mut state = self.lock();
+        if state.assignments.remove(api_key).is_some() {
+            self.save(&state)?;
+        }
+        Ok(())
+    }
+
+    /// Plan in effect for `api_key` of `tier`
+    pub fn plan_for(&self, api_key: &str, tier: QuotaTier) -> TierPlan {
+        let state = self.lock();
+        state
+            .assignments
+            .get(api_key)
+            .and_then(|name| state.plans.get(name))
+            .or_else(|| state.plans.get(tier.as_str()))
+            .cloned()
+            .unwrap_or_else(|| TierPlan::builtin(tier))
+    }
+
+    fn save(&self, state: &TierState) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(state)
+            .map_err(|e| GatewayError::InternalError(e.to_string()))?;
+        // write-then-rename so a crash never leaves a truncated file
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)
+            .and_then(|_| std::fs::rename(&tmp, path))
+            .map_err(|e| {
+                warn!("Failed to save tier registry to {}: {}", path.display(), e);
+                GatewayError::IoError(e)
+            })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TierState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn team_plan() -> TierPlan {
+        TierPlan {
+            name: "team".to_string(),
+            minute_limit: Some(500),
+            burst: 50,
+            monthly_quota: Some(200_000),
+            overage: OveragePolicy::Throttle,
+            throttled_minute_limit: 20,
+            features: ["analysis".to_string()].into(),
+        }
+    }
+
+    #[test]
+    fn test_overage_decisions() {
+        let mut plan = team_plan();
+        assert_eq!(plan.admission_limit(), 550);
+        assert_eq!(plan.overage_decision(199_999), OverageDecision::Within);
+        assert_eq!(
+            plan.overage_decision(200_000),
+            OverageDecision::Throttle(20)
+        );
+
+        plan.overage = OveragePolicy::Bill;
+        assert_eq!(plan.overage_decision(250_000), OverageDecision::Bill);
+        plan.overage = OveragePolicy::Block;
+        assert_eq!(plan.overage_decision(250_000), OverageDecision::Deny);
+
+        let enterprise = TierPlan::builtin(QuotaTier::Enterprise);
+        assert!(enterprise.is_unlimited());
+        assert_eq!(
+            enterprise.overage_decision(u64::MAX),
+            OverageDecision::Within
+        );
+        assert!(enterprise.entitles("export"));
+        assert!(!TierPlan::builtin(QuotaTier::Free).entitles("export"));
+    }
+
+    #[test]
+    fn test_registry_crud_and_assignment() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tiers.json");
+        let registry = TierRegistry::open(&path).unwrap();
+        assert_eq!(registry.list().len(), 4);
+
+        registry.create(team_plan()).unwrap();
+        assert!(registry.create(team_plan()).is_err());
+        registry.assign("sk_live_team", "team").unwrap();
+        assert_eq!(
+            registry.plan_for("sk_live_team", QuotaTier::Pro).name,
+            "team"
+        );
+        assert_eq!(
+            registry.plan_for("sk_live_other", QuotaTier::Pro).name,
+            "pro"
+        );
+        assert!(registry.delete("team").is_err());
+        assert!(registry.delete("free").is_err());
+
+        let mut free = registry.get("free").unwrap();
+        free.minute_limit = Some(60);
+        registry.update("free", free).unwrap();
+        assert!(registry.update("pro", team_plan()).is_err());
+        let invalid = TierPlan {
+            minute_limit: Some(0),
+            ..team_plan()
+        };
+        assert!(registry.update("team", invalid).is_err());
+
+        // survives a restart
+        let reopened = TierRegistry::open(&path).unwrap();
+        assert_eq!(reopened.get("free").unwrap().minute_limit, Some(60));
+        assert_eq!(
+            reopened.plan_for("sk_live_team", QuotaTier::Pro),
+            team_plan()
+        );
+
+        reopened.unassign("sk_live_team").unwrap();
+        reopened.delete("team").unwrap();
+        assert!(matches!(
+            reopened.delete("team"),
+            Err(GatewayError::NotFound(_))
+        ));
+    }
+}
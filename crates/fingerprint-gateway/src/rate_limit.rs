@@ -1,16 +1,35 @@
-//! Rate limiting module using Redis-backed fixed-window counters
-
+//! Rate limiting module using Redis
+//!
+//! Per-minute limits use fixed-window counters, a sliding window log or GCRA, chosen
+//! per tier by [`RateLimitConfig`]; monthly quotas are always counters. Limits come
+//! from the key's [`TierPlan`], and every decision is added to the key's monthly usage
+//! rollup (`usage:{key}:{YYYY-MM}`), which rate limit resets leave alone.
+
+use crate::algorithm::{
+    RateLimitAlgorithm, RateLimitConfig, GCRA_SCRIPT, SLIDING_WINDOW_LOG_SCRIPT,
+};
 use crate::error::{GatewayError, Result};
-use crate::models::{QuotaTier, RateLimitResponse, RateLimitStatus};
+use crate::models::{MonthlyUsage, QuotaTier, RateLimitResponse, RateLimitStatus, UsageReport};
+use crate::quota::{OverageDecision, OveragePolicy, TierPlan, TierRegistry};
 use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
 use redis::AsyncCommands;
+use std::collections::HashMap;
+use std::sync::Arc;
 use tracing::{debug, error, warn};
 
 pub use crate::models::QuotaTier as QuotaTierEnum;
 
+/// Length of the per-minute window
+const MINUTE_MS: u64 = 60_000;
+
+/// Monthly usage rollups are kept for about 13 months
+const USAGE_TTL_SECS: i64 = 400 * 24 * 3600;
+
 /// Rate limiter with Redis backend
 pub struct RateLimiter {
     redis_pool: bb8::Pool<bb8_redis::RedisConnectionManager>,
+    config: RateLimitConfig,
+    tiers: Arc<TierRegistry>,
 }
 
 fn next_minute_reset(now: DateTime<Utc>) -> DateTime<Utc> {
@@ -32,6 +51,94 @@ fn next_month_reset(now: DateTime<Utc>) -> DateTime<Utc> {
         .unwrap_or(now + Duration::days(31))
 }
 
+/// `count` months ending with the month of `now`, newest first, as `YYYY-MM`
+fn recent_months(now: DateTime<Utc>, count: usize) -> Vec<String> {
+    let (mut year, mut month) = (now.year(), now.month());
+    let mut months = Vec::with_capacity(count);
+    for _ in 0..count {
+        months.push(format!("{:04}-{:02}", year, month));
+        if month == 1 {
+            year -= 1;
+            month = 12;
+        } else {
+            month -= 1;
+        }
+    }
+    months
+}
+
+fn usage_key(api_key: &str, month: &str) -> String {
+    format!("usage:{}:{}", api_key, month)
+}
+
+/// Denial for a spent monthly quota under [`OveragePolicy::Block`]
+fn monthly_quota_exceeded(
+    api_key: &str,
+    quota_tier: QuotaTier,
+    plan: &TierPlan,
+    month_count: u64,
+    now: DateTime<Utc>,
+) -> RateLimitResponse {
+    let monthly_quota = plan.monthly_quota.unwrap_or_default();
+
+    warn!(
+        "Monthly quota exceeded for API key {} (plan: {}): {}/{}",
+        api_key, plan.name, month_count, monthly_quota
+    );
+
+    RateLimitResponse {
+        allowed: false,
+        quota_tier,
+        remaining: Some(0),
+        limit: Some(plan.admission_limit()),
+        reset_at: Some(next_month_reset(now)),
+        error: Some(format!(
+            "Monthly quota exceeded: {}/{} requests",
+            month_count, monthly_quota
+        )),
+        overage: None,
+    }
+}
+
+/// Count a decision against the monthly quota and the usage rollup
+async fn record_usage(
+    conn: &mut redis::aio::MultiplexedConnection,
+    api_key: &str,
+    now: DateTime<Utc>,
+    response: &RateLimitResponse,
+) -> Result<()> {
+    let month_key = format!("ratelimit:{}:month:{}", api_key, now.format("%Y%m"));
+    let usage_key = usage_key(api_key, &now.format("%Y-%m").to_string());
+
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    if response.allowed {
+        pipe.incr(&month_key, 1)
+            .ignore()
+            .expire(&month_key, 32 * 24 * 3600) // ~1 month TTL
+            .ignore()
+            .hincr(&usage_key, "allowed", 1)
+            .ignore();
+        match response.overage {
+            Some(OveragePolicy::Throttle) => {
+                pipe.hincr(&usage_key, "throttled", 1).ignore();
+            }
+            Some(OveragePolicy::Bill) => {
+                pipe.hincr(&usage_key, "billable_overage", 1).ignore();
+            }
+            _ => {}
+        }
+    } else {
+        pipe.hincr(&usage_key, "denied", 1).ignore();
+    }
+    pipe.expire(&usage_key, USAGE_TTL_SECS).ignore();
+
+    pipe.query_async::<()>(conn).await.map_err(|e| {
+        error!("Failed to record usage: {}", e);
+        GatewayError::RedisError(e)
+    })
+}
+
 impl RateLimiter {
     /// Create a new rate limiter with Redis backend
     ///
@@ -74,7 +181,33 @@ impl RateLimiter {
 
         debug!("Rate limiter initialized with Redis backend");
 
-        Ok(Self { redis_pool: pool })
+        Ok(Self {
+            redis_pool: pool,
+            config: RateLimitConfig::default(),
+            tiers: Arc::new(TierRegistry::new()),
+        })
+    }
+
+    /// Use the per-tier algorithms of `config`
+    pub fn with_config(mut self, config: RateLimitConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Take limits from the plans of `tiers`
+    pub fn with_tiers(mut self, tiers: Arc<TierRegistry>) -> Self {
+        self.tiers = tiers;
+        self
+    }
+
+    /// Algorithm selection in use
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Tier plans in use
+    pub fn tiers(&self) -> &TierRegistry {
+        &self.tiers
     }
 
     /// Check if a request is allowed under rate limits
@@ -92,16 +225,29 @@ impl RateLimiter {
         api_key: &str,
         quota_tier: QuotaTier,
     ) -> Result<RateLimitResponse> {
-        // Unlimited tiers always allow
-        if quota_tier.is_unlimited() {
-            return Ok(RateLimitResponse {
+        let plan = self.tiers.plan_for(api_key, quota_tier);
+        let now = Utc::now();
+
+        // Unlimited plans always allow; usage is recorded on a best-effort basis
+        if plan.is_unlimited() {
+            let response = RateLimitResponse {
                 allowed: true,
                 quota_tier,
                 remaining: None,
                 limit: None,
                 reset_at: None,
                 error: None,
-            });
+                overage: None,
+            };
+            match self.redis_pool.get().await {
+                Ok(mut conn) => {
+                    if let Err(e) = record_usage(&mut conn, api_key, now, &response).await {
+                        warn!("Usage of unlimited API key {} not recorded: {}", api_key, e);
+                    }
+                }
+                Err(e) => warn!("Usage of unlimited API key {} not recorded: {}", api_key, e),
+            }
+            return Ok(response);
         }
 
         let mut conn = self.redis_pool.get().await.map_err(|e| {
@@ -116,20 +262,64 @@ impl RateLimiter {
 
         crate::metrics::record_redis_operation("get_connection", true);
 
-        let now = Utc::now();
+        // Check monthly quota; a denied request does not consume per-minute capacity
+        let month_key = format!("ratelimit:{}:month:{}", api_key, now.format("%Y%m"));
+        let month_count: u64 = conn.get(&month_key).await.map_err(|e| {
+            error!("Failed to read monthly rate limit counter: {}", e);
+            GatewayError::RedisError(e)
+        })?;
+
+        let decision = plan.overage_decision(month_count);
+        let minute_limit = match decision {
+            OverageDecision::Deny => {
+                let response = monthly_quota_exceeded(api_key, quota_tier, &plan, month_count, now);
+                record_usage(&mut conn, api_key, now, &response).await?;
+                return Ok(response);
+            }
+            OverageDecision::Throttle(limit) => limit,
+            OverageDecision::Within | OverageDecision::Bill => plan.admission_limit(),
+        };
+
+        let mut response = match self.config.algorithm_for(quota_tier) {
+            RateLimitAlgorithm::FixedWindow => {
+                self.check_fixed_window(&mut conn, api_key, quota_tier, minute_limit, now)
+                    .await?
+            }
+            algorithm => {
+                self.check_scripted(&mut conn, api_key, quota_tier, algorithm, minute_limit, now)
+                    .await?
+            }
+        };
+        if response.allowed {
+            response.overage = match decision {
+                OverageDecision::Throttle(_) => Some(OveragePolicy::Throttle),
+                OverageDecision::Bill => Some(OveragePolicy::Bill),
+                _ => None,
+            };
+        }
+
+        record_usage(&mut conn, api_key, now, &response).await?;
+        Ok(response)
+    }
 
-        // Check per-minute rate limit
+    /// Per-minute admission by fixed-window counters
+    async fn check_fixed_window(
+        &self,
+        conn: &mut redis::aio::MultiplexedConnection,
+        api_key: &str,
+        quota_tier: QuotaTier,
+        minute_limit: u32,
+        now: DateTime<Utc>,
+    ) -> Result<RateLimitResponse> {
         let minute_key = format!("ratelimit:{}:minute:{}", api_key, now.format("%Y%m%d%H%M"));
         let current_count: u32 = conn.get(&minute_key).await.map_err(|e| {
             error!("Failed to read minute rate limit counter: {}", e);
             GatewayError::RedisError(e)
         })?;
 
-        let minute_limit = quota_tier.minute_limit().unwrap_or(u32::MAX);
+        let reset_at = next_minute_reset(now);
 
         if current_count >= minute_limit {
-            let reset_at = next_minute_reset(now);
-
             warn!(
                 "Rate limit exceeded for API key {} (tier: {:?}): {}/{}",
                 api_key, quota_tier, current_count, minute_limit
@@ -145,60 +335,23 @@ impl RateLimiter {
                     "Rate limit exceeded: {}/{} requests per minute",
                     current_count, minute_limit
                 )),
+                overage: None,
             });
         }
 
-        // Check monthly quota
-        let month_key = format!("ratelimit:{}:month:{}", api_key, now.format("%Y%m"));
-        let month_count: u64 = conn.get(&month_key).await.map_err(|e| {
-            error!("Failed to read monthly rate limit counter: {}", e);
-            GatewayError::RedisError(e)
-        })?;
-
-        if let Some(monthly_quota) = quota_tier.monthly_quota() {
-            if month_count >= monthly_quota {
-                let next_month = next_month_reset(now);
-
-                warn!(
-                    "Monthly quota exceeded for API key {} (tier: {:?}): {}/{}",
-                    api_key, quota_tier, month_count, monthly_quota
-                );
-
-                return Ok(RateLimitResponse {
-                    allowed: false,
-                    quota_tier,
-                    remaining: Some(0),
-                    limit: Some(minute_limit),
-                    reset_at: Some(next_month),
-                    error: Some(format!(
-                        "Monthly quota exceeded: {}/{} requests",
-                        month_count, monthly_quota
-                    )),
-                });
-            }
-        }
-
-        // Increment counters
         let _: () = redis::pipe()
             .atomic()
             .incr(&minute_key, 1)
             .ignore()
             .expire(&minute_key, 120) // 2 minutes TTL
             .ignore()
-            .incr(&month_key, 1)
-            .ignore()
-            .expire(&month_key, 32 * 24 * 3600) // ~1 month TTL
-            .ignore()
-            .query_async(&mut *conn)
+            .query_async(conn)
             .await
             .map_err(|e| {
-                error!("Failed to increment rate limit counters: {}", e);
+                error!("Failed to increment minute rate limit counter: {}", e);
                 GatewayError::RedisError(e)
             })?;
 
-        let remaining = minute_limit.saturating_sub(current_count + 1);
-        let reset_at = next_minute_reset(now);
-
         debug!(
             "Rate limit check passed for API key {} (tier: {:?}): {}/{}",
             api_key,
@@ -210,13 +363,112 @@ impl RateLimiter {
         Ok(RateLimitResponse {
             allowed: true,
             quota_tier,
-            remaining: Some(remaining),
+            remaining: Some(minute_limit.saturating_sub(current_count + 1)),
             limit: Some(minute_limit),
             reset_at: Some(reset_at),
             error: None,
+            overage: None,
         })
     }
 
+    /// Per-minute admission by a Lua script
+    async fn check_scripted(
+        &self,
+        conn: &mut redis::aio::MultiplexedConnection,
+        api_key: &str,
+        quota_tier: QuotaTier,
+        algorithm: RateLimitAlgorithm,
+        minute_limit: u32,
+        now: DateTime<Utc>,
+    ) -> Result<RateLimitResponse> {
+        let script = match algorithm {
+            RateLimitAlgorithm::SlidingWindowLog => redis::Script::new(SLIDING_WINDOW_LOG_SCRIPT),
+            _ => redis::Script::new(GCRA_SCRIPT),
+        };
+        let key = scripted_key(api_key, algorithm);
+        let mut invocation = script.key(&key);
+        invocation.arg(minute_limit).arg(MINUTE_MS);
+        if algorithm == RateLimitAlgorithm::SlidingWindowLog {
+            invocation.arg(uuid::Uuid::new_v4().simple().to_string());
+        }
+        let (allowed, remaining, reset_after_ms): (i64, i64, i64) =
+            invocation.invoke_async(conn).await.map_err(|e| {
+                error!("Failed to run {} rate limit script: {}", algorithm, e);
+                GatewayError::RedisError(e)
+            })?;
+        let reset_at = now + Duration::milliseconds(reset_after_ms.max(0));
+
+        if allowed == 0 {
+            warn!(
+                "Rate limit exceeded for API key {} (tier: {:?}, {}): limit {}",
+                api_key, quota_tier, algorithm, minute_limit
+            );
+
+            return Ok(RateLimitResponse {
+                allowed: false,
+                quota_tier,
+                remaining: Some(0),
+                limit: Some(minute_limit),
+                reset_at: Some(reset_at),
+                error: Some(format!(
+                    "Rate limit exceeded: {} requests per minute",
+                    minute_limit
+                )),
+                overage: None,
+            });
+        }
+
+        debug!(
+            "Rate limit check passed for API key {} (tier: {:?}, {}): {} remaining",
+            api_key, quota_tier, algorithm, remaining
+        );
+
+        Ok(RateLimitResponse {
+            allowed: true,
+            quota_tier,
+            remaining: Some(remaining.clamp(0, minute_limit as i64) as u32),
+            limit: Some(minute_limit),
+            reset_at: Some(reset_at),
+            error: None,
+            overage: None,
+        })
+    }
+
+    /// Requests counted against the per-minute limit by a scripted algorithm
+    async fn scripted_usage(
+        &self,
+        conn: &mut redis::aio::MultiplexedConnection,
+        api_key: &str,
+        minute_limit: u32,
+        algorithm: RateLimitAlgorithm,
+        now: DateTime<Utc>,
+    ) -> Result<u32> {
+        let key = scripted_key(api_key, algorithm);
+        let now_ms = now.timestamp_millis();
+
+        let used = if algorithm == RateLimitAlgorithm::SlidingWindowLog {
+            redis::cmd("ZCOUNT")
+                .arg(&key)
+                .arg(format!("({}", now_ms - MINUTE_MS as i64))
+                .arg("+inf")
+                .query_async::<u32>(conn)
+                .await
+                .map_err(GatewayError::RedisError)?
+        } else {
+            // the theoretical arrival time runs one emission interval ahead per request
+            let tat: Option<f64> = conn
+                .get::<_, Option<String>>(&key)
+                .await
+                .map_err(GatewayError::RedisError)?
+                .and_then(|v| v.parse().ok());
+            let interval = MINUTE_MS as f64 / minute_limit.max(1) as f64;
+            tat.map_or(0, |tat| {
+                ((tat - now_ms as f64) / interval).ceil().max(0.0) as u32
+            })
+        };
+        Ok(used.min(minute_limit))
+    }
+
     /// Get the current rate limit status for an API key
     ///
     /// # Arguments
@@ -237,14 +489,26 @@ impl RateLimiter {
         })?;
 
         let now = Utc::now();
+        let plan = self.tiers.plan_for(api_key, quota_tier);
+        let minute_limit = plan.minute_limit.map(|_| plan.admission_limit());
 
         let minute_key = format!("ratelimit:{}:minute:{}", api_key, now.format("%Y%m%d%H%M"));
         let month_key = format!("ratelimit:{}:month:{}", api_key, now.format("%Y%m"));
 
-        let current_minute_requests: u32 = conn.get(&minute_key).await.map_err(|e| {
-            error!("Failed to read minute rate limit status: {}", e);
-            GatewayError::RedisError(e)
-        })?;
+        let current_minute_requests: u32 =
+            match (self.config.algorithm_for(quota_tier), minute_limit) {
+                (_, None) => 0,
+                (RateLimitAlgorithm::FixedWindow, _) => {
+                    conn.get(&minute_key).await.map_err(|e| {
+                        error!("Failed to read minute rate limit status: {}", e);
+                        GatewayError::RedisError(e)
+                    })?
+                }
+                (algorithm, Some(limit)) => {
+                    self.scripted_usage(&mut conn, api_key, limit, algorithm, now)
+                        .await?
+                }
+            };
         let current_month_requests: u64 = conn.get(&month_key).await.map_err(|e| {
             error!("Failed to read monthly rate limit status: {}", e);
             GatewayError::RedisError(e)
@@ -258,13 +522,68 @@ impl RateLimiter {
             quota_tier,
             current_minute_requests,
             current_month_requests,
-            minute_limit: quota_tier.minute_limit(),
-            monthly_quota: quota_tier.monthly_quota(),
+            minute_limit,
+            monthly_quota: plan.monthly_quota,
             minute_reset_at,
             month_reset_at,
         })
     }
 
+    /// Usage of an API key over the current and the `months - 1` preceding months
+    pub async fn usage_report(
+        &self,
+        api_key: &str,
+        quota_tier: QuotaTier,
+        months: usize,
+    ) -> Result<UsageReport> {
+        let mut conn = self.redis_pool.get().await.map_err(|e| {
+            GatewayError::RedisError(redis::RedisError::from((
+                redis::ErrorKind::Io,
+                "Connection pool error",
+                e.to_string(),
+            )))
+        })?;
+
+        let now = Utc::now();
+        let plan = self.tiers.plan_for(api_key, quota_tier);
+
+        let mut rollups = Vec::new();
+        for month in recent_months(now, months.max(1)) {
+            let fields: HashMap<String, u64> = conn
+                .hgetall(usage_key(api_key, &month))
+                .await
+                .map_err(|e| {
+                    error!("Failed to read usage rollup: {}", e);
+                    GatewayError::RedisError(e)
+                })?;
+            let field = |name: &str| fields.get(name).copied().unwrap_or(0);
+            rollups.push(MonthlyUsage {
+                allowed: field("allowed"),
+                denied: field("denied"),
+                throttled: field("throttled"),
+                billable_overage: field("billable_overage"),
+                month,
+            });
+        }
+
+        // the quota counts admitted requests, overage included
+        let quota_remaining = plan
+            .monthly_quota
+            .map(|quota| quota.saturating_sub(rollups[0].allowed));
+
+        Ok(UsageReport {
+            api_key: api_key.to_string(),
+            minute_limit: plan.minute_limit.map(|_| plan.admission_limit()),
+            monthly_quota: plan.monthly_quota,
+            overage: plan.overage,
+            features: plan.features.iter().cloned().collect(),
+            plan: plan.name,
+            quota_remaining,
+            months: rollups,
+            month_reset_at: next_month_reset(now),
+        })
+    }
+
     /// Reset rate limits for an API key (admin function)
     pub async fn reset_limits(&self, api_key: &str) -> Result<()> {
         let mut conn = self.redis_pool.get().await.map_err(|e| {
@@ -308,6 +627,14 @@ impl RateLimiter {
     }
 }
 
+/// Key of the sliding window log or GCRA state of `api_key`
+fn scripted_key(api_key: &str, algorithm: RateLimitAlgorithm) -> String {
+    match algorithm {
+        RateLimitAlgorithm::SlidingWindowLog => format!("ratelimit:{}:sliding", api_key),
+        _ => format!("ratelimit:{}:gcra", api_key),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -327,4 +654,14 @@ mod tests {
         assert_eq!(QuotaTier::Free.monthly_quota(), Some(50_000));
         assert_eq!(QuotaTier::Pro.monthly_quota(), Some(1_000_000));
     }
+
+    #[test]
+    fn test_recent_months_cross_year() {
+        let now = Utc.with_ymd_and_hms(2026, 2, 13, 10, 0, 0).unwrap();
+        assert_eq!(
+            recent_months(now, 4),
+            ["2026-02", "2026-01", "2025-12", "2025-11"]
+        );
+        assert_eq!(usage_key("sk_test_1", "2026-02"), "usage:sk_test_1:2026-02");
+    }
 }
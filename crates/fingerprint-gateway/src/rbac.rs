@@ -0,0 +1,660 @@
+//! Role-based access control for admin and analysis endpoints
+//!
+//! Callers authenticate either with a bearer JWT (HS256, roles taken from a claim) or
+//! with an API key (`X-Admin-Key` / `X-API-Key`), whose role comes from the key owner or
+//! quota tier. Each privileged route requires a [`Permission`]; roles are hierarchical
+//! (viewer < analyst < admin). Every privileged decision, allowed or denied, is
+//! written to the audit log.
+//!
+//! The configuration mirrors the `gateway.rbac` section of fingerprint-config.
+
+use crate::{auth::ApiKeyValidator, error::GatewayError, models::QuotaTier};
+use actix_web::HttpRequest;
+use chrono::{DateTime, Utc};
+use fingerprint_core::data_dirs::DataDirs;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// Audit entries kept in memory by default
+const DEFAULT_AUDIT_CAPACITY: usize = 1000;
+
+/// Gateway roles, ordered by privilege
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Read-only access to status information
+    Viewer,
+    /// Can run fingerprint analysis
+    Analyst,
+    /// Full administrative access
+    Admin,
+}
+
+impl Role {
+    /// Parse a role name (case-insensitive)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "viewer" => Some(Self::Viewer),
+            "analyst" => Some(Self::Analyst),
+            "admin" => Some(Self::Admin),
+            _ => None,
+        }
+    }
+
+    /// Whether this role grants a permission
+    pub fn allows(&self, permission: Permission) -> bool {
+        *self >= permission.required_role()
+    }
+}
+
+/// Actions guarded by RBAC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Read rate limit status and gateway state
+    ViewStatus,
+    /// Submit fingerprints for analysis
+    RunAnalysis,
+    /// Reset rate limits of an API key
+    ResetLimits,
+    /// Create or revoke API keys
+    ManageKeys,
+    /// Read the audit log
+    ViewAudit,
+    /// Create, change or assign quota tiers
+    ManageTiers,
+}
+
+impl Permission {
+    /// Least privileged role holding this permission
+    pub fn required_role(&self) -> Role {
+        match self {
+            Self::ViewStatus => Role::Viewer,
+            Self::RunAnalysis => Role::Analyst,
+            Self::ResetLimits | Self::ManageKeys | Self::ViewAudit | Self::ManageTiers => {
+                Role::Admin
+            }
+        }
+    }
+
+    /// Name used in audit entries
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ViewStatus => "view_status",
+            Self::RunAnalysis => "run_analysis",
+            Self::ResetLimits => "reset_limits",
+            Self::ManageKeys => "manage_keys",
+            Self::ViewAudit => "view_audit",
+            Self::ManageTiers => "manage_tiers",
+        }
+    }
+}
+
+/// RBAC configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RbacConfig {
+    /// Role per API key owner (registered keys only)
+    pub owner_roles: HashMap<String, Role>,
+
+    /// Role per quota tier, for registered keys without an owner mapping
+    pub tier_roles: HashMap<QuotaTier, Role>,
+
+    /// Role of registered keys matching neither mapping (None: no access)
+    pub default_role: Option<Role>,
+
+    /// HS256 secret for bearer tokens (None: JWT authentication disabled)
+    pub jwt_secret: Option<String>,
+
+    /// Expected `iss` claim, if any
+    pub jwt_issuer: Option<String>,
+
+    /// Claim holding the role name(s), as a string or array of strings
+    pub role_claim: String,
+
+    /// Mapping of external claim values (e.g. IdP groups) to roles
+    pub claim_roles: HashMap<String, Role>,
+
+    /// Audit entries kept in memory
+    pub audit_capacity: usize,
+
+    /// JSON Lines file receiving every audit entry (None: memory only)
+    pub audit_log_path: Option<PathBuf>,
+}
+
+impl Default for RbacConfig {
+    fn default() -> Self {
+        // Enterprise and partner keys were the admin keys before RBAC existed
+        let tier_roles = HashMap::from([
+            (QuotaTier::Enterprise, Role::Admin),
+            (QuotaTier::Partner, Role::Admin),
+        ]);
+        Self {
+            owner_roles: HashMap::new(),
+            tier_roles,
+            default_role: Some(Role::Viewer),
+            jwt_secret: None,
+            jwt_issuer: None,
+            role_claim: "roles".to_string(),
+            claim_roles: HashMap::new(),
+            audit_capacity: DEFAULT_AUDIT_CAPACITY,
+            audit_log_path: None,
+        }
+    }
+}
+
+impl RbacConfig {
+    /// Load from a JSON file holding either the RBAC section itself or a full
+    /// fingerprint-config document with a `gateway.rbac` section
+    pub fn from_file(path: &str) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let value: serde_json::Value = serde_json::from_str(&content)?;
+        let section = value
+            .get("gateway")
+            .and_then(|gateway| gateway.get("rbac"))
+            .cloned()
+            .unwrap_or(value);
+        Ok(serde_json::from_value(section)?)
+    }
+}
+
+/// Authenticated caller
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Principal {
+    /// Key owner or JWT subject; never the API key itself
+    pub subject: String,
+    /// Effective role
+    pub role: Role,
+    /// How the caller authenticated (`api_key` or `jwt`)
+    pub method: &'static str,
+}
+
+/// One privileged decision
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    /// When the decision was made
+    pub timestamp: DateTime<Utc>,
+    /// Caller subject, or `anonymous` when authentication failed
+    pub subject: String,
+    /// Caller role, if authenticated
+    pub role: Option<Role>,
+    /// Requested permission
+    pub action: Permission,
+    /// Target of the action (e.g. the owner whose limits were reset)
+    pub resource: Option<String>,
+    /// Whether the action was permitted
+    pub allowed: bool,
+}
+
+/// Bounded in-memory audit trail, also emitted as `audit` tracing events
+/// and optionally appended to a JSON Lines file
+pub struct AuditLog {
+    entries: Mutex<VecDeque<AuditEntry>>,
+    capacity: usize,
+    file: Option<Mutex<File>>,
+}
+
+impl AuditLog {
+    /// Create an audit log keeping at most `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(
+                capacity.min(DEFAULT_AUDIT_CAPACITY),
+            )),
+            capacity: capacity.max(1),
+            file: None,
+        }
+    }
+
+    /// Create an audit log that also appends every entry to `path`
+    ///
+    /// Missing parent directories are created; existing entries are kept.
+    pub fn with_file(capacity: usize, path: &Path) -> io::Result<Self> {
+        let path = DataDirs::prepare_file(path.to_path_buf())?;
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Some(Mutex::new(file)),
+            ..Self::new(capacity)
+        })
+    }
+
+    /// Append an entry, evicting the oldest when full
+    pub fn record(&self, entry: AuditEntry) {
+        info!(
+            target: "audit",
+            subject = %entry.subject,
+            role = ?entry.role,
+            action = entry.action.as_str(),
+            resource = entry.resource.as_deref().unwrap_or(""),
+            allowed = entry.allowed,
+            "privileged action"
+        );
+        if let Some(file) = &self.file {
+            let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+            let written = serde_json::to_string(&entry)
+                .map_err(io::Error::from)
+                .and_then(|line| writeln!(file, "{}", line));
+            if let Err(e) = written {
+                warn!("failed to persist audit entry: {}", e);
+            }
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Most recent entries, newest first
+    pub fn recent(&self, limit: usize) -> Vec<AuditEntry> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.iter().rev().take(limit).cloned().collect()
+    }
+}
+
+/// Role resolution and permission checks
+pub struct Rbac {
+    config: RbacConfig,
+    audit: AuditLog,
+}
+
+impl Rbac {
+    /// Create the access controller
+    pub fn new(config: RbacConfig) -> Self {
+        let audit = match &config.audit_log_path {
+            Some(path) => AuditLog::with_file(config.audit_capacity, path).unwrap_or_else(|e| {
+                warn!(
+                    "audit log {} unavailable, keeping entries in memory only: {}",
+                    path.display(),
+                    e
+                );
+                AuditLog::new(config.audit_capacity)
+            }),
+            None => AuditLog::new(config.audit_capacity),
+        };
+        Self { config, audit }
+    }
+
+    /// Active configuration
+    pub fn config(&self) -> &RbacConfig {
+        &self.config
+    }
+
+    /// Audit trail
+    pub fn audit(&self) -> &AuditLog {
+        &self.audit
+    }
+
+    /// Authenticate the request and check `permission`, auditing the decision
+    ///
+    /// Returns 401 when the caller cannot be identified and 403 when their role
+    /// lacks the permission.
+    pub fn authorize(
+        &self,
+        request: &HttpRequest,
+        validator: &ApiKeyValidator,
+        permission: Permission,
+        resource: Option<&str>,
+    ) -> Result<Principal, GatewayError> {
+        let principal = match self.authenticate(request, validator) {
+            Ok(principal) => principal,
+            Err(e) => {
+                self.record(None, permission, resource, false);
+                return Err(e);
+            }
+        };
+
+        let allowed = principal.role.allows(permission);
+        self.record(Some(&principal), permission, resource, allowed);
+        if allowed {
+            Ok(principal)
+        } else {
+            warn!(
+                "{} ({:?}) denied {}",
+                principal.subject,
+                principal.role,
+                permission.as_str()
+            );
+            Err(GatewayError::Forbidden(format!(
+                "{} requires the {:?} role",
+                permission.as_str(),
+                permission.required_role()
+            )))
+        }
+    }
+
+    /// Identify the caller from a bearer token or API key header
+    pub fn authenticate(
+        &self,
+        request: &HttpRequest,
+        validator: &ApiKeyValidator,
+    ) -> Result<Principal, GatewayError> {
+        let headers = request.headers();
+        if let Some(token) = headers
+            .get("Authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+        {
+            return self.principal_from_jwt(token.trim());
+        }
+
+        let api_key = ["X-Admin-Key", "X-API-Key"]
+            .iter()
+            .find_map(|name| headers.get(*name).and_then(|value| value.to_str().ok()))
+            .ok_or_else(|| GatewayError::InvalidApiKey("Missing credentials".to_string()))?;
+        self.principal_from_api_key(api_key, validator)
+    }
+
+    /// Role of a registered API key
+    ///
+    /// Unregistered keys are rejected even though the data plane accepts them by
+    /// prefix, so a made-up `sk_enterprise_*` key cannot gain admin rights.
+    pub fn principal_from_api_key(
+        &self,
+        api_key: &str,
+        validator: &ApiKeyValidator,
+    ) -> Result<Principal, GatewayError> {
+        if !validator.is_registered(api_key) {
+            return Err(GatewayError::InvalidApiKey(
+                "API key is not registered".to_string(),
+            ));
+        }
+        let info = validator.validate(api_key)?;
+        let role = self
+            .config
+            .owner_roles
+            .get(&info.owner)
+            .or_else(|| self.config.tier_roles.get(&info.tier))
+            .copied()
+            .or(self.config.default_role)
+            .ok_or_else(|| {
+                GatewayError::Forbidden(format!("No role assigned to {}", info.owner))
+            })?;
+        Ok(Principal {
+            subject: info.owner,
+            role,
+            method: "api_key",
+        })
+    }
+
+    /// Verify a bearer token and take the highest role found in its role claim
+    pub fn principal_from_jwt(&self, token: &str) -> Result<Principal, GatewayError> {
+        let secret = self.config.jwt_secret.as_deref().ok_or_else(|| {
+            GatewayError::InvalidApiKey("Bearer tokens are not accepted".to_string())
+        })?;
+
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_required_spec_claims(&["exp", "sub"]);
+        if let Some(issuer) = &self.config.jwt_issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        let claims = jsonwebtoken::decode::<serde_json::Value>(
+            token,
+            &DecodingKey::from_secret(secret.as_bytes()),
+            &validation,
+        )
+        .map_err(|e| GatewayError::InvalidApiKey(format!("Invalid token: {}", e)))?
+        .claims;
+
+        let subject = claims
+            .get("sub")
+            .and_then(|sub| sub.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let names: Vec<&str> = match claims.get(&self.config.role_claim) {
+            Some(serde_json::Value::String(name)) => vec![name.as_str()],
+            Some(serde_json::Value::Array(names)) => {
+                names.iter().filter_map(|name| name.as_str()).collect()
+            }
+            _ => Vec::new(),
+        };
+        let role = names
+            .iter()
+            .filter_map(|name| {
+                self.config
+                    .claim_roles
+                    .get(*name)
+                    .copied()
+                    .or_else(|| Role::parse(name))
+            })
+            .max()
+            .ok_or_else(|| GatewayError::Forbidden(format!("No role assigned to {}", subject)))?;
+
+        Ok(Principal {
+            subject,
+            role,
+            method: "jwt",
+        })
+    }
+
+    fn record(
+        &self,
+        principal: Option<&Principal>,
+        action: Permission,
+        resource: Option<&str>,
+        allowed: bool,
+    ) {
+        self.audit.record(AuditEntry {
+            timestamp: Utc::now(),
+            subject: principal
+                .map(|p| p.subject.clone())
+                .unwrap_or_else(|| "anonymous".to_string()),
+            role: principal.map(|p| p.role),
+            action,
+            resource: resource.map(str::to_string),
+            allowed,
+        });
+    }
+}
+
+impl Default for Rbac {
+    fn default() -> Self {
+        Self::new(RbacConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::ApiKeyInfo;
+    use actix_web::test::TestRequest;
+    use jsonwebtoken::{EncodingKey, Header};
+
+    const SECRET: &str = "test-secret";
+
+    fn token(claims: serde_json::Value) -> String {
+        jsonwebtoken::encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(SECRET.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    fn rbac() -> Rbac {
+        let mut config = RbacConfig {
+            jwt_secret: Some(SECRET.to_string()),
+            ..RbacConfig::default()
+        };
+        config
+            .owner_roles
+            .insert("analytics_team".to_string(), Role::Analyst);
+        config
+            .claim_roles
+            .insert("fp-admins".to_string(), Role::Admin);
+        Rbac::new(config)
+    }
+
+    fn validator() -> ApiKeyValidator {
+        let mut validator = ApiKeyValidator::new();
+        validator.add_key(ApiKeyInfo {
+            key: "sk_live_analytics".to_string(),
+            tier: QuotaTier::Pro,
+            owner: "analytics_team".to_string(),
+            active: true,
+        });
+        validator
+    }
+
+    #[test]
+    fn test_roles_are_hierarchical() {
+        assert!(Role::Admin.allows(Permission::RunAnalysis));
+        assert!(Role::Analyst.allows(Permission::ViewStatus));
+        assert!(!Role::Analyst.allows(Permission::ResetLimits));
+        assert!(!Role::Viewer.allows(Permission::RunAnalysis));
+    }
+
+    #[test]
+    fn test_api_key_roles_and_audit() {
+        let rbac = rbac();
+        let validator = validator();
+        let request = |key: &str| {
+            TestRequest::default()
+                .insert_header(("X-Admin-Key", key))
+                .to_http_request()
+        };
+
+        // tier mapping keeps enterprise keys as admins
+        let admin = rbac
+            .authorize(
+                &request("sk_enterprise_corp789"),
+                &validator,
+                Permission::ResetLimits,
+                Some("demo_user"),
+            )
+            .unwrap();
+        assert_eq!(admin.role, Role::Admin);
+        assert_eq!(admin.subject, "enterprise_corp");
+
+        // owner mapping wins over the tier
+        let analyst = rbac
+            .authorize(
+                &request("sk_live_analytics"),
+                &validator,
+                Permission::RunAnalysis,
+                None,
+            )
+            .unwrap();
+        assert_eq!(analyst.role, Role::Analyst);
+
+        let denied = rbac.authorize(
+            &request("sk_test_demo123"),
+            &validator,
+            Permission::ResetLimits,
+            None,
+        );
+        assert!(matches!(denied, Err(GatewayError::Forbidden(_))));
+
+        // unregistered keys get no role even with a privileged prefix
+        let forged = rbac.authorize(
+            &request("sk_enterprise_forged"),
+            &validator,
+            Permission::ResetLimits,
+            None,
+        );
+        assert!(matches!(forged, Err(GatewayError::InvalidApiKey(_))));
+
+        let audit = rbac.audit().recent(10);
+        assert_eq!(audit.len(), 4);
+        assert_eq!(audit[0].subject, "anonymous");
+        assert!(!audit[1].allowed);
+        assert_eq!(audit[3].resource.as_deref(), Some("demo_user"));
+        assert!(audit[3].allowed);
+    }
+
+    #[test]
+    fn test_jwt_role_claims() {
+        let rbac = rbac();
+        let validator = validator();
+        let exp = Utc::now().timestamp() + 300;
+        let bearer = |token: String| {
+            TestRequest::default()
+                .insert_header(("Authorization", format!("Bearer {}", token)))
+                .to_http_request()
+        };
+
+        let mapped = token(serde_json::json!({
+            "sub": "alice", "exp": exp, "roles": ["viewer", "fp-admins"]
+        }));
+        let principal = rbac
+            .authorize(&bearer(mapped), &validator, Permission::ViewAudit, None)
+            .unwrap();
+        assert_eq!(principal.role, Role::Admin);
+        assert_eq!(principal.method, "jwt");
+
+        let analyst = token(serde_json::json!({"sub": "bob", "exp": exp, "roles": "analyst"}));
+        assert!(rbac
+            .authorize(&bearer(analyst), &validator, Permission::ManageKeys, None)
+            .is_err());
+
+        let expired = token(serde_json::json!({
+            "sub": "alice", "exp": exp - 3600, "roles": "admin"
+        }));
+        assert!(matches!(
+            rbac.authenticate(&bearer(expired), &validator),
+            Err(GatewayError::InvalidApiKey(_))
+        ));
+
+        let forged = jsonwebtoken::encode(
+            &Header::default(),
+            &serde_json::json!({"sub": "mallory", "exp": exp, "roles": "admin"}),
+            &EncodingKey::from_secret(b"wrong"),
+        )
+        .unwrap();
+        assert!(rbac.authenticate(&bearer(forged), &validator).is_err());
+    }
+
+    #[test]
+    fn test_config_from_fingerprint_config_section() {
+        let dir = std::env::temp_dir().join(format!("rbac-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+        std::fs::write(
+            &path,
+            r#"{"gateway": {"rbac": {"owner_roles": {"ops": "admin"}, "default_role": null}}}"#,
+        )
+        .unwrap();
+
+        let config = RbacConfig::from_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(config.owner_roles["ops"], Role::Admin);
+        assert_eq!(config.default_role, None);
+        assert_eq!(config.role_claim, "roles");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_audit_entries_are_appended_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("audit.jsonl");
+        let rbac = Rbac::new(RbacConfig {
+            audit_log_path: Some(path.clone()),
+            ..RbacConfig::default()
+        });
+        for allowed in [true, false] {
+            rbac.audit().record(AuditEntry {
+                timestamp: Utc::now(),
+                subject: "alice".to_string(),
+                role: Some(Role::Admin),
+                action: Permission::ResetLimits,
+                resource: Some("demo_user".to_string()),
+                allowed,
+            });
+        }
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["subject"], "alice");
+        assert_eq!(lines[1]["allowed"], false);
+        assert_eq!(rbac.audit().recent(10).len(), 2);
+    }
+}
@@ -7,8 +7,11 @@ use tracing::info;
 use crate::{
     auth::ApiKeyValidator,
     error::GatewayError,
+    labels::EnforcementReporter,
     models::{HealthResponse, QuotaTier, RateLimitRequest},
+    quota::TierPlan,
     rate_limit::RateLimiter,
+    rbac::{Permission, Rbac},
 };
 
 /// Health check endpoint
@@ -40,12 +43,25 @@ pub async fn health(rate_limiter: web::Data<RateLimiter>) -> Result<impl Respond
     Ok(HttpResponse::Ok().json(response))
 }
 
+/// Build capabilities endpoint
+///
+/// GET /api/v1/version
+pub async fn version() -> Result<impl Responder, GatewayError> {
+    use crate::metrics;
+
+    let _timer = metrics::RequestTimer::new("GET".to_string(), "/version".to_string());
+
+    metrics::record_http_request("GET", "/version", 200);
+    Ok(HttpResponse::Ok().json(crate::capabilities()))
+}
+
 /// Rate limit check endpoint
 ///
 /// POST /api/v1/rate-limit/check
 pub async fn check_rate_limit(
     rate_limiter: web::Data<RateLimiter>,
     validator: web::Data<ApiKeyValidator>,
+    reporter: Option<web::Data<EnforcementReporter>>,
     req: web::Json<RateLimitRequest>,
 ) -> Result<impl Responder, GatewayError> {
     use crate::metrics;
@@ -58,9 +74,28 @@ pub async fn check_rate_limit(
     );
 
     // Validate API key and get tier
-    let key_info = validator.validate(&req.api_key)?;
+    let key_info = match validator.validate(&req.api_key) {
+        Ok(info) => info,
+        Err(e) => {
+            if let Some(reporter) = &reporter {
+                reporter.auth_failure(req.client_ip.as_deref(), &req.fingerprints);
+            }
+            return Err(e);
+        }
+    };
     let quota_tier = key_info.tier;
 
+    if let Some(feature) = &req.feature {
+        let plan = rate_limiter.tiers().plan_for(&req.api_key, quota_tier);
+        if !plan.entitles(feature) {
+            metrics::record_http_request("POST", "/rate-limit/check", 403);
+            return Err(GatewayError::Forbidden(format!(
+                "plan '{}' does not include '{}'",
+                plan.name, feature
+            )));
+        }
+    }
+
     let result = rate_limiter
         .check_rate_limit(&req.api_key, quota_tier)
         .await?;
@@ -76,6 +111,9 @@ pub async fn check_rate_limit(
         Ok(HttpResponse::Ok().json(result))
     } else {
         info!("Rate limit check failed for API key: {}", req.api_key);
+        if let Some(reporter) = &reporter {
+            reporter.rate_limited(Some(&key_info.owner), &req.fingerprints);
+        }
         metrics::record_http_request("POST", "/rate-limit/check", 429);
         Ok(HttpResponse::TooManyRequests().json(result))
     }
@@ -102,12 +140,160 @@ pub async fn get_status(
     Ok(HttpResponse::Ok().json(status))
 }
 
+/// Usage of an API key against its plan, by month
+///
+/// GET /api/v1/usage?api_key={key}&months={n}
+pub async fn get_usage(
+    rate_limiter: web::Data<RateLimiter>,
+    validator: web::Data<ApiKeyValidator>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> Result<impl Responder, GatewayError> {
+    let api_key = query
+        .get("api_key")
+        .ok_or_else(|| GatewayError::InvalidRequest("Missing api_key parameter".to_string()))?;
+    let months = query
+        .get("months")
+        .and_then(|months| months.parse().ok())
+        .unwrap_or(1usize)
+        .clamp(1, 13);
+
+    let key_info = validator.validate(api_key)?;
+    let report = rate_limiter
+        .usage_report(api_key, key_info.tier, months)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// List quota tier plans (admin endpoint)
+///
+/// GET /api/v1/admin/tiers
+pub async fn list_tiers(
+    rate_limiter: web::Data<RateLimiter>,
+    validator: web::Data<ApiKeyValidator>,
+    rbac: web::Data<Rbac>,
+    request: HttpRequest,
+) -> Result<impl Responder, GatewayError> {
+    rbac.authorize(&request, &validator, Permission::ManageTiers, None)?;
+    Ok(HttpResponse::Ok().json(rate_limiter.tiers().list()))
+}
+
+/// Get one quota tier plan (admin endpoint)
+///
+/// GET /api/v1/admin/tiers/{name}
+pub async fn get_tier(
+    rate_limiter: web::Data<RateLimiter>,
+    validator: web::Data<ApiKeyValidator>,
+    rbac: web::Data<Rbac>,
+    request: HttpRequest,
+    name: web::Path<String>,
+) -> Result<impl Responder, GatewayError> {
+    rbac.authorize(&request, &validator, Permission::ManageTiers, Some(&name))?;
+    let plan = rate_limiter
+        .tiers()
+        .get(&name)
+        .ok_or_else(|| GatewayError::NotFound(format!("tier '{}'", name)))?;
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+/// Create a quota tier plan (admin endpoint)
+///
+/// POST /api/v1/admin/tiers
+pub async fn create_tier(
+    rate_limiter: web::Data<RateLimiter>,
+    validator: web::Data<ApiKeyValidator>,
+    rbac: web::Data<Rbac>,
+    request: HttpRequest,
+    plan: web::Json<TierPlan>,
+) -> Result<impl Responder, GatewayError> {
+    let admin = rbac.authorize(
+        &request,
+        &validator,
+        Permission::ManageTiers,
+        Some(&plan.name),
+    )?;
+    let plan = rate_limiter.tiers().create(plan.into_inner())?;
+    info!("Tier {} created by {}", plan.name, admin.subject);
+    Ok(HttpResponse::Created().json(plan))
+}
+
+/// Replace a quota tier plan (admin endpoint)
+///
+/// PUT /api/v1/admin/tiers/{name}
+pub async fn update_tier(
+    rate_limiter: web::Data<RateLimiter>,
+    validator: web::Data<ApiKeyValidator>,
+    rbac: web::Data<Rbac>,
+    request: HttpRequest,
+    name: web::Path<String>,
+    plan: web::Json<TierPlan>,
+) -> Result<impl Responder, GatewayError> {
+    let admin = rbac.authorize(&request, &validator, Permission::ManageTiers, Some(&name))?;
+    let plan = rate_limiter.tiers().update(&name, plan.into_inner())?;
+    info!("Tier {} updated by {}", plan.name, admin.subject);
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+/// Delete an unassigned custom tier plan (admin endpoint)
+///
+/// DELETE /api/v1/admin/tiers/{name}
+pub async fn delete_tier(
+    rate_limiter: web::Data<RateLimiter>,
+    validator: web::Data<ApiKeyValidator>,
+    rbac: web::Data<Rbac>,
+    request: HttpRequest,
+    name: web::Path<String>,
+) -> Result<impl Responder, GatewayError> {
+    let admin = rbac.authorize(&request, &validator, Permission::ManageTiers, Some(&name))?;
+    rate_limiter.tiers().delete(&name)?;
+    info!("Tier {} deleted by {}", name, admin.subject);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Put an API key on a tier plan, or back on its tier's plan (admin endpoint)
+///
+/// POST /api/v1/admin/tiers/assign
+///
+/// Body: `{"api_key": "...", "tier": "team"}`; without `tier` the assignment is removed.
+pub async fn assign_tier(
+    rate_limiter: web::Data<RateLimiter>,
+    validator: web::Data<ApiKeyValidator>,
+    rbac: web::Data<Rbac>,
+    request: HttpRequest,
+    req: web::Json<std::collections::HashMap<String, String>>,
+) -> Result<impl Responder, GatewayError> {
+    let api_key = req
+        .get("api_key")
+        .ok_or_else(|| GatewayError::InvalidRequest("Missing api_key field".to_string()))?;
+
+    // audit the key owner rather than the key itself
+    let target = validator
+        .validate(api_key)
+        .map(|info| info.owner)
+        .unwrap_or_else(|_| "unknown".to_string());
+    let admin = rbac.authorize(&request, &validator, Permission::ManageTiers, Some(&target))?;
+
+    match req.get("tier") {
+        Some(tier) => rate_limiter.tiers().assign(api_key, tier)?,
+        None => rate_limiter.tiers().unassign(api_key)?,
+    }
+    info!(
+        "Tier of {} set to {} by {}",
+        target,
+        req.get("tier").map_or("default", String::as_str),
+        admin.subject
+    );
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "success": true })))
+}
+
 /// Reset rate limits for an API key (admin endpoint)
 ///
 /// POST /api/v1/rate-limit/reset
 pub async fn reset_rate_limit(
     rate_limiter: web::Data<RateLimiter>,
     validator: web::Data<ApiKeyValidator>,
+    rbac: web::Data<Rbac>,
     request: HttpRequest,
     req: web::Json<std::collections::HashMap<String, String>>,
 ) -> Result<impl Responder, GatewayError> {
@@ -115,22 +301,16 @@ pub async fn reset_rate_limit(
         .get("api_key")
         .ok_or_else(|| GatewayError::InvalidRequest("Missing api_key field".to_string()))?;
 
-    let admin_key = request
-        .headers()
-        .get("X-Admin-Key")
-        .and_then(|value| value.to_str().ok())
-        .ok_or_else(|| GatewayError::InvalidApiKey("Missing admin key".to_string()))?;
-
-    let admin_info = validator.validate(admin_key)?;
-    if !matches!(admin_info.tier, QuotaTier::Enterprise | QuotaTier::Partner) {
-        return Err(GatewayError::InvalidApiKey(
-            "Admin key not authorized".to_string(),
-        ));
-    }
+    // audit the key owner rather than the key itself
+    let target = validator
+        .validate(api_key)
+        .map(|info| info.owner)
+        .unwrap_or_else(|_| "unknown".to_string());
+    let admin = rbac.authorize(&request, &validator, Permission::ResetLimits, Some(&target))?;
 
     rate_limiter.reset_limits(api_key).await?;
 
-    info!("Rate limits reset for API key: {}", api_key);
+    info!("Rate limits reset for {} by {}", target, admin.subject);
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
@@ -138,6 +318,25 @@ pub async fn reset_rate_limit(
     })))
 }
 
+/// Recent privileged actions (admin endpoint)
+///
+/// GET /api/v1/admin/audit?limit={n}
+pub async fn get_audit_log(
+    validator: web::Data<ApiKeyValidator>,
+    rbac: web::Data<Rbac>,
+    request: HttpRequest,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> Result<impl Responder, GatewayError> {
+    rbac.authorize(&request, &validator, Permission::ViewAudit, None)?;
+
+    let limit = query
+        .get("limit")
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(100);
+
+    Ok(HttpResponse::Ok().json(rbac.audit().recent(limit)))
+}
+
 /// Prometheus metrics endpoint
 ///
 /// GET /metrics
@@ -154,14 +353,31 @@ pub async fn metrics() -> Result<impl Responder, GatewayError> {
 
 /// Configure all routes
 pub fn configure(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("/api/v1")
-            .route("/health", web::get().to(health))
-            .route("/rate-limit/check", web::post().to(check_rate_limit))
-            .route("/rate-limit/status", web::get().to(get_status))
-            .route("/rate-limit/reset", web::post().to(reset_rate_limit)),
-    )
-    .route("/metrics", web::get().to(metrics));
+    let api = web::scope("/api/v1")
+        .route("/health", web::get().to(health))
+        .route("/version", web::get().to(version))
+        .route("/rate-limit/check", web::post().to(check_rate_limit))
+        .route("/rate-limit/status", web::get().to(get_status))
+        .route("/rate-limit/reset", web::post().to(reset_rate_limit))
+        .route("/usage", web::get().to(get_usage))
+        .route("/admin/tiers", web::get().to(list_tiers))
+        .route("/admin/tiers", web::post().to(create_tier))
+        .route("/admin/tiers/assign", web::post().to(assign_tier))
+        .route("/admin/tiers/{name}", web::get().to(get_tier))
+        .route("/admin/tiers/{name}", web::put().to(update_tier))
+        .route("/admin/tiers/{name}", web::delete().to(delete_tier))
+        .route("/admin/audit", web::get().to(get_audit_log));
+    #[cfg(feature = "learner-labels")]
+    let api = {
+        use crate::backfill::{get_backfill, get_backfill_changes, start_backfill};
+        api.route("/admin/backfill", web::post().to(start_backfill))
+            .route("/admin/backfill/{id}", web::get().to(get_backfill))
+            .route(
+                "/admin/backfill/{id}/changes",
+                web::get().to(get_backfill_changes),
+            )
+    };
+    cfg.service(api).route("/metrics", web::get().to(metrics));
 }
 
 /// Determine quota tier based on API key
@@ -191,6 +407,26 @@ fn determine_quota_tier(api_key: &str) -> QuotaTier {
 mod tests {
     use super::*;
 
+    #[actix_web::test]
+    async fn test_version_reports_capabilities() {
+        use actix_web::test::{self as actix_test, TestRequest};
+        use actix_web::App;
+        use fingerprint_core::CapabilityReport;
+
+        let app =
+            actix_test::init_service(App::new().route("/version", web::get().to(version))).await;
+        let req = TestRequest::get().uri("/version").to_request();
+        let report: CapabilityReport = actix_test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(report.version, env!("CARGO_PKG_VERSION"));
+        assert!(report.has("ja4"));
+        assert_eq!(
+            report.has("redis-rate-limit"),
+            cfg!(feature = "redis-backend")
+        );
+        assert!(report.providers().contains_key("fingerprint-gateway"));
+    }
+
     #[test]
     fn test_determine_quota_tier() {
         assert_eq!(determine_quota_tier("sk_test_12345"), QuotaTier::Free);
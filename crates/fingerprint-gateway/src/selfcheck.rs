@@ -0,0 +1,355 @@
+//! Startup self-check
+//!
+//! Verifies that the environment can serve traffic and that fingerprint
+//! generation is still correct:
+//! - configuration validity
+//! - Redis and label database connectivity
+//! - packet capture permissions
+//! - each enabled profile's ClientHello against its golden JA4
+//! - system clock and timezone
+//!
+//! The result is a machine-readable [`ReadinessReport`]; the `selfcheck`
+//! binary prints it as JSON and exits non-zero when the gateway is not ready.
+
+use crate::config::GatewayConfig;
+use chrono::{DateTime, Datelike, Local, Offset, Utc};
+use fingerprint::{mapped_tls_clients, TLSHandshakeBuilder};
+use fingerprint_core::{ja4::JA4, tls_parser::find_client_hello};
+use serde::Serialize;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Golden JA4 per enabled profile, as seen on the wire
+///
+/// Update together with the profile's `ClientHelloSpec`.
+pub const GOLDEN_JA4: &[(&str, &str)] = &[
+    ("chrome_133", "t13d1518h2_ba7f6aa88938_b3b6dcc3301e_ec13"),
+    ("chrome_136", "t13d1518h3_ba7f6aa88938_b3b6dcc3301e_ec13"),
+    ("firefox_133", "t13d0907h2_cab9e4056364_d3e9ce11f92f_8028"),
+    ("safari_16_0", "t13d0706h2_113c5579a2ee_730a9af5d8e9_9977"),
+];
+
+/// Server name placed in sample ClientHellos
+const SAMPLE_SNI: &str = "selfcheck.example.com";
+
+/// Upper bound on the Redis connectivity check
+const REDIS_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Linux capability bit for raw sockets
+#[cfg(target_os = "linux")]
+const CAP_NET_RAW: u32 = 13;
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    /// Check succeeded
+    Pass,
+    /// Degraded but able to serve
+    Warn,
+    /// Not ready
+    Fail,
+    /// Not applicable to this configuration
+    Skip,
+}
+
+/// Result of a single check
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    /// Check name (e.g. `redis`, `profile:chrome_133`)
+    pub name: String,
+    /// Outcome
+    pub status: CheckStatus,
+    /// Human-readable detail
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: impl Into<String>, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Machine-readable readiness report
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    /// True when no check failed
+    pub ready: bool,
+    /// Gateway version
+    pub version: String,
+    /// When the checks ran
+    pub checked_at: DateTime<Utc>,
+    /// Individual check results, in execution order
+    pub checks: Vec<CheckResult>,
+}
+
+impl ReadinessReport {
+    fn new(checks: Vec<CheckResult>) -> Self {
+        Self {
+            ready: checks.iter().all(|c| c.status != CheckStatus::Fail),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            checked_at: Utc::now(),
+            checks,
+        }
+    }
+
+    /// Checks that failed
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks.iter().filter(|c| c.status == CheckStatus::Fail)
+    }
+
+    /// Serialize as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Log each check; failures and warnings at `warn` level
+    pub fn log(&self) {
+        for check in &self.checks {
+            match check.status {
+                CheckStatus::Pass | CheckStatus::Skip => {
+                    info!(check = %check.name, status = ?check.status, "{}", check.detail)
+                }
+                CheckStatus::Warn | CheckStatus::Fail => {
+                    warn!(check = %check.name, status = ?check.status, "{}", check.detail)
+                }
+            }
+        }
+    }
+}
+
+/// Run all checks against `config`
+pub async fn run(config: &GatewayConfig) -> ReadinessReport {
+    let mut checks = vec![check_config(config)];
+    checks.push(check_redis(&config.redis_url).await);
+    checks.push(check_label_db(config));
+    checks.push(check_capture_permissions());
+    checks.extend(check_profiles());
+    checks.push(check_clock());
+    ReadinessReport::new(checks)
+}
+
+/// Validate the gateway configuration
+pub fn check_config(config: &GatewayConfig) -> CheckResult {
+    match config.validate() {
+        Ok(()) => CheckResult::new("config", CheckStatus::Pass, "configuration is valid"),
+        Err(e) => CheckResult::new("config", CheckStatus::Fail, e.to_string()),
+    }
+}
+
+/// Connect to Redis and PING it
+pub async fn check_redis(redis_url: &str) -> CheckResult {
+    let ping = async {
+        let client = redis::Client::open(redis_url)?;
+        let mut conn = client.get_multiplexed_async_connection().await?;
+        redis::cmd("PING").query_async::<String>(&mut conn).await
+    };
+
+    match tokio::time::timeout(REDIS_TIMEOUT, ping).await {
+        Ok(Ok(_)) => CheckResult::new("redis", CheckStatus::Pass, "PING ok"),
+        Ok(Err(e)) => CheckResult::new("redis", CheckStatus::Fail, e.to_string()),
+        Err(_) => CheckResult::new(
+            "redis",
+            CheckStatus::Fail,
+            format!("no PING reply within {:?}", REDIS_TIMEOUT),
+        ),
+    }
+}
+
+/// Open the learner label database, if one is configured
+pub fn check_label_db(config: &GatewayConfig) -> CheckResult {
+    let Some(path) = &config.label_db_path else {
+        return CheckResult::new(
+            "label_db",
+            CheckStatus::Skip,
+            "no label database configured",
+        );
+    };
+
+    #[cfg(feature = "learner-labels")]
+    {
+        match fingerprint_defense::FingerprintDatabase::open(path) {
+            Ok(_) => CheckResult::new("label_db", CheckStatus::Pass, format!("opened {}", path)),
+            Err(e) => CheckResult::new("label_db", CheckStatus::Fail, format!("{}: {}", path, e)),
+        }
+    }
+    #[cfg(not(feature = "learner-labels"))]
+    {
+        CheckResult::new(
+            "label_db",
+            CheckStatus::Warn,
+            format!(
+                "{} is configured but the learner-labels feature is disabled",
+                path
+            ),
+        )
+    }
+}
+
+/// Check for raw socket access needed by passive capture
+///
+/// Missing permissions only degrade passive fingerprinting, so they are
+/// reported as a warning.
+pub fn check_capture_permissions() -> CheckResult {
+    #[cfg(target_os = "linux")]
+    {
+        let cap_eff = std::fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|status| {
+                status
+                    .lines()
+                    .find_map(|line| line.strip_prefix("CapEff:"))
+                    .and_then(|hex| u64::from_str_radix(hex.trim(), 16).ok())
+            });
+
+        match cap_eff {
+            Some(caps) if caps & (1 << CAP_NET_RAW) != 0 => {
+                CheckResult::new("capture", CheckStatus::Pass, "CAP_NET_RAW available")
+            }
+            Some(_) => CheckResult::new(
+                "capture",
+                CheckStatus::Warn,
+                "CAP_NET_RAW missing; live capture unavailable",
+            ),
+            None => CheckResult::new(
+                "capture",
+                CheckStatus::Warn,
+                "could not read effective capabilities",
+            ),
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        CheckResult::new(
+            "capture",
+            CheckStatus::Skip,
+            "capability check only supported on Linux",
+        )
+    }
+}
+
+/// Build, parse and fingerprint a sample ClientHello for each enabled profile
+pub fn check_profiles() -> Vec<CheckResult> {
+    let mut profiles: Vec<_> = mapped_tls_clients().into_iter().collect();
+    profiles.sort_by(|a, b| a.0.cmp(&b.0));
+
+    profiles
+        .into_iter()
+        .map(|(id, profile)| {
+            let name = format!("profile:{}", id);
+            let Some(&(_, golden)) = GOLDEN_JA4.iter().find(|(golden_id, _)| *golden_id == id)
+            else {
+                return CheckResult::new(name, CheckStatus::Warn, "no golden JA4 recorded");
+            };
+
+            match sample_ja4(&profile.tls_config) {
+                Ok(ja4) if ja4 == golden => CheckResult::new(name, CheckStatus::Pass, ja4),
+                Ok(ja4) => CheckResult::new(
+                    name,
+                    CheckStatus::Fail,
+                    format!("JA4 {} does not match golden {}", ja4, golden),
+                ),
+                Err(e) => CheckResult::new(name, CheckStatus::Fail, e),
+            }
+        })
+        .collect()
+}
+
+/// JA4 of the ClientHello a spec puts on the wire
+///
+/// The record is parsed back rather than fingerprinting the spec directly, so
+/// serialization regressions are caught too.
+fn sample_ja4(spec: &fingerprint::ClientHelloSpec) -> Result<String, String> {
+    let record = TLSHandshakeBuilder::build_client_hello(spec, SAMPLE_SNI)?;
+    let hello = find_client_hello(&record)
+        .ok_or_else(|| "sample ClientHello could not be parsed".to_string())?;
+
+    // supported_versions (43) advertises TLS 1.3; the legacy field stays at 1.2
+    let version = if hello.extensions.contains(&43) {
+        "1.3"
+    } else {
+        "1.2"
+    };
+
+    Ok(JA4::generate(
+        't',
+        version,
+        hello.sni.is_some(),
+        &hello.cipher_suites,
+        &hello.extensions,
+        hello.alpn.as_deref(),
+        &hello.signature_algorithms,
+    )
+    .to_fingerprint_string())
+}
+
+/// Check that the clock and local timezone look plausible
+pub fn check_clock() -> CheckResult {
+    let now = Utc::now();
+    if !(2024..2100).contains(&now.year()) {
+        return CheckResult::new(
+            "clock",
+            CheckStatus::Fail,
+            format!("system clock reads {}", now.to_rfc3339()),
+        );
+    }
+
+    let offset_secs = Local::now().offset().fix().local_minus_utc();
+    let detail = format!(
+        "{} (UTC offset {}{:02}:{:02})",
+        now.to_rfc3339(),
+        if offset_secs < 0 { '-' } else { '+' },
+        offset_secs.abs() / 3600,
+        (offset_secs.abs() % 3600) / 60
+    );
+    if offset_secs.abs() > 14 * 3600 || offset_secs % 900 != 0 {
+        CheckResult::new(
+            "clock",
+            CheckStatus::Warn,
+            format!("unusual timezone: {}", detail),
+        )
+    } else {
+        CheckResult::new("clock", CheckStatus::Pass, detail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_match_golden_ja4() {
+        let checks = check_profiles();
+        assert_eq!(checks.len(), mapped_tls_clients().len());
+        for check in checks {
+            assert_eq!(check.status, CheckStatus::Pass, "{:?}", check);
+        }
+    }
+
+    #[test]
+    fn test_invalid_config_fails_report() {
+        let config = GatewayConfig {
+            port: 0,
+            ..Default::default()
+        };
+
+        let report = ReadinessReport::new(vec![check_config(&config), check_clock()]);
+        assert!(!report.ready);
+        assert_eq!(report.failures().count(), 1);
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["ready"], false);
+        assert_eq!(json["checks"][0]["name"], "config");
+        assert_eq!(json["checks"][0]["status"], "fail");
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_redis_fails() {
+        let check = check_redis("redis://127.0.0.1:1").await;
+        assert_eq!(check.status, CheckStatus::Fail);
+    }
+}
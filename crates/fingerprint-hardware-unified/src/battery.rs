@@ -0,0 +1,235 @@
+//! Battery Status API fingerprinting
+//!
+//! `navigator.getBattery()` is only exposed by Chromium (Firefox removed it in 52, WebKit
+//! never shipped it). Chromium rounds `level` to two decimals and reports a mains-powered
+//! desktop as `charging: true, level: 1, chargingTime: 0, dischargingTime: Infinity`.
+
+use crate::context::{ClientContext, HardwareAnomaly};
+use crate::screen::DeviceClass;
+use crate::HardwareError;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Battery Status API snapshot
+///
+/// `Infinity` times are represented as `None` so the struct round-trips through JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatteryFingerprint {
+    /// Whether `navigator.getBattery` exists
+    pub api_available: bool,
+    pub charging: Option<bool>,
+    /// 0.0 - 1.0
+    pub level: Option<f64>,
+    /// Seconds until full, `None` for Infinity
+    pub charging_time: Option<f64>,
+    /// Seconds until empty, `None` for Infinity
+    pub discharging_time: Option<f64>,
+}
+
+impl BatteryFingerprint {
+    /// Browser without the Battery Status API
+    pub fn unavailable() -> Self {
+        Self {
+            api_available: false,
+            charging: None,
+            level: None,
+            charging_time: None,
+            discharging_time: None,
+        }
+    }
+
+    /// Mains-powered device without a battery
+    pub fn mains_powered() -> Self {
+        Self {
+            api_available: true,
+            charging: Some(true),
+            level: Some(1.0),
+            charging_time: Some(0.0),
+            discharging_time: None,
+        }
+    }
+}
+
+pub struct BatteryAnalyzer;
+
+impl BatteryAnalyzer {
+    pub async fn analyze() -> Result<BatteryFingerprint, HardwareError> {
+        // Implementation would go here
+        Ok(BatteryFingerprint::unavailable())
+    }
+
+    /// Generate a realistic battery snapshot for the claimed client
+    pub fn generate<R: Rng + ?Sized>(rng: &mut R, ctx: &ClientContext) -> BatteryFingerprint {
+        if !ctx.is_chromium() {
+            return BatteryFingerprint::unavailable();
+        }
+        // desktops have no battery; a share of laptops sit on the charger at 100%
+        let has_battery = !matches!(ctx.class, DeviceClass::Desktop);
+        if !has_battery || rng.gen_bool(0.2) {
+            return BatteryFingerprint::mains_powered();
+        }
+
+        let level = rng.gen_range(5..100u32) as f64 / 100.0;
+        let charging = rng.gen_bool(0.4);
+        let (charging_time, discharging_time) = if charging {
+            // Chromium reports Infinity until it has an estimate
+            let estimate = rng
+                .gen_bool(0.7)
+                .then(|| ((1.0 - level) * rng.gen_range(4000.0..9000.0f64) / 60.0).round() * 60.0);
+            (estimate, None)
+        } else {
+            let estimate = rng
+                .gen_bool(0.7)
+                .then(|| (level * rng.gen_range(15000.0..40000.0f64) / 60.0).round() * 60.0);
+            (None, estimate)
+        };
+
+        BatteryFingerprint {
+            api_available: true,
+            charging: Some(charging),
+            level: Some(level),
+            charging_time,
+            discharging_time,
+        }
+    }
+
+    /// Find contradictions between the snapshot and the claimed client
+    pub fn detect(fp: &BatteryFingerprint, ctx: &ClientContext) -> Vec<HardwareAnomaly> {
+        let mut anomalies = Vec::new();
+
+        if fp.api_available != ctx.is_chromium() {
+            anomalies.push(HardwareAnomaly::new(
+                "battery.api",
+                format!(
+                    "getBattery {} but {} {:?} {}",
+                    if fp.api_available {
+                        "present"
+                    } else {
+                        "missing"
+                    },
+                    ctx.browser,
+                    ctx.platform,
+                    if ctx.is_chromium() {
+                        "ships it"
+                    } else {
+                        "does not ship it"
+                    }
+                ),
+                0.9,
+            ));
+        }
+        if !fp.api_available {
+            return anomalies;
+        }
+
+        if let Some(level) = fp.level {
+            if !(0.0..=1.0).contains(&level) {
+                anomalies.push(HardwareAnomaly::new(
+                    "battery.level",
+                    format!("level {} outside 0..1", level),
+                    1.0,
+                ));
+            } else if ((level * 100.0).round() - level * 100.0).abs() > 1e-6 {
+                anomalies.push(HardwareAnomaly::new(
+                    "battery.level",
+                    format!("level {} has more precision than Chromium exposes", level),
+                    0.7,
+                ));
+            }
+        }
+
+        match fp.charging {
+            Some(true) if fp.discharging_time.is_some() => anomalies.push(HardwareAnomaly::new(
+                "battery.dischargingTime",
+                "charging but dischargingTime is finite",
+                1.0,
+            )),
+            Some(false) if fp.charging_time.is_some() => anomalies.push(HardwareAnomaly::new(
+                "battery.chargingTime",
+                "discharging but chargingTime is finite",
+                1.0,
+            )),
+            Some(false) if fp.level == Some(1.0) && fp.discharging_time.is_none() => anomalies
+                .push(HardwareAnomaly::new(
+                    "battery.level",
+                    "full battery, not charging and no discharge estimate",
+                    0.4,
+                )),
+            _ => {}
+        }
+
+        if matches!(ctx.class, DeviceClass::Desktop) && fp.charging == Some(false) {
+            anomalies.push(HardwareAnomaly::new(
+                "battery.charging",
+                "desktop device reports running on battery",
+                0.6,
+            ));
+        }
+
+        anomalies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Platform;
+    use fingerprint_core::types::BrowserType;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_generated_battery_is_consistent() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        for browser in [
+            BrowserType::Chrome,
+            BrowserType::Firefox,
+            BrowserType::Safari,
+        ] {
+            for class in [
+                DeviceClass::Desktop,
+                DeviceClass::Laptop,
+                DeviceClass::Phone,
+            ] {
+                let ctx = ClientContext::new(browser, Platform::Windows, class);
+                for _ in 0..50 {
+                    let fp = BatteryAnalyzer::generate(&mut rng, &ctx);
+                    assert!(BatteryAnalyzer::detect(&fp, &ctx).is_empty(), "{:?}", fp);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_battery_contradictions() {
+        let firefox =
+            ClientContext::new(BrowserType::Firefox, Platform::Linux, DeviceClass::Laptop);
+        let anomalies = BatteryAnalyzer::detect(&BatteryFingerprint::mains_powered(), &firefox);
+        assert_eq!(anomalies[0].signal, "battery.api");
+
+        let chrome =
+            ClientContext::new(BrowserType::Chrome, Platform::Windows, DeviceClass::Laptop);
+        let fp = BatteryFingerprint {
+            api_available: true,
+            charging: Some(true),
+            level: Some(0.4213),
+            charging_time: None,
+            discharging_time: Some(3600.0),
+        };
+        let signals: Vec<_> = BatteryAnalyzer::detect(&fp, &chrome)
+            .into_iter()
+            .map(|a| a.signal)
+            .collect();
+        assert!(signals.contains(&"battery.level".to_string()));
+        assert!(signals.contains(&"battery.dischargingTime".to_string()));
+    }
+
+    #[test]
+    fn test_battery_serde_roundtrip() {
+        let fp = BatteryFingerprint::mains_powered();
+        let json = serde_json::to_string(&fp).unwrap();
+        assert_eq!(
+            serde_json::from_str::<BatteryFingerprint>(&json).unwrap(),
+            fp
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! Shared generation context and detection findings
+//!
+//! Browser-API signal modules (battery, sensors, permissions, ...) all need to know which
+//! browser, platform and device class they are generating for or validating against, and
+//! all report contradictions the same way.
+
+use crate::screen::DeviceClass;
+use fingerprint_core::types::BrowserType;
+use serde::{Deserialize, Serialize};
+
+/// Operating system family as seen by web APIs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Platform {
+    Windows,
+    MacOS,
+    Linux,
+    Android,
+    IOS,
+}
+
+impl Platform {
+    pub fn is_mobile(&self) -> bool {
+        matches!(self, Platform::Android | Platform::IOS)
+    }
+
+    /// Parse `navigator.platform` / UA platform strings
+    pub fn from_platform_str(s: &str) -> Option<Self> {
+        let s = s.to_lowercase();
+        if s.contains("win") {
+            Some(Platform::Windows)
+        } else if s.contains("iphone") || s.contains("ipad") || s.contains("ios") {
+            Some(Platform::IOS)
+        } else if s.contains("mac") {
+            Some(Platform::MacOS)
+        } else if s.contains("android") {
+            Some(Platform::Android)
+        } else if s.contains("linux") || s.contains("x11") {
+            Some(Platform::Linux)
+        } else {
+            None
+        }
+    }
+}
+
+/// Claimed client identity used for generation and validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ClientContext {
+    pub browser: BrowserType,
+    pub platform: Platform,
+    pub class: DeviceClass,
+}
+
+impl ClientContext {
+    pub fn new(browser: BrowserType, platform: Platform, class: DeviceClass) -> Self {
+        Self {
+            browser,
+            platform,
+            class,
+        }
+    }
+
+    /// Chromium-based browsers share most API surface
+    pub fn is_chromium(&self) -> bool {
+        // every browser on iOS is WebKit underneath
+        self.platform != Platform::IOS
+            && matches!(
+                self.browser,
+                BrowserType::Chrome | BrowserType::Edge | BrowserType::Opera
+            )
+    }
+
+    pub fn is_gecko(&self) -> bool {
+        self.platform != Platform::IOS && self.browser == BrowserType::Firefox
+    }
+
+    pub fn is_webkit(&self) -> bool {
+        self.platform == Platform::IOS || self.browser == BrowserType::Safari
+    }
+}
+
+/// Contradiction between an observed signal and the claimed client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HardwareAnomaly {
+    /// Signal that produced the finding (e.g. "battery.level")
+    pub signal: String,
+    pub detail: String,
+    /// 0.0 - 1.0, 1.0 means the combination cannot come from the claimed client
+    pub severity: f64,
+}
+
+impl HardwareAnomaly {
+    pub fn new(signal: impl Into<String>, detail: impl Into<String>, severity: f64) -> Self {
+        Self {
+            signal: signal.into(),
+            detail: detail.into(),
+            severity: severity.clamp(0.0, 1.0),
+        }
+    }
+}
@@ -0,0 +1,419 @@
+//! Device farm corpus loader
+//!
+//! Builds reference [`DeviceProfile`]s with frequency weights from real device telemetry
+//! exports. The same corpus serves two purposes:
+//!
+//! - **Generation**: [`DeviceCorpus::sample`] picks realistic profiles in proportion to how
+//!   often they were observed.
+//! - **Detection**: [`DeviceCorpus::plausibility`] scores a submitted hardware fingerprint
+//!   against the closest reference profiles, weighted by their frequency.
+//!
+//! ## Input formats
+//!
+//! - CSV with a header row (always available)
+//! - Parquet (requires the `parquet` feature)
+//!
+//! Both formats use the same column names:
+//!
+//! | column | type | required |
+//! |--------|------|----------|
+//! | `gpu_vendor` | string | yes |
+//! | `cpu_cores` | integer | yes |
+//! | `memory_gb` | float | yes |
+//! | `screen_width` | integer | yes |
+//! | `screen_height` | integer | yes |
+//! | `color_depth` | integer | no (defaults to 24) |
+//! | `touch_support` | bool | no |
+//! | `mobile` | bool | no |
+//! | `platform` | string | yes |
+//! | `count` | integer | no (pre-aggregated exports; defaults to 1) |
+
+use super::{DeviceProfile, HardwareError};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+/// Profiles whose similarity to the candidate falls below this are ignored when scoring
+const MIN_NEIGHBOUR_SIMILARITY: f32 = 0.5;
+
+fn default_color_depth() -> u32 {
+    24
+}
+
+fn default_count() -> u64 {
+    1
+}
+
+/// Single row of device telemetry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceRecord {
+    pub gpu_vendor: String,
+    pub cpu_cores: u32,
+    pub memory_gb: f32,
+    pub screen_width: u32,
+    pub screen_height: u32,
+    #[serde(default = "default_color_depth")]
+    pub color_depth: u32,
+    #[serde(default)]
+    pub touch_support: bool,
+    #[serde(default)]
+    pub mobile: bool,
+    pub platform: String,
+    /// Number of devices this row stands for
+    #[serde(default = "default_count")]
+    pub count: u64,
+}
+
+impl DeviceRecord {
+    /// Key used to merge identical devices into one reference profile
+    fn profile_key(&self) -> String {
+        format!(
+            "{}|{}|{:.0}|{}x{}|{}|{}|{}|{}",
+            self.gpu_vendor.to_lowercase(),
+            self.cpu_cores,
+            self.memory_gb,
+            self.screen_width,
+            self.screen_height,
+            self.color_depth,
+            self.touch_support,
+            self.mobile,
+            self.platform.to_lowercase()
+        )
+    }
+
+    fn to_profile(&self) -> DeviceProfile {
+        DeviceProfile {
+            gpu_vendor: self.gpu_vendor.clone(),
+            cpu_cores: self.cpu_cores,
+            memory_gb: self.memory_gb,
+            screen_resolution: (self.screen_width, self.screen_height),
+            color_depth: self.color_depth,
+            touch_support: self.touch_support,
+            mobile: self.mobile,
+            platform: self.platform.clone(),
+            // ground truth from the device farm
+            confidence_score: 1.0,
+            media: None,
+        }
+    }
+}
+
+/// Reference profile with its observed frequency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightedProfile {
+    pub profile: DeviceProfile,
+    /// Number of devices observed with this profile
+    pub count: u64,
+    /// Share of the corpus (0.0 - 1.0)
+    pub weight: f64,
+}
+
+/// Plausibility verdict for a submitted hardware fingerprint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlausibilityScore {
+    /// 0.0 (never seen anything like it) - 1.0 (common real device)
+    pub score: f64,
+    /// Similarity to the closest reference profile
+    pub best_similarity: f32,
+    /// Weight of the closest reference profile
+    pub best_match_weight: f64,
+    /// Whether the exact profile exists in the corpus
+    pub exact_match: bool,
+}
+
+/// Frequency-weighted collection of real device profiles
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeviceCorpus {
+    profiles: Vec<WeightedProfile>,
+    total_devices: u64,
+    skipped_rows: usize,
+}
+
+impl DeviceCorpus {
+    /// Build a corpus from already parsed records
+    pub fn from_records<I>(records: I) -> Self
+    where
+        I: IntoIterator<Item = DeviceRecord>,
+    {
+        let mut buckets: HashMap<String, (DeviceProfile, u64)> = HashMap::new();
+        let mut skipped_rows = 0;
+
+        for record in records {
+            if record.count == 0 || record.cpu_cores == 0 || record.screen_width == 0 {
+                skipped_rows += 1;
+                continue;
+            }
+            buckets
+                .entry(record.profile_key())
+                .and_modify(|(_, count)| *count += record.count)
+                .or_insert_with(|| (record.to_profile(), record.count));
+        }
+
+        let total_devices: u64 = buckets.values().map(|(_, count)| *count).sum();
+        let mut profiles: Vec<WeightedProfile> = buckets
+            .into_values()
+            .map(|(profile, count)| WeightedProfile {
+                profile,
+                count,
+                weight: count as f64 / total_devices.max(1) as f64,
+            })
+            .collect();
+        // most common first; ties broken by platform/vendor for deterministic output
+        profiles.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.profile.platform.cmp(&b.profile.platform))
+                .then_with(|| a.profile.gpu_vendor.cmp(&b.profile.gpu_vendor))
+        });
+
+        Self {
+            profiles,
+            total_devices,
+            skipped_rows,
+        }
+    }
+
+    /// Load a CSV export (header row required)
+    pub fn from_csv_reader<R: Read>(reader: R) -> Result<Self, HardwareError> {
+        let mut csv_reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+        let records = csv_reader
+            .deserialize::<DeviceRecord>()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| HardwareError::CorpusError(format!("invalid CSV row: {}", e)))?;
+        Ok(Self::from_records(records))
+    }
+
+    /// Load a CSV export from disk
+    pub fn from_csv_path<P: AsRef<Path>>(path: P) -> Result<Self, HardwareError> {
+        let file = std::fs::File::open(path.as_ref()).map_err(|e| {
+            HardwareError::CorpusError(format!("failed to open {}: {}", path.as_ref().display(), e))
+        })?;
+        Self::from_csv_reader(std::io::BufReader::new(file))
+    }
+
+    /// Load a Parquet export from disk
+    #[cfg(feature = "parquet")]
+    pub fn from_parquet_path<P: AsRef<Path>>(path: P) -> Result<Self, HardwareError> {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let file = std::fs::File::open(path.as_ref()).map_err(|e| {
+            HardwareError::CorpusError(format!("failed to open {}: {}", path.as_ref().display(), e))
+        })?;
+        let reader = SerializedFileReader::new(file)
+            .map_err(|e| HardwareError::CorpusError(format!("invalid parquet file: {}", e)))?;
+        let rows = reader
+            .get_row_iter(None)
+            .map_err(|e| HardwareError::CorpusError(format!("invalid parquet file: {}", e)))?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let row =
+                row.map_err(|e| HardwareError::CorpusError(format!("invalid parquet row: {}", e)))?;
+            records.push(parquet_row_to_record(&row)?);
+        }
+        Ok(Self::from_records(records))
+    }
+
+    /// Reference profiles, most common first
+    pub fn profiles(&self) -> &[WeightedProfile] {
+        &self.profiles
+    }
+
+    /// Total number of devices represented by the corpus
+    pub fn total_devices(&self) -> u64 {
+        self.total_devices
+    }
+
+    /// Rows dropped during loading because they were empty or obviously broken
+    pub fn skipped_rows(&self) -> usize {
+        self.skipped_rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+
+    /// Pick a profile at random, proportionally to its observed frequency
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<&DeviceProfile> {
+        if self.total_devices == 0 {
+            return None;
+        }
+        let mut target = rng.gen_range(0..self.total_devices);
+        for weighted in &self.profiles {
+            if target < weighted.count {
+                return Some(&weighted.profile);
+            }
+            target -= weighted.count;
+        }
+        self.profiles.last().map(|w| &w.profile)
+    }
+
+    /// Score how plausible a submitted device profile is against the corpus
+    ///
+    /// The score combines how close the nearest reference profile is with how common the
+    /// matching neighbourhood is, so a rare-but-real device still scores above a profile
+    /// no real device ever reported.
+    pub fn plausibility(&self, candidate: &DeviceProfile) -> PlausibilityScore {
+        let mut best_similarity = 0.0f32;
+        let mut best_match_weight = 0.0f64;
+        let mut neighbourhood_weight = 0.0f64;
+
+        for weighted in &self.profiles {
+            let similarity = weighted.profile.similarity_score(candidate);
+            if similarity > best_similarity
+                || (similarity == best_similarity && weighted.weight > best_match_weight)
+            {
+                best_similarity = similarity;
+                best_match_weight = weighted.weight;
+            }
+            if similarity >= MIN_NEIGHBOUR_SIMILARITY {
+                neighbourhood_weight += weighted.weight * similarity as f64;
+            }
+        }
+
+        let exact_match = best_similarity >= 1.0;
+        // frequency only boosts the score; it never makes a real device implausible
+        let frequency_factor = (neighbourhood_weight * self.profiles.len() as f64)
+            .min(1.0)
+            .sqrt();
+        let score = if self.profiles.is_empty() {
+            0.0
+        } else {
+            (best_similarity as f64 * (0.7 + 0.3 * frequency_factor)).clamp(0.0, 1.0)
+        };
+
+        PlausibilityScore {
+            score,
+            best_similarity,
+            best_match_weight,
+            exact_match,
+        }
+    }
+}
+
+#[cfg(feature = "parquet")]
+fn parquet_row_to_record(row: &parquet::record::Row) -> Result<DeviceRecord, HardwareError> {
+    use parquet::record::Field;
+
+    let mut fields: HashMap<&str, &Field> = HashMap::new();
+    for (name, field) in row.get_column_iter() {
+        fields.insert(name.as_str(), field);
+    }
+
+    let missing = |name: &str| HardwareError::CorpusError(format!("missing column: {}", name));
+    let as_u64 = |name: &str| -> Result<Option<u64>, HardwareError> {
+        Ok(match fields.get(name) {
+            None | Some(Field::Null) => None,
+            Some(Field::Byte(v)) => Some(*v as u64),
+            Some(Field::Short(v)) => Some(*v as u64),
+            Some(Field::Int(v)) => Some(*v as u64),
+            Some(Field::Long(v)) => Some(*v as u64),
+            Some(Field::UByte(v)) => Some(*v as u64),
+            Some(Field::UShort(v)) => Some(*v as u64),
+            Some(Field::UInt(v)) => Some(*v as u64),
+            Some(Field::ULong(v)) => Some(*v),
+            Some(other) => {
+                return Err(HardwareError::CorpusError(format!(
+                    "column {} has unexpected type: {}",
+                    name, other
+                )))
+            }
+        })
+    };
+    let as_f32 = |name: &str| -> Result<Option<f32>, HardwareError> {
+        Ok(match fields.get(name) {
+            Some(Field::Float(v)) => Some(*v),
+            Some(Field::Double(v)) => Some(*v as f32),
+            _ => as_u64(name)?.map(|v| v as f32),
+        })
+    };
+    let as_bool = |name: &str| match fields.get(name) {
+        Some(Field::Bool(v)) => *v,
+        _ => false,
+    };
+    let as_string = |name: &str| match fields.get(name) {
+        Some(Field::Str(v)) => Some(v.clone()),
+        _ => None,
+    };
+
+    Ok(DeviceRecord {
+        gpu_vendor: as_string("gpu_vendor").ok_or_else(|| missing("gpu_vendor"))?,
+        cpu_cores: as_u64("cpu_cores")?.ok_or_else(|| missing("cpu_cores"))? as u32,
+        memory_gb: as_f32("memory_gb")?.ok_or_else(|| missing("memory_gb"))?,
+        screen_width: as_u64("screen_width")?.ok_or_else(|| missing("screen_width"))? as u32,
+        screen_height: as_u64("screen_height")?.ok_or_else(|| missing("screen_height"))? as u32,
+        color_depth: as_u64("color_depth")?.map(|v| v as u32).unwrap_or(24),
+        touch_support: as_bool("touch_support"),
+        mobile: as_bool("mobile"),
+        platform: as_string("platform").ok_or_else(|| missing("platform"))?,
+        count: as_u64("count")?.unwrap_or(1),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    const SAMPLE_CSV: &str = "\
+gpu_vendor,cpu_cores,memory_gb,screen_width,screen_height,color_depth,touch_support,mobile,platform,count
+NVIDIA,8,16,1920,1080,24,false,false,Windows,700
+Apple,8,8,1440,900,30,false,false,macOS,250
+Qualcomm,8,6,412,915,24,true,true,Android,50
+NVIDIA,8,16,1920,1080,24,false,false,Windows,100
+Broken,0,0,0,0,24,false,false,Windows,3
+";
+
+    #[test]
+    fn test_csv_loader_merges_and_weights() {
+        let corpus = DeviceCorpus::from_csv_reader(SAMPLE_CSV.as_bytes()).unwrap();
+        assert_eq!(corpus.profiles().len(), 3);
+        assert_eq!(corpus.total_devices(), 1100);
+        assert_eq!(corpus.skipped_rows(), 1);
+
+        let top = &corpus.profiles()[0];
+        assert_eq!(top.profile.gpu_vendor, "NVIDIA");
+        assert_eq!(top.count, 800);
+        assert!((top.weight - 800.0 / 1100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_csv_loader_rejects_malformed_rows() {
+        let csv = "gpu_vendor,cpu_cores\nNVIDIA,eight\n";
+        assert!(DeviceCorpus::from_csv_reader(csv.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_sample_follows_frequency() {
+        let corpus = DeviceCorpus::from_csv_reader(SAMPLE_CSV.as_bytes()).unwrap();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let windows = (0..1000)
+            .filter(|_| corpus.sample(&mut rng).unwrap().platform == "Windows")
+            .count();
+        assert!(windows > 600 && windows < 850, "windows = {}", windows);
+    }
+
+    #[test]
+    fn test_plausibility_prefers_real_devices() {
+        let corpus = DeviceCorpus::from_csv_reader(SAMPLE_CSV.as_bytes()).unwrap();
+
+        let real = corpus.profiles()[0].profile.clone();
+        let real_score = corpus.plausibility(&real);
+        assert!(real_score.exact_match);
+
+        let mut fake = real.clone();
+        fake.gpu_vendor = "Qualcomm".to_string();
+        fake.mobile = true;
+        fake.touch_support = true;
+        fake.platform = "macOS".to_string();
+        let fake_score = corpus.plausibility(&fake);
+
+        assert!(!fake_score.exact_match);
+        assert!(real_score.score > fake_score.score);
+        assert_eq!(DeviceCorpus::default().plausibility(&real).score, 0.0);
+    }
+}
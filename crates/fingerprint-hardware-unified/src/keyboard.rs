@@ -0,0 +1,301 @@
+//! Keyboard layout fingerprinting
+//!
+//! `navigator.keyboard.getLayoutMap()` (Chromium desktop only) maps physical key codes to
+//! the characters the active layout produces, so a handful of probe keys identify the
+//! layout. The layout should agree with the UI locale; US QWERTY is the common fallback
+//! for multi-layout users, anything rarer carries a lot of identifying information.
+
+use crate::context::{ClientContext, HardwareAnomaly};
+use crate::HardwareError;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Key codes probed during collection
+pub const LAYOUT_PROBES: &[&str] = &[
+    "KeyQ",
+    "KeyW",
+    "KeyY",
+    "KeyZ",
+    "KeyA",
+    "KeyM",
+    "Semicolon",
+    "Quote",
+    "BracketLeft",
+    "Backquote",
+    "Backslash",
+    "Minus",
+    "Digit2",
+];
+
+/// Keyboard layouts distinguishable from the probe keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KeyboardLayout {
+    UsQwerty,
+    UkQwerty,
+    GermanQwertz,
+    FrenchAzerty,
+    SpanishQwerty,
+    RussianJcuken,
+    Dvorak,
+}
+
+impl KeyboardLayout {
+    pub const ALL: [KeyboardLayout; 7] = [
+        KeyboardLayout::UsQwerty,
+        KeyboardLayout::UkQwerty,
+        KeyboardLayout::GermanQwertz,
+        KeyboardLayout::FrenchAzerty,
+        KeyboardLayout::SpanishQwerty,
+        KeyboardLayout::RussianJcuken,
+        KeyboardLayout::Dvorak,
+    ];
+
+    /// Characters produced by [`LAYOUT_PROBES`], in probe order
+    fn probe_values(&self) -> [&'static str; 13] {
+        match self {
+            KeyboardLayout::UsQwerty => [
+                "q", "w", "y", "z", "a", "m", ";", "'", "[", "`", "\\", "-", "2",
+            ],
+            KeyboardLayout::UkQwerty => [
+                "q", "w", "y", "z", "a", "m", ";", "'", "[", "`", "#", "-", "2",
+            ],
+            KeyboardLayout::GermanQwertz => [
+                "q", "w", "z", "y", "a", "m", "ö", "ä", "ü", "^", "#", "ß", "2",
+            ],
+            KeyboardLayout::FrenchAzerty => [
+                "a", "z", "y", "w", "q", ",", "m", "ù", "^", "²", "*", ")", "é",
+            ],
+            KeyboardLayout::SpanishQwerty => [
+                "q", "w", "y", "z", "a", "m", "ñ", "´", "`", "º", "ç", "'", "2",
+            ],
+            KeyboardLayout::RussianJcuken => [
+                "й", "ц", "н", "я", "ф", "ь", "ж", "э", "х", "ё", "\\", "-", "2",
+            ],
+            KeyboardLayout::Dvorak => [
+                "'", ",", "f", ";", "a", "m", "s", "-", "/", "`", "\\", "[", "2",
+            ],
+        }
+    }
+
+    /// Probe subset of the map `getLayoutMap()` resolves to
+    pub fn layout_map(&self) -> BTreeMap<String, String> {
+        LAYOUT_PROBES
+            .iter()
+            .zip(self.probe_values())
+            .map(|(code, key)| (code.to_string(), key.to_string()))
+            .collect()
+    }
+
+    /// Approximate share among clients exposing `getLayoutMap()`
+    pub fn prevalence(&self) -> f64 {
+        match self {
+            KeyboardLayout::UsQwerty => 0.55,
+            KeyboardLayout::UkQwerty => 0.06,
+            KeyboardLayout::GermanQwertz => 0.08,
+            KeyboardLayout::FrenchAzerty => 0.05,
+            KeyboardLayout::SpanishQwerty => 0.05,
+            KeyboardLayout::RussianJcuken => 0.05,
+            KeyboardLayout::Dvorak => 0.005,
+        }
+    }
+
+    /// Layout a user with this UI locale most likely types on
+    pub fn for_locale(locale: &str) -> KeyboardLayout {
+        let locale = locale.replace('_', "-").to_lowercase();
+        let lang = locale.split('-').next().unwrap_or("");
+        match (lang, locale.as_str()) {
+            ("en", "en-gb") | ("en", "en-ie") => KeyboardLayout::UkQwerty,
+            ("de", _) => KeyboardLayout::GermanQwertz,
+            ("fr", "fr-fr") | ("fr", "fr-be") | ("fr", "fr") => KeyboardLayout::FrenchAzerty,
+            ("es", _) => KeyboardLayout::SpanishQwerty,
+            ("ru", _) => KeyboardLayout::RussianJcuken,
+            _ => KeyboardLayout::UsQwerty,
+        }
+    }
+}
+
+/// Keyboard API snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyboardFingerprint {
+    /// `navigator.keyboard.getLayoutMap` exists
+    pub api_available: bool,
+    /// Key code → produced character, for the probed codes
+    pub layout_map: BTreeMap<String, String>,
+}
+
+impl KeyboardFingerprint {
+    pub fn unavailable() -> Self {
+        Self {
+            api_available: false,
+            layout_map: BTreeMap::new(),
+        }
+    }
+
+    /// Identify the layout; every probed key present in the map must agree
+    pub fn layout(&self) -> Option<KeyboardLayout> {
+        if self.layout_map.is_empty() {
+            return None;
+        }
+        KeyboardLayout::ALL.into_iter().find(|layout| {
+            let expected = layout.layout_map();
+            self.layout_map
+                .iter()
+                .filter(|(code, _)| expected.contains_key(*code))
+                .all(|(code, key)| expected.get(code) == Some(key))
+        })
+    }
+}
+
+pub struct KeyboardAnalyzer;
+
+impl KeyboardAnalyzer {
+    pub async fn analyze() -> Result<KeyboardFingerprint, HardwareError> {
+        // Implementation would go here
+        Ok(KeyboardFingerprint::unavailable())
+    }
+
+    /// Only desktop Chromium ships the Keyboard Map API
+    pub fn api_expected(ctx: &ClientContext) -> bool {
+        ctx.is_chromium() && !ctx.platform.is_mobile()
+    }
+
+    /// Generate the keyboard snapshot of a real client with this OS profile and UI locale
+    pub fn generate<R: Rng + ?Sized>(
+        rng: &mut R,
+        ctx: &ClientContext,
+        locale: &str,
+    ) -> KeyboardFingerprint {
+        if !Self::api_expected(ctx) {
+            return KeyboardFingerprint::unavailable();
+        }
+        // multi-layout users are frequently switched to US when the page loads
+        let layout = if rng.gen_bool(0.1) {
+            KeyboardLayout::UsQwerty
+        } else {
+            KeyboardLayout::for_locale(locale)
+        };
+        KeyboardFingerprint {
+            api_available: true,
+            layout_map: layout.layout_map(),
+        }
+    }
+
+    /// Estimated identifying information of the layout in bits
+    ///
+    /// Unrecognised maps (custom layouts, remapped keys) are treated as 1-in-1000.
+    pub fn entropy_bits(fp: &KeyboardFingerprint) -> f64 {
+        if !fp.api_available || fp.layout_map.is_empty() {
+            return 0.0;
+        }
+        let p = fp.layout().map(|l| l.prevalence()).unwrap_or(0.001);
+        -p.log2()
+    }
+
+    /// Find contradictions between the snapshot and the claimed client
+    pub fn detect(
+        fp: &KeyboardFingerprint,
+        ctx: &ClientContext,
+        locale: &str,
+    ) -> Vec<HardwareAnomaly> {
+        let mut anomalies = Vec::new();
+
+        if fp.api_available != Self::api_expected(ctx) {
+            anomalies.push(HardwareAnomaly::new(
+                "keyboard.api",
+                format!(
+                    "getLayoutMap {} on {} {:?}",
+                    if fp.api_available {
+                        "present"
+                    } else {
+                        "missing"
+                    },
+                    ctx.browser,
+                    ctx.platform
+                ),
+                if fp.api_available { 0.9 } else { 0.5 },
+            ));
+        }
+        if !fp.api_available || fp.layout_map.is_empty() {
+            return anomalies;
+        }
+
+        match fp.layout() {
+            None => anomalies.push(HardwareAnomaly::new(
+                "keyboard.layout",
+                "layout map matches no known layout",
+                0.4,
+            )),
+            Some(layout) => {
+                let expected = KeyboardLayout::for_locale(locale);
+                if layout != expected && layout != KeyboardLayout::UsQwerty {
+                    anomalies.push(HardwareAnomaly::new(
+                        "keyboard.locale",
+                        format!("{:?} layout with locale {}", layout, locale),
+                        0.3,
+                    ));
+                }
+            }
+        }
+
+        anomalies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Platform;
+    use crate::screen::DeviceClass;
+    use fingerprint_core::types::BrowserType;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_layouts_are_distinguishable() {
+        for layout in KeyboardLayout::ALL {
+            let fp = KeyboardFingerprint {
+                api_available: true,
+                layout_map: layout.layout_map(),
+            };
+            assert_eq!(fp.layout(), Some(layout));
+        }
+        assert!(
+            KeyboardAnalyzer::entropy_bits(&KeyboardFingerprint {
+                api_available: true,
+                layout_map: KeyboardLayout::Dvorak.layout_map(),
+            }) > 7.0
+        );
+    }
+
+    #[test]
+    fn test_generated_keyboard_is_consistent() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let chrome =
+            ClientContext::new(BrowserType::Chrome, Platform::Windows, DeviceClass::Desktop);
+        let safari = ClientContext::new(BrowserType::Safari, Platform::MacOS, DeviceClass::Laptop);
+        for locale in ["en-US", "de-DE", "fr-FR", "ru-RU"] {
+            for ctx in [chrome, safari] {
+                let fp = KeyboardAnalyzer::generate(&mut rng, &ctx, locale);
+                assert!(KeyboardAnalyzer::detect(&fp, &ctx, locale).is_empty());
+            }
+        }
+
+        let fp = KeyboardAnalyzer::generate(&mut rng, &chrome, "de-DE");
+        let signals: Vec<_> = KeyboardAnalyzer::detect(&fp, &safari, "de-DE")
+            .into_iter()
+            .map(|a| a.signal)
+            .collect();
+        assert_eq!(signals, vec!["keyboard.api".to_string()]);
+    }
+
+    #[test]
+    fn test_layout_locale_mismatch() {
+        let chrome = ClientContext::new(BrowserType::Chrome, Platform::Linux, DeviceClass::Desktop);
+        let fp = KeyboardFingerprint {
+            api_available: true,
+            layout_map: KeyboardLayout::RussianJcuken.layout_map(),
+        };
+        let anomalies = KeyboardAnalyzer::detect(&fp, &chrome, "ja-JP");
+        assert_eq!(anomalies[0].signal, "keyboard.locale");
+    }
+}
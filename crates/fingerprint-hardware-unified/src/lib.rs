@@ -11,6 +11,13 @@
 //! - ✅ **Storage Analysis**: localStorage/sessionStorage fingerprinting
 //! - ✅ **WebRTC Detection**: MediaDevices and ICE candidate analysis
 //! - ✅ **Hardware Sensors**: GPU, CPU, and device characteristic detection
+//! - ✅ **Battery / Sensors / Permissions**: Battery Status, DeviceMotion/Orientation and Permissions API state
+//! - ✅ **Speech Voices / Keyboard Layout**: speechSynthesis voice sets and getLayoutMap layouts
+//! - ✅ **Media Codecs / DRM**: canPlayType and EME key system support matrices
+//! - ✅ **Private Mode Signals**: per-browser incognito storage heuristics for scoring and generation
+//! - ✅ **Screen Consistency**: screen/viewport/devicePixelRatio/zoom modeling and validation
+//! - ✅ **Device Farm Corpus**: Frequency-weighted reference profiles from real telemetry
+//! - ✅ **Host GPU Probing**: Real adapter vendor via wgpu (`gpu-probe` feature)
 //!
 //! ## Architecture
 //!
@@ -21,9 +28,32 @@
 //! ├── FontEnumerator ──→ System font detection
 //! ├── StorageScanner ──→ Storage capability analysis
 //! ├── WebRTCInspector ──→ Media device enumeration
+//! ├── BatteryAnalyzer ──→ Battery Status API
+//! ├── SensorAnalyzer ──→ DeviceMotion/Orientation availability
+//! ├── PermissionsAnalyzer ──→ Permissions API state patterns
+//! ├── SpeechAnalyzer ──→ speechSynthesis voice list
+//! ├── KeyboardAnalyzer ──→ Keyboard layout map
 //! └── DeviceProfiler ──→ Hardware characteristic profiling
+//!
+//! DeviceCorpus ──→ CSV/Parquet ground truth ──→ sampling + plausibility scoring
 //! ```
 
+#[cfg(feature = "battery")]
+pub mod battery;
+pub mod context;
+pub mod corpus;
+#[cfg(feature = "keyboard")]
+pub mod keyboard;
+pub mod media;
+#[cfg(feature = "permissions")]
+pub mod permissions;
+pub mod private_mode;
+pub mod screen;
+#[cfg(feature = "sensors")]
+pub mod sensors;
+#[cfg(feature = "speech")]
+pub mod speech;
+
 use std::collections::HashMap;
 use fingerprint_core::fingerprint::{Fingerprint, FingerprintType, FingerprintMetadata};
 use serde::{Deserialize, Serialize};
@@ -44,6 +74,8 @@ pub enum HardwareError {
     WebRTCError(String),
     #[error("Device profiling failed: {0}")]
     DeviceError(String),
+    #[error("Device corpus loading failed: {0}")]
+    CorpusError(String),
 }
 
 /// Hardware fingerprint result containing all detected characteristics
@@ -72,6 +104,26 @@ pub struct HardwareFingerprint {
     #[cfg(feature = "webrtc")]
     pub webrtc: Option<WebRTCFingerprint>,
     
+    /// Battery Status API snapshot
+    #[cfg(feature = "battery")]
+    pub battery: Option<BatteryFingerprint>,
+    
+    /// Motion/orientation sensor availability
+    #[cfg(feature = "sensors")]
+    pub sensors: Option<SensorFingerprint>,
+    
+    /// Permissions API state pattern
+    #[cfg(feature = "permissions")]
+    pub permissions: Option<PermissionsFingerprint>,
+    
+    /// speechSynthesis voice list
+    #[cfg(feature = "speech")]
+    pub speech: Option<SpeechFingerprint>,
+    
+    /// Keyboard layout map
+    #[cfg(feature = "keyboard")]
+    pub keyboard: Option<KeyboardFingerprint>,
+    
     /// Overall hardware profile
     pub device_profile: DeviceProfile,
     
@@ -94,6 +146,16 @@ impl HardwareFingerprint {
             storage: None,
             #[cfg(feature = "webrtc")]
             webrtc: None,
+            #[cfg(feature = "battery")]
+            battery: None,
+            #[cfg(feature = "sensors")]
+            sensors: None,
+            #[cfg(feature = "permissions")]
+            permissions: None,
+            #[cfg(feature = "speech")]
+            speech: None,
+            #[cfg(feature = "keyboard")]
+            keyboard: None,
             device_profile: DeviceProfile::default(),
             metadata: FingerprintMetadata::new(),
         }
@@ -126,6 +188,31 @@ impl HardwareFingerprint {
             self.webrtc = Some(WebRTCInspector::inspect().await?);
         }
         
+        #[cfg(feature = "battery")]
+        {
+            self.battery = Some(BatteryAnalyzer::analyze().await?);
+        }
+        
+        #[cfg(feature = "sensors")]
+        {
+            self.sensors = Some(SensorAnalyzer::analyze().await?);
+        }
+        
+        #[cfg(feature = "permissions")]
+        {
+            self.permissions = Some(PermissionsAnalyzer::analyze().await?);
+        }
+        
+        #[cfg(feature = "speech")]
+        {
+            self.speech = Some(SpeechAnalyzer::analyze().await?);
+        }
+        
+        #[cfg(feature = "keyboard")]
+        {
+            self.keyboard = Some(KeyboardAnalyzer::analyze().await?);
+        }
+        
         self.device_profile = DeviceProfiler::profile(self).await?;
         Ok(())
     }
@@ -386,6 +473,9 @@ pub struct DeviceProfile {
     pub mobile: bool,
     pub platform: String,
     pub confidence_score: f32,
+    /// Codec/DRM support matrix, when collected
+    #[serde(default)]
+    pub media: Option<media::MediaCapabilities>,
 }
 
 impl DeviceProfile {
@@ -400,12 +490,19 @@ impl DeviceProfile {
             mobile: false,
             platform: "Unknown".to_string(),
             confidence_score: 0.0,
+            media: None,
         }
     }
     
+    /// Take the GPU vendor from a probed adapter
+    #[cfg(feature = "gpu-probe")]
+    pub fn apply_gpu(&mut self, adapter: &fingerprint_hardware::GpuAdapter) {
+        self.gpu_vendor = adapter.vendor().name().to_string();
+    }
+    
     pub fn similarity_score(&self, other: &Self) -> f32 {
         let mut score = 0.0;
-        let total_checks = 8.0;
+        let mut total_checks = 8.0;
         
         if self.gpu_vendor == other.gpu_vendor { score += 1.0; }
         if self.cpu_cores == other.cpu_cores { score += 1.0; }
@@ -416,6 +513,12 @@ impl DeviceProfile {
         if self.mobile == other.mobile { score += 1.0; }
         if self.platform == other.platform { score += 1.0; }
         
+        // codec/DRM support only counts when both sides collected it
+        if let (Some(ours), Some(theirs)) = (&self.media, &other.media) {
+            score += ours.similarity(theirs);
+            total_checks += 1.0;
+        }
+        
         score / total_checks
     }
 }
@@ -427,6 +530,31 @@ impl DeviceProfiler {
         // Implementation would analyze all hardware fingerprints to create device profile
         Ok(DeviceProfile::default())
     }
+
+    /// Profile of the host itself, GPU from the real adapter (`gpu-probe` feature)
+    ///
+    /// Fails on hosts without any GPU adapter, e.g. headless servers.
+    #[cfg(feature = "gpu-probe")]
+    pub fn host_profile() -> Result<DeviceProfile, HardwareError> {
+        let gpu = fingerprint_hardware::GpuProber::probe()
+            .map_err(|e| HardwareError::DeviceError(e.to_string()))?;
+        let mut profile = DeviceProfile::default();
+        profile.apply_gpu(&gpu);
+        profile.cpu_cores = std::thread::available_parallelism().map_or(0, |n| n.get() as u32);
+        profile.platform = match std::env::consts::OS {
+            "windows" => "Windows",
+            "macos" => "macOS",
+            "linux" => "Linux",
+            "android" => "Android",
+            "ios" => "iOS",
+            _ => "Unknown",
+        }
+        .to_string();
+        profile.mobile = matches!(std::env::consts::OS, "android" | "ios");
+        // measured rather than reported
+        profile.confidence_score = 1.0;
+        Ok(profile)
+    }
 }
 
 // Re-export main types
@@ -436,6 +564,26 @@ pub use crate::{
     DeviceProfile,
     DeviceProfiler,
 };
+#[cfg(feature = "battery")]
+pub use battery::{BatteryAnalyzer, BatteryFingerprint};
+pub use context::{ClientContext, HardwareAnomaly, Platform};
+pub use corpus::{DeviceCorpus, DeviceRecord, PlausibilityScore, WeightedProfile};
+#[cfg(feature = "keyboard")]
+pub use keyboard::{KeyboardAnalyzer, KeyboardFingerprint, KeyboardLayout};
+pub use screen::{
+    BrowserChrome, DeviceClass, ScreenInconsistency, ScreenInconsistencyKind, ScreenMetrics,
+    ScreenModel, ScreenValidator, ZoomBehavior,
+};
+pub use media::{CodecSupport, DrmSystem, MediaAnalyzer, MediaCapabilities};
+pub use private_mode::{
+    PrivateModeAssessment, PrivateModeEvidence, PrivateModeModel, StorageSignals,
+};
+#[cfg(feature = "permissions")]
+pub use permissions::{PermissionState, PermissionsAnalyzer, PermissionsFingerprint};
+#[cfg(feature = "sensors")]
+pub use sensors::{SensorAnalyzer, SensorFingerprint};
+#[cfg(feature = "speech")]
+pub use speech::{SpeechAnalyzer, SpeechFingerprint, VoiceInfo};
 
 #[cfg(test)]
 mod tests {
@@ -463,6 +611,7 @@ mod tests {
             mobile: false,
             platform: "Windows".to_string(),
             confidence_score: 0.9,
+            media: None,
         };
 
         let profile2 = DeviceProfile {
@@ -475,6 +624,7 @@ mod tests {
             mobile: false,
             platform: "Windows".to_string(),
             confidence_score: 0.8,
+            media: None,
         };
 
         let similarity = profile1.similarity_score(&profile2);
@@ -0,0 +1,291 @@
+//! Media codec and DRM (EME) capability fingerprinting
+//!
+//! `HTMLMediaElement.canPlayType()` answers and the set of key systems accepted by
+//! `navigator.requestMediaKeySystemAccess()` are fixed per engine build and OS, which
+//! makes them a cheap, high-entropy cross-check of the claimed browser: Safari always has
+//! FairPlay, never Widevine; only Edge on Windows has PlayReady; Theora is gone from
+//! Chromium; HEVC needs platform decoders.
+
+use crate::context::{ClientContext, HardwareAnomaly, Platform};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// `canPlayType()` probes, keyed by a short name
+pub const CODEC_PROBES: &[(&str, &str)] = &[
+    ("h264", "video/mp4; codecs=\"avc1.42E01E\""),
+    ("hevc", "video/mp4; codecs=\"hev1.1.6.L93.B0\""),
+    ("vp8", "video/webm; codecs=\"vp8\""),
+    ("vp9", "video/webm; codecs=\"vp9\""),
+    ("av1", "video/mp4; codecs=\"av01.0.05M.08\""),
+    ("theora", "video/ogg; codecs=\"theora\""),
+    ("aac", "audio/mp4; codecs=\"mp4a.40.2\""),
+    ("opus", "audio/ogg; codecs=\"opus\""),
+    ("vorbis", "audio/ogg; codecs=\"vorbis\""),
+    ("flac", "audio/flac"),
+    ("hls", "application/vnd.apple.mpegurl"),
+];
+
+/// `canPlayType()` answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CodecSupport {
+    /// Empty string
+    No,
+    Maybe,
+    Probably,
+}
+
+impl CodecSupport {
+    pub fn from_can_play_type(answer: &str) -> Self {
+        match answer {
+            "probably" => CodecSupport::Probably,
+            "maybe" => CodecSupport::Maybe,
+            _ => CodecSupport::No,
+        }
+    }
+
+    pub fn is_playable(&self) -> bool {
+        !matches!(self, CodecSupport::No)
+    }
+}
+
+/// EME key system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum DrmSystem {
+    Widevine,
+    PlayReady,
+    FairPlay,
+    ClearKey,
+}
+
+impl DrmSystem {
+    /// Key system string passed to `requestMediaKeySystemAccess`
+    pub fn key_system(&self) -> &'static str {
+        match self {
+            DrmSystem::Widevine => "com.widevine.alpha",
+            DrmSystem::PlayReady => "com.microsoft.playready",
+            DrmSystem::FairPlay => "com.apple.fps",
+            DrmSystem::ClearKey => "org.w3.clearkey",
+        }
+    }
+
+    pub fn from_key_system(key_system: &str) -> Option<Self> {
+        match key_system {
+            "com.widevine.alpha" => Some(DrmSystem::Widevine),
+            "com.microsoft.playready" | "com.microsoft.playready.recommendation" => {
+                Some(DrmSystem::PlayReady)
+            }
+            "com.apple.fps" | "com.apple.fps.1_0" | "com.apple.fps.2_0" => {
+                Some(DrmSystem::FairPlay)
+            }
+            "org.w3.clearkey" => Some(DrmSystem::ClearKey),
+            _ => None,
+        }
+    }
+}
+
+/// Codec and DRM capability snapshot
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MediaCapabilities {
+    /// Probe name (see [`CODEC_PROBES`]) → answer
+    pub codecs: BTreeMap<String, CodecSupport>,
+    /// Key systems that resolved
+    pub drm: BTreeSet<DrmSystem>,
+}
+
+impl MediaCapabilities {
+    /// Expected support matrix for a browser/OS combination
+    pub fn for_client(ctx: &ClientContext) -> Self {
+        use CodecSupport::{Maybe, No, Probably};
+
+        let chromium = ctx.is_chromium();
+        let gecko = ctx.is_gecko();
+        let webkit = ctx.is_webkit();
+        let platform_hevc = matches!(
+            ctx.platform,
+            Platform::Windows | Platform::MacOS | Platform::Android | Platform::IOS
+        );
+
+        let codecs = [
+            ("h264", Probably),
+            (
+                "hevc",
+                if webkit || (chromium && platform_hevc) {
+                    Probably
+                } else {
+                    No
+                },
+            ),
+            ("vp8", Probably),
+            ("vp9", Probably),
+            ("av1", if webkit { No } else { Probably }),
+            ("theora", if gecko { Probably } else { No }),
+            ("aac", Probably),
+            ("opus", if webkit { No } else { Probably }),
+            ("vorbis", if webkit { No } else { Probably }),
+            ("flac", Probably),
+            (
+                "hls",
+                if webkit || ctx.platform == Platform::Android {
+                    Maybe
+                } else {
+                    No
+                },
+            ),
+        ]
+        .into_iter()
+        .map(|(name, support)| (name.to_string(), support))
+        .collect();
+
+        let mut drm = BTreeSet::new();
+        if webkit {
+            drm.insert(DrmSystem::FairPlay);
+        } else {
+            drm.insert(DrmSystem::Widevine);
+            drm.insert(DrmSystem::ClearKey);
+            if ctx.browser == fingerprint_core::types::BrowserType::Edge
+                && ctx.platform == Platform::Windows
+            {
+                drm.insert(DrmSystem::PlayReady);
+            }
+        }
+
+        Self { codecs, drm }
+    }
+
+    /// Jaccard similarity over playable codecs and DRM systems (0.0 - 1.0)
+    pub fn similarity(&self, other: &Self) -> f32 {
+        let playable = |caps: &Self| -> BTreeSet<String> {
+            caps.codecs
+                .iter()
+                .filter(|(_, support)| support.is_playable())
+                .map(|(name, _)| name.clone())
+                .chain(caps.drm.iter().map(|drm| drm.key_system().to_string()))
+                .collect()
+        };
+        let a = playable(self);
+        let b = playable(other);
+        let union = a.union(&b).count();
+        if union == 0 {
+            return 1.0;
+        }
+        a.intersection(&b).count() as f32 / union as f32
+    }
+}
+
+pub struct MediaAnalyzer;
+
+impl MediaAnalyzer {
+    /// Find contradictions between observed capabilities and the claimed client
+    pub fn detect(caps: &MediaCapabilities, ctx: &ClientContext) -> Vec<HardwareAnomaly> {
+        let mut anomalies = Vec::new();
+        let webkit = ctx.is_webkit();
+
+        if webkit && !caps.drm.contains(&DrmSystem::FairPlay) {
+            anomalies.push(HardwareAnomaly::new(
+                "media.drm.fairplay",
+                format!("{} on {:?} without FairPlay", ctx.browser, ctx.platform),
+                0.9,
+            ));
+        }
+        if !webkit && caps.drm.contains(&DrmSystem::FairPlay) {
+            anomalies.push(HardwareAnomaly::new(
+                "media.drm.fairplay",
+                format!("FairPlay exposed by non-WebKit {}", ctx.browser),
+                1.0,
+            ));
+        }
+        if webkit && caps.drm.contains(&DrmSystem::Widevine) {
+            anomalies.push(HardwareAnomaly::new(
+                "media.drm.widevine",
+                "Widevine exposed by WebKit",
+                1.0,
+            ));
+        }
+        let expected = MediaCapabilities::for_client(ctx);
+        if caps.drm.contains(&DrmSystem::PlayReady) && !expected.drm.contains(&DrmSystem::PlayReady)
+        {
+            anomalies.push(HardwareAnomaly::new(
+                "media.drm.playready",
+                format!("PlayReady on {} {:?}", ctx.browser, ctx.platform),
+                0.8,
+            ));
+        }
+
+        for (name, expected_support) in &expected.codecs {
+            let Some(observed) = caps.codecs.get(name) else {
+                continue;
+            };
+            let severity = match (expected_support.is_playable(), observed.is_playable()) {
+                (false, true) => 0.7,
+                (true, false) => 0.5,
+                _ => continue,
+            };
+            // HEVC depends on GPU decoders, so it is only a weak signal
+            let severity = if name == "hevc" {
+                severity * 0.4
+            } else {
+                severity
+            };
+            anomalies.push(HardwareAnomaly::new(
+                format!("media.codec.{}", name),
+                format!(
+                    "canPlayType({}) = {:?}, expected {:?} for {}",
+                    name, observed, expected_support, ctx.browser
+                ),
+                severity,
+            ));
+        }
+
+        anomalies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::screen::DeviceClass;
+    use fingerprint_core::types::BrowserType;
+
+    fn ctx(browser: BrowserType, platform: Platform) -> ClientContext {
+        ClientContext::new(browser, platform, DeviceClass::Laptop)
+    }
+
+    #[test]
+    fn test_expected_matrix_is_self_consistent() {
+        for browser in [
+            BrowserType::Chrome,
+            BrowserType::Firefox,
+            BrowserType::Safari,
+            BrowserType::Edge,
+        ] {
+            for platform in [Platform::Windows, Platform::MacOS, Platform::Linux] {
+                let c = ctx(browser, platform);
+                let caps = MediaCapabilities::for_client(&c);
+                assert!(MediaAnalyzer::detect(&caps, &c).is_empty(), "{:?}", c);
+            }
+        }
+    }
+
+    #[test]
+    fn test_safari_without_fairplay() {
+        let chrome = MediaCapabilities::for_client(&ctx(BrowserType::Chrome, Platform::MacOS));
+        let safari = ctx(BrowserType::Safari, Platform::MacOS);
+        let anomalies = MediaAnalyzer::detect(&chrome, &safari);
+        assert!(anomalies.iter().any(|a| a.signal == "media.drm.fairplay"));
+        assert!(anomalies.iter().any(|a| a.signal == "media.drm.widevine"));
+    }
+
+    #[test]
+    fn test_similarity() {
+        let chrome = MediaCapabilities::for_client(&ctx(BrowserType::Chrome, Platform::Windows));
+        let edge = MediaCapabilities::for_client(&ctx(BrowserType::Edge, Platform::Windows));
+        let safari = MediaCapabilities::for_client(&ctx(BrowserType::Safari, Platform::MacOS));
+        assert_eq!(chrome.similarity(&chrome), 1.0);
+        assert!(chrome.similarity(&edge) > chrome.similarity(&safari));
+        assert_eq!(
+            DrmSystem::from_key_system(DrmSystem::FairPlay.key_system()),
+            Some(DrmSystem::FairPlay)
+        );
+    }
+}
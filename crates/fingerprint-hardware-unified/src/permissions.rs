@@ -0,0 +1,267 @@
+//! Permissions API state fingerprinting
+//!
+//! `navigator.permissions.query()` leaks two things: which permission names the engine
+//! understands (unknown names reject with a TypeError) and the default state of each one.
+//! Fresh profiles sit at `prompt` almost everywhere; automation frameworks famously report
+//! `notifications: denied` while `Notification.permission` still says `default`.
+
+use crate::context::{ClientContext, HardwareAnomaly};
+use crate::HardwareError;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Result of `navigator.permissions.query({ name })`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionState {
+    Granted,
+    Denied,
+    Prompt,
+    /// Query rejected with TypeError (name unknown to the engine)
+    Unsupported,
+}
+
+/// Permission names understood by Chromium
+const CHROMIUM_PERMISSIONS: &[&str] = &[
+    "accelerometer",
+    "background-sync",
+    "camera",
+    "clipboard-read",
+    "clipboard-write",
+    "geolocation",
+    "gyroscope",
+    "magnetometer",
+    "microphone",
+    "midi",
+    "notifications",
+    "payment-handler",
+    "persistent-storage",
+    "screen-wake-lock",
+];
+
+/// Permission names understood by Gecko
+const GECKO_PERMISSIONS: &[&str] = &[
+    "camera",
+    "geolocation",
+    "microphone",
+    "midi",
+    "notifications",
+    "persistent-storage",
+    "push",
+    "screen-wake-lock",
+    "storage-access",
+];
+
+/// Permission names understood by WebKit
+const WEBKIT_PERMISSIONS: &[&str] = &["camera", "geolocation", "microphone", "notifications"];
+
+/// Names probed during collection (union of all engines)
+pub const PROBED_PERMISSIONS: &[&str] = &[
+    "accelerometer",
+    "background-sync",
+    "camera",
+    "clipboard-read",
+    "clipboard-write",
+    "geolocation",
+    "gyroscope",
+    "magnetometer",
+    "microphone",
+    "midi",
+    "notifications",
+    "payment-handler",
+    "persistent-storage",
+    "push",
+    "screen-wake-lock",
+    "storage-access",
+];
+
+/// Permissions API snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PermissionsFingerprint {
+    /// `navigator.permissions` exists
+    pub api_available: bool,
+    /// Query result per probed permission name
+    pub states: BTreeMap<String, PermissionState>,
+    /// `Notification.permission` ("default", "granted", "denied")
+    pub notification_permission: Option<String>,
+}
+
+pub struct PermissionsAnalyzer;
+
+impl PermissionsAnalyzer {
+    pub async fn analyze() -> Result<PermissionsFingerprint, HardwareError> {
+        // Implementation would go here
+        Ok(PermissionsFingerprint {
+            api_available: true,
+            states: BTreeMap::new(),
+            notification_permission: None,
+        })
+    }
+
+    /// Permission names the claimed engine understands
+    pub fn supported_permissions(ctx: &ClientContext) -> &'static [&'static str] {
+        if ctx.is_chromium() {
+            CHROMIUM_PERMISSIONS
+        } else if ctx.is_gecko() {
+            GECKO_PERMISSIONS
+        } else {
+            WEBKIT_PERMISSIONS
+        }
+    }
+
+    /// Generate the state pattern of a typical returning user of the claimed browser
+    pub fn generate<R: Rng + ?Sized>(rng: &mut R, ctx: &ClientContext) -> PermissionsFingerprint {
+        let supported = Self::supported_permissions(ctx);
+        let mut states = BTreeMap::new();
+
+        for name in PROBED_PERMISSIONS {
+            let state = if !supported.contains(name) {
+                PermissionState::Unsupported
+            } else {
+                match *name {
+                    // granted without a prompt in Chromium
+                    "clipboard-write" | "background-sync" | "accelerometer" | "gyroscope"
+                    | "magnetometer" | "payment-handler" | "screen-wake-lock" => {
+                        PermissionState::Granted
+                    }
+                    "notifications" if rng.gen_bool(0.15) => PermissionState::Denied,
+                    "notifications" if rng.gen_bool(0.05) => PermissionState::Granted,
+                    "geolocation" if rng.gen_bool(0.1) => PermissionState::Granted,
+                    _ => PermissionState::Prompt,
+                }
+            };
+            states.insert(name.to_string(), state);
+        }
+
+        let notification_permission = match states.get("notifications") {
+            Some(PermissionState::Granted) => "granted",
+            Some(PermissionState::Denied) => "denied",
+            _ => "default",
+        };
+
+        PermissionsFingerprint {
+            api_available: true,
+            states,
+            notification_permission: Some(notification_permission.to_string()),
+        }
+    }
+
+    /// Find contradictions between the snapshot and the claimed client
+    pub fn detect(fp: &PermissionsFingerprint, ctx: &ClientContext) -> Vec<HardwareAnomaly> {
+        let mut anomalies = Vec::new();
+        if !fp.api_available {
+            anomalies.push(HardwareAnomaly::new(
+                "permissions.api",
+                "navigator.permissions missing (shipped by all current engines)",
+                0.7,
+            ));
+            return anomalies;
+        }
+
+        let supported = Self::supported_permissions(ctx);
+        for (name, state) in &fp.states {
+            let known = supported.contains(&name.as_str());
+            if known && *state == PermissionState::Unsupported {
+                anomalies.push(HardwareAnomaly::new(
+                    format!("permissions.{}", name),
+                    format!(
+                        "{} rejects '{}' but the engine supports it",
+                        ctx.browser, name
+                    ),
+                    0.8,
+                ));
+            } else if !known && *state != PermissionState::Unsupported {
+                anomalies.push(HardwareAnomaly::new(
+                    format!("permissions.{}", name),
+                    format!(
+                        "{} answered '{}' which the engine does not know",
+                        ctx.browser, name
+                    ),
+                    0.9,
+                ));
+            }
+        }
+
+        // headless Chromium / automation signature
+        if let (Some(PermissionState::Denied), Some(notification)) = (
+            fp.states.get("notifications"),
+            fp.notification_permission.as_deref(),
+        ) {
+            if notification == "default" {
+                anomalies.push(HardwareAnomaly::new(
+                    "permissions.notifications",
+                    "permissions.query says denied while Notification.permission is default",
+                    1.0,
+                ));
+            }
+        }
+
+        let granted_sensitive = ["camera", "microphone", "geolocation", "clipboard-read"]
+            .iter()
+            .filter(|name| fp.states.get(**name) == Some(&PermissionState::Granted))
+            .count();
+        if granted_sensitive >= 3 {
+            anomalies.push(HardwareAnomaly::new(
+                "permissions.granted",
+                format!(
+                    "{} sensitive permissions pre-granted (pre-seeded automation profile)",
+                    granted_sensitive
+                ),
+                0.6,
+            ));
+        }
+
+        anomalies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Platform;
+    use crate::screen::DeviceClass;
+    use fingerprint_core::types::BrowserType;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_generated_permissions_are_consistent() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        for browser in [
+            BrowserType::Chrome,
+            BrowserType::Firefox,
+            BrowserType::Safari,
+        ] {
+            let ctx = ClientContext::new(browser, Platform::MacOS, DeviceClass::Laptop);
+            for _ in 0..30 {
+                let fp = PermissionsAnalyzer::generate(&mut rng, &ctx);
+                assert!(PermissionsAnalyzer::detect(&fp, &ctx).is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_headless_notification_mismatch() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        let ctx = ClientContext::new(BrowserType::Chrome, Platform::Linux, DeviceClass::Desktop);
+        let mut fp = PermissionsAnalyzer::generate(&mut rng, &ctx);
+        fp.states
+            .insert("notifications".to_string(), PermissionState::Denied);
+        fp.notification_permission = Some("default".to_string());
+
+        let anomalies = PermissionsAnalyzer::detect(&fp, &ctx);
+        assert!(anomalies.iter().any(|a| a.severity >= 1.0));
+    }
+
+    #[test]
+    fn test_chrome_permissions_claimed_as_safari() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        let chrome = ClientContext::new(BrowserType::Chrome, Platform::MacOS, DeviceClass::Laptop);
+        let safari = ClientContext::new(BrowserType::Safari, Platform::MacOS, DeviceClass::Laptop);
+        let fp = PermissionsAnalyzer::generate(&mut rng, &chrome);
+        assert!(!PermissionsAnalyzer::detect(&fp, &safari).is_empty());
+
+        let json = serde_json::to_string(&fp).unwrap();
+        assert!(json.contains("\"unsupported\"") || json.contains("\"prompt\""));
+    }
+}
@@ -0,0 +1,285 @@
+//! Incognito / private browsing signal modeling
+//!
+//! Private modes keep storage in memory, which leaks through quota estimates and a few
+//! per-engine API differences:
+//!
+//! - Chromium: `navigator.storage.estimate()` quota is capped by RAM instead of disk
+//!   (detectors compare it to `2 × jsHeapSizeLimit`); before 76 `webkitRequestFileSystem`
+//!   failed outright
+//! - Firefox: `navigator.serviceWorker` is undefined; before 115 `indexedDB.open` failed
+//! - Safari: `navigator.storage.getDirectory()` rejects; before 11 `localStorage.setItem`
+//!   threw `QuotaExceededError`
+//!
+//! The same rules score observed clients and guard generated storage fingerprints from
+//! accidentally looking private.
+
+use crate::context::ClientContext;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Storage-related observations relevant to private mode
+///
+/// `None` means the probe was not run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StorageSignals {
+    /// `navigator.storage.estimate().quota`
+    pub quota_bytes: Option<u64>,
+    /// `performance.memory.jsHeapSizeLimit` (Chromium only)
+    pub heap_size_limit: Option<u64>,
+    /// `indexedDB.open()` succeeded
+    pub indexed_db_open_ok: Option<bool>,
+    /// `localStorage.setItem()` succeeded
+    pub local_storage_writable: Option<bool>,
+    /// `navigator.serviceWorker` exists
+    pub service_worker_available: Option<bool>,
+    /// `webkitRequestFileSystem()` succeeded
+    pub file_system_api_ok: Option<bool>,
+    /// `navigator.storage.getDirectory()` resolved
+    pub opfs_available: Option<bool>,
+}
+
+#[cfg(feature = "storage")]
+impl From<&crate::StorageFingerprint> for StorageSignals {
+    fn from(fp: &crate::StorageFingerprint) -> Self {
+        Self {
+            quota_bytes: fp.quota_info.as_ref().map(|q| q.quota),
+            indexed_db_open_ok: Some(fp.indexed_db_available),
+            local_storage_writable: Some(fp.local_storage_available),
+            ..Default::default()
+        }
+    }
+}
+
+/// Rule that fired during assessment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrivateModeEvidence {
+    pub signal: String,
+    pub detail: String,
+    /// Likelihood ratio contribution (log-odds added when the rule fires)
+    pub weight: f64,
+}
+
+/// Private mode assessment of one client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrivateModeAssessment {
+    /// 0.0 - 1.0
+    pub probability: f64,
+    pub evidence: Vec<PrivateModeEvidence>,
+}
+
+impl PrivateModeAssessment {
+    pub fn is_private(&self) -> bool {
+        self.probability >= 0.5
+    }
+}
+
+/// Per-browser private mode model
+pub struct PrivateModeModel;
+
+impl PrivateModeModel {
+    /// Prior log-odds of private mode (~5% of sessions)
+    const PRIOR_LOG_ODDS: f64 = -2.9;
+
+    /// Largest quota a private session of the claimed browser can report
+    pub fn quota_ceiling(ctx: &ClientContext, signals: &StorageSignals) -> Option<u64> {
+        if ctx.is_chromium() {
+            // incognito quota is derived from RAM, regular quota from disk
+            Some(signals.heap_size_limit.map_or(GIB, |heap| heap * 2))
+        } else if ctx.is_webkit() {
+            Some(GIB)
+        } else {
+            // Firefox private browsing reports the regular group limit
+            None
+        }
+    }
+
+    /// Score the likelihood that the signals come from a private session
+    pub fn assess(
+        ctx: &ClientContext,
+        version: u32,
+        signals: &StorageSignals,
+    ) -> PrivateModeAssessment {
+        let mut evidence = Vec::new();
+        let mut fire = |signal: &str, detail: String, weight: f64| {
+            evidence.push(PrivateModeEvidence {
+                signal: signal.to_string(),
+                detail,
+                weight,
+            });
+        };
+
+        if let (Some(quota), Some(ceiling)) =
+            (signals.quota_bytes, Self::quota_ceiling(ctx, signals))
+        {
+            if quota < ceiling {
+                fire(
+                    "storage.quota",
+                    format!("quota {} below private ceiling {}", quota, ceiling),
+                    if ctx.is_chromium() { 5.0 } else { 2.0 },
+                );
+            }
+        }
+
+        if ctx.is_chromium() && version < 76 && signals.file_system_api_ok == Some(false) {
+            fire(
+                "storage.filesystem",
+                "webkitRequestFileSystem failed".to_string(),
+                6.0,
+            );
+        }
+
+        if ctx.is_gecko() {
+            if signals.service_worker_available == Some(false) {
+                fire(
+                    "storage.serviceWorker",
+                    "navigator.serviceWorker undefined".to_string(),
+                    5.0,
+                );
+            }
+            if version < 115 && signals.indexed_db_open_ok == Some(false) {
+                fire(
+                    "storage.indexedDB",
+                    "indexedDB.open failed".to_string(),
+                    6.0,
+                );
+            }
+        }
+
+        if ctx.is_webkit() {
+            if signals.opfs_available == Some(false) && version >= 16 {
+                fire(
+                    "storage.getDirectory",
+                    "navigator.storage.getDirectory rejected".to_string(),
+                    5.0,
+                );
+            }
+            if signals.local_storage_writable == Some(false) {
+                fire(
+                    "storage.localStorage",
+                    "localStorage.setItem threw".to_string(),
+                    if version < 11 { 6.0 } else { 2.0 },
+                );
+            }
+        }
+
+        let log_odds = Self::PRIOR_LOG_ODDS + evidence.iter().map(|e| e.weight).sum::<f64>();
+        PrivateModeAssessment {
+            probability: 1.0 / (1.0 + (-log_odds).exp()),
+            evidence,
+        }
+    }
+
+    /// Generate signals of a regular (non-private) session
+    ///
+    /// `disk_bytes` is the device disk size the quota is derived from.
+    pub fn generate_regular<R: Rng + ?Sized>(
+        rng: &mut R,
+        ctx: &ClientContext,
+        disk_bytes: u64,
+    ) -> StorageSignals {
+        // share of the free disk the browser grants to one origin
+        let free = (disk_bytes as f64 * rng.gen_range(0.2..0.7)) as u64;
+        let quota = if ctx.is_chromium() {
+            free * 6 / 10
+        } else if ctx.is_gecko() {
+            (free / 2 / 5).min(10 * GIB)
+        } else {
+            free * 6 / 10
+        };
+
+        let mut signals = StorageSignals {
+            quota_bytes: Some(quota),
+            heap_size_limit: ctx.is_chromium().then_some(4096 * 1024 * 1024),
+            indexed_db_open_ok: Some(true),
+            local_storage_writable: Some(true),
+            service_worker_available: Some(true),
+            file_system_api_ok: ctx.is_chromium().then_some(true),
+            opfs_available: Some(true),
+        };
+        Self::ensure_regular(&mut signals, ctx);
+        signals
+    }
+
+    /// Adjust signals so they cannot be mistaken for a private session
+    pub fn ensure_regular(signals: &mut StorageSignals, ctx: &ClientContext) {
+        if let (Some(quota), Some(ceiling)) =
+            (signals.quota_bytes, Self::quota_ceiling(ctx, signals))
+        {
+            if quota < ceiling {
+                // small disks still get quota above the RAM-derived cap
+                signals.quota_bytes = Some(ceiling + ceiling / 4);
+            }
+        }
+        for probe in [
+            &mut signals.indexed_db_open_ok,
+            &mut signals.local_storage_writable,
+            &mut signals.service_worker_available,
+            &mut signals.file_system_api_ok,
+            &mut signals.opfs_available,
+        ] {
+            if *probe == Some(false) {
+                *probe = Some(true);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Platform;
+    use crate::screen::DeviceClass;
+    use fingerprint_core::types::BrowserType;
+    use rand::SeedableRng;
+
+    fn ctx(browser: BrowserType, platform: Platform) -> ClientContext {
+        ClientContext::new(browser, platform, DeviceClass::Laptop)
+    }
+
+    #[test]
+    fn test_generated_regular_sessions_are_not_private() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        for c in [
+            ctx(BrowserType::Chrome, Platform::Windows),
+            ctx(BrowserType::Firefox, Platform::Linux),
+            ctx(BrowserType::Safari, Platform::MacOS),
+        ] {
+            for disk in [32 * GIB, 256 * GIB, 2048 * GIB] {
+                let signals = PrivateModeModel::generate_regular(&mut rng, &c, disk);
+                let assessment = PrivateModeModel::assess(&c, 130, &signals);
+                assert!(!assessment.is_private(), "{:?} {:?}", c, assessment);
+            }
+        }
+    }
+
+    #[test]
+    fn test_private_signals_per_browser() {
+        let chrome = ctx(BrowserType::Chrome, Platform::Windows);
+        let incognito = StorageSignals {
+            quota_bytes: Some(300 * 1024 * 1024),
+            heap_size_limit: Some(4096 * 1024 * 1024),
+            ..Default::default()
+        };
+        assert!(PrivateModeModel::assess(&chrome, 130, &incognito).is_private());
+
+        let firefox = ctx(BrowserType::Firefox, Platform::Windows);
+        let private = StorageSignals {
+            service_worker_available: Some(false),
+            ..Default::default()
+        };
+        let assessment = PrivateModeModel::assess(&firefox, 130, &private);
+        assert!(assessment.is_private());
+        assert_eq!(assessment.evidence[0].signal, "storage.serviceWorker");
+
+        let safari = ctx(BrowserType::Safari, Platform::MacOS);
+        let mut private = StorageSignals {
+            opfs_available: Some(false),
+            ..Default::default()
+        };
+        assert!(PrivateModeModel::assess(&safari, 17, &private).is_private());
+        PrivateModeModel::ensure_regular(&mut private, &safari);
+        assert!(!PrivateModeModel::assess(&safari, 17, &private).is_private());
+    }
+}
@@ -0,0 +1,580 @@
+//! Screen / viewport / zoom consistency modeling
+//!
+//! `screen.*`, `window.inner*`/`outer*` and `devicePixelRatio` are not independent: they
+//! are all derived from the physical panel, the OS scale factor, the browser chrome
+//! (tabs, address bar, borders) and the page zoom. Browsers also disagree on which of them
+//! zoom affects:
+//!
+//! | engine | `devicePixelRatio` | `screen.width` | `innerWidth` |
+//! |--------|--------------------|----------------|--------------|
+//! | Chromium | scales with zoom | fixed | shrinks with zoom |
+//! | Firefox | scales with zoom | shrinks with zoom | shrinks with zoom |
+//! | Safari | fixed | fixed | shrinks with zoom |
+//!
+//! [`ScreenModel`] generates combinations that obey these rules, and [`ScreenValidator`]
+//! flags observed telemetry that cannot be produced by any real browser window.
+
+use fingerprint_core::types::BrowserType;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Zoom levels offered by browser zoom menus / Ctrl+/-
+pub const BROWSER_ZOOM_LEVELS: &[f64] = &[
+    0.25, 0.33, 0.5, 0.67, 0.75, 0.8, 0.9, 1.0, 1.1, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0, 4.0, 5.0,
+];
+
+/// OS scale factors (Windows display scaling, macOS Retina, mobile panels)
+pub const NATIVE_SCALE_FACTORS: &[f64] = &[
+    1.0, 1.25, 1.5, 1.75, 2.0, 2.25, 2.5, 2.625, 2.75, 3.0, 3.5, 4.0,
+];
+
+/// Slack for rounding of CSS pixel values
+const PIXEL_TOLERANCE: u32 = 2;
+
+/// Device class used to pick panel sizes and window behavior
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DeviceClass {
+    Desktop,
+    Laptop,
+    Tablet,
+    Phone,
+}
+
+impl DeviceClass {
+    pub fn is_mobile(&self) -> bool {
+        matches!(self, DeviceClass::Tablet | DeviceClass::Phone)
+    }
+
+    /// Common (physical width, physical height, native scale factor) combinations
+    fn panels(&self) -> &'static [(u32, u32, f64)] {
+        match self {
+            DeviceClass::Desktop => &[
+                (1920, 1080, 1.0),
+                (2560, 1440, 1.0),
+                (3840, 2160, 1.5),
+                (3840, 2160, 2.0),
+                (1680, 1050, 1.0),
+                (2560, 1080, 1.0),
+            ],
+            DeviceClass::Laptop => &[
+                (1366, 768, 1.0),
+                (1920, 1080, 1.25),
+                (1920, 1080, 1.5),
+                (1536, 960, 1.0),
+                (2880, 1800, 2.0),
+                (2560, 1600, 2.0),
+                (3024, 1964, 2.0),
+            ],
+            DeviceClass::Tablet => &[(1620, 2160, 2.0), (1536, 2048, 2.0), (1600, 2560, 2.0)],
+            DeviceClass::Phone => &[
+                (1170, 2532, 3.0),
+                (1179, 2556, 3.0),
+                (1080, 2400, 2.625),
+                (1080, 2340, 2.75),
+                (1440, 3120, 3.5),
+                (750, 1334, 2.0),
+            ],
+        }
+    }
+}
+
+/// Browser chrome dimensions in CSS pixels at 100% zoom
+///
+/// `width` is the horizontal difference `outerWidth - innerWidth` (window borders and
+/// scrollbar-less frame), `height` is `outerHeight - innerHeight` (tab strip, toolbar,
+/// bookmarks bar).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BrowserChrome {
+    pub width: u32,
+    pub height: u32,
+    /// Height reserved by the OS (taskbar / dock / menu bar): `screen.height - availHeight`
+    pub os_reserved_height: u32,
+}
+
+impl BrowserChrome {
+    /// Typical desktop chrome for a browser (mobile browsers report outer == inner)
+    pub fn for_browser(browser: BrowserType, class: DeviceClass) -> Self {
+        if class.is_mobile() {
+            return Self {
+                width: 0,
+                height: 0,
+                os_reserved_height: 0,
+            };
+        }
+        match browser {
+            BrowserType::Chrome | BrowserType::Edge | BrowserType::Opera => Self {
+                width: 16,
+                height: 87,
+                os_reserved_height: 40,
+            },
+            BrowserType::Firefox => Self {
+                width: 16,
+                height: 78,
+                os_reserved_height: 40,
+            },
+            BrowserType::Safari => Self {
+                width: 0,
+                height: 80,
+                os_reserved_height: 25,
+            },
+        }
+    }
+}
+
+/// How page zoom propagates to the values exposed to JavaScript
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZoomBehavior {
+    /// `devicePixelRatio` is multiplied by the zoom factor
+    pub dpr_scales: bool,
+    /// `screen.width/height` are divided by the zoom factor
+    pub screen_scales: bool,
+}
+
+impl ZoomBehavior {
+    pub fn for_browser(browser: BrowserType) -> Self {
+        match browser {
+            BrowserType::Chrome | BrowserType::Edge | BrowserType::Opera => Self {
+                dpr_scales: true,
+                screen_scales: false,
+            },
+            BrowserType::Firefox => Self {
+                dpr_scales: true,
+                screen_scales: true,
+            },
+            BrowserType::Safari => Self {
+                dpr_scales: false,
+                screen_scales: false,
+            },
+        }
+    }
+}
+
+/// Screen and window metrics as reported to JavaScript (CSS pixels)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScreenMetrics {
+    pub screen_width: u32,
+    pub screen_height: u32,
+    pub avail_width: u32,
+    pub avail_height: u32,
+    pub outer_width: u32,
+    pub outer_height: u32,
+    pub inner_width: u32,
+    pub inner_height: u32,
+    pub device_pixel_ratio: f64,
+    /// Page zoom used during generation (not observable directly; `None` for telemetry)
+    pub zoom: Option<f64>,
+}
+
+/// Generator of internally consistent screen metrics
+pub struct ScreenModel;
+
+impl ScreenModel {
+    /// Generate metrics for a device class and browser
+    ///
+    /// Desktop windows are maximized most of the time and zoom is 100% for the large
+    /// majority of sessions; both are reflected in the sampling.
+    pub fn generate<R: Rng + ?Sized>(
+        rng: &mut R,
+        class: DeviceClass,
+        browser: BrowserType,
+    ) -> ScreenMetrics {
+        let &(physical_width, physical_height, native_scale) =
+            class.panels().choose(rng).unwrap_or(&(1920, 1080, 1.0));
+
+        let zoom = if class.is_mobile() || rng.gen_bool(0.85) {
+            1.0
+        } else {
+            *[0.8, 0.9, 1.1, 1.25, 1.5].choose(rng).unwrap_or(&1.0)
+        };
+        let maximized = class.is_mobile() || rng.gen_bool(0.75);
+
+        Self::compose(
+            physical_width,
+            physical_height,
+            native_scale,
+            zoom,
+            maximized,
+            class,
+            browser,
+        )
+    }
+
+    /// Derive every metric from the physical panel, scale factor, zoom and window state
+    pub fn compose(
+        physical_width: u32,
+        physical_height: u32,
+        native_scale: f64,
+        zoom: f64,
+        maximized: bool,
+        class: DeviceClass,
+        browser: BrowserType,
+    ) -> ScreenMetrics {
+        let chrome = BrowserChrome::for_browser(browser, class);
+        let behavior = ZoomBehavior::for_browser(browser);
+
+        let screen_divisor = if behavior.screen_scales {
+            native_scale * zoom
+        } else {
+            native_scale
+        };
+        let screen_width = (physical_width as f64 / screen_divisor).round() as u32;
+        let screen_height = (physical_height as f64 / screen_divisor).round() as u32;
+
+        let avail_width = screen_width;
+        let avail_height = screen_height.saturating_sub(chrome.os_reserved_height);
+
+        let (outer_width, outer_height) = if class.is_mobile() {
+            (screen_width, screen_height)
+        } else if maximized {
+            (avail_width, avail_height)
+        } else {
+            // restored windows are typically ~80% of the work area
+            (
+                (avail_width as f64 * 0.8).round() as u32,
+                (avail_height as f64 * 0.8).round() as u32,
+            )
+        };
+
+        let inner_width = (outer_width.saturating_sub(chrome.width) as f64 / zoom).round() as u32;
+        let inner_height =
+            (outer_height.saturating_sub(chrome.height) as f64 / zoom).round() as u32;
+
+        let device_pixel_ratio = if behavior.dpr_scales {
+            native_scale * zoom
+        } else {
+            native_scale
+        };
+
+        ScreenMetrics {
+            screen_width,
+            screen_height,
+            avail_width,
+            avail_height,
+            outer_width,
+            outer_height,
+            inner_width,
+            inner_height,
+            device_pixel_ratio,
+            zoom: Some(zoom),
+        }
+    }
+}
+
+/// Kind of impossible or suspicious combination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ScreenInconsistencyKind {
+    /// availWidth/availHeight larger than the screen
+    AvailExceedsScreen,
+    /// Window larger than the available work area
+    OuterExceedsAvail,
+    /// Viewport larger than the window at the implied zoom
+    InnerExceedsOuter,
+    /// devicePixelRatio outside anything a browser can produce
+    ImpossibleDevicePixelRatio,
+    /// devicePixelRatio not explained by any OS scale factor × browser zoom level
+    UnusualDevicePixelRatio,
+    /// Mobile browser whose viewport does not match the screen
+    MobileViewportMismatch,
+    /// Window chrome smaller than the browser ever renders (headless / resized window)
+    MissingBrowserChrome,
+}
+
+/// Single finding from [`ScreenValidator::validate`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScreenInconsistency {
+    pub kind: ScreenInconsistencyKind,
+    /// 0.0 - 1.0, 1.0 means physically impossible
+    pub severity: f64,
+    pub detail: String,
+}
+
+/// Validator flagging screen metrics no real browser window can produce
+pub struct ScreenValidator;
+
+impl ScreenValidator {
+    /// Check observed metrics against the claimed browser and device class
+    pub fn validate(
+        metrics: &ScreenMetrics,
+        browser: BrowserType,
+        class: DeviceClass,
+    ) -> Vec<ScreenInconsistency> {
+        let mut issues = Vec::new();
+        let behavior = ZoomBehavior::for_browser(browser);
+        let dpr = metrics.device_pixel_ratio;
+
+        if metrics.avail_width > metrics.screen_width + PIXEL_TOLERANCE
+            || metrics.avail_height > metrics.screen_height + PIXEL_TOLERANCE
+        {
+            issues.push(ScreenInconsistency {
+                kind: ScreenInconsistencyKind::AvailExceedsScreen,
+                severity: 1.0,
+                detail: format!(
+                    "avail {}x{} exceeds screen {}x{}",
+                    metrics.avail_width,
+                    metrics.avail_height,
+                    metrics.screen_width,
+                    metrics.screen_height
+                ),
+            });
+        }
+
+        // Windows may overhang the work area by their invisible resize borders
+        let border_slack = if class.is_mobile() {
+            PIXEL_TOLERANCE
+        } else {
+            16
+        };
+        if metrics.outer_width > metrics.avail_width + border_slack
+            || metrics.outer_height > metrics.avail_height + border_slack
+        {
+            issues.push(ScreenInconsistency {
+                kind: ScreenInconsistencyKind::OuterExceedsAvail,
+                severity: 0.8,
+                detail: format!(
+                    "outer {}x{} exceeds avail {}x{}",
+                    metrics.outer_width,
+                    metrics.outer_height,
+                    metrics.avail_width,
+                    metrics.avail_height
+                ),
+            });
+        }
+
+        if !(0.25..=10.0).contains(&dpr) || !dpr.is_finite() {
+            issues.push(ScreenInconsistency {
+                kind: ScreenInconsistencyKind::ImpossibleDevicePixelRatio,
+                severity: 1.0,
+                detail: format!("devicePixelRatio {} out of range", dpr),
+            });
+            return issues;
+        }
+
+        let implied_zoom = Self::implied_zoom(metrics, behavior);
+        match implied_zoom {
+            Some(zoom) => {
+                // viewport in device-independent pixels must fit in the window
+                let inner_scaled = (metrics.inner_width as f64 * zoom).round() as u32;
+                if inner_scaled > metrics.outer_width + PIXEL_TOLERANCE {
+                    issues.push(ScreenInconsistency {
+                        kind: ScreenInconsistencyKind::InnerExceedsOuter,
+                        severity: 1.0,
+                        detail: format!(
+                            "innerWidth {} at zoom {:.2} exceeds outerWidth {}",
+                            metrics.inner_width, zoom, metrics.outer_width
+                        ),
+                    });
+                }
+
+                let chrome = BrowserChrome::for_browser(browser, class);
+                let inner_height_scaled = (metrics.inner_height as f64 * zoom).round() as u32;
+                let observed_chrome_height =
+                    metrics.outer_height.saturating_sub(inner_height_scaled);
+                if !class.is_mobile()
+                    && observed_chrome_height + PIXEL_TOLERANCE < chrome.height / 2
+                {
+                    issues.push(ScreenInconsistency {
+                        kind: ScreenInconsistencyKind::MissingBrowserChrome,
+                        severity: 0.6,
+                        detail: format!(
+                            "outerHeight - innerHeight = {}, expected ~{} for {}",
+                            observed_chrome_height, chrome.height, browser
+                        ),
+                    });
+                }
+            }
+            None => issues.push(ScreenInconsistency {
+                kind: ScreenInconsistencyKind::UnusualDevicePixelRatio,
+                severity: 0.5,
+                detail: format!(
+                    "devicePixelRatio {} is not a known scale factor × zoom level for {}",
+                    dpr, browser
+                ),
+            }),
+        }
+
+        if class.is_mobile() {
+            let diff = metrics.inner_width.abs_diff(metrics.screen_width);
+            if diff > metrics.screen_width / 10 {
+                issues.push(ScreenInconsistency {
+                    kind: ScreenInconsistencyKind::MobileViewportMismatch,
+                    severity: 0.7,
+                    detail: format!(
+                        "mobile innerWidth {} vs screen.width {}",
+                        metrics.inner_width, metrics.screen_width
+                    ),
+                });
+            }
+        }
+
+        issues
+    }
+
+    /// Whether the metrics contain at least one physically impossible combination
+    pub fn is_impossible(
+        metrics: &ScreenMetrics,
+        browser: BrowserType,
+        class: DeviceClass,
+    ) -> bool {
+        Self::validate(metrics, browser, class)
+            .iter()
+            .any(|issue| issue.severity >= 1.0)
+    }
+
+    /// Find the zoom level that explains the observed devicePixelRatio
+    ///
+    /// Returns `None` when no OS scale factor × browser zoom level combination matches.
+    fn implied_zoom(metrics: &ScreenMetrics, behavior: ZoomBehavior) -> Option<f64> {
+        let dpr = metrics.device_pixel_ratio;
+        if !behavior.dpr_scales {
+            // dpr is the native scale; zoom is only visible through the viewport ratio
+            NATIVE_SCALE_FACTORS
+                .iter()
+                .find(|scale| (*scale - dpr).abs() < 0.01)?;
+            let ratio = metrics.outer_width as f64 / metrics.inner_width.max(1) as f64;
+            return BROWSER_ZOOM_LEVELS
+                .iter()
+                .copied()
+                .filter(|zoom| *zoom <= ratio + 0.02)
+                .fold(None, |best: Option<f64>, zoom| {
+                    Some(best.map_or(zoom, |b| b.max(zoom)))
+                })
+                .or(Some(1.0));
+        }
+
+        // prefer zoom 1.0, then the zoom closest to 1.0
+        let mut candidates: Vec<f64> = Vec::new();
+        for scale in NATIVE_SCALE_FACTORS {
+            for zoom in BROWSER_ZOOM_LEVELS {
+                if (scale * zoom - dpr).abs() < 0.01 {
+                    candidates.push(*zoom);
+                }
+            }
+        }
+        candidates.sort_by(|a, b| {
+            (a - 1.0)
+                .abs()
+                .partial_cmp(&(b - 1.0).abs())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        candidates.first().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_generated_metrics_validate_cleanly() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        for class in [
+            DeviceClass::Desktop,
+            DeviceClass::Laptop,
+            DeviceClass::Tablet,
+            DeviceClass::Phone,
+        ] {
+            for browser in [
+                BrowserType::Chrome,
+                BrowserType::Firefox,
+                BrowserType::Safari,
+            ] {
+                for _ in 0..20 {
+                    let metrics = ScreenModel::generate(&mut rng, class, browser);
+                    let issues = ScreenValidator::validate(&metrics, browser, class);
+                    assert!(
+                        issues.is_empty(),
+                        "{:?}/{:?} {:?} -> {:?}",
+                        class,
+                        browser,
+                        metrics,
+                        issues
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_zoom_behavior_per_engine() {
+        let chrome = ScreenModel::compose(
+            1920,
+            1080,
+            1.0,
+            1.25,
+            true,
+            DeviceClass::Desktop,
+            BrowserType::Chrome,
+        );
+        assert_eq!(chrome.screen_width, 1920);
+        assert!((chrome.device_pixel_ratio - 1.25).abs() < 1e-9);
+
+        let firefox = ScreenModel::compose(
+            1920,
+            1080,
+            1.0,
+            1.25,
+            true,
+            DeviceClass::Desktop,
+            BrowserType::Firefox,
+        );
+        assert_eq!(firefox.screen_width, 1536);
+
+        let safari = ScreenModel::compose(
+            2880,
+            1800,
+            2.0,
+            1.25,
+            true,
+            DeviceClass::Laptop,
+            BrowserType::Safari,
+        );
+        assert!((safari.device_pixel_ratio - 2.0).abs() < 1e-9);
+        assert_eq!(safari.screen_width, 1440);
+        assert!(safari.inner_width < safari.outer_width);
+    }
+
+    #[test]
+    fn test_validator_flags_impossible_combinations() {
+        let mut metrics = ScreenModel::compose(
+            1920,
+            1080,
+            1.0,
+            1.0,
+            true,
+            DeviceClass::Desktop,
+            BrowserType::Chrome,
+        );
+
+        metrics.inner_width = metrics.outer_width + 200;
+        assert!(ScreenValidator::is_impossible(
+            &metrics,
+            BrowserType::Chrome,
+            DeviceClass::Desktop
+        ));
+
+        let mut headless = ScreenModel::compose(
+            1920,
+            1080,
+            1.0,
+            1.0,
+            true,
+            DeviceClass::Desktop,
+            BrowserType::Chrome,
+        );
+        headless.inner_height = headless.outer_height;
+        let issues =
+            ScreenValidator::validate(&headless, BrowserType::Chrome, DeviceClass::Desktop);
+        assert!(issues
+            .iter()
+            .any(|i| i.kind == ScreenInconsistencyKind::MissingBrowserChrome));
+
+        let mut odd_dpr = headless.clone();
+        odd_dpr.device_pixel_ratio = 1.07;
+        let issues = ScreenValidator::validate(&odd_dpr, BrowserType::Chrome, DeviceClass::Desktop);
+        assert!(issues
+            .iter()
+            .any(|i| i.kind == ScreenInconsistencyKind::UnusualDevicePixelRatio));
+    }
+}
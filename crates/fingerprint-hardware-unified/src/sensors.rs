@@ -0,0 +1,183 @@
+//! DeviceMotion / DeviceOrientation and Generic Sensor API fingerprinting
+//!
+//! The event constructors exist on every engine, so presence alone says little. What
+//! differs is whether events actually fire (only devices with an IMU), whether iOS-style
+//! `requestPermission()` gating is present, and whether the Chromium-only Generic Sensor
+//! classes (`Accelerometer`, `Gyroscope`, ...) are exposed.
+
+use crate::context::{ClientContext, HardwareAnomaly, Platform};
+use crate::HardwareError;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Motion and orientation capability snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SensorFingerprint {
+    /// `window.DeviceMotionEvent` exists
+    pub device_motion_api: bool,
+    /// `window.DeviceOrientationEvent` exists
+    pub device_orientation_api: bool,
+    /// `DeviceMotionEvent.requestPermission` exists (iOS 13+)
+    pub motion_permission_gated: bool,
+    /// `Accelerometer` / `Gyroscope` constructors exist
+    pub generic_sensor_api: bool,
+    /// Whether a devicemotion event with non-null acceleration fired during collection
+    pub motion_events_fired: Option<bool>,
+    /// `DeviceMotionEvent.interval` in milliseconds, when an event fired
+    pub motion_interval_ms: Option<f64>,
+}
+
+pub struct SensorAnalyzer;
+
+impl SensorAnalyzer {
+    pub async fn analyze() -> Result<SensorFingerprint, HardwareError> {
+        // Implementation would go here
+        Ok(SensorFingerprint {
+            device_motion_api: true,
+            device_orientation_api: true,
+            motion_permission_gated: false,
+            generic_sensor_api: false,
+            motion_events_fired: None,
+            motion_interval_ms: None,
+        })
+    }
+
+    /// Generate the sensor snapshot a real client would report
+    pub fn generate<R: Rng + ?Sized>(rng: &mut R, ctx: &ClientContext) -> SensorFingerprint {
+        let has_imu = ctx.class.is_mobile();
+        let gated = ctx.platform == Platform::IOS;
+        // iOS needs a user gesture + permission, so most sessions never see an event
+        let motion_events_fired = if has_imu && !gated {
+            Some(true)
+        } else if gated {
+            rng.gen_bool(0.1).then_some(true)
+        } else {
+            Some(false)
+        };
+        let motion_interval_ms = motion_events_fired.and_then(|fired| {
+            fired.then(|| match ctx.platform {
+                Platform::IOS => 16.666666666666668,
+                _ if ctx.is_gecko() => 100.0,
+                _ => 16.0,
+            })
+        });
+
+        SensorFingerprint {
+            device_motion_api: true,
+            device_orientation_api: true,
+            motion_permission_gated: gated,
+            generic_sensor_api: ctx.is_chromium(),
+            motion_events_fired,
+            motion_interval_ms,
+        }
+    }
+
+    /// Find contradictions between the snapshot and the claimed client
+    pub fn detect(fp: &SensorFingerprint, ctx: &ClientContext) -> Vec<HardwareAnomaly> {
+        let mut anomalies = Vec::new();
+
+        if !fp.device_motion_api || !fp.device_orientation_api {
+            anomalies.push(HardwareAnomaly::new(
+                "sensors.api",
+                "DeviceMotionEvent/DeviceOrientationEvent missing (every engine exposes them)",
+                0.8,
+            ));
+        }
+
+        let expects_gate = ctx.platform == Platform::IOS;
+        if fp.motion_permission_gated != expects_gate {
+            anomalies.push(HardwareAnomaly::new(
+                "sensors.requestPermission",
+                format!(
+                    "requestPermission {} on {:?}",
+                    if fp.motion_permission_gated {
+                        "present"
+                    } else {
+                        "missing"
+                    },
+                    ctx.platform
+                ),
+                0.9,
+            ));
+        }
+
+        if fp.generic_sensor_api && !ctx.is_chromium() {
+            anomalies.push(HardwareAnomaly::new(
+                "sensors.generic",
+                format!("Generic Sensor API exposed by {}", ctx.browser),
+                0.9,
+            ));
+        }
+
+        if fp.motion_events_fired == Some(true) && !ctx.class.is_mobile() {
+            anomalies.push(HardwareAnomaly::new(
+                "sensors.devicemotion",
+                "motion events with acceleration on a desktop-class device",
+                0.7,
+            ));
+        }
+        if fp.motion_events_fired == Some(false)
+            && ctx.class.is_mobile()
+            && ctx.platform == Platform::Android
+        {
+            anomalies.push(HardwareAnomaly::new(
+                "sensors.devicemotion",
+                "Android device without motion events (emulator or desktop spoofing mobile)",
+                0.6,
+            ));
+        }
+
+        if let Some(interval) = fp.motion_interval_ms {
+            if !(1.0..=1000.0).contains(&interval) {
+                anomalies.push(HardwareAnomaly::new(
+                    "sensors.interval",
+                    format!("motion interval {}ms outside hardware range", interval),
+                    0.8,
+                ));
+            }
+        }
+
+        anomalies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::screen::DeviceClass;
+    use fingerprint_core::types::BrowserType;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_generated_sensors_are_consistent() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let contexts = [
+            ClientContext::new(BrowserType::Chrome, Platform::Windows, DeviceClass::Desktop),
+            ClientContext::new(BrowserType::Chrome, Platform::Android, DeviceClass::Phone),
+            ClientContext::new(BrowserType::Safari, Platform::IOS, DeviceClass::Phone),
+            ClientContext::new(BrowserType::Firefox, Platform::Linux, DeviceClass::Laptop),
+        ];
+        for ctx in contexts {
+            for _ in 0..20 {
+                let fp = SensorAnalyzer::generate(&mut rng, &ctx);
+                assert!(SensorAnalyzer::detect(&fp, &ctx).is_empty(), "{:?}", ctx);
+            }
+        }
+    }
+
+    #[test]
+    fn test_desktop_claiming_iphone_is_flagged() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let desktop =
+            ClientContext::new(BrowserType::Chrome, Platform::Windows, DeviceClass::Desktop);
+        let iphone = ClientContext::new(BrowserType::Safari, Platform::IOS, DeviceClass::Phone);
+
+        let fp = SensorAnalyzer::generate(&mut rng, &desktop);
+        let signals: Vec<_> = SensorAnalyzer::detect(&fp, &iphone)
+            .into_iter()
+            .map(|a| a.signal)
+            .collect();
+        assert!(signals.contains(&"sensors.requestPermission".to_string()));
+        assert!(signals.contains(&"sensors.generic".to_string()));
+    }
+}
@@ -0,0 +1,308 @@
+//! Speech synthesis voice list fingerprinting
+//!
+//! `speechSynthesis.getVoices()` returns the OS voices plus browser-bundled ones: Chrome
+//! adds the "Google …" network voices, Edge adds "Microsoft … Online (Natural)" voices,
+//! Firefox and Safari only expose what the OS has. Language packs add locale voices, so
+//! the list is a compound OS + browser + locale signal.
+
+use crate::context::{ClientContext, HardwareAnomaly, Platform};
+use crate::HardwareError;
+use fingerprint_core::types::BrowserType;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Single `SpeechSynthesisVoice`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct VoiceInfo {
+    pub name: String,
+    pub lang: String,
+    /// `localService`: false for network voices
+    pub local_service: bool,
+}
+
+impl VoiceInfo {
+    fn local(name: &str, lang: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            lang: lang.to_string(),
+            local_service: true,
+        }
+    }
+
+    fn remote(name: &str, lang: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            lang: lang.to_string(),
+            local_service: false,
+        }
+    }
+}
+
+/// Voice list snapshot
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpeechFingerprint {
+    pub voices: Vec<VoiceInfo>,
+}
+
+impl SpeechFingerprint {
+    /// Stable identifier of the voice set (order-independent)
+    pub fn voice_set_id(&self) -> String {
+        let names: BTreeSet<&str> = self.voices.iter().map(|v| v.name.as_str()).collect();
+        let joined = names.into_iter().collect::<Vec<_>>().join("|");
+        format!("{:016x}", fnv1a(joined.as_bytes()))
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// OS voices installed by default
+fn os_voices(platform: Platform) -> Vec<VoiceInfo> {
+    match platform {
+        Platform::Windows => vec![
+            VoiceInfo::local("Microsoft David - English (United States)", "en-US"),
+            VoiceInfo::local("Microsoft Mark - English (United States)", "en-US"),
+            VoiceInfo::local("Microsoft Zira - English (United States)", "en-US"),
+        ],
+        Platform::MacOS | Platform::IOS => vec![
+            VoiceInfo::local("Samantha", "en-US"),
+            VoiceInfo::local("Daniel", "en-GB"),
+            VoiceInfo::local("Karen", "en-AU"),
+            VoiceInfo::local("Moira", "en-IE"),
+            VoiceInfo::local("Rishi", "en-IN"),
+            VoiceInfo::local("Tessa", "en-ZA"),
+            VoiceInfo::local("Thomas", "fr-FR"),
+            VoiceInfo::local("Anna", "de-DE"),
+            VoiceInfo::local("Alice", "it-IT"),
+            VoiceInfo::local("Monica", "es-ES"),
+            VoiceInfo::local("Kyoko", "ja-JP"),
+            VoiceInfo::local("Tingting", "zh-CN"),
+            VoiceInfo::local("Milena", "ru-RU"),
+        ],
+        Platform::Android => vec![VoiceInfo::local("English United States", "en-US")],
+        // speech-dispatcher is rarely configured; browsers usually see no local voices
+        Platform::Linux => vec![],
+    }
+}
+
+/// Voices added by Windows language packs for the user's locale
+fn locale_voices(platform: Platform, locale: &str) -> Vec<VoiceInfo> {
+    let lang = locale.split(['-', '_']).next().unwrap_or("").to_lowercase();
+    match platform {
+        Platform::Windows => match lang.as_str() {
+            "de" => vec![VoiceInfo::local(
+                "Microsoft Hedda - German (Germany)",
+                "de-DE",
+            )],
+            "fr" => vec![VoiceInfo::local(
+                "Microsoft Hortense - French (France)",
+                "fr-FR",
+            )],
+            "es" => vec![VoiceInfo::local(
+                "Microsoft Helena - Spanish (Spain)",
+                "es-ES",
+            )],
+            "ja" => vec![VoiceInfo::local(
+                "Microsoft Haruka - Japanese (Japan)",
+                "ja-JP",
+            )],
+            "zh" => vec![VoiceInfo::local(
+                "Microsoft Huihui - Chinese (Simplified, PRC)",
+                "zh-CN",
+            )],
+            "ru" => vec![VoiceInfo::local(
+                "Microsoft Irina - Russian (Russia)",
+                "ru-RU",
+            )],
+            _ => vec![],
+        },
+        Platform::Android => match lang.as_str() {
+            "" | "en" => vec![],
+            other => vec![VoiceInfo::local(&format!("{} default", other), locale)],
+        },
+        _ => vec![],
+    }
+}
+
+/// Network voices bundled by Google Chrome
+const GOOGLE_VOICES: &[(&str, &str)] = &[
+    ("Google Deutsch", "de-DE"),
+    ("Google US English", "en-US"),
+    ("Google UK English Female", "en-GB"),
+    ("Google UK English Male", "en-GB"),
+    ("Google español", "es-ES"),
+    ("Google español de Estados Unidos", "es-US"),
+    ("Google français", "fr-FR"),
+    ("Google हिन्दी", "hi-IN"),
+    ("Google Bahasa Indonesia", "id-ID"),
+    ("Google italiano", "it-IT"),
+    ("Google 日本語", "ja-JP"),
+    ("Google 한국의", "ko-KR"),
+    ("Google Nederlands", "nl-NL"),
+    ("Google polski", "pl-PL"),
+    ("Google português do Brasil", "pt-BR"),
+    ("Google русский", "ru-RU"),
+    ("Google 普通话（中国大陆）", "zh-CN"),
+    ("Google 粤語（香港）", "zh-HK"),
+    ("Google 國語（臺灣）", "zh-TW"),
+];
+
+/// Online neural voices bundled by Edge (subset, English)
+const EDGE_ONLINE_VOICES: &[(&str, &str)] = &[
+    (
+        "Microsoft Aria Online (Natural) - English (United States)",
+        "en-US",
+    ),
+    (
+        "Microsoft Guy Online (Natural) - English (United States)",
+        "en-US",
+    ),
+    (
+        "Microsoft Jenny Online (Natural) - English (United States)",
+        "en-US",
+    ),
+    (
+        "Microsoft Sonia Online (Natural) - English (United Kingdom)",
+        "en-GB",
+    ),
+];
+
+pub struct SpeechAnalyzer;
+
+impl SpeechAnalyzer {
+    pub async fn analyze() -> Result<SpeechFingerprint, HardwareError> {
+        // Implementation would go here
+        Ok(SpeechFingerprint::default())
+    }
+
+    /// Voice list a real client with this OS profile and UI locale exposes
+    pub fn generate(ctx: &ClientContext, locale: &str) -> SpeechFingerprint {
+        let mut voices = os_voices(ctx.platform);
+        voices.extend(locale_voices(ctx.platform, locale));
+
+        let desktop_chrome =
+            ctx.browser == BrowserType::Chrome && ctx.is_chromium() && !ctx.platform.is_mobile();
+        if desktop_chrome {
+            voices.extend(
+                GOOGLE_VOICES
+                    .iter()
+                    .map(|(name, lang)| VoiceInfo::remote(name, lang)),
+            );
+        }
+        if ctx.browser == BrowserType::Edge && ctx.is_chromium() {
+            voices.extend(
+                EDGE_ONLINE_VOICES
+                    .iter()
+                    .map(|(name, lang)| VoiceInfo::remote(name, lang)),
+            );
+        }
+
+        SpeechFingerprint { voices }
+    }
+
+    /// Estimated identifying information of the voice list in bits
+    ///
+    /// The expected list for the claimed client carries the baseline entropy of the OS +
+    /// browser + locale combination; every extra voice (custom install) or missing
+    /// default voice narrows the anonymity set further.
+    pub fn entropy_bits(fp: &SpeechFingerprint, ctx: &ClientContext, locale: &str) -> f64 {
+        let expected: BTreeSet<String> = Self::generate(ctx, locale)
+            .voices
+            .into_iter()
+            .map(|v| v.name)
+            .collect();
+        let observed: BTreeSet<String> = fp.voices.iter().map(|v| v.name.clone()).collect();
+
+        let extra = observed.difference(&expected).count() as f64;
+        let missing = expected.difference(&observed).count() as f64;
+        // ~3 bits of OS/browser/locale baseline, custom voices are rare (~1/16 users each)
+        3.0 + extra * 4.0 + missing * 2.0
+    }
+
+    /// Find contradictions between the voice list and the claimed client
+    pub fn detect(fp: &SpeechFingerprint, ctx: &ClientContext) -> Vec<HardwareAnomaly> {
+        let mut anomalies = Vec::new();
+        let has = |prefix: &str| fp.voices.iter().any(|v| v.name.starts_with(prefix));
+
+        let google = fp
+            .voices
+            .iter()
+            .any(|v| v.name.starts_with("Google ") && !v.local_service);
+        if google && ctx.browser != BrowserType::Chrome {
+            anomalies.push(HardwareAnomaly::new(
+                "speech.google",
+                format!("Google network voices in {}", ctx.browser),
+                0.9,
+            ));
+        }
+        if has("Microsoft ") && matches!(ctx.platform, Platform::MacOS | Platform::IOS) {
+            anomalies.push(HardwareAnomaly::new(
+                "speech.os",
+                "Windows voices on an Apple platform",
+                1.0,
+            ));
+        }
+        if (has("Samantha") || has("Daniel")) && ctx.platform == Platform::Windows {
+            anomalies.push(HardwareAnomaly::new(
+                "speech.os",
+                "Apple voices on Windows",
+                1.0,
+            ));
+        }
+        if fp.voices.is_empty() && ctx.platform != Platform::Linux {
+            anomalies.push(HardwareAnomaly::new(
+                "speech.empty",
+                format!("no voices on {:?} (headless or blocked)", ctx.platform),
+                0.6,
+            ));
+        }
+
+        anomalies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::screen::DeviceClass;
+
+    #[test]
+    fn test_generated_voices_are_consistent() {
+        for browser in [BrowserType::Chrome, BrowserType::Edge, BrowserType::Firefox] {
+            let ctx = ClientContext::new(browser, Platform::Windows, DeviceClass::Desktop);
+            let fp = SpeechAnalyzer::generate(&ctx, "de-DE");
+            assert!(SpeechAnalyzer::detect(&fp, &ctx).is_empty());
+            assert!(fp.voices.iter().any(|v| v.name.contains("Hedda")));
+            assert_eq!(SpeechAnalyzer::entropy_bits(&fp, &ctx, "de-DE"), 3.0);
+        }
+    }
+
+    #[test]
+    fn test_voice_contradictions_and_entropy() {
+        let chrome_win =
+            ClientContext::new(BrowserType::Chrome, Platform::Windows, DeviceClass::Desktop);
+        let safari_mac =
+            ClientContext::new(BrowserType::Safari, Platform::MacOS, DeviceClass::Laptop);
+        let fp = SpeechAnalyzer::generate(&chrome_win, "en-US");
+
+        let signals: Vec<_> = SpeechAnalyzer::detect(&fp, &safari_mac)
+            .into_iter()
+            .map(|a| a.signal)
+            .collect();
+        assert!(signals.contains(&"speech.google".to_string()));
+        assert!(signals.contains(&"speech.os".to_string()));
+
+        let mut custom = fp.clone();
+        custom
+            .voices
+            .push(VoiceInfo::local("IVONA 2 Brian", "en-GB"));
+        assert!(
+            SpeechAnalyzer::entropy_bits(&custom, &chrome_win, "en-US")
+                > SpeechAnalyzer::entropy_bits(&fp, &chrome_win, "en-US")
+        );
+        assert_ne!(custom.voice_set_id(), fp.voice_set_id());
+    }
+}
@@ -0,0 +1,252 @@
+//! GPU adapter profiling
+//!
+//! [`GpuAdapter`] describes a real graphics adapter: PCI vendor and device ID,
+//! adapter type, driver, limits and optional features. With the `gpu-probe`
+//! feature, [`GpuProber`] queries the adapters of the host through wgpu
+//! (Vulkan, Metal, DX12 or GL); headless servers build without it.
+
+use crate::{HardwareDetector, HardwareFingerprint};
+
+/// GPU vendor, from the PCI vendor ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GpuVendor {
+    Nvidia,
+    Amd,
+    Intel,
+    Apple,
+    Qualcomm,
+    Arm,
+    ImgTec,
+    /// Microsoft Basic Render Driver (WARP)
+    Microsoft,
+    Other(u32),
+}
+
+impl GpuVendor {
+    pub fn from_id(vendor_id: u32) -> Self {
+        match vendor_id {
+            0x10de => GpuVendor::Nvidia,
+            0x1002 | 0x1022 => GpuVendor::Amd,
+            0x8086 => GpuVendor::Intel,
+            0x106b => GpuVendor::Apple,
+            0x5143 => GpuVendor::Qualcomm,
+            0x13b5 => GpuVendor::Arm,
+            0x1010 => GpuVendor::ImgTec,
+            0x1414 => GpuVendor::Microsoft,
+            other => GpuVendor::Other(other),
+        }
+    }
+
+    /// Short vendor name, as device profiles record it
+    pub fn name(&self) -> &'static str {
+        match self {
+            GpuVendor::Nvidia => "NVIDIA",
+            GpuVendor::Amd => "AMD",
+            GpuVendor::Intel => "Intel",
+            GpuVendor::Apple => "Apple",
+            GpuVendor::Qualcomm => "Qualcomm",
+            GpuVendor::Arm => "ARM",
+            GpuVendor::ImgTec => "Imagination",
+            GpuVendor::Microsoft => "Microsoft",
+            GpuVendor::Other(_) => "Unknown",
+        }
+    }
+}
+
+/// Adapter type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuDeviceType {
+    Discrete,
+    Integrated,
+    Virtual,
+    /// Software rendering
+    Cpu,
+    Other,
+}
+
+/// Adapter limits that differ between GPU generations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GpuLimits {
+    pub max_texture_dimension_2d: u32,
+    pub max_texture_dimension_3d: u32,
+    pub max_buffer_size: u64,
+    pub max_storage_buffer_binding_size: u32,
+    pub max_bind_groups: u32,
+    pub max_compute_workgroup_size_x: u32,
+    pub max_compute_invocations_per_workgroup: u32,
+}
+
+/// One graphics adapter of the host
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuAdapter {
+    /// Model name, e.g. "NVIDIA GeForce RTX 4070"
+    pub name: String,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    pub device_type: GpuDeviceType,
+    /// Graphics API the adapter was found through
+    pub backend: String,
+    pub driver: String,
+    pub driver_info: String,
+    pub limits: GpuLimits,
+    /// Optional features, sorted
+    pub features: Vec<String>,
+}
+
+impl GpuAdapter {
+    pub fn vendor(&self) -> GpuVendor {
+        GpuVendor::from_id(self.vendor_id)
+    }
+
+    /// Stable identity of the hardware model, `vendor:device` in hex
+    pub fn pci_id(&self) -> String {
+        format!("{:04x}:{:04x}", self.vendor_id, self.device_id)
+    }
+
+    /// Preference for picking the adapter a browser would render on
+    fn rank(&self) -> u8 {
+        match self.device_type {
+            GpuDeviceType::Discrete => 4,
+            GpuDeviceType::Integrated => 3,
+            GpuDeviceType::Virtual => 2,
+            GpuDeviceType::Other => 1,
+            GpuDeviceType::Cpu => 0,
+        }
+    }
+
+    /// The adapter to profile: discrete before integrated before software
+    pub fn preferred(adapters: &[GpuAdapter]) -> Option<&GpuAdapter> {
+        adapters.iter().max_by_key(|adapter| adapter.rank())
+    }
+}
+
+impl HardwareFingerprint {
+    /// Replace the reported GPU with a probed adapter
+    ///
+    /// The memory estimate uses the real model name; APIs do not expose VRAM.
+    pub fn with_gpu(mut self, adapter: &GpuAdapter) -> Self {
+        self.gpu_model = adapter.name.clone();
+        self.gpu_memory_gb = HardwareDetector::estimate_gpu_memory(&adapter.name);
+        self
+    }
+}
+
+/// Queries the GPU adapters of the host through wgpu
+#[cfg(feature = "gpu-probe")]
+pub struct GpuProber;
+
+#[cfg(feature = "gpu-probe")]
+impl GpuProber {
+    /// All adapters on all native backends; empty on a headless host
+    pub fn probe_all() -> Vec<GpuAdapter> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        instance
+            .enumerate_adapters(wgpu::Backends::all())
+            .iter()
+            .map(Self::describe)
+            .collect()
+    }
+
+    /// The adapter a browser on this host would most likely render on
+    pub fn probe() -> Result<GpuAdapter, crate::HardwareError> {
+        let adapters = Self::probe_all();
+        GpuAdapter::preferred(&adapters).cloned().ok_or_else(|| {
+            crate::HardwareError::DetectionFailed("no GPU adapter found".to_string())
+        })
+    }
+
+    fn describe(adapter: &wgpu::Adapter) -> GpuAdapter {
+        let info = adapter.get_info();
+        let limits = adapter.limits();
+        let mut features: Vec<String> = adapter
+            .features()
+            .iter_names()
+            .map(|(name, _)| name.to_string())
+            .collect();
+        features.sort();
+
+        GpuAdapter {
+            name: info.name,
+            vendor_id: info.vendor,
+            device_id: info.device,
+            device_type: match info.device_type {
+                wgpu::DeviceType::DiscreteGpu => GpuDeviceType::Discrete,
+                wgpu::DeviceType::IntegratedGpu => GpuDeviceType::Integrated,
+                wgpu::DeviceType::VirtualGpu => GpuDeviceType::Virtual,
+                wgpu::DeviceType::Cpu => GpuDeviceType::Cpu,
+                wgpu::DeviceType::Other => GpuDeviceType::Other,
+            },
+            backend: info.backend.to_str().to_string(),
+            driver: info.driver,
+            driver_info: info.driver_info,
+            limits: GpuLimits {
+                max_texture_dimension_2d: limits.max_texture_dimension_2d,
+                max_texture_dimension_3d: limits.max_texture_dimension_3d,
+                max_buffer_size: limits.max_buffer_size,
+                max_storage_buffer_binding_size: limits.max_storage_buffer_binding_size,
+                max_bind_groups: limits.max_bind_groups,
+                max_compute_workgroup_size_x: limits.max_compute_workgroup_size_x,
+                max_compute_invocations_per_workgroup: limits.max_compute_invocations_per_workgroup,
+            },
+            features,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(name: &str, vendor_id: u32, device_type: GpuDeviceType) -> GpuAdapter {
+        GpuAdapter {
+            name: name.to_string(),
+            vendor_id,
+            device_id: 0x2786,
+            device_type,
+            backend: "vulkan".to_string(),
+            driver: String::new(),
+            driver_info: String::new(),
+            limits: GpuLimits::default(),
+            features: vec![],
+        }
+    }
+
+    #[test]
+    fn test_preferred_adapter_and_fingerprint() {
+        let adapters = [
+            adapter(
+                "llvmpipe (LLVM 17.0.6, 256 bits)",
+                0x10005,
+                GpuDeviceType::Cpu,
+            ),
+            adapter(
+                "Intel(R) UHD Graphics 770",
+                0x8086,
+                GpuDeviceType::Integrated,
+            ),
+            adapter("NVIDIA GeForce RTX 4070", 0x10de, GpuDeviceType::Discrete),
+        ];
+        let gpu = GpuAdapter::preferred(&adapters).unwrap();
+        assert_eq!(gpu.vendor(), GpuVendor::Nvidia);
+        assert_eq!(gpu.vendor().name(), "NVIDIA");
+        assert_eq!(gpu.pci_id(), "10de:2786");
+        assert_eq!(adapters[0].vendor(), GpuVendor::Other(0x10005));
+
+        let hw = HardwareDetector::detect(8, "Intel HD", 32, 96.0, 2560, 1440)
+            .unwrap()
+            .with_gpu(gpu);
+        assert_eq!(hw.gpu_model, "NVIDIA GeForce RTX 4070");
+        assert_eq!(hw.gpu_memory_gb, 12);
+    }
+
+    #[cfg(feature = "gpu-probe")]
+    #[test]
+    fn test_probe_matches_enumeration() {
+        // headless CI has no adapter; a host with one must report it
+        let adapters = GpuProber::probe_all();
+        assert_eq!(GpuProber::probe().is_ok(), !adapters.is_empty());
+    }
+}
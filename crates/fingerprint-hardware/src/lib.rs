@@ -5,6 +5,17 @@
 //! 硬件指纹识别模块
 //!
 //! 提供 GPU、CPU、内存等硬件识别功能
+//!
+//! `gpu-probe` feature 通过 wgpu 查询本机真实 GPU 适配器（见 [`gpu`]）；
+//! `system-info` feature 提供 `HardwareDetector::detect_local()` 本机自检
+
+pub mod gpu;
+#[cfg(feature = "system-info")]
+mod local;
+
+#[cfg(feature = "gpu-probe")]
+pub use gpu::GpuProber;
+pub use gpu::{GpuAdapter, GpuDeviceType, GpuLimits, GpuVendor};
 
 /// 硬件fingerprint
 #[derive(Debug, Clone)]
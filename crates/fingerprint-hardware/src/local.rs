@@ -0,0 +1,280 @@
+//! Local hardware introspection (`system-info` feature)
+//!
+//! [`HardwareDetector::detect_local`] profiles the machine it runs on, so agents can
+//! self-profile without a browser:
+//! - CPU model, cores and memory from sysinfo
+//! - GPU from wgpu with `gpu-probe`, otherwise from the OS (sysfs PCI IDs on Linux,
+//!   `system_profiler` on macOS, WMI on Windows)
+//! - display resolution and DPI from DRM modes and EDID on Linux and from
+//!   `system_profiler` on macOS; headless hosts report no display
+
+use crate::{DeviceType, HardwareDetector, HardwareError, HardwareFingerprint};
+use std::path::Path;
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
+
+/// DPI assumed when the display size is unknown
+const DEFAULT_DPI: f32 = 96.0;
+
+/// Primary display as the OS reports it
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Display {
+    width: u32,
+    height: u32,
+    dpi: f32,
+}
+
+impl HardwareDetector {
+    /// Detect the hardware of this machine
+    pub fn detect_local() -> Result<HardwareFingerprint, HardwareError> {
+        let system = System::new_with_specifics(
+            RefreshKind::nothing()
+                .with_cpu(CpuRefreshKind::nothing())
+                .with_memory(MemoryRefreshKind::nothing().with_ram()),
+        );
+        let cpu_cores = system.cpus().len() as u32;
+        let memory_bytes = system.total_memory();
+        if cpu_cores == 0 || memory_bytes == 0 {
+            return Err(HardwareError::DetectionFailed(
+                "CPU and memory information unavailable".to_string(),
+            ));
+        }
+        let cpu_model = system
+            .cpus()
+            .first()
+            .map(|cpu| cpu.brand().trim().to_string())
+            .filter(|brand| !brand.is_empty())
+            .unwrap_or_else(|| Self::identify_cpu(cpu_cores));
+        // installed memory is reported a little under its nominal size
+        let system_memory_gb = (memory_bytes as f64 / (1u64 << 30) as f64).round().max(1.0) as u64;
+
+        let gpu_model = local_gpu().unwrap_or_else(|| "Unknown".to_string());
+        let display = local_display();
+        let (width, height, dpi) =
+            display.map_or((0, 0, DEFAULT_DPI), |d| (d.width, d.height, d.dpi));
+        let device_type = match display {
+            Some(_) => Self::identify_device_type(width, height, system_memory_gb),
+            None => DeviceType::Unknown,
+        };
+
+        Ok(HardwareFingerprint {
+            cpu_model,
+            cpu_cores,
+            gpu_memory_gb: Self::estimate_gpu_memory(&gpu_model),
+            gpu_model,
+            system_memory_gb,
+            screen_dpi: dpi,
+            screen_resolution: (width, height),
+            device_type,
+        })
+    }
+}
+
+fn local_gpu() -> Option<String> {
+    #[cfg(feature = "gpu-probe")]
+    if let Ok(adapter) = crate::GpuProber::probe() {
+        return Some(adapter.name);
+    }
+    os_gpu()
+}
+
+#[cfg(target_os = "linux")]
+fn os_gpu() -> Option<String> {
+    // the boot VGA device is the one driving the console
+    let mut cards = drm_entries("card")
+        .into_iter()
+        .filter(|card| !card.contains('-'))
+        .map(|card| Path::new("/sys/class/drm").join(card).join("device"));
+    let device = cards
+        .clone()
+        .find(|device| read_trimmed(&device.join("boot_vga")).as_deref() == Some("1"))
+        .or_else(|| cards.next())?;
+    let vendor = parse_hex(&read_trimmed(&device.join("vendor"))?)?;
+    let device_id = parse_hex(&read_trimmed(&device.join("device"))?)?;
+    Some(pci_gpu_name(vendor, device_id))
+}
+
+#[cfg(target_os = "macos")]
+fn os_gpu() -> Option<String> {
+    let output = command_output("system_profiler", &["SPDisplaysDataType"])?;
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Chipset Model:"))
+        .map(|model| model.trim().to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn os_gpu() -> Option<String> {
+    let output = command_output(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-Command",
+            "(Get-CimInstance Win32_VideoController | Select-Object -First 1).Name",
+        ],
+    )?;
+    Some(output.trim().to_string()).filter(|name| !name.is_empty())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn os_gpu() -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn local_display() -> Option<Display> {
+    drm_entries("card").into_iter().find_map(|connector| {
+        let dir = Path::new("/sys/class/drm").join(connector);
+        if read_trimmed(&dir.join("status")).as_deref() != Some("connected") {
+            return None;
+        }
+        // the first mode is the preferred (native) one
+        let modes = std::fs::read_to_string(dir.join("modes")).ok()?;
+        let (width, height) = parse_resolution(modes.lines().next()?)?;
+        let dpi = std::fs::read(dir.join("edid"))
+            .ok()
+            .and_then(|edid| edid_size_cm(&edid))
+            .map_or(DEFAULT_DPI, |(width_cm, _)| dpi(width, width_cm));
+        Some(Display { width, height, dpi })
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn local_display() -> Option<Display> {
+    parse_system_profiler_display(&command_output("system_profiler", &["SPDisplaysDataType"])?)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn local_display() -> Option<Display> {
+    None
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Sorted `/sys/class/drm` entries starting with `prefix`
+#[cfg(target_os = "linux")]
+fn drm_entries(prefix: &str) -> Vec<String> {
+    let mut entries: Vec<String> = std::fs::read_dir("/sys/class/drm")
+        .map(|dir| {
+            dir.flatten()
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| name.starts_with(prefix))
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort();
+    entries
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+fn parse_hex(value: &str) -> Option<u32> {
+    u32::from_str_radix(value.trim_start_matches("0x"), 16).ok()
+}
+
+/// Name for a GPU known only by PCI IDs, e.g. "NVIDIA GPU (10de:2786)"
+fn pci_gpu_name(vendor_id: u32, device_id: u32) -> String {
+    let vendor = match crate::GpuVendor::from_id(vendor_id) {
+        crate::GpuVendor::Other(_) => "Unknown",
+        known => known.name(),
+    };
+    format!("{} GPU ({:04x}:{:04x})", vendor, vendor_id, device_id)
+}
+
+/// `1920x1080` (DRM modes) or `2560 x 1600` (system_profiler)
+fn parse_resolution(value: &str) -> Option<(u32, u32)> {
+    let (width, height) = value.split_once('x')?;
+    let height: String = height
+        .trim()
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    Some((width.trim().parse().ok()?, height.parse().ok()?))
+}
+
+/// Physical image size from an EDID base block, in centimetres
+fn edid_size_cm(edid: &[u8]) -> Option<(u8, u8)> {
+    const HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+    if edid.len() < 128 || edid[..8] != HEADER {
+        return None;
+    }
+    // zero means unknown or a projector
+    (edid[21] > 0 && edid[22] > 0).then_some((edid[21], edid[22]))
+}
+
+fn dpi(width_px: u32, width_cm: u8) -> f32 {
+    (width_px as f32 / (width_cm as f32 / 2.54)).round()
+}
+
+/// First display of `system_profiler SPDisplaysDataType`
+fn parse_system_profiler_display(output: &str) -> Option<Display> {
+    let line = output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Resolution:"))?;
+    let (width, height) = parse_resolution(line)?;
+    // Retina panels render at twice the logical density
+    let dpi = if line.contains("Retina") {
+        DEFAULT_DPI * 2.0
+    } else {
+        DEFAULT_DPI
+    };
+    Some(Display { width, height, dpi })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_os_report_parsing() {
+        assert_eq!(parse_resolution("1920x1080"), Some((1920, 1080)));
+        assert_eq!(parse_resolution("2560 x 1600 Retina"), Some((2560, 1600)));
+        assert_eq!(parse_resolution("1920x1080i"), Some((1920, 1080)));
+        assert_eq!(parse_resolution("auto"), None);
+        assert_eq!(parse_hex("0x10de"), Some(0x10de));
+        assert_eq!(pci_gpu_name(0x10de, 0x2786), "NVIDIA GPU (10de:2786)");
+
+        let mut edid = vec![0u8; 128];
+        edid[..8].copy_from_slice(&[0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00]);
+        edid[21] = 60;
+        edid[22] = 34;
+        assert_eq!(edid_size_cm(&edid), Some((60, 34)));
+        // 27" 2560x1440 panel
+        assert_eq!(dpi(2560, 60), 108.0);
+        assert_eq!(edid_size_cm(&edid[..64]), None);
+
+        let report = "Graphics/Displays:\n\n    Apple M2:\n\n      Chipset Model: Apple M2\n      Displays:\n        Color LCD:\n          Resolution: 2560 x 1664 Retina\n";
+        assert_eq!(
+            parse_system_profiler_display(report),
+            Some(Display {
+                width: 2560,
+                height: 1664,
+                dpi: 192.0
+            })
+        );
+    }
+
+    #[test]
+    fn test_detect_local_profiles_this_host() {
+        let hw = HardwareDetector::detect_local().unwrap();
+        assert!(hw.cpu_cores > 0);
+        assert!(!hw.cpu_model.is_empty());
+        assert!(hw.system_memory_gb > 0);
+        if hw.screen_resolution == (0, 0) {
+            assert_eq!(hw.device_type, DeviceType::Unknown);
+        }
+    }
+}
@@ -129,6 +129,19 @@ pub fn safari_http2_settings() -> (HTTP2Settings, Vec<u16>) {
     (settings, settings_order)
 }
 
+/// Create OkHttp HTTP/2 Settings
+///
+/// OkHttp (the usual Android app client) only announces a 16 MiB initial
+/// window and leaves every other setting at its protocol default.
+pub fn okhttp_http2_settings() -> (HTTP2Settings, Vec<u16>) {
+    let mut settings = HashMap::new();
+    settings.insert(HTTP2SettingID::InitialWindowSize.as_u16(), 16777216);
+
+    let settings_order = vec![HTTP2SettingID::InitialWindowSize.as_u16()];
+
+    (settings, settings_order)
+}
+
 /// Chrome Pseudo Header Order
 pub fn chrome_pseudo_header_order() -> Vec<String> {
     vec![
@@ -0,0 +1,594 @@
+//! Navigation context module
+//!
+//! Browsers derive Sec-Fetch-Site/Mode/Dest/User, Referer and Origin from
+//! where a request comes from: a typed URL, a link click, an iframe, a
+//! subresource or a script `fetch()`. Sending the static navigation headers
+//! from [`generate_headers`](crate::headers::generate_headers) on every
+//! request is an easy tell, so [`NavigationPipeline`] tracks the current
+//! document and computes these headers per request.
+//!
+//! ```
+//! use fingerprint_core::types::BrowserType;
+//! use fingerprint_headers::{generate_headers, NavigationPipeline, RequestContext, ResourceKind};
+//!
+//! let base = generate_headers(BrowserType::Chrome, "", false);
+//! let mut pipeline = NavigationPipeline::new(BrowserType::Chrome, base);
+//!
+//! let page = pipeline.request(RequestContext::BrowserNavigation, "https://shop.example.com/", "GET").unwrap();
+//! assert_eq!(page.sec_fetch_site, "none");
+//!
+//! let image = pipeline
+//!     .request(RequestContext::Subresource(ResourceKind::Image), "https://cdn.example.com/a.png", "GET")
+//!     .unwrap();
+//! assert_eq!(image.sec_fetch_site, "same-site");
+//! assert_eq!(image.sec_fetch_dest, "image");
+//! assert_eq!(image.custom["Referer"], "https://shop.example.com/");
+//! ```
+
+use crate::headers::HTTPHeaders;
+use fingerprint_core::types::BrowserType;
+
+/// Two-label public suffixes, so `a.example.co.uk` and `b.example.co.uk` count as same-site
+const MULTI_LABEL_SUFFIXES: &[&str] = &[
+    "co.uk", "org.uk", "ac.uk", "gov.uk", "com.au", "net.au", "org.au", "co.jp", "ne.jp", "or.jp",
+    "com.cn", "net.cn", "org.cn", "com.br", "com.tw", "com.hk", "co.kr", "co.in", "co.nz",
+    "com.mx", "com.tr", "co.za",
+];
+
+/// Subresource type, which selects Sec-Fetch-Dest and Accept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceKind {
+    Script,
+    Style,
+    Image,
+    Font,
+}
+
+/// Where a request comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RequestContext {
+    /// Address bar, bookmark or other browser-UI navigation (no initiator)
+    BrowserNavigation,
+    /// Top-level navigation started by the current document
+    Navigation {
+        /// Whether a user gesture (click, key press) triggered it
+        user_activated: bool,
+    },
+    /// Iframe load inside the current document
+    Iframe,
+    /// Subresource loaded by the current document
+    Subresource(ResourceKind),
+    /// `fetch()` or `XMLHttpRequest` from the current document
+    Fetch,
+}
+
+impl RequestContext {
+    /// Sec-Fetch-Mode value
+    pub fn mode(&self) -> &'static str {
+        match self {
+            RequestContext::BrowserNavigation
+            | RequestContext::Navigation { .. }
+            | RequestContext::Iframe => "navigate",
+            // Fonts are always fetched in CORS mode
+            RequestContext::Subresource(ResourceKind::Font) | RequestContext::Fetch => "cors",
+            RequestContext::Subresource(_) => "no-cors",
+        }
+    }
+
+    /// Sec-Fetch-Dest value
+    pub fn destination(&self) -> &'static str {
+        match self {
+            RequestContext::BrowserNavigation | RequestContext::Navigation { .. } => "document",
+            RequestContext::Iframe => "iframe",
+            RequestContext::Subresource(ResourceKind::Script) => "script",
+            RequestContext::Subresource(ResourceKind::Style) => "style",
+            RequestContext::Subresource(ResourceKind::Image) => "image",
+            RequestContext::Subresource(ResourceKind::Font) => "font",
+            RequestContext::Fetch => "empty",
+        }
+    }
+
+    /// True for document and iframe loads
+    pub fn is_navigation(&self) -> bool {
+        self.mode() == "navigate"
+    }
+
+    /// Accept header for non-navigation requests (navigations keep the profile's)
+    fn accept(&self, browser: BrowserType) -> Option<&'static str> {
+        let RequestContext::Subresource(kind) = self else {
+            return (*self == RequestContext::Fetch).then_some("*/*");
+        };
+        Some(match (kind, browser) {
+            (ResourceKind::Image, BrowserType::Firefox) => {
+                "image/avif,image/webp,image/png,image/svg+xml,image/*;q=0.8,*/*;q=0.5"
+            }
+            (ResourceKind::Image, BrowserType::Safari) => {
+                "image/webp,image/avif,image/jxl,image/heic,image/heic-sequence,video/*;q=0.8,image/png,image/svg+xml,image/*;q=0.8,*/*;q=0.5"
+            }
+            (ResourceKind::Image, _) => {
+                "image/avif,image/webp,image/apng,image/svg+xml,image/*,*/*;q=0.8"
+            }
+            (ResourceKind::Style, _) => "text/css,*/*;q=0.1",
+            (ResourceKind::Script | ResourceKind::Font, _) => "*/*",
+        })
+    }
+}
+
+/// Referrer policy (https://w3c.github.io/webappsec-referrer-policy/)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ReferrerPolicy {
+    NoReferrer,
+    NoReferrerWhenDowngrade,
+    SameOrigin,
+    Origin,
+    StrictOrigin,
+    OriginWhenCrossOrigin,
+    /// Default in Chrome, Firefox and Safari
+    #[default]
+    StrictOriginWhenCrossOrigin,
+    UnsafeUrl,
+}
+
+impl ReferrerPolicy {
+    /// Referer value sent from `from` to `to`, if any
+    pub fn referer(self, from: &RequestUrl, to: &RequestUrl) -> Option<String> {
+        let same_origin = from.same_origin(to);
+        let downgrade = from.is_secure() && !to.is_secure();
+        let full = || Some(from.referrer_url());
+        let origin = || Some(format!("{}/", from.origin()));
+        match self {
+            ReferrerPolicy::NoReferrer => None,
+            ReferrerPolicy::NoReferrerWhenDowngrade if downgrade => None,
+            ReferrerPolicy::NoReferrerWhenDowngrade | ReferrerPolicy::UnsafeUrl => full(),
+            ReferrerPolicy::SameOrigin if same_origin => full(),
+            ReferrerPolicy::SameOrigin => None,
+            ReferrerPolicy::Origin => origin(),
+            ReferrerPolicy::StrictOrigin if downgrade => None,
+            ReferrerPolicy::StrictOrigin => origin(),
+            ReferrerPolicy::OriginWhenCrossOrigin if same_origin => full(),
+            ReferrerPolicy::OriginWhenCrossOrigin => origin(),
+            ReferrerPolicy::StrictOriginWhenCrossOrigin if same_origin => full(),
+            ReferrerPolicy::StrictOriginWhenCrossOrigin if downgrade => None,
+            ReferrerPolicy::StrictOriginWhenCrossOrigin => origin(),
+        }
+    }
+}
+
+/// Minimal parsed http(s) URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestUrl {
+    /// "http" or "https"
+    pub scheme: String,
+    /// Lowercased host (IPv6 without brackets)
+    pub host: String,
+    /// Port, defaulted from the scheme
+    pub port: u16,
+    /// Path and query, starting with '/'
+    pub path: String,
+}
+
+impl RequestUrl {
+    /// Parse an absolute http(s) URL
+    pub fn parse(url: &str) -> Result<Self, String> {
+        let url = url.trim();
+        let (scheme, rest) = url
+            .split_once("://")
+            .ok_or_else(|| format!("missing scheme: {}", url))?;
+        let scheme = scheme.to_ascii_lowercase();
+        let default_port = match scheme.as_str() {
+            "https" => 443,
+            "http" => 80,
+            _ => return Err(format!("unsupported scheme: {}", scheme)),
+        };
+
+        let rest = rest.split('#').next().unwrap_or_default();
+        let (authority, path) = match rest.find(['/', '?']) {
+            Some(pos) if rest[pos..].starts_with('?') => {
+                (&rest[..pos], format!("/{}", &rest[pos..]))
+            }
+            Some(pos) => (&rest[..pos], rest[pos..].to_string()),
+            None => (rest, "/".to_string()),
+        };
+        // userinfo never reaches Referer or Origin
+        let authority = authority.rsplit('@').next().unwrap_or_default();
+
+        let (host, port) = if let Some(v6) = authority.strip_prefix('[') {
+            let (host, tail) = v6
+                .split_once(']')
+                .ok_or_else(|| format!("invalid IPv6 host: {}", authority))?;
+            (host, tail.strip_prefix(':'))
+        } else {
+            match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            }
+        };
+        if host.is_empty() {
+            return Err(format!("missing host: {}", url));
+        }
+        let port = match port {
+            Some(port) => port
+                .parse()
+                .map_err(|_| format!("invalid port: {}", port))?,
+            None => default_port,
+        };
+
+        Ok(Self {
+            scheme,
+            host: host.to_ascii_lowercase(),
+            port,
+            path,
+        })
+    }
+
+    /// Resolve `href` (absolute, scheme-relative, root-relative or relative) against this URL
+    pub fn join(&self, href: &str) -> Result<Self, String> {
+        let href = href.trim();
+        if href.contains("://") {
+            return Self::parse(href);
+        }
+        if let Some(rest) = href.strip_prefix("//") {
+            return Self::parse(&format!("{}://{}", self.scheme, rest));
+        }
+        let path = if href.starts_with('/') {
+            href.to_string()
+        } else if href.starts_with('?') {
+            let base = self.path.split('?').next().unwrap_or("/");
+            format!("{}{}", base, href)
+        } else {
+            let base = self.path.split('?').next().unwrap_or("/");
+            let dir = &base[..base.rfind('/').map_or(0, |i| i + 1)];
+            format!("{}{}", dir, href)
+        };
+        Self::parse(&format!("{}{}", self.origin(), path))
+    }
+
+    /// True for https and loopback hosts (where Sec-Fetch-* is sent)
+    pub fn is_trustworthy(&self) -> bool {
+        self.is_secure()
+            || matches!(self.host.as_str(), "localhost" | "127.0.0.1" | "::1")
+            || self.host.ends_with(".localhost")
+    }
+
+    fn is_secure(&self) -> bool {
+        self.scheme == "https"
+    }
+
+    fn default_port(&self) -> u16 {
+        if self.is_secure() {
+            443
+        } else {
+            80
+        }
+    }
+
+    /// Serialized origin, e.g. "https://example.com" or "http://[::1]:8080"
+    pub fn origin(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        if self.port == self.default_port() {
+            format!("{}://{}", self.scheme, host)
+        } else {
+            format!("{}://{}:{}", self.scheme, host, self.port)
+        }
+    }
+
+    /// URL as sent in Referer (no fragment or userinfo)
+    fn referrer_url(&self) -> String {
+        format!("{}{}", self.origin(), self.path)
+    }
+
+    /// Same scheme, host and port
+    pub fn same_origin(&self, other: &RequestUrl) -> bool {
+        self.scheme == other.scheme && self.host == other.host && self.port == other.port
+    }
+
+    /// Same scheme and registrable domain (schemeful same-site)
+    pub fn same_site(&self, other: &RequestUrl) -> bool {
+        self.scheme == other.scheme && self.site() == other.site()
+    }
+
+    /// Registrable domain (eTLD+1), approximated without the full public suffix list
+    fn site(&self) -> &str {
+        if self.host.parse::<std::net::IpAddr>().is_ok() {
+            return &self.host;
+        }
+        let labels: Vec<&str> = self.host.split('.').collect();
+        let suffix_labels = if labels.len() >= 3
+            && MULTI_LABEL_SUFFIXES.contains(&labels[labels.len() - 2..].join(".").as_str())
+        {
+            2
+        } else {
+            1
+        };
+        let keep = (suffix_labels + 1).min(labels.len());
+        let skip: usize = labels[..labels.len() - keep]
+            .iter()
+            .map(|label| label.len() + 1)
+            .sum();
+        &self.host[skip..]
+    }
+}
+
+/// Per-request fetch metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchMetadata {
+    /// Sec-Fetch-Site ("none", "same-origin", "same-site", "cross-site")
+    pub site: &'static str,
+    /// Sec-Fetch-Mode
+    pub mode: &'static str,
+    /// Sec-Fetch-Dest
+    pub dest: &'static str,
+    /// Sec-Fetch-User ("?1" for user-activated navigations)
+    pub user: Option<&'static str>,
+    /// Referer
+    pub referer: Option<String>,
+    /// Origin
+    pub origin: Option<String>,
+}
+
+impl FetchMetadata {
+    /// Metadata for a `method` request to `target` from `initiator` (the current document)
+    pub fn compute(
+        context: RequestContext,
+        initiator: Option<&RequestUrl>,
+        target: &RequestUrl,
+        method: &str,
+        policy: ReferrerPolicy,
+    ) -> Self {
+        let site = match initiator {
+            None => "none",
+            Some(from) if from.same_origin(target) => "same-origin",
+            Some(from) if from.same_site(target) => "same-site",
+            Some(_) => "cross-site",
+        };
+        let user = match context {
+            RequestContext::BrowserNavigation
+            | RequestContext::Navigation {
+                user_activated: true,
+            } => Some("?1"),
+            _ => None,
+        };
+        let referer = initiator.and_then(|from| policy.referer(from, target));
+
+        // CORS requests and anything but GET/HEAD carry Origin; it is
+        // "null" where the referrer policy would hide the origin
+        let unsafe_method = !matches!(method.to_ascii_uppercase().as_str(), "GET" | "HEAD");
+        let cross_origin_cors =
+            context.mode() == "cors" && initiator.is_some_and(|from| !from.same_origin(target));
+        let origin = initiator
+            .filter(|_| unsafe_method || cross_origin_cors)
+            .map(|from| {
+                let hidden = match policy {
+                    ReferrerPolicy::NoReferrer => true,
+                    ReferrerPolicy::SameOrigin => !from.same_origin(target),
+                    ReferrerPolicy::NoReferrerWhenDowngrade
+                    | ReferrerPolicy::StrictOrigin
+                    | ReferrerPolicy::StrictOriginWhenCrossOrigin => {
+                        from.is_secure() && !target.is_secure()
+                    }
+                    _ => false,
+                };
+                // Cross-origin CORS requests always reveal the origin
+                if hidden && !cross_origin_cors {
+                    "null".to_string()
+                } else {
+                    from.origin()
+                }
+            });
+
+        Self {
+            site,
+            mode: context.mode(),
+            dest: context.destination(),
+            user,
+            referer,
+            origin,
+        }
+    }
+}
+
+/// Request pipeline that tracks the current document and emits per-request headers
+#[derive(Debug, Clone)]
+pub struct NavigationPipeline {
+    browser: BrowserType,
+    base: HTTPHeaders,
+    policy: ReferrerPolicy,
+    document: Option<RequestUrl>,
+}
+
+impl NavigationPipeline {
+    /// Create a pipeline starting with no open document
+    ///
+    /// `base` holds the profile's navigation headers (Accept, User-Agent,
+    /// client hints); they are kept for navigations and adjusted for other
+    /// requests.
+    pub fn new(browser: BrowserType, base: HTTPHeaders) -> Self {
+        Self {
+            browser,
+            base,
+            policy: ReferrerPolicy::default(),
+            document: None,
+        }
+    }
+
+    /// Use `policy` instead of the browser default
+    pub fn with_referrer_policy(mut self, policy: ReferrerPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// URL of the current top-level document
+    pub fn document(&self) -> Option<&RequestUrl> {
+        self.document.as_ref()
+    }
+
+    /// Headers for the next request; top-level navigations replace the current document
+    pub fn request(
+        &mut self,
+        context: RequestContext,
+        url: &str,
+        method: &str,
+    ) -> Result<HTTPHeaders, String> {
+        let target = RequestUrl::parse(url)?;
+        let initiator = match context {
+            RequestContext::BrowserNavigation => None,
+            _ => self.document.as_ref(),
+        };
+        let metadata = FetchMetadata::compute(context, initiator, &target, method, self.policy);
+        let headers = self.headers_for(context, &target, &metadata);
+
+        if matches!(
+            context,
+            RequestContext::BrowserNavigation | RequestContext::Navigation { .. }
+        ) {
+            self.document = Some(target);
+        }
+        Ok(headers)
+    }
+
+    fn headers_for(
+        &self,
+        context: RequestContext,
+        target: &RequestUrl,
+        metadata: &FetchMetadata,
+    ) -> HTTPHeaders {
+        let mut headers = self.base.clone();
+
+        // Fetch metadata is only sent to potentially trustworthy URLs
+        if target.is_trustworthy() {
+            headers.sec_fetch_site = metadata.site.to_string();
+            headers.sec_fetch_mode = metadata.mode.to_string();
+            headers.sec_fetch_dest = metadata.dest.to_string();
+            headers.sec_fetch_user = metadata.user.unwrap_or_default().to_string();
+        } else {
+            headers.sec_fetch_site.clear();
+            headers.sec_fetch_mode.clear();
+            headers.sec_fetch_dest.clear();
+            headers.sec_fetch_user.clear();
+        }
+
+        if !context.is_navigation() {
+            headers.upgrade_insecure_requests.clear();
+        }
+        if let Some(accept) = context.accept(self.browser) {
+            headers.accept = accept.to_string();
+        }
+
+        headers.set("Referer", metadata.referer.as_deref().unwrap_or_default());
+        headers.set("Origin", metadata.origin.as_deref().unwrap_or_default());
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::generate_headers;
+
+    fn url(s: &str) -> RequestUrl {
+        RequestUrl::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_site() {
+        let u = url("https://user:pw@a.shop.example.co.uk:8443/p?q=1#frag");
+        assert_eq!(u.host, "a.shop.example.co.uk");
+        assert_eq!(u.origin(), "https://a.shop.example.co.uk:8443");
+        assert_eq!(u.referrer_url(), "https://a.shop.example.co.uk:8443/p?q=1");
+        assert_eq!(u.site(), "example.co.uk");
+        assert_eq!(url("http://[::1]:8080").origin(), "http://[::1]:8080");
+        assert!(url("https://a.example.com").same_site(&url("https://b.example.com")));
+        assert!(!url("http://a.example.com").same_site(&url("https://a.example.com")));
+        assert!(RequestUrl::parse("ftp://example.com").is_err());
+
+        let page = url("https://example.com/shop/item?id=1");
+        assert_eq!(page.join("a.css").unwrap().path, "/shop/a.css");
+        assert_eq!(page.join("/a.js").unwrap().path, "/a.js");
+        assert_eq!(
+            page.join("//cdn.example.com/x.png").unwrap().host,
+            "cdn.example.com"
+        );
+    }
+
+    #[test]
+    fn test_referrer_policy() {
+        let page = url("https://example.com/page?id=1");
+        let policy = ReferrerPolicy::StrictOriginWhenCrossOrigin;
+        assert_eq!(
+            policy.referer(&page, &url("https://example.com/api")),
+            Some("https://example.com/page?id=1".to_string())
+        );
+        assert_eq!(
+            policy.referer(&page, &url("https://other.com/")),
+            Some("https://example.com/".to_string())
+        );
+        assert_eq!(policy.referer(&page, &url("http://other.com/")), None);
+        assert_eq!(ReferrerPolicy::NoReferrer.referer(&page, &page), None);
+    }
+
+    #[test]
+    fn test_pipeline_per_context() {
+        let base = generate_headers(BrowserType::Chrome, "", false);
+        let mut pipeline = NavigationPipeline::new(BrowserType::Chrome, base);
+
+        let doc = pipeline
+            .request(
+                RequestContext::BrowserNavigation,
+                "https://example.com/",
+                "GET",
+            )
+            .unwrap();
+        assert_eq!(
+            (doc.sec_fetch_site.as_str(), doc.sec_fetch_user.as_str()),
+            ("none", "?1")
+        );
+        assert!(!doc.custom.contains_key("Referer"));
+
+        let api = pipeline
+            .request(RequestContext::Fetch, "https://api.other.com/v1", "GET")
+            .unwrap();
+        assert_eq!(api.sec_fetch_site, "cross-site");
+        assert_eq!(api.sec_fetch_mode, "cors");
+        assert_eq!(api.sec_fetch_dest, "empty");
+        assert_eq!(api.sec_fetch_user, "");
+        assert_eq!(api.accept, "*/*");
+        assert!(api.upgrade_insecure_requests.is_empty());
+        assert_eq!(api.custom["Origin"], "https://example.com");
+        assert_eq!(api.custom["Referer"], "https://example.com/");
+
+        let frame = pipeline
+            .request(RequestContext::Iframe, "https://example.com/embed", "GET")
+            .unwrap();
+        assert_eq!(frame.sec_fetch_site, "same-origin");
+        assert_eq!(frame.sec_fetch_dest, "iframe");
+        assert!(!frame.custom.contains_key("Origin"));
+
+        let form = pipeline
+            .request(
+                RequestContext::Navigation {
+                    user_activated: true,
+                },
+                "https://example.com/login",
+                "POST",
+            )
+            .unwrap();
+        assert_eq!(form.sec_fetch_user, "?1");
+        assert_eq!(form.custom["Origin"], "https://example.com");
+        assert_eq!(pipeline.document().unwrap().path, "/login");
+
+        // Plain-http targets get no fetch metadata
+        let insecure = pipeline
+            .request(
+                RequestContext::Subresource(ResourceKind::Image),
+                "http://cdn.example.com/a.png",
+                "GET",
+            )
+            .unwrap();
+        assert!(insecure.sec_fetch_site.is_empty());
+        assert!(!insecure.custom.contains_key("Referer"));
+    }
+}
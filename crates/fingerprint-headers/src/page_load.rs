@@ -0,0 +1,809 @@
+//! Page load simulation module
+//!
+//! A real page load is more than one request: after the document arrives the
+//! browser fetches stylesheets, scripts, fonts and images in an order, with
+//! HTTP/2 priorities and concurrency that differ per engine. A client that
+//! fetches a page's subresources in document order with identical priorities
+//! does not look like a browser.
+//!
+//! [`PageDocument`] extracts subresources from HTML and [`PageLoadSimulator`]
+//! turns them into a [`PageLoadWaterfall`]: the requests in dispatch order
+//! with stream IDs, HTTP/2 priority frames, RFC 9218 `priority` headers,
+//! fetch metadata and simulated start/end times.
+//!
+//! The timing model is deliberately coarse (every response takes one RTT);
+//! it exists to reproduce request ordering and concurrency, not throughput.
+//!
+//! ## Priority schemes
+//!
+//! - Chromium: Blink priorities mapped to weights 256/220/183/147/110, each
+//!   stream depending exclusively on the newest open stream of equal or
+//!   higher priority; low-priority requests are held back while
+//!   render-blocking head resources load ("tight mode")
+//! - Firefox: requests hang off the idle group streams 3-13 (leader,
+//!   follower, unblocked, background, speculative, urgent start)
+//! - Safari: weights only, no dependencies
+
+use crate::headers::HTTPHeaders;
+use crate::http2_config::{
+    chrome_header_order, firefox_header_order, safari_header_order, HTTP2Priority,
+};
+use crate::navigation::{NavigationPipeline, RequestContext, RequestUrl, ResourceKind};
+use fingerprint_core::types::BrowserType;
+
+/// Resource load priority (Blink naming), lowest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourcePriority {
+    VeryLow,
+    Low,
+    Medium,
+    High,
+    VeryHigh,
+}
+
+impl ResourcePriority {
+    /// HTTP/2 weight as sent on the wire (weight - 1)
+    pub fn h2_weight(self) -> u8 {
+        match self {
+            ResourcePriority::VeryHigh => 255,
+            ResourcePriority::High => 219,
+            ResourcePriority::Medium => 182,
+            ResourcePriority::Low => 146,
+            ResourcePriority::VeryLow => 109,
+        }
+    }
+
+    /// RFC 9218 urgency (0 most urgent)
+    pub fn urgency(self) -> u8 {
+        match self {
+            ResourcePriority::VeryHigh => 0,
+            ResourcePriority::High => 1,
+            ResourcePriority::Medium => 2,
+            ResourcePriority::Low => 3,
+            ResourcePriority::VeryLow => 4,
+        }
+    }
+
+    fn raise(self) -> Self {
+        match self {
+            ResourcePriority::VeryLow => ResourcePriority::Low,
+            ResourcePriority::Low => ResourcePriority::Medium,
+            ResourcePriority::Medium => ResourcePriority::High,
+            _ => ResourcePriority::VeryHigh,
+        }
+    }
+
+    fn lower(self) -> Self {
+        match self {
+            ResourcePriority::VeryHigh => ResourcePriority::High,
+            ResourcePriority::High => ResourcePriority::Medium,
+            ResourcePriority::Medium => ResourcePriority::Low,
+            _ => ResourcePriority::VeryLow,
+        }
+    }
+}
+
+/// `fetchpriority` attribute
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FetchPriority {
+    #[default]
+    Auto,
+    High,
+    Low,
+}
+
+/// When the browser learns about a resource
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Discovery {
+    /// In the HTML, found by the parser or preload scanner
+    #[default]
+    Parser,
+    /// Referenced from CSS (fonts, background images)
+    Stylesheet,
+    /// Requested once layout runs (lazy images)
+    Layout,
+}
+
+/// Subresource referenced by a page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageResource {
+    /// Absolute URL
+    pub url: String,
+    /// Request context (subresource kind or fetch)
+    pub context: RequestContext,
+    /// Referenced from `<head>`
+    pub in_head: bool,
+    /// async/defer script or non-matching media stylesheet
+    pub deferred: bool,
+    /// `<link rel=preload>` or `modulepreload`
+    pub preload: bool,
+    /// `fetchpriority` attribute
+    pub fetch_priority: FetchPriority,
+    /// Discovery phase
+    pub discovery: Discovery,
+}
+
+impl PageResource {
+    /// Parser-discovered subresource with default attributes
+    pub fn new(url: &str, context: RequestContext) -> Self {
+        Self {
+            url: url.to_string(),
+            context,
+            in_head: false,
+            deferred: false,
+            preload: false,
+            fetch_priority: FetchPriority::Auto,
+            discovery: Discovery::Parser,
+        }
+    }
+
+    /// Chromium/Blink load priority
+    pub fn priority(&self) -> ResourcePriority {
+        let base = match self.context {
+            RequestContext::Subresource(ResourceKind::Style) if self.deferred => {
+                ResourcePriority::VeryLow
+            }
+            RequestContext::Subresource(ResourceKind::Style) => ResourcePriority::VeryHigh,
+            RequestContext::Subresource(ResourceKind::Script) if self.deferred => {
+                ResourcePriority::Low
+            }
+            RequestContext::Subresource(ResourceKind::Script) if self.in_head || self.preload => {
+                ResourcePriority::High
+            }
+            RequestContext::Subresource(ResourceKind::Script) => ResourcePriority::Medium,
+            RequestContext::Subresource(ResourceKind::Font) => ResourcePriority::High,
+            RequestContext::Subresource(ResourceKind::Image) => ResourcePriority::Low,
+            RequestContext::Fetch => ResourcePriority::High,
+            RequestContext::BrowserNavigation
+            | RequestContext::Navigation { .. }
+            | RequestContext::Iframe => ResourcePriority::VeryHigh,
+        };
+        match self.fetch_priority {
+            FetchPriority::Auto => base,
+            FetchPriority::High => base.raise(),
+            FetchPriority::Low => base.lower(),
+        }
+    }
+
+    /// Whether this resource blocks first render
+    fn render_blocking(&self) -> bool {
+        match self.context {
+            RequestContext::Subresource(ResourceKind::Style) => !self.deferred,
+            RequestContext::Subresource(ResourceKind::Script) => self.in_head && !self.deferred,
+            _ => false,
+        }
+    }
+}
+
+/// Subresources of an HTML document
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageDocument {
+    /// Subresources in document order, deduplicated by URL
+    pub resources: Vec<PageResource>,
+}
+
+impl PageDocument {
+    /// Extract subresources from `html` served at `page_url`
+    ///
+    /// Understands `<link rel=stylesheet|preload|modulepreload>`,
+    /// `<script src>`, `<img src>` and `url()` fonts in inline `<style>`.
+    pub fn parse(page_url: &str, html: &str) -> Result<Self, String> {
+        let base = RequestUrl::parse(page_url)?;
+        let mut document = Self::default();
+        let mut in_head = true;
+        let mut rest = html;
+
+        while let Some(start) = rest.find('<') {
+            rest = &rest[start..];
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+                continue;
+            }
+            let Some(end) = rest.find('>') else {
+                break;
+            };
+            let tag = Tag::parse(&rest[1..end]);
+            rest = &rest[end + 1..];
+
+            match tag.name.as_str() {
+                "/head" | "body" => in_head = false,
+                "style" => {
+                    let (css, tail) = raw_text(rest, "</style");
+                    rest = tail;
+                    for href in css_font_urls(css) {
+                        let mut resource = document.resource(&base, &href, ResourceKind::Font)?;
+                        resource.discovery = Discovery::Stylesheet;
+                        document.push(resource);
+                    }
+                }
+                "script" => {
+                    let (_, tail) = raw_text(rest, "</script");
+                    rest = tail;
+                    if let Some(src) = tag.attr("src") {
+                        let mut resource = document.resource(&base, src, ResourceKind::Script)?;
+                        resource.in_head = in_head;
+                        resource.deferred = tag.attr("type") != Some("module")
+                            && (tag.has("async") || tag.has("defer"));
+                        resource.fetch_priority = tag.fetch_priority();
+                        document.push(resource);
+                    }
+                }
+                "link" => {
+                    let rel = tag.attr("rel").unwrap_or_default().to_ascii_lowercase();
+                    let kind = match rel.as_str() {
+                        "stylesheet" => Some(ResourceKind::Style),
+                        "modulepreload" => Some(ResourceKind::Script),
+                        "preload" => match tag.attr("as") {
+                            Some("style") => Some(ResourceKind::Style),
+                            Some("script") => Some(ResourceKind::Script),
+                            Some("font") => Some(ResourceKind::Font),
+                            Some("image") => Some(ResourceKind::Image),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    let fetch = rel == "preload" && tag.attr("as") == Some("fetch");
+                    let Some(href) = tag.attr("href") else {
+                        continue;
+                    };
+                    let mut resource = match kind {
+                        Some(kind) => document.resource(&base, href, kind)?,
+                        None if fetch => {
+                            let mut resource =
+                                document.resource(&base, href, ResourceKind::Script)?;
+                            resource.context = RequestContext::Fetch;
+                            resource
+                        }
+                        None => continue,
+                    };
+                    resource.in_head = in_head;
+                    resource.preload = rel != "stylesheet";
+                    resource.deferred = rel == "stylesheet"
+                        && tag
+                            .attr("media")
+                            .is_some_and(|media| !matches!(media, "all" | "screen"));
+                    resource.fetch_priority = tag.fetch_priority();
+                    document.push(resource);
+                }
+                "img" => {
+                    let Some(src) = tag.attr("src") else {
+                        continue;
+                    };
+                    let mut resource = document.resource(&base, src, ResourceKind::Image)?;
+                    resource.in_head = in_head;
+                    resource.fetch_priority = tag.fetch_priority();
+                    if tag.attr("loading") == Some("lazy") {
+                        resource.discovery = Discovery::Layout;
+                    }
+                    document.push(resource);
+                }
+                _ => {}
+            }
+        }
+        Ok(document)
+    }
+
+    fn resource(
+        &self,
+        base: &RequestUrl,
+        href: &str,
+        kind: ResourceKind,
+    ) -> Result<PageResource, String> {
+        let url = base.join(href)?;
+        Ok(PageResource::new(
+            &format!("{}{}", url.origin(), url.path),
+            RequestContext::Subresource(kind),
+        ))
+    }
+
+    /// Add `resource` unless its URL is already present (browsers fetch a URL once)
+    pub fn push(&mut self, resource: PageResource) {
+        if resource.url.starts_with("http") && !self.resources.iter().any(|r| r.url == resource.url)
+        {
+            self.resources.push(resource);
+        }
+    }
+}
+
+/// Parsed start tag
+struct Tag {
+    name: String,
+    attrs: Vec<(String, String)>,
+}
+
+impl Tag {
+    fn parse(inner: &str) -> Self {
+        let inner = inner.trim_end_matches('/');
+        let (name, mut rest) = inner
+            .split_once(|c: char| c.is_ascii_whitespace())
+            .unwrap_or((inner, ""));
+        let mut attrs = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                break;
+            }
+            let key_end = rest
+                .find(|c: char| c == '=' || c.is_ascii_whitespace())
+                .unwrap_or(rest.len());
+            let key = rest[..key_end].to_ascii_lowercase();
+            rest = rest[key_end..].trim_start();
+            let value = if let Some(after) = rest.strip_prefix('=') {
+                let after = after.trim_start();
+                let (value, tail) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let body = &after[1..];
+                        let end = body.find(quote).unwrap_or(body.len());
+                        (&body[..end], body.get(end + 1..).unwrap_or_default())
+                    }
+                    _ => {
+                        let end = after
+                            .find(|c: char| c.is_ascii_whitespace())
+                            .unwrap_or(after.len());
+                        (&after[..end], &after[end..])
+                    }
+                };
+                rest = tail;
+                value.to_string()
+            } else {
+                String::new()
+            };
+            attrs.push((key, value));
+        }
+        Self {
+            name: name.to_ascii_lowercase(),
+            attrs,
+        }
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.attr(name).is_some()
+    }
+
+    fn fetch_priority(&self) -> FetchPriority {
+        match self.attr("fetchpriority") {
+            Some("high") => FetchPriority::High,
+            Some("low") => FetchPriority::Low,
+            _ => FetchPriority::Auto,
+        }
+    }
+}
+
+/// Text up to the closing `end` tag, and the input after it
+fn raw_text<'a>(html: &'a str, end: &str) -> (&'a str, &'a str) {
+    match html.to_ascii_lowercase().find(end) {
+        Some(pos) => {
+            let tail = &html[pos..];
+            (
+                &html[..pos],
+                tail.find('>').map_or("", |gt| &tail[gt + 1..]),
+            )
+        }
+        None => (html, ""),
+    }
+}
+
+/// Font URLs in CSS `url(...)` references
+fn css_font_urls(css: &str) -> Vec<String> {
+    css.split("url(")
+        .skip(1)
+        .filter_map(|part| part.split(')').next())
+        .map(|url| {
+            url.trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_string()
+        })
+        .filter(|url| {
+            let path = url.split(['?', '#']).next().unwrap_or_default();
+            [".woff2", ".woff", ".ttf", ".otf"]
+                .iter()
+                .any(|ext| path.ends_with(ext))
+        })
+        .collect()
+}
+
+/// One request in a simulated page load
+#[derive(Debug, Clone)]
+pub struct WaterfallEntry {
+    /// HTTP/2 stream ID
+    pub stream_id: u32,
+    /// Request URL
+    pub url: String,
+    /// Request context
+    pub context: RequestContext,
+    /// Load priority
+    pub priority: ResourcePriority,
+    /// HTTP/2 PRIORITY / HEADERS priority fields
+    pub h2_priority: HTTP2Priority,
+    /// Request headers in browser order, including `priority`
+    pub headers: Vec<(String, String)>,
+    /// Simulated dispatch time (ms since navigation start)
+    pub start_ms: u32,
+    /// Simulated response completion time
+    pub end_ms: u32,
+}
+
+/// Simulated page load
+#[derive(Debug, Clone)]
+pub struct PageLoadWaterfall {
+    /// Idle streams opened with PRIORITY frames before any request (Firefox)
+    pub idle_streams: Vec<HTTP2Priority>,
+    /// Requests in dispatch order
+    pub entries: Vec<WaterfallEntry>,
+}
+
+impl PageLoadWaterfall {
+    /// URLs in dispatch order
+    pub fn request_order(&self) -> Vec<&str> {
+        self.entries.iter().map(|e| e.url.as_str()).collect()
+    }
+
+    /// Largest number of streams open at once
+    pub fn peak_concurrency(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| {
+                self.entries
+                    .iter()
+                    .filter(|e| e.start_ms <= entry.start_ms && entry.start_ms < e.end_ms)
+                    .count()
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Firefox idle group streams: (stream, weight - 1, dependency)
+const FIREFOX_GROUPS: [(u32, u8, u32); 6] = [
+    (3, 200, 0),  // leader
+    (5, 0, 3),    // follower
+    (7, 100, 0),  // unblocked
+    (9, 0, 7),    // background
+    (11, 0, 9),   // speculative
+    (13, 240, 0), // urgent start
+];
+
+/// Page load simulator
+#[derive(Debug, Clone)]
+pub struct PageLoadSimulator {
+    browser: BrowserType,
+    base: HTTPHeaders,
+    rtt_ms: u32,
+    max_concurrent_streams: usize,
+}
+
+impl PageLoadSimulator {
+    /// Create a simulator for `browser` with its navigation headers
+    ///
+    /// Defaults to a 50ms RTT and a server limit of 100 concurrent streams.
+    pub fn new(browser: BrowserType, base: HTTPHeaders) -> Self {
+        Self {
+            browser,
+            base,
+            rtt_ms: 50,
+            max_concurrent_streams: 100,
+        }
+    }
+
+    /// Set the simulated round-trip time
+    pub fn with_rtt_ms(mut self, rtt_ms: u32) -> Self {
+        self.rtt_ms = rtt_ms.max(1);
+        self
+    }
+
+    /// Set the server's SETTINGS_MAX_CONCURRENT_STREAMS
+    pub fn with_max_concurrent_streams(mut self, limit: usize) -> Self {
+        self.max_concurrent_streams = limit.max(1);
+        self
+    }
+
+    fn chromium(&self) -> bool {
+        matches!(
+            self.browser,
+            BrowserType::Chrome | BrowserType::Edge | BrowserType::Opera
+        )
+    }
+
+    /// Simulate loading `document` from `page_url`
+    pub fn simulate(
+        &self,
+        page_url: &str,
+        document: &PageDocument,
+    ) -> Result<PageLoadWaterfall, String> {
+        let rtt = self.rtt_ms;
+        let mut pipeline = NavigationPipeline::new(self.browser, self.base.clone());
+        let mut waterfall = PageLoadWaterfall {
+            idle_streams: Vec::new(),
+            entries: Vec::new(),
+        };
+        if self.browser == BrowserType::Firefox {
+            waterfall.idle_streams = FIREFOX_GROUPS
+                .iter()
+                .map(|&(stream_id, weight, dependency)| HTTP2Priority {
+                    stream_id,
+                    exclusive: false,
+                    weight,
+                    stream_dependency: dependency,
+                })
+                .collect();
+        }
+
+        let document_resource = PageResource::new(page_url, RequestContext::BrowserNavigation);
+        self.dispatch(&mut waterfall, &mut pipeline, &document_resource, 0, "GET")?;
+        let parsed_at = rtt;
+
+        // Render-blocking head resources load within one RTT of parsing;
+        // CSS-referenced and lazy resources, and Chromium's held-back
+        // low-priority body requests, follow once they are done
+        let blocking = document.resources.iter().any(PageResource::render_blocking);
+        let blocking_done = parsed_at + if blocking { rtt } else { 0 };
+        let mut schedule: Vec<(u32, usize, &PageResource)> = document
+            .resources
+            .iter()
+            .enumerate()
+            .map(|(index, resource)| {
+                let ready = match resource.discovery {
+                    Discovery::Parser
+                        if self.chromium()
+                            && resource.priority() <= ResourcePriority::Low
+                            && !resource.in_head =>
+                    {
+                        blocking_done
+                    }
+                    Discovery::Parser => parsed_at,
+                    Discovery::Stylesheet | Discovery::Layout => blocking_done,
+                };
+                (ready, index, resource)
+            })
+            .collect();
+        // Preload scanner issues in document order within each phase;
+        // Chromium issues higher priorities first among ready requests
+        if self.chromium() {
+            schedule.sort_by_key(|(ready, index, resource)| {
+                (*ready, std::cmp::Reverse(resource.priority()), *index)
+            });
+        } else {
+            schedule.sort_by_key(|(ready, index, _)| (*ready, *index));
+        }
+
+        for (ready, _, resource) in schedule {
+            self.dispatch(&mut waterfall, &mut pipeline, resource, ready, "GET")?;
+        }
+        Ok(waterfall)
+    }
+
+    fn dispatch(
+        &self,
+        waterfall: &mut PageLoadWaterfall,
+        pipeline: &mut NavigationPipeline,
+        resource: &PageResource,
+        ready_ms: u32,
+        method: &str,
+    ) -> Result<(), String> {
+        // Wait for a free stream slot
+        let mut start_ms = ready_ms;
+        loop {
+            let open: Vec<u32> = waterfall
+                .entries
+                .iter()
+                .filter(|e| e.start_ms <= start_ms && start_ms < e.end_ms)
+                .map(|e| e.end_ms)
+                .collect();
+            if open.len() < self.max_concurrent_streams {
+                break;
+            }
+            start_ms = open.into_iter().min().unwrap_or(start_ms);
+        }
+
+        let priority = resource.priority();
+        let first_stream = if self.browser == BrowserType::Firefox {
+            15
+        } else {
+            1
+        };
+        let stream_id = first_stream + 2 * waterfall.entries.len() as u32;
+        let h2_priority = self.h2_priority(waterfall, resource, priority, stream_id, start_ms);
+
+        let mut headers = pipeline.request(resource.context, &resource.url, method)?;
+        let incremental = matches!(
+            resource.context,
+            RequestContext::BrowserNavigation | RequestContext::Subresource(ResourceKind::Image)
+        );
+        headers.set(
+            "Priority",
+            &format!(
+                "u={}{}",
+                priority.urgency(),
+                if incremental { ", i" } else { "" }
+            ),
+        );
+        let order = match self.browser {
+            BrowserType::Firefox => firefox_header_order(),
+            BrowserType::Safari => safari_header_order(),
+            _ => chrome_header_order(),
+        };
+
+        waterfall.entries.push(WaterfallEntry {
+            stream_id,
+            url: resource.url.clone(),
+            context: resource.context,
+            priority,
+            h2_priority,
+            headers: headers.to_ordered_vec(&order),
+            start_ms,
+            end_ms: start_ms + self.rtt_ms,
+        });
+        Ok(())
+    }
+
+    fn h2_priority(
+        &self,
+        waterfall: &PageLoadWaterfall,
+        resource: &PageResource,
+        priority: ResourcePriority,
+        stream_id: u32,
+        start_ms: u32,
+    ) -> HTTP2Priority {
+        match self.browser {
+            BrowserType::Firefox => {
+                let group = match resource.context {
+                    RequestContext::BrowserNavigation | RequestContext::Navigation { .. } => 13,
+                    RequestContext::Subresource(ResourceKind::Style | ResourceKind::Font) => 3,
+                    RequestContext::Subresource(ResourceKind::Script) if !resource.deferred => 3,
+                    RequestContext::Subresource(ResourceKind::Image)
+                        if resource.discovery == Discovery::Layout =>
+                    {
+                        9
+                    }
+                    RequestContext::Subresource(ResourceKind::Image) => 5,
+                    _ => 7,
+                };
+                HTTP2Priority {
+                    stream_id,
+                    exclusive: false,
+                    weight: match resource.fetch_priority {
+                        FetchPriority::High => 63,
+                        FetchPriority::Low => 15,
+                        FetchPriority::Auto => 31,
+                    },
+                    stream_dependency: group,
+                }
+            }
+            BrowserType::Safari => HTTP2Priority {
+                stream_id,
+                exclusive: false,
+                weight: priority.h2_weight(),
+                stream_dependency: 0,
+            },
+            _ => {
+                // Newest open stream at least as important as this one
+                let parent = waterfall
+                    .entries
+                    .iter()
+                    .rev()
+                    .find(|e| {
+                        e.start_ms <= start_ms && start_ms < e.end_ms && e.priority >= priority
+                    })
+                    .map_or(0, |e| e.stream_id);
+                HTTP2Priority {
+                    stream_id,
+                    exclusive: true,
+                    weight: priority.h2_weight(),
+                    stream_dependency: parent,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::generate_headers;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head>
+  <link rel="stylesheet" href="/main.css">
+  <link rel="stylesheet" href="/print.css" media="print">
+  <script src="/app.js"></script>
+  <script async src="https://cdn.example.net/analytics.js"></script>
+  <!-- <script src="/commented.js"></script> -->
+  <link rel="preload" href="/hero.webp" as="image" fetchpriority="high">
+  <style>@font-face { src: url("/fonts/inter.woff2") format("woff2"); }</style>
+</head><body>
+  <img src="/hero.webp">
+  <img src="/logo.png">
+  <img src="/footer.png" loading="lazy">
+  <script src="/late.js"></script>
+</body></html>"#;
+
+    fn document() -> PageDocument {
+        PageDocument::parse("https://www.example.com/", PAGE).unwrap()
+    }
+
+    #[test]
+    fn test_parse_document() {
+        let doc = document();
+        let urls: Vec<&str> = doc.resources.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://www.example.com/main.css",
+                "https://www.example.com/print.css",
+                "https://www.example.com/app.js",
+                "https://cdn.example.net/analytics.js",
+                "https://www.example.com/hero.webp",
+                "https://www.example.com/fonts/inter.woff2",
+                "https://www.example.com/logo.png",
+                "https://www.example.com/footer.png",
+                "https://www.example.com/late.js",
+            ]
+        );
+        assert_eq!(doc.resources[0].priority(), ResourcePriority::VeryHigh);
+        assert_eq!(doc.resources[1].priority(), ResourcePriority::VeryLow);
+        assert_eq!(doc.resources[2].priority(), ResourcePriority::High);
+        assert_eq!(doc.resources[3].priority(), ResourcePriority::Low);
+        assert_eq!(doc.resources[4].priority(), ResourcePriority::Medium);
+        assert_eq!(doc.resources[5].discovery, Discovery::Stylesheet);
+        assert_eq!(doc.resources[7].discovery, Discovery::Layout);
+        assert_eq!(doc.resources[8].priority(), ResourcePriority::Medium);
+    }
+
+    #[test]
+    fn test_chrome_waterfall() {
+        let base = generate_headers(BrowserType::Chrome, "", false);
+        let waterfall = PageLoadSimulator::new(BrowserType::Chrome, base)
+            .simulate("https://www.example.com/", &document())
+            .unwrap();
+
+        let order = waterfall.request_order();
+        assert_eq!(order[0], "https://www.example.com/");
+        assert_eq!(order[1], "https://www.example.com/main.css");
+        assert_eq!(order[2], "https://www.example.com/app.js");
+        // Body images wait for the render-blocking head resources
+        let logo = waterfall
+            .entries
+            .iter()
+            .find(|e| e.url.ends_with("logo.png"))
+            .unwrap();
+        assert!(logo.start_ms > waterfall.entries[1].start_ms);
+
+        let document = &waterfall.entries[0];
+        assert_eq!(document.stream_id, 1);
+        assert_eq!(document.h2_priority.weight, 255);
+        assert!(document
+            .headers
+            .contains(&("Priority".to_string(), "u=0, i".to_string())));
+
+        // app.js depends on main.css, which is more important and still open
+        let css = &waterfall.entries[1];
+        let script = &waterfall.entries[2];
+        assert!(script.h2_priority.exclusive);
+        assert_eq!(script.h2_priority.stream_dependency, css.stream_id);
+        assert!(script
+            .headers
+            .contains(&("Sec-Fetch-Dest".to_string(), "script".to_string())));
+    }
+
+    #[test]
+    fn test_firefox_groups_and_stream_limit() {
+        let base = generate_headers(BrowserType::Firefox, "", false);
+        let waterfall = PageLoadSimulator::new(BrowserType::Firefox, base)
+            .with_max_concurrent_streams(2)
+            .simulate("https://www.example.com/", &document())
+            .unwrap();
+
+        assert_eq!(waterfall.idle_streams.len(), 6);
+        assert_eq!(waterfall.entries[0].stream_id, 15);
+        assert_eq!(waterfall.entries[0].h2_priority.stream_dependency, 13);
+        assert_eq!(waterfall.entries[1].h2_priority.stream_dependency, 3);
+        assert!(waterfall.peak_concurrency() <= 2);
+    }
+}
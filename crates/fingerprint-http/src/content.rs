@@ -0,0 +1,458 @@
+//! Response content fingerprints
+//!
+//! Attributes servers by what they serve rather than how they talk:
+//!
+//! - **Favicon hash**: MurmurHash3 (x86, 32-bit, seed 0) of the MIME base64
+//!   encoding of the icon, as a signed integer. Matches Shodan's
+//!   `http.favicon.hash`.
+//! - **DOM simhash**: 64-bit simhash over 3-shingles of the HTML tag
+//!   sequence; pages built from the same template differ in a few bits.
+//! - **Script inventory**: hash of the sorted external script URLs (query
+//!   strings dropped) and inline script bodies.
+//!
+//! [`ContentFingerprint`] implements [`Fingerprint`], so it can be added to a
+//! [`NetworkFlow`](fingerprint_core::system::NetworkFlow) next to the TLS,
+//! HTTP and TCP fingerprints of the same server.
+
+use crate::http_client::HttpResponse;
+use fingerprint_core::fingerprint::{Fingerprint, FingerprintType};
+use fingerprint_core::metadata::FingerprintMetadata;
+use fingerprint_tls::hash12;
+
+/// Hash used when a page has no scripts
+const EMPTY_HASH: &str = "000000000000";
+
+/// DOM simhashes at most this many bits apart count as the same template
+pub const SIMHASH_SIMILAR_BITS: u32 = 3;
+
+/// Tag tokens per simhash feature
+const SHINGLE: usize = 3;
+
+/// Shodan-compatible favicon hash
+pub fn favicon_hash(icon: &[u8]) -> i32 {
+    murmur3_32(base64_mime(icon).as_bytes(), 0) as i32
+}
+
+/// MurmurHash3 x86 32-bit
+pub fn murmur3_32(data: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+
+    let mut h = seed;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        h ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        h = h.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let k = tail
+            .iter()
+            .rev()
+            .fold(0u32, |k, &byte| (k << 8) | byte as u32);
+        h ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    }
+
+    h ^= data.len() as u32;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+/// Base64 with a newline after every 76 characters and at the end
+/// (Python's `base64.encodebytes`, which Shodan hashes)
+fn base64_mime(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len() * 4 / 3 + 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    let mut wrapped = String::with_capacity(encoded.len() + encoded.len() / 76 + 1);
+    for line in encoded.as_bytes().chunks(76) {
+        wrapped.push_str(std::str::from_utf8(line).unwrap_or_default());
+        wrapped.push('\n');
+    }
+    wrapped
+}
+
+/// FNV-1a 64
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |h, &byte| {
+        (h ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// One `<script>` element
+struct Script<'a> {
+    src: Option<&'a str>,
+    body: &'a str,
+}
+
+/// Tag names in document order (`p`, `/p`, ...) and the page's scripts
+///
+/// Comments, doctypes and the contents of `script` / `style` are skipped.
+fn scan_html(html: &str) -> (Vec<String>, Vec<Script<'_>>) {
+    let lower = html.to_ascii_lowercase();
+    let mut tags = Vec::new();
+    let mut scripts = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = lower[pos..].find('<') {
+        let start = pos + offset;
+        let rest = &lower[start + 1..];
+        if rest.starts_with("!--") {
+            pos = lower[start..]
+                .find("-->")
+                .map_or(lower.len(), |end| start + end + 3);
+            continue;
+        }
+        let Some(end) = rest.find('>').map(|end| start + 1 + end) else {
+            break;
+        };
+        pos = end + 1;
+        if rest.starts_with('!') || rest.starts_with('?') {
+            continue;
+        }
+
+        let closing = rest.starts_with('/');
+        let name: String = rest[closing as usize..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+            .collect();
+        if name.is_empty() {
+            continue;
+        }
+        tags.push(if closing {
+            format!("/{}", name)
+        } else {
+            name.clone()
+        });
+
+        if !closing && (name == "script" || name == "style") {
+            let close = format!("</{}", name);
+            let body_end = lower[pos..].find(&close).map_or(lower.len(), |i| pos + i);
+            if name == "script" {
+                scripts.push(Script {
+                    src: attribute(&html[start..end], &lower[start..end], "src"),
+                    body: html[pos..body_end].trim(),
+                });
+            }
+            pos = body_end;
+        }
+    }
+    (tags, scripts)
+}
+
+/// Value of `name` in a start tag; `lower` is the tag lowercased
+fn attribute<'a>(tag: &'a str, lower: &str, name: &str) -> Option<&'a str> {
+    let mut from = 0;
+    while let Some(offset) = lower[from..].find(name) {
+        let at = from + offset;
+        from = at + name.len();
+        let preceded = lower[..at]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_whitespace());
+        let rest = lower[from..].trim_start();
+        if !preceded || !rest.starts_with('=') {
+            continue;
+        }
+        let value_start = lower.len() - rest.len() + 1;
+        let value = tag[value_start..].trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next(),
+            _ => value
+                .split(|c: char| c.is_ascii_whitespace() || c == '>')
+                .next(),
+        };
+        return value.map(str::trim).filter(|v| !v.is_empty());
+    }
+    None
+}
+
+/// 64-bit simhash of the page's tag sequence; `None` without any tags
+pub fn dom_simhash(html: &str) -> Option<u64> {
+    let (tags, _) = scan_html(html);
+    if tags.is_empty() {
+        return None;
+    }
+    let mut weights = [0i64; 64];
+    for shingle in tags.windows(SHINGLE.min(tags.len())) {
+        let hash = fnv1a(shingle.join(" ").as_bytes());
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            *weight += if hash >> bit & 1 == 1 { 1 } else { -1 };
+        }
+    }
+    Some(
+        weights
+            .iter()
+            .enumerate()
+            .filter(|(_, weight)| **weight > 0)
+            .fold(0, |hash, (bit, _)| hash | 1 << bit),
+    )
+}
+
+/// Differing bits between two simhashes
+pub fn simhash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Sorted, de-duplicated script inventory of a page
+///
+/// External scripts appear as `src:<url>` without query or fragment, inline
+/// ones as `inline:<hash12 of the body>`.
+pub fn script_inventory(html: &str) -> Vec<String> {
+    let (_, scripts) = scan_html(html);
+    let mut inventory: Vec<String> = scripts
+        .iter()
+        .filter_map(|script| match script.src {
+            Some(src) => {
+                let url = src.split(['?', '#']).next().unwrap_or(src);
+                Some(format!("src:{}", url.to_ascii_lowercase()))
+            }
+            None if script.body.is_empty() => None,
+            None => Some(format!("inline:{}", hash12(script.body))),
+        })
+        .collect();
+    inventory.sort();
+    inventory.dedup();
+    inventory
+}
+
+/// Content fingerprint of a server
+#[derive(Debug, Clone)]
+pub struct ContentFingerprint {
+    /// Shodan-compatible favicon hash
+    pub favicon_hash: Option<i32>,
+    /// DOM structure simhash of the landing page
+    pub dom_simhash: Option<u64>,
+    /// Hash of [`scripts`](Self::scripts)
+    pub script_hash: Option<String>,
+    /// Script inventory, see [`script_inventory`]
+    pub scripts: Vec<String>,
+    pub metadata: FingerprintMetadata,
+}
+
+impl Default for ContentFingerprint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContentFingerprint {
+    pub fn new() -> Self {
+        Self {
+            favicon_hash: None,
+            dom_simhash: None,
+            script_hash: None,
+            scripts: Vec::new(),
+            metadata: FingerprintMetadata::new(),
+        }
+    }
+
+    /// Fingerprint an HTML page
+    pub fn with_html(mut self, html: &str) -> Self {
+        self.dom_simhash = dom_simhash(html);
+        self.scripts = script_inventory(html);
+        self.script_hash = (!self.scripts.is_empty()).then(|| hash12(&self.scripts.join(",")));
+        self
+    }
+
+    /// Fingerprint a favicon
+    pub fn with_favicon(mut self, icon: &[u8]) -> Self {
+        self.favicon_hash = Some(favicon_hash(icon));
+        self
+    }
+
+    /// Fold in a response: HTML pages and images (favicons) are used,
+    /// anything else is ignored
+    pub fn with_response(self, response: &HttpResponse) -> Self {
+        if !response.is_success() || response.body.is_empty() {
+            return self;
+        }
+        let content_type = response
+            .get_header("content-type")
+            .map(|v| v.to_ascii_lowercase())
+            .unwrap_or_default();
+        if content_type.contains("html") {
+            self.with_html(&String::from_utf8_lossy(&response.body))
+        } else if content_type.starts_with("image/") {
+            self.with_favicon(&response.body)
+        } else {
+            self
+        }
+    }
+
+    /// Same template: DOM simhashes within [`SIMHASH_SIMILAR_BITS`]
+    pub fn same_template(&self, other: &ContentFingerprint) -> bool {
+        match (self.dom_simhash, other.dom_simhash) {
+            (Some(a), Some(b)) => simhash_distance(a, b) <= SIMHASH_SIMILAR_BITS,
+            _ => false,
+        }
+    }
+
+    /// Fields of an ID: favicon hash, DOM simhash, script hash
+    fn parse_id(id: &str) -> Option<(i32, u64, &str)> {
+        let mut parts = id.splitn(3, '_');
+        let favicon = parts.next()?.parse().ok()?;
+        let simhash = u64::from_str_radix(parts.next()?, 16).ok()?;
+        Some((favicon, simhash, parts.next()?))
+    }
+}
+
+impl Fingerprint for ContentFingerprint {
+    fn fingerprint_type(&self) -> FingerprintType {
+        FingerprintType::Content
+    }
+
+    /// `{favicon_hash}_{dom_simhash:016x}_{script_hash}`, zeros for missing parts
+    fn id(&self) -> String {
+        format!(
+            "{}_{:016x}_{}",
+            self.favicon_hash.unwrap_or(0),
+            self.dom_simhash.unwrap_or(0),
+            self.script_hash.as_deref().unwrap_or(EMPTY_HASH)
+        )
+    }
+
+    fn metadata(&self) -> &FingerprintMetadata {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut FingerprintMetadata {
+        &mut self.metadata
+    }
+
+    fn hash(&self) -> u64 {
+        fnv1a(self.id().as_bytes())
+    }
+
+    /// Same favicon, or the same template
+    fn similar_to(&self, other: &dyn Fingerprint) -> bool {
+        if other.fingerprint_type() != FingerprintType::Content {
+            return false;
+        }
+        let Some((favicon, simhash, _)) = Self::parse_id(&other.id()) else {
+            return false;
+        };
+        if self
+            .favicon_hash
+            .is_some_and(|own| own != 0 && own == favicon)
+        {
+            return true;
+        }
+        self.dom_simhash.is_some_and(|own| {
+            simhash != 0 && simhash_distance(own, simhash) <= SIMHASH_SIMILAR_BITS
+        })
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "Content(favicon={:?}, dom={:?}, scripts={})",
+            self.favicon_hash,
+            self.dom_simhash.map(|h| format!("{:016x}", h)),
+            self.scripts.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head><title>Shop</title>
+<script src="/static/app.js?v=42"></script>
+<script>window.dataLayer = [];</script>
+<style>p > a { color: red }</style>
+</head><body><div class="nav"><a href="/">Home</a><a href="/cart">Cart</a></div>
+<!-- <table> in a comment -->
+<main><h1>Welcome</h1><p>Deals</p><ul><li>One</li><li>Two</li></ul></main>
+<script src='https://cdn.example.com/jquery.min.js'></script>
+</body></html>"#;
+
+    #[test]
+    fn test_favicon_hash_matches_shodan_encoding() {
+        // mmh3.hash("foo") in Python
+        assert_eq!(murmur3_32(b"foo", 0) as i32, -156908512);
+        assert_eq!(base64_mime(b"foob"), "Zm9vYg==\n");
+        assert_eq!(base64_mime(&[0u8; 60]).lines().next().unwrap().len(), 76);
+        assert_eq!(favicon_hash(b"foob"), murmur3_32(b"Zm9vYg==\n", 0) as i32);
+    }
+
+    #[test]
+    fn test_dom_simhash_tolerates_content_changes() {
+        let original = ContentFingerprint::new().with_html(PAGE);
+        let edited = ContentFingerprint::new().with_html(
+            &PAGE
+                .replace("Deals", "Sale")
+                .replace("<li>Two</li>", "<li>Two</li><li>Three</li>"),
+        );
+        let other = ContentFingerprint::new().with_html(
+            "<html><body><table><tr><td>1</td></tr></table><form><input></form></body></html>",
+        );
+
+        assert!(original.same_template(&edited));
+        assert!(original.similar_to(&edited));
+        assert!(!original.same_template(&other));
+        assert!(!original.similar_to(&other));
+    }
+
+    #[test]
+    fn test_script_inventory_and_flow_storage() {
+        assert_eq!(
+            script_inventory(PAGE),
+            vec![
+                format!("inline:{}", hash12("window.dataLayer = [];")),
+                "src:/static/app.js".to_string(),
+                "src:https://cdn.example.com/jquery.min.js".to_string(),
+            ]
+        );
+
+        let mut page = HttpResponse::new(200);
+        page.headers.insert(
+            "content-type".to_string(),
+            "text/html; charset=utf-8".to_string(),
+        );
+        page.body = PAGE.as_bytes().to_vec();
+        let mut icon = HttpResponse::new(200);
+        icon.headers
+            .insert("content-type".to_string(), "image/x-icon".to_string());
+        icon.body = vec![0, 0, 1, 0, 1, 0];
+
+        let content = ContentFingerprint::new()
+            .with_response(&page)
+            .with_response(&icon);
+        assert_eq!(content.favicon_hash, Some(favicon_hash(&icon.body)));
+        assert_eq!(content.scripts.len(), 3);
+
+        let mut flow = fingerprint_core::system::NetworkFlow::new(
+            fingerprint_core::system::SystemContext::new(
+                "198.51.100.1".parse().unwrap(),
+                "192.0.2.1".parse().unwrap(),
+                fingerprint_core::system::ProtocolType::Http,
+            ),
+        );
+        flow.add_fingerprint(Box::new(content.clone()));
+        let stored = flow.get_fingerprints_by_type(FingerprintType::Content);
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].id(), content.id());
+    }
+}
@@ -0,0 +1,96 @@
+//! Experimental protocol support
+//!
+//! In-progress protocol work is compiled only with its `experimental-*` cargo
+//! feature, is reported as experimental by [`capabilities`](crate::capabilities),
+//! and may change between releases. Without the feature the client takes the
+//! stable path named by [`ExperimentalFeature::fallback`]:
+//!
+//! | feature | adds | without it |
+//! |---|---|---|
+//! | `experimental-pq` | X25519MLKEM768 key exchange for profiles that offer it | classical X25519 / P-256 |
+//! | `experimental-h3-datagrams` | HTTP/3 datagram codec (RFC 9297) | no datagrams |
+//! | `experimental-webtransport` | [`HttpClient::connect_webtransport`](crate::HttpClient) | HTTP/2 or HTTP/3 requests |
+//!
+//! Encrypted Client Hello is stable and stays under the default `ech` feature;
+//! it is not offered over QUIC.
+
+/// A protocol feature behind an `experimental-*` cargo feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExperimentalFeature {
+    /// Hybrid post-quantum key exchange (X25519MLKEM768)
+    PostQuantum,
+    /// HTTP/3 datagrams
+    H3Datagrams,
+    /// WebTransport sessions over HTTP/3
+    WebTransport,
+}
+
+impl ExperimentalFeature {
+    pub const ALL: [ExperimentalFeature; 3] = [
+        ExperimentalFeature::PostQuantum,
+        ExperimentalFeature::H3Datagrams,
+        ExperimentalFeature::WebTransport,
+    ];
+
+    /// Capability name, as listed by [`capabilities`](crate::capabilities)
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExperimentalFeature::PostQuantum => "post-quantum-kx",
+            ExperimentalFeature::H3Datagrams => "h3-datagrams",
+            ExperimentalFeature::WebTransport => "webtransport",
+        }
+    }
+
+    /// Cargo feature compiling it in
+    pub fn cargo_feature(&self) -> &'static str {
+        match self {
+            ExperimentalFeature::PostQuantum => "experimental-pq",
+            ExperimentalFeature::H3Datagrams => "experimental-h3-datagrams",
+            ExperimentalFeature::WebTransport => "experimental-webtransport",
+        }
+    }
+
+    /// Whether this build includes it
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            ExperimentalFeature::PostQuantum => cfg!(feature = "experimental-pq"),
+            ExperimentalFeature::H3Datagrams => cfg!(feature = "experimental-h3-datagrams"),
+            ExperimentalFeature::WebTransport => cfg!(feature = "experimental-webtransport"),
+        }
+    }
+
+    /// What the client does instead when it is not compiled in
+    pub fn fallback(&self) -> &'static str {
+        match self {
+            ExperimentalFeature::PostQuantum => "classical key exchange (X25519, P-256, P-384)",
+            ExperimentalFeature::H3Datagrams => "HTTP/3 without datagrams",
+            ExperimentalFeature::WebTransport => "HTTP/2 or HTTP/3 requests",
+        }
+    }
+
+    /// Experimental features compiled into this build
+    pub fn enabled() -> impl Iterator<Item = ExperimentalFeature> {
+        Self::ALL
+            .into_iter()
+            .filter(ExperimentalFeature::is_enabled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_experimental_features_match_capabilities() {
+        let capabilities = crate::capabilities();
+        for feature in ExperimentalFeature::ALL {
+            let capability = capabilities
+                .iter()
+                .find(|c| c.name == feature.name())
+                .unwrap();
+            assert!(capability.experimental);
+            assert_eq!(capability.feature.as_deref(), Some(feature.cargo_feature()));
+            assert_eq!(capability.enabled, feature.is_enabled());
+        }
+    }
+}
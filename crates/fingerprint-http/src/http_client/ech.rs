@@ -0,0 +1,687 @@
+//! Encrypted Client Hello (ECH)
+//!
+//! Chrome looks up a host's HTTPS resource record before connecting. When the
+//! record carries an `ech` parameter, the real ClientHello is HPKE-encrypted
+//! under that config and only the outer hello, whose SNI is the config's public
+//! name, is visible on the wire. Hosts without a config get a GREASE ECH
+//! extension, so both cases look alike to an observer.
+//!
+//! - [`EchPolicy`] selects the behaviour per client (`HttpClientConfig::ech`,
+//!   defaulting to whatever the browser profile's ClientHello declares)
+//! - [`EchResolver`] fetches ECHConfigLists over DNS and caches them for the record TTL
+//! - the TLS paths plan an attempt per connection and fall back after a rejection:
+//!   retry configs sent by the server are tried once, a server that disabled ECH
+//!   gets GREASE, and a server without TLS 1.3 gets no ECH at all
+//!
+//! Direct HTTP/1.1 and HTTP/2 connections retry the handshake in place. Pooled
+//! connections offer ECH too, but a rejection only updates the cache for the next
+//! connection. HTTP/3 does not offer ECH. DNS lookups and real ECH need the `ech`
+//! feature; without it every policy except [`EchPolicy::Require`] degrades to no ECH.
+
+#[cfg(any(feature = "rustls-tls", feature = "http2"))]
+use super::{HttpClientError, Result};
+use fingerprint_profiles::BrowserProfile;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Extension type of `encrypted_client_hello` (draft-ietf-tls-esni)
+pub const ECH_EXTENSION_TYPE: u16 = 0xfe0d;
+
+/// How a client uses Encrypted Client Hello
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EchPolicy {
+    /// No ECH extension at all
+    Disabled,
+    /// GREASE ECH only; configs are never looked up
+    Grease,
+    /// Real ECH when the host publishes a config, GREASE otherwise (Chrome's behaviour)
+    #[default]
+    Auto,
+    /// Real ECH or fail: a missing config and a rejection are both errors
+    Require,
+}
+
+impl EchPolicy {
+    /// Policy matching a browser profile: `Auto` when its ClientHello carries ECH
+    pub fn for_profile(profile: Option<&BrowserProfile>) -> Self {
+        let offers_ech = profile.is_some_and(|profile| {
+            profile
+                .tls_config
+                .extensions
+                .iter()
+                .any(|ext| ext.extension_id() == ECH_EXTENSION_TYPE)
+        });
+        if offers_ech {
+            EchPolicy::Auto
+        } else {
+            EchPolicy::Disabled
+        }
+    }
+}
+
+#[cfg(any(feature = "rustls-tls", feature = "http2"))]
+impl EchPolicy {
+    /// Effective policy of a client configuration
+    pub(crate) fn of(config: &super::HttpClientConfig) -> Self {
+        config
+            .ech
+            .unwrap_or_else(|| Self::for_profile(config.profile.as_ref()))
+    }
+}
+
+#[cfg(any(feature = "rustls-tls", feature = "http2"))]
+/// Handshakes after the first one: one with retry configs, one more after a rejection
+pub(crate) const MAX_FALLBACKS: usize = 2;
+
+#[cfg(any(feature = "rustls-tls", feature = "http2"))]
+/// Map a failed handshake to an error, keeping plain IO failures (timeouts) as IO
+pub(crate) fn handshake_error(err: std::io::Error) -> HttpClientError {
+    if err
+        .get_ref()
+        .is_some_and(|inner| inner.is::<rustls::Error>())
+    {
+        return HttpClientError::TlsError(format!("TLS handshakefailure: {}", err));
+    }
+    HttpClientError::from(err)
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// `None` records that the host publishes no (usable) config
+    config_list: Option<Vec<u8>>,
+    expires: Instant,
+}
+
+/// ECHConfigList cache keyed by host, filled from DNS HTTPS records
+#[derive(Debug)]
+pub struct EchResolver {
+    entries: Mutex<HashMap<String, Entry>>,
+    lookup_timeout: Duration,
+    negative_ttl: Duration,
+    max_ttl: Duration,
+}
+
+static GLOBAL_RESOLVER: Lazy<EchResolver> = Lazy::new(EchResolver::new);
+
+impl EchResolver {
+    /// Create an empty resolver (3s lookups, 5 min negative caching, TTLs capped at 1h)
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            lookup_timeout: Duration::from_secs(3),
+            negative_ttl: Duration::from_secs(300),
+            max_ttl: Duration::from_secs(3600),
+        }
+    }
+
+    /// Resolver shared by all clients
+    pub fn global() -> &'static EchResolver {
+        &GLOBAL_RESOLVER
+    }
+
+    /// Set how long a DNS lookup may take before the host is treated as having no config
+    pub fn with_lookup_timeout(mut self, timeout: Duration) -> Self {
+        self.lookup_timeout = timeout;
+        self
+    }
+
+    /// Pin a config list for `host`, e.g. one distributed out of band
+    pub fn insert(&self, host: &str, config_list: Vec<u8>, ttl: Duration) {
+        self.store(host, Some(config_list), ttl);
+    }
+
+    /// Forget what is known about `host`
+    pub fn invalidate(&self, host: &str) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(&host.to_ascii_lowercase());
+        }
+    }
+
+    /// Number of cached hosts, including ones known to have no config
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .map(|entries| entries.len())
+            .unwrap_or(0)
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// ECHConfigList for `host`, looked up over DNS (blocking) when not cached
+    pub fn config_list(&self, host: &str) -> Option<Vec<u8>> {
+        if let Some(cached) = self.cached(host) {
+            return cached;
+        }
+        let found = lookup_blocking(host, self.lookup_timeout);
+        self.store_lookup(host, found)
+    }
+
+    /// Async variant of [`config_list`](Self::config_list) for callers already on a runtime
+    pub async fn config_list_async(&self, host: &str) -> Option<Vec<u8>> {
+        if let Some(cached) = self.cached(host) {
+            return cached;
+        }
+        let found = lookup(host, self.lookup_timeout).await;
+        self.store_lookup(host, found)
+    }
+
+    /// Cached answer; `Some(None)` means the host is known to have no config
+    fn cached(&self, host: &str) -> Option<Option<Vec<u8>>> {
+        let mut entries = self.entries.lock().ok()?;
+        let key = host.to_ascii_lowercase();
+        match entries.get(&key) {
+            Some(entry) if entry.expires > Instant::now() => Some(entry.config_list.clone()),
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn store_lookup(&self, host: &str, found: Option<(Vec<u8>, Duration)>) -> Option<Vec<u8>> {
+        match found {
+            Some((config_list, ttl)) => {
+                self.store(host, Some(config_list.clone()), ttl);
+                Some(config_list)
+            }
+            None => {
+                self.store(host, None, self.negative_ttl);
+                None
+            }
+        }
+    }
+
+    fn store(&self, host: &str, config_list: Option<Vec<u8>>, ttl: Duration) {
+        let entry = Entry {
+            config_list,
+            expires: Instant::now() + ttl.min(self.max_ttl),
+        };
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(host.to_ascii_lowercase(), entry);
+        }
+    }
+
+    #[cfg(feature = "ech")]
+    /// Record that `host` has no usable config, e.g. after it rejected ECH without retry configs
+    fn forget_config(&self, host: &str) {
+        self.store(host, None, self.negative_ttl);
+    }
+}
+
+impl Default for EchResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Look up the `ech` parameter of the best service-mode HTTPS record of `host`
+#[cfg(feature = "ech")]
+async fn lookup(host: &str, timeout: Duration) -> Option<(Vec<u8>, Duration)> {
+    use hickory_resolver::proto::rr::rdata::svcb::SvcParamValue;
+    use hickory_resolver::proto::rr::{RData, RecordType};
+    use hickory_resolver::TokioResolver;
+
+    let mut builder = TokioResolver::builder_tokio().ok()?;
+    builder.options_mut().timeout = timeout;
+    builder.options_mut().attempts = 1;
+    let resolver = builder.build();
+
+    let answer = tokio::time::timeout(timeout, resolver.lookup(host, RecordType::HTTPS))
+        .await
+        .ok()?
+        .ok()?;
+    let ttl = answer
+        .valid_until()
+        .saturating_duration_since(Instant::now());
+
+    // alias-mode records (priority 0) carry no parameters
+    let mut records: Vec<_> = answer
+        .iter()
+        .filter_map(|rdata| match rdata {
+            RData::HTTPS(https) if https.0.svc_priority() > 0 => Some(&https.0),
+            _ => None,
+        })
+        .collect();
+    records.sort_by_key(|svcb| svcb.svc_priority());
+
+    records.iter().find_map(|svcb| {
+        svcb.svc_params().iter().find_map(|(_, value)| match value {
+            SvcParamValue::EchConfigList(list) if !list.0.is_empty() => Some((list.0.clone(), ttl)),
+            _ => None,
+        })
+    })
+}
+
+#[cfg(not(feature = "ech"))]
+async fn lookup(_host: &str, _timeout: Duration) -> Option<(Vec<u8>, Duration)> {
+    None
+}
+
+/// Run [`lookup`] from sync code, on a scratch runtime so it is safe inside another runtime
+fn lookup_blocking(host: &str, timeout: Duration) -> Option<(Vec<u8>, Duration)> {
+    #[cfg(feature = "ech")]
+    {
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()
+                        .ok()?
+                        .block_on(lookup(host, timeout))
+                })
+                .join()
+                .ok()
+                .flatten()
+        })
+    }
+    #[cfg(not(feature = "ech"))]
+    {
+        let _ = (host, timeout);
+        None
+    }
+}
+
+#[cfg(any(feature = "rustls-tls", feature = "http2"))]
+/// ECH behaviour chosen for one connection attempt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum EchAttempt {
+    /// No ECH extension
+    Off,
+    /// GREASE ECH extension with a throwaway key
+    Grease,
+    /// Real ECH under this ECHConfigList
+    Enabled(Vec<u8>),
+}
+
+#[cfg(any(feature = "rustls-tls", feature = "http2"))]
+impl EchAttempt {
+    /// First attempt for `host` under `policy`
+    #[cfg(feature = "rustls-tls")]
+    pub(crate) fn plan(policy: EchPolicy, host: &str, resolver: &EchResolver) -> Result<Self> {
+        let config_list = match Self::needs_lookup(policy, host)? {
+            Some(attempt) => return Ok(attempt),
+            None => resolver.config_list(host),
+        };
+        Self::from_lookup(policy, host, config_list)
+    }
+
+    /// Async variant of `plan`
+    #[cfg(feature = "http2")]
+    pub(crate) async fn plan_async(
+        policy: EchPolicy,
+        host: &str,
+        resolver: &EchResolver,
+    ) -> Result<Self> {
+        let config_list = match Self::needs_lookup(policy, host)? {
+            Some(attempt) => return Ok(attempt),
+            None => resolver.config_list_async(host).await,
+        };
+        Self::from_lookup(policy, host, config_list)
+    }
+
+    /// The attempt when it does not depend on DNS, `None` when a lookup is needed
+    fn needs_lookup(policy: EchPolicy, host: &str) -> Result<Option<Self>> {
+        // ECH names the server in the outer hello, so it needs a DNS name
+        let is_ip = host.parse::<std::net::IpAddr>().is_ok();
+        match policy {
+            EchPolicy::Require if !cfg!(feature = "ech") => Err(HttpClientError::TlsError(
+                "ECH support needs the `ech` feature".to_string(),
+            )),
+            EchPolicy::Require if is_ip => Err(HttpClientError::TlsError(format!(
+                "ECH required but {} is not a DNS name",
+                host
+            ))),
+            EchPolicy::Require => Ok(None),
+            _ if !cfg!(feature = "ech") || is_ip => Ok(Some(EchAttempt::Off)),
+            EchPolicy::Disabled => Ok(Some(EchAttempt::Off)),
+            EchPolicy::Grease => Ok(Some(EchAttempt::Grease)),
+            EchPolicy::Auto => Ok(None),
+        }
+    }
+
+    fn from_lookup(policy: EchPolicy, host: &str, config_list: Option<Vec<u8>>) -> Result<Self> {
+        let usable = config_list.filter(|list| is_supported(list));
+        match (usable, policy) {
+            (Some(list), _) => Ok(EchAttempt::Enabled(list)),
+            (None, EchPolicy::Require) => Err(HttpClientError::TlsError(format!(
+                "ECH required but {} publishes no supported ECH config",
+                host
+            ))),
+            (None, _) => Ok(EchAttempt::Grease),
+        }
+    }
+
+    /// What to try after a failed handshake, if anything
+    ///
+    /// Updates `resolver` with what the failure revealed about the host.
+    pub(crate) fn fallback(
+        &self,
+        policy: EchPolicy,
+        host: &str,
+        resolver: &EchResolver,
+        err: &std::io::Error,
+    ) -> Option<Self> {
+        #[cfg(feature = "ech")]
+        {
+            use rustls::{AlertDescription, Error, PeerIncompatible};
+
+            let err = err.get_ref()?.downcast_ref::<Error>()?;
+            match (self, err) {
+                (
+                    EchAttempt::Enabled(_),
+                    Error::PeerIncompatible(PeerIncompatible::ServerRejectedEncryptedClientHello(
+                        retry_configs,
+                    )),
+                ) => match retry_configs {
+                    Some(configs) => {
+                        use rustls::internal::msgs::codec::Codec;
+                        let list = configs.get_encoding();
+                        if !is_supported(&list) {
+                            resolver.forget_config(host);
+                            return (policy != EchPolicy::Require).then_some(EchAttempt::Grease);
+                        }
+                        // keep the fresh configs briefly; DNS remains the source of truth
+                        resolver.insert(host, list.clone(), resolver.negative_ttl);
+                        Some(EchAttempt::Enabled(list))
+                    }
+                    // the server disabled ECH for this name
+                    None => {
+                        resolver.forget_config(host);
+                        (policy != EchPolicy::Require).then_some(EchAttempt::Grease)
+                    }
+                },
+                // GREASE ECH pins the handshake to TLS 1.3; older servers get a plain hello
+                (
+                    EchAttempt::Grease,
+                    Error::PeerIncompatible(
+                        PeerIncompatible::ServerTlsVersionIsDisabledByOurConfig
+                        | PeerIncompatible::SupportedVersionsExtensionRequired,
+                    )
+                    | Error::AlertReceived(AlertDescription::ProtocolVersion),
+                ) => Some(EchAttempt::Off),
+                _ => None,
+            }
+        }
+        #[cfg(not(feature = "ech"))]
+        {
+            let _ = (policy, host, resolver, err);
+            None
+        }
+    }
+
+    /// rustls client configuration for this attempt
+    pub(crate) fn client_config(
+        &self,
+        config: &super::HttpClientConfig,
+        alpn_protocols: Vec<Vec<u8>>,
+    ) -> Result<rustls::ClientConfig> {
+        #[cfg(feature = "ech")]
+        {
+            use rustls::client::{EchConfig, EchGreaseConfig, EchMode};
+            use rustls::crypto::aws_lc_rs::hpke::{
+                ALL_SUPPORTED_SUITES, DH_KEM_X25519_HKDF_SHA256_AES_128,
+            };
+            use rustls::crypto::hpke::Hpke;
+
+            let mode = match self {
+                EchAttempt::Off => None,
+                EchAttempt::Grease => {
+                    // Chrome's GREASE suite: X25519, HKDF-SHA256, AES-128-GCM
+                    let suite = DH_KEM_X25519_HKDF_SHA256_AES_128;
+                    let (placeholder_key, _) = suite.generate_key_pair().map_err(|e| {
+                        HttpClientError::TlsError(format!("GREASE ECH key failure: {}", e))
+                    })?;
+                    Some(EchMode::Grease(EchGreaseConfig::new(
+                        suite,
+                        placeholder_key,
+                    )))
+                }
+                EchAttempt::Enabled(list) => {
+                    let ech_config = EchConfig::new(list.clone().into(), ALL_SUPPORTED_SUITES)
+                        .map_err(|e| {
+                            HttpClientError::TlsError(format!("invalid ECH config: {}", e))
+                        })?;
+                    Some(EchMode::Enable(ech_config))
+                }
+            };
+            if let Some(mode) = mode {
+                let mut tls_config = super::rustls_utils::build_ech_client_config(
+                    config.verify_tls,
+                    alpn_protocols,
+                    config.profile.as_ref(),
+                    config.revocation.as_ref(),
+                    mode,
+                )
+                .map_err(|e| {
+                    HttpClientError::TlsError(format!("ECH configuration failure: {}", e))
+                })?;
+                super::rustls_utils::apply_session_resumption(
+                    &mut tls_config,
+                    config.session_cache.as_ref(),
+                    config.profile.as_ref(),
+                );
+                return Ok(tls_config);
+            }
+        }
+
+        let mut tls_config = super::rustls_utils::build_client_config(
+            config.verify_tls,
+            alpn_protocols,
+            config.profile.as_ref(),
+            config.revocation.as_ref(),
+        );
+        super::rustls_utils::apply_session_resumption(
+            &mut tls_config,
+            config.session_cache.as_ref(),
+            config.profile.as_ref(),
+        );
+        Ok(tls_config)
+    }
+}
+
+#[cfg(any(feature = "rustls-tls", feature = "http2"))]
+/// Whether rustls can use some config in `config_list`
+fn is_supported(config_list: &[u8]) -> bool {
+    #[cfg(feature = "ech")]
+    {
+        rustls::client::EchConfig::new(
+            config_list.to_vec().into(),
+            rustls::crypto::aws_lc_rs::hpke::ALL_SUPPORTED_SUITES,
+        )
+        .is_ok()
+    }
+    #[cfg(not(feature = "ech"))]
+    {
+        let _ = config_list;
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ECHConfigList with one X25519/HKDF-SHA256/AES-128-GCM config
+    #[cfg(feature = "ech")]
+    fn config_list(public_name: &str) -> Vec<u8> {
+        use rustls::crypto::aws_lc_rs::hpke::DH_KEM_X25519_HKDF_SHA256_AES_128;
+        use rustls::crypto::hpke::Hpke;
+
+        let (public_key, _) = DH_KEM_X25519_HKDF_SHA256_AES_128
+            .generate_key_pair()
+            .unwrap();
+        let mut contents = vec![7]; // config_id
+        contents.extend_from_slice(&0x0020u16.to_be_bytes()); // DHKEM(X25519, HKDF-SHA256)
+        contents.extend_from_slice(&(public_key.0.len() as u16).to_be_bytes());
+        contents.extend_from_slice(&public_key.0);
+        contents.extend_from_slice(&4u16.to_be_bytes());
+        contents.extend_from_slice(&[0x00, 0x01, 0x00, 0x01]); // HKDF-SHA256, AES-128-GCM
+        contents.push(0); // maximum_name_length
+        contents.push(public_name.len() as u8);
+        contents.extend_from_slice(public_name.as_bytes());
+        contents.extend_from_slice(&0u16.to_be_bytes()); // extensions
+
+        let mut config = ECH_EXTENSION_TYPE.to_be_bytes().to_vec();
+        config.extend_from_slice(&(contents.len() as u16).to_be_bytes());
+        config.extend_from_slice(&contents);
+        let mut list = (config.len() as u16).to_be_bytes().to_vec();
+        list.extend_from_slice(&config);
+        list
+    }
+
+    #[test]
+    fn test_policy_follows_profile() {
+        let chrome = fingerprint_profiles::profiles::chrome_133();
+        let expected = if chrome
+            .tls_config
+            .extensions
+            .iter()
+            .any(|ext| ext.extension_id() == ECH_EXTENSION_TYPE)
+        {
+            EchPolicy::Auto
+        } else {
+            EchPolicy::Disabled
+        };
+        assert_eq!(EchPolicy::for_profile(Some(&chrome)), expected);
+        assert_eq!(EchPolicy::for_profile(None), EchPolicy::Disabled);
+    }
+
+    #[cfg(feature = "rustls-tls")]
+    #[test]
+    fn test_plan_without_lookup() {
+        let resolver = EchResolver::new();
+        let plan = |policy, host| EchAttempt::plan(policy, host, &resolver);
+
+        assert_eq!(
+            plan(EchPolicy::Disabled, "example.com").unwrap(),
+            EchAttempt::Off
+        );
+        assert_eq!(plan(EchPolicy::Auto, "192.0.2.1").unwrap(), EchAttempt::Off);
+        assert!(plan(EchPolicy::Require, "192.0.2.1").is_err());
+        if cfg!(feature = "ech") {
+            assert_eq!(
+                plan(EchPolicy::Grease, "example.com").unwrap(),
+                EchAttempt::Grease
+            );
+        }
+        assert!(resolver.is_empty());
+    }
+
+    #[cfg(feature = "ech")]
+    #[test]
+    fn test_plan_uses_cached_configs() {
+        let resolver = EchResolver::new();
+        let list = config_list("public.example");
+        resolver.insert("ech.example", list.clone(), Duration::from_secs(60));
+        resolver.insert("bad.example", vec![0, 3, 1, 2, 3], Duration::from_secs(60));
+        resolver.store("none.example", None, Duration::from_secs(60));
+
+        let plan = |policy, host| EchAttempt::plan(policy, host, &resolver);
+        assert_eq!(
+            plan(EchPolicy::Auto, "ECH.example").unwrap(),
+            EchAttempt::Enabled(list)
+        );
+        assert_eq!(
+            plan(EchPolicy::Auto, "bad.example").unwrap(),
+            EchAttempt::Grease
+        );
+        assert_eq!(
+            plan(EchPolicy::Auto, "none.example").unwrap(),
+            EchAttempt::Grease
+        );
+        assert!(plan(EchPolicy::Require, "none.example").is_err());
+
+        resolver.invalidate("ech.example");
+        assert_eq!(resolver.len(), 2);
+    }
+
+    #[cfg(feature = "ech")]
+    #[test]
+    fn test_fallback_after_rejection() {
+        use rustls::{Error, PeerIncompatible};
+
+        let resolver = EchResolver::new();
+        let host = "ech.example";
+        let list = config_list("public.example");
+        resolver.insert(host, list.clone(), Duration::from_secs(60));
+        let rejected = |retry_configs| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                Error::PeerIncompatible(PeerIncompatible::ServerRejectedEncryptedClientHello(
+                    retry_configs,
+                )),
+            )
+        };
+
+        // retry configs replace the cached ones
+        let retry_list = config_list("other.example");
+        let mut reader = rustls::internal::msgs::codec::Reader::init(&retry_list);
+        let configs = <Vec<rustls::internal::msgs::handshake::EchConfigPayload> as rustls::internal::msgs::codec::Codec>::read(&mut reader).unwrap();
+        let attempt = EchAttempt::Enabled(list.clone());
+        let next = attempt.fallback(EchPolicy::Auto, host, &resolver, &rejected(Some(configs)));
+        assert_eq!(next, Some(EchAttempt::Enabled(retry_list.clone())));
+        assert_eq!(resolver.cached(host), Some(Some(retry_list)));
+
+        // a plain rejection disables ECH for the host unless it is required
+        assert_eq!(
+            attempt.fallback(EchPolicy::Auto, host, &resolver, &rejected(None)),
+            Some(EchAttempt::Grease)
+        );
+        assert_eq!(resolver.cached(host), Some(None));
+        assert_eq!(
+            attempt.fallback(EchPolicy::Require, host, &resolver, &rejected(None)),
+            None
+        );
+
+        // GREASE falls back to no ECH against TLS 1.2-only servers
+        let tls12 = std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            Error::PeerIncompatible(PeerIncompatible::ServerTlsVersionIsDisabledByOurConfig),
+        );
+        assert_eq!(
+            EchAttempt::Grease.fallback(EchPolicy::Auto, host, &resolver, &tls12),
+            Some(EchAttempt::Off)
+        );
+        assert_eq!(
+            EchAttempt::Off.fallback(EchPolicy::Auto, host, &resolver, &tls12),
+            None
+        );
+    }
+
+    #[cfg(feature = "ech")]
+    #[test]
+    fn test_client_config_per_attempt() {
+        let config = super::super::HttpClientConfig::default();
+        let attempts = [
+            EchAttempt::Off,
+            EchAttempt::Grease,
+            EchAttempt::Enabled(config_list("public.example")),
+        ];
+        for attempt in attempts {
+            let tls_config = attempt
+                .client_config(&config, vec![b"h2".to_vec()])
+                .unwrap();
+            assert_eq!(tls_config.alpn_protocols, vec![b"h2".to_vec()]);
+        }
+        assert!(EchAttempt::Enabled(vec![0, 1, 2])
+            .client_config(&config, Vec::new())
+            .is_err());
+    }
+
+    #[cfg(feature = "ech")]
+    #[test]
+    #[ignore] // needs network access
+    fn test_lookup_published_config() {
+        let resolver = EchResolver::new();
+        let list = resolver.config_list("crypto.cloudflare.com").unwrap();
+        assert!(is_supported(&list));
+        assert_eq!(resolver.len(), 1);
+    }
+}
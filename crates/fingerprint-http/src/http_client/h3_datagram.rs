@@ -0,0 +1,103 @@
+//! HTTP/3 datagrams (RFC 9297) and the QUIC variable-length integers they use
+//!
+//! An HTTP/3 datagram is a QUIC DATAGRAM frame whose payload starts with the
+//! request stream ID divided by four ("quarter stream ID"), followed by the
+//! application data. Peers only send them after both advertised
+//! `SETTINGS_H3_DATAGRAM = 1`.
+//!
+//! Experimental: only compiled with the `experimental-h3-datagrams` feature.
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+/// `SETTINGS_H3_DATAGRAM` (RFC 9297)
+pub const SETTINGS_H3_DATAGRAM: u64 = 0x33;
+
+/// Largest value a QUIC variable-length integer holds
+pub const VARINT_MAX: u64 = (1 << 62) - 1;
+
+/// Encoded length of `value` as a QUIC variable-length integer
+pub fn varint_len(value: u64) -> usize {
+    match value {
+        0..=0x3f => 1,
+        0x40..=0x3fff => 2,
+        0x4000..=0x3fff_ffff => 4,
+        _ => 8,
+    }
+}
+
+/// Append `value` as a QUIC variable-length integer (RFC 9000 section 16)
+///
+/// Values above [`VARINT_MAX`] are a caller bug and panic.
+pub fn put_varint(buf: &mut impl BufMut, value: u64) {
+    assert!(value <= VARINT_MAX, "varint out of range: {}", value);
+    match varint_len(value) {
+        1 => buf.put_u8(value as u8),
+        2 => buf.put_u16(0x4000 | value as u16),
+        4 => buf.put_u32(0x8000_0000 | value as u32),
+        _ => buf.put_u64(0xc000_0000_0000_0000 | value),
+    }
+}
+
+/// Read a QUIC variable-length integer; `None` if `buf` ends inside it
+pub fn get_varint(buf: &mut impl Buf) -> Option<u64> {
+    if !buf.has_remaining() {
+        return None;
+    }
+    let len = 1 << (buf.chunk()[0] >> 6);
+    if buf.remaining() < len {
+        return None;
+    }
+    let value = match len {
+        1 => u64::from(buf.get_u8()),
+        2 => u64::from(buf.get_u16() & 0x3fff),
+        4 => u64::from(buf.get_u32() & 0x3fff_ffff),
+        _ => buf.get_u64() & 0x3fff_ffff_ffff_ffff,
+    };
+    Some(value)
+}
+
+/// DATAGRAM payload carrying `data` for the request stream `stream_id`
+pub fn encode_datagram(stream_id: u64, data: &[u8]) -> Bytes {
+    let quarter = stream_id / 4;
+    let mut buf = BytesMut::with_capacity(varint_len(quarter) + data.len());
+    put_varint(&mut buf, quarter);
+    buf.put_slice(data);
+    buf.freeze()
+}
+
+/// Split a DATAGRAM payload into its request stream ID and data
+pub fn decode_datagram(mut payload: Bytes) -> Option<(u64, Bytes)> {
+    let quarter = get_varint(&mut payload)?;
+    Some((quarter * 4, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_and_datagram_round_trip() {
+        // RFC 9000 appendix A.1 examples
+        for (value, encoded) in [
+            (37u64, &[0x25][..]),
+            (15293, &[0x7b, 0xbd]),
+            (494878333, &[0x9d, 0x7f, 0x3e, 0x7d]),
+            (
+                151288809941952652,
+                &[0xc2, 0x19, 0x7c, 0x5e, 0xff, 0x14, 0xe8, 0x8c],
+            ),
+        ] {
+            let mut buf = BytesMut::new();
+            put_varint(&mut buf, value);
+            assert_eq!(&buf[..], encoded);
+            assert_eq!(get_varint(&mut &encoded[..]), Some(value));
+        }
+        assert_eq!(get_varint(&mut &[0x7b][..]), None);
+
+        let payload = encode_datagram(8, b"ping");
+        assert_eq!(&payload[..], b"\x02ping");
+        let (stream_id, data) = decode_datagram(payload).unwrap();
+        assert_eq!(stream_id, 8);
+        assert_eq!(&data[..], b"ping");
+    }
+}
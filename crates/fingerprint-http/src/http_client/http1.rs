@@ -14,11 +14,27 @@ pub fn send_http1_request(
     request: &HttpRequest,
     config: &HttpClientConfig,
 ) -> Result<HttpResponse> {
+    let mut stream = open_http1_request(host, port, path, request, config)?;
+
+    // readresponse
+    let buffer =
+        super::io::read_http1_response_bytes(&mut stream, config.limits.max_response_body_bytes)
+            .map_err(HttpClientError::from)?;
+
+    // Parseresponse
+    HttpResponse::parse_with_limits(&buffer, &config.limits)
+}
+
+/// Connect and send the request, leaving the response unread on the stream
+pub(crate) fn open_http1_request(
+    host: &str,
+    port: u16,
+    path: &str,
+    request: &HttpRequest,
+    config: &HttpClientConfig,
+) -> Result<TcpStream> {
     // connectionserver
-    let addr = format!("{}:{}", host, port);
-    let mut stream = TcpStream::connect(&addr).map_err(|e| {
-        HttpClientError::ConnectionFailed(format!("Connection failed {}: {}", addr, e))
-    })?;
+    let mut stream = super::proxy::connect_tcp(config, host, port)?;
 
     // settingstimeout
     stream
@@ -50,14 +66,7 @@ pub fn send_http1_request(
         .map_err(HttpClientError::Io)?;
     stream.flush().map_err(HttpClientError::Io)?;
 
-    // readresponse
-    let mut stream = stream;
-    let buffer =
-        super::io::read_http1_response_bytes(&mut stream, super::io::DEFAULT_MAX_RESPONSE_BYTES)
-            .map_err(HttpClientError::Io)?;
-
-    // Parseresponse
-    HttpResponse::parse(&buffer).map_err(HttpClientError::InvalidResponse)
+    Ok(stream)
 }
 
 #[cfg(test)]
@@ -65,11 +65,11 @@ pub fn send_http1_request_with_pool(
     // Fix: usecompleteresponsereadlogic (include body)
     // connectionwillautomatic归still to connection pool (through Drop)
     let buffer =
-        super::io::read_http1_response_bytes(&mut stream, super::io::DEFAULT_MAX_RESPONSE_BYTES)
-            .map_err(HttpClientError::Io)?;
+        super::io::read_http1_response_bytes(&mut stream, config.limits.max_response_body_bytes)
+            .map_err(HttpClientError::from)?;
 
     // Parseresponse
-    HttpResponse::parse(&buffer).map_err(HttpClientError::InvalidResponse)
+    HttpResponse::parse_with_limits(&buffer, &config.limits)
 }
 
 #[cfg(not(feature = "connection-pool"))]
@@ -3,6 +3,8 @@
 //! 使用 h2 crate 实现完整的 HTTP/2 支持
 //! 应用 fingerprint-rust HTTP/2 设置
 
+#[cfg(feature = "http2")]
+use super::telemetry::{span, Instrument};
 use super::{HttpClientConfig, HttpClientError, HttpRequest, HttpResponse, Result};
 
 #[cfg(feature = "http2")]
@@ -29,28 +31,12 @@ async fn send_http2_request_async(
     request: &HttpRequest,
     config: &HttpClientConfig,
 ) -> Result<HttpResponse> {
-    use std::net::ToSocketAddrs;
     use std::time::Instant;
-    use tokio::net::TcpStream;
 
     let start = Instant::now();
 
-    // 1. establish TCP connection (application TCP Profile)
-    let addr = format!("{}:{}", host, port);
-    let socket_addrs = addr
-        .to_socket_addrs()
-        .map_err(|e| HttpClientError::InvalidUrl(format!("DNS Parsefailure: {}", e)))?
-        .next()
-        .ok_or_else(|| HttpClientError::InvalidUrl("unable to Parse address".to_string()))?;
-
-    // 1. 建立 TCP 连接
-    // 注意：暂时不使用 TCP fingerprint，直接建立连接
-    let tcp = TcpStream::connect(socket_addrs)
-        .await
-        .map_err(|e| HttpClientError::ConnectionFailed(format!("TCP Connection failed: {}", e)))?;
-
-    // 2. TLS 握手
-    let tls_stream = perform_tls_handshake(tcp, host, config).await?;
+    // 1-2. TCP connection and TLS handshake (with ECH fallbacks)
+    let tls_stream = perform_tls_handshake(host, port, config).await?;
 
     // 3. HTTP/2 handshake (application Settings configuration)
     let mut builder = client::Builder::new();
@@ -175,39 +161,24 @@ async fn send_http2_request_async(
     let status_code = response.status().as_u16();
     let headers = response.headers().clone();
 
-    // securityFix: Check HTTP/2 responseheadersize, prevent Header compressionbombattack
-    // h2 crate 0.4 default MAX_HEADER_LIST_SIZE usuallylarger, weAdd额outsideCheck
-    const MAX_HTTP2_HEADER_SIZE: usize = 64 * 1024; // 64KB (RFC 7540 suggestminimumvalue)
-    let total_header_size: usize = headers
-        .iter()
-        .map(|(k, v)| k.as_str().len() + v.len())
-        .sum();
-    if total_header_size > MAX_HTTP2_HEADER_SIZE {
-        return Err(HttpClientError::InvalidResponse(format!(
-            "HTTP/2 responseheadertoo large (>{} bytes)",
-            MAX_HTTP2_HEADER_SIZE
-        )));
-    }
+    // securityFix: limit HTTP/2 response header count/size (header compression bombs)
+    config
+        .limits
+        .check_headers(headers.iter().map(|(k, v)| (k.as_str(), v.as_bytes())))?;
 
     // receive body
     let mut body_stream = response.into_body();
     let mut body_data = Vec::new();
 
-    // securitylimit：prevent HTTP/2 responsebody too largecauseinsidememory exhausted
-    const MAX_HTTP2_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB
-
     while let Some(chunk) = body_stream.data().await {
         let chunk = chunk.map_err(|e| {
             HttpClientError::Io(std::io::Error::other(format!("read body failure: {}", e)))
         })?;
 
         // securityCheck：preventresponsebody too large
-        if body_data.len().saturating_add(chunk.len()) > MAX_HTTP2_BODY_SIZE {
-            return Err(HttpClientError::InvalidResponse(format!(
-                "HTTP/2 responsebody too large (>{} bytes)",
-                MAX_HTTP2_BODY_SIZE
-            )));
-        }
+        config
+            .limits
+            .check_response_body(body_data.len().saturating_add(chunk.len()))?;
 
         body_data.extend_from_slice(&chunk);
 
@@ -237,31 +208,104 @@ async fn send_http2_request_async(
     })
 }
 
+/// TCP connection to `host:port`, through the proxy chain when one is configured
+#[cfg(feature = "http2")]
+async fn connect_tcp(
+    host: &str,
+    port: u16,
+    config: &HttpClientConfig,
+) -> Result<tokio::net::TcpStream> {
+    use std::net::ToSocketAddrs;
+    use tokio::net::TcpStream;
+
+    let tcp = if let Some(chain) = &config.proxy {
+        // the proxy handshakes are blocking; keep them off the runtime workers
+        let (chain, target) = (chain.clone(), host.to_string());
+        let connect = span!(
+            "tcp.connect",
+            server.address = host,
+            server.port = port,
+            proxy = true
+        );
+        let stream =
+            tokio::task::spawn_blocking(move || connect.in_scope(|| chain.connect(&target, port)))
+                .await
+                .map_err(|e| {
+                    HttpClientError::ConnectionFailed(format!("proxy connect task failed: {}", e))
+                })??;
+        stream.set_nonblocking(true).map_err(HttpClientError::Io)?;
+        TcpStream::from_std(stream).map_err(HttpClientError::Io)?
+    } else {
+        let addr = format!("{}:{}", host, port);
+        let socket_addrs = span!("dns.resolve", server.address = host).in_scope(|| {
+            addr.to_socket_addrs()
+                .map_err(|e| HttpClientError::InvalidUrl(format!("DNS Parsefailure: {}", e)))?
+                .next()
+                .ok_or_else(|| HttpClientError::InvalidUrl("unable to Parse address".to_string()))
+        })?;
+
+        // 1. 建立 TCP 连接
+        // 注意：暂时不使用 TCP fingerprint，直接建立连接
+        TcpStream::connect(socket_addrs)
+            .instrument(span!(
+                "tcp.connect",
+                server.address = host,
+                server.port = port
+            ))
+            .await
+            .map_err(|e| {
+                HttpClientError::ConnectionFailed(format!("TCP Connection failed: {}", e))
+            })?
+    };
+    Ok(tcp)
+}
+
+/// TLS handshake on a fresh connection, starting over when an ECH fallback applies
 #[cfg(feature = "http2")]
 async fn perform_tls_handshake(
-    tcp: tokio::net::TcpStream,
     host: &str,
+    port: u16,
     config: &HttpClientConfig,
 ) -> Result<tokio_rustls::client::TlsStream<tokio::net::TcpStream>> {
+    use super::ech::{EchAttempt, EchPolicy, EchResolver};
     use rustls::pki_types::ServerName;
     use std::sync::Arc;
     use tokio_rustls::TlsConnector;
 
-    let tls_config = super::rustls_utils::build_client_config(
-        config.verify_tls,
-        vec![b"h2".to_vec(), b"http/1.1".to_vec()],
-        config.profile.as_ref(),
-    );
-
-    let connector = TlsConnector::from(Arc::new(tls_config));
-
     let server_name = ServerName::try_from(host.to_string())
         .map_err(|_| HttpClientError::TlsError("Invalid server name".to_string()))?;
 
-    connector
-        .connect(server_name, tcp)
-        .await
-        .map_err(|e| HttpClientError::TlsError(format!("TLS handshakefailure: {}", e)))
+    let policy = EchPolicy::of(config);
+    let resolver = EchResolver::global();
+    let mut attempt = EchAttempt::plan_async(policy, host, resolver).await?;
+    let mut fallbacks = 0;
+
+    loop {
+        let tcp = connect_tcp(host, port, config).await?;
+        let tls_config =
+            attempt.client_config(config, vec![b"h2".to_vec(), b"http/1.1".to_vec()])?;
+        let connector = TlsConnector::from(Arc::new(tls_config));
+
+        let handshake = span!(
+            "tls.handshake",
+            server.address = host,
+            tls.ech_fallbacks = fallbacks
+        );
+        match connector
+            .connect(server_name.clone(), tcp)
+            .instrument(handshake)
+            .await
+        {
+            Ok(tls_stream) => return Ok(tls_stream),
+            Err(e) => match attempt.fallback(policy, host, resolver, &e) {
+                Some(next) if fallbacks < super::ech::MAX_FALLBACKS => {
+                    attempt = next;
+                    fallbacks += 1;
+                }
+                _ => return Err(super::ech::handshake_error(e)),
+            },
+        }
+    }
 }
 
 #[cfg(not(feature = "http2"))]
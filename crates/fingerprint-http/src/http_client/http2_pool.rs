@@ -9,6 +9,7 @@
 
 #[cfg(all(feature = "connection-pool", feature = "http2"))]
 use super::pool::ConnectionPoolManager;
+use super::telemetry::{span, Instrument};
 use super::{HttpClientConfig, HttpClientError, HttpRequest, HttpResponse, Result};
 #[cfg(all(feature = "connection-pool", feature = "http2"))]
 use std::sync::Arc;
@@ -57,20 +58,26 @@ pub async fn send_http2_request_with_pool(
         .map_err(HttpClientError::Io)?;
     let tcp_stream = tokio::net::TcpStream::from_std(tcp_stream).map_err(HttpClientError::Io)?;
 
-    // TLS handshake
-    let tls_config = super::rustls_utils::build_client_config(
-        config.verify_tls,
-        vec![b"h2".to_vec()],
-        config.profile.as_ref(),
-    );
+    // TLS handshake (a rejected ECH offer only updates the cache; pooled sockets are not redialed)
+    let ech_policy = super::ech::EchPolicy::of(config);
+    let ech_resolver = super::ech::EchResolver::global();
+    let ech_attempt = super::ech::EchAttempt::plan_async(ech_policy, host, ech_resolver).await?;
+    let tls_config = ech_attempt.client_config(config, vec![b"h2".to_vec()])?;
     let connector = TlsConnector::from(std::sync::Arc::new(tls_config));
     let server_name = rustls::pki_types::ServerName::try_from(host.to_string())
         .map_err(|_| HttpClientError::TlsError("Invalid server name".to_string()))?;
 
-    let tls_stream = connector
+    let tls_stream = match connector
         .connect(server_name, tcp_stream)
+        .instrument(span!("tls.handshake", server.address = host, pooled = true))
         .await
-        .map_err(|e| HttpClientError::TlsError(format!("TLS handshakefailure: {}", e)))?;
+    {
+        Ok(tls_stream) => tls_stream,
+        Err(e) => {
+            ech_attempt.fallback(ech_policy, host, ech_resolver, &e);
+            return Err(super::ech::handshake_error(e));
+        }
+    };
 
     // Fix: use HTTP/2 sessionpoolimplementtrue multiplexreuse
     // avoideach timerequest都reperform TLS and HTTP/2 handshake
@@ -228,19 +235,13 @@ pub async fn send_http2_request_with_pool(
     // 先Extract status and headers
     let status_code = response.status().as_u16();
 
-    // securityFix: Check HTTP/2 responseheadersize, prevent Header compressionbombattack
-    const MAX_HTTP2_HEADER_SIZE: usize = 64 * 1024; // 64KB (RFC 7540 suggestminimumvalue)
-    let total_header_size: usize = response
-        .headers()
-        .iter()
-        .map(|(k, v)| k.as_str().len() + v.len())
-        .sum();
-    if total_header_size > MAX_HTTP2_HEADER_SIZE {
-        return Err(HttpClientError::InvalidResponse(format!(
-            "HTTP/2 responseheadertoo large (>{} bytes)",
-            MAX_HTTP2_HEADER_SIZE
-        )));
-    }
+    // securityFix: limit HTTP/2 response header count/size (header compression bombs)
+    config.limits.check_headers(
+        response
+            .headers()
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_bytes())),
+    )?;
 
     let status_text = http::StatusCode::from_u16(status_code)
         .ok()
@@ -257,21 +258,15 @@ pub async fn send_http2_request_with_pool(
     let mut body_stream = response.into_body();
     let mut body_data = Vec::new();
 
-    // securitylimit：prevent HTTP/2 responsebody too largecauseinsidememory exhausted
-    const MAX_HTTP2_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB
-
     while let Some(chunk) = body_stream.data().await {
         let chunk = chunk.map_err(|e| {
             HttpClientError::Io(std::io::Error::other(format!("read body failure: {}", e)))
         })?;
 
         // securityCheck：preventresponsebody too large
-        if body_data.len().saturating_add(chunk.len()) > MAX_HTTP2_BODY_SIZE {
-            return Err(HttpClientError::InvalidResponse(format!(
-                "HTTP/2 responsebody too large (>{} bytes)",
-                MAX_HTTP2_BODY_SIZE
-            )));
-        }
+        config
+            .limits
+            .check_response_body(body_data.len().saturating_add(chunk.len()))?;
 
         body_data.extend_from_slice(&chunk);
 
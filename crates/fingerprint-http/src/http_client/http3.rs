@@ -3,6 +3,8 @@
 //! 使用 quinn + h3 实现完整的 HTTP/3 支持
 //! HTTP/3 基于 QUIC 协议
 
+#[cfg(feature = "http3")]
+use super::telemetry::{span, Instrument};
 use super::{HttpClientConfig, HttpClientError, HttpRequest, HttpResponse, Result};
 
 #[cfg(feature = "http3")]
@@ -33,13 +35,25 @@ async fn send_http3_request_async(
     use std::sync::Arc;
     use std::time::{Duration, Instant};
 
+    if config.proxy.is_some() {
+        return Err(HttpClientError::ConnectionFailed(
+            "HTTP/3 cannot be tunnelled through a TCP proxy chain".to_string(),
+        ));
+    }
+
     let start = Instant::now();
 
     // 1. configuration QUIC client
-    let tls_config = super::rustls_utils::build_client_config(
+    let mut tls_config = super::rustls_utils::build_client_config(
         config.verify_tls,
         vec![b"h3".to_vec()],
         config.profile.as_ref(),
+        config.revocation.as_ref(),
+    );
+    super::rustls_utils::apply_session_resumption(
+        &mut tls_config,
+        config.session_cache.as_ref(),
+        config.profile.as_ref(),
     );
 
     let mut client_config = ClientConfig::new(Arc::new(
@@ -54,29 +68,29 @@ async fn send_http3_request_async(
     // connectionmigrate (Connection Migration) optimize
     // QUIC allow in IP toggle when keepconnection, thispairmobilesimulate至closeimportant
     transport.initial_rtt(Duration::from_millis(100));
-    transport.max_idle_timeout(Some(Duration::from_secs(60).try_into().map_err(|e| {
-        HttpClientError::ConnectionFailed(format!("configurationtimeoutfailure: {}", e))
-    })?));
     // increasekeep-alivefrequencyending withauxiliaryconnectionmigrateidentify
     transport.keep_alive_interval(Some(Duration::from_secs(20)));
 
     // allowpairendmigrate (defaultalreadyopen, hereexplicitexplain其importantproperty)
     // transport.allow_peer_migration(true);
 
-    // simulate Chrome streamcontrolwindow (Chrome usuallyuselargerwindowending withimprove吞吐)
-    transport.stream_receive_window((6 * 1024 * 1024u32).into()); // 6MB (Chrome style)
-    transport.receive_window((15 * 1024 * 1024u32).into()); // 15MB (Chrome style)
-
-    // allowmoreconcurrentstream
-    transport.max_concurrent_bidi_streams(100u32.into());
-    transport.max_concurrent_uni_streams(100u32.into());
+    // idle timeout, flow control windows and stream limits as the profile's browser
+    // announces them (Chrome without a profile)
+    let transport_parameters = config
+        .profile
+        .as_ref()
+        .map(crate::quic_transport::QuicTransportParameters::for_profile)
+        .unwrap_or_else(crate::quic_transport::QuicTransportParameters::chrome);
+    transport_parameters.apply_to(&mut transport).map_err(|e| {
+        HttpClientError::ConnectionFailed(format!("configurationtransportfailure: {}", e))
+    })?;
 
     client_config.transport_config(Arc::new(transport));
 
     // 2. DNS Parse (priority IPv4, avoid IPv4 endpoint connection IPv6 remote cause invalid remote address)
     let addr_str = format!("{}:{}", host, port);
-    let mut addrs: Vec<SocketAddr> = addr_str
-        .to_socket_addrs()
+    let mut addrs: Vec<SocketAddr> = span!("dns.resolve", server.address = host)
+        .in_scope(|| addr_str.to_socket_addrs())
         .map_err(|e| HttpClientError::InvalidUrl(format!("DNS Parsefailure: {}", e)))?
         .collect();
     if addrs.is_empty() {
@@ -108,7 +122,8 @@ async fn send_http3_request_async(
 
         match endpoint.connect(remote_addr, host) {
             Ok(connecting) => {
-                match connecting.await {
+                let handshake = span!("quic.handshake", server.address = host, server.port = port);
+                match connecting.instrument(handshake).await {
                     Ok(conn) => {
                         // 5. establish HTTP/3 connection
                         match h3::client::new(h3_quinn::Connection::new(conn)).await {
@@ -215,27 +230,15 @@ async fn send_http3_request_async(
     let status_code = response.status().as_u16();
     let headers = response.headers().clone();
 
-    // securityFix: Check HTTP/3 responseheadersize, prevent QPACK compressionbombattack
-    // h3 crate 0.0.4 defaultlimitusuallylarger, weAdd额outsideCheck
-    const MAX_HTTP3_HEADER_SIZE: usize = 64 * 1024; // 64KB (RFC 9114 suggestminimumvalue)
-    let total_header_size: usize = headers
-        .iter()
-        .map(|(k, v)| k.as_str().len() + v.len())
-        .sum();
-    if total_header_size > MAX_HTTP3_HEADER_SIZE {
-        return Err(HttpClientError::InvalidResponse(format!(
-            "HTTP/3 responseheadertoo large (>{} bytes)",
-            MAX_HTTP3_HEADER_SIZE
-        )));
-    }
+    // securityFix: limit HTTP/3 response header count/size (header compression bombs)
+    config
+        .limits
+        .check_headers(headers.iter().map(|(k, v)| (k.as_str(), v.as_bytes())))?;
 
     // receive body
     use bytes::Buf;
     let mut body_data = Vec::new();
 
-    // securitylimit：prevent HTTP/3 responsebody too largecauseinsidememory exhausted
-    const MAX_HTTP3_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB
-
     while let Some(mut chunk) = stream.recv_data().await.map_err(|e| {
         HttpClientError::Io(std::io::Error::other(format!("read body failure: {}", e)))
     })? {
@@ -243,12 +246,9 @@ async fn send_http3_request_async(
         let chunk_len = chunk.remaining();
 
         // securityCheck：preventresponsebody too large
-        if body_data.len().saturating_add(chunk_len) > MAX_HTTP3_BODY_SIZE {
-            return Err(HttpClientError::InvalidResponse(format!(
-                "HTTP/3 responsebody too large (>{} bytes)",
-                MAX_HTTP3_BODY_SIZE
-            )));
-        }
+        config
+            .limits
+            .check_response_body(body_data.len().saturating_add(chunk_len))?;
 
         let mut chunk_bytes = vec![0u8; chunk_len];
         chunk.copy_to_slice(&mut chunk_bytes);
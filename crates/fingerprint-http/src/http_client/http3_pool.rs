@@ -9,6 +9,8 @@
 
 #[cfg(all(feature = "connection-pool", feature = "http3"))]
 use super::pool::ConnectionPoolManager;
+#[cfg(all(feature = "connection-pool", feature = "http3"))]
+use super::telemetry::{span, Instrument};
 use super::{HttpClientConfig, HttpClientError, HttpRequest, HttpResponse, Result};
 #[cfg(all(feature = "connection-pool", feature = "http3"))]
 use std::sync::Arc;
@@ -41,8 +43,8 @@ pub async fn send_http3_request_with_pool(
             // Parsetargetaddress
             use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
             let addr = format!("{}:{}", host, port);
-            let mut addrs: Vec<SocketAddr> = addr
-                .to_socket_addrs()
+            let mut addrs: Vec<SocketAddr> = span!("dns.resolve", server.address = host)
+                .in_scope(|| addr.to_socket_addrs())
                 .map_err(|e| HttpClientError::ConnectionFailed(format!("DNS Parsefailure: {}", e)))?
                 .collect();
             if addrs.is_empty() {
@@ -54,10 +56,16 @@ pub async fn send_http3_request_with_pool(
             let remote_addr = addrs[0];
 
             // Create QUIC clientconfiguration
-            let tls_config = super::rustls_utils::build_client_config(
+            let mut tls_config = super::rustls_utils::build_client_config(
                 config.verify_tls,
                 vec![b"h3".to_vec()],
                 config.profile.as_ref(),
+                config.revocation.as_ref(),
+            );
+            super::rustls_utils::apply_session_resumption(
+                &mut tls_config,
+                config.session_cache.as_ref(),
+                config.profile.as_ref(),
             );
 
             let mut client_config = quinn::ClientConfig::new(std::sync::Arc::new(
@@ -69,20 +77,18 @@ pub async fn send_http3_request_with_pool(
             // optimizetransferconfigurationending withimproveperformance
             let mut transport_config = quinn::TransportConfig::default();
             transport_config.initial_rtt(Duration::from_millis(100));
-            transport_config.max_idle_timeout(Some(
-                quinn::IdleTimeout::try_from(std::time::Duration::from_secs(60)).map_err(|e| {
-                    HttpClientError::Http3Error(format!("configurationtimeoutfailure: {}", e))
-                })?,
-            ));
             transport_config.keep_alive_interval(Some(Duration::from_secs(10)));
 
-            // increasereceivewindowending withimprovethroughput
-            transport_config.stream_receive_window((1024 * 1024u32).into()); // 1MB
-            transport_config.receive_window((10 * 1024 * 1024u32).into()); // 10MB
-
-            // allowmoreconcurrentstream
-            transport_config.max_concurrent_bidi_streams(100u32.into());
-            transport_config.max_concurrent_uni_streams(100u32.into());
+            // idle timeout, windows and stream limits of the profile's browser
+            config
+                .profile
+                .as_ref()
+                .map(crate::quic_transport::QuicTransportParameters::for_profile)
+                .unwrap_or_else(crate::quic_transport::QuicTransportParameters::chrome)
+                .apply_to(&mut transport_config)
+                .map_err(|e| {
+                    HttpClientError::Http3Error(format!("configurationtransportfailure: {}", e))
+                })?;
 
             client_config.transport_config(std::sync::Arc::new(transport_config));
 
@@ -101,9 +107,16 @@ pub async fn send_http3_request_with_pool(
                 .connect(remote_addr, host)
                 .map_err(|e| HttpClientError::Http3Error(format!("Connection failed: {}", e)))?;
 
-            let connection = connecting.await.map_err(|e| {
-                HttpClientError::Http3Error(format!("establishConnection failed: {}", e))
-            })?;
+            let connection = connecting
+                .instrument(span!(
+                    "quic.handshake",
+                    server.address = host,
+                    pooled = true
+                ))
+                .await
+                .map_err(|e| {
+                    HttpClientError::Http3Error(format!("establishConnection failed: {}", e))
+                })?;
 
             // establish HTTP/3 connection
             let quinn_conn = h3_quinn::Connection::new(connection);
@@ -195,9 +208,6 @@ pub async fn send_http3_request_with_pool(
     // readresponse体
     let mut body_data = Vec::new();
 
-    // securitylimit：prevent HTTP/3 responsebody too largecauseinsidememory exhausted
-    const MAX_HTTP3_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB
-
     while let Some(mut chunk) = stream.recv_data().await.map_err(|e| {
         HttpClientError::Io(std::io::Error::other(format!("read body failure: {}", e)))
     })? {
@@ -205,12 +215,9 @@ pub async fn send_http3_request_with_pool(
         let chunk_len = chunk.remaining();
 
         // securityCheck：preventresponsebody too large
-        if body_data.len().saturating_add(chunk_len) > MAX_HTTP3_BODY_SIZE {
-            return Err(HttpClientError::InvalidResponse(format!(
-                "HTTP/3 responsebody too large (>{} bytes)",
-                MAX_HTTP3_BODY_SIZE
-            )));
-        }
+        config
+            .limits
+            .check_response_body(body_data.len().saturating_add(chunk_len))?;
 
         let mut chunk_bytes = vec![0u8; chunk_len];
         chunk.copy_to_slice(&mut chunk_bytes);
@@ -220,19 +227,13 @@ pub async fn send_http3_request_with_pool(
     // Parseresponse
     let status_code = response.status().as_u16();
 
-    // securityFix: Check HTTP/3 responseheadersize, prevent QPACK compressionbombattack
-    const MAX_HTTP3_HEADER_SIZE: usize = 64 * 1024; // 64KB (RFC 9114 suggestminimumvalue)
-    let total_header_size: usize = response
-        .headers()
-        .iter()
-        .map(|(k, v)| k.as_str().len() + v.len())
-        .sum();
-    if total_header_size > MAX_HTTP3_HEADER_SIZE {
-        return Err(HttpClientError::InvalidResponse(format!(
-            "HTTP/3 responseheadertoo large (>{} bytes)",
-            MAX_HTTP3_HEADER_SIZE
-        )));
-    }
+    // securityFix: limit HTTP/3 response header count/size (header compression bombs)
+    config.limits.check_headers(
+        response
+            .headers()
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_bytes())),
+    )?;
 
     let status_text = http::StatusCode::from_u16(status_code)
         .ok()
@@ -9,6 +9,7 @@
 //!
 //! same when providemaximumresponsesizeprotect, preventinsidesavebe overwhelmed.
 
+use super::limits::LimitError;
 use std::io;
 use std::io::Read;
 
@@ -66,10 +67,9 @@ pub fn read_http1_response_bytes<R: Read>(reader: &mut R, max_bytes: usize) -> i
         }
 
         if buf.len() >= max_bytes {
-            return Err(io::Error::other(format!(
-                "responsetoo large (>{} bytes)",
-                max_bytes
-            )));
+            return Err(io::Error::other(
+                LimitError::ResponseBodyTooLarge { limit: max_bytes }.record(),
+            ));
         }
 
         let n = reader.read(&mut tmp)?;
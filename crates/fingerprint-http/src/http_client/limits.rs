@@ -0,0 +1,449 @@
+//! Resource limits
+//!
+//! Hard caps protecting the client from oversized or malicious peers:
+//! - request/response body size, buffered or streamed
+//! - response header count and total header size
+//! - decompressed size and compression ratio (zip-bomb protection)
+//! - concurrent in-flight bodies per client
+//!
+//! Every triggered limit returns a typed [`LimitError`] and bumps a process-wide counter
+//! readable through [`limit_metrics`].
+
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Resource limit configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Maximum request body size (bytes)
+    pub max_request_body_bytes: usize,
+    /// Maximum response body size as received on the wire (bytes)
+    pub max_response_body_bytes: usize,
+    /// Maximum number of header fields (request and response)
+    pub max_header_count: usize,
+    /// Maximum total header size, names plus values (bytes)
+    pub max_header_bytes: usize,
+    /// Maximum body size after Content-Encoding decompression (bytes)
+    pub max_decompressed_bytes: usize,
+    /// Maximum decompressed/compressed size ratio
+    pub max_decompression_ratio: usize,
+    /// Maximum concurrent requests with bodies in flight per client (0 = unlimited)
+    pub max_in_flight_bodies: usize,
+    /// Maximum streamed response body, on the wire and after decompression (0 = unlimited)
+    pub max_streamed_body_bytes: u64,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_request_body_bytes: 16 * 1024 * 1024,
+            max_response_body_bytes: super::io::DEFAULT_MAX_RESPONSE_BYTES,
+            max_header_count: 256,
+            max_header_bytes: 64 * 1024,
+            max_decompressed_bytes: 64 * 1024 * 1024,
+            max_decompression_ratio: 100,
+            max_in_flight_bodies: 64,
+            max_streamed_body_bytes: 4 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
+/// Triggered resource limit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitError {
+    /// Request body larger than allowed
+    RequestBodyTooLarge { size: usize, limit: usize },
+    /// Response body larger than allowed
+    ResponseBodyTooLarge { limit: usize },
+    /// Too many header fields
+    TooManyHeaders { count: usize, limit: usize },
+    /// Header section larger than allowed
+    HeadersTooLarge { size: usize, limit: usize },
+    /// Decompressed body larger than allowed
+    DecompressedTooLarge { limit: usize },
+    /// Decompressed body expands beyond the allowed ratio
+    DecompressionRatio { compressed: usize, limit: usize },
+    /// Too many bodies in flight
+    TooManyInFlight { limit: usize },
+}
+
+impl LimitError {
+    /// Metric label of the limit
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LimitError::RequestBodyTooLarge { .. } => "request_body",
+            LimitError::ResponseBodyTooLarge { .. } => "response_body",
+            LimitError::TooManyHeaders { .. } => "header_count",
+            LimitError::HeadersTooLarge { .. } => "header_bytes",
+            LimitError::DecompressedTooLarge { .. } => "decompressed_bytes",
+            LimitError::DecompressionRatio { .. } => "decompression_ratio",
+            LimitError::TooManyInFlight { .. } => "in_flight",
+        }
+    }
+
+    /// Count the violation and return self
+    pub(crate) fn record(self) -> Self {
+        let counter = match self {
+            LimitError::RequestBodyTooLarge { .. } => &METRICS.request_body,
+            LimitError::ResponseBodyTooLarge { .. } => &METRICS.response_body,
+            LimitError::TooManyHeaders { .. } => &METRICS.header_count,
+            LimitError::HeadersTooLarge { .. } => &METRICS.header_bytes,
+            LimitError::DecompressedTooLarge { .. } => &METRICS.decompressed_bytes,
+            LimitError::DecompressionRatio { .. } => &METRICS.decompression_ratio,
+            LimitError::TooManyInFlight { .. } => &METRICS.in_flight,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        log::warn!("resource limit triggered: {}", self);
+        self
+    }
+}
+
+impl std::fmt::Display for LimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitError::RequestBodyTooLarge { size, limit } => {
+                write!(f, "request body of {} bytes exceeds {} bytes", size, limit)
+            }
+            LimitError::ResponseBodyTooLarge { limit } => {
+                write!(f, "response body exceeds {} bytes", limit)
+            }
+            LimitError::TooManyHeaders { count, limit } => {
+                write!(f, "{} header fields exceed limit of {}", count, limit)
+            }
+            LimitError::HeadersTooLarge { size, limit } => {
+                write!(f, "headers of {} bytes exceed {} bytes", size, limit)
+            }
+            LimitError::DecompressedTooLarge { limit } => {
+                write!(f, "decompressed body exceeds {} bytes", limit)
+            }
+            LimitError::DecompressionRatio { compressed, limit } => write!(
+                f,
+                "{} compressed bytes expand beyond ratio {}",
+                compressed, limit
+            ),
+            LimitError::TooManyInFlight { limit } => {
+                write!(f, "more than {} bodies in flight", limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LimitError {}
+
+struct Counters {
+    request_body: AtomicU64,
+    response_body: AtomicU64,
+    header_count: AtomicU64,
+    header_bytes: AtomicU64,
+    decompressed_bytes: AtomicU64,
+    decompression_ratio: AtomicU64,
+    in_flight: AtomicU64,
+}
+
+static METRICS: Counters = Counters {
+    request_body: AtomicU64::new(0),
+    response_body: AtomicU64::new(0),
+    header_count: AtomicU64::new(0),
+    header_bytes: AtomicU64::new(0),
+    decompressed_bytes: AtomicU64::new(0),
+    decompression_ratio: AtomicU64::new(0),
+    in_flight: AtomicU64::new(0),
+};
+
+/// Number of triggered limits by kind since process start
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LimitMetrics {
+    pub request_body: u64,
+    pub response_body: u64,
+    pub header_count: u64,
+    pub header_bytes: u64,
+    pub decompressed_bytes: u64,
+    pub decompression_ratio: u64,
+    pub in_flight: u64,
+}
+
+impl LimitMetrics {
+    /// Total number of triggered limits
+    pub fn total(&self) -> u64 {
+        self.request_body
+            + self.response_body
+            + self.header_count
+            + self.header_bytes
+            + self.decompressed_bytes
+            + self.decompression_ratio
+            + self.in_flight
+    }
+}
+
+/// Snapshot of the limit counters
+pub fn limit_metrics() -> LimitMetrics {
+    LimitMetrics {
+        request_body: METRICS.request_body.load(Ordering::Relaxed),
+        response_body: METRICS.response_body.load(Ordering::Relaxed),
+        header_count: METRICS.header_count.load(Ordering::Relaxed),
+        header_bytes: METRICS.header_bytes.load(Ordering::Relaxed),
+        decompressed_bytes: METRICS.decompressed_bytes.load(Ordering::Relaxed),
+        decompression_ratio: METRICS.decompression_ratio.load(Ordering::Relaxed),
+        in_flight: METRICS.in_flight.load(Ordering::Relaxed),
+    }
+}
+
+impl ResourceLimits {
+    /// Check header count and total size
+    pub fn check_headers<'a, I>(&self, headers: I) -> Result<(), LimitError>
+    where
+        I: IntoIterator<Item = (&'a str, &'a [u8])>,
+    {
+        let (count, size) = headers
+            .into_iter()
+            .fold((0usize, 0usize), |(count, size), (name, value)| {
+                (count + 1, size + name.len() + value.len())
+            });
+        if count > self.max_header_count {
+            return Err(LimitError::TooManyHeaders {
+                count,
+                limit: self.max_header_count,
+            }
+            .record());
+        }
+        if size > self.max_header_bytes {
+            return Err(LimitError::HeadersTooLarge {
+                size,
+                limit: self.max_header_bytes,
+            }
+            .record());
+        }
+        Ok(())
+    }
+
+    /// Check an outgoing request body
+    pub fn check_request_body(&self, size: usize) -> Result<(), LimitError> {
+        if size > self.max_request_body_bytes {
+            return Err(LimitError::RequestBodyTooLarge {
+                size,
+                limit: self.max_request_body_bytes,
+            }
+            .record());
+        }
+        Ok(())
+    }
+
+    /// Check the response body received so far
+    pub fn check_response_body(&self, size: usize) -> Result<(), LimitError> {
+        if size > self.max_response_body_bytes {
+            return Err(LimitError::ResponseBodyTooLarge {
+                limit: self.max_response_body_bytes,
+            }
+            .record());
+        }
+        Ok(())
+    }
+
+    /// Check a streamed body; `size` counts wire or decoded bytes read so far
+    pub fn check_streamed_body(&self, size: u64) -> Result<(), LimitError> {
+        if self.max_streamed_body_bytes != 0 && size > self.max_streamed_body_bytes {
+            return Err(LimitError::ResponseBodyTooLarge {
+                limit: self
+                    .max_streamed_body_bytes
+                    .try_into()
+                    .unwrap_or(usize::MAX),
+            }
+            .record());
+        }
+        Ok(())
+    }
+
+    /// Check the running ratio of a streamed decompression
+    pub fn check_streamed_ratio(
+        &self,
+        compressed: u64,
+        decompressed: u64,
+    ) -> Result<(), LimitError> {
+        let budget = compressed
+            .saturating_mul(self.max_decompression_ratio as u64)
+            .max(DECOMPRESSION_FLOOR as u64);
+        if decompressed > budget {
+            return Err(LimitError::DecompressionRatio {
+                compressed: compressed.try_into().unwrap_or(usize::MAX),
+                limit: self.max_decompression_ratio,
+            }
+            .record());
+        }
+        Ok(())
+    }
+
+    /// Decompressed size allowed for `compressed` input bytes
+    fn decompression_budget(&self, compressed: usize) -> usize {
+        compressed
+            .saturating_mul(self.max_decompression_ratio)
+            .min(self.max_decompressed_bytes)
+    }
+
+    /// Read a decoder to the end, aborting as soon as output exceeds the size or ratio
+    /// limit instead of materialising the whole bomb
+    pub fn read_decompressed<R: Read>(
+        &self,
+        mut decoder: R,
+        compressed: usize,
+    ) -> Result<Vec<u8>, DecompressError> {
+        // tiny inputs legitimately expand more than large ones; allow one buffer's worth
+        let budget = self
+            .decompression_budget(compressed)
+            .max(DECOMPRESSION_FLOOR.min(self.max_decompressed_bytes));
+        let mut output = Vec::new();
+        let mut buf = [0u8; 8192];
+        loop {
+            let n = decoder.read(&mut buf).map_err(DecompressError::Io)?;
+            if n == 0 {
+                return Ok(output);
+            }
+            if output.len() + n > budget {
+                let error = if output.len() + n > self.max_decompressed_bytes {
+                    LimitError::DecompressedTooLarge {
+                        limit: self.max_decompressed_bytes,
+                    }
+                } else {
+                    LimitError::DecompressionRatio {
+                        compressed,
+                        limit: self.max_decompression_ratio,
+                    }
+                };
+                return Err(DecompressError::Limit(error.record()));
+            }
+            output.extend_from_slice(&buf[..n]);
+        }
+    }
+}
+
+/// Output always allowed regardless of ratio (64 KiB)
+const DECOMPRESSION_FLOOR: usize = 64 * 1024;
+
+/// Decompression failure
+#[derive(Debug)]
+pub enum DecompressError {
+    /// Corrupt input
+    Io(io::Error),
+    /// Size or ratio limit triggered
+    Limit(LimitError),
+}
+
+/// Bounds the number of concurrent in-flight bodies
+#[derive(Debug, Clone)]
+pub struct InFlightLimiter {
+    current: Arc<AtomicUsize>,
+    limit: usize,
+}
+
+impl InFlightLimiter {
+    /// Create a limiter (0 = unlimited)
+    pub fn new(limit: usize) -> Self {
+        Self {
+            current: Arc::new(AtomicUsize::new(0)),
+            limit,
+        }
+    }
+
+    /// Reserve a slot, released when the guard drops
+    pub fn try_acquire(&self) -> Result<InFlightGuard, LimitError> {
+        let previous = self.current.fetch_add(1, Ordering::AcqRel);
+        if self.limit != 0 && previous >= self.limit {
+            self.current.fetch_sub(1, Ordering::AcqRel);
+            return Err(LimitError::TooManyInFlight { limit: self.limit }.record());
+        }
+        Ok(InFlightGuard {
+            current: self.current.clone(),
+        })
+    }
+
+    /// Bodies currently in flight
+    pub fn in_flight(&self) -> usize {
+        self.current.load(Ordering::Acquire)
+    }
+}
+
+/// Slot held by an in-flight request
+#[derive(Debug)]
+pub struct InFlightGuard {
+    current: Arc<AtomicUsize>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.current.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_limits() {
+        let limits = ResourceLimits {
+            max_header_count: 2,
+            max_header_bytes: 16,
+            ..Default::default()
+        };
+        let ok: [(&str, &[u8]); 2] = [("a", b"1"), ("b", b"2")];
+        assert!(limits.check_headers(ok).is_ok());
+
+        let many: [(&str, &[u8]); 3] = [("a", b"1"), ("b", b"2"), ("c", b"3")];
+        assert_eq!(
+            limits.check_headers(many),
+            Err(LimitError::TooManyHeaders { count: 3, limit: 2 })
+        );
+
+        let big: [(&str, &[u8]); 1] = [("cookie", &[b'x'; 32])];
+        assert!(matches!(
+            limits.check_headers(big),
+            Err(LimitError::HeadersTooLarge { size: 38, .. })
+        ));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_decompression_bomb_is_cut_off() {
+        use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&vec![0u8; 8 * 1024 * 1024]).unwrap();
+        let bomb = encoder.finish().unwrap();
+        assert!(bomb.len() < 64 * 1024);
+
+        let before = limit_metrics().decompression_ratio;
+        let limits = ResourceLimits::default();
+        let result = limits.read_decompressed(GzDecoder::new(&bomb[..]), bomb.len());
+        assert!(matches!(
+            result,
+            Err(DecompressError::Limit(
+                LimitError::DecompressionRatio { .. }
+            ))
+        ));
+        assert!(limit_metrics().decompression_ratio > before);
+
+        // ordinary text stays well below the ratio
+        let text = b"fingerprint ".repeat(1000);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&text).unwrap();
+        let gz = encoder.finish().unwrap();
+        let decoded = limits
+            .read_decompressed(GzDecoder::new(&gz[..]), gz.len())
+            .unwrap();
+        assert_eq!(decoded, text);
+    }
+
+    #[test]
+    fn test_in_flight_limiter_releases_on_drop() {
+        let limiter = InFlightLimiter::new(1);
+        let guard = limiter.try_acquire().unwrap();
+        assert_eq!(
+            limiter.try_acquire().unwrap_err(),
+            LimitError::TooManyInFlight { limit: 1 }
+        );
+        drop(guard);
+        assert_eq!(limiter.in_flight(), 0);
+        assert!(limiter.try_acquire().is_ok());
+    }
+}
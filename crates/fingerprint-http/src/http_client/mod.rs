@@ -6,12 +6,21 @@
 //! - Use netconnpool for connection management
 //! - Apply fingerprint-rust configurations
 //! - Support HTTP/1.1 and HTTP/2
+//! - Streaming HTTP/1.1 response bodies ([`ResponseStream`])
+//! - Encrypted Client Hello from DNS HTTPS records ([`EchPolicy`])
+//! - TLS session resumption and 0-RTT across requests ([`TlsSessionCache`])
+//! - Tracing spans per request, attempt, DNS lookup, connect and handshake (`otel` feature),
+//!   with optional W3C `traceparent` propagation
 //! - TLS layer designed to be replaceable
+//! - WebTransport sessions over HTTP/3 (`experimental-webtransport` feature)
 
 pub mod cookie;
 pub mod dns_helper;
+pub mod ech;
 #[cfg(all(feature = "connection-pool", feature = "http2"))]
 mod h2_session_pool;
+#[cfg(feature = "experimental-h3-datagrams")]
+pub mod h3_datagram;
 #[cfg(all(feature = "connection-pool", feature = "http3"))]
 mod h3_session_pool;
 pub mod http1;
@@ -23,39 +32,62 @@ pub mod http3;
 #[cfg(all(feature = "connection-pool", feature = "http3"))]
 pub mod http3_pool;
 pub mod io;
+pub mod limits;
 pub mod pool;
 pub mod proxy;
 pub mod reporter;
 pub mod request;
 pub mod response;
+pub mod retry;
+pub mod revocation;
 #[cfg(feature = "rustls-client-hello-customizer")]
 mod rustls_client_hello_customizer;
 #[cfg(any(feature = "rustls-tls", feature = "http2", feature = "http3"))]
 mod rustls_utils;
+pub mod session_cache;
+pub mod stream;
 pub mod tcp_fingerprint;
+mod telemetry;
 pub mod tls;
+#[cfg(feature = "experimental-webtransport")]
+pub mod webtransport;
 
 pub use cookie::{Cookie, CookieStore, SameSite};
 pub use dns_helper::DNSHelper;
+pub use ech::{EchPolicy, EchResolver};
+pub use limits::{limit_metrics, LimitError, LimitMetrics, ResourceLimits};
 pub use pool::{ConnectionPoolManager, PoolManagerConfig, PoolStats};
-pub use proxy::{ProxyConfig, ProxyType};
+pub use proxy::{ProxyChain, ProxyConfig, ProxyType};
 pub use reporter::{ReportFormat, ReportSection, ValidationReport};
 pub use request::{HttpMethod, HttpRequest};
 pub use response::HttpResponse;
+pub use retry::{ErrorClass, RetryBudget, RetryPolicy};
+pub use revocation::{
+    OcspCertStatus, OcspResponder, RevocationChecker, RevocationFailure, RevocationPolicy,
+    StapleObservation, StapleState, StapledOcsp,
+};
+pub use session_cache::{SessionCacheStats, TlsSessionCache};
+pub use stream::ResponseStream;
 pub use tls::TlsConnector;
+#[cfg(feature = "experimental-webtransport")]
+pub use webtransport::WebTransportSession;
 
+use fingerprint_core::trace_context::{TraceContext, TRACEPARENT_HEADER};
 use fingerprint_headers::headers::HTTPHeaders;
 use fingerprint_profiles::BrowserProfile;
 use std::io as std_io;
 use std::time::Duration;
+use telemetry::span;
 
-// Use a shared Tokio runtime for sync wrappers around HTTP/2 and HTTP/3 code paths.
+// Use a shared Tokio runtime for sync wrappers around HTTP/2 and HTTP/3 code paths,
+// sized by the installed `fingerprint_core::runtime::RuntimeConfig`.
 #[cfg(any(feature = "http2", feature = "http3"))]
 use once_cell::sync::Lazy;
 
 #[cfg(any(feature = "http2", feature = "http3"))]
 static SHARED_RUNTIME: Lazy<Result<tokio::runtime::Runtime>> = Lazy::new(|| {
-    tokio::runtime::Runtime::new()
+    fingerprint_core::runtime::RuntimeConfig::installed()
+        .build_runtime()
         .or_else(|err| {
             eprintln!(
                 "warning: failed to create multi-thread Tokio runtime: {}. Falling back to current-thread runtime.",
@@ -78,6 +110,20 @@ fn get_shared_runtime() -> Result<&'static tokio::runtime::Runtime> {
     })
 }
 
+/// Statistics of the shared HTTP/2 and HTTP/3 runtime, once it has been started
+pub fn runtime_stats() -> Option<fingerprint_core::runtime::RuntimeStats> {
+    #[cfg(any(feature = "http2", feature = "http3"))]
+    {
+        Lazy::get(&SHARED_RUNTIME)
+            .and_then(|runtime| runtime.as_ref().ok())
+            .map(|runtime| fingerprint_core::runtime::RuntimeStats::of(runtime.handle()))
+    }
+    #[cfg(not(any(feature = "http2", feature = "http3")))]
+    {
+        None
+    }
+}
+
 /// HTTP client error
 #[derive(Debug)]
 pub enum HttpClientError {
@@ -92,6 +138,9 @@ pub enum HttpClientError {
     #[cfg(feature = "http3")]
     Http3Error(String),
     InvalidRequest(String),
+    LimitExceeded(LimitError),
+    #[cfg(feature = "experimental-webtransport")]
+    WebTransportError(String),
 }
 
 impl std::fmt::Display for HttpClientError {
@@ -108,6 +157,9 @@ impl std::fmt::Display for HttpClientError {
             #[cfg(feature = "http3")]
             HttpClientError::Http3Error(s) => write!(f, "HTTP/3 error: {}", s),
             HttpClientError::InvalidRequest(s) => write!(f, "Invalid request: {}", s),
+            HttpClientError::LimitExceeded(e) => write!(f, "Resource limit exceeded: {}", e),
+            #[cfg(feature = "experimental-webtransport")]
+            HttpClientError::WebTransportError(s) => write!(f, "WebTransport error: {}", s),
         }
     }
 }
@@ -116,10 +168,23 @@ impl std::error::Error for HttpClientError {}
 
 impl From<std_io::Error> for HttpClientError {
     fn from(err: std_io::Error) -> Self {
+        // limits hit inside IO helpers travel as io::Error
+        if let Some(limit) = err
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<LimitError>())
+        {
+            return HttpClientError::LimitExceeded(limit.clone());
+        }
         HttpClientError::Io(err)
     }
 }
 
+impl From<LimitError> for HttpClientError {
+    fn from(err: LimitError) -> Self {
+        HttpClientError::LimitExceeded(err)
+    }
+}
+
 pub type Result<T> = std::result::Result<T, HttpClientError>;
 
 /// HTTP client configuration
@@ -149,6 +214,22 @@ pub struct HttpClientConfig {
     pub cookie_store: Option<Arc<CookieStore>>,
     /// DNS helper (optional, for DNS cache and pre-parse)
     pub dns_helper: Option<Arc<DNSHelper>>,
+    /// Body, header and decompression limits
+    pub limits: ResourceLimits,
+    /// Revocation checking and staple recording (optional, needs verify_tls)
+    pub revocation: Option<Arc<RevocationChecker>>,
+    /// Proxy chain (optional; bypasses the connection pool and HTTP/3)
+    pub proxy: Option<ProxyChain>,
+    /// Retry and backoff policy (optional; applied to every redirect hop)
+    pub retry: Option<RetryPolicy>,
+    /// Encrypted Client Hello policy (optional; `None` follows the profile's ClientHello)
+    pub ech: Option<EchPolicy>,
+    /// TLS session tickets shared across requests (optional; clients whose profile
+    /// offers pre_shared_key get one automatically)
+    pub session_cache: Option<Arc<TlsSessionCache>>,
+    /// Send a W3C `traceparent` header with every attempt (off by default: browsers do
+    /// not send it, so it changes the request's header fingerprint)
+    pub propagate_trace_context: bool,
 }
 
 impl Default for HttpClientConfig {
@@ -166,6 +247,13 @@ impl Default for HttpClientConfig {
             prefer_http3: false, // HTTP/3 default close (need special configuration)
             cookie_store: None,
             dns_helper: None, // DNS helper default close (optional functionality)
+            limits: ResourceLimits::default(),
+            revocation: None, // rustls default: staples are requested but ignored
+            proxy: None,
+            retry: None,
+            ech: None,
+            session_cache: None,
+            propagate_trace_context: false,
         }
     }
 }
@@ -178,6 +266,8 @@ pub struct HttpClient {
     /// Connection pool manager (optional)
     #[allow(clippy::arc_with_non_send_sync)]
     pool_manager: Option<Arc<ConnectionPoolManager>>,
+    /// Concurrent in-flight bodies
+    in_flight: limits::InFlightLimiter,
 }
 
 use std::sync::Arc;
@@ -185,7 +275,9 @@ use std::sync::Arc;
 impl HttpClient {
     /// Create a new HTTP client
     pub fn new(config: HttpClientConfig) -> Self {
+        let config = Self::with_resumption(config);
         Self {
+            in_flight: limits::InFlightLimiter::new(config.limits.max_in_flight_bodies),
             config,
             pool_manager: None,
         }
@@ -194,7 +286,9 @@ impl HttpClient {
     /// Create bring connection pool HTTP client
     #[allow(clippy::arc_with_non_send_sync)]
     pub fn with_pool(config: HttpClientConfig, pool_config: PoolManagerConfig) -> Self {
+        let config = Self::with_resumption(config);
         Self {
+            in_flight: limits::InFlightLimiter::new(config.limits.max_in_flight_bodies),
             config,
             pool_manager: Some(Arc::new(ConnectionPoolManager::new(pool_config))),
         }
@@ -211,6 +305,23 @@ impl HttpClient {
         Self::new(config)
     }
 
+    /// Give PSK profiles a session cache, so their pre_shared_key resumes for real
+    fn with_resumption(mut config: HttpClientConfig) -> HttpClientConfig {
+        let offers_psk = config
+            .profile
+            .as_ref()
+            .is_some_and(|p| p.tls_config.offers_psk());
+        if offers_psk && config.session_cache.is_none() {
+            config.session_cache = Some(Arc::new(TlsSessionCache::default()));
+        }
+        config
+    }
+
+    /// Session tickets this client resumes with, if any
+    pub fn session_cache(&self) -> Option<&Arc<TlsSessionCache>> {
+        self.config.session_cache.as_ref()
+    }
+
     /// Get connection pool statistics info
     pub fn pool_stats(&self) -> Option<Vec<PoolStats>> {
         self.pool_manager.as_ref().map(|pm| pm.get_stats())
@@ -243,8 +354,115 @@ impl HttpClient {
     /// Send custom request (support redirect)
     pub fn send_request(&self, request: &HttpRequest) -> Result<HttpResponse> {
         use std::time::Instant;
+        let limits = &self.config.limits;
+        limits.check_headers(
+            request
+                .headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_bytes())),
+        )?;
+        limits.check_request_body(request.body.as_ref().map_or(0, Vec::len))?;
+        // held across redirects: the response body is buffered until we return
+        let _in_flight = self.in_flight.try_acquire()?;
+
+        // one trace across attempts and redirects
+        let traced;
+        let request = if self.config.propagate_trace_context && request.trace_context.is_none() {
+            traced = request.clone().with_trace_context(TraceContext::new_root());
+            &traced
+        } else {
+            request
+        };
+
+        let span = span!(
+            "http.request",
+            http.request.method = request.method.as_str(),
+            url.full = telemetry::url_without_query(&request.url),
+            http.response.status_code = tracing::field::Empty,
+        )
+        .entered();
         let request_start = Instant::now();
-        self.send_request_with_redirects(request, 0, request_start)
+        let response = self.send_request_with_redirects(request, 0, request_start)?;
+        span.record("http.response.status_code", response.status_code);
+        Ok(response)
+    }
+
+    /// Copy of `request` carrying a `traceparent` for a new child span, when propagating
+    fn with_traceparent(
+        &self,
+        request: &HttpRequest,
+        span: &telemetry::Span,
+    ) -> Option<HttpRequest> {
+        if !self.config.propagate_trace_context {
+            return None;
+        }
+        let context = request
+            .trace_context
+            .unwrap_or_else(TraceContext::new_root)
+            .child();
+        let trace_id = context.trace_id_hex();
+        let span_id = context.span_id_hex();
+        span.record("trace_id", trace_id.as_str());
+        span.record("span_id", span_id.as_str());
+        Some(
+            request
+                .clone()
+                .with_header(TRACEPARENT_HEADER, &context.traceparent()),
+        )
+    }
+
+    /// Send GET request, returning before the body is read
+    pub fn get_streaming(&self, url: &str) -> Result<ResponseStream> {
+        let request = HttpRequest::new(HttpMethod::Get, url)
+            .with_user_agent(&self.config.user_agent)
+            .with_headers(&self.config.headers);
+        self.send_request_streaming(&request)
+    }
+
+    /// Send custom request, returning once the response headers arrived
+    ///
+    /// The body is decoded while it is read from the returned stream. Streaming always
+    /// speaks HTTP/1.1 on a dedicated connection (through the proxy chain if configured):
+    /// the connection pool, HTTP/2 and HTTP/3 preferences, redirects and retries do not apply.
+    pub fn send_request_streaming(&self, request: &HttpRequest) -> Result<ResponseStream> {
+        let limits = &self.config.limits;
+        limits.check_headers(
+            request
+                .headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_bytes())),
+        )?;
+        limits.check_request_body(request.body.as_ref().map_or(0, Vec::len))?;
+        // held by the stream until its body is dropped
+        let in_flight = self.in_flight.try_acquire()?;
+
+        let (scheme, host, port, path) = self.parse_url(&request.url)?;
+        let stream = match scheme.as_str() {
+            "http" => {
+                let connection =
+                    http1::open_http1_request(&host, port, &path, request, &self.config)?;
+                ResponseStream::from_http1(connection, request, limits)?
+            }
+            #[cfg(feature = "rustls-tls")]
+            "https" => {
+                let connection =
+                    tls::open_https_request(&host, port, &path, request, &self.config)?;
+                ResponseStream::from_http1(connection, request, limits)?
+            }
+            #[cfg(not(feature = "rustls-tls"))]
+            "https" => {
+                return Err(HttpClientError::TlsError(
+                    "needenabled rustls-tls Features".to_string(),
+                ))
+            }
+            _ => {
+                return Err(HttpClientError::InvalidUrl(format!(
+                    "Not support protocol: {}",
+                    scheme
+                )))
+            }
+        };
+        Ok(stream.with_in_flight(in_flight))
     }
 
     /// Send request and process redirect
@@ -296,15 +514,37 @@ impl HttpClient {
         let (scheme, host, port, path) = self.parse_url(&request.url)?;
 
         // Based on protocol select process method
-        let response = match scheme.as_str() {
-            "http" => self.send_http_request(&host, port, &path, request)?,
-            "https" => self.send_https_request(&host, port, &path, request)?,
-            _ => {
-                return Err(HttpClientError::InvalidUrl(format!(
+        let mut attempt = 0u32;
+        let mut send = || {
+            let span = span!(
+                "http.attempt",
+                url.full = telemetry::url_without_query(&request.url),
+                http.request.resend_count = attempt,
+                http.redirect_count = redirect_count,
+                http.response.status_code = tracing::field::Empty,
+                trace_id = tracing::field::Empty,
+                span_id = tracing::field::Empty,
+            )
+            .entered();
+            attempt += 1;
+            let traced = self.with_traceparent(request, &span);
+            let request = traced.as_ref().unwrap_or(request);
+            let response = match scheme.as_str() {
+                "http" => self.send_http_request(&host, port, &path, request),
+                "https" => self.send_https_request(&host, port, &path, request),
+                _ => Err(HttpClientError::InvalidUrl(format!(
                     "Not support protocol: {}",
                     scheme
-                )));
+                ))),
+            };
+            if let Ok(response) = &response {
+                span.record("http.response.status_code", response.status_code);
             }
+            response
+        };
+        let response = match &self.config.retry {
+            Some(policy) => policy.run(request, request_start + Duration::from_secs(300), send)?,
+            None => send()?,
         };
 
         // Process redirect
@@ -396,7 +636,9 @@ impl HttpClient {
                     if let Some(body) = &request.body {
                         final_redirect_request = final_redirect_request.with_body(body.clone());
                     }
+                    final_redirect_request.idempotent = request.idempotent;
                 }
+                final_redirect_request.trace_context = request.trace_context;
 
                 // Recursive process redirect (pass visited_urls end with detect loop)
                 return self.send_request_with_redirects_internal(
@@ -490,9 +732,14 @@ impl HttpClient {
         request: &HttpRequest,
     ) -> Result<HttpResponse> {
         // If has connection pool, use connection pool
+        // Pooled connections are dialled directly, so proxied requests skip the pool
         #[cfg(feature = "connection-pool")]
         {
-            if let Some(pool_manager) = &self.pool_manager {
+            if let Some(pool_manager) = self
+                .pool_manager
+                .as_ref()
+                .filter(|_| self.config.proxy.is_none())
+            {
                 return http1_pool::send_http1_request_with_pool(
                     host,
                     port,
@@ -517,7 +764,11 @@ impl HttpClient {
     ) -> Result<HttpResponse> {
         // If has connection pool, priority use connection pool (HTTPS: HTTP/3 > HTTP/2 > HTTP/1.1)
         #[cfg(feature = "connection-pool")]
-        if let Some(pool_manager) = &self.pool_manager {
+        if let Some(pool_manager) = self
+            .pool_manager
+            .as_ref()
+            .filter(|_| self.config.proxy.is_none())
+        {
             // HTTP/3 with pool (async -> sync wrap)
             #[cfg(feature = "http3")]
             if self.config.prefer_http3 {
@@ -568,10 +819,10 @@ impl HttpClient {
 
         // Priority: HTTP/3 > HTTP/2 > HTTP/1.1
 
-        // Try HTTP/3
+        // Try HTTP/3 (QUIC cannot cross TCP proxies; browsers skip it too)
         #[cfg(feature = "http3")]
         {
-            if self.config.prefer_http3 {
+            if self.config.prefer_http3 && self.config.proxy.is_none() {
                 // Try HTTP/3 first. On protocol failure, gracefully downgrade to HTTP/2 or HTTP/1.1.
                 match http3::send_http3_request(host, port, path, request, &self.config) {
                     Ok(resp) => return Ok(resp),
@@ -621,4 +872,94 @@ mod tests {
         assert_eq!(port, 8080);
         assert_eq!(path, "/api");
     }
+
+    #[test]
+    fn test_streaming_request_reads_body_incrementally() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n")
+                .unwrap();
+            for _ in 0..64 {
+                socket.write_all(b"400\r\n").unwrap();
+                socket.write_all(&[b'x'; 1024]).unwrap();
+                socket.write_all(b"\r\n").unwrap();
+            }
+            socket.write_all(b"0\r\n\r\n").unwrap();
+        });
+
+        let config = HttpClientConfig {
+            limits: ResourceLimits {
+                max_in_flight_bodies: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let client = HttpClient::new(config);
+        let mut stream = client
+            .get_streaming(&format!("http://{}/large", addr))
+            .unwrap();
+        assert!(stream.is_success());
+        // the open stream holds the only in-flight slot
+        assert!(matches!(
+            client.get_streaming(&format!("http://{}/", addr)),
+            Err(HttpClientError::LimitExceeded(_))
+        ));
+
+        let mut largest_chunk = 0;
+        let total = stream
+            .for_each_chunk(|chunk| {
+                largest_chunk = largest_chunk.max(chunk.len());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(total, 64 * 1024);
+        assert!(largest_chunk <= 16 * 1024);
+        drop(stream);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_traceparent_propagation() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut request = [0u8; 2048];
+            let n = socket.read(&mut request).unwrap();
+            socket
+                .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            String::from_utf8_lossy(&request[..n]).to_lowercase()
+        });
+
+        let parent =
+            TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        let client = HttpClient::new(HttpClientConfig {
+            propagate_trace_context: true,
+            ..Default::default()
+        });
+        let request = HttpRequest::new(HttpMethod::Get, &format!("http://{}/", addr))
+            .with_trace_context(parent);
+        assert_eq!(client.send_request(&request).unwrap().status_code, 204);
+
+        let sent = server.join().unwrap();
+        let value = sent
+            .lines()
+            .find_map(|line| line.strip_prefix("traceparent: "))
+            .expect("traceparent header");
+        let context = TraceContext::parse(value).unwrap();
+        assert_eq!(context.trace_id, parent.trace_id);
+        assert_ne!(context.span_id, parent.span_id);
+    }
 }
@@ -2,6 +2,8 @@
 //!
 //! based on netconnpool implementconnectionreuse and lifecyclemanage
 
+#[cfg(feature = "connection-pool")]
+use super::telemetry::span;
 use super::{HttpClientError, Result};
 use std::time::Duration;
 
@@ -181,10 +183,13 @@ impl ConnectionPoolManager {
             dialer: Some(Box::new(move |_protocol| {
                 use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
 
-                let addrs: Vec<SocketAddr> = (host.as_str(), port)
-                    .to_socket_addrs()
-                    .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?
-                    .collect();
+                let addrs: Vec<SocketAddr> = {
+                    let _span = span!("dns.resolve", server.address = host.as_str()).entered();
+                    (host.as_str(), port)
+                        .to_socket_addrs()
+                        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?
+                        .collect()
+                };
 
                 // priorityuse IPv4, avoid in "none IPv6 route"environment in appear `Network is unreachable`.
                 let mut v4 = Vec::new();
@@ -196,6 +201,12 @@ impl ConnectionPoolManager {
                     }
                 }
 
+                let _span = span!(
+                    "tcp.connect",
+                    server.address = host.as_str(),
+                    server.port = port
+                )
+                .entered();
                 let mut last_err: Option<std::io::Error> = None;
                 for addr in v4.into_iter().chain(v6.into_iter()) {
                     // Note: Currently using standard connect method
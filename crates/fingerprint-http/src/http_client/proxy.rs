@@ -2,9 +2,10 @@
 //!
 //! support HTTP and SOCKS5 proxy
 
+use super::telemetry::span;
 use super::{HttpClientError, Result};
 use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
 
 /// proxytype
 #[derive(Debug, Clone)]
@@ -63,67 +64,199 @@ impl ProxyConfig {
     }
 }
 
+/// Chain of proxies traversed in order
+///
+/// The first hop is dialled directly; each later hop is reached through the
+/// tunnel of the one before it, and the last hop connects to the target.
+/// SOCKS5 and HTTP CONNECT hops can be mixed freely, each with its own
+/// credentials.
+#[derive(Debug, Clone)]
+pub struct ProxyChain {
+    /// Hops, first to last
+    pub hops: Vec<ProxyConfig>,
+    /// Let the proxies resolve host names (socks5h semantics); when false,
+    /// names are resolved locally and hops receive IP addresses
+    pub remote_dns: bool,
+}
+
+impl ProxyChain {
+    /// Create a chain from hops, with remote DNS resolution
+    pub fn new(hops: Vec<ProxyConfig>) -> Self {
+        Self {
+            hops,
+            remote_dns: true,
+        }
+    }
+
+    /// Create a single-hop chain
+    pub fn single(proxy: ProxyConfig) -> Self {
+        Self::new(vec![proxy])
+    }
+
+    /// Append a hop after the current last one
+    pub fn then(mut self, proxy: ProxyConfig) -> Self {
+        self.hops.push(proxy);
+        self
+    }
+
+    /// Set whether host names are resolved by the proxies
+    pub fn with_remote_dns(mut self, remote_dns: bool) -> Self {
+        self.remote_dns = remote_dns;
+        self
+    }
+
+    /// Open a tunnel through every hop to `target_host:target_port`
+    pub fn connect(&self, target_host: &str, target_port: u16) -> Result<TcpStream> {
+        let Some(first) = self.hops.first() else {
+            return Err(HttpClientError::ConnectionFailed(
+                "proxy chain has no hops".to_string(),
+            ));
+        };
+
+        let proxy_addr = format!("{}:{}", first.host, first.port);
+        let mut stream = TcpStream::connect(&proxy_addr).map_err(|e| {
+            HttpClientError::ConnectionFailed(format!("connectionproxyfailure: {}", e))
+        })?;
+
+        for (index, hop) in self.hops.iter().enumerate() {
+            let (host, port) = match self.hops.get(index + 1) {
+                Some(next) => (next.host.as_str(), next.port),
+                None => (target_host, target_port),
+            };
+            let host = self.address_for_hop(host, port)?;
+            match hop.proxy_type {
+                ProxyType::Http | ProxyType::Https => {
+                    http_connect_handshake(&mut stream, hop, &host, port)?
+                }
+                ProxyType::Socks5 => socks5_handshake(&mut stream, hop, &host, port)?,
+            }
+        }
+
+        Ok(stream)
+    }
+
+    /// Host to ask a hop for: the name itself, or a locally resolved IP
+    fn address_for_hop(&self, host: &str, port: u16) -> Result<String> {
+        if self.remote_dns || host.parse::<IpAddr>().is_ok() {
+            return Ok(host.to_string());
+        }
+        (host, port)
+            .to_socket_addrs()
+            .map_err(|e| HttpClientError::ConnectionFailed(format!("DNS Parsefailure: {}", e)))?
+            .next()
+            .map(|addr| addr.ip().to_string())
+            .ok_or_else(|| {
+                HttpClientError::ConnectionFailed(format!("unable to Parse address: {}", host))
+            })
+    }
+}
+
+impl From<ProxyConfig> for ProxyChain {
+    fn from(proxy: ProxyConfig) -> Self {
+        Self::single(proxy)
+    }
+}
+
 /// throughproxyconnection
 pub fn connect_through_proxy(
     proxy: &ProxyConfig,
     target_host: &str,
     target_port: u16,
 ) -> Result<TcpStream> {
-    match proxy.proxy_type {
-        ProxyType::Http | ProxyType::Https => connect_http_proxy(proxy, target_host, target_port),
-        ProxyType::Socks5 => connect_socks5_proxy(proxy, target_host, target_port),
+    ProxyChain::single(proxy.clone()).connect(target_host, target_port)
+}
+
+/// Connect to `host:port`, through `config.proxy` when one is configured
+pub(crate) fn connect_tcp(
+    config: &super::HttpClientConfig,
+    host: &str,
+    port: u16,
+) -> Result<TcpStream> {
+    if let Some(chain) = &config.proxy {
+        let _span = span!(
+            "tcp.connect",
+            server.address = host,
+            server.port = port,
+            proxy = true
+        )
+        .entered();
+        return chain.connect(host, port);
     }
+    let addr = format!("{}:{}", host, port);
+    let failed = |e: std::io::Error| {
+        HttpClientError::ConnectionFailed(format!("Connection failed {}: {}", addr, e))
+    };
+    let addrs: Vec<_> = {
+        let _span = span!("dns.resolve", server.address = host).entered();
+        addr.to_socket_addrs().map_err(failed)?.collect()
+    };
+    let _span = span!("tcp.connect", server.address = host, server.port = port).entered();
+    TcpStream::connect(&addrs[..]).map_err(failed)
 }
 
-/// through HTTP proxyconnection
-fn connect_http_proxy(
+/// HTTP CONNECT on an open stream
+fn http_connect_handshake<S: Read + Write>(
+    stream: &mut S,
     proxy: &ProxyConfig,
     target_host: &str,
     target_port: u16,
-) -> Result<TcpStream> {
-    // connection to proxyserver
-    let proxy_addr = format!("{}:{}", proxy.host, proxy.port);
-    let mut stream = TcpStream::connect(&proxy_addr)
-        .map_err(|e| HttpClientError::ConnectionFailed(format!("connectionproxyfailure: {}", e)))?;
+) -> Result<()> {
+    // IPv6 literals need brackets in the authority
+    let authority = if target_host.contains(':') {
+        format!("[{}]:{}", target_host, target_port)
+    } else {
+        format!("{}:{}", target_host, target_port)
+    };
 
     // send CONNECT request
-    let connect_request = format!(
-        "CONNECT {}:{} HTTP/1.1\r\nHost: {}:{}\r\n\r\n",
-        target_host, target_port, target_host, target_port
-    );
+    let mut connect_request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+    if let Some(username) = &proxy.username {
+        let credentials = format!("{}:{}", username, proxy.password.as_deref().unwrap_or(""));
+        connect_request.push_str(&format!(
+            "Proxy-Authorization: Basic {}\r\n",
+            base64_encode(credentials.as_bytes())
+        ));
+    }
+    connect_request.push_str("\r\n");
 
     stream
         .write_all(connect_request.as_bytes())
         .map_err(HttpClientError::Io)?;
 
-    // readresponse
-    let mut buffer = vec![0u8; 1024];
-    let n = stream.read(&mut buffer).map_err(HttpClientError::Io)?;
+    // read the response head byte by byte so no tunnelled data is consumed
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= 8192 {
+            return Err(HttpClientError::ConnectionFailed(
+                "proxy response head too large".to_string(),
+            ));
+        }
+        stream.read_exact(&mut byte).map_err(HttpClientError::Io)?;
+        head.push(byte[0]);
+    }
 
-    let response = String::from_utf8_lossy(&buffer[..n]);
+    let response = String::from_utf8_lossy(&head);
+    let status_line = response.lines().next().unwrap_or("not知error");
 
     // Checkresponsewhethersuccess
-    if !response.contains("200") {
+    if status_line.split_whitespace().nth(1) != Some("200") {
         return Err(HttpClientError::ConnectionFailed(format!(
             "proxyConnection failed: {}",
-            response.lines().next().unwrap_or("not知error")
+            status_line
         )));
     }
 
-    Ok(stream)
+    Ok(())
 }
 
-/// through SOCKS5 proxyconnection
-fn connect_socks5_proxy(
+/// SOCKS5 CONNECT on an open stream
+fn socks5_handshake<S: Read + Write>(
+    stream: &mut S,
     proxy: &ProxyConfig,
     target_host: &str,
     target_port: u16,
-) -> Result<TcpStream> {
-    // connection to proxyserver
-    let proxy_addr = format!("{}:{}", proxy.host, proxy.port);
-    let mut stream = TcpStream::connect(&proxy_addr)
-        .map_err(|e| HttpClientError::ConnectionFailed(format!("connectionproxyfailure: {}", e)))?;
-
+) -> Result<()> {
     // SOCKS5 handshake
     // 1. sendauthenticationmethod
     let auth_methods = if proxy.username.is_some() {
@@ -188,12 +321,8 @@ fn connect_socks5_proxy(
         0x05, // version
         0x01, // CONNECT command
         0x00, // preserve
-        0x03, // domaintype
     ];
-    connect_request.push(target_host.len() as u8);
-    connect_request.extend_from_slice(target_host.as_bytes());
-    connect_request.push((target_port >> 8) as u8);
-    connect_request.push((target_port & 0xff) as u8);
+    connect_request.extend(socks5_address(target_host, target_port)?);
 
     stream
         .write_all(&connect_request)
@@ -239,7 +368,43 @@ fn connect_socks5_proxy(
         }
     }
 
-    Ok(stream)
+    Ok(())
+}
+
+/// SOCKS5 DST.ADDR and DST.PORT: IP literals as addresses, names as domains
+fn socks5_address(host: &str, port: u16) -> Result<Vec<u8>> {
+    let mut address = match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => [&[0x01][..], &ip.octets()].concat(),
+        Ok(IpAddr::V6(ip)) => [&[0x04][..], &ip.octets()].concat(),
+        Err(_) => {
+            let len = u8::try_from(host.len()).map_err(|_| {
+                HttpClientError::ConnectionFailed(format!("SOCKS5 domain too long: {}", host))
+            })?;
+            [&[0x03, len][..], host.as_bytes()].concat()
+        }
+    };
+    address.extend_from_slice(&port.to_be_bytes());
+    Ok(address)
+}
+
+/// Standard base64 with padding (Proxy-Authorization: Basic)
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
 }
 
 #[cfg(test)]
@@ -260,4 +425,88 @@ mod tests {
         assert_eq!(proxy.username, Some("user".to_string()));
         assert_eq!(proxy.password, Some("pass".to_string()));
     }
+
+    #[test]
+    fn test_socks5_address_and_base64() {
+        assert_eq!(
+            socks5_address("10.0.0.1", 443).unwrap(),
+            [0x01, 10, 0, 0, 1, 0x01, 0xbb]
+        );
+        assert_eq!(
+            socks5_address("a.test", 80).unwrap(),
+            [&[0x03, 6][..], b"a.test", &[0, 80]].concat()
+        );
+        assert_eq!(socks5_address("::1", 1).unwrap()[0], 0x04);
+        assert_eq!(base64_encode(b"user:pass"), "dXNlcjpwYXNz");
+        assert_eq!(base64_encode(b"ab"), "YWI=");
+    }
+
+    #[test]
+    fn test_socks5_then_http_connect_chain() {
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // one socket plays both hops: the SOCKS5 proxy, then (through its
+        // tunnel) the HTTP proxy, then the target
+        let server = std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut greeting = [0u8; 4];
+            socket.read_exact(&mut greeting).unwrap();
+            assert_eq!(greeting, [0x05, 0x02, 0x00, 0x02]);
+            socket.write_all(&[0x05, 0x02]).unwrap();
+
+            let mut auth = [0u8; 11];
+            socket.read_exact(&mut auth).unwrap();
+            assert_eq!(&auth, b"\x01\x03bob\x05s3cr3");
+            socket.write_all(&[0x01, 0x00]).unwrap();
+
+            let expected = [
+                &[0x05, 0x01, 0x00, 0x03, 10][..],
+                b"http.proxy",
+                &[0x0c, 0x38],
+            ]
+            .concat();
+            let mut request = vec![0u8; expected.len()];
+            socket.read_exact(&mut request).unwrap();
+            assert_eq!(request, expected);
+            socket
+                .write_all(&[0x05, 0x00, 0x00, 0x01, 127, 0, 0, 1, 0, 0])
+                .unwrap();
+
+            let mut head = Vec::new();
+            let mut byte = [0u8; 1];
+            while !head.ends_with(b"\r\n\r\n") {
+                socket.read_exact(&mut byte).unwrap();
+                head.push(byte[0]);
+            }
+            let head = String::from_utf8(head).unwrap();
+            assert!(head.starts_with("CONNECT example.com:443 HTTP/1.1\r\n"));
+            assert!(head.contains("Proxy-Authorization: Basic YWxpY2U6\r\n"));
+            socket
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\nhello")
+                .unwrap();
+        });
+
+        let chain = ProxyChain::single(
+            ProxyConfig::socks5("127.0.0.1".to_string(), port)
+                .with_auth("bob".to_string(), "s3cr3".to_string()),
+        )
+        .then(
+            ProxyConfig::http("http.proxy".to_string(), 3128)
+                .with_auth("alice".to_string(), String::new()),
+        );
+        let mut stream = chain.connect("example.com", 443).unwrap();
+
+        // data after the CONNECT response belongs to the tunnel
+        let mut tunnelled = [0u8; 5];
+        stream.read_exact(&mut tunnelled).unwrap();
+        assert_eq!(&tunnelled, b"hello");
+        server.join().unwrap();
+
+        assert!(ProxyChain::new(Vec::new())
+            .connect("example.com", 443)
+            .is_err());
+    }
 }
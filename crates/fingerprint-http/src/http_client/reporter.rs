@@ -2,8 +2,11 @@
 //!
 //! for GeneratedetailedValidate and testreport
 
+use fingerprint_core::data_dirs::DataDirs;
+use fingerprint_core::i18n::tr;
 use std::fs::File;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 
 /// Validatereport
 #[derive(Debug, Clone)]
@@ -81,21 +84,32 @@ impl ValidationReport {
         // title
         md.push_str(&format!("# {}\n\n", self.title));
         md.push_str(&format!(
-            "**Generate when between**: {}\n\n",
+            "**{}**: {}\n\n",
+            tr("report.generated_at", &[]),
             self.generated_at
         ));
         md.push_str("---\n\n");
 
         // digest
-        md.push_str("## 📊 testdigest\n\n");
+        md.push_str(&format!("## 📊 {}\n\n", tr("report.summary", &[])));
         md.push_str(&format!(
-            "- **总testcount**: {}\n",
+            "- **{}**: {}\n",
+            tr("report.total_tests", &[]),
             self.summary.total_tests
         ));
-        md.push_str(&format!("- **through**: {} ✅\n", self.summary.passed));
-        md.push_str(&format!("- **failure**: {} ❌\n", self.summary.failed));
         md.push_str(&format!(
-            "- **success率**: {:.2}%\n\n",
+            "- **{}**: {} ✅\n",
+            tr("report.passed", &[]),
+            self.summary.passed
+        ));
+        md.push_str(&format!(
+            "- **{}**: {} ❌\n",
+            tr("report.failed", &[]),
+            self.summary.failed
+        ));
+        md.push_str(&format!(
+            "- **{}**: {:.2}%\n\n",
+            tr("report.success_rate", &[]),
             self.summary.success_rate
         ));
         md.push_str("---\n\n");
@@ -114,17 +128,34 @@ impl ValidationReport {
 
         // title
         text.push_str(&format!("# {}\n\n", self.title));
-        text.push_str(&format!("Generate when between: {}\n", self.generated_at));
+        text.push_str(&format!(
+            "{}: {}\n",
+            tr("report.generated_at", &[]),
+            self.generated_at
+        ));
         text.push_str(&"=".repeat(70));
         text.push_str("\n\n");
 
         // digest
-        text.push_str("testdigest:\n");
-        text.push_str(&format!(" 总testcount: {}\n", self.summary.total_tests));
-        text.push_str(&format!(" through: {}\n", self.summary.passed));
-        text.push_str(&format!(" failure: {}\n", self.summary.failed));
+        text.push_str(&format!("{}:\n", tr("report.summary", &[])));
+        text.push_str(&format!(
+            " {}: {}\n",
+            tr("report.total_tests", &[]),
+            self.summary.total_tests
+        ));
         text.push_str(&format!(
-            " success率: {:.2}%\n\n",
+            " {}: {}\n",
+            tr("report.passed", &[]),
+            self.summary.passed
+        ));
+        text.push_str(&format!(
+            " {}: {}\n",
+            tr("report.failed", &[]),
+            self.summary.failed
+        ));
+        text.push_str(&format!(
+            " {}: {:.2}%\n\n",
+            tr("report.success_rate", &[]),
             self.summary.success_rate
         ));
         text.push_str(&"=".repeat(70));
@@ -140,16 +171,36 @@ impl ValidationReport {
 
     /// save as file
     pub fn save_to_file(&self, filename: &str, format: ReportFormat) -> std::io::Result<()> {
+        self.write_to(Path::new(filename), format)
+    }
+
+    fn write_to(&self, path: &Path, format: ReportFormat) -> std::io::Result<()> {
         let content = match format {
             ReportFormat::Markdown => self.to_markdown(),
             ReportFormat::Text => self.to_text(),
         };
 
-        let mut file = File::create(filename)?;
+        let mut file = File::create(path)?;
         file.write_all(content.as_bytes())?;
 
         Ok(())
     }
+
+    /// Save under the reports directory of `dirs` as `<name>.md` or `<name>.txt`
+    pub fn save_to_dirs(
+        &self,
+        dirs: &DataDirs,
+        name: &str,
+        format: ReportFormat,
+    ) -> std::io::Result<PathBuf> {
+        let extension = match format {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Text => "txt",
+        };
+        let path = DataDirs::prepare_dir(dirs.reports_dir())?.join(format!("{name}.{extension}"));
+        self.write_to(&path, format)?;
+        Ok(path)
+    }
 }
 
 impl ReportSection {
@@ -260,8 +311,8 @@ mod tests {
 
         let md = report.to_markdown();
         assert!(md.contains("# Test Report"));
-        // Checksuccess率field exists (not mandatoryrequirepreciseformat)
-        assert!(md.contains("success率") || md.contains("Success"));
+        // Labels default to English
+        assert!(md.contains("Success rate"));
         assert!(md.contains("90."));
     }
 }
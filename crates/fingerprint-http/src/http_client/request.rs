@@ -1,5 +1,6 @@
 //! HTTP requestBuilder
 
+use fingerprint_core::trace_context::TraceContext;
 use fingerprint_headers::headers::HTTPHeaders;
 use std::collections::HashMap;
 
@@ -27,6 +28,11 @@ impl HttpMethod {
             HttpMethod::Patch => "PATCH",
         }
     }
+
+    /// Whether repeating the request has the same effect as sending it once (RFC 9110 9.2.2)
+    pub fn is_idempotent(&self) -> bool {
+        !matches!(self, HttpMethod::Post | HttpMethod::Patch)
+    }
 }
 
 /// HTTP request
@@ -36,6 +42,11 @@ pub struct HttpRequest {
     pub url: String,
     pub headers: HashMap<String, String>,
     pub body: Option<Vec<u8>>,
+    /// Explicit idempotency marking for retries (None: decided by method)
+    pub idempotent: Option<bool>,
+    /// Trace this request belongs to; each attempt becomes a child span of it when
+    /// `propagate_trace_context` is on (None: a new trace per request)
+    pub trace_context: Option<TraceContext>,
 }
 
 /// auxiliaryfunction： as requestAdd Cookie ( if exists)
@@ -59,9 +70,17 @@ impl HttpRequest {
             url: url.to_string(),
             headers: HashMap::new(),
             body: None,
+            idempotent: None,
+            trace_context: None,
         }
     }
 
+    /// Continue the trace of `context`, e.g. the incoming request being served
+    pub fn with_trace_context(mut self, context: TraceContext) -> Self {
+        self.trace_context = Some(context);
+        self
+    }
+
     /// Add User-Agent
     pub fn with_user_agent(mut self, user_agent: &str) -> Self {
         self.headers
@@ -90,6 +109,12 @@ impl HttpRequest {
         self
     }
 
+    /// Mark whether the request may be retried regardless of its method
+    pub fn with_idempotent(mut self, idempotent: bool) -> Self {
+        self.idempotent = Some(idempotent);
+        self
+    }
+
     /// settings JSON request体
     pub fn with_json_body(mut self, json: &str) -> Self {
         self.headers
@@ -2,14 +2,18 @@
 //!
 //! support：
 //! - chunked encoding
-//! - gzip/deflate/brotli compression
+//! - gzip/deflate/brotli/zstd compression
 //! - complete HTTP/1.1 responseParse
 
+#[cfg(feature = "compression")]
+use super::limits::DecompressError;
+use super::limits::ResourceLimits;
+use super::HttpClientError;
 #[cfg(feature = "compression")]
 use brotli_decompressor::Decompressor;
+use fingerprint_core::edge::{EdgeClassifier, EdgeDetection, EdgeObservation};
+use fingerprint_core::i18n::tr;
 use std::collections::HashMap;
-#[cfg(feature = "compression")]
-use std::io::Read;
 
 /// HTTP response
 #[derive(Debug, Clone)]
@@ -37,27 +41,32 @@ impl HttpResponse {
 
     /// from originalbeginningresponseParse (completeversion)
     pub fn parse(raw_response: &[u8]) -> Result<Self, String> {
+        Self::parse_with_limits(raw_response, &ResourceLimits::default()).map_err(|e| match e {
+            HttpClientError::InvalidResponse(msg) => msg,
+            other => other.to_string(),
+        })
+    }
+
+    /// Parse a raw response, enforcing header and decompression limits
+    pub fn parse_with_limits(
+        raw_response: &[u8],
+        limits: &ResourceLimits,
+    ) -> Result<Self, HttpClientError> {
         let start = std::time::Instant::now();
 
         // 1. separate headers and body
-        let (headers_end, body_start) = Self::find_headers_end(raw_response)?;
+        let (headers_end, body_start) =
+            Self::find_headers_end(raw_response).map_err(HttpClientError::InvalidResponse)?;
 
         let header_bytes = &raw_response[..headers_end];
         let body_bytes = &raw_response[body_start..];
 
-        // 2. Parse headers
-        let header_str = String::from_utf8_lossy(header_bytes);
-        let mut lines = header_str.lines();
-
-        // 3. Parsestatusexecute: HTTP/1.1 200 OK
-        let status_line = lines.next().ok_or("missingstatus行")?;
-        let (http_version, status_code, status_text) = Self::parse_status_line(status_line)?;
-
-        // 4. Parse headers
-        let headers = Self::parse_headers(lines)?;
+        // 2-4. status line and headers
+        let (http_version, status_code, status_text, headers) =
+            Self::parse_head(header_bytes, limits)?;
 
         // 5. process body
-        let body = Self::process_body(body_bytes, &headers)?;
+        let body = Self::process_body(body_bytes, &headers, limits)?;
 
         let response_time_ms = start.elapsed().as_millis() as u64;
 
@@ -71,11 +80,41 @@ impl HttpResponse {
         })
     }
 
+    /// Parse the status line and header fields (without the blank line)
+    ///
+    /// Limits count every field line, including repeated names.
+    pub(super) fn parse_head(
+        header_bytes: &[u8],
+        limits: &ResourceLimits,
+    ) -> Result<(String, u16, String, HashMap<String, String>), HttpClientError> {
+        let header_str = String::from_utf8_lossy(header_bytes);
+        let mut lines = header_str.lines();
+
+        // Parsestatusexecute: HTTP/1.1 200 OK
+        let status_line = lines
+            .next()
+            .ok_or_else(|| HttpClientError::InvalidResponse(tr("http.missing_status_line", &[])))?;
+        let (http_version, status_code, status_text) =
+            Self::parse_status_line(status_line).map_err(HttpClientError::InvalidResponse)?;
+
+        let lines: Vec<&str> = lines.collect();
+        limits.check_headers(
+            lines
+                .iter()
+                .filter_map(|line| line.split_once(':'))
+                .map(|(name, value)| (name, value.as_bytes())),
+        )?;
+        let headers =
+            Self::parse_headers(lines.into_iter()).map_err(HttpClientError::InvalidResponse)?;
+
+        Ok((http_version, status_code, status_text, headers))
+    }
+
     /// find headers endbitplace (\r\n\r\n)
     fn find_headers_end(data: &[u8]) -> Result<(usize, usize), String> {
         // securityCheck：ensurecountdatalengthat least as 4 bytes
         if data.len() < 4 {
-            return Err("countdatatoo short，unable toincluding headers endmarker".to_string());
+            return Err(tr("http.headers_too_short", &[]));
         }
 
         // use saturating_sub preventdown溢, butneed额outsideCheckedgeboundary
@@ -86,7 +125,7 @@ impl HttpResponse {
                 return Ok((i, i + 4));
             }
         }
-        Err("not找 to headers endmarker".to_string())
+        Err(tr("http.headers_end_not_found", &[]))
     }
 
     /// Parsestatusexecute
@@ -94,7 +133,7 @@ impl HttpResponse {
         let parts: Vec<&str> = line.splitn(3, ' ').collect();
 
         if parts.len() < 2 {
-            return Err(format!("invalidstatus行: {}", line));
+            return Err(tr("http.invalid_status_line", &[("line", &line)]));
         }
 
         let http_version = parts[0].to_string();
@@ -132,19 +171,21 @@ impl HttpResponse {
     fn process_body(
         body_bytes: &[u8],
         headers: &HashMap<String, String>,
-    ) -> Result<Vec<u8>, String> {
+        limits: &ResourceLimits,
+    ) -> Result<Vec<u8>, HttpClientError> {
         let mut body = body_bytes.to_vec();
 
         // 1. process Transfer-Encoding: chunked
         if let Some(te) = headers.get("transfer-encoding") {
             if te.contains("chunked") {
-                body = Self::parse_chunked(&body)?;
+                body = Self::parse_chunked(&body).map_err(HttpClientError::InvalidResponse)?;
             }
         }
+        limits.check_response_body(body.len())?;
 
         // 2. process Content-Encoding
         if let Some(ce) = headers.get("content-encoding") {
-            body = Self::decompress(&body, ce)?;
+            body = Self::decompress(&body, ce, limits)?;
         }
 
         Ok(body)
@@ -213,94 +254,65 @@ impl HttpResponse {
     }
 
     /// 解compressionresponse体
-    fn decompress(data: &[u8], encoding: &str) -> Result<Vec<u8>, String> {
+    fn decompress(
+        data: &[u8],
+        encoding: &str,
+        limits: &ResourceLimits,
+    ) -> Result<Vec<u8>, HttpClientError> {
         match encoding.to_lowercase().as_str() {
+            "identity" | "" => Ok(data.to_vec()),
             #[cfg(feature = "compression")]
-            "gzip" => Self::decompress_gzip(data),
-            #[cfg(not(feature = "compression"))]
-            "gzip" => Err("gzip decompressionneed --features compression".to_string()),
+            "gzip" => {
+                Self::decode_limited(flate2::read::GzDecoder::new(data), data, "gzip", limits)
+            }
+            #[cfg(feature = "compression")]
+            "deflate" => Self::decode_limited(
+                flate2::read::DeflateDecoder::new(data),
+                data,
+                "deflate",
+                limits,
+            ),
             #[cfg(feature = "compression")]
-            "deflate" => Self::decompress_deflate(data),
+            "br" => Self::decode_limited(Decompressor::new(data, 4096), data, "brotli", limits),
+            #[cfg(feature = "compression")]
+            "zstd" => {
+                let decoder = zstd::stream::read::Decoder::new(data).map_err(|e| {
+                    HttpClientError::InvalidResponse(format!("zstd decompressionfailure: {}", e))
+                })?;
+                Self::decode_limited(decoder, data, "zstd", limits)
+            }
             #[cfg(not(feature = "compression"))]
-            "deflate" => Err("deflate decompressionneed --features compression".to_string()),
-            "br" => Self::decompress_brotli(data),
-            "identity" | "" => Ok(data.to_vec()),
-            _ => Err(format!("不supportencoding: {}", encoding)),
-        }
-    }
-
-    /// decompression gzip
-    #[cfg(feature = "compression")]
-    fn decompress_gzip(data: &[u8]) -> Result<Vec<u8>, String> {
-        #[cfg(not(feature = "compression"))]
-        {
-            let _ = data;
-            return Err("gzip decompressionneedenabled feature: compression".to_string());
-        }
-
-        #[cfg(feature = "compression")]
-        use flate2::read::GzDecoder;
-
-        #[cfg(feature = "compression")]
-        let mut decoder = GzDecoder::new(data);
-        #[cfg(feature = "compression")]
-        let mut result = Vec::new();
-        #[cfg(feature = "compression")]
-        decoder
-            .read_to_end(&mut result)
-            .map_err(|e| format!("gzip decompressionfailure: {}", e))?;
-        #[cfg(feature = "compression")]
-        Ok(result)
-    }
-
-    #[cfg(not(feature = "compression"))]
-    fn decompress_gzip(_data: &[u8]) -> Result<Vec<u8>, String> {
-        Err("compressionFeaturesnotenabled，请use --features compression compile".to_string())
-    }
-
-    /// decompression deflate
-    #[cfg(feature = "compression")]
-    fn decompress_deflate(data: &[u8]) -> Result<Vec<u8>, String> {
-        #[cfg(not(feature = "compression"))]
-        {
-            let _ = data;
-            return Err("deflate decompressionneedenabled feature: compression".to_string());
+            "gzip" | "deflate" | "br" | "zstd" => {
+                let _ = limits;
+                Err(HttpClientError::InvalidResponse(tr(
+                    "http.compression_disabled",
+                    &[("encoding", &encoding)],
+                )))
+            }
+            _ => Err(HttpClientError::InvalidResponse(tr(
+                "http.unsupported_encoding",
+                &[("encoding", &encoding)],
+            ))),
         }
-
-        #[cfg(feature = "compression")]
-        use flate2::read::DeflateDecoder;
-
-        #[cfg(feature = "compression")]
-        let mut decoder = DeflateDecoder::new(data);
-        #[cfg(feature = "compression")]
-        let mut result = Vec::new();
-        #[cfg(feature = "compression")]
-        decoder
-            .read_to_end(&mut result)
-            .map_err(|e| format!("deflate decompressionfailure: {}", e))?;
-        #[cfg(feature = "compression")]
-        Ok(result)
-    }
-
-    #[cfg(not(feature = "compression"))]
-    fn decompress_deflate(_data: &[u8]) -> Result<Vec<u8>, String> {
-        Err("compressionFeaturesnotenabled，请use --features compression compile".to_string())
     }
 
-    /// decompression brotli
+    /// decompression bounded by the size and ratio limits
     #[cfg(feature = "compression")]
-    fn decompress_brotli(data: &[u8]) -> Result<Vec<u8>, String> {
-        let mut decompressor = Decompressor::new(data, 4096);
-        let mut result = Vec::new();
-        decompressor
-            .read_to_end(&mut result)
-            .map_err(|e| format!("brotli decompressionfailure: {}", e))?;
-        Ok(result)
-    }
-
-    #[cfg(not(feature = "compression"))]
-    fn decompress_brotli(_data: &[u8]) -> Result<Vec<u8>, String> {
-        Err("brotli decompressionneedenabled feature: compression".to_string())
+    fn decode_limited<R: std::io::Read>(
+        decoder: R,
+        data: &[u8],
+        name: &str,
+        limits: &ResourceLimits,
+    ) -> Result<Vec<u8>, HttpClientError> {
+        limits
+            .read_decompressed(decoder, data.len())
+            .map_err(|e| match e {
+                DecompressError::Io(e) => HttpClientError::InvalidResponse(format!(
+                    "{} decompressionfailure: {}",
+                    name, e
+                )),
+                DecompressError::Limit(limit) => HttpClientError::LimitExceeded(limit),
+            })
     }
 
     /// Getresponse体 as string
@@ -317,6 +329,23 @@ impl HttpResponse {
     pub fn get_header(&self, key: &str) -> Option<&String> {
         self.headers.get(key)
     }
+
+    /// Headers and cookies for edge vendor classification
+    ///
+    /// Add the peer address and certificate issuer when known, then classify
+    /// with a custom [`EdgeClassifier`].
+    pub fn edge_observation(&self) -> EdgeObservation {
+        self.headers
+            .iter()
+            .fold(EdgeObservation::new(), |obs, (name, value)| {
+                obs.header(name, value)
+            })
+    }
+
+    /// CDN / WAF vendor in front of the server, from headers and cookies
+    pub fn edge_vendor(&self) -> Option<EdgeDetection> {
+        EdgeClassifier::builtin().classify(&self.edge_observation())
+    }
 }
 
 #[cfg(test)]
@@ -347,6 +376,16 @@ mod tests {
         assert!(response.is_success());
     }
 
+    #[test]
+    fn test_edge_vendor() {
+        let raw = b"HTTP/1.1 403 Forbidden\r\nX-CDN: Imperva\r\nSet-Cookie: visid_incap_1=a; path=/\r\nContent-Length: 0\r\n\r\n";
+        let response = HttpResponse::parse(raw).unwrap();
+        let edge = response.edge_vendor().unwrap();
+        assert_eq!(edge.vendor, fingerprint_core::edge::EdgeVendor::Imperva);
+        assert_eq!(edge.evidence.len(), 2);
+        assert!(HttpResponse::new(200).edge_vendor().is_none());
+    }
+
     #[test]
     fn test_parse_error_response() {
         let raw = b"HTTP/1.1 404 Not Found\r\n\r\n";
@@ -436,4 +475,17 @@ mod tests {
         let response = HttpResponse::parse(&raw).expect("Chunked+Gzip Parsefailure");
         assert_eq!(response.body_as_string().unwrap(), data);
     }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_zstd_compression() {
+        let data = "Hello Zstd World";
+        let compressed = zstd::stream::encode_all(data.as_bytes(), 3).unwrap();
+
+        let mut raw = b"HTTP/1.1 200 OK\r\nContent-Encoding: zstd\r\n\r\n".to_vec();
+        raw.extend_from_slice(&compressed);
+
+        let response = HttpResponse::parse(&raw).expect("Zstd Parsefailure");
+        assert_eq!(response.body_as_string().unwrap(), data);
+    }
 }
@@ -0,0 +1,447 @@
+//! Retry and backoff policy
+//!
+//! A [`RetryPolicy`] decides, per request hop, whether a failed attempt is
+//! sent again and how long to wait first. Attempts are retried when
+//! - the outcome matches a rule: a listed response status (429 and 503 by
+//!   default) or a listed [`ErrorClass`] (connect failures and timeouts by
+//!   default);
+//! - the request is safe to repeat: an idempotent method, or a request
+//!   marked with [`HttpRequest::with_idempotent`] or an `Idempotency-Key`
+//!   header, so a POST is never sent twice by accident;
+//! - the shared [`RetryBudget`], if any, still has tokens, so a failing
+//!   upstream does not receive a multiple of the normal load.
+//!
+//! Delays grow exponentially from `initial_backoff` up to `max_backoff`, with
+//! jitter, and honor `Retry-After` (delta-seconds) on 429/503 responses.
+
+use super::{HttpClientError, HttpRequest, HttpResponse, Result};
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Failure classes retry rules can match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// TCP connect or proxy handshake failed, or the connection was refused/reset
+    Connect,
+    /// TLS handshake or certificate failure
+    Tls,
+    /// Connect, read or write timed out
+    Timeout,
+    /// Other IO failure mid-exchange
+    Io,
+    /// Malformed response or HTTP/2, HTTP/3 protocol error
+    Protocol,
+}
+
+impl ErrorClass {
+    /// Class of `err`, or `None` for errors no retry can fix (bad URL, limits)
+    pub fn of(err: &HttpClientError) -> Option<Self> {
+        match err {
+            HttpClientError::ConnectionFailed(_) => Some(Self::Connect),
+            HttpClientError::TlsError(_) => Some(Self::Tls),
+            HttpClientError::Timeout => Some(Self::Timeout),
+            HttpClientError::Io(e) => Some(match e.kind() {
+                ErrorKind::TimedOut | ErrorKind::WouldBlock => Self::Timeout,
+                ErrorKind::ConnectionRefused
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::NotConnected
+                | ErrorKind::AddrNotAvailable => Self::Connect,
+                _ => Self::Io,
+            }),
+            HttpClientError::InvalidResponse(_) => Some(Self::Protocol),
+            #[cfg(feature = "http2")]
+            HttpClientError::Http2Error(_) => Some(Self::Protocol),
+            #[cfg(feature = "http3")]
+            HttpClientError::Http3Error(_) => Some(Self::Protocol),
+            #[cfg(feature = "experimental-webtransport")]
+            HttpClientError::WebTransportError(_) => Some(Self::Protocol),
+            HttpClientError::InvalidUrl(_)
+            | HttpClientError::InvalidRequest(_)
+            | HttpClientError::LimitExceeded(_) => None,
+        }
+    }
+}
+
+/// Token bucket limiting retries across requests
+///
+/// Every retryable failure withdraws one token and every success deposits
+/// `token_ratio`; retries are allowed only while more than half of
+/// `max_tokens` remain. Share one budget (via `Arc`) between clients talking
+/// to the same upstream.
+#[derive(Debug)]
+pub struct RetryBudget {
+    max_tokens: f64,
+    token_ratio: f64,
+    tokens: Mutex<f64>,
+}
+
+impl RetryBudget {
+    /// Create a full budget
+    pub fn new(max_tokens: u32, token_ratio: f64) -> Self {
+        let max_tokens = max_tokens.max(1) as f64;
+        Self {
+            max_tokens,
+            token_ratio: token_ratio.max(0.0),
+            tokens: Mutex::new(max_tokens),
+        }
+    }
+
+    /// Tokens currently available
+    pub fn tokens(&self) -> f64 {
+        *self.tokens.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether a retry may be sent now
+    pub fn allows_retry(&self) -> bool {
+        self.tokens() > self.max_tokens / 2.0
+    }
+
+    /// Record a successful (non-retryable) outcome
+    pub fn record_success(&self) {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        *tokens = (*tokens + self.token_ratio).min(self.max_tokens);
+    }
+
+    /// Record a retryable failure
+    pub fn record_failure(&self) {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        *tokens = (*tokens - 1.0).max(0.0);
+    }
+}
+
+impl Default for RetryBudget {
+    /// 10 tokens, one retry earned back per ten successes
+    fn default() -> Self {
+        Self::new(10, 0.1)
+    }
+}
+
+/// Retry rules and backoff schedule
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound of the exponential delay
+    pub max_backoff: Duration,
+    /// Growth factor between consecutive delays
+    pub multiplier: f64,
+    /// Share of each delay that is randomized (0.0: none, 1.0: full jitter)
+    pub jitter: f64,
+    /// Response statuses that are retried
+    pub retry_statuses: Vec<u16>,
+    /// Error classes that are retried
+    pub retry_errors: Vec<ErrorClass>,
+    /// Wait at least `Retry-After` on retried responses
+    pub respect_retry_after: bool,
+    /// Longest acceptable `Retry-After`; longer waits return the response instead
+    pub max_retry_after: Duration,
+    /// Shared retry budget (optional)
+    pub budget: Option<Arc<RetryBudget>>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.5,
+            retry_statuses: vec![429, 503],
+            retry_errors: vec![ErrorClass::Connect, ErrorClass::Timeout],
+            respect_retry_after: true,
+            max_retry_after: Duration::from_secs(60),
+            budget: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy with the default rules and `max_retries` retries
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::default()
+        }
+    }
+
+    /// Also retry responses with `status`
+    pub fn retry_status(mut self, status: u16) -> Self {
+        if !self.retry_statuses.contains(&status) {
+            self.retry_statuses.push(status);
+        }
+        self
+    }
+
+    /// Also retry errors of `class`
+    pub fn retry_error(mut self, class: ErrorClass) -> Self {
+        if !self.retry_errors.contains(&class) {
+            self.retry_errors.push(class);
+        }
+        self
+    }
+
+    /// Exponential schedule starting at `initial` and capped at `max`
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Randomize `jitter` (0.0 - 1.0) of each delay
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Draw retries from a shared budget
+    pub fn with_budget(mut self, budget: Arc<RetryBudget>) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Whether `request` may be sent more than once
+    pub fn is_retryable_request(request: &HttpRequest) -> bool {
+        request.idempotent.unwrap_or_else(|| {
+            request.method.is_idempotent()
+                || request
+                    .headers
+                    .keys()
+                    .any(|k| k.eq_ignore_ascii_case("idempotency-key"))
+        })
+    }
+
+    /// Whether an outcome matches a retry rule
+    pub fn matches(&self, outcome: &Result<HttpResponse>) -> bool {
+        match outcome {
+            Ok(response) => self.retry_statuses.contains(&response.status_code),
+            Err(err) => ErrorClass::of(err).is_some_and(|class| self.retry_errors.contains(&class)),
+        }
+    }
+
+    /// Delay before retry number `retry` (0-based), before jitter
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.max(1.0).powi(retry.min(64) as i32);
+        let secs = self.initial_backoff.as_secs_f64() * factor;
+        Duration::from_secs_f64(secs.min(self.max_backoff.as_secs_f64()))
+    }
+
+    /// Delay before retry number `retry` after `outcome`, or `None` to stop
+    fn delay(&self, retry: u32, outcome: &Result<HttpResponse>) -> Option<Duration> {
+        let backoff = self.backoff(retry);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let delay = backoff.mul_f64(1.0 - jitter + jitter * unit_random());
+
+        let retry_after = match outcome {
+            Ok(response) if self.respect_retry_after => retry_after(response),
+            _ => None,
+        };
+        match retry_after {
+            Some(wait) if wait > self.max_retry_after => None,
+            Some(wait) => Some(delay.max(wait)),
+            None => Some(delay),
+        }
+    }
+
+    /// Send with `send`, retrying per this policy until `deadline`
+    pub(crate) fn run<F>(
+        &self,
+        request: &HttpRequest,
+        deadline: Instant,
+        mut send: F,
+    ) -> Result<HttpResponse>
+    where
+        F: FnMut() -> Result<HttpResponse>,
+    {
+        let retryable_request = Self::is_retryable_request(request);
+        let mut retry = 0;
+        loop {
+            let outcome = send();
+            if !self.matches(&outcome) {
+                if let Some(budget) = &self.budget {
+                    budget.record_success();
+                }
+                return outcome;
+            }
+            if let Some(budget) = &self.budget {
+                budget.record_failure();
+            }
+
+            if !retryable_request || retry >= self.max_retries {
+                return outcome;
+            }
+            if self.budget.as_ref().is_some_and(|b| !b.allows_retry()) {
+                log::debug!("retry budget exhausted for {}", request.url);
+                return outcome;
+            }
+            let Some(delay) = self.delay(retry, &outcome) else {
+                return outcome;
+            };
+            if Instant::now() + delay >= deadline {
+                return outcome;
+            }
+
+            log::debug!(
+                "retrying {} {} in {:?} (retry {}/{}): {}",
+                request.method.as_str(),
+                request.url,
+                delay,
+                retry + 1,
+                self.max_retries,
+                match &outcome {
+                    Ok(response) => format!("status {}", response.status_code),
+                    Err(e) => e.to_string(),
+                }
+            );
+            std::thread::sleep(delay);
+            retry += 1;
+        }
+    }
+}
+
+/// `Retry-After` in delta-seconds form (HTTP-dates are ignored)
+fn retry_after(response: &HttpResponse) -> Option<Duration> {
+    response
+        .headers
+        .get("retry-after")
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Uniform value in [0, 1) for jitter; not cryptographic
+fn unit_random() -> f64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    let mut x = nanos
+        ^ COUNTER
+            .fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed)
+            .rotate_left(17);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^= x >> 31;
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::HttpMethod;
+    use std::cell::Cell;
+
+    fn fast(max_retries: u32) -> RetryPolicy {
+        RetryPolicy::new(max_retries).with_backoff(Duration::ZERO, Duration::ZERO)
+    }
+
+    fn deadline() -> Instant {
+        Instant::now() + Duration::from_secs(60)
+    }
+
+    #[test]
+    fn test_retries_rules_and_idempotency() {
+        let policy = fast(2);
+        let get = HttpRequest::new(HttpMethod::Get, "https://example.com/");
+
+        // 503 twice, then success
+        let calls = Cell::new(0);
+        let response = policy
+            .run(&get, deadline(), || {
+                calls.set(calls.get() + 1);
+                Ok(HttpResponse::new(if calls.get() < 3 { 503 } else { 200 }))
+            })
+            .unwrap();
+        assert_eq!(response.status_code, 200);
+        assert_eq!(calls.get(), 3);
+
+        // retries stop at max_retries and return the last outcome
+        calls.set(0);
+        let err = policy.run(&get, deadline(), || {
+            calls.set(calls.get() + 1);
+            Err(HttpClientError::ConnectionFailed("refused".to_string()))
+        });
+        assert!(matches!(err, Err(HttpClientError::ConnectionFailed(_))));
+        assert_eq!(calls.get(), 3);
+
+        // TLS errors are not retried unless a rule asks for it
+        calls.set(0);
+        let _ = policy.run(&get, deadline(), || {
+            calls.set(calls.get() + 1);
+            Err(HttpClientError::TlsError("bad certificate".to_string()))
+        });
+        assert_eq!(calls.get(), 1);
+
+        // POST is sent once unless marked idempotent
+        let post =
+            HttpRequest::new(HttpMethod::Post, "https://example.com/").with_body(b"{}".to_vec());
+        let unavailable = || {
+            calls.set(calls.get() + 1);
+            Ok(HttpResponse::new(503))
+        };
+        calls.set(0);
+        policy.run(&post, deadline(), unavailable).unwrap();
+        assert_eq!(calls.get(), 1);
+
+        calls.set(0);
+        let marked = post.clone().with_idempotent(true);
+        policy.run(&marked, deadline(), unavailable).unwrap();
+        assert_eq!(calls.get(), 3);
+
+        calls.set(0);
+        let keyed = post.with_header("Idempotency-Key", "8e03978e");
+        policy.run(&keyed, deadline(), unavailable).unwrap();
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn test_backoff_retry_after_and_budget() {
+        let policy = RetryPolicy::new(5)
+            .with_backoff(Duration::from_millis(100), Duration::from_secs(1))
+            .with_jitter(0.0);
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(10), Duration::from_secs(1));
+
+        let mut throttled = HttpResponse::new(429);
+        throttled
+            .headers
+            .insert("retry-after".to_string(), "2".to_string());
+        assert_eq!(
+            policy.delay(0, &Ok(throttled.clone())),
+            Some(Duration::from_secs(2))
+        );
+        throttled
+            .headers
+            .insert("retry-after".to_string(), "3600".to_string());
+        assert_eq!(policy.delay(0, &Ok(throttled)), None);
+
+        let jittered = policy.clone().with_jitter(1.0);
+        for retry in 0..5 {
+            assert!(
+                jittered.delay(retry, &Ok(HttpResponse::new(503))).unwrap()
+                    <= policy.backoff(retry)
+            );
+        }
+
+        // a budget of 4 tokens allows retries while more than 2 remain
+        let budget = Arc::new(RetryBudget::new(4, 0.5));
+        let policy = fast(10).with_budget(budget.clone());
+        let get = HttpRequest::new(HttpMethod::Get, "https://example.com/");
+        let calls = Cell::new(0);
+        let _ = policy.run(&get, deadline(), || {
+            calls.set(calls.get() + 1);
+            Ok(HttpResponse::new(503))
+        });
+        assert_eq!(calls.get(), 2);
+        assert!(!budget.allows_retry());
+        budget.record_success();
+        budget.record_success();
+        assert!(budget.allows_retry());
+    }
+}
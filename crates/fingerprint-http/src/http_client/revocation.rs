@@ -0,0 +1,899 @@
+//! Certificate revocation behavior
+//!
+//! rustls offers the `status_request` extension in every ClientHello but
+//! ignores whatever the server staples. Browsers do not: Firefox hard-fails a
+//! must-staple certificate without a good staple and rejects stapled
+//! "revoked" answers, Safari honors revoked staples but soft-fails
+//! everything else, and Chrome ignores OCSP entirely in favor of its pushed
+//! CRLSet. `RevocationChecker` reproduces those policies on top of normal
+//! WebPKI verification and records the stapled response it saw for each
+//! host, so the staple itself can feed server fingerprinting.
+//!
+//! Stapled responses are parsed but their signatures are not verified: a
+//! forged "revoked" answer can only fail a connection an on-path attacker
+//! could drop anyway, and a "good" answer never overrides a failed check.
+//!
+//! The local revocation set stands in for CRLite and CRLSet: serial numbers
+//! pushed ahead of time and checked without any network request.
+
+use fingerprint_profiles::BrowserProfile;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Mutex, RwLock};
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_ENUMERATED: u8 = 0x0a;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_CONTEXT_0: u8 = 0xa0;
+const TAG_CONTEXT_1: u8 = 0xa1;
+const TAG_CONTEXT_2: u8 = 0xa2;
+const TAG_CONTEXT_3: u8 = 0xa3;
+
+/// id-pe-tlsfeature (1.3.6.1.5.5.7.1.24)
+const OID_TLS_FEATURE: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x01, 0x18];
+/// id-pkix-ocsp-basic (1.3.6.1.5.5.7.48.1.1)
+const OID_OCSP_BASIC: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x01];
+/// TLS extension number of status_request, as listed in a TLS Feature extension
+const STATUS_REQUEST: u8 = 5;
+
+/// How a client acts on revocation information
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RevocationPolicy {
+    /// Reject a certificate whose stapled response says "revoked"
+    pub honor_revoked_staple: bool,
+    /// Require a good staple for certificates carrying the must-staple TLS Feature
+    pub enforce_must_staple: bool,
+    /// Reject a stapled response past its nextUpdate
+    pub reject_expired_staple: bool,
+    /// Require a good staple for every certificate (hard-fail)
+    pub require_staple: bool,
+    /// Consult the local revocation set (CRLite / CRLSet)
+    pub use_local_set: bool,
+}
+
+impl RevocationPolicy {
+    /// Policy that ignores revocation entirely (rustls default, Android apps)
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Hard-fail policy: every certificate needs a good, fresh staple
+    pub fn strict() -> Self {
+        Self {
+            honor_revoked_staple: true,
+            enforce_must_staple: true,
+            reject_expired_staple: true,
+            require_staple: true,
+            use_local_set: true,
+        }
+    }
+
+    /// Policy of the browser or app a profile emulates
+    pub fn for_profile(profile: &BrowserProfile) -> Self {
+        let metadata = &profile.metadata;
+        let apple = matches!(metadata.platform.as_str(), "iOS" | "iPadOS");
+
+        match metadata.browser_name.to_lowercase().as_str() {
+            // Every browser on iOS verifies through the system trust evaluation
+            _ if apple => Self::apple(),
+            "safari" | "nsurlsession" => Self::apple(),
+            // Chromium never acts on OCSP; revocation comes from the CRLSet
+            "chrome" | "edge" | "opera" => Self {
+                use_local_set: true,
+                ..Self::none()
+            },
+            // OCSP soft-fail plus CRLite, hard-fail on must-staple
+            "firefox" => Self {
+                honor_revoked_staple: true,
+                enforce_must_staple: true,
+                reject_expired_staple: true,
+                require_staple: false,
+                use_local_set: true,
+            },
+            _ => Self::none(),
+        }
+    }
+
+    /// Apple trust evaluation: revoked staples and the pushed set count, the rest soft-fails
+    fn apple() -> Self {
+        Self {
+            honor_revoked_staple: true,
+            use_local_set: true,
+            ..Self::none()
+        }
+    }
+}
+
+/// Responder identification of a stapled response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OcspResponder {
+    /// Responder identified by distinguished name
+    ByName,
+    /// Responder identified by public key hash
+    ByKey,
+}
+
+/// Certificate status reported by a stapled response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OcspCertStatus {
+    Good,
+    /// Revoked, with the revocation time (unix seconds) when readable
+    Revoked {
+        revoked_at: Option<u64>,
+    },
+    Unknown,
+}
+
+/// Details of a stapled OCSP response
+///
+/// Status, freshness window, responder style and size differ between CAs and
+/// between server stacks that fetch and cache staples, which makes them a
+/// useful server-side signal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StapledOcsp {
+    /// OCSPResponseStatus (0 = successful, 3 = tryLater, ...)
+    pub response_status: u8,
+    /// Responder identification (None for unsuccessful responses)
+    pub responder: Option<OcspResponder>,
+    /// producedAt (unix seconds)
+    pub produced_at: Option<u64>,
+    /// Status of the served certificate, or of the first entry when none matches
+    pub cert_status: Option<OcspCertStatus>,
+    /// Whether an entry matches the served certificate's serial number
+    pub serial_matches: bool,
+    /// thisUpdate of that entry (unix seconds)
+    pub this_update: Option<u64>,
+    /// nextUpdate of that entry (unix seconds)
+    pub next_update: Option<u64>,
+    /// Number of SingleResponse entries
+    pub response_count: usize,
+    /// Whether the responder embedded its own certificates
+    pub embeds_certs: bool,
+    /// Size of the stapled response in bytes
+    pub size: usize,
+}
+
+impl StapledOcsp {
+    /// Parse a DER OCSPResponse, matching entries against `serial`
+    pub fn parse(der: &[u8], serial: &[u8]) -> Result<Self, String> {
+        let (tag, response, _) = read_tlv(der)?;
+        expect_tag(tag, TAG_SEQUENCE, "OCSPResponse")?;
+        let (tag, status, mut rest) = read_tlv(response)?;
+        expect_tag(tag, TAG_ENUMERATED, "responseStatus")?;
+
+        let mut stapled = Self {
+            response_status: status.last().copied().unwrap_or_default(),
+            responder: None,
+            produced_at: None,
+            cert_status: None,
+            serial_matches: false,
+            this_update: None,
+            next_update: None,
+            response_count: 0,
+            embeds_certs: false,
+            size: der.len(),
+        };
+        if rest.is_empty() {
+            return Ok(stapled);
+        }
+
+        let (tag, bytes) = next_field(&mut rest, "responseBytes")?;
+        expect_tag(tag, TAG_CONTEXT_0, "responseBytes")?;
+        let (tag, bytes, _) = read_tlv(bytes)?;
+        expect_tag(tag, TAG_SEQUENCE, "ResponseBytes")?;
+        let mut bytes = bytes;
+        let (tag, response_type) = next_field(&mut bytes, "responseType")?;
+        expect_tag(tag, TAG_OID, "responseType")?;
+        if response_type != OID_OCSP_BASIC {
+            return Err("unsupported OCSP response type".to_string());
+        }
+        let (tag, basic) = next_field(&mut bytes, "response")?;
+        expect_tag(tag, TAG_OCTET_STRING, "response")?;
+
+        let (tag, mut basic, _) = read_tlv(basic)?;
+        expect_tag(tag, TAG_SEQUENCE, "BasicOCSPResponse")?;
+        let (tag, mut data) = next_field(&mut basic, "tbsResponseData")?;
+        expect_tag(tag, TAG_SEQUENCE, "tbsResponseData")?;
+        next_field(&mut basic, "signatureAlgorithm")?;
+        next_field(&mut basic, "signature")?;
+        stapled.embeds_certs = !basic.is_empty();
+
+        // version is optional and defaults to v1
+        let (mut tag, _) = next_field(&mut data, "responderID")?;
+        if tag == TAG_CONTEXT_0 {
+            tag = next_field(&mut data, "responderID")?.0;
+        }
+        stapled.responder = match tag {
+            TAG_CONTEXT_1 => Some(OcspResponder::ByName),
+            TAG_CONTEXT_2 => Some(OcspResponder::ByKey),
+            _ => return Err(format!("invalid responderID tag 0x{:02x}", tag)),
+        };
+        let (tag, produced_at) = next_field(&mut data, "producedAt")?;
+        expect_tag(tag, TAG_GENERALIZED_TIME, "producedAt")?;
+        stapled.produced_at = parse_generalized_time(produced_at);
+
+        let (tag, mut responses) = next_field(&mut data, "responses")?;
+        expect_tag(tag, TAG_SEQUENCE, "responses")?;
+        let serial = trim_serial(serial);
+        while !responses.is_empty() {
+            let (tag, single) = next_field(&mut responses, "SingleResponse")?;
+            expect_tag(tag, TAG_SEQUENCE, "SingleResponse")?;
+            let entry = SingleResponse::parse(single)?;
+            stapled.response_count += 1;
+
+            let matches = trim_serial(entry.serial) == serial;
+            if stapled.response_count == 1 || (matches && !stapled.serial_matches) {
+                stapled.serial_matches = matches;
+                stapled.cert_status = Some(entry.status);
+                stapled.this_update = entry.this_update;
+                stapled.next_update = entry.next_update;
+            }
+        }
+
+        Ok(stapled)
+    }
+
+    /// Whether the responder answered successfully
+    pub fn is_successful(&self) -> bool {
+        self.response_status == 0
+    }
+
+    /// Length of the validity window (nextUpdate - thisUpdate) in seconds
+    pub fn validity_secs(&self) -> Option<u64> {
+        Some(self.next_update?.saturating_sub(self.this_update?))
+    }
+
+    /// Seconds since thisUpdate at `now` (unix seconds)
+    pub fn age_secs(&self, now: u64) -> Option<u64> {
+        self.this_update
+            .map(|this_update| now.saturating_sub(this_update))
+    }
+
+    /// Whether `now` (unix seconds) is before nextUpdate; responses without one never expire
+    pub fn is_fresh(&self, now: u64) -> bool {
+        self.next_update
+            .is_none_or(|next_update| now <= next_update)
+    }
+
+    /// Whether this staple vouches for the served certificate at `now`
+    pub fn is_good_for(&self, now: u64) -> bool {
+        self.is_successful()
+            && self.serial_matches
+            && self.cert_status == Some(OcspCertStatus::Good)
+            && self.is_fresh(now)
+    }
+}
+
+/// One SingleResponse entry
+struct SingleResponse<'a> {
+    serial: &'a [u8],
+    status: OcspCertStatus,
+    this_update: Option<u64>,
+    next_update: Option<u64>,
+}
+
+impl<'a> SingleResponse<'a> {
+    fn parse(mut single: &'a [u8]) -> Result<Self, String> {
+        let (tag, mut cert_id) = next_field(&mut single, "certID")?;
+        expect_tag(tag, TAG_SEQUENCE, "certID")?;
+        next_field(&mut cert_id, "hashAlgorithm")?;
+        next_field(&mut cert_id, "issuerNameHash")?;
+        next_field(&mut cert_id, "issuerKeyHash")?;
+        let (tag, serial) = next_field(&mut cert_id, "serialNumber")?;
+        expect_tag(tag, TAG_INTEGER, "serialNumber")?;
+
+        let (tag, status) = next_field(&mut single, "certStatus")?;
+        let status = match tag {
+            0x80 => OcspCertStatus::Good,
+            TAG_CONTEXT_1 => OcspCertStatus::Revoked {
+                revoked_at: read_tlv(status)
+                    .ok()
+                    .filter(|(tag, _, _)| *tag == TAG_GENERALIZED_TIME)
+                    .and_then(|(_, time, _)| parse_generalized_time(time)),
+            },
+            0x82 => OcspCertStatus::Unknown,
+            _ => return Err(format!("invalid certStatus tag 0x{:02x}", tag)),
+        };
+
+        let (tag, this_update) = next_field(&mut single, "thisUpdate")?;
+        expect_tag(tag, TAG_GENERALIZED_TIME, "thisUpdate")?;
+        let next_update = match single.first() {
+            Some(&TAG_CONTEXT_0) => {
+                let (_, explicit) = next_field(&mut single, "nextUpdate")?;
+                let (tag, time, _) = read_tlv(explicit)?;
+                expect_tag(tag, TAG_GENERALIZED_TIME, "nextUpdate")?;
+                parse_generalized_time(time)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            serial,
+            status,
+            this_update: parse_generalized_time(this_update),
+            next_update,
+        })
+    }
+}
+
+/// State of the staple seen on a connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StapleState {
+    /// Server stapled nothing
+    Absent,
+    /// Server stapled something that is not a readable OCSP response
+    Malformed(String),
+    /// Parsed stapled response
+    Present(StapledOcsp),
+}
+
+/// Why a certificate was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevocationFailure {
+    /// Stapled response reports the certificate revoked
+    RevokedStaple,
+    /// Serial number is in the local revocation set
+    RevokedLocally,
+    /// Stapled response is past its nextUpdate
+    ExpiredStaple,
+    /// Policy or must-staple requires a good staple and none was served
+    MissingStaple,
+}
+
+impl fmt::Display for RevocationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RevocationFailure::RevokedStaple => write!(f, "stapled OCSP response: revoked"),
+            RevocationFailure::RevokedLocally => {
+                write!(f, "certificate is in the local revocation set")
+            }
+            RevocationFailure::ExpiredStaple => write!(f, "stapled OCSP response expired"),
+            RevocationFailure::MissingStaple => write!(f, "required OCSP staple missing"),
+        }
+    }
+}
+
+impl std::error::Error for RevocationFailure {}
+
+/// Revocation outcome of one handshake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StapleObservation {
+    /// Whether the certificate carries the must-staple TLS Feature
+    pub must_staple: bool,
+    /// Stapled response served with the certificate
+    pub staple: StapleState,
+    /// Failure the policy raised, if any
+    pub failure: Option<RevocationFailure>,
+}
+
+/// Applies a [`RevocationPolicy`] to server certificates and records staples per host
+#[derive(Debug, Default)]
+pub struct RevocationChecker {
+    policy: RevocationPolicy,
+    /// Locally revoked serial numbers (leading zero bytes trimmed)
+    revoked_serials: RwLock<HashSet<Vec<u8>>>,
+    /// Last observation per host
+    observations: Mutex<HashMap<String, StapleObservation>>,
+}
+
+impl RevocationChecker {
+    /// Create a checker with `policy`
+    pub fn new(policy: RevocationPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// Create a checker with the policy of `profile`
+    pub fn for_profile(profile: &BrowserProfile) -> Self {
+        Self::new(RevocationPolicy::for_profile(profile))
+    }
+
+    /// Policy in effect
+    pub fn policy(&self) -> RevocationPolicy {
+        self.policy
+    }
+
+    /// Add a serial number to the local revocation set
+    pub fn revoke_serial(&self, serial: &[u8]) {
+        if let Ok(mut serials) = self.revoked_serials.write() {
+            serials.insert(trim_serial(serial).to_vec());
+        }
+    }
+
+    /// Last observation for `host`
+    pub fn observation(&self, host: &str) -> Option<StapleObservation> {
+        self.observations
+            .lock()
+            .ok()
+            .and_then(|observations| observations.get(host).cloned())
+    }
+
+    /// Check a DER end-entity certificate and its staple at `now` (unix seconds)
+    ///
+    /// Runs after chain verification succeeded. The observation is recorded
+    /// for `host` whether or not the check passes.
+    pub fn check(
+        &self,
+        host: &str,
+        end_entity: &[u8],
+        ocsp_response: &[u8],
+        now: u64,
+    ) -> Result<StapleObservation, RevocationFailure> {
+        let (serial, must_staple) =
+            certificate_serial_and_must_staple(end_entity).unwrap_or((&[] as &[u8], false));
+        let staple = if ocsp_response.is_empty() {
+            StapleState::Absent
+        } else {
+            match StapledOcsp::parse(ocsp_response, serial) {
+                Ok(stapled) => StapleState::Present(stapled),
+                Err(e) => StapleState::Malformed(e),
+            }
+        };
+
+        let observation = StapleObservation {
+            must_staple,
+            failure: self.evaluate(serial, must_staple, &staple, now),
+            staple,
+        };
+        if let Ok(mut observations) = self.observations.lock() {
+            observations.insert(host.to_string(), observation.clone());
+        }
+
+        match observation.failure {
+            Some(failure) => Err(failure),
+            None => Ok(observation),
+        }
+    }
+
+    fn evaluate(
+        &self,
+        serial: &[u8],
+        must_staple: bool,
+        staple: &StapleState,
+        now: u64,
+    ) -> Option<RevocationFailure> {
+        let policy = &self.policy;
+        if policy.use_local_set
+            && !serial.is_empty()
+            && self
+                .revoked_serials
+                .read()
+                .is_ok_and(|serials| serials.contains(trim_serial(serial)))
+        {
+            return Some(RevocationFailure::RevokedLocally);
+        }
+
+        let stapled = match staple {
+            StapleState::Present(stapled) if stapled.is_successful() && stapled.serial_matches => {
+                Some(stapled)
+            }
+            _ => None,
+        };
+        if let Some(stapled) = stapled {
+            if policy.honor_revoked_staple
+                && matches!(stapled.cert_status, Some(OcspCertStatus::Revoked { .. }))
+            {
+                return Some(RevocationFailure::RevokedStaple);
+            }
+            if policy.reject_expired_staple && !stapled.is_fresh(now) {
+                return Some(RevocationFailure::ExpiredStaple);
+            }
+        }
+
+        let needs_staple = policy.require_staple || (must_staple && policy.enforce_must_staple);
+        if needs_staple && !stapled.is_some_and(|stapled| stapled.is_good_for(now)) {
+            return Some(RevocationFailure::MissingStaple);
+        }
+        None
+    }
+}
+
+/// rustls verifier running a [`RevocationChecker`] after WebPKI verification
+#[cfg(any(feature = "rustls-tls", feature = "http2", feature = "http3"))]
+#[derive(Debug)]
+pub(crate) struct RevocationVerifier {
+    inner: std::sync::Arc<dyn rustls::client::danger::ServerCertVerifier>,
+    checker: std::sync::Arc<RevocationChecker>,
+}
+
+#[cfg(any(feature = "rustls-tls", feature = "http2", feature = "http3"))]
+impl RevocationVerifier {
+    pub(crate) fn new(
+        inner: std::sync::Arc<dyn rustls::client::danger::ServerCertVerifier>,
+        checker: std::sync::Arc<RevocationChecker>,
+    ) -> Self {
+        Self { inner, checker }
+    }
+}
+
+#[cfg(any(feature = "rustls-tls", feature = "http2", feature = "http3"))]
+impl rustls::client::danger::ServerCertVerifier for RevocationVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::pki_types::CertificateDer,
+        intermediates: &[rustls::pki_types::CertificateDer],
+        server_name: &rustls::pki_types::ServerName,
+        ocsp_response: &[u8],
+        now: rustls::pki_types::UnixTime,
+    ) -> std::result::Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+        self.checker
+            .check(
+                &server_name.to_str(),
+                end_entity.as_ref(),
+                ocsp_response,
+                now.as_secs(),
+            )
+            .map_err(|failure| match failure {
+                RevocationFailure::RevokedStaple | RevocationFailure::RevokedLocally => {
+                    rustls::Error::InvalidCertificate(rustls::CertificateError::Revoked)
+                }
+                other => rustls::Error::General(other.to_string()),
+            })?;
+        Ok(verified)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> std::result::Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> std::result::Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+/// Serial number and must-staple flag of a DER certificate
+fn certificate_serial_and_must_staple(der: &[u8]) -> Result<(&[u8], bool), String> {
+    let (tag, certificate, _) = read_tlv(der)?;
+    expect_tag(tag, TAG_SEQUENCE, "Certificate")?;
+    let (tag, mut tbs, _) = read_tlv(certificate)?;
+    expect_tag(tag, TAG_SEQUENCE, "TBSCertificate")?;
+
+    let (mut tag, mut serial) = next_field(&mut tbs, "serialNumber")?;
+    if tag == TAG_CONTEXT_0 {
+        (tag, serial) = next_field(&mut tbs, "serialNumber")?;
+    }
+    expect_tag(tag, TAG_INTEGER, "serialNumber")?;
+    for name in [
+        "signature",
+        "issuer",
+        "validity",
+        "subject",
+        "subjectPublicKeyInfo",
+    ] {
+        next_field(&mut tbs, name)?;
+    }
+
+    while !tbs.is_empty() {
+        let (tag, value) = next_field(&mut tbs, "extensions")?;
+        if tag != TAG_CONTEXT_3 {
+            continue;
+        }
+        let (tag, mut extensions, _) = read_tlv(value)?;
+        expect_tag(tag, TAG_SEQUENCE, "extensions")?;
+        while !extensions.is_empty() {
+            let (tag, mut extension) = next_field(&mut extensions, "Extension")?;
+            expect_tag(tag, TAG_SEQUENCE, "Extension")?;
+            let (tag, oid) = next_field(&mut extension, "extnID")?;
+            expect_tag(tag, TAG_OID, "extnID")?;
+            if oid != OID_TLS_FEATURE {
+                continue;
+            }
+            // critical BOOLEAN is optional
+            let (mut tag, mut value) = next_field(&mut extension, "extnValue")?;
+            if tag == 0x01 {
+                (tag, value) = next_field(&mut extension, "extnValue")?;
+            }
+            expect_tag(tag, TAG_OCTET_STRING, "extnValue")?;
+            let (tag, mut features, _) = read_tlv(value)?;
+            expect_tag(tag, TAG_SEQUENCE, "Features")?;
+            while !features.is_empty() {
+                let (tag, feature) = next_field(&mut features, "feature")?;
+                if tag == TAG_INTEGER && feature == [STATUS_REQUEST] {
+                    return Ok((serial, true));
+                }
+            }
+        }
+        break;
+    }
+    Ok((serial, false))
+}
+
+/// Serial number without leading zero bytes (DER INTEGER sign padding)
+fn trim_serial(serial: &[u8]) -> &[u8] {
+    let start = serial.iter().position(|&b| b != 0).unwrap_or(serial.len());
+    &serial[start..]
+}
+
+/// GeneralizedTime ("YYYYMMDDHHMMSS[.fff]Z") to unix seconds
+fn parse_generalized_time(time: &[u8]) -> Option<u64> {
+    let digits = |range: std::ops::Range<usize>| -> Option<u64> {
+        let text = std::str::from_utf8(time.get(range)?).ok()?;
+        text.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| text.parse().ok())?
+    };
+    let (year, month, day) = (digits(0..4)?, digits(4..6)?, digits(6..8)?);
+    let (hour, minute, second) = (digits(8..10)?, digits(10..12)?, digits(12..14)?);
+    if !(1970..=9999).contains(&year)
+        || !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    // days from 1970-01-01 (Howard Hinnant's days_from_civil)
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y / 400;
+    let year_of_era = y % 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = (era * 146_097 + day_of_era).checked_sub(719_468)?;
+    Some(days * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+/// Next field of a SEQUENCE body, advancing `rest` past it
+fn next_field<'a>(rest: &mut &'a [u8], name: &str) -> Result<(u8, &'a [u8]), String> {
+    if rest.is_empty() {
+        return Err(format!("structure ends before {}", name));
+    }
+    let (tag, value, tail) = read_tlv(rest)?;
+    *rest = tail;
+    Ok((tag, value))
+}
+
+fn expect_tag(tag: u8, expected: u8, what: &str) -> Result<(), String> {
+    if tag == expected {
+        Ok(())
+    } else {
+        Err(format!(
+            "expected {} (tag 0x{:02x}), found tag 0x{:02x}",
+            what, expected, tag
+        ))
+    }
+}
+
+/// Split one DER TLV off the front of `data`: (tag, value, rest)
+fn read_tlv(data: &[u8]) -> Result<(u8, &[u8], &[u8]), String> {
+    if data.len() < 2 {
+        return Err("DER data too short".to_string());
+    }
+    let tag = data[0];
+    let (len, header) = match data[1] {
+        short if short < 0x80 => (short as usize, 2),
+        0x80 => return Err("indefinite DER length".to_string()),
+        long => {
+            let count = (long & 0x7f) as usize;
+            if count > 4 || data.len() < 2 + count {
+                return Err("invalid DER length".to_string());
+            }
+            let len = data[2..2 + count]
+                .iter()
+                .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+            (len, 2 + count)
+        }
+    };
+    let end = header
+        .checked_add(len)
+        .filter(|&end| end <= data.len())
+        .ok_or_else(|| format!("DER value needs {} bytes, got {}", len, data.len() - header))?;
+    Ok((tag, &data[header..end], &data[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERIAL: &[u8] = &[0x00, 0x9a, 0x42];
+    /// 2024-01-01T00:00:00Z
+    const JAN_1: u64 = 1_704_067_200;
+
+    fn tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if value.len() < 0x80 {
+            out.push(value.len() as u8);
+        } else {
+            out.extend([0x82, (value.len() >> 8) as u8, value.len() as u8]);
+        }
+        out.extend_from_slice(value);
+        out
+    }
+
+    fn certificate(must_staple: bool) -> Vec<u8> {
+        let mut extensions = tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_OID, &[0x55, 0x1d, 0x0f]),
+                tlv(TAG_OCTET_STRING, &[]),
+            ]
+            .concat(),
+        );
+        if must_staple {
+            let features = tlv(TAG_SEQUENCE, &tlv(TAG_INTEGER, &[STATUS_REQUEST]));
+            extensions.extend(tlv(
+                TAG_SEQUENCE,
+                &[
+                    tlv(TAG_OID, OID_TLS_FEATURE),
+                    tlv(TAG_OCTET_STRING, &features),
+                ]
+                .concat(),
+            ));
+        }
+        let tbs = [
+            tlv(TAG_CONTEXT_0, &tlv(TAG_INTEGER, &[2])),
+            tlv(TAG_INTEGER, SERIAL),
+            tlv(TAG_SEQUENCE, &[]),
+            tlv(TAG_SEQUENCE, &[]),
+            tlv(TAG_SEQUENCE, &[]),
+            tlv(TAG_SEQUENCE, &[]),
+            tlv(TAG_SEQUENCE, &[0u8; 64]),
+            tlv(TAG_CONTEXT_3, &tlv(TAG_SEQUENCE, &extensions)),
+        ]
+        .concat();
+        tlv(TAG_SEQUENCE, &tlv(TAG_SEQUENCE, &tbs))
+    }
+
+    fn ocsp(status: &[u8]) -> Vec<u8> {
+        let cert_id = [
+            tlv(TAG_SEQUENCE, &[]),
+            tlv(TAG_OCTET_STRING, &[1; 20]),
+            tlv(TAG_OCTET_STRING, &[2; 20]),
+            tlv(TAG_INTEGER, SERIAL),
+        ]
+        .concat();
+        let single = [
+            tlv(TAG_SEQUENCE, &cert_id),
+            status.to_vec(),
+            tlv(TAG_GENERALIZED_TIME, b"20240101000000Z"),
+            tlv(
+                TAG_CONTEXT_0,
+                &tlv(TAG_GENERALIZED_TIME, b"20240108000000Z"),
+            ),
+        ]
+        .concat();
+        let data = [
+            tlv(TAG_CONTEXT_2, &tlv(TAG_OCTET_STRING, &[3; 20])),
+            tlv(TAG_GENERALIZED_TIME, b"20240101000000Z"),
+            tlv(TAG_SEQUENCE, &tlv(TAG_SEQUENCE, &single)),
+        ]
+        .concat();
+        let basic = tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_SEQUENCE, &data),
+                tlv(TAG_SEQUENCE, &[]),
+                tlv(0x03, &[0]),
+            ]
+            .concat(),
+        );
+        let bytes = tlv(
+            TAG_SEQUENCE,
+            &[tlv(TAG_OID, OID_OCSP_BASIC), tlv(TAG_OCTET_STRING, &basic)].concat(),
+        );
+        tlv(
+            TAG_SEQUENCE,
+            &[tlv(TAG_ENUMERATED, &[0]), tlv(TAG_CONTEXT_0, &bytes)].concat(),
+        )
+    }
+
+    fn good() -> Vec<u8> {
+        ocsp(&tlv(0x80, &[]))
+    }
+
+    fn revoked() -> Vec<u8> {
+        ocsp(&tlv(
+            TAG_CONTEXT_1,
+            &tlv(TAG_GENERALIZED_TIME, b"20231231000000Z"),
+        ))
+    }
+
+    #[test]
+    fn test_stapled_ocsp_details() {
+        let stapled = StapledOcsp::parse(&good(), &[0x9a, 0x42]).unwrap();
+        assert!(stapled.serial_matches);
+        assert_eq!(stapled.responder, Some(OcspResponder::ByKey));
+        assert_eq!(stapled.produced_at, Some(JAN_1));
+        assert_eq!(stapled.validity_secs(), Some(7 * 86_400));
+        assert!(!stapled.embeds_certs);
+        assert!(stapled.is_good_for(JAN_1 + 3_600));
+        assert!(!stapled.is_good_for(JAN_1 + 8 * 86_400));
+
+        let stapled = StapledOcsp::parse(&revoked(), SERIAL).unwrap();
+        assert_eq!(
+            stapled.cert_status,
+            Some(OcspCertStatus::Revoked {
+                revoked_at: Some(JAN_1 - 86_400)
+            })
+        );
+
+        let try_later = tlv(TAG_SEQUENCE, &tlv(TAG_ENUMERATED, &[3]));
+        let stapled = StapledOcsp::parse(&try_later, SERIAL).unwrap();
+        assert!(!stapled.is_successful());
+        assert_eq!(stapled.responder, None);
+    }
+
+    #[test]
+    fn test_browser_policies() {
+        let firefox =
+            RevocationChecker::for_profile(&fingerprint_profiles::profiles::firefox_133());
+        let chrome = RevocationChecker::for_profile(&fingerprint_profiles::profiles::chrome_133());
+        let safari = RevocationChecker::for_profile(&fingerprint_profiles::profiles::safari_16_0());
+
+        // must-staple without a staple: only Firefox hard-fails
+        let must_staple = certificate(true);
+        assert_eq!(
+            firefox.check("a.test", &must_staple, &[], JAN_1),
+            Err(RevocationFailure::MissingStaple)
+        );
+        assert!(firefox
+            .check("a.test", &must_staple, &good(), JAN_1)
+            .is_ok());
+        assert!(chrome.check("a.test", &must_staple, &[], JAN_1).is_ok());
+
+        // revoked staple: Chrome ignores OCSP
+        let plain = certificate(false);
+        for checker in [&firefox, &safari] {
+            assert_eq!(
+                checker.check("b.test", &plain, &revoked(), JAN_1),
+                Err(RevocationFailure::RevokedStaple)
+            );
+        }
+        assert!(chrome.check("b.test", &plain, &revoked(), JAN_1).is_ok());
+        let observation = firefox.observation("b.test").unwrap();
+        assert!(!observation.must_staple);
+        assert!(matches!(observation.staple, StapleState::Present(_)));
+        assert_eq!(observation.failure, Some(RevocationFailure::RevokedStaple));
+
+        // CRLSet / CRLite
+        chrome.revoke_serial(&[0x9a, 0x42]);
+        assert_eq!(
+            chrome.check("c.test", &plain, &[], JAN_1),
+            Err(RevocationFailure::RevokedLocally)
+        );
+        let android = RevocationChecker::new(RevocationPolicy::none());
+        android.revoke_serial(SERIAL);
+        assert!(android.check("c.test", &plain, &revoked(), JAN_1).is_ok());
+
+        let strict = RevocationChecker::new(RevocationPolicy::strict());
+        assert_eq!(
+            strict.check("d.test", &plain, &[0x30, 0x01], JAN_1),
+            Err(RevocationFailure::MissingStaple)
+        );
+        assert!(matches!(
+            strict.observation("d.test").unwrap().staple,
+            StapleState::Malformed(_)
+        ));
+    }
+}
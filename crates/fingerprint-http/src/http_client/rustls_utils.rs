@@ -6,7 +6,11 @@
 //!
 //! - `build_root_store()`: Build root certificate store using Mozilla roots
 //! - `apply_verify_tls()`: Configure TLS certificate verification
+//! - `crypto_provider()`: Key exchange provider for a profile, hybrid post-quantum with `experimental-pq`
 //! - `build_client_config()`: Build complete rustls ClientConfig with ALPN and verification
+//! - `build_ech_client_config()`: Same, offering Encrypted Client Hello (TLS 1.3 only)
+//! - `apply_revocation()`: Act on stapled OCSP responses per browser policy
+//! - `apply_session_resumption()`: Resume from a shared ticket cache, 0-RTT if the profile asks
 //!
 //! ## Security Warning
 //!
@@ -29,9 +33,10 @@
 
 #![cfg(any(feature = "rustls-tls", feature = "http2", feature = "http3"))]
 
-#[cfg(feature = "dangerous_configuration")]
 use std::sync::Arc;
 
+use super::revocation::{RevocationChecker, RevocationVerifier};
+use super::session_cache::TlsSessionCache;
 use fingerprint_profiles::BrowserProfile;
 use std::sync::Once;
 
@@ -51,6 +56,33 @@ fn ensure_crypto_provider() {
     });
 }
 
+/// Crypto provider for connections made as `profile`
+///
+/// With `experimental-pq`, profiles whose ClientHello offers a hybrid post-quantum
+/// group get aws-lc-rs with X25519MLKEM768 preferred. Everything else, and every
+/// build without the feature, uses the installed default (ring): classical key
+/// exchange is the fallback.
+#[allow(unused_variables)]
+pub fn crypto_provider(profile: Option<&BrowserProfile>) -> Arc<rustls::crypto::CryptoProvider> {
+    ensure_crypto_provider();
+    #[cfg(feature = "experimental-pq")]
+    if profile.is_some_and(|p| p.tls_config.offers_post_quantum()) {
+        use rustls::crypto::aws_lc_rs::{self, kx_group};
+        return Arc::new(rustls::crypto::CryptoProvider {
+            kx_groups: vec![
+                kx_group::X25519MLKEM768,
+                kx_group::X25519,
+                kx_group::SECP256R1,
+                kx_group::SECP384R1,
+            ],
+            ..aws_lc_rs::default_provider()
+        });
+    }
+    rustls::crypto::CryptoProvider::get_default()
+        .cloned()
+        .unwrap_or_else(|| Arc::new(rustls::crypto::ring::default_provider()))
+}
+
 /// Build rustls rootcertificatestore (Mozilla roots)
 pub fn build_root_store() -> rustls::RootCertStore {
     ensure_crypto_provider();
@@ -164,17 +196,55 @@ pub fn apply_verify_tls(cfg: &mut rustls::ClientConfig, verify_tls: bool) {
     }
 }
 
+/// Wrap WebPKI verification with a revocation checker
+///
+/// rustls already sends status_request; this makes the server's answer count.
+/// Without verification there is nothing to wrap, so verify_tls=false skips it.
+pub fn apply_revocation(
+    cfg: &mut rustls::ClientConfig,
+    root_store: Arc<rustls::RootCertStore>,
+    checker: &Arc<RevocationChecker>,
+) {
+    match rustls::client::WebPkiServerVerifier::builder(root_store).build() {
+        Ok(webpki) => {
+            cfg.dangerous()
+                .set_certificate_verifier(Arc::new(RevocationVerifier::new(
+                    webpki,
+                    checker.clone(),
+                )));
+        }
+        Err(e) => eprintln!("warning: revocation checking disabled: {}", e),
+    }
+}
+
+/// Keep session tickets in the client's shared cache
+///
+/// Early data is only enabled for profiles whose ClientHello declares it.
+/// Without a cache the per-config default store stays (tickets die with the config).
+pub fn apply_session_resumption(
+    cfg: &mut rustls::ClientConfig,
+    cache: Option<&Arc<TlsSessionCache>>,
+    profile: Option<&BrowserProfile>,
+) {
+    let Some(cache) = cache else { return };
+    cfg.resumption = rustls::client::Resumption::store(cache.clone());
+    cfg.enable_early_data = profile.is_some_and(|p| p.tls_config.offers_early_data());
+}
+
 /// Build rustls::ClientConfig with ALPN/verify_tls settings, and match cipher suites based on fingerprint profile.
 pub fn build_client_config(
     verify_tls: bool,
     alpn_protocols: Vec<Vec<u8>>,
-    #[allow(unused_variables)] profile: Option<&BrowserProfile>,
+    profile: Option<&BrowserProfile>,
+    revocation: Option<&Arc<RevocationChecker>>,
 ) -> rustls::ClientConfig {
-    let root_store = build_root_store();
+    let root_store = Arc::new(build_root_store());
 
     // defaultconfiguration ( if unable toBased on profile match, thenback to securitydefaultvalue)
-    let builder = rustls::ClientConfig::builder()
-        .with_root_certificates(root_store)
+    let builder = rustls::ClientConfig::builder_with_provider(crypto_provider(profile))
+        .with_safe_default_protocol_versions()
+        .expect("provider supports the default protocol versions")
+        .with_root_certificates(root_store.clone())
         .with_no_client_auth();
 
     let mut cfg = builder;
@@ -229,8 +299,7 @@ pub fn build_client_config(
      }
      */
 
-    cfg.alpn_protocols = alpn_protocols;
-    apply_verify_tls(&mut cfg, verify_tls);
+    finish_client_config(&mut cfg, verify_tls, alpn_protocols, root_store, revocation);
 
     // optional： in send ClientHello before by fingerprint spec reorderextensionencodingorder (needmatch套 rustls fork).
     // Note: 此Featuresneedsupport ClientHelloCustomizer rustls fork, standard rustls 不support.
@@ -244,3 +313,60 @@ pub fn build_client_config(
     }
     cfg
 }
+
+/// Build rustls::ClientConfig like [`build_client_config`], offering ECH in `mode`
+///
+/// rustls only offers ECH (real or GREASE) with TLS 1.3, so the config drops TLS 1.2.
+#[cfg(feature = "ech")]
+pub fn build_ech_client_config(
+    verify_tls: bool,
+    alpn_protocols: Vec<Vec<u8>>,
+    profile: Option<&BrowserProfile>,
+    revocation: Option<&Arc<RevocationChecker>>,
+    mode: rustls::client::EchMode,
+) -> Result<rustls::ClientConfig, rustls::Error> {
+    let root_store = Arc::new(build_root_store());
+    let mut cfg = rustls::ClientConfig::builder_with_provider(crypto_provider(profile))
+        .with_ech(mode)?
+        .with_root_certificates(root_store.clone())
+        .with_no_client_auth();
+    finish_client_config(&mut cfg, verify_tls, alpn_protocols, root_store, revocation);
+    Ok(cfg)
+}
+
+/// ALPN, verification and revocation settings shared by every client config
+fn finish_client_config(
+    cfg: &mut rustls::ClientConfig,
+    verify_tls: bool,
+    alpn_protocols: Vec<Vec<u8>>,
+    root_store: Arc<rustls::RootCertStore>,
+    revocation: Option<&Arc<RevocationChecker>>,
+) {
+    cfg.alpn_protocols = alpn_protocols;
+    apply_verify_tls(cfg, verify_tls);
+    if let (true, Some(checker)) = (verify_tls, revocation) {
+        apply_revocation(cfg, root_store, checker);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustls::NamedGroup;
+
+    #[test]
+    fn test_post_quantum_provider_only_for_profiles_offering_it() {
+        let pq = fingerprint_profiles::profiles::chrome_133_psk();
+        let classical = fingerprint_profiles::profiles::safari_ipad_15_6();
+        assert!(pq.tls_config.offers_post_quantum());
+        assert!(!classical.tls_config.offers_post_quantum());
+
+        let first_group =
+            |profile: &BrowserProfile| crypto_provider(Some(profile)).kx_groups[0].name();
+        assert_eq!(
+            first_group(&pq) == NamedGroup::X25519MLKEM768,
+            cfg!(feature = "experimental-pq")
+        );
+        assert_ne!(first_group(&classical), NamedGroup::X25519MLKEM768);
+    }
+}
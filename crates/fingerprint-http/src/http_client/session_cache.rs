@@ -0,0 +1,440 @@
+//! TLS session resumption
+//!
+//! Every request builds a fresh rustls `ClientConfig`, and with it a fresh
+//! in-memory session store, so tickets a server issued were thrown away with the
+//! connection and PSK profiles never actually resumed. `TlsSessionCache` is
+//! shared by all connections of a client instead: it keeps the tickets each host
+//! issued and hands one back on the next handshake, so the ClientHello carries a
+//! real pre_shared_key (rustls appends it last and computes the binders).
+//!
+//! Tickets are single use, as in browsers: a resumed handshake consumes one and
+//! the server usually issues fresh ones. Profiles that declare early_data also
+//! send idempotent requests as 0-RTT data when the ticket allows it; a rejected
+//! attempt is resent after the handshake.
+
+use std::collections::HashMap;
+#[cfg(any(feature = "rustls-tls", feature = "http2", feature = "http3"))]
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Tickets kept per host (rustls' own store keeps the same number)
+const DEFAULT_TICKETS_PER_HOST: usize = 8;
+/// Hosts kept before the least recently used one is evicted
+const DEFAULT_MAX_HOSTS: usize = 256;
+
+/// Counters of a [`TlsSessionCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionCacheStats {
+    /// Tickets received from servers
+    pub tickets_stored: u64,
+    /// Handshakes that found a cached session for their host
+    pub resumptions_offered: u64,
+    /// Handshakes that found no ticket for their host
+    pub misses: u64,
+    /// Requests sent as 0-RTT early data
+    pub early_data_sent: u64,
+    /// 0-RTT requests the server accepted
+    pub early_data_accepted: u64,
+}
+
+#[derive(Default)]
+struct HostSessions {
+    #[cfg(any(feature = "rustls-tls", feature = "http2", feature = "http3"))]
+    tls13: VecDeque<rustls::client::Tls13ClientSessionValue>,
+    #[cfg(any(feature = "rustls-tls", feature = "http2", feature = "http3"))]
+    tls12: Option<rustls::client::Tls12ClientSessionValue>,
+    #[cfg(any(feature = "rustls-tls", feature = "http2", feature = "http3"))]
+    kx_hint: Option<rustls::NamedGroup>,
+    last_used: u64,
+}
+
+impl HostSessions {
+    fn tls13_count(&self) -> usize {
+        #[cfg(any(feature = "rustls-tls", feature = "http2", feature = "http3"))]
+        {
+            self.tls13.len()
+        }
+        #[cfg(not(any(feature = "rustls-tls", feature = "http2", feature = "http3")))]
+        {
+            0
+        }
+    }
+
+    fn has_ticket(&self) -> bool {
+        #[cfg(any(feature = "rustls-tls", feature = "http2", feature = "http3"))]
+        {
+            !self.tls13.is_empty() || self.tls12.is_some()
+        }
+        #[cfg(not(any(feature = "rustls-tls", feature = "http2", feature = "http3")))]
+        {
+            false
+        }
+    }
+}
+
+struct Sessions {
+    hosts: HashMap<String, HostSessions>,
+    clock: u64,
+}
+
+/// Session tickets per host, shared by every connection of a client
+pub struct TlsSessionCache {
+    sessions: Mutex<Sessions>,
+    max_hosts: usize,
+    tickets_per_host: usize,
+    tickets_stored: AtomicU64,
+    resumptions_offered: AtomicU64,
+    misses: AtomicU64,
+    early_data_sent: AtomicU64,
+    early_data_accepted: AtomicU64,
+}
+
+impl TlsSessionCache {
+    /// Cache holding tickets for up to `max_hosts` hosts
+    pub fn new(max_hosts: usize) -> Self {
+        Self {
+            sessions: Mutex::new(Sessions {
+                hosts: HashMap::new(),
+                clock: 0,
+            }),
+            max_hosts: max_hosts.max(1),
+            tickets_per_host: DEFAULT_TICKETS_PER_HOST,
+            tickets_stored: AtomicU64::new(0),
+            resumptions_offered: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            early_data_sent: AtomicU64::new(0),
+            early_data_accepted: AtomicU64::new(0),
+        }
+    }
+
+    /// Keep at most `n` TLS 1.3 tickets per host (oldest dropped first)
+    pub fn with_tickets_per_host(mut self, n: usize) -> Self {
+        self.tickets_per_host = n.max(1);
+        self
+    }
+
+    /// Whether a session for `host` is cached
+    pub fn has_ticket(&self, host: &str) -> bool {
+        self.sessions
+            .lock()
+            .map(|s| {
+                s.hosts
+                    .get(&host.to_ascii_lowercase())
+                    .is_some_and(HostSessions::has_ticket)
+            })
+            .unwrap_or(false)
+    }
+
+    /// TLS 1.3 tickets cached for `host`
+    pub fn ticket_count(&self, host: &str) -> usize {
+        self.sessions
+            .lock()
+            .map(|s| {
+                s.hosts
+                    .get(&host.to_ascii_lowercase())
+                    .map_or(0, HostSessions::tls13_count)
+            })
+            .unwrap_or(0)
+    }
+
+    /// Hosts with cached state
+    pub fn host_count(&self) -> usize {
+        self.sessions.lock().map(|s| s.hosts.len()).unwrap_or(0)
+    }
+
+    /// Forget everything cached for `host`
+    pub fn remove(&self, host: &str) {
+        if let Ok(mut s) = self.sessions.lock() {
+            s.hosts.remove(&host.to_ascii_lowercase());
+        }
+    }
+
+    /// Forget every session
+    pub fn clear(&self) {
+        if let Ok(mut s) = self.sessions.lock() {
+            s.hosts.clear();
+        }
+    }
+
+    pub fn stats(&self) -> SessionCacheStats {
+        SessionCacheStats {
+            tickets_stored: self.tickets_stored.load(Ordering::Relaxed),
+            resumptions_offered: self.resumptions_offered.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            early_data_sent: self.early_data_sent.load(Ordering::Relaxed),
+            early_data_accepted: self.early_data_accepted.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Count a request sent as early data and whether the server took it
+    #[cfg_attr(not(feature = "rustls-tls"), allow(dead_code))]
+    pub(crate) fn record_early_data(&self, accepted: bool) {
+        self.early_data_sent.fetch_add(1, Ordering::Relaxed);
+        if accepted {
+            self.early_data_accepted.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Run `f` on the entry of `host`, creating it (and evicting the least
+    /// recently used host) if needed
+    #[cfg_attr(
+        not(any(feature = "rustls-tls", feature = "http2", feature = "http3")),
+        allow(dead_code)
+    )]
+    fn with_host<R>(&self, host: String, f: impl FnOnce(&mut HostSessions) -> R) -> Option<R> {
+        let mut s = self.sessions.lock().ok()?;
+        s.clock += 1;
+        let now = s.clock;
+        if !s.hosts.contains_key(&host) && s.hosts.len() >= self.max_hosts {
+            if let Some(oldest) = s
+                .hosts
+                .iter()
+                .min_by_key(|(_, h)| h.last_used)
+                .map(|(k, _)| k.clone())
+            {
+                s.hosts.remove(&oldest);
+            }
+        }
+        let entry = s.hosts.entry(host).or_default();
+        entry.last_used = now;
+        Some(f(entry))
+    }
+
+    /// Run `f` on the existing entry of `host`
+    #[cfg_attr(
+        not(any(feature = "rustls-tls", feature = "http2", feature = "http3")),
+        allow(dead_code)
+    )]
+    fn with_existing<R>(&self, host: &str, f: impl FnOnce(&mut HostSessions) -> R) -> Option<R> {
+        let mut s = self.sessions.lock().ok()?;
+        s.clock += 1;
+        let now = s.clock;
+        let entry = s.hosts.get_mut(host)?;
+        entry.last_used = now;
+        Some(f(entry))
+    }
+}
+
+impl Default for TlsSessionCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_HOSTS)
+    }
+}
+
+impl fmt::Debug for TlsSessionCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // tickets are secrets; only show how many hosts there are
+        f.debug_struct("TlsSessionCache")
+            .field("hosts", &self.host_count())
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// Host part of a server name, as the cache keys it
+#[cfg(any(feature = "rustls-tls", feature = "http2", feature = "http3"))]
+fn host_key(server_name: &rustls::pki_types::ServerName<'_>) -> String {
+    server_name.to_str().to_ascii_lowercase()
+}
+
+#[cfg(any(feature = "rustls-tls", feature = "http2", feature = "http3"))]
+impl rustls::client::ClientSessionStore for TlsSessionCache {
+    fn set_kx_hint(
+        &self,
+        server_name: rustls::pki_types::ServerName<'static>,
+        group: rustls::NamedGroup,
+    ) {
+        self.with_host(host_key(&server_name), |h| h.kx_hint = Some(group));
+    }
+
+    fn kx_hint(
+        &self,
+        server_name: &rustls::pki_types::ServerName<'_>,
+    ) -> Option<rustls::NamedGroup> {
+        self.with_existing(&host_key(server_name), |h| h.kx_hint)
+            .flatten()
+    }
+
+    fn set_tls12_session(
+        &self,
+        server_name: rustls::pki_types::ServerName<'static>,
+        value: rustls::client::Tls12ClientSessionValue,
+    ) {
+        self.tickets_stored.fetch_add(1, Ordering::Relaxed);
+        self.with_host(host_key(&server_name), |h| h.tls12 = Some(value));
+    }
+
+    fn tls12_session(
+        &self,
+        server_name: &rustls::pki_types::ServerName<'_>,
+    ) -> Option<rustls::client::Tls12ClientSessionValue> {
+        let session = self
+            .with_existing(&host_key(server_name), |h| h.tls12.clone())
+            .flatten();
+        let counter = if session.is_some() {
+            &self.resumptions_offered
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        session
+    }
+
+    fn remove_tls12_session(&self, server_name: &rustls::pki_types::ServerName<'static>) {
+        self.with_existing(&host_key(server_name), |h| h.tls12 = None);
+    }
+
+    fn insert_tls13_ticket(
+        &self,
+        server_name: rustls::pki_types::ServerName<'static>,
+        value: rustls::client::Tls13ClientSessionValue,
+    ) {
+        self.tickets_stored.fetch_add(1, Ordering::Relaxed);
+        let max = self.tickets_per_host;
+        self.with_host(host_key(&server_name), |h| {
+            if h.tls13.len() >= max {
+                h.tls13.pop_front();
+            }
+            h.tls13.push_back(value);
+        });
+    }
+
+    fn take_tls13_ticket(
+        &self,
+        server_name: &rustls::pki_types::ServerName<'static>,
+    ) -> Option<rustls::client::Tls13ClientSessionValue> {
+        // newest first: it has the most lifetime left
+        let ticket = self
+            .with_existing(&host_key(server_name), |h| h.tls13.pop_back())
+            .flatten();
+        // a miss falls through to tls12_session, which counts it
+        if ticket.is_some() {
+            self.resumptions_offered.fetch_add(1, Ordering::Relaxed);
+        }
+        ticket
+    }
+}
+
+#[cfg(all(test, feature = "rustls-tls"))]
+mod tests {
+    use super::*;
+    use rustls::client::ClientSessionStore;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+    use rustls::{ClientConnection, HandshakeKind, ServerConnection};
+    use std::io::Write;
+    use std::sync::Arc;
+
+    const CERT: &[u8] = include_bytes!("../../testdata/localhost.cert.der");
+    const KEY: &[u8] = include_bytes!("../../testdata/localhost.key.der");
+
+    fn provider() -> Arc<rustls::crypto::CryptoProvider> {
+        Arc::new(rustls::crypto::ring::default_provider())
+    }
+
+    fn server_config() -> Arc<rustls::ServerConfig> {
+        let key = PrivateKeyDer::try_from(KEY.to_vec()).unwrap();
+        let mut cfg = rustls::ServerConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![CertificateDer::from(CERT.to_vec())], key)
+            .unwrap();
+        // rustls only accepts 0-RTT with stateful (session id) tickets
+        cfg.max_early_data_size = 16 * 1024;
+        Arc::new(cfg)
+    }
+
+    fn client_config(cache: &Arc<TlsSessionCache>) -> Arc<rustls::ClientConfig> {
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(CertificateDer::from(CERT.to_vec())).unwrap();
+        let mut cfg = rustls::ClientConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        cfg.resumption = rustls::client::Resumption::store(cache.clone());
+        cfg.enable_early_data = true;
+        Arc::new(cfg)
+    }
+
+    /// Shuttle records between the two ends until neither has anything to send
+    fn pump(client: &mut ClientConnection, server: &mut ServerConnection) {
+        loop {
+            let mut moved = false;
+            while client.wants_write() {
+                let mut buf = Vec::new();
+                client.write_tls(&mut buf).unwrap();
+                server.read_tls(&mut buf.as_slice()).unwrap();
+                server.process_new_packets().unwrap();
+                moved = true;
+            }
+            while server.wants_write() {
+                let mut buf = Vec::new();
+                server.write_tls(&mut buf).unwrap();
+                client.read_tls(&mut buf.as_slice()).unwrap();
+                client.process_new_packets().unwrap();
+                moved = true;
+            }
+            if !moved {
+                return;
+            }
+        }
+    }
+
+    #[test]
+    fn second_connection_resumes_with_early_data() {
+        let cache = Arc::new(TlsSessionCache::default());
+        let server_config = server_config();
+        let client_config = client_config(&cache);
+        let name = ServerName::try_from("localhost").unwrap();
+
+        let mut client = ClientConnection::new(client_config.clone(), name.clone()).unwrap();
+        let mut server = ServerConnection::new(server_config.clone()).unwrap();
+        pump(&mut client, &mut server);
+        assert_eq!(client.handshake_kind(), Some(HandshakeKind::Full));
+        let issued = cache.ticket_count("LOCALHOST");
+        assert!(issued > 0);
+        assert_eq!(cache.stats().resumptions_offered, 0);
+
+        let mut client = ClientConnection::new(client_config, name).unwrap();
+        client
+            .early_data()
+            .expect("ticket allows early data")
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut server = ServerConnection::new(server_config).unwrap();
+        pump(&mut client, &mut server);
+        assert_eq!(client.handshake_kind(), Some(HandshakeKind::Resumed));
+        assert!(client.is_early_data_accepted());
+
+        // the used ticket is gone, fresh ones arrived with the resumed handshake
+        let stats = cache.stats();
+        assert_eq!(stats.resumptions_offered, 1);
+        assert!(stats.tickets_stored > issued as u64);
+        assert!(cache.has_ticket("localhost"));
+    }
+
+    #[test]
+    fn least_recently_used_host_is_evicted() {
+        let cache = TlsSessionCache::new(2);
+        let host = |h: &str| ServerName::try_from(h.to_string()).unwrap();
+        cache.set_kx_hint(host("a.example"), rustls::NamedGroup::X25519);
+        cache.set_kx_hint(host("b.example"), rustls::NamedGroup::X25519);
+        // touching a makes b the oldest
+        assert!(cache.kx_hint(&host("a.example")).is_some());
+        cache.set_kx_hint(host("c.example"), rustls::NamedGroup::secp256r1);
+
+        assert_eq!(cache.host_count(), 2);
+        assert!(cache.kx_hint(&host("b.example")).is_none());
+        assert_eq!(
+            cache.kx_hint(&host("c.example")),
+            Some(rustls::NamedGroup::secp256r1)
+        );
+
+        cache.remove("A.example");
+        assert_eq!(cache.host_count(), 1);
+        assert!(!cache.has_ticket("c.example"));
+    }
+}
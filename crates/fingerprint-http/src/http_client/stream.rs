@@ -0,0 +1,569 @@
+//! Streaming response bodies
+//!
+//! [`ResponseStream`] returns the status line and headers as soon as they
+//! arrive and leaves the body on the connection. The body is read through
+//! [`Read`] (or [`ResponseStream::for_each_chunk`]), de-framed
+//! (Content-Length, chunked or read-to-close) and decompressed on the fly, so
+//! a large download never has to fit in memory.
+//!
+//! Decoding follows the request: a Content-Encoding the request did not list
+//! in `Accept-Encoding` is rejected instead of being handed out still encoded.
+//! Streamed bodies are bounded by [`ResourceLimits::max_streamed_body_bytes`]
+//! (wire and decoded size) and by the decompression ratio limit.
+
+use super::limits::{InFlightGuard, ResourceLimits};
+use super::{HttpClientError, HttpMethod, HttpRequest, HttpResponse, Result};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Read size while waiting for the header section
+const HEAD_READ_CHUNK: usize = 4096;
+
+/// Longest accepted chunk-size line, extensions included
+const MAX_CHUNK_LINE: u64 = 4096;
+
+/// Response whose body is read incrementally
+pub struct ResponseStream {
+    pub status_code: u16,
+    pub status_text: String,
+    /// Header names are lowercase
+    pub headers: HashMap<String, String>,
+    pub http_version: String,
+    /// Time until the header section arrived
+    pub response_time_ms: u64,
+    body: Box<dyn Read + Send>,
+    _in_flight: Option<InFlightGuard>,
+}
+
+impl std::fmt::Debug for ResponseStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseStream")
+            .field("status_code", &self.status_code)
+            .field("status_text", &self.status_text)
+            .field("headers", &self.headers)
+            .field("http_version", &self.http_version)
+            .field("response_time_ms", &self.response_time_ms)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ResponseStream {
+    /// Read the response head of `request` from an HTTP/1.x connection
+    ///
+    /// Interim 1xx responses are skipped; the body stays unread on `reader`.
+    pub fn from_http1<R>(reader: R, request: &HttpRequest, limits: &ResourceLimits) -> Result<Self>
+    where
+        R: Read + Send + 'static,
+    {
+        let start = Instant::now();
+        let mut reader = reader;
+        let mut pending = Vec::new();
+        let (http_version, status_code, status_text, headers) = loop {
+            let head = read_head(&mut reader, &mut pending, limits)?;
+            let parsed = HttpResponse::parse_head(&head, limits)?;
+            // 101 hands the connection over; other 1xx precede the real response
+            if !(100..200).contains(&parsed.1) || parsed.1 == 101 {
+                break parsed;
+            }
+        };
+        let response_time_ms = start.elapsed().as_millis() as u64;
+
+        let framing = if request.method == HttpMethod::Head
+            || (100..200).contains(&status_code)
+            || status_code == 204
+            || status_code == 304
+        {
+            Framing::Length(0)
+        } else if headers
+            .get("transfer-encoding")
+            .is_some_and(|te| te.to_ascii_lowercase().contains("chunked"))
+        {
+            Framing::Chunked { remaining: 0 }
+        } else if let Some(length) = headers.get("content-length") {
+            let length = length.parse::<u64>().map_err(|_| {
+                HttpClientError::InvalidResponse(format!("invalid Content-Length: {}", length))
+            })?;
+            limits.check_streamed_body(length)?;
+            Framing::Length(length)
+        } else {
+            Framing::UntilEof
+        };
+
+        let wire = Arc::new(AtomicU64::new(0));
+        let framed = FramedBody {
+            inner: BufReader::new(Cursor::new(pending).chain(reader)),
+            framing,
+            wire: wire.clone(),
+            limits: limits.clone(),
+        };
+
+        let encodings = content_encodings(&headers);
+        if let Some(accepted) = accepted_encodings(request) {
+            if let Some(unadvertised) = encodings
+                .iter()
+                .find(|e| !accepted.iter().any(|a| a == *e || a == "*"))
+            {
+                return Err(HttpClientError::InvalidResponse(format!(
+                    "server used Content-Encoding {} not offered in Accept-Encoding",
+                    unadvertised
+                )));
+            }
+        }
+        let mut body: Box<dyn Read + Send> = Box::new(framed);
+        // applied in listed order, so undone in reverse
+        for encoding in encodings.iter().rev() {
+            body = decoder(encoding, body)?;
+        }
+        let body = Box::new(BoundedBody {
+            inner: body,
+            wire,
+            decoded: 0,
+            compressed: !encodings.is_empty(),
+            limits: limits.clone(),
+        });
+
+        Ok(Self {
+            status_code,
+            status_text,
+            headers,
+            http_version,
+            response_time_ms,
+            body,
+            _in_flight: None,
+        })
+    }
+
+    /// Keep an in-flight slot until the stream is dropped
+    pub(crate) fn with_in_flight(mut self, guard: InFlightGuard) -> Self {
+        self._in_flight = Some(guard);
+        self
+    }
+
+    /// Checkwhethersuccess
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status_code)
+    }
+
+    /// Get header (lowercase name)
+    pub fn get_header(&self, key: &str) -> Option<&String> {
+        self.headers.get(key)
+    }
+
+    /// Pass the decoded body to `f` chunk by chunk, returning the byte count
+    pub fn for_each_chunk<F>(&mut self, mut f: F) -> Result<u64>
+    where
+        F: FnMut(&[u8]) -> io::Result<()>,
+    {
+        let mut buf = vec![0u8; 16 * 1024];
+        let mut total = 0u64;
+        loop {
+            let n = match self.body.read(&mut buf) {
+                Ok(0) => return Ok(total),
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            f(&buf[..n])?;
+            total += n as u64;
+        }
+    }
+
+    /// Read the rest of the body into a buffered [`HttpResponse`]
+    pub fn into_response(mut self) -> Result<HttpResponse> {
+        let mut body = Vec::new();
+        self.body.read_to_end(&mut body)?;
+        Ok(HttpResponse {
+            status_code: self.status_code,
+            status_text: self.status_text,
+            headers: self.headers,
+            body,
+            http_version: self.http_version,
+            response_time_ms: self.response_time_ms,
+        })
+    }
+}
+
+impl Read for ResponseStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.body.read(buf)
+    }
+}
+
+/// Read up to the blank line ending a header section
+///
+/// `pending` holds bytes already received; on return it holds the bytes
+/// after the header section.
+fn read_head<R: Read>(
+    reader: &mut R,
+    pending: &mut Vec<u8>,
+    limits: &ResourceLimits,
+) -> Result<Vec<u8>> {
+    // status line and separators come on top of the header field budget
+    let max_head = limits.max_header_bytes.saturating_add(HEAD_READ_CHUNK);
+    let mut searched = 0;
+    let mut tmp = [0u8; HEAD_READ_CHUNK];
+    loop {
+        if let Some(pos) = pending[searched..]
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+        {
+            let end = searched + pos;
+            let head = pending[..end].to_vec();
+            pending.drain(..end + 4);
+            return Ok(head);
+        }
+        searched = pending.len().saturating_sub(3);
+
+        if pending.len() > max_head {
+            return Err(super::limits::LimitError::HeadersTooLarge {
+                size: pending.len(),
+                limit: limits.max_header_bytes,
+            }
+            .record()
+            .into());
+        }
+        let n = reader.read(&mut tmp)?;
+        if n == 0 {
+            return Err(HttpClientError::InvalidResponse(
+                "connection closed before the response headers ended".to_string(),
+            ));
+        }
+        pending.extend_from_slice(&tmp[..n]);
+    }
+}
+
+/// Lowercase content codings, in the order they were applied
+fn content_encodings(headers: &HashMap<String, String>) -> Vec<String> {
+    headers
+        .get("content-encoding")
+        .map(|value| {
+            value
+                .split(',')
+                .map(|e| e.trim().to_ascii_lowercase())
+                .filter(|e| !e.is_empty() && e != "identity")
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Codings the request offered, or `None` when it sent no `Accept-Encoding`
+fn accepted_encodings(request: &HttpRequest) -> Option<Vec<String>> {
+    let (_, value) = request
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("accept-encoding"))?;
+    Some(
+        value
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let coding = parts.next()?.trim().to_ascii_lowercase();
+                let refused = parts.any(|p| {
+                    p.trim()
+                        .strip_prefix("q=")
+                        .and_then(|q| q.trim().parse::<f32>().ok())
+                        .is_some_and(|q| q <= 0.0)
+                });
+                (!coding.is_empty() && !refused).then_some(coding)
+            })
+            .collect(),
+    )
+}
+
+/// Streaming decoder for one content coding
+fn decoder(encoding: &str, inner: Box<dyn Read + Send>) -> Result<Box<dyn Read + Send>> {
+    match encoding {
+        #[cfg(feature = "compression")]
+        "gzip" | "x-gzip" => Ok(Box::new(flate2::read::GzDecoder::new(inner))),
+        #[cfg(feature = "compression")]
+        "deflate" => Ok(Box::new(flate2::read::DeflateDecoder::new(inner))),
+        #[cfg(feature = "compression")]
+        "br" => Ok(Box::new(brotli_decompressor::Decompressor::new(
+            inner, 4096,
+        ))),
+        #[cfg(feature = "compression")]
+        "zstd" => Ok(Box::new(zstd::stream::read::Decoder::new(inner)?)),
+        _ => {
+            let _ = inner;
+            Err(HttpClientError::InvalidResponse(
+                fingerprint_core::i18n::tr("http.unsupported_encoding", &[("encoding", &encoding)]),
+            ))
+        }
+    }
+}
+
+/// Message framing of the body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Framing {
+    /// Bytes left of a Content-Length body
+    Length(u64),
+    /// Bytes left of the current chunk (0: a size line comes next)
+    Chunked { remaining: u64 },
+    /// Body ends when the connection closes
+    UntilEof,
+    /// Body fully read
+    Done,
+}
+
+/// Removes the HTTP/1.1 framing, counting wire bytes
+struct FramedBody<R> {
+    inner: R,
+    framing: Framing,
+    wire: Arc<AtomicU64>,
+    limits: ResourceLimits,
+}
+
+impl<R: BufRead> FramedBody<R> {
+    fn count(&self, n: usize) -> io::Result<()> {
+        let total = self.wire.fetch_add(n as u64, Ordering::Relaxed) + n as u64;
+        self.limits
+            .check_streamed_body(total)
+            .map_err(io::Error::other)
+    }
+
+    /// Read a chunk-size line, or the trailer section after the last chunk
+    fn next_chunk_size(&mut self) -> io::Result<u64> {
+        let line = self.read_line()?;
+        let size = line.split(';').next().unwrap_or("").trim();
+        let size = u64::from_str_radix(size, 16).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid chunk size '{}'", size),
+            )
+        })?;
+        if size == 0 {
+            // trailer fields up to the blank line are dropped
+            while !self.read_line()?.is_empty() {}
+        }
+        Ok(size)
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = Vec::new();
+        (&mut self.inner)
+            .take(MAX_CHUNK_LINE)
+            .read_until(b'\n', &mut line)?;
+        if line.last() != Some(&b'\n') {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Invalid chunked encoding: missing CRLF",
+            ));
+        }
+        Ok(String::from_utf8_lossy(&line).trim_end().to_string())
+    }
+}
+
+impl<R: BufRead> Read for FramedBody<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match self.framing {
+                Framing::Done | Framing::Length(0) => {
+                    self.framing = Framing::Done;
+                    return Ok(0);
+                }
+                Framing::Length(remaining) => {
+                    let max = buf.len().min(remaining.try_into().unwrap_or(usize::MAX));
+                    let n = self.inner.read(&mut buf[..max])?;
+                    if n == 0 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            format!("body ended {} bytes before Content-Length", remaining),
+                        ));
+                    }
+                    self.framing = Framing::Length(remaining - n as u64);
+                    self.count(n)?;
+                    return Ok(n);
+                }
+                Framing::UntilEof => {
+                    let n = self.inner.read(buf)?;
+                    if n == 0 {
+                        self.framing = Framing::Done;
+                    }
+                    self.count(n)?;
+                    return Ok(n);
+                }
+                Framing::Chunked { remaining: 0 } => match self.next_chunk_size()? {
+                    0 => self.framing = Framing::Done,
+                    size => self.framing = Framing::Chunked { remaining: size },
+                },
+                Framing::Chunked { remaining } => {
+                    let max = buf.len().min(remaining.try_into().unwrap_or(usize::MAX));
+                    let n = self.inner.read(&mut buf[..max])?;
+                    if n == 0 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "connection closed inside a chunk",
+                        ));
+                    }
+                    let remaining = remaining - n as u64;
+                    if remaining == 0 && !self.read_line()?.is_empty() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "Invalid chunked encoding: missing CRLF after chunk data",
+                        ));
+                    }
+                    self.framing = Framing::Chunked { remaining };
+                    self.count(n)?;
+                    return Ok(n);
+                }
+            }
+        }
+    }
+}
+
+/// Applies the streamed size and ratio limits to the decoded body
+struct BoundedBody {
+    inner: Box<dyn Read + Send>,
+    wire: Arc<AtomicU64>,
+    decoded: u64,
+    compressed: bool,
+    limits: ResourceLimits,
+}
+
+impl Read for BoundedBody {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.decoded += n as u64;
+        self.limits
+            .check_streamed_body(self.decoded)
+            .map_err(io::Error::other)?;
+        if self.compressed {
+            self.limits
+                .check_streamed_ratio(self.wire.load(Ordering::Relaxed), self.decoded)
+                .map_err(io::Error::other)?;
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::LimitError;
+
+    fn get() -> HttpRequest {
+        HttpRequest::new(HttpMethod::Get, "http://example.com/")
+            .with_header("Accept-Encoding", "gzip, deflate, br, zstd")
+    }
+
+    fn stream(
+        raw: Vec<u8>,
+        request: &HttpRequest,
+        limits: &ResourceLimits,
+    ) -> Result<ResponseStream> {
+        ResponseStream::from_http1(Cursor::new(raw), request, limits)
+    }
+
+    #[test]
+    fn test_streams_framed_bodies() {
+        let limits = ResourceLimits::default();
+
+        // interim 100 Continue, then a chunked body split across reads
+        let raw = b"HTTP/1.1 100 Continue\r\n\r\n\
+HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+4;ext=1\r\nWiki\r\n5\r\npedia\r\n0\r\nX-Trailer: 1\r\n\r\n"
+            .to_vec();
+        let mut response = stream(raw, &get(), &limits).unwrap();
+        assert_eq!(response.status_code, 200);
+        let mut chunks = Vec::new();
+        let total = response
+            .for_each_chunk(|chunk| {
+                chunks.extend_from_slice(chunk);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(total, 9);
+        assert_eq!(chunks, b"Wikipedia");
+
+        // Content-Length ignores bytes after the body; HEAD has no body
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhelloEXTRA".to_vec();
+        let response = stream(raw.clone(), &get(), &limits).unwrap();
+        assert_eq!(response.into_response().unwrap().body, b"hello");
+        let head = HttpRequest::new(HttpMethod::Head, "http://example.com/");
+        let response = stream(raw, &head, &limits).unwrap();
+        assert!(response.into_response().unwrap().body.is_empty());
+
+        // a truncated body is an error, not a short read
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nhello".to_vec();
+        let response = stream(raw, &get(), &limits).unwrap();
+        assert!(response.into_response().is_err());
+    }
+
+    #[test]
+    fn test_streamed_body_limit() {
+        let limits = ResourceLimits {
+            max_streamed_body_bytes: 8,
+            ..Default::default()
+        };
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n".to_vec();
+        assert!(matches!(
+            stream(raw, &get(), &limits),
+            Err(HttpClientError::LimitExceeded(
+                LimitError::ResponseBodyTooLarge { .. }
+            ))
+        ));
+
+        let mut raw = b"HTTP/1.1 200 OK\r\n\r\n".to_vec();
+        raw.extend_from_slice(&[b'x'; 64]);
+        let response = stream(raw, &get(), &limits).unwrap();
+        assert!(matches!(
+            response.into_response(),
+            Err(HttpClientError::LimitExceeded(
+                LimitError::ResponseBodyTooLarge { .. }
+            ))
+        ));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_decodes_advertised_encodings() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let text = b"streamed fingerprint body ".repeat(100);
+        let limits = ResourceLimits::default();
+        let with_body = |encoding: &str, body: &[u8]| {
+            let mut raw = format!(
+                "HTTP/1.1 200 OK\r\nContent-Encoding: {}\r\nContent-Length: {}\r\n\r\n",
+                encoding,
+                body.len()
+            )
+            .into_bytes();
+            raw.extend_from_slice(body);
+            raw
+        };
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&text).unwrap();
+        let gz = encoder.finish().unwrap();
+        let response = stream(with_body("gzip", &gz), &get(), &limits).unwrap();
+        assert_eq!(response.into_response().unwrap().body, text);
+
+        let zst = zstd::stream::encode_all(&text[..], 3).unwrap();
+        let response = stream(with_body("zstd", &zst), &get(), &limits).unwrap();
+        assert_eq!(response.into_response().unwrap().body, text);
+
+        // codings the request did not offer are refused
+        let gzip_only = HttpRequest::new(HttpMethod::Get, "http://example.com/")
+            .with_header("accept-encoding", "gzip, br;q=0");
+        assert!(stream(with_body("zstd", &zst), &gzip_only, &limits).is_err());
+
+        // bombs are cut off by the ratio limit while streaming
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&vec![0u8; 8 * 1024 * 1024]).unwrap();
+        let bomb = encoder.finish().unwrap();
+        let response = stream(with_body("gzip", &bomb), &get(), &limits).unwrap();
+        assert!(matches!(
+            response.into_response(),
+            Err(HttpClientError::LimitExceeded(
+                LimitError::DecompressionRatio { .. }
+            ))
+        ));
+    }
+}
@@ -0,0 +1,63 @@
+//! Tracing spans (`otel` feature)
+//!
+//! The client records `tracing` spans (target `fingerprint_http`) for each request,
+//! each attempt or redirect hop, DNS resolution, TCP connect and the TLS or QUIC
+//! handshake. Field names follow the OpenTelemetry semantic conventions, so a
+//! `tracing-opentelemetry` layer exports them as they are. Without the feature the
+//! call sites compile to no-ops.
+//!
+//! Trace context propagation is separate: see
+//! [`HttpClientConfig::propagate_trace_context`](super::HttpClientConfig::propagate_trace_context).
+
+#[cfg(feature = "otel")]
+pub(crate) use tracing::{Instrument, Span};
+
+/// Stand-in for `tracing::Span`
+#[cfg(not(feature = "otel"))]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Span;
+
+#[cfg(not(feature = "otel"))]
+impl Span {
+    pub(crate) fn entered(self) -> Self {
+        self
+    }
+
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        f()
+    }
+
+    pub(crate) fn record<V>(&self, _field: &str, _value: V) -> &Self {
+        self
+    }
+}
+
+/// Stand-in for `tracing::Instrument`
+#[cfg(not(feature = "otel"))]
+pub(crate) trait Instrument: Sized {
+    fn instrument(self, _span: Span) -> Self {
+        self
+    }
+}
+
+#[cfg(not(feature = "otel"))]
+impl<F: std::future::Future> Instrument for F {}
+
+/// Info-level span taking `tracing` field syntax; a no-op [`Span`] without `otel`
+macro_rules! span {
+    ($name:literal $(, $($fields:tt)*)?) => {{
+        #[cfg(feature = "otel")]
+        let span = tracing::info_span!(target: "fingerprint_http", $name $(, $($fields)*)?);
+        #[cfg(not(feature = "otel"))]
+        let span = $crate::http_client::telemetry::Span;
+        span
+    }};
+}
+
+pub(crate) use span;
+
+#[cfg_attr(not(feature = "otel"), allow(dead_code))]
+/// `url` without its query string and fragment, which may carry credentials
+pub(crate) fn url_without_query(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
@@ -4,9 +4,8 @@
 //! through ClientHelloCustomizer applicationbrowserfingerprint (Chrome, Firefox, Safari etc.)
 //! simulatemarket maturebrowser TLS fingerprint, 不customselffingerprint
 
+use super::telemetry::span;
 use super::{HttpClientConfig, HttpClientError, HttpRequest, HttpResponse, Result};
-use std::io::Write;
-use std::net::TcpStream;
 #[allow(unused_imports)]
 use std::sync::Arc;
 
@@ -41,83 +40,154 @@ pub fn send_https_request(
     request: &HttpRequest,
     config: &HttpClientConfig,
 ) -> Result<HttpResponse> {
-    // use rustls, if configuration了 profile, willautomaticthrough ClientHelloCustomizer applicationbrowserfingerprint
-
-    // establish TCP connection
-    let addr = format!("{}:{}", host, port);
-    let tcp_stream = TcpStream::connect(&addr).map_err(|e| {
-        HttpClientError::ConnectionFailed(format!("Connection failed {}: {}", addr, e))
-    })?;
+    #[cfg(feature = "rustls-tls")]
+    {
+        let mut tls_stream = open_https_request(host, port, path, request, config)?;
 
-    // settingstimeout
-    tcp_stream
-        .set_read_timeout(Some(config.read_timeout))
-        .map_err(HttpClientError::Io)?;
-    tcp_stream
-        .set_write_timeout(Some(config.write_timeout))
-        .map_err(HttpClientError::Io)?;
+        // readresponse
+        let buffer = super::io::read_http1_response_bytes(
+            &mut tls_stream,
+            config.limits.max_response_body_bytes,
+        )
+        .map_err(HttpClientError::from)?;
 
-    // useofficial rustls, through ClientHelloCustomizer applicationbrowserfingerprint
+        // Parseresponse
+        HttpResponse::parse_with_limits(&buffer, &config.limits)
+    }
 
-    #[cfg(feature = "rustls-tls")]
+    #[cfg(not(feature = "rustls-tls"))]
     {
-        use rustls::pki_types::ServerName;
-        use std::sync::Arc;
+        let _ = (host, port, path, request, config);
+        Err(HttpClientError::TlsError(
+            "needenabled rustls-tls Features".to_string(),
+        ))
+    }
+}
+
+/// Connect, handshake and send an HTTP/1.1 request, leaving the response unread
+///
+/// useofficial rustls asbottomlayer TLS implement; if configuration了 profile, willautomaticthrough
+/// ClientHelloCustomizer applicationbrowserfingerprint
+#[cfg(feature = "rustls-tls")]
+pub(crate) fn open_https_request(
+    host: &str,
+    port: u16,
+    path: &str,
+    request: &HttpRequest,
+    config: &HttpClientConfig,
+) -> Result<rustls::StreamOwned<rustls::ClientConnection, std::net::TcpStream>> {
+    use super::ech::{EchAttempt, EchPolicy, EchResolver};
+    use rustls::pki_types::ServerName;
+    use std::io::Write;
 
-        // Build TLS configuration (尊重 verify_tls)
-        let tls_config = super::rustls_utils::build_client_config(
-            config.verify_tls,
-            Vec::new(),
-            config.profile.as_ref(),
+    let server_name = ServerName::try_from(host.to_string())
+        .map_err(|_| HttpClientError::TlsError("Invalid server name".to_string()))?;
+
+    // Fix: Add Cookie to request ( if exists)
+    let mut request_with_cookies = request.clone();
+    if let Some(cookie_store) = &config.cookie_store {
+        super::request::add_cookies_to_request(
+            &mut request_with_cookies,
+            cookie_store,
+            host,
+            path,
+            true, // HTTPS is securityconnection
         );
+    }
 
-        let server_name = ServerName::try_from(host.to_string())
-            .map_err(|_| HttpClientError::TlsError("Invalid server name".to_string()))?;
+    // useChromestandardheader顺序configure
+    let header_order = Some(fingerprint_headers::chrome_header_order());
+    let http_request =
+        request_with_cookies.build_http1_request_bytes(host, path, header_order.as_deref());
 
-        let conn =
-            rustls::ClientConnection::new(Arc::new(tls_config), server_name).map_err(|e| {
+    let policy = EchPolicy::of(config);
+    let resolver = EchResolver::global();
+    let mut attempt = EchAttempt::plan(policy, host, resolver)?;
+    let mut fallbacks = 0;
+
+    let (mut tls_stream, early) = loop {
+        // establish TCP connection
+        let tcp_stream = super::proxy::connect_tcp(config, host, port)?;
+
+        // settingstimeout
+        tcp_stream
+            .set_read_timeout(Some(config.read_timeout))
+            .map_err(HttpClientError::Io)?;
+        tcp_stream
+            .set_write_timeout(Some(config.write_timeout))
+            .map_err(HttpClientError::Io)?;
+
+        // Build TLS configuration (尊重 verify_tls, ECH per attempt)
+        let tls_config = attempt.client_config(config, Vec::new())?;
+
+        let conn = rustls::ClientConnection::new(Arc::new(tls_config), server_name.clone())
+            .map_err(|e| {
                 HttpClientError::TlsError(format!("TLS connectionCreatefailure: {}", e))
             })?;
 
         let mut tls_stream = rustls::StreamOwned::new(conn, tcp_stream);
-
-        // Fix: Add Cookie to request ( if exists)
-        let mut request_with_cookies = request.clone();
-        if let Some(cookie_store) = &config.cookie_store {
-            super::request::add_cookies_to_request(
-                &mut request_with_cookies,
-                cookie_store,
-                host,
-                path,
-                true, // HTTPS is securityconnection
-            );
+        let early = write_early_data(&mut tls_stream.conn, request, &http_request);
+
+        // handshake before writing, so an ECH fallback can start over on a fresh connection
+        let handshake = {
+            let _span = span!(
+                "tls.handshake",
+                server.address = host,
+                tls.ech_fallbacks = fallbacks
+            )
+            .entered();
+            tls_stream.conn.complete_io(&mut tls_stream.sock)
+        };
+        match handshake {
+            Ok(_) => break (tls_stream, early),
+            Err(e) => match attempt.fallback(policy, host, resolver, &e) {
+                Some(next) if fallbacks < super::ech::MAX_FALLBACKS => {
+                    attempt = next;
+                    fallbacks += 1;
+                }
+                _ => return Err(super::ech::handshake_error(e)),
+            },
         }
+    };
 
-        // useChromestandardheader顺序configure
-        let header_order = Some(fingerprint_headers::chrome_header_order());
-        let http_request =
-            request_with_cookies.build_http1_request_bytes(host, path, header_order.as_deref());
+    let accepted = early && tls_stream.conn.is_early_data_accepted();
+    if early {
+        if let Some(cache) = &config.session_cache {
+            cache.record_early_data(accepted);
+        }
+    }
+    // a rejected 0-RTT request never reached the application; send it again
+    if !accepted {
         tls_stream
             .write_all(&http_request)
             .map_err(HttpClientError::Io)?;
-        tls_stream.flush().map_err(HttpClientError::Io)?;
+    }
+    tls_stream.flush().map_err(HttpClientError::Io)?;
 
-        // readresponse
-        let buffer = super::io::read_http1_response_bytes(
-            &mut tls_stream,
-            super::io::DEFAULT_MAX_RESPONSE_BYTES,
-        )
-        .map_err(HttpClientError::Io)?;
+    Ok(tls_stream)
+}
 
-        // Parseresponse
-        HttpResponse::parse(&buffer).map_err(HttpClientError::InvalidResponse)
-    }
+/// Queue `bytes` as 0-RTT data if the resumed ticket allows all of them
+///
+/// Only safe methods go out early: early data can be replayed by an attacker.
+#[cfg(feature = "rustls-tls")]
+fn write_early_data(
+    conn: &mut rustls::ClientConnection,
+    request: &HttpRequest,
+    bytes: &[u8],
+) -> bool {
+    use super::HttpMethod;
+    use std::io::Write;
 
-    #[cfg(not(feature = "rustls-tls"))]
-    {
-        Err(HttpClientError::TlsError(
-            "needenabled rustls-tls Features".to_string(),
-        ))
+    if !matches!(
+        request.method,
+        HttpMethod::Get | HttpMethod::Head | HttpMethod::Options
+    ) {
+        return false;
+    }
+    match conn.early_data() {
+        Some(mut early) if early.bytes_left() >= bytes.len() => early.write_all(bytes).is_ok(),
+        _ => false,
     }
 }
 
@@ -160,14 +230,14 @@ pub fn send_https_request_with_pool(
     // rustls path and send_https_request keepconsistent
     #[cfg(feature = "rustls-tls")]
     {
+        use super::ech::{EchAttempt, EchPolicy, EchResolver};
         use rustls::pki_types::ServerName;
         use std::sync::Arc;
 
-        let tls_config = super::rustls_utils::build_client_config(
-            config.verify_tls,
-            Vec::new(),
-            config.profile.as_ref(),
-        );
+        // a pooled socket cannot be swapped mid-request; a rejection only updates the ECH cache
+        let policy = EchPolicy::of(config);
+        let attempt = EchAttempt::plan(policy, host, EchResolver::global())?;
+        let tls_config = attempt.client_config(config, Vec::new())?;
         let server_name = ServerName::try_from(host.to_string())
             .map_err(|_| HttpClientError::TlsError("Invalid server name".to_string()))?;
         let conn_tls =
@@ -176,6 +246,14 @@ pub fn send_https_request_with_pool(
             })?;
 
         let mut tls_stream = rustls::StreamOwned::new(conn_tls, tcp_stream);
+        let handshake = {
+            let _span = span!("tls.handshake", server.address = host, pooled = true).entered();
+            tls_stream.conn.complete_io(&mut tls_stream.sock)
+        };
+        if let Err(e) = handshake {
+            attempt.fallback(policy, host, EchResolver::global(), &e);
+            return Err(super::ech::handshake_error(e));
+        }
 
         // Fix: Add Cookie to request ( if exists)
         let mut request_with_cookies = request.clone();
@@ -200,11 +278,11 @@ pub fn send_https_request_with_pool(
 
         let buffer = super::io::read_http1_response_bytes(
             &mut tls_stream,
-            super::io::DEFAULT_MAX_RESPONSE_BYTES,
+            config.limits.max_response_body_bytes,
         )
-        .map_err(HttpClientError::Io)?;
+        .map_err(HttpClientError::from)?;
 
-        HttpResponse::parse(&buffer).map_err(HttpClientError::InvalidResponse)
+        HttpResponse::parse_with_limits(&buffer, &config.limits)
     }
 
     #[cfg(not(feature = "rustls-tls"))]
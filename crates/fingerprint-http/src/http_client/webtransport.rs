@@ -0,0 +1,830 @@
+//! WebTransport over HTTP/3 (experimental)
+//!
+//! Client for draft-ietf-webtrans-http3 sessions, written directly on quinn: the
+//! pinned h3 crates predate extended CONNECT and HTTP/3 datagrams. It speaks just
+//! enough HTTP/3 for one session per connection: its own control stream and
+//! SETTINGS, an extended CONNECT encoded with the QPACK static table only, and the
+//! WebTransport stream and datagram framing.
+//!
+//! Servers that do not advertise extended CONNECT and WebTransport in their
+//! SETTINGS are refused with [`HttpClientError::WebTransportError`] before the
+//! CONNECT is sent, so callers can fall back to plain HTTP/2 or HTTP/3 requests.
+//!
+//! Only compiled with the `experimental-webtransport` feature.
+
+use super::h3_datagram::{self, get_varint, put_varint, SETTINGS_H3_DATAGRAM};
+use super::{HttpClient, HttpClientError, Result};
+use bytes::{BufMut, Bytes, BytesMut};
+use quinn::{Connection, Endpoint, RecvStream, SendStream, TransportConfig, VarInt};
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+const STREAM_CONTROL: u64 = 0x00;
+const STREAM_WT_UNI: u64 = 0x54;
+const FRAME_DATA: u64 = 0x00;
+const FRAME_HEADERS: u64 = 0x01;
+const FRAME_SETTINGS: u64 = 0x04;
+const WT_BIDI_SIGNAL: u64 = 0x41;
+
+const SETTINGS_ENABLE_CONNECT_PROTOCOL: u64 = 0x08;
+/// `SETTINGS_H3_DATAGRAM` before RFC 9297, still sent by draft-02 peers
+const SETTINGS_H3_DATAGRAM_DRAFT04: u64 = 0xff_d277;
+/// WebTransport drafts 02 to 06
+const SETTINGS_ENABLE_WEBTRANSPORT: u64 = 0x2b60_3742;
+/// WebTransport draft 07 onwards
+const SETTINGS_WT_MAX_SESSIONS: u64 = 0xc671_706a;
+const CAPSULE_CLOSE_SESSION: u64 = 0x2843;
+
+const CLIENT_SETTINGS: [(u64, u64); 5] = [
+    (SETTINGS_ENABLE_CONNECT_PROTOCOL, 1),
+    (SETTINGS_H3_DATAGRAM, 1),
+    (SETTINGS_H3_DATAGRAM_DRAFT04, 1),
+    (SETTINGS_ENABLE_WEBTRANSPORT, 1),
+    (SETTINGS_WT_MAX_SESSIONS, 1),
+];
+
+/// Largest frame accepted on the control and CONNECT streams
+const MAX_FRAME_LEN: u64 = 64 * 1024;
+/// Server-initiated streams queued before the application accepts them
+const INCOMING_QUEUE: usize = 16;
+
+// QPACK static table (RFC 9204 appendix A)
+const QPACK_AUTHORITY: u64 = 0;
+const QPACK_PATH: u64 = 1;
+const QPACK_METHOD_CONNECT: u64 = 15;
+const QPACK_SCHEME_HTTPS: u64 = 23;
+const QPACK_USER_AGENT: u64 = 95;
+
+fn wt_error(message: impl std::fmt::Display) -> HttpClientError {
+    HttpClientError::WebTransportError(message.to_string())
+}
+
+/// What the server's SETTINGS frame offers
+#[derive(Debug, Default, Clone, Copy)]
+struct PeerSettings {
+    connect_protocol: bool,
+    datagrams: bool,
+    webtransport: bool,
+}
+
+impl PeerSettings {
+    fn parse(mut payload: &[u8]) -> Result<Self> {
+        let mut settings = Self::default();
+        while !payload.is_empty() {
+            let (Some(id), Some(value)) = (get_varint(&mut payload), get_varint(&mut payload))
+            else {
+                return Err(wt_error("truncated SETTINGS frame"));
+            };
+            match id {
+                SETTINGS_ENABLE_CONNECT_PROTOCOL => settings.connect_protocol = value == 1,
+                SETTINGS_H3_DATAGRAM | SETTINGS_H3_DATAGRAM_DRAFT04 => {
+                    settings.datagrams |= value == 1
+                }
+                SETTINGS_ENABLE_WEBTRANSPORT => settings.webtransport |= value == 1,
+                SETTINGS_WT_MAX_SESSIONS => settings.webtransport |= value > 0,
+                _ => {}
+            }
+        }
+        Ok(settings)
+    }
+}
+
+fn put_frame(buf: &mut BytesMut, frame_type: u64, payload: &[u8]) {
+    put_varint(buf, frame_type);
+    put_varint(buf, payload.len() as u64);
+    buf.put_slice(payload);
+}
+
+fn settings_frame(settings: &[(u64, u64)]) -> BytesMut {
+    let mut payload = BytesMut::new();
+    for &(id, value) in settings {
+        put_varint(&mut payload, id);
+        put_varint(&mut payload, value);
+    }
+    let mut frame = BytesMut::new();
+    put_frame(&mut frame, FRAME_SETTINGS, &payload);
+    frame
+}
+
+async fn read_varint(recv: &mut RecvStream) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    recv.read_exact(&mut buf[..1]).await.map_err(wt_error)?;
+    let len = 1 << (buf[0] >> 6);
+    recv.read_exact(&mut buf[1..len]).await.map_err(wt_error)?;
+    get_varint(&mut &buf[..len]).ok_or_else(|| wt_error("malformed varint"))
+}
+
+async fn read_frame(recv: &mut RecvStream) -> Result<(u64, Vec<u8>)> {
+    let frame_type = read_varint(recv).await?;
+    let len = read_varint(recv).await?;
+    if len > MAX_FRAME_LEN {
+        return Err(wt_error(format!("frame of {} bytes", len)));
+    }
+    let mut payload = vec![0u8; len as usize];
+    recv.read_exact(&mut payload).await.map_err(wt_error)?;
+    Ok((frame_type, payload))
+}
+
+/// Read and discard a stream the client has no use for
+async fn drain(mut recv: RecvStream) {
+    while let Ok(Some(_)) = recv.read_chunk(usize::MAX, true).await {}
+}
+
+// Just enough QPACK (RFC 9204) for one request and its response status
+
+fn put_prefixed_int(buf: &mut BytesMut, flags: u8, prefix_bits: u8, value: u64) {
+    let max = (1u64 << prefix_bits) - 1;
+    if value < max {
+        buf.put_u8(flags | value as u8);
+        return;
+    }
+    buf.put_u8(flags | max as u8);
+    let mut rest = value - max;
+    while rest >= 0x80 {
+        buf.put_u8(rest as u8 | 0x80);
+        rest >>= 7;
+    }
+    buf.put_u8(rest as u8);
+}
+
+fn get_prefixed_int(buf: &mut &[u8], prefix_bits: u8) -> Option<u64> {
+    let (&first, rest) = buf.split_first()?;
+    *buf = rest;
+    let max = (1u64 << prefix_bits) - 1;
+    let mut value = u64::from(first) & max;
+    if value < max {
+        return Some(value);
+    }
+    for shift in (0..63).step_by(7) {
+        let (&byte, rest) = buf.split_first()?;
+        *buf = rest;
+        value = value.checked_add(u64::from(byte & 0x7f) << shift)?;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// String literal without Huffman coding
+fn put_string(buf: &mut BytesMut, flags: u8, prefix_bits: u8, value: &str) {
+    put_prefixed_int(buf, flags, prefix_bits, value.len() as u64);
+    buf.put_slice(value.as_bytes());
+}
+
+/// String literal and whether it is Huffman coded (the bit above the length prefix)
+fn get_string<'a>(buf: &mut &'a [u8], prefix_bits: u8) -> Option<(bool, &'a [u8])> {
+    let huffman = buf.first()? & (1 << prefix_bits) != 0;
+    let len = get_prefixed_int(buf, prefix_bits)? as usize;
+    if buf.len() < len {
+        return None;
+    }
+    let (value, rest) = buf.split_at(len);
+    *buf = rest;
+    Some((huffman, value))
+}
+
+/// Extended CONNECT field section, static table references only
+fn encode_connect(authority: &str, path: &str, user_agent: &str) -> BytesMut {
+    let mut block = BytesMut::new();
+    // Required Insert Count 0, Base 0: no dynamic table
+    block.put_slice(&[0x00, 0x00]);
+    put_prefixed_int(&mut block, 0xc0, 6, QPACK_METHOD_CONNECT);
+    put_prefixed_int(&mut block, 0xc0, 6, QPACK_SCHEME_HTTPS);
+    put_prefixed_int(&mut block, 0x50, 4, QPACK_AUTHORITY);
+    put_string(&mut block, 0x00, 7, authority);
+    put_prefixed_int(&mut block, 0x50, 4, QPACK_PATH);
+    put_string(&mut block, 0x00, 7, path);
+    put_string(&mut block, 0x20, 3, ":protocol");
+    put_string(&mut block, 0x00, 7, "webtransport");
+    if !user_agent.is_empty() {
+        put_prefixed_int(&mut block, 0x50, 4, QPACK_USER_AGENT);
+        put_string(&mut block, 0x00, 7, user_agent);
+    }
+    put_string(&mut block, 0x20, 3, "sec-webtransport-http3-draft02");
+    put_string(&mut block, 0x00, 7, "1");
+    block
+}
+
+/// `:status` entries of the QPACK static table
+fn static_status(index: u64) -> Option<u16> {
+    Some(match index {
+        24 => 103,
+        25 => 200,
+        26 => 304,
+        27 => 404,
+        28 => 503,
+        63 => 100,
+        64 => 204,
+        65 => 206,
+        66 => 302,
+        67 => 400,
+        68 => 403,
+        69 => 421,
+        70 => 425,
+        71 => 500,
+        _ => return None,
+    })
+}
+
+/// Decode Huffman-coded ASCII digits (RFC 7541 appendix B), all a status needs
+fn huffman_digits(data: &[u8]) -> Option<String> {
+    let mut digits = String::new();
+    let (mut bits, mut len) = (0u64, 0u32);
+    for &byte in data {
+        bits = (bits << 8) | u64::from(byte);
+        len += 8;
+        loop {
+            // '0'..'2' are 00000..00010, '3'..'9' are 011001..011111
+            if len >= 5 && (bits >> (len - 5)) & 0x1f <= 2 {
+                digits.push(char::from(b'0' + ((bits >> (len - 5)) & 0x1f) as u8));
+                len -= 5;
+            } else if len >= 6 && (0x19..=0x1f).contains(&((bits >> (len - 6)) & 0x3f)) {
+                digits.push(char::from(b'3' + ((bits >> (len - 6)) & 0x3f) as u8 - 0x19));
+                len -= 6;
+            } else {
+                break;
+            }
+            bits &= (1 << len) - 1;
+        }
+        if len >= 16 {
+            return None;
+        }
+    }
+    // what is left must be padding: fewer than 8 bits, all ones
+    (len < 8 && bits == (1 << len) - 1).then_some(digits)
+}
+
+fn parse_status(huffman: bool, value: &[u8]) -> Result<u16> {
+    let text = if huffman {
+        huffman_digits(value).ok_or_else(|| wt_error("malformed :status"))?
+    } else {
+        String::from_utf8_lossy(value).into_owned()
+    };
+    text.parse()
+        .map_err(|_| wt_error(format!("malformed :status {:?}", text)))
+}
+
+/// `:status` of a response field section
+///
+/// The client advertises no dynamic table, so any reference to one is an error.
+fn decode_status(mut block: &[u8]) -> Result<u16> {
+    let truncated = || wt_error("truncated response field section");
+    let required_insert_count = get_prefixed_int(&mut block, 8).ok_or_else(truncated)?;
+    get_prefixed_int(&mut block, 7).ok_or_else(truncated)?;
+    if required_insert_count != 0 {
+        return Err(wt_error("response references the QPACK dynamic table"));
+    }
+    while let Some(&first) = block.first() {
+        if first & 0x80 != 0 {
+            // indexed field line, T bit selects the static table
+            if first & 0x40 == 0 {
+                return Err(wt_error("response references the QPACK dynamic table"));
+            }
+            let index = get_prefixed_int(&mut block, 6).ok_or_else(truncated)?;
+            if let Some(status) = static_status(index) {
+                return Ok(status);
+            }
+        } else if first & 0x40 != 0 {
+            // literal field line with name reference
+            if first & 0x10 == 0 {
+                return Err(wt_error("response references the QPACK dynamic table"));
+            }
+            let index = get_prefixed_int(&mut block, 4).ok_or_else(truncated)?;
+            let (huffman, value) = get_string(&mut block, 7).ok_or_else(truncated)?;
+            if static_status(index).is_some() {
+                return parse_status(huffman, value);
+            }
+        } else if first & 0x20 != 0 {
+            // literal field line with literal name
+            let (name_huffman, name) = get_string(&mut block, 3).ok_or_else(truncated)?;
+            let (huffman, value) = get_string(&mut block, 7).ok_or_else(truncated)?;
+            if !name_huffman && name == b":status" {
+                return parse_status(huffman, value);
+            }
+        } else {
+            return Err(wt_error("response references the QPACK dynamic table"));
+        }
+    }
+    Err(wt_error("response has no :status"))
+}
+
+/// Where and as whom to open a session
+struct Target {
+    host: String,
+    port: u16,
+    path: String,
+    user_agent: String,
+    connect_timeout: Duration,
+}
+
+impl HttpClient {
+    /// Open a WebTransport session to an `https://` URL (experimental)
+    ///
+    /// The QUIC connection uses the profile's transport parameters and TLS
+    /// settings. Servers without WebTransport support fail with
+    /// [`HttpClientError::WebTransportError`].
+    pub fn connect_webtransport(
+        &self,
+        url: &str,
+    ) -> impl Future<Output = Result<WebTransportSession>> + Send + 'static {
+        let prepared = self.prepare_webtransport(url);
+        async move {
+            let (target, tls, transport) = prepared?;
+            connect(target, tls, transport).await
+        }
+    }
+
+    fn prepare_webtransport(
+        &self,
+        url: &str,
+    ) -> Result<(Target, rustls::ClientConfig, TransportConfig)> {
+        let (scheme, host, port, path) = self.parse_url(url)?;
+        if scheme != "https" {
+            return Err(HttpClientError::InvalidUrl(
+                "WebTransport needs an https:// URL".to_string(),
+            ));
+        }
+        if self.config.proxy.is_some() {
+            return Err(HttpClientError::ConnectionFailed(
+                "WebTransport cannot be tunnelled through a TCP proxy chain".to_string(),
+            ));
+        }
+
+        let tls = super::rustls_utils::build_client_config(
+            self.config.verify_tls,
+            vec![b"h3".to_vec()],
+            self.config.profile.as_ref(),
+            self.config.revocation.as_ref(),
+        );
+        let mut transport = TransportConfig::default();
+        self.config
+            .profile
+            .as_ref()
+            .map(crate::quic_transport::QuicTransportParameters::for_profile)
+            .unwrap_or_else(crate::quic_transport::QuicTransportParameters::chrome)
+            .apply_to(&mut transport)
+            .map_err(|e| {
+                HttpClientError::ConnectionFailed(format!("QUIC transport parameters: {}", e))
+            })?;
+
+        let target = Target {
+            host,
+            port,
+            path,
+            user_agent: self.config.user_agent.clone(),
+            connect_timeout: self.config.connect_timeout,
+        };
+        Ok((target, tls, transport))
+    }
+}
+
+async fn connect(
+    target: Target,
+    tls: rustls::ClientConfig,
+    transport: TransportConfig,
+) -> Result<WebTransportSession> {
+    let quic = quinn::crypto::rustls::QuicClientConfig::try_from(tls)
+        .map_err(|e| HttpClientError::TlsError(format!("Failed to create QUIC config: {}", e)))?;
+    let mut client_config = quinn::ClientConfig::new(Arc::new(quic));
+    client_config.transport_config(Arc::new(transport));
+
+    let remote = (target.host.as_str(), target.port)
+        .to_socket_addrs()
+        .map_err(|e| HttpClientError::InvalidUrl(format!("DNS resolution failed: {}", e)))?
+        .min_by_key(|addr| addr.is_ipv6())
+        .ok_or_else(|| HttpClientError::InvalidUrl("no address for host".to_string()))?;
+    let bind = match remote.ip() {
+        IpAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+        IpAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+    };
+    let endpoint = Endpoint::client(bind)
+        .map_err(|e| HttpClientError::ConnectionFailed(format!("QUIC endpoint: {}", e)))?;
+
+    tokio::time::timeout(target.connect_timeout, async {
+        let connection = endpoint
+            .connect_with(client_config, remote, &target.host)
+            .map_err(|e| HttpClientError::ConnectionFailed(e.to_string()))?
+            .await
+            .map_err(|e| HttpClientError::ConnectionFailed(e.to_string()))?;
+        establish(endpoint.clone(), connection, &target).await
+    })
+    .await
+    .map_err(|_| HttpClientError::Timeout)?
+}
+
+/// Exchange SETTINGS and send the extended CONNECT on a fresh connection
+async fn establish(
+    endpoint: Endpoint,
+    connection: Connection,
+    target: &Target,
+) -> Result<WebTransportSession> {
+    let mut control = connection.open_uni().await.map_err(wt_error)?;
+    let mut preface = BytesMut::new();
+    put_varint(&mut preface, STREAM_CONTROL);
+    preface.put_slice(&settings_frame(&CLIENT_SETTINGS));
+    control.write_all(&preface).await.map_err(wt_error)?;
+
+    let (mut request, mut response) = connection.open_bi().await.map_err(wt_error)?;
+    let session_id = u64::from(request.id());
+
+    let (settings_tx, settings_rx) = oneshot::channel();
+    let (bi_tx, incoming_bi) = mpsc::channel(INCOMING_QUEUE);
+    let (uni_tx, incoming_uni) = mpsc::channel(INCOMING_QUEUE);
+    tokio::spawn(accept_uni_streams(
+        connection.clone(),
+        session_id,
+        settings_tx,
+        uni_tx,
+    ));
+    tokio::spawn(accept_bi_streams(connection.clone(), session_id, bi_tx));
+
+    let settings = settings_rx
+        .await
+        .map_err(|_| wt_error("connection closed before the server's SETTINGS"))??;
+    if !settings.connect_protocol || !settings.webtransport {
+        connection.close(VarInt::from_u32(0), b"");
+        return Err(wt_error(
+            "server does not offer WebTransport (no extended CONNECT or WebTransport SETTINGS)",
+        ));
+    }
+
+    let authority = if target.port == 443 {
+        target.host.clone()
+    } else {
+        format!("{}:{}", target.host, target.port)
+    };
+    let mut headers = BytesMut::new();
+    put_frame(
+        &mut headers,
+        FRAME_HEADERS,
+        &encode_connect(&authority, &target.path, &target.user_agent),
+    );
+    request.write_all(&headers).await.map_err(wt_error)?;
+
+    // skip reserved and unknown frames until the response HEADERS
+    let status = loop {
+        let (frame_type, payload) = read_frame(&mut response).await?;
+        if frame_type == FRAME_HEADERS {
+            break decode_status(&payload)?;
+        }
+    };
+    if !(200..300).contains(&status) {
+        connection.close(VarInt::from_u32(0), b"");
+        return Err(wt_error(format!(
+            "server refused the session with status {}",
+            status
+        )));
+    }
+
+    Ok(WebTransportSession {
+        datagrams: settings.datagrams && connection.max_datagram_size().is_some(),
+        connection,
+        session_id,
+        _endpoint: endpoint,
+        _control: control,
+        request,
+        _response: response,
+        incoming_bi,
+        incoming_uni,
+    })
+}
+
+/// Route server-initiated unidirectional streams
+async fn accept_uni_streams(
+    connection: Connection,
+    session_id: u64,
+    settings: oneshot::Sender<Result<PeerSettings>>,
+    sessions: mpsc::Sender<RecvStream>,
+) {
+    let settings = Arc::new(Mutex::new(Some(settings)));
+    while let Ok(mut recv) = connection.accept_uni().await {
+        let settings = settings.clone();
+        let sessions = sessions.clone();
+        tokio::spawn(async move {
+            match read_varint(&mut recv).await {
+                Ok(STREAM_CONTROL) => {
+                    let Some(tx) = settings.lock().ok().and_then(|mut s| s.take()) else {
+                        return;
+                    };
+                    let parsed = match read_frame(&mut recv).await {
+                        Ok((FRAME_SETTINGS, payload)) => PeerSettings::parse(&payload),
+                        Ok(_) => Err(wt_error("control stream does not start with SETTINGS")),
+                        Err(e) => Err(e),
+                    };
+                    let _ = tx.send(parsed);
+                    drain(recv).await;
+                }
+                Ok(STREAM_WT_UNI) => {
+                    if read_varint(&mut recv).await.ok() == Some(session_id) {
+                        let _ = sessions.send(recv).await;
+                    }
+                }
+                // push and QPACK streams: nothing the client acts on
+                Ok(_) => drain(recv).await,
+                Err(_) => {}
+            }
+        });
+    }
+}
+
+/// Route server-initiated bidirectional streams
+async fn accept_bi_streams(
+    connection: Connection,
+    session_id: u64,
+    sessions: mpsc::Sender<(SendStream, RecvStream)>,
+) {
+    while let Ok((send, mut recv)) = connection.accept_bi().await {
+        let sessions = sessions.clone();
+        tokio::spawn(async move {
+            if read_varint(&mut recv).await.ok() == Some(WT_BIDI_SIGNAL)
+                && read_varint(&mut recv).await.ok() == Some(session_id)
+            {
+                let _ = sessions.send((send, recv)).await;
+            }
+        });
+    }
+}
+
+/// An established WebTransport session
+///
+/// Dropping the session closes its QUIC connection.
+pub struct WebTransportSession {
+    connection: Connection,
+    session_id: u64,
+    datagrams: bool,
+    request: SendStream,
+    _endpoint: Endpoint,
+    _control: SendStream,
+    _response: RecvStream,
+    incoming_bi: mpsc::Receiver<(SendStream, RecvStream)>,
+    incoming_uni: mpsc::Receiver<RecvStream>,
+}
+
+impl WebTransportSession {
+    /// ID of the CONNECT stream, which identifies the session
+    pub fn session_id(&self) -> u64 {
+        self.session_id
+    }
+
+    /// Whether both sides enabled HTTP/3 datagrams
+    pub fn supports_datagrams(&self) -> bool {
+        self.datagrams
+    }
+
+    /// Open a bidirectional stream in this session
+    pub async fn open_bi(&self) -> Result<(SendStream, RecvStream)> {
+        let (mut send, recv) = self.connection.open_bi().await.map_err(wt_error)?;
+        let mut header = BytesMut::new();
+        put_varint(&mut header, WT_BIDI_SIGNAL);
+        put_varint(&mut header, self.session_id);
+        send.write_all(&header).await.map_err(wt_error)?;
+        Ok((send, recv))
+    }
+
+    /// Open a unidirectional stream in this session
+    pub async fn open_uni(&self) -> Result<SendStream> {
+        let mut send = self.connection.open_uni().await.map_err(wt_error)?;
+        let mut header = BytesMut::new();
+        put_varint(&mut header, STREAM_WT_UNI);
+        put_varint(&mut header, self.session_id);
+        send.write_all(&header).await.map_err(wt_error)?;
+        Ok(send)
+    }
+
+    /// Next bidirectional stream opened by the server
+    pub async fn accept_bi(&mut self) -> Result<(SendStream, RecvStream)> {
+        self.incoming_bi
+            .recv()
+            .await
+            .ok_or_else(|| wt_error("session closed"))
+    }
+
+    /// Next unidirectional stream opened by the server
+    pub async fn accept_uni(&mut self) -> Result<RecvStream> {
+        self.incoming_uni
+            .recv()
+            .await
+            .ok_or_else(|| wt_error("session closed"))
+    }
+
+    /// Send an unreliable datagram
+    pub fn send_datagram(&self, data: &[u8]) -> Result<()> {
+        if !self.datagrams {
+            return Err(wt_error("HTTP/3 datagrams are not enabled on this session"));
+        }
+        self.connection
+            .send_datagram(h3_datagram::encode_datagram(self.session_id, data))
+            .map_err(wt_error)
+    }
+
+    /// Next datagram for this session
+    pub async fn read_datagram(&self) -> Result<Bytes> {
+        if !self.datagrams {
+            return Err(wt_error("HTTP/3 datagrams are not enabled on this session"));
+        }
+        loop {
+            let payload = self.connection.read_datagram().await.map_err(wt_error)?;
+            if let Some((stream_id, data)) = h3_datagram::decode_datagram(payload) {
+                if stream_id == self.session_id {
+                    return Ok(data);
+                }
+            }
+        }
+    }
+
+    /// Close the session with an application error code and reason
+    pub async fn close(&mut self, code: u32, reason: &str) -> Result<()> {
+        let mut capsule = BytesMut::new();
+        capsule.put_u32(code);
+        capsule.put_slice(&reason.as_bytes()[..reason.len().min(1024)]);
+        let mut data = BytesMut::new();
+        put_varint(&mut data, CAPSULE_CLOSE_SESSION);
+        put_varint(&mut data, capsule.len() as u64);
+        data.put_slice(&capsule);
+        let mut frame = BytesMut::new();
+        put_frame(&mut frame, FRAME_DATA, &data);
+
+        self.request.write_all(&frame).await.map_err(wt_error)?;
+        self.request.finish().map_err(wt_error)?;
+        // give the capsule a moment to arrive before the connection goes
+        let _ = tokio::time::timeout(Duration::from_secs(1), self.request.stopped()).await;
+        self.connection
+            .close(VarInt::from_u32(0), reason.as_bytes());
+        Ok(())
+    }
+}
+
+impl Drop for WebTransportSession {
+    fn drop(&mut self) {
+        self.connection.close(VarInt::from_u32(0), b"");
+    }
+}
+
+impl std::fmt::Debug for WebTransportSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebTransportSession")
+            .field("remote", &self.connection.remote_address())
+            .field("session_id", &self.session_id)
+            .field("datagrams", &self.datagrams)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+
+    fn provider() -> Arc<rustls::crypto::CryptoProvider> {
+        Arc::new(rustls::crypto::ring::default_provider())
+    }
+
+    /// Loopback server with a self-signed certificate, and a client config trusting it
+    fn loopback() -> (Endpoint, rustls::ClientConfig) {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert = CertificateDer::from(certified.cert.der().to_vec());
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+            certified.signing_key.serialize_der(),
+        ));
+
+        let mut server = rustls::ServerConfig::builder_with_provider(provider())
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert.clone()], key)
+            .unwrap();
+        server.alpn_protocols = vec![b"h3".to_vec()];
+        let server = quinn::ServerConfig::with_crypto(Arc::new(
+            quinn::crypto::rustls::QuicServerConfig::try_from(server).unwrap(),
+        ));
+        let endpoint = Endpoint::server(server, "127.0.0.1:0".parse().unwrap()).unwrap();
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(cert).unwrap();
+        let mut client = rustls::ClientConfig::builder_with_provider(provider())
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        client.alpn_protocols = vec![b"h3".to_vec()];
+        (endpoint, client)
+    }
+
+    /// Accept one session, echo one bidirectional stream and one datagram
+    async fn serve(endpoint: Endpoint, offer_webtransport: bool) {
+        let connection = endpoint.accept().await.unwrap().await.unwrap();
+        let mut client_control = connection.accept_uni().await.unwrap();
+        assert_eq!(
+            read_varint(&mut client_control).await.unwrap(),
+            STREAM_CONTROL
+        );
+        let (frame_type, payload) = read_frame(&mut client_control).await.unwrap();
+        assert_eq!(frame_type, FRAME_SETTINGS);
+        let client = PeerSettings::parse(&payload).unwrap();
+        assert!(client.connect_protocol && client.datagrams && client.webtransport);
+
+        let mut control = connection.open_uni().await.unwrap();
+        let mut preface = BytesMut::new();
+        put_varint(&mut preface, STREAM_CONTROL);
+        preface.put_slice(&settings_frame(if offer_webtransport {
+            &[
+                (SETTINGS_ENABLE_CONNECT_PROTOCOL, 1),
+                (SETTINGS_H3_DATAGRAM, 1),
+                (SETTINGS_WT_MAX_SESSIONS, 4),
+            ]
+        } else {
+            &[(SETTINGS_H3_DATAGRAM, 1)]
+        }));
+        control.write_all(&preface).await.unwrap();
+        if !offer_webtransport {
+            connection.closed().await;
+            return;
+        }
+
+        let (mut send, mut recv) = connection.accept_bi().await.unwrap();
+        let (frame_type, headers) = read_frame(&mut recv).await.unwrap();
+        assert_eq!(frame_type, FRAME_HEADERS);
+        assert!(headers.windows(12).any(|w| w == b"webtransport"));
+        let mut response = BytesMut::new();
+        // :status 200 from the static table
+        put_frame(&mut response, FRAME_HEADERS, &[0x00, 0x00, 0xd9]);
+        send.write_all(&response).await.unwrap();
+        let session_id = u64::from(send.id());
+
+        let (mut echo_send, mut echo_recv) = connection.accept_bi().await.unwrap();
+        assert_eq!(read_varint(&mut echo_recv).await.unwrap(), WT_BIDI_SIGNAL);
+        assert_eq!(read_varint(&mut echo_recv).await.unwrap(), session_id);
+        let body = echo_recv.read_to_end(1024).await.unwrap();
+        echo_send.write_all(&body).await.unwrap();
+        echo_send.finish().unwrap();
+
+        let datagram = connection.read_datagram().await.unwrap();
+        connection.send_datagram(datagram).unwrap();
+        connection.closed().await;
+    }
+
+    fn loopback_target(endpoint: &Endpoint) -> Target {
+        Target {
+            host: "localhost".to_string(),
+            port: endpoint.local_addr().unwrap().port(),
+            path: "/echo".to_string(),
+            user_agent: "Mozilla/5.0".to_string(),
+            connect_timeout: Duration::from_secs(10),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_echoes_streams_and_datagrams() {
+        let (endpoint, client) = loopback();
+        let target = loopback_target(&endpoint);
+        let server = tokio::spawn(serve(endpoint, true));
+
+        let mut session = connect(target, client, TransportConfig::default())
+            .await
+            .unwrap();
+        assert!(session.supports_datagrams());
+
+        let (mut send, mut recv) = session.open_bi().await.unwrap();
+        send.write_all(b"hello").await.unwrap();
+        send.finish().unwrap();
+        assert_eq!(recv.read_to_end(1024).await.unwrap(), b"hello");
+
+        session.send_datagram(b"ping").unwrap();
+        assert_eq!(&session.read_datagram().await.unwrap()[..], b"ping");
+
+        session.close(0, "done").await.unwrap();
+        server.await.unwrap();
+
+        // servers without WebTransport are refused before the CONNECT
+        let (endpoint, client) = loopback();
+        let target = loopback_target(&endpoint);
+        let server = tokio::spawn(serve(endpoint, false));
+        let err = connect(target, client, TransportConfig::default())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, HttpClientError::WebTransportError(_)),
+            "{}",
+            err
+        );
+        server.await.unwrap();
+    }
+
+    #[test]
+    fn test_qpack_status_decoding() {
+        assert_eq!(decode_status(&[0x00, 0x00, 0xd9]).unwrap(), 200);
+        // literal with name reference to :status 200, Huffman "429"
+        assert_eq!(
+            decode_status(&[0x00, 0x00, 0x5f, 0x0a, 0x83, 0x68, 0x4f, 0xff]).unwrap(),
+            429
+        );
+        assert_eq!(huffman_digits(&[0x08, 0x3f]), Some("10".to_string()));
+        // dynamic table references are refused
+        assert!(decode_status(&[0x01, 0x00, 0x80]).is_err());
+        assert!(decode_status(&[0x00, 0x00, 0x4f, 0x01, 0x01, b'x']).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+//! JA4H HTTP client fingerprint module
+//!
+//! Fingerprints an HTTP request from its method, version, header order and
+//! cookies, following the FoxIO JA4H specification:
+//!
+//! ```text
+//! ja4h_a: {method:2}{version:2}{cookie c|n}{referer r|n}{header_count:02}{accept_language:4}
+//! ja4h_b: hash of the header names in wire order (Cookie and Referer excluded)
+//! ja4h_c: hash of the sorted cookie names
+//! ja4h_d: hash of the sorted cookie name=value pairs
+//! ```
+//!
+//! Hashes are the first 12 hex characters of SHA256, or `000000000000` when
+//! the input is empty.
+
+use crate::http_client::HttpRequest;
+use fingerprint_tls::hash12;
+
+/// Hash used for an empty field
+const EMPTY_HASH: &str = "000000000000";
+
+/// JA4H payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ja4hPayload {
+    /// JA4H_a: method, version, cookie/referer presence, header count, language
+    pub ja4h_a: String,
+    /// JA4H_b: header name hash
+    pub ja4h_b: String,
+    /// JA4H_c: cookie name hash
+    pub ja4h_c: String,
+    /// JA4H_d: cookie name=value hash
+    pub ja4h_d: String,
+    /// JA4H fingerprint (ja4h)
+    pub fingerprint: String,
+    /// JA4H raw fingerprint with the unhashed fields (ja4h_r)
+    pub raw: String,
+}
+
+/// HTTP request signature (for JA4H generation)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ja4hSignature {
+    /// Request method (e.g. "GET")
+    pub method: String,
+    /// HTTP version (e.g. "HTTP/1.1", "HTTP/2")
+    pub version: String,
+    /// Headers in wire order, pseudo-headers excluded
+    pub headers: Vec<(String, String)>,
+}
+
+impl Ja4hSignature {
+    /// Create a signature from headers in wire order
+    pub fn new(method: &str, version: &str, headers: &[(&str, &str)]) -> Self {
+        Self {
+            method: method.to_string(),
+            version: version.to_string(),
+            headers: headers
+                .iter()
+                .filter(|(name, _)| !name.starts_with(':'))
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Parse an HTTP/1.x request head (request line and headers)
+    pub fn from_http1_head(data: &[u8]) -> Result<Self, String> {
+        let text = String::from_utf8_lossy(data);
+        let mut lines = text.split("\r\n");
+        let request_line = lines.next().unwrap_or_default();
+        let mut parts = request_line.split(' ');
+        let (Some(method), Some(_target), Some(version)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(format!("invalid request line: {}", request_line));
+        };
+
+        let headers = lines
+            .take_while(|line| !line.is_empty())
+            .map(|line| {
+                line.split_once(':')
+                    .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                    .ok_or_else(|| format!("invalid header line: {}", line))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            method: method.to_string(),
+            version: version.to_string(),
+            headers,
+        })
+    }
+
+    /// Signature of the HTTP/1.1 request `request` sends to `host`
+    pub fn from_request(request: &HttpRequest, host: &str, path: &str) -> Result<Self, String> {
+        Self::from_http1_head(request.build_http1_request(host, path).as_bytes())
+    }
+
+    /// Generate the JA4H fingerprint
+    pub fn generate(&self) -> Ja4hPayload {
+        let method: String = self
+            .method
+            .to_lowercase()
+            .chars()
+            .chain("00".chars())
+            .take(2)
+            .collect();
+        let version = match self.version.trim_start_matches("HTTP/") {
+            "2" | "2.0" => "20",
+            "3" | "3.0" => "30",
+            "1.0" => "10",
+            _ => "11",
+        };
+
+        let header = |name: &str| {
+            self.headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        };
+        let cookie = if header("cookie").is_some() { 'c' } else { 'n' };
+        let referer = if header("referer").is_some() {
+            'r'
+        } else {
+            'n'
+        };
+
+        let names: Vec<&str> = self
+            .headers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| {
+                !name.eq_ignore_ascii_case("cookie") && !name.eq_ignore_ascii_case("referer")
+            })
+            .collect();
+
+        let language: String = header("accept-language")
+            .unwrap_or_default()
+            .to_lowercase()
+            .chars()
+            .filter(|c| *c != '-' && *c != ';' && *c != ',')
+            .chain("0000".chars())
+            .take(4)
+            .collect();
+
+        let ja4h_a = format!(
+            "{method}{version}{cookie}{referer}{:02}{language}",
+            names.len().min(99)
+        );
+
+        // Cookies may arrive in one header or split across several (HTTP/2)
+        let mut cookies: Vec<(&str, &str)> = self
+            .headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("cookie"))
+            .flat_map(|(_, value)| value.split(';'))
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+            .collect();
+        cookies.sort_unstable();
+
+        let names_raw = names.join(",");
+        let cookie_names_raw = cookies
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(",");
+        let cookie_pairs_raw = cookies
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let ja4h_b = hash_or_empty(&names_raw);
+        let ja4h_c = hash_or_empty(&cookie_names_raw);
+        let ja4h_d = hash_or_empty(&cookie_pairs_raw);
+
+        Ja4hPayload {
+            fingerprint: format!("{ja4h_a}_{ja4h_b}_{ja4h_c}_{ja4h_d}"),
+            raw: format!("{ja4h_a}_{names_raw}_{cookie_names_raw}_{cookie_pairs_raw}"),
+            ja4h_a,
+            ja4h_b,
+            ja4h_c,
+            ja4h_d,
+        }
+    }
+}
+
+fn hash_or_empty(input: &str) -> String {
+    if input.is_empty() {
+        EMPTY_HASH.to_string()
+    } else {
+        hash12(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ja4h_from_http1_head() {
+        let head = b"GET /index.html HTTP/1.1\r\n\
+            Host: example.com\r\n\
+            User-Agent: Mozilla/5.0\r\n\
+            Accept-Language: en-US,en;q=0.9\r\n\
+            Referer: https://example.com/\r\n\
+            Cookie: b=2; a=1\r\n\
+            \r\n";
+        let ja4h = Ja4hSignature::from_http1_head(head).unwrap().generate();
+
+        assert_eq!(ja4h.ja4h_a, "ge11cr03enus");
+        assert_eq!(ja4h.ja4h_b, hash12("Host,User-Agent,Accept-Language"));
+        assert_eq!(ja4h.ja4h_c, hash12("a,b"));
+        assert_eq!(ja4h.ja4h_d, hash12("a=1,b=2"));
+        assert_eq!(
+            ja4h.raw,
+            "ge11cr03enus_Host,User-Agent,Accept-Language_a,b_a=1,b=2"
+        );
+    }
+
+    #[test]
+    fn test_ja4h_http2_without_cookies() {
+        let ja4h = Ja4hSignature::new(
+            "POST",
+            "HTTP/2",
+            &[(":method", "POST"), ("content-type", "application/json")],
+        )
+        .generate();
+        assert_eq!(ja4h.ja4h_a, "po20nn010000");
+        assert_eq!(ja4h.ja4h_c, EMPTY_HASH);
+        assert_eq!(ja4h.ja4h_d, EMPTY_HASH);
+        assert!(Ja4hSignature::from_http1_head(b"GET\r\n\r\n").is_err());
+    }
+}
@@ -1,10 +1,92 @@
 //! # fingerprint-http
 //!
 //! HTTP client implementation module supporting HTTP/1.1, HTTP/2, and HTTP/3 protocols.
-//! Also includes QUIC (RFC 9000) initial packet fingerprinting.
+//! Also includes QUIC (RFC 9000) initial packet and transport parameter fingerprinting, JA4H
+//! request fingerprinting, response content fingerprints (favicon hash, DOM simhash, script
+//! inventory), and (with the `self-audit` feature) a loopback auditor reporting drift in our own fingerprints.
+//! In-progress protocol work sits behind `experimental-*` features; see [`experimental`].
 
+pub mod content;
+pub mod experimental;
 pub mod http_client;
+pub mod ja4h;
 pub mod quic_fingerprint;
+pub mod quic_transport;
+#[cfg(feature = "self-audit")]
+pub mod self_audit;
 
+pub use content::{ContentFingerprint, SIMHASH_SIMILAR_BITS};
+pub use experimental::ExperimentalFeature;
 pub use http_client::*;
+pub use ja4h::{Ja4hPayload, Ja4hSignature};
 pub use quic_fingerprint::{QuicInitialPacket, QuicPacketType, QuicVersion};
+#[cfg(feature = "crypto")]
+pub use quic_transport::QuicInitialDecryptor;
+pub use quic_transport::{
+    QuicTransportComparison, QuicTransportFingerprint, QuicTransportParameter,
+    QuicTransportParameters,
+};
+
+/// Protocols, analyzers and backends compiled into this crate
+pub fn capabilities() -> Vec<fingerprint_core::Capability> {
+    use fingerprint_core::CapabilityKind::*;
+    fingerprint_core::CapabilitySet::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+        .builtin(Protocol, "http1")
+        .optional(Protocol, "http2", "http2", cfg!(feature = "http2"))
+        .optional(Protocol, "http3", "http3", cfg!(feature = "http3"))
+        .optional(Protocol, "ech", "ech", cfg!(feature = "ech"))
+        .optional(
+            Backend,
+            "rustls",
+            "rustls-tls",
+            cfg!(feature = "rustls-tls"),
+        )
+        .optional(
+            Service,
+            "compression",
+            "compression",
+            cfg!(feature = "compression"),
+        )
+        .optional(
+            Service,
+            "connection-pool",
+            "connection-pool",
+            cfg!(feature = "connection-pool"),
+        )
+        .optional(Service, "reporter", "reporter", cfg!(feature = "reporter"))
+        .optional(
+            Service,
+            "self-audit",
+            "self-audit",
+            cfg!(feature = "self-audit"),
+        )
+        .optional(Service, "otel", "otel", cfg!(feature = "otel"))
+        .experimental(
+            Protocol,
+            "post-quantum-kx",
+            "experimental-pq",
+            cfg!(feature = "experimental-pq"),
+        )
+        .experimental(
+            Protocol,
+            "h3-datagrams",
+            "experimental-h3-datagrams",
+            cfg!(feature = "experimental-h3-datagrams"),
+        )
+        .experimental(
+            Protocol,
+            "webtransport",
+            "experimental-webtransport",
+            cfg!(feature = "experimental-webtransport"),
+        )
+        .builtin(Analyzer, "ja4h")
+        .builtin(Analyzer, "quic-transport")
+        .optional(
+            Analyzer,
+            "quic-initial-decrypt",
+            "crypto",
+            cfg!(feature = "crypto"),
+        )
+        .builtin(Analyzer, "content")
+        .into_vec()
+}
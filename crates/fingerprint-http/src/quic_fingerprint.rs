@@ -312,7 +312,7 @@ impl QuicInitialPacket {
 }
 
 /// Parse variable-length integer (QUIC format, RFC 9000 Section 16)
-fn parse_variable_length_integer(data: &[u8]) -> Result<(u64, usize), String> {
+pub(crate) fn parse_variable_length_integer(data: &[u8]) -> Result<(u64, usize), String> {
     if data.is_empty() {
         return Err("Empty data for variable length integer".to_string());
     }
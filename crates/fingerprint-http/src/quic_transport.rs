@@ -0,0 +1,898 @@
+//! QUIC Transport Parameter Fingerprinting
+//!
+//! Clients announce their QUIC transport parameters (RFC 9000 Section 18) in the
+//! `quic_transport_parameters` extension of the ClientHello carried by the
+//! Initial packets. Which parameters are sent, their order and values such as
+//! `initial_max_data` or `max_idle_timeout` differ per QUIC stack, so they identify
+//! the browser behind an HTTP/3 connection independently of the TLS fingerprint.
+//!
+//! - [`QuicTransportParameters::from_client_hello`] extracts the parameters from a
+//!   ClientHello; with the `crypto` feature, [`QuicInitialDecryptor`] recovers that
+//!   ClientHello from client Initial datagrams (RFC 9001 Section 5).
+//! - [`QuicTransportParameters::fingerprint`] hashes the parameter set and values,
+//!   [`QuicTransportParameters::compare`] reports how two parameter sets differ.
+//! - [`QuicTransportParameters::chrome`], [`firefox`](QuicTransportParameters::firefox)
+//!   and [`safari`](QuicTransportParameters::safari) hold the parameters these
+//!   browsers send; the HTTP/3 client configures its transport from them.
+
+use crate::quic_fingerprint::parse_variable_length_integer;
+use fingerprint_profiles::BrowserProfile;
+use fingerprint_tls::tls_config::hash12;
+
+/// TLS extension carrying QUIC transport parameters (RFC 9001)
+pub const QUIC_TRANSPORT_PARAMETERS_EXTENSION: u16 = 0x0039;
+/// Pre-RFC codepoint still sent by draft implementations
+pub const QUIC_TRANSPORT_PARAMETERS_DRAFT_EXTENSION: u16 = 0xffa5;
+
+/// Transport parameter IDs (RFC 9000 Section 18.2 and common extensions)
+pub mod param {
+    pub const ORIGINAL_DESTINATION_CONNECTION_ID: u64 = 0x00;
+    pub const MAX_IDLE_TIMEOUT: u64 = 0x01;
+    pub const STATELESS_RESET_TOKEN: u64 = 0x02;
+    pub const MAX_UDP_PAYLOAD_SIZE: u64 = 0x03;
+    pub const INITIAL_MAX_DATA: u64 = 0x04;
+    pub const INITIAL_MAX_STREAM_DATA_BIDI_LOCAL: u64 = 0x05;
+    pub const INITIAL_MAX_STREAM_DATA_BIDI_REMOTE: u64 = 0x06;
+    pub const INITIAL_MAX_STREAM_DATA_UNI: u64 = 0x07;
+    pub const INITIAL_MAX_STREAMS_BIDI: u64 = 0x08;
+    pub const INITIAL_MAX_STREAMS_UNI: u64 = 0x09;
+    pub const ACK_DELAY_EXPONENT: u64 = 0x0a;
+    pub const MAX_ACK_DELAY: u64 = 0x0b;
+    pub const DISABLE_ACTIVE_MIGRATION: u64 = 0x0c;
+    pub const PREFERRED_ADDRESS: u64 = 0x0d;
+    pub const ACTIVE_CONNECTION_ID_LIMIT: u64 = 0x0e;
+    pub const INITIAL_SOURCE_CONNECTION_ID: u64 = 0x0f;
+    pub const RETRY_SOURCE_CONNECTION_ID: u64 = 0x10;
+    /// RFC 9368
+    pub const VERSION_INFORMATION: u64 = 0x11;
+    /// RFC 9221
+    pub const MAX_DATAGRAM_FRAME_SIZE: u64 = 0x20;
+    /// RFC 9287
+    pub const GREASE_QUIC_BIT: u64 = 0x2ab2;
+    /// Chrome (quiche)
+    pub const GOOGLE_CONNECTION_OPTIONS: u64 = 0x3128;
+    /// Chrome (quiche)
+    pub const GOOGLE_VERSION: u64 = 0x4752;
+}
+
+/// Parameters whose value is a variable-length integer and stable per client
+const INTEGER_PARAMETERS: &[u64] = &[
+    param::MAX_IDLE_TIMEOUT,
+    param::MAX_UDP_PAYLOAD_SIZE,
+    param::INITIAL_MAX_DATA,
+    param::INITIAL_MAX_STREAM_DATA_BIDI_LOCAL,
+    param::INITIAL_MAX_STREAM_DATA_BIDI_REMOTE,
+    param::INITIAL_MAX_STREAM_DATA_UNI,
+    param::INITIAL_MAX_STREAMS_BIDI,
+    param::INITIAL_MAX_STREAMS_UNI,
+    param::ACK_DELAY_EXPONENT,
+    param::MAX_ACK_DELAY,
+    param::ACTIVE_CONNECTION_ID_LIMIT,
+    param::MAX_DATAGRAM_FRAME_SIZE,
+];
+
+/// Reserved IDs of the form `31 * N + 27` (RFC 9000 Section 18.1)
+pub fn is_grease_transport_parameter(id: u64) -> bool {
+    id >= 27 && (id - 27).is_multiple_of(31)
+}
+
+/// Encode a QUIC variable-length integer (RFC 9000 Section 16)
+pub fn encode_variable_length_integer(value: u64, out: &mut Vec<u8>) {
+    match value {
+        0..=0x3f => out.push(value as u8),
+        0x40..=0x3fff => out.extend_from_slice(&(value as u16 | 0x4000).to_be_bytes()),
+        0x4000..=0x3fff_ffff => out.extend_from_slice(&(value as u32 | 0x8000_0000).to_be_bytes()),
+        _ => out.extend_from_slice(&(value | 0xc000_0000_0000_0000).to_be_bytes()),
+    }
+}
+
+fn encode_integer(value: u64) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_variable_length_integer(value, &mut out);
+    out
+}
+
+/// One transport parameter as sent on the wire
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuicTransportParameter {
+    pub id: u64,
+    pub value: Vec<u8>,
+}
+
+/// Transport parameters in the order the client sent them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuicTransportParameters {
+    pub parameters: Vec<QuicTransportParameter>,
+}
+
+/// Hashed transport parameter fingerprint
+///
+/// Format: `q<count><g|n>_<hash12 of sorted IDs>_<hash12 of integer values>`, where
+/// `g` marks a client that sends a GREASE parameter. GREASE IDs are left out of the
+/// count and the ID hash, so the fingerprint is stable across connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuicTransportFingerprint {
+    pub fingerprint: String,
+    /// Same layout with the ID and `id=value` lists in clear
+    pub raw: String,
+}
+
+/// How two transport parameter sets differ
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuicTransportComparison {
+    /// Same non-GREASE parameter IDs
+    pub same_set: bool,
+    /// Same non-GREASE parameter IDs in the same order
+    pub same_order: bool,
+    /// IDs the expected set has and the observed one lacks
+    pub missing: Vec<u64>,
+    /// IDs the observed set has and the expected one lacks
+    pub extra: Vec<u64>,
+    /// Integer parameters present in both with different values
+    pub differing_values: Vec<u64>,
+    /// Share of the union of IDs present in both with equal integer values (0.0-1.0)
+    pub similarity: f64,
+}
+
+impl QuicTransportParameters {
+    /// Parse the body of the `quic_transport_parameters` extension
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut parameters = Vec::new();
+        let mut pos = 0;
+        while pos < data.len() {
+            let (id, read) = parse_variable_length_integer(&data[pos..])?;
+            pos += read;
+            let (len, read) = parse_variable_length_integer(&data[pos..])?;
+            pos += read;
+            let len = len as usize;
+            if data.len() - pos < len {
+                return Err(format!("Transport parameter 0x{:x} truncated", id));
+            }
+            parameters.push(QuicTransportParameter {
+                id,
+                value: data[pos..pos + len].to_vec(),
+            });
+            pos += len;
+        }
+        Ok(Self { parameters })
+    }
+
+    /// Extract the parameters from a ClientHello (handshake message or TLS records)
+    pub fn from_client_hello(data: &[u8]) -> Result<Self, String> {
+        let handshake = match data.first() {
+            Some(0x16) => data.get(5..).ok_or("Truncated TLS record")?,
+            Some(0x01) => data,
+            _ => return Err("Not a ClientHello".to_string()),
+        };
+        let body = handshake.get(4..).ok_or("Truncated ClientHello")?;
+
+        // [Version(2)][Random(32)][SessionID][CipherSuites][Compression][Extensions]
+        let truncated = || "Truncated ClientHello".to_string();
+        let mut pos = 34;
+        let session_id_len = *body.get(pos).ok_or_else(truncated)? as usize;
+        pos += 1 + session_id_len;
+        let ciphers_len = read_u16(body, pos).ok_or_else(truncated)? as usize;
+        pos += 2 + ciphers_len;
+        let compression_len = *body.get(pos).ok_or_else(truncated)? as usize;
+        pos += 1 + compression_len;
+        let extensions_len = read_u16(body, pos).ok_or_else(truncated)? as usize;
+        pos += 2;
+        let extensions = body.get(pos..pos + extensions_len).ok_or_else(truncated)?;
+
+        let mut pos = 0;
+        while pos + 4 <= extensions.len() {
+            let ext_type = read_u16(extensions, pos).ok_or_else(truncated)?;
+            let ext_len = read_u16(extensions, pos + 2).ok_or_else(truncated)? as usize;
+            let ext = extensions
+                .get(pos + 4..pos + 4 + ext_len)
+                .ok_or_else(truncated)?;
+            if ext_type == QUIC_TRANSPORT_PARAMETERS_EXTENSION
+                || ext_type == QUIC_TRANSPORT_PARAMETERS_DRAFT_EXTENSION
+            {
+                return Self::parse(ext);
+            }
+            pos += 4 + ext_len;
+        }
+        Err("ClientHello has no quic_transport_parameters extension".to_string())
+    }
+
+    /// Encode as the body of the `quic_transport_parameters` extension
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for parameter in &self.parameters {
+            encode_variable_length_integer(parameter.id, &mut out);
+            encode_variable_length_integer(parameter.value.len() as u64, &mut out);
+            out.extend_from_slice(&parameter.value);
+        }
+        out
+    }
+
+    /// Raw value of a parameter
+    pub fn get(&self, id: u64) -> Option<&[u8]> {
+        self.parameters
+            .iter()
+            .find(|p| p.id == id)
+            .map(|p| p.value.as_slice())
+    }
+
+    /// Value of an integer parameter
+    pub fn integer(&self, id: u64) -> Option<u64> {
+        let value = self.get(id)?;
+        match parse_variable_length_integer(value) {
+            Ok((v, read)) if read == value.len() => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn max_idle_timeout(&self) -> Option<u64> {
+        self.integer(param::MAX_IDLE_TIMEOUT)
+    }
+
+    pub fn max_udp_payload_size(&self) -> Option<u64> {
+        self.integer(param::MAX_UDP_PAYLOAD_SIZE)
+    }
+
+    pub fn initial_max_data(&self) -> Option<u64> {
+        self.integer(param::INITIAL_MAX_DATA)
+    }
+
+    pub fn initial_max_stream_data_bidi_local(&self) -> Option<u64> {
+        self.integer(param::INITIAL_MAX_STREAM_DATA_BIDI_LOCAL)
+    }
+
+    pub fn initial_max_streams_bidi(&self) -> Option<u64> {
+        self.integer(param::INITIAL_MAX_STREAMS_BIDI)
+    }
+
+    pub fn initial_max_streams_uni(&self) -> Option<u64> {
+        self.integer(param::INITIAL_MAX_STREAMS_UNI)
+    }
+
+    pub fn max_datagram_frame_size(&self) -> Option<u64> {
+        self.integer(param::MAX_DATAGRAM_FRAME_SIZE)
+    }
+
+    /// Whether a GREASE parameter was sent
+    pub fn has_grease(&self) -> bool {
+        self.parameters
+            .iter()
+            .any(|p| is_grease_transport_parameter(p.id))
+    }
+
+    /// Non-GREASE parameter IDs in wire order
+    pub fn order(&self) -> Vec<u64> {
+        self.parameters
+            .iter()
+            .map(|p| p.id)
+            .filter(|id| !is_grease_transport_parameter(*id))
+            .collect()
+    }
+
+    /// Generate the transport parameter fingerprint
+    pub fn fingerprint(&self) -> QuicTransportFingerprint {
+        let mut ids = self.order();
+        ids.sort_unstable();
+        ids.dedup();
+        let id_list = ids
+            .iter()
+            .map(|id| format!("{:x}", id))
+            .collect::<Vec<_>>()
+            .join(",");
+        let value_list = ids
+            .iter()
+            .filter(|id| INTEGER_PARAMETERS.contains(id))
+            .filter_map(|id| self.integer(*id).map(|v| format!("{:x}={}", id, v)))
+            .collect::<Vec<_>>()
+            .join(",");
+        let prefix = format!(
+            "q{:02}{}",
+            ids.len().min(99),
+            if self.has_grease() { 'g' } else { 'n' }
+        );
+        QuicTransportFingerprint {
+            fingerprint: format!("{}_{}_{}", prefix, hash12(&id_list), hash12(&value_list)),
+            raw: format!("{}_{}_{}", prefix, id_list, value_list),
+        }
+    }
+
+    /// Compare observed parameters (`self`) against an expected set
+    pub fn compare(&self, expected: &QuicTransportParameters) -> QuicTransportComparison {
+        let observed_order = self.order();
+        let expected_order = expected.order();
+        let missing: Vec<u64> = expected_order
+            .iter()
+            .filter(|id| !observed_order.contains(id))
+            .copied()
+            .collect();
+        let extra: Vec<u64> = observed_order
+            .iter()
+            .filter(|id| !expected_order.contains(id))
+            .copied()
+            .collect();
+        let differing_values: Vec<u64> = observed_order
+            .iter()
+            .filter(|id| INTEGER_PARAMETERS.contains(id) && expected_order.contains(id))
+            .filter(|id| self.integer(**id) != expected.integer(**id))
+            .copied()
+            .collect();
+
+        let union = expected_order.len() + extra.len();
+        let matching = expected_order.len() - missing.len() - differing_values.len();
+        let similarity = if union == 0 {
+            1.0
+        } else {
+            matching as f64 / union as f64
+        };
+
+        QuicTransportComparison {
+            same_set: missing.is_empty() && extra.is_empty(),
+            same_order: observed_order == expected_order,
+            missing,
+            extra,
+            differing_values,
+            similarity,
+        }
+    }
+
+    /// Browser preset closest to these parameters, with its similarity
+    pub fn closest_browser(&self) -> Option<(&'static str, f64)> {
+        [
+            ("chrome", Self::chrome()),
+            ("firefox", Self::firefox()),
+            ("safari", Self::safari()),
+        ]
+        .into_iter()
+        .map(|(name, preset)| (name, self.compare(&preset).similarity))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    fn from_pairs(pairs: Vec<(u64, Vec<u8>)>) -> Self {
+        Self {
+            parameters: pairs
+                .into_iter()
+                .map(|(id, value)| QuicTransportParameter { id, value })
+                .collect(),
+        }
+    }
+
+    /// Parameters sent by Chrome (quiche), in wire order
+    ///
+    /// Connection-specific values (source connection ID, GREASE ID and value)
+    /// are placeholders.
+    pub fn chrome() -> Self {
+        Self::from_pairs(vec![
+            (
+                param::INITIAL_MAX_STREAM_DATA_UNI,
+                encode_integer(6_291_456),
+            ),
+            (param::MAX_UDP_PAYLOAD_SIZE, encode_integer(1472)),
+            (param::INITIAL_MAX_STREAMS_UNI, encode_integer(103)),
+            (param::GOOGLE_VERSION, 1u32.to_be_bytes().to_vec()),
+            (param::INITIAL_MAX_DATA, encode_integer(15_728_640)),
+            (param::GREASE_QUIC_BIT, Vec::new()),
+            (param::MAX_DATAGRAM_FRAME_SIZE, encode_integer(65_536)),
+            (param::INITIAL_SOURCE_CONNECTION_ID, Vec::new()),
+            (
+                param::INITIAL_MAX_STREAM_DATA_BIDI_LOCAL,
+                encode_integer(6_291_456),
+            ),
+            (31 * 1000 + 27, vec![0; 4]),
+            (param::MAX_IDLE_TIMEOUT, encode_integer(30_000)),
+            (
+                param::VERSION_INFORMATION,
+                [1u32.to_be_bytes(), 1u32.to_be_bytes()].concat(),
+            ),
+            (
+                param::INITIAL_MAX_STREAM_DATA_BIDI_REMOTE,
+                encode_integer(6_291_456),
+            ),
+            (param::INITIAL_MAX_STREAMS_BIDI, encode_integer(100)),
+        ])
+    }
+
+    /// Parameters sent by Firefox (neqo), in wire order
+    pub fn firefox() -> Self {
+        Self::from_pairs(vec![
+            (param::MAX_IDLE_TIMEOUT, encode_integer(30_000)),
+            (param::INITIAL_MAX_DATA, encode_integer(25_165_824)),
+            (
+                param::INITIAL_MAX_STREAM_DATA_BIDI_LOCAL,
+                encode_integer(12_582_912),
+            ),
+            (
+                param::INITIAL_MAX_STREAM_DATA_BIDI_REMOTE,
+                encode_integer(1_048_576),
+            ),
+            (
+                param::INITIAL_MAX_STREAM_DATA_UNI,
+                encode_integer(1_048_576),
+            ),
+            (param::INITIAL_MAX_STREAMS_BIDI, encode_integer(16)),
+            (param::INITIAL_MAX_STREAMS_UNI, encode_integer(16)),
+            (param::ACK_DELAY_EXPONENT, encode_integer(3)),
+            (param::MAX_ACK_DELAY, encode_integer(20)),
+            (param::ACTIVE_CONNECTION_ID_LIMIT, encode_integer(8)),
+            (param::INITIAL_SOURCE_CONNECTION_ID, vec![0; 8]),
+            (param::MAX_DATAGRAM_FRAME_SIZE, encode_integer(65_535)),
+            (param::GREASE_QUIC_BIT, Vec::new()),
+            (
+                param::VERSION_INFORMATION,
+                [1u32.to_be_bytes(), 1u32.to_be_bytes()].concat(),
+            ),
+            (31 * 2000 + 27, Vec::new()),
+        ])
+    }
+
+    /// Parameters sent by Safari (Network.framework), in wire order
+    pub fn safari() -> Self {
+        Self::from_pairs(vec![
+            (param::INITIAL_MAX_DATA, encode_integer(2_097_152)),
+            (param::MAX_IDLE_TIMEOUT, encode_integer(30_000)),
+            (
+                param::INITIAL_MAX_STREAM_DATA_BIDI_LOCAL,
+                encode_integer(2_097_152),
+            ),
+            (
+                param::INITIAL_MAX_STREAM_DATA_BIDI_REMOTE,
+                encode_integer(2_097_152),
+            ),
+            (
+                param::INITIAL_MAX_STREAM_DATA_UNI,
+                encode_integer(2_097_152),
+            ),
+            (param::INITIAL_MAX_STREAMS_BIDI, encode_integer(100)),
+            (param::INITIAL_MAX_STREAMS_UNI, encode_integer(100)),
+            (param::MAX_UDP_PAYLOAD_SIZE, encode_integer(1472)),
+            (param::ACTIVE_CONNECTION_ID_LIMIT, encode_integer(8)),
+            (param::INITIAL_SOURCE_CONNECTION_ID, vec![0; 8]),
+        ])
+    }
+
+    /// Parameters of the browser behind a profile (Chromium derivatives use Chrome's)
+    pub fn for_profile(profile: &BrowserProfile) -> Self {
+        let name = profile.metadata.browser_name.to_ascii_lowercase();
+        if name.contains("firefox") {
+            Self::firefox()
+        } else if name.contains("safari") {
+            Self::safari()
+        } else {
+            Self::chrome()
+        }
+    }
+
+    /// Configure a quinn transport with these parameters
+    ///
+    /// quinn chooses the order, the GREASE parameter and the connection IDs itself,
+    /// and `max_udp_payload_size` belongs to the endpoint, so only the flow control,
+    /// stream, idle and datagram limits carry over.
+    #[cfg(any(feature = "http3", feature = "experimental-h3-datagrams"))]
+    pub fn apply_to(&self, transport: &mut quinn::TransportConfig) -> Result<(), String> {
+        use quinn::VarInt;
+        use std::time::Duration;
+
+        let varint = |value: u64| VarInt::from_u64(value).map_err(|e| format!("{}: {}", value, e));
+        if let Some(timeout) = self.max_idle_timeout() {
+            let timeout = Duration::from_millis(timeout)
+                .try_into()
+                .map_err(|e| format!("max_idle_timeout: {}", e))?;
+            transport.max_idle_timeout(Some(timeout));
+        }
+        if let Some(data) = self.initial_max_data() {
+            transport.receive_window(varint(data)?);
+        }
+        if let Some(window) = self.initial_max_stream_data_bidi_local() {
+            transport.stream_receive_window(varint(window)?);
+        }
+        if let Some(streams) = self.initial_max_streams_bidi() {
+            transport.max_concurrent_bidi_streams(varint(streams)?);
+        }
+        if let Some(streams) = self.initial_max_streams_uni() {
+            transport.max_concurrent_uni_streams(varint(streams)?);
+        }
+        transport
+            .datagram_receive_buffer_size(self.max_datagram_frame_size().map(|size| size as usize));
+        Ok(())
+    }
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Option<u16> {
+    data.get(pos..pos + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+#[cfg(feature = "crypto")]
+pub use initial::QuicInitialDecryptor;
+
+/// Client Initial decryption (RFC 9001 Section 5)
+#[cfg(feature = "crypto")]
+mod initial {
+    use super::parse_variable_length_integer;
+    use ring::aead::{self, quic, Aad, LessSafeKey, Nonce, UnboundKey};
+    use ring::hkdf;
+    use std::collections::BTreeMap;
+
+    const INITIAL_SALT_V1: [u8; 20] = [
+        0x38, 0x76, 0x2c, 0xf7, 0xf5, 0x59, 0x34, 0xb3, 0x4d, 0x17, 0x9a, 0xe6, 0xa4, 0xc8, 0x0c,
+        0xad, 0xcc, 0xbb, 0x7f, 0x0a,
+    ];
+    const INITIAL_SALT_V2: [u8; 20] = [
+        0x0d, 0xed, 0xe3, 0xde, 0xf7, 0x00, 0xa6, 0xdb, 0x81, 0x93, 0x81, 0xbe, 0x6e, 0x26, 0x9d,
+        0xcb, 0xf9, 0xbd, 0x2e, 0xd9,
+    ];
+    const VERSION_V2: u32 = 0x6b33_43cf;
+
+    struct Len(usize);
+
+    impl hkdf::KeyType for Len {
+        fn len(&self) -> usize {
+            self.0
+        }
+    }
+
+    fn expand_label(secret: &hkdf::Prk, label: &str, len: usize) -> Result<Vec<u8>, String> {
+        let label = format!("tls13 {}", label);
+        let info = [
+            &(len as u16).to_be_bytes()[..],
+            &[label.len() as u8],
+            label.as_bytes(),
+            &[0],
+        ];
+        let mut out = vec![0; len];
+        secret
+            .expand(&info, Len(len))
+            .and_then(|okm| okm.fill(&mut out))
+            .map_err(|_| format!("HKDF-Expand-Label {} failed", label))?;
+        Ok(out)
+    }
+
+    /// Client Initial packet protection keys
+    pub(super) struct InitialKeys {
+        pub(super) key: Vec<u8>,
+        pub(super) iv: Vec<u8>,
+        pub(super) hp: Vec<u8>,
+    }
+
+    impl InitialKeys {
+        pub(super) fn client(version: u32, dcid: &[u8]) -> Result<Self, String> {
+            let (salt, prefix) = if version == VERSION_V2 {
+                (&INITIAL_SALT_V2, "quicv2")
+            } else {
+                (&INITIAL_SALT_V1, "quic")
+            };
+            let initial = hkdf::Salt::new(hkdf::HKDF_SHA256, salt).extract(dcid);
+            let client = hkdf::Prk::new_less_safe(
+                hkdf::HKDF_SHA256,
+                &expand_label(&initial, "client in", 32)?,
+            );
+            Ok(Self {
+                key: expand_label(&client, &format!("{} key", prefix), 16)?,
+                iv: expand_label(&client, &format!("{} iv", prefix), 12)?,
+                hp: expand_label(&client, &format!("{} hp", prefix), 16)?,
+            })
+        }
+    }
+
+    /// Recovers the ClientHello from client Initial datagrams
+    ///
+    /// Large ClientHellos (post-quantum key shares) span several Initial packets,
+    /// and Chrome scatters CRYPTO frames out of order, so data is reassembled by
+    /// offset until the complete handshake message is available.
+    #[derive(Debug, Default)]
+    pub struct QuicInitialDecryptor {
+        crypto: BTreeMap<u64, Vec<u8>>,
+    }
+
+    impl QuicInitialDecryptor {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Feed one UDP datagram from the client
+        ///
+        /// Returns the ClientHello handshake message once it is complete.
+        pub fn push_datagram(&mut self, datagram: &[u8]) -> Result<Option<Vec<u8>>, String> {
+            let mut rest = datagram;
+            // coalesced packets: Initial first, anything after it is skipped
+            while rest.first().is_some_and(|b| b & 0xf0 == 0xc0) {
+                let consumed = self.push_packet(rest)?;
+                rest = &rest[consumed..];
+            }
+            Ok(self.client_hello())
+        }
+
+        /// Decrypt one long-header Initial packet, returning its length
+        fn push_packet(&mut self, data: &[u8]) -> Result<usize, String> {
+            let truncated = || "Truncated QUIC Initial packet".to_string();
+            let version = u32::from_be_bytes(
+                data.get(1..5)
+                    .ok_or_else(truncated)?
+                    .try_into()
+                    .map_err(|_| truncated())?,
+            );
+            let mut pos = 5;
+            let dcid_len = *data.get(pos).ok_or_else(truncated)? as usize;
+            let dcid = data
+                .get(pos + 1..pos + 1 + dcid_len)
+                .ok_or_else(truncated)?;
+            pos += 1 + dcid_len;
+            let scid_len = *data.get(pos).ok_or_else(truncated)? as usize;
+            pos += 1 + scid_len;
+            let (token_len, read) =
+                parse_variable_length_integer(data.get(pos..).ok_or_else(truncated)?)?;
+            pos += read + token_len as usize;
+            let (length, read) =
+                parse_variable_length_integer(data.get(pos..).ok_or_else(truncated)?)?;
+            let pn_offset = pos + read;
+            let end = pn_offset + length as usize;
+            if data.len() < end || length < 20 {
+                return Err(truncated());
+            }
+
+            let keys = InitialKeys::client(version, dcid)?;
+            let hp = quic::HeaderProtectionKey::new(&quic::AES_128, &keys.hp)
+                .map_err(|_| "Invalid header protection key".to_string())?;
+            let mask = hp
+                .new_mask(&data[pn_offset + 4..pn_offset + 20])
+                .map_err(|_| "Header protection failed".to_string())?;
+
+            let mut packet = data[..end].to_vec();
+            packet[0] ^= mask[0] & 0x0f;
+            let pn_len = (packet[0] & 0x03) as usize + 1;
+            let mut packet_number = 0u64;
+            for i in 0..pn_len {
+                packet[pn_offset + i] ^= mask[1 + i];
+                packet_number = packet_number << 8 | packet[pn_offset + i] as u64;
+            }
+
+            let mut nonce = [0u8; 12];
+            nonce.copy_from_slice(&keys.iv);
+            for (n, p) in nonce[4..].iter_mut().zip(packet_number.to_be_bytes()) {
+                *n ^= p;
+            }
+            let key = LessSafeKey::new(
+                UnboundKey::new(&aead::AES_128_GCM, &keys.key)
+                    .map_err(|_| "Invalid packet protection key".to_string())?,
+            );
+            let (header, payload) = packet.split_at_mut(pn_offset + pn_len);
+            let plaintext = key
+                .open_in_place(
+                    Nonce::assume_unique_for_key(nonce),
+                    Aad::from(&header[..]),
+                    payload,
+                )
+                .map_err(|_| "QUIC Initial decryption failed".to_string())?;
+            self.push_frames(plaintext)?;
+            Ok(end)
+        }
+
+        /// Collect CRYPTO frames; stops at frames it does not need to understand
+        fn push_frames(&mut self, mut frames: &[u8]) -> Result<(), String> {
+            while let Some(&frame_type) = frames.first() {
+                match frame_type {
+                    // PADDING, PING
+                    0x00 | 0x01 => frames = &frames[1..],
+                    // CRYPTO
+                    0x06 => {
+                        let mut pos = 1;
+                        let (offset, read) = parse_variable_length_integer(&frames[pos..])?;
+                        pos += read;
+                        let (len, read) = parse_variable_length_integer(&frames[pos..])?;
+                        pos += read;
+                        let data = frames
+                            .get(pos..pos + len as usize)
+                            .ok_or("Truncated CRYPTO frame")?;
+                        self.crypto.insert(offset, data.to_vec());
+                        frames = &frames[pos + len as usize..];
+                    }
+                    _ => break,
+                }
+            }
+            Ok(())
+        }
+
+        /// Contiguous CRYPTO data from offset 0, if it holds a whole handshake message
+        fn client_hello(&self) -> Option<Vec<u8>> {
+            let mut stream = Vec::new();
+            for (&offset, data) in &self.crypto {
+                let offset = offset as usize;
+                if offset > stream.len() {
+                    break;
+                }
+                let overlap = stream.len() - offset;
+                if overlap < data.len() {
+                    stream.extend_from_slice(&data[overlap..]);
+                }
+            }
+            let len =
+                u32::from_be_bytes([0, *stream.get(1)?, *stream.get(2)?, *stream.get(3)?]) as usize;
+            (stream.first() == Some(&0x01) && stream.len() >= 4 + len)
+                .then(|| stream[..4 + len].to_vec())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_initial_keys_rfc9001_vector() {
+            // RFC 9001 Appendix A.1
+            let dcid = [0x83, 0x94, 0xc8, 0xf0, 0x3e, 0x51, 0x57, 0x08];
+            let keys = InitialKeys::client(1, &dcid).unwrap();
+            let hex = |b: &[u8]| b.iter().map(|x| format!("{:02x}", x)).collect::<String>();
+            assert_eq!(hex(&keys.key), "1f369613dd76d5467730efcbe3b1a22d");
+            assert_eq!(hex(&keys.iv), "fa044b2f42a3fd3b46fb255c");
+            assert_eq!(hex(&keys.hp), "9f50449e04a0e810283a1e9933adedd2");
+        }
+
+        /// Protect `frames` as a client Initial the way a QUIC client would
+        fn seal_initial(dcid: &[u8], packet_number: u8, frames: &[u8]) -> Vec<u8> {
+            let keys = InitialKeys::client(1, dcid).unwrap();
+            let mut payload = frames.to_vec();
+            payload.resize(1100, 0); // PADDING
+            let mut packet = vec![0xc0, 0, 0, 0, 1, dcid.len() as u8];
+            packet.extend_from_slice(dcid);
+            packet.extend_from_slice(&[0, 0]); // empty SCID and token
+            let length = (1 + payload.len() + 16) as u16 | 0x4000;
+            packet.extend_from_slice(&length.to_be_bytes());
+            let pn_offset = packet.len();
+            packet.push(packet_number);
+
+            let mut nonce = [0u8; 12];
+            nonce.copy_from_slice(&keys.iv);
+            nonce[11] ^= packet_number;
+            let key = LessSafeKey::new(UnboundKey::new(&aead::AES_128_GCM, &keys.key).unwrap());
+            key.seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(&packet[..]),
+                &mut payload,
+            )
+            .unwrap();
+            packet.extend_from_slice(&payload);
+
+            let hp = quic::HeaderProtectionKey::new(&quic::AES_128, &keys.hp).unwrap();
+            let mask = hp.new_mask(&packet[pn_offset + 4..pn_offset + 20]).unwrap();
+            packet[0] ^= mask[0] & 0x0f;
+            packet[pn_offset] ^= mask[1];
+            packet
+        }
+
+        fn crypto_frame(offset: u64, data: &[u8]) -> Vec<u8> {
+            let mut frame = vec![0x06];
+            super::super::encode_variable_length_integer(offset, &mut frame);
+            super::super::encode_variable_length_integer(data.len() as u64, &mut frame);
+            frame.extend_from_slice(data);
+            frame
+        }
+
+        #[test]
+        fn test_reassembles_client_hello_across_packets() {
+            let mut hello = vec![0x01, 0x00, 0x00, 0xfc];
+            hello.extend((0..0xfc).map(|i| i as u8));
+            let dcid = [7u8; 8];
+
+            // second half arrives first, in a packet with a PING ahead of it
+            let second = seal_initial(
+                &dcid,
+                1,
+                &[&[0x01][..], &crypto_frame(100, &hello[100..])].concat(),
+            );
+            let first = seal_initial(&dcid, 0, &crypto_frame(0, &hello[..100]));
+
+            let mut decryptor = QuicInitialDecryptor::new();
+            assert_eq!(decryptor.push_datagram(&second).unwrap(), None);
+            assert_eq!(decryptor.push_datagram(&first).unwrap(), Some(hello));
+
+            let mut corrupted = seal_initial(&dcid, 2, &crypto_frame(0, &[1, 2, 3]));
+            let last = corrupted.len() - 1;
+            corrupted[last] ^= 0xff;
+            assert!(QuicInitialDecryptor::new()
+                .push_datagram(&corrupted)
+                .is_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_round_trip_and_parse() {
+        for value in [0, 63, 64, 16_383, 16_384, 1_073_741_823, 1_073_741_824] {
+            let encoded = encode_integer(value);
+            assert_eq!(
+                parse_variable_length_integer(&encoded).unwrap(),
+                (value, encoded.len())
+            );
+        }
+
+        let chrome = QuicTransportParameters::chrome();
+        let parsed = QuicTransportParameters::parse(&chrome.encode()).unwrap();
+        assert_eq!(parsed, chrome);
+        assert_eq!(parsed.initial_max_data(), Some(15_728_640));
+        assert_eq!(parsed.max_idle_timeout(), Some(30_000));
+        assert!(parsed.has_grease());
+        assert!(QuicTransportParameters::parse(&[0x01, 0x04, 0x80]).is_err());
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_order_and_grease() {
+        let chrome = QuicTransportParameters::chrome();
+        let mut shuffled = chrome.clone();
+        shuffled.parameters.reverse();
+        for p in &mut shuffled.parameters {
+            if is_grease_transport_parameter(p.id) {
+                p.id = 31 * 77 + 27;
+            }
+        }
+        assert_eq!(shuffled.fingerprint(), chrome.fingerprint());
+        assert!(chrome.fingerprint().fingerprint.starts_with("q13g_"));
+
+        let comparison = shuffled.compare(&chrome);
+        assert!(comparison.same_set);
+        assert!(!comparison.same_order);
+        assert_eq!(comparison.similarity, 1.0);
+
+        assert_ne!(
+            QuicTransportParameters::firefox().fingerprint(),
+            chrome.fingerprint()
+        );
+        let comparison = QuicTransportParameters::firefox().compare(&chrome);
+        assert!(!comparison.same_set);
+        assert!(comparison.missing.contains(&param::GOOGLE_VERSION));
+        assert!(comparison
+            .differing_values
+            .contains(&param::INITIAL_MAX_DATA));
+    }
+
+    /// Minimal ClientHello record carrying `extensions`
+    fn client_hello(extensions: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut body = vec![0x03, 0x03];
+        body.extend_from_slice(&[0x11; 32]);
+        body.extend_from_slice(&[0, 0x00, 0x02, 0x13, 0x01, 0x01, 0x00]);
+        let exts: Vec<u8> = extensions
+            .iter()
+            .flat_map(|(ext_type, data)| {
+                [
+                    &ext_type.to_be_bytes()[..],
+                    &(data.len() as u16).to_be_bytes(),
+                    data,
+                ]
+                .concat()
+            })
+            .collect();
+        body.extend_from_slice(&(exts.len() as u16).to_be_bytes());
+        body.extend_from_slice(&exts);
+        let mut handshake = vec![0x01];
+        handshake.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+        handshake.extend_from_slice(&body);
+        let mut record = vec![0x16, 0x03, 0x01];
+        record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+        record.extend_from_slice(&handshake);
+        record
+    }
+
+    #[test]
+    fn test_extract_from_client_hello() {
+        let firefox = QuicTransportParameters::firefox();
+        let hello = client_hello(&[
+            (0x0000, vec![0, 0]),
+            (QUIC_TRANSPORT_PARAMETERS_EXTENSION, firefox.encode()),
+            (0x0010, vec![0, 3, 2, b'h', b'3']),
+        ]);
+
+        let extracted = QuicTransportParameters::from_client_hello(&hello).unwrap();
+        assert_eq!(extracted, firefox);
+        assert_eq!(extracted.closest_browser().unwrap().0, "firefox");
+        // bare handshake message too
+        assert_eq!(
+            QuicTransportParameters::from_client_hello(&hello[5..]).unwrap(),
+            firefox
+        );
+
+        let without = client_hello(&[(0x0000, vec![0, 0])]);
+        assert!(QuicTransportParameters::from_client_hello(&without).is_err());
+        assert!(QuicTransportParameters::from_client_hello(&hello[..40]).is_err());
+    }
+}
@@ -0,0 +1,741 @@
+//! Continuous self-fingerprint audit
+//!
+//! What the client puts on the wire depends on rustls, h2 and our own serializers,
+//! so a dependency update can silently move every profile's fingerprint. The
+//! [`SelfAuditor`] periodically sends requests with each audited profile to a
+//! loopback capture server, recomputes the fingerprints from the captured bytes and
+//! compares them with the expected values:
+//!
+//! - JA3 (normalized: extensions sorted, since Chrome permutes them per connection)
+//!   and JA4 from the raw ClientHello
+//! - the HTTP/2 fingerprint (SETTINGS, connection WINDOW_UPDATE, initial frame
+//!   sequence) from the decrypted connection preface
+//! - JA4H from an HTTP/1.1 request head
+//!
+//! Values without a configured expectation are pinned at their first sample, so
+//! drift is still reported relative to the baseline the process started with.
+//! Every drift is logged and passed to the alert callback.
+//!
+//! The capture server terminates TLS with a self-signed certificate, so the audit
+//! clients run with `verify_tls: false` (the feature enables
+//! `dangerous_configuration` for that) and are never pointed anywhere but loopback.
+
+use crate::http_client::{EchPolicy, HttpClient, HttpClientConfig};
+use crate::ja4h::Ja4hSignature;
+use fingerprint_core::http2_frame_parser::{
+    collect_initial_frames, find_settings_frame, frame_sequence_fingerprint, is_http2_connection,
+    Http2FrameType, Http2WindowUpdateFrame, HTTP2_PREFACE,
+};
+use fingerprint_core::ja3::JA3;
+use fingerprint_core::ja4::JA4;
+use fingerprint_profiles::profiles::{mapped_app_clients, mapped_tls_clients, BrowserProfile};
+use fingerprint_tls::tls_config::{ClientHelloParser, ClientHelloSignature, TlsVersion};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Host name audit requests are sent to (a DNS name, so the ClientHello carries SNI)
+const LOOPBACK_HOST: &str = "localhost";
+
+/// Accept-Language of audit requests
+const AUDIT_ACCEPT_LANGUAGE: &str = "en-US,en;q=0.9";
+
+/// Upper bound on the bytes read from one captured connection
+const MAX_CAPTURE_BYTES: usize = 256 * 1024;
+
+/// Fingerprint layers compared by the audit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FingerprintKind {
+    /// Normalized JA3 of the ClientHello
+    Ja3,
+    /// JA4 of the ClientHello
+    Ja4,
+    /// HTTP/2 SETTINGS, WINDOW_UPDATE and frame order
+    H2,
+    /// JA4H of an HTTP/1.1 request
+    Ja4h,
+}
+
+impl FingerprintKind {
+    /// All kinds, in report order
+    pub const ALL: [FingerprintKind; 4] = [Self::Ja3, Self::Ja4, Self::H2, Self::Ja4h];
+
+    /// Short name (`ja3`, `ja4`, `h2`, `ja4h`)
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ja3 => "ja3",
+            Self::Ja4 => "ja4",
+            Self::H2 => "h2",
+            Self::Ja4h => "ja4h",
+        }
+    }
+}
+
+impl fmt::Display for FingerprintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Fingerprints per layer; absent layers were not observed or are not expected
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fingerprints {
+    /// Normalized JA3 (MD5)
+    pub ja3: Option<String>,
+    /// JA4
+    pub ja4: Option<String>,
+    /// HTTP/2 fingerprint
+    pub h2: Option<String>,
+    /// JA4H
+    pub ja4h: Option<String>,
+}
+
+impl Fingerprints {
+    /// Value of one layer
+    pub fn get(&self, kind: FingerprintKind) -> Option<&str> {
+        match kind {
+            FingerprintKind::Ja3 => self.ja3.as_deref(),
+            FingerprintKind::Ja4 => self.ja4.as_deref(),
+            FingerprintKind::H2 => self.h2.as_deref(),
+            FingerprintKind::Ja4h => self.ja4h.as_deref(),
+        }
+    }
+
+    fn slot(&mut self, kind: FingerprintKind) -> &mut Option<String> {
+        match kind {
+            FingerprintKind::Ja3 => &mut self.ja3,
+            FingerprintKind::Ja4 => &mut self.ja4,
+            FingerprintKind::H2 => &mut self.h2,
+            FingerprintKind::Ja4h => &mut self.ja4h,
+        }
+    }
+}
+
+/// An observed fingerprint that differs from the expected one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriftAlert {
+    /// Audited profile
+    pub profile: String,
+    /// Drifted layer
+    pub kind: FingerprintKind,
+    /// Expected (configured or pinned) value
+    pub expected: String,
+    /// Value seen on the wire
+    pub observed: String,
+}
+
+impl fmt::Display for DriftAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} drifted: expected {}, observed {}",
+            self.profile, self.kind, self.expected, self.observed
+        )
+    }
+}
+
+/// Outcome of auditing one profile
+#[derive(Debug, Clone)]
+pub struct ProfileAudit {
+    /// Audited profile
+    pub profile: String,
+    /// Fingerprints recomputed from the captured traffic
+    pub observed: Fingerprints,
+    /// Layers that differ from their expectation
+    pub drift: Vec<DriftAlert>,
+    /// Sampling problems (a layer that could not be captured)
+    pub errors: Vec<String>,
+}
+
+/// Outcome of one audit round
+#[derive(Debug, Clone, Default)]
+pub struct AuditReport {
+    /// Per-profile results, in configuration order
+    pub profiles: Vec<ProfileAudit>,
+}
+
+impl AuditReport {
+    /// Every drift found in this round
+    pub fn drift(&self) -> impl Iterator<Item = &DriftAlert> {
+        self.profiles.iter().flat_map(|p| p.drift.iter())
+    }
+
+    /// True when any profile drifted
+    pub fn has_drift(&self) -> bool {
+        self.drift().next().is_some()
+    }
+}
+
+/// Callback receiving every drift alert
+pub type DriftCallback = Arc<dyn Fn(&DriftAlert) + Send + Sync>;
+
+/// Audit configuration
+#[derive(Debug, Clone)]
+pub struct SelfAuditConfig {
+    /// Profiles to audit (names from `mapped_tls_clients` / `mapped_app_clients`)
+    pub profiles: Vec<String>,
+    /// Time between audit rounds of a spawned auditor
+    pub interval: Duration,
+    /// Bound on each sample (connect, handshake and capture)
+    pub timeout: Duration,
+    /// Expected fingerprints per profile; missing values are pinned at first sample
+    pub expected: HashMap<String, Fingerprints>,
+}
+
+impl Default for SelfAuditConfig {
+    fn default() -> Self {
+        Self {
+            profiles: vec![
+                "chrome_133".to_string(),
+                "chrome_136".to_string(),
+                "firefox_133".to_string(),
+                "safari_16_0".to_string(),
+            ],
+            interval: Duration::from_secs(15 * 60),
+            timeout: Duration::from_secs(5),
+            expected: HashMap::new(),
+        }
+    }
+}
+
+/// Samples our own traffic over loopback and reports fingerprint drift
+pub struct SelfAuditor {
+    config: SelfAuditConfig,
+    tls: Arc<rustls::ServerConfig>,
+    baselines: Mutex<HashMap<String, Fingerprints>>,
+    on_drift: Option<DriftCallback>,
+}
+
+impl SelfAuditor {
+    /// Create an auditor with a fresh self-signed loopback certificate
+    pub fn new(config: SelfAuditConfig) -> Result<Self, String> {
+        let certified = rcgen::generate_simple_self_signed(vec![LOOPBACK_HOST.to_string()])
+            .map_err(|e| format!("loopback certificate: {}", e))?;
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+            certified.signing_key.serialize_der(),
+        ));
+        let tls = rustls::ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![CertificateDer::from(certified.cert.der().to_vec())],
+            key,
+        )
+        .map_err(|e| format!("loopback server config: {}", e))?;
+
+        Ok(Self {
+            config,
+            tls: Arc::new(tls),
+            baselines: Mutex::new(HashMap::new()),
+            on_drift: None,
+        })
+    }
+
+    /// Call `callback` for every drift alert
+    pub fn with_alert(mut self, callback: impl Fn(&DriftAlert) + Send + Sync + 'static) -> Self {
+        self.on_drift = Some(Arc::new(callback));
+        self
+    }
+
+    /// Audit every configured profile once
+    pub fn audit_once(&self) -> AuditReport {
+        let profiles = self
+            .config
+            .profiles
+            .iter()
+            .map(|name| self.audit_profile(name))
+            .collect();
+        AuditReport { profiles }
+    }
+
+    /// Run audit rounds every `interval` on a background thread
+    pub fn spawn(self) -> SelfAuditHandle {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let last_report = Arc::new(Mutex::new(None));
+        let shared = last_report.clone();
+        let thread = std::thread::Builder::new()
+            .name("fingerprint-self-audit".to_string())
+            .spawn(move || loop {
+                let report = self.audit_once();
+                *shared.lock().unwrap_or_else(|e| e.into_inner()) = Some(report);
+                match stopped.recv_timeout(self.config.interval) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => break,
+                }
+            })
+            .expect("spawn self-audit thread");
+        SelfAuditHandle {
+            stop: Some(stop),
+            thread: Some(thread),
+            last_report,
+        }
+    }
+
+    /// Pinned baseline of a profile (values first observed without an expectation)
+    pub fn baseline(&self, profile: &str) -> Option<Fingerprints> {
+        self.baselines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(profile)
+            .cloned()
+    }
+
+    fn audit_profile(&self, name: &str) -> ProfileAudit {
+        let (observed, errors) = self.sample(name);
+        let expected = self.config.expected.get(name);
+        let mut baselines = self.baselines.lock().unwrap_or_else(|e| e.into_inner());
+        let baseline = baselines.entry(name.to_string()).or_default();
+
+        let mut drift = Vec::new();
+        for kind in FingerprintKind::ALL {
+            let Some(observed) = observed.get(kind) else {
+                continue;
+            };
+            let expected = match expected.and_then(|e| e.get(kind)) {
+                Some(value) => value,
+                None => match baseline.slot(kind) {
+                    Some(value) => value.as_str(),
+                    pinned => {
+                        *pinned = Some(observed.to_string());
+                        continue;
+                    }
+                },
+            };
+            if expected != observed {
+                drift.push(DriftAlert {
+                    profile: name.to_string(),
+                    kind,
+                    expected: expected.to_string(),
+                    observed: observed.to_string(),
+                });
+            }
+        }
+        drop(baselines);
+
+        for alert in &drift {
+            log::warn!("self-audit: {}", alert);
+            if let Some(callback) = &self.on_drift {
+                callback(alert);
+            }
+        }
+        for error in &errors {
+            log::warn!("self-audit: {}: {}", name, error);
+        }
+        ProfileAudit {
+            profile: name.to_string(),
+            observed,
+            drift,
+            errors,
+        }
+    }
+
+    /// Capture one HTTP/2 and one HTTP/1.1 request of a profile
+    fn sample(&self, name: &str) -> (Fingerprints, Vec<String>) {
+        let mut fingerprints = Fingerprints::default();
+        let mut errors = Vec::new();
+
+        match self.capture(name, true) {
+            Ok(capture) => {
+                let hello = &capture.client_hello;
+                fingerprints.ja3 = Some(normalized_ja3(hello));
+                fingerprints.ja4 = Some(ja4(hello));
+                match capture.h2.as_deref().map(h2_fingerprint) {
+                    Some(Ok(h2)) => fingerprints.h2 = Some(h2),
+                    Some(Err(e)) => errors.push(format!("h2: {}", e)),
+                    None => errors.push("h2: not negotiated".to_string()),
+                }
+            }
+            Err(e) => errors.push(format!("tls: {}", e)),
+        }
+
+        match self.capture(name, false) {
+            Ok(Capture {
+                http1: Some(head), ..
+            }) => match Ja4hSignature::from_http1_head(&head) {
+                Ok(signature) => fingerprints.ja4h = Some(signature.generate().fingerprint),
+                Err(e) => errors.push(format!("ja4h: {}", e)),
+            },
+            Ok(_) => errors.push("ja4h: no HTTP/1.1 request captured".to_string()),
+            Err(e) => errors.push(format!("ja4h: {}", e)),
+        }
+
+        (fingerprints, errors)
+    }
+
+    /// Send one request with the profile and capture what arrives
+    fn capture(&self, name: &str, http2: bool) -> Result<Capture, String> {
+        let profile = lookup_profile(name)?;
+        let addr = (LOOPBACK_HOST, 0)
+            .to_socket_addrs()
+            .map_err(|e| format!("resolve {}: {}", LOOPBACK_HOST, e))?
+            .next()
+            .ok_or_else(|| format!("{} does not resolve", LOOPBACK_HOST))?;
+        // bind where the client will connect (its first resolved address)
+        let listener = TcpListener::bind(addr).map_err(|e| format!("bind: {}", e))?;
+        let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+
+        let client_config = self.client_config(profile, http2);
+        let url = format!("https://{}:{}/", LOOPBACK_HOST, port);
+        let client = std::thread::spawn(move || HttpClient::new(client_config).get(&url));
+
+        let deadline = Instant::now() + self.config.timeout;
+        let captured = accept_until(&listener, deadline)
+            .and_then(|stream| self.capture_connection(stream, http2, deadline));
+        drop(listener);
+        match captured {
+            Ok(capture) => Ok(capture),
+            Err(e) => match client.join() {
+                Ok(Err(client_error)) => Err(format!("{} (client: {})", e, client_error)),
+                _ => Err(e),
+            },
+        }
+    }
+
+    fn client_config(&self, profile: BrowserProfile, http2: bool) -> HttpClientConfig {
+        // ECH configs are never published for localhost; GREASE keeps the extension
+        let ech = match EchPolicy::for_profile(Some(&profile)) {
+            EchPolicy::Auto | EchPolicy::Require => EchPolicy::Grease,
+            policy => policy,
+        };
+        // profiles draw Accept-Language at random, which would read as JA4H drift
+        let mut headers = profile.http_headers.clone();
+        if !headers.accept_language.is_empty() {
+            headers.accept_language = AUDIT_ACCEPT_LANGUAGE.to_string();
+        }
+        HttpClientConfig {
+            user_agent: headers.user_agent.clone(),
+            headers,
+            profile: Some(profile),
+            connect_timeout: self.config.timeout,
+            read_timeout: self.config.timeout,
+            write_timeout: self.config.timeout,
+            verify_tls: false,
+            prefer_http2: http2,
+            prefer_http3: false,
+            ech: Some(ech),
+            ..Default::default()
+        }
+    }
+
+    fn capture_connection(
+        &self,
+        mut stream: TcpStream,
+        http2: bool,
+        deadline: Instant,
+    ) -> Result<Capture, String> {
+        stream
+            .set_nonblocking(false)
+            .and_then(|_| stream.set_read_timeout(Some(remaining(deadline)?)))
+            .map_err(|e| e.to_string())?;
+
+        // the ClientHello as sent, before rustls sees it
+        let mut parser = ClientHelloParser::new();
+        let mut raw = Vec::new();
+        let client_hello = loop {
+            let mut chunk = [0u8; 4096];
+            let n = stream.read(&mut chunk).map_err(|e| e.to_string())?;
+            if n == 0 {
+                return Err("connection closed before ClientHello".to_string());
+            }
+            raw.extend_from_slice(&chunk[..n]);
+            if let Some(hello) = parser.push(&chunk[..n])? {
+                break hello;
+            }
+        };
+
+        let mut tls = self.tls.as_ref().clone();
+        tls.alpn_protocols = if http2 {
+            vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+        } else {
+            vec![b"http/1.1".to_vec()]
+        };
+        let mut conn = rustls::ServerConnection::new(Arc::new(tls)).map_err(|e| e.to_string())?;
+        conn.read_tls(&mut raw.as_slice())
+            .map_err(|e| e.to_string())?;
+        conn.process_new_packets().map_err(|e| e.to_string())?;
+        let mut tls = rustls::StreamOwned::new(conn, stream);
+        while tls.conn.is_handshaking() {
+            tls.conn
+                .complete_io(&mut tls.sock)
+                .map_err(|e| format!("handshake: {}", e))?;
+        }
+
+        let mut capture = Capture {
+            client_hello,
+            h2: None,
+            http1: None,
+        };
+        if tls.conn.alpn_protocol() == Some(b"h2") {
+            // our SETTINGS, so clients that wait for it still send their request
+            let _ = tls.write_all(&[0, 0, 0, 4, 0, 0, 0, 0, 0]);
+            capture.h2 = Some(read_until(&mut tls, deadline, h2_request_complete)?);
+        } else {
+            let head = read_until(&mut tls, deadline, |buf| {
+                buf.windows(4).any(|w| w == b"\r\n\r\n")
+            })?;
+            let end = head
+                .windows(4)
+                .position(|w| w == b"\r\n\r\n")
+                .map_or(head.len(), |pos| pos + 4);
+            capture.http1 = Some(head[..end].to_vec());
+            let _ = tls.write_all(
+                b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            );
+        }
+        tls.conn.send_close_notify();
+        let _ = tls.flush();
+        Ok(capture)
+    }
+}
+
+/// Controls a spawned auditor; dropping it stops the audit thread
+pub struct SelfAuditHandle {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+    last_report: Arc<Mutex<Option<AuditReport>>>,
+}
+
+impl SelfAuditHandle {
+    /// Report of the most recent completed round
+    pub fn last_report(&self) -> Option<AuditReport> {
+        self.last_report
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Stop after the current round and wait for the thread
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for SelfAuditHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Bytes captured from one audit connection
+struct Capture {
+    client_hello: ClientHelloSignature,
+    /// Decrypted HTTP/2 connection start, through the first HEADERS frame
+    h2: Option<Vec<u8>>,
+    /// HTTP/1.1 request head
+    http1: Option<Vec<u8>>,
+}
+
+fn lookup_profile(name: &str) -> Result<BrowserProfile, String> {
+    mapped_tls_clients()
+        .remove(name)
+        .or_else(|| mapped_app_clients().remove(name))
+        .ok_or_else(|| format!("unknown profile {}", name))
+}
+
+fn remaining(deadline: Instant) -> std::io::Result<Duration> {
+    deadline
+        .checked_duration_since(Instant::now())
+        .filter(|d| !d.is_zero())
+        .ok_or_else(|| std::io::Error::new(ErrorKind::TimedOut, "audit sample timed out"))
+}
+
+fn accept_until(listener: &TcpListener, deadline: Instant) -> Result<TcpStream, String> {
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => return Ok(stream),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                remaining(deadline).map_err(|_| "client never connected".to_string())?;
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            Err(e) => return Err(format!("accept: {}", e)),
+        }
+    }
+}
+
+/// Read until `done` holds for the buffer, the peer closes or the deadline passes
+fn read_until(
+    stream: &mut impl Read,
+    deadline: Instant,
+    done: impl Fn(&[u8]) -> bool,
+) -> Result<Vec<u8>, String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    while !done(&buf) && buf.len() < MAX_CAPTURE_BYTES {
+        remaining(deadline).map_err(|e| e.to_string())?;
+        match stream.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Err("timed out waiting for the request".to_string())
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Ok(buf)
+}
+
+/// Whether the buffer holds the preface and frames through the first HEADERS frame
+fn h2_request_complete(buf: &[u8]) -> bool {
+    is_http2_connection(buf)
+        && collect_initial_frames(buf)
+            .iter()
+            .any(|frame| frame.frame_type == Http2FrameType::Headers as u8)
+}
+
+/// `settings|window_update|frame sequence` of a client connection preface
+fn h2_fingerprint(data: &[u8]) -> Result<String, String> {
+    if !is_http2_connection(data) {
+        return Err("no HTTP/2 connection preface".to_string());
+    }
+    let settings = find_settings_frame(data)
+        .ok_or_else(|| "no SETTINGS frame".to_string())?
+        .settings
+        .iter()
+        .map(|(id, value)| format!("{}:{}", id, value))
+        .collect::<Vec<_>>()
+        .join(";");
+
+    // connection-level WINDOW_UPDATE among the initial frames
+    let mut window_update = "00".to_string();
+    let mut pos = HTTP2_PREFACE.len();
+    for frame in collect_initial_frames(data) {
+        if frame.frame_type == Http2FrameType::WindowUpdate as u8 && frame.stream_id == 0 {
+            if let Ok(update) = Http2WindowUpdateFrame::parse(&data[pos..]) {
+                window_update = update.window_size_increment.to_string();
+            }
+            break;
+        }
+        pos += 9 + frame.length as usize;
+    }
+
+    Ok(format!(
+        "{}|{}|{}",
+        settings,
+        window_update,
+        frame_sequence_fingerprint(data)
+    ))
+}
+
+/// JA3 over the sorted extension list (stable across Chrome's extension permutation)
+fn normalized_ja3(hello: &ClientHelloSignature) -> String {
+    let mut extensions = hello.extensions.clone();
+    extensions.sort_unstable();
+    // JA3 takes the legacy version field, which TLS 1.3 clients freeze at 1.2
+    let legacy_version = hello.version.to_u16().min(TlsVersion::V1_2.to_u16());
+    JA3::generate(
+        legacy_version,
+        &hello.cipher_suites,
+        &extensions,
+        &hello.elliptic_curves,
+        &hello.elliptic_curve_point_formats,
+    )
+    .fingerprint
+}
+
+fn ja4(hello: &ClientHelloSignature) -> String {
+    let version = match hello.version {
+        TlsVersion::V1_3 => "1.3",
+        TlsVersion::V1_2 => "1.2",
+        TlsVersion::V1_1 => "1.1",
+        TlsVersion::V1_0 => "1.0",
+        _ => "00",
+    };
+    JA4::generate(
+        't',
+        version,
+        hello.sni.is_some(),
+        &hello.cipher_suites,
+        &hello.extensions,
+        hello.alpn.as_deref(),
+        &hello.signature_algorithms,
+    )
+    .to_fingerprint_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auditor(expected: HashMap<String, Fingerprints>) -> SelfAuditor {
+        SelfAuditor::new(SelfAuditConfig {
+            profiles: vec!["chrome_133".to_string()],
+            expected,
+            ..SelfAuditConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_first_round_pins_baseline_and_second_is_clean() {
+        let auditor = auditor(HashMap::new());
+        let first = auditor.audit_once();
+        let chrome = &first.profiles[0];
+        assert!(chrome.errors.is_empty(), "{:?}", chrome.errors);
+        assert!(!first.has_drift());
+
+        let ja4 = chrome.observed.ja4.as_deref().unwrap();
+        assert!(ja4.starts_with("t13d"), "{}", ja4);
+        let h2 = chrome.observed.h2.as_deref().unwrap();
+        assert!(h2.contains("|S["), "{}", h2);
+        assert!(chrome.observed.ja4h.as_deref().unwrap().starts_with("ge11"));
+        assert_eq!(auditor.baseline("chrome_133").unwrap(), chrome.observed);
+
+        let second = auditor.audit_once();
+        assert!(
+            !second.has_drift(),
+            "{:?}",
+            second.drift().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_drift_from_expected_value_alerts() {
+        let expected = HashMap::from([(
+            "chrome_133".to_string(),
+            Fingerprints {
+                ja4: Some("t13d0000h2_000000000000_000000000000".to_string()),
+                ..Fingerprints::default()
+            },
+        )]);
+        let alerts = Arc::new(Mutex::new(Vec::new()));
+        let sink = alerts.clone();
+        let auditor =
+            auditor(expected).with_alert(move |alert| sink.lock().unwrap().push(alert.clone()));
+
+        let report = auditor.audit_once();
+        let drift: Vec<_> = report.drift().cloned().collect();
+        assert_eq!(drift.len(), 1);
+        assert_eq!(drift[0].kind, FingerprintKind::Ja4);
+        assert_eq!(drift[0].expected, "t13d0000h2_000000000000_000000000000");
+        assert_eq!(*alerts.lock().unwrap(), drift);
+    }
+
+    #[test]
+    fn test_unknown_profile_is_reported() {
+        let auditor = SelfAuditor::new(SelfAuditConfig {
+            profiles: vec!["netscape_4".to_string()],
+            ..SelfAuditConfig::default()
+        })
+        .unwrap();
+        let report = auditor.audit_once();
+        assert_eq!(report.profiles[0].observed, Fingerprints::default());
+        assert!(report.profiles[0].errors[0].contains("unknown profile"));
+    }
+}
@@ -0,0 +1,557 @@
+//! Clustering of fingerprint batches for campaign detection
+//!
+//! A botnet campaign shows up as many clients sharing near-identical fingerprints
+//! (same JA4, same header order), i.e. a dense, tight cluster of vectors.
+//! [`ClusterModel`] groups a batch of [`FingerprintVector`]s with either
+//! - k-means (k-means++ seeding, Lloyd iterations): every sample gets a cluster, or
+//! - DBSCAN: clusters are dense regions of at least `min_points` neighbours within
+//!   `eps`; isolated samples are left as noise.
+//!
+//! Clusters are labeled by the majority label of their members, scored with the
+//! silhouette coefficient, and new fingerprints can be matched to the cluster they
+//! belong to. Distances are Euclidean; DBSCAN and the silhouette are O(n²), meant
+//! for batches of at most tens of thousands of samples.
+
+use crate::isolation_forest::check_samples;
+use crate::FingerprintVector;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// k-means parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KMeansConfig {
+    /// Number of clusters
+    pub k: usize,
+    pub max_iterations: usize,
+    /// Stop once no centroid moves further than this
+    pub tolerance: f32,
+    /// Seed of the k-means++ seeding
+    pub seed: u64,
+}
+
+impl Default for KMeansConfig {
+    fn default() -> Self {
+        Self {
+            k: 8,
+            max_iterations: 100,
+            tolerance: 1e-4,
+            seed: 42,
+        }
+    }
+}
+
+/// DBSCAN parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DbscanConfig {
+    /// Neighbourhood radius
+    pub eps: f32,
+    /// Neighbours (the point included) making a point a core point
+    pub min_points: usize,
+}
+
+impl Default for DbscanConfig {
+    fn default() -> Self {
+        Self {
+            eps: 0.5,
+            min_points: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClusterAlgorithm {
+    KMeans,
+    Dbscan,
+}
+
+/// One cluster of the batch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cluster {
+    pub id: usize,
+    pub centroid: Vec<f32>,
+    pub size: usize,
+    /// Largest member distance from the centroid
+    pub radius: f32,
+    /// Majority label of the members, or one set by [`ClusterModel::set_label`]
+    pub label: Option<String>,
+    /// Share of the members carrying `label`
+    pub label_purity: f32,
+    /// Mean silhouette of the members, if computed
+    pub silhouette: Option<f32>,
+}
+
+/// Cluster a new fingerprint was matched to
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterMatch {
+    pub cluster: usize,
+    /// Distance to the centroid (k-means) or the nearest core point (DBSCAN)
+    pub distance: f32,
+}
+
+/// Clusters found in a batch, and the means to match new fingerprints to them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterModel {
+    algorithm: ClusterAlgorithm,
+    clusters: Vec<Cluster>,
+    /// Cluster of each input sample; `None` is DBSCAN noise
+    assignments: Vec<Option<usize>>,
+    /// DBSCAN core points and their clusters
+    core_points: Vec<(Vec<f32>, usize)>,
+    eps: f32,
+    silhouette: Option<f32>,
+}
+
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+impl ClusterModel {
+    /// Partition `samples` into `config.k` clusters
+    pub fn kmeans(samples: &[FingerprintVector], config: &KMeansConfig) -> Result<Self, String> {
+        let dimensions = check_samples(samples)?;
+        if config.k == 0 || config.k > samples.len() {
+            return Err(format!(
+                "k must be between 1 and the sample count ({}), got {}",
+                samples.len(),
+                config.k
+            ));
+        }
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let mut centroids = Self::seed_centroids(samples, config.k, &mut rng);
+        let mut assignments = vec![0; samples.len()];
+        for _ in 0..config.max_iterations.max(1) {
+            for (assignment, sample) in assignments.iter_mut().zip(samples) {
+                *assignment = Self::nearest(&centroids, &sample.features).0;
+            }
+            let mut sums = vec![vec![0.0f32; dimensions]; config.k];
+            let mut counts = vec![0usize; config.k];
+            for (cluster, sample) in assignments.iter().zip(samples) {
+                counts[*cluster] += 1;
+                for (sum, x) in sums[*cluster].iter_mut().zip(&sample.features) {
+                    *sum += x;
+                }
+            }
+            let mut shift = 0.0f32;
+            for ((centroid, sum), count) in centroids.iter_mut().zip(sums).zip(counts) {
+                // an emptied cluster keeps its centroid
+                if count == 0 {
+                    continue;
+                }
+                let next: Vec<f32> = sum.iter().map(|s| s / count as f32).collect();
+                shift = shift.max(distance(centroid, &next));
+                *centroid = next;
+            }
+            if shift <= config.tolerance {
+                break;
+            }
+        }
+
+        let assignments: Vec<Option<usize>> = assignments.into_iter().map(Some).collect();
+        Ok(Self::build(
+            ClusterAlgorithm::KMeans,
+            samples,
+            assignments,
+            config.k,
+            Vec::new(),
+            0.0,
+        ))
+    }
+
+    /// k-means++: each next centroid drawn with probability proportional to the
+    /// squared distance from the closest one chosen so far
+    fn seed_centroids(samples: &[FingerprintVector], k: usize, rng: &mut StdRng) -> Vec<Vec<f32>> {
+        let mut centroids = vec![samples[rng.gen_range(0..samples.len())].features.clone()];
+        while centroids.len() < k {
+            let weights: Vec<f32> = samples
+                .iter()
+                .map(|s| Self::nearest(&centroids, &s.features).1.powi(2))
+                .collect();
+            let total: f32 = weights.iter().sum();
+            let next = if total > 0.0 {
+                let mut target = rng.gen_range(0.0..total);
+                weights
+                    .iter()
+                    .position(|w| {
+                        target -= w;
+                        target < 0.0
+                    })
+                    .unwrap_or(samples.len() - 1)
+            } else {
+                // fewer distinct points than clusters
+                rng.gen_range(0..samples.len())
+            };
+            centroids.push(samples[next].features.clone());
+        }
+        centroids
+    }
+
+    /// Index of and distance to the closest of `points`
+    fn nearest(points: &[Vec<f32>], features: &[f32]) -> (usize, f32) {
+        points
+            .iter()
+            .map(|p| distance(p, features))
+            .enumerate()
+            .fold(
+                (0, f32::MAX),
+                |best, (i, d)| if d < best.1 { (i, d) } else { best },
+            )
+    }
+
+    /// Group dense regions of `samples`; sparse samples stay unassigned
+    pub fn dbscan(samples: &[FingerprintVector], config: &DbscanConfig) -> Result<Self, String> {
+        check_samples(samples)?;
+        if config.eps.is_nan() || config.eps <= 0.0 || config.min_points == 0 {
+            return Err("eps and min_points must be positive".to_string());
+        }
+
+        let neighbours: Vec<Vec<usize>> = samples
+            .iter()
+            .map(|a| {
+                (0..samples.len())
+                    .filter(|&j| distance(&a.features, &samples[j].features) <= config.eps)
+                    .collect()
+            })
+            .collect();
+        let is_core: Vec<bool> = neighbours
+            .iter()
+            .map(|n| n.len() >= config.min_points)
+            .collect();
+
+        let mut assignments: Vec<Option<usize>> = vec![None; samples.len()];
+        let mut clusters = 0;
+        for start in 0..samples.len() {
+            if !is_core[start] || assignments[start].is_some() {
+                continue;
+            }
+            // expand from a new core point; border points join but do not expand
+            assignments[start] = Some(clusters);
+            let mut queue = VecDeque::from([start]);
+            while let Some(point) = queue.pop_front() {
+                for &neighbour in &neighbours[point] {
+                    if assignments[neighbour].is_none() {
+                        assignments[neighbour] = Some(clusters);
+                        if is_core[neighbour] {
+                            queue.push_back(neighbour);
+                        }
+                    }
+                }
+            }
+            clusters += 1;
+        }
+
+        let core_points = (0..samples.len())
+            .filter(|&i| is_core[i])
+            .filter_map(|i| assignments[i].map(|c| (samples[i].features.clone(), c)))
+            .collect();
+        Ok(Self::build(
+            ClusterAlgorithm::Dbscan,
+            samples,
+            assignments,
+            clusters,
+            core_points,
+            config.eps,
+        ))
+    }
+
+    fn build(
+        algorithm: ClusterAlgorithm,
+        samples: &[FingerprintVector],
+        assignments: Vec<Option<usize>>,
+        count: usize,
+        core_points: Vec<(Vec<f32>, usize)>,
+        eps: f32,
+    ) -> Self {
+        let dimensions = samples[0].features.len();
+        let per_point = silhouettes(samples, &assignments);
+        let clusters = (0..count)
+            .map(|id| {
+                let members: Vec<usize> = (0..samples.len())
+                    .filter(|&i| assignments[i] == Some(id))
+                    .collect();
+                let size = members.len();
+                let mut centroid = vec![0.0; dimensions];
+                for &i in &members {
+                    for (c, x) in centroid.iter_mut().zip(&samples[i].features) {
+                        *c += x / size.max(1) as f32;
+                    }
+                }
+                let radius = members
+                    .iter()
+                    .map(|&i| distance(&centroid, &samples[i].features))
+                    .fold(0.0, f32::max);
+
+                let mut votes: HashMap<&str, usize> = HashMap::new();
+                for &i in &members {
+                    if let Some(label) = &samples[i].label {
+                        *votes.entry(label.as_str()).or_default() += 1;
+                    }
+                }
+                // ties go to the alphabetically first label, for determinism
+                let majority = votes
+                    .into_iter()
+                    .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)));
+                let silhouette = per_point.as_ref().and_then(|scores| {
+                    let scores: Vec<f32> = members.iter().filter_map(|&i| scores[i]).collect();
+                    (!scores.is_empty()).then(|| scores.iter().sum::<f32>() / scores.len() as f32)
+                });
+
+                Cluster {
+                    id,
+                    centroid,
+                    size,
+                    radius,
+                    label: majority.map(|(label, _)| label.to_string()),
+                    label_purity: majority.map_or(0.0, |(_, votes)| votes as f32 / size as f32),
+                    silhouette,
+                }
+            })
+            .collect();
+
+        Self {
+            algorithm,
+            clusters,
+            silhouette: per_point.and_then(|scores| mean_silhouette(&scores)),
+            assignments,
+            core_points,
+            eps,
+        }
+    }
+
+    pub fn algorithm(&self) -> ClusterAlgorithm {
+        self.algorithm
+    }
+
+    pub fn clusters(&self) -> &[Cluster] {
+        &self.clusters
+    }
+
+    /// Cluster of each input sample, in input order; `None` is DBSCAN noise
+    pub fn assignments(&self) -> &[Option<usize>] {
+        &self.assignments
+    }
+
+    /// Mean silhouette over clustered samples; `None` with fewer than two clusters
+    pub fn silhouette(&self) -> Option<f32> {
+        self.silhouette
+    }
+
+    /// Clusters of at least `min_size` members, largest first
+    pub fn campaigns(&self, min_size: usize) -> Vec<&Cluster> {
+        let mut campaigns: Vec<&Cluster> = self
+            .clusters
+            .iter()
+            .filter(|c| c.size >= min_size)
+            .collect();
+        campaigns.sort_by_key(|c| std::cmp::Reverse(c.size));
+        campaigns
+    }
+
+    /// Name a cluster, e.g. after an analyst attributed the campaign
+    pub fn set_label(&mut self, cluster: usize, label: &str) -> Result<(), String> {
+        let cluster = self
+            .clusters
+            .get_mut(cluster)
+            .ok_or_else(|| format!("no cluster {}", cluster))?;
+        cluster.label = Some(label.to_string());
+        Ok(())
+    }
+
+    /// Cluster a new fingerprint belongs to
+    ///
+    /// k-means: the nearest centroid. DBSCAN: the cluster of the nearest core point
+    /// within `eps`, or `None` if the fingerprint would be noise.
+    pub fn assign(&self, features: &[f32]) -> Option<ClusterMatch> {
+        match self.algorithm {
+            ClusterAlgorithm::KMeans => {
+                let centroids: Vec<Vec<f32>> =
+                    self.clusters.iter().map(|c| c.centroid.clone()).collect();
+                let (cluster, distance) = Self::nearest(&centroids, features);
+                (!centroids.is_empty()).then_some(ClusterMatch { cluster, distance })
+            }
+            ClusterAlgorithm::Dbscan => self
+                .core_points
+                .iter()
+                .map(|(point, cluster)| ClusterMatch {
+                    cluster: *cluster,
+                    distance: distance(point, features),
+                })
+                .filter(|m| m.distance <= self.eps)
+                .min_by(|a, b| a.distance.total_cmp(&b.distance)),
+        }
+    }
+}
+
+/// Silhouette of each sample; `None` for noise and members of singleton clusters
+///
+/// `None` overall when fewer than two clusters are present.
+fn silhouettes(
+    samples: &[FingerprintVector],
+    assignments: &[Option<usize>],
+) -> Option<Vec<Option<f32>>> {
+    let clusters = assignments.iter().flatten().max().map_or(0, |max| max + 1);
+    let mut sizes = vec![0usize; clusters];
+    for cluster in assignments.iter().flatten() {
+        sizes[*cluster] += 1;
+    }
+    if sizes.iter().filter(|&&size| size > 0).count() < 2 {
+        return None;
+    }
+
+    let scores = samples
+        .iter()
+        .zip(assignments)
+        .map(|(sample, assignment)| {
+            let own = (*assignment)?;
+            if sizes[own] < 2 {
+                return None;
+            }
+            let mut sums = vec![0.0f32; clusters];
+            for (other, cluster) in samples.iter().zip(assignments) {
+                if let Some(cluster) = cluster {
+                    sums[*cluster] += distance(&sample.features, &other.features);
+                }
+            }
+            // own distance sum includes the zero distance to itself
+            let a = sums[own] / (sizes[own] - 1) as f32;
+            let b = (0..clusters)
+                .filter(|&c| c != own && sizes[c] > 0)
+                .map(|c| sums[c] / sizes[c] as f32)
+                .fold(f32::MAX, f32::min);
+            let scale = a.max(b);
+            Some(if scale > 0.0 { (b - a) / scale } else { 0.0 })
+        })
+        .collect();
+    Some(scores)
+}
+
+fn mean_silhouette(scores: &[Option<f32>]) -> Option<f32> {
+    let scores: Vec<f32> = scores.iter().flatten().copied().collect();
+    (!scores.is_empty()).then(|| scores.iter().sum::<f32>() / scores.len() as f32)
+}
+
+/// Mean silhouette coefficient of a clustering, in [-1, 1]
+///
+/// Noise (`None`) is left out; `None` with fewer than two clusters.
+pub fn silhouette_score(
+    samples: &[FingerprintVector],
+    assignments: &[Option<usize>],
+) -> Option<f32> {
+    mean_silhouette(&silhouettes(samples, assignments)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three campaigns of near-identical clients plus scattered organic traffic
+    fn traffic() -> Vec<FingerprintVector> {
+        let mut rng = StdRng::seed_from_u64(9);
+        let mut samples = Vec::new();
+        for (centre, label, count) in [
+            ([0.1, 0.1, 0.9], "botnet-a", 40),
+            ([0.9, 0.2, 0.1], "botnet-b", 30),
+            ([0.5, 0.9, 0.5], "scraper", 20),
+        ] {
+            for i in 0..count {
+                let features = centre
+                    .iter()
+                    .map(|c| c + rng.gen_range(-0.02..0.02))
+                    .collect();
+                // a few members carry no label
+                let label = (i % 5 != 0).then(|| label.to_string());
+                samples.push(FingerprintVector::new(features, label, 1.0));
+            }
+        }
+        for _ in 0..5 {
+            let features = (0..3).map(|_| rng.gen_range(0.0..1.0)).collect();
+            samples.push(FingerprintVector::new(
+                features,
+                Some("organic".to_string()),
+                1.0,
+            ));
+        }
+        samples
+    }
+
+    #[test]
+    fn test_dbscan_finds_campaigns_and_matches_new_clients() {
+        let samples = traffic();
+        let mut model = ClusterModel::dbscan(
+            &samples,
+            &DbscanConfig {
+                eps: 0.08,
+                min_points: 5,
+            },
+        )
+        .unwrap();
+
+        let campaigns = model.campaigns(10);
+        assert_eq!(campaigns.len(), 3);
+        assert_eq!(campaigns[0].size, 40);
+        assert_eq!(campaigns[0].label.as_deref(), Some("botnet-a"));
+        assert_eq!(campaigns[0].label_purity, 0.8);
+        assert!(model.assignments()[90..].iter().all(Option::is_none));
+        assert!(model.silhouette().unwrap() > 0.8);
+        assert_eq!(
+            silhouette_score(&samples, model.assignments()),
+            model.silhouette()
+        );
+
+        let id = model.assignments()[40].unwrap();
+        let matched = model.assign(&[0.91, 0.2, 0.1]).unwrap();
+        assert_eq!(matched.cluster, id);
+        assert!(matched.distance < 0.08);
+        assert!(model.assign(&[0.5, 0.5, 0.0]).is_none());
+
+        model.set_label(id, "campaign-2026-10").unwrap();
+        assert_eq!(
+            model.clusters()[id].label.as_deref(),
+            Some("campaign-2026-10")
+        );
+        assert!(model.set_label(99, "x").is_err());
+    }
+
+    #[test]
+    fn test_kmeans_partitions_and_scores() {
+        let samples: Vec<FingerprintVector> = traffic().into_iter().take(90).collect();
+        let config = KMeansConfig {
+            k: 3,
+            ..KMeansConfig::default()
+        };
+        let model = ClusterModel::kmeans(&samples, &config).unwrap();
+
+        assert!(model.assignments().iter().all(Option::is_some));
+        let mut sizes: Vec<usize> = model.clusters().iter().map(|c| c.size).collect();
+        sizes.sort();
+        assert_eq!(sizes, [20, 30, 40]);
+        assert!(model.silhouette().unwrap() > 0.9);
+        assert!(model.clusters().iter().all(|c| c.label_purity == 0.8));
+
+        let matched = model.assign(&[0.5, 0.85, 0.5]).unwrap();
+        assert_eq!(
+            model.clusters()[matched.cluster].label.as_deref(),
+            Some("scraper")
+        );
+
+        // too many clusters for the data scores worse
+        let over = ClusterModel::kmeans(
+            &samples,
+            &KMeansConfig {
+                k: 9,
+                ..config.clone()
+            },
+        )
+        .unwrap();
+        assert!(over.silhouette().unwrap() < model.silhouette().unwrap());
+        assert!(ClusterModel::kmeans(&samples, &KMeansConfig { k: 0, ..config }).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+//! Browser Extension / Adblock Presence Inference
+//!
+//! Extensions change what a page observes: ad and tracker requests fail with
+//! client-side block errors, cosmetic filters hide bait elements, and popular
+//! extensions inject DOM markers or globals. Real users run extensions, fresh
+//! automation profiles almost never do, so presence is a useful behavioral
+//! feature for the classifier.
+
+use crate::FingerprintVector;
+
+/// Number of features appended by [`ExtensionSignals::append_to`]
+pub const EXTENSION_FEATURE_COUNT: usize = 5;
+
+/// Third-party ad / tracker hosts blocked by the common filter lists
+const AD_TRACKER_HOSTS: &[&str] = &[
+    "doubleclick.net",
+    "googlesyndication.com",
+    "google-analytics.com",
+    "googletagmanager.com",
+    "adservice.google.com",
+    "connect.facebook.net",
+    "amazon-adsystem.com",
+    "scorecardresearch.com",
+    "criteo.com",
+    "taboola.com",
+    "hotjar.com",
+];
+
+/// Error strings browsers report for requests cancelled by an extension
+const CLIENT_BLOCK_ERRORS: &[&str] = &["ERR_BLOCKED_BY_CLIENT", "NS_ERROR_CONTENT_BLOCKED"];
+
+/// Resource load observed by the collector (Resource Timing / onerror)
+#[derive(Debug, Clone)]
+pub struct ResourceLoad {
+    /// Request URL
+    pub url: String,
+    /// Whether the load failed
+    pub failed: bool,
+    /// Browser error string, when exposed
+    pub error: Option<String>,
+}
+
+impl ResourceLoad {
+    fn host(&self) -> &str {
+        let rest = self.url.split("://").nth(1).unwrap_or(&self.url);
+        rest.split(['/', ':', '?']).next().unwrap_or("")
+    }
+
+    fn is_ad_tracker(&self) -> bool {
+        let host = self.host();
+        AD_TRACKER_HOSTS
+            .iter()
+            .any(|h| host == *h || host.ends_with(&format!(".{}", h)))
+    }
+
+    fn blocked_by_client(&self) -> bool {
+        self.error
+            .as_deref()
+            .is_some_and(|e| CLIENT_BLOCK_ERRORS.iter().any(|b| e.contains(b)))
+    }
+}
+
+/// Page telemetry relevant to extension inference
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionTelemetry {
+    /// Loads of first- and third-party resources
+    pub resource_loads: Vec<ResourceLoad>,
+    /// Whether an ad bait element (e.g. `div.adsbox`) ended up hidden or zero-sized
+    pub bait_element_hidden: Option<bool>,
+    /// DOM attributes, element names and globals found on the page
+    pub dom_markers: Vec<String>,
+}
+
+/// Extensions recognizable from DOM markers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KnownExtension {
+    Grammarly,
+    LastPass,
+    OnePassword,
+    DarkReader,
+    MetaMask,
+    GoogleTranslate,
+}
+
+impl KnownExtension {
+    /// DOM markers injected by each extension
+    const MARKERS: &'static [(&'static str, KnownExtension)] = &[
+        ("data-gr-ext-installed", KnownExtension::Grammarly),
+        ("grammarly-desktop-integration", KnownExtension::Grammarly),
+        ("data-lastpass-icon-root", KnownExtension::LastPass),
+        ("data-lastpass-root", KnownExtension::LastPass),
+        ("com-1password-button", KnownExtension::OnePassword),
+        ("data-darkreader-mode", KnownExtension::DarkReader),
+        ("data-darkreader-scheme", KnownExtension::DarkReader),
+        ("window.ethereum", KnownExtension::MetaMask),
+        ("translated-ltr", KnownExtension::GoogleTranslate),
+        ("translated-rtl", KnownExtension::GoogleTranslate),
+    ];
+
+    /// Match a DOM marker
+    pub fn from_marker(marker: &str) -> Option<Self> {
+        Self::MARKERS
+            .iter()
+            .find(|(m, _)| marker.contains(m))
+            .map(|(_, ext)| *ext)
+    }
+
+    /// Password managers only appear on long-lived real profiles
+    pub fn is_password_manager(&self) -> bool {
+        matches!(self, KnownExtension::LastPass | KnownExtension::OnePassword)
+    }
+}
+
+/// Inferred extension presence
+#[derive(Debug, Clone)]
+pub struct ExtensionSignals {
+    /// Probability that a content blocker is active (0.0 to 1.0)
+    pub adblock_probability: f32,
+    /// Share of ad/tracker loads that failed while first-party loads succeeded
+    pub tracker_block_ratio: f32,
+    /// Bait element was hidden by cosmetic filtering
+    pub bait_hidden: bool,
+    /// Extensions recognized from DOM markers
+    pub extensions: Vec<KnownExtension>,
+}
+
+impl ExtensionSignals {
+    /// Whether any extension presence was inferred
+    pub fn any_extension(&self) -> bool {
+        self.adblock_probability >= 0.5 || !self.extensions.is_empty()
+    }
+
+    /// Fixed-order feature vector
+    pub fn features(&self) -> [f32; EXTENSION_FEATURE_COUNT] {
+        [
+            self.adblock_probability,
+            self.tracker_block_ratio,
+            if self.bait_hidden { 1.0 } else { 0.0 },
+            (self.extensions.len() as f32 / 3.0).min(1.0),
+            if self.extensions.iter().any(|e| e.is_password_manager()) {
+                1.0
+            } else {
+                0.0
+            },
+        ]
+    }
+
+    /// Append the features to a classifier input vector
+    pub fn append_to(&self, vector: &mut FingerprintVector) {
+        vector.features.extend_from_slice(&self.features());
+    }
+}
+
+/// Extension presence inference
+pub struct ExtensionInference;
+
+impl ExtensionInference {
+    /// Infer extension presence from page telemetry
+    pub fn infer(telemetry: &ExtensionTelemetry) -> ExtensionSignals {
+        let (trackers, first_party): (Vec<&ResourceLoad>, Vec<&ResourceLoad>) = telemetry
+            .resource_loads
+            .iter()
+            .partition(|load| load.is_ad_tracker());
+
+        let first_party_ok = first_party.is_empty() || first_party.iter().any(|l| !l.failed);
+        let tracker_block_ratio = if trackers.is_empty() || !first_party_ok {
+            // everything failing is a network problem, not a blocker
+            0.0
+        } else {
+            trackers.iter().filter(|l| l.failed).count() as f32 / trackers.len() as f32
+        };
+        let client_blocked = trackers.iter().any(|l| l.blocked_by_client());
+        let bait_hidden = telemetry.bait_element_hidden == Some(true);
+
+        let mut log_odds: f32 = -1.0;
+        if client_blocked {
+            log_odds += 6.0;
+        }
+        log_odds += tracker_block_ratio * 3.0;
+        if bait_hidden {
+            log_odds += 3.0;
+        }
+        let adblock_probability = 1.0 / (1.0 + (-log_odds).exp());
+
+        let mut extensions: Vec<KnownExtension> = Vec::new();
+        for marker in &telemetry.dom_markers {
+            if let Some(ext) = KnownExtension::from_marker(marker) {
+                if !extensions.contains(&ext) {
+                    extensions.push(ext);
+                }
+            }
+        }
+
+        ExtensionSignals {
+            adblock_probability,
+            tracker_block_ratio,
+            bait_hidden,
+            extensions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(url: &str, failed: bool, error: Option<&str>) -> ResourceLoad {
+        ResourceLoad {
+            url: url.to_string(),
+            failed,
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_adblock_inferred_from_blocked_trackers() {
+        let telemetry = ExtensionTelemetry {
+            resource_loads: vec![
+                load("https://example.com/app.js", false, None),
+                load(
+                    "https://www.googletagmanager.com/gtm.js",
+                    true,
+                    Some("net::ERR_BLOCKED_BY_CLIENT"),
+                ),
+                load("https://securepubads.g.doubleclick.net/tag", true, None),
+            ],
+            bait_element_hidden: Some(true),
+            dom_markers: vec!["data-lastpass-icon-root".to_string()],
+        };
+
+        let signals = ExtensionInference::infer(&telemetry);
+        assert!(signals.adblock_probability > 0.9);
+        assert_eq!(signals.tracker_block_ratio, 1.0);
+        assert_eq!(signals.extensions, vec![KnownExtension::LastPass]);
+
+        let mut vector = FingerprintVector::new(vec![0.5], None, 1.0);
+        signals.append_to(&mut vector);
+        assert_eq!(vector.features.len(), 1 + EXTENSION_FEATURE_COUNT);
+        assert_eq!(vector.features[5], 1.0);
+    }
+
+    #[test]
+    fn test_network_failure_is_not_adblock() {
+        let telemetry = ExtensionTelemetry {
+            resource_loads: vec![
+                load("https://example.com/app.js", true, None),
+                load("https://www.google-analytics.com/analytics.js", true, None),
+            ],
+            bait_element_hidden: Some(false),
+            dom_markers: vec![],
+        };
+
+        let signals = ExtensionInference::infer(&telemetry);
+        assert_eq!(signals.tracker_block_ratio, 0.0);
+        assert!(!signals.any_extension());
+    }
+}
@@ -0,0 +1,592 @@
+//! Feature extraction from fingerprints
+//!
+//! A [`FeatureExtractor`] turns the raw [`fields`](Fingerprint::fields) of one
+//! fingerprint type into columns, one [`FeatureSpec`] at a time:
+//! - [`Encoding::Numeric`]: numbers as-is, flags as 1.0 / 0.0
+//! - [`Encoding::OneHot`]: one column per known category plus an "other" column
+//! - [`Encoding::Hashed`]: text or list items counted into a fixed number of buckets
+//!   (xxh3, stable across processes and platforms)
+//! - [`Encoding::Length`]: list item count, text length
+//!
+//! A [`FeatureRegistry`] holds one extractor per type and lays their columns out
+//! into one combined vector: a one-hot block of the registered types, then each
+//! extractor's block in registration order, zero for the other types. The layout
+//! is described by a [`VectorSchema`] whose `id` changes whenever a column does,
+//! so normalization stats (and models) fitted on one layout are never applied to
+//! another.
+//!
+//! Normalization stats (per-column mean and standard deviation) are persisted as
+//! a versioned model artifact under the model directory.
+
+use crate::FingerprintVector;
+use fingerprint_core::data_dirs::DataDirs;
+use fingerprint_core::fingerprint::{FieldValue, Fingerprint, FingerprintType};
+use fingerprint_core::schema::{self, ArtifactKind};
+use fingerprint_core::stable_hash::{hash_str, StableHashBuilder};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Version of the combined vector layout rules; bump when they change
+pub const VECTOR_SCHEMA_VERSION: u32 = 1;
+
+/// File under the model directory holding normalization stats
+const NORMALIZATION_FILE: &str = "feature_normalization.json";
+
+/// How one field becomes columns
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Encoding {
+    /// Number or flag; missing is 0.0
+    Numeric,
+    /// One column per category, then one for anything else
+    OneHot { categories: Vec<String> },
+    /// Text or list items counted into `buckets` columns by hash
+    Hashed { buckets: usize },
+    /// Item count of a list, character count of text
+    Length,
+}
+
+/// One field of a fingerprint and its encoding
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureSpec {
+    pub field: String,
+    pub encoding: Encoding,
+}
+
+impl FeatureSpec {
+    pub fn numeric(field: &str) -> Self {
+        Self::new(field, Encoding::Numeric)
+    }
+
+    pub fn one_hot(field: &str, categories: &[&str]) -> Self {
+        let categories = categories.iter().map(|c| c.to_string()).collect();
+        Self::new(field, Encoding::OneHot { categories })
+    }
+
+    pub fn hashed(field: &str, buckets: usize) -> Self {
+        Self::new(field, Encoding::Hashed { buckets })
+    }
+
+    pub fn length(field: &str) -> Self {
+        Self::new(field, Encoding::Length)
+    }
+
+    fn new(field: &str, encoding: Encoding) -> Self {
+        Self {
+            field: field.to_string(),
+            encoding,
+        }
+    }
+
+    /// Column names, prefixed with `prefix`
+    pub fn columns(&self, prefix: &str) -> Vec<String> {
+        let field = format!("{}.{}", prefix, self.field);
+        match &self.encoding {
+            Encoding::Numeric => vec![field],
+            Encoding::OneHot { categories } => categories
+                .iter()
+                .map(|c| format!("{}={}", field, c))
+                .chain(std::iter::once(format!("{}=?", field)))
+                .collect(),
+            Encoding::Hashed { buckets } => {
+                (0..*buckets).map(|b| format!("{}#{}", field, b)).collect()
+            }
+            Encoding::Length => vec![format!("{}.len", field)],
+        }
+    }
+
+    fn width(&self) -> usize {
+        match &self.encoding {
+            Encoding::Numeric | Encoding::Length => 1,
+            Encoding::OneHot { categories } => categories.len() + 1,
+            Encoding::Hashed { buckets } => *buckets,
+        }
+    }
+
+    /// Append this spec's columns for `value` to `out`
+    fn encode(&self, value: Option<&FieldValue>, out: &mut Vec<f32>) {
+        let start = out.len();
+        out.resize(start + self.width(), 0.0);
+        let columns = &mut out[start..];
+        let Some(value) = value else {
+            return;
+        };
+        // categorical view: numbers and flags by their text form
+        let items: Vec<String> = match value {
+            FieldValue::Text(text) => vec![text.clone()],
+            FieldValue::List(items) => items.clone(),
+            FieldValue::Number(n) => vec![n.to_string()],
+            FieldValue::Bool(b) => vec![b.to_string()],
+        };
+        match &self.encoding {
+            Encoding::Numeric => {
+                columns[0] = match value {
+                    FieldValue::Number(n) => *n as f32,
+                    FieldValue::Bool(b) => f32::from(u8::from(*b)),
+                    FieldValue::Text(_) | FieldValue::List(_) => 0.0,
+                }
+            }
+            Encoding::OneHot { categories } => {
+                for item in items {
+                    let index = categories
+                        .iter()
+                        .position(|c| *c == item)
+                        .unwrap_or(categories.len());
+                    columns[index] = 1.0;
+                }
+            }
+            Encoding::Hashed { buckets } if *buckets > 0 => {
+                for item in items {
+                    columns[(hash_str(&item) % *buckets as u64) as usize] += 1.0;
+                }
+            }
+            Encoding::Hashed { .. } => {}
+            Encoding::Length => {
+                columns[0] = match value {
+                    FieldValue::Text(text) => text.chars().count() as f32,
+                    FieldValue::List(items) => items.len() as f32,
+                    FieldValue::Number(_) | FieldValue::Bool(_) => 0.0,
+                }
+            }
+        }
+    }
+}
+
+/// Columns for one fingerprint type
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureExtractor {
+    fingerprint_type: FingerprintType,
+    specs: Vec<FeatureSpec>,
+}
+
+impl FeatureExtractor {
+    pub fn new(fingerprint_type: FingerprintType) -> Self {
+        Self {
+            fingerprint_type,
+            specs: Vec::new(),
+        }
+    }
+
+    /// Add a field
+    pub fn with(mut self, spec: FeatureSpec) -> Self {
+        self.specs.push(spec);
+        self
+    }
+
+    /// ClientHello: version, cipher / extension / group / signature sets, ALPN
+    pub fn tls() -> Self {
+        Self::new(FingerprintType::Tls)
+            .with(FeatureSpec::one_hot(
+                "version",
+                &["769", "770", "771", "772"],
+            ))
+            .with(FeatureSpec::length("cipher_suites"))
+            .with(FeatureSpec::hashed("cipher_suites", 16))
+            .with(FeatureSpec::length("extensions"))
+            .with(FeatureSpec::hashed("extensions", 16))
+            .with(FeatureSpec::hashed("elliptic_curves", 8))
+            .with(FeatureSpec::hashed("signature_algorithms", 8))
+            .with(FeatureSpec::numeric("sni"))
+            .with(FeatureSpec::one_hot("alpn", &["h2", "http/1.1", "h3"]))
+    }
+
+    /// Request headers, User-Agent and HTTP/2 SETTINGS
+    pub fn http() -> Self {
+        Self::new(FingerprintType::Http)
+            .with(FeatureSpec::length("header_names"))
+            .with(FeatureSpec::hashed("header_names", 16))
+            .with(FeatureSpec::hashed("user_agent", 8))
+            .with(FeatureSpec::length("user_agent"))
+            .with(FeatureSpec::numeric("http2"))
+            .with(FeatureSpec::numeric("h2_header_table_size"))
+            .with(FeatureSpec::numeric("h2_initial_window_size"))
+            .with(FeatureSpec::numeric("h2_max_concurrent_streams"))
+            .with(FeatureSpec::numeric("h2_max_frame_size"))
+            .with(FeatureSpec::numeric("h2_max_header_list_size"))
+    }
+
+    /// SYN: TTL, window, MSS, window scale and option layout
+    pub fn tcp() -> Self {
+        Self::new(FingerprintType::Tcp)
+            .with(FeatureSpec::numeric("ttl"))
+            .with(FeatureSpec::numeric("window_size"))
+            .with(FeatureSpec::numeric("mss"))
+            .with(FeatureSpec::numeric("window_scale"))
+            .with(FeatureSpec::hashed("options", 8))
+    }
+
+    pub fn fingerprint_type(&self) -> FingerprintType {
+        self.fingerprint_type
+    }
+
+    pub fn specs(&self) -> &[FeatureSpec] {
+        &self.specs
+    }
+
+    /// Column names, prefixed with the fingerprint type
+    pub fn columns(&self) -> Vec<String> {
+        let prefix = self.fingerprint_type.as_str();
+        self.specs.iter().flat_map(|s| s.columns(prefix)).collect()
+    }
+
+    pub fn width(&self) -> usize {
+        self.specs.iter().map(FeatureSpec::width).sum()
+    }
+
+    /// This extractor's block for `fingerprint`; missing fields are zero
+    pub fn extract(&self, fingerprint: &dyn Fingerprint) -> Vec<f32> {
+        let fields = fingerprint.fields();
+        let mut out = Vec::with_capacity(self.width());
+        for spec in &self.specs {
+            let value = fields
+                .iter()
+                .find(|(name, _)| *name == spec.field)
+                .map(|(_, value)| value);
+            spec.encode(value, &mut out);
+        }
+        out
+    }
+}
+
+/// Layout of the vectors a [`FeatureRegistry`] produces
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorSchema {
+    /// [`VECTOR_SCHEMA_VERSION`] of the layout rules
+    pub version: u32,
+    /// `v<version>-<hash of the column names>`
+    pub id: String,
+    pub columns: Vec<String>,
+}
+
+impl VectorSchema {
+    fn new(columns: Vec<String>) -> Self {
+        let mut hasher = StableHashBuilder::new();
+        hasher.write_u32(VECTOR_SCHEMA_VERSION);
+        hasher.write_usize(columns.len());
+        for column in &columns {
+            hasher.write_str(column);
+        }
+        Self {
+            version: VECTOR_SCHEMA_VERSION,
+            id: format!("v{}-{:016x}", VECTOR_SCHEMA_VERSION, hasher.finish()),
+            columns,
+        }
+    }
+}
+
+/// Per-column mean and variance (Welford), fitted on one [`VectorSchema`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NormalizationStats {
+    /// [`VectorSchema::id`] the stats were fitted on
+    pub schema: String,
+    pub count: u64,
+    pub mean: Vec<f64>,
+    m2: Vec<f64>,
+}
+
+impl NormalizationStats {
+    pub fn new(schema: &VectorSchema) -> Self {
+        Self {
+            schema: schema.id.clone(),
+            count: 0,
+            mean: vec![0.0; schema.columns.len()],
+            m2: vec![0.0; schema.columns.len()],
+        }
+    }
+
+    /// Add one raw vector
+    pub fn observe(&mut self, row: &[f32]) {
+        self.count += 1;
+        let n = self.count as f64;
+        for ((mean, m2), x) in self.mean.iter_mut().zip(&mut self.m2).zip(row) {
+            let x = *x as f64;
+            let delta = x - *mean;
+            *mean += delta / n;
+            *m2 += delta * (x - *mean);
+        }
+    }
+
+    /// Population standard deviation of each column
+    pub fn std_dev(&self) -> Vec<f64> {
+        let n = self.count.max(1) as f64;
+        self.m2.iter().map(|m2| (m2 / n).sqrt()).collect()
+    }
+
+    /// Z-score `row` in place; constant columns are only centred
+    pub fn apply(&self, row: &mut [f32]) {
+        for ((x, mean), std) in row.iter_mut().zip(&self.mean).zip(self.std_dev()) {
+            let centred = *x as f64 - mean;
+            *x = if std > f64::EPSILON {
+                centred / std
+            } else {
+                centred
+            } as f32;
+        }
+    }
+}
+
+/// Extractors by fingerprint type, combined into one vector layout
+#[derive(Debug, Clone, Default)]
+pub struct FeatureRegistry {
+    extractors: Vec<FeatureExtractor>,
+    normalization: Option<NormalizationStats>,
+}
+
+impl FeatureRegistry {
+    /// Registry without extractors
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// TLS, HTTP and TCP extractors
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(FeatureExtractor::tls());
+        registry.register(FeatureExtractor::http());
+        registry.register(FeatureExtractor::tcp());
+        registry
+    }
+
+    /// Add an extractor, replacing the one for the same type
+    ///
+    /// The layout changes, so fitted normalization stats are dropped.
+    pub fn register(&mut self, extractor: FeatureExtractor) {
+        match self
+            .extractors
+            .iter_mut()
+            .find(|e| e.fingerprint_type == extractor.fingerprint_type)
+        {
+            Some(existing) => *existing = extractor,
+            None => self.extractors.push(extractor),
+        }
+        self.normalization = None;
+    }
+
+    pub fn extractor(&self, fingerprint_type: FingerprintType) -> Option<&FeatureExtractor> {
+        self.extractors
+            .iter()
+            .find(|e| e.fingerprint_type == fingerprint_type)
+    }
+
+    /// Layout of [`FeatureRegistry::extract_raw`] vectors
+    pub fn schema(&self) -> VectorSchema {
+        let types = self
+            .extractors
+            .iter()
+            .map(|e| format!("type={}", e.fingerprint_type));
+        let blocks = self.extractors.iter().flat_map(FeatureExtractor::columns);
+        VectorSchema::new(types.chain(blocks).collect())
+    }
+
+    /// Combined vector before normalization
+    pub fn extract_raw(&self, fingerprint: &dyn Fingerprint) -> Result<Vec<f32>, String> {
+        let fingerprint_type = fingerprint.fingerprint_type();
+        if self.extractor(fingerprint_type).is_none() {
+            return Err(format!(
+                "no feature extractor for {} fingerprints",
+                fingerprint_type
+            ));
+        }
+        let mut out: Vec<f32> = self
+            .extractors
+            .iter()
+            .map(|e| f32::from(u8::from(e.fingerprint_type == fingerprint_type)))
+            .collect();
+        for extractor in &self.extractors {
+            if extractor.fingerprint_type == fingerprint_type {
+                out.extend(extractor.extract(fingerprint));
+            } else {
+                out.resize(out.len() + extractor.width(), 0.0);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Combined vector, normalized once stats are fitted or loaded
+    ///
+    /// Confidence comes from the fingerprint metadata; there is no label.
+    pub fn extract(&self, fingerprint: &dyn Fingerprint) -> Result<FingerprintVector, String> {
+        let mut features = self.extract_raw(fingerprint)?;
+        if let Some(stats) = &self.normalization {
+            stats.apply(&mut features);
+        }
+        Ok(FingerprintVector::new(
+            features,
+            None,
+            fingerprint.metadata().confidence as f32,
+        ))
+    }
+
+    /// Fit normalization stats on a sample of fingerprints
+    pub fn fit_normalization(&mut self, fingerprints: &[&dyn Fingerprint]) -> Result<(), String> {
+        let mut stats = NormalizationStats::new(&self.schema());
+        for fingerprint in fingerprints {
+            stats.observe(&self.extract_raw(*fingerprint)?);
+        }
+        self.normalization = Some(stats);
+        Ok(())
+    }
+
+    pub fn normalization(&self) -> Option<&NormalizationStats> {
+        self.normalization.as_ref()
+    }
+
+    /// Persist the normalization stats under the model directory of `dirs`
+    ///
+    /// Returns the file written. Without fitted stats this is an error.
+    pub fn save_normalization(&self, dirs: &DataDirs) -> io::Result<PathBuf> {
+        let stats = self.normalization.as_ref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "no normalization stats fitted")
+        })?;
+        let json = schema::registry()
+            .to_json(ArtifactKind::Model, stats)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let path = DataDirs::prepare_file(dirs.model_dir().join(NORMALIZATION_FILE))?;
+        fs::write(&path, json)?;
+        Ok(path)
+    }
+
+    /// Load stats saved by [`FeatureRegistry::save_normalization`]
+    ///
+    /// Returns whether stats were loaded; a missing file loads none. Stats
+    /// fitted on another vector layout are an error.
+    pub fn load_normalization(&mut self, dirs: &DataDirs) -> io::Result<bool> {
+        let path = dirs.model_dir().join(NORMALIZATION_FILE);
+        let json = match fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        let invalid = |message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), message),
+            )
+        };
+        let stats: NormalizationStats = schema::registry()
+            .from_json(ArtifactKind::Model, &json)
+            .map_err(|e| invalid(e.to_string()))?;
+        let schema = self.schema();
+        if stats.schema != schema.id || stats.mean.len() != schema.columns.len() {
+            return Err(invalid(format!(
+                "stats fitted on vector schema {}, registry produces {}",
+                stats.schema, schema.id
+            )));
+        }
+        self.normalization = Some(stats);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fingerprint_core::http::HttpFingerprint;
+    use fingerprint_core::signature::ClientHelloSignature;
+    use fingerprint_core::version::TlsVersion;
+    use std::collections::HashMap;
+
+    fn client_hello(ciphers: &[u16], alpn: &str) -> ClientHelloSignature {
+        let mut hello = ClientHelloSignature::new();
+        hello.version = TlsVersion::V1_3;
+        hello.cipher_suites = ciphers.to_vec();
+        hello.extensions = vec![0x0000, 0x0010, 0x002b];
+        hello.alpn = Some(alpn.to_string());
+        hello.metadata.confidence = 0.7;
+        hello
+    }
+
+    #[test]
+    fn test_registry_lays_out_typed_blocks() {
+        let registry = FeatureRegistry::builtin();
+        let schema = registry.schema();
+        assert_eq!(&schema.columns[..3], ["type=tls", "type=http", "type=tcp"]);
+        assert!(schema.columns.contains(&"tls.alpn=h2".to_string()));
+        assert!(schema.id.starts_with("v1-"));
+
+        let hello = client_hello(&[0x1301, 0x1302, 0x0a0a], "h2");
+        let raw = registry.extract_raw(&hello).unwrap();
+        assert_eq!(raw.len(), schema.columns.len());
+        let column = |name: &str| raw[schema.columns.iter().position(|c| c == name).unwrap()];
+        assert_eq!(column("type=tls"), 1.0);
+        assert_eq!(column("type=http"), 0.0);
+        assert_eq!(column("tls.version=772"), 1.0);
+        // GREASE is dropped before counting
+        assert_eq!(column("tls.cipher_suites.len"), 2.0);
+        assert_eq!(column("tls.alpn=h2"), 1.0);
+        assert_eq!(column("tls.alpn=?"), 0.0);
+        let hashed: f32 = schema
+            .columns
+            .iter()
+            .zip(&raw)
+            .filter(|(c, _)| c.starts_with("tls.cipher_suites#"))
+            .map(|(_, x)| x)
+            .sum();
+        assert_eq!(hashed, 2.0);
+        // hashing is stable, so the same fingerprint always lands in the same buckets
+        assert_eq!(registry.extract_raw(&hello.clone()).unwrap(), raw);
+
+        let mut headers = HashMap::new();
+        headers.insert("Accept".to_string(), "*/*".to_string());
+        let http = HttpFingerprint::new("curl/8.0".to_string(), headers);
+        let raw = registry.extract_raw(&http).unwrap();
+        assert_eq!(raw[1], 1.0);
+        assert_eq!(
+            raw[schema
+                .columns
+                .iter()
+                .position(|c| c == "http.user_agent.len")
+                .unwrap()],
+            8.0
+        );
+
+        assert!(FeatureRegistry::new().extract_raw(&http).is_err());
+    }
+
+    #[test]
+    fn test_normalization_persists_per_schema() {
+        let mut registry = FeatureRegistry::builtin();
+        let hellos = [
+            client_hello(&[0x1301], "h2"),
+            client_hello(&[0x1301, 0x1302, 0x1303], "http/1.1"),
+        ];
+        let fingerprints: Vec<&dyn Fingerprint> = hellos.iter().map(|h| h as _).collect();
+        registry.fit_normalization(&fingerprints).unwrap();
+
+        let schema = registry.schema();
+        let len = schema
+            .columns
+            .iter()
+            .position(|c| c == "tls.cipher_suites.len")
+            .unwrap();
+        let vector = registry.extract(&hellos[0]).unwrap();
+        assert_eq!(vector.features[len], -1.0);
+        assert_eq!(vector.confidence, 0.7);
+
+        let root = tempfile::tempdir().unwrap();
+        let dirs = DataDirs::portable(root.path());
+        let path = registry.save_normalization(&dirs).unwrap();
+        assert!(path.starts_with(dirs.model_dir()));
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("\"kind\": \"model\""));
+
+        let mut restored = FeatureRegistry::builtin();
+        assert!(restored.load_normalization(&dirs).unwrap());
+        assert_eq!(
+            restored.extract(&hellos[0]).unwrap().features,
+            vector.features
+        );
+
+        // another layout refuses the stats
+        let mut changed = FeatureRegistry::builtin();
+        changed.register(FeatureExtractor::tcp().with(FeatureSpec::numeric("extra")));
+        assert_ne!(changed.schema().id, schema.id);
+        assert!(changed.load_normalization(&dirs).is_err());
+
+        let empty = DataDirs::portable(root.path().join("empty"));
+        assert!(!restored.load_normalization(&empty).unwrap());
+    }
+}
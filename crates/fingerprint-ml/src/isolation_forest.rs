@@ -0,0 +1,286 @@
+//! Isolation Forest (Liu, Ting & Zhou, 2008)
+//!
+//! Each tree recursively splits a random subsample on a random feature at a
+//! random value until every point is isolated or the height limit is reached.
+//! Outliers are isolated after few splits, so the average path length over the
+//! forest, normalized by the expected path length of an unsuccessful BST search,
+//! gives the score `s = 2^(-E[h(x)] / c(n))`: close to 1 for anomalies, well below
+//! 0.5 for points inside dense regions.
+//!
+//! [`IsolationForest::fit_partial`] regrows the oldest trees on recent samples,
+//! so the forest follows drifting traffic without a full retrain.
+
+use crate::FingerprintVector;
+use rand::rngs::StdRng;
+use rand::seq::index::sample;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Isolation Forest training parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IsolationForestConfig {
+    /// Number of trees
+    pub trees: usize,
+    /// Points drawn (without replacement) for each tree
+    pub sample_size: usize,
+    /// Seed of the subsampling and split choices
+    pub seed: u64,
+}
+
+impl Default for IsolationForestConfig {
+    fn default() -> Self {
+        Self {
+            trees: 100,
+            sample_size: 256,
+            seed: 42,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Node {
+    Split {
+        feature: usize,
+        value: f32,
+        left: Box<Node>,
+        right: Box<Node>,
+    },
+    Leaf {
+        size: usize,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Tree {
+    root: Node,
+    /// Points the tree was grown on, normalizing its path lengths
+    sample_size: usize,
+}
+
+/// Trained Isolation Forest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IsolationForest {
+    trees: Vec<Tree>,
+    dimensions: usize,
+    config: IsolationForestConfig,
+    /// Tree regrown next by [`IsolationForest::fit_partial`]
+    next_tree: usize,
+    /// Partial fits so far, mixed into the seed of each one
+    updates: u64,
+}
+
+/// Average path length of an unsuccessful search in a BST of `n` points
+fn average_path_length(n: usize) -> f32 {
+    match n {
+        0 | 1 => 0.0,
+        2 => 1.0,
+        _ => {
+            let n = n as f32;
+            // harmonic number H(n-1) ~ ln(n-1) + Euler-Mascheroni constant
+            2.0 * ((n - 1.0).ln() + 0.577_215_7) - 2.0 * (n - 1.0) / n
+        }
+    }
+}
+
+/// Length shared by all samples
+pub(crate) fn check_samples(samples: &[FingerprintVector]) -> Result<usize, String> {
+    let dimensions = samples
+        .first()
+        .map(|s| s.features.len())
+        .ok_or("no training samples")?;
+    if dimensions == 0 {
+        return Err("training samples have no features".to_string());
+    }
+    if samples.iter().any(|s| s.features.len() != dimensions) {
+        return Err("training samples differ in length".to_string());
+    }
+    Ok(dimensions)
+}
+
+impl IsolationForest {
+    /// Train on a batch of fingerprint vectors of equal length
+    pub fn fit(
+        samples: &[FingerprintVector],
+        config: &IsolationForestConfig,
+    ) -> Result<Self, String> {
+        let dimensions = check_samples(samples)?;
+        if config.trees == 0 || config.sample_size < 2 {
+            return Err("need at least one tree and a sample size of 2".to_string());
+        }
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let trees = (0..config.trees)
+            .map(|_| Self::grow_tree(samples, config.sample_size, &mut rng))
+            .collect();
+        Ok(Self {
+            trees,
+            dimensions,
+            config: config.clone(),
+            next_tree: 0,
+            updates: 0,
+        })
+    }
+
+    /// Regrow the `count` oldest trees on subsamples of `samples`
+    ///
+    /// Samples of another length than the forest was trained on are an error.
+    pub fn fit_partial(
+        &mut self,
+        samples: &[FingerprintVector],
+        count: usize,
+    ) -> Result<(), String> {
+        if check_samples(samples)? != self.dimensions {
+            return Err(format!(
+                "forest was trained on {} features",
+                self.dimensions
+            ));
+        }
+        self.updates += 1;
+        let mut rng = StdRng::seed_from_u64(self.config.seed ^ self.updates.rotate_left(32));
+        for _ in 0..count.min(self.trees.len()) {
+            self.trees[self.next_tree] =
+                Self::grow_tree(samples, self.config.sample_size, &mut rng);
+            self.next_tree = (self.next_tree + 1) % self.trees.len();
+        }
+        Ok(())
+    }
+
+    fn grow_tree(samples: &[FingerprintVector], sample_size: usize, rng: &mut StdRng) -> Tree {
+        let sample_size = sample_size.min(samples.len());
+        let height_limit = (sample_size as f32).log2().ceil() as usize;
+        let points: Vec<&[f32]> = sample(rng, samples.len(), sample_size)
+            .into_iter()
+            .map(|i| samples[i].features.as_slice())
+            .collect();
+        Tree {
+            root: Self::grow(&points, 0, height_limit, rng),
+            sample_size,
+        }
+    }
+
+    fn grow(points: &[&[f32]], depth: usize, height_limit: usize, rng: &mut StdRng) -> Node {
+        if depth >= height_limit || points.len() <= 1 {
+            return Node::Leaf { size: points.len() };
+        }
+        // only features that still vary can split these points
+        let dimensions = points[0].len();
+        let ranges: Vec<(usize, f32, f32)> = (0..dimensions)
+            .filter_map(|feature| {
+                let (min, max) = points.iter().fold((f32::MAX, f32::MIN), |(lo, hi), p| {
+                    (lo.min(p[feature]), hi.max(p[feature]))
+                });
+                (max > min).then_some((feature, min, max))
+            })
+            .collect();
+        if ranges.is_empty() {
+            return Node::Leaf { size: points.len() };
+        }
+
+        let (feature, min, max) = ranges[rng.gen_range(0..ranges.len())];
+        let value = rng.gen_range(min..max);
+        let (left, right): (Vec<&[f32]>, Vec<&[f32]>) =
+            points.iter().partition(|p| p[feature] < value);
+        Node::Split {
+            feature,
+            value,
+            left: Box::new(Self::grow(&left, depth + 1, height_limit, rng)),
+            right: Box::new(Self::grow(&right, depth + 1, height_limit, rng)),
+        }
+    }
+
+    fn path_length(node: &Node, features: &[f32], depth: usize) -> f32 {
+        match node {
+            Node::Leaf { size } => depth as f32 + average_path_length(*size),
+            Node::Split {
+                feature,
+                value,
+                left,
+                right,
+            } => {
+                let next = if features.get(*feature).copied().unwrap_or(0.0) < *value {
+                    left
+                } else {
+                    right
+                };
+                Self::path_length(next, features, depth + 1)
+            }
+        }
+    }
+
+    /// Mean isolation depth of `features` over the forest
+    pub fn mean_path_length(&self, features: &[f32]) -> f32 {
+        self.trees
+            .iter()
+            .map(|tree| Self::path_length(&tree.root, features, 0))
+            .sum::<f32>()
+            / self.trees.len() as f32
+    }
+
+    /// Anomaly score in (0, 1]; about 0.5 and below is normal
+    pub fn score(&self, features: &[f32]) -> f32 {
+        // each tree normalized by its own sample size, which partial fits may change
+        let normalized = self
+            .trees
+            .iter()
+            .map(|tree| {
+                Self::path_length(&tree.root, features, 0)
+                    / average_path_length(tree.sample_size).max(f32::EPSILON)
+            })
+            .sum::<f32>()
+            / self.trees.len() as f32;
+        2f32.powf(-normalized)
+    }
+
+    /// Feature count the forest was trained on
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    pub fn tree_count(&self) -> usize {
+        self.trees.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outliers_isolate_faster() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let samples: Vec<FingerprintVector> = (0..500)
+            .map(|_| {
+                // roughly normal around 0.5: sparse tails, dense centre
+                let features = (0..4)
+                    .map(|_| 0.5 + (0..3).map(|_| rng.gen_range(-0.05..0.05)).sum::<f32>())
+                    .collect();
+                FingerprintVector::new(features, None, 1.0)
+            })
+            .collect();
+        let forest = IsolationForest::fit(&samples, &IsolationForestConfig::default()).unwrap();
+        assert_eq!(forest.tree_count(), 100);
+
+        let inlier = forest.score(&[0.5, 0.5, 0.5, 0.5]);
+        let outlier = forest.score(&[0.9, 0.1, 0.9, 0.1]);
+        assert!(inlier < 0.5, "inlier {}", inlier);
+        assert!(outlier > 0.65, "outlier {}", outlier);
+
+        assert!(IsolationForest::fit(&[], &IsolationForestConfig::default()).is_err());
+
+        // regrowing on shifted traffic moves the dense region
+        let shifted: Vec<FingerprintVector> = samples
+            .iter()
+            .map(|s| {
+                let features = s.features.iter().map(|x| x + 0.3).collect();
+                FingerprintVector::new(features, None, 1.0)
+            })
+            .collect();
+        let mut drifting = forest.clone();
+        drifting.fit_partial(&shifted, 100).unwrap();
+        assert!(drifting.score(&[0.8, 0.8, 0.8, 0.8]) < 0.5);
+        assert!(drifting.score(&[0.5, 0.5, 0.5, 0.5]) > inlier);
+        let narrow = [FingerprintVector::new(vec![0.5, 0.5], None, 1.0)];
+        assert!(drifting.fit_partial(&narrow, 1).is_err());
+    }
+}
@@ -9,17 +9,61 @@
 //! - One-Class SVM for novelty detection
 //! - AutoEncoder neural networks for reconstruction-based anomaly detection
 //! - Statistical ensemble methods for robust detection
+//! - k-means / DBSCAN clustering for botnet campaign detection
 //! - Pre-trained models for classification tasks
 //! - Online learning capabilities for adaptive threat detection
-
+//! - Feature drift (PSI) and precision / recall monitoring of deployed models
+//! - Extension/adblock presence inference as a behavioral feature
+//! - Adversarial robustness evaluation (minimum label-flipping perturbation)
+//!
+//! [`AdvancedAnomalyDetector`] starts as a baseline distance check; after
+//! [`train`](AdvancedAnomalyDetector::train) it combines the baseline with an
+//! Isolation Forest and a linear One-Class SVM, weighted per
+//! [`AnomalyEnsembleConfig`] (the `ml.ensemble` section of fingerprint-config).
+//! It keeps learning from production traffic through the [`online`] API.
+//!
+//! [`FeatureRegistry`] turns TLS, HTTP and TCP fingerprints into the
+//! [`FingerprintVector`]s these models consume.
+
+pub mod adversarial;
+pub mod clustering;
+pub mod extensions;
+pub mod features;
+pub mod isolation_forest;
+pub mod monitor;
+pub mod one_class_svm;
+pub mod online;
 pub mod pretrained_models;
 
+pub use adversarial::{
+    AdversarialConfig, AdversarialEvaluator, AdversarialTarget, AttackMethod, AttackResult,
+    ModelTarget, RobustnessMetrics,
+};
+pub use clustering::{
+    silhouette_score, Cluster, ClusterAlgorithm, ClusterMatch, ClusterModel, DbscanConfig,
+    KMeansConfig,
+};
+pub use extensions::{
+    ExtensionInference, ExtensionSignals, ExtensionTelemetry, KnownExtension, ResourceLoad,
+    EXTENSION_FEATURE_COUNT,
+};
+pub use features::{
+    Encoding, FeatureExtractor, FeatureRegistry, FeatureSpec, NormalizationStats, VectorSchema,
+    VECTOR_SCHEMA_VERSION,
+};
+pub use isolation_forest::{IsolationForest, IsolationForestConfig};
+pub use monitor::{
+    ClassificationMetrics, ModelMonitor, ModelMonitorConfig, MonitorReport, MonitorViolation,
+};
+pub use one_class_svm::{OneClassSvm, OneClassSvmConfig};
+pub use online::{OnlineLearningConfig, UpdateReport};
 pub use pretrained_models::{
     EnsemblePredictor, ModelCacheStats, ModelMetrics, ModelPrediction, PreTrainedModel,
     PreTrainedModelManager,
 };
 
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 
 /// Fingerprint vector
 #[derive(Debug, Clone)]
@@ -71,32 +115,200 @@ pub enum AnomalyClassification {
     Uncertain,
 }
 
+/// Weight of each model in the anomaly ensemble
+///
+/// Only trained models take part; the weights of those present are normalized.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnsembleWeights {
+    pub baseline: f32,
+    pub isolation_forest: f32,
+    pub one_class_svm: f32,
+}
+
+impl Default for EnsembleWeights {
+    fn default() -> Self {
+        Self {
+            baseline: 0.2,
+            isolation_forest: 0.5,
+            one_class_svm: 0.3,
+        }
+    }
+}
+
+/// Anomaly ensemble configuration, the `ml.ensemble` section of fingerprint-config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnomalyEnsembleConfig {
+    pub weights: EnsembleWeights,
+    pub isolation_forest: IsolationForestConfig,
+    pub one_class_svm: OneClassSvmConfig,
+    pub online: OnlineLearningConfig,
+}
+
+impl AnomalyEnsembleConfig {
+    /// Read either the section itself or a full fingerprint-config document
+    pub fn from_value(value: &serde_json::Value) -> Result<Self, String> {
+        let section = value
+            .get("ml")
+            .and_then(|ml| ml.get("ensemble"))
+            .unwrap_or(value);
+        let config: Self = serde_json::from_value(section.clone()).map_err(|e| e.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Load from a JSON file, as [`AnomalyEnsembleConfig::from_value`]
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let value: serde_json::Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        Self::from_value(&value)
+    }
+
+    /// Weights must be non-negative and not all zero
+    pub fn validate(&self) -> Result<(), String> {
+        self.online.validate()?;
+        let weights = [
+            self.weights.baseline,
+            self.weights.isolation_forest,
+            self.weights.one_class_svm,
+        ];
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err("ensemble weights must be non-negative".to_string());
+        }
+        if weights.iter().sum::<f32>() <= 0.0 {
+            return Err("at least one ensemble weight must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Per-model anomaly scores (0.0 = typical, 1.0 = extreme)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelScores {
+    /// Distance from the baseline (training centroid once trained)
+    pub baseline: f32,
+    /// Isolation Forest score rescaled so 0.5 and below maps to 0.0
+    pub isolation_forest: Option<f32>,
+    /// Distance outside the One-Class SVM region
+    pub one_class_svm: Option<f32>,
+}
+
 /// Advanced anomaly detector using multiple ML techniques
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AdvancedAnomalyDetector {
-    // Simple implementation for now - baseline for normal behavior
+    /// Baseline for normal behavior, replaced by the training centroid
     baseline_normal: Vec<f32>,
+    config: AnomalyEnsembleConfig,
+    isolation_forest: Option<IsolationForest>,
+    one_class_svm: Option<OneClassSvm>,
+    /// Samples accepted by online learning, not learned yet
+    #[serde(default)]
+    pending: Vec<Vec<f32>>,
+    /// Recent learned samples the forest is regrown on
+    #[serde(default)]
+    window: VecDeque<Vec<f32>>,
 }
 
 impl AdvancedAnomalyDetector {
     /// Create new anomaly detector with default parameters
     pub fn new() -> Self {
+        Self::with_config(AnomalyEnsembleConfig::default())
+    }
+
+    /// Create an untrained detector with the given ensemble configuration
+    pub fn with_config(config: AnomalyEnsembleConfig) -> Self {
         Self {
             baseline_normal: vec![0.1, 0.15, 0.12, 0.18, 0.14],
+            config,
+            isolation_forest: None,
+            one_class_svm: None,
+            pending: Vec::new(),
+            window: VecDeque::new(),
         }
     }
 
-    /// Detect anomalies in the given fingerprint
-    pub fn detect_anomalies(&self, fingerprint: &FingerprintVector) -> AnomalyDetectionResult {
-        // Simple distance-based detection as placeholder for more sophisticated ML algorithms
-        let distance: f32 = self
-            .baseline_normal
+    /// Train the Isolation Forest and One-Class SVM on a batch of normal traffic
+    ///
+    /// The baseline moves to the batch centroid. On error the detector is unchanged.
+    pub fn train(&mut self, samples: &[FingerprintVector]) -> Result<(), String> {
+        self.config.validate()?;
+        let forest = IsolationForest::fit(samples, &self.config.isolation_forest)?;
+        let svm = OneClassSvm::fit(samples, &self.config.one_class_svm)?;
+        let n = samples.len() as f32;
+        self.baseline_normal = (0..forest.dimensions())
+            .map(|d| samples.iter().map(|s| s.features[d]).sum::<f32>() / n)
+            .collect();
+        self.isolation_forest = Some(forest);
+        self.one_class_svm = Some(svm);
+        Ok(())
+    }
+
+    /// Whether [`AdvancedAnomalyDetector::train`] has succeeded
+    pub fn is_trained(&self) -> bool {
+        self.isolation_forest.is_some()
+    }
+
+    pub fn config(&self) -> &AnomalyEnsembleConfig {
+        &self.config
+    }
+
+    /// Change the ensemble weights (e.g. after a config reload) without retraining
+    pub fn set_weights(&mut self, weights: EnsembleWeights) -> Result<(), String> {
+        let config = AnomalyEnsembleConfig {
+            weights,
+            ..self.config.clone()
+        };
+        config.validate()?;
+        self.config = config;
+        Ok(())
+    }
+
+    fn baseline_distance(&self, features: &[f32]) -> f32 {
+        self.baseline_normal
             .iter()
-            .zip(fingerprint.features.iter())
+            .zip(features.iter())
             .map(|(a, b)| (a - b).powi(2))
             .sum::<f32>()
-            .sqrt();
+            .sqrt()
+    }
 
-        let anomaly_score = (distance / self.baseline_normal.len() as f32).min(1.0);
+    /// Score of every model taking part
+    pub fn model_scores(&self, features: &[f32]) -> ModelScores {
+        ModelScores {
+            baseline: (self.baseline_distance(features) / self.baseline_normal.len() as f32)
+                .min(1.0),
+            isolation_forest: self
+                .isolation_forest
+                .as_ref()
+                .map(|forest| (2.0 * forest.score(features) - 1.0).max(0.0)),
+            one_class_svm: self.one_class_svm.as_ref().map(|svm| svm.score(features)),
+        }
+    }
+
+    /// Weighted mean of the available model scores
+    fn combine(&self, scores: &ModelScores) -> f32 {
+        let weights = &self.config.weights;
+        let parts = [
+            (Some(scores.baseline), weights.baseline),
+            (scores.isolation_forest, weights.isolation_forest),
+            (scores.one_class_svm, weights.one_class_svm),
+        ];
+        let (sum, total) = parts
+            .iter()
+            .filter_map(|(score, weight)| score.map(|s| (s * weight, *weight)))
+            .fold((0.0, 0.0), |(sum, total), (s, w)| (sum + s, total + w));
+        if total > 0.0 {
+            (sum / total).min(1.0)
+        } else {
+            scores.baseline
+        }
+    }
+
+    /// Detect anomalies in the given fingerprint
+    pub fn detect_anomalies(&self, fingerprint: &FingerprintVector) -> AnomalyDetectionResult {
+        let scores = self.model_scores(&fingerprint.features);
+        let anomaly_score = self.combine(&scores);
 
         let classification = if anomaly_score < 0.1 {
             AnomalyClassification::Normal
@@ -108,11 +320,22 @@ impl AdvancedAnomalyDetector {
             AnomalyClassification::Critical
         };
 
+        let mut explanation = format!(
+            "Distance from baseline: {:.3}",
+            self.baseline_distance(&fingerprint.features)
+        );
+        if let Some(score) = scores.isolation_forest {
+            explanation.push_str(&format!(", isolation forest: {:.3}", score));
+        }
+        if let Some(score) = scores.one_class_svm {
+            explanation.push_str(&format!(", one-class SVM: {:.3}", score));
+        }
+
         AnomalyDetectionResult {
             anomaly_score,
             confidence: fingerprint.confidence,
             classification,
-            explanation: format!("Distance from baseline: {:.3}", distance),
+            explanation,
         }
     }
 }
@@ -316,6 +539,56 @@ mod tests {
         assert_eq!(result.classification, AnomalyClassification::Critical);
     }
 
+    #[test]
+    fn test_trained_ensemble_uses_configured_weights() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let samples: Vec<FingerprintVector> = (0..300)
+            .map(|_| {
+                let features = (0..4)
+                    .map(|_| 0.5 + (0..3).map(|_| rng.gen_range(-0.05..0.05)).sum::<f32>())
+                    .collect();
+                FingerprintVector::new(features, None, 1.0)
+            })
+            .collect();
+        let config = AnomalyEnsembleConfig::from_value(&serde_json::json!({
+            "ml": { "ensemble": {
+                "weights": { "baseline": 0.0, "isolation_forest": 1.0, "one_class_svm": 1.0 },
+                "isolation_forest": { "trees": 50 }
+            }}
+        }))
+        .unwrap();
+        assert_eq!(config.isolation_forest.trees, 50);
+        assert_eq!(config.one_class_svm, OneClassSvmConfig::default());
+
+        let mut detector = AdvancedAnomalyDetector::with_config(config);
+        detector.train(&samples).unwrap();
+        assert!(detector.is_trained());
+
+        let inlier = FingerprintVector::new(vec![0.5, 0.51, 0.49, 0.5], None, 1.0);
+        let outlier = FingerprintVector::new(vec![0.1, 0.05, 0.9, 0.1], None, 1.0);
+        assert_eq!(
+            detector.detect_anomalies(&inlier).classification,
+            AnomalyClassification::Normal
+        );
+        let result = detector.detect_anomalies(&outlier);
+        assert_eq!(result.classification, AnomalyClassification::Critical);
+        assert!(result.explanation.contains("isolation forest"));
+
+        assert!(detector
+            .set_weights(EnsembleWeights {
+                baseline: -1.0,
+                ..EnsembleWeights::default()
+            })
+            .is_err());
+        assert!(AnomalyEnsembleConfig::from_value(&serde_json::json!({
+            "weights": { "baseline": 0.0, "isolation_forest": 0.0, "one_class_svm": 0.0 }
+        }))
+        .is_err());
+    }
+
     #[test]
     fn test_fingerprint_matcher() {
         let mut matcher = FingerprintMatcher::new();
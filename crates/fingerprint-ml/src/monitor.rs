@@ -0,0 +1,511 @@
+//! Model evaluation and drift monitoring
+//!
+//! A deployed model degrades silently: traffic moves away from what it was trained
+//! on, or attackers adapt until the model stops catching them. [`ModelMonitor`]
+//! watches both:
+//! - input drift: Population Stability Index (PSI) of each feature, and of the
+//!   model score, between a frozen reference window and a sliding current window;
+//!   bins are reference quantiles, so features need not be normalized
+//! - quality: precision and recall over a sliding window of labeled feedback
+//!   (analyst verdicts, confirmed incidents)
+//!
+//! [`ModelMonitor::check`] reports thresholds exceeded per [`ModelMonitorConfig`]
+//! (the `ml.monitor` section of fingerprint-config); fingerprint-analysis turns
+//! those reports into alerts.
+
+use crate::FingerprintVector;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Probability floor for empty histogram bins
+const BIN_EPSILON: f64 = 1e-4;
+
+/// Model monitor configuration, the `ml.monitor` section of fingerprint-config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelMonitorConfig {
+    /// Observations collected before the reference window is frozen
+    pub reference_size: usize,
+    /// Size of the sliding current window
+    pub window_size: usize,
+    /// Quantile bins per feature
+    pub bins: usize,
+    /// Report a feature or the score when its PSI exceeds this
+    pub psi_threshold: f64,
+    /// Labeled feedback kept for precision and recall
+    pub feedback_window: usize,
+    /// Feedback needed before precision and recall are judged
+    pub min_feedback: usize,
+    /// Report when precision falls below this
+    pub min_precision: f64,
+    /// Report when recall falls below this
+    pub min_recall: f64,
+}
+
+impl Default for ModelMonitorConfig {
+    fn default() -> Self {
+        Self {
+            reference_size: 1000,
+            window_size: 500,
+            bins: 10,
+            psi_threshold: 0.25,
+            feedback_window: 1000,
+            min_feedback: 50,
+            min_precision: 0.8,
+            min_recall: 0.7,
+        }
+    }
+}
+
+impl ModelMonitorConfig {
+    /// Read either the section itself or a full fingerprint-config document
+    pub fn from_value(value: &serde_json::Value) -> Result<Self, String> {
+        let section = value
+            .get("ml")
+            .and_then(|ml| ml.get("monitor"))
+            .unwrap_or(value);
+        let config: Self = serde_json::from_value(section.clone()).map_err(|e| e.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.reference_size == 0 || self.window_size == 0 || self.feedback_window == 0 {
+            return Err(
+                "reference_size, window_size and feedback_window must be positive".to_string(),
+            );
+        }
+        if self.bins < 2 {
+            return Err("bins must be at least 2".to_string());
+        }
+        if self.psi_threshold.is_nan() || self.psi_threshold <= 0.0 {
+            return Err(format!(
+                "psi_threshold must be positive, got {}",
+                self.psi_threshold
+            ));
+        }
+        for (name, value) in [
+            ("min_precision", self.min_precision),
+            ("min_recall", self.min_recall),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("{} must be in [0, 1], got {}", name, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Confusion matrix over the feedback window; "positive" means anomalous
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassificationMetrics {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub true_negatives: usize,
+    pub false_negatives: usize,
+}
+
+impl ClassificationMetrics {
+    pub fn total(&self) -> usize {
+        self.true_positives + self.false_positives + self.true_negatives + self.false_negatives
+    }
+
+    /// Share of flagged samples that were anomalous; `None` before anything was flagged
+    pub fn precision(&self) -> Option<f64> {
+        let flagged = self.true_positives + self.false_positives;
+        (flagged > 0).then(|| self.true_positives as f64 / flagged as f64)
+    }
+
+    /// Share of anomalous samples that were flagged; `None` before any was seen
+    pub fn recall(&self) -> Option<f64> {
+        let anomalous = self.true_positives + self.false_negatives;
+        (anomalous > 0).then(|| self.true_positives as f64 / anomalous as f64)
+    }
+
+    pub fn f1(&self) -> Option<f64> {
+        let (p, r) = (self.precision()?, self.recall()?);
+        Some(if p + r > 0.0 {
+            2.0 * p * r / (p + r)
+        } else {
+            0.0
+        })
+    }
+}
+
+/// A monitored value past its threshold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorViolation {
+    /// `psi:<feature>`, `psi:score`, `precision` or `recall`
+    pub metric: String,
+    pub value: f64,
+    pub threshold: f64,
+    /// PSI over twice its threshold, or precision / recall under half its floor
+    pub severe: bool,
+}
+
+/// Current state of the monitored model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorReport {
+    /// PSI per feature, once the reference and a full current window exist
+    pub feature_psi: Vec<(String, f64)>,
+    /// PSI of the model score, likewise
+    pub score_psi: Option<f64>,
+    pub metrics: ClassificationMetrics,
+    pub precision: Option<f64>,
+    pub recall: Option<f64>,
+    /// Feature observations in the current window
+    pub window_len: usize,
+    pub violations: Vec<MonitorViolation>,
+}
+
+impl MonitorReport {
+    pub fn is_degraded(&self) -> bool {
+        !self.violations.is_empty()
+    }
+}
+
+/// Reference and current window of one monitored value
+#[derive(Debug, Default)]
+struct Window {
+    reference: Vec<f64>,
+    current: VecDeque<f64>,
+}
+
+impl Window {
+    fn push(&mut self, value: f64, config: &ModelMonitorConfig) {
+        if self.reference.len() < config.reference_size {
+            self.reference.push(value);
+            return;
+        }
+        self.current.push_back(value);
+        if self.current.len() > config.window_size {
+            self.current.pop_front();
+        }
+    }
+
+    fn psi(&self, config: &ModelMonitorConfig) -> Option<f64> {
+        if self.reference.len() < config.reference_size || self.current.len() < config.window_size {
+            return None;
+        }
+        let current: Vec<f64> = self.current.iter().copied().collect();
+        Some(population_stability_index(
+            &self.reference,
+            &current,
+            config.bins,
+        ))
+    }
+}
+
+#[derive(Debug, Default)]
+struct MonitorState {
+    features: Vec<Window>,
+    score: Window,
+    /// (predicted anomalous, actually anomalous)
+    feedback: VecDeque<(bool, bool)>,
+    /// Events since the last reported degradation, so one shift is not reported on
+    /// every observation
+    since_report: usize,
+}
+
+/// Tracks input drift and precision / recall of a deployed model
+pub struct ModelMonitor {
+    config: ModelMonitorConfig,
+    feature_names: Vec<String>,
+    state: Mutex<MonitorState>,
+}
+
+impl ModelMonitor {
+    pub fn new(config: ModelMonitorConfig) -> Self {
+        Self {
+            config,
+            feature_names: Vec::new(),
+            state: Mutex::new(MonitorState::default()),
+        }
+    }
+
+    /// Name features in reports, e.g. with [`crate::VectorSchema`] columns;
+    /// unnamed ones are reported by index
+    pub fn with_feature_names(mut self, names: Vec<String>) -> Self {
+        self.feature_names = names;
+        self
+    }
+
+    pub fn config(&self) -> &ModelMonitorConfig {
+        &self.config
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MonitorState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Replace the feature reference (e.g. with the training set)
+    pub fn set_reference(&self, samples: &[FingerprintVector]) -> Result<(), String> {
+        let dimensions = crate::isolation_forest::check_samples(samples)?;
+        let mut state = self.state();
+        state.features = (0..dimensions)
+            .map(|d| Window {
+                reference: samples.iter().map(|s| s.features[d] as f64).collect(),
+                current: VecDeque::new(),
+            })
+            .collect();
+        state.since_report = 0;
+        Ok(())
+    }
+
+    /// Record the features of a sample the model scored
+    ///
+    /// The first `reference_size` observations build the reference unless one was set.
+    pub fn observe(&self, features: &[f32]) -> Result<(), String> {
+        let mut state = self.state();
+        if state.features.is_empty() {
+            state.features = features.iter().map(|_| Window::default()).collect();
+        }
+        if features.len() != state.features.len() {
+            return Err(format!(
+                "monitor tracks {} features, got {}",
+                state.features.len(),
+                features.len()
+            ));
+        }
+        for (window, value) in state.features.iter_mut().zip(features) {
+            window.push(*value as f64, &self.config);
+        }
+        state.since_report += 1;
+        Ok(())
+    }
+
+    /// Record a model score
+    pub fn observe_score(&self, score: f64) {
+        let mut state = self.state();
+        state.score.push(score, &self.config);
+        state.since_report += 1;
+    }
+
+    /// Record the verdict for a sample once its true label is known
+    pub fn record_feedback(&self, predicted_anomalous: bool, actually_anomalous: bool) {
+        let mut state = self.state();
+        state
+            .feedback
+            .push_back((predicted_anomalous, actually_anomalous));
+        if state.feedback.len() > self.config.feedback_window {
+            state.feedback.pop_front();
+        }
+        state.since_report += 1;
+    }
+
+    /// Confusion matrix over the feedback window
+    pub fn metrics(&self) -> ClassificationMetrics {
+        Self::confusion(&self.state())
+    }
+
+    fn confusion(state: &MonitorState) -> ClassificationMetrics {
+        let mut metrics = ClassificationMetrics::default();
+        for (predicted, actual) in &state.feedback {
+            match (predicted, actual) {
+                (true, true) => metrics.true_positives += 1,
+                (true, false) => metrics.false_positives += 1,
+                (false, false) => metrics.true_negatives += 1,
+                (false, true) => metrics.false_negatives += 1,
+            }
+        }
+        metrics
+    }
+
+    /// Evaluate drift and metrics against the thresholds
+    pub fn report(&self) -> MonitorReport {
+        let state = self.state();
+        let config = &self.config;
+        let feature_psi: Vec<(String, f64)> = state
+            .features
+            .iter()
+            .enumerate()
+            .filter_map(|(i, window)| {
+                let name = self
+                    .feature_names
+                    .get(i)
+                    .cloned()
+                    .unwrap_or_else(|| i.to_string());
+                window.psi(config).map(|psi| (name, psi))
+            })
+            .collect();
+        let score_psi = state.score.psi(config);
+        let metrics = Self::confusion(&state);
+
+        let mut violations: Vec<MonitorViolation> = feature_psi
+            .iter()
+            .map(|(name, psi)| (format!("psi:{}", name), *psi))
+            .chain(score_psi.map(|psi| ("psi:score".to_string(), psi)))
+            .filter(|(_, psi)| *psi > config.psi_threshold)
+            .map(|(metric, value)| MonitorViolation {
+                metric,
+                value,
+                threshold: config.psi_threshold,
+                severe: value > config.psi_threshold * 2.0,
+            })
+            .collect();
+        if metrics.total() >= config.min_feedback {
+            for (metric, value, floor) in [
+                ("precision", metrics.precision(), config.min_precision),
+                ("recall", metrics.recall(), config.min_recall),
+            ] {
+                if let Some(value) = value.filter(|v| *v < floor) {
+                    violations.push(MonitorViolation {
+                        metric: metric.to_string(),
+                        value,
+                        threshold: floor,
+                        severe: value < floor / 2.0,
+                    });
+                }
+            }
+        }
+
+        MonitorReport {
+            feature_psi,
+            score_psi,
+            precision: metrics.precision(),
+            recall: metrics.recall(),
+            metrics,
+            window_len: state.features.first().map_or(0, |w| w.current.len()),
+            violations,
+        }
+    }
+
+    /// Report if drift or degradation exceeds the thresholds
+    ///
+    /// After a report, the next one is held back until `window_size` new events
+    /// (observations or feedback) have been recorded.
+    pub fn check(&self) -> Option<MonitorReport> {
+        if self.state().since_report < self.config.window_size {
+            return None;
+        }
+        let report = self.report();
+        if !report.is_degraded() {
+            return None;
+        }
+        self.state().since_report = 0;
+        Some(report)
+    }
+}
+
+impl Default for ModelMonitor {
+    fn default() -> Self {
+        Self::new(ModelMonitorConfig::default())
+    }
+}
+
+/// Population Stability Index of `current` against `reference`
+///
+/// Bins are `bins` quantiles of the reference; empty bins are floored at a small
+/// epsilon. Below 0.1 is stable, above 0.25 a significant shift.
+pub fn population_stability_index(reference: &[f64], current: &[f64], bins: usize) -> f64 {
+    let mut sorted: Vec<f64> = reference.iter().copied().filter(|v| !v.is_nan()).collect();
+    sorted.sort_by(f64::total_cmp);
+    if sorted.is_empty() || current.is_empty() {
+        return 0.0;
+    }
+    let bins = bins.max(2);
+    let mut edges: Vec<f64> = (1..bins).map(|i| sorted[i * sorted.len() / bins]).collect();
+    edges.dedup();
+
+    let histogram = |values: &[f64]| {
+        let mut counts = vec![0usize; edges.len() + 1];
+        for value in values {
+            counts[edges.partition_point(|edge| edge <= value)] += 1;
+        }
+        counts
+            .into_iter()
+            .map(|c| (c as f64 / values.len() as f64).max(BIN_EPSILON))
+            .collect::<Vec<f64>>()
+    };
+    histogram(&sorted)
+        .into_iter()
+        .zip(histogram(current))
+        .map(|(r, c)| (c - r) * (c / r).ln())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ModelMonitorConfig {
+        ModelMonitorConfig {
+            reference_size: 200,
+            window_size: 100,
+            feedback_window: 200,
+            min_feedback: 20,
+            ..ModelMonitorConfig::default()
+        }
+    }
+
+    /// Deterministic values spread over `[lo, hi)`
+    fn spread(i: usize, lo: f32, hi: f32) -> f32 {
+        lo + (hi - lo) * ((i * 37) % 100) as f32 / 100.0
+    }
+
+    #[test]
+    fn test_feature_drift_is_reported_per_feature() {
+        let monitor = ModelMonitor::new(config())
+            .with_feature_names(vec!["ttl".to_string(), "cipher_count".to_string()]);
+        for i in 0..300 {
+            monitor
+                .observe(&[spread(i, 0.0, 1.0), 40.0 + spread(i, 0.0, 10.0)])
+                .unwrap();
+            assert!(monitor.check().is_none());
+        }
+        let stable = monitor.report();
+        assert_eq!(stable.feature_psi.len(), 2);
+        assert!(stable.feature_psi.iter().all(|(_, psi)| *psi < 0.05));
+        assert!(monitor.observe(&[0.5]).is_err());
+
+        // clients start sending far more cipher suites
+        let reports: Vec<MonitorReport> = (0..300)
+            .filter_map(|i| {
+                monitor
+                    .observe(&[spread(i, 0.0, 1.0), 60.0 + spread(i, 0.0, 10.0)])
+                    .unwrap();
+                monitor.check()
+            })
+            .collect();
+        // rate limited to one report per window while the shift persists
+        assert_eq!(reports.len(), 3);
+        let violations = &reports[2].violations;
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].metric, "psi:cipher_count");
+        assert!(violations[0].severe);
+    }
+
+    #[test]
+    fn test_feedback_tracks_precision_and_recall() {
+        let monitor = ModelMonitor::new(config());
+        for i in 0..100 {
+            // a healthy model: one false positive in ten
+            monitor.record_feedback(i % 2 == 0, i % 2 == 0 && i % 10 != 0);
+        }
+        let metrics = monitor.metrics();
+        assert_eq!(metrics.total(), 100);
+        assert_eq!(metrics.precision(), Some(0.8));
+        assert_eq!(metrics.recall(), Some(1.0));
+        assert!(monitor.check().is_none());
+
+        // attackers adapt: anomalies slip through
+        for i in 0..200 {
+            monitor.record_feedback(false, i % 2 == 0);
+        }
+        let report = monitor.check().unwrap();
+        assert_eq!(report.recall, Some(0.0));
+        assert_eq!(report.violations[0].metric, "recall");
+        assert!(report.violations[0].severe);
+        assert!(report.feature_psi.is_empty());
+        assert!(monitor.check().is_none());
+
+        let value = serde_json::json!({ "ml": { "monitor": { "min_recall": 1.5 } } });
+        assert!(ModelMonitorConfig::from_value(&value).is_err());
+        let value = serde_json::json!({ "min_recall": 0.5 });
+        assert_eq!(
+            ModelMonitorConfig::from_value(&value).unwrap().min_recall,
+            0.5
+        );
+    }
+}
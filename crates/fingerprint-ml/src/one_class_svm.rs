@@ -0,0 +1,269 @@
+//! Linear-kernel One-Class SVM (Schölkopf et al., 2001)
+//!
+//! Finds the hyperplane `w·x = ρ` separating the training data from the origin
+//! with maximum margin, allowing a fraction `nu` of training points on the wrong
+//! side. The primal objective
+//!
+//! ```text
+//! ½‖w‖² − ρ + 1/(ν·n) · Σ max(0, ρ − w·xᵢ)
+//! ```
+//!
+//! is minimized by full-batch subgradient descent. Features are divided by their
+//! largest training magnitude first, so no single feature dominates the margin;
+//! they are not centred, since a linear one-class SVM separates from the origin.
+//!
+//! [`OneClassSvm::fit_partial`] keeps descending on new batches with the learning
+//! rate held at a floor, so the hyperplane keeps tracking drifting traffic. The
+//! feature scaling stays fixed after the first fit.
+
+use crate::isolation_forest::check_samples;
+use crate::FingerprintVector;
+use serde::{Deserialize, Serialize};
+
+/// One-Class SVM training parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OneClassSvmConfig {
+    /// Upper bound on the fraction of training points treated as outliers (0, 1]
+    pub nu: f32,
+    /// Gradient descent epochs
+    pub epochs: usize,
+    /// Initial learning rate, decayed as `rate / sqrt(epoch)`
+    pub learning_rate: f32,
+}
+
+impl Default for OneClassSvmConfig {
+    fn default() -> Self {
+        Self {
+            nu: 0.1,
+            epochs: 500,
+            learning_rate: 0.5,
+        }
+    }
+}
+
+/// Fraction of the initial learning rate partial fits never go below
+const MIN_RATE_FRACTION: f32 = 0.05;
+
+/// Trained linear One-Class SVM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OneClassSvm {
+    weights: Vec<f32>,
+    rho: f32,
+    scale: Vec<f32>,
+    /// Mean distance of training points from the hyperplane
+    spread: f32,
+    config: OneClassSvmConfig,
+    /// Epochs run so far, continuing the learning rate schedule
+    epochs_run: usize,
+}
+
+impl OneClassSvm {
+    /// Train on a batch of fingerprint vectors of equal length
+    pub fn fit(samples: &[FingerprintVector], config: &OneClassSvmConfig) -> Result<Self, String> {
+        let dimensions = check_samples(samples)?;
+        if !(config.nu > 0.0 && config.nu <= 1.0) {
+            return Err(format!("nu must be in (0, 1], got {}", config.nu));
+        }
+
+        let scale: Vec<f32> = (0..dimensions)
+            .map(|d| {
+                let max = samples
+                    .iter()
+                    .map(|s| s.features[d].abs())
+                    .fold(0.0, f32::max);
+                if max > 0.0 {
+                    max
+                } else {
+                    1.0
+                }
+            })
+            .collect();
+        let mut svm = Self {
+            weights: Vec::new(),
+            rho: 0.0,
+            scale,
+            spread: 1.0,
+            config: config.clone(),
+            epochs_run: 0,
+        };
+        let points = svm.scaled(samples);
+        let n = points.len() as f32;
+        // start from the normalized mean direction, with no point violating
+        svm.weights = (0..dimensions)
+            .map(|d| points.iter().map(|p| p[d]).sum::<f32>() / n)
+            .collect();
+        svm.descend(&points, config.epochs.max(1), 0.0);
+        svm.spread = svm.mean_distance(samples).unwrap_or(1.0);
+        Ok(svm)
+    }
+
+    /// Continue training on a new batch
+    ///
+    /// Runs `epochs` more epochs on `samples` and blends the spread of the batch
+    /// into the stored one, keeping `decay` of the old value.
+    pub fn fit_partial(
+        &mut self,
+        samples: &[FingerprintVector],
+        epochs: usize,
+        decay: f32,
+    ) -> Result<(), String> {
+        if check_samples(samples)? != self.dimensions() {
+            return Err(format!(
+                "model was trained on {} features",
+                self.dimensions()
+            ));
+        }
+        let points = self.scaled(samples);
+        let floor = self.config.learning_rate * MIN_RATE_FRACTION;
+        self.descend(&points, epochs, floor);
+        if let Some(spread) = self.mean_distance(samples) {
+            self.spread = decay * self.spread + (1.0 - decay) * spread;
+        }
+        Ok(())
+    }
+
+    fn scaled(&self, samples: &[FingerprintVector]) -> Vec<Vec<f32>> {
+        samples
+            .iter()
+            .map(|s| {
+                s.features
+                    .iter()
+                    .zip(&self.scale)
+                    .map(|(x, s)| x / s)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Subgradient steps at rate `learning_rate / sqrt(epoch)`, at least `floor`
+    fn descend(&mut self, points: &[Vec<f32>], epochs: usize, floor: f32) {
+        let penalty = 1.0 / (self.config.nu * points.len() as f32);
+        for _ in 0..epochs {
+            self.epochs_run += 1;
+            let rate = (self.config.learning_rate / (self.epochs_run as f32).sqrt()).max(floor);
+            let mut grad_w = self.weights.clone();
+            let mut grad_rho = -1.0;
+            for point in points {
+                if dot(&self.weights, point) < self.rho {
+                    for (g, x) in grad_w.iter_mut().zip(point) {
+                        *g -= penalty * x;
+                    }
+                    grad_rho += penalty;
+                }
+            }
+            for (w, g) in self.weights.iter_mut().zip(&grad_w) {
+                *w -= rate * g;
+            }
+            self.rho -= rate * grad_rho;
+        }
+    }
+
+    /// Mean distance of `samples` from the hyperplane, if positive
+    fn mean_distance(&self, samples: &[FingerprintVector]) -> Option<f32> {
+        let spread = samples
+            .iter()
+            .map(|s| self.signed_distance(&s.features).abs())
+            .sum::<f32>()
+            / samples.len() as f32;
+        (spread > 0.0).then_some(spread)
+    }
+
+    /// `w·x − ρ` on scaled features: positive inside the learned region
+    pub fn decision_function(&self, features: &[f32]) -> f32 {
+        let dot: f32 = self
+            .weights
+            .iter()
+            .zip(&self.scale)
+            .zip(features)
+            .map(|((w, s), x)| w * x / s)
+            .sum();
+        dot - self.rho
+    }
+
+    fn signed_distance(&self, features: &[f32]) -> f32 {
+        let norm = dot(&self.weights, &self.weights).sqrt();
+        if norm == 0.0 {
+            return 0.0;
+        }
+        self.decision_function(features) / norm
+    }
+
+    /// Whether `features` falls inside the learned region
+    pub fn is_inlier(&self, features: &[f32]) -> bool {
+        self.decision_function(features) >= 0.0
+    }
+
+    /// Anomaly score in [0, 1]: 0 inside the region, 1 three spreads beyond it
+    pub fn score(&self, features: &[f32]) -> f32 {
+        (-self.signed_distance(features) / (3.0 * self.spread)).clamp(0.0, 1.0)
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.weights.len()
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_learns_region_with_nu_outliers() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let samples: Vec<FingerprintVector> = (0..400)
+            .map(|_| {
+                let features = vec![
+                    0.7 + rng.gen_range(-0.1..0.1),
+                    0.6 + rng.gen_range(-0.1..0.1),
+                ];
+                FingerprintVector::new(features, None, 1.0)
+            })
+            .collect();
+        let config = OneClassSvmConfig::default();
+        let svm = OneClassSvm::fit(&samples, &config).unwrap();
+
+        let outside = samples
+            .iter()
+            .filter(|s| !svm.is_inlier(&s.features))
+            .count() as f32
+            / samples.len() as f32;
+        assert!(outside <= config.nu + 0.05, "outside {}", outside);
+
+        assert_eq!(svm.score(&[0.8, 0.7]), 0.0);
+        assert!(svm.score(&[0.1, 0.1]) > 0.5);
+        assert!(OneClassSvm::fit(
+            &samples,
+            &OneClassSvmConfig {
+                nu: 0.0,
+                ..config.clone()
+            }
+        )
+        .is_err());
+
+        // traffic moving towards the origin is learned as normal again
+        let drifted: Vec<FingerprintVector> = samples
+            .iter()
+            .map(|s| {
+                FingerprintVector::new(s.features.iter().map(|x| x * 0.5).collect(), None, 1.0)
+            })
+            .collect();
+        let mut online = svm.clone();
+        let before = online.score(&[0.35, 0.3]);
+        for _ in 0..10 {
+            online.fit_partial(&drifted, 50, 0.5).unwrap();
+        }
+        assert!(
+            online.score(&[0.35, 0.3]) < before,
+            "{}",
+            online.score(&[0.35, 0.3])
+        );
+        assert!(online.fit_partial(&drifted[..0], 1, 0.5).is_err());
+    }
+}
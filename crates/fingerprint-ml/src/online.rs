@@ -0,0 +1,308 @@
+//! Online learning for [`AdvancedAnomalyDetector`]
+//!
+//! [`AdvancedAnomalyDetector::update`] and [`AdvancedAnomalyDetector::fit_partial`]
+//! learn from a stream of fingerprints in production. Each sample is screened
+//! first, since the one-class models only learn what normal traffic looks like:
+//! - labeled with one of [`OnlineLearningConfig::normal_labels`]: learned
+//! - labeled otherwise: skipped
+//! - unlabeled: pseudo-labeled normal when the current ensemble scores it below
+//!   [`OnlineLearningConfig::pseudo_label_threshold`] (always before training)
+//!
+//! Accepted samples are buffered until `min_samples` have arrived. The first
+//! full buffer trains the detector; each later one regrows `1 - decay` of the
+//! forest on the window of recent samples, continues the SVM descent on the
+//! buffer and blends the buffer centroid into the baseline, keeping `decay` of
+//! the old state. Old traffic is forgotten that way when the distribution drifts.
+//!
+//! The whole state, buffer and window included, round-trips through JSON
+//! ([`AdvancedAnomalyDetector::save_state`]) so learning survives restarts.
+
+use crate::{AdvancedAnomalyDetector, FingerprintVector};
+use fingerprint_core::data_dirs::DataDirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// File under the model directory holding the detector state
+const STATE_FILE: &str = "anomaly_detector.json";
+
+/// Online learning parameters, `ml.ensemble.online` in fingerprint-config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OnlineLearningConfig {
+    /// Share of the old model state kept by each update, in [0, 1)
+    pub decay: f32,
+    /// Accepted samples buffered before the models are updated
+    pub min_samples: usize,
+    /// Most recent accepted samples the forest is regrown on
+    pub window: usize,
+    /// One-Class SVM epochs per update
+    pub svm_epochs: usize,
+    /// Unlabeled samples scoring below this are learned as normal
+    pub pseudo_label_threshold: f32,
+    /// Labels marking normal traffic (case-insensitive)
+    pub normal_labels: Vec<String>,
+}
+
+impl Default for OnlineLearningConfig {
+    fn default() -> Self {
+        Self {
+            decay: 0.9,
+            min_samples: 64,
+            window: 1024,
+            svm_epochs: 50,
+            pseudo_label_threshold: 0.1,
+            normal_labels: vec![
+                "normal".to_string(),
+                "benign".to_string(),
+                "human".to_string(),
+            ],
+        }
+    }
+}
+
+impl OnlineLearningConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..1.0).contains(&self.decay) {
+            return Err(format!("decay must be in [0, 1), got {}", self.decay));
+        }
+        if self.min_samples < 2 {
+            return Err("min_samples must be at least 2".to_string());
+        }
+        if self.window < self.min_samples {
+            return Err("window must hold at least min_samples samples".to_string());
+        }
+        if !(self.pseudo_label_threshold > 0.0 && self.pseudo_label_threshold <= 1.0) {
+            return Err(format!(
+                "pseudo_label_threshold must be in (0, 1], got {}",
+                self.pseudo_label_threshold
+            ));
+        }
+        Ok(())
+    }
+
+    fn is_normal_label(&self, label: &str) -> bool {
+        self.normal_labels
+            .iter()
+            .any(|normal| normal.eq_ignore_ascii_case(label))
+    }
+}
+
+/// What a call to [`AdvancedAnomalyDetector::fit_partial`] did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpdateReport {
+    /// Samples accepted as normal traffic
+    pub accepted: usize,
+    /// Samples skipped: anomalous label, too high a score or wrong length
+    pub skipped: usize,
+    /// Model updates applied; the first one on an untrained detector trains it
+    pub updates: usize,
+}
+
+fn to_vectors<'a>(rows: impl IntoIterator<Item = &'a Vec<f32>>) -> Vec<FingerprintVector> {
+    rows.into_iter()
+        .map(|features| FingerprintVector::new(features.clone(), None, 1.0))
+        .collect()
+}
+
+impl AdvancedAnomalyDetector {
+    /// Learn from one sample, as [`AdvancedAnomalyDetector::fit_partial`]
+    pub fn update(&mut self, sample: &FingerprintVector) -> Result<UpdateReport, String> {
+        self.fit_partial(std::slice::from_ref(sample))
+    }
+
+    /// Learn from a batch of streamed samples
+    ///
+    /// Models only change once `min_samples` samples have been accepted; until
+    /// then they wait in a buffer that is saved with the state.
+    pub fn fit_partial(&mut self, samples: &[FingerprintVector]) -> Result<UpdateReport, String> {
+        self.config.validate()?;
+        let mut report = UpdateReport::default();
+        for sample in samples {
+            if !self.accepts(sample) {
+                report.skipped += 1;
+                continue;
+            }
+            report.accepted += 1;
+            self.pending.push(sample.features.clone());
+            if self.pending.len() >= self.config.online.min_samples {
+                self.apply_pending()?;
+                report.updates += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Samples accepted but not learned yet
+    pub fn pending_samples(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn accepts(&self, sample: &FingerprintVector) -> bool {
+        let expected = match &self.isolation_forest {
+            Some(forest) => Some(forest.dimensions()),
+            None => self.pending.first().map(Vec::len),
+        };
+        if sample.features.is_empty() || expected.is_some_and(|d| d != sample.features.len()) {
+            return false;
+        }
+        match &sample.label {
+            Some(label) => self.config.online.is_normal_label(label),
+            None => {
+                !self.is_trained()
+                    || self.combine(&self.model_scores(&sample.features))
+                        < self.config.online.pseudo_label_threshold
+            }
+        }
+    }
+
+    fn apply_pending(&mut self) -> Result<(), String> {
+        let online = self.config.online.clone();
+        let batch = std::mem::take(&mut self.pending);
+        self.window.extend(batch.iter().cloned());
+        while self.window.len() > online.window {
+            self.window.pop_front();
+        }
+
+        let (Some(forest), Some(svm)) = (&mut self.isolation_forest, &mut self.one_class_svm)
+        else {
+            return self.train(&to_vectors(&self.window));
+        };
+        let count = ((1.0 - online.decay) * forest.tree_count() as f32).ceil() as usize;
+        forest.fit_partial(&to_vectors(&self.window), count)?;
+        svm.fit_partial(&to_vectors(&batch), online.svm_epochs, online.decay)?;
+
+        let n = batch.len() as f32;
+        for (d, baseline) in self.baseline_normal.iter_mut().enumerate() {
+            let mean = batch.iter().map(|features| features[d]).sum::<f32>() / n;
+            *baseline = online.decay * *baseline + (1.0 - online.decay) * mean;
+        }
+        Ok(())
+    }
+
+    /// Serialize models, configuration and online learning buffers
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| e.to_string())
+    }
+
+    /// Restore a detector serialized by [`AdvancedAnomalyDetector::to_json`]
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let detector: Self = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        detector.config.validate()?;
+        Ok(detector)
+    }
+
+    /// Persist the detector state under the model directory of `dirs`
+    ///
+    /// Returns the file written.
+    pub fn save_state(&self, dirs: &DataDirs) -> io::Result<PathBuf> {
+        let path = DataDirs::prepare_file(dirs.model_dir().join(STATE_FILE))?;
+        let json = self
+            .to_json()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(&path, json)?;
+        Ok(path)
+    }
+
+    /// Load a detector saved by [`AdvancedAnomalyDetector::save_state`]
+    ///
+    /// A missing file yields `None`.
+    pub fn load_state(dirs: &DataDirs) -> io::Result<Option<Self>> {
+        let path = dirs.model_dir().join(STATE_FILE);
+        let json = match fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        Self::from_json(&json).map(Some).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid detector state in {}: {}", path.display(), e),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AnomalyClassification, AnomalyEnsembleConfig};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn traffic(rng: &mut StdRng, centre: f32, count: usize) -> Vec<FingerprintVector> {
+        (0..count)
+            .map(|_| {
+                let features = (0..4)
+                    .map(|_| centre + (0..3).map(|_| rng.gen_range(-0.05..0.05)).sum::<f32>())
+                    .collect();
+                FingerprintVector::new(features, None, 1.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_streaming_updates_follow_drift_and_survive_restart() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut config = AnomalyEnsembleConfig::default();
+        config.online.decay = 0.5;
+        config.online.min_samples = 100;
+        config.isolation_forest.trees = 50;
+        let mut detector = AdvancedAnomalyDetector::with_config(config);
+
+        // buffered until min_samples, then the first full buffer trains
+        let report = detector.fit_partial(&traffic(&mut rng, 0.5, 99)).unwrap();
+        assert_eq!((report.accepted, report.updates), (99, 0));
+        assert!(!detector.is_trained());
+        detector.fit_partial(&traffic(&mut rng, 0.5, 1)).unwrap();
+        assert!(detector.is_trained());
+        assert_eq!(detector.pending_samples(), 0);
+
+        // labeled anomalies and far-off unlabeled samples are not learned
+        let bot = FingerprintVector::new(vec![0.5; 4], Some("bot".to_string()), 1.0);
+        let outlier = FingerprintVector::new(vec![0.1, 0.05, 0.9, 0.1], None, 1.0);
+        let report = detector.fit_partial(&[bot, outlier]).unwrap();
+        assert_eq!((report.accepted, report.skipped), (0, 2));
+
+        // labeled normal traffic drifts to a new region, which becomes normal
+        let drifted: Vec<FingerprintVector> = traffic(&mut rng, 0.7, 600)
+            .into_iter()
+            .map(|s| FingerprintVector::new(s.features, Some("Normal".to_string()), 1.0))
+            .collect();
+        let probe = FingerprintVector::new(vec![0.7; 4], None, 1.0);
+        let before = detector.detect_anomalies(&probe).anomaly_score;
+        let report = detector.fit_partial(&drifted).unwrap();
+        assert_eq!(report.updates, 6);
+        let after = detector.detect_anomalies(&probe);
+        assert!(
+            after.anomaly_score < before,
+            "{} -> {}",
+            before,
+            after.anomaly_score
+        );
+        assert_eq!(after.classification, AnomalyClassification::Normal);
+
+        detector
+            .update(&FingerprintVector::new(vec![0.7; 4], None, 1.0))
+            .unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let dirs = DataDirs::portable(root.path());
+        let path = detector.save_state(&dirs).unwrap();
+        assert!(path.starts_with(dirs.model_dir()));
+
+        let restored = AdvancedAnomalyDetector::load_state(&dirs).unwrap().unwrap();
+        assert!(restored.is_trained());
+        assert_eq!(restored.pending_samples(), 1);
+        assert_eq!(restored.config(), detector.config());
+        assert_eq!(
+            restored.detect_anomalies(&probe).anomaly_score,
+            after.anomaly_score
+        );
+
+        let empty = DataDirs::portable(root.path().join("empty"));
+        assert!(AdvancedAnomalyDetector::load_state(&empty)
+            .unwrap()
+            .is_none());
+    }
+}
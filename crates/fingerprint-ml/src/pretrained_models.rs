@@ -9,11 +9,20 @@
 //! - Anomaly detector: Behavioral anomaly detection
 //! - Ensemble: Combined predictions from multiple models
 
+use crate::adversarial::{AdversarialEvaluator, ModelTarget, RobustnessMetrics};
+use fingerprint_core::data_dirs::DataDirs;
+use fingerprint_core::runtime::{spawn_compute, ComputeError, ComputeHandle};
 use std::collections::{HashMap, VecDeque};
-use std::sync::Mutex;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
 const DEFAULT_MODEL_CACHE_CAPACITY: usize = 3;
 
+/// File under the model directory holding recorded robustness metrics
+const ROBUSTNESS_FILE: &str = "robustness.tsv";
+
 /// Model performance metrics
 #[derive(Debug, Clone)]
 pub struct ModelMetrics {
@@ -165,6 +174,7 @@ pub struct ModelPrediction {
 pub struct PreTrainedModelManager {
     registry: HashMap<PreTrainedModel, ModelDescriptor>,
     cache: Mutex<ModelCache>,
+    robustness: Mutex<HashMap<PreTrainedModel, RobustnessMetrics>>,
 }
 
 #[derive(Debug, Clone)]
@@ -279,6 +289,7 @@ impl PreTrainedModelManager {
         Self {
             registry,
             cache: Mutex::new(ModelCache::new(capacity)),
+            robustness: Mutex::new(HashMap::new()),
         }
     }
 
@@ -339,10 +350,147 @@ impl PreTrainedModelManager {
             .contains_key(&model)
     }
 
+    /// Run the adversarial evaluation for a model and record the result in the registry
+    pub fn evaluate_robustness(
+        &self,
+        model: PreTrainedModel,
+        samples: &[Vec<f32>],
+        evaluator: &AdversarialEvaluator,
+    ) -> RobustnessMetrics {
+        let target = ModelTarget::new(self, model);
+        let metrics = evaluator.evaluate(model.name(), &target, samples);
+        self.robustness
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(model, metrics.clone());
+        metrics
+    }
+
+    /// Last recorded adversarial robustness of a model
+    pub fn robustness_metrics(&self, model: PreTrainedModel) -> Option<RobustnessMetrics> {
+        self.robustness
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&model)
+            .cloned()
+    }
+
+    /// Persist recorded robustness metrics under the model directory of `dirs`
+    ///
+    /// One tab-separated line per model; returns the file written.
+    pub fn save_robustness(&self, dirs: &DataDirs) -> io::Result<PathBuf> {
+        let path = DataDirs::prepare_file(dirs.model_dir().join(ROBUSTNESS_FILE))?;
+        let robustness = self
+            .robustness
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut out = String::new();
+        for model in PreTrainedModel::ALL {
+            if let Some(m) = robustness.get(&model) {
+                let optional = |v: Option<f32>| v.map(|v| v.to_string()).unwrap_or_default();
+                out.push_str(&format!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                    model.name(),
+                    m.samples,
+                    m.flipped,
+                    m.flip_rate,
+                    optional(m.min_epsilon),
+                    optional(m.mean_min_epsilon),
+                    m.max_epsilon
+                ));
+            }
+        }
+        fs::write(&path, out)?;
+        Ok(path)
+    }
+
+    /// Load robustness metrics saved by [`Self::save_robustness`]
+    ///
+    /// Returns the number of models restored; a missing file restores none.
+    /// Lines for unknown models are skipped.
+    pub fn load_robustness(&self, dirs: &DataDirs) -> io::Result<usize> {
+        let path = dirs.model_dir().join(ROBUSTNESS_FILE);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid robustness line in {}: {}", path.display(), line),
+            )
+        };
+        let mut robustness = self
+            .robustness
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut restored = 0;
+        for line in content.lines().filter(|l| !l.trim().is_empty()) {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 7 {
+                return Err(invalid(line));
+            }
+            let Some(model) = PreTrainedModel::ALL
+                .into_iter()
+                .find(|m| m.name() == fields[0])
+            else {
+                continue;
+            };
+            let optional = |v: &str| -> Result<Option<f32>, io::Error> {
+                if v.is_empty() {
+                    Ok(None)
+                } else {
+                    v.parse().map(Some).map_err(|_| invalid(line))
+                }
+            };
+            let metrics = RobustnessMetrics {
+                model: model.name().to_string(),
+                samples: fields[1].parse().map_err(|_| invalid(line))?,
+                flipped: fields[2].parse().map_err(|_| invalid(line))?,
+                flip_rate: fields[3].parse().map_err(|_| invalid(line))?,
+                min_epsilon: optional(fields[4])?,
+                mean_min_epsilon: optional(fields[5])?,
+                max_epsilon: fields[6].parse().map_err(|_| invalid(line))?,
+            };
+            robustness.insert(model, metrics);
+            restored += 1;
+        }
+        Ok(restored)
+    }
+
     fn ensure_model_loaded(&self, model: PreTrainedModel) {
         let _ = self.load_model(model);
     }
 
+    /// Predict on the shared compute pool instead of the calling thread
+    ///
+    /// Await the handle from async code so inference never occupies a runtime worker.
+    pub fn predict_offloaded(
+        self: &Arc<Self>,
+        model: PreTrainedModel,
+        features: Vec<f32>,
+    ) -> Result<ComputeHandle<ModelPrediction>, ComputeError> {
+        let manager = Arc::clone(self);
+        spawn_compute(move || manager.predict(model, &features))
+    }
+
+    /// Predict with any registered model
+    pub fn predict(&self, model: PreTrainedModel, features: &[f32]) -> ModelPrediction {
+        match model {
+            PreTrainedModel::AuthenticityClassifier => self.predict_authenticity(features),
+            PreTrainedModel::BrowserTypeClassifier => self.predict_browser(features),
+            PreTrainedModel::BehaviorAnomalyDetector => self.predict_anomaly(features),
+            PreTrainedModel::OSClassifier => {
+                self.predict_authenticity(features) // Placeholder
+            }
+            PreTrainedModel::DeviceTypeClassifier => {
+                self.predict_authenticity(features) // Placeholder
+            }
+        }
+    }
+
     /// Predict fingerprint authenticity
     pub fn predict_authenticity(&self, features: &[f32]) -> ModelPrediction {
         self.ensure_model_loaded(PreTrainedModel::AuthenticityClassifier);
@@ -469,19 +617,7 @@ impl EnsemblePredictor {
         let mut scores = HashMap::new();
 
         for model in &self.models {
-            let prediction = match model {
-                PreTrainedModel::AuthenticityClassifier => {
-                    self.manager.predict_authenticity(features)
-                }
-                PreTrainedModel::BrowserTypeClassifier => self.manager.predict_browser(features),
-                PreTrainedModel::BehaviorAnomalyDetector => self.manager.predict_anomaly(features),
-                PreTrainedModel::OSClassifier => {
-                    self.manager.predict_authenticity(features) // Placeholder
-                }
-                PreTrainedModel::DeviceTypeClassifier => {
-                    self.manager.predict_authenticity(features) // Placeholder
-                }
-            };
+            let prediction = self.manager.predict(*model, features);
 
             *scores.entry(prediction.label).or_insert(0.0) += prediction.confidence;
         }
@@ -583,6 +719,20 @@ mod tests {
         assert!(!predictions.is_empty());
     }
 
+    #[test]
+    fn test_offloaded_prediction_matches_inline() {
+        let manager = Arc::new(PreTrainedModelManager::new());
+        let features = vec![0.8; 10];
+        let inline = manager.predict(PreTrainedModel::AuthenticityClassifier, &features);
+        let offloaded = manager
+            .predict_offloaded(PreTrainedModel::AuthenticityClassifier, features)
+            .unwrap()
+            .wait()
+            .unwrap();
+        assert_eq!(offloaded.label, inline.label);
+        assert_eq!(offloaded.confidence, inline.confidence);
+    }
+
     #[test]
     fn test_model_metrics() {
         let model = PreTrainedModel::AuthenticityClassifier;
@@ -592,4 +742,33 @@ mod tests {
         assert!(metrics.precision > 0.9);
         assert_eq!(metrics.version, "2.1.0");
     }
+
+    #[test]
+    fn test_robustness_round_trips_through_model_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let dirs = DataDirs::portable(root.path());
+        let manager = PreTrainedModelManager::new();
+        let evaluator = AdversarialEvaluator::default();
+        let samples = vec![vec![0.5; 5], vec![0.0; 5]];
+        let metrics = manager.evaluate_robustness(
+            PreTrainedModel::BehaviorAnomalyDetector,
+            &samples,
+            &evaluator,
+        );
+
+        let path = manager.save_robustness(&dirs).unwrap();
+        assert!(path.starts_with(dirs.model_dir()));
+
+        let restored = PreTrainedModelManager::new();
+        assert_eq!(restored.load_robustness(&dirs).unwrap(), 1);
+        let loaded = restored
+            .robustness_metrics(PreTrainedModel::BehaviorAnomalyDetector)
+            .unwrap();
+        assert_eq!(loaded.samples, metrics.samples);
+        assert_eq!(loaded.flipped, metrics.flipped);
+        assert_eq!(loaded.min_epsilon, metrics.min_epsilon);
+
+        let empty = DataDirs::portable(root.path().join("empty"));
+        assert_eq!(restored.load_robustness(&empty).unwrap(), 0);
+    }
 }
@@ -1,5 +1,6 @@
 //! Comprehensive metrics collection for fingerprint-rust.
 
+use fingerprint_core::events::{self, MetricRecorded};
 use lazy_static::lazy_static;
 use prometheus::{
     opts, register_counter_vec, register_gauge_vec, register_histogram_vec, CounterVec, GaugeVec,
@@ -216,20 +217,39 @@ lazy_static! {
     .unwrap();
 }
 
+/// Announce a sample on the global event bus, if anyone listens
+fn announce(name: &str, labels: &[&str], value: f64) {
+    let bus = events::global();
+    if bus.has_subscribers::<MetricRecorded>() {
+        bus.publish(MetricRecorded {
+            name: name.to_string(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            value,
+        });
+    }
+}
+
 pub fn record_fingerprint_duration(browser: &str, duration_ms: f64) {
     FINGERPRINT_RECOGNITION_DURATION_MS
         .with_label_values(&[browser])
         .observe(duration_ms);
+    announce(
+        "fingerprint_recognition_duration_ms",
+        &[browser],
+        duration_ms,
+    );
 }
 
 pub fn record_cache_hit(level: &str, cache_type: &str) {
     CACHE_HIT_RATE.with_label_values(&[level, cache_type]).inc();
+    announce("fingerprint_cache_hits_total", &[level, cache_type], 1.0);
 }
 
 pub fn record_cache_miss(level: &str, cache_type: &str) {
     CACHE_MISS_RATE
         .with_label_values(&[level, cache_type])
         .inc();
+    announce("fingerprint_cache_misses_total", &[level, cache_type], 1.0);
 }
 
 pub fn record_db_operation(operation: &str, table: &str, duration_ms: f64) {
@@ -239,12 +259,22 @@ pub fn record_db_operation(operation: &str, table: &str, duration_ms: f64) {
     DB_QUERIES_TOTAL
         .with_label_values(&[operation, table, "success"])
         .inc();
+    announce(
+        "fingerprint_db_operation_duration_ms",
+        &[operation, table],
+        duration_ms,
+    );
 }
 
 pub fn record_error(error_type: &str, module: &str, severity: &str) {
     ERRORS_TOTAL
         .with_label_values(&[error_type, module, severity])
         .inc();
+    announce(
+        "fingerprint_errors_total",
+        &[error_type, module, severity],
+        1.0,
+    );
 }
 
 pub fn record_ml_inference(model: &str, duration_ms: f64) {
@@ -254,4 +284,9 @@ pub fn record_ml_inference(model: &str, duration_ms: f64) {
     ML_PREDICTION_TOTAL
         .with_label_values(&[model, "unknown"])
         .inc();
+    announce(
+        "fingerprint_ml_inference_duration_ms",
+        &[model],
+        duration_ms,
+    );
 }
@@ -0,0 +1,434 @@
+//! JavaScript engine quirk tables
+//!
+//! V8, SpiderMonkey and JavaScriptCore disagree on a number of observable details that
+//! have nothing to do with feature support: error message wording, `Error.stack` frame
+//! layout, native function `toString()` whitespace and a few libm-dependent `Math`
+//! results. A UA that claims Chrome but throws SpiderMonkey errors is lying.
+//!
+//! The same table feeds both sides: [`JsQuirkTable::validate`] checks collected probe
+//! values against the claimed browser/version, and [`JsQuirkTable::expected`] hands the
+//! spoofing layer the values it must return to stay consistent with the profile.
+
+use super::version_detector::VersionDetector;
+use super::version_registry::BrowserType;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// JavaScript engine family
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum JsEngine {
+    V8,
+    SpiderMonkey,
+    JavaScriptCore,
+}
+
+impl JsEngine {
+    /// Engine used by a browser on desktop/Android
+    pub fn for_browser(browser: BrowserType) -> Self {
+        match browser {
+            BrowserType::Chrome | BrowserType::Edge | BrowserType::Opera => JsEngine::V8,
+            BrowserType::Firefox => JsEngine::SpiderMonkey,
+            BrowserType::Safari => JsEngine::JavaScriptCore,
+        }
+    }
+
+    /// Engine and engine-native major version from a User-Agent
+    ///
+    /// Every browser on iOS runs JavaScriptCore regardless of its brand.
+    pub fn from_user_agent(user_agent: &str) -> Option<(Self, u32)> {
+        let info = VersionDetector::detect(user_agent)?;
+        if user_agent.contains("iPhone") || user_agent.contains("iPad") {
+            let version = user_agent
+                .split("Version/")
+                .nth(1)
+                .and_then(|v| v.split('.').next())
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(info.version);
+            return Some((JsEngine::JavaScriptCore, version));
+        }
+        Some((
+            Self::for_browser(info.browser),
+            Self::engine_version(info.browser, info.version),
+        ))
+    }
+
+    /// Map a browser major version onto the version line the quirk table is keyed by
+    /// (Chromium major for V8 browsers, Firefox/Safari major otherwise)
+    pub fn engine_version(browser: BrowserType, version: u32) -> u32 {
+        match browser {
+            // Opera N ships Chromium N + 14
+            BrowserType::Opera => version + 14,
+            _ => version,
+        }
+    }
+}
+
+impl std::fmt::Display for JsEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::V8 => write!(f, "V8"),
+            Self::SpiderMonkey => write!(f, "SpiderMonkey"),
+            Self::JavaScriptCore => write!(f, "JavaScriptCore"),
+        }
+    }
+}
+
+/// Probe expressions evaluated by the collector
+pub mod probes {
+    /// `null.x` TypeError message
+    pub const NULL_PROPERTY: &str = "null.x";
+    /// ReferenceError message for an undeclared identifier `x`
+    pub const UNDEFINED_VARIABLE: &str = "x";
+    /// `new Array(-1)` RangeError message
+    pub const INVALID_ARRAY_LENGTH: &str = "new Array(-1)";
+    /// `(1).toFixed(101)` RangeError message
+    pub const TO_FIXED_RANGE: &str = "(1).toFixed(101)";
+    /// `Error.stackTraceLimit` (undefined where unsupported)
+    pub const STACK_TRACE_LIMIT: &str = "Error.stackTraceLimit";
+    /// First `Error.stack` frame, normalized with [`super::normalize_stack_frame`]
+    pub const STACK_FRAME: &str = "Error.stack[frame]";
+    /// Whether `Error.stack` starts with `"Error: message"`
+    pub const STACK_HEADER: &str = "Error.stack[header]";
+    /// `Function.prototype.toString.call(Math.max)`
+    pub const NATIVE_TO_STRING: &str = "Math.max.toString()";
+    /// `Math.pow(Math.PI, -100)`
+    pub const POW_PI: &str = "Math.pow(Math.PI, -100)";
+}
+
+/// One engine-specific value, valid for an engine version range
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuirkEntry {
+    pub probe: String,
+    pub engine: JsEngine,
+    /// First engine-native major version with this value (inclusive)
+    pub since: Option<u32>,
+    /// First engine-native major version without this value (exclusive)
+    pub until: Option<u32>,
+    pub value: String,
+}
+
+impl QuirkEntry {
+    fn new(probe: &str, engine: JsEngine, value: &str) -> Self {
+        Self {
+            probe: probe.to_string(),
+            engine,
+            since: None,
+            until: None,
+            value: value.to_string(),
+        }
+    }
+
+    fn since(mut self, version: u32) -> Self {
+        self.since = Some(version);
+        self
+    }
+
+    fn until(mut self, version: u32) -> Self {
+        self.until = Some(version);
+        self
+    }
+
+    pub fn applies_to(&self, engine: JsEngine, version: u32) -> bool {
+        self.engine == engine
+            && self.since.is_none_or(|since| version >= since)
+            && self.until.is_none_or(|until| version < until)
+    }
+}
+
+/// Probe whose observed value contradicts the claimed browser
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuirkMismatch {
+    pub probe: String,
+    pub expected: String,
+    pub observed: String,
+    /// Engine whose table contains the observed value, if any
+    pub observed_engine: Option<JsEngine>,
+}
+
+/// Result of validating observed probe values against a claimed browser
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuirkValidation {
+    pub claimed_engine: JsEngine,
+    /// Probes that were both observed and pinned for the claimed engine
+    pub checked: usize,
+    pub mismatches: Vec<QuirkMismatch>,
+    /// Engine matching the most observed probes
+    pub inferred_engine: Option<JsEngine>,
+}
+
+impl QuirkValidation {
+    pub fn is_consistent(&self) -> bool {
+        self.mismatches.is_empty()
+            && self
+                .inferred_engine
+                .is_none_or(|engine| engine == self.claimed_engine)
+    }
+}
+
+/// Table of engine-specific probe values
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsQuirkTable {
+    entries: Vec<QuirkEntry>,
+}
+
+impl Default for JsQuirkTable {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl JsQuirkTable {
+    /// Table with known values
+    ///
+    /// `Math` results vary with the platform libm, so only values that are stable across
+    /// OSes for an engine are pinned.
+    pub fn builtin() -> Self {
+        use probes::*;
+        use JsEngine::*;
+
+        let entries = vec![
+            // V8 9.1 (Chrome 91) reworded property access errors
+            QuirkEntry::new(NULL_PROPERTY, V8, "Cannot read property 'x' of null").until(91),
+            QuirkEntry::new(
+                NULL_PROPERTY,
+                V8,
+                "Cannot read properties of null (reading 'x')",
+            )
+            .since(91),
+            QuirkEntry::new(NULL_PROPERTY, SpiderMonkey, "null has no properties"),
+            QuirkEntry::new(
+                NULL_PROPERTY,
+                JavaScriptCore,
+                "null is not an object (evaluating 'null.x')",
+            ),
+            QuirkEntry::new(UNDEFINED_VARIABLE, V8, "x is not defined"),
+            QuirkEntry::new(UNDEFINED_VARIABLE, SpiderMonkey, "x is not defined"),
+            QuirkEntry::new(UNDEFINED_VARIABLE, JavaScriptCore, "Can't find variable: x"),
+            QuirkEntry::new(INVALID_ARRAY_LENGTH, V8, "Invalid array length"),
+            QuirkEntry::new(INVALID_ARRAY_LENGTH, SpiderMonkey, "invalid array length"),
+            QuirkEntry::new(
+                INVALID_ARRAY_LENGTH,
+                JavaScriptCore,
+                "Array size is not a small enough positive integer.",
+            ),
+            QuirkEntry::new(
+                TO_FIXED_RANGE,
+                V8,
+                "toFixed() digits argument must be between 0 and 100",
+            ),
+            QuirkEntry::new(TO_FIXED_RANGE, SpiderMonkey, "precision 101 out of range"),
+            QuirkEntry::new(
+                TO_FIXED_RANGE,
+                JavaScriptCore,
+                "toFixed() argument must be between 0 and 100",
+            ),
+            QuirkEntry::new(STACK_TRACE_LIMIT, V8, "10"),
+            QuirkEntry::new(STACK_TRACE_LIMIT, SpiderMonkey, "undefined"),
+            QuirkEntry::new(STACK_TRACE_LIMIT, JavaScriptCore, "100"),
+            QuirkEntry::new(STACK_FRAME, V8, "    at {fn} ({url}:{line}:{col})"),
+            QuirkEntry::new(STACK_FRAME, SpiderMonkey, "{fn}@{url}:{line}:{col}"),
+            QuirkEntry::new(STACK_FRAME, JavaScriptCore, "{fn}@{url}:{line}:{col}"),
+            QuirkEntry::new(STACK_HEADER, V8, "true"),
+            QuirkEntry::new(STACK_HEADER, SpiderMonkey, "false"),
+            QuirkEntry::new(STACK_HEADER, JavaScriptCore, "false"),
+            QuirkEntry::new(NATIVE_TO_STRING, V8, "function max() { [native code] }"),
+            QuirkEntry::new(
+                NATIVE_TO_STRING,
+                SpiderMonkey,
+                "function max() {\n    [native code]\n}",
+            ),
+            QuirkEntry::new(
+                NATIVE_TO_STRING,
+                JavaScriptCore,
+                "function max() {\n    [native code]\n}",
+            ),
+            QuirkEntry::new(POW_PI, V8, "1.9275814160560204e-50"),
+            QuirkEntry::new(POW_PI, SpiderMonkey, "1.9275814160560185e-50"),
+        ];
+
+        Self { entries }
+    }
+
+    /// Build a table from custom entries (e.g. loaded from JSON)
+    pub fn from_entries(entries: Vec<QuirkEntry>) -> Self {
+        Self { entries }
+    }
+
+    pub fn entries(&self) -> &[QuirkEntry] {
+        &self.entries
+    }
+
+    /// Probe values an engine version produces, for the spoofing side
+    pub fn expected_for_engine(&self, engine: JsEngine, version: u32) -> BTreeMap<String, String> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.applies_to(engine, version))
+            .map(|entry| (entry.probe.clone(), entry.value.clone()))
+            .collect()
+    }
+
+    /// Probe values a browser version produces, for the spoofing side
+    pub fn expected(&self, browser: BrowserType, version: u32) -> BTreeMap<String, String> {
+        self.expected_for_engine(
+            JsEngine::for_browser(browser),
+            JsEngine::engine_version(browser, version),
+        )
+    }
+
+    /// Engine whose values match the most observed probes
+    ///
+    /// Versions are ignored: any value the engine ever produced counts as a match.
+    pub fn infer_engine(&self, observed: &HashMap<String, String>) -> Option<JsEngine> {
+        let mut scores: HashMap<JsEngine, usize> = HashMap::new();
+        for (probe, value) in observed {
+            let matching: Vec<JsEngine> = self
+                .entries
+                .iter()
+                .filter(|entry| &entry.probe == probe && &entry.value == value)
+                .map(|entry| entry.engine)
+                .collect();
+            for engine in matching {
+                *scores.entry(engine).or_default() += 1;
+            }
+        }
+
+        let best = scores.values().copied().max()?;
+        let mut leaders = scores.iter().filter(|(_, score)| **score == best);
+        let (engine, _) = leaders.next()?;
+        // a tie (e.g. only probes shared by SpiderMonkey and JSC) is not an answer
+        leaders.next().is_none().then_some(*engine)
+    }
+
+    /// Validate observed probe values against the claimed browser version
+    pub fn validate(
+        &self,
+        observed: &HashMap<String, String>,
+        browser: BrowserType,
+        version: u32,
+    ) -> QuirkValidation {
+        let engine = JsEngine::for_browser(browser);
+        self.validate_engine(observed, engine, JsEngine::engine_version(browser, version))
+    }
+
+    /// Validate observed probe values against an engine version
+    pub fn validate_engine(
+        &self,
+        observed: &HashMap<String, String>,
+        engine: JsEngine,
+        version: u32,
+    ) -> QuirkValidation {
+        let expected = self.expected_for_engine(engine, version);
+        let mut checked = 0;
+        let mut mismatches = Vec::new();
+
+        for (probe, expected_value) in &expected {
+            let Some(value) = observed.get(probe) else {
+                continue;
+            };
+            checked += 1;
+            if value != expected_value {
+                let observed_engine = self
+                    .entries
+                    .iter()
+                    .find(|entry| &entry.probe == probe && &entry.value == value)
+                    .map(|entry| entry.engine);
+                mismatches.push(QuirkMismatch {
+                    probe: probe.clone(),
+                    expected: expected_value.clone(),
+                    observed: value.clone(),
+                    observed_engine,
+                });
+            }
+        }
+
+        QuirkValidation {
+            claimed_engine: engine,
+            checked,
+            mismatches,
+            inferred_engine: self.infer_engine(observed),
+        }
+    }
+}
+
+/// Reduce an `Error.stack` frame to its layout template
+///
+/// `"    at foo (https://a/b.js:1:2)"` → `"    at {fn} ({url}:{line}:{col})"`,
+/// `"foo@https://a/b.js:1:2"` → `"{fn}@{url}:{line}:{col}"`.
+pub fn normalize_stack_frame(frame: &str) -> String {
+    if frame.trim_start().starts_with("at ") {
+        "    at {fn} ({url}:{line}:{col})".to_string()
+    } else if frame.contains('@') {
+        "{fn}@{url}:{line}:{col}".to_string()
+    } else {
+        frame.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observed(values: &BTreeMap<String, String>) -> HashMap<String, String> {
+        values.clone().into_iter().collect()
+    }
+
+    #[test]
+    fn test_expected_values_validate() {
+        let table = JsQuirkTable::builtin();
+        for browser in [
+            BrowserType::Chrome,
+            BrowserType::Firefox,
+            BrowserType::Safari,
+            BrowserType::Edge,
+            BrowserType::Opera,
+        ] {
+            let values = table.expected(browser, 120);
+            let result = table.validate(&observed(&values), browser, 120);
+            assert!(result.is_consistent(), "{:?}", result);
+            assert_eq!(result.inferred_engine, Some(JsEngine::for_browser(browser)));
+        }
+    }
+
+    #[test]
+    fn test_version_keyed_error_message() {
+        let table = JsQuirkTable::builtin();
+        let old = table.expected(BrowserType::Chrome, 90);
+        let new = table.expected(BrowserType::Chrome, 91);
+        assert_ne!(old[probes::NULL_PROPERTY], new[probes::NULL_PROPERTY]);
+
+        // Opera 77 is Chromium 91
+        let opera = table.expected(BrowserType::Opera, 77);
+        assert_eq!(opera[probes::NULL_PROPERTY], new[probes::NULL_PROPERTY]);
+    }
+
+    #[test]
+    fn test_firefox_claiming_chrome() {
+        let table = JsQuirkTable::builtin();
+        let firefox = observed(&table.expected(BrowserType::Firefox, 133));
+        let result = table.validate(&firefox, BrowserType::Chrome, 133);
+
+        assert!(!result.is_consistent());
+        assert_eq!(result.inferred_engine, Some(JsEngine::SpiderMonkey));
+        assert!(result
+            .mismatches
+            .iter()
+            .any(|m| m.observed_engine == Some(JsEngine::SpiderMonkey)));
+    }
+
+    #[test]
+    fn test_engine_from_user_agent() {
+        let ios_chrome = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) CriOS/120.0.0.0 Mobile/15E148 Safari/604.1";
+        let desktop = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/133.0.0.0 Safari/537.36";
+        assert_eq!(
+            JsEngine::from_user_agent(desktop),
+            Some((JsEngine::V8, 133))
+        );
+        if let Some((engine, _)) = JsEngine::from_user_agent(ios_chrome) {
+            assert_eq!(engine, JsEngine::JavaScriptCore);
+        }
+        assert_eq!(
+            normalize_stack_frame("    at foo (https://a/b.js:1:2)"),
+            "    at {fn} ({url}:{line}:{col})"
+        );
+    }
+}
@@ -2,13 +2,20 @@
 //!
 //! Browser fingerprint profiles module
 
+pub mod js_quirks;
+pub mod navigator;
 pub mod profiles;
 pub mod version_adapter;
 pub mod version_detector;
 pub mod version_registry;
 pub mod version_update;
 
-pub use profiles::{mapped_tls_clients, BrowserProfile, ProfileMetadata};
+pub use js_quirks::{JsEngine, JsQuirkTable, QuirkEntry, QuirkMismatch, QuirkValidation};
+pub use navigator::{
+    ConsistencyEngine, ConsistencyWarning, EngineFamily, NavigatorEnvironment, Platform,
+    ScreenMetrics,
+};
+pub use profiles::{mapped_app_clients, mapped_tls_clients, BrowserProfile, ProfileMetadata};
 pub use version_adapter::VersionAdapter;
 pub use version_detector::VersionDetector;
 pub use version_registry::{BrowserType, VersionEntry, VersionRegistry};
@@ -0,0 +1,659 @@
+//! Navigator and screen consistency
+//!
+//! Spoofing a User-Agent is not enough: `navigator.platform`, `vendor`, `deviceMemory`,
+//! touch points, screen size, language and timezone all have to agree with it, and
+//! detectors cross-check them the way `ContradictionDetector` does.
+//!
+//! [`ConsistencyEngine::generate`] produces a mutually consistent
+//! [`NavigatorEnvironment`] for a [`BrowserProfile`] (e.g. `chrome_133` on Windows),
+//! drawing screen and hardware values from common configurations of its platform.
+//! [`ConsistencyEngine::apply_overrides`] and [`ConsistencyEngine::validate`] check
+//! user-supplied values against the profile and report every contradiction.
+
+use fingerprint_core::types::BrowserType;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::profiles::BrowserProfile;
+
+/// Values Chromium exposes for `navigator.deviceMemory`
+const DEVICE_MEMORY_BUCKETS: [f32; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+/// Rendering engine family, which decides vendor strings and exposed APIs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EngineFamily {
+    Chromium,
+    Gecko,
+    WebKit,
+}
+
+impl EngineFamily {
+    fn for_browser(browser: BrowserType, platform: Platform) -> Self {
+        match (browser, platform) {
+            // every iOS browser is WebKit underneath
+            (_, Platform::Ios | Platform::IpadOs) => EngineFamily::WebKit,
+            (BrowserType::Firefox, _) => EngineFamily::Gecko,
+            (BrowserType::Safari, _) => EngineFamily::WebKit,
+            _ => EngineFamily::Chromium,
+        }
+    }
+
+    /// `navigator.vendor`
+    pub fn vendor(&self) -> &'static str {
+        match self {
+            EngineFamily::Chromium => "Google Inc.",
+            EngineFamily::Gecko => "",
+            EngineFamily::WebKit => "Apple Computer, Inc.",
+        }
+    }
+
+    /// `navigator.productSub`
+    pub fn product_sub(&self) -> &'static str {
+        match self {
+            EngineFamily::Gecko => "20100101",
+            _ => "20030107",
+        }
+    }
+}
+
+/// Platform a profile claims
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Platform {
+    Windows,
+    MacOs,
+    Linux,
+    Android,
+    Ios,
+    IpadOs,
+}
+
+impl Platform {
+    /// From [`crate::ProfileMetadata::platform`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Windows" => Some(Platform::Windows),
+            "macOS" => Some(Platform::MacOs),
+            "Linux" => Some(Platform::Linux),
+            "Android" => Some(Platform::Android),
+            "iOS" => Some(Platform::Ios),
+            "iPadOS" => Some(Platform::IpadOs),
+            _ => None,
+        }
+    }
+
+    pub fn is_mobile(&self) -> bool {
+        matches!(self, Platform::Android | Platform::Ios | Platform::IpadOs)
+    }
+
+    /// `navigator.platform` values real browsers report
+    ///
+    /// iPadOS Safari requests desktop sites by default and reports "MacIntel".
+    pub fn navigator_platforms(&self) -> &'static [&'static str] {
+        match self {
+            Platform::Windows => &["Win32"],
+            Platform::MacOs => &["MacIntel"],
+            Platform::Linux => &["Linux x86_64"],
+            Platform::Android => &["Linux armv81", "Linux armv8l", "Linux aarch64"],
+            Platform::Ios => &["iPhone"],
+            Platform::IpadOs => &["MacIntel", "iPad"],
+        }
+    }
+
+    /// `navigator.oscpu` (Firefox only)
+    fn oscpu(&self) -> &'static str {
+        match self {
+            Platform::Windows => "Windows NT 10.0; Win64; x64",
+            Platform::MacOs => "Intel Mac OS X 10.15",
+            Platform::Linux => "Linux x86_64",
+            Platform::Android => "Linux armv81",
+            Platform::Ios | Platform::IpadOs => "",
+        }
+    }
+
+    /// Common (width, height, devicePixelRatio) configurations
+    fn screens(&self) -> &'static [(u32, u32, f32)] {
+        match self {
+            Platform::Windows => &[
+                (1920, 1080, 1.0),
+                (1536, 864, 1.25),
+                (2560, 1440, 1.0),
+                (1366, 768, 1.0),
+                (1280, 720, 1.5),
+            ],
+            Platform::MacOs => &[
+                (1440, 900, 2.0),
+                (1512, 982, 2.0),
+                (1728, 1117, 2.0),
+                (2560, 1440, 1.0),
+            ],
+            Platform::Linux => &[(1920, 1080, 1.0), (2560, 1440, 1.0), (1366, 768, 1.0)],
+            Platform::Android => &[(412, 915, 2.625), (393, 873, 2.75), (360, 800, 3.0)],
+            Platform::Ios => &[(390, 844, 3.0), (393, 852, 3.0), (430, 932, 3.0)],
+            Platform::IpadOs => &[(820, 1180, 2.0), (1024, 1366, 2.0)],
+        }
+    }
+
+    /// Screen height taken by the taskbar, menu bar or dock
+    fn reserved_height(&self) -> u32 {
+        match self {
+            Platform::Windows => 40,
+            Platform::MacOs => 25,
+            Platform::Linux => 27,
+            _ => 0,
+        }
+    }
+
+    fn core_counts(&self) -> &'static [u32] {
+        match self {
+            Platform::Windows | Platform::Linux => &[4, 8, 12, 16],
+            Platform::MacOs => &[8, 10, 12],
+            Platform::Android => &[8],
+            Platform::Ios | Platform::IpadOs => &[4, 6],
+        }
+    }
+
+    fn device_memory(&self) -> &'static [f32] {
+        if self.is_mobile() {
+            &[4.0, 8.0]
+        } else {
+            &[8.0]
+        }
+    }
+}
+
+/// `screen.*` and `devicePixelRatio`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScreenMetrics {
+    pub width: u32,
+    pub height: u32,
+    pub avail_width: u32,
+    pub avail_height: u32,
+    pub color_depth: u32,
+    pub pixel_depth: u32,
+    pub device_pixel_ratio: f32,
+}
+
+/// Navigator, screen and locale values a page can read
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NavigatorEnvironment {
+    pub user_agent: String,
+    pub app_version: String,
+    pub platform: String,
+    pub vendor: String,
+    pub product_sub: String,
+    /// Firefox only
+    pub oscpu: Option<String>,
+    pub hardware_concurrency: u32,
+    /// Chromium only, in GiB
+    pub device_memory: Option<f32>,
+    pub max_touch_points: u32,
+    pub language: String,
+    pub languages: Vec<String>,
+    /// IANA zone, e.g. "America/New_York"
+    pub timezone: String,
+    pub screen: ScreenMetrics,
+}
+
+/// A value that contradicts the profile or another value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConsistencyWarning {
+    /// JS name, e.g. "navigator.platform"
+    pub attribute: String,
+    pub value: String,
+    pub reason: String,
+    /// Impossible for the profile, not just unusual
+    pub severe: bool,
+}
+
+impl fmt::Display for ConsistencyWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {:?}: {}", self.attribute, self.value, self.reason)
+    }
+}
+
+/// Builds and checks navigator environments for one profile
+#[derive(Debug, Clone)]
+pub struct ConsistencyEngine {
+    user_agent: String,
+    platform: Platform,
+    engine: EngineFamily,
+    language: String,
+    timezone: String,
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+fn pick<T: Copy>(items: &[T], state: &mut u64) -> T {
+    items[(splitmix64(state) % items.len() as u64) as usize]
+}
+
+/// Timezone prefixes plausible for a language's region
+fn region_timezones(language: &str) -> Option<&'static [&'static str]> {
+    let region = language.split('-').nth(1)?;
+    Some(match region {
+        "US" | "CA" | "MX" | "BR" | "AR" => &["America/", "Pacific/Honolulu"],
+        "GB" => &["Europe/London"],
+        "IE" => &["Europe/Dublin"],
+        "DE" | "FR" | "ES" | "IT" | "NL" | "PL" | "SE" | "AT" | "CH" | "BE" | "PT" => &["Europe/"],
+        "RU" => &["Europe/", "Asia/"],
+        "CN" => &["Asia/Shanghai", "Asia/Urumqi"],
+        "TW" => &["Asia/Taipei"],
+        "HK" => &["Asia/Hong_Kong"],
+        "JP" => &["Asia/Tokyo"],
+        "KR" => &["Asia/Seoul"],
+        "IN" => &["Asia/Kolkata", "Asia/Calcutta"],
+        "AU" => &["Australia/"],
+        "NZ" => &["Pacific/Auckland"],
+        _ => return None,
+    })
+}
+
+impl ConsistencyEngine {
+    /// Engine for a browser profile; native app profiles have no navigator
+    pub fn for_profile(profile: &BrowserProfile) -> Result<Self, String> {
+        let metadata = &profile.metadata;
+        let browser = BrowserType::from_str(&metadata.browser_name)
+            .ok_or_else(|| format!("{} is not a browser profile", metadata.browser_name))?;
+        let platform = Platform::from_name(&metadata.platform)
+            .ok_or_else(|| format!("unknown platform {}", metadata.platform))?;
+        Ok(Self {
+            user_agent: metadata.user_agent.clone(),
+            platform,
+            engine: EngineFamily::for_browser(browser, platform),
+            language: "en-US".to_string(),
+            timezone: "America/New_York".to_string(),
+        })
+    }
+
+    /// Language and IANA timezone to report (default en-US, America/New_York)
+    pub fn with_locale(mut self, language: &str, timezone: &str) -> Self {
+        self.language = language.to_string();
+        self.timezone = timezone.to_string();
+        self
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    pub fn engine(&self) -> EngineFamily {
+        self.engine
+    }
+
+    /// Consistent environment; the same seed always gives the same values
+    pub fn generate(&self, seed: u64) -> NavigatorEnvironment {
+        let mut state = seed;
+        let (width, height, device_pixel_ratio) = pick(self.platform.screens(), &mut state);
+        let color_depth = 24;
+
+        let app_version = match self.engine {
+            EngineFamily::Gecko => match self.platform {
+                Platform::Windows => "5.0 (Windows)".to_string(),
+                Platform::MacOs => "5.0 (Macintosh)".to_string(),
+                _ => "5.0 (X11)".to_string(),
+            },
+            _ => self
+                .user_agent
+                .strip_prefix("Mozilla/")
+                .unwrap_or(&self.user_agent)
+                .to_string(),
+        };
+
+        let mut languages = vec![self.language.clone()];
+        if let Some((base, _)) = self.language.split_once('-') {
+            languages.push(base.to_string());
+        }
+
+        NavigatorEnvironment {
+            user_agent: self.user_agent.clone(),
+            app_version,
+            platform: self.platform.navigator_platforms()[0].to_string(),
+            vendor: self.engine.vendor().to_string(),
+            product_sub: self.engine.product_sub().to_string(),
+            oscpu: (self.engine == EngineFamily::Gecko).then(|| self.platform.oscpu().to_string()),
+            hardware_concurrency: pick(self.platform.core_counts(), &mut state),
+            device_memory: (self.engine == EngineFamily::Chromium)
+                .then(|| pick(self.platform.device_memory(), &mut state)),
+            max_touch_points: if self.platform.is_mobile() { 5 } else { 0 },
+            language: self.language.clone(),
+            languages,
+            timezone: self.timezone.clone(),
+            screen: ScreenMetrics {
+                width,
+                height,
+                avail_width: width,
+                avail_height: height - self.platform.reserved_height(),
+                color_depth,
+                pixel_depth: color_depth,
+                device_pixel_ratio,
+            },
+        }
+    }
+
+    /// Apply user overrides (JS attribute names) to `base` and validate the result
+    ///
+    /// Unknown attributes and unparsable values are errors; contradictions are
+    /// returned as warnings so the caller decides whether to use the environment.
+    pub fn apply_overrides(
+        &self,
+        base: &NavigatorEnvironment,
+        overrides: &[(&str, &str)],
+    ) -> Result<(NavigatorEnvironment, Vec<ConsistencyWarning>), String> {
+        fn parse<T: std::str::FromStr>(attribute: &str, value: &str) -> Result<T, String> {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid value {:?} for {}", value, attribute))
+        }
+
+        let mut env = base.clone();
+        for &(attribute, value) in overrides {
+            match attribute {
+                "navigator.userAgent" => env.user_agent = value.to_string(),
+                "navigator.appVersion" => env.app_version = value.to_string(),
+                "navigator.platform" => env.platform = value.to_string(),
+                "navigator.vendor" => env.vendor = value.to_string(),
+                "navigator.productSub" => env.product_sub = value.to_string(),
+                "navigator.oscpu" => env.oscpu = Some(value.to_string()),
+                "navigator.hardwareConcurrency" => {
+                    env.hardware_concurrency = parse(attribute, value)?
+                }
+                "navigator.deviceMemory" => env.device_memory = Some(parse(attribute, value)?),
+                "navigator.maxTouchPoints" => env.max_touch_points = parse(attribute, value)?,
+                "navigator.language" => env.language = value.to_string(),
+                "navigator.languages" => {
+                    env.languages = value.split(',').map(|l| l.trim().to_string()).collect()
+                }
+                "timezone" => env.timezone = value.to_string(),
+                "screen.width" => env.screen.width = parse(attribute, value)?,
+                "screen.height" => env.screen.height = parse(attribute, value)?,
+                "screen.availWidth" => env.screen.avail_width = parse(attribute, value)?,
+                "screen.availHeight" => env.screen.avail_height = parse(attribute, value)?,
+                "screen.colorDepth" => env.screen.color_depth = parse(attribute, value)?,
+                "screen.pixelDepth" => env.screen.pixel_depth = parse(attribute, value)?,
+                "window.devicePixelRatio" => {
+                    env.screen.device_pixel_ratio = parse(attribute, value)?
+                }
+                _ => return Err(format!("unknown attribute {}", attribute)),
+            }
+        }
+        let warnings = self.validate(&env);
+        Ok((env, warnings))
+    }
+
+    /// Every value in `env` that contradicts the profile or another value
+    pub fn validate(&self, env: &NavigatorEnvironment) -> Vec<ConsistencyWarning> {
+        let mut warnings = Vec::new();
+        let mut warn = |attribute: &str, value: String, reason: &str, severe: bool| {
+            warnings.push(ConsistencyWarning {
+                attribute: attribute.to_string(),
+                value,
+                reason: reason.to_string(),
+                severe,
+            })
+        };
+
+        if env.user_agent != self.user_agent {
+            warn(
+                "navigator.userAgent",
+                env.user_agent.clone(),
+                "differs from the profile User-Agent sent in HTTP headers",
+                true,
+            );
+        }
+        if !self
+            .platform
+            .navigator_platforms()
+            .contains(&env.platform.as_str())
+        {
+            warn(
+                "navigator.platform",
+                env.platform.clone(),
+                "does not match the profile OS",
+                true,
+            );
+        }
+        if env.vendor != self.engine.vendor() {
+            warn(
+                "navigator.vendor",
+                env.vendor.clone(),
+                "does not match the browser engine",
+                true,
+            );
+        }
+        if env.product_sub != self.engine.product_sub() {
+            warn(
+                "navigator.productSub",
+                env.product_sub.clone(),
+                "does not match the browser engine",
+                true,
+            );
+        }
+        if env.oscpu.is_some() != (self.engine == EngineFamily::Gecko) {
+            warn(
+                "navigator.oscpu",
+                format!("{:?}", env.oscpu),
+                "only Firefox exposes oscpu",
+                true,
+            );
+        }
+
+        if env.hardware_concurrency == 0 || env.hardware_concurrency > 128 {
+            warn(
+                "navigator.hardwareConcurrency",
+                env.hardware_concurrency.to_string(),
+                "not a real core count",
+                true,
+            );
+        } else if self.platform.is_mobile() && env.hardware_concurrency > 12 {
+            warn(
+                "navigator.hardwareConcurrency",
+                env.hardware_concurrency.to_string(),
+                "unusually many cores for a mobile device",
+                false,
+            );
+        }
+        match env.device_memory {
+            Some(memory) if self.engine != EngineFamily::Chromium => warn(
+                "navigator.deviceMemory",
+                memory.to_string(),
+                "only Chromium browsers expose deviceMemory",
+                true,
+            ),
+            Some(memory) if !DEVICE_MEMORY_BUCKETS.contains(&memory) => warn(
+                "navigator.deviceMemory",
+                memory.to_string(),
+                "Chromium reports 0.25, 0.5, 1, 2, 4 or 8",
+                true,
+            ),
+            None if self.engine == EngineFamily::Chromium => warn(
+                "navigator.deviceMemory",
+                "undefined".to_string(),
+                "Chromium browsers always expose deviceMemory",
+                true,
+            ),
+            _ => {}
+        }
+
+        if self.platform.is_mobile() && env.max_touch_points == 0 {
+            warn(
+                "navigator.maxTouchPoints",
+                "0".to_string(),
+                "mobile devices have a touch screen",
+                true,
+            );
+        } else if self.platform == Platform::MacOs && env.max_touch_points > 0 {
+            warn(
+                "navigator.maxTouchPoints",
+                env.max_touch_points.to_string(),
+                "Macs have no touch screen",
+                true,
+            );
+        }
+
+        let screen = &env.screen;
+        if screen.avail_width > screen.width || screen.avail_height > screen.height {
+            warn(
+                "screen.availHeight",
+                format!("{}x{}", screen.avail_width, screen.avail_height),
+                "available area larger than the screen",
+                true,
+            );
+        }
+        let short_side = screen.width.min(screen.height);
+        if self.platform.is_mobile() && short_side > 1024 {
+            warn(
+                "screen.width",
+                screen.width.to_string(),
+                "desktop resolution on a mobile profile",
+                false,
+            );
+        } else if !self.platform.is_mobile() && screen.width < 1024 {
+            warn(
+                "screen.width",
+                screen.width.to_string(),
+                "mobile resolution on a desktop profile",
+                false,
+            );
+        }
+        if screen.color_depth != screen.pixel_depth {
+            warn(
+                "screen.pixelDepth",
+                screen.pixel_depth.to_string(),
+                "browsers report pixelDepth equal to colorDepth",
+                true,
+            );
+        }
+        if screen.device_pixel_ratio <= 0.0 {
+            warn(
+                "window.devicePixelRatio",
+                screen.device_pixel_ratio.to_string(),
+                "must be positive",
+                true,
+            );
+        }
+
+        if env.languages.first() != Some(&env.language) {
+            warn(
+                "navigator.languages",
+                env.languages.join(","),
+                "first entry must equal navigator.language",
+                true,
+            );
+        }
+        let iana = env.timezone == "UTC"
+            || env
+                .timezone
+                .split_once('/')
+                .is_some_and(|(area, city)| !area.is_empty() && !city.is_empty());
+        if !iana {
+            warn(
+                "timezone",
+                env.timezone.clone(),
+                "not an IANA timezone name",
+                true,
+            );
+        } else if let Some(zones) = region_timezones(&env.language) {
+            if !zones.iter().any(|zone| env.timezone.starts_with(zone)) {
+                warn(
+                    "timezone",
+                    env.timezone.clone(),
+                    "unusual for the language region",
+                    false,
+                );
+            }
+        }
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::{chrome_133, firefox_133, nsurlsession_ios_17_0, safari_ios_17_0};
+
+    #[test]
+    fn test_generated_environments_are_consistent() {
+        for profile in [chrome_133(), firefox_133(), safari_ios_17_0()] {
+            let engine = ConsistencyEngine::for_profile(&profile).unwrap();
+            for seed in 0..20 {
+                let env = engine.generate(seed);
+                assert_eq!(engine.validate(&env), vec![], "{}", profile.id());
+            }
+        }
+
+        let chrome = ConsistencyEngine::for_profile(&chrome_133()).unwrap();
+        let env = chrome.generate(7);
+        assert_eq!(env, chrome.generate(7));
+        assert_eq!(
+            (env.platform.as_str(), env.vendor.as_str()),
+            ("Win32", "Google Inc.")
+        );
+        assert_eq!(env.device_memory, Some(8.0));
+
+        let firefox = ConsistencyEngine::for_profile(&firefox_133())
+            .unwrap()
+            .generate(7);
+        assert_eq!(firefox.device_memory, None);
+        assert_eq!(
+            firefox.oscpu.as_deref(),
+            Some("Windows NT 10.0; Win64; x64")
+        );
+
+        let iphone = ConsistencyEngine::for_profile(&safari_ios_17_0())
+            .unwrap()
+            .generate(7);
+        assert_eq!(iphone.platform, "iPhone");
+        assert_eq!(iphone.max_touch_points, 5);
+
+        assert!(ConsistencyEngine::for_profile(&nsurlsession_ios_17_0()).is_err());
+    }
+
+    #[test]
+    fn test_overrides_raise_contradictions() {
+        let engine = ConsistencyEngine::for_profile(&chrome_133())
+            .unwrap()
+            .with_locale("de-DE", "Europe/Berlin");
+        let base = engine.generate(1);
+        assert!(engine.validate(&base).is_empty());
+
+        let (env, warnings) = engine
+            .apply_overrides(
+                &base,
+                &[
+                    ("navigator.platform", "MacIntel"),
+                    ("navigator.deviceMemory", "16"),
+                    ("screen.width", "2560"),
+                    ("timezone", "Asia/Tokyo"),
+                ],
+            )
+            .unwrap();
+        assert_eq!(env.screen.width, 2560);
+        let attributes: Vec<(&str, bool)> = warnings
+            .iter()
+            .map(|w| (w.attribute.as_str(), w.severe))
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                ("navigator.platform", true),
+                ("navigator.deviceMemory", true),
+                ("timezone", false),
+            ]
+        );
+
+        assert!(engine
+            .apply_overrides(&base, &[("navigator.hardwareConcurrency", "many")])
+            .is_err());
+        assert!(engine
+            .apply_overrides(&base, &[("navigator.gpu", "x")])
+            .is_err());
+    }
+}
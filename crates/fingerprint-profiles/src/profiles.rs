@@ -7,11 +7,12 @@ use fingerprint_core::types::BrowserType;
 use fingerprint_headers::{
     generate_headers,
     http2_config::{
-        chrome_http2_settings, firefox_http2_settings, safari_http2_settings, HTTP2Settings,
+        chrome_http2_settings, firefox_http2_settings, okhttp_http2_settings,
+        safari_http2_settings, HTTP2Settings,
     },
     HTTPHeaders,
 };
-use fingerprint_tls::tls_config::ClientHelloSpec;
+use fingerprint_tls::tls_config::{ClientHelloSpec, MobileTlsStack};
 
 /// Complete browser fingerprint profile
 ///
@@ -114,6 +115,50 @@ impl BrowserProfile {
         }
     }
 
+    /// Create a native app profile on a mobile OS TLS stack
+    ///
+    /// Apps send the OS stack's ClientHello and the HTTP client's own headers:
+    /// URLSession (CFNetwork) on iOS, OkHttp on Android.
+    fn create_app(
+        stack: MobileTlsStack,
+        version: u32,
+        version_string: String,
+        user_agent: String,
+    ) -> Self {
+        let mut http_headers = HTTPHeaders::new();
+        http_headers.user_agent = user_agent.clone();
+
+        let (browser_name, (http2_settings, http2_settings_order)) = match stack {
+            MobileTlsStack::NsUrlSession { .. } => {
+                http_headers.accept = "*/*".to_string();
+                http_headers.accept_language = "en-US,en;q=0.9".to_string();
+                http_headers.accept_encoding = "gzip, deflate, br".to_string();
+                ("NSURLSession", safari_http2_settings())
+            }
+            MobileTlsStack::Conscrypt { .. } => {
+                http_headers.accept_encoding = "gzip".to_string();
+                ("Conscrypt", okhttp_http2_settings())
+            }
+        };
+
+        let metadata = ProfileMetadata {
+            browser_name: browser_name.to_string(),
+            browser_version: version,
+            user_agent,
+            platform: stack.platform().to_string(),
+            is_mobile: true,
+            version_string,
+        };
+
+        Self {
+            tls_config: stack.spec(),
+            http_headers,
+            http2_settings,
+            http2_settings_order,
+            metadata,
+        }
+    }
+
     /// Get a unique identifier for this profile (e.g., "chrome_133", "chrome_mobile_134")
     pub fn id(&self) -> String {
         let browser_name = if self.metadata.is_mobile {
@@ -480,6 +525,95 @@ define_firefox_mobile_version!(firefox_mobile_120, 120, ClientHelloSpec::firefox
 define_firefox_mobile_version!(firefox_mobile_130, 130, ClientHelloSpec::firefox_133);
 define_firefox_mobile_version!(firefox_mobile_135, 135, ClientHelloSpec::firefox_133);
 
+// ============================================================================
+// Mobile App Profiles (OS TLS stacks)
+// ============================================================================
+
+// iOS app using URLSession with App Transport Security defaults
+macro_rules! define_nsurlsession_version {
+    ($fn_name:ident, $version_str:expr, $ios_major:expr, $version_num:expr, $cfnetwork:expr, $darwin:expr) => {
+        pub fn $fn_name() -> BrowserProfile {
+            BrowserProfile::create_app(
+                MobileTlsStack::NsUrlSession {
+                    ios_major: $ios_major,
+                    ats: true,
+                },
+                $version_num,
+                $version_str.to_string(),
+                format!("App/1 CFNetwork/{} Darwin/{}", $cfnetwork, $darwin),
+            )
+        }
+    };
+}
+
+define_nsurlsession_version!(nsurlsession_ios_15_6, "15.6", 15, 156, "1335.0.3", "21.6.0");
+define_nsurlsession_version!(nsurlsession_ios_16_0, "16.0", 16, 160, "1390", "22.0.0");
+define_nsurlsession_version!(nsurlsession_ios_17_0, "17.0", 17, 170, "1474", "23.0.0");
+define_nsurlsession_version!(
+    nsurlsession_ios_18_0,
+    "18.0",
+    18,
+    180,
+    "1568.100.1",
+    "24.0.0"
+);
+define_nsurlsession_version!(
+    nsurlsession_ios_26_0,
+    "26.0",
+    26,
+    260,
+    "3826.400.120",
+    "25.0.0"
+);
+
+// Android app using OkHttp on the platform Conscrypt provider
+macro_rules! define_conscrypt_version {
+    ($fn_name:ident, $api_level:expr) => {
+        pub fn $fn_name() -> BrowserProfile {
+            BrowserProfile::create_app(
+                MobileTlsStack::Conscrypt {
+                    api_level: $api_level,
+                },
+                $api_level,
+                $api_level.to_string(),
+                "okhttp/4.12.0".to_string(),
+            )
+        }
+    };
+}
+
+define_conscrypt_version!(conscrypt_mobile_23, 23);
+define_conscrypt_version!(conscrypt_mobile_28, 28);
+define_conscrypt_version!(conscrypt_mobile_29, 29);
+define_conscrypt_version!(conscrypt_mobile_31, 31);
+define_conscrypt_version!(conscrypt_mobile_33, 33);
+define_conscrypt_version!(conscrypt_mobile_34, 34);
+define_conscrypt_version!(conscrypt_mobile_35, 35);
+
+/// Get all native app profiles as a map, keyed by [`BrowserProfile::id`]
+///
+/// Kept apart from [`mapped_tls_clients`] so random browser selection never
+/// picks an app client.
+pub fn mapped_app_clients() -> std::collections::HashMap<String, BrowserProfile> {
+    [
+        nsurlsession_ios_15_6(),
+        nsurlsession_ios_16_0(),
+        nsurlsession_ios_17_0(),
+        nsurlsession_ios_18_0(),
+        nsurlsession_ios_26_0(),
+        conscrypt_mobile_23(),
+        conscrypt_mobile_28(),
+        conscrypt_mobile_29(),
+        conscrypt_mobile_31(),
+        conscrypt_mobile_33(),
+        conscrypt_mobile_34(),
+        conscrypt_mobile_35(),
+    ]
+    .into_iter()
+    .map(|profile| (profile.id(), profile))
+    .collect()
+}
+
 /// Get all available browser profiles as a map
 pub fn mapped_tls_clients() -> std::collections::HashMap<String, BrowserProfile> {
     let mut map = std::collections::HashMap::new();
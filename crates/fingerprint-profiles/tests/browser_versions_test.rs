@@ -112,4 +112,29 @@ mod tests {
         let firefox_mobile_130 = firefox_mobile_130();
         assert_eq!(firefox_mobile_130.id(), "firefox_mobile_130");
     }
+
+    #[test]
+    fn test_mobile_app_profiles() {
+        use fingerprint_tls::tls_config::MobileTlsStack;
+
+        let ios = nsurlsession_ios_17_0();
+        assert_eq!(ios.id(), "nsurlsession_ios_17_0");
+        assert!(ios.metadata.user_agent.contains("CFNetwork/"));
+        assert!(ios.http_headers.sec_fetch_mode.is_empty());
+
+        let android = conscrypt_mobile_34();
+        assert_eq!(android.id(), "conscrypt_mobile_34");
+        assert_eq!(android.metadata.platform, "Android");
+        assert_eq!(
+            android.tls_config.ja4_string(),
+            MobileTlsStack::Conscrypt { api_level: 34 }
+                .spec()
+                .ja4_string()
+        );
+
+        let apps = mapped_app_clients();
+        assert!(apps.contains_key("nsurlsession_ios_26_0"));
+        assert!(apps.values().all(|p| p.metadata.is_mobile));
+        assert!(!mapped_tls_clients().contains_key("conscrypt_mobile_34"));
+    }
 }
@@ -0,0 +1,662 @@
+//! Coordinated fleet mode
+//!
+//! Workers that generate identities independently from the same library collide: with a
+//! few hundred workers, birthday collisions on (profile, platform, locale, screen) are
+//! near certain. Each worker reserves its combination in a shared store before using it.
+//! Reservations are exclusive (`SET NX` with a TTL), renewed while the worker is alive and
+//! expire if it dies. Before reserving, the coordinator checks fleet-wide distribution
+//! targets so the fleet as a whole keeps a realistic mix instead of piling onto the
+//! first candidates.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::rate_limiting::{RedisBackendError, RedisResult};
+
+/// Separator of combination fields inside a reservation key
+const KEY_SEPARATOR: char = '|';
+
+/// Identity dimension a distribution target applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dimension {
+    Profile,
+    Platform,
+    Locale,
+    Screen,
+}
+
+impl Dimension {
+    pub const ALL: [Dimension; 4] = [
+        Dimension::Profile,
+        Dimension::Platform,
+        Dimension::Locale,
+        Dimension::Screen,
+    ];
+}
+
+/// Identity parameters reserved as one unit
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IdentityCombination {
+    pub profile: String,
+    pub platform: String,
+    pub locale: String,
+    pub screen: String,
+}
+
+impl IdentityCombination {
+    pub fn new(profile: &str, platform: &str, locale: &str, screen: &str) -> Self {
+        Self {
+            profile: profile.to_string(),
+            platform: platform.to_string(),
+            locale: locale.to_string(),
+            screen: screen.to_string(),
+        }
+    }
+
+    pub fn get(&self, dimension: Dimension) -> &str {
+        match dimension {
+            Dimension::Profile => &self.profile,
+            Dimension::Platform => &self.platform,
+            Dimension::Locale => &self.locale,
+            Dimension::Screen => &self.screen,
+        }
+    }
+
+    /// Canonical key (`profile|platform|locale|screen`)
+    pub fn key(&self) -> String {
+        [&self.profile, &self.platform, &self.locale, &self.screen]
+            .map(|s| s.replace(KEY_SEPARATOR, "_"))
+            .join(&KEY_SEPARATOR.to_string())
+    }
+
+    /// Parse a key produced by [`IdentityCombination::key`]
+    pub fn from_key(key: &str) -> Option<Self> {
+        let mut parts = key.split(KEY_SEPARATOR);
+        let combination = Self::new(parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        parts.next().is_none().then_some(combination)
+    }
+}
+
+/// Shared reservation storage
+#[async_trait]
+pub trait ReservationStore: Send + Sync {
+    /// Reserve `key` for `worker_id` unless another worker holds it
+    async fn try_reserve(&self, key: &str, worker_id: &str, ttl: Duration) -> RedisResult<bool>;
+
+    /// Extend a reservation held by `worker_id`
+    async fn renew(&self, key: &str, worker_id: &str, ttl: Duration) -> RedisResult<bool>;
+
+    /// Release a reservation held by `worker_id`
+    async fn release(&self, key: &str, worker_id: &str) -> RedisResult<bool>;
+
+    /// All live reservations as `(key, worker_id)`
+    async fn reservations(&self) -> RedisResult<Vec<(String, String)>>;
+}
+
+/// In-process store for single-node fleets and tests
+#[derive(Default)]
+pub struct MemoryReservationStore {
+    entries: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl MemoryReservationStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn live(&self) -> std::sync::MutexGuard<'_, HashMap<String, (String, Instant)>> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        entries.retain(|_, (_, expires)| *expires > now);
+        entries
+    }
+}
+
+#[async_trait]
+impl ReservationStore for MemoryReservationStore {
+    async fn try_reserve(&self, key: &str, worker_id: &str, ttl: Duration) -> RedisResult<bool> {
+        let mut entries = self.live();
+        if entries.contains_key(key) {
+            return Ok(false);
+        }
+        entries.insert(
+            key.to_string(),
+            (worker_id.to_string(), Instant::now() + ttl),
+        );
+        Ok(true)
+    }
+
+    async fn renew(&self, key: &str, worker_id: &str, ttl: Duration) -> RedisResult<bool> {
+        let mut entries = self.live();
+        match entries.get_mut(key) {
+            Some((owner, expires)) if owner == worker_id => {
+                *expires = Instant::now() + ttl;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn release(&self, key: &str, worker_id: &str) -> RedisResult<bool> {
+        let mut entries = self.live();
+        if entries
+            .get(key)
+            .is_some_and(|(owner, _)| owner == worker_id)
+        {
+            entries.remove(key);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    async fn reservations(&self) -> RedisResult<Vec<(String, String)>> {
+        Ok(self
+            .live()
+            .iter()
+            .map(|(key, (owner, _))| (key.clone(), owner.clone()))
+            .collect())
+    }
+}
+
+/// Lets several coordinators in one process share a store
+#[async_trait]
+impl<T: ReservationStore + ?Sized> ReservationStore for Arc<T> {
+    async fn try_reserve(&self, key: &str, worker_id: &str, ttl: Duration) -> RedisResult<bool> {
+        (**self).try_reserve(key, worker_id, ttl).await
+    }
+
+    async fn renew(&self, key: &str, worker_id: &str, ttl: Duration) -> RedisResult<bool> {
+        (**self).renew(key, worker_id, ttl).await
+    }
+
+    async fn release(&self, key: &str, worker_id: &str) -> RedisResult<bool> {
+        (**self).release(key, worker_id).await
+    }
+
+    async fn reservations(&self) -> RedisResult<Vec<(String, String)>> {
+        (**self).reservations().await
+    }
+}
+
+/// Compare-and-delete, so a worker cannot release a reservation it lost to expiry
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// Compare-and-extend
+const RENEW_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+/// Redis-backed store shared by all workers
+///
+/// Each reservation is a `{prefix}:combo:{key}` string holding the worker id.
+pub struct RedisReservationStore {
+    prefix: String,
+    connection: redis::aio::ConnectionManager,
+}
+
+impl RedisReservationStore {
+    /// Connect to Redis; `prefix` namespaces one fleet
+    pub async fn connect(url: &str, prefix: &str) -> RedisResult<Self> {
+        let client = redis::Client::open(url)
+            .map_err(|e| RedisBackendError::ConnectionError(e.to_string()))?;
+        let connection = client
+            .get_connection_manager()
+            .await
+            .map_err(|e| RedisBackendError::ConnectionError(e.to_string()))?;
+        Ok(Self {
+            prefix: prefix.to_string(),
+            connection,
+        })
+    }
+
+    fn combo_key(&self, key: &str) -> String {
+        format!("{}:combo:{}", self.prefix, key)
+    }
+}
+
+fn command_error(e: redis::RedisError) -> RedisBackendError {
+    RedisBackendError::CommandError(e.to_string())
+}
+
+#[async_trait]
+impl ReservationStore for RedisReservationStore {
+    async fn try_reserve(&self, key: &str, worker_id: &str, ttl: Duration) -> RedisResult<bool> {
+        let mut conn = self.connection.clone();
+        let reply: Option<String> = redis::cmd("SET")
+            .arg(self.combo_key(key))
+            .arg(worker_id)
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl.as_millis() as u64)
+            .query_async(&mut conn)
+            .await
+            .map_err(command_error)?;
+        Ok(reply.is_some())
+    }
+
+    async fn renew(&self, key: &str, worker_id: &str, ttl: Duration) -> RedisResult<bool> {
+        let mut conn = self.connection.clone();
+        let renewed: i64 = redis::Script::new(RENEW_SCRIPT)
+            .key(self.combo_key(key))
+            .arg(worker_id)
+            .arg(ttl.as_millis() as u64)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(command_error)?;
+        Ok(renewed == 1)
+    }
+
+    async fn release(&self, key: &str, worker_id: &str) -> RedisResult<bool> {
+        let mut conn = self.connection.clone();
+        let deleted: i64 = redis::Script::new(RELEASE_SCRIPT)
+            .key(self.combo_key(key))
+            .arg(worker_id)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(command_error)?;
+        Ok(deleted == 1)
+    }
+
+    async fn reservations(&self) -> RedisResult<Vec<(String, String)>> {
+        let mut conn = self.connection.clone();
+        let pattern = self.combo_key("*");
+        let strip = self.combo_key("");
+
+        let mut keys: Vec<String> = Vec::new();
+        let mut cursor: u64 = 0;
+        loop {
+            let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(500)
+                .query_async(&mut conn)
+                .await
+                .map_err(command_error)?;
+            keys.extend(batch);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let owners: Vec<Option<String>> = redis::cmd("MGET")
+            .arg(&keys)
+            .query_async(&mut conn)
+            .await
+            .map_err(command_error)?;
+        Ok(keys
+            .into_iter()
+            .zip(owners)
+            // keys may expire between SCAN and MGET
+            .filter_map(|(key, owner)| Some((key.strip_prefix(&strip)?.to_string(), owner?)))
+            .collect())
+    }
+}
+
+/// Desired fleet-wide share of each value of one dimension
+#[derive(Debug, Clone)]
+pub struct DistributionTarget {
+    pub dimension: Dimension,
+    /// Target share per value; values not listed are not limited
+    pub shares: HashMap<String, f64>,
+    /// Allowed relative overshoot (0.2 = up to 120% of the target share)
+    pub tolerance: f64,
+}
+
+impl DistributionTarget {
+    pub fn new(dimension: Dimension, shares: &[(&str, f64)]) -> Self {
+        Self {
+            dimension,
+            shares: shares.iter().map(|(v, s)| (v.to_string(), *s)).collect(),
+            tolerance: 0.2,
+        }
+    }
+
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Maximum count for `value` in a fleet of `fleet_size`
+    ///
+    /// Always allows at least one, so small fleets can still use low-share values.
+    fn limit(&self, value: &str, fleet_size: usize) -> Option<usize> {
+        let share = self.shares.get(value)?;
+        let limit = (share * (1.0 + self.tolerance) * fleet_size as f64).ceil() as usize;
+        Some(limit.max(1))
+    }
+}
+
+/// Fleet coordinator configuration
+#[derive(Debug, Clone)]
+pub struct FleetConfig {
+    pub worker_id: String,
+    /// Reservation lifetime; workers renew well before it elapses
+    pub reservation_ttl: Duration,
+    /// Expected number of concurrent identities, used to size distribution limits
+    pub expected_fleet_size: usize,
+    pub targets: Vec<DistributionTarget>,
+}
+
+impl FleetConfig {
+    pub fn new(worker_id: &str, expected_fleet_size: usize) -> Self {
+        Self {
+            worker_id: worker_id.to_string(),
+            reservation_ttl: Duration::from_secs(300),
+            expected_fleet_size,
+            targets: Vec::new(),
+        }
+    }
+
+    pub fn with_target(mut self, target: DistributionTarget) -> Self {
+        self.targets.push(target);
+        self
+    }
+
+    pub fn with_reservation_ttl(mut self, ttl: Duration) -> Self {
+        self.reservation_ttl = ttl;
+        self
+    }
+}
+
+/// Identity combination held by this worker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reservation {
+    pub combination: IdentityCombination,
+    pub worker_id: String,
+}
+
+/// Distribution of one dimension across the fleet
+#[derive(Debug, Clone)]
+pub struct DimensionStats {
+    pub counts: HashMap<String, usize>,
+    /// Shannon entropy of the value distribution
+    pub entropy_bits: f64,
+    /// Entropy relative to a uniform distribution over the observed values (0.0 - 1.0)
+    pub evenness: f64,
+    /// Largest share held by a single value
+    pub max_share: f64,
+    /// Largest absolute gap between actual and target share, if a target is set
+    pub target_deviation: Option<f64>,
+}
+
+/// Fleet-wide identity diversity
+#[derive(Debug, Clone)]
+pub struct FleetDiversity {
+    /// Live reservations
+    pub identities: usize,
+    /// Workers holding at least one reservation
+    pub workers: usize,
+    pub dimensions: HashMap<Dimension, DimensionStats>,
+}
+
+/// Reserves unique identity combinations for one worker
+pub struct FleetCoordinator<S: ReservationStore> {
+    store: S,
+    config: FleetConfig,
+}
+
+impl<S: ReservationStore> FleetCoordinator<S> {
+    pub fn new(store: S, config: FleetConfig) -> Self {
+        Self { store, config }
+    }
+
+    pub fn config(&self) -> &FleetConfig {
+        &self.config
+    }
+
+    async fn live_combinations(&self) -> RedisResult<Vec<(IdentityCombination, String)>> {
+        Ok(self
+            .store
+            .reservations()
+            .await?
+            .into_iter()
+            .filter_map(|(key, worker)| Some((IdentityCombination::from_key(&key)?, worker)))
+            .collect())
+    }
+
+    /// Reserve the first candidate that is free and keeps the fleet within its targets
+    ///
+    /// Callers pass candidates in their preferred order (typically shuffled). Returns
+    /// `None` when every candidate is taken or over target. Uniqueness is enforced by the
+    /// store; targets are checked against a snapshot, so concurrent reservations can
+    /// overshoot a limit by the number of racing workers.
+    pub async fn reserve(
+        &self,
+        candidates: &[IdentityCombination],
+    ) -> RedisResult<Option<Reservation>> {
+        let live = self.live_combinations().await?;
+        let fleet_size = self.config.expected_fleet_size.max(live.len() + 1);
+        let mut counts: HashMap<(Dimension, &str), usize> = HashMap::new();
+        for (combination, _) in &live {
+            for dimension in Dimension::ALL {
+                *counts
+                    .entry((dimension, combination.get(dimension)))
+                    .or_default() += 1;
+            }
+        }
+
+        for candidate in candidates {
+            let within_targets = self.config.targets.iter().all(|target| {
+                let value = candidate.get(target.dimension);
+                target.limit(value, fleet_size).is_none_or(|limit| {
+                    counts.get(&(target.dimension, value)).copied().unwrap_or(0) < limit
+                })
+            });
+            if !within_targets {
+                continue;
+            }
+            if self
+                .store
+                .try_reserve(
+                    &candidate.key(),
+                    &self.config.worker_id,
+                    self.config.reservation_ttl,
+                )
+                .await?
+            {
+                return Ok(Some(Reservation {
+                    combination: candidate.clone(),
+                    worker_id: self.config.worker_id.clone(),
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Keep a reservation alive; `false` means it expired and may now belong to another worker
+    pub async fn renew(&self, reservation: &Reservation) -> RedisResult<bool> {
+        self.store
+            .renew(
+                &reservation.combination.key(),
+                &reservation.worker_id,
+                self.config.reservation_ttl,
+            )
+            .await
+    }
+
+    pub async fn release(&self, reservation: &Reservation) -> RedisResult<bool> {
+        self.store
+            .release(&reservation.combination.key(), &reservation.worker_id)
+            .await
+    }
+
+    /// Diversity metrics of the live fleet
+    pub async fn diversity(&self) -> RedisResult<FleetDiversity> {
+        let live = self.live_combinations().await?;
+        let total = live.len();
+        let mut workers: Vec<&str> = live.iter().map(|(_, w)| w.as_str()).collect();
+        workers.sort_unstable();
+        workers.dedup();
+
+        let mut dimensions = HashMap::new();
+        for dimension in Dimension::ALL {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for (combination, _) in &live {
+                *counts
+                    .entry(combination.get(dimension).to_string())
+                    .or_default() += 1;
+            }
+
+            let shares: Vec<f64> = counts
+                .values()
+                .map(|c| *c as f64 / total.max(1) as f64)
+                .collect();
+            let entropy_bits: f64 = shares.iter().map(|p| -p * p.log2()).sum();
+            let evenness = if counts.len() > 1 {
+                entropy_bits / (counts.len() as f64).log2()
+            } else {
+                0.0
+            };
+            let target_deviation = self
+                .config
+                .targets
+                .iter()
+                .find(|t| t.dimension == dimension)
+                .map(|target| {
+                    target
+                        .shares
+                        .iter()
+                        .map(|(value, share)| {
+                            let actual = counts.get(value).copied().unwrap_or(0) as f64
+                                / total.max(1) as f64;
+                            (actual - share).abs()
+                        })
+                        .fold(0.0, f64::max)
+                });
+
+            dimensions.insert(
+                dimension,
+                DimensionStats {
+                    max_share: shares.iter().copied().fold(0.0, f64::max),
+                    counts,
+                    entropy_bits,
+                    evenness,
+                    target_deviation,
+                },
+            );
+        }
+
+        Ok(FleetDiversity {
+            identities: total,
+            workers: workers.len(),
+            dimensions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> Vec<IdentityCombination> {
+        let mut out = Vec::new();
+        for profile in ["chrome_133", "chrome_136", "firefox_133"] {
+            for locale in ["en-US", "de-DE"] {
+                out.push(IdentityCombination::new(
+                    profile,
+                    "windows",
+                    locale,
+                    "1920x1080",
+                ));
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_combination_key_roundtrip() {
+        let combination = IdentityCombination::new("chrome_133", "windows", "en-US", "1920x1080");
+        assert_eq!(
+            IdentityCombination::from_key(&combination.key()),
+            Some(combination)
+        );
+        assert_eq!(IdentityCombination::from_key("a|b|c"), None);
+    }
+
+    #[tokio::test]
+    async fn test_workers_never_share_a_combination() {
+        let store = Arc::new(MemoryReservationStore::new());
+        let mut reserved = Vec::new();
+        for worker in 0..6 {
+            let coordinator = FleetCoordinator::new(
+                store.clone(),
+                FleetConfig::new(&format!("worker-{}", worker), 6),
+            );
+            let reservation = coordinator.reserve(&candidates()).await.unwrap().unwrap();
+            assert!(!reserved.contains(&reservation.combination));
+            reserved.push(reservation.combination);
+        }
+
+        let late = FleetCoordinator::new(store.clone(), FleetConfig::new("worker-6", 6));
+        assert!(late.reserve(&candidates()).await.unwrap().is_none());
+
+        let diversity = late.diversity().await.unwrap();
+        assert_eq!(diversity.identities, 6);
+        assert_eq!(diversity.workers, 6);
+        let locale = &diversity.dimensions[&Dimension::Locale];
+        assert!((locale.evenness - 1.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_distribution_targets_limit_values() {
+        let store = Arc::new(MemoryReservationStore::new());
+        let target = DistributionTarget::new(
+            Dimension::Profile,
+            &[
+                ("chrome_133", 0.5),
+                ("chrome_136", 0.25),
+                ("firefox_133", 0.25),
+            ],
+        )
+        .with_tolerance(0.0);
+
+        let mut held = Vec::new();
+        for worker in 0..4 {
+            let coordinator = FleetCoordinator::new(
+                store.clone(),
+                FleetConfig::new(&format!("worker-{}", worker), 4).with_target(target.clone()),
+            );
+            held.push(coordinator.reserve(&candidates()).await.unwrap().unwrap());
+        }
+
+        let coordinator = FleetCoordinator::new(
+            store.clone(),
+            FleetConfig::new("observer", 4).with_target(target),
+        );
+        let diversity = coordinator.diversity().await.unwrap();
+        let profiles = &diversity.dimensions[&Dimension::Profile];
+        assert_eq!(profiles.counts["chrome_133"], 2);
+        assert_eq!(profiles.counts["chrome_136"], 1);
+        assert_eq!(profiles.counts["firefox_133"], 1);
+        assert_eq!(profiles.target_deviation, Some(0.0));
+
+        // released reservations can be taken by someone else, but only by their owner
+        assert!(!coordinator
+            .release(&Reservation {
+                combination: held[0].combination.clone(),
+                worker_id: "observer".to_string(),
+            })
+            .await
+            .unwrap());
+        let owner = FleetCoordinator::new(store.clone(), FleetConfig::new("worker-0", 4));
+        assert!(owner.renew(&held[0]).await.unwrap());
+        assert!(owner.release(&held[0]).await.unwrap());
+        assert_eq!(coordinator.diversity().await.unwrap().identities, 3);
+    }
+}
@@ -1,7 +1,13 @@
 pub mod cache;
+pub mod fleet;
 pub mod rate_limiting;
 
 pub use cache::{RedisCache, RedisCacheConfig, RedisClusterCache, RedisClusterConfig};
+pub use fleet::{
+    Dimension, DimensionStats, DistributionTarget, FleetConfig, FleetCoordinator, FleetDiversity,
+    IdentityCombination, MemoryReservationStore, RedisReservationStore, Reservation,
+    ReservationStore,
+};
 pub use rate_limiting::{
     RedisBackendError, RedisConfig, RedisQuotaEntry, RedisRateLimitBackend, RedisResult,
 };
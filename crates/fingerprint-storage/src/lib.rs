@@ -6,6 +6,7 @@
 //!
 //! 提供 LocalStorage/SessionStorage/IndexedDB 指纹识别功能
 
+use fingerprint_core::hashing::{self, FingerprintHasher, HashAlgorithm, HashDigest, HashUpgrade};
 use std::collections::HashMap;
 
 /// storefingerprint
@@ -120,12 +121,66 @@ impl StorageAnalyzer {
         })
     }
 
-    /// generatestorehash
+    /// generatestorehash (configured content hash backend)
     fn generate_storage_hash(
         localstorage: &HashMap<String, String>,
         sessionstorage: &HashMap<String, String>,
         indexeddb_dbs: &[String],
         cookies: &[CookieInfo],
+    ) -> String {
+        Self::storage_digest(
+            hashing::config().content,
+            localstorage,
+            sessionstorage,
+            indexeddb_dbs,
+            cookies,
+        )
+        .to_string()
+    }
+
+    fn storage_digest(
+        algorithm: HashAlgorithm,
+        localstorage: &HashMap<String, String>,
+        sessionstorage: &HashMap<String, String>,
+        indexeddb_dbs: &[String],
+        cookies: &[CookieInfo],
+    ) -> HashDigest {
+        let mut hasher = FingerprintHasher::new(algorithm);
+
+        // 键值对按键排序，保证 hash 与 HashMap 迭代顺序无关
+        for map in [localstorage, sessionstorage] {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort();
+            hasher.write_u64(entries.len() as u64);
+            for (k, v) in entries {
+                hasher.write_str(k);
+                hasher.write_str(v);
+            }
+        }
+
+        hasher.write_u64(indexeddb_dbs.len() as u64);
+        for db in indexeddb_dbs {
+            hasher.write_str(db);
+        }
+
+        hasher.write_u64(cookies.len() as u64);
+        for cookie in cookies {
+            hasher.write_str(&cookie.name);
+            hasher.write_str(&cookie.domain);
+        }
+
+        hasher.finish()
+    }
+
+    /// polynomial hash used by earlier releases
+    ///
+    /// depends on HashMap iteration order, so it only reproduces reliably for
+    /// maps with at most one entry or the same map instance it was computed from
+    fn legacy_storage_hash(
+        localstorage: &HashMap<String, String>,
+        sessionstorage: &HashMap<String, String>,
+        indexeddb_dbs: &[String],
+        cookies: &[CookieInfo],
     ) -> String {
         let mut hash_input = String::new();
 
@@ -155,6 +210,32 @@ impl StorageAnalyzer {
         format!("{:x}", hash_value)
     }
 
+    /// check a stored storage hash against `fingerprint` and return its replacement
+    pub fn migrate_hash(stored: &str, fingerprint: &StorageFingerprint) -> HashUpgrade {
+        let fp = fingerprint;
+        hashing::migrate_stored_hash(
+            stored,
+            hashing::config().content,
+            |algorithm| {
+                Self::storage_digest(
+                    algorithm,
+                    &fp.localstorage,
+                    &fp.sessionstorage,
+                    &fp.indexeddb_databases,
+                    &fp.cookies,
+                )
+            },
+            || {
+                Self::legacy_storage_hash(
+                    &fp.localstorage,
+                    &fp.sessionstorage,
+                    &fp.indexeddb_databases,
+                    &fp.cookies,
+                )
+            },
+        )
+    }
+
     /// detectstore更改
     pub fn detect_changes(
         before: &StorageFingerprint,
@@ -214,4 +295,35 @@ mod tests {
         assert!(changes.localstorage_changed);
         assert!(changes.hash_changed);
     }
+
+    #[test]
+    fn test_storage_hash_order_independent_and_migrates() {
+        let mut ls1 = HashMap::new();
+        let mut ls2 = HashMap::new();
+        for i in 0..16 {
+            ls1.insert(format!("k{}", i), "v".to_string());
+        }
+        for i in (0..16).rev() {
+            ls2.insert(format!("k{}", i), "v".to_string());
+        }
+        let fp1 = StorageAnalyzer::analyze(&ls1, &HashMap::new(), &[], &[]).unwrap();
+        let fp2 = StorageAnalyzer::analyze(&ls2, &HashMap::new(), &[], &[]).unwrap();
+        assert_eq!(fp1.storage_hash, fp2.storage_hash);
+        assert!(fp1.storage_hash.starts_with("blake3:"));
+
+        let legacy = StorageAnalyzer::legacy_storage_hash(
+            &fp1.localstorage,
+            &fp1.sessionstorage,
+            &fp1.indexeddb_databases,
+            &fp1.cookies,
+        );
+        match StorageAnalyzer::migrate_hash(&legacy, &fp1) {
+            HashUpgrade::Upgraded(digest) => assert_eq!(digest.to_string(), fp1.storage_hash),
+            other => panic!("expected upgrade, got {:?}", other),
+        }
+        assert_eq!(
+            StorageAnalyzer::migrate_hash(&fp1.storage_hash, &fp2),
+            HashUpgrade::Current
+        );
+    }
 }
@@ -6,7 +6,8 @@
 //!
 //! Provides timestamp consistency checking and timing side-channel protection
 
-use std::time::{Duration, SystemTime};
+use fingerprint_core::clock::{system_clock, SharedClock};
+use std::time::Duration;
 
 /// Timing fingerprint
 #[derive(Debug, Clone)]
@@ -61,28 +62,32 @@ impl std::error::Error for TimingError {}
 
 /// timinganalyzer
 pub struct TimingAnalyzer {
+    clock: SharedClock,
     last_timestamp: u64,
 }
 
 impl TimingAnalyzer {
     /// createnewanalyzer
     pub fn new() -> Self {
+        Self::with_clock(system_clock())
+    }
+
+    /// analyzer reading time from `clock`
+    pub fn with_clock(clock: SharedClock) -> Self {
         TimingAnalyzer {
-            last_timestamp: Self::current_timestamp(),
+            last_timestamp: clock.unix_millis(),
+            clock,
         }
     }
 
     /// get当前time戳
-    fn current_timestamp() -> u64 {
-        SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap_or(Duration::ZERO)
-            .as_millis() as u64
+    fn current_timestamp(&self) -> u64 {
+        self.clock.unix_millis()
     }
 
     /// analyzetiminginfo
     pub fn analyze(&mut self, high_res_time: f64) -> Result<TimingFingerprint, TimingError> {
-        let current = Self::current_timestamp();
+        let current = self.current_timestamp();
         let drift = (current as i64) - (self.last_timestamp as i64);
         self.last_timestamp = current;
 
@@ -196,6 +201,17 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_timing_analyzer_with_clock() {
+        let clock = std::sync::Arc::new(fingerprint_core::clock::FrozenClock::at_unix(1_000));
+        let mut analyzer = TimingAnalyzer::with_clock(clock.clone());
+        clock.advance(Duration::from_millis(250));
+        let result = analyzer.analyze(1000.5).unwrap();
+        assert_eq!(result.timestamp, 1_000_250);
+        assert_eq!(result.drift, 250);
+        assert_eq!(result.consistency_score, 0.8);
+    }
+
     #[test]
     fn test_timing_obfuscation() {
         let ts = 123456789u64;
@@ -9,3 +9,19 @@ pub mod tls_handshake;
 pub use tls_config::*;
 pub use tls_extensions::*;
 pub use tls_handshake::TLSHandshakeBuilder;
+
+/// Analyzers and backends compiled into this crate
+pub fn capabilities() -> Vec<fingerprint_core::Capability> {
+    use fingerprint_core::CapabilityKind::*;
+    fingerprint_core::CapabilitySet::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+        .builtin(Analyzer, "ja4-client-hello")
+        .builtin(Analyzer, "ja4x")
+        .builtin(Protocol, "tls-client-hello-builder")
+        .optional(
+            Backend,
+            "tls-key-generation",
+            "crypto",
+            cfg!(feature = "crypto"),
+        )
+        .into_vec()
+}
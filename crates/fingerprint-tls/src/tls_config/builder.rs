@@ -344,26 +344,16 @@ impl ClientHelloSpecBuilder {
 
     /// Build Chrome PSK (Pre-Shared Key) Session Resumption extensions
     /// For TLS 1.3 session resumption with PSK
+    ///
+    /// pre_shared_key goes last, after padding (RFC 8446 Section 4.2.11). The
+    /// identity and binder are placeholders of the right shape; a real resumption
+    /// replaces them with the cached ticket.
     pub fn chrome_psk_extensions() -> (
         Vec<Box<dyn TLSExtension>>,
         crate::tls_config::metadata::SpecMetadata,
     ) {
         let (mut extensions, metadata) = Self::chrome_133_extensions();
-
-        // Insert PSK and PSK Key Exchange Modes before the last GREASE extension
-        // Find the insertion point (before the last UtlsGREASEExtension)
-        let psk_ext = Box::new(PreSharedKeyExtension::for_session_resumption(
-            vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16],
-            vec![0x20; 32], // 32-byte binder (SHA-256)
-        ));
-
-        // Add PSK extension before padding
-        if !extensions.is_empty() {
-            extensions.insert(extensions.len() - 1, psk_ext);
-        } else {
-            extensions.push(psk_ext);
-        }
-
+        extensions.push(Self::placeholder_psk());
         (extensions, metadata)
     }
 
@@ -375,10 +365,8 @@ impl ClientHelloSpecBuilder {
     ) {
         let (mut extensions, metadata) = Self::chrome_133_extensions();
 
-        // Insert Early Data extension
+        // Add Early Data before padding
         let early_data = Box::new(EarlyDataExtension::standard());
-
-        // Add Early Data before PSK if PSK is present, otherwise before padding
         if !extensions.is_empty() {
             extensions.insert(extensions.len() - 1, early_data);
         } else {
@@ -394,23 +382,16 @@ impl ClientHelloSpecBuilder {
         Vec<Box<dyn TLSExtension>>,
         crate::tls_config::metadata::SpecMetadata,
     ) {
-        let (mut extensions, metadata) = Self::chrome_133_extensions();
+        let (mut extensions, metadata) = Self::chrome_0rtt_extensions();
+        extensions.push(Self::placeholder_psk());
+        (extensions, metadata)
+    }
 
-        // Insert both Early Data and PSK
-        let early_data = Box::new(EarlyDataExtension::standard());
-        let psk_ext = Box::new(PreSharedKeyExtension::for_session_resumption(
+    /// Ticket-sized identity with a SHA-256 binder
+    fn placeholder_psk() -> Box<dyn TLSExtension> {
+        Box::new(PreSharedKeyExtension::for_session_resumption(
             vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16],
-            vec![0x20; 32],
-        ));
-
-        // Add both before padding (last 1-2 extensions)
-        if !extensions.is_empty() {
-            extensions.insert(extensions.len() - 1, psk_ext);
-        }
-        if !extensions.is_empty() {
-            extensions.insert(extensions.len() - 1, early_data);
-        }
-
-        (extensions, metadata)
+            vec![0x20; 32], // 32-byte binder (SHA-256)
+        ))
     }
 }
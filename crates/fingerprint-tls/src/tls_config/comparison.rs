@@ -3,9 +3,10 @@
 //! providefingerprintcompare and matchFeatures
 //! reference：Huginn Net fingerprintcompareimplement
 
-use crate::tls_config::extract::extract_signature;
+use crate::tls_config::extract::{extract_signature, extract_wire_signature};
 use crate::tls_config::signature::ClientHelloSignature;
 use crate::tls_config::spec::ClientHelloSpec;
+use fingerprint_core::similarity::{BootstrapConfig, SimilarityEstimate};
 
 /// fingerprintmatchresult
 #[derive(Debug, Clone, PartialEq)]
@@ -66,6 +67,46 @@ pub fn compare_signatures(
     FingerprintMatch::None
 }
 
+/// similarity of two ClientHelloSpec with a confidence interval
+///
+/// The point value is `1 - distance` of the signatures; the interval comes
+/// from bootstrapping the per-field scores, so downstream thresholds can
+/// require e.g. `ci_low >= 0.8` rather than trusting a single number.
+///
+/// # Examples
+/// ```
+/// use fingerprint_core::similarity::BootstrapConfig;
+/// use fingerprint_tls::tls_config::{compare_specs_with_confidence, ClientHelloSpec};
+/// let spec1 = ClientHelloSpec::chrome_133();
+/// let spec2 = ClientHelloSpec::firefox_133();
+/// let estimate = compare_specs_with_confidence(&spec1, &spec2, &BootstrapConfig::default());
+/// assert!(estimate.ci_low <= estimate.mean && estimate.mean <= estimate.ci_high);
+/// ```
+pub fn compare_specs_with_confidence(
+    spec1: &ClientHelloSpec,
+    spec2: &ClientHelloSpec,
+    config: &BootstrapConfig,
+) -> SimilarityEstimate {
+    let sig1 = extract_signature(spec1);
+    let sig2 = extract_signature(spec2);
+
+    compare_signatures_with_confidence(&sig1, &sig2, config)
+}
+
+/// similarity of two signatures with a confidence interval
+///
+/// Identical canonical forms give an exact 1.
+pub fn compare_signatures_with_confidence(
+    sig1: &ClientHelloSignature,
+    sig2: &ClientHelloSignature,
+    config: &BootstrapConfig,
+) -> SimilarityEstimate {
+    if sig1.canonical() == sig2.canonical() {
+        return SimilarityEstimate::exact(1.0);
+    }
+    config.estimate(&sig1.feature_scores(sig2))
+}
+
 /// find and 给fixedsignaturemostsimilarfingerprintconfiguration
 ///
 /// # Parameters
@@ -88,6 +129,7 @@ pub fn find_best_match(
         let score = match match_result {
             FingerprintMatch::Exact => 100,
             FingerprintMatch::Similar => 50,
+            FingerprintMatch::None if matches_on_wire(signature, spec) => 50,
             FingerprintMatch::None => 0,
         };
 
@@ -100,6 +142,18 @@ pub fn find_best_match(
     best_index
 }
 
+/// whether a signature parsed from traffic is what `spec` sends for the same host
+///
+/// Specs rarely carry the metadata a captured ClientHello has (curves, signature
+/// algorithms, SNI), so captured signatures are compared with the spec's wire form;
+/// extension order is ignored because Chrome permutes it per connection.
+fn matches_on_wire(signature: &ClientHelloSignature, spec: &ClientHelloSpec) -> bool {
+    let server_name = signature.sni.as_deref().unwrap_or_default();
+    extract_wire_signature(spec, server_name)
+        .map(|wire| wire.canonical() == signature.canonical())
+        .unwrap_or(false)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,4 +182,19 @@ mod tests {
         let best = find_best_match(&signature, &specs);
         assert_eq!(best, Some(1)); // chrome_133 should is 最matchof
     }
+
+    #[test]
+    fn test_compare_with_confidence() {
+        let config = BootstrapConfig::default();
+        let chrome = ClientHelloSpec::chrome_133();
+        let same = compare_specs_with_confidence(&chrome, &ClientHelloSpec::chrome_133(), &config);
+        assert_eq!(same, SimilarityEstimate::exact(1.0));
+
+        let sig1 = extract_signature(&chrome);
+        let sig2 = extract_signature(&ClientHelloSpec::firefox_133());
+        let estimate = compare_signatures_with_confidence(&sig1, &sig2, &config);
+        assert!(estimate.ci_low <= estimate.mean && estimate.mean <= estimate.ci_high);
+        assert!((estimate.mean - (1.0 - sig1.distance(&sig2))).abs() < 0.1);
+        assert!(estimate.width() > 0.0);
+    }
 }
@@ -4,9 +4,11 @@
 //!
 //! reference：Huginn Net Signature Extractimplement
 
+use crate::tls_config::parser::ClientHelloParser;
 use crate::tls_config::signature::ClientHelloSignature;
 use crate::tls_config::spec::ClientHelloSpec;
 use crate::tls_config::version::TlsVersion;
+use crate::tls_handshake::TLSHandshakeBuilder;
 
 /// from ClientHelloSpec in Extractsignatureinfo
 ///
@@ -87,3 +89,17 @@ pub fn extract_signature(spec: &ClientHelloSpec) -> ClientHelloSignature {
 
     signature
 }
+
+/// Signature of the ClientHello a spec actually puts on the wire
+///
+/// Unlike [`extract_signature`], which only sees what the spec's metadata records,
+/// this builds the ClientHello for `server_name` and parses it back, so every field
+/// (curves, signature algorithms, the SNI the builder adds) is present and it is
+/// comparable with signatures parsed from captured traffic.
+pub fn extract_wire_signature(
+    spec: &ClientHelloSpec,
+    server_name: &str,
+) -> Result<ClientHelloSignature, String> {
+    let record = TLSHandshakeBuilder::build_client_hello(spec, server_name)?;
+    ClientHelloParser::parse(&record)
+}
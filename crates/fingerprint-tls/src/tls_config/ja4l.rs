@@ -0,0 +1,145 @@
+//! JA4L latency fingerprint module
+//!
+//! Measures one-way latency and TTL at the start of a connection, from which
+//! the physical distance to the peer and its hop count can be estimated. A
+//! client claiming a nearby residential IP but answering from thousands of
+//! kilometres away (a proxy or relay) stands out.
+//!
+//! - JA4L-C (client): half the time from the server's SYN-ACK to the client's
+//!   ACK, with the client's TTL
+//! - JA4L-S (server): half the time from the client's SYN to the server's
+//!   SYN-ACK, with the server's TTL
+//!
+//! For QUIC, pass the matching Initial/Handshake packet times instead.
+//!
+//! format: {latency_us}_{ttl}
+
+use std::fmt;
+use std::time::Duration;
+
+/// Miles travelled by light per microsecond
+const LIGHT_MILES_PER_US: f64 = 0.128;
+
+/// Kilometres per mile
+const KM_PER_MILE: f64 = 1.609_344;
+
+/// Propagation delay factor for a typical route (1.5 good, 2.0 poor terrain)
+pub const DEFAULT_PROPAGATION_FACTOR: f64 = 1.6;
+
+/// Connection side a JA4L measurement describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ja4lSide {
+    /// Client latency (ja4l_c)
+    Client,
+    /// Server latency (ja4l_s)
+    Server,
+}
+
+/// JA4L latency measurement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ja4lMeasurement {
+    /// Side of the connection measured
+    pub side: Ja4lSide,
+    /// One-way latency in microseconds
+    pub latency_us: u64,
+    /// Observed IP TTL (hop limit)
+    pub ttl: u8,
+}
+
+impl Ja4lMeasurement {
+    /// Measurement from two packet timestamps on the same clock
+    ///
+    /// `first` is the packet that starts the round trip and `second` the
+    /// reply; the one-way latency is half the gap.
+    pub fn new(side: Ja4lSide, first: Duration, second: Duration, ttl: u8) -> Self {
+        let round_trip = second.saturating_sub(first);
+        Self {
+            side,
+            latency_us: (round_trip.as_micros() / 2).min(u64::MAX as u128) as u64,
+            ttl,
+        }
+    }
+
+    /// Client latency from the server's SYN-ACK and the client's ACK
+    pub fn client(syn_ack: Duration, ack: Duration, ttl: u8) -> Self {
+        Self::new(Ja4lSide::Client, syn_ack, ack, ttl)
+    }
+
+    /// Server latency from the client's SYN and the server's SYN-ACK
+    pub fn server(syn: Duration, syn_ack: Duration, ttl: u8) -> Self {
+        Self::new(Ja4lSide::Server, syn, syn_ack, ttl)
+    }
+
+    /// Variant name ("ja4l_c" or "ja4l_s")
+    pub fn variant_name(&self) -> &'static str {
+        match self.side {
+            Ja4lSide::Client => "ja4l_c",
+            Ja4lSide::Server => "ja4l_s",
+        }
+    }
+
+    /// JA4L fingerprint string
+    pub fn fingerprint(&self) -> String {
+        format!("{}_{}", self.latency_us, self.ttl)
+    }
+
+    /// Estimated distance to the peer in miles
+    ///
+    /// `propagation_factor` accounts for routing overhead; see
+    /// [`DEFAULT_PROPAGATION_FACTOR`].
+    pub fn distance_miles(&self, propagation_factor: f64) -> f64 {
+        self.latency_us as f64 * LIGHT_MILES_PER_US / propagation_factor
+    }
+
+    /// Estimated distance to the peer in kilometres
+    pub fn distance_km(&self, propagation_factor: f64) -> f64 {
+        self.distance_miles(propagation_factor) * KM_PER_MILE
+    }
+
+    /// Initial TTL the peer most likely used (64, 128 or 255)
+    pub fn initial_ttl(&self) -> u8 {
+        match self.ttl {
+            0..=64 => 64,
+            65..=128 => 128,
+            _ => 255,
+        }
+    }
+
+    /// Estimated number of hops to the peer
+    pub fn hop_count(&self) -> u8 {
+        self.initial_ttl() - self.ttl
+    }
+}
+
+impl fmt::Display for Ja4lMeasurement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.fingerprint())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ja4l_client() {
+        let ja4l = Ja4lMeasurement::client(
+            Duration::from_micros(1_000),
+            Duration::from_micros(21_000),
+            118,
+        );
+        assert_eq!(ja4l.fingerprint(), "10000_118");
+        assert_eq!(ja4l.variant_name(), "ja4l_c");
+        assert_eq!(ja4l.initial_ttl(), 128);
+        assert_eq!(ja4l.hop_count(), 10);
+        assert_eq!(ja4l.distance_miles(1.6), 800.0);
+        assert!((ja4l.distance_km(1.6) - 1287.475).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_ja4l_server_clock_skew() {
+        let ja4l = Ja4lMeasurement::server(Duration::from_millis(5), Duration::from_millis(4), 52);
+        assert_eq!(ja4l.to_string(), "0_52");
+        assert_eq!(ja4l.hop_count(), 12);
+    }
+}
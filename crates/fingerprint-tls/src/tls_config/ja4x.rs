@@ -0,0 +1,340 @@
+//! JA4X certificate fingerprint module
+//!
+//! Fingerprints how an X.509 certificate was generated rather than what it
+//! contains: the issuer and subject RDN attribute types and the extension
+//! OIDs, each in certificate order. Certificates minted by the same tooling
+//! share a JA4X even when names and keys differ.
+//!
+//! format: {issuer_hash}_{subject_hash}_{extension_hash}, each the first 12
+//! hex characters of the SHA256 of the comma-joined hex OIDs.
+
+use crate::tls_config::ja4::hash12;
+
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_OID: u8 = 0x06;
+const TAG_VERSION: u8 = 0xa0;
+const TAG_EXTENSIONS: u8 = 0xa3;
+
+/// Hash used for an empty OID list
+const EMPTY_HASH: &str = "000000000000";
+
+/// JA4X payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ja4xPayload {
+    /// JA4X_a: issuer RDN hash
+    pub ja4x_a: String,
+    /// JA4X_b: subject RDN hash
+    pub ja4x_b: String,
+    /// JA4X_c: extension hash
+    pub ja4x_c: String,
+    /// JA4X fingerprint (ja4x)
+    pub fingerprint: String,
+    /// JA4X raw fingerprint with the hex OIDs (ja4x_r)
+    pub raw: String,
+}
+
+/// OIDs of an X.509 certificate (for JA4X generation)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ja4xSignature {
+    /// Issuer RDN attribute types, hex-encoded, in certificate order
+    pub issuer_oids: Vec<String>,
+    /// Subject RDN attribute types, hex-encoded, in certificate order
+    pub subject_oids: Vec<String>,
+    /// Extension OIDs, hex-encoded, in certificate order
+    pub extension_oids: Vec<String>,
+}
+
+impl Ja4xSignature {
+    /// Extract the OIDs from a DER-encoded certificate
+    pub fn from_der(der: &[u8]) -> Result<Self, String> {
+        let TbsNames {
+            issuer,
+            subject,
+            mut rest,
+        } = tbs_names(der)?;
+        next_field(&mut rest, "subjectPublicKeyInfo")?;
+
+        let mut extension_oids = Vec::new();
+        // issuerUniqueID [1] and subjectUniqueID [2] may precede extensions [3]
+        while !rest.is_empty() {
+            let (tag, value) = next_field(&mut rest, "extensions")?;
+            if tag == TAG_EXTENSIONS {
+                let (tag, extensions, _) = read_tlv(value)?;
+                expect_tag(tag, TAG_SEQUENCE, "extensions")?;
+                extension_oids = extension_oid_list(extensions)?;
+                break;
+            }
+        }
+
+        Ok(Self {
+            issuer_oids: rdn_oid_list(issuer)?,
+            subject_oids: rdn_oid_list(subject)?,
+            extension_oids,
+        })
+    }
+
+    /// Generate the JA4X fingerprint
+    pub fn generate(&self) -> Ja4xPayload {
+        let issuer = self.issuer_oids.join(",");
+        let subject = self.subject_oids.join(",");
+        let extensions = self.extension_oids.join(",");
+
+        let ja4x_a = hash_or_empty(&issuer);
+        let ja4x_b = hash_or_empty(&subject);
+        let ja4x_c = hash_or_empty(&extensions);
+
+        Ja4xPayload {
+            fingerprint: format!("{ja4x_a}_{ja4x_b}_{ja4x_c}"),
+            raw: format!("{issuer}_{subject}_{extensions}"),
+            ja4x_a,
+            ja4x_b,
+            ja4x_c,
+        }
+    }
+}
+
+/// Issuer of a DER-encoded certificate as `C=US, O=Example, CN=Example CA`
+///
+/// Only the common attributes (C, ST, L, O, OU, CN) with string values are
+/// kept, in certificate order.
+pub fn certificate_issuer(der: &[u8]) -> Result<String, String> {
+    let mut name = tbs_names(der)?.issuer;
+    let mut parts = Vec::new();
+    while !name.is_empty() {
+        let (tag, mut rdn, rest) = read_tlv(name)?;
+        expect_tag(tag, TAG_SET, "RelativeDistinguishedName")?;
+        while !rdn.is_empty() {
+            let (tag, attribute, tail) = read_tlv(rdn)?;
+            expect_tag(tag, TAG_SEQUENCE, "AttributeTypeAndValue")?;
+            let (tag, oid, value) = read_tlv(attribute)?;
+            expect_tag(tag, TAG_OID, "OBJECT IDENTIFIER")?;
+            let (tag, value, _) = read_tlv(value)?;
+            let key = match oid {
+                [0x55, 0x04, 0x06] => Some("C"),
+                [0x55, 0x04, 0x08] => Some("ST"),
+                [0x55, 0x04, 0x07] => Some("L"),
+                [0x55, 0x04, 0x0a] => Some("O"),
+                [0x55, 0x04, 0x0b] => Some("OU"),
+                [0x55, 0x04, 0x03] => Some("CN"),
+                _ => None,
+            };
+            // UTF8String, PrintableString, T61String, IA5String
+            if let (Some(key), 0x0c | 0x13 | 0x14 | 0x16) = (key, tag) {
+                parts.push(format!("{}={}", key, String::from_utf8_lossy(value)));
+            }
+            rdn = tail;
+        }
+        name = rest;
+    }
+    Ok(parts.join(", "))
+}
+
+/// Issuer and subject Names of a certificate
+struct TbsNames<'a> {
+    issuer: &'a [u8],
+    subject: &'a [u8],
+    /// TBSCertificate fields after the subject
+    rest: &'a [u8],
+}
+
+fn tbs_names(der: &[u8]) -> Result<TbsNames<'_>, String> {
+    let (tag, certificate, _) = read_tlv(der)?;
+    expect_tag(tag, TAG_SEQUENCE, "Certificate")?;
+    let (tag, tbs, _) = read_tlv(certificate)?;
+    expect_tag(tag, TAG_SEQUENCE, "TBSCertificate")?;
+
+    let mut rest = tbs;
+
+    // version is optional (v1 certificates omit it)
+    let (mut tag, _) = next_field(&mut rest, "serialNumber")?;
+    if tag == TAG_VERSION {
+        tag = next_field(&mut rest, "serialNumber")?.0;
+    }
+    expect_tag(tag, 0x02, "serialNumber")?;
+    next_field(&mut rest, "signature")?;
+    let (tag, issuer) = next_field(&mut rest, "issuer")?;
+    expect_tag(tag, TAG_SEQUENCE, "issuer")?;
+    next_field(&mut rest, "validity")?;
+    let (tag, subject) = next_field(&mut rest, "subject")?;
+    expect_tag(tag, TAG_SEQUENCE, "subject")?;
+    Ok(TbsNames {
+        issuer,
+        subject,
+        rest,
+    })
+}
+
+/// Next TBSCertificate field, advancing `rest` past it
+fn next_field<'a>(rest: &mut &'a [u8], name: &str) -> Result<(u8, &'a [u8]), String> {
+    if rest.is_empty() {
+        return Err(format!("TBSCertificate ends before {}", name));
+    }
+    let (tag, value, tail) = read_tlv(rest)?;
+    *rest = tail;
+    Ok((tag, value))
+}
+
+fn hash_or_empty(input: &str) -> String {
+    if input.is_empty() {
+        EMPTY_HASH.to_string()
+    } else {
+        hash12(input)
+    }
+}
+
+/// Attribute types of a Name (SEQUENCE OF SET OF AttributeTypeAndValue)
+fn rdn_oid_list(mut name: &[u8]) -> Result<Vec<String>, String> {
+    let mut oids = Vec::new();
+    while !name.is_empty() {
+        let (tag, mut rdn, rest) = read_tlv(name)?;
+        expect_tag(tag, TAG_SET, "RelativeDistinguishedName")?;
+        while !rdn.is_empty() {
+            let (tag, attribute, tail) = read_tlv(rdn)?;
+            expect_tag(tag, TAG_SEQUENCE, "AttributeTypeAndValue")?;
+            oids.push(read_oid(attribute)?);
+            rdn = tail;
+        }
+        name = rest;
+    }
+    Ok(oids)
+}
+
+/// extnID of every Extension (SEQUENCE OF Extension)
+fn extension_oid_list(mut extensions: &[u8]) -> Result<Vec<String>, String> {
+    let mut oids = Vec::new();
+    while !extensions.is_empty() {
+        let (tag, extension, rest) = read_tlv(extensions)?;
+        expect_tag(tag, TAG_SEQUENCE, "Extension")?;
+        oids.push(read_oid(extension)?);
+        extensions = rest;
+    }
+    Ok(oids)
+}
+
+/// Leading OID of `data`, hex-encoded
+fn read_oid(data: &[u8]) -> Result<String, String> {
+    let (tag, oid, _) = read_tlv(data)?;
+    expect_tag(tag, TAG_OID, "OBJECT IDENTIFIER")?;
+    Ok(oid.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+fn expect_tag(tag: u8, expected: u8, what: &str) -> Result<(), String> {
+    if tag == expected {
+        Ok(())
+    } else {
+        Err(format!(
+            "expected {} (tag 0x{:02x}), found tag 0x{:02x}",
+            what, expected, tag
+        ))
+    }
+}
+
+/// Split one DER TLV off the front of `data`: (tag, value, rest)
+fn read_tlv(data: &[u8]) -> Result<(u8, &[u8], &[u8]), String> {
+    if data.len() < 2 {
+        return Err("DER data too short".to_string());
+    }
+    let tag = data[0];
+    let (len, header) = match data[1] {
+        short if short < 0x80 => (short as usize, 2),
+        0x80 => return Err("indefinite DER length".to_string()),
+        long => {
+            let count = (long & 0x7f) as usize;
+            if count > 4 || data.len() < 2 + count {
+                return Err("invalid DER length".to_string());
+            }
+            let len = data[2..2 + count]
+                .iter()
+                .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+            (len, 2 + count)
+        }
+    };
+    let end = header
+        .checked_add(len)
+        .filter(|&end| end <= data.len())
+        .ok_or_else(|| format!("DER value needs {} bytes, got {}", len, data.len() - header))?;
+    Ok((tag, &data[header..end], &data[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if value.len() < 0x80 {
+            out.push(value.len() as u8);
+        } else {
+            out.extend([0x82, (value.len() >> 8) as u8, value.len() as u8]);
+        }
+        out.extend_from_slice(value);
+        out
+    }
+
+    fn name(oids: &[&[u8]]) -> Vec<u8> {
+        let rdns: Vec<u8> = oids
+            .iter()
+            .flat_map(|oid| {
+                let attribute = [tlv(TAG_OID, oid), tlv(0x0c, b"x")].concat();
+                tlv(TAG_SET, &tlv(TAG_SEQUENCE, &attribute))
+            })
+            .collect();
+        tlv(TAG_SEQUENCE, &rdns)
+    }
+
+    fn certificate(extensions: &[&[u8]]) -> Vec<u8> {
+        const COUNTRY: &[u8] = &[0x55, 0x04, 0x06];
+        const ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0a];
+        const COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+
+        let extensions: Vec<u8> = extensions
+            .iter()
+            .flat_map(|oid| tlv(TAG_SEQUENCE, &[tlv(TAG_OID, oid), tlv(0x04, &[])].concat()))
+            .collect();
+        let tbs = [
+            tlv(TAG_VERSION, &tlv(0x02, &[2])),
+            tlv(0x02, &[1]),
+            tlv(TAG_SEQUENCE, &[]),
+            name(&[COUNTRY, ORGANIZATION, COMMON_NAME]),
+            tlv(TAG_SEQUENCE, &[]),
+            name(&[COMMON_NAME]),
+            tlv(TAG_SEQUENCE, &[0u8; 200]),
+            tlv(TAG_EXTENSIONS, &tlv(TAG_SEQUENCE, &extensions)),
+        ]
+        .concat();
+        tlv(
+            TAG_SEQUENCE,
+            &[tlv(TAG_SEQUENCE, &tbs), tlv(TAG_SEQUENCE, &[])].concat(),
+        )
+    }
+
+    #[test]
+    fn test_ja4x_from_der() {
+        let der = certificate(&[&[0x55, 0x1d, 0x0f], &[0x55, 0x1d, 0x11]]);
+        let signature = Ja4xSignature::from_der(&der).unwrap();
+        assert_eq!(signature.issuer_oids, ["550406", "55040a", "550403"]);
+        assert_eq!(signature.subject_oids, ["550403"]);
+
+        let ja4x = signature.generate();
+        assert_eq!(ja4x.raw, "550406,55040a,550403_550403_551d0f,551d11");
+        assert_eq!(ja4x.ja4x_b, hash12("550403"));
+        assert_eq!(
+            ja4x.fingerprint,
+            format!("{}_{}_{}", ja4x.ja4x_a, ja4x.ja4x_b, ja4x.ja4x_c)
+        );
+    }
+
+    #[test]
+    fn test_ja4x_without_extensions() {
+        let ja4x = Ja4xSignature::from_der(&certificate(&[]))
+            .unwrap()
+            .generate();
+        assert_eq!(ja4x.ja4x_c, EMPTY_HASH);
+        assert_eq!(
+            certificate_issuer(&certificate(&[])).unwrap(),
+            "C=x, O=x, CN=x"
+        );
+        assert!(Ja4xSignature::from_der(&[0x30, 0x05, 0x30]).is_err());
+    }
+}
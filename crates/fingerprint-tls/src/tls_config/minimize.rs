@@ -0,0 +1,561 @@
+//! Profile-guided ClientHello minimization
+//!
+//! Constrained clients (embedded stacks, small MTUs, hand-written TLS) often only
+//! need to look like a profile to the detectors that actually run against them,
+//! which today usually means JA4. [`SpecMinimizer`] starts from a profile spec and
+//! greedily applies size reductions (drop GREASE, trim ALPN/ALPS/cert compression,
+//! keep a single key share, disable padding, and drop whole extensions or ciphers
+//! when the chosen components allow it), keeping each reduction only if the
+//! [`Ja4Component`]s that must match are unchanged on the wire.
+//!
+//! The report lists which other detectors (JA3, raw JA4, ordered signature, record
+//! length) would still tell the minimized ClientHello apart from the profile.
+//!
+//! ```rust,no_run
+//! use fingerprint_tls::tls_config::{ClientHelloSpec, Ja4Component, SpecMinimizer};
+//!
+//! let minimized = SpecMinimizer::new(ClientHelloSpec::chrome_133)
+//!     .with_components(&[Ja4Component::Full])
+//!     .minimize()
+//!     .unwrap();
+//! println!(
+//!     "{} -> {} bytes, still distinguishable by {:?}",
+//!     minimized.original_length,
+//!     minimized.minimized_length,
+//!     minimized.warnings.iter().map(|w| &w.detector).collect::<Vec<_>>()
+//! );
+//! ```
+//!
+//! Like [`MutationHarness`](super::MutationHarness), the minimizer takes a factory
+//! because specs cannot be cloned; candidates are replayed on a fresh spec.
+
+use crate::tls_config::comparison::{compare_signatures, FingerprintMatch};
+use crate::tls_config::extract::extract_wire_signature;
+use crate::tls_config::grease::is_grease_value;
+use crate::tls_config::ja4::{Ja4Payload, Ja4Signature};
+use crate::tls_config::signature::ClientHelloSignature;
+use crate::tls_config::spec::{ClientHelloSpec, VERSION_TLS13};
+use crate::tls_extensions::{
+    ALPNExtension, ApplicationSettingsExtensionNew, KeyShareExtension, SupportedCurvesExtension,
+    SupportedVersionsExtension, TLSExtension, UtlsCompressCertExtension, UtlsPaddingExtension,
+};
+use crate::tls_handshake::TLSHandshakeBuilder;
+use fingerprint_core::dicttls::extensions::{
+    EXT_TYPE_APPLICATION_SETTINGS, EXT_TYPE_APPLICATION_SETTINGS_NEW, EXT_TYPE_EARLY_DATA,
+    EXT_TYPE_KEY_SHARE, EXT_TYPE_PRE_SHARED_KEY, EXT_TYPE_PSK_KEY_EXCHANGE_MODES,
+    EXT_TYPE_SERVER_NAME, EXT_TYPE_SIGNATURE_ALGORITHMS, EXT_TYPE_SUPPORTED_GROUPS,
+    EXT_TYPE_SUPPORTED_VERSIONS,
+};
+use fingerprint_core::ja3::JA3;
+use std::fmt;
+
+/// extensions never dropped: needed for a TLS 1.3 handshake, or (PSK/early data)
+/// only valid together
+const REQUIRED_EXTENSIONS: &[u16] = &[
+    EXT_TYPE_SERVER_NAME,
+    EXT_TYPE_SUPPORTED_GROUPS,
+    EXT_TYPE_SIGNATURE_ALGORITHMS,
+    EXT_TYPE_SUPPORTED_VERSIONS,
+    EXT_TYPE_PSK_KEY_EXCHANGE_MODES,
+    EXT_TYPE_KEY_SHARE,
+    EXT_TYPE_PRE_SHARED_KEY,
+    EXT_TYPE_EARLY_DATA,
+];
+
+/// TLS 1.3 cipher suites, kept so the minimized spec can still negotiate 1.3
+const TLS13_CIPHERS: std::ops::RangeInclusive<u16> = 0x1301..=0x1305;
+
+/// part of JA4 that must survive minimization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ja4Component {
+    /// the whole fingerprint
+    Full,
+    /// JA4_a: transport, version, SNI, cipher/extension counts, ALPN
+    A,
+    /// JA4_b: sorted cipher hash
+    B,
+    /// JA4_c: sorted extension + signature algorithm hash
+    C,
+}
+
+impl Ja4Component {
+    fn of<'a>(&self, payload: &'a Ja4Payload) -> &'a str {
+        match self {
+            Ja4Component::Full => payload.full.value(),
+            Ja4Component::A => &payload.ja4_a,
+            Ja4Component::B => &payload.ja4_b,
+            Ja4Component::C => &payload.ja4_c,
+        }
+    }
+}
+
+/// single size reduction tried by the minimizer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reduction {
+    /// remove GREASE cipher suites
+    DropGreaseCiphers,
+    /// remove GREASE extensions
+    DropGreaseExtensions,
+    /// remove GREASE entries from supported_versions
+    DropGreaseVersions,
+    /// disable the padding extension
+    DisablePadding,
+    /// keep only the first ALPN protocol
+    TrimAlpn,
+    /// keep only the first ALPS protocol
+    TrimAlps,
+    /// keep only the first certificate compression algorithm
+    TrimCertCompression,
+    /// keep only the smallest non-GREASE key share
+    SingleKeyShare,
+    /// offer only the groups that have a key share
+    TrimSupportedGroups,
+    /// remove every extension with this ID
+    DropExtension(u16),
+    /// remove cipher suite
+    DropCipher(u16),
+}
+
+impl Reduction {
+    /// apply reduction in place; returns false when it does not change anything
+    pub fn apply(&self, spec: &mut ClientHelloSpec) -> bool {
+        match *self {
+            Reduction::DropGreaseCiphers => {
+                let before = spec.cipher_suites.len();
+                spec.cipher_suites.retain(|c| !is_grease_value(*c));
+                spec.cipher_suites.len() != before
+            }
+            Reduction::DropGreaseExtensions => {
+                let before = spec.extensions.len();
+                spec.extensions
+                    .retain(|ext| !is_grease_value(ext.extension_id()));
+                spec.extensions.len() != before
+            }
+            Reduction::DropGreaseVersions => {
+                map_extension(spec, |ext: &mut SupportedVersionsExtension| {
+                    retain_changed(&mut ext.versions, |v| !is_grease_value(*v))
+                })
+            }
+            Reduction::DisablePadding => map_extension(spec, |ext: &mut UtlsPaddingExtension| {
+                let changed = ext.will_pad || ext.padding_len > 0;
+                ext.will_pad = false;
+                ext.padding_len = 0;
+                changed
+            }),
+            Reduction::TrimAlpn => {
+                let changed = map_extension(spec, |ext: &mut ALPNExtension| {
+                    truncate_changed(&mut ext.alpn_protocols)
+                });
+                if changed {
+                    if let Some(metadata) = &mut spec.metadata {
+                        if let Some(first) = metadata.get_first_alpn() {
+                            metadata.set_alpn(vec![first]);
+                        }
+                    }
+                }
+                changed
+            }
+            Reduction::TrimAlps => {
+                map_extension(spec, |ext: &mut ApplicationSettingsExtensionNew| {
+                    truncate_changed(&mut ext.supported_protocols)
+                })
+            }
+            Reduction::TrimCertCompression => {
+                map_extension(spec, |ext: &mut UtlsCompressCertExtension| {
+                    truncate_changed(&mut ext.algorithms)
+                })
+            }
+            Reduction::SingleKeyShare => map_extension(spec, |ext: &mut KeyShareExtension| {
+                let Some(keep) = ext
+                    .key_shares
+                    .iter()
+                    .filter(|share| !is_grease_value(share.group))
+                    .min_by_key(|share| share.data.len())
+                    .cloned()
+                else {
+                    return false;
+                };
+                let changed = ext.key_shares.len() != 1;
+                ext.key_shares = vec![keep];
+                changed
+            }),
+            Reduction::TrimSupportedGroups => {
+                let shared: Vec<u16> = spec
+                    .extensions
+                    .iter()
+                    .filter_map(|ext| ext.as_any().downcast_ref::<KeyShareExtension>())
+                    .flat_map(|ext| ext.key_shares.iter().map(|share| share.group))
+                    .filter(|group| !is_grease_value(*group))
+                    .collect();
+                if shared.is_empty() {
+                    return false;
+                }
+                let changed = map_extension(spec, |ext: &mut SupportedCurvesExtension| {
+                    retain_changed(&mut ext.curves, |c| shared.contains(c))
+                });
+                if changed {
+                    if let Some(metadata) = &mut spec.metadata {
+                        metadata.set_elliptic_curves(shared);
+                    }
+                }
+                changed
+            }
+            Reduction::DropExtension(id) => {
+                let before = spec.extensions.len();
+                spec.extensions.retain(|ext| ext.extension_id() != id);
+                if let Some(metadata) = &mut spec.metadata {
+                    metadata.extension_metadata.remove(&id);
+                }
+                spec.extensions.len() != before
+            }
+            Reduction::DropCipher(id) => {
+                let before = spec.cipher_suites.len();
+                spec.cipher_suites.retain(|c| *c != id);
+                spec.cipher_suites.len() != before
+            }
+        }
+    }
+}
+
+impl fmt::Display for Reduction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reduction::DropGreaseCiphers => write!(f, "drop-grease-ciphers"),
+            Reduction::DropGreaseExtensions => write!(f, "drop-grease-extensions"),
+            Reduction::DropGreaseVersions => write!(f, "drop-grease-versions"),
+            Reduction::DisablePadding => write!(f, "disable-padding"),
+            Reduction::TrimAlpn => write!(f, "trim-alpn"),
+            Reduction::TrimAlps => write!(f, "trim-alps"),
+            Reduction::TrimCertCompression => write!(f, "trim-cert-compression"),
+            Reduction::SingleKeyShare => write!(f, "single-key-share"),
+            Reduction::TrimSupportedGroups => write!(f, "trim-supported-groups"),
+            Reduction::DropExtension(id) => write!(f, "drop-ext-{:#06x}", id),
+            Reduction::DropCipher(id) => write!(f, "drop-cipher-{:#06x}", id),
+        }
+    }
+}
+
+/// replace the first extension of type `T` with a modified copy
+fn map_extension<T, F>(spec: &mut ClientHelloSpec, modify: F) -> bool
+where
+    T: TLSExtension + Clone,
+    F: FnOnce(&mut T) -> bool,
+{
+    for slot in spec.extensions.iter_mut() {
+        if let Some(ext) = slot.as_any().downcast_ref::<T>() {
+            let mut ext = ext.clone();
+            if !modify(&mut ext) {
+                return false;
+            }
+            *slot = Box::new(ext);
+            return true;
+        }
+    }
+    false
+}
+
+fn retain_changed<T>(values: &mut Vec<T>, keep: impl FnMut(&T) -> bool) -> bool {
+    let before = values.len();
+    values.retain(keep);
+    values.len() != before
+}
+
+fn truncate_changed<T>(values: &mut Vec<T>) -> bool {
+    let before = values.len();
+    values.truncate(1);
+    values.len() != before
+}
+
+fn ja4_signature(signature: &ClientHelloSignature) -> Ja4Signature {
+    Ja4Signature {
+        version: signature.version,
+        cipher_suites: signature.cipher_suites.clone(),
+        extensions: signature.extensions.clone(),
+        signature_algorithms: signature.signature_algorithms.clone(),
+        sni: signature.sni.clone(),
+        alpn: signature.alpn.clone(),
+    }
+}
+
+/// JA4 as a server computes it from the parsed ClientHello
+pub fn wire_ja4(signature: &ClientHelloSignature) -> Ja4Payload {
+    ja4_signature(signature).generate_ja4()
+}
+
+/// what a built spec looks like on the wire
+struct WireView {
+    length: usize,
+    signature: ClientHelloSignature,
+    ja4: Ja4Payload,
+}
+
+impl WireView {
+    fn of(spec: &ClientHelloSpec, server_name: &str) -> Result<Self, String> {
+        let length = TLSHandshakeBuilder::build_client_hello(spec, server_name)?.len();
+        let signature = extract_wire_signature(spec, server_name)?;
+        Ok(Self {
+            length,
+            ja4: wire_ja4(&signature),
+            signature,
+        })
+    }
+}
+
+/// detector that still distinguishes the minimized ClientHello from the profile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectorWarning {
+    pub detector: String,
+    /// value for the profile
+    pub original: String,
+    /// value for the minimized spec
+    pub minimized: String,
+}
+
+/// minimization result
+#[derive(Debug)]
+pub struct MinimizedSpec {
+    pub spec: ClientHelloSpec,
+    /// reductions kept, in the order applied
+    pub applied: Vec<Reduction>,
+    /// TLS record length of the profile
+    pub original_length: usize,
+    /// TLS record length of the minimized spec
+    pub minimized_length: usize,
+    /// wire JA4 of the minimized spec
+    pub ja4: String,
+    pub warnings: Vec<DetectorWarning>,
+}
+
+impl MinimizedSpec {
+    /// whether `detector` still tells the two apart
+    pub fn distinguishable_by(&self, detector: &str) -> bool {
+        self.warnings.iter().any(|w| w.detector == detector)
+    }
+}
+
+/// greedy ClientHello minimizer
+pub struct SpecMinimizer {
+    factory: fn() -> ClientHelloSpec,
+    components: Vec<Ja4Component>,
+    server_name: String,
+}
+
+impl SpecMinimizer {
+    /// minimizer preserving the full JA4 of `factory`'s spec
+    pub fn new(factory: fn() -> ClientHelloSpec) -> Self {
+        Self {
+            factory,
+            components: vec![Ja4Component::Full],
+            server_name: "example.com".to_string(),
+        }
+    }
+
+    /// JA4 components that must stay identical
+    pub fn with_components(mut self, components: &[Ja4Component]) -> Self {
+        self.components = components.to_vec();
+        self
+    }
+
+    /// SNI used when building the ClientHello (default `example.com`)
+    pub fn with_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_name = server_name.into();
+        self
+    }
+
+    /// reductions tried against `spec`, cheapest to most invasive
+    pub fn candidates(&self, spec: &ClientHelloSpec) -> Vec<Reduction> {
+        let mut candidates = vec![
+            Reduction::DropGreaseCiphers,
+            Reduction::DropGreaseExtensions,
+            Reduction::DropGreaseVersions,
+            Reduction::DisablePadding,
+            Reduction::TrimAlpn,
+            Reduction::TrimAlps,
+            Reduction::TrimCertCompression,
+            Reduction::SingleKeyShare,
+            Reduction::TrimSupportedGroups,
+        ];
+
+        let mut extension_ids: Vec<u16> = Vec::new();
+        for ext in &spec.extensions {
+            let id = ext.extension_id();
+            if !is_grease_value(id)
+                && !REQUIRED_EXTENSIONS.contains(&id)
+                && !extension_ids.contains(&id)
+            {
+                extension_ids.push(id);
+            }
+        }
+        // ALPS repeats ALPN's protocols, so it goes before the smaller extensions
+        extension_ids.sort_by_key(|id| {
+            !matches!(
+                *id,
+                EXT_TYPE_APPLICATION_SETTINGS | EXT_TYPE_APPLICATION_SETTINGS_NEW
+            )
+        });
+        candidates.extend(extension_ids.into_iter().map(Reduction::DropExtension));
+
+        let tls13 = spec.tls_vers_max >= VERSION_TLS13;
+        candidates.extend(
+            spec.cipher_suites
+                .iter()
+                .filter(|c| !is_grease_value(**c))
+                .filter(|c| !tls13 || !TLS13_CIPHERS.contains(*c))
+                .map(|c| Reduction::DropCipher(*c)),
+        );
+        candidates
+    }
+
+    fn replay(&self, reductions: &[Reduction]) -> ClientHelloSpec {
+        let mut spec = (self.factory)();
+        for reduction in reductions {
+            reduction.apply(&mut spec);
+        }
+        spec
+    }
+
+    fn preserves(&self, original: &Ja4Payload, candidate: &Ja4Payload) -> bool {
+        self.components
+            .iter()
+            .all(|component| component.of(original) == component.of(candidate))
+    }
+
+    /// apply every reduction that keeps the chosen components, and report what still differs
+    pub fn minimize(&self) -> Result<MinimizedSpec, String> {
+        let original_spec = (self.factory)();
+        let original = WireView::of(&original_spec, &self.server_name)?;
+
+        let mut applied: Vec<Reduction> = Vec::new();
+        for reduction in self.candidates(&original_spec) {
+            let mut trial = self.replay(&applied);
+            if !reduction.apply(&mut trial) {
+                continue;
+            }
+            // a reduction that no longer builds is simply not taken
+            let Ok(view) = WireView::of(&trial, &self.server_name) else {
+                continue;
+            };
+            if self.preserves(&original.ja4, &view.ja4) {
+                applied.push(reduction);
+            }
+        }
+
+        let spec = self.replay(&applied);
+        let minimized = WireView::of(&spec, &self.server_name)?;
+        Ok(MinimizedSpec {
+            spec,
+            original_length: original.length,
+            minimized_length: minimized.length,
+            ja4: minimized.ja4.full.value().to_string(),
+            warnings: Self::warnings(&original, &minimized),
+            applied,
+        })
+    }
+
+    fn warnings(original: &WireView, minimized: &WireView) -> Vec<DetectorWarning> {
+        let ja3 = |view: &WireView| {
+            let sig = &view.signature;
+            JA3::generate(
+                sig.version.to_u16(),
+                &sig.cipher_suites,
+                &sig.extensions,
+                &sig.elliptic_curves,
+                &sig.elliptic_curve_point_formats,
+            )
+            .fingerprint
+        };
+        let ja4_raw = |view: &WireView| {
+            ja4_signature(&view.signature)
+                .generate_ja4_original()
+                .raw
+                .value()
+                .to_string()
+        };
+        let signature_match =
+            compare_signatures(&original.signature, &minimized.signature) != FingerprintMatch::None;
+
+        let checks = [
+            (
+                "ja4",
+                original.ja4.full.value().to_string(),
+                minimized.ja4.full.value().to_string(),
+            ),
+            ("ja4_r", ja4_raw(original), ja4_raw(minimized)),
+            ("ja3", ja3(original), ja3(minimized)),
+            (
+                "signature",
+                "match".to_string(),
+                if signature_match { "match" } else { "differs" }.to_string(),
+            ),
+            (
+                "wire_length",
+                original.length.to_string(),
+                minimized.length.to_string(),
+            ),
+        ];
+        checks
+            .into_iter()
+            .filter(|(_, before, after)| before != after)
+            .map(|(detector, original, minimized)| DetectorWarning {
+                detector: detector.to_string(),
+                original,
+                minimized,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_ja4_preserved_while_shrinking() {
+        let original = WireView::of(&ClientHelloSpec::chrome_133(), "example.com").unwrap();
+        let minimized = SpecMinimizer::new(ClientHelloSpec::chrome_133)
+            .minimize()
+            .unwrap();
+
+        assert_eq!(minimized.ja4, original.ja4.full.value());
+        assert!(minimized.minimized_length < minimized.original_length);
+        assert!(minimized.applied.contains(&Reduction::SingleKeyShare));
+        // the counts in JA4_a pin every extension and cipher
+        assert!(!minimized
+            .applied
+            .iter()
+            .any(|r| matches!(r, Reduction::DropExtension(_) | Reduction::DropCipher(_))));
+        assert!(!minimized.distinguishable_by("ja4"));
+        assert!(minimized.distinguishable_by("wire_length"));
+    }
+
+    #[test]
+    fn test_cipher_hash_only_drops_extensions() {
+        let minimized = SpecMinimizer::new(ClientHelloSpec::chrome_133)
+            .with_components(&[Ja4Component::B])
+            .minimize()
+            .unwrap();
+        let full = SpecMinimizer::new(ClientHelloSpec::chrome_133)
+            .minimize()
+            .unwrap();
+
+        assert!(minimized
+            .applied
+            .iter()
+            .any(|r| matches!(r, Reduction::DropExtension(_))));
+        assert!(!minimized
+            .applied
+            .iter()
+            .any(|r| matches!(r, Reduction::DropCipher(_))));
+        assert!(minimized.minimized_length < full.minimized_length);
+        assert!(minimized.distinguishable_by("ja4"));
+        assert!(minimized.distinguishable_by("ja3"));
+        // required extensions survive
+        for id in [EXT_TYPE_SUPPORTED_VERSIONS, EXT_TYPE_KEY_SHARE] {
+            assert!(minimized
+                .spec
+                .extensions
+                .iter()
+                .any(|ext| ext.extension_id() == id));
+        }
+    }
+}
@@ -0,0 +1,496 @@
+//! Mobile OS TLS stacks
+//!
+//! Native apps rarely ship their own TLS: on iOS they go through URLSession
+//! (NSURLSession, Apple's BoringSSL-derived stack) and on Android through
+//! Conscrypt (BoringSSL behind the platform `SSLSocket`, used by OkHttp and
+//! `HttpsURLConnection`). Their ClientHellos differ from the browsers on the
+//! same device, and change with the OS release rather than the app.
+//!
+//! [`MobileTlsStack`] names one of these stacks at an OS version and builds its
+//! [`ClientHelloSpec`], for spoofing app traffic, and matches observed
+//! ClientHellos back to the stacks that could have sent them, for detecting
+//! app clients.
+//!
+//! | Stack | Versions | Differences |
+//! |-------|----------|-------------|
+//! | NSURLSession | iOS 15-18 | GREASE, TLS 1.0-1.3, 3DES, zlib certificate compression |
+//! | NSURLSession | iOS 26+ | X25519MLKEM768 key share, TLS 1.2-1.3 only, no 3DES |
+//! | NSURLSession with ATS | any | forward-secret suites only, TLS 1.2 minimum |
+//! | Conscrypt | API 21-23 | TLS 1.2 only, no ChaCha20 |
+//! | Conscrypt | API 24-28 | TLS 1.2 only, ChaCha20 |
+//! | Conscrypt | API 29+ | TLS 1.3 |
+
+use crate::tls_config::extract::extract_signature;
+use crate::tls_config::grease::filter_grease_values;
+use crate::tls_config::metadata::SpecMetadata;
+use crate::tls_config::signature::ClientHelloSignature;
+use crate::tls_config::spec::{
+    ClientHelloSpec, CERT_COMPRESSION_ZLIB, COMPRESSION_NONE, POINT_FORMAT_UNCOMPRESSED,
+    PSK_MODE_DHE, RENEGOTIATE_ONCE_AS_CLIENT, VERSION_TLS10, VERSION_TLS11, VERSION_TLS12,
+    VERSION_TLS13,
+};
+use crate::tls_extensions::{
+    ALPNExtension, ExtendedMasterSecretExtension, KeyShare, KeyShareExtension,
+    PSKKeyExchangeModesExtension, RenegotiationInfoExtension, SCTExtension, SNIExtension,
+    SessionTicketExtension, SignatureAlgorithmsExtension, StatusRequestExtension,
+    SupportedCurvesExtension, SupportedPointsExtension, SupportedVersionsExtension, TLSExtension,
+    UtlsCompressCertExtension, UtlsGREASEExtension, UtlsPaddingExtension,
+};
+use fingerprint_core::dicttls::{
+    cipher_suites::{self as cs, GREASE_PLACEHOLDER as GREASE_CS},
+    extensions::{EXT_TYPE_PADDING, EXT_TYPE_PRE_SHARED_KEY, EXT_TYPE_SERVER_NAME},
+    signature_schemes::{
+        ECDSA_SHA1, ECDSA_WITH_P256_AND_SHA256, ECDSA_WITH_P384_AND_SHA384, PKCS1_WITH_SHA256,
+        PKCS1_WITH_SHA384, PKCS1_WITH_SHA512, PSS_WITH_SHA256, PSS_WITH_SHA384, PSS_WITH_SHA512,
+        RSA_PKCS1_SHA1,
+    },
+    supported_groups::{
+        CURVE_P256, CURVE_P384, GREASE_PLACEHOLDER as GREASE_SG, SECP521R1, X25519, X25519_MLKEM768,
+    },
+};
+use std::sync::OnceLock;
+
+/// First iOS release whose URLSession offers the hybrid post-quantum key share
+const IOS_PQ_RELEASE: u32 = 26;
+
+/// Lowest Android API level Conscrypt profiles are modelled for (Android 5.0)
+const ANDROID_MIN_API: u32 = 21;
+
+/// Android API level that enabled ChaCha20-Poly1305 (Android 7.0)
+const ANDROID_CHACHA_API: u32 = 24;
+
+/// Android API level that enabled TLS 1.3 (Android 10)
+const ANDROID_TLS13_API: u32 = 29;
+
+/// A mobile OS TLS stack at a given OS version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MobileTlsStack {
+    /// iOS / iPadOS URLSession
+    NsUrlSession {
+        /// iOS major version (15, 16, 17, 18, 26, ...)
+        ios_major: u32,
+        /// App Transport Security defaults in force (no `NSAllowsArbitraryLoads`)
+        ats: bool,
+    },
+    /// Android Conscrypt
+    Conscrypt {
+        /// Android API level (21 = Android 5.0, 34 = Android 14, ...)
+        api_level: u32,
+    },
+}
+
+impl MobileTlsStack {
+    /// Every modelled stack, one entry per OS release
+    pub fn catalog() -> Vec<Self> {
+        let ios = [15, 16, 17, 18, 26].into_iter().flat_map(|ios_major| {
+            [true, false]
+                .into_iter()
+                .map(move |ats| Self::NsUrlSession { ios_major, ats })
+        });
+        let android = (ANDROID_MIN_API..=35).map(|api_level| Self::Conscrypt { api_level });
+        ios.chain(android).collect()
+    }
+
+    /// Stable name, e.g. `nsurlsession_ios_17`, `nsurlsession_ios_17_no_ats`, `conscrypt_api_34`
+    pub fn name(&self) -> String {
+        match self {
+            Self::NsUrlSession {
+                ios_major,
+                ats: true,
+            } => format!("nsurlsession_ios_{}", ios_major),
+            Self::NsUrlSession {
+                ios_major,
+                ats: false,
+            } => format!("nsurlsession_ios_{}_no_ats", ios_major),
+            Self::Conscrypt { api_level } => format!("conscrypt_api_{}", api_level),
+        }
+    }
+
+    /// OS platform name, as used in profile metadata
+    pub fn platform(&self) -> &'static str {
+        match self {
+            Self::NsUrlSession { .. } => "iOS",
+            Self::Conscrypt { .. } => "Android",
+        }
+    }
+
+    /// ClientHello this stack sends
+    pub fn spec(&self) -> ClientHelloSpec {
+        match *self {
+            Self::NsUrlSession { ios_major, ats } => {
+                ClientHelloSpec::ios_nsurlsession(ios_major, ats)
+            }
+            Self::Conscrypt { api_level } => ClientHelloSpec::android_conscrypt(api_level),
+        }
+    }
+
+    /// JA4 of this stack connecting to a host name (SNI present)
+    pub fn ja4(&self) -> String {
+        let mut ja4 = self.spec().calculate_ja4();
+        ja4.destination = 'd';
+        ja4.to_fingerprint_string()
+    }
+
+    /// Stacks whose ClientHello matches an observed signature
+    ///
+    /// GREASE values are ignored, as are the SNI, padding and pre-shared key
+    /// extensions, which depend on the host name, the hello length and session
+    /// resumption rather than on the stack. Several OS versions can share one
+    /// ClientHello, so the result lists all of them, oldest first.
+    pub fn identify(signature: &ClientHelloSignature) -> Vec<Self> {
+        let ciphers = signature.cipher_suites_without_grease();
+        let extensions = stack_extensions(&signature.extensions);
+        let sig_algs = signature.signature_algorithms_without_grease();
+        let curves = filter_grease_values(&signature.elliptic_curves);
+
+        known_signatures()
+            .iter()
+            .filter(|(_, known)| {
+                known.cipher_suites_without_grease() == ciphers
+                    && stack_extensions(&known.extensions) == extensions
+                    && (sig_algs.is_empty()
+                        || known.signature_algorithms_without_grease() == sig_algs)
+                    && (curves.is_empty() || filter_grease_values(&known.elliptic_curves) == curves)
+            })
+            .map(|(stack, _)| *stack)
+            .collect()
+    }
+
+    /// Stacks whose [`MobileTlsStack::ja4`] equals `ja4`
+    pub fn identify_ja4(ja4: &str) -> Vec<Self> {
+        Self::catalog()
+            .into_iter()
+            .filter(|stack| stack.ja4() == ja4)
+            .collect()
+    }
+}
+
+impl std::fmt::Display for MobileTlsStack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name())
+    }
+}
+
+/// Catalog signatures, built once
+fn known_signatures() -> &'static [(MobileTlsStack, ClientHelloSignature)] {
+    static KNOWN: OnceLock<Vec<(MobileTlsStack, ClientHelloSignature)>> = OnceLock::new();
+    KNOWN.get_or_init(|| {
+        MobileTlsStack::catalog()
+            .into_iter()
+            .map(|stack| (stack, extract_signature(&stack.spec())))
+            .collect()
+    })
+}
+
+/// Sorted extension IDs without GREASE and the host/length/resumption dependent ones
+fn stack_extensions(extensions: &[u16]) -> Vec<u16> {
+    let mut ids: Vec<u16> = filter_grease_values(extensions)
+        .into_iter()
+        .filter(|id| {
+            ![
+                EXT_TYPE_SERVER_NAME,
+                EXT_TYPE_PADDING,
+                EXT_TYPE_PRE_SHARED_KEY,
+            ]
+            .contains(id)
+        })
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+impl ClientHelloSpec {
+    /// iOS URLSession (NSURLSession) ClientHello for `ios_major`
+    ///
+    /// With `ats` the App Transport Security defaults apply: only
+    /// forward-secret suites and TLS 1.2 or later are offered. Without it
+    /// (`NSAllowsArbitraryLoads`) the legacy RSA key exchange and 3DES suites
+    /// and TLS 1.0/1.1 come back on releases that still support them.
+    pub fn ios_nsurlsession(ios_major: u32, ats: bool) -> Self {
+        let pq = ios_major >= IOS_PQ_RELEASE;
+        let legacy = !ats && !pq;
+
+        let mut cipher_suites = vec![
+            GREASE_CS,
+            cs::TLS_AES_128_GCM_SHA256,
+            cs::TLS_AES_256_GCM_SHA384,
+            cs::TLS_CHACHA20_POLY1305_SHA256,
+            cs::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+            cs::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+            cs::TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+            cs::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+            cs::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            cs::TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+            cs::TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA,
+            cs::TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA,
+            cs::TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA,
+            cs::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA,
+        ];
+        if !ats {
+            cipher_suites.extend([
+                cs::TLS_RSA_WITH_AES_256_GCM_SHA384,
+                cs::TLS_RSA_WITH_AES_128_GCM_SHA256,
+                cs::TLS_RSA_WITH_AES_256_CBC_SHA,
+                cs::TLS_RSA_WITH_AES_128_CBC_SHA,
+            ]);
+        }
+        if legacy {
+            cipher_suites.extend([
+                cs::TLS_ECDHE_ECDSA_WITH_3DES_EDE_CBC_SHA,
+                cs::TLS_ECDHE_RSA_WITH_3DES_EDE_CBC_SHA,
+                cs::TLS_RSA_WITH_3DES_EDE_CBC_SHA,
+            ]);
+        }
+
+        let mut curves = vec![GREASE_SG];
+        if pq {
+            curves.push(X25519_MLKEM768);
+        }
+        curves.extend([X25519, CURVE_P256, CURVE_P384, SECP521R1]);
+
+        let signature_algorithms = vec![
+            ECDSA_WITH_P256_AND_SHA256,
+            PSS_WITH_SHA256,
+            PKCS1_WITH_SHA256,
+            ECDSA_WITH_P384_AND_SHA384,
+            ECDSA_SHA1,
+            PSS_WITH_SHA384,
+            PSS_WITH_SHA384,
+            PKCS1_WITH_SHA384,
+            PSS_WITH_SHA512,
+            PKCS1_WITH_SHA512,
+            RSA_PKCS1_SHA1,
+        ];
+
+        let mut versions = vec![GREASE_SG, VERSION_TLS13, VERSION_TLS12];
+        if legacy {
+            versions.extend([VERSION_TLS11, VERSION_TLS10]);
+        }
+
+        let mut key_shares = vec![KeyShare {
+            group: GREASE_SG,
+            data: vec![0],
+        }];
+        if pq {
+            key_shares.push(KeyShare {
+                group: X25519_MLKEM768,
+                data: vec![], // actualneedGeneratekey
+            });
+        }
+        key_shares.push(KeyShare {
+            group: X25519,
+            data: vec![], // actualneedGeneratekey
+        });
+
+        let alpn = vec!["h2".to_string(), "http/1.1".to_string()];
+        let metadata = mobile_metadata(&alpn, &curves, &signature_algorithms, &versions);
+
+        let mut extensions: Vec<Box<dyn TLSExtension>> = vec![
+            Box::new(UtlsGREASEExtension::new()),
+            Box::new(SNIExtension::new(String::new())),
+            Box::new(ExtendedMasterSecretExtension),
+            Box::new(RenegotiationInfoExtension::new(RENEGOTIATE_ONCE_AS_CLIENT)),
+            Box::new(SupportedCurvesExtension::new(curves)),
+            Box::new(SupportedPointsExtension::new(vec![
+                POINT_FORMAT_UNCOMPRESSED,
+            ])),
+            Box::new(ALPNExtension::new(alpn)),
+            Box::new(StatusRequestExtension),
+            Box::new(SignatureAlgorithmsExtension::new(signature_algorithms)),
+            Box::new(SCTExtension),
+            Box::new(KeyShareExtension::new(key_shares)),
+            Box::new(PSKKeyExchangeModesExtension::new(vec![PSK_MODE_DHE])),
+            Box::new(SupportedVersionsExtension::new(versions)),
+            Box::new(UtlsCompressCertExtension::new(vec![CERT_COMPRESSION_ZLIB])),
+            Box::new(UtlsGREASEExtension::new()),
+        ];
+        // The ML-KEM key share pushes the hello past the length BoringSSL pads
+        if !pq {
+            extensions.push(Box::new(UtlsPaddingExtension::new()));
+        }
+
+        ClientHelloSpec {
+            cipher_suites,
+            compression_methods: vec![COMPRESSION_NONE],
+            extensions,
+            tls_vers_min: if legacy { VERSION_TLS10 } else { VERSION_TLS12 },
+            tls_vers_max: VERSION_TLS13,
+            metadata: Some(metadata),
+        }
+    }
+
+    /// Android Conscrypt ClientHello for `api_level`
+    ///
+    /// Conscrypt sends no GREASE and keeps the same extension order across
+    /// releases; TLS 1.3 arrived with API 29 and ChaCha20 with API 24. Levels
+    /// below 21 are treated as 21. ALPN is what OkHttp offers (`h2`, `http/1.1`).
+    pub fn android_conscrypt(api_level: u32) -> Self {
+        let api_level = api_level.max(ANDROID_MIN_API);
+        let tls13 = api_level >= ANDROID_TLS13_API;
+        let chacha = api_level >= ANDROID_CHACHA_API;
+
+        let mut cipher_suites = Vec::new();
+        if tls13 {
+            cipher_suites.extend([
+                cs::TLS_AES_128_GCM_SHA256,
+                cs::TLS_AES_256_GCM_SHA384,
+                cs::TLS_CHACHA20_POLY1305_SHA256,
+            ]);
+        }
+        cipher_suites.extend([
+            cs::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+            cs::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+        ]);
+        if chacha {
+            cipher_suites.push(cs::TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256);
+        }
+        cipher_suites.extend([
+            cs::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            cs::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+        ]);
+        if chacha {
+            cipher_suites.push(cs::TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256);
+        }
+        cipher_suites.extend([
+            cs::TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA,
+            cs::TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA,
+            cs::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA,
+            cs::TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA,
+            cs::TLS_RSA_WITH_AES_128_GCM_SHA256,
+            cs::TLS_RSA_WITH_AES_256_GCM_SHA384,
+            cs::TLS_RSA_WITH_AES_128_CBC_SHA,
+            cs::TLS_RSA_WITH_AES_256_CBC_SHA,
+        ]);
+
+        let curves = vec![X25519, CURVE_P256, CURVE_P384];
+        let signature_algorithms = vec![
+            ECDSA_WITH_P256_AND_SHA256,
+            PSS_WITH_SHA256,
+            PKCS1_WITH_SHA256,
+            ECDSA_WITH_P384_AND_SHA384,
+            PSS_WITH_SHA384,
+            PKCS1_WITH_SHA384,
+            PSS_WITH_SHA512,
+            PKCS1_WITH_SHA512,
+            RSA_PKCS1_SHA1,
+        ];
+        let versions = if tls13 {
+            vec![VERSION_TLS13, VERSION_TLS12]
+        } else {
+            Vec::new()
+        };
+
+        let alpn = vec!["h2".to_string(), "http/1.1".to_string()];
+        let metadata = mobile_metadata(&alpn, &curves, &signature_algorithms, &versions);
+
+        let mut extensions: Vec<Box<dyn TLSExtension>> = vec![
+            Box::new(SNIExtension::new(String::new())),
+            Box::new(ExtendedMasterSecretExtension),
+            Box::new(RenegotiationInfoExtension::new(RENEGOTIATE_ONCE_AS_CLIENT)),
+            Box::new(SupportedCurvesExtension::new(curves)),
+            Box::new(SupportedPointsExtension::new(vec![
+                POINT_FORMAT_UNCOMPRESSED,
+            ])),
+            Box::new(SessionTicketExtension),
+            Box::new(ALPNExtension::new(alpn)),
+            Box::new(StatusRequestExtension),
+            Box::new(SignatureAlgorithmsExtension::new(signature_algorithms)),
+        ];
+        if tls13 {
+            extensions.push(Box::new(KeyShareExtension::new(vec![KeyShare {
+                group: X25519,
+                data: vec![], // actualneedGeneratekey
+            }])));
+            extensions.push(Box::new(PSKKeyExchangeModesExtension::new(vec![
+                PSK_MODE_DHE,
+            ])));
+            extensions.push(Box::new(SupportedVersionsExtension::new(versions)));
+        }
+
+        ClientHelloSpec {
+            cipher_suites,
+            compression_methods: vec![COMPRESSION_NONE],
+            extensions,
+            tls_vers_min: VERSION_TLS12,
+            tls_vers_max: if tls13 { VERSION_TLS13 } else { VERSION_TLS12 },
+            metadata: Some(metadata),
+        }
+    }
+}
+
+fn mobile_metadata(
+    alpn: &[String],
+    curves: &[u16],
+    signature_algorithms: &[u16],
+    versions: &[u16],
+) -> SpecMetadata {
+    let mut metadata = SpecMetadata::new();
+    metadata.set_alpn(alpn.to_vec());
+    metadata.set_elliptic_curves(curves.to_vec());
+    metadata.set_elliptic_curve_point_formats(vec![POINT_FORMAT_UNCOMPRESSED]);
+    metadata.set_signature_algorithms(signature_algorithms.to_vec());
+    if !versions.is_empty() {
+        metadata.set_supported_versions(versions.to_vec());
+    }
+    metadata
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ats_and_release_shape_the_hello() {
+        let ats = ClientHelloSpec::ios_nsurlsession(17, true);
+        let arbitrary = ClientHelloSpec::ios_nsurlsession(17, false);
+        assert!(!ats
+            .cipher_suites
+            .contains(&cs::TLS_RSA_WITH_AES_128_GCM_SHA256));
+        assert!(arbitrary
+            .cipher_suites
+            .contains(&cs::TLS_RSA_WITH_3DES_EDE_CBC_SHA));
+        assert_eq!(ats.tls_vers_min, VERSION_TLS12);
+        assert_eq!(arbitrary.tls_vers_min, VERSION_TLS10);
+
+        let pq = extract_signature(&ClientHelloSpec::ios_nsurlsession(26, true));
+        assert_eq!(pq.elliptic_curves[1], X25519_MLKEM768);
+        assert!(!pq.extensions.contains(&EXT_TYPE_PADDING));
+
+        let old = ClientHelloSpec::android_conscrypt(26);
+        let new = ClientHelloSpec::android_conscrypt(34);
+        assert_eq!(old.tls_vers_max, VERSION_TLS12);
+        assert_eq!(new.tls_vers_max, VERSION_TLS13);
+        assert!(!new.cipher_suites.contains(&GREASE_CS));
+        assert_ne!(
+            MobileTlsStack::Conscrypt { api_level: 26 }.ja4(),
+            MobileTlsStack::Conscrypt { api_level: 34 }.ja4()
+        );
+    }
+
+    #[test]
+    fn test_identify_ignores_grease_and_padding() {
+        let mut observed = extract_signature(&ClientHelloSpec::ios_nsurlsession(18, true));
+        observed.cipher_suites[0] = 0x3a3a;
+        observed.extensions.retain(|&id| id != EXT_TYPE_PADDING);
+        observed.sni = Some("api.example.com".to_string());
+
+        let stacks = MobileTlsStack::identify(&observed);
+        assert!(stacks.contains(&MobileTlsStack::NsUrlSession {
+            ios_major: 18,
+            ats: true
+        }));
+        assert!(stacks
+            .iter()
+            .all(|s| matches!(s, MobileTlsStack::NsUrlSession { ats: true, .. })));
+
+        let android = MobileTlsStack::Conscrypt { api_level: 34 };
+        let by_ja4 = MobileTlsStack::identify_ja4(&android.ja4());
+        assert!(by_ja4.contains(&android));
+        assert!(by_ja4
+            .iter()
+            .all(|s| matches!(s, MobileTlsStack::Conscrypt { api_level } if *api_level >= 29)));
+
+        let chrome = extract_signature(&ClientHelloSpec::chrome_133());
+        assert!(MobileTlsStack::identify(&chrome).is_empty());
+    }
+}
@@ -28,29 +28,51 @@ mod comparison;
 mod extract;
 mod grease;
 mod ja4;
+mod ja4l;
+mod ja4x;
 mod metadata;
+mod minimize;
+mod mobile;
+mod mutation;
+mod neighbors;
 mod observable;
+mod parser;
 mod signature;
 mod spec;
 mod stats;
 mod version;
 
 pub use builder::ClientHelloSpecBuilder;
-pub use comparison::{compare_signatures, compare_specs, find_best_match, FingerprintMatch};
-pub use extract::extract_signature;
+pub use comparison::{
+    compare_signatures, compare_signatures_with_confidence, compare_specs,
+    compare_specs_with_confidence, find_best_match, FingerprintMatch,
+};
+pub use extract::{extract_signature, extract_wire_signature};
 pub use grease::{filter_grease_values, is_grease_value, remove_grease_values, TLS_GREASE_VALUES};
 pub use ja4::{
     first_last_alpn, hash12, Ja4Fingerprint, Ja4Payload, Ja4RawFingerprint, Ja4Signature,
 };
+pub use ja4l::{Ja4lMeasurement, Ja4lSide, DEFAULT_PROPAGATION_FACTOR};
+pub use ja4x::{certificate_issuer, Ja4xPayload, Ja4xSignature};
 pub use metadata::{ExtensionMetadata, SpecMetadata};
+pub use minimize::{
+    wire_ja4, DetectorWarning, Ja4Component, MinimizedSpec, Reduction, SpecMinimizer,
+};
+pub use mobile::MobileTlsStack;
+pub use mutation::{
+    ClientHelloDetector, EvasionReport, Ja4Detector, Ja4PrefixDetector, Mutation, MutationHarness,
+    MutationOutcome, SignatureDetector, WireLengthDetector,
+};
+pub use neighbors::{Neighbor, SignatureIndex};
 pub use observable::TlsClientObserved;
+pub use parser::ClientHelloParser;
 pub use signature::ClientHelloSignature;
 pub use spec::{
     chrome_103_spec, chrome_133_0rtt_spec, chrome_133_psk_0rtt_spec, chrome_133_psk_spec,
     chrome_133_spec, chrome_136_spec, firefox_133_spec, safari_16_0_spec, CipherSuiteID,
-    ClientHelloSpec, CERT_COMPRESSION_BROTLI, COMPRESSION_NONE, POINT_FORMAT_UNCOMPRESSED,
-    PSK_MODE_DHE, RENEGOTIATE_ONCE_AS_CLIENT, VERSION_TLS10, VERSION_TLS11, VERSION_TLS12,
-    VERSION_TLS13,
+    ClientHelloSpec, CERT_COMPRESSION_BROTLI, CERT_COMPRESSION_ZLIB, COMPRESSION_NONE,
+    POINT_FORMAT_UNCOMPRESSED, PSK_MODE_DHE, RENEGOTIATE_ONCE_AS_CLIENT, VERSION_TLS10,
+    VERSION_TLS11, VERSION_TLS12, VERSION_TLS13,
 };
 pub use stats::FingerprintStats;
 pub use version::TlsVersion;
@@ -0,0 +1,438 @@
+//! ClientHello mutationengine and detector robustness harness
+//!
+//! Takes a base ClientHelloSpec, produces systematic variants (drop/reorder extensions,
+//! tweak cipher lists, resize padding) and runs detectors over them to report which
+//! mutations evade which detector. Used to red-team our own detection pipeline: a
+//! detector that only matches exact extension order is trivially evaded.
+//!
+//! Note: ClientHelloSpec cannot be cloned (extensions are trait objects), so the harness
+//! takes a factory that builds a fresh base spec for every variant.
+
+use crate::tls_config::comparison::{compare_signatures, FingerprintMatch};
+use crate::tls_config::extract::extract_signature;
+use crate::tls_config::grease::is_grease_value;
+use crate::tls_config::signature::ClientHelloSignature;
+use crate::tls_config::spec::ClientHelloSpec;
+use crate::tls_extensions::UtlsPaddingExtension;
+use fingerprint_core::dicttls::extensions::EXT_TYPE_PADDING;
+
+/// cipher suite appended by [`Mutation::AppendCipher`] in systematic variants
+/// (TLS_RSA_WITH_AES_128_CBC_SHA, present in almost every legacy list)
+const LEGACY_CIPHER: u16 = 0x002f;
+
+/// padding lengths tried by systematic variants
+const PADDING_LENGTHS: &[usize] = &[0, 64, 256, 512];
+
+/// single ClientHello mutation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mutation {
+    /// remove every extension with this ID
+    DropExtension(u16),
+    /// swap extensions at two positions
+    SwapExtensions(usize, usize),
+    /// reverse extension order
+    ReverseExtensions,
+    /// remove cipher suite
+    DropCipher(u16),
+    /// append cipher suite at the end
+    AppendCipher(u16),
+    /// reverse cipher suite order
+    ReverseCiphers,
+    /// set padding extension length (0 disables padding), adding the extension if missing
+    SetPadding(usize),
+}
+
+impl Mutation {
+    /// apply mutation in place; returns false when it does not change anything
+    pub fn apply(&self, spec: &mut ClientHelloSpec) -> bool {
+        match *self {
+            Mutation::DropExtension(id) => {
+                let before = spec.extensions.len();
+                spec.extensions.retain(|ext| ext.extension_id() != id);
+                spec.extensions.len() != before
+            }
+            Mutation::SwapExtensions(a, b) => {
+                if a == b || a >= spec.extensions.len() || b >= spec.extensions.len() {
+                    return false;
+                }
+                spec.extensions.swap(a, b);
+                true
+            }
+            Mutation::ReverseExtensions => {
+                spec.extensions.reverse();
+                spec.extensions.len() > 1
+            }
+            Mutation::DropCipher(id) => {
+                let before = spec.cipher_suites.len();
+                spec.cipher_suites.retain(|c| *c != id);
+                spec.cipher_suites.len() != before
+            }
+            Mutation::AppendCipher(id) => {
+                if spec.cipher_suites.contains(&id) {
+                    return false;
+                }
+                spec.cipher_suites.push(id);
+                true
+            }
+            Mutation::ReverseCiphers => {
+                spec.cipher_suites.reverse();
+                spec.cipher_suites.len() > 1
+            }
+            Mutation::SetPadding(len) => {
+                let padding = UtlsPaddingExtension {
+                    padding_len: len,
+                    will_pad: len > 0,
+                    get_padding_len: None,
+                };
+                match spec
+                    .extensions
+                    .iter()
+                    .position(|ext| ext.extension_id() == EXT_TYPE_PADDING)
+                {
+                    Some(index) => spec.extensions[index] = Box::new(padding),
+                    None => spec.extensions.push(Box::new(padding)),
+                }
+                true
+            }
+        }
+    }
+
+    /// systematic single-step variants of a spec
+    pub fn systematic(spec: &ClientHelloSpec) -> Vec<Mutation> {
+        let mut mutations = Vec::new();
+
+        let mut extension_ids: Vec<u16> = Vec::new();
+        for ext in &spec.extensions {
+            let id = ext.extension_id();
+            if !is_grease_value(id) && !extension_ids.contains(&id) {
+                extension_ids.push(id);
+            }
+        }
+        mutations.extend(extension_ids.into_iter().map(Mutation::DropExtension));
+        mutations.extend(
+            (1..spec.extensions.len()).map(|index| Mutation::SwapExtensions(index - 1, index)),
+        );
+        mutations.push(Mutation::ReverseExtensions);
+
+        mutations.extend(
+            spec.cipher_suites
+                .iter()
+                .filter(|c| !is_grease_value(**c))
+                .map(|c| Mutation::DropCipher(*c)),
+        );
+        if !spec.cipher_suites.contains(&LEGACY_CIPHER) {
+            mutations.push(Mutation::AppendCipher(LEGACY_CIPHER));
+        }
+        mutations.push(Mutation::ReverseCiphers);
+
+        mutations.extend(PADDING_LENGTHS.iter().map(|len| Mutation::SetPadding(*len)));
+        mutations
+    }
+}
+
+impl std::fmt::Display for Mutation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mutation::DropExtension(id) => write!(f, "drop-ext-{:#06x}", id),
+            Mutation::SwapExtensions(a, b) => write!(f, "swap-ext-{}-{}", a, b),
+            Mutation::ReverseExtensions => write!(f, "reverse-ext"),
+            Mutation::DropCipher(id) => write!(f, "drop-cipher-{:#06x}", id),
+            Mutation::AppendCipher(id) => write!(f, "append-cipher-{:#06x}", id),
+            Mutation::ReverseCiphers => write!(f, "reverse-ciphers"),
+            Mutation::SetPadding(len) => write!(f, "padding-{}", len),
+        }
+    }
+}
+
+/// detector under test
+///
+/// `matches` returns true when the detector still identifies the variant as the
+/// reference client (i.e. the mutation did not evade it).
+pub trait ClientHelloDetector {
+    fn name(&self) -> &str;
+    fn matches(&self, spec: &ClientHelloSpec) -> bool;
+}
+
+/// exact JA4 match
+pub struct Ja4Detector {
+    reference: String,
+}
+
+impl Ja4Detector {
+    pub fn new(reference: &ClientHelloSpec) -> Self {
+        Self {
+            reference: reference.ja4_string(),
+        }
+    }
+}
+
+impl ClientHelloDetector for Ja4Detector {
+    fn name(&self) -> &str {
+        "ja4"
+    }
+
+    fn matches(&self, spec: &ClientHelloSpec) -> bool {
+        spec.ja4_string() == self.reference
+    }
+}
+
+/// JA4_a match (transport, version, SNI, counts, ALPN) ignoring the hashed lists
+pub struct Ja4PrefixDetector {
+    reference: String,
+}
+
+impl Ja4PrefixDetector {
+    pub fn new(reference: &ClientHelloSpec) -> Self {
+        Self {
+            reference: Self::prefix(reference),
+        }
+    }
+
+    fn prefix(spec: &ClientHelloSpec) -> String {
+        spec.ja4_string()
+            .split('_')
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    }
+}
+
+impl ClientHelloDetector for Ja4PrefixDetector {
+    fn name(&self) -> &str {
+        "ja4_a"
+    }
+
+    fn matches(&self, spec: &ClientHelloSpec) -> bool {
+        Self::prefix(spec) == self.reference
+    }
+}
+
+/// ordered signature match (GREASE ignored), see [`compare_signatures`]
+pub struct SignatureDetector {
+    reference: ClientHelloSignature,
+}
+
+impl SignatureDetector {
+    pub fn new(reference: &ClientHelloSpec) -> Self {
+        Self {
+            reference: extract_signature(reference),
+        }
+    }
+}
+
+impl ClientHelloDetector for SignatureDetector {
+    fn name(&self) -> &str {
+        "signature"
+    }
+
+    fn matches(&self, spec: &ClientHelloSpec) -> bool {
+        compare_signatures(&self.reference, &extract_signature(spec)) != FingerprintMatch::None
+    }
+}
+
+/// ClientHello body length match (cipher list + extensions), catches padding changes
+pub struct WireLengthDetector {
+    reference: usize,
+    tolerance: usize,
+}
+
+impl WireLengthDetector {
+    pub fn new(reference: &ClientHelloSpec, tolerance: usize) -> Self {
+        Self {
+            reference: Self::wire_length(reference),
+            tolerance,
+        }
+    }
+
+    fn wire_length(spec: &ClientHelloSpec) -> usize {
+        spec.cipher_suites.len() * 2 + spec.extensions.iter().map(|ext| ext.len()).sum::<usize>()
+    }
+}
+
+impl ClientHelloDetector for WireLengthDetector {
+    fn name(&self) -> &str {
+        "wire_length"
+    }
+
+    fn matches(&self, spec: &ClientHelloSpec) -> bool {
+        Self::wire_length(spec).abs_diff(self.reference) <= self.tolerance
+    }
+}
+
+/// detection outcome of one variant
+#[derive(Debug, Clone)]
+pub struct MutationOutcome {
+    pub mutation: Mutation,
+    /// JA4 of the variant
+    pub ja4: String,
+    /// (detector name, still matched)
+    pub detections: Vec<(String, bool)>,
+}
+
+impl MutationOutcome {
+    pub fn evades(&self, detector: &str) -> bool {
+        self.detections
+            .iter()
+            .any(|(name, matched)| name == detector && !matched)
+    }
+
+    pub fn evades_all(&self) -> bool {
+        self.detections.iter().all(|(_, matched)| !matched)
+    }
+}
+
+/// evaluation report
+#[derive(Debug, Clone, Default)]
+pub struct EvasionReport {
+    pub outcomes: Vec<MutationOutcome>,
+}
+
+impl EvasionReport {
+    /// mutations that evade a detector
+    pub fn evading(&self, detector: &str) -> Vec<&Mutation> {
+        self.outcomes
+            .iter()
+            .filter(|o| o.evades(detector))
+            .map(|o| &o.mutation)
+            .collect()
+    }
+
+    /// share of variants that evade a detector (0.0 - 1.0)
+    pub fn evasion_rate(&self, detector: &str) -> f64 {
+        if self.outcomes.is_empty() {
+            return 0.0;
+        }
+        self.evading(detector).len() as f64 / self.outcomes.len() as f64
+    }
+
+    /// mutations no detector catches
+    pub fn evading_all(&self) -> Vec<&Mutation> {
+        self.outcomes
+            .iter()
+            .filter(|o| o.evades_all())
+            .map(|o| &o.mutation)
+            .collect()
+    }
+}
+
+/// mutation harness
+pub struct MutationHarness {
+    factory: fn() -> ClientHelloSpec,
+    detectors: Vec<Box<dyn ClientHelloDetector>>,
+}
+
+impl MutationHarness {
+    /// harness without detectors
+    pub fn new(factory: fn() -> ClientHelloSpec) -> Self {
+        Self {
+            factory,
+            detectors: Vec::new(),
+        }
+    }
+
+    /// harness with the built-in detectors referencing the base spec
+    pub fn with_default_detectors(factory: fn() -> ClientHelloSpec) -> Self {
+        let reference = factory();
+        Self::new(factory)
+            .detector(Ja4Detector::new(&reference))
+            .detector(Ja4PrefixDetector::new(&reference))
+            .detector(SignatureDetector::new(&reference))
+            .detector(WireLengthDetector::new(&reference, 0))
+    }
+
+    /// add a detector
+    pub fn detector(mut self, detector: impl ClientHelloDetector + 'static) -> Self {
+        self.detectors.push(Box::new(detector));
+        self
+    }
+
+    /// systematic single-step variants of the base spec
+    pub fn systematic_mutations(&self) -> Vec<Mutation> {
+        Mutation::systematic(&(self.factory)())
+    }
+
+    /// run detectors over variants; no-op mutations are skipped
+    pub fn run(&self, mutations: &[Mutation]) -> EvasionReport {
+        let mut outcomes = Vec::new();
+        for mutation in mutations {
+            let mut spec = (self.factory)();
+            if !mutation.apply(&mut spec) {
+                continue;
+            }
+            let detections = self
+                .detectors
+                .iter()
+                .map(|d| (d.name().to_string(), d.matches(&spec)))
+                .collect();
+            outcomes.push(MutationOutcome {
+                mutation: mutation.clone(),
+                ja4: spec.ja4_string(),
+                detections,
+            });
+        }
+        EvasionReport { outcomes }
+    }
+
+    /// run every systematic variant
+    pub fn run_systematic(&self) -> EvasionReport {
+        self.run(&self.systematic_mutations())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reordering_evades_signature_but_not_ja4() {
+        let harness = MutationHarness::with_default_detectors(ClientHelloSpec::chrome_133);
+        let report = harness.run(&[Mutation::ReverseExtensions, Mutation::ReverseCiphers]);
+
+        assert_eq!(report.outcomes.len(), 2);
+        for outcome in &report.outcomes {
+            // JA4 sorts both lists
+            assert!(!outcome.evades("ja4"), "{}", outcome.mutation);
+            assert!(!outcome.evades("wire_length"));
+        }
+        assert!(report.outcomes[0].evades("signature"));
+    }
+
+    #[test]
+    fn test_systematic_report() {
+        let harness = MutationHarness::with_default_detectors(ClientHelloSpec::chrome_133);
+        let mutations = harness.systematic_mutations();
+        assert!(mutations.contains(&Mutation::ReverseExtensions));
+        assert!(mutations.contains(&Mutation::SetPadding(512)));
+
+        let report = harness.run(&mutations);
+        // dropping a cipher changes the JA4 count and hash
+        let drop_cipher = report
+            .outcomes
+            .iter()
+            .find(|o| matches!(o.mutation, Mutation::DropCipher(_)))
+            .unwrap();
+        assert!(drop_cipher.evades("ja4"));
+        assert!(drop_cipher.evades("ja4_a"));
+
+        // padding only moves the wire length
+        let padding = report
+            .outcomes
+            .iter()
+            .find(|o| o.mutation == Mutation::SetPadding(256))
+            .unwrap();
+        assert!(padding.evades("wire_length"));
+        assert!(report.evasion_rate("ja4") > 0.0);
+        assert!(report.evasion_rate("ja4") <= 1.0);
+    }
+
+    #[test]
+    fn test_noop_mutations_skipped() {
+        let harness = MutationHarness::new(ClientHelloSpec::chrome_133);
+        let report = harness.run(&[
+            Mutation::SwapExtensions(0, 0),
+            Mutation::DropExtension(0xfeed),
+        ]);
+        assert!(report.outcomes.is_empty());
+        assert_eq!(Mutation::SetPadding(64).to_string(), "padding-64");
+    }
+}
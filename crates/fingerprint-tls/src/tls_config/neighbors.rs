@@ -0,0 +1,316 @@
+//! ClientHello nearest-neighbor search
+//!
+//! `compare_signatures` answers "are these two the same client"; this module
+//! answers "which stored signatures are near this one" without scanning
+//! every entry. Signatures are turned into token sets (values plus adjacent
+//! cipher pairs, so order matters a little), MinHashed, and banded into
+//! buckets: two signatures share a bucket with high probability when their
+//! token sets overlap strongly. Candidates from the buckets are then ranked
+//! by the exact [`ClientHelloSignature::distance`].
+//!
+//! With `b` bands of `r` rows, pairs whose token Jaccard similarity is above
+//! roughly `(1/b)^(1/r)` are found; anything far below that is not, which is
+//! what keeps lookups sub-linear.
+
+use crate::tls_config::signature::ClientHelloSignature;
+use fingerprint_core::stable_hash::StableHashBuilder;
+use std::collections::{HashMap, HashSet};
+
+/// Token kinds, so equal values in different fields stay distinct
+const TOKEN_CIPHER: u8 = 1;
+const TOKEN_CIPHER_PAIR: u8 = 2;
+const TOKEN_EXTENSION: u8 = 3;
+const TOKEN_SIGNATURE_ALGORITHM: u8 = 4;
+const TOKEN_CURVE: u8 = 5;
+const TOKEN_POINT_FORMAT: u8 = 6;
+const TOKEN_VERSION: u8 = 7;
+const TOKEN_ALPN: u8 = 8;
+
+/// A stored signature near the query
+#[derive(Debug, Clone, PartialEq)]
+pub struct Neighbor<'a, L> {
+    /// Id returned by [`SignatureIndex::insert`]
+    pub id: usize,
+    /// Label stored with the signature
+    pub label: &'a L,
+    /// Stored signature
+    pub signature: &'a ClientHelloSignature,
+    /// Distance to the query
+    pub distance: f64,
+}
+
+/// Locality-sensitive hash index over ClientHello signatures
+///
+/// Each signature is stored with a label `L` (a profile name, a client id,
+/// or `()` when the id is enough).
+#[derive(Debug, Clone)]
+pub struct SignatureIndex<L = ()> {
+    rows: usize,
+    /// One MinHash seed per row of every band
+    seeds: Vec<u64>,
+    /// Per band: band hash -> entry ids
+    buckets: Vec<HashMap<u64, Vec<usize>>>,
+    entries: Vec<(L, ClientHelloSignature)>,
+}
+
+impl<L> SignatureIndex<L> {
+    /// Default band count
+    pub const DEFAULT_BANDS: usize = 20;
+    /// Default rows per band
+    pub const DEFAULT_ROWS: usize = 5;
+
+    /// Create an index with the default banding (threshold around 0.55)
+    pub fn new() -> Self {
+        Self::with_bands(Self::DEFAULT_BANDS, Self::DEFAULT_ROWS)
+    }
+
+    /// Create an index with `bands` bands of `rows` MinHash rows each
+    ///
+    /// More bands find more distant neighbors at the cost of larger
+    /// candidate sets; more rows do the opposite.
+    pub fn with_bands(bands: usize, rows: usize) -> Self {
+        let bands = bands.max(1);
+        let rows = rows.max(1);
+        Self {
+            rows,
+            seeds: (0..bands * rows)
+                .map(|i| splitmix64(0x6a09_e667_f3bc_c908 ^ i as u64))
+                .collect(),
+            buckets: vec![HashMap::new(); bands],
+            entries: Vec::new(),
+        }
+    }
+
+    /// Token Jaccard similarity at which a pair is found half the time
+    pub fn similarity_threshold(&self) -> f64 {
+        (1.0 / self.buckets.len() as f64).powf(1.0 / self.rows as f64)
+    }
+
+    /// Store `signature` under `label`, returning its id
+    pub fn insert(&mut self, label: L, signature: ClientHelloSignature) -> usize {
+        let id = self.entries.len();
+        for (band, key) in self.band_keys(&signature).into_iter().enumerate() {
+            self.buckets[band].entry(key).or_default().push(id);
+        }
+        self.entries.push((label, signature));
+        id
+    }
+
+    /// Number of stored signatures
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the index is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Label and signature stored under `id`
+    pub fn get(&self, id: usize) -> Option<(&L, &ClientHelloSignature)> {
+        self.entries
+            .get(id)
+            .map(|(label, signature)| (label, signature))
+    }
+
+    /// Ids sharing at least one band bucket with `signature`
+    pub fn candidates(&self, signature: &ClientHelloSignature) -> Vec<usize> {
+        let mut seen = HashSet::new();
+        let mut ids = Vec::new();
+        for (band, key) in self.band_keys(signature).into_iter().enumerate() {
+            for &id in self.buckets[band].get(&key).into_iter().flatten() {
+                if seen.insert(id) {
+                    ids.push(id);
+                }
+            }
+        }
+        ids
+    }
+
+    /// The `k` stored signatures nearest to `signature`, closest first
+    ///
+    /// Only LSH candidates are ranked, so fewer than `k` neighbors come back
+    /// when little in the index resembles the query.
+    pub fn find_k_nearest(
+        &self,
+        signature: &ClientHelloSignature,
+        k: usize,
+    ) -> Vec<Neighbor<'_, L>> {
+        let mut neighbors: Vec<Neighbor<'_, L>> = self
+            .candidates(signature)
+            .into_iter()
+            .map(|id| {
+                let (label, stored) = &self.entries[id];
+                Neighbor {
+                    id,
+                    label,
+                    signature: stored,
+                    distance: signature.distance(stored),
+                }
+            })
+            .collect();
+        neighbors.sort_by(|a, b| a.distance.total_cmp(&b.distance).then(a.id.cmp(&b.id)));
+        neighbors.truncate(k);
+        neighbors
+    }
+
+    /// Band bucket keys of `signature`
+    fn band_keys(&self, signature: &ClientHelloSignature) -> Vec<u64> {
+        let tokens = tokens(signature);
+        let minhash: Vec<u64> = self
+            .seeds
+            .iter()
+            .map(|&seed| {
+                tokens
+                    .iter()
+                    .map(|&token| splitmix64(token ^ seed))
+                    .min()
+                    .unwrap_or(u64::MAX)
+            })
+            .collect();
+
+        minhash
+            .chunks(self.rows)
+            .map(|band| {
+                let mut hasher = StableHashBuilder::new();
+                for &value in band {
+                    hasher.write_u64(value);
+                }
+                hasher.finish()
+            })
+            .collect()
+    }
+}
+
+impl<L> Default for SignatureIndex<L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Token set of the canonical signature
+fn tokens(signature: &ClientHelloSignature) -> Vec<u64> {
+    let canonical = signature.canonical();
+    let token = |kind: u8, value: u32| {
+        let mut hasher = StableHashBuilder::new();
+        hasher.write_u8(kind);
+        hasher.write_u32(value);
+        hasher.finish()
+    };
+
+    let mut tokens = vec![token(TOKEN_VERSION, canonical.version.to_u16() as u32)];
+    tokens.extend(
+        canonical
+            .cipher_suites
+            .iter()
+            .map(|&v| token(TOKEN_CIPHER, v as u32)),
+    );
+    tokens.extend(
+        canonical
+            .cipher_suites
+            .windows(2)
+            .map(|pair| token(TOKEN_CIPHER_PAIR, (pair[0] as u32) << 16 | pair[1] as u32)),
+    );
+    tokens.extend(
+        canonical
+            .extensions
+            .iter()
+            .map(|&v| token(TOKEN_EXTENSION, v as u32)),
+    );
+    tokens.extend(
+        canonical
+            .signature_algorithms
+            .iter()
+            .map(|&v| token(TOKEN_SIGNATURE_ALGORITHM, v as u32)),
+    );
+    tokens.extend(
+        canonical
+            .elliptic_curves
+            .iter()
+            .map(|&v| token(TOKEN_CURVE, v as u32)),
+    );
+    tokens.extend(
+        canonical
+            .elliptic_curve_point_formats
+            .iter()
+            .map(|&v| token(TOKEN_POINT_FORMAT, v as u32)),
+    );
+    if let Some(alpn) = &canonical.alpn {
+        let mut hasher = StableHashBuilder::new();
+        hasher.write_u8(TOKEN_ALPN);
+        hasher.write_str(alpn);
+        tokens.push(hasher.finish());
+    }
+    tokens
+}
+
+/// SplitMix64 finalizer, used as the MinHash permutation family
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls_config::{extract_signature, ClientHelloSpec};
+
+    #[test]
+    fn test_find_k_nearest() {
+        let mut index = SignatureIndex::new();
+        index.insert(
+            "chrome_103",
+            extract_signature(&ClientHelloSpec::chrome_103()),
+        );
+        index.insert(
+            "chrome_133",
+            extract_signature(&ClientHelloSpec::chrome_133()),
+        );
+        index.insert(
+            "firefox_133",
+            extract_signature(&ClientHelloSpec::firefox_133()),
+        );
+        index.insert(
+            "safari_16_0",
+            extract_signature(&ClientHelloSpec::safari_16_0()),
+        );
+        assert_eq!(index.len(), 4);
+
+        // a fresh Chrome 133 hello (new GREASE, new extension order) finds itself first
+        let query = extract_signature(&ClientHelloSpec::chrome_133());
+        let nearest = index.find_k_nearest(&query, 2);
+        assert_eq!(*nearest[0].label, "chrome_133");
+        assert_eq!(nearest[0].distance, 0.0);
+        assert!(nearest.len() <= 2);
+        assert!(nearest.windows(2).all(|w| w[0].distance <= w[1].distance));
+    }
+
+    #[test]
+    fn test_lsh_recall_and_selectivity() {
+        // near-duplicates of one signature, plus unrelated signatures
+        let mut base = ClientHelloSignature::new();
+        base.cipher_suites = (0x1301..0x1311).collect();
+        base.extensions = (0..16).collect();
+        base.signature_algorithms = vec![0x0403, 0x0804, 0x0401];
+        base.elliptic_curves = vec![0x001d, 0x0017];
+
+        let mut index = SignatureIndex::new();
+        for i in 0..50u16 {
+            let mut far = ClientHelloSignature::new();
+            far.cipher_suites = (0..12).map(|j| 0xc000 + i * 16 + j).collect();
+            far.extensions = (0..10).map(|j| 0x4000 + i * 16 + j).collect();
+            index.insert(i as usize, far);
+        }
+        let mut near = base.clone();
+        near.extensions.push(0x0033);
+        let near_id = index.insert(1000, near);
+
+        assert!(index.candidates(&base).len() < 5);
+        let nearest = index.find_k_nearest(&base, 1);
+        assert_eq!(nearest[0].id, near_id);
+        assert!(nearest[0].distance < 0.05);
+        assert!(index.similarity_threshold() > 0.5);
+    }
+}
@@ -0,0 +1,410 @@
+//! ClientHello parser for raw captured bytes
+//!
+//! The inverse of [`TLSHandshakeBuilder`](crate::tls_handshake::TLSHandshakeBuilder): decodes
+//! the first bytes a client sends (TLS records from a pcap or TCP stream, or a bare
+//! handshake message) into a [`ClientHelloSignature`], so observed traffic can be fed
+//! to [`find_best_match`](crate::tls_config::find_best_match) and the other comparison APIs.
+//!
+//! A ClientHello may be split across several records (large post-quantum key shares
+//! push it past one TCP segment) and records may arrive in arbitrary pieces, so the
+//! parser buffers until the whole handshake message is available.
+
+use crate::tls_config::grease::is_grease_value;
+use crate::tls_config::observable::TlsClientObserved;
+use crate::tls_config::signature::ClientHelloSignature;
+use crate::tls_config::version::TlsVersion;
+use crate::tls_handshake::{TLSHandshakeType, TLSRecordType};
+use fingerprint_core::dicttls::extensions::{
+    EXT_TYPE_APPLICATION_LAYER_PROTOCOL_NEGOTIATION, EXT_TYPE_EC_POINT_FORMATS,
+    EXT_TYPE_SERVER_NAME, EXT_TYPE_SIGNATURE_ALGORITHMS, EXT_TYPE_SUPPORTED_GROUPS,
+    EXT_TYPE_SUPPORTED_VERSIONS,
+};
+
+/// Largest record fragment allowed by RFC 8446 (2^14 plus expansion slack)
+const MAX_RECORD_LEN: usize = (1 << 14) + 256;
+/// Largest ClientHello accepted before giving up on a stream
+const MAX_CLIENT_HELLO_LEN: usize = 1 << 16;
+
+/// Incremental ClientHello decoder over the client side of a connection
+///
+/// # Examples
+/// ```
+/// use fingerprint_tls::tls_config::{ClientHelloParser, ClientHelloSpec};
+/// use fingerprint_tls::TLSHandshakeBuilder;
+///
+/// let record = TLSHandshakeBuilder::build_client_hello(&ClientHelloSpec::chrome_133(), "example.com").unwrap();
+/// let signature = ClientHelloParser::parse(&record).unwrap();
+/// assert_eq!(signature.sni.as_deref(), Some("example.com"));
+/// ```
+#[derive(Debug, Default)]
+pub struct ClientHelloParser {
+    /// bytes of an incomplete record
+    pending: Vec<u8>,
+    /// handshake bytes reassembled from record fragments
+    handshake: Vec<u8>,
+}
+
+impl ClientHelloParser {
+    /// Create a parser for a new connection
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append client bytes in stream order
+    ///
+    /// Returns `Ok(None)` while the ClientHello is incomplete and the signature once
+    /// it has been received. Fails as soon as the stream cannot be a ClientHello
+    /// (non-handshake record, other handshake type, malformed lengths).
+    pub fn push(&mut self, data: &[u8]) -> Result<Option<ClientHelloSignature>, String> {
+        self.pending.extend_from_slice(data);
+
+        let mut offset = 0;
+        while self.pending.len() - offset >= 5 {
+            let header = &self.pending[offset..offset + 5];
+            if header[0] != TLSRecordType::Handshake.as_u8() {
+                return Err(format!(
+                    "expected a handshake record, got content type {}",
+                    header[0]
+                ));
+            }
+            if header[1] != 0x03 {
+                return Err(format!(
+                    "unsupported record version 0x{:02x}{:02x}",
+                    header[1], header[2]
+                ));
+            }
+            let length = u16::from_be_bytes([header[3], header[4]]) as usize;
+            if length == 0 || length > MAX_RECORD_LEN {
+                return Err(format!("invalid record length {}", length));
+            }
+            if self.pending.len() - offset < 5 + length {
+                break;
+            }
+            self.handshake
+                .extend_from_slice(&self.pending[offset + 5..offset + 5 + length]);
+            offset += 5 + length;
+        }
+        self.pending.drain(..offset);
+
+        if self.handshake.len() < 4 {
+            return Ok(None);
+        }
+        let length = handshake_length(&self.handshake)?;
+        if self.handshake.len() < 4 + length {
+            return Ok(None);
+        }
+        parse_client_hello_body(&self.handshake[4..4 + length]).map(Some)
+    }
+
+    /// Parse a complete capture of the ClientHello
+    ///
+    /// `data` is either the TLS record(s) carrying it or the bare handshake message
+    /// (type, 24-bit length, body), as found in QUIC CRYPTO frames.
+    pub fn parse(data: &[u8]) -> Result<ClientHelloSignature, String> {
+        match data.first() {
+            Some(&byte) if byte == TLSRecordType::Handshake.as_u8() => Self::new()
+                .push(data)?
+                .ok_or_else(|| format!("ClientHello truncated after {} bytes", data.len())),
+            Some(&byte) if byte == TLSHandshakeType::ClientHello.as_u8() => {
+                Self::parse_handshake(data)
+            }
+            Some(&byte) => Err(format!("not a ClientHello: leading byte 0x{:02x}", byte)),
+            None => Err("no data".to_string()),
+        }
+    }
+
+    /// Parse a bare ClientHello handshake message
+    pub fn parse_handshake(data: &[u8]) -> Result<ClientHelloSignature, String> {
+        if data.len() < 4 {
+            return Err(format!("handshake header truncated: {} bytes", data.len()));
+        }
+        let length = handshake_length(data)?;
+        if data.len() < 4 + length {
+            return Err(format!(
+                "ClientHello truncated: need {} bytes, got {}",
+                4 + length,
+                data.len()
+            ));
+        }
+        parse_client_hello_body(&data[4..4 + length])
+    }
+
+    /// Parse a complete capture into its observable properties
+    pub fn parse_observed(data: &[u8]) -> Result<TlsClientObserved, String> {
+        Self::parse(data).map(|signature| TlsClientObserved::from_signature(&signature))
+    }
+}
+
+/// Body length of a ClientHello handshake header
+fn handshake_length(header: &[u8]) -> Result<usize, String> {
+    if header[0] != TLSHandshakeType::ClientHello.as_u8() {
+        return Err(format!(
+            "expected ClientHello, got handshake type {}",
+            header[0]
+        ));
+    }
+    let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+    if length > MAX_CLIENT_HELLO_LEN {
+        return Err(format!("ClientHello length {} exceeds limit", length));
+    }
+    Ok(length)
+}
+
+/// Bounds-checked reader over a byte slice
+struct Reader<'a> {
+    data: &'a [u8],
+    what: &'static str,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], what: &'static str) -> Self {
+        Self { data, what }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.data.len() < n {
+            return Err(format!(
+                "{} truncated: need {} bytes, {} left",
+                self.what,
+                n,
+                self.data.len()
+            ));
+        }
+        let (head, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Vector with a one-byte length prefix
+    fn vec8(&mut self) -> Result<&'a [u8], String> {
+        let len = self.u8()? as usize;
+        self.take(len)
+    }
+
+    /// Vector with a two-byte length prefix
+    fn vec16(&mut self) -> Result<&'a [u8], String> {
+        let len = self.u16()? as usize;
+        self.take(len)
+    }
+
+    /// Remaining bytes as big-endian u16 values
+    fn u16_list(&mut self) -> Result<Vec<u16>, String> {
+        if !self.data.len().is_multiple_of(2) {
+            return Err(format!("{} has odd length {}", self.what, self.data.len()));
+        }
+        let mut values = Vec::with_capacity(self.data.len() / 2);
+        while !self.is_empty() {
+            values.push(self.u16()?);
+        }
+        Ok(values)
+    }
+}
+
+fn parse_client_hello_body(body: &[u8]) -> Result<ClientHelloSignature, String> {
+    let mut reader = Reader::new(body, "ClientHello");
+    let legacy_version = reader.u16()?;
+    reader.take(32)?; // random
+    reader.vec8()?; // legacy_session_id
+    let cipher_suites = Reader::new(reader.vec16()?, "cipher suites").u16_list()?;
+    reader.vec8()?; // compression methods
+
+    let mut signature = ClientHelloSignature::new();
+    signature.version = TlsVersion::from_u16(legacy_version);
+    signature.cipher_suites = cipher_suites;
+
+    // SSL 3.0 / early TLS ClientHellos may end without an extensions block
+    if reader.is_empty() {
+        return Ok(signature);
+    }
+    let mut extensions = Reader::new(reader.vec16()?, "extensions");
+    while !extensions.is_empty() {
+        let ext_type = extensions.u16()?;
+        let ext_data = extensions.vec16()?;
+        signature.extensions.push(ext_type);
+        parse_extension(&mut signature, ext_type, ext_data)?;
+    }
+    Ok(signature)
+}
+
+/// Fill the signature fields carried by one extension
+fn parse_extension(
+    signature: &mut ClientHelloSignature,
+    ext_type: u16,
+    data: &[u8],
+) -> Result<(), String> {
+    match ext_type {
+        EXT_TYPE_SERVER_NAME if !data.is_empty() => {
+            let mut names = Reader::new(Reader::new(data, "server_name").vec16()?, "server_name");
+            while !names.is_empty() {
+                let name_type = names.u8()?;
+                let name = names.vec16()?;
+                // host_name is the only defined name type
+                if name_type == 0 && signature.sni.is_none() {
+                    signature.sni = Some(String::from_utf8_lossy(name).into_owned());
+                }
+            }
+        }
+        EXT_TYPE_SUPPORTED_GROUPS => {
+            let groups = Reader::new(data, "supported_groups").vec16()?;
+            signature.elliptic_curves = Reader::new(groups, "supported_groups").u16_list()?;
+        }
+        EXT_TYPE_EC_POINT_FORMATS => {
+            signature.elliptic_curve_point_formats =
+                Reader::new(data, "ec_point_formats").vec8()?.to_vec();
+        }
+        EXT_TYPE_SIGNATURE_ALGORITHMS => {
+            let algorithms = Reader::new(data, "signature_algorithms").vec16()?;
+            signature.signature_algorithms =
+                Reader::new(algorithms, "signature_algorithms").u16_list()?;
+        }
+        EXT_TYPE_APPLICATION_LAYER_PROTOCOL_NEGOTIATION => {
+            let mut protocols = Reader::new(Reader::new(data, "alpn").vec16()?, "alpn");
+            if !protocols.is_empty() {
+                let first = protocols.vec8()?;
+                signature.alpn = Some(String::from_utf8_lossy(first).into_owned());
+            }
+        }
+        EXT_TYPE_SUPPORTED_VERSIONS => {
+            let versions = Reader::new(data, "supported_versions").vec8()?;
+            let highest = Reader::new(versions, "supported_versions")
+                .u16_list()?
+                .into_iter()
+                .filter(|&v| !is_grease_value(v))
+                .max();
+            // the legacy version field is frozen at TLS 1.2; the real one is here
+            if let Some(version) = highest {
+                signature.version = TlsVersion::from_u16(version);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls_config::{
+        extract_signature, extract_wire_signature, find_best_match, ClientHelloSpec,
+    };
+    use crate::tls_handshake::TLSHandshakeBuilder;
+
+    fn chrome_record() -> Vec<u8> {
+        TLSHandshakeBuilder::build_client_hello(&ClientHelloSpec::chrome_133(), "example.com")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_built_client_hello() {
+        let spec = ClientHelloSpec::chrome_133();
+        let record = TLSHandshakeBuilder::build_client_hello(&spec, "example.com").unwrap();
+        let parsed = ClientHelloParser::parse(&record).unwrap();
+
+        assert_eq!(parsed.version, TlsVersion::V1_3);
+        assert_eq!(parsed.sni.as_deref(), Some("example.com"));
+        assert_eq!(parsed.alpn.as_deref(), Some("h2"));
+        assert!(!parsed.signature_algorithms.is_empty());
+        assert!(!parsed.elliptic_curves.is_empty());
+
+        let expected = extract_signature(&spec);
+        assert_eq!(
+            parsed.cipher_suites_without_grease(),
+            expected.cipher_suites_without_grease()
+        );
+        let mut parsed_extensions = parsed.extensions_without_grease();
+        let mut expected_extensions = expected.extensions_without_grease();
+        parsed_extensions.sort_unstable();
+        expected_extensions.sort_unstable();
+        assert_eq!(parsed_extensions, expected_extensions);
+
+        // the bare handshake message parses the same
+        let bare = ClientHelloParser::parse(&record[5..]).unwrap();
+        assert_eq!(bare, parsed);
+    }
+
+    #[test]
+    fn test_parsed_hello_matches_its_profile() {
+        let record =
+            TLSHandshakeBuilder::build_client_hello(&ClientHelloSpec::firefox_133(), "example.com")
+                .unwrap();
+        let parsed = ClientHelloParser::parse(&record).unwrap();
+        let specs = vec![
+            ClientHelloSpec::chrome_133(),
+            ClientHelloSpec::firefox_133(),
+            ClientHelloSpec::safari_16_0(),
+        ];
+        assert_eq!(find_best_match(&parsed, &specs), Some(1));
+        assert_eq!(
+            parsed.canonical(),
+            extract_wire_signature(&specs[1], "example.com")
+                .unwrap()
+                .canonical()
+        );
+
+        let observed = ClientHelloParser::parse_observed(&record).unwrap();
+        assert_eq!(observed.cipher_suites, parsed.cipher_suites);
+        assert!(observed.has_extension(EXT_TYPE_SUPPORTED_VERSIONS));
+    }
+
+    #[test]
+    fn test_stream_reassembles_fragmented_records() {
+        let record = chrome_record();
+        let expected = ClientHelloParser::parse(&record).unwrap();
+
+        // split the handshake message over two records
+        let handshake = &record[5..];
+        let (first, second) = handshake.split_at(100);
+        let mut stream = Vec::new();
+        for fragment in [first, second] {
+            stream.push(TLSRecordType::Handshake.as_u8());
+            stream.extend_from_slice(&[0x03, 0x01]);
+            stream.extend_from_slice(&(fragment.len() as u16).to_be_bytes());
+            stream.extend_from_slice(fragment);
+        }
+
+        // and deliver it a few bytes at a time
+        let mut parser = ClientHelloParser::new();
+        let mut parsed = None;
+        for chunk in stream.chunks(7) {
+            assert!(parsed.is_none(), "ClientHello completed early");
+            parsed = parser.push(chunk).unwrap();
+        }
+        assert_eq!(parsed, Some(expected));
+    }
+
+    #[test]
+    fn test_rejects_non_client_hello() {
+        assert!(ClientHelloParser::parse(&[]).is_err());
+        // application data record
+        assert!(ClientHelloParser::parse(&[0x17, 0x03, 0x03, 0x00, 0x01, 0x00]).is_err());
+        // ServerHello handshake
+        assert!(ClientHelloParser::parse(&[0x16, 0x03, 0x03, 0x00, 0x04, 0x02, 0, 0, 0]).is_err());
+
+        // truncated record
+        let record = chrome_record();
+        assert!(ClientHelloParser::parse(&record[..record.len() - 10]).is_err());
+
+        // extension length running past the block
+        let mut corrupt = record.clone();
+        let last = corrupt.len() - 1;
+        corrupt.truncate(last);
+        let record_len = (corrupt.len() - 5) as u16;
+        corrupt[3..5].copy_from_slice(&record_len.to_be_bytes());
+        let handshake_len = (corrupt.len() - 9) as u32;
+        corrupt[6..9].copy_from_slice(&handshake_len.to_be_bytes()[1..]);
+        assert!(ClientHelloParser::parse(&corrupt).is_err());
+    }
+}
@@ -6,6 +6,7 @@
 use crate::tls_config::grease::{filter_grease_values, is_grease_value};
 use crate::tls_config::version::TlsVersion;
 use fingerprint_core::dicttls::supported_groups::CurveID;
+use fingerprint_core::similarity::FeatureScore;
 use fingerprint_core::stable_hash::StableHashBuilder;
 
 /// TLS ClientHello signature
@@ -89,6 +90,109 @@ impl ClientHelloSignature {
             && self.alpn == other.alpn
     }
 
+    /// Canonical form: GREASE removed and extensions sorted
+    ///
+    /// Chrome 110+ permutes its extension order per connection, so only the
+    /// extension set is stable; cipher suite and signature algorithm order
+    /// are kept because they do identify the client.
+    pub fn canonical(&self) -> Self {
+        let mut extensions = self.extensions_without_grease();
+        extensions.sort_unstable();
+        Self {
+            version: self.version,
+            cipher_suites: self.cipher_suites_without_grease(),
+            extensions,
+            elliptic_curves: filter_grease_values(&self.elliptic_curves),
+            elliptic_curve_point_formats: self.elliptic_curve_point_formats.clone(),
+            signature_algorithms: self.signature_algorithms_without_grease(),
+            sni: self.sni.clone(),
+            alpn: self.alpn.clone(),
+        }
+    }
+
+    /// Distance to `other` in [0, 1]
+    ///
+    /// A weighted sum of per-field metrics over the canonical forms: Jaccard
+    /// distance on each value set, the discrete metric on version, ALPN, SNI
+    /// presence and on cipher suite / signature algorithm order. Every term
+    /// is a metric, so the sum satisfies the triangle inequality and is zero
+    /// only when the canonical forms agree (SNI host names aside, which
+    /// differ per site rather than per client).
+    pub fn distance(&self, other: &Self) -> f64 {
+        self.distance_terms(other)
+            .iter()
+            .map(|(_, weight, distance)| weight * distance)
+            .sum()
+    }
+
+    /// Per-field similarities behind [`distance`](Self::distance), with the
+    /// same weights, for bootstrap confidence intervals
+    pub fn feature_scores(&self, other: &Self) -> Vec<FeatureScore> {
+        self.distance_terms(other)
+            .into_iter()
+            .map(|(name, weight, distance)| FeatureScore::new(name, weight, 1.0 - distance))
+            .collect()
+    }
+
+    /// `(field, weight, distance)` of every term of [`distance`](Self::distance)
+    fn distance_terms(&self, other: &Self) -> [(&'static str, f64, f64); 10] {
+        let a = self.canonical();
+        let b = other.canonical();
+        let discrete = |equal: bool| if equal { 0.0 } else { 1.0 };
+
+        [
+            (
+                "cipher_suites",
+                DISTANCE_WEIGHTS.cipher_suites,
+                jaccard_distance(&a.cipher_suites, &b.cipher_suites),
+            ),
+            (
+                "cipher_order",
+                DISTANCE_WEIGHTS.cipher_order,
+                discrete(a.cipher_suites == b.cipher_suites),
+            ),
+            (
+                "extensions",
+                DISTANCE_WEIGHTS.extensions,
+                jaccard_distance(&a.extensions, &b.extensions),
+            ),
+            (
+                "signature_algorithms",
+                DISTANCE_WEIGHTS.signature_algorithms,
+                jaccard_distance(&a.signature_algorithms, &b.signature_algorithms),
+            ),
+            (
+                "signature_order",
+                DISTANCE_WEIGHTS.signature_order,
+                discrete(a.signature_algorithms == b.signature_algorithms),
+            ),
+            (
+                "curves",
+                DISTANCE_WEIGHTS.curves,
+                jaccard_distance(&a.elliptic_curves, &b.elliptic_curves),
+            ),
+            (
+                "point_formats",
+                DISTANCE_WEIGHTS.point_formats,
+                jaccard_distance(
+                    &a.elliptic_curve_point_formats,
+                    &b.elliptic_curve_point_formats,
+                ),
+            ),
+            (
+                "version",
+                DISTANCE_WEIGHTS.version,
+                discrete(a.version == b.version),
+            ),
+            ("alpn", DISTANCE_WEIGHTS.alpn, discrete(a.alpn == b.alpn)),
+            (
+                "sni",
+                DISTANCE_WEIGHTS.sni,
+                discrete(a.sni.is_some() == b.sni.is_some()),
+            ),
+        ]
+    }
+
     /// Calculatesignaturehashvalue ( for fastcompare)
     /// usefilter GREASE backvalue
     pub fn hash(&self) -> u64 {
@@ -105,6 +209,62 @@ impl ClientHelloSignature {
     }
 }
 
+/// Per-field weights of [`ClientHelloSignature::distance`] (sum to 1)
+struct DistanceWeights {
+    cipher_suites: f64,
+    cipher_order: f64,
+    extensions: f64,
+    signature_algorithms: f64,
+    signature_order: f64,
+    curves: f64,
+    point_formats: f64,
+    version: f64,
+    alpn: f64,
+    sni: f64,
+}
+
+const DISTANCE_WEIGHTS: DistanceWeights = DistanceWeights {
+    cipher_suites: 0.25,
+    cipher_order: 0.04,
+    extensions: 0.27,
+    signature_algorithms: 0.12,
+    signature_order: 0.02,
+    curves: 0.12,
+    point_formats: 0.03,
+    version: 0.08,
+    alpn: 0.05,
+    sni: 0.02,
+};
+
+/// Jaccard distance between the value sets of two lists (0 when both are empty)
+fn jaccard_distance<T: Ord + Copy>(a: &[T], b: &[T]) -> f64 {
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort_unstable();
+    a.dedup();
+    b.sort_unstable();
+    b.dedup();
+
+    let (mut i, mut j, mut shared) = (0, 0, 0usize);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                shared += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    let union = a.len() + b.len() - shared;
+    if union == 0 {
+        0.0
+    } else {
+        1.0 - shared as f64 / union as f64
+    }
+}
+
 impl Default for ClientHelloSignature {
     fn default() -> Self {
         Self::new()
@@ -135,6 +295,36 @@ mod tests {
         assert!(sig1.similar_to(&sig2));
     }
 
+    #[test]
+    fn test_distance_metric() {
+        let mut a = ClientHelloSignature::new();
+        a.version = TlsVersion::V1_3;
+        a.cipher_suites = vec![0x0a0a, 0x1301, 0x1302, 0x1303];
+        a.extensions = vec![0x0000, 0x0010, 0x002b];
+        a.alpn = Some("h2".to_string());
+
+        // GREASE and extension order do not count
+        let mut b = a.clone();
+        b.cipher_suites[0] = 0x2a2a;
+        b.extensions.reverse();
+        assert_eq!(a.distance(&b), 0.0);
+
+        // cipher order counts, but less than a different cipher set
+        let mut c = a.clone();
+        c.cipher_suites.swap(1, 2);
+        let mut d = a.clone();
+        d.cipher_suites = vec![0x1301, 0xc02b];
+        assert!(a.distance(&c) > 0.0);
+        assert!(a.distance(&c) < a.distance(&d));
+
+        // symmetric, bounded, triangle inequality
+        for (x, y, z) in [(&a, &c, &d), (&c, &d, &a), (&d, &a, &c)] {
+            assert_eq!(x.distance(y), y.distance(x));
+            assert!(x.distance(y) <= 1.0);
+            assert!(x.distance(z) <= x.distance(y) + y.distance(z) + 1e-12);
+        }
+    }
+
     #[test]
     fn test_has_grease() {
         let mut sig = ClientHelloSignature::new();
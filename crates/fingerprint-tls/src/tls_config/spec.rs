@@ -11,6 +11,7 @@ use crate::tls_extensions::{
 };
 use fingerprint_core::dicttls::{
     cipher_suites::{self as cs, GREASE_PLACEHOLDER as GREASE_CS},
+    extensions::{EXT_TYPE_EARLY_DATA, EXT_TYPE_PRE_SHARED_KEY},
     signature_schemes::{
         self as ss, ECDSA_WITH_P256_AND_SHA256, ECDSA_WITH_P384_AND_SHA384, PKCS1_WITH_SHA256,
         PKCS1_WITH_SHA384, PKCS1_WITH_SHA512, PSS_WITH_SHA256, PSS_WITH_SHA384, PSS_WITH_SHA512,
@@ -40,6 +41,7 @@ pub const RENEGOTIATE_ONCE_AS_CLIENT: u8 = 1;
 
 /// certificatecompressionalgorithmconstant
 pub const CERT_COMPRESSION_BROTLI: u16 = 0x0002;
+pub const CERT_COMPRESSION_ZLIB: u16 = 0x0001;
 
 /// cipher suite ID
 pub type CipherSuiteID = u16;
@@ -299,6 +301,38 @@ impl ClientHelloSpec {
         self.calculate_ja4().to_fingerprint_string()
     }
 
+    /// Whether the ClientHello offers a pre_shared_key, i.e. resumes TLS 1.3 sessions
+    pub fn offers_psk(&self) -> bool {
+        self.has_extension(EXT_TYPE_PRE_SHARED_KEY)
+    }
+
+    /// Whether the ClientHello declares early_data (0-RTT) support
+    pub fn offers_early_data(&self) -> bool {
+        self.has_extension(EXT_TYPE_EARLY_DATA)
+    }
+
+    /// Whether the ClientHello offers a hybrid post-quantum key exchange group
+    ///
+    /// Both the draft Kyber codepoint this repo calls `X25519_MLKEM768` (0x6399)
+    /// and the final X25519MLKEM768 codepoint (0x11ec) count.
+    pub fn offers_post_quantum(&self) -> bool {
+        const X25519_MLKEM768_FINAL: u16 = 0x11ec;
+        let is_pq = |group: u16| group == X25519_MLKEM768 || group == X25519_MLKEM768_FINAL;
+        self.extensions.iter().any(|e| {
+            if let Some(curves) = e.as_any().downcast_ref::<SupportedCurvesExtension>() {
+                curves.curves.iter().any(|&g| is_pq(g))
+            } else if let Some(shares) = e.as_any().downcast_ref::<KeyShareExtension>() {
+                shares.key_shares.iter().any(|k| is_pq(k.group))
+            } else {
+                false
+            }
+        })
+    }
+
+    fn has_extension(&self, id: u16) -> bool {
+        self.extensions.iter().any(|e| e.extension_id() == id)
+    }
+
     /// Create Chrome 103 fingerprint ClientHelloSpec
     /// Corresponds to Go version's Chrome_103 SpecFactory
     pub fn chrome_103() -> Self {
@@ -617,7 +617,7 @@ impl SupportedVersionsExtension {
 
 impl TLSExtension for SupportedVersionsExtension {
     fn len(&self) -> usize {
-        6 + 2 * self.versions.len() // extension_id (2) + length (2) + versions_length (1) + versions
+        5 + 2 * self.versions.len() // extension_id (2) + length (2) + versions_length (1) + versions
     }
 
     fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
@@ -1057,10 +1057,15 @@ impl TLSExtensionWriter for UtlsCompressCertExtension {
 /// Pre-Shared Key extension
 /// Pre-Shared Key Extension (RFC 8446 Section 4.2.11)
 /// Supports TLS 1.3 PSK for session resumption
+///
+/// Must be the last extension of a ClientHello; binders are computed over everything
+/// before them, so the spec builders always append it after padding.
 #[derive(Debug, Clone)]
 pub struct PreSharedKeyExtension {
-    /// Identities offered by the client
+    /// Identities offered by the client (session tickets)
     pub identities: Vec<Vec<u8>>,
+    /// Obfuscated ticket age of each identity (ticket age in ms + ticket_age_add)
+    pub obfuscated_ticket_ages: Vec<u32>,
     /// Binders (signatures) for each identity
     pub binders: Vec<Vec<u8>>,
 }
@@ -1069,6 +1074,7 @@ impl PreSharedKeyExtension {
     /// Create a new PSK extension with identities and binders
     pub fn new(identities: Vec<Vec<u8>>, binders: Vec<Vec<u8>>) -> Self {
         Self {
+            obfuscated_ticket_ages: vec![0; identities.len()],
             identities,
             binders,
         }
@@ -1076,19 +1082,29 @@ impl PreSharedKeyExtension {
 
     /// Create PSK extension for session resumption (typical case)
     pub fn for_session_resumption(session_id: Vec<u8>, binder: Vec<u8>) -> Self {
-        Self {
-            identities: vec![session_id],
-            binders: vec![binder],
-        }
+        Self::new(vec![session_id], vec![binder])
+    }
+
+    /// Set the obfuscated age of every identity, in order
+    pub fn with_obfuscated_ticket_ages(mut self, ages: Vec<u32>) -> Self {
+        self.obfuscated_ticket_ages = ages;
+        self
+    }
+
+    /// Length of the binders list including its 2-byte length prefix
+    ///
+    /// The truncated ClientHello a binder signs ends this many bytes before the end.
+    pub fn binders_len(&self) -> usize {
+        2 + self.binders.iter().map(|b| b.len() + 1).sum::<usize>()
     }
 }
 
 impl TLSExtension for PreSharedKeyExtension {
     fn len(&self) -> usize {
-        // Extension header (4) + identities list (2) + identities + binders list (2) + binders
-        let identities_len: usize = self.identities.iter().map(|id| id.len() + 4).sum();
-        let binders_len: usize = self.binders.iter().map(|b| b.len() + 1).sum();
-        4 + 2 + identities_len + 2 + binders_len
+        // Extension header (4) + identities list (2) + identities + binders list
+        // each identity: length (2) + identity + obfuscated_ticket_age (4)
+        let identities_len: usize = self.identities.iter().map(|id| id.len() + 6).sum();
+        4 + 2 + identities_len + self.binders_len()
     }
 
     fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
@@ -1112,26 +1128,24 @@ impl TLSExtension for PreSharedKeyExtension {
         let mut pos = 4;
 
         // Identities list
-        let identities_len: usize = self.identities.iter().map(|id| id.len() + 4).sum();
+        let identities_len: usize = self.identities.iter().map(|id| id.len() + 6).sum();
         buf[pos] = (identities_len >> 8) as u8;
         buf[pos + 1] = (identities_len & 0xff) as u8;
         pos += 2;
 
-        for identity in &self.identities {
-            // Identity length (4 bytes: 2 for length + 2 for obfuscated_ticket_age)
+        for (i, identity) in self.identities.iter().enumerate() {
             buf[pos] = (identity.len() >> 8) as u8;
             buf[pos + 1] = (identity.len() & 0xff) as u8;
             pos += 2;
             buf[pos..pos + identity.len()].copy_from_slice(identity);
             pos += identity.len();
-            // Obfuscated ticket age (2 bytes, typically 0 for new connections)
-            buf[pos] = 0;
-            buf[pos + 1] = 0;
-            pos += 2;
+            let age = self.obfuscated_ticket_ages.get(i).copied().unwrap_or(0);
+            buf[pos..pos + 4].copy_from_slice(&age.to_be_bytes());
+            pos += 4;
         }
 
         // Binders list
-        let binders_len: usize = self.binders.iter().map(|b| b.len() + 1).sum();
+        let binders_len = self.binders_len() - 2;
         buf[pos] = (binders_len >> 8) as u8;
         buf[pos + 1] = (binders_len & 0xff) as u8;
         pos += 2;
@@ -1198,9 +1212,8 @@ impl TLSExtension for EarlyDataExtension {
             ));
         }
 
-        // Extension ID (0x002a)
-        buf[0] = 0x00;
-        buf[1] = 0x2a;
+        buf[0] = (EXT_TYPE_EARLY_DATA >> 8) as u8;
+        buf[1] = (EXT_TYPE_EARLY_DATA & 0xff) as u8;
 
         // Extension length (0 for ClientHello, 4 for NewSessionTicket)
         buf[2] = 0;
@@ -1210,7 +1223,7 @@ impl TLSExtension for EarlyDataExtension {
     }
 
     fn extension_id(&self) -> ExtensionID {
-        0x002a // Early Data extension ID
+        EXT_TYPE_EARLY_DATA
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -1517,6 +1530,7 @@ pub fn extension_from_id(id: ExtensionID) -> Option<Box<dyn TLSExtension>> {
         }
         EXT_TYPE_COMPRESS_CERTIFICATE => Some(Box::new(UtlsCompressCertExtension::new(vec![]))),
         EXT_TYPE_PRE_SHARED_KEY => Some(Box::new(UtlsPreSharedKeyExtension)),
+        EXT_TYPE_EARLY_DATA => Some(Box::new(EarlyDataExtension::standard())),
         EXT_TYPE_ECH => Some(Box::new(GREASEEncryptedClientHelloExtension::new())),
         _ => {
             // Checkwhether is GREASE
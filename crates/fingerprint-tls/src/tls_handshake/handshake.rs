@@ -9,6 +9,8 @@
 //! } Handshake;
 //! ```
 
+use fingerprint_core::i18n::tr;
+
 /// TLS handshaketype
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -87,7 +89,12 @@ impl TLSHandshake {
             15 => TLSHandshakeType::CertificateVerify,
             16 => TLSHandshakeType::ClientKeyExchange,
             20 => TLSHandshakeType::Finished,
-            _ => return Err(format!("not知handshaketype: {}", data[0])),
+            _ => {
+                return Err(tr(
+                    "tls.unknown_handshake_type",
+                    &[("handshake_type", &data[0])],
+                ))
+            }
         };
 
         // 3 byteslength
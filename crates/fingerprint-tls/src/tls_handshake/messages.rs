@@ -16,6 +16,7 @@
 
 use crate::tls_config::ClientHelloSpec;
 use crate::tls_extensions::TLSExtension;
+use fingerprint_core::dicttls::extensions::EXT_TYPE_PRE_SHARED_KEY;
 
 /// ClientHello message
 #[derive(Debug, Clone)]
@@ -119,9 +120,14 @@ impl ClientHelloMessage {
     fn serialize_extensions(extensions: &[Box<dyn TLSExtension>], server_name: &str) -> Vec<u8> {
         let mut ext_bytes = Vec::new();
         let mut has_sni = false;
+        // pre_shared_key must stay last, so a late SNI goes in front of it
+        let mut psk_start = None;
 
         for ext in extensions {
             let ext_id = ext.extension_id();
+            if ext_id == EXT_TYPE_PRE_SHARED_KEY {
+                psk_start = Some(ext_bytes.len());
+            }
 
             // If is SNI extension (ID == 0), weneedspecialprocess
             if ext_id == 0 {
@@ -160,9 +166,12 @@ impl ClientHelloMessage {
         // Ifno SNI extension, Addan
         if !has_sni && !server_name.is_empty() {
             let sni_data = Self::build_sni_extension(server_name);
-            ext_bytes.extend_from_slice(&0u16.to_be_bytes()); // SNI extension ID
-            ext_bytes.extend_from_slice(&(sni_data.len() as u16).to_be_bytes());
-            ext_bytes.extend_from_slice(&sni_data);
+            let mut sni = Vec::with_capacity(4 + sni_data.len());
+            sni.extend_from_slice(&0u16.to_be_bytes()); // SNI extension ID
+            sni.extend_from_slice(&(sni_data.len() as u16).to_be_bytes());
+            sni.extend_from_slice(&sni_data);
+            let at = psk_start.unwrap_or(ext_bytes.len());
+            ext_bytes.splice(at..at, sni);
         }
 
         ext_bytes
@@ -291,4 +300,68 @@ mod tests {
         let name_len = u16::from_be_bytes([data[3], data[4]]) as usize;
         assert_eq!(name_len, 11); // "example.com".len()
     }
+
+    /// Extension ids of a serialized extensions block, checking every length
+    fn extension_ids(mut data: &[u8]) -> Vec<u16> {
+        let mut ids = Vec::new();
+        while !data.is_empty() {
+            let id = u16::from_be_bytes([data[0], data[1]]);
+            let len = u16::from_be_bytes([data[2], data[3]]) as usize;
+            assert!(data.len() >= 4 + len, "extension {} overruns the block", id);
+            ids.push(id);
+            data = &data[4 + len..];
+        }
+        ids
+    }
+
+    #[test]
+    fn test_pre_shared_key_is_last() {
+        let msg =
+            ClientHelloMessage::from_spec(&ClientHelloSpec::chrome_133_psk_0rtt(), "example.com")
+                .unwrap();
+        let ids = extension_ids(&msg.extensions);
+        assert_eq!(ids.last(), Some(&EXT_TYPE_PRE_SHARED_KEY));
+        assert!(ids.contains(&42)); // early_data
+
+        // a spec without SNI gets one, still in front of pre_shared_key
+        let spec = ClientHelloSpec {
+            cipher_suites: vec![0x1301],
+            compression_methods: vec![0],
+            extensions: vec![Box::new(
+                crate::tls_extensions::PreSharedKeyExtension::for_session_resumption(
+                    vec![7; 16],
+                    vec![0; 32],
+                ),
+            )],
+            tls_vers_min: 0x0304,
+            tls_vers_max: 0x0304,
+            metadata: None,
+        };
+        let msg = ClientHelloMessage::from_spec(&spec, "example.com").unwrap();
+        assert_eq!(
+            extension_ids(&msg.extensions),
+            vec![0, EXT_TYPE_PRE_SHARED_KEY]
+        );
+    }
+
+    #[test]
+    fn test_pre_shared_key_wire_format() {
+        let psk = crate::tls_extensions::PreSharedKeyExtension::for_session_resumption(
+            vec![0xaa; 3],
+            vec![0xbb; 32],
+        )
+        .with_obfuscated_ticket_ages(vec![0x01020304]);
+        let mut buf = vec![0u8; psk.len()];
+        psk.read(&mut buf).unwrap();
+
+        // identities<7..2^16-1>: identity<1..2^16-1> + uint32 obfuscated_ticket_age
+        assert_eq!(&buf[4..6], &[0, 9]);
+        assert_eq!(&buf[6..8], &[0, 3]);
+        assert_eq!(&buf[11..15], &[1, 2, 3, 4]);
+        // binders<33..2^16-1>
+        assert_eq!(&buf[15..17], &[0, 33]);
+        assert_eq!(buf[17], 32);
+        assert_eq!(buf.len(), 17 + 33);
+        assert_eq!(psk.binders_len(), 35);
+    }
 }
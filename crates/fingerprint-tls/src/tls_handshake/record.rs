@@ -10,6 +10,8 @@
 //! } TLSPlaintext;
 //! ```
 
+use fingerprint_core::i18n::tr;
+
 /// TLS recordtype
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -75,7 +77,7 @@ impl TLSRecord {
     /// from bytesstreamParse
     pub fn from_bytes(data: &[u8]) -> Result<(Self, usize), String> {
         if data.len() < 5 {
-            return Err("countdatatoo short，unable toParse TLS record".to_string());
+            return Err(tr("tls.record_too_short", &[]));
         }
 
         let content_type = match data[0] {
@@ -83,17 +85,21 @@ impl TLSRecord {
             21 => TLSRecordType::Alert,
             22 => TLSRecordType::Handshake,
             23 => TLSRecordType::ApplicationData,
-            _ => return Err(format!("not知inside容type: {}", data[0])),
+            _ => {
+                return Err(tr(
+                    "tls.unknown_content_type",
+                    &[("content_type", &data[0])],
+                ))
+            }
         };
 
         let version = u16::from_be_bytes([data[1], data[2]]);
         let length = u16::from_be_bytes([data[3], data[4]]) as usize;
 
         if data.len() < 5 + length {
-            return Err(format!(
-                "countdata不complete，need {} bytes，actualonly {} bytes",
-                5 + length,
-                data.len()
+            return Err(tr(
+                "tls.record_incomplete",
+                &[("needed", &(5 + length)), ("actual", &data.len())],
             ));
         }
 
@@ -0,0 +1,322 @@
+//! Active WebRTC leak testing
+//!
+//! [`WebRTCAnalyzer`] and [`WebRTCProtection::detect_leaks`] only inspect candidate
+//! strings a page already collected. [`LeakTester`] gathers the same addresses a
+//! browser would, from this machine:
+//! - host: the local address the OS routes each family through
+//! - server-reflexive: STUN Binding against each configured server, IPv4 and IPv6
+//! - relayed: TURN Allocate, with the `turn` feature
+//!
+//! and checks them against the proxy/VPN egress addresses the user expects. Any
+//! reflexive address outside the egress set means UDP bypasses the tunnel.
+
+use crate::stun::StunClient;
+#[cfg(feature = "turn")]
+use crate::stun::{TurnAllocation, TurnCredentials};
+use crate::{WebRTCAnalyzer, WebRTCError, WebRTCLeakReport};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+/// Public STUN servers browsers are commonly configured with
+pub const DEFAULT_STUN_SERVERS: [&str; 2] = ["stun.l.google.com:19302", "stun.cloudflare.com:3478"];
+
+/// Gathers candidates from this machine and compares them with the expected egress
+#[derive(Debug, Clone)]
+pub struct LeakTester {
+    egress: Vec<IpAddr>,
+    stun_servers: Vec<String>,
+    #[cfg(feature = "turn")]
+    turn: Option<(String, TurnCredentials)>,
+    client: StunClient,
+    browser_candidates: Vec<String>,
+}
+
+impl LeakTester {
+    /// Tester for traffic expected to leave through `egress` (proxy or VPN exit IPs)
+    pub fn new(egress: Vec<IpAddr>) -> Self {
+        Self {
+            egress,
+            stun_servers: DEFAULT_STUN_SERVERS.iter().map(|s| s.to_string()).collect(),
+            #[cfg(feature = "turn")]
+            turn: None,
+            client: StunClient::default(),
+            browser_candidates: Vec::new(),
+        }
+    }
+
+    /// Replace the STUN servers ("host:port")
+    pub fn with_stun_servers(mut self, servers: &[&str]) -> Self {
+        self.stun_servers = servers.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// Also allocate a relay on a TURN server ("host:port")
+    #[cfg(feature = "turn")]
+    pub fn with_turn(mut self, server: &str, credentials: TurnCredentials) -> Self {
+        self.turn = Some((server.to_string(), credentials));
+        self
+    }
+
+    /// Wait per STUN attempt
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client.timeout = timeout;
+        self
+    }
+
+    /// Candidates a browser gathered on this machine, to see whether it hides host
+    /// addresses behind mDNS names
+    pub fn with_browser_candidates(mut self, candidates: &[&str]) -> Self {
+        self.browser_candidates = candidates.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// Gather candidates and build the report
+    pub fn run(&self) -> Result<WebRTCLeakReport, WebRTCError> {
+        if self.egress.is_empty() {
+            return Err(WebRTCError::Other(
+                "no proxy/VPN egress address to compare with".to_string(),
+            ));
+        }
+        let mut report = WebRTCLeakReport::default();
+
+        let servers: Vec<SocketAddr> = self
+            .stun_servers
+            .iter()
+            .filter_map(|server| match server.to_socket_addrs() {
+                Ok(addresses) => Some(addresses.collect::<Vec<_>>()),
+                Err(e) => {
+                    report
+                        .findings
+                        .push(format!("cannot resolve STUN server {}: {}", server, e));
+                    None
+                }
+            })
+            .flatten()
+            .collect();
+
+        for server in &servers {
+            if let Some(local) = route_address(server) {
+                if !report.local_addresses.contains(&local) {
+                    report.local_addresses.push(local);
+                }
+            }
+            let Ok(socket) = bind_for(server) else {
+                continue;
+            };
+            match self.client.binding(&socket, *server) {
+                Ok(reflexive) => {
+                    report.ipv6_reachable |= reflexive.is_ipv6();
+                    if !report.reflexive_addresses.contains(&reflexive) {
+                        report.reflexive_addresses.push(reflexive);
+                    }
+                }
+                Err(e) => report.findings.push(e.to_string()),
+            }
+        }
+        if !servers.is_empty() && report.reflexive_addresses.is_empty() {
+            report
+                .findings
+                .push("no STUN server answered over UDP; UDP may be blocked".to_string());
+        }
+
+        #[cfg(feature = "turn")]
+        if let Some((server, credentials)) = &self.turn {
+            match self.allocate(server, credentials) {
+                Ok(allocation) => {
+                    report.relayed_address = Some(allocation.relayed);
+                    // the TURN server sees our address just like a STUN server does
+                    if let Some(mapped) = allocation.mapped {
+                        if !report.reflexive_addresses.contains(&mapped) {
+                            report.reflexive_addresses.push(mapped);
+                        }
+                    }
+                }
+                Err(e) => report.findings.push(e.to_string()),
+            }
+        }
+
+        if !self.browser_candidates.is_empty() {
+            let candidates: Vec<&str> =
+                self.browser_candidates.iter().map(String::as_str).collect();
+            let fingerprint = WebRTCAnalyzer::analyze(&candidates)?;
+            let mdns_hidden = self
+                .browser_candidates
+                .iter()
+                .any(|candidate| candidate.contains(".local"))
+                && fingerprint.local_candidates.is_empty();
+            report.mdns_hidden = Some(mdns_hidden);
+        }
+
+        self.evaluate(&mut report);
+        Ok(report)
+    }
+
+    #[cfg(feature = "turn")]
+    fn allocate(
+        &self,
+        server: &str,
+        credentials: &TurnCredentials,
+    ) -> Result<TurnAllocation, WebRTCError> {
+        let address = server
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addresses| addresses.next())
+            .ok_or_else(|| WebRTCError::Other(format!("cannot resolve TURN server {}", server)))?;
+        let socket = bind_for(&address)?;
+        self.client.allocate(&socket, address, credentials)
+    }
+
+    /// Flag every gathered address that exposes more than the egress
+    fn evaluate(&self, report: &mut WebRTCLeakReport) {
+        for reflexive in report.reflexive_addresses.clone() {
+            let ip = reflexive.ip();
+            if self.egress.contains(&ip) {
+                continue;
+            }
+            let reason = if ip.is_ipv6() && !self.egress.iter().any(IpAddr::is_ipv6) {
+                "IPv6 traffic bypasses the tunnel"
+            } else {
+                "STUN sees an address outside the egress"
+            };
+            record_leak(report, ip, format!("{}: {}", reason, ip));
+        }
+
+        // host candidates reach the page unless the browser replaces them with mDNS names
+        if report.mdns_hidden != Some(true) {
+            for local in report.local_addresses.clone() {
+                if self.egress.contains(&local) {
+                    continue;
+                }
+                let exposure = if report.mdns_hidden.is_none() {
+                    "would be exposed if the browser does not use mDNS"
+                } else {
+                    "exposed as a host candidate"
+                };
+                if report.mdns_hidden == Some(false) || !is_private(&local) {
+                    record_leak(
+                        report,
+                        local,
+                        format!("local address {} {}", local, exposure),
+                    );
+                } else {
+                    report
+                        .findings
+                        .push(format!("local address {} {}", local, exposure));
+                }
+            }
+        }
+        report.has_leaks = !report.leaked_ips.is_empty();
+    }
+}
+
+fn record_leak(report: &mut WebRTCLeakReport, ip: IpAddr, finding: String) {
+    if !report.leaked_ips.contains(&ip.to_string()) {
+        report.leaked_ips.push(ip.to_string());
+    }
+    report.findings.push(finding);
+}
+
+/// Local address the OS picks to reach `server`, i.e. the host candidate
+fn route_address(server: &SocketAddr) -> Option<IpAddr> {
+    let socket = bind_for(server).ok()?;
+    socket.connect(server).ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+}
+
+fn bind_for(server: &SocketAddr) -> Result<UdpSocket, WebRTCError> {
+    let local = if server.is_ipv6() {
+        "[::]:0"
+    } else {
+        "0.0.0.0:0"
+    };
+    UdpSocket::bind(local).map_err(|e| WebRTCError::Other(format!("bind {}: {}", local, e)))
+}
+
+/// RFC 1918 / RFC 4193 / link-local addresses
+fn is_private(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_link_local() || ip.is_loopback(),
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80 || ip.is_loopback()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stun::{
+        encode_xor_address, StunMessage, ATTR_XOR_MAPPED_ADDRESS, BINDING_REQUEST, BINDING_SUCCESS,
+    };
+
+    /// STUN server on loopback that reports `reflexive` for every binding
+    fn fake_stun_server(reflexive: SocketAddr) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let mut buffer = [0u8; 1500];
+            while let Ok((len, from)) = socket.recv_from(&mut buffer) {
+                let request = StunMessage::decode(&buffer[..len]).unwrap();
+                assert_eq!(request.message_type, BINDING_REQUEST);
+                let response = StunMessage {
+                    message_type: BINDING_SUCCESS,
+                    transaction_id: request.transaction_id,
+                    attributes: vec![(
+                        ATTR_XOR_MAPPED_ADDRESS,
+                        encode_xor_address(reflexive, &request.transaction_id),
+                    )],
+                };
+                socket.send_to(&response.encode(), from).unwrap();
+            }
+        });
+        address
+    }
+
+    #[test]
+    fn test_leak_report_against_egress() {
+        let server = fake_stun_server("203.0.113.5:40000".parse().unwrap()).to_string();
+        let vpn: IpAddr = "198.51.100.7".parse().unwrap();
+
+        let report = LeakTester::new(vec![vpn])
+            .with_stun_servers(&[&server])
+            .run()
+            .unwrap();
+        assert_eq!(
+            report.reflexive_addresses,
+            ["203.0.113.5:40000".parse().unwrap()]
+        );
+        assert!(report.has_leaks);
+        assert_eq!(report.leaked_ips, ["203.0.113.5"]);
+
+        let tunnelled = LeakTester::new(vec!["203.0.113.5".parse().unwrap()])
+            .with_stun_servers(&[&server])
+            .with_browser_candidates(&[
+                "candidate:1 1 udp 2122260223 4b2f6c1e-8d2a.local 54321 typ host",
+                "candidate:2 1 udp 1686052607 203.0.113.5 40000 typ srflx",
+            ])
+            .run()
+            .unwrap();
+        assert!(!tunnelled.has_leaks, "{:?}", tunnelled.findings);
+        assert_eq!(tunnelled.mdns_hidden, Some(true));
+        assert!(!tunnelled.ipv6_reachable);
+
+        // nothing listens on this port: UDP looks blocked, which is not a leak
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let silent_address = silent.local_addr().unwrap().to_string();
+        drop(silent);
+        let blocked = LeakTester::new(vec![vpn])
+            .with_stun_servers(&[&silent_address])
+            .with_timeout(Duration::from_millis(50))
+            .run()
+            .unwrap();
+        assert!(!blocked.has_leaks);
+        assert!(blocked
+            .findings
+            .iter()
+            .any(|f| f.contains("UDP may be blocked")));
+
+        assert!(LeakTester::new(Vec::new()).run().is_err());
+    }
+}
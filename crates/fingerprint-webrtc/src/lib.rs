@@ -5,9 +5,17 @@
 //! WebRTC 泄露防护模块
 //!
 //! 提供 WebRTC IP 泄露防护和指纹识别功能
+//!
+//! [`LeakTester`] 通过真实 STUN 绑定请求（`turn` feature 下还有 TURN 分配）主动检测
+//! 本机 IP 是否绕过代理/VPN 出口泄露
+
+pub mod leak;
+pub mod stun;
+
+pub use leak::LeakTester;
 
 use std::collections::HashSet;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 
 /// WebRTC fingerprint
 #[derive(Debug, Clone)]
@@ -180,15 +188,28 @@ impl WebRTCProtection {
         WebRTCLeakReport {
             has_leaks: !leaked_ips.is_empty(),
             leaked_ips,
+            ..WebRTCLeakReport::default()
         }
     }
 }
 
 /// WebRTC leakreport
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct WebRTCLeakReport {
     pub has_leaks: bool,
     pub leaked_ips: Vec<String>,
+    /// addresses the OS would offer as host candidates
+    pub local_addresses: Vec<IpAddr>,
+    /// server-reflexive addresses STUN servers saw
+    pub reflexive_addresses: Vec<SocketAddr>,
+    /// TURN relay address, when one was allocated
+    pub relayed_address: Option<SocketAddr>,
+    /// whether a STUN server was reached over IPv6
+    pub ipv6_reachable: bool,
+    /// whether the browser's host candidates were mDNS names; None if none were given
+    pub mdns_hidden: Option<bool>,
+    /// what was found, including non-leak problems such as unreachable servers
+    pub findings: Vec<String>,
 }
 
 #[cfg(test)]
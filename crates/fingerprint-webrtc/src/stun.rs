@@ -0,0 +1,486 @@
+//! Minimal STUN client (RFC 5389) with optional TURN allocation (RFC 5766)
+//!
+//! Enough of the protocol to learn the addresses a browser would gather as ICE
+//! candidates: a Binding request returns the server-reflexive address, and with
+//! the `turn` feature an authenticated Allocate returns the relayed address.
+
+use crate::WebRTCError;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::time::Duration;
+
+pub const MAGIC_COOKIE: u32 = 0x2112_a442;
+
+pub const BINDING_REQUEST: u16 = 0x0001;
+pub const BINDING_SUCCESS: u16 = 0x0101;
+pub const ALLOCATE_REQUEST: u16 = 0x0003;
+pub const ALLOCATE_SUCCESS: u16 = 0x0103;
+pub const ALLOCATE_ERROR: u16 = 0x0113;
+pub const REFRESH_REQUEST: u16 = 0x0004;
+
+pub const ATTR_MAPPED_ADDRESS: u16 = 0x0001;
+pub const ATTR_USERNAME: u16 = 0x0006;
+pub const ATTR_MESSAGE_INTEGRITY: u16 = 0x0008;
+pub const ATTR_ERROR_CODE: u16 = 0x0009;
+pub const ATTR_LIFETIME: u16 = 0x000d;
+pub const ATTR_REALM: u16 = 0x0014;
+pub const ATTR_NONCE: u16 = 0x0015;
+pub const ATTR_XOR_RELAYED_ADDRESS: u16 = 0x0016;
+pub const ATTR_REQUESTED_TRANSPORT: u16 = 0x0019;
+pub const ATTR_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+
+const HEADER_LEN: usize = 20;
+/// MESSAGE-INTEGRITY attribute: 4-byte header + HMAC-SHA1
+#[cfg(feature = "turn")]
+const INTEGRITY_LEN: usize = 24;
+
+/// A STUN message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StunMessage {
+    pub message_type: u16,
+    pub transaction_id: [u8; 12],
+    pub attributes: Vec<(u16, Vec<u8>)>,
+}
+
+impl StunMessage {
+    /// Request with a random transaction ID
+    pub fn request(message_type: u16) -> Self {
+        Self {
+            message_type,
+            transaction_id: rand::random(),
+            attributes: Vec::new(),
+        }
+    }
+
+    pub fn with_attribute(mut self, attribute: u16, value: Vec<u8>) -> Self {
+        self.attributes.push((attribute, value));
+        self
+    }
+
+    pub fn attribute(&self, attribute: u16) -> Option<&[u8]> {
+        self.attributes
+            .iter()
+            .find(|(kind, _)| *kind == attribute)
+            .map(|(_, value)| value.as_slice())
+    }
+
+    fn encode_attributes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (kind, value) in &self.attributes {
+            out.extend_from_slice(&kind.to_be_bytes());
+            out.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            out.extend_from_slice(value);
+            out.resize(out.len().next_multiple_of(4), 0);
+        }
+        out
+    }
+
+    fn header(&self, length: usize) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + length);
+        out.extend_from_slice(&self.message_type.to_be_bytes());
+        out.extend_from_slice(&(length as u16).to_be_bytes());
+        out.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        out.extend_from_slice(&self.transaction_id);
+        out
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let attributes = self.encode_attributes();
+        let mut out = self.header(attributes.len());
+        out.extend_from_slice(&attributes);
+        out
+    }
+
+    /// Encode with a trailing MESSAGE-INTEGRITY computed with `key`
+    #[cfg(feature = "turn")]
+    pub fn encode_with_integrity(&self, key: &[u8]) -> Vec<u8> {
+        let attributes = self.encode_attributes();
+        // the length covers the integrity attribute itself
+        let mut out = self.header(attributes.len() + INTEGRITY_LEN);
+        out.extend_from_slice(&attributes);
+        let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, key);
+        let tag = ring::hmac::sign(&key, &out);
+        out.extend_from_slice(&ATTR_MESSAGE_INTEGRITY.to_be_bytes());
+        out.extend_from_slice(&20u16.to_be_bytes());
+        out.extend_from_slice(tag.as_ref());
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, WebRTCError> {
+        let invalid =
+            |reason: &str| WebRTCError::Other(format!("invalid STUN message: {}", reason));
+        if bytes.len() < HEADER_LEN {
+            return Err(invalid("shorter than the header"));
+        }
+        let message_type = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let length = u16::from_be_bytes([bytes[2], bytes[3]]) as usize;
+        if message_type & 0xc000 != 0 || bytes[4..8] != MAGIC_COOKIE.to_be_bytes() {
+            return Err(invalid("not a STUN message"));
+        }
+        if bytes.len() < HEADER_LEN + length {
+            return Err(invalid("truncated"));
+        }
+        let transaction_id = bytes[8..20].try_into().expect("12-byte slice");
+
+        let mut attributes = Vec::new();
+        let mut rest = &bytes[HEADER_LEN..HEADER_LEN + length];
+        while rest.len() >= 4 {
+            let kind = u16::from_be_bytes([rest[0], rest[1]]);
+            let len = u16::from_be_bytes([rest[2], rest[3]]) as usize;
+            let padded = len.next_multiple_of(4);
+            if rest.len() < 4 + len {
+                return Err(invalid("truncated attribute"));
+            }
+            attributes.push((kind, rest[4..4 + len].to_vec()));
+            rest = &rest[(4 + padded).min(rest.len())..];
+        }
+        Ok(Self {
+            message_type,
+            transaction_id,
+            attributes,
+        })
+    }
+
+    /// Address from an XOR-MAPPED-ADDRESS style attribute
+    pub fn xor_address(&self, attribute: u16) -> Option<SocketAddr> {
+        let value = self.attribute(attribute)?;
+        if value.len() < 8 {
+            return None;
+        }
+        let port = u16::from_be_bytes([value[2], value[3]]) ^ (MAGIC_COOKIE >> 16) as u16;
+        let ip = match value[1] {
+            0x01 => {
+                let raw = u32::from_be_bytes(value[4..8].try_into().ok()?) ^ MAGIC_COOKIE;
+                IpAddr::V4(Ipv4Addr::from(raw))
+            }
+            0x02 if value.len() >= 20 => {
+                let mut mask = [0u8; 16];
+                mask[..4].copy_from_slice(&MAGIC_COOKIE.to_be_bytes());
+                mask[4..].copy_from_slice(&self.transaction_id);
+                let mut raw = [0u8; 16];
+                for (i, byte) in raw.iter_mut().enumerate() {
+                    *byte = value[4 + i] ^ mask[i];
+                }
+                IpAddr::V6(Ipv6Addr::from(raw))
+            }
+            _ => return None,
+        };
+        Some(SocketAddr::new(ip, port))
+    }
+
+    /// Reflexive address, preferring XOR-MAPPED-ADDRESS over the legacy attribute
+    pub fn mapped_address(&self) -> Option<SocketAddr> {
+        self.xor_address(ATTR_XOR_MAPPED_ADDRESS).or_else(|| {
+            let value = self.attribute(ATTR_MAPPED_ADDRESS)?;
+            let port = u16::from_be_bytes([*value.get(2)?, *value.get(3)?]);
+            let ip = match (value[1], value.len()) {
+                (0x01, 8) => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(&value[4..8]).ok()?)),
+                (0x02, 20) => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(&value[4..20]).ok()?)),
+                _ => return None,
+            };
+            Some(SocketAddr::new(ip, port))
+        })
+    }
+
+    /// ERROR-CODE as (code, reason)
+    pub fn error_code(&self) -> Option<(u16, String)> {
+        let value = self.attribute(ATTR_ERROR_CODE)?;
+        if value.len() < 4 {
+            return None;
+        }
+        let code = (value[2] & 0x07) as u16 * 100 + value[3] as u16;
+        Some((code, String::from_utf8_lossy(&value[4..]).into_owned()))
+    }
+}
+
+/// XOR-MAPPED-ADDRESS style attribute value for `address`
+pub fn encode_xor_address(address: SocketAddr, transaction_id: &[u8; 12]) -> Vec<u8> {
+    let port = address.port() ^ (MAGIC_COOKIE >> 16) as u16;
+    let mut out = vec![0];
+    match address.ip() {
+        IpAddr::V4(ip) => {
+            out.push(0x01);
+            out.extend_from_slice(&port.to_be_bytes());
+            out.extend_from_slice(&(u32::from(ip) ^ MAGIC_COOKIE).to_be_bytes());
+        }
+        IpAddr::V6(ip) => {
+            out.push(0x02);
+            out.extend_from_slice(&port.to_be_bytes());
+            let mut mask = [0u8; 16];
+            mask[..4].copy_from_slice(&MAGIC_COOKIE.to_be_bytes());
+            mask[4..].copy_from_slice(transaction_id);
+            out.extend(ip.octets().iter().zip(mask).map(|(a, b)| a ^ b));
+        }
+    }
+    out
+}
+
+/// Long-term TURN credentials
+#[cfg(feature = "turn")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Addresses a TURN server assigned
+#[cfg(feature = "turn")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TurnAllocation {
+    pub relayed: SocketAddr,
+    /// our address as the TURN server saw it
+    pub mapped: Option<SocketAddr>,
+}
+
+/// Request/response over UDP with retransmission
+#[derive(Debug, Clone, Copy)]
+pub struct StunClient {
+    /// wait per attempt
+    pub timeout: Duration,
+    pub attempts: u32,
+}
+
+impl Default for StunClient {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_millis(500),
+            attempts: 3,
+        }
+    }
+}
+
+impl StunClient {
+    /// Send `request` and wait for the response with the same transaction ID
+    pub fn transact(
+        &self,
+        socket: &UdpSocket,
+        server: SocketAddr,
+        request: &[u8],
+    ) -> Result<StunMessage, WebRTCError> {
+        let io = |e: std::io::Error| WebRTCError::Other(format!("STUN {}: {}", server, e));
+        let transaction_id = &request[8..HEADER_LEN];
+        socket.set_read_timeout(Some(self.timeout)).map_err(io)?;
+        let mut buffer = [0u8; 1500];
+        for _ in 0..self.attempts.max(1) {
+            socket.send_to(request, server).map_err(io)?;
+            loop {
+                match socket.recv_from(&mut buffer) {
+                    Ok((len, from)) if from == server => {
+                        match StunMessage::decode(&buffer[..len]) {
+                            Ok(response) if response.transaction_id == transaction_id => {
+                                return Ok(response)
+                            }
+                            // stray or malformed datagram: keep waiting
+                            _ => continue,
+                        }
+                    }
+                    Ok(_) => continue,
+                    Err(e)
+                        if matches!(
+                            e.kind(),
+                            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                        ) =>
+                    {
+                        break
+                    }
+                    Err(e) => return Err(io(e)),
+                }
+            }
+        }
+        Err(WebRTCError::Other(format!("STUN {}: no response", server)))
+    }
+
+    /// Server-reflexive address of `socket` as seen by `server`
+    pub fn binding(
+        &self,
+        socket: &UdpSocket,
+        server: SocketAddr,
+    ) -> Result<SocketAddr, WebRTCError> {
+        let request = StunMessage::request(BINDING_REQUEST);
+        let response = self.transact(socket, server, &request.encode())?;
+        if response.message_type != BINDING_SUCCESS {
+            return Err(WebRTCError::Other(format!(
+                "STUN {}: binding failed {:?}",
+                server,
+                response.error_code()
+            )));
+        }
+        response
+            .mapped_address()
+            .ok_or_else(|| WebRTCError::Other(format!("STUN {}: no mapped address", server)))
+    }
+
+    /// Allocate a relay on a TURN server, then release it again
+    #[cfg(feature = "turn")]
+    pub fn allocate(
+        &self,
+        socket: &UdpSocket,
+        server: SocketAddr,
+        credentials: &TurnCredentials,
+    ) -> Result<TurnAllocation, WebRTCError> {
+        let failed = |reason: String| WebRTCError::Other(format!("TURN {}: {}", server, reason));
+        // UDP (17) in the first byte
+        let transport = vec![17, 0, 0, 0];
+        let unauthenticated = StunMessage::request(ALLOCATE_REQUEST)
+            .with_attribute(ATTR_REQUESTED_TRANSPORT, transport.clone());
+        let mut response = self.transact(socket, server, &unauthenticated.encode())?;
+
+        // first 401 carries the realm; 438 means the nonce went stale
+        let mut key = Vec::new();
+        let mut realm = Vec::new();
+        let mut nonce = Vec::new();
+        for _ in 0..2 {
+            match response.error_code() {
+                Some((401 | 438, _)) if response.message_type == ALLOCATE_ERROR => {}
+                _ => break,
+            }
+            if let Some(value) = response.attribute(ATTR_REALM) {
+                realm = value.to_vec();
+            }
+            nonce = response
+                .attribute(ATTR_NONCE)
+                .ok_or_else(|| failed("challenge without nonce".to_string()))?
+                .to_vec();
+            key = long_term_key(credentials, &realm);
+            let request = StunMessage::request(ALLOCATE_REQUEST)
+                .with_attribute(ATTR_REQUESTED_TRANSPORT, transport.clone())
+                .with_attribute(ATTR_USERNAME, credentials.username.as_bytes().to_vec())
+                .with_attribute(ATTR_REALM, realm.clone())
+                .with_attribute(ATTR_NONCE, nonce.clone());
+            response = self.transact(socket, server, &request.encode_with_integrity(&key))?;
+        }
+
+        if response.message_type != ALLOCATE_SUCCESS {
+            return Err(failed(format!(
+                "allocation refused {:?}",
+                response.error_code()
+            )));
+        }
+        let relayed = response
+            .xor_address(ATTR_XOR_RELAYED_ADDRESS)
+            .ok_or_else(|| failed("no relayed address".to_string()))?;
+        let allocation = TurnAllocation {
+            relayed,
+            mapped: response.mapped_address(),
+        };
+
+        // lifetime 0 deletes the allocation; failure only delays its expiry
+        let refresh = StunMessage::request(REFRESH_REQUEST)
+            .with_attribute(ATTR_LIFETIME, 0u32.to_be_bytes().to_vec());
+        let refresh = if key.is_empty() {
+            refresh.encode()
+        } else {
+            refresh
+                .with_attribute(ATTR_USERNAME, credentials.username.as_bytes().to_vec())
+                .with_attribute(ATTR_REALM, realm)
+                .with_attribute(ATTR_NONCE, nonce)
+                .encode_with_integrity(&key)
+        };
+        let _ = self.transact(socket, server, &refresh);
+        Ok(allocation)
+    }
+}
+
+/// MD5(username ":" realm ":" password)
+#[cfg(feature = "turn")]
+pub fn long_term_key(credentials: &TurnCredentials, realm: &[u8]) -> Vec<u8> {
+    let mut input = credentials.username.as_bytes().to_vec();
+    input.push(b':');
+    input.extend_from_slice(realm);
+    input.push(b':');
+    input.extend_from_slice(credentials.password.as_bytes());
+    md5::compute(input).0.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_round_trip() {
+        let request =
+            StunMessage::request(BINDING_REQUEST).with_attribute(ATTR_USERNAME, b"abc".to_vec());
+        let encoded = request.encode();
+        assert_eq!(encoded.len(), HEADER_LEN + 8);
+        assert_eq!(StunMessage::decode(&encoded).unwrap(), request);
+        assert!(StunMessage::decode(&encoded[..10]).is_err());
+
+        for address in ["203.0.113.5:40000", "[2001:db8::1]:3478"] {
+            let address: SocketAddr = address.parse().unwrap();
+            let response = StunMessage {
+                message_type: BINDING_SUCCESS,
+                transaction_id: request.transaction_id,
+                attributes: vec![(
+                    ATTR_XOR_MAPPED_ADDRESS,
+                    encode_xor_address(address, &request.transaction_id),
+                )],
+            };
+            let decoded = StunMessage::decode(&response.encode()).unwrap();
+            assert_eq!(decoded.mapped_address(), Some(address));
+        }
+    }
+
+    #[cfg(feature = "turn")]
+    #[test]
+    fn test_turn_allocation_with_long_term_credentials() {
+        let credentials = TurnCredentials {
+            username: "alice".to_string(),
+            password: "secret".to_string(),
+        };
+        let key = long_term_key(&credentials, b"example.org");
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let mut buffer = [0u8; 1500];
+            while let Ok((len, from)) = server.recv_from(&mut buffer) {
+                let datagram = &buffer[..len];
+                let request = StunMessage::decode(datagram).unwrap();
+                let reply = |message_type, attributes| StunMessage {
+                    message_type,
+                    transaction_id: request.transaction_id,
+                    attributes,
+                };
+                let response = match request.attribute(ATTR_MESSAGE_INTEGRITY) {
+                    None => reply(
+                        ALLOCATE_ERROR,
+                        vec![
+                            (ATTR_ERROR_CODE, vec![0, 0, 4, 1]),
+                            (ATTR_REALM, b"example.org".to_vec()),
+                            (ATTR_NONCE, b"n1".to_vec()),
+                        ],
+                    ),
+                    Some(tag) => {
+                        let signed = &datagram[..len - INTEGRITY_LEN];
+                        let hmac =
+                            ring::hmac::Key::new(ring::hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, &key);
+                        assert!(ring::hmac::verify(&hmac, signed, tag).is_ok());
+                        if request.message_type == REFRESH_REQUEST {
+                            reply(0x0104, Vec::new())
+                        } else {
+                            let id = request.transaction_id;
+                            let relayed = "198.51.100.20:50000".parse().unwrap();
+                            let mapped = "203.0.113.5:40000".parse().unwrap();
+                            reply(
+                                ALLOCATE_SUCCESS,
+                                vec![
+                                    (ATTR_XOR_RELAYED_ADDRESS, encode_xor_address(relayed, &id)),
+                                    (ATTR_XOR_MAPPED_ADDRESS, encode_xor_address(mapped, &id)),
+                                ],
+                            )
+                        }
+                    }
+                };
+                server.send_to(&response.encode(), from).unwrap();
+            }
+        });
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let allocation = StunClient::default()
+            .allocate(&socket, address, &credentials)
+            .unwrap();
+        assert_eq!(allocation.relayed, "198.51.100.20:50000".parse().unwrap());
+        assert_eq!(
+            allocation.mapped,
+            Some("203.0.113.5:40000".parse().unwrap())
+        );
+    }
+}
@@ -0,0 +1,43 @@
+/// Capability Report
+/// Lists the analyzers, protocols and backends compiled into this build
+///
+/// Usage: fingerprint_capabilities [--json] [--require <name>]...
+///
+/// Exits with 1 when a `--require`d capability is missing, 2 on usage errors.
+fn usage(error: &str) -> ! {
+    eprintln!("❌ {}", error);
+    eprintln!("   Usage: fingerprint_capabilities [--json] [--require <name>]...");
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut json = false;
+    let mut required = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--require" => match args.next() {
+                Some(name) => required.push(name),
+                None => usage("--require needs a capability name"),
+            },
+            other => usage(&format!("unknown argument {}", other)),
+        }
+    }
+
+    let report = fingerprint::capabilities();
+    if json {
+        println!("{}", report.to_json());
+    } else {
+        print!("{}", report);
+    }
+
+    let missing: Vec<&String> = required.iter().filter(|name| !report.has(name)).collect();
+    if !missing.is_empty() {
+        for name in missing {
+            eprintln!("❌ missing capability: {}", name);
+        }
+        std::process::exit(1);
+    }
+}
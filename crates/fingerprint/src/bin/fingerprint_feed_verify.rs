@@ -0,0 +1,86 @@
+/// Feed Transparency Verifier
+/// Audits the locally applied profile/model feed artifacts against the transparency log
+///
+/// Usage: fingerprint_feed_verify [--root <dir>] --pin <ed25519 public key hex>...
+///
+/// Keys may also be pinned through `FINGERPRINT_FEED_PINS` (comma-separated).
+/// Exits with 1 when the audit finds problems, 2 on usage or I/O errors.
+use fingerprint_core::feed_trust::{audit_feed_state, AuditProblem, TrustStore};
+use fingerprint_core::DataDirs;
+
+fn usage(error: &str) -> ! {
+    eprintln!("❌ {}", error);
+    eprintln!("   Usage: fingerprint_feed_verify [--root <dir>] --pin <public key hex>...");
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut dirs = DataDirs::from_env();
+    let mut trust = TrustStore::new();
+    let env_pins = std::env::var("FINGERPRINT_FEED_PINS").unwrap_or_default();
+    for pin in env_pins.split(',').filter(|p| !p.trim().is_empty()) {
+        if let Err(e) = trust.pin_hex(pin) {
+            usage(&format!("FINGERPRINT_FEED_PINS: {}", e));
+        }
+    }
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--root" => match args.next() {
+                Some(root) => dirs = DataDirs::portable(root),
+                None => usage("--root needs a directory"),
+            },
+            "--pin" => match args.next().map(|key| trust.pin_hex(&key)) {
+                Some(Ok(_)) => {}
+                Some(Err(e)) => usage(&format!("--pin: {}", e)),
+                None => usage("--pin needs a public key"),
+            },
+            other => usage(&format!("unknown argument {}", other)),
+        }
+    }
+    if trust.is_empty() {
+        usage("no pinned keys");
+    }
+
+    println!("🔑 Pinned keys: {}", trust.key_ids().join(", "));
+    println!("📂 Artifacts:   {}", dirs.feed_dir().display());
+    println!("📜 Log:         {}", dirs.feed_log().display());
+    println!();
+
+    let report = match audit_feed_state(&dirs.feed_dir(), &dirs.feed_log(), &trust) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("❌ Audit failed: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    println!(
+        "{} log entries, {} artifacts, head {}",
+        report.entries, report.artifacts, report.head
+    );
+    if report.is_clean() {
+        println!("✅ Local feed state matches the transparency log");
+        return;
+    }
+    for problem in &report.problems {
+        let line = match problem {
+            AuditProblem::BrokenChain { seq, reason } => {
+                format!("log entry {}: hash chain broken ({})", seq, reason)
+            }
+            AuditProblem::UntrustedKey { seq, key_id } => {
+                format!("log entry {}: signed by unpinned key {}", seq, key_id)
+            }
+            AuditProblem::BadSignature { seq } => format!("log entry {}: bad signature", seq),
+            AuditProblem::VersionRegression { seq } => {
+                format!("log entry {}: version rollback", seq)
+            }
+            AuditProblem::Modified { artifact } => format!("{}: modified on disk", artifact),
+            AuditProblem::Missing { artifact } => format!("{}: missing on disk", artifact),
+            AuditProblem::Unlogged { artifact } => format!("{}: not in the log", artifact),
+        };
+        println!("❌ {}", line);
+    }
+    std::process::exit(1);
+}
@@ -0,0 +1,70 @@
+//! Capabilities of this build
+
+use fingerprint_core::{CapabilityKind, CapabilityReport, CapabilitySet};
+
+/// Analyzers, protocols and backends compiled into this build
+///
+/// Covers the crates `fingerprint` is built from; optional crates that are
+/// left out are listed as missing under the feature that would add them.
+pub fn capabilities() -> CapabilityReport {
+    let report = CapabilityReport::new(env!("CARGO_PKG_VERSION"))
+        .with(fingerprint_core::capabilities::capabilities())
+        .with(fingerprint_tls::capabilities())
+        .with(fingerprint_http::capabilities());
+
+    #[cfg(feature = "dns")]
+    let report = report.with(fingerprint_dns::capabilities());
+    #[cfg(feature = "defense")]
+    let report = report.with(fingerprint_defense::capabilities());
+
+    report.with(
+        CapabilitySet::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+            .builtin(CapabilityKind::Analyzer, "browser-profiles")
+            .optional(
+                CapabilityKind::Protocol,
+                "dns",
+                "dns",
+                cfg!(feature = "dns"),
+            )
+            .optional(
+                CapabilityKind::Analyzer,
+                "defense",
+                "defense",
+                cfg!(feature = "defense"),
+            )
+            .optional(
+                CapabilityKind::Analyzer,
+                "api-noise",
+                "api-noise",
+                cfg!(feature = "api-noise"),
+            )
+            .optional(
+                CapabilityKind::Service,
+                "export",
+                "export",
+                cfg!(feature = "export"),
+            )
+            .into_vec(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_every_compiled_crate() {
+        let report = capabilities();
+        let providers = report.providers();
+        for name in [
+            "fingerprint",
+            "fingerprint-core",
+            "fingerprint-tls",
+            "fingerprint-http",
+        ] {
+            assert_eq!(providers[name], env!("CARGO_PKG_VERSION"));
+        }
+        assert!(report.has("ja4h"));
+        assert_eq!(report.has("http2"), cfg!(feature = "http2"));
+    }
+}
@@ -0,0 +1,786 @@
+//! Identity entropy budget
+//!
+//! Estimates how identifying a generated identity is: each observable attribute
+//! (JA4, HTTP/2 settings, header values, hardware) contributes `-log2(share)` bits, where
+//! `share` is the fraction of the population that presents the same value. The report
+//! breaks the total down per surface so callers can see which attributes to randomize or
+//! pin to a more common value.
+//!
+//! Attributes derived from the same underlying choice (the browser build decides JA4,
+//! HTTP/2 settings and the User-Agent version together) are grouped and only the most
+//! identifying member of a group counts toward the total.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use fingerprint_core::types::BrowserType;
+use fingerprint_headers::headers::HTTPHeaders;
+use fingerprint_profiles::profiles::{mapped_tls_clients, BrowserProfile};
+
+use crate::random::FingerprintResult;
+
+/// Observable surface an attribute belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Surface {
+    Tls,
+    Http2,
+    Headers,
+    Hardware,
+}
+
+impl fmt::Display for Surface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Surface::Tls => "tls",
+            Surface::Http2 => "http2",
+            Surface::Headers => "headers",
+            Surface::Hardware => "hardware",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One observable attribute value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdentityAttribute {
+    pub surface: Surface,
+    pub name: String,
+    pub value: String,
+}
+
+/// Attributes a generated identity exposes
+#[derive(Debug, Clone, Default)]
+pub struct Identity {
+    attributes: Vec<IdentityAttribute>,
+}
+
+impl Identity {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set an attribute, replacing an existing value for the same surface and name
+    pub fn set(&mut self, surface: Surface, name: &str, value: impl Into<String>) {
+        let value = value.into();
+        match self
+            .attributes
+            .iter_mut()
+            .find(|a| a.surface == surface && a.name == name)
+        {
+            Some(attr) => attr.value = value,
+            None => self.attributes.push(IdentityAttribute {
+                surface,
+                name: name.to_string(),
+                value,
+            }),
+        }
+    }
+
+    /// Builder form of [`Identity::set`]
+    pub fn with_attribute(
+        mut self,
+        surface: Surface,
+        name: &str,
+        value: impl Into<String>,
+    ) -> Self {
+        self.set(surface, name, value);
+        self
+    }
+
+    pub fn attributes(&self) -> &[IdentityAttribute] {
+        &self.attributes
+    }
+
+    /// TLS, HTTP/2 and header attributes of a browser profile
+    pub fn from_profile(profile: &BrowserProfile) -> Self {
+        let mut identity = Self::new();
+        identity.set(Surface::Tls, "ja4", profile.tls_config.ja4_string());
+        identity.set(Surface::Http2, "settings", http2_settings_value(profile));
+        identity.set(
+            Surface::Headers,
+            "browser",
+            browser_value(
+                &profile.metadata.browser_name,
+                profile.metadata.browser_version,
+            ),
+        );
+        identity.with_headers(&profile.http_headers)
+    }
+
+    /// Identity of a randomly generated fingerprint
+    ///
+    /// TLS and HTTP/2 attributes are taken from the profile the result was built from;
+    /// headers come from the result itself, so an OS override is reflected.
+    pub fn from_fingerprint(result: &FingerprintResult) -> Self {
+        let identity = mapped_tls_clients()
+            .values()
+            .find(|profile| profile.id() == result.profile_id)
+            .map(Self::from_profile)
+            .unwrap_or_default();
+        identity.with_headers(&result.headers)
+    }
+
+    /// Add header attributes
+    pub fn with_headers(mut self, headers: &HTTPHeaders) -> Self {
+        self.set(
+            Surface::Headers,
+            "platform",
+            ua_platform(&headers.user_agent),
+        );
+        self.set(
+            Surface::Headers,
+            "accept_language",
+            primary_language(&headers.accept_language),
+        );
+        self.set(
+            Surface::Headers,
+            "accept_encoding",
+            headers.accept_encoding.clone(),
+        );
+        self
+    }
+
+    /// Add hardware attributes
+    pub fn with_hardware(mut self, hardware: &fingerprint_hardware::HardwareFingerprint) -> Self {
+        self.set(
+            Surface::Hardware,
+            "cpu_cores",
+            hardware.cpu_cores.to_string(),
+        );
+        self.set(
+            Surface::Hardware,
+            "memory_gb",
+            hardware.system_memory_gb.to_string(),
+        );
+        let (width, height) = hardware.screen_resolution;
+        self.set(
+            Surface::Hardware,
+            "screen_resolution",
+            format!("{}x{}", width, height),
+        );
+        self.set(
+            Surface::Hardware,
+            "gpu_vendor",
+            gpu_vendor(&hardware.gpu_model),
+        );
+        self.set(
+            Surface::Hardware,
+            "device_type",
+            format!("{:?}", hardware.device_type).to_lowercase(),
+        );
+        self
+    }
+}
+
+fn browser_value(name: &str, version: u32) -> String {
+    format!("{} {}", name.to_lowercase(), version)
+}
+
+fn http2_settings_value(profile: &BrowserProfile) -> String {
+    profile
+        .http2_settings_order
+        .iter()
+        .map(|id| {
+            let value = profile.http2_settings.get(id).copied().unwrap_or_default();
+            format!("{}:{}", id, value)
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn ua_platform(user_agent: &str) -> &'static str {
+    if user_agent.contains("Android") {
+        "android"
+    } else if user_agent.contains("iPhone") || user_agent.contains("iPad") {
+        "ios"
+    } else if user_agent.contains("Windows") {
+        "windows"
+    } else if user_agent.contains("Macintosh") {
+        "macos"
+    } else if user_agent.contains("Linux") || user_agent.contains("X11") {
+        "linux"
+    } else {
+        "other"
+    }
+}
+
+/// First language tag of an Accept-Language value, lowercased
+fn primary_language(accept_language: &str) -> String {
+    accept_language
+        .split(',')
+        .next()
+        .and_then(|tag| tag.split(';').next())
+        .unwrap_or("")
+        .trim()
+        .to_lowercase()
+}
+
+fn gpu_vendor(gpu_model: &str) -> &'static str {
+    let model = gpu_model.to_lowercase();
+    if model.contains("nvidia") || model.contains("geforce") || model.contains("rtx") {
+        "nvidia"
+    } else if model.contains("amd") || model.contains("radeon") {
+        "amd"
+    } else if model.contains("intel") {
+        "intel"
+    } else if model.contains("adreno") {
+        "qualcomm"
+    } else if model.contains("mali") {
+        "arm"
+    } else if model.contains("apple")
+        || ["m1", "m2", "m3", "m4"]
+            .iter()
+            .any(|m| model.starts_with(m))
+    {
+        "apple"
+    } else {
+        "other"
+    }
+}
+
+/// Value distribution of one attribute in the population
+#[derive(Debug, Clone)]
+pub struct AttributeDistribution {
+    shares: HashMap<String, f64>,
+    /// Share assumed for values not in the table
+    unseen_share: f64,
+}
+
+impl AttributeDistribution {
+    pub fn new(shares: &[(&str, f64)], unseen_share: f64) -> Self {
+        Self {
+            shares: shares.iter().map(|(v, s)| (v.to_string(), *s)).collect(),
+            unseen_share,
+        }
+    }
+
+    /// Population share of a value and whether it was in the table
+    pub fn share(&self, value: &str) -> (f64, bool) {
+        match self.shares.get(value) {
+            Some(share) => (*share, true),
+            None => (self.unseen_share, false),
+        }
+    }
+}
+
+/// Population the identity is compared against
+#[derive(Debug, Clone)]
+pub struct PopulationModel {
+    /// Number of distinct users the identity hides among
+    population_size: f64,
+    distributions: HashMap<(Surface, String), AttributeDistribution>,
+    /// Attributes decided by the same underlying choice
+    groups: Vec<Vec<(Surface, String)>>,
+}
+
+impl PopulationModel {
+    pub fn new(population_size: f64) -> Self {
+        Self {
+            population_size,
+            distributions: HashMap::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    /// Set the distribution of an attribute
+    pub fn with_distribution(
+        mut self,
+        surface: Surface,
+        name: &str,
+        distribution: AttributeDistribution,
+    ) -> Self {
+        self.distributions
+            .insert((surface, name.to_string()), distribution);
+        self
+    }
+
+    /// Declare attributes as correlated; only the largest contribution is counted
+    pub fn with_group(mut self, members: &[(Surface, &str)]) -> Self {
+        self.groups.push(
+            members
+                .iter()
+                .map(|(surface, name)| (*surface, name.to_string()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Empirical model from observed identities
+    ///
+    /// Values never observed get the share of a single extra observation.
+    pub fn from_observations(population: &[Identity]) -> Self {
+        let total = population.len() as f64;
+        let mut counts: HashMap<(Surface, String), HashMap<String, f64>> = HashMap::new();
+        for identity in population {
+            for attr in identity.attributes() {
+                *counts
+                    .entry((attr.surface, attr.name.clone()))
+                    .or_default()
+                    .entry(attr.value.clone())
+                    .or_default() += 1.0;
+            }
+        }
+
+        let mut model = Self::new(total);
+        for (key, values) in counts {
+            let distribution = AttributeDistribution {
+                shares: values.into_iter().map(|(v, c)| (v, c / total)).collect(),
+                unseen_share: 1.0 / (total + 1.0),
+            };
+            model.distributions.insert(key, distribution);
+        }
+        model
+    }
+
+    /// Approximate global web population
+    ///
+    /// Browser-derived attributes (JA4, HTTP/2 settings, browser version) are spread over
+    /// the bundled profiles according to browser market share; platform, language and
+    /// hardware shares follow public usage statistics.
+    pub fn builtin() -> Self {
+        let mut ja4: HashMap<String, f64> = HashMap::new();
+        let mut http2: HashMap<String, f64> = HashMap::new();
+        let mut browser: HashMap<String, f64> = HashMap::new();
+
+        let profiles = mapped_tls_clients();
+        let mut per_family: HashMap<String, usize> = HashMap::new();
+        for profile in profiles.values() {
+            *per_family
+                .entry(profile.metadata.browser_name.to_lowercase())
+                .or_default() += 1;
+        }
+        for profile in profiles.values() {
+            let family = profile.metadata.browser_name.to_lowercase();
+            let weight = browser_share(&family) / per_family[&family] as f64;
+            *ja4.entry(profile.tls_config.ja4_string()).or_default() += weight;
+            *http2.entry(http2_settings_value(profile)).or_default() += weight;
+            *browser
+                .entry(browser_value(
+                    &profile.metadata.browser_name,
+                    profile.metadata.browser_version,
+                ))
+                .or_default() += weight;
+        }
+        let table = |shares: HashMap<String, f64>, unseen_share: f64| AttributeDistribution {
+            shares,
+            unseen_share,
+        };
+
+        Self::new(4.0e9)
+            .with_distribution(Surface::Tls, "ja4", table(ja4, 1e-4))
+            .with_distribution(Surface::Http2, "settings", table(http2, 1e-4))
+            .with_distribution(Surface::Headers, "browser", table(browser, 1e-4))
+            .with_distribution(
+                Surface::Headers,
+                "platform",
+                AttributeDistribution::new(
+                    &[
+                        ("android", 0.44),
+                        ("windows", 0.27),
+                        ("ios", 0.18),
+                        ("macos", 0.06),
+                        ("linux", 0.02),
+                    ],
+                    0.03,
+                ),
+            )
+            .with_distribution(
+                Surface::Headers,
+                "accept_language",
+                AttributeDistribution::new(
+                    &[
+                        ("en-us", 0.30),
+                        ("zh-cn", 0.12),
+                        ("es-es", 0.05),
+                        ("en-gb", 0.04),
+                        ("pt-br", 0.04),
+                        ("de-de", 0.04),
+                        ("fr-fr", 0.04),
+                        ("ja-jp", 0.03),
+                        ("ru-ru", 0.03),
+                        ("en", 0.03),
+                        ("ko-kr", 0.02),
+                        ("it-it", 0.02),
+                    ],
+                    0.005,
+                ),
+            )
+            .with_distribution(
+                Surface::Headers,
+                "accept_encoding",
+                AttributeDistribution::new(
+                    &[
+                        ("gzip, deflate, br, zstd", 0.70),
+                        ("gzip, deflate, br", 0.28),
+                    ],
+                    0.01,
+                ),
+            )
+            .with_distribution(
+                Surface::Hardware,
+                "cpu_cores",
+                AttributeDistribution::new(
+                    &[
+                        ("8", 0.34),
+                        ("4", 0.24),
+                        ("6", 0.10),
+                        ("12", 0.09),
+                        ("16", 0.07),
+                        ("2", 0.05),
+                        ("10", 0.04),
+                        ("20", 0.02),
+                        ("24", 0.02),
+                    ],
+                    0.005,
+                ),
+            )
+            .with_distribution(
+                Surface::Hardware,
+                "memory_gb",
+                AttributeDistribution::new(
+                    &[
+                        ("8", 0.32),
+                        ("16", 0.26),
+                        ("4", 0.16),
+                        ("32", 0.09),
+                        ("6", 0.06),
+                        ("12", 0.05),
+                        ("64", 0.02),
+                        ("2", 0.02),
+                    ],
+                    0.005,
+                ),
+            )
+            .with_distribution(
+                Surface::Hardware,
+                "screen_resolution",
+                AttributeDistribution::new(
+                    &[
+                        ("1920x1080", 0.22),
+                        ("1536x864", 0.08),
+                        ("1366x768", 0.06),
+                        ("2560x1440", 0.05),
+                        ("1440x900", 0.03),
+                        ("1280x720", 0.03),
+                        ("390x844", 0.05),
+                        ("393x873", 0.04),
+                        ("414x896", 0.03),
+                        ("412x915", 0.04),
+                        ("360x800", 0.04),
+                    ],
+                    0.002,
+                ),
+            )
+            .with_distribution(
+                Surface::Hardware,
+                "gpu_vendor",
+                AttributeDistribution::new(
+                    &[
+                        ("qualcomm", 0.25),
+                        ("intel", 0.20),
+                        ("apple", 0.20),
+                        ("nvidia", 0.14),
+                        ("arm", 0.12),
+                        ("amd", 0.07),
+                    ],
+                    0.02,
+                ),
+            )
+            .with_distribution(
+                Surface::Hardware,
+                "device_type",
+                AttributeDistribution::new(
+                    &[
+                        ("phone", 0.58),
+                        ("laptop", 0.22),
+                        ("desktop", 0.16),
+                        ("tablet", 0.04),
+                    ],
+                    0.01,
+                ),
+            )
+            .with_group(&[
+                (Surface::Tls, "ja4"),
+                (Surface::Http2, "settings"),
+                (Surface::Headers, "browser"),
+                (Surface::Headers, "accept_encoding"),
+            ])
+            .with_group(&[
+                (Surface::Hardware, "screen_resolution"),
+                (Surface::Hardware, "device_type"),
+            ])
+    }
+
+    /// Bits needed to single out one member of the population
+    pub fn population_bits(&self) -> f64 {
+        self.population_size.max(1.0).log2()
+    }
+
+    fn group_of(&self, surface: Surface, name: &str) -> Option<usize> {
+        self.groups
+            .iter()
+            .position(|g| g.iter().any(|(s, n)| *s == surface && n == name))
+    }
+}
+
+/// Browser market share by family
+fn browser_share(family: &str) -> f64 {
+    match BrowserType::from_str(family) {
+        Some(BrowserType::Chrome) => 0.66,
+        Some(BrowserType::Safari) => 0.18,
+        Some(BrowserType::Edge) => 0.05,
+        Some(BrowserType::Firefox) => 0.03,
+        Some(BrowserType::Opera) => 0.02,
+        None => 0.01,
+    }
+}
+
+/// Surprisal of one attribute
+#[derive(Debug, Clone)]
+pub struct AttributeEntropy {
+    pub surface: Surface,
+    pub name: String,
+    pub value: String,
+    /// Population share of the value
+    pub share: f64,
+    /// `-log2(share)`
+    pub bits: f64,
+    /// Whether the population model covers the attribute
+    pub modeled: bool,
+    /// Whether the bits are already counted through a correlated attribute
+    pub redundant: bool,
+}
+
+/// Bits contributed by one surface
+#[derive(Debug, Clone)]
+pub struct SurfaceEntropy {
+    pub surface: Surface,
+    /// Non-redundant bits of the surface
+    pub bits: f64,
+    pub attributes: Vec<AttributeEntropy>,
+}
+
+/// Entropy budget of an identity
+#[derive(Debug, Clone)]
+pub struct EntropyReport {
+    pub surfaces: Vec<SurfaceEntropy>,
+    /// Sum of non-redundant bits, capped at the population bits
+    pub total_bits: f64,
+    /// Bits needed to single out one member of the population
+    pub population_bits: f64,
+}
+
+impl EntropyReport {
+    /// Expected number of population members sharing this identity
+    pub fn anonymity_set(&self) -> f64 {
+        2f64.powf(self.population_bits - self.total_bits)
+    }
+
+    /// Whether the identity is expected to be unique in the population
+    pub fn is_unique(&self) -> bool {
+        self.anonymity_set() < 2.0
+    }
+
+    pub fn surface(&self, surface: Surface) -> Option<&SurfaceEntropy> {
+        self.surfaces.iter().find(|s| s.surface == surface)
+    }
+
+    /// Non-redundant attributes ordered by contribution, most identifying first
+    pub fn top_contributors(&self, n: usize) -> Vec<&AttributeEntropy> {
+        let mut attrs: Vec<&AttributeEntropy> = self
+            .surfaces
+            .iter()
+            .flat_map(|s| &s.attributes)
+            .filter(|a| !a.redundant)
+            .collect();
+        attrs.sort_by(|a, b| b.bits.total_cmp(&a.bits));
+        attrs.truncate(n);
+        attrs
+    }
+}
+
+impl fmt::Display for EntropyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Identity entropy: {:.1} / {:.1} bits (anonymity set ~{:.0})",
+            self.total_bits,
+            self.population_bits,
+            self.anonymity_set()
+        )?;
+        for surface in &self.surfaces {
+            writeln!(f, "  {:<8} {:>5.1} bits", surface.surface, surface.bits)?;
+            for attr in &surface.attributes {
+                writeln!(
+                    f,
+                    "    {:<18} {:>5.1} bits  share {:.4}{}{}  {}",
+                    attr.name,
+                    attr.bits,
+                    attr.share,
+                    if attr.modeled { "" } else { " (unmodeled)" },
+                    if attr.redundant { " (correlated)" } else { "" },
+                    attr.value
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Estimates identifying information of identities against a population model
+pub struct EntropyEstimator {
+    model: PopulationModel,
+}
+
+impl EntropyEstimator {
+    pub fn new(model: PopulationModel) -> Self {
+        Self { model }
+    }
+
+    pub fn builtin() -> Self {
+        Self::new(PopulationModel::builtin())
+    }
+
+    pub fn model(&self) -> &PopulationModel {
+        &self.model
+    }
+
+    /// Estimate the entropy budget of an identity
+    ///
+    /// Attributes the model has no distribution for are reported with zero bits and
+    /// flagged as unmodeled.
+    pub fn estimate(&self, identity: &Identity) -> EntropyReport {
+        let mut attributes: Vec<AttributeEntropy> = identity
+            .attributes()
+            .iter()
+            .map(|attr| {
+                let (share, modeled) = match self
+                    .model
+                    .distributions
+                    .get(&(attr.surface, attr.name.clone()))
+                {
+                    Some(distribution) => distribution.share(&attr.value),
+                    None => (1.0, false),
+                };
+                let share = share.clamp(f64::MIN_POSITIVE, 1.0);
+                AttributeEntropy {
+                    surface: attr.surface,
+                    name: attr.name.clone(),
+                    value: attr.value.clone(),
+                    share,
+                    bits: -share.log2(),
+                    modeled,
+                    redundant: false,
+                }
+            })
+            .collect();
+
+        // keep only the largest contribution of each correlated group
+        let mut best: HashMap<usize, usize> = HashMap::new();
+        for (i, attr) in attributes.iter().enumerate() {
+            if let Some(group) = self.model.group_of(attr.surface, &attr.name) {
+                let entry = best.entry(group).or_insert(i);
+                if attributes[*entry].bits < attr.bits {
+                    *entry = i;
+                }
+            }
+        }
+        for (i, attr) in attributes.iter_mut().enumerate() {
+            if let Some(group) = self.model.group_of(attr.surface, &attr.name) {
+                attr.redundant = best[&group] != i;
+            }
+        }
+
+        let mut surfaces: Vec<SurfaceEntropy> = Vec::new();
+        for attr in attributes {
+            let surface = match surfaces.iter_mut().find(|s| s.surface == attr.surface) {
+                Some(surface) => surface,
+                None => {
+                    surfaces.push(SurfaceEntropy {
+                        surface: attr.surface,
+                        bits: 0.0,
+                        attributes: Vec::new(),
+                    });
+                    surfaces.last_mut().expect("just pushed")
+                }
+            };
+            if !attr.redundant {
+                surface.bits += attr.bits;
+            }
+            surface.attributes.push(attr);
+        }
+        surfaces.sort_by_key(|s| s.surface);
+
+        let population_bits = self.model.population_bits();
+        let total_bits = surfaces
+            .iter()
+            .map(|s| s.bits)
+            .sum::<f64>()
+            .min(population_bits);
+
+        EntropyReport {
+            surfaces,
+            total_bits,
+            population_bits,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_estimate_of_profile() {
+        let profiles = mapped_tls_clients();
+        let chrome = profiles.get("chrome_133").expect("chrome_133 profile");
+        let identity = Identity::from_profile(chrome);
+
+        let estimator = EntropyEstimator::builtin();
+        let report = estimator.estimate(&identity);
+
+        let tls = report.surface(Surface::Tls).expect("tls surface");
+        assert!(tls.attributes[0].modeled);
+        assert!(tls.attributes[0].bits > 0.0);
+        assert!(report.total_bits > 0.0 && report.total_bits < report.population_bits);
+        assert!(!report.is_unique());
+        assert!(report.to_string().contains("ja4"));
+    }
+
+    #[test]
+    fn test_rare_hardware_dominates_budget() {
+        let hardware = fingerprint_hardware::HardwareDetector::detect(
+            64,
+            "NVIDIA RTX 4090",
+            256,
+            1.0,
+            5120,
+            2880,
+        )
+        .unwrap();
+        let identity = Identity::new()
+            .with_attribute(Surface::Headers, "platform", "windows")
+            .with_hardware(&hardware);
+
+        let report = EntropyEstimator::builtin().estimate(&identity);
+        let top = report.top_contributors(2);
+        assert_eq!(top[0].name, "screen_resolution");
+        assert!(top[0].share <= 0.005);
+        // screen and device type are correlated, only one of them counts
+        let hw = report.surface(Surface::Hardware).unwrap();
+        assert_eq!(hw.attributes.iter().filter(|a| a.redundant).count(), 1);
+    }
+
+    #[test]
+    fn test_empirical_model() {
+        let common = Identity::new().with_attribute(Surface::Tls, "ja4", "a");
+        let rare = Identity::new().with_attribute(Surface::Tls, "ja4", "b");
+        let mut population = vec![common.clone(); 7];
+        population.push(rare.clone());
+
+        let estimator = EntropyEstimator::new(PopulationModel::from_observations(&population));
+        assert!((estimator.estimate(&common).total_bits - (8.0f64 / 7.0).log2()).abs() < 1e-9);
+        assert!((estimator.estimate(&rare).total_bits - 3.0).abs() < 1e-9);
+        assert!(estimator.estimate(&rare).is_unique());
+    }
+}
@@ -7,6 +7,7 @@
 //! - ✅ **Real browser fingerprints**: 90+ real browser fingerprints (Chrome, Firefox, Safari, Opera, Edge)
 //! - ✅ **Real TLS configuration**: Complete TLS Client Hello Spec (cipher suite, elliptic curve, extension, etc.)
 //! - ✅ **JA4 fingerprint generation**: Complete JA4 TLS client fingerprint generation (sorted and unsorted versions)
+//! - ✅ **JA4+ family**: JA4H (HTTP requests), JA4L (latency/distance) and JA4X (X.509 certificates)
 //! - ✅ **Fingerprint comparison**: Support fingerprint similarity comparison and best match finding
 //! - ✅ **GREASE processing**: Complete GREASE value filtering and handling
 //! - ✅ **Mobile support**: iOS and Android mobile device fingerprints
@@ -19,11 +20,34 @@
 //! - ✅ **Rust standards**: Strictly follows Rust language standards and best practices
 //! - ✅ **Independent library**: Does not depend on other TLS client libraries
 //! - ✅ **Code quality**: Passes all Clippy checks, follows Rust best practices
+//!
+//! ## API stability
+//!
+//! - [`prelude`] is the supported surface; `use fingerprint::prelude::*;` is the recommended import.
+//! - Root re-exports and the `fingerprint_*` sub-crate paths follow semver, checked in CI by
+//!   `cargo semver-checks` against the last release tag.
+//! - Experimental APIs live in `fingerprint::unstable` behind the `unstable` feature and are
+//!   exempt from semver.
+//! - Items are never removed without a deprecation period: they are first marked
+//!   `#[deprecated(since = "...")]` with a pointer to the replacement and kept as a shim for
+//!   at least one minor release.
 
+mod capabilities;
+#[cfg(feature = "unstable")]
+mod entropy;
 #[cfg(feature = "export")]
 pub mod export;
+pub mod prelude;
 pub mod random;
+#[cfg(feature = "unstable")]
+mod rotation;
+#[cfg(feature = "unstable")]
+pub mod unstable;
 /// Re-export types module from fingerprint_core for backward compatibility
+#[deprecated(
+    since = "2.1.0",
+    note = "use `fingerprint::prelude` or the root re-exports (e.g. `fingerprint::OPERATING_SYSTEMS`)"
+)]
 pub mod types {
     pub use fingerprint_core::types::*;
 }
@@ -32,7 +56,7 @@ pub mod types {
 pub use fingerprint_core::{
     extract_chrome_version, extract_platform, infer_browser_from_profile_name, is_mobile_profile,
     random_choice, random_choice_string, BrowserType, OperatingSystem, OperatingSystems,
-    UserAgentTemplate,
+    UserAgentTemplate, OPERATING_SYSTEMS,
 };
 pub use fingerprint_headers::{
     chrome_header_priority, chrome_http2_settings, chrome_pseudo_header_order,
@@ -43,15 +67,24 @@ pub use fingerprint_headers::{
     CHROME_CONNECTION_FLOW,
 };
 pub use fingerprint_http::{
-    Cookie, CookieStore, DNSHelper, HttpClient, HttpClientConfig, HttpClientError, HttpMethod,
-    HttpRequest, HttpResponse, ProxyConfig, ProxyType, ReportFormat, ReportSection, SameSite,
+    Cookie, CookieStore, DNSHelper, EchPolicy, EchResolver, ExperimentalFeature, HttpClient,
+    HttpClientConfig, HttpClientError, HttpMethod, HttpRequest, HttpResponse, Ja4hPayload,
+    Ja4hSignature, ProxyChain, ProxyConfig, ProxyType, ReportFormat, ReportSection, ResponseStream,
+    RetryBudget, RetryPolicy, RevocationChecker, RevocationPolicy, SameSite, StapledOcsp,
     TlsConnector, ValidationReport,
 };
 
+pub use fingerprint_http::runtime_stats as http_runtime_stats;
+
+#[cfg(feature = "experimental-webtransport")]
+pub use fingerprint_http::WebTransportSession;
+
 #[cfg(feature = "connection-pool")]
 pub use fingerprint_http::{ConnectionPoolManager, PoolManagerConfig, PoolStats};
 
 pub use fingerprint_profiles::*;
+
+pub use capabilities::capabilities;
 pub use fingerprint_tls::*;
 pub use random::{
     get_random_fingerprint, get_random_fingerprint_by_browser,
@@ -0,0 +1,36 @@
+//! Stable prelude
+//!
+//! `use fingerprint::prelude::*;` imports the supported, semver-stable surface of the
+//! library: random fingerprint selection, profiles, header generation and the HTTP client.
+//!
+//! Everything re-exported here is covered by the stability policy described in the crate
+//! root documentation. Items outside the prelude may still be public, but experimental
+//! APIs live in [`crate::unstable`] (behind the `unstable` feature) and can change in any
+//! minor release.
+
+// random fingerprint selection
+pub use crate::random::{
+    get_random_fingerprint, get_random_fingerprint_by_browser,
+    get_random_fingerprint_by_browser_with_os, get_random_fingerprint_with_os, FingerprintResult,
+};
+
+// core types
+pub use fingerprint_core::types::{
+    BrowserType, OperatingSystem, OperatingSystems, UserAgentTemplate, OPERATING_SYSTEMS,
+};
+
+// profiles and TLS configuration
+pub use fingerprint_profiles::profiles::{mapped_tls_clients, BrowserProfile, ProfileMetadata};
+pub use fingerprint_tls::tls_config::{ClientHelloSpec, ClientHelloSpecBuilder};
+
+// headers and User-Agent
+pub use fingerprint_headers::{
+    generate_headers, get_user_agent_by_profile_name, get_user_agent_by_profile_name_with_os,
+    random_language, random_os, HTTPHeaders, UserAgentGenerator,
+};
+
+// HTTP client
+pub use fingerprint_http::{
+    HttpClient, HttpClientConfig, HttpClientError, HttpMethod, HttpRequest, HttpResponse,
+    ProxyConfig, ProxyType,
+};
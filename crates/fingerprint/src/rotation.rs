@@ -0,0 +1,630 @@
+//! Identity rotation scheduling
+//!
+//! A long-lived identity should change the way a real installation does: the browser
+//! updates roughly monthly to the next release on the same OS, canvas/GPU noise stays put,
+//! and the exit IP changes per session. [`RotationScheduler`] keeps a due time per
+//! attribute, applies at most a configured number of identity changes per step, and runs
+//! every candidate through the consistency constraints before it replaces the current
+//! identity. Rejected candidates are recorded and retried later instead of being forced.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, Duration, Utc};
+use fingerprint_core::types::{BrowserType, OperatingSystem};
+use fingerprint_core::utils::{
+    extract_platform, infer_browser_from_profile_name, is_mobile_profile,
+};
+use fingerprint_headers::headers::{generate_headers, HTTPHeaders};
+use fingerprint_headers::useragent::{
+    get_user_agent_by_profile_name, get_user_agent_by_profile_name_with_os,
+};
+use fingerprint_profiles::js_quirks::JsEngine;
+use fingerprint_profiles::mapped_tls_clients;
+use fingerprint_profiles::version_registry::BrowserType as RegistryBrowser;
+use rand::Rng;
+
+/// Attributes the scheduler rotates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RotatingAttribute {
+    /// Browser release (TLS profile, User-Agent, headers)
+    BrowserVersion,
+    /// Seed for canvas / WebGL / audio noise
+    CanvasSeed,
+    /// Network session (sticky proxy / exit IP)
+    Session,
+}
+
+impl fmt::Display for RotatingAttribute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RotatingAttribute::BrowserVersion => "browser_version",
+            RotatingAttribute::CanvasSeed => "canvas_seed",
+            RotatingAttribute::Session => "session",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Identity state carried across rotations
+#[derive(Debug, Clone)]
+pub struct RotatingIdentity {
+    /// Key in [`mapped_tls_clients`] (e.g. "chrome_133")
+    pub profile_name: String,
+    pub browser_type: BrowserType,
+    pub browser_version: u32,
+    pub is_mobile: bool,
+    /// OS the User-Agent was generated for (`None` keeps the profile's own UA)
+    pub os: Option<OperatingSystem>,
+    pub user_agent: String,
+    pub headers: HTTPHeaders,
+    pub canvas_seed: u64,
+    /// Network session key; callers map it to a sticky proxy session
+    pub session_id: u64,
+}
+
+impl RotatingIdentity {
+    /// Build an identity from a profile name
+    pub fn from_profile_name<R: Rng + ?Sized>(
+        rng: &mut R,
+        profile_name: &str,
+        os: Option<OperatingSystem>,
+    ) -> Result<Self, String> {
+        let profile = mapped_tls_clients()
+            .remove(profile_name)
+            .ok_or_else(|| format!("profile {} not found", profile_name))?;
+        let is_mobile = is_mobile_profile(profile_name);
+        let os = os.filter(|_| !is_mobile);
+        let user_agent = match os {
+            Some(os) => get_user_agent_by_profile_name_with_os(profile_name, os)?,
+            None => get_user_agent_by_profile_name(profile_name)?,
+        };
+        let (browser, _) = infer_browser_from_profile_name(profile_name);
+        let browser_type = BrowserType::from_str(&browser).unwrap_or(BrowserType::Chrome);
+
+        Ok(Self {
+            profile_name: profile_name.to_string(),
+            browser_type,
+            browser_version: profile.metadata.browser_version,
+            is_mobile,
+            os,
+            headers: generate_headers(browser_type, &user_agent, is_mobile),
+            user_agent,
+            canvas_seed: rng.gen(),
+            session_id: rng.gen(),
+        })
+    }
+
+    /// Same identity moved to another profile, keeping OS, language and seeds
+    fn with_profile(&self, profile_name: &str) -> Result<Self, String> {
+        let profile = mapped_tls_clients()
+            .remove(profile_name)
+            .ok_or_else(|| format!("profile {} not found", profile_name))?;
+        let user_agent = match self.os {
+            Some(os) => get_user_agent_by_profile_name_with_os(profile_name, os)?,
+            None => get_user_agent_by_profile_name(profile_name)?,
+        };
+        let (browser, _) = infer_browser_from_profile_name(profile_name);
+        let browser_type = BrowserType::from_str(&browser).unwrap_or(BrowserType::Chrome);
+
+        let mut headers = generate_headers(browser_type, &user_agent, self.is_mobile);
+        // a browser update does not change the user's language settings
+        headers.accept_language = self.headers.accept_language.clone();
+
+        Ok(Self {
+            profile_name: profile_name.to_string(),
+            browser_type,
+            browser_version: profile.metadata.browser_version,
+            is_mobile: is_mobile_profile(profile_name),
+            os: self.os,
+            user_agent,
+            headers,
+            canvas_seed: self.canvas_seed,
+            session_id: self.session_id,
+        })
+    }
+
+    /// Next newer release of the same browser and device class, if bundled
+    fn next_release(&self) -> Option<String> {
+        mapped_tls_clients()
+            .into_iter()
+            .filter(|(name, profile)| {
+                let (browser, _) = infer_browser_from_profile_name(name);
+                BrowserType::from_str(&browser) == Some(self.browser_type)
+                    && is_mobile_profile(name) == self.is_mobile
+                    && profile.metadata.browser_version > self.browser_version
+            })
+            .min_by_key(|(_, profile)| profile.metadata.browser_version)
+            .map(|(name, _)| name)
+    }
+}
+
+/// Consistency rule every rotation candidate must satisfy
+pub trait RotationConstraint: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Check `candidate` as a successor of `current`
+    fn check(&self, current: &RotatingIdentity, candidate: &RotatingIdentity)
+        -> Result<(), String>;
+}
+
+/// Browser family, device class and OS never change
+pub struct SamePlatform;
+
+impl RotationConstraint for SamePlatform {
+    fn name(&self) -> &str {
+        "same_platform"
+    }
+
+    fn check(
+        &self,
+        current: &RotatingIdentity,
+        candidate: &RotatingIdentity,
+    ) -> Result<(), String> {
+        if candidate.browser_type != current.browser_type {
+            return Err(format!(
+                "browser changed from {} to {}",
+                current.browser_type, candidate.browser_type
+            ));
+        }
+        if candidate.is_mobile != current.is_mobile {
+            return Err("device class changed".to_string());
+        }
+        let (from, to) = (
+            extract_platform(&current.user_agent),
+            extract_platform(&candidate.user_agent),
+        );
+        if from != to {
+            return Err(format!("platform changed from {} to {}", from, to));
+        }
+        Ok(())
+    }
+}
+
+/// Versions only move forward, by a bounded number of major releases
+pub struct ForwardUpdate {
+    pub max_major_step: u32,
+}
+
+impl Default for ForwardUpdate {
+    fn default() -> Self {
+        Self { max_major_step: 4 }
+    }
+}
+
+impl RotationConstraint for ForwardUpdate {
+    fn name(&self) -> &str {
+        "forward_update"
+    }
+
+    fn check(
+        &self,
+        current: &RotatingIdentity,
+        candidate: &RotatingIdentity,
+    ) -> Result<(), String> {
+        if candidate.browser_version < current.browser_version {
+            return Err(format!(
+                "downgrade from {} to {}",
+                current.browser_version, candidate.browser_version
+            ));
+        }
+        if candidate.browser_version - current.browser_version > self.max_major_step {
+            return Err(format!(
+                "jump from {} to {} exceeds {} releases",
+                current.browser_version, candidate.browser_version, self.max_major_step
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// User-Agent must imply the JS engine of the TLS profile's browser
+pub struct EngineConsistency;
+
+impl RotationConstraint for EngineConsistency {
+    fn name(&self) -> &str {
+        "engine_consistency"
+    }
+
+    fn check(
+        &self,
+        _current: &RotatingIdentity,
+        candidate: &RotatingIdentity,
+    ) -> Result<(), String> {
+        let expected = JsEngine::for_browser(registry_browser(candidate.browser_type));
+        match JsEngine::from_user_agent(&candidate.user_agent) {
+            Some((engine, _)) if engine == expected => Ok(()),
+            Some((engine, _)) => Err(format!(
+                "User-Agent implies {} but profile is {}",
+                engine, expected
+            )),
+            None => Err("User-Agent engine not recognized".to_string()),
+        }
+    }
+}
+
+fn registry_browser(browser: BrowserType) -> RegistryBrowser {
+    match browser {
+        BrowserType::Chrome => RegistryBrowser::Chrome,
+        BrowserType::Firefox => RegistryBrowser::Firefox,
+        BrowserType::Safari => RegistryBrowser::Safari,
+        BrowserType::Edge => RegistryBrowser::Edge,
+        BrowserType::Opera => RegistryBrowser::Opera,
+    }
+}
+
+/// Accept-Language stays the same
+pub struct StableLocale;
+
+impl RotationConstraint for StableLocale {
+    fn name(&self) -> &str {
+        "stable_locale"
+    }
+
+    fn check(
+        &self,
+        current: &RotatingIdentity,
+        candidate: &RotatingIdentity,
+    ) -> Result<(), String> {
+        if current.headers.accept_language != candidate.headers.accept_language {
+            return Err(format!(
+                "Accept-Language changed from {} to {}",
+                current.headers.accept_language, candidate.headers.accept_language
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Rotation cadence
+#[derive(Debug, Clone)]
+pub struct RotationPolicy {
+    /// Mean interval between browser updates
+    pub browser_update_every: Duration,
+    /// Random spread around the update interval
+    pub browser_update_jitter: Duration,
+    /// Canvas seed rotation interval (`None` keeps it for the identity's lifetime)
+    pub canvas_rotate_every: Option<Duration>,
+    /// Maximum session age before a new session is forced (`None` rotates only on
+    /// [`RotationScheduler::start_session`])
+    pub session_max_age: Option<Duration>,
+    /// Identity changes applied per step; session rotation is not counted
+    pub max_changes_per_step: usize,
+    /// Delay before retrying a rejected or unavailable change
+    pub retry_after: Duration,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        Self {
+            browser_update_every: Duration::days(30),
+            browser_update_jitter: Duration::days(7),
+            canvas_rotate_every: None,
+            session_max_age: Some(Duration::hours(24)),
+            max_changes_per_step: 1,
+            retry_after: Duration::days(1),
+        }
+    }
+}
+
+/// Result of one attempted change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RotationOutcome {
+    Applied,
+    /// Constraint violations (`name: reason`)
+    Rejected(Vec<String>),
+    /// No candidate exists (e.g. already on the newest bundled release)
+    Unavailable,
+}
+
+/// Entry in the rotation history
+#[derive(Debug, Clone)]
+pub struct RotationEvent {
+    pub at: DateTime<Utc>,
+    pub attribute: RotatingAttribute,
+    pub from: String,
+    pub to: String,
+    pub outcome: RotationOutcome,
+}
+
+/// Schedules gradual, constraint-checked identity changes
+pub struct RotationScheduler {
+    policy: RotationPolicy,
+    identity: RotatingIdentity,
+    constraints: Vec<Box<dyn RotationConstraint>>,
+    next_due: HashMap<RotatingAttribute, DateTime<Utc>>,
+    history: Vec<RotationEvent>,
+}
+
+impl RotationScheduler {
+    /// Create a scheduler with the default constraints
+    pub fn new<R: Rng + ?Sized>(
+        rng: &mut R,
+        identity: RotatingIdentity,
+        policy: RotationPolicy,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let mut scheduler = Self {
+            policy,
+            identity,
+            constraints: vec![
+                Box::new(SamePlatform),
+                Box::new(ForwardUpdate::default()),
+                Box::new(EngineConsistency),
+                Box::new(StableLocale),
+            ],
+            next_due: HashMap::new(),
+            history: Vec::new(),
+        };
+        for attribute in [
+            RotatingAttribute::BrowserVersion,
+            RotatingAttribute::CanvasSeed,
+            RotatingAttribute::Session,
+        ] {
+            scheduler.schedule(rng, attribute, now);
+        }
+        scheduler
+    }
+
+    /// Add a constraint
+    pub fn with_constraint(mut self, constraint: Box<dyn RotationConstraint>) -> Self {
+        self.constraints.push(constraint);
+        self
+    }
+
+    pub fn identity(&self) -> &RotatingIdentity {
+        &self.identity
+    }
+
+    pub fn history(&self) -> &[RotationEvent] {
+        &self.history
+    }
+
+    pub fn next_due(&self, attribute: RotatingAttribute) -> Option<DateTime<Utc>> {
+        self.next_due.get(&attribute).copied()
+    }
+
+    /// Attributes due at `now`, earliest first
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<RotatingAttribute> {
+        let mut due: Vec<(RotatingAttribute, DateTime<Utc>)> = self
+            .next_due
+            .iter()
+            .filter(|(_, at)| **at <= now)
+            .map(|(attribute, at)| (*attribute, *at))
+            .collect();
+        due.sort_by_key(|(_, at)| *at);
+        due.into_iter().map(|(attribute, _)| attribute).collect()
+    }
+
+    fn schedule<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        attribute: RotatingAttribute,
+        now: DateTime<Utc>,
+    ) {
+        let interval = match attribute {
+            RotatingAttribute::BrowserVersion => {
+                let jitter = self.policy.browser_update_jitter.num_seconds().max(0);
+                Some(
+                    self.policy.browser_update_every
+                        + Duration::seconds(rng.gen_range(-jitter..=jitter)),
+                )
+            }
+            RotatingAttribute::CanvasSeed => self.policy.canvas_rotate_every,
+            RotatingAttribute::Session => self.policy.session_max_age,
+        };
+        match interval {
+            Some(interval) => {
+                self.next_due.insert(attribute, now + interval);
+            }
+            None => {
+                self.next_due.remove(&attribute);
+            }
+        }
+    }
+
+    /// Violations of `candidate` against the current identity
+    pub fn validate(&self, candidate: &RotatingIdentity) -> Vec<String> {
+        self.constraints
+            .iter()
+            .filter_map(|c| {
+                c.check(&self.identity, candidate)
+                    .err()
+                    .map(|reason| format!("{}: {}", c.name(), reason))
+            })
+            .collect()
+    }
+
+    /// Start a new network session (new exit IP); returns the session id
+    pub fn start_session<R: Rng + ?Sized>(&mut self, rng: &mut R, now: DateTime<Utc>) -> u64 {
+        let from = self.identity.session_id;
+        self.identity.session_id = rng.gen();
+        self.history.push(RotationEvent {
+            at: now,
+            attribute: RotatingAttribute::Session,
+            from: from.to_string(),
+            to: self.identity.session_id.to_string(),
+            outcome: RotationOutcome::Applied,
+        });
+        self.schedule(rng, RotatingAttribute::Session, now);
+        self.identity.session_id
+    }
+
+    /// Apply due changes
+    ///
+    /// At most `max_changes_per_step` identity attributes change per call; remaining due
+    /// attributes stay due for the next step. Returns the events of this step.
+    pub fn step<R: Rng + ?Sized>(&mut self, rng: &mut R, now: DateTime<Utc>) -> Vec<RotationEvent> {
+        let start = self.history.len();
+        let mut changes = 0;
+
+        for attribute in self.due(now) {
+            if attribute == RotatingAttribute::Session {
+                self.start_session(rng, now);
+                continue;
+            }
+            if changes >= self.policy.max_changes_per_step {
+                continue;
+            }
+
+            let (from, candidate) = match attribute {
+                RotatingAttribute::BrowserVersion => (
+                    self.identity.profile_name.clone(),
+                    self.identity
+                        .next_release()
+                        .and_then(|name| self.identity.with_profile(&name).ok()),
+                ),
+                RotatingAttribute::CanvasSeed => {
+                    let mut candidate = self.identity.clone();
+                    candidate.canvas_seed = rng.gen();
+                    (self.identity.canvas_seed.to_string(), Some(candidate))
+                }
+                RotatingAttribute::Session => unreachable!("handled above"),
+            };
+
+            let (to, outcome) = match candidate {
+                None => (from.clone(), RotationOutcome::Unavailable),
+                Some(candidate) => {
+                    let to = match attribute {
+                        RotatingAttribute::CanvasSeed => candidate.canvas_seed.to_string(),
+                        _ => candidate.profile_name.clone(),
+                    };
+                    let violations = self.validate(&candidate);
+                    if violations.is_empty() {
+                        self.identity = candidate;
+                        (to, RotationOutcome::Applied)
+                    } else {
+                        (to, RotationOutcome::Rejected(violations))
+                    }
+                }
+            };
+
+            if outcome == RotationOutcome::Applied {
+                changes += 1;
+                self.schedule(rng, attribute, now);
+            } else {
+                self.next_due
+                    .insert(attribute, now + self.policy.retry_after);
+            }
+            self.history.push(RotationEvent {
+                at: now,
+                attribute,
+                from,
+                to,
+                outcome,
+            });
+        }
+
+        self.history[start..].to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn start() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_browser_updates_gradually_and_keeps_stable_attributes() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let identity = RotatingIdentity::from_profile_name(
+            &mut rng,
+            "chrome_133",
+            Some(OperatingSystem::Windows10),
+        )
+        .unwrap();
+        let canvas_seed = identity.canvas_seed;
+        let language = identity.headers.accept_language.clone();
+        let policy = RotationPolicy {
+            canvas_rotate_every: Some(Duration::days(30)),
+            browser_update_jitter: Duration::zero(),
+            session_max_age: None,
+            ..RotationPolicy::default()
+        };
+        let mut scheduler = RotationScheduler::new(&mut rng, identity, policy, start());
+
+        assert!(scheduler
+            .step(&mut rng, start() + Duration::days(10))
+            .is_empty());
+
+        // browser and canvas are both due, only one identity change is applied
+        let day_31 = start() + Duration::days(31);
+        let events = scheduler.step(&mut rng, day_31);
+        let applied: Vec<_> = events
+            .iter()
+            .filter(|e| e.attribute != RotatingAttribute::Session)
+            .collect();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(scheduler.due(day_31).len(), 1);
+
+        scheduler.step(&mut rng, day_31 + Duration::days(1));
+        let identity = scheduler.identity();
+        assert_eq!(identity.profile_name, "chrome_136");
+        assert!(identity.user_agent.contains("Windows NT 10.0"));
+        assert_eq!(identity.headers.accept_language, language);
+        assert_ne!(identity.canvas_seed, canvas_seed);
+
+        // no newer bundled release: the update is reported and retried later
+        let later = day_31 + Duration::days(40);
+        scheduler.step(&mut rng, later);
+        scheduler.step(&mut rng, later + Duration::days(1));
+        assert!(scheduler
+            .history()
+            .iter()
+            .any(|e| e.attribute == RotatingAttribute::BrowserVersion
+                && e.outcome == RotationOutcome::Unavailable));
+        assert_eq!(scheduler.identity().profile_name, "chrome_136");
+    }
+
+    #[test]
+    fn test_constraints_reject_inconsistent_candidate() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let current = RotatingIdentity::from_profile_name(
+            &mut rng,
+            "chrome_136",
+            Some(OperatingSystem::MacOS14),
+        )
+        .unwrap();
+        let scheduler = RotationScheduler::new(
+            &mut rng,
+            current.clone(),
+            RotationPolicy::default(),
+            start(),
+        );
+
+        let mut downgrade = current.with_profile("chrome_133").unwrap();
+        assert!(scheduler.validate(&downgrade)[0].starts_with("forward_update"));
+
+        downgrade.browser_version = current.browser_version;
+        downgrade.user_agent = current.user_agent.replace("Chrome/136", "Firefox/136");
+        let violations = scheduler.validate(&downgrade);
+        assert!(violations
+            .iter()
+            .any(|v| v.starts_with("engine_consistency")));
+    }
+
+    #[test]
+    fn test_session_rotates_independently() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let identity = RotatingIdentity::from_profile_name(&mut rng, "firefox_133", None).unwrap();
+        let profile = identity.profile_name.clone();
+        let mut scheduler =
+            RotationScheduler::new(&mut rng, identity, RotationPolicy::default(), start());
+
+        let first = scheduler.identity().session_id;
+        let second = scheduler.start_session(&mut rng, start() + Duration::hours(1));
+        assert_ne!(first, second);
+
+        let events = scheduler.step(&mut rng, start() + Duration::hours(26));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].attribute, RotatingAttribute::Session);
+        assert_eq!(scheduler.identity().profile_name, profile);
+    }
+}
@@ -0,0 +1,40 @@
+//! Experimental APIs
+//!
+//! Only compiled with the `unstable` feature. Nothing in this module is covered by the
+//! semver policy: signatures may change or disappear in any minor release. The
+//! semver-checks CI job builds with default features, so this module is never compared
+//! against the previous release.
+
+// Post-quantum key exchange detection (hybrid ML-KEM groups)
+pub use fingerprint_core::pqc::{PQCAlgorithm, PQCBrowserSupport, PQCCapabilities};
+
+// WebAssembly capability fingerprinting
+pub use fingerprint_core::wasm::{
+    WasmBrowserSupport, WasmCapabilities, WasmMemoryFingerprint, WasmTableFingerprint, WasmVersion,
+};
+
+// Incremental TCP fingerprint updates
+pub use fingerprint_core::incremental_fingerprint::{
+    IncrementalFingerprintResult, IncrementalTcpFingerprint,
+};
+
+// HPACK header compression analysis
+pub use fingerprint_core::hpack::{HpackAnalyzer, HpackFingerprint};
+
+// JARM active TLS server fingerprinting
+pub use fingerprint_core::jarm;
+
+// Packet capture and PCAP generation
+pub use fingerprint_parsers as parsers;
+
+// Identity entropy budget estimation
+pub use crate::entropy::{
+    AttributeDistribution, AttributeEntropy, EntropyEstimator, EntropyReport, Identity,
+    IdentityAttribute, PopulationModel, Surface, SurfaceEntropy,
+};
+
+// Gradual identity rotation
+pub use crate::rotation::{
+    EngineConsistency, ForwardUpdate, RotatingAttribute, RotatingIdentity, RotationConstraint,
+    RotationEvent, RotationOutcome, RotationPolicy, RotationScheduler, SamePlatform, StableLocale,
+};
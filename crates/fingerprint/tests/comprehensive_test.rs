@@ -291,7 +291,7 @@ fn test_user_agent_generation() {
 
     // Testing random operating system
     let os = random_os();
-    assert!(fingerprint::types::OPERATING_SYSTEMS.contains(&os));
+    assert!(fingerprint::OPERATING_SYSTEMS.contains(&os));
 
     println!("✅ User-Agent generation works correctly");
     println!("   Sample UA: {}", ua1);
@@ -3,7 +3,7 @@
 //! Comprehensive testing of fingerprint library functionality
 
 use fingerprint::generate_headers;
-use fingerprint::types::OPERATING_SYSTEMS;
+use fingerprint::OPERATING_SYSTEMS;
 use fingerprint::*;
 
 #[test]
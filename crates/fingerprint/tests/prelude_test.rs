@@ -0,0 +1,50 @@
+//! Stable API surface snapshot
+//!
+//! Compile-time checks that every item promised by `fingerprint::prelude` keeps its shape.
+//! Breaking any of these is a semver-major change (see the crate-level stability policy).
+
+use fingerprint::prelude::*;
+
+#[test]
+fn test_prelude_random_fingerprint_signatures() {
+    let _: fn() -> Result<FingerprintResult, String> = get_random_fingerprint;
+    let _: fn(Option<OperatingSystem>) -> Result<FingerprintResult, String> =
+        get_random_fingerprint_with_os;
+    let _: fn(&str) -> Result<FingerprintResult, Box<dyn std::error::Error>> =
+        get_random_fingerprint_by_browser;
+
+    let result = get_random_fingerprint().expect("random fingerprint");
+    assert!(!result.profile_id.is_empty());
+    assert!(!result.user_agent.is_empty());
+}
+
+#[test]
+fn test_prelude_profiles_and_headers() {
+    let profiles = mapped_tls_clients();
+    let (name, profile): (&String, &BrowserProfile) = profiles.iter().next().expect("profiles");
+    assert!(!name.is_empty());
+    let _spec: &ClientHelloSpec = &profile.tls_config;
+    let _metadata: &ProfileMetadata = &profile.metadata;
+
+    assert!(OPERATING_SYSTEMS.contains(&random_os()));
+    let ua = get_user_agent_by_profile_name("chrome_133").expect("user agent");
+    let headers: HTTPHeaders = generate_headers(BrowserType::Chrome, &ua, false);
+    assert_eq!(headers.user_agent, ua);
+}
+
+#[test]
+fn test_prelude_http_client_types() {
+    let config = HttpClientConfig::default();
+    let _client = HttpClient::new(config);
+    let _method = HttpMethod::Get;
+    let _proxy_type = ProxyType::Http;
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_deprecated_types_shim_still_resolves() {
+    assert_eq!(
+        fingerprint::types::OPERATING_SYSTEMS.len(),
+        OPERATING_SYSTEMS.len()
+    );
+}
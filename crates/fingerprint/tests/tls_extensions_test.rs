@@ -124,11 +124,13 @@ fn test_supported_versions_extension() {
     let versions = vec![0x0304, 0x0303];
     let ext = SupportedVersionsExtension::new(versions);
     assert_eq!(ext.extension_id(), 43);
-    assert_eq!(ext.len(), 6 + 2 * 2);
+    // header (4) + list length (1) + versions
+    assert_eq!(ext.len(), 5 + 2 * 2);
 
     let mut buf = vec![0u8; 256];
     let n = ext.read(&mut buf).unwrap();
-    assert_eq!(n, 10);
+    assert_eq!(n, 9);
+    assert_eq!(u16::from_be_bytes([buf[2], buf[3]]) as usize, n - 4);
 }
 
 #[test]
@@ -0,0 +1,21 @@
+-- Rows written by detection-sensor (see src/sink.rs)
+CREATE DATABASE IF NOT EXISTS fingerprint;
+
+CREATE TABLE IF NOT EXISTS fingerprint.observations
+(
+    ts          DateTime,
+    client_ip   String,
+    server_port UInt16,
+    tls         Nullable(String),
+    http        Nullable(String),
+    tcp         Nullable(String),
+    os          Nullable(String),
+    user_agent  Nullable(String),
+    prior       Float64,
+    decision    LowCardinality(String),
+    reason      String
+)
+ENGINE = MergeTree
+PARTITION BY toDate(ts)
+ORDER BY (ts, client_ip)
+TTL ts + INTERVAL 30 DAY;
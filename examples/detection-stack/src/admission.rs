@@ -0,0 +1,122 @@
+//! Admission decisions
+//!
+//! A client whose learned prior is already high is blocked locally. Everyone else is
+//! checked against the gateway's `POST /api/v1/rate-limit/check` with the protected
+//! site's API key and the client's fingerprints; when the gateway rejects, it labels
+//! those fingerprints in the learner database, closing the loop back to the sensor.
+
+use crate::sensor::Observation;
+use fingerprint::{HttpClient, HttpClientConfig, HttpMethod, HttpRequest};
+use fingerprint_gateway::models::{RateLimitRequest, RateLimitResponse};
+use serde::Serialize;
+use std::time::Duration;
+
+/// What happens to a client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Allow,
+    Block {
+        reason: String,
+    },
+    /// Gateway unreachable; the sensor fails open
+    Unchecked,
+}
+
+impl Decision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Decision::Allow => "allow",
+            Decision::Block { .. } => "block",
+            Decision::Unchecked => "unchecked",
+        }
+    }
+}
+
+/// Client for the gateway's rate-limit check
+pub struct AdmissionClient {
+    url: String,
+    api_key: String,
+    endpoint: String,
+    client: HttpClient,
+}
+
+impl AdmissionClient {
+    /// `gateway_url` is the gateway's base URL, e.g. `http://gateway:8080`
+    pub fn new(gateway_url: &str, api_key: &str) -> Self {
+        Self {
+            url: format!(
+                "{}/api/v1/rate-limit/check",
+                gateway_url.trim_end_matches('/')
+            ),
+            api_key: api_key.to_string(),
+            endpoint: "/".to_string(),
+            client: HttpClient::new(HttpClientConfig {
+                connect_timeout: Duration::from_secs(2),
+                read_timeout: Duration::from_secs(2),
+                write_timeout: Duration::from_secs(2),
+                ..HttpClientConfig::default()
+            }),
+        }
+    }
+
+    /// Protected endpoint reported to the gateway (default `/`)
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.to_string();
+        self
+    }
+
+    /// Ask the gateway about `observation`
+    pub fn check(&self, observation: &Observation) -> Result<RateLimitResponse, String> {
+        let body = serde_json::to_string(&RateLimitRequest {
+            api_key: self.api_key.clone(),
+            endpoint: self.endpoint.clone(),
+            client_ip: Some(observation.client_ip.clone()),
+            fingerprints: observation.fingerprints(),
+            feature: None,
+        })
+        .map_err(|e| e.to_string())?;
+        let request = HttpRequest::new(HttpMethod::Post, &self.url).with_json_body(&body);
+        let response = self
+            .client
+            .send_request(&request)
+            .map_err(|e| e.to_string())?;
+
+        // 429 carries a RateLimitResponse too
+        match response.status_code {
+            200 | 429 => serde_json::from_slice(&response.body).map_err(|e| e.to_string()),
+            status => Err(format!(
+                "gateway answered {}: {}",
+                status,
+                String::from_utf8_lossy(&response.body)
+            )),
+        }
+    }
+}
+
+/// Local prior threshold plus optional gateway check
+pub struct AdmissionPolicy {
+    /// Prior at or above which a client is blocked without asking the gateway
+    pub block_prior: f64,
+    pub gateway: Option<AdmissionClient>,
+}
+
+impl AdmissionPolicy {
+    pub fn decide(&self, observation: &Observation) -> (Decision, Option<String>) {
+        if observation.prior >= self.block_prior {
+            let reason = format!("learned prior {:.2}", observation.prior);
+            return (Decision::Block { reason }, None);
+        }
+        let Some(gateway) = &self.gateway else {
+            return (Decision::Allow, None);
+        };
+        match gateway.check(observation) {
+            Ok(response) if response.allowed => (Decision::Allow, None),
+            Ok(response) => {
+                let reason = response.error.unwrap_or_else(|| "rate limited".to_string());
+                (Decision::Block { reason }, None)
+            }
+            Err(error) => (Decision::Unchecked, Some(error)),
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! Detection stack: reference wiring of the defender-side components
+//!
+//! ```text
+//! CaptureEngine ──observer──▶ bounded queue ──▶ worker thread
+//!   (pcap / live)                                 ├─ Sensor: PassiveAnalyzer result
+//!                                                 │    → SelfLearningAnalyzer + weak-label prior
+//!                                                 ├─ AdmissionPolicy → gateway /rate-limit/check
+//!                                                 │    (429s label fingerprints in the learner DB)
+//!                                                 ├─ ClickHouseSink (JSONEachRow over HTTP)
+//!                                                 └─ SensorMetrics (Prometheus, next to gateway /metrics)
+//! ```
+//!
+//! The worker owns the learner database (a SQLite connection is not shareable
+//! across threads) and the HTTP clients, so capture never blocks on the gateway or
+//! ClickHouse; when the queue is full, packets are dropped and counted.
+//!
+//! `fingerprint-analysis`' `AnalysisEngine` is not part of the workspace build, so
+//! the analysis stage here is the defense crate's passive analyzer and learner.
+
+pub mod admission;
+pub mod metrics;
+pub mod sensor;
+pub mod sink;
+pub mod soak;
+
+pub use admission::{AdmissionClient, AdmissionPolicy, Decision};
+pub use metrics::SensorMetrics;
+pub use sensor::{Observation, Sensor};
+pub use sink::ClickHouseSink;
+
+use fingerprint_defense::{
+    CaptureEngine, CaptureObserver, FingerprintDatabase, Packet, PassiveAnalysisResult,
+    PassiveAnalyzer,
+};
+use std::env;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Pipeline settings
+#[derive(Debug, Clone)]
+pub struct StackConfig {
+    /// Gateway base URL; unset allows everything below `block_prior`
+    pub gateway_url: Option<String>,
+    /// API key of the protected site, used for the gateway check
+    pub api_key: String,
+    /// ClickHouse HTTP interface; unset disables the sink
+    pub clickhouse_url: Option<String>,
+    pub clickhouse_table: String,
+    /// Learner database shared with the gateway's `LABEL_DB_PATH`; unset uses memory
+    pub label_db_path: Option<PathBuf>,
+    /// Prior at or above which clients are blocked without asking the gateway
+    pub block_prior: f64,
+    /// Rows per ClickHouse insert
+    pub batch_size: usize,
+    /// Longest time a partial batch waits
+    pub flush_interval: Duration,
+    /// Packets buffered between capture and analysis
+    pub queue_capacity: usize,
+}
+
+impl Default for StackConfig {
+    fn default() -> Self {
+        Self {
+            gateway_url: None,
+            api_key: "sk_test_demo123".to_string(),
+            clickhouse_url: None,
+            clickhouse_table: "fingerprint.observations".to_string(),
+            label_db_path: None,
+            block_prior: 0.8,
+            batch_size: 500,
+            flush_interval: Duration::from_secs(5),
+            queue_capacity: 10_000,
+        }
+    }
+}
+
+impl StackConfig {
+    /// Load from environment variables
+    ///
+    /// - `GATEWAY_URL`, `SENSOR_API_KEY`
+    /// - `CLICKHOUSE_URL`, `CLICKHOUSE_TABLE`
+    /// - `LABEL_DB_PATH`
+    /// - `SENSOR_BLOCK_PRIOR`, `SENSOR_BATCH_SIZE`, `SENSOR_FLUSH_SECS`, `SENSOR_QUEUE`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        fn parsed<T: std::str::FromStr>(name: &str, default: T) -> T {
+            env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        Self {
+            gateway_url: env::var("GATEWAY_URL").ok(),
+            api_key: env::var("SENSOR_API_KEY").unwrap_or(defaults.api_key),
+            clickhouse_url: env::var("CLICKHOUSE_URL").ok(),
+            clickhouse_table: env::var("CLICKHOUSE_TABLE").unwrap_or(defaults.clickhouse_table),
+            label_db_path: env::var("LABEL_DB_PATH").ok().map(PathBuf::from),
+            block_prior: parsed("SENSOR_BLOCK_PRIOR", defaults.block_prior),
+            batch_size: parsed("SENSOR_BATCH_SIZE", defaults.batch_size),
+            flush_interval: Duration::from_secs(parsed(
+                "SENSOR_FLUSH_SECS",
+                defaults.flush_interval.as_secs(),
+            )),
+            queue_capacity: parsed("SENSOR_QUEUE", defaults.queue_capacity),
+        }
+    }
+}
+
+/// Totals of a finished pipeline
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PipelineSummary {
+    pub observations: u64,
+    pub allowed: u64,
+    pub blocked: u64,
+    pub unchecked: u64,
+    pub rows_written: u64,
+}
+
+type Job = (Packet, PassiveAnalysisResult);
+
+/// Analysis worker fed by a [`CaptureEngine`] observer
+pub struct Pipeline {
+    sender: SyncSender<Job>,
+    worker: thread::JoinHandle<Result<PipelineSummary, String>>,
+    metrics: Arc<SensorMetrics>,
+}
+
+impl Pipeline {
+    /// Open the learner database and start the worker
+    pub fn start(config: StackConfig, metrics: Arc<SensorMetrics>) -> Result<Self, String> {
+        let (sender, receiver) = mpsc::sync_channel::<Job>(config.queue_capacity.max(1));
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let worker_metrics = metrics.clone();
+        let worker = thread::Builder::new()
+            .name("detection-worker".to_string())
+            .spawn(move || {
+                let db = match &config.label_db_path {
+                    Some(path) => FingerprintDatabase::open(path),
+                    None => FingerprintDatabase::new_in_memory(),
+                };
+                let db = match db {
+                    Ok(db) => {
+                        let _ = ready_tx.send(Ok(()));
+                        db
+                    }
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e.clone()));
+                        return Err(e);
+                    }
+                };
+                Worker::new(config, db, worker_metrics).run(receiver)
+            })
+            .map_err(|e| e.to_string())?;
+        ready_rx
+            .recv()
+            .map_err(|_| "detection worker exited".to_string())??;
+
+        Ok(Self {
+            sender,
+            worker,
+            metrics,
+        })
+    }
+
+    /// Observer for [`CaptureEngine::with_observer`]
+    pub fn observer(&self) -> CaptureObserver {
+        let sender = self.sender.clone();
+        let metrics = self.metrics.clone();
+        Arc::new(move |packet: &Packet, result: &PassiveAnalysisResult| {
+            metrics.packets.inc();
+            match sender.try_send((packet.clone(), result.clone())) {
+                Ok(()) => metrics.queue_depth.inc(),
+                Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                    metrics.dropped.inc()
+                }
+            }
+        })
+    }
+
+    /// Capture engine wired to this pipeline
+    pub fn capture_engine(&self) -> Result<CaptureEngine, String> {
+        let analyzer = PassiveAnalyzer::new().map_err(|e| e.to_string())?;
+        Ok(CaptureEngine::new(Arc::new(analyzer)).with_observer(self.observer()))
+    }
+
+    /// Drain the queue, flush the sink and stop the worker
+    ///
+    /// Capture engines built from this pipeline must be dropped first, since their
+    /// observers keep the queue open.
+    pub fn finish(self) -> Result<PipelineSummary, String> {
+        drop(self.sender);
+        self.worker
+            .join()
+            .map_err(|_| "detection worker panicked".to_string())?
+    }
+}
+
+/// Analyze a pcap file end to end
+pub fn run_pcap(
+    path: &str,
+    config: StackConfig,
+    metrics: Arc<SensorMetrics>,
+) -> Result<PipelineSummary, String> {
+    let pipeline = Pipeline::start(config, metrics)?;
+    pipeline.capture_engine()?.process_file(path)?;
+    pipeline.finish()
+}
+
+struct Worker {
+    sensor: Sensor,
+    policy: AdmissionPolicy,
+    sink: Option<ClickHouseSink>,
+    flush_interval: Duration,
+    metrics: Arc<SensorMetrics>,
+    summary: PipelineSummary,
+}
+
+impl Worker {
+    fn new(config: StackConfig, db: FingerprintDatabase, metrics: Arc<SensorMetrics>) -> Self {
+        Self {
+            sensor: Sensor::new(db),
+            policy: AdmissionPolicy {
+                block_prior: config.block_prior,
+                gateway: config
+                    .gateway_url
+                    .as_deref()
+                    .map(|url| AdmissionClient::new(url, &config.api_key)),
+            },
+            sink: config
+                .clickhouse_url
+                .as_deref()
+                .map(|url| ClickHouseSink::new(url, &config.clickhouse_table, config.batch_size)),
+            flush_interval: config.flush_interval,
+            metrics,
+            summary: PipelineSummary::default(),
+        }
+    }
+
+    fn run(mut self, receiver: mpsc::Receiver<Job>) -> Result<PipelineSummary, String> {
+        let mut last_flush = Instant::now();
+        loop {
+            match receiver.recv_timeout(self.flush_interval) {
+                Ok((packet, result)) => {
+                    self.metrics.queue_depth.dec();
+                    self.handle(&packet, &result);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if last_flush.elapsed() >= self.flush_interval {
+                self.flush();
+                last_flush = Instant::now();
+            }
+        }
+        self.flush();
+        if let Some(sink) = &self.sink {
+            self.summary.rows_written = sink.written();
+        }
+        Ok(self.summary)
+    }
+
+    fn handle(&mut self, packet: &Packet, result: &PassiveAnalysisResult) {
+        let Some(observation) = self.sensor.observe(packet, result) else {
+            return;
+        };
+        self.summary.observations += 1;
+        for layer in observation.layers() {
+            self.metrics.observations.with_label_values(&[layer]).inc();
+        }
+        self.metrics.prior.observe(observation.prior);
+
+        let (decision, error) = self.policy.decide(&observation);
+        if let Some(error) = error {
+            self.metrics.errors.with_label_values(&["gateway"]).inc();
+            eprintln!("[Sensor] gateway check failed: {}", error);
+        }
+        self.metrics
+            .decisions
+            .with_label_values(&[decision.as_str()])
+            .inc();
+        match decision {
+            Decision::Allow => self.summary.allowed += 1,
+            Decision::Block { .. } => self.summary.blocked += 1,
+            Decision::Unchecked => self.summary.unchecked += 1,
+        }
+
+        if let Some(sink) = &mut self.sink {
+            if let Err(e) = sink.push(&observation, &decision) {
+                self.metrics.errors.with_label_values(&["clickhouse"]).inc();
+                eprintln!("[Sensor] ClickHouse insert failed: {}", e);
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        let Some(sink) = &mut self.sink else { return };
+        let before = sink.written();
+        if let Err(e) = sink.flush() {
+            self.metrics.errors.with_label_values(&["clickhouse"]).inc();
+            eprintln!("[Sensor] ClickHouse insert failed: {}", e);
+        }
+        self.metrics.rows_written.inc_by(sink.written() - before);
+    }
+}
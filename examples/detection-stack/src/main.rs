@@ -0,0 +1,84 @@
+//! detection-sensor: passive sensor feeding the gateway, ClickHouse and Prometheus
+//!
+//! ```text
+//! detection-sensor --pcap capture.pcap
+//! detection-sensor --iface eth0
+//! ```
+//!
+//! Configuration comes from the environment (see [`StackConfig::from_env`]);
+//! sensor metrics are served on `SENSOR_METRICS_ADDR` (default `0.0.0.0:9187`).
+
+use detection_stack::{Pipeline, SensorMetrics, StackConfig};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+enum Source {
+    Pcap(String),
+    Iface(String),
+}
+
+fn parse_args() -> Result<Source, String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [flag, path] if flag == "--pcap" => Ok(Source::Pcap(path.clone())),
+        [flag, name] if flag == "--iface" => Ok(Source::Iface(name.clone())),
+        _ => Err("usage: detection-sensor (--pcap FILE | --iface NAME)".to_string()),
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("detection-sensor: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run() -> Result<(), String> {
+    let source = parse_args()?;
+    let config = StackConfig::from_env();
+    let metrics = Arc::new(SensorMetrics::new());
+    let metrics_addr =
+        std::env::var("SENSOR_METRICS_ADDR").unwrap_or_else(|_| "0.0.0.0:9187".to_string());
+    metrics
+        .serve(&metrics_addr)
+        .map_err(|e| format!("metrics listener on {}: {}", metrics_addr, e))?;
+    println!("[Sensor] metrics on http://{}/metrics", metrics_addr);
+    if let Some(gateway) = &config.gateway_url {
+        println!("[Sensor] admission checks against {}", gateway);
+    }
+    if let Some(clickhouse) = &config.clickhouse_url {
+        println!(
+            "[Sensor] observations to {} ({})",
+            clickhouse, config.clickhouse_table
+        );
+    }
+
+    let pipeline = Pipeline::start(config, metrics)?;
+    match source {
+        Source::Pcap(path) => {
+            pipeline.capture_engine()?.process_file(&path)?;
+        }
+        Source::Iface(name) => {
+            // the capture loop runs until the process exits and keeps its observer
+            pipeline.capture_engine()?.start_live(&name).await?;
+            tokio::signal::ctrl_c().await.map_err(|e| e.to_string())?;
+            println!("[Sensor] shutting down");
+            return Ok(());
+        }
+    }
+
+    let summary = pipeline.finish()?;
+    println!(
+        "[Sensor] {} observations: {} allowed, {} blocked, {} unchecked; {} rows written",
+        summary.observations,
+        summary.allowed,
+        summary.blocked,
+        summary.unchecked,
+        summary.rows_written
+    );
+    Ok(())
+}
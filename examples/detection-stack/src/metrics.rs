@@ -0,0 +1,147 @@
+//! Sensor metrics in the Prometheus text format
+//!
+//! The gateway exports its own metrics on `/metrics`; these cover the sensor side
+//! of the pipeline and are served on a separate port.
+
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge, Opts, Registry,
+    TextEncoder,
+};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::thread;
+
+/// Sensor counters, registered in their own registry
+pub struct SensorMetrics {
+    registry: Registry,
+    /// Packets analyzed
+    pub packets: IntCounter,
+    /// Packets dropped because the analysis queue was full
+    pub dropped: IntCounter,
+    /// Observations by layer
+    pub observations: IntCounterVec,
+    /// Admission decisions by outcome
+    pub decisions: IntCounterVec,
+    /// Failed gateway checks and ClickHouse inserts, by target
+    pub errors: IntCounterVec,
+    /// Rows inserted into ClickHouse
+    pub rows_written: IntCounter,
+    /// Packets waiting for analysis
+    pub queue_depth: IntGauge,
+    /// Learned prior of observed clients
+    pub prior: Histogram,
+}
+
+impl SensorMetrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+        let metrics = Self {
+            packets: IntCounter::new(
+                "fingerprint_sensor_packets_total",
+                "Packets analyzed by the sensor",
+            )
+            .unwrap(),
+            dropped: IntCounter::new(
+                "fingerprint_sensor_dropped_total",
+                "Packets dropped because the analysis queue was full",
+            )
+            .unwrap(),
+            observations: IntCounterVec::new(
+                Opts::new(
+                    "fingerprint_sensor_observations_total",
+                    "Fingerprints observed, by layer",
+                ),
+                &["layer"],
+            )
+            .unwrap(),
+            decisions: IntCounterVec::new(
+                Opts::new(
+                    "fingerprint_sensor_decisions_total",
+                    "Admission decisions, by outcome",
+                ),
+                &["decision"],
+            )
+            .unwrap(),
+            errors: IntCounterVec::new(
+                Opts::new(
+                    "fingerprint_sensor_errors_total",
+                    "Failed gateway checks and ClickHouse inserts",
+                ),
+                &["target"],
+            )
+            .unwrap(),
+            rows_written: IntCounter::new(
+                "fingerprint_sensor_clickhouse_rows_total",
+                "Rows inserted into ClickHouse",
+            )
+            .unwrap(),
+            queue_depth: IntGauge::new(
+                "fingerprint_sensor_queue_depth",
+                "Packets waiting for analysis",
+            )
+            .unwrap(),
+            prior: Histogram::with_opts(
+                HistogramOpts::new(
+                    "fingerprint_sensor_prior",
+                    "Learned abuse prior of observed clients",
+                )
+                .buckets(vec![0.0, 0.1, 0.25, 0.5, 0.75, 0.9, 1.0]),
+            )
+            .unwrap(),
+            registry,
+        };
+        for collector in [
+            Box::new(metrics.packets.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(metrics.dropped.clone()),
+            Box::new(metrics.observations.clone()),
+            Box::new(metrics.decisions.clone()),
+            Box::new(metrics.errors.clone()),
+            Box::new(metrics.rows_written.clone()),
+            Box::new(metrics.queue_depth.clone()),
+            Box::new(metrics.prior.clone()),
+        ] {
+            metrics.registry.register(collector).unwrap();
+        }
+        metrics
+    }
+
+    /// Text exposition of every sensor metric
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .is_err()
+        {
+            return String::new();
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+
+    /// Answer every HTTP request on `addr` with [`render`](Self::render)
+    pub fn serve(self: &Arc<Self>, addr: &str) -> std::io::Result<thread::JoinHandle<()>> {
+        let listener = TcpListener::bind(addr)?;
+        let metrics = self.clone();
+        Ok(thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                // the request itself does not matter; read it so the peer sees a clean close
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request);
+                let body = metrics.render();
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+            }
+        }))
+    }
+}
+
+impl Default for SensorMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
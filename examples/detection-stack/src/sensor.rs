@@ -0,0 +1,88 @@
+//! Per-packet analysis stage
+//!
+//! Turns a passive analysis result into an [`Observation`]: the client's TLS, HTTP
+//! and TCP fingerprint ids plus the weak-label prior the learner has accumulated for
+//! them. The learner database is the one the gateway writes enforcement labels to,
+//! so every 429 the gateway issues raises the prior of the fingerprints involved.
+
+use chrono::Utc;
+use fingerprint_core::fingerprint::Fingerprint;
+use fingerprint_defense::learner::SelfLearningAnalyzer;
+use fingerprint_defense::{FingerprintDatabase, Packet, PassiveAnalysisResult};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// One client observation, also the ClickHouse row
+#[derive(Debug, Clone, Serialize)]
+pub struct Observation {
+    /// `YYYY-MM-DD hh:mm:ss` (UTC), as ClickHouse `DateTime` parses it
+    pub ts: String,
+    pub client_ip: String,
+    pub server_port: u16,
+    pub tls: Option<String>,
+    pub http: Option<String>,
+    pub tcp: Option<String>,
+    /// OS guessed from the TCP signature
+    pub os: Option<String>,
+    pub user_agent: Option<String>,
+    /// Strongest learned abuse prior over the layers (0.0 - 1.0)
+    pub prior: f64,
+}
+
+impl Observation {
+    /// Fingerprints keyed by layer, as the gateway's `fingerprints` field expects
+    pub fn fingerprints(&self) -> HashMap<String, String> {
+        [("tls", &self.tls), ("http", &self.http), ("tcp", &self.tcp)]
+            .into_iter()
+            .filter_map(|(layer, id)| Some((layer.to_string(), id.clone()?)))
+            .collect()
+    }
+
+    /// Layers that produced a fingerprint
+    pub fn layers(&self) -> impl Iterator<Item = &'static str> + '_ {
+        [("tls", &self.tls), ("http", &self.http), ("tcp", &self.tcp)]
+            .into_iter()
+            .filter(|(_, id)| id.is_some())
+            .map(|(layer, _)| layer)
+    }
+}
+
+/// Learner-backed analysis of capture results
+pub struct Sensor {
+    learner: SelfLearningAnalyzer,
+}
+
+impl Sensor {
+    pub fn new(db: FingerprintDatabase) -> Self {
+        #[allow(clippy::arc_with_non_send_sync)]
+        let db = Arc::new(db);
+        Self {
+            learner: SelfLearningAnalyzer::new(db),
+        }
+    }
+
+    /// Feed the learner and build the observation; `None` for packets without a
+    /// fingerprint (ACKs, payload segments)
+    pub fn observe(&self, packet: &Packet, result: &PassiveAnalysisResult) -> Option<Observation> {
+        if result.tls.is_none() && result.http.is_none() && result.tcp.is_none() {
+            return None;
+        }
+        self.learner.process_result(result);
+
+        Some(Observation {
+            ts: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            client_ip: packet.src_ip.to_string(),
+            server_port: packet.dst_port.unwrap_or(0),
+            tls: result.tls.as_ref().map(|tls| tls.id()),
+            http: result.http.as_ref().map(|http| http.id()),
+            tcp: result.tcp.as_ref().map(|tcp| tcp.id()),
+            os: result.tcp.as_ref().and_then(|tcp| tcp.os.clone()),
+            user_agent: result
+                .http
+                .as_ref()
+                .and_then(|http| http.user_agent.clone()),
+            prior: self.learner.weak_label_prior(result),
+        })
+    }
+}
@@ -0,0 +1,96 @@
+//! ClickHouse sink
+//!
+//! Rows are buffered and inserted through ClickHouse's HTTP interface as
+//! `JSONEachRow`, so no native driver is needed. The table is created by
+//! `deploy/clickhouse/init.sql`.
+
+use crate::admission::Decision;
+use crate::sensor::Observation;
+use fingerprint::{HttpClient, HttpClientConfig, HttpMethod, HttpRequest};
+use serde::Serialize;
+use std::time::Duration;
+
+#[derive(Serialize)]
+struct Row<'a> {
+    #[serde(flatten)]
+    observation: &'a Observation,
+    decision: &'static str,
+    reason: &'a str,
+}
+
+/// Batched `INSERT ... FORMAT JSONEachRow`
+pub struct ClickHouseSink {
+    url: String,
+    batch_size: usize,
+    buffer: Vec<String>,
+    client: HttpClient,
+    written: u64,
+}
+
+impl ClickHouseSink {
+    /// `base_url` is the HTTP interface, e.g. `http://clickhouse:8123`
+    pub fn new(base_url: &str, table: &str, batch_size: usize) -> Self {
+        let query = format!("INSERT INTO {} FORMAT JSONEachRow", table).replace(' ', "%20");
+        Self {
+            url: format!("{}/?query={}", base_url.trim_end_matches('/'), query),
+            batch_size: batch_size.max(1),
+            buffer: Vec::new(),
+            client: HttpClient::new(HttpClientConfig {
+                connect_timeout: Duration::from_secs(5),
+                read_timeout: Duration::from_secs(10),
+                write_timeout: Duration::from_secs(10),
+                ..HttpClientConfig::default()
+            }),
+            written: 0,
+        }
+    }
+
+    /// Rows inserted so far
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Buffer a row, inserting the batch once it is full
+    pub fn push(&mut self, observation: &Observation, decision: &Decision) -> Result<(), String> {
+        let reason = match decision {
+            Decision::Block { reason } => reason.as_str(),
+            _ => "",
+        };
+        let row = Row {
+            observation,
+            decision: decision.as_str(),
+            reason,
+        };
+        self.buffer
+            .push(serde_json::to_string(&row).map_err(|e| e.to_string())?);
+        if self.buffer.len() >= self.batch_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Insert buffered rows; they are dropped on failure so a dead ClickHouse
+    /// cannot grow the buffer without bound
+    pub fn flush(&mut self) -> Result<(), String> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let rows = std::mem::take(&mut self.buffer);
+        let request = HttpRequest::new(HttpMethod::Post, &self.url)
+            .with_header("Content-Type", "application/x-ndjson")
+            .with_body(rows.join("\n").into_bytes());
+        let response = self
+            .client
+            .send_request(&request)
+            .map_err(|e| e.to_string())?;
+        if response.status_code != 200 {
+            return Err(format!(
+                "ClickHouse answered {}: {}",
+                response.status_code,
+                String::from_utf8_lossy(&response.body)
+            ));
+        }
+        self.written += rows.len() as u64;
+        Ok(())
+    }
+}
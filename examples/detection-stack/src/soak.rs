@@ -0,0 +1,254 @@
+//! Soak-test support: memory sampling and growth detection
+//!
+//! A soak run drives the pipelines for hours and records a [`MemorySample`] at a
+//! fixed interval. [`SoakRecorder::check`] fails the run when resident memory or
+//! live heap keeps rising after warmup: the post-warmup samples are split into
+//! windows, and growth is reported only when every window's median is at least the
+//! previous one's and the total rise exceeds [`GrowthThresholds`]. Plateaus, sawtooth
+//! patterns from periodic flushes and one-off steps pass.
+//!
+//! Live heap is only available when the test binary installs [`CountingAllocator`]
+//! as its global allocator; RSS is read from `/proc` on Linux.
+
+use serde::Serialize;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// System allocator that counts live heap bytes
+pub struct CountingAllocator {
+    live: AtomicU64,
+}
+
+impl CountingAllocator {
+    pub const fn new() -> Self {
+        Self {
+            live: AtomicU64::new(0),
+        }
+    }
+
+    /// Bytes currently allocated through this allocator
+    pub fn live_bytes(&self) -> u64 {
+        self.live.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for CountingAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            self.live.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            self.live.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        self.live.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            self.live.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+            self.live.fetch_add(new_size as u64, Ordering::Relaxed);
+        }
+        new_ptr
+    }
+}
+
+/// Resident set size of this process (Linux only)
+pub fn rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kb: u64 = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+/// Memory of the process at one point of a soak run
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MemorySample {
+    pub elapsed_secs: f64,
+    /// Load iterations completed so far
+    pub iterations: u64,
+    pub rss_bytes: Option<u64>,
+    pub heap_bytes: Option<u64>,
+}
+
+/// When a rising memory series fails the run
+#[derive(Debug, Clone)]
+pub struct GrowthThresholds {
+    /// Leading part of the run excluded (caches filling, allocator warming up)
+    pub warmup_fraction: f64,
+    /// Windows the remaining samples are split into
+    pub windows: usize,
+    /// Rise allowed relative to the first window's median
+    pub max_growth_fraction: f64,
+    /// Rise always allowed, whatever the baseline
+    pub min_growth_bytes: u64,
+}
+
+impl Default for GrowthThresholds {
+    fn default() -> Self {
+        Self {
+            warmup_fraction: 0.25,
+            windows: 6,
+            max_growth_fraction: 0.10,
+            min_growth_bytes: 8 * 1024 * 1024,
+        }
+    }
+}
+
+/// Trend of one memory series after warmup
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrowthStats {
+    /// Median of each window, oldest first
+    pub window_medians: Vec<u64>,
+    /// Last window median minus first, negative when memory shrank
+    pub growth_bytes: i64,
+    /// Every window median at least the previous one's
+    pub monotonic: bool,
+}
+
+impl GrowthStats {
+    /// Analyze `series`; `None` when there are too few samples after warmup
+    pub fn of(series: &[u64], thresholds: &GrowthThresholds) -> Option<Self> {
+        let windows = thresholds.windows.max(2);
+        let skip = (series.len() as f64 * thresholds.warmup_fraction.clamp(0.0, 0.9)) as usize;
+        let steady = &series[skip..];
+        if steady.len() < windows {
+            return None;
+        }
+        let size = steady.len() / windows;
+        let window_medians: Vec<u64> = steady
+            .chunks(size)
+            .take(windows)
+            .map(|chunk| {
+                let mut sorted = chunk.to_vec();
+                sorted.sort_unstable();
+                sorted[sorted.len() / 2]
+            })
+            .collect();
+        let first = window_medians[0];
+        let last = window_medians[window_medians.len() - 1];
+        Some(Self {
+            monotonic: window_medians.windows(2).all(|pair| pair[1] >= pair[0]),
+            growth_bytes: last as i64 - first as i64,
+            window_medians,
+        })
+    }
+
+    /// Whether this trend is unbounded growth under `thresholds`
+    pub fn exceeds(&self, thresholds: &GrowthThresholds) -> bool {
+        let baseline = self.window_medians[0] as f64;
+        let allowed =
+            (baseline * thresholds.max_growth_fraction).max(thresholds.min_growth_bytes as f64);
+        self.monotonic && self.growth_bytes as f64 > allowed
+    }
+}
+
+/// Collects samples of a soak run, optionally appending them to a JSON lines file
+pub struct SoakRecorder {
+    started: Instant,
+    samples: Vec<MemorySample>,
+    report: Option<File>,
+}
+
+impl SoakRecorder {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            samples: Vec::new(),
+            report: None,
+        }
+    }
+
+    /// Also write every sample to `path`, one JSON object per line
+    ///
+    /// Keeping these files across runs tracks memory regressions between builds.
+    pub fn with_report(mut self, path: impl AsRef<Path>) -> Result<Self, String> {
+        self.report = Some(File::create(path).map_err(|e| e.to_string())?);
+        Ok(self)
+    }
+
+    /// Take a sample now
+    pub fn sample(&mut self, iterations: u64, heap_bytes: Option<u64>) -> MemorySample {
+        let sample = MemorySample {
+            elapsed_secs: self.started.elapsed().as_secs_f64(),
+            iterations,
+            rss_bytes: rss_bytes(),
+            heap_bytes,
+        };
+        if let Some(report) = &mut self.report {
+            if let Ok(line) = serde_json::to_string(&sample) {
+                let _ = writeln!(report, "{}", line);
+            }
+        }
+        self.samples.push(sample);
+        sample
+    }
+
+    pub fn samples(&self) -> &[MemorySample] {
+        &self.samples
+    }
+
+    /// Fail when RSS or live heap grew monotonically beyond `thresholds`
+    pub fn check(&self, thresholds: &GrowthThresholds) -> Result<(), String> {
+        let series: [(&str, Vec<u64>); 2] = [
+            (
+                "rss",
+                self.samples.iter().filter_map(|s| s.rss_bytes).collect(),
+            ),
+            (
+                "heap",
+                self.samples.iter().filter_map(|s| s.heap_bytes).collect(),
+            ),
+        ];
+        let mut failures = Vec::new();
+        for (name, values) in &series {
+            let Some(stats) = GrowthStats::of(values, thresholds) else {
+                continue;
+            };
+            if stats.exceeds(thresholds) {
+                failures.push(format!(
+                    "{} grew {} bytes across windows {:?}",
+                    name, stats.growth_bytes, stats.window_medians
+                ));
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures.join("; "))
+        }
+    }
+}
+
+impl Default for SoakRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,207 @@
+//! Runs a generated pcap through the pipeline against stub gateway and ClickHouse servers
+
+use detection_stack::{run_pcap, SensorMetrics, StackConfig};
+use fingerprint_gateway::models::{QuotaTier, RateLimitRequest, RateLimitResponse};
+use fingerprint_parsers::pcap_generator::PcapGenerator;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Request target and body of every request received
+type Log = Arc<Mutex<Vec<(String, String)>>>;
+
+/// Serve `respond(request_index)` as `(status, json body)` for every request
+fn spawn_server<F>(respond: F) -> (String, Log)
+where
+    F: Fn(usize) -> (u16, String) + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let log: Log = Arc::default();
+    let server_log = log.clone();
+    let count = AtomicUsize::new(0);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { break };
+            let (status, body) = respond(count.fetch_add(1, Ordering::SeqCst));
+            handle(stream, &server_log, status, &body);
+        }
+    });
+    (base, log)
+}
+
+fn handle(mut stream: TcpStream, log: &Log, status: u16, body: &str) {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        let Ok(n) = stream.read(&mut chunk) else {
+            return;
+        };
+        if n == 0 {
+            return;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+    let mut lines = head.lines();
+    let target = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("/")
+        .to_string();
+    let content_length: usize = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(0);
+    while buf.len() < head_end + 4 + content_length {
+        match stream.read(&mut chunk) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    }
+    let request_body = String::from_utf8_lossy(&buf[head_end + 4..]).to_string();
+    log.lock().unwrap().push((target, request_body));
+
+    let reason = if status == 200 {
+        "OK"
+    } else {
+        "Too Many Requests"
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+}
+
+fn rate_limit_response(allowed: bool) -> String {
+    serde_json::to_string(&RateLimitResponse {
+        allowed,
+        quota_tier: QuotaTier::Free,
+        remaining: Some(if allowed { 99 } else { 0 }),
+        limit: Some(100),
+        reset_at: None,
+        error: (!allowed).then(|| "rate limit exceeded".to_string()),
+        overage: None,
+    })
+    .unwrap()
+}
+
+#[test]
+fn pcap_flows_through_gateway_clickhouse_and_metrics() {
+    let dir = tempfile::tempdir().unwrap();
+    let pcap = dir.path().join("capture.pcap");
+    let mut generator = PcapGenerator::new();
+    generator.add_chrome_syn();
+    generator.add_firefox_syn();
+    generator.write_to_file(&pcap).unwrap();
+
+    // first client admitted, second one rate limited
+    let (gateway, gateway_log) = spawn_server(|index| {
+        if index == 0 {
+            (200, rate_limit_response(true))
+        } else {
+            (429, rate_limit_response(false))
+        }
+    });
+    let (clickhouse, clickhouse_log) = spawn_server(|_| (200, String::new()));
+
+    let metrics = Arc::new(SensorMetrics::new());
+    let config = StackConfig {
+        gateway_url: Some(gateway),
+        clickhouse_url: Some(clickhouse),
+        label_db_path: Some(dir.path().join("labels.db")),
+        batch_size: 100,
+        flush_interval: Duration::from_secs(60),
+        ..StackConfig::default()
+    };
+    let summary = run_pcap(pcap.to_str().unwrap(), config, metrics.clone()).unwrap();
+
+    assert_eq!(summary.observations, 2);
+    assert_eq!(summary.allowed, 1);
+    assert_eq!(summary.blocked, 1);
+    assert_eq!(summary.unchecked, 0);
+    assert_eq!(summary.rows_written, 2);
+
+    // the gateway saw the sensor's API key and the clients' TCP fingerprints
+    let checks = gateway_log.lock().unwrap();
+    assert_eq!(checks.len(), 2);
+    for (target, body) in checks.iter() {
+        assert_eq!(target, "/api/v1/rate-limit/check");
+        let request: RateLimitRequest = serde_json::from_str(body).unwrap();
+        assert_eq!(request.api_key, "sk_test_demo123");
+        assert_eq!(request.client_ip.as_deref(), Some("192.168.1.100"));
+        assert!(request.fingerprints.contains_key("tcp"));
+    }
+
+    // both rows went out in a single JSONEachRow insert
+    let inserts = clickhouse_log.lock().unwrap();
+    assert_eq!(inserts.len(), 1);
+    let (target, body) = &inserts[0];
+    assert_eq!(
+        target,
+        "/?query=INSERT%20INTO%20fingerprint.observations%20FORMAT%20JSONEachRow"
+    );
+    let rows: Vec<HashMap<String, serde_json::Value>> = body
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let decisions: Vec<&str> = rows
+        .iter()
+        .map(|r| r["decision"].as_str().unwrap())
+        .collect();
+    assert_eq!(decisions, ["allow", "block"]);
+    assert_eq!(rows[1]["reason"], "rate limit exceeded");
+    assert_eq!(rows[0]["server_port"], 443);
+
+    let exposition = metrics.render();
+    for line in [
+        "fingerprint_sensor_decisions_total{decision=\"allow\"} 1",
+        "fingerprint_sensor_decisions_total{decision=\"block\"} 1",
+        "fingerprint_sensor_observations_total{layer=\"tcp\"} 2",
+        "fingerprint_sensor_clickhouse_rows_total 2",
+        "fingerprint_sensor_queue_depth 0",
+        "fingerprint_sensor_dropped_total 0",
+    ] {
+        assert!(exposition.contains(line), "missing {line}:\n{exposition}");
+    }
+}
+
+#[test]
+fn unreachable_gateway_fails_open() {
+    let dir = tempfile::tempdir().unwrap();
+    let pcap = dir.path().join("capture.pcap");
+    let mut generator = PcapGenerator::new();
+    generator.add_chrome_syn();
+    generator.write_to_file(&pcap).unwrap();
+
+    // bind and drop to get a port nobody listens on
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let metrics = Arc::new(SensorMetrics::new());
+    let config = StackConfig {
+        gateway_url: Some(format!("http://127.0.0.1:{}", port)),
+        ..StackConfig::default()
+    };
+    let summary = run_pcap(pcap.to_str().unwrap(), config, metrics.clone()).unwrap();
+
+    assert_eq!(summary.observations, 1);
+    assert_eq!(summary.unchecked, 1);
+    assert!(metrics
+        .render()
+        .contains("fingerprint_sensor_errors_total{target=\"gateway\"} 1"));
+}
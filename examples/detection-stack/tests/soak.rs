@@ -0,0 +1,234 @@
+//! Soak test: sustained synthetic load on the capture, analysis and HTTP paths
+//!
+//! Opt-in, since it runs for hours by default:
+//!
+//! ```bash
+//! cargo test --release -p detection-stack --test soak -- --ignored --nocapture
+//!
+//! # shorter run, samples kept for comparison with earlier builds
+//! SOAK_SECS=900 SOAK_SAMPLE_SECS=5 SOAK_REPORT=soak.jsonl \
+//!   cargo test --release -p detection-stack --test soak -- --ignored --nocapture
+//! ```
+
+use chrono::{Duration as ChronoDuration, Utc};
+use detection_stack::soak::{CountingAllocator, GrowthStats, GrowthThresholds, SoakRecorder};
+use detection_stack::{Pipeline, SensorMetrics, StackConfig};
+use fingerprint::{HttpClient, HttpClientConfig};
+use fingerprint_defense::{
+    GeoLocation, SessionFingerprint, SessionGuard, SessionGuardConfig, VelocityAnalyzer,
+    VelocityConfig, VelocityObservation,
+};
+use fingerprint_gateway::models::{QuotaTier, RateLimitResponse};
+use fingerprint_parsers::pcap_generator::PcapGenerator;
+use std::env;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[global_allocator]
+static HEAP: CountingAllocator = CountingAllocator::new();
+
+fn env_secs(name: &str, default: u64) -> Duration {
+    Duration::from_secs(
+        env::var(name)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default),
+    )
+}
+
+/// Answer every request with `200` and `body`
+fn spawn_server(body: String) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { break };
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 4096];
+            let head_end = loop {
+                match stream.read(&mut chunk) {
+                    Ok(0) | Err(_) => break None,
+                    Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                }
+                if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break Some(pos);
+                }
+            };
+            let Some(head_end) = head_end else { continue };
+            let content_length: usize = String::from_utf8_lossy(&buf[..head_end])
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, value)| value.trim().parse().ok())
+                .unwrap_or(0);
+            while buf.len() < head_end + 4 + content_length {
+                match stream.read(&mut chunk) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                }
+            }
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+    base
+}
+
+#[test]
+fn growth_check_ignores_plateaus_and_flags_leaks() {
+    let thresholds = GrowthThresholds {
+        min_growth_bytes: 1000,
+        ..GrowthThresholds::default()
+    };
+    // warmup ramp, then a plateau with flush sawtooth
+    let plateau: Vec<u64> = (0..200)
+        .map(|i| {
+            if i < 40 {
+                10_000 + i * 500
+            } else {
+                30_000 + (i % 7) * 300
+            }
+        })
+        .collect();
+    let stats = GrowthStats::of(&plateau, &thresholds).unwrap();
+    assert!(!stats.exceeds(&thresholds), "{:?}", stats);
+
+    // one step, then flat: within the relative allowance
+    let step: Vec<u64> = (0..200)
+        .map(|i| if i < 120 { 30_000 } else { 32_000 })
+        .collect();
+    assert!(!GrowthStats::of(&step, &thresholds)
+        .unwrap()
+        .exceeds(&thresholds));
+
+    // steady leak with noise
+    let leak: Vec<u64> = (0..200).map(|i| 30_000 + i * 100 + (i % 5) * 200).collect();
+    let stats = GrowthStats::of(&leak, &thresholds).unwrap();
+    assert!(stats.monotonic);
+    assert!(stats.exceeds(&thresholds), "{:?}", stats);
+
+    assert_eq!(GrowthStats::of(&leak[..4], &thresholds), None);
+}
+
+#[test]
+#[ignore] // hours by default; see module docs
+fn soak_pipelines_without_unbounded_growth() {
+    let duration = env_secs("SOAK_SECS", 4 * 3600);
+    let sample_every = env_secs("SOAK_SAMPLE_SECS", 30);
+
+    let dir = tempfile::tempdir().unwrap();
+    let pcap = dir.path().join("load.pcap");
+    let mut generator = PcapGenerator::new();
+    generator.add_chrome_syn();
+    generator.add_firefox_syn();
+    generator.write_to_file(&pcap).unwrap();
+    let pcap = pcap.to_str().unwrap().to_string();
+
+    let allowed = serde_json::to_string(&RateLimitResponse {
+        allowed: true,
+        quota_tier: QuotaTier::Free,
+        remaining: Some(99),
+        limit: Some(100),
+        reset_at: None,
+        error: None,
+        overage: None,
+    })
+    .unwrap();
+    let gateway = spawn_server(allowed);
+    let clickhouse = spawn_server(String::new());
+    let origin = spawn_server("{\"status\":\"ok\"}".to_string());
+
+    // one long-lived pipeline and capture engine, as in a deployed sensor
+    let metrics = Arc::new(SensorMetrics::new());
+    let pipeline = Pipeline::start(
+        StackConfig {
+            gateway_url: Some(gateway),
+            clickhouse_url: Some(clickhouse),
+            batch_size: 200,
+            flush_interval: Duration::from_secs(1),
+            ..StackConfig::default()
+        },
+        metrics.clone(),
+    )
+    .unwrap();
+    let engine = pipeline.capture_engine().unwrap();
+    let client = HttpClient::new(HttpClientConfig::default());
+
+    // identities never repeat, so these only stay flat if their caps hold
+    let sessions = SessionGuard::new(SessionGuardConfig {
+        max_sessions: 5_000,
+        ..SessionGuardConfig::default()
+    });
+    let velocity = VelocityAnalyzer::new(VelocityConfig {
+        max_identities: 5_000,
+        ..VelocityConfig::default()
+    });
+
+    let mut recorder = SoakRecorder::new();
+    if let Ok(path) = env::var("SOAK_REPORT") {
+        recorder = recorder.with_report(path).unwrap();
+    }
+    let started = Instant::now();
+    let mut next_sample = started;
+    let mut iterations = 0u64;
+    let mut http_errors = 0u64;
+    while started.elapsed() < duration {
+        engine.process_file(&pcap).unwrap();
+
+        if client
+            .get(&format!("{}/health?i={}", origin, iterations))
+            .is_err()
+        {
+            http_errors += 1;
+        }
+
+        let identity = format!("user-{}", iterations);
+        sessions.observe(
+            &identity,
+            &SessionFingerprint {
+                ja4: "t13d1516h2_8daaf6152771_02713d6af862".to_string(),
+                user_agent:
+                    "Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0"
+                        .to_string(),
+                hardware_hash: None,
+                ip: "10.0.0.1".parse().unwrap(),
+            },
+        );
+        velocity.observe(&VelocityObservation {
+            identity,
+            fingerprint: "t13d1516h2_8daaf6152771_02713d6af862".to_string(),
+            ip: "10.0.0.1".parse().unwrap(),
+            geo: Some(GeoLocation::new("DE").with_coordinates(52.52, 13.405)),
+            timestamp: Utc::now() + ChronoDuration::milliseconds(iterations as i64),
+        });
+
+        iterations += 1;
+        if Instant::now() >= next_sample {
+            let sample = recorder.sample(iterations, Some(HEAP.live_bytes()));
+            println!(
+                "[soak] {:>7.0}s  {:>9} iterations  rss {:?}  heap {:?}",
+                sample.elapsed_secs, sample.iterations, sample.rss_bytes, sample.heap_bytes
+            );
+            next_sample += sample_every;
+        }
+    }
+
+    drop(engine);
+    let summary = pipeline.finish().unwrap();
+    println!(
+        "[soak] {} iterations, {} observations, {} HTTP errors",
+        iterations, summary.observations, http_errors
+    );
+    assert!(summary.observations > 0);
+    assert_eq!(http_errors, 0);
+    assert!(sessions.len() <= 5_000);
+    assert!(velocity.len() <= 5_000);
+    recorder.check(&GrowthThresholds::default()).unwrap();
+}
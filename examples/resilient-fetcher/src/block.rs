@@ -0,0 +1,203 @@
+//! Block-page classification
+//!
+//! Anti-bot vendors rarely answer with a clean error: a 403 can be a WAF rule, a
+//! 200 can be a CAPTCHA interstitial, and a 503 can be a JavaScript challenge. The
+//! classifier combines the status code with markers from the first part of the body
+//! so the fetcher can tell "rotate the identity" apart from "the server is broken".
+
+use fingerprint::HttpResponse;
+use serde::Serialize;
+
+/// Body prefix inspected for markers
+const SCAN_BYTES: usize = 64 * 1024;
+
+/// Pages larger than this are real content even if they mention a CAPTCHA
+const SOFT_BLOCK_MAX_BYTES: usize = 32 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockKind {
+    /// Interactive CAPTCHA (reCAPTCHA, hCaptcha, Turnstile, vendor widgets)
+    Captcha,
+    /// JavaScript / proof-of-work interstitial
+    Challenge,
+    /// WAF or IP reputation denial
+    AccessDenied,
+}
+
+/// What a response means for the fetcher
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "verdict", rename_all = "snake_case")]
+pub enum Verdict {
+    Ok,
+    Blocked {
+        kind: BlockKind,
+    },
+    RateLimited,
+    /// 4xx that is not a block; retrying with another identity will not help
+    ClientError,
+    ServerError,
+}
+
+impl Verdict {
+    /// Whether a new session (exit IP, cookies) may get a different answer
+    pub fn wants_rotation(&self) -> bool {
+        matches!(self, Verdict::Blocked { .. } | Verdict::RateLimited)
+    }
+}
+
+const MARKERS: &[(&str, BlockKind)] = &[
+    ("g-recaptcha", BlockKind::Captcha),
+    ("h-captcha", BlockKind::Captcha),
+    ("hcaptcha.com", BlockKind::Captcha),
+    ("cf-turnstile", BlockKind::Captcha),
+    ("px-captcha", BlockKind::Captcha),
+    ("captcha-delivery.com", BlockKind::Captcha),
+    ("/cdn-cgi/challenge-platform/", BlockKind::Challenge),
+    ("cf-chl-", BlockKind::Challenge),
+    ("just a moment...", BlockKind::Challenge),
+    ("_incapsula_resource", BlockKind::Challenge),
+    ("checking your browser", BlockKind::Challenge),
+    ("access denied", BlockKind::AccessDenied),
+    ("request blocked", BlockKind::AccessDenied),
+    ("you have been blocked", BlockKind::AccessDenied),
+    ("the requested url was rejected", BlockKind::AccessDenied),
+];
+
+/// Marker-based block detection
+#[derive(Debug, Clone, Default)]
+pub struct BlockClassifier {
+    extra_markers: Vec<(String, BlockKind)>,
+}
+
+impl BlockClassifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a site-specific marker (matched case-insensitively)
+    pub fn with_marker(mut self, marker: &str, kind: BlockKind) -> Self {
+        self.extra_markers.push((marker.to_ascii_lowercase(), kind));
+        self
+    }
+
+    pub fn classify(&self, response: &HttpResponse) -> Verdict {
+        let marker = self.find_marker(&response.body);
+        let blocked_by_vendor = response.headers.contains_key("cf-mitigated")
+            || response.headers.contains_key("x-datadome");
+
+        match response.status_code {
+            429 => Verdict::RateLimited,
+            401 | 403 => Verdict::Blocked {
+                kind: marker.unwrap_or(BlockKind::AccessDenied),
+            },
+            503 if marker.is_some() || blocked_by_vendor => Verdict::Blocked {
+                kind: marker.unwrap_or(BlockKind::Challenge),
+            },
+            500..=599 => Verdict::ServerError,
+            400..=499 => Verdict::ClientError,
+            // soft blocks: a small 200 page that is really an interstitial
+            _ if blocked_by_vendor => Verdict::Blocked {
+                kind: marker.unwrap_or(BlockKind::Challenge),
+            },
+            _ => match marker {
+                Some(kind)
+                    if kind != BlockKind::AccessDenied
+                        && response.body.len() <= SOFT_BLOCK_MAX_BYTES =>
+                {
+                    Verdict::Blocked { kind }
+                }
+                _ => Verdict::Ok,
+            },
+        }
+    }
+
+    fn find_marker(&self, body: &[u8]) -> Option<BlockKind> {
+        let text = String::from_utf8_lossy(&body[..body.len().min(SCAN_BYTES)]).to_lowercase();
+        MARKERS
+            .iter()
+            .map(|(marker, kind)| (*marker, *kind))
+            .chain(
+                self.extra_markers
+                    .iter()
+                    .map(|(marker, kind)| (marker.as_str(), *kind)),
+            )
+            .find(|(marker, _)| text.contains(marker))
+            .map(|(_, kind)| kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, body: &str) -> HttpResponse {
+        let mut response = HttpResponse::new(status);
+        response.body = body.as_bytes().to_vec();
+        response
+    }
+
+    #[test]
+    fn test_classify() {
+        let classifier = BlockClassifier::new();
+        let cases = [
+            (200, "<h1>Catalogue</h1>", Verdict::Ok),
+            (
+                200,
+                "<div class=\"g-recaptcha\"></div>",
+                Verdict::Blocked {
+                    kind: BlockKind::Captcha,
+                },
+            ),
+            (
+                403,
+                "nope",
+                Verdict::Blocked {
+                    kind: BlockKind::AccessDenied,
+                },
+            ),
+            (
+                503,
+                "<title>Just a moment...</title>",
+                Verdict::Blocked {
+                    kind: BlockKind::Challenge,
+                },
+            ),
+            (503, "maintenance", Verdict::ServerError),
+            (429, "", Verdict::RateLimited),
+            (404, "not found", Verdict::ClientError),
+            // an article about access denied errors is not a block
+            (200, "Fixing access denied errors", Verdict::Ok),
+        ];
+        for (status, body, expected) in cases {
+            assert_eq!(
+                classifier.classify(&response(status, body)),
+                expected,
+                "{}",
+                body
+            );
+        }
+
+        let big_page = format!("{}g-recaptcha", "x".repeat(SOFT_BLOCK_MAX_BYTES));
+        assert_eq!(classifier.classify(&response(200, &big_page)), Verdict::Ok);
+    }
+
+    #[test]
+    fn test_vendor_headers_and_custom_markers() {
+        let mut challenged = response(200, "");
+        challenged
+            .headers
+            .insert("cf-mitigated".to_string(), "challenge".to_string());
+        assert!(BlockClassifier::new()
+            .classify(&challenged)
+            .wants_rotation());
+
+        let classifier = BlockClassifier::new().with_marker("Robot Check", BlockKind::Captcha);
+        assert_eq!(
+            classifier.classify(&response(200, "<title>robot check</title>")),
+            Verdict::Blocked {
+                kind: BlockKind::Captcha
+            }
+        );
+    }
+}
@@ -0,0 +1,335 @@
+//! The resilient fetch loop
+//!
+//! For each target the fetcher:
+//!
+//! 1. lets the [`RotationScheduler`] apply due identity changes (browser update,
+//!    session expiry);
+//! 2. binds the current session to a sticky proxy chain and, when the session or
+//!    browser changed, starts from a clean cookie jar and navigation state;
+//! 3. derives headers for the request from the identity through the
+//!    [`NavigationPipeline`] (Sec-Fetch-*, Referer, Origin);
+//! 4. sends it with a client built from the identity's TLS profile, whose
+//!    [`RetryPolicy`] absorbs transient 429/503s and connection errors;
+//! 5. records the attempt in the HAR log and classifies the response. A block page
+//!    cools the proxy down and starts a new session before the next attempt.
+
+use crate::block::{BlockClassifier, Verdict};
+use crate::har::{EntryMeta, HarRecorder};
+use crate::job::{JobError, JobSpec, TargetContext, TargetSpec};
+use crate::proxy_pool::{ProxyPool, ProxyStats};
+use chrono::Utc;
+use fingerprint::unstable::{RotatingIdentity, RotationOutcome, RotationPolicy, RotationScheduler};
+use fingerprint::{
+    CookieStore, HttpClient, HttpClientConfig, HttpMethod, HttpRequest, RetryBudget, RetryPolicy,
+};
+use fingerprint_headers::{NavigationPipeline, RequestContext, RequestUrl};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Outcome of one target
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetReport {
+    pub url: String,
+    pub ok: bool,
+    pub status: Option<u16>,
+    pub verdict: Option<Verdict>,
+    /// Attempts made by the fetcher (transport retries inside one attempt not counted)
+    pub attempts: u32,
+    /// Sessions used, in order
+    pub sessions: Vec<String>,
+    pub bytes: usize,
+    pub error: Option<String>,
+}
+
+/// Identity change applied during the job
+#[derive(Debug, Clone, Serialize)]
+pub struct RotationRecord {
+    pub at: String,
+    pub attribute: String,
+    pub from: String,
+    pub to: String,
+    pub outcome: String,
+}
+
+/// Summary of a job run
+#[derive(Debug, Clone, Serialize)]
+pub struct JobReport {
+    pub job: String,
+    pub profile: String,
+    pub user_agent: String,
+    pub targets: Vec<TargetReport>,
+    pub rotations: Vec<RotationRecord>,
+    pub proxies: Vec<ProxyStats>,
+    pub retry_budget_tokens: f64,
+}
+
+impl JobReport {
+    pub fn succeeded(&self) -> bool {
+        self.targets.iter().all(|target| target.ok)
+    }
+}
+
+/// Per-session browser state
+struct Session {
+    id: u64,
+    profile_name: String,
+    pipeline: NavigationPipeline,
+    cookies: Arc<CookieStore>,
+}
+
+impl Session {
+    fn new(identity: &RotatingIdentity) -> Self {
+        Self {
+            id: identity.session_id,
+            profile_name: identity.profile_name.clone(),
+            pipeline: NavigationPipeline::new(identity.browser_type, identity.headers.clone()),
+            cookies: Arc::new(CookieStore::new()),
+        }
+    }
+
+    fn matches(&self, identity: &RotatingIdentity) -> bool {
+        self.id == identity.session_id && self.profile_name == identity.profile_name
+    }
+}
+
+/// Runs a [`JobSpec`]
+pub struct Fetcher {
+    job: JobSpec,
+    scheduler: RotationScheduler,
+    session: Session,
+    proxies: ProxyPool,
+    classifier: BlockClassifier,
+    budget: Arc<RetryBudget>,
+    har: HarRecorder,
+    rng: StdRng,
+}
+
+impl Fetcher {
+    /// Build the identity stack for `job`; `seed` makes identity choices reproducible
+    pub fn new(job: JobSpec, seed: u64) -> Result<Self, JobError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let identity =
+            RotatingIdentity::from_profile_name(&mut rng, &job.profile, job.operating_system()?)
+                .map_err(JobError::Invalid)?;
+        let scheduler =
+            RotationScheduler::new(&mut rng, identity, RotationPolicy::default(), Utc::now());
+        let proxies = ProxyPool::parse(
+            &job.proxies,
+            Duration::from_secs(job.identity.proxy_cooldown_secs),
+        )
+        .map_err(JobError::Invalid)?;
+
+        Ok(Self {
+            session: Session::new(scheduler.identity()),
+            scheduler,
+            proxies,
+            classifier: BlockClassifier::new(),
+            budget: Arc::new(RetryBudget::default()),
+            har: HarRecorder::new(),
+            rng,
+            job,
+        })
+    }
+
+    /// Use a custom block classifier
+    pub fn with_classifier(mut self, classifier: BlockClassifier) -> Self {
+        self.classifier = classifier;
+        self
+    }
+
+    pub fn har(&self) -> &HarRecorder {
+        &self.har
+    }
+
+    /// Fetch every target in order
+    pub fn run(&mut self) -> JobReport {
+        let targets: Vec<TargetSpec> = self.job.targets.iter().map(|t| t.spec()).collect();
+        let reports = targets.iter().map(|target| self.fetch(target)).collect();
+
+        let identity = self.scheduler.identity();
+        JobReport {
+            job: self.job.name.clone(),
+            profile: identity.profile_name.clone(),
+            user_agent: identity.user_agent.clone(),
+            targets: reports,
+            rotations: self
+                .scheduler
+                .history()
+                .iter()
+                .map(|event| RotationRecord {
+                    at: event.at.to_rfc3339(),
+                    attribute: event.attribute.to_string(),
+                    from: event.from.clone(),
+                    to: event.to.clone(),
+                    outcome: match &event.outcome {
+                        RotationOutcome::Applied => "applied".to_string(),
+                        RotationOutcome::Rejected(reasons) => {
+                            format!("rejected: {}", reasons.join("; "))
+                        }
+                        RotationOutcome::Unavailable => "unavailable".to_string(),
+                    },
+                })
+                .collect(),
+            proxies: self.proxies.stats(),
+            retry_budget_tokens: self.budget.tokens(),
+        }
+    }
+
+    /// Fetch one target, rotating the session on blocks
+    fn fetch(&mut self, target: &TargetSpec) -> TargetReport {
+        let mut report = TargetReport {
+            url: target.url.clone(),
+            ok: false,
+            status: None,
+            verdict: None,
+            attempts: 0,
+            sessions: Vec::new(),
+            bytes: 0,
+            error: None,
+        };
+
+        loop {
+            self.scheduler.step(&mut self.rng, Utc::now());
+            if !self.session.matches(self.scheduler.identity()) {
+                self.session = Session::new(self.scheduler.identity());
+            }
+            report.attempts += 1;
+            report.sessions.push(self.session.id.to_string());
+
+            let retry_with_new_session = match self.attempt(target, &mut report) {
+                Ok(verdict) => verdict.wants_rotation(),
+                Err(error) => {
+                    report.error = Some(error);
+                    // a proxy that is down looks the same as one that is blocked
+                    !self.proxies.is_empty()
+                }
+            };
+            if !retry_with_new_session || report.attempts > self.job.identity.max_blocks {
+                break;
+            }
+            if self.job.identity.rotate_session_on_block {
+                self.scheduler.start_session(&mut self.rng, Utc::now());
+            }
+        }
+        report
+    }
+
+    /// One attempt with the current session
+    fn attempt(
+        &mut self,
+        target: &TargetSpec,
+        report: &mut TargetReport,
+    ) -> Result<Verdict, String> {
+        let identity = self.scheduler.identity().clone();
+        let context = match target.context {
+            TargetContext::Navigate => RequestContext::BrowserNavigation,
+            TargetContext::Link => RequestContext::Navigation {
+                user_activated: true,
+            },
+            TargetContext::Fetch => RequestContext::Fetch,
+        };
+        let headers = self.session.pipeline.request(
+            context,
+            &target.url,
+            &target.method.to_ascii_uppercase(),
+        )?;
+        let method = method(&target.method).ok_or("unsupported method")?;
+
+        let mut request = HttpRequest::new(method, &target.url).with_headers(&headers);
+        for (name, value) in &target.headers {
+            request = request.with_header(name, value);
+        }
+        if let Some(body) = &target.body {
+            request = request.with_body(body.clone().into_bytes());
+        }
+        // the client consults the jar for HTTPS and redirects only; attach it for
+        // plain HTTP too so both schemes behave the same
+        let url = RequestUrl::parse(&target.url)?;
+        if let Some(cookie) =
+            self.session
+                .cookies
+                .generate_cookie_header(&url.host, &url.path, url.scheme == "https")
+        {
+            request = request.with_header("Cookie", &cookie);
+        }
+
+        let proxy = self.proxies.pick(self.session.id);
+        let client = HttpClient::new(HttpClientConfig {
+            user_agent: identity.user_agent.clone(),
+            headers: identity.headers.clone(),
+            profile: fingerprint::mapped_tls_clients().remove(&identity.profile_name),
+            connect_timeout: self.job.timeout(),
+            read_timeout: self.job.timeout(),
+            write_timeout: self.job.timeout(),
+            cookie_store: Some(self.session.cookies.clone()),
+            proxy: proxy.as_ref().map(|(_, chain)| chain.clone()),
+            retry: Some(
+                RetryPolicy::new(self.job.retry.max_retries)
+                    .with_backoff(
+                        Duration::from_millis(self.job.retry.initial_backoff_ms),
+                        Duration::from_millis(self.job.retry.max_backoff_ms),
+                    )
+                    .with_budget(self.budget.clone()),
+            ),
+            ..HttpClientConfig::default()
+        });
+
+        let meta_started = Utc::now();
+        let started = Instant::now();
+        let result = client.send_request(&request);
+        let meta = EntryMeta {
+            started: meta_started,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            session_id: self.session.id,
+            proxy: proxy.as_ref().map(|(index, _)| self.proxies.label(*index)),
+        };
+
+        match result {
+            Ok(response) => {
+                if let Some(set_cookie) = response.headers.get("set-cookie") {
+                    self.session
+                        .cookies
+                        .add_from_response(set_cookie, url.host.clone());
+                }
+                let verdict = self.classifier.classify(&response);
+                let verdict_json = serde_json::to_value(verdict).unwrap_or_default();
+                self.har
+                    .record_response(&meta, &request, &response, &verdict_json);
+                if let Some((index, _)) = proxy {
+                    self.proxies.report(index, !verdict.wants_rotation());
+                }
+                report.ok = verdict == Verdict::Ok;
+                report.status = Some(response.status_code);
+                report.verdict = Some(verdict);
+                report.bytes = response.body.len();
+                report.error = None;
+                Ok(verdict)
+            }
+            Err(error) => {
+                let error = error.to_string();
+                self.har.record_error(&meta, &request, &error);
+                if let Some((index, _)) = proxy {
+                    self.proxies.report(index, false);
+                }
+                Err(error)
+            }
+        }
+    }
+}
+
+/// Method name to [`HttpMethod`]
+pub(crate) fn method(name: &str) -> Option<HttpMethod> {
+    Some(match name.to_ascii_uppercase().as_str() {
+        "GET" => HttpMethod::Get,
+        "POST" => HttpMethod::Post,
+        "PUT" => HttpMethod::Put,
+        "DELETE" => HttpMethod::Delete,
+        "HEAD" => HttpMethod::Head,
+        "OPTIONS" => HttpMethod::Options,
+        "PATCH" => HttpMethod::Patch,
+        _ => return None,
+    })
+}
@@ -0,0 +1,224 @@
+//! HAR 1.2 recording
+//!
+//! Every attempt, including blocked pages and transport failures, becomes an entry so
+//! a run can be replayed in browser devtools or diffed against a real browser capture.
+//! Fields HAR has no slot for are stored with the `_` prefix the format reserves for
+//! custom data: `_session`, `_proxy`, `_verdict` and `_error`.
+
+use chrono::{DateTime, Utc};
+use fingerprint::{HttpRequest, HttpResponse};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Bodies larger than this are recorded without text
+const MAX_TEXT_BYTES: usize = 256 * 1024;
+
+/// Request-side context of an entry
+pub struct EntryMeta<'a> {
+    pub started: DateTime<Utc>,
+    pub elapsed_ms: u64,
+    pub session_id: u64,
+    pub proxy: Option<&'a str>,
+}
+
+/// In-memory HAR log
+#[derive(Debug, Default)]
+pub struct HarRecorder {
+    entries: Vec<Value>,
+}
+
+impl HarRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Record a completed exchange
+    pub fn record_response(
+        &mut self,
+        meta: &EntryMeta<'_>,
+        request: &HttpRequest,
+        response: &HttpResponse,
+        verdict: &Value,
+    ) {
+        let mime_type = response
+            .headers
+            .get("content-type")
+            .cloned()
+            .unwrap_or_default();
+        let mut content = json!({
+            "size": response.body.len(),
+            "mimeType": mime_type,
+        });
+        if response.body.len() <= MAX_TEXT_BYTES {
+            if let Ok(text) = std::str::from_utf8(&response.body) {
+                content["text"] = Value::from(text);
+            }
+        }
+        let response = json!({
+            "status": response.status_code,
+            "statusText": response.status_text,
+            "httpVersion": response.http_version,
+            "headers": name_values(response.headers.iter()),
+            "cookies": [],
+            "content": content,
+            "redirectURL": response.headers.get("location").cloned().unwrap_or_default(),
+            "headersSize": -1,
+            "bodySize": response.body.len(),
+        });
+        let mut entry = self.entry(meta, request, response);
+        entry["_verdict"] = verdict.clone();
+        self.entries.push(entry);
+    }
+
+    /// Record an attempt that produced no response
+    pub fn record_error(&mut self, meta: &EntryMeta<'_>, request: &HttpRequest, error: &str) {
+        let response = json!({
+            "status": 0,
+            "statusText": "",
+            "httpVersion": "",
+            "headers": [],
+            "cookies": [],
+            "content": { "size": 0, "mimeType": "" },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": -1,
+        });
+        let mut entry = self.entry(meta, request, response);
+        entry["_error"] = Value::from(error);
+        self.entries.push(entry);
+    }
+
+    fn entry(&self, meta: &EntryMeta<'_>, request: &HttpRequest, response: Value) -> Value {
+        let mut har_request = json!({
+            "method": request.method.as_str(),
+            "url": request.url,
+            "httpVersion": "HTTP/1.1",
+            "headers": name_values(request.headers.iter()),
+            "queryString": query_string(&request.url),
+            "cookies": [],
+            "headersSize": -1,
+            "bodySize": request.body.as_ref().map_or(0, Vec::len),
+        });
+        if let Some(body) = &request.body {
+            let mime_type = request
+                .headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default();
+            har_request["postData"] = json!({
+                "mimeType": mime_type,
+                "text": String::from_utf8_lossy(body),
+            });
+        }
+        json!({
+            "startedDateTime": meta.started.to_rfc3339(),
+            "time": meta.elapsed_ms,
+            "request": har_request,
+            "response": response,
+            "cache": {},
+            "timings": { "send": 0, "wait": meta.elapsed_ms, "receive": 0 },
+            "_session": meta.session_id.to_string(),
+            "_proxy": meta.proxy,
+        })
+    }
+
+    /// The complete HAR document
+    pub fn to_json(&self) -> Value {
+        json!({
+            "log": {
+                "version": "1.2",
+                "creator": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "entries": self.entries,
+            }
+        })
+    }
+
+    /// Write the HAR document, creating parent directories
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_vec_pretty(&self.to_json()).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
+
+/// Sorted `[{name, value}]` list
+fn name_values<'a>(pairs: impl Iterator<Item = (&'a String, &'a String)>) -> Value {
+    let mut pairs: Vec<_> = pairs.collect();
+    pairs.sort();
+    pairs
+        .into_iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+fn query_string(url: &str) -> Value {
+    let query = url
+        .split_once('?')
+        .map(|(_, query)| query.split('#').next().unwrap_or_default())
+        .unwrap_or_default();
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            json!({ "name": name, "value": value })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fingerprint::HttpMethod;
+
+    #[test]
+    fn test_har_document() {
+        let mut har = HarRecorder::new();
+        let meta = EntryMeta {
+            started: Utc::now(),
+            elapsed_ms: 12,
+            session_id: 7,
+            proxy: Some("socks5://10.0.0.2:1080"),
+        };
+        let request = HttpRequest::new(HttpMethod::Post, "https://example.com/api?page=2&q")
+            .with_header("Content-Type", "application/json")
+            .with_body(b"{}".to_vec());
+        let mut response = HttpResponse::new(200);
+        response
+            .headers
+            .insert("content-type".to_string(), "text/html".to_string());
+        response.body = b"<p>ok</p>".to_vec();
+
+        har.record_response(&meta, &request, &response, &json!({ "verdict": "ok" }));
+        har.record_error(&meta, &request, "connection refused");
+        assert_eq!(har.len(), 2);
+
+        let doc = har.to_json();
+        let entries = doc["log"]["entries"].as_array().unwrap();
+        assert_eq!(doc["log"]["version"], "1.2");
+        assert_eq!(entries[0]["request"]["queryString"][1]["name"], "q");
+        assert_eq!(
+            entries[0]["request"]["postData"]["mimeType"],
+            "application/json"
+        );
+        assert_eq!(entries[0]["response"]["content"]["text"], "<p>ok</p>");
+        assert_eq!(entries[0]["_verdict"]["verdict"], "ok");
+        assert_eq!(entries[1]["response"]["status"], 0);
+        assert_eq!(entries[1]["_error"], "connection refused");
+        assert_eq!(entries[1]["_session"], "7");
+    }
+}
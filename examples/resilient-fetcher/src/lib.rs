@@ -0,0 +1,28 @@
+//! Resilient fetcher: reference composition of the fingerprint identity stack
+//!
+//! This crate is an example application, not a library to depend on. It wires the
+//! public APIs together the way a scraping client would:
+//!
+//! - browser profiles and [`fingerprint::unstable::RotationScheduler`] for a
+//!   consistent, slowly evolving identity;
+//! - a sticky [`proxy_pool::ProxyPool`] keyed by the identity's session id;
+//! - [`fingerprint::RetryPolicy`] with a shared [`fingerprint::RetryBudget`] for
+//!   transient failures;
+//! - a [`har::HarRecorder`] capturing every attempt;
+//! - a [`block::BlockClassifier`] that turns block pages into session rotations.
+//!
+//! The proxy pool, HAR recorder and block classifier live here rather than in the
+//! library because their policies (cool-downs, markers, what counts as a block) are
+//! deployment decisions.
+
+pub mod block;
+pub mod fetcher;
+pub mod har;
+pub mod job;
+pub mod proxy_pool;
+
+pub use block::{BlockClassifier, BlockKind, Verdict};
+pub use fetcher::{Fetcher, JobReport, TargetReport};
+pub use har::HarRecorder;
+pub use job::{JobError, JobSpec};
+pub use proxy_pool::ProxyPool;
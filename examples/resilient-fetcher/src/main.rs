@@ -0,0 +1,70 @@
+//! `resilient-fetcher <job.yaml> [--seed N]`
+//!
+//! Runs the job, prints the report as JSON, writes the HAR file if the job names one
+//! and exits with status 1 when any target failed.
+
+use resilient_fetcher::{Fetcher, JobSpec};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let mut job_path: Option<PathBuf> = None;
+    let mut seed: u64 = rand::random();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => match args.next().and_then(|s| s.parse().ok()) {
+                Some(value) => seed = value,
+                None => return usage("--seed needs a number"),
+            },
+            "-h" | "--help" => return usage(""),
+            _ if job_path.is_none() => job_path = Some(PathBuf::from(arg)),
+            _ => return usage(&format!("unexpected argument {}", arg)),
+        }
+    }
+    let Some(job_path) = job_path else {
+        return usage("missing job file");
+    };
+
+    let job = match JobSpec::load(&job_path) {
+        Ok(job) => job,
+        Err(e) => {
+            eprintln!("{}: {}", job_path.display(), e);
+            return ExitCode::from(2);
+        }
+    };
+    let har_path = job.har.clone();
+    let mut fetcher = match Fetcher::new(job, seed) {
+        Ok(fetcher) => fetcher,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let report = fetcher.run();
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("cannot serialize report: {}", e),
+    }
+    if let Some(path) = har_path {
+        if let Err(e) = fetcher.har().save(&path) {
+            eprintln!("cannot write {}: {}", path.display(), e);
+        }
+    }
+
+    if report.succeeded() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn usage(error: &str) -> ExitCode {
+    if !error.is_empty() {
+        eprintln!("error: {}", error);
+    }
+    eprintln!("usage: resilient-fetcher <job.yaml> [--seed N]");
+    ExitCode::from(2)
+}
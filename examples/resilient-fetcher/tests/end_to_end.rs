@@ -0,0 +1,193 @@
+//! Runs a YAML job against a local HTTP server that serves block pages and transient errors
+
+use resilient_fetcher::{BlockKind, Fetcher, JobSpec, Verdict};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Request line path and lowercased headers of every request received
+type Log = Arc<Mutex<Vec<(String, HashMap<String, String>)>>>;
+
+fn spawn_server() -> (String, Log) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let log: Log = Arc::default();
+    let server_log = log.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { break };
+            handle(stream, &server_log);
+        }
+    });
+    (base, log)
+}
+
+fn handle(mut stream: TcpStream, log: &Log) {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        let Ok(n) = stream.read(&mut chunk) else {
+            return;
+        };
+        if n == 0 {
+            return;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+    let mut lines = head.lines();
+    let path = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("/")
+        .to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    let content_length: usize = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    while buf.len() < head_end + 4 + content_length {
+        match stream.read(&mut chunk) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    }
+
+    let seen = {
+        let mut log = log.lock().unwrap();
+        log.push((path.clone(), headers));
+        log.iter().filter(|(p, _)| *p == path).count()
+    };
+    let (status, extra, body) = match (path.as_str(), seen) {
+        ("/ok", _) => (
+            "200 OK",
+            "Set-Cookie: sid=first; Path=/\r\n",
+            "<h1>hello</h1>",
+        ),
+        ("/guarded", 1) => (
+            "200 OK",
+            "",
+            "<html><div class=\"g-recaptcha\" data-sitekey=\"x\"></div></html>",
+        ),
+        ("/guarded", _) => ("200 OK", "", "<h1>catalogue</h1>"),
+        ("/flaky", 1) => ("503 Service Unavailable", "", "try later"),
+        ("/flaky", _) => ("200 OK", "", "{\"ok\":true}"),
+        ("/denied", _) => ("403 Forbidden", "", "Access Denied"),
+        _ => ("404 Not Found", "", "missing"),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+        status,
+        body.len(),
+        extra,
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+#[test]
+fn test_job_end_to_end() {
+    let (base, log) = spawn_server();
+    let dir = tempfile::tempdir().unwrap();
+    let har_path = dir.path().join("out/run.har");
+    let yaml = format!(
+        r#"
+name: local
+profile: chrome_133
+os: linux
+retry: {{ max_retries: 2, initial_backoff_ms: 10, max_backoff_ms: 50 }}
+identity: {{ max_blocks: 1 }}
+har: {har}
+timeout_secs: 5
+targets:
+  - {base}/ok
+  - url: {base}/guarded
+    context: link
+  - url: {base}/flaky
+    method: POST
+    context: fetch
+    headers: {{ content-type: application/json, idempotency-key: abc }}
+    body: '{{"n":1}}'
+  - {base}/denied
+"#,
+        base = base,
+        har = har_path.display()
+    );
+    let job = JobSpec::from_yaml(&yaml).unwrap();
+    let mut fetcher = Fetcher::new(job.clone(), 42).unwrap();
+    let report = fetcher.run();
+
+    // /ok straight through
+    let ok = &report.targets[0];
+    assert!(ok.ok, "{:?}", ok);
+    assert_eq!(ok.attempts, 1);
+
+    // CAPTCHA page: one new session, then the real page
+    let guarded = &report.targets[1];
+    assert!(guarded.ok, "{:?}", guarded);
+    assert_eq!(guarded.attempts, 2);
+    assert_ne!(guarded.sessions[0], guarded.sessions[1]);
+    assert!(report
+        .rotations
+        .iter()
+        .any(|r| r.attribute == "session" && r.to == guarded.sessions[1]));
+
+    // 503 absorbed by the client's retry policy in a single attempt
+    let flaky = &report.targets[2];
+    assert!(flaky.ok, "{:?}", flaky);
+    assert_eq!((flaky.attempts, flaky.status), (1, Some(200)));
+
+    // hard block: gives up after max_blocks rotations
+    let denied = &report.targets[3];
+    assert!(!denied.ok);
+    assert_eq!(denied.attempts, 2);
+    assert_eq!(
+        denied.verdict,
+        Some(Verdict::Blocked {
+            kind: BlockKind::AccessDenied
+        })
+    );
+    assert!(!report.succeeded());
+
+    let requests = log.lock().unwrap().clone();
+    let headers_for = |path: &str| -> Vec<HashMap<String, String>> {
+        requests
+            .iter()
+            .filter(|(p, _)| p == path)
+            .map(|(_, h)| h.clone())
+            .collect()
+    };
+    // identity headers reach the wire
+    let first = &headers_for("/ok")[0];
+    assert!(first["user-agent"].contains("Linux"), "{:?}", first);
+    // the link click carries the first session's cookie and a referer...
+    let guarded_requests = headers_for("/guarded");
+    assert_eq!(guarded_requests[0]["cookie"], "sid=first");
+    assert_eq!(guarded_requests[0]["referer"], format!("{}/ok", base));
+    // ...and the new session starts with an empty cookie jar and no referer
+    assert!(!guarded_requests[1].contains_key("cookie"));
+    assert!(!guarded_requests[1].contains_key("referer"));
+    assert_eq!(headers_for("/flaky").len(), 2);
+
+    // every attempt, including transport-level retries' final response, is in the HAR
+    fetcher.har().save(&har_path).unwrap();
+    let har: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&har_path).unwrap()).unwrap();
+    let entries = har["log"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 6);
+    assert_eq!(entries[1]["_verdict"]["verdict"], "blocked");
+    assert_eq!(entries[1]["_verdict"]["kind"], "captcha");
+    assert_eq!(entries[3]["request"]["postData"]["text"], "{\"n\":1}");
+
+    // the seed makes the identity reproducible
+    let again = Fetcher::new(job, 42).unwrap().run();
+    assert_eq!(again.user_agent, report.user_agent);
+}